@@ -2,6 +2,7 @@
 
 use std::fmt;
 
+use crate::json::{JsonError, JsonObject, JsonValue};
 use crate::Response;
 
 /// Result type for API operations
@@ -172,46 +173,31 @@ impl ApiError {
 
         let mut response = Response::new(status, status_text(status));
 
+        let body = JsonObject::new()
+            .field("error", error_code)
+            .field("message", message);
         let body = match self {
             ApiError::ValidationError(errors) => {
-                let field_errors: Vec<String> = errors
+                let field_errors: Vec<JsonValue> = errors
                     .iter()
                     .map(|e| {
-                        format!(
-                            r#"{{"field":"{}","code":"{}","message":"{}"}}"#,
-                            e.field,
-                            e.code,
-                            escape_json(&e.message)
-                        )
+                        JsonObject::new()
+                            .field("field", &e.field)
+                            .field("code", &e.code)
+                            .field("message", &e.message)
+                            .build()
                     })
                     .collect();
-
-                format!(
-                    r#"{{"error":"{}","message":"{}","errors":[{}]}}"#,
-                    error_code,
-                    escape_json(&message),
-                    field_errors.join(",")
-                )
+                body.field("errors", field_errors)
             }
             ApiError::RateLimited { retry_after } => {
                 response = response.with_header("Retry-After", retry_after.to_string());
-                format!(
-                    r#"{{"error":"{}","message":"{}","retry_after":{}}}"#,
-                    error_code,
-                    escape_json(&message),
-                    retry_after
-                )
-            }
-            _ => {
-                format!(
-                    r#"{{"error":"{}","message":"{}"}}"#,
-                    error_code,
-                    escape_json(&message)
-                )
+                body.field("retry_after", *retry_after)
             }
+            _ => body,
         };
 
-        response.body = body.into_bytes();
+        response.body = body.build().to_string().into_bytes();
         response
     }
 
@@ -243,13 +229,18 @@ fn status_text(code: u16) -> &'static str {
     }
 }
 
-/// Escape JSON string
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+impl From<JsonError> for ApiError {
+    fn from(e: JsonError) -> Self {
+        match e {
+            JsonError::Syntax { .. } => ApiError::BadRequest(e.to_string()),
+            JsonError::Missing(field) => {
+                ApiError::ValidationError(vec![FieldError::required(&field)])
+            }
+            JsonError::Type { ref field, .. } => {
+                ApiError::ValidationError(vec![FieldError::invalid(field, &e.to_string())])
+            }
+        }
+    }
 }
 
 // Conversions from domain errors
@@ -267,7 +258,11 @@ impl From<vaya_book::BookError> for ApiError {
 
 impl From<vaya_pool::PoolError> for ApiError {
     fn from(e: vaya_pool::PoolError) -> Self {
-        ApiError::PoolError(e.to_string())
+        if e.is_conflict() {
+            ApiError::Conflict(e.to_string())
+        } else {
+            ApiError::PoolError(e.to_string())
+        }
     }
 }
 
@@ -367,4 +362,12 @@ mod tests {
         assert_eq!(err.field, "email");
         assert_eq!(err.code, "required");
     }
+
+    #[test]
+    fn test_pool_conflicts_map_to_409() {
+        let err = ApiError::from(vaya_pool::PoolError::AlreadyMember);
+        assert_eq!(err.status_code(), 409);
+        let err = ApiError::from(vaya_pool::PoolError::PoolExpired);
+        assert_eq!(err.status_code(), 400);
+    }
 }
@@ -1,6 +1,13 @@
-//! Admin handlers (8 handlers)
+//! Admin handlers (38 handlers)
 
-use crate::{ApiError, ApiResult, Request, Response};
+use vaya_common::metrics;
+use vaya_fleet::{MemberHealth, RaftState};
+
+use super::extract_field;
+use crate::{
+    ApiError, ApiResult, FieldError, JsonObject, JsonValue, Request, Response, SystemState,
+    RATE_LIMITED_METRIC,
+};
 
 /// Check if user has admin role
 fn require_admin(req: &Request) -> ApiResult<()> {
@@ -14,6 +21,33 @@ fn require_admin(req: &Request) -> ApiResult<()> {
     Ok(())
 }
 
+/// Check that the user's roles grant `permission` under the application's RBAC
+fn require_permission(req: &Request, permission: &str) -> ApiResult<()> {
+    let user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    if !system_state(req)?.is_granted(user_id, &req.user_roles, permission) {
+        return Err(ApiError::Forbidden(format!(
+            "Missing permission: {}",
+            permission
+        )));
+    }
+    Ok(())
+}
+
+/// Check if user has the finance or admin role
+fn require_finance(req: &Request) -> ApiResult<()> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    if !req.has_role("finance") && !req.has_role("admin") {
+        return Err(ApiError::forbidden("Finance access required"));
+    }
+    Ok(())
+}
+
 /// GET /admin/users - List all users (admin only)
 pub fn admin_list_users_handler(req: &Request) -> ApiResult<Response> {
     require_admin(req)?;
@@ -59,9 +93,9 @@ pub fn admin_delete_user_handler(req: &Request) -> ApiResult<Response> {
 /// GET /admin/stats - Get system statistics (admin only)
 pub fn admin_get_stats_handler(req: &Request) -> ApiResult<Response> {
     require_admin(req)?;
-    // TODO: Implement admin stats retrieval
+    // TODO: Implement admin stats retrieval; "booking_holds" from HoldExpirySweeper::stats
     Ok(Response::ok().with_body(
-        br#"{"total_users":10000,"active_users":5000,"total_bookings":25000,"total_pools":500}"#
+        br#"{"total_users":10000,"active_users":5000,"total_bookings":25000,"total_pools":500,"booking_holds":{"reminded":0,"in_grace":0,"expired":0,"failed":0}}"#
             .to_vec(),
     ))
 }
@@ -94,9 +128,1080 @@ pub fn admin_get_oracle_stats_handler(req: &Request) -> ApiResult<Response> {
     ))
 }
 
+/// GET /admin/oracle/trace - Features and model outputs behind a prediction (admin only)
+///
+/// `route=KUL-SIN&date=YYYY-MM-DD&as_of=<unix seconds>` rebuilds the
+/// prediction from the history observed by `as_of`.
+pub fn admin_oracle_trace_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let mut errors = Vec::new();
+    let route = req.query("route").map(String::as_str).unwrap_or_default();
+    let valid_route = route.split_once('-').is_some_and(|(from, to)| {
+        from.len() == 3
+            && to.len() == 3
+            && from != to
+            && from
+                .chars()
+                .chain(to.chars())
+                .all(|c| c.is_ascii_alphabetic())
+    });
+    if !valid_route {
+        errors.push(FieldError::invalid(
+            "route",
+            "Route must be two airport codes, e.g. KUL-SIN",
+        ));
+    }
+    let date = req.query("date").map(String::as_str).unwrap_or_default();
+    let parts: Vec<&str> = date.split('-').collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.parse::<u16>().is_err()) {
+        errors.push(FieldError::invalid("date", "Date must be YYYY-MM-DD"));
+    }
+    let as_of = match req.query("as_of").map(|t| t.parse::<i64>()) {
+        Some(Ok(ts)) if ts > 0 => ts,
+        Some(_) => {
+            errors.push(FieldError::invalid(
+                "as_of",
+                "as_of must be a Unix timestamp in seconds",
+            ));
+            0
+        }
+        None => {
+            errors.push(FieldError::required("as_of"));
+            0
+        }
+    };
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    // TODO: Call OracleService::trace and serialize with vaya_core::trace_to_json
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("route", route.to_ascii_uppercase())
+            .field("date", date)
+            .field("as_of", as_of)
+            .field("model_version", "1.0.0")
+            .field(
+                "feature_names",
+                vec![
+                    "price",
+                    "days_before_departure",
+                    "day_of_week",
+                    "weekend_departure",
+                    "holiday",
+                ],
+            )
+            .field("features", Vec::<JsonValue>::new())
+            .field("scaling", JsonValue::Null)
+            .field("members", Vec::<JsonValue>::new())
+            .build(),
+    );
+    Ok(response)
+}
+
+/// Extract the mandatory audit reason from a request body
+fn require_reason(body: &str) -> ApiResult<String> {
+    extract_field(body, "reason")
+        .filter(|r| !r.trim().is_empty())
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("reason")]))
+}
+
+/// POST /admin/users/{id}/suspend - Suspend a user and revoke their sessions (admin only)
+pub fn admin_suspend_user_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing user ID"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_reason(&body)?;
+    // TODO: Call AdminService::suspend_user
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field("status", "suspended")
+            .field("sessions_revoked", true)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/users/{id}/unsuspend - Lift a user's suspension (admin only)
+pub fn admin_unsuspend_user_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing user ID"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_reason(&body)?;
+    // TODO: Call AdminService::unsuspend_user
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field("status", "active")
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/users/{id}/merge - Merge a duplicate user into another account (admin only)
+pub fn admin_merge_user_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing user ID"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let target = extract_field(&body, "into")
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("into")]))?;
+    if target == *id {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "into",
+            "Cannot merge an account into itself",
+        )]));
+    }
+    require_reason(&body)?;
+    // TODO: Call AdminService::merge_users
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("merged", id)
+            .field("into", target)
+            .field(
+                "moved",
+                JsonObject::new()
+                    .field("bookings", 0)
+                    .field("alerts", 0)
+                    .field("points", 0),
+            )
+            .build(),
+    );
+    Ok(response)
+}
+
+/// PUT /admin/users/{id}/tier - Override a user's tier, optionally until an expiry (admin only)
+pub fn admin_override_tier_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing user ID"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let tier = extract_field(&body, "tier")
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("tier")]))?;
+    if !matches!(tier.as_str(), "free" | "premium" | "enterprise") {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "tier",
+            "Tier must be one of: free, premium, enterprise",
+        )]));
+    }
+    let expires_at = match extract_field(&body, "expires_at") {
+        Some(raw) => {
+            let exp: i64 = raw.parse().map_err(|_| {
+                ApiError::ValidationError(vec![FieldError::invalid(
+                    "expires_at",
+                    "Expiry must be a Unix timestamp",
+                )])
+            })?;
+            if exp <= vaya_common::Timestamp::now().as_unix() {
+                return Err(ApiError::ValidationError(vec![FieldError::invalid(
+                    "expires_at",
+                    "Expiry must be in the future",
+                )]));
+            }
+            Some(exp)
+        }
+        None => None,
+    };
+    require_reason(&body)?;
+    // TODO: Call AdminService::override_tier
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field(
+                "tier_override",
+                JsonObject::new()
+                    .field("tier", tier)
+                    .field("expires_at", expires_at),
+            )
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/compliance/data-inventory - Where personal data is stored, by table and region (admin only)
+pub fn admin_data_inventory_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    if let Some(min_class) = req.query("min_class") {
+        if !matches!(min_class.as_str(), "internal" | "personal" | "sensitive") {
+            return Err(ApiError::ValidationError(vec![FieldError::invalid(
+                "min_class",
+                "Class must be one of: internal, personal, sensitive",
+            )]));
+        }
+    }
+    // TODO: Build from vaya_store::DataInventory over the registered table schemas
+    Ok(Response::ok().with_body(br#"{"tables":[{"table":"users","residency":"MY","highest_class":"sensitive","columns":[{"name":"email","class":"personal"},{"name":"phone","class":"personal"},{"name":"passport_number","class":"sensitive"}]}],"regions":{"MY":["users"]},"generated_at":"2026-01-09T00:00:00Z"}"#.to_vec()))
+}
+
+/// GET /admin/notifications/templates - Registered templates and the event types that send them (admin only)
+pub fn admin_list_templates_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    // TODO: Build from vaya_notification::TemplateEngine::usage
+    Ok(Response::ok().with_body(br#"{"templates":[{"template":"booking_confirmation_html","event_types":["booking_confirmation"]},{"template":"booking_confirmation_text","event_types":["booking_confirmation"]},{"template":"flight_reminder_text","event_types":["flight_reminder"]}],"total":3}"#.to_vec()))
+}
+
+/// POST /admin/notifications/templates/{name}/preview - Render a template with a sample or supplied context (admin only)
+pub fn admin_preview_template_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let name = req
+        .param("name")
+        .ok_or(ApiError::bad_request("Missing template name"))?;
+    // An empty body previews with sample data
+    // TODO: Call TemplateEngine::preview with the body's "context" object
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("template", name)
+            .field("subject", "Your Booking Confirmation")
+            .field("html", "<html>...</html>")
+            .field("text", "BOOKING CONFIRMED ...")
+            .field("sample_context", true)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/notifications/templates/{name}/test-send - Send a rendered template to a whitelisted address (admin only)
+pub fn admin_test_send_template_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let name = req
+        .param("name")
+        .ok_or(ApiError::bad_request("Missing template name"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let to = extract_field(&body, "to")
+        .filter(|t| !t.trim().is_empty())
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("to")]))?;
+    if !to.contains('@') {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "to",
+            "Must be an email address",
+        )]));
+    }
+    // TODO: Build with TemplateEngine::test_email against the configured whitelist and send
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("template", name)
+            .field("to", to)
+            .field("status", "sent")
+            .field("message_id", "test_123")
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/notifications/templates/lint - Check templates for variables their event types don't supply (admin only)
+pub fn admin_lint_templates_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    // TODO: Call TemplateEngine::lint, or lint_template for the "template" query
+    let _template = req.query("template");
+    Ok(Response::ok().with_body(br#"{"templates":[],"issues":0}"#.to_vec()))
+}
+
+/// POST /admin/jobs - Submit a long-running export or report job (admin only)
+pub fn admin_submit_job_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let job_type = extract_field(&body, "type")
+        .filter(|t| !t.trim().is_empty())
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("type")]))?;
+    if !job_type
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+    {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "type",
+            "Must be a job type name",
+        )]));
+    }
+    // TODO: Call JobManager::submit with the body's "params" object
+    let mut response = Response::accepted().with_header("Location", "/api/v1/admin/jobs/job_123");
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", "job_123")
+            .field("type", job_type)
+            .field("status", "queued")
+            .field("progress", 0)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/jobs/{id} - Job status and progress, with a download URL once complete (admin only)
+pub fn admin_get_job_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing job ID"))?;
+    // TODO: Call JobManager::get, and JobManager::download_url for succeeded jobs
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field("type", "bookings_export")
+            .field("status", "running")
+            .field("progress", 40)
+            .field("progress_message", "Exporting bookings")
+            .field("download_url", JsonValue::Null)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// DELETE /admin/jobs/{id} - Cancel a queued or running job (admin only)
+pub fn admin_cancel_job_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing job ID"))?;
+    // TODO: Call JobManager::cancel
+    let mut response = Response::accepted();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field("cancel_requested", true)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/jobs/{id}/download - Download a finished job's output through a signed URL
+///
+/// The signature is the authorization, so the link works from a browser
+/// download without the admin's bearer token.
+pub fn admin_download_job_handler(req: &Request) -> ApiResult<Response> {
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing job ID"))?;
+    let expires = req
+        .query("expires")
+        .and_then(|e| e.parse::<i64>().ok())
+        .ok_or(ApiError::forbidden("Invalid download link"))?;
+    let signature = req
+        .query("signature")
+        .filter(|s| !s.is_empty())
+        .ok_or(ApiError::forbidden("Invalid download link"))?;
+    // TODO: Call JobManager::download and stream the output
+    let _ = (id, expires, signature);
+    Ok(Response::ok()
+        .with_header("Content-Type", "text/csv")
+        .with_header(
+            "Content-Disposition",
+            "attachment; filename=\"bookings.csv\"",
+        )
+        .with_body(b"id,status\n".to_vec()))
+}
+
+/// GET /admin/bookings/{id}/timeline - Chronological events for a booking across systems (admin only)
+pub fn admin_booking_timeline_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing booking ID"))?;
+    if let Some(category) = req.query("category") {
+        const CATEGORIES: &[&str] = &[
+            "created",
+            "status",
+            "payment",
+            "refund",
+            "gds",
+            "ticketing",
+            "notification",
+            "schedule_change",
+            "support",
+            "admin",
+        ];
+        if !CATEGORIES.contains(&category.as_str()) {
+            return Err(ApiError::ValidationError(vec![FieldError::invalid(
+                "category",
+                "Unknown timeline category",
+            )]));
+        }
+    }
+    // TODO: Call TimelineService::timeline, filtered by the "category" query
+    let events = vec![
+        JsonObject::new()
+            .field("at", "2026-01-10T08:00:00Z")
+            .field("category", "created")
+            .field("source", "booking")
+            .field("summary", "Booking created (PENDING)")
+            .field("actor", "user_123")
+            .field("reference", JsonValue::Null),
+        JsonObject::new()
+            .field("at", "2026-01-10T08:01:00Z")
+            .field("category", "payment")
+            .field("source", "payments")
+            .field("summary", "CARD payment of 150000 MYR COMPLETED")
+            .field("actor", JsonValue::Null)
+            .field("reference", "pay_123"),
+    ];
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("booking_id", id)
+            .field("events", events)
+            .field("unavailable_sources", Vec::<JsonValue>::new())
+            .build(),
+    );
+    Ok(response)
+}
+
+/// Parse a comma-separated tag list from the "tags" query
+fn tag_filter(req: &Request) -> ApiResult<Vec<String>> {
+    let Some(raw) = req.query("tags") else {
+        return Ok(Vec::new());
+    };
+    raw.split(',')
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| {
+            vaya_book::normalize_tag(t).map_err(|e| {
+                ApiError::ValidationError(vec![FieldError::invalid("tags", &e.to_string())])
+            })
+        })
+        .collect()
+}
+
+/// GET /admin/bookings - List bookings, filtered by tags (admin only)
+///
+/// `tags=vip,chargeback` returns bookings carrying every listed tag.
+pub fn admin_list_bookings_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let tags = tag_filter(req)?;
+    // TODO: Call NotesService::find_by_tags (or the booking list when no tags are given)
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("bookings", Vec::<JsonValue>::new())
+            .field("total", 0)
+            .field("page", 1)
+            .field("page_size", 50)
+            .field("tags", tags)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/bookings/{id}/notes - Add a categorized note, notifying mentioned agents (admin only)
+pub fn admin_add_booking_note_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing booking ID"))?;
+    let body: JsonValue = req.json_body()?;
+    let content: String = body.field("content")?;
+    vaya_book::validate_note(&content).map_err(|e| {
+        ApiError::ValidationError(vec![FieldError::invalid("content", &e.to_string())])
+    })?;
+    let category = match body.field::<Option<String>>("category")? {
+        Some(raw) => vaya_book::NoteCategory::parse(&raw).ok_or_else(|| {
+            ApiError::ValidationError(vec![FieldError::invalid(
+                "category",
+                "Category must be one of: general, support, fraud-review, ops",
+            )])
+        })?,
+        None => vaya_book::NoteCategory::General,
+    };
+    let visibility = match body.field::<Option<String>>("visibility")? {
+        Some(raw) => vaya_book::NoteVisibility::parse(&raw).ok_or_else(|| {
+            ApiError::ValidationError(vec![FieldError::invalid(
+                "visibility",
+                "Visibility must be one of: internal, customer",
+            )])
+        })?,
+        None => vaya_book::NoteVisibility::Internal,
+    };
+    if category == vaya_book::NoteCategory::FraudReview
+        && visibility == vaya_book::NoteVisibility::Customer
+    {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "visibility",
+            "Fraud review notes must be internal",
+        )]));
+    }
+    // TODO: Call NotesService::add_note
+    let mut response = Response::created();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("booking_id", id)
+            .field("category", category.as_str())
+            .field("visibility", visibility.as_str())
+            .field("content", &content)
+            .field("mentions", vaya_book::parse_mentions(&content))
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/bookings/{id}/tags - Tag a booking (admin only)
+pub fn admin_add_booking_tag_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing booking ID"))?;
+    let body: JsonValue = req.json_body()?;
+    let tag = vaya_book::normalize_tag(&body.field::<String>("tag")?)
+        .map_err(|e| ApiError::ValidationError(vec![FieldError::invalid("tag", &e.to_string())]))?;
+    // TODO: Call NotesService::add_tags
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("booking_id", id)
+            .field("tags", JsonValue::Array(vec![tag.into()]))
+            .build(),
+    );
+    Ok(response)
+}
+
+/// DELETE /admin/bookings/{id}/tags/{tag} - Remove a tag from a booking (admin only)
+pub fn admin_remove_booking_tag_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing booking ID"))?;
+    let _tag = req
+        .param("tag")
+        .ok_or(ApiError::bad_request("Missing tag"))?;
+    // TODO: Call NotesService::remove_tag
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("booking_id", id)
+            .field("tags", JsonValue::Array(Vec::new()))
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/finance/price-variance - Daily displayed vs charged report (finance or admin)
+///
+/// `date=YYYY-MM-DD` selects the UTC day; captures beyond tolerance are
+/// listed with their settlement held.
+pub fn admin_price_variance_report_handler(req: &Request) -> ApiResult<Response> {
+    require_finance(req)?;
+    let date = req
+        .query("date")
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("date")]))?;
+    let parts: Vec<&str> = date.split('-').collect();
+    let valid = match parts.as_slice() {
+        [y, m, d] => match (y.parse::<i16>(), m.parse::<u8>(), d.parse::<u8>()) {
+            (Ok(y), Ok(m), Ok(d)) => vaya_common::Date::new(y, m, d).is_valid(),
+            _ => false,
+        },
+        _ => false,
+    };
+    if !valid {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "date",
+            "Date must be YYYY-MM-DD",
+        )]));
+    }
+    // TODO: Call PriceVarianceMonitor::daily_report
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("date", date.as_str())
+            .field("captures", 0)
+            .field("currencies", Vec::<JsonValue>::new())
+            .field("violations", Vec::<JsonValue>::new())
+            .build(),
+    );
+    Ok(response)
+}
+
+/// Normalize a promo code, checking it is 3-20 letters, digits or dashes
+fn promo_code(raw: &str) -> ApiResult<String> {
+    let code = raw.trim().to_ascii_uppercase();
+    if !(3..=20).contains(&code.len())
+        || !code
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'-')
+    {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "code",
+            "Promo code must be 3-20 letters, digits or dashes",
+        )]));
+    }
+    Ok(code)
+}
+
+/// Validate a promo code's settings, returning them for the response
+fn promotion_settings(body: &JsonValue) -> ApiResult<JsonObject> {
+    let mut errors = Vec::new();
+    let campaign: String = body.field("campaign")?;
+    if campaign.trim().is_empty() {
+        errors.push(FieldError::required("campaign"));
+    }
+
+    let kind: String = body.field("type")?;
+    let value: i64 = body.field("value")?;
+    let currency: Option<String> = body.field("currency")?;
+    match kind.as_str() {
+        "percentage" if !(1..=10_000).contains(&value) => errors.push(FieldError::invalid(
+            "value",
+            "Percentage must be 1-10000 basis points",
+        )),
+        "fixed" if value <= 0 => errors.push(FieldError::invalid(
+            "value",
+            "Fixed discount must be a positive amount in minor units",
+        )),
+        "fixed" if currency.as_deref().map(str::len) != Some(3) => {
+            errors.push(FieldError::required("currency"));
+        }
+        "percentage" | "fixed" => {}
+        _ => errors.push(FieldError::invalid(
+            "type",
+            "Type must be one of: percentage, fixed",
+        )),
+    }
+    let cap: Option<i64> = body.field("cap")?;
+    if cap.is_some_and(|c| c <= 0) {
+        errors.push(FieldError::invalid("cap", "Cap must be positive"));
+    }
+
+    let valid_from: i64 = body.field("valid_from")?;
+    let valid_until: i64 = body.field("valid_until")?;
+    if valid_until <= valid_from {
+        errors.push(FieldError::invalid(
+            "valid_until",
+            "Promo code must end after it starts",
+        ));
+    }
+
+    let max_uses: Option<u32> = body.field("max_uses")?;
+    let max_uses_per_user: Option<u32> = body.field("max_uses_per_user")?;
+    for (field, limit) in [
+        ("max_uses", max_uses),
+        ("max_uses_per_user", max_uses_per_user),
+    ] {
+        if limit == Some(0) {
+            errors.push(FieldError::invalid(field, "Limit must be at least 1"));
+        }
+    }
+
+    let cabins: Vec<String> = body.field::<Option<_>>("cabins")?.unwrap_or_default();
+    if cabins.iter().any(|c| {
+        !matches!(
+            c.as_str(),
+            "economy" | "premium_economy" | "business" | "first"
+        )
+    }) {
+        errors.push(FieldError::invalid(
+            "cabins",
+            "Cabins must be: economy, premium_economy, business, first",
+        ));
+    }
+    let routes: Vec<String> = body
+        .field::<Option<Vec<String>>>("routes")?
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.to_ascii_uppercase())
+        .collect();
+    let valid_routes = routes.iter().all(|r| {
+        r.split_once('-')
+            .is_some_and(|(from, to)| vaya_common::Route::from_codes(from, to).is_valid())
+    });
+    if !valid_routes {
+        errors.push(FieldError::invalid(
+            "routes",
+            "Routes must be airport code pairs, e.g. KUL-SIN",
+        ));
+    }
+    let stackable = body.field::<Option<bool>>("stackable")?.unwrap_or(false);
+
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    Ok(JsonObject::new()
+        .field("campaign", campaign.trim())
+        .field("type", kind)
+        .field("value", value)
+        .field("currency", currency.map(|c| c.to_ascii_uppercase()))
+        .field("cap", cap)
+        .field("valid_from", valid_from)
+        .field("valid_until", valid_until)
+        .field("max_uses", max_uses)
+        .field("max_uses_per_user", max_uses_per_user)
+        .field("cabins", cabins)
+        .field("routes", routes)
+        .field("stackable", stackable))
+}
+
+/// GET /admin/promotions - List promo codes, optionally for one campaign (admin only)
+pub fn admin_list_promotions_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let campaign = req.query("campaign").map(String::as_str);
+    // TODO: Call PromotionService::list with the "campaign" query
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("campaign", campaign)
+            .field("promotions", Vec::<JsonValue>::new())
+            .field("total", 0)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/promotions - Create a promo code (admin only)
+pub fn admin_create_promotion_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let body: JsonValue = req.json_body()?;
+    let code = promo_code(&body.field::<String>("code")?)?;
+    let settings = promotion_settings(&body)?;
+    // TODO: Call PromotionService::create
+    let mut response = Response::created();
+    response.set_json_body(
+        &settings
+            .field("code", code)
+            .field("active", true)
+            .field("uses", 0)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/promotions/{id} - Promo code settings and usage (admin only)
+pub fn admin_get_promotion_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let code = promo_code(
+        req.param("id")
+            .ok_or(ApiError::bad_request("Missing promo code"))?,
+    )?;
+    // TODO: Call PromotionService::get and PromotionService::uses
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("code", code)
+            .field("active", true)
+            .field("uses", 0)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// PUT /admin/promotions/{id} - Replace a promo code's settings, or switch it on or off (admin only)
+///
+/// A body with only `active` toggles the code; otherwise all settings are
+/// replaced.
+pub fn admin_update_promotion_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let code = promo_code(
+        req.param("id")
+            .ok_or(ApiError::bad_request("Missing promo code"))?,
+    )?;
+    let body: JsonValue = req.json_body()?;
+    let active: Option<bool> = body.field("active")?;
+    let settings = match (active, body.field::<Option<String>>("type")?) {
+        // TODO: Call PromotionService::set_active
+        (Some(_), None) => JsonObject::new(),
+        // TODO: Call PromotionService::update
+        _ => promotion_settings(&body)?,
+    };
+    let mut response = Response::ok();
+    response.set_json_body(
+        &settings
+            .field("code", code)
+            .field("active", active.unwrap_or(true))
+            .build(),
+    );
+    Ok(response)
+}
+
+/// DELETE /admin/promotions/{id} - Delete an unused promo code (admin only)
+///
+/// Codes that have been redeemed are kept for their history and must be
+/// deactivated instead.
+pub fn admin_delete_promotion_handler(req: &Request) -> ApiResult<Response> {
+    require_admin(req)?;
+    let code = promo_code(
+        req.param("id")
+            .ok_or(ApiError::bad_request("Missing promo code"))?,
+    )?;
+    // TODO: Call PromotionService::delete
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("code", code)
+            .field("deleted", true)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// Permission to view system internals
+const SYSTEM_READ: &str = "system:read";
+
+/// Permission to run operational controls
+const SYSTEM_MANAGE: &str = "system:manage";
+
+/// GET /admin/system - Overview of storage, cache, rate limits and fleet (system:read)
+///
+/// Components this node doesn't run are reported as `null`.
+pub fn admin_system_overview_handler(req: &Request) -> ApiResult<Response> {
+    require_permission(req, SYSTEM_READ)?;
+    let system = system_state(req)?;
+    let db = system.db().ok().map(|db| {
+        let stats = db.stats();
+        JsonObject::new()
+            .field("sequence", stats.sequence)
+            .field(
+                "tables",
+                stats.levels.iter().map(|l| l.table_count).sum::<usize>(),
+            )
+            .field("stale_tables", db.stale_table_count())
+    });
+    let cache = system.cache().ok().map(|cache| {
+        let stats = cache.stats();
+        JsonObject::new()
+            .field("size", stats.size)
+            .field("hit_rate", stats.hit_rate)
+    });
+    let fleet = system.cluster().ok().map(|cluster| {
+        let view = cluster.membership();
+        JsonObject::new()
+            .field("node_id", view.node_id.as_str())
+            .field("state", raft_state(view.state))
+            .field("members", view.members.len())
+    });
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("uptime_seconds", system.uptime_seconds())
+            .field("db", db)
+            .field("cache", cache)
+            .field("rate_limits", rate_limits_json(system))
+            .field("fleet", fleet)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/system/db - Memtable, per-level SSTable counts and stale tables (system:read)
+///
+/// Stale tables are sealed with a rotated-out key (or none) and are
+/// rewritten as the database flushes.
+pub fn admin_system_db_handler(req: &Request) -> ApiResult<Response> {
+    require_permission(req, SYSTEM_READ)?;
+    let db = system_state(req)?.db()?;
+    let stats = db.stats();
+    let levels: Vec<JsonValue> = stats
+        .levels
+        .iter()
+        .map(|level| {
+            JsonObject::new()
+                .field("level", level.level)
+                .field("tables", level.table_count)
+                .field("size", level.total_size)
+                .field("entries", level.total_entries)
+                .build()
+        })
+        .collect();
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field(
+                "memtable",
+                JsonObject::new()
+                    .field("size", stats.memtable_size)
+                    .field("entries", stats.memtable_entries),
+            )
+            .field("immutable_memtables", stats.immutable_count)
+            .field("sequence", stats.sequence)
+            .field("levels", levels)
+            .field("stale_tables", db.stale_table_count())
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/system/cache - Cache size, hit rate and evictions (system:read)
+pub fn admin_system_cache_handler(req: &Request) -> ApiResult<Response> {
+    require_permission(req, SYSTEM_READ)?;
+    let stats = system_state(req)?.cache()?.stats();
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("size", stats.size)
+            .field("hits", stats.hits)
+            .field("misses", stats.misses)
+            .field("evictions", stats.evictions)
+            .field("coalesced", stats.coalesced)
+            .field("hit_rate", stats.hit_rate)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /admin/system/rate-limits - Tracked keys and rejections per tier (system:read)
+///
+/// Keys are only counted when limits are shared across the fleet; the
+/// per-process store isn't inspectable.
+pub fn admin_system_rate_limits_handler(req: &Request) -> ApiResult<Response> {
+    require_permission(req, SYSTEM_READ)?;
+    let mut response = Response::ok();
+    response.set_json_body(&rate_limits_json(system_state(req)?).build());
+    Ok(response)
+}
+
+/// GET /admin/system/fleet - This node's view of cluster membership (system:read)
+pub fn admin_system_fleet_handler(req: &Request) -> ApiResult<Response> {
+    require_permission(req, SYSTEM_READ)?;
+    let view = system_state(req)?.cluster()?.membership();
+    let members: Vec<JsonValue> = view
+        .members
+        .iter()
+        .map(|member| {
+            JsonObject::new()
+                .field("id", member.id.as_str())
+                .field("address", &member.address)
+                .field("is_leader", member.is_leader)
+                .field("health", member_health(member.health))
+                .field("last_seen_ms", member.last_seen_ms)
+                .build()
+        })
+        .collect();
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("node_id", view.node_id.as_str())
+            .field("state", raft_state(view.state))
+            .field("term", view.term)
+            .field("leader", view.leader.as_ref().map(|id| id.as_str()))
+            .field("commit_index", view.commit_index)
+            .field("change_pending", view.change_pending)
+            .field("members", members)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /admin/system/cache/purge - Drop cached entries (system:manage)
+///
+/// With no body everything goes; `{"prefix":"search:"}` drops one
+/// namespace and `{"expired_only":true}` only what has already expired.
+pub fn admin_system_purge_cache_handler(req: &Request) -> ApiResult<Response> {
+    require_permission(req, SYSTEM_MANAGE)?;
+    let body = req.body_string().unwrap_or_default();
+    let prefix = extract_field(&body, "prefix");
+    let expired_only = match extract_field(&body, "expired_only").as_deref() {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(_) => {
+            return Err(ApiError::ValidationError(vec![FieldError::invalid(
+                "expired_only",
+                "Must be true or false",
+            )]))
+        }
+    };
+    if let Some(ref prefix) = prefix {
+        if prefix.is_empty() || prefix.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(ApiError::ValidationError(vec![FieldError::invalid(
+                "prefix",
+                "Must be a non-empty key prefix without spaces",
+            )]));
+        }
+    }
+    if prefix.is_some() && expired_only {
+        return Err(ApiError::bad_request(
+            "Use either prefix or expired_only, not both",
+        ));
+    }
+    let cache = system_state(req)?.cache()?;
+    let (scope, purged) = match (&prefix, expired_only) {
+        (Some(prefix), _) => (
+            format!("prefix:{}", prefix),
+            cache.invalidate_prefix(prefix),
+        ),
+        (None, true) => ("expired".to_string(), cache.purge_expired()),
+        (None, false) => {
+            let purged = cache.len();
+            cache.clear();
+            ("all".to_string(), purged)
+        }
+    };
+    tracing::info!(scope = %scope, purged, "Cache purged from the admin API");
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("scope", scope)
+            .field("purged", purged)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// System state attached to the request by the server
+fn system_state(req: &Request) -> ApiResult<&SystemState> {
+    req.system
+        .as_deref()
+        .ok_or_else(|| ApiError::ServiceUnavailable("System state unavailable".into()))
+}
+
+/// Tracked keys (fleet-wide store only) and rejections per tier
+fn rate_limits_json(system: &SystemState) -> JsonObject {
+    let rejected = ["none", "free", "premium", "enterprise"].iter().fold(
+        JsonObject::new(),
+        |rejected, tier| {
+            let count = metrics::global()
+                .counter(RATE_LIMITED_METRIC, &[("tier", tier)])
+                .get();
+            rejected.field(tier, count)
+        },
+    );
+    let shared = system.rate_limits();
+    JsonObject::new()
+        .field("store", if shared.is_some() { "shared" } else { "local" })
+        .field("tracked_keys", shared.map(|limits| limits.len()))
+        .field("rejected", rejected)
+}
+
+fn raft_state(state: RaftState) -> &'static str {
+    match state {
+        RaftState::Follower => "follower",
+        RaftState::Candidate => "candidate",
+        RaftState::Leader => "leader",
+    }
+}
+
+fn member_health(health: MemberHealth) -> &'static str {
+    match health {
+        MemberHealth::Alive => "alive",
+        MemberHealth::Suspect => "suspect",
+        MemberHealth::Unreachable => "unreachable",
+        MemberHealth::Unknown => "unknown",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, RwLock};
+    use vaya_auth::{RbacManager, Role};
+    use vaya_cache::Cache;
+    use vaya_db::{DbConfig, VayaDb};
 
     #[test]
     fn test_admin_list_users_handler() {
@@ -116,6 +1221,159 @@ mod tests {
         assert_eq!(resp.status, 200);
     }
 
+    #[test]
+    fn test_admin_data_inventory_handler() {
+        let mut req = Request::new("GET", "/admin/compliance/data-inventory");
+        req.user_id = Some("admin_123".into());
+        req.user_roles = vec!["admin".into()];
+        let resp = admin_data_inventory_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+
+        req.query_params.insert("min_class".into(), "secret".into());
+        assert!(admin_data_inventory_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_admin_template_handlers() {
+        let mut req = admin_request(
+            "POST",
+            "/admin/notifications/templates/welcome/preview",
+            "",
+            "",
+        );
+        req.path_params.insert("name".into(), "welcome".into());
+        assert_eq!(admin_preview_template_handler(&req).unwrap().status, 200);
+
+        req.body = br#"{"to":"not-an-email"}"#.to_vec();
+        assert!(admin_test_send_template_handler(&req).is_err());
+        req.body = br#"{"to":"qa@vaya.my"}"#.to_vec();
+        assert_eq!(admin_test_send_template_handler(&req).unwrap().status, 200);
+
+        assert_eq!(admin_lint_templates_handler(&req).unwrap().status, 200);
+        assert_eq!(admin_list_templates_handler(&req).unwrap().status, 200);
+    }
+
+    fn system(dir: &std::path::Path) -> Arc<SystemState> {
+        let db = VayaDb::open(DbConfig::new(dir)).unwrap();
+        let cache = Cache::new(64, 4);
+        cache.insert("search:KUL:NRT".to_string(), b"[]".to_vec(), None);
+        cache.insert("search:KUL:SIN".to_string(), b"[]".to_vec(), None);
+        cache.insert("fx:MYR".to_string(), b"4.7".to_vec(), None);
+        Arc::new(
+            SystemState::new(Arc::new(RwLock::new(RbacManager::with_default_roles())))
+                .with_db(Arc::new(db))
+                .with_cache(Arc::new(cache)),
+        )
+    }
+
+    fn system_request(method: &str, path: &str, body: &str, system: &Arc<SystemState>) -> Request {
+        let mut req = admin_request(method, path, "", body);
+        req.system = Some(Arc::clone(system));
+        req
+    }
+
+    #[test]
+    fn test_admin_system_handlers() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = system(dir.path());
+        let req = system_request("GET", "/admin/system", "", &system);
+        for handler in [
+            admin_system_overview_handler,
+            admin_system_db_handler,
+            admin_system_cache_handler,
+            admin_system_rate_limits_handler,
+        ] {
+            assert_eq!(handler(&req).unwrap().status, 200);
+        }
+        let body = |resp: Response| String::from_utf8(resp.body).unwrap();
+        let cache = body(admin_system_cache_handler(&req).unwrap());
+        assert!(cache.contains(r#""size":3"#));
+        let db = body(admin_system_db_handler(&req).unwrap());
+        assert!(db.contains(r#""stale_tables":0"#));
+        let overview = body(admin_system_overview_handler(&req).unwrap());
+        assert!(overview.contains(r#""fleet":null"#));
+        assert!(overview.contains(r#""store":"local","tracked_keys":null"#));
+
+        // No cluster on this node
+        assert!(matches!(
+            admin_system_fleet_handler(&req),
+            Err(ApiError::ServiceUnavailable(_))
+        ));
+
+        // Without system state nothing is granted or reported
+        let mut detached = req.clone();
+        detached.system = None;
+        assert!(matches!(
+            admin_system_db_handler(&detached),
+            Err(ApiError::ServiceUnavailable(_))
+        ));
+
+        // Roles without the system permissions are refused
+        let mut user = req.clone();
+        user.user_roles = vec!["moderator".into()];
+        assert!(matches!(
+            admin_system_overview_handler(&user),
+            Err(ApiError::Forbidden(_))
+        ));
+        user.user_id = None;
+        assert!(matches!(
+            admin_system_overview_handler(&user),
+            Err(ApiError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_admin_system_uses_application_rbac() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = system(dir.path());
+        let mut req = system_request("GET", "/admin/system/cache", "", &system);
+        req.user_roles = vec!["ops".into()];
+        assert!(admin_system_cache_handler(&req).is_err());
+
+        // Roles added at runtime apply straight away
+        system
+            .rbac()
+            .write()
+            .unwrap()
+            .add_role(Role::new("ops").with_permission(SYSTEM_READ));
+        assert_eq!(admin_system_cache_handler(&req).unwrap().status, 200);
+
+        // So do roles assigned to the user
+        req.user_roles.clear();
+        assert!(admin_system_cache_handler(&req).is_err());
+        system
+            .rbac()
+            .write()
+            .unwrap()
+            .assign_role("admin_123", "ops")
+            .unwrap();
+        assert_eq!(admin_system_cache_handler(&req).unwrap().status, 200);
+    }
+
+    #[test]
+    fn test_admin_system_purge_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = system(dir.path());
+        let purge = |body: &str| {
+            let req = system_request("POST", "/admin/system/cache/purge", body, &system);
+            admin_system_purge_cache_handler(&req)
+        };
+        let result = |body: &str| String::from_utf8(purge(body).unwrap().body).unwrap();
+        assert!(purge(r#"{"prefix":"a b"}"#).is_err());
+        assert!(purge(r#"{"prefix":"search:","expired_only":true}"#).is_err());
+
+        assert_eq!(
+            result(r#"{"prefix":"search:"}"#),
+            r#"{"scope":"prefix:search:","purged":2}"#
+        );
+        assert_eq!(
+            result(r#"{"expired_only":true}"#),
+            r#"{"scope":"expired","purged":0}"#
+        );
+        assert_eq!(result(""), r#"{"scope":"all","purged":1}"#);
+        assert!(system.cache().unwrap().is_empty());
+    }
+
     #[test]
     fn test_admin_requires_role() {
         let mut req = Request::new("GET", "/admin/users");
@@ -124,4 +1382,267 @@ mod tests {
         let result = admin_list_users_handler(&req);
         assert!(result.is_err());
     }
+
+    fn admin_request(method: &str, path: &str, id: &str, body: &str) -> Request {
+        let mut req = Request::new(method, path);
+        req.user_id = Some("admin_123".into());
+        req.user_roles = vec!["admin".into()];
+        req.path_params.insert("id".into(), id.into());
+        req.body = body.as_bytes().to_vec();
+        req
+    }
+
+    #[test]
+    fn test_oracle_trace_handler() {
+        let mut req = admin_request("GET", "/admin/oracle/trace", "", "");
+        let err = admin_oracle_trace_handler(&req).unwrap_err();
+        assert!(matches!(err, ApiError::ValidationError(ref e) if e.len() == 3));
+
+        req.query_params.insert("route".into(), "kul-sin".into());
+        req.query_params.insert("date".into(), "2026-02-01".into());
+        req.query_params.insert("as_of".into(), "yesterday".into());
+        assert!(admin_oracle_trace_handler(&req).is_err());
+
+        req.query_params.insert("as_of".into(), "1767225600".into());
+        let resp = admin_oracle_trace_handler(&req).unwrap();
+        let body = String::from_utf8_lossy(&resp.body).to_string();
+        assert!(body.contains(r#""route":"KUL-SIN""#));
+        assert!(body.contains(r#""as_of":1767225600"#));
+
+        req.user_roles.clear();
+        assert!(admin_oracle_trace_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_price_variance_report_handler() {
+        let mut req = admin_request("GET", "/admin/finance/price-variance", "", "");
+        req.user_roles = vec!["finance".into()];
+        assert!(admin_price_variance_report_handler(&req).is_err());
+
+        req.query_params.insert("date".into(), "2026-02-30".into());
+        assert!(admin_price_variance_report_handler(&req).is_err());
+
+        req.query_params.insert("date".into(), "2026-02-01".into());
+        let resp = admin_price_variance_report_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+        assert!(String::from_utf8_lossy(&resp.body).contains(r#""date":"2026-02-01""#));
+
+        req.user_roles = vec!["support".into()];
+        assert!(admin_price_variance_report_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_admin_suspend_requires_reason() {
+        let req = admin_request("POST", "/admin/users/u1/suspend", "u1", r#"{"reason":""}"#);
+        assert!(admin_suspend_user_handler(&req).is_err());
+
+        let req = admin_request(
+            "POST",
+            "/admin/users/u1/suspend",
+            "u1",
+            r#"{"reason":"fraud"}"#,
+        );
+        let resp = admin_suspend_user_handler(&req).unwrap();
+        assert!(String::from_utf8(resp.body).unwrap().contains("suspended"));
+    }
+
+    #[test]
+    fn test_admin_user_handlers_escape_path_id() {
+        let id = r#"u1","admin":true"#;
+        let req = admin_request("POST", "/admin/users/x/unsuspend", id, r#"{"reason":"ok"}"#);
+        let resp = admin_unsuspend_user_handler(&req).unwrap();
+        let body = JsonValue::parse(&String::from_utf8(resp.body).unwrap()).unwrap();
+        assert_eq!(body.get("id").and_then(JsonValue::as_str), Some(id));
+        assert!(body.get("admin").is_none());
+    }
+
+    #[test]
+    fn test_admin_merge_validation() {
+        let req = admin_request(
+            "POST",
+            "/admin/users/u1/merge",
+            "u1",
+            r#"{"into":"u1","reason":"dup"}"#,
+        );
+        assert!(admin_merge_user_handler(&req).is_err());
+
+        let req = admin_request(
+            "POST",
+            "/admin/users/u1/merge",
+            "u1",
+            r#"{"into":"u2","reason":"dup"}"#,
+        );
+        assert_eq!(admin_merge_user_handler(&req).unwrap().status, 200);
+    }
+
+    #[test]
+    fn test_admin_override_tier_validation() {
+        let req = admin_request(
+            "PUT",
+            "/admin/users/u1/tier",
+            "u1",
+            r#"{"tier":"platinum","reason":"vip"}"#,
+        );
+        assert!(admin_override_tier_handler(&req).is_err());
+
+        let req = admin_request(
+            "PUT",
+            "/admin/users/u1/tier",
+            "u1",
+            r#"{"tier":"premium","expires_at":1,"reason":"vip"}"#,
+        );
+        assert!(admin_override_tier_handler(&req).is_err());
+
+        let req = admin_request(
+            "PUT",
+            "/admin/users/u1/tier",
+            "u1",
+            r#"{"tier":"premium","reason":"vip"}"#,
+        );
+        assert_eq!(admin_override_tier_handler(&req).unwrap().status, 200);
+    }
+
+    #[test]
+    fn test_admin_job_handlers() {
+        let req = admin_request("POST", "/admin/jobs", "", r#"{"params":{}}"#);
+        assert!(admin_submit_job_handler(&req).is_err());
+        let req = admin_request("POST", "/admin/jobs", "", r#"{"type":"bookings_export"}"#);
+        assert_eq!(admin_submit_job_handler(&req).unwrap().status, 202);
+
+        let req = admin_request("GET", "/admin/jobs/job_123", "job_123", "");
+        assert_eq!(admin_get_job_handler(&req).unwrap().status, 200);
+        assert_eq!(admin_cancel_job_handler(&req).unwrap().status, 202);
+
+        let id = r#"job_123","status":"succeeded"#;
+        let req = admin_request("DELETE", "/admin/jobs/x", id, "");
+        let resp = admin_cancel_job_handler(&req).unwrap();
+        let body = JsonValue::parse(&String::from_utf8(resp.body).unwrap()).unwrap();
+        assert_eq!(body.get("id").and_then(JsonValue::as_str), Some(id));
+        assert!(body.get("status").is_none());
+
+        // Download needs a signature but no session
+        let mut req = Request::new("GET", "/admin/jobs/job_123/download");
+        req.path_params.insert("id".into(), "job_123".into());
+        assert!(admin_download_job_handler(&req).is_err());
+        req.query_params
+            .insert("expires".into(), "1700000000".into());
+        req.query_params.insert("signature".into(), "ab".into());
+        assert_eq!(admin_download_job_handler(&req).unwrap().status, 200);
+    }
+
+    #[test]
+    fn test_admin_booking_timeline_handler() {
+        let mut req = admin_request("GET", "/admin/bookings/bk_1/timeline", "bk_1", "");
+        let resp = admin_booking_timeline_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+        let body = JsonValue::parse(&String::from_utf8(resp.body).unwrap()).unwrap();
+        assert_eq!(
+            body.get("events")
+                .and_then(JsonValue::as_array)
+                .map(<[_]>::len),
+            Some(2)
+        );
+        req.query_params.insert("category".into(), "weather".into());
+        assert!(admin_booking_timeline_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_admin_booking_notes_and_tags() {
+        let mut req = admin_request("GET", "/admin/bookings", "", "");
+        req.query_params
+            .insert("tags".into(), "VIP,chargeback".into());
+        let resp = admin_list_bookings_handler(&req).unwrap();
+        assert!(String::from_utf8(resp.body)
+            .unwrap()
+            .contains(r#""tags":["vip","chargeback"]"#));
+        req.query_params.insert("tags".into(), "group desk".into());
+        assert!(admin_list_bookings_handler(&req).is_err());
+
+        let req = admin_request(
+            "POST",
+            "/admin/bookings/bk_1/notes",
+            "bk_1",
+            r#"{"category":"fraud-review","visibility":"customer","content":"Velocity check"}"#,
+        );
+        assert!(admin_add_booking_note_handler(&req).is_err());
+        let req = admin_request(
+            "POST",
+            "/admin/bookings/bk_1/notes",
+            "bk_1",
+            r#"{"category":"ops","content":"@siti please chase the \"WCHR\" SSR"}"#,
+        );
+        let resp = admin_add_booking_note_handler(&req).unwrap();
+        assert_eq!(resp.status, 201);
+        assert!(String::from_utf8(resp.body)
+            .unwrap()
+            .contains(r#""mentions":["siti"]"#));
+
+        let req = admin_request(
+            "POST",
+            "/admin/bookings/bk_1/tags",
+            "bk_1",
+            r#"{"tag":"a b"}"#,
+        );
+        assert!(admin_add_booking_tag_handler(&req).is_err());
+        let id = r#"bk_1","tags":["vip"]}"#;
+        let req = admin_request("POST", "/admin/bookings/x/tags", id, r#"{"tag":"VIP"}"#);
+        let resp = admin_add_booking_tag_handler(&req).unwrap();
+        let body = JsonValue::parse(&String::from_utf8(resp.body).unwrap()).unwrap();
+        assert_eq!(body.get("booking_id").and_then(JsonValue::as_str), Some(id));
+        assert_eq!(
+            body.to_string(),
+            format!(r#"{{"booking_id":{},"tags":["vip"]}}"#, JsonValue::from(id))
+        );
+        let mut req = admin_request("DELETE", "/admin/bookings/bk_1/tags/vip", "bk_1", "");
+        req.path_params.insert("tag".into(), "vip".into());
+        assert_eq!(admin_remove_booking_tag_handler(&req).unwrap().status, 200);
+    }
+
+    #[test]
+    fn test_admin_promotion_handlers() {
+        let req = admin_request(
+            "POST",
+            "/admin/promotions",
+            "",
+            r#"{"code":"merdeka10","campaign":"merdeka","type":"percentage","value":1000,"cap":5000,"valid_from":1754006400,"valid_until":1756684800,"max_uses_per_user":1,"routes":["kul-sin"],"stackable":true}"#,
+        );
+        let resp = admin_create_promotion_handler(&req).unwrap();
+        assert_eq!(resp.status, 201);
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains(r#""code":"MERDEKA10""#));
+        assert!(body.contains(r#""routes":["KUL-SIN"]"#));
+
+        // Fixed amounts need a currency, windows must end after they start
+        let req = admin_request(
+            "POST",
+            "/admin/promotions",
+            "",
+            r#"{"code":"RM50","campaign":"raya","type":"fixed","value":5000,"valid_from":1756684800,"valid_until":1754006400}"#,
+        );
+        let err = admin_create_promotion_handler(&req).unwrap_err();
+        assert!(matches!(err, ApiError::ValidationError(ref e) if e.len() == 2));
+        let req = admin_request(
+            "POST",
+            "/admin/promotions",
+            "",
+            r#"{"code":"no way","campaign":"raya","type":"percentage","value":500,"valid_from":1,"valid_until":2}"#,
+        );
+        assert!(admin_create_promotion_handler(&req).is_err());
+
+        let req = admin_request(
+            "PUT",
+            "/admin/promotions/MERDEKA10",
+            "merdeka10",
+            r#"{"active":false}"#,
+        );
+        let resp = admin_update_promotion_handler(&req).unwrap();
+        assert!(String::from_utf8(resp.body)
+            .unwrap()
+            .contains(r#""active":false"#));
+
+        let req = admin_request("DELETE", "/admin/promotions/MERDEKA10", "MERDEKA10", "");
+        assert_eq!(admin_delete_promotion_handler(&req).unwrap().status, 200);
+        assert_eq!(admin_get_promotion_handler(&req).unwrap().status, 200);
+        assert_eq!(admin_list_promotions_handler(&req).unwrap().status, 200);
+    }
 }
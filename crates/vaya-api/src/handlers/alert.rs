@@ -1,6 +1,134 @@
 //! Alert handlers (6 handlers)
+//!
+//! Alerts are created with a `condition` object whose `type` picks the
+//! [`AlertCondition`]:
+//!
+//! - `price_below`: `threshold`
+//! - `percent_drop`: `reference_price`, `percent`
+//! - `below_average`: price under the 30-day average
+//! - `trend_falling`: trend turns falling
+//! - `cabin_below`: `cabin` (`economy`, `premium_economy`, `business`,
+//!   `first`), `threshold`
+//! - `any_price`
+//!
+//! A bare `target_price` is still accepted as `price_below`.
 
-use crate::{ApiError, ApiResult, Request, Response};
+use vaya_common::{CabinClass, MinorUnits};
+use vaya_oracle::AlertCondition;
+
+use crate::{
+    ApiError, ApiResult, FieldError, FromJson, JsonError, JsonObject, JsonValue, Request, Response,
+};
+
+/// Alert condition read from a request body
+struct ConditionBody(AlertCondition);
+
+impl FromJson for ConditionBody {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        let kind: String = value.field("type")?;
+        let threshold = || value.field::<i64>("threshold").map(MinorUnits::new);
+        let condition = match kind.as_str() {
+            "price_below" => AlertCondition::PriceBelow {
+                threshold: threshold()?,
+            },
+            "percent_drop" => AlertCondition::DropsByPercent {
+                reference: MinorUnits::new(value.field("reference_price")?),
+                percent: value.field("percent")?,
+            },
+            "below_average" => AlertCondition::BelowAverage,
+            "trend_falling" => AlertCondition::TrendTurnsFalling,
+            "cabin_below" => AlertCondition::CabinBelow {
+                cabin: value.field::<CabinBody>("cabin")?.0,
+                threshold: threshold()?,
+            },
+            "any_price" => AlertCondition::AnyPrice,
+            _ => {
+                return Err(JsonError::Type {
+                    field: "type".into(),
+                    expected: "alert condition type",
+                })
+            }
+        };
+        Ok(Self(condition))
+    }
+}
+
+/// Cabin name read from a request body
+struct CabinBody(CabinClass);
+
+impl FromJson for CabinBody {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        let cabin = match value.as_str() {
+            Some("economy") => CabinClass::Economy,
+            Some("premium_economy") => CabinClass::PremiumEconomy,
+            Some("business") => CabinClass::Business,
+            Some("first") => CabinClass::First,
+            _ => return Err(JsonError::expected("cabin class")),
+        };
+        Ok(Self(cabin))
+    }
+}
+
+/// Body of a create alert request
+struct CreateAlertRequest {
+    route: String,
+    condition: Option<ConditionBody>,
+    target_price: Option<i64>,
+}
+
+impl FromJson for CreateAlertRequest {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        Ok(Self {
+            route: value.field("route")?,
+            condition: value.field("condition")?,
+            target_price: value.field("target_price")?,
+        })
+    }
+}
+
+impl CreateAlertRequest {
+    /// The requested condition, checked
+    fn condition(&self) -> ApiResult<AlertCondition> {
+        let condition = match (&self.condition, self.target_price) {
+            (Some(ConditionBody(condition)), _) => *condition,
+            (None, Some(price)) => AlertCondition::PriceBelow {
+                threshold: MinorUnits::new(price),
+            },
+            (None, None) => {
+                return Err(ApiError::ValidationError(vec![FieldError::required(
+                    "condition",
+                )]))
+            }
+        };
+        condition.validate().map_err(|e| {
+            ApiError::ValidationError(vec![FieldError::invalid("condition", &e.to_string())])
+        })?;
+        Ok(condition)
+    }
+}
+
+/// Response form of an alert condition
+fn condition_json(condition: &AlertCondition) -> JsonValue {
+    let object = JsonObject::new().field("trigger", condition.trigger().as_str());
+    match *condition {
+        AlertCondition::PriceBelow { threshold } => object
+            .field("type", "price_below")
+            .field("threshold", threshold.as_i64()),
+        AlertCondition::DropsByPercent { reference, percent } => object
+            .field("type", "percent_drop")
+            .field("reference_price", reference.as_i64())
+            .field("percent", percent),
+        AlertCondition::BelowAverage => object.field("type", "below_average"),
+        AlertCondition::TrendTurnsFalling => object.field("type", "trend_falling"),
+        AlertCondition::CabinBelow { cabin, threshold } => object
+            .field("type", "cabin_below")
+            .field("cabin", cabin.as_str())
+            .field("threshold", threshold.as_i64()),
+        AlertCondition::AnyPrice => object.field("type", "any_price"),
+        AlertCondition::BestPrice => object.field("type", "best_price"),
+    }
+    .build()
+}
 
 /// POST /alerts - Create a price alert
 pub fn create_alert_handler(req: &Request) -> ApiResult<Response> {
@@ -11,11 +139,20 @@ pub fn create_alert_handler(req: &Request) -> ApiResult<Response> {
     if req.body.is_empty() {
         return Err(ApiError::bad_request("Missing request body"));
     }
-    // TODO: Implement alert creation
-    Ok(Response::created().with_body(
-        br#"{"alert_id":"alert_123","status":"active","created_at":"2026-01-09T00:00:00Z"}"#
-            .to_vec(),
-    ))
+    let body: CreateAlertRequest = req.json_body()?;
+    let condition = body.condition()?;
+    // TODO: Persist through an AlertRepository
+    let mut response = Response::created();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("alert_id", "alert_123")
+            .field("route", body.route)
+            .field("condition", condition_json(&condition))
+            .field("status", "active")
+            .field("created_at", "2026-01-09T00:00:00Z")
+            .build(),
+    );
+    Ok(response)
 }
 
 /// GET /alerts - List user's alerts
@@ -100,6 +237,45 @@ mod tests {
         assert_eq!(resp.status, 201);
     }
 
+    #[test]
+    fn test_create_alert_conditions() {
+        let create = |body: &str| {
+            let mut req = Request::new("POST", "/alerts");
+            req.user_id = Some("user_123".into());
+            req.body = body.as_bytes().to_vec();
+            create_alert_handler(&req)
+        };
+
+        let resp = create(
+            r#"{"route":"SIN-BKK","condition":{"type":"cabin_below","cabin":"business","threshold":90000}}"#,
+        )
+        .unwrap();
+        assert_eq!(resp.status, 201);
+        let body = JsonValue::parse(&resp.body_string().unwrap()).unwrap();
+        let condition = body.get("condition").unwrap();
+        assert_eq!(
+            condition.get("trigger").and_then(JsonValue::as_str),
+            Some("CABIN_PRICE_BELOW")
+        );
+        assert_eq!(
+            condition.get("cabin").and_then(JsonValue::as_str),
+            Some("business")
+        );
+
+        let resp = create(r#"{"route":"SIN-BKK","condition":{"type":"trend_falling"}}"#).unwrap();
+        assert_eq!(resp.status, 201);
+
+        // Invalid percentage, unknown type and missing fields are rejected
+        for body in [
+            r#"{"route":"SIN-BKK","condition":{"type":"percent_drop","reference_price":30000,"percent":120}}"#,
+            r#"{"route":"SIN-BKK","condition":{"type":"whenever"}}"#,
+            r#"{"route":"SIN-BKK","condition":{"type":"cabin_below","cabin":"business"}}"#,
+            r#"{"route":"SIN-BKK"}"#,
+        ] {
+            assert!(matches!(create(body), Err(ApiError::ValidationError(_))));
+        }
+    }
+
     #[test]
     fn test_list_alerts_handler() {
         let mut req = Request::new("GET", "/alerts");
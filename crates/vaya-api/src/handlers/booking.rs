@@ -1,6 +1,7 @@
-//! Booking handlers (8 handlers)
+//! Booking handlers (9 handlers)
 
-use crate::{ApiError, ApiResult, Request, Response};
+use super::extract_field;
+use crate::{ApiError, ApiResult, FieldError, Request, Response};
 
 /// POST /bookings - Create a new booking
 pub fn create_booking_handler(req: &Request) -> ApiResult<Response> {
@@ -18,6 +19,42 @@ pub fn create_booking_handler(req: &Request) -> ApiResult<Response> {
     ))
 }
 
+/// POST /bookings/verify-fare - Re-price an offer before checkout
+///
+/// Returns `price_changed` with both amounts when the difference is outside
+/// the configured tolerance, so the review page can ask the user to confirm.
+pub fn verify_fare_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let mut errors = Vec::new();
+    for field in ["offer_id", "displayed_amount", "currency"] {
+        if extract_field(&body, field).is_none() {
+            errors.push(FieldError::required(field));
+        }
+    }
+    if let Some(amount) = extract_field(&body, "displayed_amount") {
+        if amount.parse::<i64>().is_err() {
+            errors.push(FieldError::invalid(
+                "displayed_amount",
+                "Amount must be an integer in minor units",
+            ));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    // TODO: Call BookingService::verify_fare
+    Ok(Response::ok().with_body(
+        br#"{"status":"unchanged","displayed_amount":45000,"current_amount":45000,"currency":"MYR"}"#
+            .to_vec(),
+    ))
+}
+
 /// GET /bookings - List user's bookings
 pub fn list_bookings_handler(req: &Request) -> ApiResult<Response> {
     let _user_id = req
@@ -39,7 +76,7 @@ pub fn get_booking_handler(req: &Request) -> ApiResult<Response> {
         .ok_or(ApiError::unauthorized("Authentication required"))?;
     // TODO: Implement booking retrieval
     Ok(Response::ok().with_body(
-        br#"{"booking_id":"booking_123","status":"confirmed","passengers":[],"flights":[]}"#
+        br#"{"booking_id":"booking_123","status":"confirmed","passengers":[],"flights":[],"pricing":{"policy_version":1,"currency":"MYR","base_fare":30000,"taxes":8000,"carrier_fees":2000,"items":[{"name":"Service fee","category":"service_fee","amount":1500}],"total":41500}}"#
             .to_vec(),
     ))
 }
@@ -124,6 +161,20 @@ pub fn request_refund_handler(req: &Request) -> ApiResult<Response> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_verify_fare_handler() {
+        let mut req = Request::new("POST", "/bookings/verify-fare");
+        req.user_id = Some("user_123".into());
+        req.body =
+            br#"{"offer_id":"offer_123","displayed_amount":"abc","currency":"MYR"}"#.to_vec();
+        assert!(verify_fare_handler(&req).is_err());
+
+        req.body =
+            br#"{"offer_id":"offer_123","displayed_amount":45000,"currency":"MYR"}"#.to_vec();
+        let resp = verify_fare_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+    }
+
     #[test]
     fn test_create_booking_handler() {
         let mut req = Request::new("POST", "/bookings");
@@ -1,19 +1,19 @@
-//! API Handlers - All 71 REST API endpoint handlers
+//! API Handlers - All 111 REST API endpoint handlers
 //!
 //! Organized by domain:
 //! - auth: Authentication and session management (8 handlers)
-//! - search: Flight search and suggestions (6 handlers)
-//! - oracle: Price predictions (4 handlers)
-//! - booking: Booking management (8 handlers)
-//! - pool: Group buying pools (10 handlers)
+//! - search: Flight search, suggestions, airport autocomplete, saved searches, search defaults, and fare calendars (14 handlers)
+//! - oracle: Price predictions and verdicts (5 handlers)
+//! - booking: Booking management and fare re-verification (9 handlers)
+//! - pool: Group buying pools (12 handlers)
 //! - alert: Price alerts (6 handlers)
-//! - user: User profile and settings (11 handlers)
+//! - user: User profile, settings, contact verification, two-factor authentication, loyalty wallets, and notification preferences (24 handlers)
 //! - traveler: Traveler profiles (5 handlers)
 //! - payment: Payment processing (6 handlers)
 //! - trip: Trip management (6 handlers)
 //! - notification: Notifications (4 handlers)
 //! - support: Customer support tickets (4 handlers)
-//! - admin: Admin operations (8 handlers)
+//! - admin: Admin operations, compliance reports, and template tools, background jobs, booking timelines, booking notes and tags, price variance reports, promo codes, oracle prediction traces, and system stats and operational controls (38 handlers)
 
 pub mod admin;
 pub mod alert;
@@ -44,4 +44,27 @@ pub use trip::*;
 pub use user::*;
 
 /// Total number of API handlers
-pub const HANDLER_COUNT: usize = 71;
+pub const HANDLER_COUNT: usize = 123;
+
+/// Extract a field value from JSON string (simplified parser)
+pub(crate) fn extract_field(json: &str, field: &str) -> Option<String> {
+    let pattern = format!("\"{}\":", field);
+    let start = json.find(&pattern)?;
+    let value_start = start + pattern.len();
+    let rest = &json[value_start..];
+
+    // Skip whitespace
+    let rest = rest.trim_start();
+
+    if let Some(rest) = rest.strip_prefix('"') {
+        // String value
+        let end = rest.find('"')?;
+        Some(rest[..end].to_string())
+    } else if rest.starts_with("null") {
+        None
+    } else {
+        // Number or boolean
+        let end = rest.find([',', '}', ']']).unwrap_or(rest.len());
+        Some(rest[..end].trim().to_string())
+    }
+}
@@ -1,6 +1,6 @@
-//! Oracle/Prediction handlers (4 handlers)
+//! Oracle/Prediction handlers (5 handlers)
 
-use crate::{ApiError, ApiResult, Request, Response};
+use crate::{ApiError, ApiResult, FieldError, Request, Response};
 
 /// POST /oracle/predict - Get price prediction
 pub fn predict_handler(req: &Request) -> ApiResult<Response> {
@@ -39,6 +39,42 @@ pub fn get_oracle_accuracy_handler(_req: &Request) -> ApiResult<Response> {
     ))
 }
 
+/// GET /oracle/verdict?route=&date= - Combined prediction, insight, and recommendation
+pub fn get_oracle_verdict_handler(req: &Request) -> ApiResult<Response> {
+    let mut errors = Vec::new();
+    match req.query("route") {
+        None => errors.push(FieldError::required("route")),
+        Some(route) => {
+            let valid = route.split_once('-').is_some_and(|(from, to)| {
+                from.len() == 3
+                    && to.len() == 3
+                    && from != to
+                    && (from.chars().chain(to.chars())).all(|c| c.is_ascii_alphabetic())
+            });
+            if !valid {
+                errors.push(FieldError::invalid(
+                    "route",
+                    "Route must be two airport codes, e.g. KUL-SIN",
+                ));
+            }
+        }
+    }
+    match req.query("date") {
+        None => errors.push(FieldError::required("date")),
+        Some(date) => {
+            let parts: Vec<&str> = date.split('-').collect();
+            if parts.len() != 3 || parts.iter().any(|p| p.parse::<u16>().is_err()) {
+                errors.push(FieldError::invalid("date", "Date must be YYYY-MM-DD"));
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    // TODO: Call OracleService::verdict
+    Ok(Response::ok().with_body(br#"{"route":"KUL-SIN","date":"2026-02-01","currency":"MYR","prediction":{"predicted_price":36500,"price_low":34000,"price_high":39000,"confidence":0.78,"confidence_level":"HIGH","trend":"UP","expected_change_percent":6.5,"model_version":"1.0.0"},"insight":{"current_price":35000,"avg_price_30d":38000,"low_price_30d":33000,"high_price_30d":42000,"trend":"UP","deal_score":77,"position":"Good - below average"},"best_time":{"book_by":"2026-01-02","days_before":30,"season":"LOW","expected_price":29750,"confidence":0.6},"accuracy":{"accuracy":0.82,"mape":8.5,"samples":10000},"recommendation":{"action":"BOOK_SOON","confidence":0.64,"headline":"Book in the next few days","reasons":["Prices are expected to rise about 7%","Current price is good - below average"]}}"#.to_vec()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,4 +93,18 @@ mod tests {
         let resp = get_oracle_accuracy_handler(&req).unwrap();
         assert_eq!(resp.status, 200);
     }
+
+    #[test]
+    fn test_get_oracle_verdict_handler() {
+        let mut req = Request::new("GET", "/oracle/verdict");
+        assert!(get_oracle_verdict_handler(&req).is_err());
+
+        req.query_params.insert("route".into(), "KULSIN".into());
+        req.query_params.insert("date".into(), "2026-02-01".into());
+        assert!(get_oracle_verdict_handler(&req).is_err());
+
+        req.query_params.insert("route".into(), "KUL-SIN".into());
+        let resp = get_oracle_verdict_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+    }
 }
@@ -1,6 +1,7 @@
-//! Pool handlers (10 handlers)
+//! Pool handlers (11 handlers)
 
-use crate::{ApiError, ApiResult, Request, Response};
+use super::extract_field;
+use crate::{ApiError, ApiResult, FieldError, JsonObject, Request, Response};
 
 /// POST /pools - Create a new demand pool
 pub fn create_pool_handler(req: &Request) -> ApiResult<Response> {
@@ -36,7 +37,20 @@ pub fn get_pool_handler(req: &Request) -> ApiResult<Response> {
     ))
 }
 
-/// POST /pools/{id}/join - Join a pool
+/// Client-generated operation ID from the `Idempotency-Key` header or `operation_id` field
+fn operation_id(req: &Request) -> ApiResult<String> {
+    let id = req
+        .header("idempotency-key")
+        .cloned()
+        .or_else(|| extract_field(&req.body_string().unwrap_or_default(), "operation_id"))
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("operation_id")]))?;
+    vaya_pool::validate_operation_id(&id).map_err(|e| {
+        ApiError::ValidationError(vec![FieldError::invalid("operation_id", &e.to_string())])
+    })?;
+    Ok(id)
+}
+
+/// POST /pools/{id}/join - Join a pool (idempotent per operation ID)
 pub fn join_pool_handler(req: &Request) -> ApiResult<Response> {
     let _id = req
         .param("id")
@@ -45,8 +59,81 @@ pub fn join_pool_handler(req: &Request) -> ApiResult<Response> {
         .user_id
         .as_ref()
         .ok_or(ApiError::unauthorized("Authentication required"))?;
-    // TODO: Implement pool joining
-    Ok(Response::ok().with_body(br#"{"pool_id":"pool_123","joined":true,"position":5}"#.to_vec()))
+    let _operation_id = operation_id(req)?;
+    // TODO: Call PoolService::join; conflicts map to 409 via From<PoolError>
+    Ok(Response::ok().with_body(
+        br#"{"pool_id":"pool_123","joined":true,"position":5,"replayed":false}"#.to_vec(),
+    ))
+}
+
+/// POST /pools/{id}/waitlist - Join a full pool's waitlist (idempotent per operation ID)
+pub fn join_pool_waitlist_handler(req: &Request) -> ApiResult<Response> {
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing pool ID"))?;
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let spots = match req.body_string().and_then(|b| extract_field(&b, "spots")) {
+        Some(spots) => spots
+            .parse::<u32>()
+            .ok()
+            .filter(|s| *s > 0)
+            .ok_or_else(|| {
+                ApiError::ValidationError(vec![FieldError::invalid(
+                    "spots",
+                    "Spots must be a positive integer",
+                )])
+            })?,
+        None => 1,
+    };
+    let _operation_id = operation_id(req)?;
+    // TODO: Call PoolService::join_waitlist; conflicts map to 409 via From<PoolError>
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("pool_id", id)
+            .field("waitlisted", true)
+            .field("spots", spots)
+            .field("position", 1)
+            .field("replayed", false)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /pools/{id}/contribute - Pay a member's share (idempotent per operation ID)
+pub fn contribute_pool_handler(req: &Request) -> ApiResult<Response> {
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing pool ID"))?;
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let amount = extract_field(&body, "amount")
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("amount")]))?;
+    if !amount.parse::<i64>().is_ok_and(|a| a > 0) {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "amount",
+            "Amount must be a positive integer in minor units",
+        )]));
+    }
+    let _operation_id = operation_id(req)?;
+    // TODO: Call PoolService::contribute; conflicts map to 409 via From<PoolError>
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("pool_id", id)
+            .field("contributed", true)
+            .field("replayed", false)
+            .build(),
+    );
+    Ok(response)
 }
 
 /// DELETE /pools/{id}/leave - Leave a pool
@@ -58,7 +145,7 @@ pub fn leave_pool_handler(req: &Request) -> ApiResult<Response> {
         .user_id
         .as_ref()
         .ok_or(ApiError::unauthorized("Authentication required"))?;
-    // TODO: Implement pool leaving
+    // TODO: Call PoolService::leave, which promotes waitlisted users into freed spots
     Ok(Response::ok().with_body(br#"{"pool_id":"pool_123","left":true}"#.to_vec()))
 }
 
@@ -137,6 +224,55 @@ mod tests {
         assert_eq!(resp.status, 201);
     }
 
+    #[test]
+    fn test_join_and_contribute_require_operation_id() {
+        let mut req = Request::new("POST", "/pools/POOL-1/join");
+        req.path_params.insert("id".into(), "POOL-1".into());
+        req.user_id = Some("user_123".into());
+        assert!(matches!(
+            join_pool_handler(&req),
+            Err(ApiError::ValidationError(_))
+        ));
+        req.headers
+            .insert("idempotency-key".into(), "tap-01HZX".into());
+        assert_eq!(join_pool_handler(&req).unwrap().status, 200);
+
+        let mut req = Request::new("POST", "/pools/POOL-1/contribute");
+        req.path_params.insert("id".into(), "POOL-1".into());
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"amount":10000,"operation_id":"pay 1"}"#.to_vec();
+        assert!(contribute_pool_handler(&req).is_err());
+        req.body = br#"{"amount":10000,"operation_id":"pay-1"}"#.to_vec();
+        assert_eq!(contribute_pool_handler(&req).unwrap().status, 200);
+
+        req.path_params
+            .insert("id".into(), r#"POOL-1","replayed":true"#.into());
+        let body = contribute_pool_handler(&req)
+            .unwrap()
+            .body_string()
+            .unwrap();
+        assert!(body.contains(r#""replayed":false"#));
+        assert!(!body.contains(r#""replayed":true"#));
+    }
+
+    #[test]
+    fn test_join_pool_waitlist_handler() {
+        let mut req = Request::new("POST", "/pools/POOL-1/waitlist");
+        req.path_params.insert("id".into(), "POOL-1".into());
+        req.user_id = Some("user_123".into());
+        req.headers
+            .insert("idempotency-key".into(), "wait-01HZX".into());
+        req.body = br#"{"spots":0}"#.to_vec();
+        assert!(matches!(
+            join_pool_waitlist_handler(&req),
+            Err(ApiError::ValidationError(_))
+        ));
+        req.body = br#"{"spots":2}"#.to_vec();
+        let resp = join_pool_waitlist_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+        assert!(resp.body_string().unwrap().contains(r#""spots":2"#));
+    }
+
     #[test]
     fn test_list_pools_handler() {
         let req = Request::new("GET", "/pools");
@@ -1,6 +1,12 @@
-//! Search handlers (6 handlers)
+//! Search handlers (14 handlers)
 
-use crate::{ApiError, ApiResult, Request, Response};
+use vaya_common::{refdata, IataCode};
+use vaya_search::{CalendarRequest, SuggestionKind};
+
+use super::extract_field;
+use crate::{
+    escape_json, ApiError, ApiResult, FieldError, JsonObject, JsonValue, Request, Response,
+};
 
 /// GET /search - Search for flights
 pub fn search_flights_handler(req: &Request) -> ApiResult<Response> {
@@ -52,13 +58,351 @@ pub fn get_popular_routes_handler(_req: &Request) -> ApiResult<Response> {
     ))
 }
 
-/// GET /search/suggestions - Get search suggestions
+/// GET /search/suggestions - Cities, airports and airlines for the search box
+///
+/// Places come from the fuzzy airport autocomplete and are enriched with
+/// timezone and coordinates from the reference data; `?origin=` adds the
+/// great-circle distance from that airport.
 pub fn get_search_suggestions_handler(req: &Request) -> ApiResult<Response> {
-    let _query = req
+    let (query, limit, locale) = suggestion_query(req)?;
+    let origin = match req.query("origin").map(|o| o.trim()) {
+        Some(code) => Some(refdata::airport(code).ok_or_else(|| {
+            ApiError::ValidationError(vec![FieldError::invalid("origin", "Unknown airport")])
+        })?),
+        None => None,
+    };
+
+    let mut items: Vec<JsonValue> = vaya_search::airports::global()
+        .suggest(query, &locale, limit)
+        .iter()
+        .map(|s| {
+            let code = s.airports.first().copied().unwrap_or(s.code);
+            let place = refdata::airport(code);
+            let mut item = JsonObject::new()
+                .field("type", s.kind.as_str())
+                .field("code", s.code)
+                .field("name", &s.name)
+                .field("city", s.city)
+                .field("country", s.country)
+                .field_if_some("timezone", place.map(|p| p.timezone));
+            if let (SuggestionKind::Airport, Some(place)) = (s.kind, place) {
+                item = item
+                    .field("latitude", place.latitude)
+                    .field("longitude", place.longitude)
+                    .field_if_some(
+                        "distance_km",
+                        origin.map(|o| o.distance_km(place).round() as i64),
+                    );
+            }
+            item.build()
+        })
+        .collect();
+    items.extend(
+        refdata::find_airlines(query, limit.unwrap_or(3).min(5))
+            .iter()
+            .map(|a| {
+                JsonObject::new()
+                    .field("type", "airline")
+                    .field("code", a.code)
+                    .field("name", a.name)
+                    .field("country", a.country)
+                    .build()
+            }),
+    );
+    let mut response = Response::ok().with_header("Cache-Control", "public, max-age=300");
+    response.set_json_body(&JsonObject::new().field("suggestions", items).build());
+    Ok(response)
+}
+
+/// Query text, limit and locale shared by the autocomplete handlers
+fn suggestion_query(req: &Request) -> ApiResult<(&str, Option<usize>, String)> {
+    let query = req
         .query("q")
+        .map(|q| q.trim())
+        .filter(|q| !q.is_empty())
         .ok_or(ApiError::bad_request("Missing query"))?;
-    // TODO: Implement search suggestions
-    Ok(Response::ok().with_body(br#"{"suggestions":[]}"#.to_vec()))
+    if query.chars().count() > 64 {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "q",
+            "Query must be at most 64 characters",
+        )]));
+    }
+    let limit = match req.query("limit") {
+        Some(limit) => Some(limit.parse::<usize>().map_err(|_| {
+            ApiError::ValidationError(vec![FieldError::invalid("limit", "Must be a number")])
+        })?),
+        None => None,
+    };
+    let locale = req
+        .query("locale")
+        .or_else(|| req.header("accept-language"))
+        .map(|l| l.split(',').next().unwrap_or(l).trim().to_string())
+        .unwrap_or_else(|| "en".to_string());
+    Ok((query, limit, locale))
+}
+
+/// GET /airports/suggest - Airport autocomplete from the local dataset (never calls the GDS)
+pub fn suggest_airports_handler(req: &Request) -> ApiResult<Response> {
+    let (query, limit, locale) = suggestion_query(req)?;
+    let suggestions = vaya_search::airports::global().suggest(query, &locale, limit);
+    let items: Vec<String> = suggestions
+        .iter()
+        .map(|s| {
+            format!(
+                r#"{{"code":"{}","type":"{}","name":"{}","city":"{}","country":"{}","airports":[{}]}}"#,
+                s.code,
+                s.kind.as_str(),
+                escape_json(&s.name),
+                escape_json(s.city),
+                s.country,
+                s.airports
+                    .iter()
+                    .map(|a| format!("\"{}\"", a))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )
+        })
+        .collect();
+    Ok(Response::ok()
+        .with_header("Cache-Control", "public, max-age=300")
+        .with_body(format!(r#"{{"suggestions":[{}]}}"#, items.join(",")).into_bytes()))
+}
+
+/// GET /defaults - Pre-filled search form values for the Home screen
+///
+/// Market comes from `?market=`, then GeoIP on the client IP, then
+/// `Accept-Language`. `?last_origin=` and `?recent=` (comma-separated
+/// destinations) carry the user's search history.
+pub fn get_search_defaults_handler(req: &Request) -> ApiResult<Response> {
+    let mut errors = Vec::new();
+    let market = req.query("market").map(|m| m.trim().to_uppercase());
+    if let Some(ref m) = market {
+        if vaya_search::defaults::market(m).is_none() {
+            errors.push(FieldError::invalid("market", "Unsupported market"));
+        }
+    }
+    let last_origin = req.query("last_origin").map(|o| o.trim().to_uppercase());
+    if let Some(ref o) = last_origin {
+        if o.len() != 3 || !o.bytes().all(|b| b.is_ascii_alphabetic()) {
+            errors.push(FieldError::invalid("last_origin", "Must be an IATA code"));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    // TODO: Fill last_origin and recent destinations from the user's search history when authenticated
+    let query = vaya_search::DefaultsQuery {
+        ip: req.client_ip.as_deref().and_then(|ip| ip.parse().ok()),
+        accept_language: req.header("accept-language").cloned(),
+        market,
+        last_origin,
+        recent_destinations: req
+            .query("recent")
+            .map(|r| {
+                r.split(',')
+                    .take(10)
+                    .map(|d| d.trim().to_uppercase())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+
+    let defaults = vaya_search::defaults::global().defaults(&query);
+    let locale = defaults.market.locale;
+    let airport_json = |a: &vaya_search::airports::Airport| {
+        format!(
+            r#"{{"code":"{}","city":"{}","country":"{}"}}"#,
+            a.code,
+            escape_json(a.city_name(locale)),
+            a.country
+        )
+    };
+    let destinations: Vec<String> = defaults
+        .destinations
+        .iter()
+        .map(|a| airport_json(a))
+        .collect();
+    let body = format!(
+        r#"{{"market":"{}","currency":"{}","locale":"{}","origin":{},"origin_source":"{}","destinations":[{}],"departure_in_days":{},"trip_length_days":{}}}"#,
+        defaults.market.country,
+        defaults.market.currency.as_str(),
+        locale,
+        airport_json(defaults.origin),
+        defaults.origin_source.as_str(),
+        destinations.join(","),
+        defaults.departure_in_days,
+        defaults.trip_length_days
+    );
+    // Depends on the client IP and history, so only the browser may cache it
+    Ok(Response::ok()
+        .with_header("Cache-Control", "private, max-age=300")
+        .with_header("Vary", "Accept-Language")
+        .with_body(body.into_bytes()))
+}
+
+/// GET /search/calendar?origin=&destination=&month= - Cheapest fare per day for a month
+pub fn get_fare_calendar_handler(req: &Request) -> ApiResult<Response> {
+    let mut errors = Vec::new();
+    let mut airport = |field: &'static str| match req.query(field).map(|c| c.trim()) {
+        None => {
+            errors.push(FieldError::required(field));
+            None
+        }
+        Some(code) if code.len() == 3 && code.bytes().all(|b| b.is_ascii_alphabetic()) => {
+            Some(IataCode::new(code))
+        }
+        Some(_) => {
+            errors.push(FieldError::invalid(field, "Must be an IATA code"));
+            None
+        }
+    };
+    let origin = airport("origin");
+    let destination = airport("destination");
+    let month = match req.query("month") {
+        None => {
+            errors.push(FieldError::required("month"));
+            None
+        }
+        Some(month) => match vaya_search::calendar::parse_month(month) {
+            Ok(month) => Some(month),
+            Err(_) => {
+                errors.push(FieldError::invalid("month", "Month must be YYYY-MM"));
+                None
+            }
+        },
+    };
+    let (Some(origin), Some(destination), Some((year, month))) = (origin, destination, month)
+    else {
+        return Err(ApiError::ValidationError(errors));
+    };
+    let request = CalendarRequest::new(origin, destination, year, month);
+    if request.validate().is_err() {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "destination",
+            "Destination must differ from origin",
+        )]));
+    }
+
+    let calendar = vaya_search::engine::global().fare_calendar(&request)?;
+    let days: Vec<JsonValue> = calendar
+        .days
+        .iter()
+        .map(|day| {
+            let date = JsonObject::new().field("date", day.date.to_string());
+            match day.fare {
+                Some(fare) => date
+                    .field("price", fare.price.as_i64())
+                    .field("currency", fare.currency.as_str())
+                    .field("source", fare.source.as_str())
+                    .field("deal_score", day.deal_score)
+                    .field("is_cheapest", day.is_cheapest),
+                None => date.field("price", JsonValue::Null),
+            }
+            .build()
+        })
+        .collect();
+    let mut response = Response::ok().with_header("Cache-Control", "public, max-age=3600");
+    response.set_json_body(
+        &JsonObject::new()
+            .field("origin", calendar.origin.as_str())
+            .field("destination", calendar.destination.as_str())
+            .field(
+                "month",
+                format!("{}-{:02}", calendar.year, calendar.month as u8),
+            )
+            .field("cabin", calendar.cabin.code().to_string())
+            .field("days", days)
+            .field("from_cache", calendar.from_cache)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /searches - Save search criteria (and optionally a snapshot) under a shareable ID
+pub fn create_saved_search_handler(req: &Request) -> ApiResult<Response> {
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let mut errors = Vec::new();
+    for field in ["origin", "destination", "departure_date"] {
+        if extract_field(&body, field).is_none() {
+            errors.push(FieldError::required(field));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    // TODO: Call SavedSearchService::save (owner is the authenticated user, if any)
+    Ok(Response::created().with_body(
+        br#"{"id":"Xk3p9QaZ","share_url":"/api/v1/searches/Xk3p9QaZ","snapshot":true}"#.to_vec(),
+    ))
+}
+
+/// GET /searches/{id} - Open a saved search (snapshot with staleness label, or re-run)
+pub fn get_saved_search_handler(req: &Request) -> ApiResult<Response> {
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing search ID"))?;
+    let _refresh = req.query("refresh").is_some_and(|v| v == "true");
+    // TODO: Call SavedSearchService::open
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field("results", Vec::<JsonValue>::new())
+            .field("freshness", "fresh")
+            .field("captured_at", "2026-01-09T00:00:00Z")
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /users/me/searches - List the current user's saved searches
+pub fn list_saved_searches_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    // TODO: Call SavedSearchService::list_for
+    Ok(Response::ok().with_body(br#"{"searches":[],"total":0}"#.to_vec()))
+}
+
+/// PATCH /searches/{id} - Rename a saved search
+pub fn rename_saved_search_handler(req: &Request) -> ApiResult<Response> {
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing search ID"))?;
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    extract_field(&body, "name")
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required("name")]))?;
+    // TODO: Call SavedSearchService::rename
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("id", id)
+            .field("updated", true)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// DELETE /searches/{id} - Delete a saved search
+pub fn delete_saved_search_handler(req: &Request) -> ApiResult<Response> {
+    let _id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing search ID"))?;
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    // TODO: Call SavedSearchService::delete
+    Ok(Response::ok().with_body(br#"{"deleted":true}"#.to_vec()))
 }
 
 #[cfg(test)]
@@ -80,4 +424,116 @@ mod tests {
         let resp = get_popular_routes_handler(&req).unwrap();
         assert_eq!(resp.status, 200);
     }
+
+    #[test]
+    fn test_get_search_suggestions_handler() {
+        let mut req = Request::new("GET", "/search/suggestions");
+        assert!(get_search_suggestions_handler(&req).is_err());
+
+        req.query_params.insert("q".into(), "singapore".into());
+        req.query_params.insert("origin".into(), "KUL".into());
+        let resp = get_search_suggestions_handler(&req).unwrap();
+        let body = String::from_utf8_lossy(&resp.body);
+        assert!(
+            body.starts_with(r#"{"suggestions":[{"type":"airport","code":"SIN","name":"Changi""#)
+        );
+        assert!(body.contains(r#""timezone":"Asia/Singapore","latitude":1.3644"#));
+        assert!(body.contains(r#""distance_km":296"#));
+        assert!(body.contains(r#"{"type":"airline","code":"SQ","name":"Singapore Airlines""#));
+
+        req.query_params.insert("origin".into(), "XXX".into());
+        assert!(get_search_suggestions_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_suggest_airports_handler() {
+        let mut req = Request::new("GET", "/airports/suggest");
+        assert!(suggest_airports_handler(&req).is_err());
+
+        req.query_params.insert("q".into(), "tokyo".into());
+        req.query_params.insert("limit".into(), "3".into());
+        let resp = suggest_airports_handler(&req).unwrap();
+        let body = String::from_utf8_lossy(&resp.body);
+        assert!(body.starts_with(r#"{"suggestions":[{"code":"TYO","type":"city""#));
+        assert!(body.contains(r#""airports":["NRT","HND"]"#));
+
+        req.query_params.insert("limit".into(), "many".into());
+        assert!(suggest_airports_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_get_search_defaults_handler() {
+        let mut req = Request::new("GET", "/defaults");
+        req.headers
+            .insert("accept-language".into(), "th-TH,th;q=0.9".into());
+        let resp = get_search_defaults_handler(&req).unwrap();
+        let body = String::from_utf8_lossy(&resp.body);
+        assert!(body.starts_with(r#"{"market":"TH","currency":"THB","locale":"th-TH""#));
+        assert!(body.contains(r#""origin_source":"market""#));
+
+        req.query_params.insert("last_origin".into(), "pen".into());
+        let resp = get_search_defaults_handler(&req).unwrap();
+        let body = String::from_utf8_lossy(&resp.body);
+        assert!(body.contains(r#""origin":{"code":"PEN""#));
+        assert!(body.contains(r#""origin_source":"last_search""#));
+
+        req.query_params.insert("market".into(), "XX".into());
+        assert!(get_search_defaults_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_get_fare_calendar_handler() {
+        let mut req = Request::new("GET", "/search/calendar");
+        req.query_params.insert("origin".into(), "KUL".into());
+        req.query_params.insert("destination".into(), "KUL".into());
+        req.query_params.insert("month".into(), "2025-13".into());
+        assert!(get_fare_calendar_handler(&req).is_err());
+
+        req.query_params.insert("month".into(), "2025-07".into());
+        assert!(get_fare_calendar_handler(&req).is_err());
+
+        req.query_params.insert("destination".into(), "nrt".into());
+        let resp = get_fare_calendar_handler(&req).unwrap();
+        let body = String::from_utf8_lossy(&resp.body);
+        assert!(body.starts_with(r#"{"origin":"KUL","destination":"NRT","month":"2025-07""#));
+        assert!(body.contains(r#"{"date":"2025-07-31","price":null}"#));
+
+        // The shared engine serves the month again from its cache
+        let resp = get_fare_calendar_handler(&req).unwrap();
+        assert!(String::from_utf8_lossy(&resp.body).ends_with(r#""from_cache":true}"#));
+    }
+
+    #[test]
+    fn test_create_saved_search_handler() {
+        let mut req = Request::new("POST", "/searches");
+        req.body = br#"{"origin":"KUL"}"#.to_vec();
+        assert!(create_saved_search_handler(&req).is_err());
+
+        req.body =
+            br#"{"origin":"KUL","destination":"NRT","departure_date":"2026-06-15"}"#.to_vec();
+        let resp = create_saved_search_handler(&req).unwrap();
+        assert_eq!(resp.status, 201);
+    }
+
+    #[test]
+    fn test_get_saved_search_handler() {
+        let mut req = Request::new("GET", "/searches/Xk3p9QaZ");
+        req.path_params.insert("id".into(), "Xk3p9QaZ".into());
+        let resp = get_saved_search_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+        assert!(String::from_utf8_lossy(&resp.body).contains("\"freshness\""));
+    }
+
+    #[test]
+    fn test_rename_saved_search_escapes_id() {
+        let id = r#"Xk3p9QaZ","admin":true"#;
+        let mut req = Request::new("PATCH", "/searches/x");
+        req.path_params.insert("id".into(), id.into());
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"name":"Tokyo in June"}"#.to_vec();
+        let resp = rename_saved_search_handler(&req).unwrap();
+        let body = JsonValue::parse(&String::from_utf8(resp.body).unwrap()).unwrap();
+        assert_eq!(body.get("id").and_then(JsonValue::as_str), Some(id));
+        assert!(body.get("admin").is_none());
+    }
 }
@@ -6,7 +6,10 @@
 //! - GET /support/tickets/{id} - Get ticket details
 //! - POST /support/tickets/{id}/reply - Reply to ticket
 
-use crate::{ApiError, ApiResult, JsonSerialize, Request, Response};
+use crate::{
+    ApiError, ApiResult, FromJson, JsonError, JsonObject, JsonSerialize, JsonValue, Request,
+    Response,
+};
 
 /// Ticket priority levels
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -91,23 +94,19 @@ pub struct SupportTicket {
 }
 
 impl JsonSerialize for SupportTicket {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"id":"{}","user_id":"{}","subject":"{}","description":"{}","category":"{}","priority":"{}","status":"{}","booking_id":{},"created_at":"{}","updated_at":"{}"}}"#,
-            self.id,
-            self.user_id,
-            escape_json(&self.subject),
-            escape_json(&self.description),
-            self.category.as_str(),
-            self.priority.as_str(),
-            self.status.as_str(),
-            self.booking_id
-                .as_ref()
-                .map(|b| format!("\"{}\"", b))
-                .unwrap_or_else(|| "null".to_string()),
-            self.created_at,
-            self.updated_at
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("user_id", &self.user_id)
+            .field("subject", &self.subject)
+            .field("description", &self.description)
+            .field("category", self.category.as_str())
+            .field("priority", self.priority.as_str())
+            .field("status", self.status.as_str())
+            .field("booking_id", self.booking_id.as_ref())
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .build()
     }
 }
 
@@ -123,33 +122,34 @@ pub struct TicketReply {
 }
 
 impl JsonSerialize for TicketReply {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"id":"{}","ticket_id":"{}","user_id":{},"agent_id":{},"message":"{}","is_internal":{},"created_at":"{}"}}"#,
-            self.id,
-            self.ticket_id,
-            self.user_id
-                .as_ref()
-                .map(|u| format!("\"{}\"", u))
-                .unwrap_or_else(|| "null".to_string()),
-            self.agent_id
-                .as_ref()
-                .map(|a| format!("\"{}\"", a))
-                .unwrap_or_else(|| "null".to_string()),
-            escape_json(&self.message),
-            self.is_internal,
-            self.created_at
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("ticket_id", &self.ticket_id)
+            .field("user_id", self.user_id.as_ref())
+            .field("agent_id", self.agent_id.as_ref())
+            .field("message", &self.message)
+            .field("is_internal", self.is_internal)
+            .field("created_at", &self.created_at)
+            .build()
     }
 }
 
-/// Escape special JSON characters
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
+/// Body of a create ticket request
+struct CreateTicketRequest {
+    subject: String,
+    description: String,
+    booking_id: Option<String>,
+}
+
+impl FromJson for CreateTicketRequest {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        Ok(Self {
+            subject: value.field("subject")?,
+            description: value.field("description")?,
+            booking_id: value.field("booking_id")?,
+        })
+    }
 }
 
 /// POST /support/tickets - Create support ticket
@@ -159,19 +159,7 @@ pub fn create_ticket_handler(req: &Request) -> ApiResult<Response> {
         .as_ref()
         .ok_or(ApiError::unauthorized("Authentication required"))?;
 
-    if req.body.is_empty() {
-        return Err(ApiError::bad_request("Missing request body"));
-    }
-
-    // Parse request body (simplified - in production would use proper JSON parsing)
-    let body = String::from_utf8_lossy(&req.body);
-
-    // Validate required fields
-    if !body.contains("\"subject\"") || !body.contains("\"description\"") {
-        return Err(ApiError::bad_request(
-            "Missing required fields: subject, description",
-        ));
-    }
+    let body: CreateTicketRequest = req.json_body()?;
 
     // Generate ticket ID
     let ticket_id = format!("TKT-{}", generate_id());
@@ -180,12 +168,12 @@ pub fn create_ticket_handler(req: &Request) -> ApiResult<Response> {
     let ticket = SupportTicket {
         id: ticket_id.clone(),
         user_id: user_id.clone(),
-        subject: extract_field(&body, "subject").unwrap_or_default(),
-        description: extract_field(&body, "description").unwrap_or_default(),
+        subject: body.subject,
+        description: body.description,
         category: TicketCategory::General,
         priority: TicketPriority::Medium,
         status: TicketStatus::Open,
-        booking_id: extract_field(&body, "booking_id"),
+        booking_id: body.booking_id,
         created_at: now.clone(),
         updated_at: now,
     };
@@ -250,9 +238,15 @@ pub fn get_ticket_handler(req: &Request) -> ApiResult<Response> {
     };
 
     // Include replies
-    let response_body = format!(r#"{{"ticket":{},"replies":[]}}"#, ticket.to_json());
-
-    Ok(Response::ok().with_body(response_body.into_bytes()))
+    let replies: Vec<TicketReply> = Vec::new();
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .nested("ticket", &ticket)
+            .array("replies", &replies)
+            .build(),
+    );
+    Ok(response)
 }
 
 /// POST /support/tickets/{id}/reply - Reply to ticket
@@ -265,15 +259,9 @@ pub fn reply_to_ticket_handler(req: &Request) -> ApiResult<Response> {
         .as_ref()
         .ok_or(ApiError::unauthorized("Authentication required"))?;
 
-    if req.body.is_empty() {
-        return Err(ApiError::bad_request("Missing request body"));
-    }
-
-    let body = String::from_utf8_lossy(&req.body);
-
     // Validate message
-    let message = extract_field(&body, "message")
-        .ok_or(ApiError::bad_request("Missing required field: message"))?;
+    let body: JsonValue = req.json_body()?;
+    let message: String = body.field("message")?;
 
     if message.is_empty() {
         return Err(ApiError::bad_request("Message cannot be empty"));
@@ -359,32 +347,10 @@ fn current_timestamp() -> String {
     )
 }
 
-/// Extract a field value from JSON string (simplified parser)
-fn extract_field(json: &str, field: &str) -> Option<String> {
-    let pattern = format!("\"{}\":", field);
-    let start = json.find(&pattern)?;
-    let value_start = start + pattern.len();
-    let rest = &json[value_start..];
-
-    // Skip whitespace
-    let rest = rest.trim_start();
-
-    if let Some(rest) = rest.strip_prefix('"') {
-        // String value
-        let end = rest.find('"')?;
-        Some(rest[..end].to_string())
-    } else if rest.starts_with("null") {
-        None
-    } else {
-        // Number or boolean
-        let end = rest.find([',', '}', ']']).unwrap_or(rest.len());
-        Some(rest[..end].trim().to_string())
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handlers::extract_field;
 
     #[test]
     fn test_create_ticket_handler() {
@@ -395,6 +361,22 @@ mod tests {
 
         let resp = create_ticket_handler(&req).unwrap();
         assert_eq!(resp.status, 201);
+
+        // Quotes in user input stay inside the string
+        req.body = br#"{"subject":"Seat \"12A\" broken","description":"Line 1\nLine 2"}"#.to_vec();
+        let resp = create_ticket_handler(&req).unwrap();
+        let body = JsonValue::parse(&resp.body_string().unwrap()).unwrap();
+        assert_eq!(
+            body.get("subject").unwrap().as_str(),
+            Some(r#"Seat "12A" broken"#)
+        );
+        assert!(body.get("booking_id").unwrap().is_null());
+
+        req.body = br#"{"subject":"Help needed"}"#.to_vec();
+        assert!(matches!(
+            create_ticket_handler(&req),
+            Err(ApiError::ValidationError(_))
+        ));
     }
 
     #[test]
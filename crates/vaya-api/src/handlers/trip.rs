@@ -8,7 +8,7 @@
 //! - DELETE /trips/{id} - Delete trip
 //! - POST /trips/{id}/bookings - Add booking to trip
 
-use crate::{ApiError, ApiResult, Request, Response};
+use crate::{ApiError, ApiResult, JsonObject, Request, Response};
 
 /// GET /trips - List user's trips
 pub fn list_trips_handler(req: &Request) -> ApiResult<Response> {
@@ -98,11 +98,15 @@ pub fn add_booking_to_trip_handler(req: &Request) -> ApiResult<Response> {
         return Err(ApiError::bad_request("Missing required field: booking_id"));
     }
     // TODO: Implement adding booking to trip in database
-    let response = format!(
-        r#"{{"trip_id":"{}","booking_added":true,"updated_at":"2026-01-09T00:00:00Z"}}"#,
-        trip_id
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("trip_id", trip_id)
+            .field("booking_added", true)
+            .field("updated_at", "2026-01-09T00:00:00Z")
+            .build(),
     );
-    Ok(Response::ok().with_body(response.into_bytes()))
+    Ok(response)
 }
 
 #[cfg(test)]
@@ -1,6 +1,10 @@
-//! User handlers (11 handlers)
+//! User handlers (24 handlers)
 
-use crate::{ApiError, ApiResult, Request, Response};
+use vaya_auth::totp::generate_backup_codes;
+use vaya_auth::{Totp, TwoFactor};
+
+use super::extract_field;
+use crate::{ApiError, ApiResult, FieldError, JsonObject, JsonValue, Request, Response};
 
 /// GET /users/me - Get current user profile
 pub fn get_current_user_handler(req: &Request) -> ApiResult<Response> {
@@ -149,6 +153,455 @@ pub fn delete_saved_route_handler(req: &Request) -> ApiResult<Response> {
     Ok(Response::ok().with_body(br#"{"deleted":true}"#.to_vec()))
 }
 
+/// Extract a required, non-empty string field from a request body
+fn require_field(body: &str, field: &str) -> ApiResult<String> {
+    extract_field(body, field)
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| ApiError::ValidationError(vec![FieldError::required(field)]))
+}
+
+/// POST /users/me/email - Request a login email change (requires current password)
+pub fn request_email_change_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_field(&body, "current_password")?;
+    let new_email = require_field(&body, "new_email")?;
+    if !new_email.contains('@') {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "new_email",
+            "Invalid email address",
+        )]));
+    }
+    // TODO: Call VerificationService::request_email_change
+    Ok(Response::accepted()
+        .with_body(br#"{"status":"pending_confirmation","confirm_expires_in":86400}"#.to_vec()))
+}
+
+/// POST /users/email/confirm - Confirm an email change from the link sent to the new address
+pub fn confirm_email_change_handler(req: &Request) -> ApiResult<Response> {
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_field(&body, "token")?;
+    // TODO: Call VerificationService::confirm_email_change
+    Ok(Response::ok().with_body(br#"{"status":"confirmed","revert_window_days":7}"#.to_vec()))
+}
+
+/// POST /users/email/revert - Revert an email change from the link sent to the old address
+pub fn revert_email_change_handler(req: &Request) -> ApiResult<Response> {
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_field(&body, "token")?;
+    // TODO: Call VerificationService::revert_email_change
+    Ok(Response::ok().with_body(br#"{"status":"reverted","sessions_revoked":true}"#.to_vec()))
+}
+
+/// POST /users/me/phone - Send a verification code to a phone number (requires current password)
+pub fn start_phone_verification_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_field(&body, "current_password")?;
+    let phone = require_field(&body, "phone")?;
+    if !phone.starts_with('+') {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "phone",
+            "Phone number must be in international format",
+        )]));
+    }
+    // TODO: Call VerificationService::start_phone_verification
+    Ok(Response::accepted().with_body(br#"{"status":"code_sent","expires_in":600}"#.to_vec()))
+}
+
+/// POST /users/me/phone/verify - Verify a phone number with the code sent by SMS
+pub fn verify_phone_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let code = require_field(&body, "code")?;
+    if code.len() != 6 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "code",
+            "Code must be 6 digits",
+        )]));
+    }
+    // TODO: Call VerificationService::verify_phone
+    Ok(Response::ok().with_body(br#"{"phone_verified":true}"#.to_vec()))
+}
+
+/// Issuer shown in authenticator apps
+const TOTP_ISSUER: &str = "VAYA";
+
+/// Validate a 2FA code: 6 digits, or a backup code (`xxxxx-xxxxx`) if allowed
+fn validate_mfa_code(code: &str, allow_backup: bool) -> ApiResult<()> {
+    let is_totp = code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit());
+    let is_backup = allow_backup
+        && code.len() == 11
+        && code.bytes().enumerate().all(|(i, b)| {
+            if i == 5 {
+                b == b'-'
+            } else {
+                b.is_ascii_alphanumeric()
+            }
+        });
+    if is_totp || is_backup {
+        Ok(())
+    } else {
+        Err(ApiError::ValidationError(vec![FieldError::invalid(
+            "code",
+            if allow_backup {
+                "Code must be 6 digits or a backup code"
+            } else {
+                "Code must be 6 digits"
+            },
+        )]))
+    }
+}
+
+/// POST /users/2fa/enable - Start TOTP enrollment (requires current password)
+pub fn enable_two_factor_handler(req: &Request) -> ApiResult<Response> {
+    let user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_field(&body, "current_password")?;
+
+    let enrollment = TwoFactor::begin().map_err(|e| ApiError::internal(e.to_string()))?;
+    // TODO: Store the pending enrollment; label the URI with the user's email
+    let uri = Totp::new(TOTP_ISSUER).provisioning_uri(&enrollment.secret, user_id);
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("status", "pending_verification")
+            .field("secret", enrollment.secret.to_base32())
+            .field("otpauth_uri", uri)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /users/2fa/verify - Confirm TOTP enrollment with a first code
+pub fn verify_two_factor_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    let code = require_field(&body, "code")?;
+    validate_mfa_code(code.trim(), false)?;
+
+    // TODO: Call TwoFactor::confirm on the pending enrollment and return its backup codes
+    let (backup_codes, _hashes) = generate_backup_codes(TwoFactor::BACKUP_CODE_COUNT)
+        .map_err(|e| ApiError::internal(e.to_string()))?;
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("enabled", true)
+            .field("backup_codes", backup_codes)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /users/2fa/disable - Turn off 2FA (requires current password and a code)
+pub fn disable_two_factor_handler(req: &Request) -> ApiResult<Response> {
+    let _user_id = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let body = req
+        .body_string()
+        .ok_or(ApiError::bad_request("Missing request body"))?;
+    require_field(&body, "current_password")?;
+    let code = require_field(&body, "code")?;
+    validate_mfa_code(code.trim(), true)?;
+    // TODO: Call TwoFactor::verify, then delete the enrollment
+    Ok(Response::ok().with_body(br#"{"enabled":false}"#.to_vec()))
+}
+
+/// User from the path (`me` for the caller); only that user or an admin may access it
+fn path_user(req: &Request, denied: &str) -> ApiResult<String> {
+    let caller = req
+        .user_id
+        .as_ref()
+        .ok_or(ApiError::unauthorized("Authentication required"))?;
+    let id = req
+        .param("id")
+        .ok_or(ApiError::bad_request("Missing user ID"))?;
+    let owner = if id == "me" { caller } else { id };
+    if owner != caller && !req.has_role("admin") {
+        return Err(ApiError::forbidden(denied));
+    }
+    Ok(owner.clone())
+}
+
+/// Wallet owner from the path
+fn wallet_owner(req: &Request) -> ApiResult<String> {
+    path_user(req, "Not your wallet")
+}
+
+/// GET /users/{id}/wallet - Points balance and when unspent points expire
+pub fn get_wallet_handler(req: &Request) -> ApiResult<Response> {
+    let user_id = wallet_owner(req)?;
+    // TODO: Call WalletService::balance
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("user_id", user_id)
+            .field("points", 0)
+            .field("value", "MYR 0.00")
+            .field("lots", Vec::<JsonValue>::new())
+            .build(),
+    );
+    Ok(response)
+}
+
+/// GET /users/{id}/wallet/transactions - Points earned, spent and expired, newest first
+pub fn list_wallet_transactions_handler(req: &Request) -> ApiResult<Response> {
+    let user_id = wallet_owner(req)?;
+    // TODO: Call WalletService::history
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("user_id", user_id)
+            .field("transactions", Vec::<JsonValue>::new())
+            .field("total", 0)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// POST /users/{id}/wallet/redeem - Spend points toward a booking or pool contribution
+///
+/// Idempotent per `Idempotency-Key` header (or `idempotency_key` field);
+/// a repeated key returns the original credit.
+pub fn redeem_wallet_handler(req: &Request) -> ApiResult<Response> {
+    let user_id = wallet_owner(req)?;
+    let body: JsonValue = req.json_body()?;
+    let key = match req.header("idempotency-key") {
+        Some(key) => key.clone(),
+        None => body.field("idempotency_key")?,
+    };
+    let points: i64 = body.field("points")?;
+    let target: String = body.field("target")?;
+    let target_id: String = body.field("target_id")?;
+
+    let mut errors = Vec::new();
+    if key.is_empty() || key.len() > 64 {
+        errors.push(FieldError::invalid(
+            "idempotency_key",
+            "Idempotency key must be 1-64 characters",
+        ));
+    }
+    if points <= 0 {
+        errors.push(FieldError::invalid("points", "Points must be positive"));
+    }
+    if !matches!(target.as_str(), "booking" | "pool") {
+        errors.push(FieldError::invalid(
+            "target",
+            "Target must be one of: booking, pool",
+        ));
+    }
+    if target_id.trim().is_empty() {
+        errors.push(FieldError::required("target_id"));
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::ValidationError(errors));
+    }
+    // TODO: Call WalletService::redeem (apply_to_booking for bookings)
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("user_id", user_id)
+            .field("points", points)
+            .field("target", format!("{}:{}", target, target_id))
+            .field("idempotency_key", key)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// Notification categories, as named by `NotificationCategory`
+const NOTIFICATION_CATEGORIES: [&str; 6] = [
+    "bookings",
+    "flight_updates",
+    "price_alerts",
+    "pools",
+    "account",
+    "marketing",
+];
+
+/// Notification channels, as named by `NotificationChannel`
+const NOTIFICATION_CHANNELS: [&str; 4] = ["email", "sms", "whatsapp", "telegram"];
+
+/// Digest frequencies, as named by `DigestFrequency`
+const DIGEST_FREQUENCIES: [&str; 3] = ["immediate", "daily", "weekly"];
+
+/// Parse a `HH:MM` time of day into minutes after midnight
+fn parse_clock(value: &str) -> Option<u16> {
+    let (hours, minutes) = value.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Check a notification preferences body, collecting every field error
+fn validate_notification_preferences(body: &JsonValue) -> ApiResult<()> {
+    let mut errors = Vec::new();
+
+    match body.get("channels") {
+        Some(JsonValue::Object(categories)) => {
+            for (category, channels) in categories {
+                let field = format!("channels.{}", category);
+                if !NOTIFICATION_CATEGORIES.contains(&category.as_str()) {
+                    errors.push(FieldError::invalid(&field, "Unknown notification category"));
+                    continue;
+                }
+                let valid = channels.as_array().is_some_and(|channels| {
+                    channels.iter().all(|c| {
+                        c.as_str()
+                            .is_some_and(|c| NOTIFICATION_CHANNELS.contains(&c))
+                    })
+                });
+                if !valid {
+                    errors.push(FieldError::invalid(
+                        &field,
+                        "Channels must be a list of: email, sms, whatsapp, telegram",
+                    ));
+                }
+            }
+        }
+        Some(_) => errors.push(FieldError::invalid(
+            "channels",
+            "Channels must be an object keyed by category",
+        )),
+        None => errors.push(FieldError::required("channels")),
+    }
+
+    match body.get("quiet_hours") {
+        None | Some(JsonValue::Null) => {}
+        Some(quiet) => {
+            let start = quiet
+                .get("start")
+                .and_then(JsonValue::as_str)
+                .and_then(parse_clock);
+            let end = quiet
+                .get("end")
+                .and_then(JsonValue::as_str)
+                .and_then(parse_clock);
+            match (start, end) {
+                (Some(start), Some(end)) if start != end => {}
+                _ => errors.push(FieldError::invalid(
+                    "quiet_hours",
+                    "Quiet hours need different start and end times as HH:MM",
+                )),
+            }
+        }
+    }
+
+    match body.field::<Option<i64>>("utc_offset_minutes") {
+        Ok(None) => {}
+        Ok(Some(offset)) if (-720..=840).contains(&offset) => {}
+        _ => errors.push(FieldError::invalid(
+            "utc_offset_minutes",
+            "UTC offset must be between -720 and 840 minutes",
+        )),
+    }
+
+    match body.field::<Option<String>>("digest") {
+        Ok(None) => {}
+        Ok(Some(digest)) if DIGEST_FREQUENCIES.contains(&digest.as_str()) => {}
+        _ => errors.push(FieldError::invalid(
+            "digest",
+            "Digest must be one of: immediate, daily, weekly",
+        )),
+    }
+
+    match body.field::<Option<u32>>("version") {
+        Ok(_) => {}
+        Err(_) => errors.push(FieldError::invalid(
+            "version",
+            "Version must be a non-negative integer",
+        )),
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::ValidationError(errors))
+    }
+}
+
+/// GET /users/{id}/notification-preferences - Channels per category, quiet hours and digest
+pub fn get_notification_preferences_handler(req: &Request) -> ApiResult<Response> {
+    let user_id = path_user(req, "Not your notification preferences")?;
+    // TODO: Call PreferenceService::get; these are the defaults for users who never saved any
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("user_id", user_id)
+            .field(
+                "channels",
+                JsonObject::new()
+                    .field("bookings", vec!["email", "sms"])
+                    .field("flight_updates", vec!["email", "sms"])
+                    .field("price_alerts", vec!["email"])
+                    .field("pools", vec!["email"])
+                    .field("account", vec!["email"])
+                    .field("marketing", Vec::<JsonValue>::new()),
+            )
+            .field("quiet_hours", JsonValue::Null)
+            .field("utc_offset_minutes", 0)
+            .field("digest", "immediate")
+            .field("version", 0)
+            .build(),
+    );
+    Ok(response)
+}
+
+/// PUT /users/{id}/notification-preferences - Replace notification preferences
+///
+/// `version` is the version read with GET; a stale version is rejected
+/// with 409 so concurrent edits are not lost.
+pub fn update_notification_preferences_handler(req: &Request) -> ApiResult<Response> {
+    let user_id = path_user(req, "Not your notification preferences")?;
+    let body: JsonValue = req.json_body()?;
+    validate_notification_preferences(&body)?;
+    let version: Option<u32> = body.field("version")?;
+    // TODO: Call PreferenceService::update
+    let mut response = Response::ok();
+    response.set_json_body(
+        &JsonObject::new()
+            .field("user_id", user_id)
+            .field("updated", true)
+            .field("version", version.unwrap_or(0) + 1)
+            .build(),
+    );
+    Ok(response)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,4 +647,117 @@ mod tests {
         let resp = delete_saved_route_handler(&req).unwrap();
         assert_eq!(resp.status, 200);
     }
+
+    #[test]
+    fn test_request_email_change_handler() {
+        let mut req = Request::new("POST", "/users/me/email");
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"new_email":"new@example.com"}"#.to_vec();
+        assert!(request_email_change_handler(&req).is_err());
+
+        req.body = br#"{"current_password":"secret","new_email":"new@example.com"}"#.to_vec();
+        let resp = request_email_change_handler(&req).unwrap();
+        assert_eq!(resp.status, 202);
+    }
+
+    #[test]
+    fn test_verify_phone_handler() {
+        let mut req = Request::new("POST", "/users/me/phone/verify");
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"code":"12ab"}"#.to_vec();
+        assert!(verify_phone_handler(&req).is_err());
+
+        req.body = br#"{"code":"123456"}"#.to_vec();
+        let resp = verify_phone_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+    }
+
+    #[test]
+    fn test_two_factor_handlers() {
+        let mut req = Request::new("POST", "/users/2fa/enable");
+        req.user_id = Some("user_123".into());
+        req.body = br#"{}"#.to_vec();
+        assert!(enable_two_factor_handler(&req).is_err());
+
+        req.body = br#"{"current_password":"secret"}"#.to_vec();
+        let resp = enable_two_factor_handler(&req).unwrap();
+        assert_eq!(resp.status, 200);
+        let body = String::from_utf8(resp.body).unwrap();
+        assert!(body.contains(r#""otpauth_uri":"otpauth://totp/VAYA:user_123?secret="#));
+
+        let mut req = Request::new("POST", "/users/2fa/verify");
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"code":"abcde-fghjk"}"#.to_vec();
+        assert!(verify_two_factor_handler(&req).is_err());
+        req.body = br#"{"code":"123456"}"#.to_vec();
+        let body = String::from_utf8(verify_two_factor_handler(&req).unwrap().body).unwrap();
+        assert!(body.contains(r#""backup_codes":[""#));
+
+        let mut req = Request::new("POST", "/users/2fa/disable");
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"current_password":"secret","code":"abcde-fghjk"}"#.to_vec();
+        assert_eq!(disable_two_factor_handler(&req).unwrap().status, 200);
+        req.body = br#"{"current_password":"secret","code":"12345"}"#.to_vec();
+        assert!(disable_two_factor_handler(&req).is_err());
+    }
+
+    #[test]
+    fn test_wallet_handlers() {
+        let mut req = Request::new("GET", "/users/user_123/wallet");
+        req.user_id = Some("user_123".into());
+        req.path_params.insert("id".into(), "user_123".into());
+        assert_eq!(get_wallet_handler(&req).unwrap().status, 200);
+        assert_eq!(list_wallet_transactions_handler(&req).unwrap().status, 200);
+
+        // Someone else's wallet needs the admin role
+        req.path_params.insert("id".into(), "user_456".into());
+        assert!(get_wallet_handler(&req).is_err());
+        req.user_roles = vec!["admin".into()];
+        assert_eq!(get_wallet_handler(&req).unwrap().status, 200);
+
+        let mut req = Request::new("POST", "/users/me/wallet/redeem");
+        req.user_id = Some("user_123".into());
+        req.path_params.insert("id".into(), "me".into());
+        req.body = br#"{"points":500,"target":"pool","target_id":"pool_1"}"#.to_vec();
+        assert!(redeem_wallet_handler(&req).is_err());
+        req.headers
+            .insert("idempotency-key".into(), "redeem-01HZX".into());
+        let body = String::from_utf8(redeem_wallet_handler(&req).unwrap().body).unwrap();
+        assert!(body.contains(r#""user_id":"user_123""#));
+        assert!(body.contains(r#""target":"pool:pool_1""#));
+        req.body = br#"{"points":0,"target":"voucher","target_id":"x"}"#.to_vec();
+        let err = redeem_wallet_handler(&req).unwrap_err();
+        assert!(matches!(err, ApiError::ValidationError(ref e) if e.len() == 2));
+    }
+
+    #[test]
+    fn test_notification_preferences_handlers() {
+        let mut req = Request::new("GET", "/users/me/notification-preferences");
+        req.user_id = Some("user_123".into());
+        req.path_params.insert("id".into(), "me".into());
+        let body =
+            String::from_utf8(get_notification_preferences_handler(&req).unwrap().body).unwrap();
+        assert!(body.contains(r#""bookings":["email","sms"]"#));
+
+        let mut other = req.clone();
+        other.path_params.insert("id".into(), "user_456".into());
+        assert!(get_notification_preferences_handler(&other).is_err());
+
+        let mut req = Request::new("PUT", "/users/me/notification-preferences");
+        req.user_id = Some("user_123".into());
+        req.path_params.insert("id".into(), "me".into());
+        req.body = br#"{"channels":{"price_alerts":["whatsapp"],"marketing":[]},
+            "quiet_hours":{"start":"22:00","end":"07:00"},"utc_offset_minutes":480,
+            "digest":"daily","version":3}"#
+            .to_vec();
+        let body =
+            String::from_utf8(update_notification_preferences_handler(&req).unwrap().body).unwrap();
+        assert!(body.contains(r#""version":4"#));
+
+        req.body = br#"{"channels":{"promos":["email"],"pools":["pigeon"]},
+            "quiet_hours":{"start":"25:00","end":"07:00"},"digest":"hourly"}"#
+            .to_vec();
+        let err = update_notification_preferences_handler(&req).unwrap_err();
+        assert!(matches!(err, ApiError::ValidationError(ref e) if e.len() == 4));
+    }
 }
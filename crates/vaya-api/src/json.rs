@@ -0,0 +1,835 @@
+//! JSON values, serialization and parsing
+//!
+//! Response bodies are built as [`JsonValue`]s, usually with
+//! [`JsonObject`], and printed with every string escaped, so user input can
+//! never break the document. Request bodies are parsed into a [`JsonValue`]
+//! and read into typed fields with [`FromJson`]; errors name the offending
+//! field (`passengers.0.name`) so they map onto validation errors.
+
+use std::fmt::{self, Write};
+
+/// Maximum nesting of arrays and objects accepted when parsing
+pub const MAX_DEPTH: usize = 64;
+
+/// A JSON value
+///
+/// Objects keep their fields in insertion order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    /// `null`
+    Null,
+    /// `true` or `false`
+    Bool(bool),
+    /// Number without fraction or exponent that fits an `i64`
+    Int(i64),
+    /// Any other number
+    Float(f64),
+    /// String
+    String(String),
+    /// Array
+    Array(Vec<JsonValue>),
+    /// Object
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parse a JSON document
+    pub fn parse(input: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser {
+            bytes: input.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Get an object field
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => {
+                fields.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            _ => None,
+        }
+    }
+
+    /// Read an object field as `T`
+    ///
+    /// A missing or `null` field is an error unless `T` is an `Option`.
+    pub fn field<T: FromJson>(&self, key: &str) -> Result<T, JsonError> {
+        match self.get(key) {
+            None | Some(JsonValue::Null) => {
+                T::from_missing().ok_or_else(|| JsonError::Missing(key.to_string()))
+            }
+            Some(value) => T::from_json(value).map_err(|e| e.within(key)),
+        }
+    }
+
+    /// Get the value as a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an integer
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Int(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a float (integers are converted)
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Int(n) => Some(*n as f64),
+            JsonValue::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a boolean
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an array
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Check if the value is `null`
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    /// Name of the value's type, for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::Null => "null",
+            JsonValue::Bool(_) => "boolean",
+            JsonValue::Int(_) | JsonValue::Float(_) => "number",
+            JsonValue::String(_) => "string",
+            JsonValue::Array(_) => "array",
+            JsonValue::Object(_) => "object",
+        }
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(b) => write!(f, "{}", b),
+            JsonValue::Int(n) => write!(f, "{}", n),
+            // JSON has no NaN or infinity
+            JsonValue::Float(n) if !n.is_finite() => f.write_str("null"),
+            // Debug keeps a fraction or exponent, so floats read back as floats
+            JsonValue::Float(n) => write!(f, "{:?}", n),
+            JsonValue::String(s) => write_escaped(f, s),
+            JsonValue::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            JsonValue::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// Escape a string for use inside JSON quotes
+pub fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    write_escaped(&mut out, s).expect("writing to a String cannot fail");
+    // Drop the surrounding quotes
+    out[1..out.len() - 1].to_string()
+}
+
+fn write_escaped(out: &mut impl Write, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+macro_rules! from_int {
+    ($($t:ty),*) => {
+        $(impl From<$t> for JsonValue {
+            fn from(n: $t) -> Self {
+                JsonValue::Int(n as i64)
+            }
+        })*
+    };
+}
+
+from_int!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<u64> for JsonValue {
+    fn from(n: u64) -> Self {
+        i64::try_from(n).map_or(JsonValue::Float(n as f64), JsonValue::Int)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(n: usize) -> Self {
+        JsonValue::from(n as u64)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(n: f64) -> Self {
+        JsonValue::Float(n)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<&String> for JsonValue {
+    fn from(s: &String) -> Self {
+        JsonValue::String(s.clone())
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(items: Vec<T>) -> Self {
+        JsonValue::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<JsonObject> for JsonValue {
+    fn from(object: JsonObject) -> Self {
+        object.build()
+    }
+}
+
+/// Builder for JSON objects
+///
+/// ```ignore
+/// let body = JsonObject::new()
+///     .field("id", &ticket.id)
+///     .field("booking_id", ticket.booking_id.as_deref())
+///     .array("replies", &ticket.replies)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct JsonObject {
+    fields: Vec<(String, JsonValue)>,
+}
+
+impl JsonObject {
+    /// Create an empty object
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field; `None` values are written as `null`
+    pub fn field(mut self, key: &str, value: impl Into<JsonValue>) -> Self {
+        self.fields.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Add a field only if the value is present
+    pub fn field_if_some<T: Into<JsonValue>>(self, key: &str, value: Option<T>) -> Self {
+        match value {
+            Some(value) => self.field(key, value),
+            None => self,
+        }
+    }
+
+    /// Add a nested serializable value
+    pub fn nested<T: JsonSerialize + ?Sized>(self, key: &str, value: &T) -> Self {
+        self.field(key, value.to_json_value())
+    }
+
+    /// Add an array of serializable values
+    pub fn array<T: JsonSerialize>(self, key: &str, items: &[T]) -> Self {
+        let items = items.iter().map(JsonSerialize::to_json_value).collect();
+        self.field(key, JsonValue::Array(items))
+    }
+
+    /// Finish the object
+    pub fn build(self) -> JsonValue {
+        JsonValue::Object(self.fields)
+    }
+}
+
+/// Trait for types that serialize to JSON
+pub trait JsonSerialize {
+    /// Build the JSON value
+    fn to_json_value(&self) -> JsonValue;
+
+    /// Serialize to a JSON string
+    fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+}
+
+impl JsonSerialize for JsonValue {
+    fn to_json_value(&self) -> JsonValue {
+        self.clone()
+    }
+}
+
+/// Trait for types read from parsed JSON
+///
+/// Implement it for request types by reading each field:
+///
+/// ```ignore
+/// impl FromJson for CreateTicket {
+///     fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+///         Ok(Self {
+///             subject: value.field("subject")?,
+///             booking_id: value.field("booking_id")?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromJson: Sized {
+    /// Read from a JSON value
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError>;
+
+    /// Value to use when the field is missing or `null`; `None` makes it required
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+impl FromJson for JsonValue {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        Ok(value.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| JsonError::expected("string"))
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        value
+            .as_bool()
+            .ok_or_else(|| JsonError::expected("boolean"))
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        value.as_f64().ok_or_else(|| JsonError::expected("number"))
+    }
+}
+
+macro_rules! from_json_int {
+    ($($t:ty),*) => {
+        $(impl FromJson for $t {
+            fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+                value
+                    .as_i64()
+                    .and_then(|n| <$t>::try_from(n).ok())
+                    .ok_or_else(|| JsonError::expected(concat!("integer (", stringify!($t), ")")))
+            }
+        })*
+    };
+}
+
+from_json_int!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        match value {
+            JsonValue::Null => Ok(None),
+            value => T::from_json(value).map(Some),
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+        let items = value
+            .as_array()
+            .ok_or_else(|| JsonError::expected("array"))?;
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| T::from_json(item).map_err(|e| e.within(&i.to_string())))
+            .collect()
+    }
+}
+
+/// JSON parse or conversion error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonError {
+    /// Malformed document
+    Syntax {
+        /// Byte offset of the error
+        offset: usize,
+        /// What was wrong
+        message: &'static str,
+    },
+    /// Required field missing or `null`
+    Missing(String),
+    /// Field has the wrong type
+    Type {
+        /// Path to the field (empty for the document itself)
+        field: String,
+        /// Expected type
+        expected: &'static str,
+    },
+}
+
+impl JsonError {
+    /// Wrong type at the current value
+    pub fn expected(expected: &'static str) -> Self {
+        JsonError::Type {
+            field: String::new(),
+            expected,
+        }
+    }
+
+    /// Prefix the field path with `key`
+    fn within(self, key: &str) -> Self {
+        let join = |field: String| {
+            if field.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", key, field)
+            }
+        };
+        match self {
+            JsonError::Missing(field) => JsonError::Missing(join(field)),
+            JsonError::Type { field, expected } => JsonError::Type {
+                field: join(field),
+                expected,
+            },
+            syntax => syntax,
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Syntax { offset, message } => {
+                write!(f, "Invalid JSON at byte {}: {}", offset, message)
+            }
+            JsonError::Missing(field) => write!(f, "{} is required", field),
+            JsonError::Type { field, expected } if field.is_empty() => {
+                write!(f, "Expected {}", expected)
+            }
+            JsonError::Type { field, expected } => write!(f, "{} must be {}", field, expected),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> JsonError {
+        JsonError::Syntax {
+            offset: self.pos,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8, message: &'static str) -> Result<(), JsonError> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'n') => self.literal("null", JsonValue::Null),
+            Some(b't') => self.literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.literal("false", JsonValue::Bool(false)),
+            Some(b'"') => self.string().map(JsonValue::String),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<JsonValue, JsonError>,
+    ) -> Result<JsonValue, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<JsonValue, JsonError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, JsonError> {
+        self.pos += 1;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected field name"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':', "expected ':'")?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(fields));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            // Copy the run of plain characters up to the next quote or escape
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            // The input is a &str and runs end on ASCII, so this is valid UTF-8
+            out.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).expect("valid UTF-8"));
+
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => out.push(self.unicode_escape()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                Some(_) => return Err(self.error("control character in string")),
+            }
+        }
+    }
+
+    /// Decode `XXXX` after `\u`, joining surrogate pairs
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if !self.bytes[self.pos..].starts_with(b"\\u") {
+                    return Err(self.error("unpaired surrogate"));
+                }
+                self.pos += 2;
+                let low = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(self.error("unpaired surrogate"));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => return Err(self.error("unpaired surrogate")),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    fn number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => self.digits(),
+            _ => return Err(self.error("invalid number")),
+        }
+        let mut integer = true;
+        if self.peek() == Some(b'.') {
+            integer = false;
+            self.pos += 1;
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            integer = false;
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.digits();
+        }
+
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).expect("ASCII digits");
+        if integer {
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(JsonValue::Int(n));
+            }
+        }
+        text.parse::<f64>()
+            .map(JsonValue::Float)
+            .map_err(|_| self.error("invalid number"))
+    }
+
+    fn digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_print_round_trip() {
+        let input = r#"{"name":"Ali \"Boss\" bin Abu","tags":["vip",null,true],"n":-12,"x":1.5e3,"nested":{"a":[]},"emoji":"🚀é"}"#;
+        let value = JsonValue::parse(input).unwrap();
+        assert_eq!(
+            value.get("name").unwrap().as_str(),
+            Some(r#"Ali "Boss" bin Abu"#)
+        );
+        assert_eq!(value.get("n").unwrap().as_i64(), Some(-12));
+        assert_eq!(value.get("x").unwrap().as_f64(), Some(1500.0));
+        assert_eq!(value.get("emoji").unwrap().as_str(), Some("🚀é"));
+        assert_eq!(value.get("tags").unwrap().as_array().unwrap().len(), 3);
+
+        let printed = value.to_string();
+        assert_eq!(JsonValue::parse(&printed).unwrap(), value);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_documents() {
+        for bad in [
+            "",
+            "{",
+            r#"{"a":1,}"#,
+            "[1 2]",
+            "01",
+            "1.",
+            r#""tab	inside""#,
+            r#""\x""#,
+            r#""\ud83d""#,
+            "nul",
+            "{} extra",
+        ] {
+            assert!(
+                matches!(JsonValue::parse(bad), Err(JsonError::Syntax { .. })),
+                "accepted {:?}",
+                bad
+            );
+        }
+        let deep = "[".repeat(MAX_DEPTH + 1) + &"]".repeat(MAX_DEPTH + 1);
+        assert!(JsonValue::parse(&deep).is_err());
+    }
+
+    #[test]
+    fn test_escaping() {
+        let value = JsonObject::new()
+            .field("message", "say \"hi\"\n\\ \u{1}")
+            .field("missing", None::<String>)
+            .field_if_some("skipped", None::<i64>)
+            .field("items", vec![1u32, 2])
+            .build();
+        assert_eq!(
+            value.to_string(),
+            r#"{"message":"say \"hi\"\n\\ \u0001","missing":null,"items":[1,2]}"#
+        );
+        assert_eq!(escape_json("a\"b"), r#"a\"b"#);
+    }
+
+    #[derive(Debug)]
+    struct Passenger {
+        name: String,
+        age: Option<u8>,
+    }
+
+    impl FromJson for Passenger {
+        fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+            Ok(Self {
+                name: value.field("name")?,
+                age: value.field("age")?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let value =
+            JsonValue::parse(r#"{"passengers":[{"name":"A","age":30},{"name":"B"}]}"#).unwrap();
+        let passengers: Vec<Passenger> = value.field("passengers").unwrap();
+        assert_eq!(passengers[0].name, "A");
+        assert_eq!(passengers[0].age, Some(30));
+        assert_eq!(passengers[1].age, None);
+
+        let value = JsonValue::parse(r#"{"passengers":[{"name":"A"},{"age":300}]}"#).unwrap();
+        let err = value.field::<Vec<Passenger>>("passengers").unwrap_err();
+        assert_eq!(err, JsonError::Missing("passengers.1.name".into()));
+
+        let value = JsonValue::parse(r#"{"passengers":[{"name":"A","age":300}]}"#).unwrap();
+        let err = value.field::<Vec<Passenger>>("passengers").unwrap_err();
+        assert_eq!(err.to_string(), "passengers.0.age must be integer (u8)");
+    }
+}
@@ -6,16 +6,29 @@
 //! - **Middleware**: Authentication, rate limiting, CORS, logging
 //! - **Request/Response**: Type-safe HTTP types
 //! - **Error handling**: Consistent error responses
+//! - **JSON**: Escaped serialization and typed request body parsing
+//! - **Upstreams**: Calls to internal services found through fleet
+//!   discovery, load-balanced by health
 //!
 //! # Architecture
 //!
 //! The API follows RESTful conventions with versioned endpoints:
 //!
 //! - `/api/v1/search` - Flight search
+//! - `/api/v1/searches` - Saved searches and share links
+//! - `/api/v1/airports` - Airport autocomplete
+//! - `/api/v1/defaults` - Market-aware default search parameters
 //! - `/api/v1/bookings` - Booking management
+//! - `/api/v1/oracle` - Price predictions and verdicts
 //! - `/api/v1/pools` - Group buying pools
 //! - `/api/v1/alerts` - Price alerts
 //! - `/api/v1/users` - User management
+//! - `/api/v1/admin/jobs` - Background export and report jobs
+//! - `/api/v1/admin/system` - System stats and operational controls
+//!
+//! Handlers can be registered under several versions with
+//! [`ApiServer::versioned`]; deprecated routes and versions answer with
+//! `Deprecation`/`Sunset` headers, and requests are counted per version.
 //!
 //! # Example
 //!
@@ -32,25 +45,42 @@
 
 mod error;
 pub mod handlers;
+mod json;
 mod middleware;
+mod ratelimit;
 mod router;
+mod system;
 mod types;
+mod upstream;
+mod versioning;
+
+use std::fmt;
+use std::sync::Arc;
+
+use vaya_common::{Mask, Redact};
 
 pub use error::{ApiError, ApiResult, FieldError};
+pub use json::{escape_json, FromJson, JsonError, JsonObject, JsonSerialize, JsonValue, MAX_DEPTH};
 pub use middleware::{
-    AuthMiddleware, CorsConfig, Middleware, MiddlewareChain, RateLimitInfo, RateLimiter,
-    RequestLogger, TokenClaims,
+    AuthMiddleware, CorsConfig, Middleware, MiddlewareChain, RequestLogger, TokenClaims,
+};
+pub use ratelimit::{
+    tier_for_roles, LocalRateLimitStore, RateLimitInfo, RateLimitStore, RateLimiter, RateQuota,
+    RATE_LIMITED_METRIC,
 };
 pub use router::{Handler, Method, Route, Router};
-pub use types::{
-    parse_query_string, ErrorBody, JsonSerialize, PaginatedBody, Request, Response, SuccessBody,
+pub use system::SystemState;
+pub use types::{parse_query_string, ErrorBody, PaginatedBody, Request, Response, SuccessBody};
+pub use upstream::{Upstream, ORACLE_TRAINER_SERVICE};
+pub use versioning::{
+    ApiVersion, Deprecation, DEPRECATED_REQUESTS_METRIC, VERSION_REQUESTS_METRIC,
 };
 
 /// API version
 pub const API_VERSION: &str = "v1";
 
 /// API configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ApiConfig {
     /// API prefix (e.g., "/api/v1")
     pub prefix: String,
@@ -70,6 +100,21 @@ pub struct ApiConfig {
     pub max_body_size: usize,
 }
 
+impl fmt::Debug for ApiConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiConfig")
+            .field("prefix", &self.prefix)
+            .field("rate_limit_requests", &self.rate_limit_requests)
+            .field("rate_limit_window", &self.rate_limit_window)
+            .field("jwt_secret", &self.jwt_secret.redacted(Mask::Full))
+            .field("enable_cors", &self.enable_cors)
+            .field("cors_origins", &self.cors_origins)
+            .field("request_timeout", &self.request_timeout)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
 impl Default for ApiConfig {
     fn default() -> Self {
         Self {
@@ -132,6 +177,8 @@ pub struct ApiServer {
     cors: Option<CorsConfig>,
     /// Request logger
     logger: RequestLogger,
+    /// Handles for the admin system endpoints, attached to every request
+    system: Option<Arc<SystemState>>,
 }
 
 impl ApiServer {
@@ -155,6 +202,7 @@ impl ApiServer {
             rate_limiter,
             cors,
             logger: RequestLogger::new(),
+            system: None,
         }
     }
 
@@ -183,11 +231,54 @@ impl ApiServer {
         self.router.delete(path, handler, name);
     }
 
+    /// Add a route under several API versions
+    pub fn versioned(
+        &mut self,
+        method: Method,
+        versions: &[ApiVersion],
+        path: &str,
+        handler: Handler,
+        name: &str,
+    ) {
+        self.router.versioned(method, versions, path, handler, name);
+    }
+
+    /// Mark one version of a route deprecated
+    pub fn deprecate(
+        &mut self,
+        version: ApiVersion,
+        method: Method,
+        path: &str,
+        deprecation: Deprecation,
+    ) -> bool {
+        self.router.deprecate(version, method, path, deprecation)
+    }
+
+    /// Mark a whole API version deprecated
+    pub fn deprecate_version(&mut self, version: ApiVersion, deprecation: Deprecation) {
+        self.router.deprecate_version(version, deprecation);
+    }
+
     /// Add middleware
     pub fn add_middleware(&mut self, name: &'static str, middleware: Middleware) {
         self.middleware.add(name, middleware);
     }
 
+    /// Replace the rate limiter (`None` disables rate limiting)
+    pub fn set_rate_limiter(&mut self, limiter: Option<RateLimiter>) {
+        self.rate_limiter = limiter;
+    }
+
+    /// Get the rate limiter, if rate limiting is enabled
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Attach the system state the admin system endpoints read
+    pub fn set_system(&mut self, system: Arc<SystemState>) {
+        self.system = Some(system);
+    }
+
     /// Merge another router
     pub fn mount(&mut self, path: &str, router: Router) {
         self.router.merge(router, Some(path));
@@ -199,35 +290,38 @@ impl ApiServer {
 
         // Log request start
         self.logger.log_start(&request);
-
-        // Check rate limit
-        if let Some(ref limiter) = self.rate_limiter {
-            let client_id = request
-                .client_ip
-                .clone()
-                .unwrap_or_else(|| "unknown".into());
-            match limiter.check(&client_id) {
-                Ok(info) => {
-                    // Rate limit OK, continue
-                    let _ = info; // Would apply headers later
-                }
-                Err(e) => {
-                    return e.to_response();
-                }
-            }
-        }
+        request.system = self.system.clone();
 
         // Execute middleware chain
         if let Err(e) = self.middleware.execute(&mut request) {
             return e.to_response();
         }
 
+        // Check rate limit: signed-in users by tier, others by IP
+        let rate_limit = match self.rate_limiter {
+            Some(ref limiter) => {
+                let result = match (&request.user_id, tier_for_roles(&request.user_roles)) {
+                    (Some(user_id), Some(tier)) => limiter.check_tier(user_id, tier),
+                    _ => limiter.check(request.client_ip.as_deref().unwrap_or("unknown")),
+                };
+                match result {
+                    Ok(info) => Some((limiter, info)),
+                    Err(e) => return e.to_response(),
+                }
+            }
+            None => None,
+        };
+
         // Route request
         let mut response = match self.router.route(&request) {
             Ok(r) => r,
             Err(e) => e.to_response(),
         };
 
+        if let Some((limiter, info)) = rate_limit {
+            limiter.apply_headers(&mut response, &info);
+        }
+
         // Apply CORS headers
         if let Some(ref cors) = self.cors {
             cors.apply(&request, &mut response);
@@ -260,11 +354,12 @@ pub struct HealthResponse {
 }
 
 impl JsonSerialize for HealthResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"status":"{}","version":"{}","uptime_seconds":{}}}"#,
-            self.status, self.version, self.uptime_seconds
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("status", &self.status)
+            .field("version", &self.version)
+            .field("uptime_seconds", self.uptime_seconds)
+            .build()
     }
 }
 
@@ -341,6 +436,37 @@ mod tests {
         assert_eq!(response.status, 200);
     }
 
+    #[test]
+    fn test_server_rate_limit() {
+        fn test_handler(_req: &Request) -> ApiResult<Response> {
+            Ok(Response::ok())
+        }
+
+        let config = ApiConfig::new().with_prefix("/api").with_rate_limit(2, 60);
+        let mut server = ApiServer::new(config);
+        server.get("/test", test_handler, "test");
+
+        let request = || {
+            let mut request = Request::new("GET", "/api/test");
+            request.client_ip = Some("10.0.0.1".into());
+            request
+        };
+        let response = server.handle(request());
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.headers.get("x-ratelimit-remaining"),
+            Some(&"1".to_string())
+        );
+        server.handle(request());
+
+        let response = server.handle(request());
+        assert_eq!(response.status, 429);
+        assert_eq!(response.headers.get("retry-after"), Some(&"30".to_string()));
+
+        server.set_rate_limiter(None);
+        assert_eq!(server.handle(request()).status, 200);
+    }
+
     #[test]
     fn test_server_not_found() {
         let config = ApiConfig::new();
@@ -1,7 +1,5 @@
-//! API Middleware for authentication, rate limiting, and logging
+//! API Middleware for authentication, CORS, and logging
 
-use std::collections::HashMap;
-use std::sync::Mutex;
 use time::OffsetDateTime;
 
 use crate::{ApiError, ApiResult, Request, Response};
@@ -131,98 +129,6 @@ pub struct TokenClaims {
     pub exp: i64,
 }
 
-/// Rate limiter using token bucket algorithm
-pub struct RateLimiter {
-    /// Requests per window
-    requests_per_window: u32,
-    /// Window size in seconds
-    window_seconds: i64,
-    /// Client buckets (would use proper storage in production)
-    buckets: Mutex<HashMap<String, RateBucket>>,
-}
-
-impl std::fmt::Debug for RateLimiter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("RateLimiter")
-            .field("requests_per_window", &self.requests_per_window)
-            .field("window_seconds", &self.window_seconds)
-            .finish()
-    }
-}
-
-#[derive(Debug, Clone)]
-struct RateBucket {
-    tokens: u32,
-    last_refill: i64,
-}
-
-impl RateLimiter {
-    /// Create new rate limiter
-    pub fn new(requests_per_window: u32, window_seconds: i64) -> Self {
-        Self {
-            requests_per_window,
-            window_seconds,
-            buckets: Mutex::new(HashMap::new()),
-        }
-    }
-
-    /// Check rate limit for client
-    pub fn check(&self, client_id: &str) -> ApiResult<RateLimitInfo> {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
-        let mut buckets = self.buckets.lock().unwrap();
-
-        let bucket = buckets.entry(client_id.to_string()).or_insert(RateBucket {
-            tokens: self.requests_per_window,
-            last_refill: now,
-        });
-
-        // Refill tokens if window has passed
-        let elapsed = now - bucket.last_refill;
-        if elapsed >= self.window_seconds {
-            bucket.tokens = self.requests_per_window;
-            bucket.last_refill = now;
-        }
-
-        // Check if tokens available
-        if bucket.tokens == 0 {
-            let retry_after = self.window_seconds - elapsed;
-            return Err(ApiError::RateLimited {
-                retry_after: retry_after as u32,
-            });
-        }
-
-        // Consume token
-        bucket.tokens -= 1;
-
-        Ok(RateLimitInfo {
-            remaining: bucket.tokens,
-            limit: self.requests_per_window,
-            reset_at: bucket.last_refill + self.window_seconds,
-        })
-    }
-
-    /// Apply rate limit headers to response
-    pub fn apply_headers(&self, response: &mut Response, info: &RateLimitInfo) {
-        response
-            .headers
-            .insert("x-ratelimit-remaining".into(), info.remaining.to_string());
-        response
-            .headers
-            .insert("x-ratelimit-limit".into(), info.limit.to_string());
-        response
-            .headers
-            .insert("x-ratelimit-reset".into(), info.reset_at.to_string());
-    }
-}
-
-/// Rate limit info
-#[derive(Debug, Clone)]
-pub struct RateLimitInfo {
-    pub remaining: u32,
-    pub limit: u32,
-    pub reset_at: i64,
-}
-
 /// Request logging middleware
 #[derive(Debug)]
 pub struct RequestLogger {
@@ -400,41 +306,6 @@ mod tests {
         assert!(!auth.should_skip("/api/v1/users"));
     }
 
-    #[test]
-    fn test_rate_limiter() {
-        let limiter = RateLimiter::new(5, 60);
-
-        // First request should pass
-        let info = limiter.check("client-1").unwrap();
-        assert_eq!(info.remaining, 4);
-        assert_eq!(info.limit, 5);
-
-        // Consume all tokens
-        for _ in 0..4 {
-            limiter.check("client-1").unwrap();
-        }
-
-        // Should be rate limited
-        let result = limiter.check("client-1");
-        assert!(matches!(result, Err(ApiError::RateLimited { .. })));
-
-        // Different client should work
-        let info = limiter.check("client-2").unwrap();
-        assert_eq!(info.remaining, 4);
-    }
-
-    #[test]
-    fn test_rate_limit_headers() {
-        let limiter = RateLimiter::new(100, 60);
-        let mut response = Response::ok();
-
-        let info = limiter.check("client").unwrap();
-        limiter.apply_headers(&mut response, &info);
-
-        assert!(response.headers.contains_key("x-ratelimit-remaining"));
-        assert!(response.headers.contains_key("x-ratelimit-limit"));
-    }
-
     #[test]
     fn test_cors_config() {
         let cors = CorsConfig::new()
@@ -0,0 +1,422 @@
+//! Rate limiting with GCRA and per-tier quotas
+//!
+//! The limiter implements the generic cell rate algorithm: each key has a
+//! theoretical arrival time (TAT) that advances by one emission interval
+//! (`window / limit`) per admitted request, and a request is rejected when
+//! the TAT would run more than `burst` intervals ahead of now. This is a
+//! smooth sliding limit with a single integer of state per key, and the
+//! `Retry-After` of a rejection is exact.
+//!
+//! State lives in a [`RateLimitStore`]. [`LocalRateLimitStore`] limits per
+//! process; on multi-node deployments a [`vaya_fleet::SharedRateLimits`]
+//! adds the backlog of every node so limits are enforced fleet-wide.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use time::OffsetDateTime;
+use vaya_common::{metrics, UserTier};
+use vaya_fleet::SharedRateLimits;
+
+use crate::{ApiError, ApiResult, Response};
+
+/// Metric counting rejected requests per tier
+pub const RATE_LIMITED_METRIC: &str = "vaya_api_rate_limited_total";
+
+/// Window of the per-tier quotas (one day)
+const TIER_WINDOW_SECS: i64 = 86_400;
+
+/// Drained entries are pruned from the local store beyond this many keys
+const LOCAL_PRUNE_THRESHOLD: usize = 10_000;
+
+/// Requests allowed per window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateQuota {
+    /// Sustained requests per window
+    pub limit: u32,
+    /// Window size in seconds
+    pub window_secs: i64,
+    /// Requests that may be made at once before spacing kicks in
+    pub burst: u32,
+}
+
+impl RateQuota {
+    /// Quota of `limit` requests per window, all usable at once
+    pub fn new(limit: u32, window_secs: i64) -> Self {
+        Self {
+            limit,
+            window_secs,
+            burst: limit,
+        }
+    }
+
+    /// Set the burst allowance
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Daily search quota of a tier, a quarter of which may be used at once
+    pub fn for_tier(tier: UserTier) -> Self {
+        let limit = tier.search_limit();
+        Self::new(limit, TIER_WINDOW_SECS).with_burst(limit / 4)
+    }
+
+    /// Time between requests at the sustained rate (ms)
+    pub fn emission_interval_ms(&self) -> i64 {
+        (self.window_secs * 1000 / i64::from(self.limit.max(1))).max(1)
+    }
+}
+
+/// Storage for per-key rate limiter state
+pub trait RateLimitStore: Send + Sync {
+    /// Atomically update this node's TAT for `key`
+    ///
+    /// `decide` receives the stored TAT (ms) and the summed backlog of
+    /// other nodes at `now_ms` (0 for a single node), and returns the TAT
+    /// to store, or `None` to leave it unchanged.
+    fn update(
+        &self,
+        key: &str,
+        now_ms: i64,
+        decide: &mut dyn FnMut(Option<i64>, i64) -> Option<i64>,
+    );
+}
+
+/// Per-process rate limiter state
+#[derive(Debug, Default)]
+pub struct LocalRateLimitStore {
+    tats: Mutex<HashMap<String, i64>>,
+}
+
+impl LocalRateLimitStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl RateLimitStore for LocalRateLimitStore {
+    fn update(
+        &self,
+        key: &str,
+        now_ms: i64,
+        decide: &mut dyn FnMut(Option<i64>, i64) -> Option<i64>,
+    ) {
+        let mut tats = self.tats.lock().unwrap();
+        if tats.len() > LOCAL_PRUNE_THRESHOLD {
+            tats.retain(|_, tat| *tat > now_ms);
+        }
+        if let Some(tat) = decide(tats.get(key).copied(), 0) {
+            tats.insert(key.to_string(), tat);
+        }
+    }
+}
+
+impl RateLimitStore for SharedRateLimits {
+    fn update(
+        &self,
+        key: &str,
+        now_ms: i64,
+        decide: &mut dyn FnMut(Option<i64>, i64) -> Option<i64>,
+    ) {
+        SharedRateLimits::update(self, key, now_ms, |tat, peers| (decide(tat, peers), ()));
+    }
+}
+
+/// Rate limiter using GCRA
+pub struct RateLimiter {
+    /// Quota for clients without a tier
+    quota: RwLock<RateQuota>,
+    /// Quotas overriding [`RateQuota::for_tier`]
+    tier_quotas: HashMap<UserTier, RateQuota>,
+    /// Limiter state
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("quota", &self.quota())
+            .field("tier_quotas", &self.tier_quotas)
+            .finish()
+    }
+}
+
+impl RateLimiter {
+    /// Create a per-process limiter of `requests_per_window` per window
+    pub fn new(requests_per_window: u32, window_seconds: i64) -> Self {
+        Self {
+            quota: RwLock::new(RateQuota::new(requests_per_window, window_seconds)),
+            tier_quotas: HashMap::new(),
+            store: Arc::new(LocalRateLimitStore::new()),
+        }
+    }
+
+    /// Keep state in the given store (e.g. one shared across the fleet)
+    pub fn with_store(mut self, store: Arc<dyn RateLimitStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Set the burst allowance of the default quota
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        let quota = self.quota.get_mut().unwrap();
+        *quota = quota.with_burst(burst);
+        self
+    }
+
+    /// Override the quota of a tier
+    pub fn with_tier_quota(mut self, tier: UserTier, quota: RateQuota) -> Self {
+        self.tier_quotas.insert(tier, quota);
+        self
+    }
+
+    /// Quota for clients without a tier
+    pub fn quota(&self) -> RateQuota {
+        *self.quota.read().unwrap()
+    }
+
+    /// Replace the quota for clients without a tier
+    ///
+    /// Takes effect on the next request; what each client has used so far
+    /// is kept, so a lower limit doesn't hand out a fresh allowance.
+    pub fn set_quota(&self, quota: RateQuota) {
+        *self.quota.write().unwrap() = quota;
+    }
+
+    /// Quota applied to a tier
+    pub fn tier_quota(&self, tier: UserTier) -> RateQuota {
+        self.tier_quotas
+            .get(&tier)
+            .copied()
+            .unwrap_or_else(|| RateQuota::for_tier(tier))
+    }
+
+    /// Check rate limit for client under the default quota
+    pub fn check(&self, client_id: &str) -> ApiResult<RateLimitInfo> {
+        let result = self.check_at(client_id, &self.quota(), now_ms());
+        if result.is_err() {
+            record_rejection("none");
+        }
+        result
+    }
+
+    /// Check rate limit for a user under their tier's quota
+    pub fn check_tier(&self, client_id: &str, tier: UserTier) -> ApiResult<RateLimitInfo> {
+        let key = format!("{}:{}", tier.as_str(), client_id);
+        let result = self.check_at(&key, &self.tier_quota(tier), now_ms());
+        if result.is_err() {
+            record_rejection(tier.as_str());
+        }
+        result
+    }
+
+    /// Admit or reject one request at `now_ms`
+    fn check_at(&self, key: &str, quota: &RateQuota, now_ms: i64) -> ApiResult<RateLimitInfo> {
+        let interval = quota.emission_interval_ms();
+        let capacity = interval * i64::from(quota.burst.max(1));
+        let mut outcome = Err(0);
+
+        self.store.update(key, now_ms, &mut |tat, peer_backlog| {
+            let tat = tat.unwrap_or(now_ms).max(now_ms);
+            let backlog = tat - now_ms + peer_backlog + interval;
+            if backlog > capacity {
+                outcome = Err(backlog - capacity);
+                return None;
+            }
+            outcome = Ok(backlog);
+            Some(tat + interval)
+        });
+
+        match outcome {
+            Ok(backlog) => Ok(RateLimitInfo {
+                remaining: ((capacity - backlog) / interval) as u32,
+                limit: quota.limit,
+                reset_at: (now_ms + backlog + 999).div_euclid(1000),
+            }),
+            Err(wait_ms) => Err(ApiError::RateLimited {
+                retry_after: ((wait_ms + 999) / 1000).max(1) as u32,
+            }),
+        }
+    }
+
+    /// Apply rate limit headers to response
+    pub fn apply_headers(&self, response: &mut Response, info: &RateLimitInfo) {
+        response
+            .headers
+            .insert("x-ratelimit-remaining".into(), info.remaining.to_string());
+        response
+            .headers
+            .insert("x-ratelimit-limit".into(), info.limit.to_string());
+        response
+            .headers
+            .insert("x-ratelimit-reset".into(), info.reset_at.to_string());
+    }
+}
+
+/// Rate limit info
+#[derive(Debug, Clone)]
+pub struct RateLimitInfo {
+    /// Requests that can be made right now
+    pub remaining: u32,
+    /// Sustained requests per window
+    pub limit: u32,
+    /// When the limit fully resets (Unix timestamp)
+    pub reset_at: i64,
+}
+
+/// Subscription tier implied by a user's roles
+pub fn tier_for_roles(roles: &[String]) -> Option<UserTier> {
+    let has = |role: &str| roles.iter().any(|r| r == role);
+    if has("enterprise") {
+        Some(UserTier::Enterprise)
+    } else if has("premium") {
+        Some(UserTier::Premium)
+    } else if has("user") {
+        Some(UserTier::Free)
+    } else {
+        None
+    }
+}
+
+fn now_ms() -> i64 {
+    (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+fn record_rejection(tier: &str) {
+    metrics::global()
+        .counter(RATE_LIMITED_METRIC, &[("tier", tier)])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_fleet::NodeId;
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(5, 60);
+
+        // First request should pass
+        let info = limiter.check("client-1").unwrap();
+        assert_eq!(info.remaining, 4);
+        assert_eq!(info.limit, 5);
+
+        // Consume all tokens
+        for _ in 0..4 {
+            limiter.check("client-1").unwrap();
+        }
+
+        // Should be rate limited
+        let result = limiter.check("client-1");
+        assert!(matches!(result, Err(ApiError::RateLimited { .. })));
+
+        // Different client should work
+        let info = limiter.check("client-2").unwrap();
+        assert_eq!(info.remaining, 4);
+    }
+
+    #[test]
+    fn test_set_quota_keeps_usage() {
+        let limiter = RateLimiter::new(5, 60);
+        for _ in 0..3 {
+            limiter.check("client").unwrap();
+        }
+
+        limiter.set_quota(RateQuota::new(4, 60));
+        assert_eq!(limiter.quota().limit, 4);
+        // Three of the four were already used
+        assert_eq!(limiter.check("client").unwrap().limit, 4);
+        assert!(limiter.check("client").is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let limiter = RateLimiter::new(100, 60);
+        let mut response = Response::ok();
+
+        let info = limiter.check("client").unwrap();
+        limiter.apply_headers(&mut response, &info);
+
+        assert!(response.headers.contains_key("x-ratelimit-remaining"));
+        assert!(response.headers.contains_key("x-ratelimit-limit"));
+    }
+
+    #[test]
+    fn test_gcra_burst_and_retry_after() {
+        // 60 per minute (one per second) with a burst of 3
+        let limiter = RateLimiter::new(60, 60).with_burst(3);
+        let quota = limiter.quota();
+        let t0 = 1_000_000;
+
+        for remaining in [2, 1, 0] {
+            let info = limiter.check_at("c", &quota, t0).unwrap();
+            assert_eq!(info.remaining, remaining);
+        }
+        match limiter.check_at("c", &quota, t0) {
+            Err(ApiError::RateLimited { retry_after }) => assert_eq!(retry_after, 1),
+            other => panic!("expected rate limit, got {:?}", other.map(|i| i.remaining)),
+        }
+
+        // One request drains per second, and rejections don't consume any
+        assert!(limiter.check_at("c", &quota, t0 + 500).is_err());
+        assert!(limiter.check_at("c", &quota, t0 + 1_000).is_ok());
+        assert!(limiter.check_at("c", &quota, t0 + 1_000).is_err());
+        let info = limiter.check_at("c", &quota, t0 + 10_000).unwrap();
+        assert_eq!(info.remaining, 2);
+    }
+
+    #[test]
+    fn test_tier_quotas() {
+        let limiter =
+            RateLimiter::new(100, 60).with_tier_quota(UserTier::Enterprise, RateQuota::new(2, 60));
+
+        let free = limiter.tier_quota(UserTier::Free);
+        assert_eq!(free.limit, UserTier::Free.search_limit());
+        assert_eq!(free.window_secs, 86_400);
+        assert_eq!(free.burst, UserTier::Free.search_limit() / 4);
+
+        for _ in 0..free.burst {
+            limiter.check_tier("user-1", UserTier::Free).unwrap();
+        }
+        assert!(limiter.check_tier("user-1", UserTier::Free).is_err());
+        // Tiers and the default quota keep separate state
+        assert!(limiter.check("user-1").is_ok());
+
+        limiter.check_tier("user-2", UserTier::Enterprise).unwrap();
+        limiter.check_tier("user-2", UserTier::Enterprise).unwrap();
+        assert!(limiter.check_tier("user-2", UserTier::Enterprise).is_err());
+
+        let roles = |r: &[&str]| r.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            tier_for_roles(&roles(&["user", "premium"])),
+            Some(UserTier::Premium)
+        );
+        assert_eq!(tier_for_roles(&roles(&["user"])), Some(UserTier::Free));
+        assert_eq!(tier_for_roles(&[]), None);
+    }
+
+    #[test]
+    fn test_shared_store_enforces_fleet_wide() {
+        let a = Arc::new(SharedRateLimits::new(NodeId::new("a")));
+        let b = Arc::new(SharedRateLimits::new(NodeId::new("b")));
+        let node_a = RateLimiter::new(4, 60).with_store(a.clone());
+        let node_b = RateLimiter::new(4, 60).with_store(b.clone());
+        let quota = node_a.quota();
+        let now = 1_000_000;
+
+        node_a.check_at("c", &quota, now).unwrap();
+        node_a.check_at("c", &quota, now).unwrap();
+        node_b.check_at("c", &quota, now).unwrap();
+        node_b.check_at("c", &quota, now).unwrap();
+
+        // Each node alone is under the limit until they exchange state
+        a.merge(&b.snapshot(now));
+        b.merge(&a.snapshot(now));
+        assert!(node_a.check_at("c", &quota, now).is_err());
+        assert!(node_b.check_at("c", &quota, now).is_err());
+        assert!(node_a.check_at("c", &quota, now + 15_000).is_ok());
+    }
+}
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 
+use crate::versioning::{self, ApiVersion, Deprecation};
 use crate::{ApiError, ApiResult, Request, Response};
 
 /// HTTP Method
@@ -57,6 +58,10 @@ pub struct Route {
     pub pattern: String,
     /// Handler function name (for debugging)
     pub handler_name: String,
+    /// API version, from the pattern's version segment
+    pub version: Option<ApiVersion>,
+    /// Deprecation notice for this route
+    pub deprecation: Option<Deprecation>,
     /// Path segments for matching
     segments: Vec<PathSegment>,
 }
@@ -65,11 +70,28 @@ pub struct Route {
 enum PathSegment {
     Literal(String),
     Param(String),
-    Wildcard,
+    /// Rest of the path, captured under the name if one is given
+    Wildcard(Option<String>),
+}
+
+impl PathSegment {
+    /// Rank for choosing between overlapping routes
+    fn rank(&self) -> u8 {
+        match self {
+            PathSegment::Literal(_) => 0,
+            PathSegment::Param(_) => 1,
+            PathSegment::Wildcard(_) => 2,
+        }
+    }
 }
 
 impl Route {
     /// Create a new route
+    ///
+    /// Pattern segments are literals, `:name` parameters matching one
+    /// segment, or a trailing `*` or `*name` wildcard matching the rest of
+    /// the path (possibly empty). A named wildcard captures the rest,
+    /// without the leading slash, as a parameter.
     pub fn new(
         method: Method,
         pattern: impl Into<String>,
@@ -80,8 +102,10 @@ impl Route {
 
         Self {
             method,
+            version: ApiVersion::from_path(&pattern),
             pattern,
             handler_name: handler_name.into(),
+            deprecation: None,
             segments,
         }
     }
@@ -93,10 +117,10 @@ impl Route {
             .split('/')
             .filter(|s| !s.is_empty())
             .map(|s| {
-                if s.starts_with(':') {
-                    PathSegment::Param(s[1..].to_string())
-                } else if s == "*" {
-                    PathSegment::Wildcard
+                if let Some(name) = s.strip_prefix(':') {
+                    PathSegment::Param(name.to_string())
+                } else if let Some(name) = s.strip_prefix('*') {
+                    PathSegment::Wildcard((!name.is_empty()).then(|| name.to_string()))
                 } else {
                     PathSegment::Literal(s.to_string())
                 }
@@ -105,51 +129,72 @@ impl Route {
     }
 
     /// Match a path and extract parameters
+    ///
+    /// Any query string is ignored. Parameter values are percent-decoded.
     pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
-        let path_parts: Vec<&str> = path
-            .trim_matches('/')
-            .split('/')
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        if path_parts.len() != self.segments.len() {
-            // Check for wildcard at end
-            if let Some(PathSegment::Wildcard) = self.segments.last() {
-                if path_parts.len() >= self.segments.len() - 1 {
-                    // Wildcard matches remaining
-                } else {
-                    return None;
-                }
-            } else {
-                return None;
-            }
-        }
-
+        let path = path.split(['?', '#']).next().unwrap_or_default();
+        let mut parts = path.trim_matches('/').split('/').filter(|s| !s.is_empty());
         let mut params = HashMap::new();
 
-        for (i, segment) in self.segments.iter().enumerate() {
+        for segment in &self.segments {
             match segment {
                 PathSegment::Literal(expected) => {
-                    if i >= path_parts.len() || path_parts[i] != expected.as_str() {
+                    if parts.next()? != expected.as_str() {
                         return None;
                     }
                 }
                 PathSegment::Param(name) => {
-                    if i < path_parts.len() {
-                        params.insert(name.clone(), path_parts[i].to_string());
-                    } else {
-                        return None;
-                    }
+                    params.insert(name.clone(), decode_segment(parts.next()?)?);
                 }
-                PathSegment::Wildcard => {
-                    // Wildcard matches rest
-                    break;
+                PathSegment::Wildcard(name) => {
+                    // A wildcard ends the pattern
+                    let rest = parts.by_ref().collect::<Vec<_>>().join("/");
+                    if let Some(name) = name {
+                        params.insert(name.clone(), decode_segment(&rest)?);
+                    }
+                    return Some(params);
                 }
             }
         }
 
+        if parts.next().is_some() {
+            return None;
+        }
         Some(params)
     }
+
+    /// Ordering key for overlapping routes: literals beat parameters,
+    /// which beat wildcards, comparing segment by segment
+    fn specificity(&self) -> Vec<u8> {
+        self.segments.iter().map(PathSegment::rank).collect()
+    }
+}
+
+/// Percent-decode a path segment; `None` if it doesn't decode to UTF-8
+fn decode_segment(segment: &str) -> Option<String> {
+    if !segment.contains('%') {
+        return Some(segment.to_string());
+    }
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| segment.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
 }
 
 /// Router for matching requests to handlers
@@ -161,16 +206,14 @@ pub struct Router {
     handlers: HashMap<usize, Handler>,
     /// Prefix for all routes
     prefix: String,
+    /// Deprecation notices covering every route of a version
+    version_deprecations: HashMap<ApiVersion, Deprecation>,
 }
 
 impl Router {
     /// Create a new router
     pub fn new() -> Self {
-        Self {
-            routes: Vec::new(),
-            handlers: HashMap::new(),
-            prefix: String::new(),
-        }
+        Self::with_prefix("")
     }
 
     /// Create router with prefix
@@ -179,6 +222,7 @@ impl Router {
             routes: Vec::new(),
             handlers: HashMap::new(),
             prefix: prefix.into(),
+            version_deprecations: HashMap::new(),
         }
     }
 
@@ -216,24 +260,109 @@ impl Router {
         self.handlers.insert(index, handler);
     }
 
+    /// Add a route under several API versions
+    ///
+    /// The version segment of the router prefix is replaced, so with prefix
+    /// "/api/v1" and versions v1 and v2, "/bookings" is served at both
+    /// "/api/v1/bookings" and "/api/v2/bookings".
+    pub fn versioned(
+        &mut self,
+        method: Method,
+        versions: &[ApiVersion],
+        pattern: &str,
+        handler: Handler,
+        name: &str,
+    ) {
+        let (base, _) = ApiVersion::split_prefix(&self.prefix);
+        let base = base.to_string();
+        for version in versions {
+            let route = Route::new(method, format!("{}/{}{}", base, version, pattern), name);
+            let index = self.routes.len();
+            self.routes.push(route);
+            self.handlers.insert(index, handler);
+        }
+    }
+
+    /// Mark one version of a route deprecated
+    ///
+    /// `pattern` is relative to the router base, as passed to
+    /// [`Router::versioned`]. Returns false if no such route is registered.
+    pub fn deprecate(
+        &mut self,
+        version: ApiVersion,
+        method: Method,
+        pattern: &str,
+        deprecation: Deprecation,
+    ) -> bool {
+        let (base, _) = ApiVersion::split_prefix(&self.prefix);
+        let full_pattern = format!("{}/{}{}", base, version, pattern);
+        match self
+            .routes
+            .iter_mut()
+            .find(|r| r.method == method && r.pattern == full_pattern)
+        {
+            Some(route) => {
+                route.deprecation = Some(deprecation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark every route of a version deprecated
+    ///
+    /// Route-level notices from [`Router::deprecate`] take precedence.
+    pub fn deprecate_version(&mut self, version: ApiVersion, deprecation: Deprecation) {
+        self.version_deprecations.insert(version, deprecation);
+    }
+
+    /// API versions with at least one route, ascending
+    pub fn versions(&self) -> Vec<ApiVersion> {
+        let mut versions: Vec<ApiVersion> = self.routes.iter().filter_map(|r| r.version).collect();
+        versions.sort();
+        versions.dedup();
+        versions
+    }
+
+    /// Deprecation notice in effect for a route
+    pub fn deprecation_for<'a>(&'a self, route: &'a Route) -> Option<&'a Deprecation> {
+        route.deprecation.as_ref().or_else(|| {
+            route
+                .version
+                .and_then(|v| self.version_deprecations.get(&v))
+        })
+    }
+
     /// Find matching route for request
+    ///
+    /// When several routes match, the most specific wins (see
+    /// [`Route::new`]), so `/users/me` is found before `/users/:id`
+    /// whichever was registered first. Equally specific routes match in
+    /// registration order.
     pub fn find(
         &self,
         method: Method,
         path: &str,
     ) -> Option<(&Route, HashMap<String, String>, Handler)> {
+        let mut best: Option<(&Route, HashMap<String, String>, Handler)> = None;
         for (i, route) in self.routes.iter().enumerate() {
             if route.method != method {
                 continue;
             }
+            if best
+                .as_ref()
+                .is_some_and(|(b, _, _)| route.specificity() >= b.specificity())
+            {
+                continue;
+            }
 
             if let Some(params) = route.match_path(path) {
                 if let Some(handler) = self.handlers.get(&i) {
-                    return Some((route, params, *handler));
+                    best = Some((route, params, *handler));
                 }
             }
         }
-        None
+        best
     }
 
     /// Get all routes for documentation
@@ -258,12 +387,40 @@ impl Router {
                     "Routing request"
                 );
 
-                handler(&req)
+                let Some(version) = route.version else {
+                    return handler(&req);
+                };
+                let deprecation = self.deprecation_for(route);
+                versioning::record_usage(version, &route.handler_name, deprecation.is_some());
+                let mut response = handler(&req)?;
+                response
+                    .headers
+                    .insert("api-version".into(), version.to_string());
+                if let Some(deprecation) = deprecation {
+                    deprecation.apply(&mut response);
+                }
+                Ok(response)
+            }
+            None => {
+                let supported = self.versions();
+                match ApiVersion::from_path(&request.path) {
+                    Some(version) if !supported.is_empty() && !supported.contains(&version) => {
+                        Err(ApiError::NotFound(format!(
+                            "API version {} is not supported (supported: {})",
+                            version,
+                            supported
+                                .iter()
+                                .map(ApiVersion::to_string)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )))
+                    }
+                    _ => Err(ApiError::NotFound(format!(
+                        "No route for {} {}",
+                        request.method, request.path
+                    ))),
+                }
             }
-            None => Err(ApiError::NotFound(format!(
-                "No route for {} {}",
-                request.method, request.path
-            ))),
         }
     }
 
@@ -275,7 +432,9 @@ impl Router {
                 None => route.pattern,
             };
 
-            let new_route = Route::new(route.method, pattern, route.handler_name);
+            let mut new_route = Route::new(route.method, pattern, route.handler_name);
+            new_route.version = new_route.version.or(route.version);
+            new_route.deprecation = route.deprecation;
             let new_index = self.routes.len();
             self.routes.push(new_route);
 
@@ -283,6 +442,11 @@ impl Router {
                 self.handlers.insert(new_index, *handler);
             }
         }
+        for (version, deprecation) in other.version_deprecations {
+            self.version_deprecations
+                .entry(version)
+                .or_insert(deprecation);
+        }
     }
 }
 
@@ -337,6 +501,156 @@ mod tests {
         assert_eq!(route.handler_name, "list_users");
     }
 
+    #[test]
+    fn test_versioned_routes() {
+        use vaya_common::Timestamp;
+
+        fn v2_handler(_req: &Request) -> ApiResult<Response> {
+            Ok(Response::ok().with_body(b"v2".to_vec()))
+        }
+
+        let mut router = Router::with_prefix("/api/v1");
+        router.get("/bookings", test_handler, "list_bookings");
+        router.versioned(
+            Method::GET,
+            &[ApiVersion::V1, ApiVersion::V2],
+            "/flights/:id",
+            test_handler,
+            "get_flight",
+        );
+        router.versioned(
+            Method::GET,
+            &[ApiVersion::V2],
+            "/bookings",
+            v2_handler,
+            "list_bookings_v2",
+        );
+        assert_eq!(router.versions(), vec![ApiVersion::V1, ApiVersion::V2]);
+
+        let (route, params, _) = router.find(Method::GET, "/api/v2/flights/42").unwrap();
+        assert_eq!(route.version, Some(ApiVersion::V2));
+        assert_eq!(params.get("id"), Some(&"42".to_string()));
+
+        // Same path, different handler per version
+        let v2 = router
+            .route(&Request::new("GET", "/api/v2/bookings"))
+            .unwrap();
+        assert_eq!(v2.body, b"v2");
+        assert_eq!(v2.headers.get("api-version"), Some(&"v2".to_string()));
+
+        // Whole-version deprecation, overridden per route
+        router.deprecate_version(ApiVersion::V1, Deprecation::new(Timestamp::from_unix(100)));
+        assert!(router.deprecate(
+            ApiVersion::V1,
+            Method::GET,
+            "/flights/:id",
+            Deprecation::new(Timestamp::from_unix(200)).with_sunset(Timestamp::from_unix(300)),
+        ));
+        assert!(!router.deprecate(
+            ApiVersion::V2,
+            Method::DELETE,
+            "/flights/:id",
+            Deprecation::new(Timestamp::from_unix(200)),
+        ));
+        let v1 = router
+            .route(&Request::new("GET", "/api/v1/bookings"))
+            .unwrap();
+        assert_eq!(v1.headers.get("deprecation"), Some(&"@100".to_string()));
+        let v1 = router
+            .route(&Request::new("GET", "/api/v1/flights/42"))
+            .unwrap();
+        assert_eq!(v1.headers.get("deprecation"), Some(&"@200".to_string()));
+        assert!(v1.headers.contains_key("sunset"));
+        let v2 = router
+            .route(&Request::new("GET", "/api/v2/flights/42"))
+            .unwrap();
+        assert!(!v2.headers.contains_key("deprecation"));
+
+        let err = router
+            .route(&Request::new("GET", "/api/v9/bookings"))
+            .unwrap_err();
+        assert!(err.to_string().contains("supported: v1, v2"));
+    }
+
+    #[test]
+    fn test_wildcards_and_decoding() {
+        let route = Route::new(Method::GET, "/files/:bucket/*key", "get_file");
+        let params = route
+            .match_path("/files/docs/2026/q1/report%20final.pdf")
+            .unwrap();
+        assert_eq!(params.get("bucket"), Some(&"docs".to_string()));
+        assert_eq!(
+            params.get("key"),
+            Some(&"2026/q1/report final.pdf".to_string())
+        );
+        assert_eq!(
+            route.match_path("/files/docs").unwrap().get("key"),
+            Some(&String::new())
+        );
+        assert!(route.match_path("/files").is_none());
+
+        let route = Route::new(Method::GET, "/static/*", "static");
+        assert!(route.match_path("/static/css/app.css").unwrap().is_empty());
+
+        let route = Route::new(Method::GET, "/travelers/:name", "get_traveler");
+        let params = route.match_path("/travelers/Nur%20Aisyah?full=1").unwrap();
+        assert_eq!(params.get("name"), Some(&"Nur Aisyah".to_string()));
+        assert_eq!(
+            route
+                .match_path("/travelers/caf%C3%A9")
+                .unwrap()
+                .get("name"),
+            Some(&"café".to_string())
+        );
+        assert!(route.match_path("/travelers/%FF").is_none());
+    }
+
+    #[test]
+    fn test_decoded_params_stay_escaped_in_responses() {
+        let mut router = Router::new();
+        router.post(
+            "/trips/:id/bookings",
+            crate::handlers::trip::add_booking_to_trip_handler,
+            "add_booking_to_trip",
+        );
+        let mut req = Request::new("POST", "/trips/trip_1%22,%22admin%22:true/bookings");
+        req.user_id = Some("user_123".into());
+        req.body = br#"{"booking_id":"BK-123456"}"#.to_vec();
+        let resp = router.route(&req).unwrap();
+        let body = crate::JsonValue::parse(&resp.body_string().unwrap()).unwrap();
+        assert_eq!(
+            body.get("trip_id").and_then(crate::JsonValue::as_str),
+            Some(r#"trip_1","admin":true"#)
+        );
+        assert!(body.get("admin").is_none());
+    }
+
+    #[test]
+    fn test_most_specific_route_wins() {
+        let mut router = Router::new();
+        router.get("/assets/*path", test_handler, "assets");
+        router.get("/users/:id", test_handler, "get_user");
+        router.get("/users/me", test_handler, "get_me");
+        router.get("/users/:id/*rest", test_handler, "user_fallback");
+        router.get("/users/:id/bookings", test_handler, "user_bookings");
+        router.get("/assets/logo.svg", test_handler, "logo");
+
+        let name = |path: &str| {
+            router
+                .find(Method::GET, path)
+                .unwrap()
+                .0
+                .handler_name
+                .clone()
+        };
+        assert_eq!(name("/users/me"), "get_me");
+        assert_eq!(name("/users/42"), "get_user");
+        assert_eq!(name("/users/42/bookings"), "user_bookings");
+        assert_eq!(name("/users/42/trips/7"), "user_fallback");
+        assert_eq!(name("/assets/logo.svg"), "logo");
+        assert_eq!(name("/assets/img/logo.png"), "assets");
+    }
+
     #[test]
     fn test_nested_params() {
         let route = Route::new(
@@ -348,5 +662,18 @@ mod tests {
         let params = route.match_path("/api/users/123/bookings/456").unwrap();
         assert_eq!(params.get("user_id"), Some(&"123".to_string()));
         assert_eq!(params.get("booking_id"), Some(&"456".to_string()));
+
+        let mut router = Router::with_prefix("/api/v1");
+        router.delete(
+            "/pools/:pool_id/members/:user_id",
+            test_handler,
+            "remove_pool_member",
+        );
+        let (_, params, _) = router
+            .find(Method::DELETE, "/api/v1/pools/p-9/members/u-3")
+            .unwrap();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params.get("pool_id"), Some(&"p-9".to_string()));
+        assert_eq!(params.get("user_id"), Some(&"u-3".to_string()));
     }
 }
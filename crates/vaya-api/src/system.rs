@@ -0,0 +1,132 @@
+//! Handles to the running system for the admin API
+//!
+//! Handlers are plain functions, so the parts of the application the
+//! `/admin/system` endpoints inspect and control are collected in a
+//! [`SystemState`] that [`crate::ApiServer`] attaches to every request.
+//! Components a deployment doesn't run (e.g. the fleet on a single node)
+//! are left unset and their endpoints answer 503.
+
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use vaya_auth::RbacManager;
+use vaya_cache::Cache;
+use vaya_db::VayaDb;
+use vaya_fleet::{Cluster, SharedRateLimits};
+
+use crate::{ApiError, ApiResult};
+
+/// What the admin system endpoints read and control
+pub struct SystemState {
+    /// Roles and permissions, shared with the rest of the application
+    rbac: Arc<RwLock<RbacManager>>,
+    /// Database
+    db: Option<Arc<VayaDb>>,
+    /// Application cache
+    cache: Option<Arc<Cache<String, Vec<u8>>>>,
+    /// Fleet-wide rate limiter state
+    rate_limits: Option<Arc<SharedRateLimits>>,
+    /// Cluster this node belongs to
+    cluster: Option<Arc<Cluster>>,
+    /// When the application started
+    started_at: Instant,
+}
+
+impl SystemState {
+    /// Create with the application's RBAC manager and nothing else attached
+    pub fn new(rbac: Arc<RwLock<RbacManager>>) -> Self {
+        Self {
+            rbac,
+            db: None,
+            cache: None,
+            rate_limits: None,
+            cluster: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Attach the database
+    pub fn with_db(mut self, db: Arc<VayaDb>) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    /// Attach the application cache
+    pub fn with_cache(mut self, cache: Arc<Cache<String, Vec<u8>>>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Attach the fleet-wide rate limiter state
+    pub fn with_rate_limits(mut self, rate_limits: Arc<SharedRateLimits>) -> Self {
+        self.rate_limits = Some(rate_limits);
+        self
+    }
+
+    /// Attach the cluster
+    pub fn with_cluster(mut self, cluster: Arc<Cluster>) -> Self {
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// Set when the application started (defaults to creation time)
+    pub fn with_started_at(mut self, started_at: Instant) -> Self {
+        self.started_at = started_at;
+        self
+    }
+
+    /// Roles and permissions
+    pub fn rbac(&self) -> &Arc<RwLock<RbacManager>> {
+        &self.rbac
+    }
+
+    /// Whether `roles`, or the roles assigned to `user_id`, grant `permission`
+    pub fn is_granted(&self, user_id: &str, roles: &[String], permission: &str) -> bool {
+        let rbac = self.rbac.read().unwrap_or_else(|e| e.into_inner());
+        roles
+            .iter()
+            .filter_map(|role| rbac.get_role(role))
+            .any(|role| role.has_permission(permission))
+            || rbac.has_permission(user_id, permission)
+    }
+
+    /// The database
+    pub fn db(&self) -> ApiResult<&VayaDb> {
+        self.db.as_deref().ok_or_else(|| not_attached("Database"))
+    }
+
+    /// The application cache
+    pub fn cache(&self) -> ApiResult<&Cache<String, Vec<u8>>> {
+        self.cache.as_deref().ok_or_else(|| not_attached("Cache"))
+    }
+
+    /// The fleet-wide rate limiter state, if limits are shared
+    pub fn rate_limits(&self) -> Option<&SharedRateLimits> {
+        self.rate_limits.as_deref()
+    }
+
+    /// The cluster
+    pub fn cluster(&self) -> ApiResult<&Cluster> {
+        self.cluster.as_deref().ok_or_else(|| not_attached("Fleet"))
+    }
+
+    /// Seconds since the application started
+    pub fn uptime_seconds(&self) -> u64 {
+        self.started_at.elapsed().as_secs()
+    }
+}
+
+impl std::fmt::Debug for SystemState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SystemState")
+            .field("db", &self.db.is_some())
+            .field("cache", &self.cache.is_some())
+            .field("rate_limits", &self.rate_limits.is_some())
+            .field("cluster", &self.cluster.is_some())
+            .finish()
+    }
+}
+
+fn not_attached(component: &str) -> ApiError {
+    ApiError::ServiceUnavailable(format!("{} is not enabled on this node", component))
+}
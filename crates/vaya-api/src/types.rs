@@ -1,6 +1,10 @@
 //! API request and response types
 
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::json::{FromJson, JsonObject, JsonSerialize, JsonValue};
+use crate::{ApiError, ApiResult, SystemState};
 
 /// HTTP Request
 #[derive(Debug, Clone)]
@@ -25,6 +29,8 @@ pub struct Request {
     pub user_id: Option<String>,
     /// User roles (set by auth middleware)
     pub user_roles: Vec<String>,
+    /// Running system, for the admin system endpoints (set by the server)
+    pub system: Option<Arc<SystemState>>,
 }
 
 impl Request {
@@ -41,6 +47,7 @@ impl Request {
             request_id: generate_request_id(),
             user_id: None,
             user_roles: Vec::new(),
+            system: None,
         }
     }
 
@@ -64,6 +71,20 @@ impl Request {
         String::from_utf8(self.body.clone()).ok()
     }
 
+    /// Parse the body as JSON into `T`
+    ///
+    /// Malformed JSON is a bad request; missing or mistyped fields are
+    /// validation errors naming the field.
+    pub fn json_body<T: FromJson>(&self) -> ApiResult<T> {
+        let body = std::str::from_utf8(&self.body)
+            .map_err(|_| ApiError::bad_request("Request body is not valid UTF-8"))?;
+        if body.trim().is_empty() {
+            return Err(ApiError::bad_request("Missing request body"));
+        }
+        let value = JsonValue::parse(body)?;
+        Ok(T::from_json(&value)?)
+    }
+
     /// Check if request is authenticated
     pub fn is_authenticated(&self) -> bool {
         self.user_id.is_some()
@@ -135,6 +156,11 @@ impl Response {
         Self::new(201, "Created")
     }
 
+    /// Create 202 Accepted response
+    pub fn accepted() -> Self {
+        Self::new(202, "Accepted")
+    }
+
     /// Create 204 No Content response
     pub fn no_content() -> Self {
         Self::new(204, "No Content")
@@ -212,7 +238,7 @@ impl Response {
         self
     }
 
-    /// Set JSON body (manual serialization for zero-dep)
+    /// Set JSON body
     pub fn set_json_body<T: JsonSerialize>(&mut self, body: &T) {
         self.body = body.to_json().into_bytes();
         self.headers
@@ -239,19 +265,13 @@ pub struct ErrorBody {
     pub code: u16,
 }
 
-/// Trait for JSON serialization (manual, zero-dep)
-pub trait JsonSerialize {
-    fn to_json(&self) -> String;
-}
-
 impl JsonSerialize for ErrorBody {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"error":"{}","message":"{}","code":{}}}"#,
-            escape_json(&self.error),
-            escape_json(&self.message),
-            self.code
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("error", &self.error)
+            .field("message", &self.message)
+            .field("code", self.code)
+            .build()
     }
 }
 
@@ -262,8 +282,8 @@ pub struct SuccessBody<T> {
 }
 
 impl<T: JsonSerialize> JsonSerialize for SuccessBody<T> {
-    fn to_json(&self) -> String {
-        format!(r#"{{"data":{}}}"#, self.data.to_json())
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new().nested("data", &self.data).build()
     }
 }
 
@@ -278,28 +298,17 @@ pub struct PaginatedBody<T> {
 }
 
 impl<T: JsonSerialize> JsonSerialize for PaginatedBody<T> {
-    fn to_json(&self) -> String {
-        let items: Vec<String> = self.data.iter().map(|d| d.to_json()).collect();
-        format!(
-            r#"{{"data":[{}],"total":{},"page":{},"page_size":{},"has_more":{}}}"#,
-            items.join(","),
-            self.total,
-            self.page,
-            self.page_size,
-            self.has_more
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .array("data", &self.data)
+            .field("total", self.total)
+            .field("page", self.page)
+            .field("page_size", self.page_size)
+            .field("has_more", self.has_more)
+            .build()
     }
 }
 
-/// Escape JSON string
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
 /// Generate unique request ID
 fn generate_request_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
@@ -350,6 +359,7 @@ fn url_decode(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::json::{escape_json, JsonError};
 
     #[test]
     fn test_request_creation() {
@@ -398,6 +408,40 @@ mod tests {
         assert_eq!(escape_json("Line1\nLine2"), "Line1\\nLine2");
     }
 
+    #[test]
+    fn test_json_body() {
+        struct Login {
+            email: String,
+            remember: Option<bool>,
+        }
+
+        impl FromJson for Login {
+            fn from_json(value: &JsonValue) -> Result<Self, JsonError> {
+                Ok(Self {
+                    email: value.field("email")?,
+                    remember: value.field("remember")?,
+                })
+            }
+        }
+
+        let mut req = Request::new("POST", "/auth/login");
+        req.body = br#"{"email":"a\"b@example.com"}"#.to_vec();
+        let login: Login = req.json_body().unwrap();
+        assert_eq!(login.email, r#"a"b@example.com"#);
+        assert_eq!(login.remember, None);
+
+        req.body = br#"{"email":42}"#.to_vec();
+        assert!(matches!(
+            req.json_body::<Login>(),
+            Err(ApiError::ValidationError(_))
+        ));
+        req.body = b"{email}".to_vec();
+        assert!(matches!(
+            req.json_body::<Login>(),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
     #[test]
     fn test_error_body_json() {
         let body = ErrorBody {
@@ -0,0 +1,152 @@
+//! Calls to internal services found through fleet discovery
+//!
+//! An [`Upstream`] names an internal service, such as the oracle trainer,
+//! and runs each call against an endpoint the
+//! [`vaya_fleet::DiscoveryClient`] picks, favouring healthy ones. Every
+//! outcome is reported back, so endpoints that keep failing drop out of
+//! rotation, and a failed call is retried on the next endpoint up to the
+//! configured number of attempts.
+
+use std::fmt;
+use std::sync::Arc;
+
+use vaya_fleet::{DiscoveryClient, ServiceEndpoint};
+
+use crate::{ApiError, ApiResult};
+
+/// Service name the oracle trainer registers under
+pub const ORACLE_TRAINER_SERVICE: &str = "oracle-trainer";
+
+/// Attempts per call by default
+const DEFAULT_ATTEMPTS: u32 = 2;
+
+/// An internal service, reached through discovery
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    discovery: Arc<DiscoveryClient>,
+    service: String,
+    attempts: u32,
+}
+
+impl Upstream {
+    /// Upstream for `service`
+    pub fn new(discovery: Arc<DiscoveryClient>, service: impl Into<String>) -> Self {
+        Self {
+            discovery,
+            service: service.into(),
+            attempts: DEFAULT_ATTEMPTS,
+        }
+    }
+
+    /// Upstream for the oracle trainer
+    pub fn oracle_trainer(discovery: Arc<DiscoveryClient>) -> Self {
+        Self::new(discovery, ORACLE_TRAINER_SERVICE)
+    }
+
+    /// Set how many endpoints a call tries before failing
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Service name
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Check if any endpoint is usable
+    pub fn is_available(&self) -> bool {
+        self.discovery.resolve(&self.service).is_ok()
+    }
+
+    /// Every usable endpoint
+    pub fn endpoints(&self) -> Vec<ServiceEndpoint> {
+        self.discovery
+            .resolve_all(&self.service)
+            .unwrap_or_default()
+    }
+
+    /// Run `call` against an endpoint, retrying on failure
+    ///
+    /// Fails with [`ApiError::ServiceUnavailable`] when no endpoint is
+    /// usable or every attempt failed.
+    pub fn call<T, E, F>(&self, mut call: F) -> ApiResult<T>
+    where
+        E: fmt::Display,
+        F: FnMut(&ServiceEndpoint) -> Result<T, E>,
+    {
+        let mut last_error = None;
+        for _ in 0..self.attempts {
+            let endpoint = match self.discovery.resolve(&self.service) {
+                Ok(endpoint) => endpoint,
+                Err(e) => {
+                    last_error.get_or_insert_with(|| e.to_string());
+                    break;
+                }
+            };
+            match call(&endpoint) {
+                Ok(value) => {
+                    self.discovery
+                        .report(&self.service, &endpoint.node_id, true);
+                    return Ok(value);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        service = %self.service,
+                        endpoint = %endpoint.full_address(),
+                        error = %e,
+                        "Upstream call failed"
+                    );
+                    self.discovery
+                        .report(&self.service, &endpoint.node_id, false);
+                    last_error = Some(format!("{}: {}", endpoint.full_address(), e));
+                }
+            }
+        }
+        Err(ApiError::ServiceUnavailable(format!(
+            "{}: {}",
+            self.service,
+            last_error.unwrap_or_default()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_fleet::{DiscoveryConfig, NodeId, ServiceHealth};
+
+    #[test]
+    fn test_call_reports_outcomes() {
+        let discovery = Arc::new(DiscoveryClient::new(
+            NodeId::new("node-1"),
+            DiscoveryConfig::default(),
+        ));
+        let trainer = Upstream::oracle_trainer(Arc::clone(&discovery)).with_attempts(3);
+        assert!(!trainer.is_available());
+        assert!(matches!(
+            trainer.call(|_| Ok::<_, String>(())),
+            Err(ApiError::ServiceUnavailable(_))
+        ));
+
+        discovery
+            .register_local(ORACLE_TRAINER_SERVICE, 9100)
+            .unwrap();
+        let port = trainer.call(|e| Ok::<_, String>(e.port)).unwrap();
+        assert_eq!(port, 9100);
+        assert_eq!(trainer.endpoints()[0].health, ServiceHealth::Healthy);
+
+        // Three failures in a row take the only endpoint out of rotation
+        let mut calls = 0;
+        let err = trainer
+            .call(|_| {
+                calls += 1;
+                Err::<(), _>("connection refused")
+            })
+            .unwrap_err();
+        assert_eq!(calls, 3);
+        assert!(matches!(err, ApiError::ServiceUnavailable(_)));
+        assert!(!trainer.is_available());
+        assert!(trainer.endpoints().is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! API versioning and deprecation
+//!
+//! The version is the path segment after the API base ("/api/v2/bookings").
+//! A handler can be registered under several versions at once, so an
+//! endpoint that did not change between versions is written once. Routes,
+//! or whole versions, can be marked deprecated: responses then carry
+//! `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and `Link` headers pointing
+//! at the migration guide, and every request is counted per version so we
+//! can see when an old version has no traffic left.
+
+use std::fmt;
+
+use vaya_common::{metrics, Timestamp};
+
+use crate::Response;
+
+/// Metric counting requests per API version and route
+pub const VERSION_REQUESTS_METRIC: &str = "vaya_api_version_requests_total";
+
+/// Metric counting requests to deprecated routes
+pub const DEPRECATED_REQUESTS_METRIC: &str = "vaya_api_deprecated_requests_total";
+
+/// An API major version ("v1", "v2")
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ApiVersion(pub u16);
+
+impl ApiVersion {
+    /// First public version
+    pub const V1: Self = Self(1);
+    /// Second version
+    pub const V2: Self = Self(2);
+
+    /// Parse a path segment ("v2")
+    pub fn parse(segment: &str) -> Option<Self> {
+        let digits = segment.strip_prefix('v')?;
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok().filter(|v| *v > 0).map(Self)
+    }
+
+    /// First version segment in a path or pattern
+    pub fn from_path(path: &str) -> Option<Self> {
+        path.split('/').find_map(Self::parse)
+    }
+
+    /// Split a prefix like "/api/v1" into its base ("/api") and version
+    pub fn split_prefix(prefix: &str) -> (&str, Option<Self>) {
+        let trimmed = prefix.trim_end_matches('/');
+        match trimmed.rsplit_once('/') {
+            Some((base, last)) => match Self::parse(last) {
+                Some(version) => (base, Some(version)),
+                None => (trimmed, None),
+            },
+            None => (trimmed, None),
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+/// Deprecation notice for a route or a whole version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// When the route was (or will be) deprecated
+    pub deprecated_at: Timestamp,
+    /// When the route stops working
+    pub sunset: Option<Timestamp>,
+    /// Migration guide or successor documentation
+    pub link: Option<String>,
+}
+
+impl Deprecation {
+    /// Deprecated as of a date
+    pub fn new(deprecated_at: Timestamp) -> Self {
+        Self {
+            deprecated_at,
+            sunset: None,
+            link: None,
+        }
+    }
+
+    /// Set the sunset date
+    pub fn with_sunset(mut self, sunset: Timestamp) -> Self {
+        self.sunset = Some(sunset);
+        self
+    }
+
+    /// Set the migration guide link
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    /// Add the deprecation headers to a response
+    pub fn apply(&self, response: &mut Response) {
+        response.headers.insert(
+            "deprecation".into(),
+            format!("@{}", self.deprecated_at.as_unix()),
+        );
+        if let Some(sunset) = self.sunset {
+            response.headers.insert("sunset".into(), http_date(sunset));
+        }
+        if let Some(ref link) = self.link {
+            response
+                .headers
+                .insert("link".into(), format!("<{}>; rel=\"deprecation\"", link));
+        }
+    }
+}
+
+/// Count a request against its version
+pub(crate) fn record_usage(version: ApiVersion, route: &str, deprecated: bool) {
+    let version = version.to_string();
+    let labels = [("version", version.as_str()), ("route", route)];
+    metrics::global()
+        .counter(VERSION_REQUESTS_METRIC, &labels)
+        .inc();
+    if deprecated {
+        metrics::global()
+            .counter(DEPRECATED_REQUESTS_METRIC, &labels)
+            .inc();
+    }
+}
+
+/// Format a timestamp as an HTTP-date ("Sun, 06 Nov 1994 08:49:37 GMT")
+fn http_date(ts: Timestamp) -> String {
+    let dt = time::OffsetDateTime::from_unix_timestamp(ts.as_unix())
+        .unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
+    let weekday = match dt.weekday() {
+        time::Weekday::Monday => "Mon",
+        time::Weekday::Tuesday => "Tue",
+        time::Weekday::Wednesday => "Wed",
+        time::Weekday::Thursday => "Thu",
+        time::Weekday::Friday => "Fri",
+        time::Weekday::Saturday => "Sat",
+        time::Weekday::Sunday => "Sun",
+    };
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        weekday,
+        dt.day(),
+        MONTHS[usize::from(u8::from(dt.month())) - 1],
+        dt.year(),
+        dt.hour(),
+        dt.minute(),
+        dt.second()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_parsing() {
+        assert_eq!(ApiVersion::parse("v2"), Some(ApiVersion::V2));
+        assert_eq!(ApiVersion::parse("v0"), None);
+        assert_eq!(ApiVersion::parse("version"), None);
+        assert_eq!(
+            ApiVersion::from_path("/api/v1/bookings/v2"),
+            Some(ApiVersion::V1)
+        );
+        assert_eq!(
+            ApiVersion::split_prefix("/api/v1"),
+            ("/api", Some(ApiVersion::V1))
+        );
+        assert_eq!(ApiVersion::split_prefix("/api/"), ("/api", None));
+        assert_eq!(ApiVersion::V2.to_string(), "v2");
+    }
+
+    #[test]
+    fn test_deprecation_headers() {
+        let deprecation = Deprecation::new(Timestamp::from_unix(1_688_169_599))
+            .with_sunset(Timestamp::from_unix(784_111_777))
+            .with_link("https://docs.vaya.my/api/v2-migration");
+        let mut response = Response::ok();
+        deprecation.apply(&mut response);
+
+        assert_eq!(
+            response.headers.get("deprecation").map(String::as_str),
+            Some("@1688169599")
+        );
+        assert_eq!(
+            response.headers.get("sunset").map(String::as_str),
+            Some("Sun, 06 Nov 1994 08:49:37 GMT")
+        );
+        assert!(response.headers["link"].contains("rel=\"deprecation\""));
+    }
+}
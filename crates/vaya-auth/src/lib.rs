@@ -3,8 +3,9 @@
 //! This crate provides:
 //! - Password hashing with PBKDF2-SHA256
 //! - JWT token generation and validation
-//! - Session management with in-memory store
-//! - Role-based access control (RBAC)
+//! - Session management with in-memory or VayaDb-backed storage
+//! - Role-based access control (RBAC) with resource ownership checks
+//! - TOTP two-factor authentication with backup codes
 //!
 //! # Example
 //!
@@ -32,10 +33,16 @@ pub mod error;
 pub mod password;
 pub mod permission;
 pub mod session;
+pub mod session_db;
 pub mod token;
+pub mod totp;
 
 pub use error::{AuthError, AuthResult};
 pub use password::PasswordHasher;
-pub use permission::{Permission, PermissionGuard, RbacManager, Role, RoleName};
-pub use session::{Session, SessionConfig, SessionStore};
+pub use permission::{
+    OwnerResolver, Permission, PermissionGuard, RbacManager, Resource, Role, RoleName,
+};
+pub use session::{MemorySessionBackend, Session, SessionBackend, SessionConfig, SessionStore};
+pub use session_db::DbSessionBackend;
 pub use token::{Claims, JwtTokenizer};
+pub use totp::{MfaMethod, Totp, TotpSecret, TwoFactor};
@@ -1,6 +1,17 @@
 //! Role-based access control (RBAC)
+//!
+//! Permissions are `scope:action` strings granted to roles, with `scope:*`
+//! and `*` wildcards. Permissions can also be scoped to one resource as
+//! `kind:id:action` (e.g. `booking:BK12AB:cancel`), with `kind:*:action`
+//! covering every resource of a kind.
+//!
+//! `kind:own:action` grants an action on resources the user owns. Owners
+//! are looked up through a resolver registered per resource kind, so a
+//! handler checks "user owns this booking or may cancel any booking" with
+//! a single [`RbacManager::authorize`] call.
 
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 
 use crate::{AuthError, AuthResult};
 
@@ -70,17 +81,70 @@ impl Role {
             }
         }
 
+        // Resource wildcards (e.g., "booking:*:cancel" and "booking:BK1:*"
+        // match "booking:BK1:cancel")
+        if parts.len() == 3 {
+            let any_resource = format!("{}:*:{}", parts[0], parts[2]);
+            let any_action = format!("{}:{}:*", parts[0], parts[1]);
+            if self.permissions.contains(&any_resource) || self.permissions.contains(&any_action) {
+                return true;
+            }
+        }
+
         // Global wildcard
         self.permissions.contains("*")
     }
 }
 
+/// Scope in `kind:own:action` permissions
+const OWN_SCOPE: &str = "own";
+
+/// A resource permissions can be scoped to (e.g. `booking:BK12AB`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Resource {
+    /// Resource kind (e.g. "booking")
+    pub kind: String,
+    /// Resource ID
+    pub id: String,
+}
+
+impl Resource {
+    /// Create a resource reference
+    pub fn new(kind: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Permission for an action on this resource (`kind:id:action`)
+    pub fn permission(&self, action: &str) -> Permission {
+        format!("{}:{}:{}", self.kind, self.id, action)
+    }
+
+    /// IDs that would collide with wildcard or ownership scopes
+    fn has_reserved_id(&self) -> bool {
+        self.id.is_empty() || self.id == "*" || self.id == OWN_SCOPE || self.id.contains(':')
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.kind, self.id)
+    }
+}
+
+/// Looks up the owner (user ID) of a resource by its ID
+pub type OwnerResolver = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
 /// RBAC manager
 pub struct RbacManager {
     /// All defined roles
     roles: HashMap<RoleName, Role>,
     /// User to roles mapping
     user_roles: HashMap<String, HashSet<RoleName>>,
+    /// Owner lookups by resource kind
+    owners: HashMap<String, OwnerResolver>,
 }
 
 impl RbacManager {
@@ -89,6 +153,7 @@ impl RbacManager {
         Self {
             roles: HashMap::new(),
             user_roles: HashMap::new(),
+            owners: HashMap::new(),
         }
     }
 
@@ -114,6 +179,9 @@ impl RbacManager {
                     "alerts:read",
                     "alerts:write",
                     "search:read",
+                    "booking:own:*",
+                    "alert:own:*",
+                    "trip:own:*",
                 ])
                 .with_description("Standard user access"),
         );
@@ -129,6 +197,10 @@ impl RbacManager {
                     "search:*",
                     "pools:*",
                     "analytics:read",
+                    "booking:own:*",
+                    "alert:own:*",
+                    "trip:own:*",
+                    "pool:own:*",
                 ])
                 .with_description("Premium user access"),
         );
@@ -141,6 +213,7 @@ impl RbacManager {
                     "bookings:read",
                     "reports:read",
                     "reports:write",
+                    "booking:*:read",
                 ])
                 .with_description("Content moderator access"),
         );
@@ -221,6 +294,44 @@ impl RbacManager {
         }
     }
 
+    /// Register how to find the owner of resources of a kind
+    pub fn set_owner_resolver(
+        &mut self,
+        kind: impl Into<String>,
+        resolver: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.owners.insert(kind.into(), Box::new(resolver));
+    }
+
+    /// Check if a user owns a resource (false if its kind has no resolver)
+    pub fn owns(&self, user_id: &str, resource: &Resource) -> bool {
+        self.owners
+            .get(&resource.kind)
+            .and_then(|resolve| resolve(&resource.id))
+            .is_some_and(|owner| owner == user_id)
+    }
+
+    /// Require permission for an action on a resource
+    ///
+    /// Allowed if the user holds `kind:id:action` (or a wildcard covering
+    /// it), or holds `kind:own:action` and owns the resource.
+    pub fn authorize(&self, user_id: &str, action: &str, resource: &Resource) -> AuthResult<()> {
+        let permission = resource.permission(action);
+        if resource.has_reserved_id() {
+            return Err(AuthError::MissingPermission(permission));
+        }
+        if self.has_permission(user_id, &permission) {
+            return Ok(());
+        }
+
+        // Only look up the owner when ownership would grant access
+        let own = format!("{}:{}:{}", resource.kind, OWN_SCOPE, action);
+        if self.has_permission(user_id, &own) && self.owns(user_id, resource) {
+            return Ok(());
+        }
+        Err(AuthError::MissingPermission(permission))
+    }
+
     /// Get all permissions for a user (flattened from all roles)
     pub fn get_user_permissions(&self, user_id: &str) -> HashSet<&Permission> {
         self.get_user_roles(user_id)
@@ -270,6 +381,11 @@ impl<'a> PermissionGuard<'a> {
     pub fn require(&self, permission: &str) -> AuthResult<()> {
         self.manager.require_permission(&self.user_id, permission)
     }
+
+    /// Require permission for an action on a resource
+    pub fn authorize(&self, action: &str, resource: &Resource) -> AuthResult<()> {
+        self.manager.authorize(&self.user_id, action, resource)
+    }
 }
 
 #[cfg(test)]
@@ -356,4 +472,52 @@ mod tests {
         assert!(guard.can_any(&["admin:delete", "profile:read"]));
         assert!(!guard.can_all(&["profile:read", "admin:delete"]));
     }
+
+    #[test]
+    fn test_resource_scoped_permissions() {
+        let role = Role::new("agent")
+            .with_permission("booking:BK1:refund")
+            .with_permission("booking:*:read")
+            .with_permission("pool:P1:*");
+
+        assert!(role.has_permission("booking:BK1:refund"));
+        assert!(!role.has_permission("booking:BK2:refund"));
+        assert!(role.has_permission("booking:BK2:read"));
+        assert!(role.has_permission("pool:P1:close"));
+        assert!(!role.has_permission("pool:P2:close"));
+    }
+
+    #[test]
+    fn test_authorize_with_ownership() {
+        let mut manager = RbacManager::with_default_roles();
+        manager.add_role(Role::new("support").with_permission("booking:*:cancel"));
+        manager.assign_role("user-1", "user").unwrap();
+        manager.assign_role("user-2", "user").unwrap();
+        manager.assign_role("agent-1", "support").unwrap();
+        manager.assign_role("admin-1", "admin").unwrap();
+        manager.set_owner_resolver("booking", |id| (id == "BK1").then(|| "user-1".to_string()));
+
+        let booking = Resource::new("booking", "BK1");
+        assert_eq!(booking.to_string(), "booking:BK1");
+
+        // Owner, anyone with a covering permission, and admins
+        assert!(manager.authorize("user-1", "cancel", &booking).is_ok());
+        assert!(manager.authorize("agent-1", "cancel", &booking).is_ok());
+        assert!(manager.authorize("admin-1", "cancel", &booking).is_ok());
+        assert!(matches!(
+            manager.authorize("user-2", "cancel", &booking),
+            Err(AuthError::MissingPermission(p)) if p == "booking:BK1:cancel"
+        ));
+
+        // No resolver for the kind means nobody owns it
+        let alert = Resource::new("alert", "AL1");
+        assert!(manager.authorize("user-1", "delete", &alert).is_err());
+
+        // Reserved IDs can't be used to reach wildcard or own scopes
+        let guard = PermissionGuard::new(&manager, "agent-1");
+        assert!(guard.authorize("cancel", &booking).is_ok());
+        assert!(guard
+            .authorize("cancel", &Resource::new("booking", "own"))
+            .is_err());
+    }
 }
@@ -1,10 +1,15 @@
 //! Session management
+//!
+//! [`SessionStore`] keeps sessions in a [`SessionBackend`]: in memory by
+//! default, or in VayaDb (see [`crate::session_db`]) so that sessions
+//! survive a server restart.
 
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use ring::rand::{SecureRandom, SystemRandom};
 use time::{Duration, OffsetDateTime};
+use tracing::warn;
 
 use crate::{AuthError, AuthResult};
 
@@ -72,89 +77,163 @@ impl Default for SessionConfig {
     }
 }
 
-/// In-memory session store
-pub struct SessionStore {
+/// Storage for sessions
+///
+/// Backends keep, per user, the IDs of that user's sessions in creation
+/// order; the [`SessionStore`] uses them to enforce `max_per_user`.
+pub trait SessionBackend: Send + Sync {
+    /// Insert or replace a session
+    fn save(&self, session: &Session) -> AuthResult<()>;
+
+    /// Load a session by ID
+    fn load(&self, session_id: &str) -> AuthResult<Option<Session>>;
+
+    /// Delete a session, returning it if it existed
+    fn delete(&self, session_id: &str) -> AuthResult<Option<Session>>;
+
+    /// IDs of a user's sessions, oldest first
+    fn user_session_ids(&self, user_id: &str) -> AuthResult<Vec<String>>;
+
+    /// Delete sessions that expired before `now`, returning how many
+    fn purge_expired(&self, now: i64) -> AuthResult<usize>;
+}
+
+#[derive(Default)]
+struct MemoryState {
     /// Sessions by ID
-    sessions: Mutex<HashMap<String, Session>>,
+    sessions: HashMap<String, Session>,
     /// Session IDs by user
-    user_sessions: Mutex<HashMap<String, Vec<String>>>,
+    user_sessions: HashMap<String, Vec<String>>,
+}
+
+impl MemoryState {
+    fn remove(&mut self, session_id: &str) -> Option<Session> {
+        let session = self.sessions.remove(session_id)?;
+        if let Some(ids) = self.user_sessions.get_mut(&session.user_id) {
+            ids.retain(|id| id != session_id);
+            if ids.is_empty() {
+                self.user_sessions.remove(&session.user_id);
+            }
+        }
+        Some(session)
+    }
+}
+
+/// In-memory session backend (sessions are lost on restart)
+#[derive(Default)]
+pub struct MemorySessionBackend {
+    state: Mutex<MemoryState>,
+}
+
+impl MemorySessionBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionBackend for MemorySessionBackend {
+    fn save(&self, session: &Session) -> AuthResult<()> {
+        let mut state = self.state.lock().unwrap();
+        let previous = state.sessions.insert(session.id.clone(), session.clone());
+        if previous.is_none() {
+            state
+                .user_sessions
+                .entry(session.user_id.clone())
+                .or_default()
+                .push(session.id.clone());
+        }
+        Ok(())
+    }
+
+    fn load(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        Ok(self.state.lock().unwrap().sessions.get(session_id).cloned())
+    }
+
+    fn delete(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        Ok(self.state.lock().unwrap().remove(session_id))
+    }
+
+    fn user_session_ids(&self, user_id: &str) -> AuthResult<Vec<String>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .user_sessions
+            .get(user_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn purge_expired(&self, now: i64) -> AuthResult<usize> {
+        let mut state = self.state.lock().unwrap();
+        let expired: Vec<String> = state
+            .sessions
+            .values()
+            .filter(|s| s.expires_at < now)
+            .map(|s| s.id.clone())
+            .collect();
+        for id in &expired {
+            state.remove(id);
+        }
+        Ok(expired.len())
+    }
+}
+
+/// Session store over a pluggable [`SessionBackend`]
+pub struct SessionStore {
+    /// Session storage
+    backend: Arc<dyn SessionBackend>,
     /// Configuration
     config: SessionConfig,
 }
 
 impl SessionStore {
-    /// Create a new session store
+    /// Create a new in-memory session store
     pub fn new() -> Self {
         Self::with_config(SessionConfig::default())
     }
 
-    /// Create with custom config
+    /// Create an in-memory store with custom config
     pub fn with_config(config: SessionConfig) -> Self {
-        Self {
-            sessions: Mutex::new(HashMap::new()),
-            user_sessions: Mutex::new(HashMap::new()),
-            config,
-        }
+        Self::with_backend(config, Arc::new(MemorySessionBackend::new()))
+    }
+
+    /// Create a store over the given backend
+    pub fn with_backend(config: SessionConfig, backend: Arc<dyn SessionBackend>) -> Self {
+        Self { backend, config }
     }
 
     /// Create a new session
     pub fn create(&self, user_id: impl Into<String>) -> AuthResult<Session> {
+        self.create_with_meta(user_id, None, None)
+    }
+
+    /// Create session with metadata
+    pub fn create_with_meta(
+        &self,
+        user_id: impl Into<String>,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> AuthResult<Session> {
         let user_id = user_id.into();
         let now = OffsetDateTime::now_utc();
 
-        // Generate session ID
-        let session_id = self.generate_session_id()?;
-
         let session = Session {
-            id: session_id.clone(),
-            user_id: user_id.clone(),
+            id: self.generate_session_id()?,
+            user_id,
             created_at: now.unix_timestamp(),
             last_activity: now.unix_timestamp(),
             expires_at: (now + self.config.ttl).unix_timestamp(),
-            ip_address: None,
-            user_agent: None,
+            ip_address,
+            user_agent,
             data: HashMap::new(),
         };
+        self.backend.save(&session)?;
 
-        // Store session
-        {
-            let mut sessions = self.sessions.lock().unwrap();
-            sessions.insert(session_id.clone(), session.clone());
-        }
-
-        // Track user sessions
-        {
-            let mut user_sessions = self.user_sessions.lock().unwrap();
-            let sessions = user_sessions.entry(user_id.clone()).or_default();
-            sessions.push(session_id);
-
-            // Enforce max sessions per user
-            while sessions.len() > self.config.max_per_user {
-                if let Some(old_id) = sessions.first().cloned() {
-                    sessions.remove(0);
-                    self.sessions.lock().unwrap().remove(&old_id);
-                }
-            }
-        }
-
-        Ok(session)
-    }
-
-    /// Create session with metadata
-    pub fn create_with_meta(
-        &self,
-        user_id: impl Into<String>,
-        ip_address: Option<String>,
-        user_agent: Option<String>,
-    ) -> AuthResult<Session> {
-        let mut session = self.create(user_id)?;
-        session.ip_address = ip_address;
-        session.user_agent = user_agent;
-
-        // Update stored session
-        {
-            let mut sessions = self.sessions.lock().unwrap();
-            sessions.insert(session.id.clone(), session.clone());
+        // Enforce max sessions per user, evicting the oldest
+        let ids = self.backend.user_session_ids(&session.user_id)?;
+        let excess = ids.len().saturating_sub(self.config.max_per_user);
+        for old_id in ids.iter().take(excess) {
+            self.backend.delete(old_id)?;
         }
 
         Ok(session)
@@ -162,93 +241,78 @@ impl SessionStore {
 
     /// Get a session by ID
     pub fn get(&self, session_id: &str) -> AuthResult<Session> {
-        let sessions = self.sessions.lock().unwrap();
-        let session = sessions.get(session_id).ok_or(AuthError::SessionNotFound)?;
+        let session = self
+            .backend
+            .load(session_id)?
+            .ok_or(AuthError::SessionNotFound)?;
 
         if session.is_expired() {
-            drop(sessions);
             self.remove(session_id);
             return Err(AuthError::SessionExpired);
         }
 
-        Ok(session.clone())
+        Ok(session)
     }
 
     /// Validate and refresh a session
     pub fn validate(&self, session_id: &str) -> AuthResult<Session> {
-        let mut sessions = self.sessions.lock().unwrap();
-        let session = sessions
-            .get_mut(session_id)
-            .ok_or(AuthError::SessionNotFound)?;
+        let mut session = self.get(session_id)?;
+        session.touch();
+        self.backend.save(&session)?;
+        Ok(session)
+    }
 
-        if session.is_expired() {
-            let id = session.id.clone();
-            drop(sessions);
-            self.remove(&id);
-            return Err(AuthError::SessionExpired);
+    /// Store changes made to a session's custom data
+    pub fn update(&self, session: &Session) -> AuthResult<()> {
+        if self.backend.load(&session.id)?.is_none() {
+            return Err(AuthError::SessionNotFound);
         }
-
-        session.touch();
-        Ok(session.clone())
+        self.backend.save(session)
     }
 
     /// Remove a session
     pub fn remove(&self, session_id: &str) {
-        let mut sessions = self.sessions.lock().unwrap();
-        if let Some(session) = sessions.remove(session_id) {
-            let mut user_sessions = self.user_sessions.lock().unwrap();
-            if let Some(user_sess) = user_sessions.get_mut(&session.user_id) {
-                user_sess.retain(|id| id != session_id);
-            }
+        if let Err(e) = self.backend.delete(session_id) {
+            warn!(error = %e, "Failed to remove session");
         }
     }
 
     /// Remove all sessions for a user
     pub fn remove_user_sessions(&self, user_id: &str) {
-        let mut user_sessions = self.user_sessions.lock().unwrap();
-        if let Some(session_ids) = user_sessions.remove(user_id) {
-            let mut sessions = self.sessions.lock().unwrap();
-            for id in session_ids {
-                sessions.remove(&id);
+        match self.backend.user_session_ids(user_id) {
+            Ok(ids) => {
+                for id in ids {
+                    self.remove(&id);
+                }
             }
+            Err(e) => warn!(error = %e, user_id, "Failed to list user sessions"),
         }
     }
 
     /// Get all sessions for a user
     pub fn get_user_sessions(&self, user_id: &str) -> Vec<Session> {
-        let user_sessions = self.user_sessions.lock().unwrap();
-        let sessions = self.sessions.lock().unwrap();
-
-        user_sessions
-            .get(user_id)
-            .map(|ids| {
-                ids.iter()
-                    .filter_map(|id| sessions.get(id))
-                    .filter(|s| !s.is_expired())
-                    .cloned()
-                    .collect()
-            })
-            .unwrap_or_default()
-    }
-
-    /// Clean up expired sessions
-    pub fn cleanup(&self) {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
-
-        let mut sessions = self.sessions.lock().unwrap();
-        let mut user_sessions = self.user_sessions.lock().unwrap();
+        let ids = match self.backend.user_session_ids(user_id) {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!(error = %e, user_id, "Failed to list user sessions");
+                return Vec::new();
+            }
+        };
 
-        let expired: Vec<String> = sessions
-            .iter()
-            .filter(|(_, s)| s.expires_at < now)
-            .map(|(id, _)| id.clone())
-            .collect();
+        ids.iter()
+            .filter_map(|id| self.backend.load(id).ok().flatten())
+            .filter(|s| !s.is_expired())
+            .collect()
+    }
 
-        for id in expired {
-            if let Some(session) = sessions.remove(&id) {
-                if let Some(user_sess) = user_sessions.get_mut(&session.user_id) {
-                    user_sess.retain(|sid| sid != &id);
-                }
+    /// Clean up expired sessions, returning how many were removed
+    pub fn cleanup(&self) -> usize {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        match self.backend.purge_expired(now) {
+            Ok(removed) => removed,
+            Err(e) => {
+                warn!(error = %e, "Failed to purge expired sessions");
+                0
             }
         }
     }
@@ -335,4 +399,34 @@ mod tests {
         let sessions = store.get_user_sessions("user-123");
         assert_eq!(sessions.len(), 0);
     }
+
+    #[test]
+    fn test_cleanup_expired() {
+        let config = SessionConfig {
+            ttl: Duration::seconds(-10),
+            ..Default::default()
+        };
+        let store = SessionStore::with_config(config);
+        let session = store.create("user-123").unwrap();
+
+        assert_eq!(store.cleanup(), 1);
+        assert!(matches!(
+            store.get(&session.id),
+            Err(AuthError::SessionNotFound)
+        ));
+        assert!(store.get_user_sessions("user-123").is_empty());
+    }
+
+    #[test]
+    fn test_update_session_data() {
+        let store = SessionStore::new();
+        let mut session = store.create("user-123").unwrap();
+        session.set("locale", "ms-MY");
+        store.update(&session).unwrap();
+
+        assert_eq!(store.get(&session.id).unwrap().get("locale"), Some("ms-MY"));
+
+        store.remove(&session.id);
+        assert!(store.update(&session).is_err());
+    }
 }
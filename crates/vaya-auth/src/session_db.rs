@@ -0,0 +1,391 @@
+//! VayaDb-backed session storage
+//!
+//! Sessions are stored rkyv-serialized under `session:id:{id}`. Because
+//! VayaDb has no key iteration, two index kinds are kept alongside them:
+//!
+//! - `session:user:{user_id}`: the user's session IDs, oldest first
+//! - `session:expiry:{bucket}`: IDs of sessions expiring within each
+//!   hour-long bucket, walked by [`SessionBackend::purge_expired`] from the
+//!   oldest unpurged bucket (`session:expiry_cursor`) up to the current one
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use rkyv::{Archive, Deserialize, Serialize};
+use vaya_db::{DbError, VayaDb};
+
+use crate::session::{Session, SessionBackend};
+use crate::{AuthError, AuthResult};
+
+/// Width of an expiry index bucket (seconds)
+pub const EXPIRY_BUCKET_SECS: i64 = 3600;
+
+const SESSION_PREFIX: &str = "session:id:";
+const USER_PREFIX: &str = "session:user:";
+const EXPIRY_PREFIX: &str = "session:expiry:";
+const EXPIRY_CURSOR_KEY: &[u8] = b"session:expiry_cursor";
+
+/// Serialized form of a [`Session`]
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredSession {
+    id: String,
+    user_id: String,
+    created_at: i64,
+    last_activity: i64,
+    expires_at: i64,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    data: Vec<StoredEntry>,
+}
+
+/// One custom data entry of a stored session
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredEntry {
+    key: String,
+    value: String,
+}
+
+impl From<&Session> for StoredSession {
+    fn from(session: &Session) -> Self {
+        Self {
+            id: session.id.clone(),
+            user_id: session.user_id.clone(),
+            created_at: session.created_at,
+            last_activity: session.last_activity,
+            expires_at: session.expires_at,
+            ip_address: session.ip_address.clone(),
+            user_agent: session.user_agent.clone(),
+            data: session
+                .data
+                .iter()
+                .map(|(k, v)| StoredEntry {
+                    key: k.clone(),
+                    value: v.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<StoredSession> for Session {
+    fn from(stored: StoredSession) -> Self {
+        Self {
+            id: stored.id,
+            user_id: stored.user_id,
+            created_at: stored.created_at,
+            last_activity: stored.last_activity,
+            expires_at: stored.expires_at,
+            ip_address: stored.ip_address,
+            user_agent: stored.user_agent,
+            data: stored
+                .data
+                .into_iter()
+                .map(|e| (e.key, e.value))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+}
+
+/// Serialized session ID list
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredIds {
+    ids: Vec<String>,
+}
+
+/// Session backend persisting to VayaDb
+pub struct DbSessionBackend {
+    db: Arc<VayaDb>,
+    /// Serializes read-modify-write updates of the index keys
+    index_lock: Mutex<()>,
+}
+
+impl DbSessionBackend {
+    /// Create a backend over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            index_lock: Mutex::new(()),
+        }
+    }
+
+    fn session_key(session_id: &str) -> Vec<u8> {
+        format!("{}{}", SESSION_PREFIX, session_id).into_bytes()
+    }
+
+    fn user_key(user_id: &str) -> Vec<u8> {
+        format!("{}{}", USER_PREFIX, user_id).into_bytes()
+    }
+
+    fn expiry_key(bucket: i64) -> Vec<u8> {
+        format!("{}{}", EXPIRY_PREFIX, bucket).into_bytes()
+    }
+
+    fn bucket(timestamp: i64) -> i64 {
+        timestamp.div_euclid(EXPIRY_BUCKET_SECS)
+    }
+
+    fn read_session(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        let Some(bytes) = self
+            .db
+            .get(&Self::session_key(session_id))
+            .map_err(db_error)?
+        else {
+            return Ok(None);
+        };
+        let archived = rkyv::check_archived_root::<StoredSession>(&bytes)
+            .map_err(|e| AuthError::Internal(format!("Corrupt session record: {}", e)))?;
+        let stored: StoredSession = archived
+            .deserialize(&mut rkyv::Infallible)
+            .map_err(|_| AuthError::Internal("Corrupt session record".into()))?;
+        Ok(Some(stored.into()))
+    }
+
+    fn read_ids(&self, key: &[u8]) -> AuthResult<Vec<String>> {
+        let Some(bytes) = self.db.get(key).map_err(db_error)? else {
+            return Ok(Vec::new());
+        };
+        let archived = rkyv::check_archived_root::<StoredIds>(&bytes)
+            .map_err(|e| AuthError::Internal(format!("Corrupt session index: {}", e)))?;
+        let stored: StoredIds = archived
+            .deserialize(&mut rkyv::Infallible)
+            .map_err(|_| AuthError::Internal("Corrupt session index".into()))?;
+        Ok(stored.ids)
+    }
+
+    fn write_ids(&self, key: &[u8], ids: Vec<String>) -> AuthResult<()> {
+        if ids.is_empty() {
+            return self.db.delete(key).map_err(db_error);
+        }
+        let bytes = rkyv::to_bytes::<_, 256>(&StoredIds { ids })
+            .map_err(|e| AuthError::Internal(format!("Failed to serialize index: {}", e)))?;
+        self.db.put(key, &bytes).map_err(db_error)
+    }
+
+    fn read_cursor(&self) -> AuthResult<Option<i64>> {
+        let Some(bytes) = self.db.get(EXPIRY_CURSOR_KEY).map_err(db_error)? else {
+            return Ok(None);
+        };
+        let bytes: [u8; 8] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| AuthError::Internal("Corrupt session expiry cursor".into()))?;
+        Ok(Some(i64::from_le_bytes(bytes)))
+    }
+
+    fn write_cursor(&self, bucket: i64) -> AuthResult<()> {
+        self.db
+            .put(EXPIRY_CURSOR_KEY, &bucket.to_le_bytes())
+            .map_err(db_error)
+    }
+
+    /// Add a session to its expiry bucket, moving the cursor back if needed
+    fn index_expiry(&self, session_id: &str, expires_at: i64) -> AuthResult<()> {
+        let bucket = Self::bucket(expires_at);
+        let key = Self::expiry_key(bucket);
+        let mut ids = self.read_ids(&key)?;
+        if !ids.iter().any(|id| id == session_id) {
+            ids.push(session_id.to_string());
+            self.write_ids(&key, ids)?;
+        }
+        match self.read_cursor()? {
+            Some(cursor) if cursor <= bucket => Ok(()),
+            _ => self.write_cursor(bucket),
+        }
+    }
+
+    /// Delete a session and its user index entry (index lock held)
+    fn delete_indexed(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        let Some(session) = self.read_session(session_id)? else {
+            return Ok(None);
+        };
+        self.db
+            .delete(&Self::session_key(session_id))
+            .map_err(db_error)?;
+
+        let key = Self::user_key(&session.user_id);
+        let mut ids = self.read_ids(&key)?;
+        ids.retain(|id| id != session_id);
+        self.write_ids(&key, ids)?;
+        Ok(Some(session))
+    }
+}
+
+impl SessionBackend for DbSessionBackend {
+    fn save(&self, session: &Session) -> AuthResult<()> {
+        let _guard = self.index_lock.lock().unwrap();
+        let previous = self.read_session(&session.id)?;
+
+        let bytes = rkyv::to_bytes::<_, 256>(&StoredSession::from(session))
+            .map_err(|e| AuthError::Internal(format!("Failed to serialize session: {}", e)))?;
+        self.db
+            .put(&Self::session_key(&session.id), &bytes)
+            .map_err(db_error)?;
+
+        if previous.is_none() {
+            let key = Self::user_key(&session.user_id);
+            let mut ids = self.read_ids(&key)?;
+            ids.push(session.id.clone());
+            self.write_ids(&key, ids)?;
+        }
+        // A stale entry left in the old bucket is dropped by the purge
+        if !matches!(previous, Some(ref p) if p.expires_at == session.expires_at) {
+            self.index_expiry(&session.id, session.expires_at)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        self.read_session(session_id)
+    }
+
+    fn delete(&self, session_id: &str) -> AuthResult<Option<Session>> {
+        let _guard = self.index_lock.lock().unwrap();
+        self.delete_indexed(session_id)
+    }
+
+    fn user_session_ids(&self, user_id: &str) -> AuthResult<Vec<String>> {
+        self.read_ids(&Self::user_key(user_id))
+    }
+
+    fn purge_expired(&self, now: i64) -> AuthResult<usize> {
+        let _guard = self.index_lock.lock().unwrap();
+        let Some(cursor) = self.read_cursor()? else {
+            return Ok(0);
+        };
+        let current = Self::bucket(now);
+        let mut removed = 0;
+        let mut next_cursor = None;
+
+        for bucket in cursor..=current {
+            let key = Self::expiry_key(bucket);
+            let mut remaining = Vec::new();
+            for id in self.read_ids(&key)? {
+                match self.read_session(&id)? {
+                    Some(session) if session.expires_at < now => {
+                        self.delete_indexed(&id)?;
+                        removed += 1;
+                    }
+                    // Still live and indexed in this bucket
+                    Some(session) if Self::bucket(session.expires_at) == bucket => {
+                        remaining.push(id)
+                    }
+                    _ => {}
+                }
+            }
+            if !remaining.is_empty() {
+                next_cursor.get_or_insert(bucket);
+            }
+            self.write_ids(&key, remaining)?;
+        }
+
+        self.write_cursor(next_cursor.unwrap_or(current + 1))?;
+        Ok(removed)
+    }
+}
+
+fn db_error(e: DbError) -> AuthError {
+    AuthError::Internal(format!("Session storage error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionConfig, SessionStore};
+    use time::Duration;
+    use vaya_db::DbConfig;
+
+    fn open(dir: &std::path::Path) -> Arc<VayaDb> {
+        Arc::new(VayaDb::open(DbConfig::new(dir)).unwrap())
+    }
+
+    fn store(db: Arc<VayaDb>, config: SessionConfig) -> SessionStore {
+        SessionStore::with_backend(config, Arc::new(DbSessionBackend::new(db)))
+    }
+
+    #[test]
+    fn test_sessions_survive_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let (kept, removed) = {
+            let db = open(dir.path());
+            let store = store(db.clone(), SessionConfig::default());
+            let mut kept = store
+                .create_with_meta("user-123", Some("10.0.0.1".into()), None)
+                .unwrap();
+            kept.set("locale", "ms-MY");
+            store.update(&kept).unwrap();
+            let removed = store.create("user-123").unwrap();
+            store.remove(&removed.id);
+            db.close().unwrap();
+            (kept, removed)
+        };
+
+        let store = store(open(dir.path()), SessionConfig::default());
+        let fetched = store.get(&kept.id).unwrap();
+        assert_eq!(fetched.user_id, "user-123");
+        assert_eq!(fetched.ip_address.as_deref(), Some("10.0.0.1"));
+        assert_eq!(fetched.get("locale"), Some("ms-MY"));
+        assert!(store.get(&removed.id).is_err());
+        assert_eq!(store.get_user_sessions("user-123").len(), 1);
+    }
+
+    #[test]
+    fn test_max_sessions_per_user_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SessionConfig {
+            max_per_user: 2,
+            ..Default::default()
+        };
+        let store = store(open(dir.path()), config);
+
+        let s1 = store.create("user-123").unwrap();
+        let s2 = store.create("user-123").unwrap();
+        let s3 = store.create("user-123").unwrap();
+
+        assert!(store.get(&s1.id).is_err());
+        assert!(store.get(&s2.id).is_ok());
+        assert!(store.get(&s3.id).is_ok());
+
+        store.remove_user_sessions("user-123");
+        assert!(store.get_user_sessions("user-123").is_empty());
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = open(dir.path());
+        let expired = store(
+            db.clone(),
+            SessionConfig {
+                ttl: Duration::hours(-3),
+                ..Default::default()
+            },
+        );
+        let live = store(db.clone(), SessionConfig::default());
+
+        let old1 = expired.create("user-1").unwrap();
+        let old2 = expired.create("user-2").unwrap();
+        let current = live.create("user-1").unwrap();
+
+        assert_eq!(live.cleanup(), 2);
+        assert!(live.get(&old1.id).is_err());
+        assert!(live.get(&old2.id).is_err());
+        assert!(live.get(&current.id).is_ok());
+
+        let backend = DbSessionBackend::new(db);
+        assert_eq!(
+            backend.user_session_ids("user-1").unwrap(),
+            vec![current.id.clone()]
+        );
+        assert!(backend.user_session_ids("user-2").unwrap().is_empty());
+
+        // Nothing left to purge until the live session expires
+        assert_eq!(live.cleanup(), 0);
+        let later = current.expires_at + 1;
+        assert_eq!(backend.purge_expired(later).unwrap(), 1);
+        assert!(backend.load(&current.id).unwrap().is_none());
+    }
+}
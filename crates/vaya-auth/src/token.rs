@@ -2,13 +2,16 @@
 //!
 //! Implements HS256 (HMAC-SHA256) JWT tokens.
 
+use std::fmt;
+
 use ring::hmac;
 use time::{Duration, OffsetDateTime};
+use vaya_common::{Mask, Redact};
 
 use crate::{AuthError, AuthResult};
 
 /// JWT token claims
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Claims {
     /// Subject (user ID)
     pub sub: String,
@@ -28,6 +31,28 @@ pub struct Claims {
     pub custom: Vec<(String, String)>,
 }
 
+impl fmt::Debug for Claims {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Claims")
+            .field("sub", &self.sub)
+            .field("iss", &self.iss)
+            .field("aud", &self.aud)
+            .field("exp", &self.exp)
+            .field("iat", &self.iat)
+            .field("nbf", &self.nbf)
+            .field("jti", &self.jti)
+            .field(
+                "custom",
+                &self
+                    .custom
+                    .iter()
+                    .map(|(k, v)| (k, v.redacted(Mask::Full)))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
 impl Claims {
     /// Create new claims with subject and expiration
     pub fn new(
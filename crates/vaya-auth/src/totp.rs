@@ -0,0 +1,377 @@
+//! TOTP two-factor authentication (RFC 6238)
+//!
+//! [`Totp`] generates and checks time-based one-time codes compatible with
+//! authenticator apps (HMAC-SHA1, 6 digits, 30-second steps), and builds
+//! the `otpauth://` URI shown as a QR code during enrollment. Codes from
+//! the adjacent steps are accepted to allow for clock drift, and a step
+//! that was already used is rejected so a code can't be replayed.
+//!
+//! [`TwoFactor`] is a user's enrollment: the secret, whether it has been
+//! confirmed with a first code, and single-use backup codes (stored as
+//! SHA-256 hashes) for when the authenticator is lost.
+
+use std::fmt;
+
+use vaya_crypto::{
+    base32_decode, base32_encode, constant_time_eq, hmac_sha1, random, random_bytes, sha256,
+};
+
+use crate::{AuthError, AuthResult};
+
+/// Secret length in bytes (the RFC 4226 recommendation)
+const SECRET_LEN: usize = 20;
+/// Backup code alphabet (no 0/o, 1/l/i)
+const BACKUP_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+/// Characters per backup code
+const BACKUP_CODE_LEN: usize = 10;
+
+/// A TOTP shared secret
+#[derive(Clone, PartialEq, Eq)]
+pub struct TotpSecret(Vec<u8>);
+
+impl TotpSecret {
+    /// Generate a random secret
+    pub fn generate() -> AuthResult<Self> {
+        random_bytes(SECRET_LEN)
+            .map(Self)
+            .map_err(|_| AuthError::Internal("Failed to generate TOTP secret".into()))
+    }
+
+    /// Parse a base32 secret (as stored or typed in by the user)
+    pub fn from_base32(encoded: &str) -> AuthResult<Self> {
+        let bytes = base32_decode(encoded)
+            .map_err(|_| AuthError::Internal("Invalid TOTP secret encoding".into()))?;
+        if bytes.len() < 10 {
+            return Err(AuthError::Internal("TOTP secret is too short".into()));
+        }
+        Ok(Self(bytes))
+    }
+
+    /// Base32 encoding, as used in `otpauth://` URIs
+    pub fn to_base32(&self) -> String {
+        base32_encode(&self.0)
+    }
+
+    /// Raw secret bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for TotpSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TotpSecret(<redacted>)")
+    }
+}
+
+/// HOTP value (RFC 4226) for a counter, truncated to `digits` digits
+pub fn hotp(secret: &[u8], counter: u64, digits: u32) -> u32 {
+    let hash = hmac_sha1(secret, &counter.to_be_bytes());
+    let offset = (hash[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+    binary % 10u32.pow(digits)
+}
+
+/// Time-based one-time password generator and verifier
+#[derive(Debug, Clone)]
+pub struct Totp {
+    /// Issuer shown in the authenticator app
+    issuer: String,
+    /// Code length
+    digits: u32,
+    /// Step length in seconds
+    period: u64,
+    /// Steps accepted either side of the current one
+    skew: u64,
+}
+
+impl Totp {
+    /// Create a generator with authenticator-app defaults
+    pub fn new(issuer: impl Into<String>) -> Self {
+        Self {
+            issuer: issuer.into(),
+            digits: 6,
+            period: 30,
+            skew: 1,
+        }
+    }
+
+    /// Set the code length (6-8 digits)
+    pub fn with_digits(mut self, digits: u32) -> Self {
+        self.digits = digits.clamp(6, 8);
+        self
+    }
+
+    /// Set the step length in seconds
+    pub fn with_period(mut self, seconds: u64) -> Self {
+        self.period = seconds.max(1);
+        self
+    }
+
+    /// Set how many steps of clock drift are accepted each way
+    pub fn with_skew(mut self, steps: u64) -> Self {
+        self.skew = steps;
+        self
+    }
+
+    /// Time step containing a Unix timestamp
+    pub fn step_at(&self, unix_time: i64) -> u64 {
+        unix_time.max(0) as u64 / self.period
+    }
+
+    /// Code for a Unix timestamp
+    pub fn code_at(&self, secret: &TotpSecret, unix_time: i64) -> String {
+        self.code_for_step(secret, self.step_at(unix_time))
+    }
+
+    fn code_for_step(&self, secret: &TotpSecret, step: u64) -> String {
+        format!(
+            "{:0width$}",
+            hotp(secret.as_bytes(), step, self.digits),
+            width = self.digits as usize
+        )
+    }
+
+    /// Verify a code at `now`, returning the step it matched
+    ///
+    /// Steps at or before `last_step` (the last code accepted for this
+    /// secret) are rejected, so each code works once.
+    pub fn verify(
+        &self,
+        secret: &TotpSecret,
+        code: &str,
+        now: i64,
+        last_step: Option<u64>,
+    ) -> AuthResult<u64> {
+        let code = code.trim();
+        if code.len() != self.digits as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(AuthError::InvalidMfaCode);
+        }
+
+        let current = self.step_at(now);
+        let first = current.saturating_sub(self.skew);
+        (first..=current + self.skew)
+            .filter(|step| last_step.is_none_or(|last| *step > last))
+            .find(|step| {
+                constant_time_eq(
+                    self.code_for_step(secret, *step).as_bytes(),
+                    code.as_bytes(),
+                )
+            })
+            .ok_or(AuthError::InvalidMfaCode)
+    }
+
+    /// `otpauth://` URI for enrolling `account` in an authenticator app
+    pub fn provisioning_uri(&self, secret: &TotpSecret, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            uri_encode(&self.issuer),
+            uri_encode(account),
+            secret.to_base32(),
+            uri_encode(&self.issuer),
+            self.digits,
+            self.period
+        )
+    }
+}
+
+/// Generate `count` backup codes, returning the codes and their hashes
+///
+/// Show the codes to the user once and store only the hashes.
+pub fn generate_backup_codes(count: usize) -> AuthResult<(Vec<String>, Vec<String>)> {
+    let rng = random();
+    let mut codes = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut code = String::with_capacity(BACKUP_CODE_LEN + 1);
+        for i in 0..BACKUP_CODE_LEN {
+            if i == BACKUP_CODE_LEN / 2 {
+                code.push('-');
+            }
+            let index = rng
+                .range(BACKUP_ALPHABET.len() as u64)
+                .map_err(|_| AuthError::Internal("Failed to generate backup code".into()))?;
+            code.push(BACKUP_ALPHABET[index as usize] as char);
+        }
+        codes.push(code);
+    }
+    let hashes = codes.iter().map(|c| hash_backup_code(c)).collect();
+    Ok((codes, hashes))
+}
+
+/// Hash a backup code for storage, ignoring case, spaces and dashes
+pub fn hash_backup_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !matches!(c, '-' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    sha256(normalized.as_bytes()).to_hex()
+}
+
+/// How a second factor was proven
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MfaMethod {
+    /// Code from the authenticator app
+    Totp,
+    /// Single-use backup code
+    BackupCode,
+}
+
+/// A user's TOTP enrollment
+#[derive(Debug, Clone)]
+pub struct TwoFactor {
+    /// Shared secret
+    pub secret: TotpSecret,
+    /// Whether enrollment was confirmed with a code
+    pub enabled: bool,
+    /// Last step a code was accepted for
+    pub last_step: Option<u64>,
+    /// Hashes of unused backup codes
+    pub backup_code_hashes: Vec<String>,
+}
+
+impl TwoFactor {
+    /// Backup codes issued on confirmation
+    pub const BACKUP_CODE_COUNT: usize = 10;
+
+    /// Start enrollment with a new secret; not enabled until confirmed
+    pub fn begin() -> AuthResult<Self> {
+        Ok(Self {
+            secret: TotpSecret::generate()?,
+            enabled: false,
+            last_step: None,
+            backup_code_hashes: Vec::new(),
+        })
+    }
+
+    /// Confirm enrollment with a first code, returning fresh backup codes
+    pub fn confirm(&mut self, totp: &Totp, code: &str, now: i64) -> AuthResult<Vec<String>> {
+        let step = totp.verify(&self.secret, code, now, self.last_step)?;
+        self.last_step = Some(step);
+        self.enabled = true;
+        self.regenerate_backup_codes()
+    }
+
+    /// Verify a TOTP code or a backup code; a backup code is used up
+    pub fn verify(&mut self, totp: &Totp, code: &str, now: i64) -> AuthResult<MfaMethod> {
+        if !self.enabled {
+            return Err(AuthError::InvalidMfaCode);
+        }
+        if code.trim().bytes().all(|b| b.is_ascii_digit()) {
+            let step = totp.verify(&self.secret, code, now, self.last_step)?;
+            self.last_step = Some(step);
+            return Ok(MfaMethod::Totp);
+        }
+
+        let hash = hash_backup_code(code);
+        let index = self
+            .backup_code_hashes
+            .iter()
+            .position(|h| constant_time_eq(h.as_bytes(), hash.as_bytes()))
+            .ok_or(AuthError::InvalidMfaCode)?;
+        self.backup_code_hashes.swap_remove(index);
+        Ok(MfaMethod::BackupCode)
+    }
+
+    /// Replace all backup codes, returning the new ones
+    pub fn regenerate_backup_codes(&mut self) -> AuthResult<Vec<String>> {
+        let (codes, hashes) = generate_backup_codes(Self::BACKUP_CODE_COUNT)?;
+        self.backup_code_hashes = hashes;
+        Ok(codes)
+    }
+
+    /// Number of unused backup codes
+    pub fn backup_codes_remaining(&self) -> usize {
+        self.backup_code_hashes.len()
+    }
+}
+
+/// Percent-encode a URI component
+fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~' | b'@') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rfc_secret() -> TotpSecret {
+        TotpSecret(b"12345678901234567890".to_vec())
+    }
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        let totp = Totp::new("VAYA").with_digits(8);
+        let secret = rfc_secret();
+        assert_eq!(totp.code_at(&secret, 59), "94287082");
+        assert_eq!(totp.code_at(&secret, 1111111109), "07081804");
+        assert_eq!(totp.code_at(&secret, 1234567890), "89005924");
+        assert_eq!(totp.code_at(&secret, 2000000000), "69279037");
+
+        // Drift window of one step either way, and no replays
+        let totp = Totp::new("VAYA");
+        let now = 1_700_000_000;
+        let previous = totp.code_at(&secret, now - 30);
+        let step = totp.verify(&secret, &previous, now, None).unwrap();
+        assert_eq!(step, totp.step_at(now) - 1);
+        assert!(totp.verify(&secret, &previous, now, Some(step)).is_err());
+        let stale = totp.code_at(&secret, now - 90);
+        assert!(totp.verify(&secret, &stale, now, None).is_err());
+        assert!(totp.verify(&secret, "12a456", now, None).is_err());
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let totp = Totp::new("VAYA Travel");
+        let secret = TotpSecret::from_base32(&rfc_secret().to_base32().to_lowercase()).unwrap();
+        assert_eq!(secret, rfc_secret());
+        assert_eq!(
+            totp.provisioning_uri(&secret, "aisyah@example.com"),
+            "otpauth://totp/VAYA%20Travel:aisyah@example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=VAYA%20Travel&algorithm=SHA1&digits=6&period=30"
+        );
+        assert!(!format!("{:?}", secret).contains("GEZ"));
+    }
+
+    #[test]
+    fn test_enrollment_and_backup_codes() {
+        let totp = Totp::new("VAYA");
+        let now = 1_700_000_000;
+        let mut two_factor = TwoFactor::begin().unwrap();
+        assert!(two_factor.verify(&totp, "000000", now).is_err());
+
+        let code = totp.code_at(&two_factor.secret, now);
+        let backup = two_factor.confirm(&totp, &code, now).unwrap();
+        assert!(two_factor.enabled);
+        assert_eq!(backup.len(), TwoFactor::BACKUP_CODE_COUNT);
+
+        // The confirmation code can't be reused; the next step's can
+        assert!(two_factor.verify(&totp, &code, now).is_err());
+        let next = totp.code_at(&two_factor.secret, now + 30);
+        assert_eq!(
+            two_factor.verify(&totp, &next, now + 30).unwrap(),
+            MfaMethod::Totp
+        );
+
+        // Backup codes work once, in any case
+        let typed = backup[0].to_uppercase();
+        assert_eq!(
+            two_factor.verify(&totp, &typed, now).unwrap(),
+            MfaMethod::BackupCode
+        );
+        assert!(two_factor.verify(&totp, &backup[0], now).is_err());
+        assert_eq!(two_factor.backup_codes_remaining(), 9);
+    }
+}
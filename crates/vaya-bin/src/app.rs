@@ -1,32 +1,49 @@
 //! Application state and lifecycle management
 
-use std::sync::Arc;
-use std::time::Instant;
-
-use vaya_api::{ApiConfig, ApiServer, RateLimiter};
-use vaya_auth::{JwtTokenizer, PasswordHasher, SessionStore};
-use vaya_cache::LruCache;
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+use vaya_api::{ApiConfig, ApiServer, RateLimiter, RateQuota, SystemState};
+use vaya_auth::{
+    DbSessionBackend, JwtTokenizer, PasswordHasher, RbacManager, SessionConfig, SessionStore,
+};
+use vaya_cache::Cache;
 use vaya_db::{DbConfig, VayaDb};
+use vaya_fleet::{DiscoveryClient, DiscoveryConfig, NodeId};
 
-use crate::config::Config;
+use crate::config::{Config, DynamicSettings};
+use crate::migrations;
 use crate::routes;
+use crate::shutdown::{Phase, Shutdown, ShutdownReport};
+
+/// Name this node registers under in service discovery
+pub const SERVICE_NAME: &str = "vaya-api";
 
 /// Application state shared across requests
 pub struct AppState {
-    /// Configuration
+    /// Configuration as loaded at startup
     pub config: Arc<Config>,
+    /// Settings as last reloaded
+    settings: RwLock<DynamicSettings>,
     /// Database
     pub db: Arc<VayaDb>,
     /// Cache
-    pub cache: Arc<LruCache<String, Vec<u8>>>,
+    pub cache: Arc<Cache<String, Vec<u8>>>,
     /// JWT tokenizer
     pub jwt: Arc<JwtTokenizer>,
     /// Password hasher
     pub hasher: Arc<PasswordHasher>,
     /// Session store
     pub sessions: Arc<SessionStore>,
+    /// Roles and permissions
+    pub rbac: Arc<RwLock<RbacManager>>,
     /// Rate limiter
     pub rate_limiter: Arc<RateLimiter>,
+    /// Service discovery client
+    pub discovery: Arc<DiscoveryClient>,
     /// Start time
     pub started_at: Instant,
 }
@@ -37,15 +54,22 @@ impl AppState {
         let config = Arc::new(config);
 
         // Initialize database
-        let db_config = DbConfig::new(&config.database.data_dir)
+        let mut db_config = DbConfig::new(&config.database.data_dir)
             .memtable_size(config.database.memtable_size)
             .compression(config.database.compression);
+        if let Some(ref dir) = config.database.wal_archive_dir {
+            db_config = db_config.wal_archive_dir(dir);
+        }
+        if let Some(ref keys) = config.database.encryption_keys {
+            db_config = db_config.encryption(keys.clone());
+        }
 
         let db = VayaDb::open(db_config).map_err(|e| AppError::DatabaseInit(e.to_string()))?;
         let db = Arc::new(db);
+        check_migrations(&config, &db)?;
 
         // Initialize cache
-        let cache = LruCache::new(config.cache.max_size);
+        let cache = Cache::new(config.cache.max_size, 16);
         let cache = Arc::new(cache);
 
         // Initialize auth components
@@ -55,8 +79,14 @@ impl AppState {
         let hasher = PasswordHasher::new();
         let hasher = Arc::new(hasher);
 
-        let sessions = SessionStore::new();
+        // Sessions persist in the database so restarts don't log users out
+        let sessions = SessionStore::with_backend(
+            SessionConfig::default(),
+            Arc::new(DbSessionBackend::new(Arc::clone(&db))),
+        );
+        sessions.cleanup();
         let sessions = Arc::new(sessions);
+        let rbac = Arc::new(RwLock::new(RbacManager::with_default_roles()));
 
         // Initialize rate limiter
         let rate_limiter = RateLimiter::new(
@@ -65,28 +95,33 @@ impl AppState {
         );
         let rate_limiter = Arc::new(rate_limiter);
 
+        let discovery = DiscoveryClient::new(NodeId::generate(), DiscoveryConfig::default());
+        let discovery = Arc::new(discovery);
+
         Ok(Self {
+            settings: RwLock::new(config.dynamic()),
             config,
             db,
             cache,
             jwt,
             hasher,
             sessions,
+            rbac,
             rate_limiter,
+            discovery,
             started_at: Instant::now(),
         })
     }
 
+    /// Current runtime-adjustable settings
+    pub fn settings(&self) -> DynamicSettings {
+        self.settings.read().unwrap().clone()
+    }
+
     /// Get uptime in seconds
     pub fn uptime_seconds(&self) -> u64 {
         self.started_at.elapsed().as_secs()
     }
-
-    /// Check if healthy
-    pub fn is_healthy(&self) -> bool {
-        // Could add more checks here
-        true
-    }
 }
 
 impl std::fmt::Debug for AppState {
@@ -125,11 +160,45 @@ impl AppBuilder {
             .with_cors_origins(state.config.api.cors_origins.clone());
 
         let mut server = ApiServer::new(api_config);
+        server.set_system(Arc::new(
+            SystemState::new(Arc::clone(&state.rbac))
+                .with_db(Arc::clone(&state.db))
+                .with_cache(Arc::clone(&state.cache))
+                .with_started_at(state.started_at),
+        ));
 
         // Register routes
         routes::register_routes(&mut server, Arc::clone(&state));
 
-        Ok(App { state, server })
+        let grace = Duration::from_secs(state.config.server.shutdown_grace);
+        let shutdown = Arc::new(Shutdown::new().grace(grace));
+
+        // Make acknowledged writes durable even if closing storage overruns.
+        // This is the only Flush hook: the binary never constructs a
+        // notification outbox, and one that is added needs no drain here
+        // since its messages are stored before any delivery is attempted.
+        let db = Arc::clone(&state.db);
+        shutdown.on_shutdown("wal", Phase::Flush, move || async move {
+            tokio::task::spawn_blocking(move || db.sync())
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())
+        });
+
+        // Storage closes last, after every request that could write has ended
+        let db = Arc::clone(&state.db);
+        shutdown.on_shutdown("database", Phase::Storage, move || async move {
+            tokio::task::spawn_blocking(move || db.close())
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())
+        });
+
+        Ok(App {
+            state,
+            server,
+            shutdown,
+        })
     }
 }
 
@@ -139,6 +208,8 @@ pub struct App {
     pub state: Arc<AppState>,
     /// API server
     pub server: ApiServer,
+    /// Shutdown coordinator
+    pub shutdown: Arc<Shutdown>,
 }
 
 impl App {
@@ -161,12 +232,208 @@ impl App {
     pub fn handle(&self, request: vaya_api::Request) -> vaya_api::Response {
         self.server.handle(request)
     }
+
+    /// Apply the runtime-adjustable settings of a reloaded configuration
+    ///
+    /// Returns the keys of the settings that changed. Everything else in
+    /// `config` is ignored until restart.
+    pub fn reload(&self, config: &Config) -> Vec<&'static str> {
+        let next = config.dynamic();
+        let mut settings = self.state.settings.write().unwrap();
+        let changed = settings.changes(&next);
+        if changed.iter().any(|key| key.starts_with("api.rate_limit")) {
+            let quota = RateQuota::new(next.rate_limit_requests, next.rate_limit_window as i64);
+            self.state.rate_limiter.set_quota(quota);
+            if let Some(limiter) = self.server.rate_limiter() {
+                limiter.set_quota(quota);
+            }
+        }
+        *settings = next;
+        changed
+    }
+
+    /// Register a hook to run when the application shuts down
+    pub fn on_shutdown<F, Fut>(&self, name: impl Into<String>, phase: Phase, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.shutdown.on_shutdown(name, phase, hook);
+    }
+
+    /// Serve the API over HTTP until `signal` resolves, then shut down
+    ///
+    /// The node is registered in service discovery while it serves. On
+    /// shutdown it deregisters, stops accepting connections and drains the
+    /// open ones, then runs the remaining hooks, all within the configured
+    /// shutdown timeout.
+    pub async fn serve(
+        self: Arc<Self>,
+        signal: impl Future<Output = ()>,
+    ) -> Result<ShutdownReport, AppError> {
+        let server_config = &self.state.config.server;
+        let addr = server_config.bind_addr;
+        let timeout = Duration::from_secs(server_config.shutdown_timeout);
+        let config = vaya_net::ServerConfig::new(addr)
+            .read_timeout(server_config.request_timeout)
+            .drain_timeout(server_config.shutdown_timeout);
+
+        let mut router = vaya_net::Router::new();
+        let app = Arc::clone(&self);
+        router.not_found(move |request| {
+            let app = Arc::clone(&app);
+            async move {
+                let request = to_api_request(request, &app.state.config.api.trusted_proxies);
+                let response = tokio::task::spawn_blocking(move || app.handle(request))
+                    .await
+                    .map_err(|e| vaya_net::NetError::Protocol(e.to_string()))?;
+                Ok(to_net_response(response))
+            }
+        });
+        let server =
+            vaya_net::Server::new(config, router).map_err(|e| AppError::Server(e.to_string()))?;
+
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let mut serving = tokio::spawn(async move {
+            server
+                .run_until(async {
+                    let _ = stop_rx.await;
+                })
+                .await
+        });
+
+        self.register_discovery(addr.port());
+        info!(addr = %addr, "Server ready to accept connections");
+
+        let triggered = self.shutdown.signal();
+        let failed = tokio::select! {
+            result = &mut serving => Some(match result {
+                Ok(Ok(())) => "server stopped unexpectedly".to_string(),
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => e.to_string(),
+            }),
+            _ = signal => None,
+            _ = triggered => None,
+        };
+
+        if failed.is_none() {
+            self.shutdown
+                .on_shutdown("http", Phase::Drain, move || async move {
+                    let _ = stop_tx.send(());
+                    serving
+                        .await
+                        .map_err(|e| e.to_string())?
+                        .map_err(|e| e.to_string())
+                });
+        }
+
+        info!(timeout_secs = timeout.as_secs(), "Shutting down");
+        let report = self.shutdown.run(timeout).await;
+        match failed {
+            Some(e) => Err(AppError::Server(e)),
+            None => Ok(report),
+        }
+    }
+
+    /// Announce this node, and withdraw it first thing on shutdown
+    fn register_discovery(&self, port: u16) {
+        let discovery = Arc::clone(&self.state.discovery);
+        if let Err(e) = discovery.register_local(SERVICE_NAME, port) {
+            warn!(error = %e, "Failed to register with service discovery");
+            return;
+        }
+        self.shutdown
+            .on_shutdown("discovery", Phase::Intake, move || async move {
+                discovery.withdraw().await;
+                Ok(())
+            });
+    }
+}
+
+/// Convert a request from the HTTP server into the API's representation
+fn to_api_request(request: vaya_net::Request, trusted_proxies: &[IpAddr]) -> vaya_api::Request {
+    let mut api = vaya_api::Request::new(request.method().as_str(), request.path());
+    api.query_params = request.query_params().clone();
+    for (name, value) in request.headers().iter() {
+        api.headers.insert(name.to_string(), value.to_string());
+    }
+    api.client_ip = client_ip(&request, trusted_proxies).map(|ip| ip.to_string());
+    api.body = request.body().to_vec();
+    api
+}
+
+/// The address a request came from, as far as it can be trusted
+///
+/// This is the connection's peer, unless the peer is one of our proxies:
+/// then `X-Forwarded-For` is followed back from the nearest hop, past
+/// every trusted proxy, to the first address a client could not forge.
+fn client_ip(request: &vaya_net::Request, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let mut client = request.peer_addr()?.ip();
+    if let Some(hops) = request.headers().get("x-forwarded-for") {
+        for hop in hops.rsplit(',') {
+            if !trusted_proxies.contains(&client) {
+                break;
+            }
+            match hop.trim().parse() {
+                Ok(ip) => client = ip,
+                Err(_) => break,
+            }
+        }
+    }
+    Some(client)
+}
+
+/// Convert an API response for the HTTP server
+fn to_net_response(response: vaya_api::Response) -> vaya_net::Response {
+    let status = vaya_net::StatusCode::from_code(response.status)
+        .unwrap_or(vaya_net::StatusCode::InternalServerError);
+    let mut net = vaya_net::Response::new(status).body_bytes(response.body);
+    for (name, value) in response.headers {
+        net.headers_mut().set(name, value);
+    }
+    net
 }
 
 impl std::fmt::Debug for App {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("App").field("state", &self.state).finish()
+        f.debug_struct("App")
+            .field("state", &self.state)
+            .field("shutdown", &self.shutdown)
+            .finish()
+    }
+}
+
+/// Refuse to serve a production database with pending migrations, or one
+/// migrated by a newer build; elsewhere just warn
+fn check_migrations(config: &Config, db: &Arc<VayaDb>) -> Result<(), AppError> {
+    let status = migrations::migrator(Arc::clone(db))
+        .status()
+        .map_err(|e| AppError::DatabaseInit(e.to_string()))?;
+    let problem = if status.is_ahead() {
+        format!(
+            "database is at version {}, newer than this build's {}",
+            status.current, status.latest
+        )
+    } else if !status.is_up_to_date() {
+        format!(
+            "{} pending (versions {}); run `vaya migrate`",
+            status.pending.len(),
+            status
+                .pending
+                .iter()
+                .map(|(version, _)| version.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    } else {
+        return Ok(());
+    };
+
+    if config.is_production() {
+        return Err(AppError::Migrations(problem));
     }
+    warn!(%problem, "Schema migrations are not up to date");
+    Ok(())
 }
 
 /// Application error
@@ -176,6 +443,8 @@ pub enum AppError {
     Config(String),
     /// Database initialization error
     DatabaseInit(String),
+    /// Schema migrations don't match this build
+    Migrations(String),
     /// Cache initialization error
     CacheInit(String),
     /// Auth initialization error
@@ -189,6 +458,7 @@ impl std::fmt::Display for AppError {
         match self {
             AppError::Config(msg) => write!(f, "Configuration error: {}", msg),
             AppError::DatabaseInit(msg) => write!(f, "Database initialization error: {}", msg),
+            AppError::Migrations(msg) => write!(f, "Schema migrations: {}", msg),
             AppError::CacheInit(msg) => write!(f, "Cache initialization error: {}", msg),
             AppError::AuthInit(msg) => write!(f, "Auth initialization error: {}", msg),
             AppError::Server(msg) => write!(f, "Server error: {}", msg),
@@ -227,10 +497,177 @@ mod tests {
         assert!(err.to_string().contains("Configuration error"));
     }
 
+    #[test]
+    fn test_client_ip() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let request = |peer: &str, forwarded: Option<&str>| {
+            let mut request = vaya_net::Request::new(vaya_net::Method::GET, "/");
+            request.set_peer_addr(Some(peer.parse().unwrap()));
+            if let Some(hops) = forwarded {
+                request.headers_mut().set("X-Forwarded-For", hops);
+            }
+            request
+        };
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+
+        // A direct client can't claim another address
+        let direct = request("203.0.113.7:5000", Some("198.51.100.1"));
+        assert_eq!(client_ip(&direct, &[proxy]), ip("203.0.113.7"));
+
+        // Through our proxy, the hop it recorded is the client, not what
+        // the client itself put in front of it
+        let proxied = request("10.0.0.1:5000", Some("198.51.100.1, 203.0.113.7"));
+        assert_eq!(client_ip(&proxied, &[proxy]), ip("203.0.113.7"));
+        assert_eq!(client_ip(&proxied, &[]), ip("10.0.0.1"));
+
+        // A malformed hop stops the walk at the last trusted proxy
+        let garbled = request("10.0.0.1:5000", Some("not-an-ip"));
+        assert_eq!(client_ip(&garbled, &[proxy]), ip("10.0.0.1"));
+
+        assert_eq!(
+            client_ip(
+                &vaya_net::Request::new(vaya_net::Method::GET, "/"),
+                &[proxy]
+            ),
+            None
+        );
+    }
+
     #[test]
     fn test_app_builder() {
         let config = test_config();
         let _builder = AppBuilder::new(config);
         // Just verify builder can be created
     }
+
+    #[test]
+    fn test_reload() {
+        let data_dir = std::env::temp_dir().join(format!("vaya-bin-reload-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_dir);
+        let mut config = test_config();
+        config.database.data_dir = data_dir.clone();
+        config.cache.max_size = 1024;
+        let app = App::new(config.clone()).unwrap();
+
+        config.api.rate_limit_requests = 2;
+        config.cache.search_ttl = 30;
+        config.server.workers += 1;
+        assert_eq!(
+            app.reload(&config),
+            ["api.rate_limit_requests", "cache.search_ttl"]
+        );
+        assert_eq!(app.state.settings().search_cache_ttl, 30);
+        assert_eq!(app.state.rate_limiter.quota().limit, 2);
+        assert_eq!(app.server.rate_limiter().unwrap().quota().limit, 2);
+        assert!(app.reload(&config).is_empty());
+
+        let request = || vaya_api::Request::new("GET", "/api/v1/health");
+        assert_eq!(app.handle(request()).status, 200);
+        assert_eq!(app.handle(request()).status, 200);
+        assert_eq!(app.handle(request()).status, 429);
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn test_admin_system_reads_app_state() {
+        let data_dir = std::env::temp_dir().join(format!("vaya-bin-system-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_dir);
+        let mut config = test_config();
+        config.database.data_dir = data_dir.clone();
+        let app = App::new(config).unwrap();
+        app.state
+            .cache
+            .insert("search:KUL:NRT".to_string(), Vec::new(), None);
+
+        let mut request = vaya_api::Request::new("POST", "/api/v1/admin/system/cache/purge");
+        request.user_id = Some("ops_1".into());
+        request.user_roles = vec!["ops".into()];
+        assert_eq!(app.handle(request.clone()).status, 403);
+
+        app.state
+            .rbac
+            .write()
+            .unwrap()
+            .add_role(vaya_auth::Role::new("ops").with_permission("system:*"));
+        let response = app.handle(request);
+        assert_eq!(response.status, 200);
+        assert!(String::from_utf8(response.body)
+            .unwrap()
+            .contains(r#""purged":1"#));
+        assert!(app.state.cache.is_empty());
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[test]
+    fn test_pending_migrations_block_production() {
+        let data_dir =
+            std::env::temp_dir().join(format!("vaya-bin-migrations-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_dir);
+        let db = Arc::new(VayaDb::open(DbConfig::new(&data_dir)).unwrap());
+        let mut config = test_config();
+        assert!(check_migrations(&config, &db).is_ok());
+
+        config.server.environment = "production".into();
+        let err = check_migrations(&config, &db).unwrap_err();
+        assert!(matches!(err, AppError::Migrations(_)), "{}", err);
+
+        migrations::migrator(Arc::clone(&db))
+            .migrate(None, false)
+            .unwrap();
+        assert!(check_migrations(&config, &db).is_ok());
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
+
+    #[tokio::test]
+    async fn test_serve_and_graceful_shutdown() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let data_dir = std::env::temp_dir().join(format!("vaya-bin-serve-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&data_dir);
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut config = test_config();
+        config.server.bind_addr = ([127, 0, 0, 1], port).into();
+        config.server.shutdown_timeout = 5;
+        config.database.data_dir = data_dir.clone();
+        config.cache.max_size = 1024;
+
+        let app = Arc::new(App::new(config).unwrap());
+        let state = Arc::clone(&app.state);
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let serving = tokio::spawn(Arc::clone(&app).serve(async {
+            let _ = stop_rx.await;
+        }));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Requests reach the API server, and the node is discoverable
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET /api/v1/health HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(state.discovery.resolve(SERVICE_NAME).is_ok());
+
+        stop_tx.send(()).unwrap();
+        let report = serving.await.unwrap().unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+        let order: Vec<&str> = report.outcomes.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(order, ["discovery", "http", "wal", "database"]);
+
+        // Deregistered, no longer listening, and the database is closed
+        assert!(state.discovery.resolve(SERVICE_NAME).is_err());
+        assert!(tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_err());
+        assert!(state.db.get(b"anything").is_err());
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
 }
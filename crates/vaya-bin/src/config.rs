@@ -1,11 +1,31 @@
 //! Application configuration
 //!
-//! Configuration is loaded from environment variables with sensible defaults.
-//! No external config file parsing to maintain zero-dependency philosophy.
+//! Settings come in layers: defaults, then an optional config file (named
+//! by `VAYA_CONFIG`), then `VAYA_*` environment variables, each overriding
+//! the one before. The file format is a TOML subset parsed in [`file`],
+//! keeping the binary free of external parsers.
+//!
+//! A few settings ([`DynamicSettings`]) can change while the server runs;
+//! the rest take effect on restart.
+//!
+//! Secret settings may hold a `secret://name` reference instead of the
+//! secret itself; it is looked up in the sealed secrets file
+//! ([`SecretsConfig`]) when the configuration loads.
 
+mod file;
+
+use std::cell::OnceCell;
 use std::env;
+use std::fmt;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub use file::{ConfigFile, FileIssue};
+
+use vaya_common::secret::secret_name;
+use vaya_common::{resolve_secret, Mask, Redact};
+use vaya_crypto::{KeyStore, MasterKey, SecretStore};
 
 /// Application configuration
 #[derive(Debug, Clone)]
@@ -26,20 +46,58 @@ pub struct Config {
     pub logging: LogConfig,
 }
 
+/// Environment variable naming the config file
+pub const CONFIG_PATH_VAR: &str = "VAYA_CONFIG";
+
 impl Config {
+    /// Load configuration from the file named by `VAYA_CONFIG`, if set,
+    /// overridden by environment variables
+    pub fn load() -> Result<Self, ConfigError> {
+        match config_path() {
+            Some(path) => Self::from_file(&path),
+            None => Self::from_env(),
+        }
+    }
+
+    /// Load configuration from `path`, overridden by environment variables
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        Self::layered(Some(&ConfigFile::load(path)?))
+    }
+
     /// Load configuration from environment
     pub fn from_env() -> Result<Self, ConfigError> {
+        Self::layered(None)
+    }
+
+    /// Load configuration from environment variables over `file`
+    pub fn layered(file: Option<&ConfigFile>) -> Result<Self, ConfigError> {
+        Self::from_sources(&Sources::new(file))
+    }
+
+    fn from_sources(src: &Sources<'_>) -> Result<Self, ConfigError> {
         Ok(Self {
-            server: ServerConfig::from_env()?,
-            database: DatabaseConfig::from_env()?,
-            cache: CacheConfig::from_env()?,
-            auth: AuthConfig::from_env()?,
-            api: ApiConfig::from_env()?,
-            collector: CollectorConfig::from_env()?,
-            logging: LogConfig::from_env()?,
+            server: ServerConfig::load(src)?,
+            database: DatabaseConfig::load(src)?,
+            cache: CacheConfig::load(src)?,
+            auth: AuthConfig::load(src)?,
+            api: ApiConfig::load(src)?,
+            collector: CollectorConfig::load(src)?,
+            logging: LogConfig::load(src)?,
         })
     }
 
+    /// Settings that can be applied without a restart
+    pub fn dynamic(&self) -> DynamicSettings {
+        DynamicSettings {
+            log_level: self.logging.level.clone(),
+            rate_limit_requests: self.api.rate_limit_requests,
+            rate_limit_window: self.api.rate_limit_window,
+            cache_ttl: self.cache.default_ttl,
+            search_cache_ttl: self.cache.search_ttl,
+            session_cache_ttl: self.cache.session_ttl,
+        }
+    }
+
     /// Get environment name
     pub fn environment(&self) -> &str {
         &self.server.environment
@@ -51,6 +109,170 @@ impl Config {
     }
 }
 
+/// Path of the config file, from `VAYA_CONFIG`
+pub fn config_path() -> Option<PathBuf> {
+    env::var(CONFIG_PATH_VAR)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Where settings are read from: environment variables, falling back to
+/// the config file
+struct Sources<'a> {
+    file: Option<&'a ConfigFile>,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    /// Secrets file, opened on the first `secret://` reference
+    store: OnceCell<Result<SecretStore, ConfigError>>,
+}
+
+impl<'a> Sources<'a> {
+    fn new(file: Option<&'a ConfigFile>) -> Self {
+        Self::with_env(file, &|key| env::var(key).ok())
+    }
+
+    fn with_env(file: Option<&'a ConfigFile>, env: &'a dyn Fn(&str) -> Option<String>) -> Self {
+        Self {
+            file,
+            env,
+            store: OnceCell::new(),
+        }
+    }
+
+    /// Value of a setting, by its environment variable
+    fn var(&self, key: &str) -> Result<String, env::VarError> {
+        (self.env)(key)
+            .or_else(|| self.file.and_then(|file| file.env_value(key)))
+            .ok_or(env::VarError::NotPresent)
+    }
+
+    /// Value of a secret setting, with a `secret://` reference replaced by
+    /// the secret it names
+    fn secret_var(&self, key: &str) -> Result<Option<String>, ConfigError> {
+        let Ok(mut value) = self.var(key) else {
+            return Ok(None);
+        };
+        if secret_name(&value).is_some() {
+            let store = self
+                .store
+                .get_or_init(|| SecretsConfig::load(self)?.open_store())
+                .as_ref()
+                .map_err(Clone::clone)?;
+            resolve_secret(&mut value, store)
+                .map_err(|e| ConfigError::InvalidValue(format!("{}: {}", key, e)))?;
+        }
+        Ok(Some(value))
+    }
+}
+
+/// Where a setting's value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// An environment variable
+    Env,
+    /// The config file
+    File,
+    /// Neither; the default applies
+    Default,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Origin::Env => "env",
+            Origin::File => "file",
+            Origin::Default => "default",
+        })
+    }
+}
+
+/// One setting, where it is set, and its value as set
+#[derive(Debug, Clone)]
+pub struct SettingSource {
+    /// Dotted key in the config file
+    pub key: &'static str,
+    /// Environment variable that overrides it
+    pub env: &'static str,
+    /// Layer the value comes from
+    pub origin: Origin,
+    /// Value as set (masked for secrets; `None` for defaults)
+    pub value: Option<String>,
+}
+
+/// Every setting and the layer its value comes from
+pub fn setting_sources(file: Option<&ConfigFile>) -> Vec<SettingSource> {
+    file::SETTINGS
+        .iter()
+        .map(|setting| {
+            let (origin, value) = match env::var(setting.env) {
+                Ok(value) => (Origin::Env, Some(value)),
+                Err(_) => match file.and_then(|f| f.env_value(setting.env)) {
+                    Some(value) => (Origin::File, Some(value)),
+                    None => (Origin::Default, None),
+                },
+            };
+            let value = if setting.is_secret() {
+                value.map(|v| v.redacted(Mask::Full).to_string())
+            } else {
+                value
+            };
+            SettingSource {
+                key: setting.key,
+                env: setting.env,
+                origin,
+                value,
+            }
+        })
+        .collect()
+}
+
+/// Settings applied without a restart when the configuration is reloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicSettings {
+    /// Log filter directives
+    pub log_level: String,
+    /// Rate limit requests per window
+    pub rate_limit_requests: u32,
+    /// Rate limit window in seconds
+    pub rate_limit_window: u64,
+    /// Default cache TTL in seconds
+    pub cache_ttl: u64,
+    /// Search result cache TTL in seconds
+    pub search_cache_ttl: u64,
+    /// Session cache TTL in seconds
+    pub session_cache_ttl: u64,
+}
+
+impl DynamicSettings {
+    /// Keys of the settings that differ in `other`
+    pub fn changes(&self, other: &Self) -> Vec<&'static str> {
+        [
+            ("logging.level", self.log_level != other.log_level),
+            (
+                "api.rate_limit_requests",
+                self.rate_limit_requests != other.rate_limit_requests,
+            ),
+            (
+                "api.rate_limit_window",
+                self.rate_limit_window != other.rate_limit_window,
+            ),
+            ("cache.default_ttl", self.cache_ttl != other.cache_ttl),
+            (
+                "cache.search_ttl",
+                self.search_cache_ttl != other.search_cache_ttl,
+            ),
+            (
+                "cache.session_ttl",
+                self.session_cache_ttl != other.session_cache_ttl,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(key, _)| key)
+        .collect()
+    }
+}
+
 /// Server configuration
 #[derive(Debug, Clone)]
 pub struct ServerConfig {
@@ -64,37 +286,49 @@ pub struct ServerConfig {
     pub request_timeout: u64,
     /// Graceful shutdown timeout in seconds
     pub shutdown_timeout: u64,
+    /// Seconds a shutdown phase still gets once the timeout has passed
+    pub shutdown_grace: u64,
 }
 
 impl ServerConfig {
-    fn from_env() -> Result<Self, ConfigError> {
-        let host: IpAddr = env::var("VAYA_HOST")
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
+        let host: IpAddr = src
+            .var("VAYA_HOST")
             .unwrap_or_else(|_| "0.0.0.0".into())
             .parse()
             .map_err(|_| ConfigError::InvalidValue("VAYA_HOST".into()))?;
 
-        let port: u16 = env::var("VAYA_PORT")
+        let port: u16 = src
+            .var("VAYA_PORT")
             .unwrap_or_else(|_| "8080".into())
             .parse()
             .map_err(|_| ConfigError::InvalidValue("VAYA_PORT".into()))?;
 
-        let workers = env::var("VAYA_WORKERS")
+        let workers = src
+            .var("VAYA_WORKERS")
             .unwrap_or_else(|_| num_cpus().to_string())
             .parse()
             .map_err(|_| ConfigError::InvalidValue("VAYA_WORKERS".into()))?;
 
         Ok(Self {
             bind_addr: SocketAddr::new(host, port),
-            environment: env::var("VAYA_ENV").unwrap_or_else(|_| "development".into()),
+            environment: src.var("VAYA_ENV").unwrap_or_else(|_| "development".into()),
             workers,
-            request_timeout: env::var("VAYA_REQUEST_TIMEOUT")
+            request_timeout: src
+                .var("VAYA_REQUEST_TIMEOUT")
                 .unwrap_or_else(|_| "30".into())
                 .parse()
                 .unwrap_or(30),
-            shutdown_timeout: env::var("VAYA_SHUTDOWN_TIMEOUT")
+            shutdown_timeout: src
+                .var("VAYA_SHUTDOWN_TIMEOUT")
                 .unwrap_or_else(|_| "30".into())
                 .parse()
                 .unwrap_or(30),
+            shutdown_grace: src
+                .var("VAYA_SHUTDOWN_GRACE")
+                .unwrap_or_else(|_| "5".into())
+                .parse()
+                .unwrap_or(5),
         })
     }
 }
@@ -107,6 +341,7 @@ impl Default for ServerConfig {
             workers: num_cpus(),
             request_timeout: 30,
             shutdown_timeout: 30,
+            shutdown_grace: 5,
         }
     }
 }
@@ -118,6 +353,11 @@ pub struct DatabaseConfig {
     pub data_dir: PathBuf,
     /// WAL directory (separate for performance)
     pub wal_dir: PathBuf,
+    /// WAL archive directory for point-in-time recovery (disabled if unset)
+    pub wal_archive_dir: Option<PathBuf>,
+    /// Encryption-at-rest keys (`VAYA_DB_ENCRYPTION_KEYS=id:base64,...`;
+    /// the highest ID is active)
+    pub encryption_keys: Option<Arc<KeyStore>>,
     /// Max memtable size in bytes
     pub memtable_size: usize,
     /// Bloom filter false positive rate
@@ -129,28 +369,53 @@ pub struct DatabaseConfig {
 }
 
 impl DatabaseConfig {
-    fn from_env() -> Result<Self, ConfigError> {
-        let data_dir =
-            PathBuf::from(env::var("VAYA_DATA_DIR").unwrap_or_else(|_| "./data/db".into()));
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
+        let data_dir = PathBuf::from(
+            src.var("VAYA_DATA_DIR")
+                .unwrap_or_else(|_| "./data/db".into()),
+        );
+
+        let wal_dir = PathBuf::from(
+            src.var("VAYA_WAL_DIR")
+                .unwrap_or_else(|_| "./data/wal".into()),
+        );
 
-        let wal_dir =
-            PathBuf::from(env::var("VAYA_WAL_DIR").unwrap_or_else(|_| "./data/wal".into()));
+        let wal_archive_dir = src
+            .var("VAYA_WAL_ARCHIVE_DIR")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from);
+
+        let encryption_keys = match src.secret_var("VAYA_DB_ENCRYPTION_KEYS")? {
+            Some(spec) if !spec.trim().is_empty() => {
+                Some(Arc::new(KeyStore::from_spec(&spec).map_err(|_| {
+                    ConfigError::InvalidValue("VAYA_DB_ENCRYPTION_KEYS".into())
+                })?))
+            }
+            _ => None,
+        };
 
         Ok(Self {
             data_dir,
             wal_dir,
-            memtable_size: env::var("VAYA_MEMTABLE_SIZE")
+            wal_archive_dir,
+            encryption_keys,
+            memtable_size: src
+                .var("VAYA_MEMTABLE_SIZE")
                 .unwrap_or_else(|_| "67108864".into()) // 64MB
                 .parse()
                 .unwrap_or(64 * 1024 * 1024),
-            bloom_fp_rate: env::var("VAYA_BLOOM_FP_RATE")
+            bloom_fp_rate: src
+                .var("VAYA_BLOOM_FP_RATE")
                 .unwrap_or_else(|_| "0.01".into())
                 .parse()
                 .unwrap_or(0.01),
-            compression: env::var("VAYA_DB_COMPRESSION")
+            compression: src
+                .var("VAYA_DB_COMPRESSION")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(true),
-            compaction_threads: env::var("VAYA_COMPACTION_THREADS")
+            compaction_threads: src
+                .var("VAYA_COMPACTION_THREADS")
                 .unwrap_or_else(|_| "2".into())
                 .parse()
                 .unwrap_or(2),
@@ -163,6 +428,8 @@ impl Default for DatabaseConfig {
         Self {
             data_dir: PathBuf::from("./data/db"),
             wal_dir: PathBuf::from("./data/wal"),
+            wal_archive_dir: None,
+            encryption_keys: None,
             memtable_size: 64 * 1024 * 1024,
             bloom_fp_rate: 0.01,
             compression: true,
@@ -185,21 +452,25 @@ pub struct CacheConfig {
 }
 
 impl CacheConfig {
-    fn from_env() -> Result<Self, ConfigError> {
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
         Ok(Self {
-            max_size: env::var("VAYA_CACHE_SIZE")
+            max_size: src
+                .var("VAYA_CACHE_SIZE")
                 .unwrap_or_else(|_| "268435456".into()) // 256MB
                 .parse()
                 .unwrap_or(256 * 1024 * 1024),
-            default_ttl: env::var("VAYA_CACHE_TTL")
+            default_ttl: src
+                .var("VAYA_CACHE_TTL")
                 .unwrap_or_else(|_| "3600".into())
                 .parse()
                 .unwrap_or(3600),
-            search_ttl: env::var("VAYA_SEARCH_CACHE_TTL")
+            search_ttl: src
+                .var("VAYA_SEARCH_CACHE_TTL")
                 .unwrap_or_else(|_| "300".into()) // 5 minutes
                 .parse()
                 .unwrap_or(300),
-            session_ttl: env::var("VAYA_SESSION_TTL")
+            session_ttl: src
+                .var("VAYA_SESSION_TTL")
                 .unwrap_or_else(|_| "86400".into()) // 24 hours
                 .parse()
                 .unwrap_or(86400),
@@ -219,7 +490,7 @@ impl Default for CacheConfig {
 }
 
 /// Authentication configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct AuthConfig {
     /// JWT secret (must be set in production)
     pub jwt_secret: Vec<u8>,
@@ -239,13 +510,29 @@ pub struct AuthConfig {
     pub lockout_duration: u64,
 }
 
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("jwt_secret", &self.jwt_secret.redacted(Mask::Full))
+            .field("access_token_ttl", &self.access_token_ttl)
+            .field("refresh_token_ttl", &self.refresh_token_ttl)
+            .field("password_min_length", &self.password_min_length)
+            .field("argon2_memory", &self.argon2_memory)
+            .field("argon2_iterations", &self.argon2_iterations)
+            .field("max_login_attempts", &self.max_login_attempts)
+            .field("lockout_duration", &self.lockout_duration)
+            .finish()
+    }
+}
+
 impl AuthConfig {
-    fn from_env() -> Result<Self, ConfigError> {
-        let jwt_secret = env::var("VAYA_JWT_SECRET")
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
+        let jwt_secret = src
+            .secret_var("VAYA_JWT_SECRET")?
             .map(|s| s.into_bytes())
-            .unwrap_or_else(|_| {
+            .unwrap_or_else(|| {
                 // Only allow missing in development
-                if env::var("VAYA_ENV").unwrap_or_default() == "production" {
+                if src.var("VAYA_ENV").unwrap_or_default() == "production" {
                     Vec::new() // Will fail validation
                 } else {
                     b"development-secret-do-not-use-in-production".to_vec()
@@ -264,31 +551,38 @@ impl AuthConfig {
 
         Ok(Self {
             jwt_secret,
-            access_token_ttl: env::var("VAYA_ACCESS_TOKEN_TTL")
+            access_token_ttl: src
+                .var("VAYA_ACCESS_TOKEN_TTL")
                 .unwrap_or_else(|_| "900".into()) // 15 minutes
                 .parse()
                 .unwrap_or(900),
-            refresh_token_ttl: env::var("VAYA_REFRESH_TOKEN_TTL")
+            refresh_token_ttl: src
+                .var("VAYA_REFRESH_TOKEN_TTL")
                 .unwrap_or_else(|_| "604800".into()) // 7 days
                 .parse()
                 .unwrap_or(604800),
-            password_min_length: env::var("VAYA_PASSWORD_MIN_LENGTH")
+            password_min_length: src
+                .var("VAYA_PASSWORD_MIN_LENGTH")
                 .unwrap_or_else(|_| "12".into())
                 .parse()
                 .unwrap_or(12),
-            argon2_memory: env::var("VAYA_ARGON2_MEMORY")
+            argon2_memory: src
+                .var("VAYA_ARGON2_MEMORY")
                 .unwrap_or_else(|_| "65536".into()) // 64MB
                 .parse()
                 .unwrap_or(65536),
-            argon2_iterations: env::var("VAYA_ARGON2_ITERATIONS")
+            argon2_iterations: src
+                .var("VAYA_ARGON2_ITERATIONS")
                 .unwrap_or_else(|_| "3".into())
                 .parse()
                 .unwrap_or(3),
-            max_login_attempts: env::var("VAYA_MAX_LOGIN_ATTEMPTS")
+            max_login_attempts: src
+                .var("VAYA_MAX_LOGIN_ATTEMPTS")
                 .unwrap_or_else(|_| "5".into())
                 .parse()
                 .unwrap_or(5),
-            lockout_duration: env::var("VAYA_LOCKOUT_DURATION")
+            lockout_duration: src
+                .var("VAYA_LOCKOUT_DURATION")
                 .unwrap_or_else(|_| "900".into()) // 15 minutes
                 .parse()
                 .unwrap_or(900),
@@ -324,33 +618,54 @@ pub struct ApiConfig {
     pub cors_enabled: bool,
     /// CORS allowed origins
     pub cors_origins: Vec<String>,
+    /// Proxies trusted to report the client address in `X-Forwarded-For`
+    pub trusted_proxies: Vec<IpAddr>,
     /// Max request body size in bytes
     pub max_body_size: usize,
 }
 
 impl ApiConfig {
-    fn from_env() -> Result<Self, ConfigError> {
-        let cors_origins = env::var("VAYA_CORS_ORIGINS")
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
+        let cors_origins = src
+            .var("VAYA_CORS_ORIGINS")
             .unwrap_or_else(|_| "*".into())
             .split(',')
             .map(|s| s.trim().to_string())
             .collect();
+        let trusted_proxies = src
+            .var("VAYA_TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| ConfigError::InvalidValue("VAYA_TRUSTED_PROXIES".into()))
+            })
+            .collect::<Result<_, _>>()?;
 
         Ok(Self {
-            prefix: env::var("VAYA_API_PREFIX").unwrap_or_else(|_| "/api/v1".into()),
-            rate_limit_requests: env::var("VAYA_RATE_LIMIT_REQUESTS")
+            prefix: src
+                .var("VAYA_API_PREFIX")
+                .unwrap_or_else(|_| "/api/v1".into()),
+            rate_limit_requests: src
+                .var("VAYA_RATE_LIMIT_REQUESTS")
                 .unwrap_or_else(|_| "100".into())
                 .parse()
                 .unwrap_or(100),
-            rate_limit_window: env::var("VAYA_RATE_LIMIT_WINDOW")
+            rate_limit_window: src
+                .var("VAYA_RATE_LIMIT_WINDOW")
                 .unwrap_or_else(|_| "60".into())
                 .parse()
                 .unwrap_or(60),
-            cors_enabled: env::var("VAYA_CORS_ENABLED")
+            cors_enabled: src
+                .var("VAYA_CORS_ENABLED")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(true),
             cors_origins,
-            max_body_size: env::var("VAYA_MAX_BODY_SIZE")
+            trusted_proxies,
+            max_body_size: src
+                .var("VAYA_MAX_BODY_SIZE")
                 .unwrap_or_else(|_| "1048576".into()) // 1MB
                 .parse()
                 .unwrap_or(1024 * 1024),
@@ -366,6 +681,7 @@ impl Default for ApiConfig {
             rate_limit_window: 60,
             cors_enabled: true,
             cors_origins: vec!["*".into()],
+            trusted_proxies: Vec::new(),
             max_body_size: 1024 * 1024,
         }
     }
@@ -385,20 +701,24 @@ pub struct CollectorConfig {
 }
 
 impl CollectorConfig {
-    fn from_env() -> Result<Self, ConfigError> {
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
         Ok(Self {
-            enabled: env::var("VAYA_COLLECTOR_ENABLED")
+            enabled: src
+                .var("VAYA_COLLECTOR_ENABLED")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(true),
-            interval: env::var("VAYA_COLLECTOR_INTERVAL")
+            interval: src
+                .var("VAYA_COLLECTOR_INTERVAL")
                 .unwrap_or_else(|_| "300".into()) // 5 minutes
                 .parse()
                 .unwrap_or(300),
-            batch_size: env::var("VAYA_COLLECTOR_BATCH_SIZE")
+            batch_size: src
+                .var("VAYA_COLLECTOR_BATCH_SIZE")
                 .unwrap_or_else(|_| "100".into())
                 .parse()
                 .unwrap_or(100),
-            retry_attempts: env::var("VAYA_COLLECTOR_RETRIES")
+            retry_attempts: src
+                .var("VAYA_COLLECTOR_RETRIES")
                 .unwrap_or_else(|_| "3".into())
                 .parse()
                 .unwrap_or(3),
@@ -428,19 +748,24 @@ pub struct LogConfig {
     pub timestamps: bool,
     /// Include file/line info
     pub file_info: bool,
+    /// Domain-event log destination (file path, `stdout`, or unset to disable)
+    pub event_log: Option<String>,
 }
 
 impl LogConfig {
-    fn from_env() -> Result<Self, ConfigError> {
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
         Ok(Self {
-            level: env::var("VAYA_LOG_LEVEL").unwrap_or_else(|_| "info".into()),
-            format: env::var("VAYA_LOG_FORMAT").unwrap_or_else(|_| "json".into()),
-            timestamps: env::var("VAYA_LOG_TIMESTAMPS")
+            level: src.var("VAYA_LOG_LEVEL").unwrap_or_else(|_| "info".into()),
+            format: src.var("VAYA_LOG_FORMAT").unwrap_or_else(|_| "json".into()),
+            timestamps: src
+                .var("VAYA_LOG_TIMESTAMPS")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(true),
-            file_info: env::var("VAYA_LOG_FILE_INFO")
+            file_info: src
+                .var("VAYA_LOG_FILE_INFO")
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(false),
+            event_log: src.var("VAYA_EVENT_LOG").ok().filter(|v| !v.is_empty()),
         })
     }
 }
@@ -452,6 +777,95 @@ impl Default for LogConfig {
             format: "json".into(),
             timestamps: true,
             file_info: false,
+            event_log: None,
+        }
+    }
+}
+
+/// Secrets file configuration
+///
+/// The master key is taken only from the environment (`VAYA_MASTER_KEY`),
+/// never from the config file; `VAYA_MASTER_KEY_COMMAND` fetches it from
+/// a KMS or vault instead.
+#[derive(Clone)]
+pub struct SecretsConfig {
+    /// Sealed secrets file
+    pub file: PathBuf,
+    /// Master key as base64
+    master_key: Option<String>,
+    /// Command printing the base64 master key
+    pub master_key_command: Option<String>,
+}
+
+impl fmt::Debug for SecretsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretsConfig")
+            .field("file", &self.file)
+            .field(
+                "master_key",
+                &self.master_key.as_ref().map(|k| k.redacted(Mask::Full)),
+            )
+            .field("master_key_command", &self.master_key_command)
+            .finish()
+    }
+}
+
+impl SecretsConfig {
+    /// Load the secrets settings alone, from the config file named by
+    /// `VAYA_CONFIG` and the environment
+    ///
+    /// Unlike [`Config::load`] this needs no secret to be resolvable, so
+    /// secrets can be managed before the rest of the configuration is valid.
+    pub fn current() -> Result<Self, ConfigError> {
+        let file = config_path()
+            .map(|path| ConfigFile::load(&path))
+            .transpose()?;
+        Self::load(&Sources::new(file.as_ref()))
+    }
+
+    fn load(src: &Sources<'_>) -> Result<Self, ConfigError> {
+        Ok(Self {
+            file: PathBuf::from(
+                src.var("VAYA_SECRETS_FILE")
+                    .unwrap_or_else(|_| "./data/secrets.vault".into()),
+            ),
+            master_key: (src.env)("VAYA_MASTER_KEY").filter(|v| !v.is_empty()),
+            master_key_command: src
+                .var("VAYA_MASTER_KEY_COMMAND")
+                .ok()
+                .filter(|v| !v.is_empty()),
+        })
+    }
+
+    /// Get the master key, running the key command if there is one
+    fn master_key(&self) -> Result<MasterKey, ConfigError> {
+        match (&self.master_key, &self.master_key_command) {
+            (Some(encoded), _) => MasterKey::from_base64(encoded)
+                .map_err(|_| ConfigError::InvalidValue("VAYA_MASTER_KEY".into())),
+            (None, Some(command)) => Ok(MasterKey::command(command.as_str())),
+            (None, None) => Err(ConfigError::MissingRequired(
+                "VAYA_MASTER_KEY or VAYA_MASTER_KEY_COMMAND".into(),
+            )),
+        }
+    }
+
+    /// Open the secrets file (empty if it doesn't exist yet)
+    pub fn open_store(&self) -> Result<SecretStore, ConfigError> {
+        self.master_key()?
+            .load()
+            .and_then(|key| SecretStore::open(&self.file, key))
+            .map_err(|e| {
+                ConfigError::InvalidValue(format!("secrets file {}: {}", self.file.display(), e))
+            })
+    }
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from("./data/secrets.vault"),
+            master_key: None,
+            master_key_command: None,
         }
     }
 }
@@ -465,6 +879,13 @@ pub enum ConfigError {
     InvalidValue(String),
     /// IO error
     IoError(String),
+    /// Config file with syntax or schema errors
+    InvalidFile {
+        /// Path of the file
+        path: String,
+        /// Every problem found
+        issues: Vec<FileIssue>,
+    },
 }
 
 impl std::fmt::Display for ConfigError {
@@ -475,6 +896,13 @@ impl std::fmt::Display for ConfigError {
             }
             ConfigError::InvalidValue(msg) => write!(f, "Invalid configuration value: {}", msg),
             ConfigError::IoError(msg) => write!(f, "Configuration IO error: {}", msg),
+            ConfigError::InvalidFile { path, issues } => {
+                write!(f, "Invalid configuration file {}:", path)?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -547,6 +975,120 @@ mod tests {
         assert_eq!(config.format, "json");
     }
 
+    #[test]
+    fn test_env_overrides_file() {
+        let file = ConfigFile::parse(
+            "[server]\nport = 9000\nenvironment = \"staging\"\n\
+             [api]\nrate_limit_requests = 250\ncors_origins = [\"https://a\", \"https://b\"]\n",
+        )
+        .unwrap();
+        let env = |key: &str| (key == "VAYA_PORT").then(|| "9100".to_string());
+        let config = Config::from_sources(&Sources::with_env(Some(&file), &env)).unwrap();
+
+        assert_eq!(config.server.bind_addr.port(), 9100);
+        assert_eq!(config.server.environment, "staging");
+        assert_eq!(config.api.rate_limit_requests, 250);
+        assert_eq!(config.api.cors_origins, ["https://a", "https://b"]);
+        assert_eq!(config.api.rate_limit_window, 60);
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let file = ConfigFile::parse("[api]\ntrusted_proxies = [\"10.0.0.1\", \"::1\"]\n").unwrap();
+        let no_env = |_: &str| None;
+        let config = Config::from_sources(&Sources::with_env(Some(&file), &no_env)).unwrap();
+        assert_eq!(
+            config.api.trusted_proxies,
+            [
+                "10.0.0.1".parse::<IpAddr>().unwrap(),
+                "::1".parse().unwrap()
+            ]
+        );
+
+        let env =
+            |key: &str| (key == "VAYA_TRUSTED_PROXIES").then(|| "10.0.0.1, proxy".to_string());
+        assert!(matches!(
+            Config::from_sources(&Sources::with_env(None, &env)),
+            Err(ConfigError::InvalidValue(key)) if key == "VAYA_TRUSTED_PROXIES"
+        ));
+    }
+
+    #[test]
+    fn test_dynamic_changes() {
+        let file =
+            ConfigFile::parse("[logging]\nlevel = \"debug\"\n[cache]\nsearch_ttl = 60\n").unwrap();
+        let no_env = |_: &str| None;
+        let before = Config::from_sources(&Sources::with_env(None, &no_env))
+            .unwrap()
+            .dynamic();
+        let after = Config::from_sources(&Sources::with_env(Some(&file), &no_env))
+            .unwrap()
+            .dynamic();
+
+        assert!(before.changes(&before).is_empty());
+        assert_eq!(
+            before.changes(&after),
+            ["logging.level", "cache.search_ttl"]
+        );
+    }
+
+    #[test]
+    fn test_secret_references() {
+        let path = env::temp_dir().join(format!("vaya-config-secrets-{}", std::process::id()));
+        let key = vaya_crypto::AeadKey::generate().unwrap();
+        let mut store = SecretStore::open(&path, key.clone()).unwrap();
+        store
+            .set("jwt", "a-very-long-signing-secret-from-the-vault")
+            .unwrap();
+        store.save().unwrap();
+
+        let file = ConfigFile::parse(&format!(
+            "[auth]\njwt_secret = \"secret://jwt\"\n[secrets]\nfile = {:?}\n",
+            path.display().to_string()
+        ))
+        .unwrap();
+        let master_key = vaya_crypto::random::base64_encode(key.as_bytes());
+        let env = |name: &str| (name == "VAYA_MASTER_KEY").then(|| master_key.clone());
+        let sources = Sources::with_env(Some(&file), &env);
+        let config = Config::from_sources(&sources).unwrap();
+        assert_eq!(
+            config.auth.jwt_secret,
+            b"a-very-long-signing-secret-from-the-vault"
+        );
+        let secrets = SecretsConfig::load(&sources).unwrap();
+        assert!(!format!("{:?}", secrets).contains(&master_key));
+
+        // A reference to a missing secret, or no master key, fails loading
+        let env = |name: &str| match name {
+            "VAYA_MASTER_KEY" => Some(master_key.clone()),
+            "VAYA_DB_ENCRYPTION_KEYS" => Some("secret://db-keys".to_string()),
+            _ => None,
+        };
+        let err = Config::from_sources(&Sources::with_env(Some(&file), &env)).unwrap_err();
+        assert!(
+            err.to_string().contains("no secret named `db-keys`"),
+            "{}",
+            err
+        );
+        let no_env = |_: &str| None;
+        let err = Config::from_sources(&Sources::with_env(Some(&file), &no_env)).unwrap_err();
+        assert!(matches!(err, ConfigError::MissingRequired(_)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_invalid_file_display() {
+        let err = ConfigError::InvalidFile {
+            path: "vaya.toml".into(),
+            issues: ConfigFile::parse("[server]\nport = \"80\"\n").unwrap_err(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid configuration file vaya.toml:\n  \
+             line 2: `server.port` must be an integer from 0 to 65535, found a string \"80\""
+        );
+    }
+
     #[test]
     fn test_config_error_display() {
         let err = ConfigError::MissingRequired("TEST_KEY".into());
@@ -0,0 +1,808 @@
+//! Config file parsing and schema validation
+//!
+//! Config files are written in a subset of TOML: `[section]` headers,
+//! `key = value` pairs (dotted keys allowed), `#` comments, and values that
+//! are strings (basic or literal), integers, floats, booleans or arrays of
+//! these. Every key maps onto one of the `VAYA_*` environment variables, so
+//! a file is a way of setting them in one place; variables that are
+//! actually set still win.
+//!
+//! Parsing and validation report every problem in the file at once, each
+//! with its line number, rather than stopping at the first.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+
+use super::ConfigError;
+
+/// Type a setting's value must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Any string
+    String,
+    /// A string never printed back
+    Secret,
+    /// One of a fixed set of strings
+    OneOf(&'static [&'static str]),
+    /// An IP address, as a string
+    Ip,
+    /// A non-negative integer no larger than the bound
+    Unsigned(u64),
+    /// A number
+    Float,
+    /// `true` or `false`
+    Bool,
+    /// An array of strings (a comma-separated environment variable)
+    List,
+}
+
+impl Kind {
+    fn describe(&self) -> String {
+        match self {
+            Kind::String | Kind::Secret => "a string".into(),
+            Kind::OneOf(options) => format!("one of {}", quote_all(options)),
+            Kind::Ip => "an IP address".into(),
+            Kind::Unsigned(max) => format!("an integer from 0 to {}", max),
+            Kind::Float => "a number".into(),
+            Kind::Bool => "true or false".into(),
+            Kind::List => "an array of strings".into(),
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Kind::String | Kind::Secret, Value::String(_)) => true,
+            (Kind::OneOf(options), Value::String(s)) => options.contains(&s.as_str()),
+            (Kind::Ip, Value::String(s)) => s.parse::<IpAddr>().is_ok(),
+            (Kind::Unsigned(max), Value::Integer(n)) => *n >= 0 && (*n as u64) <= *max,
+            (Kind::Float, Value::Float(_) | Value::Integer(_)) => true,
+            (Kind::Bool, Value::Bool(_)) => true,
+            (Kind::List, Value::Array(items)) => {
+                items.iter().all(|item| matches!(item, Value::String(_)))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A setting a config file may contain
+#[derive(Debug)]
+pub(crate) struct Setting {
+    /// Dotted key in the file
+    pub(crate) key: &'static str,
+    /// Environment variable that overrides it
+    pub(crate) env: &'static str,
+    kind: Kind,
+}
+
+impl Setting {
+    /// Check if the value must not be printed
+    pub(crate) fn is_secret(&self) -> bool {
+        self.kind == Kind::Secret
+    }
+}
+
+const U16: u64 = u16::MAX as u64;
+const U32: u64 = u32::MAX as u64;
+const U64: u64 = i64::MAX as u64;
+
+const ENVIRONMENTS: &[&str] = &["development", "staging", "production"];
+const LOG_FORMATS: &[&str] = &["json", "pretty"];
+
+/// Every setting, in the order `vaya config check` lists them
+pub(crate) const SETTINGS: &[Setting] = &[
+    setting("server.environment", "VAYA_ENV", Kind::OneOf(ENVIRONMENTS)),
+    setting("server.host", "VAYA_HOST", Kind::Ip),
+    setting("server.port", "VAYA_PORT", Kind::Unsigned(U16)),
+    setting("server.workers", "VAYA_WORKERS", Kind::Unsigned(U32)),
+    setting(
+        "server.request_timeout",
+        "VAYA_REQUEST_TIMEOUT",
+        Kind::Unsigned(U64),
+    ),
+    setting(
+        "server.shutdown_timeout",
+        "VAYA_SHUTDOWN_TIMEOUT",
+        Kind::Unsigned(U64),
+    ),
+    setting(
+        "server.shutdown_grace",
+        "VAYA_SHUTDOWN_GRACE",
+        Kind::Unsigned(U64),
+    ),
+    setting("database.data_dir", "VAYA_DATA_DIR", Kind::String),
+    setting("database.wal_dir", "VAYA_WAL_DIR", Kind::String),
+    setting(
+        "database.wal_archive_dir",
+        "VAYA_WAL_ARCHIVE_DIR",
+        Kind::String,
+    ),
+    setting(
+        "database.encryption_keys",
+        "VAYA_DB_ENCRYPTION_KEYS",
+        Kind::Secret,
+    ),
+    setting(
+        "database.memtable_size",
+        "VAYA_MEMTABLE_SIZE",
+        Kind::Unsigned(U64),
+    ),
+    setting("database.bloom_fp_rate", "VAYA_BLOOM_FP_RATE", Kind::Float),
+    setting("database.compression", "VAYA_DB_COMPRESSION", Kind::Bool),
+    setting(
+        "database.compaction_threads",
+        "VAYA_COMPACTION_THREADS",
+        Kind::Unsigned(U32),
+    ),
+    setting("cache.max_size", "VAYA_CACHE_SIZE", Kind::Unsigned(U64)),
+    setting("cache.default_ttl", "VAYA_CACHE_TTL", Kind::Unsigned(U64)),
+    setting(
+        "cache.search_ttl",
+        "VAYA_SEARCH_CACHE_TTL",
+        Kind::Unsigned(U64),
+    ),
+    setting("cache.session_ttl", "VAYA_SESSION_TTL", Kind::Unsigned(U64)),
+    setting("auth.jwt_secret", "VAYA_JWT_SECRET", Kind::Secret),
+    setting(
+        "auth.access_token_ttl",
+        "VAYA_ACCESS_TOKEN_TTL",
+        Kind::Unsigned(U64),
+    ),
+    setting(
+        "auth.refresh_token_ttl",
+        "VAYA_REFRESH_TOKEN_TTL",
+        Kind::Unsigned(U64),
+    ),
+    setting(
+        "auth.password_min_length",
+        "VAYA_PASSWORD_MIN_LENGTH",
+        Kind::Unsigned(U32),
+    ),
+    setting(
+        "auth.argon2_memory",
+        "VAYA_ARGON2_MEMORY",
+        Kind::Unsigned(U32),
+    ),
+    setting(
+        "auth.argon2_iterations",
+        "VAYA_ARGON2_ITERATIONS",
+        Kind::Unsigned(U32),
+    ),
+    setting(
+        "auth.max_login_attempts",
+        "VAYA_MAX_LOGIN_ATTEMPTS",
+        Kind::Unsigned(U32),
+    ),
+    setting(
+        "auth.lockout_duration",
+        "VAYA_LOCKOUT_DURATION",
+        Kind::Unsigned(U64),
+    ),
+    setting("api.prefix", "VAYA_API_PREFIX", Kind::String),
+    setting(
+        "api.rate_limit_requests",
+        "VAYA_RATE_LIMIT_REQUESTS",
+        Kind::Unsigned(U32),
+    ),
+    setting(
+        "api.rate_limit_window",
+        "VAYA_RATE_LIMIT_WINDOW",
+        Kind::Unsigned(U64),
+    ),
+    setting("api.cors_enabled", "VAYA_CORS_ENABLED", Kind::Bool),
+    setting("api.cors_origins", "VAYA_CORS_ORIGINS", Kind::List),
+    setting("api.trusted_proxies", "VAYA_TRUSTED_PROXIES", Kind::List),
+    setting(
+        "api.max_body_size",
+        "VAYA_MAX_BODY_SIZE",
+        Kind::Unsigned(U64),
+    ),
+    setting("collector.enabled", "VAYA_COLLECTOR_ENABLED", Kind::Bool),
+    setting(
+        "collector.interval",
+        "VAYA_COLLECTOR_INTERVAL",
+        Kind::Unsigned(U64),
+    ),
+    setting(
+        "collector.batch_size",
+        "VAYA_COLLECTOR_BATCH_SIZE",
+        Kind::Unsigned(U32),
+    ),
+    setting(
+        "collector.retry_attempts",
+        "VAYA_COLLECTOR_RETRIES",
+        Kind::Unsigned(U32),
+    ),
+    setting("logging.level", "VAYA_LOG_LEVEL", Kind::String),
+    setting(
+        "logging.format",
+        "VAYA_LOG_FORMAT",
+        Kind::OneOf(LOG_FORMATS),
+    ),
+    setting("logging.timestamps", "VAYA_LOG_TIMESTAMPS", Kind::Bool),
+    setting("logging.file_info", "VAYA_LOG_FILE_INFO", Kind::Bool),
+    setting("logging.event_log", "VAYA_EVENT_LOG", Kind::String),
+    setting("secrets.file", "VAYA_SECRETS_FILE", Kind::String),
+    setting(
+        "secrets.master_key_command",
+        "VAYA_MASTER_KEY_COMMAND",
+        Kind::String,
+    ),
+];
+
+const fn setting(key: &'static str, env: &'static str, kind: Kind) -> Setting {
+    Setting { key, env, kind }
+}
+
+/// A value in a config file
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a float",
+            Value::Bool(_) => "a boolean",
+            Value::Array(_) => "an array",
+        }
+    }
+
+    /// The value as its environment variable would spell it
+    fn to_env(&self) -> String {
+        match self {
+            Value::String(s) => s.clone(),
+            Value::Integer(n) => n.to_string(),
+            Value::Float(f) => f.to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Array(items) => items
+                .iter()
+                .map(Value::to_env)
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            other => write!(f, "{}", other.to_env()),
+        }
+    }
+}
+
+/// A problem found in a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIssue {
+    /// 1-based line number
+    pub line: usize,
+    /// What is wrong
+    pub message: String,
+}
+
+impl fmt::Display for FileIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A parsed and validated config file
+#[derive(Debug, Clone, Default)]
+pub struct ConfigFile {
+    /// Values by dotted key, with the line that set them
+    values: HashMap<String, (Value, usize)>,
+}
+
+impl ConfigFile {
+    /// Read and validate the file at `path`
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::IoError(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&text).map_err(|issues| ConfigError::InvalidFile {
+            path: path.display().to_string(),
+            issues,
+        })
+    }
+
+    /// Parse and validate a file's contents
+    pub fn parse(text: &str) -> Result<Self, Vec<FileIssue>> {
+        let mut parser = Parser::new(text);
+        parser.document();
+        let mut issues = parser.issues;
+
+        for (key, (value, line)) in &parser.values {
+            let message = match SETTINGS.iter().find(|s| s.key == key) {
+                Some(setting) if setting.kind.accepts(value) => continue,
+                Some(setting) => {
+                    let found = match setting.kind {
+                        Kind::Secret => value.type_name().to_string(),
+                        _ => format!("{} {}", value.type_name(), value),
+                    };
+                    format!(
+                        "`{}` must be {}, found {}",
+                        key,
+                        setting.kind.describe(),
+                        found
+                    )
+                }
+                None => match suggest(key) {
+                    Some(near) => format!("unknown key `{}` (did you mean `{}`?)", key, near),
+                    None => format!("unknown key `{}`", key),
+                },
+            };
+            issues.push(FileIssue {
+                line: *line,
+                message,
+            });
+        }
+
+        if issues.is_empty() {
+            Ok(Self {
+                values: parser.values,
+            })
+        } else {
+            issues.sort_by_key(|issue| issue.line);
+            Err(issues)
+        }
+    }
+
+    /// Value set for the environment variable `env`, spelled as the
+    /// variable would be
+    pub(crate) fn env_value(&self, env: &str) -> Option<String> {
+        let setting = SETTINGS.iter().find(|s| s.env == env)?;
+        self.get(setting.key).map(Value::to_env)
+    }
+
+    /// Value set for a dotted key
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key).map(|(value, _)| value)
+    }
+}
+
+/// The known key closest to an unknown one, if any is close
+fn suggest(key: &str) -> Option<&'static str> {
+    let (distance, near) = SETTINGS
+        .iter()
+        .map(|s| (edit_distance(key, s.key), s.key))
+        .min()?;
+    if distance <= 3 {
+        return Some(near);
+    }
+    // The right name in the wrong section
+    let name = key.rsplit('.').next()?;
+    SETTINGS
+        .iter()
+        .find(|s| s.key.rsplit('.').next() == Some(name))
+        .map(|s| s.key)
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+fn quote_all(options: &[&str]) -> String {
+    options
+        .iter()
+        .map(|o| format!("{:?}", o))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Line-oriented TOML-subset parser that records issues and carries on
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    line: usize,
+    section: String,
+    values: HashMap<String, (Value, usize)>,
+    issues: Vec<FileIssue>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            line: 1,
+            section: String::new(),
+            values: HashMap::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    fn document(&mut self) {
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => break,
+                Some('\n') => {
+                    self.bump();
+                    continue;
+                }
+                Some('#') => {
+                    self.skip_line();
+                    continue;
+                }
+                _ => {}
+            }
+            let line = self.line;
+            let result = if self.peek() == Some('[') {
+                self.table_header()
+            } else {
+                self.key_value()
+            }
+            .and_then(|()| self.end_of_line());
+            if let Err(message) = result {
+                self.issues.push(FileIssue { line, message });
+                self.skip_line();
+            }
+        }
+    }
+
+    fn table_header(&mut self) -> Result<(), String> {
+        self.bump();
+        if self.peek() == Some('[') {
+            return Err("arrays of tables (`[[...]]`) are not supported".into());
+        }
+        self.skip_blank();
+        let name = self.key()?;
+        self.skip_blank();
+        if self.bump() != Some(']') {
+            return Err(format!("expected `]` to close section `{}`", name));
+        }
+        self.section = name;
+        Ok(())
+    }
+
+    fn key_value(&mut self) -> Result<(), String> {
+        let line = self.line;
+        let key = self.key()?;
+        self.skip_blank();
+        if self.bump() != Some('=') {
+            return Err(format!("expected `=` after key `{}`", key));
+        }
+        self.skip_blank();
+        let value = self.value()?;
+
+        let key = if self.section.is_empty() {
+            key
+        } else {
+            format!("{}.{}", self.section, key)
+        };
+        if let Some((_, first)) = self.values.get(&key) {
+            return Err(format!("`{}` is already set on line {}", key, first));
+        }
+        self.values.insert(key, (value, line));
+        Ok(())
+    }
+
+    /// A bare key, possibly dotted
+    fn key(&mut self) -> Result<String, String> {
+        let mut parts = Vec::new();
+        loop {
+            let start = self.pos;
+            while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                self.bump();
+            }
+            if start == self.pos {
+                return Err(match self.peek() {
+                    Some('"' | '\'') => "quoted keys are not supported".into(),
+                    Some(c) if c != '\n' => format!("expected a key, found `{}`", c),
+                    _ => "expected a key".into(),
+                });
+            }
+            parts.push(&self.src[start..self.pos]);
+            self.skip_blank();
+            if self.peek() != Some('.') {
+                return Ok(parts.join("."));
+            }
+            self.bump();
+            self.skip_blank();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some(c) if c == '+' || c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let word = self.word();
+                match word {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => Err(format!(
+                        "expected a value, found `{}` (strings must be quoted)",
+                        word
+                    )),
+                }
+            }
+            _ => Err("expected a value".into()),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.bump();
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err("unterminated string".into()),
+                Some('"') => return Ok(out),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('u') => {
+                            let start = self.pos;
+                            for _ in 0..4 {
+                                self.bump();
+                            }
+                            u32::from_str_radix(&self.src[start..self.pos], 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or("invalid `\\u` escape")?
+                        }
+                        Some(c) => return Err(format!("invalid escape `\\{}`", c)),
+                        None => return Err("unterminated string".into()),
+                    };
+                    out.push(escaped);
+                }
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.bump();
+        let start = self.pos;
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err("unterminated string".into()),
+                Some('\'') => return Ok(self.src[start..self.pos - 1].to_string()),
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.bump();
+        let mut items = Vec::new();
+        loop {
+            self.skip_space_in_array();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_space_in_array();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                _ => return Err("expected `,` or `]` in array".into()),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let word = self.word();
+        let digits = word.replace('_', "");
+        let is_float = digits.contains(['.', 'e', 'E']);
+        let parsed = if is_float {
+            digits.parse().ok().map(Value::Float)
+        } else {
+            digits.parse().ok().map(Value::Integer)
+        };
+        parsed.ok_or_else(|| format!("invalid number `{}`", word))
+    }
+
+    /// Run of characters up to whitespace, a comment or a delimiter
+    fn word(&mut self) -> &'a str {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if !c.is_whitespace() && !matches!(c, '#' | ',' | ']'))
+        {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_blank();
+        match self.peek() {
+            None | Some('\n') | Some('#') => {
+                self.skip_line();
+                Ok(())
+            }
+            Some(_) => {
+                let rest = self.src[self.pos..].lines().next().unwrap_or_default();
+                let rest = rest.split('#').next().unwrap_or_default().trim();
+                Err(format!("unexpected `{}` after value", rest))
+            }
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\r')) {
+            self.bump();
+        }
+    }
+
+    /// Arrays may span lines and hold comments
+    fn skip_space_in_array(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Skip past the end of the current line
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let file = ConfigFile::parse(
+            r#"
+# Staging node
+[server]
+environment = "staging"
+port = 8_081   # behind the proxy
+
+[api]
+rate_limit_requests = 250
+cors_origins = [
+    "https://vaya.example",
+    'https://admin.vaya.example',  # trailing comma
+]
+
+[database]
+bloom_fp_rate = 0.02
+compression = false
+logging.level = "never read: a key under [database]"
+"#,
+        );
+        let issues = file.as_ref().unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].line, 17);
+        assert!(issues[0].message.contains("database.logging.level"));
+        assert!(issues[0].message.contains("did you mean `logging.level`?"));
+
+        let file = ConfigFile::parse(
+            "[server]\nenvironment = \"staging\"\nport = 8_081\n\
+             [api]\ncors_origins = [\"https://a\", 'https://b']\n\
+             [database]\nbloom_fp_rate = 0.02\ncompression = false\n",
+        )
+        .unwrap();
+        assert_eq!(file.env_value("VAYA_ENV").as_deref(), Some("staging"));
+        assert_eq!(file.env_value("VAYA_PORT").as_deref(), Some("8081"));
+        assert_eq!(
+            file.env_value("VAYA_CORS_ORIGINS").as_deref(),
+            Some("https://a,https://b")
+        );
+        assert_eq!(
+            file.env_value("VAYA_BLOOM_FP_RATE").as_deref(),
+            Some("0.02")
+        );
+        assert_eq!(
+            file.env_value("VAYA_DB_COMPRESSION").as_deref(),
+            Some("false")
+        );
+        assert_eq!(file.env_value("VAYA_HOST"), None);
+    }
+
+    #[test]
+    fn test_issues_name_line_and_key() {
+        let issues = ConfigFile::parse(
+            "[server]\n\
+             prot = 8080\n\
+             port = 70000\n\
+             environment = \"prod\"\n\
+             host = localhost\n\
+             [auth]\n\
+             jwt_secret = 42\n\
+             [api]\n\
+             rate_limit_requests = \"100\"\n\
+             rate_limit_requests = 100\n\
+             prefix = \"/api\" extra\n",
+        )
+        .unwrap_err();
+        let messages: Vec<String> = issues.iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "line 2: unknown key `server.prot` (did you mean `server.port`?)",
+                "line 3: `server.port` must be an integer from 0 to 65535, found an integer 70000",
+                "line 4: `server.environment` must be one of \"development\", \"staging\", \
+                 \"production\", found a string \"prod\"",
+                "line 5: expected a value, found `localhost` (strings must be quoted)",
+                "line 7: `auth.jwt_secret` must be a string, found an integer",
+                "line 9: `api.rate_limit_requests` must be an integer from 0 to 4294967295, \
+                 found a string \"100\"",
+                "line 10: `api.rate_limit_requests` is already set on line 9",
+                "line 11: unexpected `extra` after value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_setting_is_unique() {
+        for (i, setting) in SETTINGS.iter().enumerate() {
+            assert!(SETTINGS[i + 1..]
+                .iter()
+                .all(|s| s.key != setting.key && s.env != setting.env));
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Alert handlers
 
-use vaya_api::{ApiError, ApiResult, JsonSerialize, Request, Response};
+use vaya_api::{ApiError, ApiResult, JsonObject, JsonSerialize, JsonValue, Request, Response};
 
 /// Create a new price alert
 pub fn create_alert(req: &Request) -> ApiResult<Response> {
@@ -121,17 +121,16 @@ pub struct AlertResponse {
 }
 
 impl JsonSerialize for AlertResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"id":"{}","origin":"{}","destination":"{}","target_price_cents":{},"current_price_cents":{},"status":"{}","created_at":"{}"}}"#,
-            self.id,
-            self.origin,
-            self.destination,
-            self.target_price_cents,
-            self.current_price_cents,
-            self.status,
-            self.created_at
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("origin", &self.origin)
+            .field("destination", &self.destination)
+            .field("target_price_cents", self.target_price_cents)
+            .field("current_price_cents", self.current_price_cents)
+            .field("status", &self.status)
+            .field("created_at", &self.created_at)
+            .build()
     }
 }
 
@@ -145,15 +144,13 @@ pub struct AlertsListResponse {
 }
 
 impl JsonSerialize for AlertsListResponse {
-    fn to_json(&self) -> String {
-        let alerts: Vec<String> = self.alerts.iter().map(|a| a.to_json()).collect();
-        format!(
-            r#"{{"alerts":[{}],"total":{},"page":{},"page_size":{}}}"#,
-            alerts.join(","),
-            self.total,
-            self.page,
-            self.page_size
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .array("alerts", &self.alerts)
+            .field("total", self.total)
+            .field("page", self.page)
+            .field("page_size", self.page_size)
+            .build()
     }
 }
 
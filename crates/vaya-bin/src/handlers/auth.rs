@@ -1,6 +1,8 @@
 //! Authentication handlers
 
-use vaya_api::{ApiError, ApiResult, FieldError, JsonSerialize, Request, Response};
+use vaya_api::{
+    ApiError, ApiResult, FieldError, JsonObject, JsonSerialize, JsonValue, Request, Response,
+};
 
 /// Register a new user
 pub fn register(req: &Request) -> ApiResult<Response> {
@@ -135,15 +137,14 @@ pub struct AuthResponse {
 }
 
 impl JsonSerialize for AuthResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"user_id":"{}","email":"{}","access_token":"{}","refresh_token":"{}","expires_in":{}}}"#,
-            self.user_id,
-            escape_json(&self.email),
-            self.access_token,
-            self.refresh_token,
-            self.expires_in
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("user_id", &self.user_id)
+            .field("email", &self.email)
+            .field("access_token", &self.access_token)
+            .field("refresh_token", &self.refresh_token)
+            .field("expires_in", self.expires_in)
+            .build()
     }
 }
 
@@ -155,11 +156,11 @@ pub struct TokenRefreshResponse {
 }
 
 impl JsonSerialize for TokenRefreshResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"access_token":"{}","expires_in":{}}}"#,
-            self.access_token, self.expires_in
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("access_token", &self.access_token)
+            .field("expires_in", self.expires_in)
+            .build()
     }
 }
 
@@ -233,15 +234,6 @@ fn generate_user_id() -> String {
     format!("usr-{:x}", timestamp)
 }
 
-/// Escape JSON string
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
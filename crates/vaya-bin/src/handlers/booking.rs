@@ -1,6 +1,6 @@
 //! Booking handlers
 
-use vaya_api::{ApiError, ApiResult, JsonSerialize, Request, Response};
+use vaya_api::{ApiError, ApiResult, JsonObject, JsonSerialize, JsonValue, Request, Response};
 
 /// Create a new booking
 pub fn create_booking(req: &Request) -> ApiResult<Response> {
@@ -118,11 +118,13 @@ pub struct BookingResponse {
 }
 
 impl JsonSerialize for BookingResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"id":"{}","pnr":"{}","status":"{}","created_at":"{}"}}"#,
-            self.id, self.pnr, self.status, self.created_at
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("pnr", &self.pnr)
+            .field("status", &self.status)
+            .field("created_at", &self.created_at)
+            .build()
     }
 }
 
@@ -136,15 +138,13 @@ pub struct BookingsListResponse {
 }
 
 impl JsonSerialize for BookingsListResponse {
-    fn to_json(&self) -> String {
-        let bookings: Vec<String> = self.bookings.iter().map(|b| b.to_json()).collect();
-        format!(
-            r#"{{"bookings":[{}],"total":{},"page":{},"page_size":{}}}"#,
-            bookings.join(","),
-            self.total,
-            self.page,
-            self.page_size
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .array("bookings", &self.bookings)
+            .field("total", self.total)
+            .field("page", self.page)
+            .field("page_size", self.page_size)
+            .build()
     }
 }
 
@@ -1,6 +1,6 @@
 //! Health check handlers
 
-use vaya_api::{ApiResult, JsonSerialize, Request, Response};
+use vaya_api::{ApiResult, JsonObject, JsonSerialize, JsonValue, Request, Response};
 
 /// Health response
 #[derive(Debug, Clone)]
@@ -11,11 +11,12 @@ pub struct HealthResponse {
 }
 
 impl JsonSerialize for HealthResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"status":"{}","version":"{}","uptime_seconds":{}}}"#,
-            self.status, self.version, self.uptime_seconds
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("status", &self.status)
+            .field("version", &self.version)
+            .field("uptime_seconds", self.uptime_seconds)
+            .build()
     }
 }
 
@@ -27,13 +28,11 @@ pub struct ReadyResponse {
 }
 
 impl JsonSerialize for ReadyResponse {
-    fn to_json(&self) -> String {
-        let checks: Vec<String> = self.checks.iter().map(|c| c.to_json()).collect();
-        format!(
-            r#"{{"ready":{},"checks":[{}]}}"#,
-            self.ready,
-            checks.join(",")
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("ready", self.ready)
+            .array("checks", &self.checks)
+            .build()
     }
 }
 
@@ -46,16 +45,12 @@ pub struct CheckResult {
 }
 
 impl JsonSerialize for CheckResult {
-    fn to_json(&self) -> String {
-        match &self.message {
-            Some(msg) => format!(
-                r#"{{"name":"{}","status":"{}","message":"{}"}}"#,
-                self.name,
-                self.status,
-                escape_json(msg)
-            ),
-            None => format!(r#"{{"name":"{}","status":"{}"}}"#, self.name, self.status),
-        }
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("name", &self.name)
+            .field("status", &self.status)
+            .field_if_some("message", self.message.as_ref())
+            .build()
     }
 }
 
@@ -113,15 +108,6 @@ pub fn live(_req: &Request) -> ApiResult<Response> {
     Ok(response)
 }
 
-/// Escape JSON string
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
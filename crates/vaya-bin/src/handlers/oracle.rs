@@ -1,6 +1,6 @@
 //! Oracle (pricing insights) handlers
 
-use vaya_api::{ApiError, ApiResult, JsonSerialize, Request, Response};
+use vaya_api::{ApiError, ApiResult, JsonObject, JsonSerialize, JsonValue, Request, Response};
 
 /// Get price prediction
 pub fn get_prediction(req: &Request) -> ApiResult<Response> {
@@ -113,19 +113,18 @@ pub struct PredictionResponse {
 }
 
 impl JsonSerialize for PredictionResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"origin":"{}","destination":"{}","date":"{}","predicted_price_cents":{},"confidence":{},"trend":"{}","recommendation":"{}","price_range_low_cents":{},"price_range_high_cents":{}}}"#,
-            self.origin,
-            self.destination,
-            self.date,
-            self.predicted_price_cents,
-            self.confidence,
-            self.trend,
-            self.recommendation,
-            self.price_range_low_cents,
-            self.price_range_high_cents
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("origin", &self.origin)
+            .field("destination", &self.destination)
+            .field("date", &self.date)
+            .field("predicted_price_cents", self.predicted_price_cents)
+            .field("confidence", self.confidence)
+            .field("trend", &self.trend)
+            .field("recommendation", &self.recommendation)
+            .field("price_range_low_cents", self.price_range_low_cents)
+            .field("price_range_high_cents", self.price_range_high_cents)
+            .build()
     }
 }
 
@@ -143,18 +142,17 @@ pub struct InsightsResponse {
 }
 
 impl JsonSerialize for InsightsResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"origin":"{}","destination":"{}","cheapest_day":"{}","most_expensive_day":"{}","average_price_cents":{},"price_volatility":"{}","best_advance_days":{},"season":"{}"}}"#,
-            self.origin,
-            self.destination,
-            self.cheapest_day,
-            self.most_expensive_day,
-            self.average_price_cents,
-            self.price_volatility,
-            self.best_advance_days,
-            self.season
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("origin", &self.origin)
+            .field("destination", &self.destination)
+            .field("cheapest_day", &self.cheapest_day)
+            .field("most_expensive_day", &self.most_expensive_day)
+            .field("average_price_cents", self.average_price_cents)
+            .field("price_volatility", &self.price_volatility)
+            .field("best_advance_days", self.best_advance_days)
+            .field("season", &self.season)
+            .build()
     }
 }
 
@@ -171,17 +169,16 @@ pub struct BestTimeResponse {
 }
 
 impl JsonSerialize for BestTimeResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"origin":"{}","destination":"{}","departure_date":"{}","recommended_booking_date":"{}","expected_price_cents":{},"savings_percent":{},"confidence":{}}}"#,
-            self.origin,
-            self.destination,
-            self.departure_date,
-            self.recommended_booking_date,
-            self.expected_price_cents,
-            self.savings_percent,
-            self.confidence
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("origin", &self.origin)
+            .field("destination", &self.destination)
+            .field("departure_date", &self.departure_date)
+            .field("recommended_booking_date", &self.recommended_booking_date)
+            .field("expected_price_cents", self.expected_price_cents)
+            .field("savings_percent", self.savings_percent)
+            .field("confidence", self.confidence)
+            .build()
     }
 }
 
@@ -1,6 +1,6 @@
 //! Pool (group buying) handlers
 
-use vaya_api::{ApiError, ApiResult, JsonSerialize, Request, Response};
+use vaya_api::{ApiError, ApiResult, JsonObject, JsonSerialize, JsonValue, Request, Response};
 
 /// Create a new pool
 pub fn create_pool(req: &Request) -> ApiResult<Response> {
@@ -112,16 +112,15 @@ pub struct PoolResponse {
 }
 
 impl JsonSerialize for PoolResponse {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"id":"{}","status":"{}","members":{},"target_size":{},"current_price_cents":{},"created_at":"{}"}}"#,
-            self.id,
-            self.status,
-            self.members,
-            self.target_size,
-            self.current_price_cents,
-            self.created_at
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("status", &self.status)
+            .field("members", self.members)
+            .field("target_size", self.target_size)
+            .field("current_price_cents", self.current_price_cents)
+            .field("created_at", &self.created_at)
+            .build()
     }
 }
 
@@ -135,15 +134,13 @@ pub struct PoolsListResponse {
 }
 
 impl JsonSerialize for PoolsListResponse {
-    fn to_json(&self) -> String {
-        let pools: Vec<String> = self.pools.iter().map(|p| p.to_json()).collect();
-        format!(
-            r#"{{"pools":[{}],"total":{},"page":{},"page_size":{}}}"#,
-            pools.join(","),
-            self.total,
-            self.page,
-            self.page_size
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .array("pools", &self.pools)
+            .field("total", self.total)
+            .field("page", self.page)
+            .field("page_size", self.page_size)
+            .build()
     }
 }
 
@@ -1,6 +1,8 @@
 //! Search handlers
 
-use vaya_api::{ApiError, ApiResult, JsonSerialize, Request, Response};
+use vaya_api::{ApiError, ApiResult, JsonObject, JsonSerialize, JsonValue, Request, Response};
+use vaya_common::refdata::{self, AirportInfo};
+use vaya_search::SuggestionKind;
 
 /// Search flights
 pub fn search_flights(req: &Request) -> ApiResult<Response> {
@@ -33,8 +35,20 @@ pub fn search_airports(req: &Request) -> ApiResult<Response> {
         ));
     }
 
-    // TODO: Search airports
-    let response_body = SearchAirportsResponse { airports: vec![] };
+    // City suggestions expand to their airports
+    let mut airports: Vec<AirportResult> = Vec::new();
+    for suggestion in vaya_search::airports::global().suggest(&query, "en", None) {
+        let codes = match suggestion.kind {
+            SuggestionKind::City => suggestion.airports,
+            SuggestionKind::Airport => vec![suggestion.code],
+        };
+        for airport in codes.into_iter().filter_map(refdata::airport) {
+            if airports.iter().all(|a| a.code != airport.code) {
+                airports.push(AirportResult::from(airport));
+            }
+        }
+    }
+    let response_body = SearchAirportsResponse { airports };
 
     let mut response = Response::ok();
     response.set_json_body(&response_body);
@@ -51,8 +65,15 @@ pub fn search_airlines(req: &Request) -> ApiResult<Response> {
         ));
     }
 
-    // TODO: Search airlines
-    let response_body = SearchAirlinesResponse { airlines: vec![] };
+    let response_body = SearchAirlinesResponse {
+        airlines: refdata::find_airlines(&query, 10)
+            .into_iter()
+            .map(|a| AirlineResult {
+                code: a.code.into(),
+                name: a.name.into(),
+            })
+            .collect(),
+    };
 
     let mut response = Response::ok();
     response.set_json_body(&response_body);
@@ -68,14 +89,12 @@ pub struct SearchFlightsResponse {
 }
 
 impl JsonSerialize for SearchFlightsResponse {
-    fn to_json(&self) -> String {
-        let results: Vec<String> = self.results.iter().map(|r| r.to_json()).collect();
-        format!(
-            r#"{{"results":[{}],"total":{},"search_id":"{}"}}"#,
-            results.join(","),
-            self.total,
-            self.search_id
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .array("results", &self.results)
+            .field("total", self.total)
+            .field("search_id", &self.search_id)
+            .build()
     }
 }
 
@@ -94,19 +113,18 @@ pub struct FlightResult {
 }
 
 impl JsonSerialize for FlightResult {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"id":"{}","origin":"{}","destination":"{}","departure":"{}","arrival":"{}","price_cents":{},"currency":"{}","airline":"{}","stops":{}}}"#,
-            self.id,
-            self.origin,
-            self.destination,
-            self.departure,
-            self.arrival,
-            self.price_cents,
-            self.currency,
-            self.airline,
-            self.stops
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("origin", &self.origin)
+            .field("destination", &self.destination)
+            .field("departure", &self.departure)
+            .field("arrival", &self.arrival)
+            .field("price_cents", self.price_cents)
+            .field("currency", &self.currency)
+            .field("airline", &self.airline)
+            .field("stops", self.stops)
+            .build()
     }
 }
 
@@ -117,9 +135,8 @@ pub struct SearchAirportsResponse {
 }
 
 impl JsonSerialize for SearchAirportsResponse {
-    fn to_json(&self) -> String {
-        let airports: Vec<String> = self.airports.iter().map(|a| a.to_json()).collect();
-        format!(r#"{{"airports":[{}]}}"#, airports.join(","))
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new().array("airports", &self.airports).build()
     }
 }
 
@@ -130,14 +147,30 @@ pub struct AirportResult {
     pub name: String,
     pub city: String,
     pub country: String,
+    pub timezone: String,
+}
+
+impl From<&AirportInfo> for AirportResult {
+    fn from(airport: &AirportInfo) -> Self {
+        Self {
+            code: airport.code.into(),
+            name: airport.name.into(),
+            city: airport.city.into(),
+            country: airport.country.into(),
+            timezone: airport.timezone.into(),
+        }
+    }
 }
 
 impl JsonSerialize for AirportResult {
-    fn to_json(&self) -> String {
-        format!(
-            r#"{{"code":"{}","name":"{}","city":"{}","country":"{}"}}"#,
-            self.code, self.name, self.city, self.country
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("code", &self.code)
+            .field("name", &self.name)
+            .field("city", &self.city)
+            .field("country", &self.country)
+            .field("timezone", &self.timezone)
+            .build()
     }
 }
 
@@ -148,9 +181,8 @@ pub struct SearchAirlinesResponse {
 }
 
 impl JsonSerialize for SearchAirlinesResponse {
-    fn to_json(&self) -> String {
-        let airlines: Vec<String> = self.airlines.iter().map(|a| a.to_json()).collect();
-        format!(r#"{{"airlines":[{}]}}"#, airlines.join(","))
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new().array("airlines", &self.airlines).build()
     }
 }
 
@@ -162,8 +194,11 @@ pub struct AirlineResult {
 }
 
 impl JsonSerialize for AirlineResult {
-    fn to_json(&self) -> String {
-        format!(r#"{{"code":"{}","name":"{}"}}"#, self.code, self.name)
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("code", &self.code)
+            .field("name", &self.name)
+            .build()
     }
 }
 
@@ -189,11 +224,28 @@ mod tests {
         assert!(matches!(result, Err(ApiError::BadRequest(_))));
     }
 
+    #[test]
+    fn test_search_airports_expands_cities() {
+        let mut req = Request::new("GET", "/search/airports");
+        req.query_params.insert("q".into(), "london".into());
+        let response = search_airports(&req).unwrap();
+        let body = String::from_utf8_lossy(&response.body);
+        assert!(body.contains(r#""code":"LHR""#));
+        assert!(body.contains(r#""code":"LGW""#));
+        assert!(body.contains(r#""timezone":"Europe/London""#));
+    }
+
     #[test]
     fn test_search_airlines_missing_query() {
         let req = Request::new("GET", "/search/airlines");
         let result = search_airlines(&req);
         assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let mut req = Request::new("GET", "/search/airlines");
+        req.query_params.insert("q".into(), "qatar".into());
+        let response = search_airlines(&req).unwrap();
+        let body = String::from_utf8_lossy(&response.body);
+        assert!(body.contains(r#"{"code":"QR","name":"Qatar Airways"}"#));
     }
 
     #[test]
@@ -1,6 +1,8 @@
 //! User profile handlers
 
-use vaya_api::{ApiError, ApiResult, FieldError, JsonSerialize, Request, Response};
+use vaya_api::{
+    ApiError, ApiResult, FieldError, JsonObject, JsonSerialize, JsonValue, Request, Response,
+};
 
 /// Get user profile
 pub fn get_profile(req: &Request) -> ApiResult<Response> {
@@ -115,24 +117,15 @@ pub struct UserProfile {
 }
 
 impl JsonSerialize for UserProfile {
-    fn to_json(&self) -> String {
-        let name = match &self.name {
-            Some(n) => format!(r#""{}""#, escape_json(n)),
-            None => "null".into(),
-        };
-        let phone = match &self.phone {
-            Some(p) => format!(r#""{}""#, escape_json(p)),
-            None => "null".into(),
-        };
-        format!(
-            r#"{{"id":"{}","email":"{}","name":{},"phone":{},"created_at":"{}","updated_at":"{}"}}"#,
-            self.id,
-            escape_json(&self.email),
-            name,
-            phone,
-            self.created_at,
-            self.updated_at
-        )
+    fn to_json_value(&self) -> JsonValue {
+        JsonObject::new()
+            .field("id", &self.id)
+            .field("email", &self.email)
+            .field("name", self.name.as_ref())
+            .field("phone", self.phone.as_ref())
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .build()
     }
 }
 
@@ -170,15 +163,6 @@ fn is_valid_phone(phone: &str) -> bool {
         .all(|c| c.is_ascii_digit() || *c == ' ' || *c == '-')
 }
 
-/// Escape JSON string
-fn escape_json(s: &str) -> String {
-    s.replace('\\', "\\\\")
-        .replace('"', "\\\"")
-        .replace('\n', "\\n")
-        .replace('\r', "\\r")
-        .replace('\t', "\\t")
-}
-
 /// Get current timestamp
 fn current_timestamp() -> String {
     use time::OffsetDateTime;
@@ -0,0 +1,89 @@
+//! Tracing layer that copies events into the live-tail log buffer
+
+use std::fmt::Write as _;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use vaya_common::logbuf::{self, LogLevel};
+
+/// Copies every log event into [`vaya_common::logbuf::global`]
+pub struct LogBufferLayer;
+
+impl<S: Subscriber> Layer<S> for LogBufferLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            Level::TRACE => LogLevel::Trace,
+            Level::DEBUG => LogLevel::Debug,
+            Level::INFO => LogLevel::Info,
+            Level::WARN => LogLevel::Warn,
+            Level::ERROR => LogLevel::Error,
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        logbuf::global().push(level, metadata.target(), visitor.finish());
+    }
+}
+
+/// Renders the message followed by `key=value` fields
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        if self.fields.is_empty() {
+            self.message
+        } else if self.message.is_empty() {
+            self.fields.trim_start().to_string()
+        } else {
+            format!("{}{}", self.message, self.fields)
+        }
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn test_layer_captures_events() {
+        let subscriber = tracing_subscriber::registry().with(LogBufferLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(table = "users", "compaction slow");
+        });
+
+        let batch = logbuf::global().read_since(0, &Default::default(), 1000);
+        let entry = batch
+            .entries
+            .iter()
+            .rev()
+            .find(|e| e.message.starts_with("compaction slow"))
+            .unwrap();
+        assert_eq!(entry.level, LogLevel::Warn);
+        assert_eq!(entry.message, "compaction slow table=users");
+    }
+}
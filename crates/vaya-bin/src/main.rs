@@ -11,26 +11,80 @@
 //! # Run with custom port
 //! VAYA_PORT=3000 vaya serve
 //!
-//! # Run database migrations
+//! # Settings from a file, with environment variables taking precedence
+//! VAYA_CONFIG=/etc/vaya/vaya.toml vaya serve
+//!
+//! # Validate a config file and show where each setting comes from
+//! vaya config check /etc/vaya/vaya.toml
+//!
+//! # Seal a secret and refer to it from configuration
+//! VAYA_MASTER_KEY=... vaya secrets set jwt
+//! VAYA_MASTER_KEY=... VAYA_JWT_SECRET=secret://jwt vaya serve
+//!
+//! # Run database migrations (or see what would run, or undo them)
 //! vaya migrate
+//! vaya migrate --dry-run
+//! vaya migrate status
+//! vaya migrate rollback --to 1
+//!
+//! # Back up the database (incremental after the first), then restore it
+//! vaya backup /var/backups/vaya
+//! vaya restore --backup /var/backups/vaya --id 3 --target ./data/restored
+//!
+//! # Point-in-time restore from a checkpoint plus archived WAL
+//! vaya restore --base ./backup --archive ./wal-archive --to-timestamp 2026-01-01T14:31:00Z
+//!
+//! # Export bookings for finance (personal data masked), then load them elsewhere
+//! vaya export --table bookings --format csv --since 2025-01-01 --redact mask --output bookings.csv
+//! vaya import --table bookings --format csv --input bookings.csv --dry-run
+//!
+//! # Re-encrypt database files under the active key after a rotation
+//! VAYA_DB_ENCRYPTION_KEYS=1:...,2:... vaya reencrypt
+//!
+//! # Load reproducible demo data (same seed, same rows)
+//! vaya seed --seed 42 --scale 2
 //!
 //! # Show version
 //! vaya version
 //! ```
+//!
+//! A running server reloads its configuration on SIGHUP and applies the log
+//! level, rate limits and cache TTLs; other settings need a restart.
 
 mod app;
 mod config;
 mod handlers;
+mod log_buffer;
+mod migrations;
 mod routes;
+mod seed;
+mod shutdown;
 
 use std::env;
+use std::path::PathBuf;
 use std::process::ExitCode;
+use std::sync::{Arc, OnceLock};
 
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 use tracing::{error, info, warn};
 use tracing_subscriber::prelude::*;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+use vaya_common::events::{self, EventSink, WriterSink};
+use vaya_db::recovery::{self, RecoveryTarget};
+use vaya_db::{BackupEngine, DbConfig, DirectoryArchive, VayaDb};
+use vaya_store::migration::Direction;
+use vaya_store::query::Condition;
+use vaya_store::schema::Value;
+use vaya_store::{
+    DataFormat, ImportOptions, PiiClass, Query, RedactionAction, RedactionPolicy, Table,
+};
 
-use crate::config::Config;
+use crate::config::{Config, ConfigFile, LogConfig, Origin, SecretsConfig};
+use crate::shutdown::HookResult;
+
+/// Handle for swapping the log filter when the configuration is reloaded
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
 /// Main entry point
 fn main() -> ExitCode {
@@ -40,7 +94,15 @@ fn main() -> ExitCode {
 
     match command {
         "serve" | "server" | "run" => run_server(),
-        "migrate" => run_migrations(),
+        "migrate" => run_migrations(&args[2..]),
+        "backup" => run_backup(&args[2..]),
+        "restore" => run_restore(&args[2..]),
+        "export" => run_export(&args[2..]),
+        "import" => run_import(&args[2..]),
+        "reencrypt" => run_reencrypt(),
+        "seed" => run_seed(&args[2..]),
+        "config" => run_config(&args[2..]),
+        "secrets" => run_secrets(&args[2..]),
         "version" | "-v" | "--version" => show_version(),
         "help" | "-h" | "--help" => show_help(),
         "check" => run_health_check(),
@@ -54,32 +116,28 @@ fn main() -> ExitCode {
 
 /// Run the HTTP server
 fn run_server() -> ExitCode {
-    // Initialize logging
-    if let Err(e) = init_logging() {
-        eprintln!("Failed to initialize logging: {}", e);
-        return ExitCode::from(1);
-    }
-
-    info!(version = env!("CARGO_PKG_VERSION"), "Starting VAYA server");
-
-    // Load configuration
-    let config = match Config::from_env() {
+    // Load configuration, then log as it says
+    let config = match load_config() {
         Ok(c) => c,
-        Err(e) => {
-            error!(error = %e, "Failed to load configuration");
-            return ExitCode::from(1);
-        }
+        Err(code) => return code,
     };
 
+    info!(version = env!("CARGO_PKG_VERSION"), "Starting VAYA server");
+
     info!(
         environment = %config.server.environment,
         bind_addr = %config.server.bind_addr,
         "Configuration loaded"
     );
 
+    if let Err(e) = init_event_log(config.logging.event_log.as_deref()) {
+        error!(error = %e, "Failed to open domain event log");
+        return ExitCode::from(1);
+    }
+
     // Build application
-    let _app = match app::App::new(config.clone()) {
-        Ok(a) => a,
+    let app = match app::App::new(config.clone()) {
+        Ok(a) => Arc::new(a),
         Err(e) => {
             error!(error = %e, "Failed to initialize application");
             return ExitCode::from(1);
@@ -101,56 +159,999 @@ fn run_server() -> ExitCode {
         }
     };
 
-    rt.block_on(async {
+    let result = rt.block_on(async {
         info!(
             addr = %config.server.bind_addr,
             workers = config.server.workers,
             "Server starting"
         );
+        #[cfg(unix)]
+        tokio::spawn(reload_on_hangup(Arc::clone(&app)));
+        app.serve(termination_signal()).await
+    });
 
-        // In a real implementation, this would use vaya-net's TcpListener
-        // For now, we just simulate server startup
-        info!("Server ready to accept connections");
+    match result {
+        Ok(report) if report.is_clean() => {
+            info!("Server shutdown complete");
+            ExitCode::SUCCESS
+        }
+        Ok(report) => {
+            for outcome in &report.outcomes {
+                info!(hook = %outcome.name, phase = %outcome.phase, result = ?outcome.result, "Shutdown hook");
+            }
+            if report
+                .get("database")
+                .is_some_and(|outcome| outcome.result != HookResult::Completed)
+            {
+                error!("Database did not close cleanly; its WAL will be replayed on next start");
+            }
+            warn!("Server shutdown completed with errors");
+            ExitCode::from(1)
+        }
+        Err(e) => {
+            error!(error = %e, "Server failed");
+            ExitCode::from(1)
+        }
+    }
+}
 
-        // Wait for shutdown signal
-        tokio::signal::ctrl_c().await.ok();
-        info!("Received shutdown signal");
-    });
+/// Resolve on Ctrl-C, or SIGTERM where there is one
+async fn termination_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            info!("Received shutdown signal");
+            return;
+        }
+    }
+    tokio::signal::ctrl_c().await.ok();
+    info!("Received shutdown signal");
+}
 
-    info!("Server shutdown complete");
-    ExitCode::SUCCESS
+/// Reload the configuration on each SIGHUP
+#[cfg(unix)]
+async fn reload_on_hangup(app: Arc<app::App>) {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            warn!(error = %e, "Configuration reload on SIGHUP unavailable");
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        info!("Received SIGHUP, reloading configuration");
+        reload_config(&app);
+    }
 }
 
-/// Run database migrations
-fn run_migrations() -> ExitCode {
-    if let Err(e) = init_logging() {
-        eprintln!("Failed to initialize logging: {}", e);
-        return ExitCode::from(1);
+/// Load the configuration again and apply what can change at runtime
+///
+/// A configuration that fails to load or validate changes nothing.
+fn reload_config(app: &app::App) {
+    let config = match Config::load() {
+        Ok(c) => c,
+        Err(e) => {
+            error!(error = %e, "Configuration reload failed, keeping current settings");
+            return;
+        }
+    };
+    let filter = match EnvFilter::try_new(&config.logging.level) {
+        Ok(filter) => filter,
+        Err(e) => {
+            error!(error = %e, level = %config.logging.level, "Invalid log level, keeping current settings");
+            return;
+        }
+    };
+
+    let changed = app.reload(&config);
+    if changed.contains(&"logging.level") {
+        if let Some(Err(e)) = LOG_FILTER.get().map(|handle| handle.reload(filter)) {
+            warn!(error = %e, "Failed to apply log level");
+        }
+    }
+    if changed.is_empty() {
+        info!("Configuration reloaded, no runtime settings changed");
+        return;
+    }
+    let settings = app.state.settings();
+    info!(
+        changed = %changed.join(", "),
+        log_level = %settings.log_level,
+        rate_limit = %format!("{}/{}s", settings.rate_limit_requests, settings.rate_limit_window),
+        cache_ttl = settings.cache_ttl,
+        search_cache_ttl = settings.search_cache_ttl,
+        session_cache_ttl = settings.session_cache_ttl,
+        "Configuration reloaded"
+    );
+}
+
+/// Show, apply or roll back schema migrations
+///
+/// `vaya migrate [--to <version>]` applies pending migrations, `rollback
+/// --to <version>` undoes those above the version, and `status` lists
+/// them. `--dry-run` prints the plan without running it.
+fn run_migrations(args: &[String]) -> ExitCode {
+    let config = match load_config() {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+
+    let command = args
+        .first()
+        .map(|s| s.as_str())
+        .filter(|a| !a.starts_with("--"));
+    let target = match flag_value(args, "--to").map(str::parse::<u64>) {
+        Some(Ok(version)) => Some(version),
+        Some(Err(_)) => {
+            error!("Invalid --to version");
+            return ExitCode::from(2);
+        }
+        None => None,
+    };
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+
+    let db = match VayaDb::open(db_config(&config, &config.database.data_dir)) {
+        Ok(db) => Arc::new(db),
+        Err(e) => {
+            error!(error = %e, "Failed to open database");
+            return ExitCode::from(1);
+        }
+    };
+    let migrator = migrations::migrator(Arc::clone(&db));
+
+    let result = match (command, target) {
+        (Some("status"), _) => migrator.status().map(|status| {
+            println!(
+                "Schema version {} (latest {})",
+                status.current, status.latest
+            );
+            for applied in &status.applied {
+                println!(
+                    "  applied  {:>4}  {}  {}",
+                    applied.version,
+                    applied.name,
+                    format_millis(applied.applied_at.max(0) as u64)
+                );
+            }
+            for (version, name) in &status.pending {
+                println!("  pending  {:>4}  {}", version, name);
+            }
+        }),
+        (Some("rollback"), Some(target)) => migrator
+            .rollback(target, dry_run)
+            .map(|report| log_migrations(&report)),
+        (Some("rollback"), None) => {
+            error!("rollback needs --to <version>");
+            return ExitCode::from(2);
+        }
+        (None | Some("up"), _) => migrator
+            .migrate(target, dry_run)
+            .map(|report| log_migrations(&report)),
+        (Some(other), _) => {
+            error!(command = %other, "Usage: vaya migrate [up|status|rollback] [--to <version>] [--dry-run]");
+            return ExitCode::from(2);
+        }
+    };
+
+    let closed = db.close();
+    match result
+        .map_err(|e| e.to_string())
+        .and(closed.map_err(|e| e.to_string()))
+    {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!(error = %e, "Migration failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Log the migrations a run applied, rolled back or would have
+fn log_migrations(report: &vaya_store::MigrationReport) {
+    let action = match (report.direction, report.dry_run) {
+        (Direction::Up, false) => "Applied migration",
+        (Direction::Up, true) => "Would apply migration",
+        (Direction::Down, false) => "Rolled back migration",
+        (Direction::Down, true) => "Would roll back migration",
+    };
+    for (version, name) in &report.steps {
+        info!(version, name = %name, "{}", action);
+    }
+    if report.steps.is_empty() {
+        info!("No migrations to run");
     }
+}
 
-    info!("Running database migrations");
+/// Back up the database, or list or verify existing backups
+///
+/// Files already in the backup directory are referenced, not copied, so
+/// every backup after the first is incremental.
+fn run_backup(args: &[String]) -> ExitCode {
+    let config = match load_config() {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
 
-    // Load config to get database path
-    let config = match Config::from_env() {
+    let Some(dir) = args.first().filter(|a| !a.starts_with("--")) else {
+        error!("Usage: vaya backup <dir> [--list | --verify [<id>]]");
+        return ExitCode::from(2);
+    };
+    let engine = match BackupEngine::new(dir) {
+        Ok(engine) => engine,
+        Err(e) => {
+            error!(error = %e, "Failed to open backup directory");
+            return ExitCode::from(1);
+        }
+    };
+
+    if args.iter().any(|a| a == "--list") {
+        return match engine.list() {
+            Ok(backups) => {
+                for backup in backups {
+                    println!(
+                        "{}\tsequence {}\t{} files\t{} bytes\t{}",
+                        backup.id,
+                        backup.sequence,
+                        backup.files.len(),
+                        backup.size(),
+                        format_millis(backup.timestamp_ms)
+                    );
+                }
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to list backups");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    if args.iter().any(|a| a == "--verify") {
+        let ids = match flag_value(args, "--verify") {
+            Some(id) => match id.parse() {
+                Ok(id) => Ok(vec![id]),
+                Err(_) => {
+                    error!(value = %id, "Invalid --verify backup ID");
+                    return ExitCode::from(2);
+                }
+            },
+            None => engine
+                .list()
+                .map(|backups| backups.iter().map(|b| b.id).collect()),
+        };
+        let result = ids.and_then(|ids| {
+            ids.into_iter()
+                .try_for_each(|id| engine.verify(id).map(|b| info!(id = b.id, "Backup OK")))
+        });
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                error!(error = %e, "Backup verification failed");
+                ExitCode::from(1)
+            }
+        };
+    }
+
+    let result = VayaDb::open(db_config(&config, &config.database.data_dir)).and_then(|db| {
+        let report = engine.create(&db)?;
+        db.close()?;
+        Ok(report)
+    });
+    match result {
+        Ok(report) => {
+            info!(
+                id = report.info.id,
+                sequence = report.info.sequence,
+                copied = report.files_copied,
+                reused = report.files_reused,
+                bytes_copied = report.bytes_copied,
+                "Backup complete"
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(error = %e, "Backup failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Restore the database from a backup or to a point in time
+///
+/// With `--backup`, restores a backup taken by `vaya backup`. Otherwise
+/// copies a base checkpoint into the data directory and replays archived
+/// WAL segments up to the requested sequence number or timestamp.
+fn run_restore(args: &[String]) -> ExitCode {
+    let config = match load_config() {
         Ok(c) => c,
+        Err(code) => return code,
+    };
+
+    if let Some(dir) = flag_value(args, "--backup") {
+        return restore_backup(&config, dir, args);
+    }
+
+    let Some(base) = flag_value(args, "--base") else {
+        error!("--base <checkpoint dir> is required");
+        return ExitCode::from(2);
+    };
+    let Some(archive_dir) = flag_value(args, "--archive")
+        .map(PathBuf::from)
+        .or_else(|| config.database.wal_archive_dir.clone())
+    else {
+        error!("--archive <dir> is required when VAYA_WAL_ARCHIVE_DIR is unset");
+        return ExitCode::from(2);
+    };
+    let target_dir = flag_value(args, "--target")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config.database.data_dir.clone());
+
+    let target = match (
+        flag_value(args, "--to-timestamp"),
+        flag_value(args, "--to-sequence"),
+    ) {
+        (Some(_), Some(_)) => {
+            error!("--to-timestamp and --to-sequence are mutually exclusive");
+            return ExitCode::from(2);
+        }
+        (Some(ts), None) => match parse_timestamp_millis(ts) {
+            Some(ms) => RecoveryTarget::Timestamp(ms),
+            None => {
+                error!(value = %ts, "Invalid --to-timestamp (expected RFC 3339 or Unix seconds)");
+                return ExitCode::from(2);
+            }
+        },
+        (None, Some(seq)) => match seq.parse() {
+            Ok(seq) => RecoveryTarget::Sequence(seq),
+            Err(_) => {
+                error!(value = %seq, "Invalid --to-sequence");
+                return ExitCode::from(2);
+            }
+        },
+        (None, None) => RecoveryTarget::Latest,
+    };
+
+    let archive = match DirectoryArchive::new(&archive_dir) {
+        Ok(a) => a,
+        Err(e) => {
+            error!(error = %e, "Failed to open WAL archive");
+            return ExitCode::from(1);
+        }
+    };
+
+    info!(base = %base, archive = ?archive_dir, target_dir = ?target_dir, ?target, "Starting point-in-time restore");
+
+    match recovery::restore(base, &archive, db_config(&config, &target_dir), target) {
+        Ok(report) => {
+            info!(
+                base_sequence = report.base_sequence,
+                restored_sequence = report.restored_sequence,
+                restored_timestamp_ms = ?report.restored_timestamp_ms,
+                records = report.records_replayed,
+                segments = report.segments_read,
+                tables = report.verification.tables,
+                entries = report.verification.entries,
+                "Restore complete and verified"
+            );
+            ExitCode::SUCCESS
+        }
         Err(e) => {
-            error!(error = %e, "Failed to load configuration");
+            error!(error = %e, "Restore failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Restore a backup into an empty data directory
+fn restore_backup(config: &Config, dir: &str, args: &[String]) -> ExitCode {
+    let target_dir = flag_value(args, "--target")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config.database.data_dir.clone());
+
+    let engine = match BackupEngine::new(dir) {
+        Ok(engine) => engine,
+        Err(e) => {
+            error!(error = %e, "Failed to open backup directory");
             return ExitCode::from(1);
         }
     };
+    let id = match flag_value(args, "--id") {
+        Some(id) => match id.parse() {
+            Ok(id) => id,
+            Err(_) => {
+                error!(value = %id, "Invalid --id");
+                return ExitCode::from(2);
+            }
+        },
+        None => match engine.latest() {
+            Ok(Some(backup)) => backup.id,
+            Ok(None) => {
+                error!(dir = %dir, "No backups to restore");
+                return ExitCode::from(1);
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to list backups");
+                return ExitCode::from(1);
+            }
+        },
+    };
+
+    info!(backup = %dir, id, target_dir = ?target_dir, "Restoring backup");
+    match engine.restore(id, &db_config(config, &target_dir)) {
+        Ok(report) => {
+            info!(
+                id,
+                sequence = report.info.sequence,
+                tables = report.verification.tables,
+                entries = report.verification.entries,
+                wal_records = report.verification.wal_records,
+                "Restore complete and verified"
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(error = %e, "Restore failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Database settings from the configuration, for the store at `path`
+fn db_config(config: &Config, path: &std::path::Path) -> DbConfig {
+    let mut db_config = DbConfig::new(path)
+        .memtable_size(config.database.memtable_size)
+        .compression(config.database.compression);
+    if let Some(ref keys) = config.database.encryption_keys {
+        db_config = db_config.encryption(keys.clone());
+    }
+    db_config
+}
+
+/// Rewrite every database file under the active encryption key
+///
+/// Run after adding a new key to `VAYA_DB_ENCRYPTION_KEYS` (or when first
+/// enabling encryption) so retired keys can be removed.
+fn run_reencrypt() -> ExitCode {
+    let config = match load_config() {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+
+    let Some(keys) = config.database.encryption_keys.clone() else {
+        error!("VAYA_DB_ENCRYPTION_KEYS must be set to re-encrypt");
+        return ExitCode::from(2);
+    };
+    let active_key = keys.active_id();
+
+    let db_config = DbConfig::new(&config.database.data_dir)
+        .memtable_size(config.database.memtable_size)
+        .compression(config.database.compression)
+        .encryption(keys);
+
+    let result = VayaDb::open(db_config).and_then(|db| {
+        // Flushing restamps the WAL under the active key as well
+        db.flush()?;
+        let rewritten = db.reencrypt()?;
+        db.close()?;
+        Ok(rewritten)
+    });
+
+    match result {
+        Ok(rewritten) => {
+            info!(tables = rewritten, active_key, "Re-encryption complete");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(error = %e, "Re-encryption failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Export a table's rows to a JSONL or CSV file
+///
+/// Rows can be filtered by a timestamp window (`--since`/`--until` on
+/// `created_at` unless `--since-column` names another) and by `--where`
+/// equality tests. Personal data columns must be covered by `--redact` or
+/// `--redact-column`, or left out with `--fields`.
+fn run_export(args: &[String]) -> ExitCode {
+    let config = match load_config() {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+    let (Some(name), Some(output)) = (flag_value(args, "--table"), flag_value(args, "--output"))
+    else {
+        error!("Usage: vaya export --table <name> --output <file> [--format jsonl|csv] [--fields <a,b>] [--since <date>] [--until <date>] [--where <col=value>] [--redact mask|drop|allow]");
+        return ExitCode::from(2);
+    };
+    let Some(format) = data_format(args) else {
+        return ExitCode::from(2);
+    };
+    let policy = match redaction_policy(args) {
+        Ok(policy) => policy,
+        Err(e) => {
+            error!(error = %e, "Invalid redaction options");
+            return ExitCode::from(2);
+        }
+    };
+
+    let (db, table) = match open_table(&config, name) {
+        Ok(opened) => opened,
+        Err(code) => return code,
+    };
+    let query = match export_query(&table, args) {
+        Ok(query) => query,
+        Err(e) => {
+            error!(error = %e, "Invalid export filter");
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = std::fs::File::create(output)
+        .map_err(vaya_store::StoreError::from)
+        .and_then(|file| {
+            let mut out = std::io::BufWriter::new(file);
+            table.export_to(&query, format, &policy, &mut out)
+        });
+    let closed = db.close();
+    match result
+        .map_err(|e| e.to_string())
+        .and_then(|rows| closed.map(|()| rows).map_err(|e| e.to_string()))
+    {
+        Ok(rows) => {
+            info!(table = %name, rows, format = format.as_str(), output = %output, "Export complete");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(error = %e, "Export failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Import rows from a JSONL or CSV file written by `vaya export`
+fn run_import(args: &[String]) -> ExitCode {
+    let config = match load_config() {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+    let (Some(name), Some(input)) = (flag_value(args, "--table"), flag_value(args, "--input"))
+    else {
+        error!("Usage: vaya import --table <name> --input <file> [--format jsonl|csv] [--replace] [--dry-run]");
+        return ExitCode::from(2);
+    };
+    let Some(format) = data_format(args) else {
+        return ExitCode::from(2);
+    };
+    let options = ImportOptions::new(format)
+        .replace(args.iter().any(|a| a == "--replace"))
+        .dry_run(args.iter().any(|a| a == "--dry-run"));
+
+    let (db, table) = match open_table(&config, name) {
+        Ok(opened) => opened,
+        Err(code) => return code,
+    };
+    let result = std::fs::File::open(input)
+        .map_err(vaya_store::StoreError::from)
+        .and_then(|file| table.import_from(&mut std::io::BufReader::new(file), &options));
+    let closed = db.flush().and_then(|()| db.close());
+    match result
+        .map_err(|e| e.to_string())
+        .and_then(|report| closed.map(|()| report).map_err(|e| e.to_string()))
+    {
+        Ok(report) => {
+            let message = if report.dry_run {
+                "Import checked (dry run, nothing written)"
+            } else {
+                "Import complete"
+            };
+            info!(
+                table = %name,
+                rows = report.rows,
+                inserted = report.inserted,
+                updated = report.updated,
+                "{}",
+                message
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(error = %e, "Import failed");
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Open the database and one of its tables
+fn open_table(config: &Config, name: &str) -> Result<(Arc<VayaDb>, Table), ExitCode> {
+    let db = match VayaDb::open(db_config(config, &config.database.data_dir)) {
+        Ok(db) => Arc::new(db),
+        Err(e) => {
+            error!(error = %e, "Failed to open database");
+            return Err(ExitCode::from(1));
+        }
+    };
+    match Table::open(name, Arc::clone(&db)) {
+        Ok(table) => Ok((db, table)),
+        Err(e) => {
+            error!(error = %e, "Failed to open table");
+            Err(ExitCode::from(1))
+        }
+    }
+}
+
+/// The `--format` option, JSONL by default
+fn data_format(args: &[String]) -> Option<DataFormat> {
+    match flag_value(args, "--format") {
+        None => Some(DataFormat::Jsonl),
+        Some(value) => {
+            let format = DataFormat::parse(value);
+            if format.is_none() {
+                error!(value = %value, "Invalid --format (expected jsonl or csv)");
+            }
+            format
+        }
+    }
+}
+
+/// Build the redaction policy from `--redact` and `--redact-column`
+///
+/// `--redact` sets the action for every personal data class;
+/// `--redact-column col=action` overrides it for one column.
+fn redaction_policy(args: &[String]) -> Result<RedactionPolicy, String> {
+    let action = |value: &str| match value {
+        "allow" => Ok(RedactionAction::Allow),
+        "mask" => Ok(RedactionAction::Mask),
+        "drop" => Ok(RedactionAction::Drop),
+        other => Err(format!(
+            "unknown redaction action `{}` (expected mask, drop or allow)",
+            other
+        )),
+    };
+    let mut policy = RedactionPolicy::new();
+    if let Some(value) = flag_value(args, "--redact") {
+        let action = action(value)?;
+        for class in [PiiClass::Internal, PiiClass::Personal, PiiClass::Sensitive] {
+            policy = policy.class(class, action);
+        }
+    }
+    for rule in flag_values(args, "--redact-column") {
+        let (column, value) = rule
+            .split_once('=')
+            .ok_or_else(|| format!("expected column=action, got `{}`", rule))?;
+        policy = policy.column(column, action(value)?);
+    }
+    Ok(policy)
+}
+
+/// Build the export query from `--fields`, `--since`, `--until` and `--where`
+fn export_query(table: &Table, args: &[String]) -> Result<Query, String> {
+    let schema = table.schema();
+    let mut query = Query::new(table.name());
+    if let Some(fields) = flag_value(args, "--fields") {
+        query = query.select(fields.split(',').map(|f| f.trim().to_string()).collect());
+    }
+
+    let column = flag_value(args, "--since-column").unwrap_or("created_at");
+    for (flag, condition) in [
+        ("--since", Condition::ge as fn(String, Value) -> Condition),
+        ("--until", Condition::lt),
+    ] {
+        let Some(value) = flag_value(args, flag) else {
+            continue;
+        };
+        if schema.get_column(column).is_none() {
+            return Err(format!("{} has no column `{}`", table.name(), column));
+        }
+        let ms = parse_timestamp_millis(value).ok_or_else(|| {
+            format!(
+                "invalid {} `{}` (expected a date, RFC 3339 or Unix seconds)",
+                flag, value
+            )
+        })?;
+        query = query.filter(condition(column.to_string(), Value::Int64(ms as i64)));
+    }
+
+    for test in flag_values(args, "--where") {
+        let (column, text) = test
+            .split_once('=')
+            .ok_or_else(|| format!("expected column=value, got `{}`", test))?;
+        let value = schema
+            .parse_value(column, text)
+            .map_err(|e| e.to_string())?;
+        query = query.eq(column, value);
+    }
+    if let Some(limit) = flag_value(args, "--limit") {
+        let limit = limit
+            .parse()
+            .map_err(|_| format!("invalid --limit `{}`", limit))?;
+        query = query.limit(limit);
+    }
+    Ok(query)
+}
+
+/// Fill the database with deterministic demo data
+fn run_seed(args: &[String]) -> ExitCode {
+    let config = match load_config() {
+        Ok(c) => c,
+        Err(code) => return code,
+    };
+    if config.is_production() && !args.iter().any(|a| a == "--force") {
+        error!("Refusing to seed demo data in production (pass --force to override)");
+        return ExitCode::from(2);
+    }
+
+    let seed = match flag_value(args, "--seed").map(str::parse).transpose() {
+        Ok(seed) => seed.unwrap_or(1),
+        Err(_) => {
+            error!("Invalid --seed (expected an unsigned integer)");
+            return ExitCode::from(2);
+        }
+    };
+    // Data is relative to the anchor day; pin it to rebuild an identical demo
+    let anchor_ms = match flag_value(args, "--anchor") {
+        Some(value) => match parse_timestamp_millis(value) {
+            Some(ms) => ms as i64,
+            None => {
+                error!(value = %value, "Invalid --anchor (expected RFC 3339 or Unix seconds)");
+                return ExitCode::from(2);
+            }
+        },
+        None => (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64,
+    };
+    let mut seed_config = seed::SeedConfig::new(seed, anchor_ms);
+    for (flag, target) in [
+        ("--users", &mut seed_config.users),
+        ("--price-days", &mut seed_config.price_days),
+        ("--pools", &mut seed_config.pools),
+        ("--bookings", &mut seed_config.bookings),
+        ("--alerts", &mut seed_config.alerts),
+    ] {
+        if let Some(value) = flag_value(args, flag) {
+            match value.parse() {
+                Ok(n) => *target = n,
+                Err(_) => {
+                    error!(flag, value = %value, "Invalid count");
+                    return ExitCode::from(2);
+                }
+            }
+        }
+    }
+    if let Some(scale) = flag_value(args, "--scale") {
+        match scale.parse() {
+            Ok(factor) => seed_config = seed_config.scaled(factor),
+            Err(_) => {
+                error!(value = %scale, "Invalid --scale");
+                return ExitCode::from(2);
+            }
+        }
+    }
 
     info!(
-        data_dir = ?config.database.data_dir,
-        "Database configuration loaded"
+        seed,
+        anchor_ms = seed_config.anchor_ms,
+        "Generating demo data"
     );
+    let tables = seed::generate(&seed_config);
+
+    let mut db_config = DbConfig::new(&config.database.data_dir)
+        .memtable_size(config.database.memtable_size)
+        .compression(config.database.compression);
+    if let Some(ref keys) = config.database.encryption_keys {
+        db_config = db_config.encryption(keys.clone());
+    }
+    let db = match VayaDb::open(db_config) {
+        Ok(db) => Arc::new(db),
+        Err(e) => {
+            error!(error = %e, "Failed to open database");
+            return ExitCode::from(1);
+        }
+    };
+
+    let result = seed::write(db.clone(), &tables);
+    if let Err(e) = db.flush() {
+        error!(error = %e, "Failed to flush database");
+        return ExitCode::from(1);
+    }
+    match result {
+        Ok(report) => {
+            for (table, rows) in &report.tables {
+                info!(table = %table, rows, "Seeded");
+            }
+            info!("Demo data ready");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!(error = %e, "Seeding failed");
+            ExitCode::from(1)
+        }
+    }
+}
 
-    // TODO: Implement actual migrations
-    warn!("Migrations not yet implemented");
+/// Run a `vaya config` subcommand
+fn run_config(args: &[String]) -> ExitCode {
+    match args.first().map(|s| s.as_str()) {
+        Some("check") => {
+            let path = args.get(1).map(PathBuf::from).or_else(config::config_path);
+            check_config(path)
+        }
+        _ => {
+            eprintln!("Usage: vaya config check [<path>]");
+            ExitCode::from(2)
+        }
+    }
+}
 
-    info!("Migrations complete");
+/// Validate the configuration and show where each setting comes from
+fn check_config(path: Option<PathBuf>) -> ExitCode {
+    let file = match &path {
+        Some(path) => match ConfigFile::load(path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::from(1);
+            }
+        },
+        None => None,
+    };
+    match &path {
+        Some(path) => println!("Config file: {}", path.display()),
+        None => println!(
+            "Config file: none (pass a path or set {})",
+            config::CONFIG_PATH_VAR
+        ),
+    }
+
+    for source in config::setting_sources(file.as_ref()) {
+        let Some(value) = source.value else {
+            continue;
+        };
+        let shadows_file = source.origin == Origin::Env
+            && file
+                .as_ref()
+                .is_some_and(|f| f.env_value(source.env).is_some());
+        match source.origin {
+            Origin::Env if shadows_file => println!(
+                "  {} = {} (env {}, overrides file)",
+                source.key, value, source.env
+            ),
+            Origin::Env => println!("  {} = {} (env {})", source.key, value, source.env),
+            _ => println!("  {} = {} ({})", source.key, value, source.origin),
+        }
+    }
+
+    let config = match Config::layered(file.as_ref()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(1);
+        }
+    };
+    if let Err(e) = EnvFilter::try_new(&config.logging.level) {
+        eprintln!(
+            "Invalid configuration value: logging.level {:?}: {}",
+            config.logging.level, e
+        );
+        return ExitCode::from(1);
+    }
+    println!("Configuration OK ({})", config.environment());
     ExitCode::SUCCESS
 }
 
+/// Manage the sealed secrets file
+fn run_secrets(args: &[String]) -> ExitCode {
+    let command = args.first().map(|s| s.as_str());
+    let name = args.get(1).map(|s| s.as_str());
+    if !matches!(
+        (command, name),
+        (Some("set" | "get" | "rm"), Some(_)) | (Some("list"), None)
+    ) {
+        eprintln!("Usage: vaya secrets set <name> [<value>] | get <name> | list | rm <name>");
+        return ExitCode::from(2);
+    }
+
+    let store = SecretsConfig::current().and_then(|config| config.open_store());
+    let mut store = match store {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(1);
+        }
+    };
+
+    let result = match (command, name) {
+        (Some("set"), Some(name)) => {
+            // Read from stdin when no value is given, keeping it out of
+            // shell history
+            let value = match args.get(2) {
+                Some(value) => Ok(value.clone()),
+                None => std::io::read_to_string(std::io::stdin()).map(|v| {
+                    v.strip_suffix('\n')
+                        .map(|v| v.strip_suffix('\r').unwrap_or(v))
+                        .unwrap_or(&v)
+                        .to_string()
+                }),
+            };
+            match value {
+                Ok(value) => store
+                    .set(name, value)
+                    .and_then(|()| store.save())
+                    .map(|()| println!("Stored {} in {}", name, store.path().display()))
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(format!("Failed to read the value from stdin: {}", e)),
+            }
+        }
+        (Some("get"), Some(name)) => match store.get(name) {
+            Some(value) => {
+                println!("{}", value);
+                Ok(())
+            }
+            None => Err(format!("No secret named {}", name)),
+        },
+        (Some("rm"), Some(name)) if store.remove(name) => store
+            .save()
+            .map(|()| println!("Removed {} from {}", name, store.path().display()))
+            .map_err(|e| e.to_string()),
+        (Some("rm"), Some(name)) => Err(format!("No secret named {}", name)),
+        _ => {
+            for name in store.names() {
+                println!("{}", name);
+            }
+            Ok(())
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Get the value following a `--flag` argument
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+/// Get the value following every occurrence of a repeatable `--flag`
+fn flag_values<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+    args.windows(2)
+        .filter(|pair| pair[0] == flag)
+        .map(|pair| pair[1].as_str())
+        .collect()
+}
+
+/// Format Unix milliseconds as RFC 3339
+fn format_millis(ms: u64) -> String {
+    OffsetDateTime::from_unix_timestamp_nanos(i128::from(ms) * 1_000_000)
+        .ok()
+        .and_then(|dt| dt.format(&Rfc3339).ok())
+        .unwrap_or_else(|| ms.to_string())
+}
+
+/// Parse an RFC 3339 timestamp, a date (midnight UTC) or Unix seconds
+/// into Unix milliseconds
+fn parse_timestamp_millis(value: &str) -> Option<u64> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return secs.checked_mul(1000);
+    }
+    let dt = OffsetDateTime::parse(value, &Rfc3339)
+        .or_else(|_| OffsetDateTime::parse(&format!("{}T00:00:00Z", value), &Rfc3339))
+        .ok()?;
+    u64::try_from(dt.unix_timestamp_nanos() / 1_000_000).ok()
+}
+
 /// Show version information
 fn show_version() -> ExitCode {
     println!("vaya {}", env!("CARGO_PKG_VERSION"));
@@ -182,20 +1183,53 @@ fn show_help() -> ExitCode {
     println!();
     println!("COMMANDS:");
     println!("    serve       Start the HTTP server");
-    println!("    migrate     Run database migrations");
+    println!("    migrate     Apply pending schema migrations");
+    println!("                  [up|status|rollback] [--to <version>] [--dry-run]");
+    println!("    backup      Back up the database (incremental after the first)");
+    println!("                  <dir> [--list | --verify [<id>]]");
+    println!("    restore     Restore a backup into an empty data directory");
+    println!("                  --backup <dir> [--id <n>] [--target <dir>]");
+    println!("                Or point-in-time restore from a checkpoint and WAL archive");
+    println!("                  --base <dir> [--archive <dir>] [--target <dir>]");
+    println!("                  [--to-timestamp <RFC3339|unix>] [--to-sequence <n>]");
+    println!("    export      Export a table's rows for other systems");
+    println!("                  --table <name> --output <file> [--format jsonl|csv]");
+    println!("                  [--fields <a,b,..>] [--since <date>] [--until <date>]");
+    println!("                  [--since-column <col>] [--where <col=value>] [--limit <n>]");
+    println!("                  [--redact mask|drop|allow] [--redact-column <col=action>]");
+    println!("    import      Load rows from an export into a table");
+    println!("                  --table <name> --input <file> [--format jsonl|csv]");
+    println!("                  [--replace] [--dry-run]");
+    println!("    seed        Load deterministic demo data");
+    println!("                  [--seed <n>] [--scale <n>] [--anchor <RFC3339|unix>]");
+    println!("                  [--users <n>] [--price-days <n>] [--pools <n>]");
+    println!("                  [--bookings <n>] [--alerts <n>] [--force]");
+    println!("    config      Validate configuration: config check [<path>]");
+    println!("    secrets     Manage sealed secrets: secrets set <name> [<value>],");
+    println!("                  secrets get <name>, secrets list, secrets rm <name>");
     println!("    check       Run health checks");
+    println!("    reencrypt   Rewrite database files under the active encryption key");
     println!("    version     Show version information");
     println!("    help        Show this help message");
     println!();
     println!("ENVIRONMENT VARIABLES:");
+    println!("    VAYA_CONFIG              Config file (TOML); variables below override it");
     println!("    VAYA_ENV                Environment (development/staging/production)");
     println!("    VAYA_HOST                Bind host (default: 0.0.0.0)");
     println!("    VAYA_PORT                Bind port (default: 8080)");
     println!("    VAYA_WORKERS             Worker threads (default: CPU count)");
     println!("    VAYA_DATA_DIR            Database directory (default: ./data/db)");
+    println!("    VAYA_WAL_ARCHIVE_DIR     WAL archive directory (enables point-in-time recovery)");
+    println!(
+        "    VAYA_DB_ENCRYPTION_KEYS  Encryption-at-rest keys as id:base64,... (highest active)"
+    );
     println!("    VAYA_JWT_SECRET          JWT signing secret (required in production)");
+    println!("    VAYA_SECRETS_FILE        Sealed secrets file (default: ./data/secrets.vault)");
+    println!("    VAYA_MASTER_KEY          Secrets master key (base64, 32 bytes)");
+    println!("    VAYA_MASTER_KEY_COMMAND  Command printing the master key (e.g. a KMS call)");
     println!("    VAYA_LOG_LEVEL           Log level (trace/debug/info/warn/error)");
     println!("    VAYA_LOG_FORMAT          Log format (json/pretty)");
+    println!("    VAYA_EVENT_LOG           Domain event log (file path or stdout; unset = off)");
     println!();
     println!("EXAMPLES:");
     println!("    # Start server on port 3000");
@@ -204,27 +1238,30 @@ fn show_help() -> ExitCode {
     println!("    # Start in production mode");
     println!("    VAYA_ENV=production VAYA_JWT_SECRET=... vaya serve");
     println!();
+    println!("    # Keep the JWT secret sealed rather than in the environment");
+    println!("    vaya secrets set jwt < jwt.txt");
+    println!("    VAYA_JWT_SECRET=secret://jwt vaya serve");
+    println!();
+    println!("    # Bookings since the start of 2025 for finance, without personal data");
+    println!(
+        "    vaya export --table bookings --since 2025-01-01 --redact drop --output bookings.jsonl"
+    );
+    println!();
+    println!("    # Apply a new log level, rate limits or cache TTLs without restarting");
+    println!("    kill -HUP <pid>");
+    println!();
     println!("For more information, visit: https://github.com/vaya/vaya-oracle");
     ExitCode::SUCCESS
 }
 
 /// Run health check
 fn run_health_check() -> ExitCode {
-    if let Err(e) = init_logging() {
-        eprintln!("Failed to initialize logging: {}", e);
-        return ExitCode::from(1);
-    }
-
-    info!("Running health checks");
-
-    // Load config
-    let config = match Config::from_env() {
+    let config = match load_config() {
         Ok(c) => c,
-        Err(e) => {
-            error!(error = %e, "Configuration check failed");
-            return ExitCode::from(1);
-        }
+        Err(code) => return code,
     };
+
+    info!("Running health checks");
     info!("Configuration: OK");
 
     // Check database directory
@@ -246,16 +1283,32 @@ fn run_health_check() -> ExitCode {
     ExitCode::SUCCESS
 }
 
-/// Initialize logging
-fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
-    let log_level = env::var("VAYA_LOG_LEVEL").unwrap_or_else(|_| "info".into());
-    let log_format = env::var("VAYA_LOG_FORMAT").unwrap_or_else(|_| "json".into());
+/// Load configuration, then start logging as it says
+fn load_config() -> Result<Config, ExitCode> {
+    let config = Config::load().map_err(|e| {
+        eprintln!("Failed to load configuration: {}", e);
+        ExitCode::from(1)
+    })?;
+    if let Err(e) = init_logging(&config.logging) {
+        eprintln!("Failed to initialize logging: {}", e);
+        return Err(ExitCode::from(1));
+    }
+    Ok(config)
+}
 
-    let filter = EnvFilter::try_new(&log_level).unwrap_or_else(|_| EnvFilter::new("info"));
+/// Initialize logging
+fn init_logging(config: &LogConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let filter = EnvFilter::try_new(&config.level).unwrap_or_else(|_| EnvFilter::new("info"));
+    // The filter can be swapped when the configuration is reloaded
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = LOG_FILTER.set(handle);
 
-    let subscriber = tracing_subscriber::registry().with(filter);
+    // Recent lines are also kept in memory for the admin live tail
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(log_buffer::LogBufferLayer);
 
-    if log_format == "json" {
+    if config.format == "json" {
         subscriber
             .with(fmt::layer().json())
             .try_init()
@@ -270,6 +1323,20 @@ fn init_logging() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Route domain events to their dedicated sink, separate from debug logs
+fn init_event_log(destination: Option<&str>) -> std::io::Result<()> {
+    let sink: Option<Arc<dyn EventSink>> = match destination {
+        None => None,
+        Some("stdout") => Some(Arc::new(WriterSink::new(std::io::stdout()))),
+        Some(path) => Some(Arc::new(WriterSink::append_file(path)?)),
+    };
+    if let Some(destination) = destination {
+        info!(destination, "Domain event logging enabled");
+    }
+    events::set_sink(sink);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,6 +1348,39 @@ mod tests {
         assert_eq!(result, ExitCode::SUCCESS);
     }
 
+    #[test]
+    fn test_parse_timestamp_millis() {
+        assert_eq!(
+            parse_timestamp_millis("1700000000"),
+            Some(1_700_000_000_000)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2023-11-14T22:13:20.5Z"),
+            Some(1_700_000_000_500)
+        );
+        assert_eq!(
+            parse_timestamp_millis("2025-01-01"),
+            Some(1_735_689_600_000)
+        );
+        assert_eq!(parse_timestamp_millis("yesterday"), None);
+    }
+
+    #[test]
+    fn test_flag_value() {
+        let args: Vec<String> = ["--base", "/b", "--to-sequence"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(flag_value(&args, "--base"), Some("/b"));
+        assert_eq!(flag_value(&args, "--to-sequence"), None);
+
+        let args: Vec<String> = ["--where", "a=1", "--redact", "mask", "--where", "b=2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(flag_values(&args, "--where"), ["a=1", "b=2"]);
+    }
+
     #[test]
     fn test_show_help() {
         // Just verify it doesn't panic
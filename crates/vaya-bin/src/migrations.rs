@@ -0,0 +1,37 @@
+//! Schema migrations shipped with this build
+//!
+//! Add a migration by appending it with the next version; never renumber
+//! or change one that has shipped, since databases record which versions
+//! they have applied. `vaya migrate` applies them, and the server refuses
+//! to start in production while any are pending.
+
+use std::sync::Arc;
+
+use vaya_db::VayaDb;
+use vaya_store::{Migration, Migrator};
+
+/// Every migration, oldest first
+pub fn all() -> Vec<Migration> {
+    vec![
+        // Tables that existed before migrations did are created by the
+        // code that uses them; this marks where versioning starts
+        Migration::new(1, "baseline", |_| Ok(())).with_down(|_| Ok(())),
+    ]
+}
+
+/// Migrator for `db` with every migration registered
+pub fn migrator(db: Arc<VayaDb>) -> Migrator {
+    Migrator::new(db).with_migrations(all())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_unique_and_increasing() {
+        let versions: Vec<u64> = all().iter().map(|m| m.version).collect();
+        assert!(versions.windows(2).all(|w| w[0] < w[1]), "{:?}", versions);
+        assert_eq!(versions.first(), Some(&1));
+    }
+}
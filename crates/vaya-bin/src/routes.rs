@@ -30,6 +30,26 @@ pub fn register_routes(server: &mut ApiServer, _state: Arc<AppState>) {
         handlers::search::search_airlines,
         "search_airlines",
     );
+    server.get(
+        "/search/calendar",
+        vaya_api::handlers::get_fare_calendar_handler,
+        "fare_calendar",
+    );
+    server.get(
+        "/search/suggestions",
+        vaya_api::handlers::get_search_suggestions_handler,
+        "search_suggestions",
+    );
+    server.get(
+        "/airports/suggest",
+        vaya_api::handlers::suggest_airports_handler,
+        "suggest_airports",
+    );
+    server.get(
+        "/defaults",
+        vaya_api::handlers::get_search_defaults_handler,
+        "search_defaults",
+    );
 
     // Booking routes
     server.post(
@@ -123,6 +143,38 @@ pub fn register_routes(server: &mut ApiServer, _state: Arc<AppState>) {
         handlers::oracle::get_best_time,
         "get_best_time",
     );
+
+    // Admin system routes (RBAC system:read / system:manage)
+    server.get(
+        "/admin/system",
+        vaya_api::handlers::admin_system_overview_handler,
+        "admin_system_overview",
+    );
+    server.get(
+        "/admin/system/db",
+        vaya_api::handlers::admin_system_db_handler,
+        "admin_system_db",
+    );
+    server.get(
+        "/admin/system/cache",
+        vaya_api::handlers::admin_system_cache_handler,
+        "admin_system_cache",
+    );
+    server.get(
+        "/admin/system/rate-limits",
+        vaya_api::handlers::admin_system_rate_limits_handler,
+        "admin_system_rate_limits",
+    );
+    server.get(
+        "/admin/system/fleet",
+        vaya_api::handlers::admin_system_fleet_handler,
+        "admin_system_fleet",
+    );
+    server.post(
+        "/admin/system/cache/purge",
+        vaya_api::handlers::admin_system_purge_cache_handler,
+        "admin_system_purge_cache",
+    );
 }
 
 /// Health check handler
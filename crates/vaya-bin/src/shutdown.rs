@@ -0,0 +1,320 @@
+//! Coordinated graceful shutdown
+//!
+//! Subsystems register named hooks with [`Shutdown::on_shutdown`]. When
+//! shutdown starts, hooks run phase by phase in [`Phase`] order: the hooks
+//! of one phase run concurrently, and the next phase starts once they have
+//! all finished or timed out. The whole sequence shares one deadline, but
+//! a phase that starts after it has passed still gets a grace period
+//! ([`HOOK_GRACE`] by default), so storage is flushed even when draining
+//! overruns.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Default time a phase gets when the shutdown deadline has already passed
+pub const HOOK_GRACE: Duration = Duration::from_secs(5);
+
+/// When a hook runs, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Stop taking new work: deregister from discovery, stop schedulers
+    Intake,
+    /// Let in-flight requests finish
+    Drain,
+    /// Hand off buffered work, e.g. sync the WAL
+    Flush,
+    /// Persist and close storage; runs last
+    Storage,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::Intake => "intake",
+            Phase::Drain => "drain",
+            Phase::Flush => "flush",
+            Phase::Storage => "storage",
+        };
+        f.write_str(name)
+    }
+}
+
+type HookFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+struct Hook {
+    name: String,
+    phase: Phase,
+    run: Box<dyn FnOnce() -> HookFuture + Send>,
+}
+
+/// How a hook ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookResult {
+    /// Finished successfully
+    Completed,
+    /// Returned an error or panicked
+    Failed(String),
+    /// Still running at its deadline; abandoned
+    TimedOut,
+}
+
+/// The outcome of one hook
+#[derive(Debug, Clone)]
+pub struct HookOutcome {
+    /// Hook name
+    pub name: String,
+    /// Phase it ran in
+    pub phase: Phase,
+    /// How it ended
+    pub result: HookResult,
+}
+
+/// What happened during shutdown
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// Every hook, in the order they finished
+    pub outcomes: Vec<HookOutcome>,
+}
+
+impl ShutdownReport {
+    /// Whether every hook completed
+    pub fn is_clean(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|outcome| outcome.result == HookResult::Completed)
+    }
+
+    /// The outcome of a hook by name
+    pub fn get(&self, name: &str) -> Option<&HookOutcome> {
+        self.outcomes.iter().find(|outcome| outcome.name == name)
+    }
+}
+
+/// Shutdown coordinator
+///
+/// Shared behind an `Arc`; [`Shutdown::signal`] lets long-running tasks
+/// notice that shutdown has begun.
+pub struct Shutdown {
+    trigger: watch::Sender<bool>,
+    hooks: Mutex<Vec<Hook>>,
+    grace: Duration,
+}
+
+impl Shutdown {
+    /// Create a coordinator with no hooks
+    pub fn new() -> Self {
+        Self {
+            trigger: watch::channel(false).0,
+            hooks: Mutex::new(Vec::new()),
+            grace: HOOK_GRACE,
+        }
+    }
+
+    /// Set the time a phase gets once the deadline has passed
+    pub fn grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Register a hook to run during `phase`
+    ///
+    /// Hooks in the same phase run concurrently; an error is logged and
+    /// reported but does not stop later hooks.
+    pub fn on_shutdown<F, Fut>(&self, name: impl Into<String>, phase: Phase, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.hooks.lock().unwrap().push(Hook {
+            name: name.into(),
+            phase,
+            run: Box::new(move || Box::pin(hook())),
+        });
+    }
+
+    /// Number of registered hooks
+    pub fn hook_count(&self) -> usize {
+        self.hooks.lock().unwrap().len()
+    }
+
+    /// Signal that shutdown has begun
+    pub fn trigger(&self) {
+        self.trigger.send_replace(true);
+    }
+
+    /// Whether shutdown has begun
+    pub fn is_triggered(&self) -> bool {
+        *self.trigger.borrow()
+    }
+
+    /// A future that resolves once shutdown begins
+    pub fn signal(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut triggered = self.trigger.subscribe();
+        async move {
+            // The sender lives in `self`; if it's gone nothing will trigger
+            if triggered.wait_for(|t| *t).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Trigger shutdown and run every registered hook within `timeout`
+    ///
+    /// Each hook runs at most once; calling this again runs only hooks
+    /// registered since.
+    pub async fn run(&self, timeout: Duration) -> ShutdownReport {
+        self.trigger();
+        let deadline = Instant::now() + timeout;
+        let mut hooks = std::mem::take(&mut *self.hooks.lock().unwrap());
+        hooks.sort_by_key(|hook| hook.phase);
+
+        let mut report = ShutdownReport::default();
+        let mut hooks = hooks.into_iter().peekable();
+        while let Some(phase) = hooks.peek().map(|hook| hook.phase) {
+            let mut tasks = JoinSet::new();
+            let mut pending = HashMap::new();
+            while let Some(hook) = hooks.next_if(|hook| hook.phase == phase) {
+                let id = tasks.spawn((hook.run)()).id();
+                pending.insert(id, hook.name);
+            }
+            info!(phase = %phase, hooks = pending.len(), "Running shutdown phase");
+
+            let phase_deadline = deadline.max(Instant::now() + self.grace);
+            loop {
+                let joined = tokio::time::timeout_at(phase_deadline, tasks.join_next_with_id());
+                let (id, result) = match joined.await {
+                    Ok(Some(Ok((id, result)))) => (id, result),
+                    Ok(Some(Err(e))) => (e.id(), Err(format!("panicked: {}", e))),
+                    Ok(None) => break,
+                    Err(_) => {
+                        tasks.abort_all();
+                        for name in pending.into_values() {
+                            warn!(hook = %name, phase = %phase, "Shutdown hook timed out");
+                            report.outcomes.push(HookOutcome {
+                                name,
+                                phase,
+                                result: HookResult::TimedOut,
+                            });
+                        }
+                        break;
+                    }
+                };
+                let name = pending.remove(&id).unwrap_or_default();
+                let result = match result {
+                    Ok(()) => HookResult::Completed,
+                    Err(e) => {
+                        warn!(hook = %name, phase = %phase, error = %e, "Shutdown hook failed");
+                        HookResult::Failed(e)
+                    }
+                };
+                report.outcomes.push(HookOutcome {
+                    name,
+                    phase,
+                    result,
+                });
+            }
+        }
+        report
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("triggered", &self.is_triggered())
+            .field("hooks", &self.hook_count())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_phases_run_in_order() {
+        let shutdown = Shutdown::new();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for (name, phase) in [
+            ("wal", Phase::Storage),
+            ("outbox", Phase::Flush),
+            ("discovery", Phase::Intake),
+            ("http", Phase::Drain),
+        ] {
+            let log = Arc::clone(&log);
+            shutdown.on_shutdown(name, phase, move || async move {
+                log.lock().unwrap().push(name);
+                Ok(())
+            });
+        }
+        shutdown.on_shutdown("broken", Phase::Flush, || async {
+            Err("disk full".to_string())
+        });
+
+        let signal = shutdown.signal();
+        assert!(!shutdown.is_triggered());
+        let report = shutdown.run(Duration::from_secs(1)).await;
+        signal.await;
+
+        assert_eq!(*log.lock().unwrap(), ["discovery", "http", "outbox", "wal"]);
+        assert!(!report.is_clean());
+        assert_eq!(
+            report.get("broken").unwrap().result,
+            HookResult::Failed("disk full".into())
+        );
+        assert_eq!(report.get("wal").unwrap().result, HookResult::Completed);
+
+        // Hooks run once
+        assert_eq!(shutdown.hook_count(), 0);
+        assert!(shutdown
+            .run(Duration::from_secs(1))
+            .await
+            .outcomes
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_slow_hook_does_not_block_storage() {
+        let shutdown = Shutdown::new().grace(Duration::from_millis(50));
+        shutdown.on_shutdown("http", Phase::Drain, || async {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            Ok(())
+        });
+        shutdown.on_shutdown("panics", Phase::Drain, || async {
+            panic!("boom");
+        });
+        let flushed = Arc::new(Mutex::new(false));
+        let flag = Arc::clone(&flushed);
+        shutdown.on_shutdown("wal", Phase::Storage, move || async move {
+            *flag.lock().unwrap() = true;
+            Ok(())
+        });
+
+        let started = Instant::now();
+        let report = shutdown.run(Duration::from_millis(100)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(report.get("http").unwrap().result, HookResult::TimedOut);
+        assert!(matches!(
+            report.get("panics").unwrap().result,
+            HookResult::Failed(_)
+        ));
+        assert_eq!(report.get("wal").unwrap().result, HookResult::Completed);
+        assert!(*flushed.lock().unwrap());
+    }
+}
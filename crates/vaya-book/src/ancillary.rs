@@ -0,0 +1,419 @@
+//! Ancillary services (extras)
+//!
+//! Extras sold alongside the fare: extra checked baggage, meals, travel
+//! insurance and priority boarding. An [`AncillaryCatalogue`] prices each
+//! extra per flight segment, or once for the trip in the case of
+//! insurance. Extras can be attached and detached until the booking is
+//! ticketed; their prices are part of the booking total and so of the
+//! amount the traveler pays.
+
+use std::mem;
+
+use time::OffsetDateTime;
+use vaya_common::{CurrencyCode, MinorUnits};
+
+use crate::booking::{Booking, BookingStatus};
+use crate::passenger::MealPreference;
+use crate::{BookError, BookResult};
+
+/// Travel insurance cover level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsuranceCover {
+    /// Medical and cancellation basics
+    Basic,
+    /// Adds baggage and delay cover
+    Standard,
+    /// Comprehensive cover
+    Premium,
+}
+
+impl InsuranceCover {
+    /// Get cover as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InsuranceCover::Basic => "BASIC",
+            InsuranceCover::Standard => "STANDARD",
+            InsuranceCover::Premium => "PREMIUM",
+        }
+    }
+}
+
+/// Kind of extra
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AncillaryKind {
+    /// Additional checked bag up to the given weight
+    ExtraBaggage { weight_kg: u8 },
+    /// Pre-ordered meal
+    Meal(MealPreference),
+    /// Travel insurance for the whole trip
+    Insurance(InsuranceCover),
+    /// Priority boarding
+    PriorityBoarding,
+}
+
+impl AncillaryKind {
+    /// Get kind as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AncillaryKind::ExtraBaggage { .. } => "EXTRA_BAGGAGE",
+            AncillaryKind::Meal(_) => "MEAL",
+            AncillaryKind::Insurance(_) => "INSURANCE",
+            AncillaryKind::PriorityBoarding => "PRIORITY_BOARDING",
+        }
+    }
+
+    /// Check if the extra is sold per flight segment
+    pub fn is_per_segment(&self) -> bool {
+        !matches!(self, AncillaryKind::Insurance(_))
+    }
+
+    /// Check if a passenger may hold more than one on the same segment
+    pub fn is_stackable(&self) -> bool {
+        matches!(self, AncillaryKind::ExtraBaggage { .. })
+    }
+}
+
+/// A priced extra in the catalogue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AncillaryOffer {
+    /// Extra on sale
+    pub kind: AncillaryKind,
+    /// Segment the price applies to (`None` for every segment, or the trip)
+    pub segment: Option<usize>,
+    /// Price per passenger
+    pub price: MinorUnits,
+}
+
+/// Extras on sale for a booking, with their prices
+#[derive(Debug, Clone)]
+pub struct AncillaryCatalogue {
+    /// Currency of all prices
+    pub currency: CurrencyCode,
+    offers: Vec<AncillaryOffer>,
+}
+
+impl AncillaryCatalogue {
+    /// Create an empty catalogue
+    pub fn new(currency: CurrencyCode) -> Self {
+        Self {
+            currency,
+            offers: Vec::new(),
+        }
+    }
+
+    /// Offer an extra at the same price on every segment (or for the trip)
+    pub fn with_offer(mut self, kind: AncillaryKind, price: MinorUnits) -> Self {
+        self.offers.push(AncillaryOffer {
+            kind,
+            segment: None,
+            price,
+        });
+        self
+    }
+
+    /// Offer an extra on one segment, overriding any every-segment price
+    pub fn with_segment_offer(
+        mut self,
+        kind: AncillaryKind,
+        segment: usize,
+        price: MinorUnits,
+    ) -> Self {
+        self.offers.push(AncillaryOffer {
+            kind,
+            segment: Some(segment),
+            price,
+        });
+        self
+    }
+
+    /// All offers
+    pub fn offers(&self) -> &[AncillaryOffer] {
+        &self.offers
+    }
+
+    /// Price of an extra on a segment, if on sale
+    pub fn price(&self, kind: &AncillaryKind, segment: Option<usize>) -> Option<MinorUnits> {
+        let matching = |s: Option<usize>| {
+            self.offers
+                .iter()
+                .find(|o| o.kind == *kind && o.segment == s)
+                .map(|o| o.price)
+        };
+        segment
+            .and_then(|s| matching(Some(s)))
+            .or_else(|| matching(None))
+    }
+}
+
+/// An extra attached to a booking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookedAncillary {
+    /// ID within the booking
+    pub id: u32,
+    /// Extra
+    pub kind: AncillaryKind,
+    /// Passenger the extra is for
+    pub passenger_id: u8,
+    /// Segment (`None` for trip-wide extras)
+    pub segment: Option<usize>,
+    /// Price charged
+    pub price: MinorUnits,
+    /// When it was attached
+    pub added_at: i64,
+}
+
+impl Booking {
+    /// Attach an extra for a passenger at the catalogue price
+    pub fn attach_ancillary(
+        &mut self,
+        catalogue: &AncillaryCatalogue,
+        kind: AncillaryKind,
+        passenger_id: u8,
+        segment: Option<usize>,
+    ) -> BookResult<&BookedAncillary> {
+        self.check_ancillaries_open()?;
+        if catalogue.currency != self.currency {
+            return Err(BookError::InvalidAncillary(format!(
+                "Catalogue priced in {}, booking in {}",
+                catalogue.currency, self.currency
+            )));
+        }
+        if !self.passengers.iter().any(|p| p.id == passenger_id) {
+            return Err(BookError::InvalidPassenger(format!(
+                "No passenger with id {}",
+                passenger_id
+            )));
+        }
+        match segment {
+            None if kind.is_per_segment() => {
+                return Err(BookError::InvalidAncillary(format!(
+                    "{} is sold per segment",
+                    kind.as_str()
+                )))
+            }
+            Some(_) if !kind.is_per_segment() => {
+                return Err(BookError::InvalidAncillary(format!(
+                    "{} covers the whole trip",
+                    kind.as_str()
+                )))
+            }
+            Some(s) if s >= self.segments().count() => {
+                return Err(BookError::InvalidAncillary(format!(
+                    "Booking has no segment {}",
+                    s
+                )))
+            }
+            _ => {}
+        }
+        let duplicate = self.ancillaries.iter().any(|a| {
+            a.passenger_id == passenger_id
+                && a.segment == segment
+                && mem::discriminant(&a.kind) == mem::discriminant(&kind)
+        });
+        if duplicate && !kind.is_stackable() {
+            return Err(BookError::InvalidAncillary(format!(
+                "Passenger {} already has {}",
+                passenger_id,
+                kind.as_str()
+            )));
+        }
+        let price = catalogue.price(&kind, segment).ok_or_else(|| {
+            BookError::InvalidAncillary(format!("{} is not on sale", kind.as_str()))
+        })?;
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let id = self.ancillaries.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+        self.total_price = MinorUnits::new(self.total_price.as_i64() + price.as_i64());
+        self.updated_at = now;
+        self.ancillaries.push(BookedAncillary {
+            id,
+            kind,
+            passenger_id,
+            segment,
+            price,
+            added_at: now,
+        });
+        Ok(self.ancillaries.last().expect("ancillary just added"))
+    }
+
+    /// Detach an extra, removing its price from the total
+    ///
+    /// Extras on a paid booking must be refunded instead.
+    pub fn detach_ancillary(&mut self, id: u32) -> BookResult<BookedAncillary> {
+        self.check_ancillaries_open()?;
+        if self.status == BookingStatus::PaymentReceived {
+            return Err(BookError::InvalidAncillary(
+                "Extras on a paid booking must be refunded".into(),
+            ));
+        }
+        let idx = self
+            .ancillaries
+            .iter()
+            .position(|a| a.id == id)
+            .ok_or_else(|| BookError::InvalidAncillary(format!("No extra with id {}", id)))?;
+
+        let removed = self.ancillaries.remove(idx);
+        self.total_price = MinorUnits::new(self.total_price.as_i64() - removed.price.as_i64());
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        Ok(removed)
+    }
+
+    /// Total price of attached extras
+    pub fn ancillary_total(&self) -> MinorUnits {
+        MinorUnits::new(self.ancillaries.iter().map(|a| a.price.as_i64()).sum())
+    }
+
+    fn check_ancillaries_open(&self) -> BookResult<()> {
+        if !matches!(
+            self.status,
+            BookingStatus::Pending | BookingStatus::Confirmed | BookingStatus::PaymentReceived
+        ) {
+            return Err(BookError::InvalidAncillary(format!(
+                "Extras cannot be changed in {} status",
+                self.status.as_str()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payment::{PaymentMethod, PaymentRecord};
+    use vaya_common::IataCode;
+    use vaya_search::PassengerType;
+
+    use crate::testing;
+
+    const BAG: AncillaryKind = AncillaryKind::ExtraBaggage { weight_kg: 20 };
+    const INSURANCE: AncillaryKind = AncillaryKind::Insurance(InsuranceCover::Standard);
+
+    fn booking() -> Booking {
+        let mut offer = testing::offer("offer-1", 100_000);
+        offer.outbound.segments = vec![
+            testing::segment(IataCode::KUL, IataCode::SIN),
+            testing::segment(IataCode::SIN, IataCode::NRT),
+        ];
+        offer.price_per_pax = vec![(PassengerType::Adult, MinorUnits::new(120_000))];
+        Booking::new("user-1", offer, vec![testing::passenger()]).unwrap()
+    }
+
+    fn catalogue() -> AncillaryCatalogue {
+        AncillaryCatalogue::new(CurrencyCode::MYR)
+            .with_offer(BAG, MinorUnits::new(8_000))
+            .with_segment_offer(BAG, 1, MinorUnits::new(12_000))
+            .with_offer(
+                AncillaryKind::Meal(MealPreference::Vegetarian),
+                MinorUnits::new(3_500),
+            )
+            .with_offer(INSURANCE, MinorUnits::new(4_500))
+    }
+
+    #[test]
+    fn test_catalogue_prices() {
+        let catalogue = catalogue();
+        assert_eq!(catalogue.price(&BAG, Some(0)), Some(MinorUnits::new(8_000)));
+        assert_eq!(
+            catalogue.price(&BAG, Some(1)),
+            Some(MinorUnits::new(12_000))
+        );
+        assert_eq!(
+            catalogue.price(&AncillaryKind::PriorityBoarding, Some(0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_attach_and_detach() {
+        let catalogue = catalogue();
+        let mut booking = booking();
+
+        booking
+            .attach_ancillary(&catalogue, BAG, 1, Some(0))
+            .unwrap();
+        booking
+            .attach_ancillary(&catalogue, BAG, 1, Some(1))
+            .unwrap();
+        // Bags stack, meals do not
+        booking
+            .attach_ancillary(&catalogue, BAG, 1, Some(1))
+            .unwrap();
+        let meal = AncillaryKind::Meal(MealPreference::Vegetarian);
+        booking
+            .attach_ancillary(&catalogue, meal, 1, Some(0))
+            .unwrap();
+        assert!(booking
+            .attach_ancillary(&catalogue, meal, 1, Some(0))
+            .is_err());
+        let insurance = booking
+            .attach_ancillary(&catalogue, INSURANCE, 1, None)
+            .unwrap()
+            .id;
+
+        assert_eq!(booking.ancillary_total(), MinorUnits::new(40_000));
+        assert_eq!(booking.total_price, MinorUnits::new(160_000));
+
+        let removed = booking.detach_ancillary(insurance).unwrap();
+        assert_eq!(removed.kind, INSURANCE);
+        assert_eq!(booking.total_price, MinorUnits::new(155_500));
+        assert!(booking.detach_ancillary(insurance).is_err());
+    }
+
+    #[test]
+    fn test_attach_validation() {
+        let catalogue = catalogue();
+        let mut booking = booking();
+
+        // Per-segment extras need a segment, trip-wide ones must not have one
+        assert!(booking.attach_ancillary(&catalogue, BAG, 1, None).is_err());
+        assert!(booking
+            .attach_ancillary(&catalogue, INSURANCE, 1, Some(0))
+            .is_err());
+        assert!(booking
+            .attach_ancillary(&catalogue, BAG, 1, Some(2))
+            .is_err());
+        assert!(booking
+            .attach_ancillary(&catalogue, AncillaryKind::PriorityBoarding, 1, Some(0))
+            .is_err());
+        assert!(matches!(
+            booking.attach_ancillary(&catalogue, BAG, 7, Some(0)),
+            Err(BookError::InvalidPassenger(_))
+        ));
+        let usd = AncillaryCatalogue::new(CurrencyCode::USD).with_offer(BAG, MinorUnits::new(20));
+        assert!(booking.attach_ancillary(&usd, BAG, 1, Some(0)).is_err());
+        assert!(booking.ancillaries.is_empty());
+    }
+
+    #[test]
+    fn test_extras_after_payment() {
+        let catalogue = catalogue();
+        let mut booking = booking();
+        let bag = booking
+            .attach_ancillary(&catalogue, BAG, 1, Some(0))
+            .unwrap()
+            .id;
+        booking.confirm("GDS123", "system").unwrap();
+        let mut payment = PaymentRecord::new(
+            "pay-1",
+            booking.total_price,
+            CurrencyCode::MYR,
+            PaymentMethod::Card,
+        );
+        payment.complete(None);
+        booking.mark_paid(payment, "user-1").unwrap();
+        assert_eq!(booking.amount_due(), MinorUnits::ZERO);
+
+        // Added extras are owed; paid ones are not detached
+        booking
+            .attach_ancillary(&catalogue, INSURANCE, 1, None)
+            .unwrap();
+        assert_eq!(booking.amount_due(), MinorUnits::new(4_500));
+        assert!(booking.detach_ancillary(bag).is_err());
+
+        booking.start_ticketing("system").unwrap();
+        assert!(booking
+            .attach_ancillary(&catalogue, BAG, 1, Some(1))
+            .is_err());
+    }
+}
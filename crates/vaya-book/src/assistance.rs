@@ -0,0 +1,337 @@
+//! Accessibility assistance requests
+//!
+//! Assistance needs are captured as structured data on the passenger and
+//! sent to the airline as IATA special service requests (SSRs). The airline
+//! answers each SSR with an action code; those are tracked per request so
+//! unconfirmed assistance can be chased before departure.
+
+use crate::{BookError, BookResult};
+
+/// Maximum SSR free-text length accepted by airline systems
+pub const MAX_SSR_TEXT_LEN: usize = 70;
+
+/// Largest lithium battery (watt-hours) airlines accept on a mobility aid
+pub const MAX_LITHIUM_WATT_HOURS: u16 = 300;
+
+/// Wheelchair assistance level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelchairNeed {
+    /// Can climb steps and walk in the cabin; needs a wheelchair for distance
+    Ramp,
+    /// Cannot climb steps; can walk to a seat
+    Steps,
+    /// Immobile; needs carrying to and from the cabin seat
+    Cabin,
+}
+
+impl WheelchairNeed {
+    /// IATA SSR code
+    pub fn code(&self) -> &'static str {
+        match self {
+            WheelchairNeed::Ramp => "WCHR",
+            WheelchairNeed::Steps => "WCHS",
+            WheelchairNeed::Cabin => "WCHC",
+        }
+    }
+
+    /// Short description for documents and agents
+    pub fn description(&self) -> &'static str {
+        match self {
+            WheelchairNeed::Ramp => "Wheelchair to aircraft door (can climb steps)",
+            WheelchairNeed::Steps => "Wheelchair to aircraft seat (cannot climb steps)",
+            WheelchairNeed::Cabin => "Wheelchair and carry to cabin seat (immobile)",
+        }
+    }
+}
+
+/// Passenger's own mobility aid, carried in the hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MobilityAid {
+    /// Manual wheelchair
+    Manual,
+    /// Powered, dry (sealed) battery
+    DryBattery,
+    /// Powered, wet (spillable) battery
+    WetBattery,
+    /// Powered, lithium-ion battery
+    Lithium {
+        /// Battery rating in watt-hours
+        watt_hours: u16,
+    },
+}
+
+impl MobilityAid {
+    /// IATA SSR code
+    pub fn code(&self) -> &'static str {
+        match self {
+            MobilityAid::Manual => "WCMP",
+            MobilityAid::DryBattery => "WCBD",
+            MobilityAid::WetBattery => "WCBW",
+            MobilityAid::Lithium { .. } => "WCLB",
+        }
+    }
+
+    /// Short description for documents and agents
+    pub fn description(&self) -> &'static str {
+        match self {
+            MobilityAid::Manual => "Own manual wheelchair",
+            MobilityAid::DryBattery => "Own powered wheelchair (dry battery)",
+            MobilityAid::WetBattery => "Own powered wheelchair (wet battery)",
+            MobilityAid::Lithium { .. } => "Own powered wheelchair (lithium battery)",
+        }
+    }
+}
+
+/// A special service request as sent to the airline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ssr {
+    /// IATA SSR code
+    pub code: &'static str,
+    /// Free text
+    pub text: Option<String>,
+}
+
+/// Structured accessibility needs of a passenger
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssistanceNeeds {
+    /// Wheelchair assistance
+    pub wheelchair: Option<WheelchairNeed>,
+    /// Own mobility aid
+    pub mobility_aid: Option<MobilityAid>,
+    /// Blind or visually impaired
+    pub visual: bool,
+    /// Deaf or hard of hearing
+    pub hearing: bool,
+    /// Intellectual or developmental disability needing assistance
+    pub cognitive: bool,
+    /// Travelling with a service animal
+    pub service_animal: bool,
+    /// Details for the airline (SSR free text)
+    pub remarks: Option<String>,
+}
+
+impl AssistanceNeeds {
+    /// Whether no assistance is needed
+    pub fn is_empty(&self) -> bool {
+        self.wheelchair.is_none()
+            && self.mobility_aid.is_none()
+            && !self.visual
+            && !self.hearing
+            && !self.cognitive
+            && !self.service_animal
+    }
+
+    /// Validate the needs can be sent to the airline
+    pub fn validate(&self) -> BookResult<()> {
+        if let Some(ref remarks) = self.remarks {
+            if remarks.len() > MAX_SSR_TEXT_LEN {
+                return Err(BookError::InvalidPassenger(format!(
+                    "Assistance details too long (max {} chars)",
+                    MAX_SSR_TEXT_LEN
+                )));
+            }
+            if !remarks
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || " .,-/()".contains(c))
+            {
+                return Err(BookError::InvalidPassenger(
+                    "Assistance details may only contain letters, digits and basic punctuation"
+                        .into(),
+                ));
+            }
+            if self.is_empty() {
+                return Err(BookError::InvalidPassenger(
+                    "Assistance details given without an assistance type".into(),
+                ));
+            }
+        }
+
+        if let Some(MobilityAid::Lithium { watt_hours }) = self.mobility_aid {
+            if watt_hours == 0 || watt_hours > MAX_LITHIUM_WATT_HOURS {
+                return Err(BookError::InvalidPassenger(format!(
+                    "Lithium wheelchair battery must be 1-{} Wh",
+                    MAX_LITHIUM_WATT_HOURS
+                )));
+            }
+        }
+
+        // DPNA is only actionable if the airline knows what help is needed
+        if self.cognitive && self.remarks.is_none() {
+            return Err(BookError::MissingField("assistance remarks".into()));
+        }
+
+        Ok(())
+    }
+
+    /// SSRs to send to the airline, in a stable order
+    ///
+    /// The free-text remarks are attached to the first SSR.
+    pub fn ssrs(&self) -> Vec<Ssr> {
+        let mut ssrs = Vec::new();
+        if let Some(wheelchair) = self.wheelchair {
+            ssrs.push(Ssr {
+                code: wheelchair.code(),
+                text: None,
+            });
+        }
+        if let Some(aid) = self.mobility_aid {
+            let text = match aid {
+                MobilityAid::Lithium { watt_hours } => Some(format!("{}WH", watt_hours)),
+                _ => None,
+            };
+            ssrs.push(Ssr {
+                code: aid.code(),
+                text,
+            });
+        }
+        for (needed, code) in [
+            (self.visual, "BLND"),
+            (self.hearing, "DEAF"),
+            (self.cognitive, "DPNA"),
+            (self.service_animal, "SVAN"),
+        ] {
+            if needed {
+                ssrs.push(Ssr { code, text: None });
+            }
+        }
+
+        if let (Some(first), Some(remarks)) = (ssrs.first_mut(), &self.remarks) {
+            let remarks = remarks.to_uppercase();
+            first.text = Some(match first.text.take() {
+                Some(text) => format!("{} {}", text, remarks),
+                None => remarks,
+            });
+        }
+        ssrs
+    }
+
+    /// Human-readable lines for booking documents
+    pub fn descriptions(&self) -> Vec<&'static str> {
+        self.ssrs()
+            .iter()
+            .filter_map(|ssr| describe_ssr(ssr.code))
+            .collect()
+    }
+}
+
+/// Describe an assistance SSR code, if it is one
+pub fn describe_ssr(code: &str) -> Option<&'static str> {
+    let description = match code {
+        "WCHR" => WheelchairNeed::Ramp.description(),
+        "WCHS" => WheelchairNeed::Steps.description(),
+        "WCHC" => WheelchairNeed::Cabin.description(),
+        "WCMP" => MobilityAid::Manual.description(),
+        "WCBD" => MobilityAid::DryBattery.description(),
+        "WCBW" => MobilityAid::WetBattery.description(),
+        "WCLB" => MobilityAid::Lithium { watt_hours: 0 }.description(),
+        "BLND" => "Blind or visually impaired",
+        "DEAF" => "Deaf or hard of hearing",
+        "DPNA" => "Intellectual or developmental disability",
+        "SVAN" => "Travelling with a service animal",
+        _ => return None,
+    };
+    Some(description)
+}
+
+/// Airline response to an SSR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsrStatus {
+    /// Sent, no answer yet
+    Requested,
+    /// Confirmed by the airline
+    Confirmed,
+    /// Declined or unable to confirm
+    Declined,
+}
+
+impl SsrStatus {
+    /// Map an airline action/status code ("HK", "UN", ...)
+    pub fn from_action_code(code: &str) -> Option<Self> {
+        match code.trim().to_ascii_uppercase().as_str() {
+            "HK" | "KK" | "KL" | "TK" => Some(SsrStatus::Confirmed),
+            "HN" | "NN" | "PN" | "HL" => Some(SsrStatus::Requested),
+            "UC" | "UN" | "NO" | "HX" | "US" => Some(SsrStatus::Declined),
+            _ => None,
+        }
+    }
+
+    /// Get the status name as a string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SsrStatus::Requested => "requested",
+            SsrStatus::Confirmed => "confirmed",
+            SsrStatus::Declined => "declined",
+        }
+    }
+}
+
+/// Tracking record for one SSR sent to the airline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssistanceRequest {
+    /// IATA SSR code
+    pub code: &'static str,
+    /// Latest airline status
+    pub status: SsrStatus,
+    /// Last status change (Unix timestamp)
+    pub updated_at: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssr_mapping() {
+        let needs = AssistanceNeeds {
+            wheelchair: Some(WheelchairNeed::Steps),
+            mobility_aid: Some(MobilityAid::Lithium { watt_hours: 160 }),
+            visual: true,
+            remarks: Some("Travels with companion".into()),
+            ..Default::default()
+        };
+        needs.validate().unwrap();
+
+        let ssrs = needs.ssrs();
+        let codes: Vec<&str> = ssrs.iter().map(|s| s.code).collect();
+        assert_eq!(codes, ["WCHS", "WCLB", "BLND"]);
+        assert_eq!(ssrs[0].text.as_deref(), Some("TRAVELS WITH COMPANION"));
+        assert_eq!(ssrs[1].text.as_deref(), Some("160WH"));
+        assert_eq!(
+            needs.descriptions()[0],
+            "Wheelchair to aircraft seat (cannot climb steps)"
+        );
+    }
+
+    #[test]
+    fn test_assistance_validation() {
+        assert!(AssistanceNeeds::default().validate().is_ok());
+
+        let too_big = AssistanceNeeds {
+            mobility_aid: Some(MobilityAid::Lithium { watt_hours: 400 }),
+            ..Default::default()
+        };
+        assert!(too_big.validate().is_err());
+
+        let remarks_only = AssistanceNeeds {
+            remarks: Some("Needs help".into()),
+            ..Default::default()
+        };
+        assert!(remarks_only.validate().is_err());
+
+        let cognitive = AssistanceNeeds {
+            cognitive: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            cognitive.validate(),
+            Err(BookError::MissingField(_))
+        ));
+
+        assert_eq!(
+            SsrStatus::from_action_code("hk"),
+            Some(SsrStatus::Confirmed)
+        );
+        assert_eq!(SsrStatus::from_action_code("UN"), Some(SsrStatus::Declined));
+        assert_eq!(SsrStatus::from_action_code("ZZ"), None);
+    }
+}
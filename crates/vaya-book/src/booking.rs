@@ -1,133 +1,26 @@
 //! Booking types and state machine
 
-use ring::rand::{SecureRandom, SystemRandom};
 use time::OffsetDateTime;
+pub use vaya_common::BookingStatus;
 use vaya_common::{CurrencyCode, MinorUnits};
-use vaya_search::FlightOffer;
-
-use crate::passenger::Passenger;
-use crate::payment::PaymentRecord;
+use vaya_search::{FlightOffer, FlightSegment, PassengerType};
+
+use crate::ancillary::BookedAncillary;
+use crate::assistance::SsrStatus;
+use crate::change::PendingChange;
+use crate::notes::{self, NoteCategory, NoteVisibility};
+use crate::passenger::{Passenger, SeatAssignment};
+use crate::payment::{PaymentRecord, RefundRecord};
+use crate::reference::{self, ReferenceGenerator};
 use crate::{BookError, BookResult};
 
-/// Booking status (state machine)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum BookingStatus {
-    /// Initial state - booking created but not confirmed
-    Pending,
-    /// Offer confirmed with provider, awaiting payment
-    Confirmed,
-    /// Payment received, awaiting ticketing
-    PaymentReceived,
-    /// Ticketing in progress
-    Ticketing,
-    /// Fully ticketed
-    Ticketed,
-    /// Booking cancelled
-    Cancelled,
-    /// Booking expired (timeout)
-    Expired,
-    /// Refund in progress
-    RefundPending,
-    /// Fully refunded
-    Refunded,
-    /// Booking failed (error state)
-    Failed,
-}
-
-impl BookingStatus {
-    /// Get status as string
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            BookingStatus::Pending => "PENDING",
-            BookingStatus::Confirmed => "CONFIRMED",
-            BookingStatus::PaymentReceived => "PAYMENT_RECEIVED",
-            BookingStatus::Ticketing => "TICKETING",
-            BookingStatus::Ticketed => "TICKETED",
-            BookingStatus::Cancelled => "CANCELLED",
-            BookingStatus::Expired => "EXPIRED",
-            BookingStatus::RefundPending => "REFUND_PENDING",
-            BookingStatus::Refunded => "REFUNDED",
-            BookingStatus::Failed => "FAILED",
-        }
-    }
-
-    /// Check if this is a terminal state
-    pub fn is_terminal(&self) -> bool {
-        matches!(
-            self,
-            BookingStatus::Ticketed
-                | BookingStatus::Cancelled
-                | BookingStatus::Expired
-                | BookingStatus::Refunded
-                | BookingStatus::Failed
-        )
-    }
-
-    /// Check if booking can be cancelled from this state
-    pub fn can_cancel(&self) -> bool {
-        matches!(
-            self,
-            BookingStatus::Pending
-                | BookingStatus::Confirmed
-                | BookingStatus::PaymentReceived
-                | BookingStatus::Ticketed
-        )
-    }
-
-    /// Check if booking can be ticketed from this state
-    pub fn can_ticket(&self) -> bool {
-        matches!(self, BookingStatus::PaymentReceived)
-    }
-
-    /// Check if booking can receive payment
-    pub fn can_pay(&self) -> bool {
-        matches!(self, BookingStatus::Confirmed)
-    }
-
-    /// Validate state transition
-    pub fn can_transition_to(&self, target: BookingStatus) -> bool {
-        match (self, target) {
-            // From Pending
-            (BookingStatus::Pending, BookingStatus::Confirmed) => true,
-            (BookingStatus::Pending, BookingStatus::Expired) => true,
-            (BookingStatus::Pending, BookingStatus::Cancelled) => true,
-            (BookingStatus::Pending, BookingStatus::Failed) => true,
-
-            // From Confirmed
-            (BookingStatus::Confirmed, BookingStatus::PaymentReceived) => true,
-            (BookingStatus::Confirmed, BookingStatus::Expired) => true,
-            (BookingStatus::Confirmed, BookingStatus::Cancelled) => true,
-            (BookingStatus::Confirmed, BookingStatus::Failed) => true,
-
-            // From PaymentReceived
-            (BookingStatus::PaymentReceived, BookingStatus::Ticketing) => true,
-            (BookingStatus::PaymentReceived, BookingStatus::Cancelled) => true,
-            (BookingStatus::PaymentReceived, BookingStatus::RefundPending) => true,
-            (BookingStatus::PaymentReceived, BookingStatus::Failed) => true,
-
-            // From Ticketing
-            (BookingStatus::Ticketing, BookingStatus::Ticketed) => true,
-            (BookingStatus::Ticketing, BookingStatus::Failed) => true,
-
-            // From Ticketed
-            (BookingStatus::Ticketed, BookingStatus::Cancelled) => true,
-            (BookingStatus::Ticketed, BookingStatus::RefundPending) => true,
-
-            // From RefundPending
-            (BookingStatus::RefundPending, BookingStatus::Refunded) => true,
-            (BookingStatus::RefundPending, BookingStatus::Failed) => true,
-
-            // All other transitions invalid
-            _ => false,
-        }
-    }
-}
-
 /// A booking record
 #[derive(Debug, Clone)]
 pub struct Booking {
     /// Unique booking reference (PNR)
     pub pnr: String,
+    /// Customer-facing `VAY-` booking reference
+    pub reference: String,
     /// User ID who made the booking
     pub user_id: String,
     /// Current status
@@ -138,6 +31,8 @@ pub struct Booking {
     pub passengers: Vec<Passenger>,
     /// Payment records
     pub payments: Vec<PaymentRecord>,
+    /// Refund records
+    pub refunds: Vec<RefundRecord>,
     /// Total price
     pub total_price: MinorUnits,
     /// Currency
@@ -164,16 +59,48 @@ pub struct Booking {
     pub version: u32,
     /// Notes
     pub notes: Vec<BookingNote>,
+    /// Tags for admin filtering (normalized, sorted)
+    pub tags: Vec<String>,
+    /// Change quoted and awaiting the traveler's decision
+    pub pending_change: Option<PendingChange>,
+    /// Extras attached to the booking
+    pub ancillaries: Vec<BookedAncillary>,
 }
 
 impl Booking {
-    /// Create a new booking
+    /// Create a new booking with freshly generated, unchecked references
+    ///
+    /// Use [`Booking::with_references`] with codes checked against the
+    /// booking store when persisting.
     pub fn new(
         user_id: impl Into<String>,
         offer: FlightOffer,
         passengers: Vec<Passenger>,
     ) -> BookResult<Self> {
-        let pnr = generate_pnr()?;
+        let generator = ReferenceGenerator::new();
+        Self::with_references(
+            generator.pnr()?,
+            generator.reference()?,
+            user_id,
+            offer,
+            passengers,
+        )
+    }
+
+    /// Create a new booking with a given PNR and booking reference
+    pub fn with_references(
+        pnr: String,
+        reference: String,
+        user_id: impl Into<String>,
+        offer: FlightOffer,
+        passengers: Vec<Passenger>,
+    ) -> BookResult<Self> {
+        if !reference::is_valid_pnr(&pnr) {
+            return Err(BookError::InvalidReference(pnr));
+        }
+        if !reference::is_valid_reference(&reference) {
+            return Err(BookError::InvalidReference(reference));
+        }
         let now = OffsetDateTime::now_utc().unix_timestamp();
 
         // Calculate total price
@@ -181,12 +108,14 @@ impl Booking {
         let currency = offer.price.currency;
 
         let mut booking = Self {
-            pnr: pnr.clone(),
+            pnr,
+            reference,
             user_id: user_id.into(),
             status: BookingStatus::Pending,
             offer,
             passengers,
             payments: Vec::new(),
+            refunds: Vec::new(),
             total_price,
             currency,
             created_at: now,
@@ -200,6 +129,9 @@ impl Booking {
             history: Vec::new(),
             version: 1,
             notes: Vec::new(),
+            tags: Vec::new(),
+            pending_change: None,
+            ancillaries: Vec::new(),
         };
 
         // Record initial state
@@ -267,7 +199,7 @@ impl Booking {
         if !self.status.can_pay() {
             return Err(BookError::InvalidStateTransition {
                 from: self.status.as_str().to_string(),
-                to: "PAYMENT_RECEIVED".to_string(),
+                to: BookingStatus::PaymentReceived.as_str().to_string(),
             });
         }
 
@@ -351,17 +283,234 @@ impl Booking {
         MinorUnits::new(sum)
     }
 
-    /// Add a note
+    /// Amount still to pay: the total, including extras, less payments
+    pub fn amount_due(&self) -> MinorUnits {
+        MinorUnits::new((self.total_price.as_i64() - self.total_paid().as_i64()).max(0))
+    }
+
+    /// Add a general internal note
     pub fn add_note(&mut self, content: &str, author: &str) {
         let now = OffsetDateTime::now_utc().unix_timestamp();
         self.notes.push(BookingNote {
             content: content.to_string(),
             author: author.to_string(),
             timestamp: now,
+            category: NoteCategory::General,
+            visibility: NoteVisibility::Internal,
+            mentions: notes::parse_mentions(content),
         });
         self.updated_at = now;
     }
 
+    /// Add a categorized note written by an agent
+    pub fn add_note_with(
+        &mut self,
+        category: NoteCategory,
+        visibility: NoteVisibility,
+        content: &str,
+        author: &str,
+    ) -> BookResult<&BookingNote> {
+        notes::validate_note(content)?;
+        if author.trim().is_empty() {
+            return Err(BookError::MissingField("author".into()));
+        }
+        // Fraud review must never leak to the traveler
+        if category == NoteCategory::FraudReview && visibility == NoteVisibility::Customer {
+            return Err(BookError::InvalidNote(
+                "Fraud review notes must be internal".into(),
+            ));
+        }
+
+        self.add_note(content, author);
+        let note = self.notes.last_mut().expect("note just added");
+        note.category = category;
+        note.visibility = visibility;
+        Ok(note)
+    }
+
+    /// Notes the traveler may see
+    pub fn customer_notes(&self) -> impl Iterator<Item = &BookingNote> {
+        self.notes
+            .iter()
+            .filter(|n| n.visibility == NoteVisibility::Customer)
+    }
+
+    /// Tag the booking; returns false if it already had the tag
+    pub fn add_tag(&mut self, tag: &str) -> BookResult<bool> {
+        let tag = notes::normalize_tag(tag)?;
+        let idx = match self.tags.binary_search(&tag) {
+            Ok(_) => return Ok(false),
+            Err(idx) => idx,
+        };
+        if self.tags.len() >= notes::MAX_TAGS {
+            return Err(BookError::InvalidNote(format!(
+                "Booking already has {} tags",
+                notes::MAX_TAGS
+            )));
+        }
+        self.tags.insert(idx, tag);
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        Ok(true)
+    }
+
+    /// Remove a tag; returns false if the booking did not have it
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim().to_ascii_lowercase();
+        match self.tags.binary_search(&tag) {
+            Ok(idx) => {
+                self.tags.remove(idx);
+                self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Check if the booking has a tag
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .binary_search(&tag.trim().to_ascii_lowercase())
+            .is_ok()
+    }
+
+    /// Check if the booking has every tag in `tags`
+    pub fn has_all_tags<S: AsRef<str>>(&self, tags: &[S]) -> bool {
+        tags.iter().all(|t| self.has_tag(t.as_ref()))
+    }
+
+    /// Passengers with assistance the airline has not yet confirmed
+    pub fn unconfirmed_assistance(&self) -> Vec<&Passenger> {
+        self.passengers
+            .iter()
+            .filter(|p| !p.assistance_confirmed())
+            .collect()
+    }
+
+    /// Record an airline reply to a passenger's assistance SSR
+    ///
+    /// Declines are noted on the booking so an agent can follow up.
+    pub fn record_assistance_status(
+        &mut self,
+        passenger_id: u8,
+        code: &str,
+        action_code: &str,
+        actor: &str,
+    ) -> BookResult<SsrStatus> {
+        let passenger = self.passenger_mut(passenger_id)?;
+        let status = passenger.update_assistance_status(code, action_code)?;
+        let name = passenger.pnr_name();
+        if status == SsrStatus::Declined {
+            self.add_note(
+                &format!(
+                    "Airline declined {} assistance for {} ({})",
+                    code.to_ascii_uppercase(),
+                    name,
+                    action_code.to_ascii_uppercase()
+                ),
+                actor,
+            );
+        } else {
+            self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        }
+        Ok(status)
+    }
+
+    /// Flight segments in itinerary order (outbound, then return)
+    pub fn segments(&self) -> impl Iterator<Item = &FlightSegment> {
+        self.offer
+            .outbound
+            .segments
+            .iter()
+            .chain(self.offer.inbound.iter().flat_map(|leg| &leg.segments))
+    }
+
+    /// Assign a seat, replacing the passenger's seat on that segment
+    ///
+    /// The seat price is added to the price breakdown and the total.
+    pub fn assign_seat(&mut self, passenger_id: u8, seat: SeatAssignment) -> BookResult<()> {
+        self.check_seat_selection()?;
+        if seat.segment >= self.segments().count() {
+            return Err(BookError::InvalidSeat(format!(
+                "Booking has no segment {}",
+                seat.segment
+            )));
+        }
+        if seat.price.as_i64() < 0 {
+            return Err(BookError::InvalidSeat(
+                "Seat price cannot be negative".into(),
+            ));
+        }
+        let taken = self.passengers.iter().any(|p| {
+            p.id != passenger_id
+                && p.seat_for(seat.segment)
+                    .is_some_and(|s| s.seat_number == seat.seat_number)
+        });
+        if taken {
+            return Err(BookError::InvalidSeat(format!(
+                "Seat {} is already assigned on segment {}",
+                seat.seat_number, seat.segment
+            )));
+        }
+
+        let passenger = self.passenger_mut(passenger_id)?;
+        if passenger.pax_type == PassengerType::Infant {
+            return Err(BookError::InvalidSeat(
+                "Infants travel on an adult's lap".into(),
+            ));
+        }
+        let previous = passenger
+            .seats
+            .iter()
+            .position(|s| s.segment == seat.segment)
+            .map(|idx| passenger.seats.remove(idx).price.as_i64())
+            .unwrap_or(0);
+        let delta = seat.price.as_i64() - previous;
+        passenger.seats.push(seat);
+        self.adjust_seat_charges(delta);
+        Ok(())
+    }
+
+    /// Release a passenger's seat on a segment, removing its charge
+    pub fn release_seat(
+        &mut self,
+        passenger_id: u8,
+        segment: usize,
+    ) -> BookResult<Option<SeatAssignment>> {
+        self.check_seat_selection()?;
+        let passenger = self.passenger_mut(passenger_id)?;
+        let released = match passenger.seats.iter().position(|s| s.segment == segment) {
+            Some(idx) => passenger.seats.remove(idx),
+            None => return Ok(None),
+        };
+        self.adjust_seat_charges(-released.price.as_i64());
+        Ok(Some(released))
+    }
+
+    fn check_seat_selection(&self) -> BookResult<()> {
+        if !self.status.can_select_seats() {
+            return Err(BookError::InvalidSeat(format!(
+                "Seats cannot be changed in {} status",
+                self.status.as_str()
+            )));
+        }
+        Ok(())
+    }
+
+    fn passenger_mut(&mut self, passenger_id: u8) -> BookResult<&mut Passenger> {
+        self.passengers
+            .iter_mut()
+            .find(|p| p.id == passenger_id)
+            .ok_or_else(|| {
+                BookError::InvalidPassenger(format!("No passenger with id {}", passenger_id))
+            })
+    }
+
+    fn adjust_seat_charges(&mut self, delta: i64) {
+        self.offer.price.seats = MinorUnits::new(self.offer.price.seats.as_i64() + delta);
+        self.total_price = MinorUnits::new(self.total_price.as_i64() + delta);
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+    }
+
     /// Get time remaining until next deadline
     pub fn time_to_deadline(&self) -> Option<i64> {
         let now = OffsetDateTime::now_utc().unix_timestamp();
@@ -400,23 +549,12 @@ pub struct BookingNote {
     pub author: String,
     /// Timestamp
     pub timestamp: i64,
-}
-
-/// Generate a PNR (6 alphanumeric characters)
-fn generate_pnr() -> BookResult<String> {
-    const CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789"; // Avoid confusing chars (0, O, 1, I)
-    let rng = SystemRandom::new();
-    let mut bytes = [0u8; 6];
-
-    rng.fill(&mut bytes)
-        .map_err(|_| BookError::Internal("Failed to generate PNR".into()))?;
-
-    let pnr: String = bytes
-        .iter()
-        .map(|b| CHARS[(*b as usize) % CHARS.len()] as char)
-        .collect();
-
-    Ok(pnr)
+    /// Team the note is for
+    pub category: NoteCategory,
+    /// Who can see the note
+    pub visibility: NoteVisibility,
+    /// Agent IDs mentioned in the content
+    pub mentions: Vec<String>,
 }
 
 #[cfg(test)]
@@ -436,6 +574,7 @@ mod tests {
                 base_fare: MinorUnits::new(10000),
                 taxes: MinorUnits::new(2000),
                 surcharges: MinorUnits::new(500),
+                seats: MinorUnits::ZERO,
                 currency: CurrencyCode::SGD,
             },
             price_per_pax: vec![],
@@ -445,6 +584,8 @@ mod tests {
             changeable: true,
             baggage: None,
             fare_rules: None,
+            self_transfer: false,
+            variation: None,
         }
     }
 
@@ -458,6 +599,143 @@ mod tests {
         assert_eq!(booking.history.len(), 1);
     }
 
+    #[test]
+    fn test_assistance_decline_is_noted() {
+        use crate::assistance::{AssistanceNeeds, WheelchairNeed};
+
+        let dob = time::Date::from_calendar_date(1948, time::Month::May, 9).unwrap();
+        let mut pax = Passenger::adult("Tan", "Ah Kow", dob, vaya_common::Gender::Male);
+        pax.id = 1;
+        pax.request_assistance(AssistanceNeeds {
+            wheelchair: Some(WheelchairNeed::Ramp),
+            ..Default::default()
+        })
+        .unwrap();
+        let mut booking = Booking::new("user-123", mock_offer(), vec![pax]).unwrap();
+        assert_eq!(booking.unconfirmed_assistance().len(), 1);
+
+        let status = booking
+            .record_assistance_status(1, "WCHR", "UN", "SYSTEM:queue")
+            .unwrap();
+        assert_eq!(status, SsrStatus::Declined);
+        assert!(booking.notes[0].content.contains("declined WCHR"));
+
+        booking
+            .record_assistance_status(1, "WCHR", "HK", "SYSTEM:queue")
+            .unwrap();
+        assert!(booking.unconfirmed_assistance().is_empty());
+        assert!(booking
+            .record_assistance_status(2, "WCHR", "HK", "agent")
+            .is_err());
+    }
+
+    #[test]
+    fn test_seat_selection() {
+        use vaya_common::{AirlineCode, IataCode};
+        use vaya_search::CabinClass;
+
+        let date = time::Date::from_calendar_date(2025, time::Month::June, 1).unwrap();
+        let mut offer = mock_offer();
+        offer.outbound.segments.push(FlightSegment {
+            airline: AirlineCode::SQ,
+            flight_number: "107".into(),
+            marketing_airline: None,
+            origin: IataCode::SIN,
+            destination: IataCode::KUL,
+            departure_date: date,
+            departure_time: time::Time::from_hms(9, 0, 0).unwrap(),
+            arrival_date: date,
+            arrival_time: time::Time::from_hms(10, 0, 0).unwrap(),
+            duration_minutes: 60,
+            aircraft: None,
+            cabin: CabinClass::Economy,
+            booking_class: 'Y',
+            seats_remaining: None,
+        });
+        let dob = time::Date::from_calendar_date(1990, time::Month::May, 9).unwrap();
+        let mut first = Passenger::adult("Tan", "Mei", dob, vaya_common::Gender::Female);
+        first.id = 1;
+        let mut second = first.clone();
+        second.id = 2;
+        let mut booking = Booking::new("user-123", offer, vec![first, second]).unwrap();
+        assert_eq!(booking.total_price, MinorUnits::new(12500));
+
+        booking
+            .assign_seat(1, SeatAssignment::new(0, "12a", MinorUnits::new(3000)))
+            .unwrap();
+        assert_eq!(
+            booking.passengers[0].seat_for(0).unwrap().seat_number,
+            "12A"
+        );
+        assert_eq!(booking.offer.price.seats, MinorUnits::new(3000));
+        assert_eq!(booking.total_price, MinorUnits::new(15500));
+
+        // Taken by another passenger, or no such segment
+        assert!(booking
+            .assign_seat(2, SeatAssignment::new(0, "12A", MinorUnits::ZERO))
+            .is_err());
+        assert!(booking
+            .assign_seat(2, SeatAssignment::new(1, "12B", MinorUnits::ZERO))
+            .is_err());
+
+        // Switching to a free seat refunds the difference
+        booking
+            .assign_seat(1, SeatAssignment::new(0, "30C", MinorUnits::ZERO))
+            .unwrap();
+        assert_eq!(booking.passengers[0].seats.len(), 1);
+        assert_eq!(booking.total_price, MinorUnits::new(12500));
+
+        booking
+            .assign_seat(2, SeatAssignment::new(0, "12A", MinorUnits::new(3000)))
+            .unwrap();
+        let released = booking.release_seat(2, 0).unwrap().unwrap();
+        assert_eq!(released.seat_number, "12A");
+        assert_eq!(booking.offer.price.seats, MinorUnits::ZERO);
+        assert!(booking.release_seat(2, 0).unwrap().is_none());
+
+        booking.confirm("GDS1", "system").unwrap();
+        booking
+            .transition(BookingStatus::Cancelled, "test", "system")
+            .unwrap();
+        assert!(matches!(
+            booking.assign_seat(2, SeatAssignment::new(0, "14A", MinorUnits::ZERO)),
+            Err(BookError::InvalidSeat(_))
+        ));
+    }
+
+    #[test]
+    fn test_notes_and_tags() {
+        let mut booking = Booking::new("user-123", mock_offer(), vec![]).unwrap();
+
+        let note = booking
+            .add_note_with(
+                NoteCategory::Support,
+                NoteVisibility::Customer,
+                "Rebooked on the 18:05, @siti to confirm seats",
+                "agent-1",
+            )
+            .unwrap();
+        assert_eq!(note.mentions, ["siti"]);
+        booking.add_note("Hold extended", "SYSTEM:hold");
+        assert_eq!(booking.customer_notes().count(), 1);
+        assert!(booking
+            .add_note_with(
+                NoteCategory::FraudReview,
+                NoteVisibility::Customer,
+                "Card velocity",
+                "agent-1"
+            )
+            .is_err());
+
+        assert!(booking.add_tag("VIP").unwrap());
+        assert!(!booking.add_tag("vip").unwrap());
+        booking.add_tag("chargeback").unwrap();
+        assert_eq!(booking.tags, ["chargeback", "vip"]);
+        assert!(booking.has_all_tags(&["vip", "Chargeback"]));
+        assert!(booking.remove_tag("vip"));
+        assert!(!booking.has_tag("vip"));
+    }
+
     #[test]
     fn test_status_transitions() {
         assert!(BookingStatus::Pending.can_transition_to(BookingStatus::Confirmed));
@@ -485,6 +763,7 @@ mod tests {
             status: crate::payment::PaymentStatus::Completed,
             provider_ref: Some("stripe-123".into()),
             timestamp: 0,
+            refunded: MinorUnits::ZERO,
         };
         assert!(booking.mark_paid(payment, "system").is_ok());
         assert_eq!(booking.status, BookingStatus::PaymentReceived);
@@ -497,7 +776,8 @@ mod tests {
             .mark_ticketed("ABC123", vec!["TKT001".into()], "system")
             .is_ok());
         assert_eq!(booking.status, BookingStatus::Ticketed);
-        assert!(booking.status.is_terminal());
+        // Ticketed bookings can still be flown, missed, changed or refunded
+        assert!(!booking.status.is_terminal());
     }
 
     #[test]
@@ -510,15 +790,36 @@ mod tests {
     }
 
     #[test]
-    fn test_pnr_generation() {
-        let pnr = generate_pnr().unwrap();
-        assert_eq!(pnr.len(), 6);
-        assert!(pnr.chars().all(|c| c.is_ascii_alphanumeric()));
+    fn test_booking_references() {
+        let booking = Booking::new("user-123", mock_offer(), vec![]).unwrap();
+        assert!(reference::is_valid_pnr(&booking.pnr));
+        assert!(booking.reference.starts_with("VAY-"));
+
+        let booking = Booking::with_references(
+            "ABC234".into(),
+            "VAY-7KQ2M9XP".into(),
+            "user-123",
+            mock_offer(),
+            vec![],
+        )
+        .unwrap();
+        assert_eq!(booking.pnr, "ABC234");
+        assert_eq!(booking.reference, "VAY-7KQ2M9XP");
+
+        let err = Booking::with_references(
+            "ABC10O".into(),
+            "VAY-7KQ2M9XP".into(),
+            "user-123",
+            mock_offer(),
+            vec![],
+        );
+        assert!(matches!(err, Err(BookError::InvalidReference(_))));
     }
 
     #[test]
     fn test_terminal_states() {
-        assert!(BookingStatus::Ticketed.is_terminal());
+        assert!(!BookingStatus::Ticketed.is_terminal());
+        assert!(BookingStatus::Completed.is_terminal());
         assert!(BookingStatus::Cancelled.is_terminal());
         assert!(BookingStatus::Expired.is_terminal());
         assert!(!BookingStatus::Pending.is_terminal());
@@ -0,0 +1,404 @@
+//! Booking changes (rebooking and date changes)
+//!
+//! A change takes two steps. [`Booking::request_change`] prices the new
+//! flights against the booked fare and moves the booking to
+//! `ChangeRequested` with a [`ChangeQuote`]: the fare difference plus the
+//! change fee from the fare rules. The traveler then accepts the quote with
+//! [`Booking::confirm_change`], which swaps in the new offer and collects
+//! any amount due on a paid booking, or turns it down with
+//! [`Booking::decline_change`], which puts the booking back as it was.
+//!
+//! A confirmed change on an unpaid booking returns it to `Confirmed`
+//! awaiting payment of the new total; on a paid booking it stays
+//! `ChangeConfirmed` until the tickets are reissued.
+
+use time::OffsetDateTime;
+use vaya_common::{CurrencyCode, MinorUnits};
+use vaya_search::FlightOffer;
+
+use crate::booking::{Booking, BookingStatus};
+use crate::payment::PaymentRecord;
+use crate::{BookError, BookResult};
+
+/// Change conditions of the booked fare
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeRules {
+    /// Whether the fare may be changed at all
+    pub changeable: bool,
+    /// Fee charged per change, in the booking currency
+    pub change_fee: MinorUnits,
+}
+
+impl ChangeRules {
+    /// Changeable for the given fee
+    pub fn with_fee(change_fee: MinorUnits) -> Self {
+        Self {
+            changeable: true,
+            change_fee,
+        }
+    }
+
+    /// Changes not permitted
+    pub fn not_changeable() -> Self {
+        Self {
+            changeable: false,
+            change_fee: MinorUnits::ZERO,
+        }
+    }
+}
+
+/// Price of moving a booking onto a new offer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeQuote {
+    /// Offer the booking would move to
+    pub offer_id: String,
+    /// Currency of all amounts
+    pub currency: CurrencyCode,
+    /// Fare total of the current offer
+    pub current_fare: MinorUnits,
+    /// Fare total of the new offer
+    pub new_fare: MinorUnits,
+    /// New fare minus current fare (negative when the new fare is cheaper)
+    pub fare_difference: MinorUnits,
+    /// Change fee from the fare rules
+    pub change_fee: MinorUnits,
+    /// Amount the traveler pays to accept the change
+    pub amount_due: MinorUnits,
+    /// Unused value of the current fare when the new one is cheaper
+    pub residual: MinorUnits,
+    /// When the quote was made (Unix timestamp)
+    pub quoted_at: i64,
+    /// When the new offer's price lapses (Unix timestamp)
+    pub expires_at: Option<i64>,
+}
+
+impl ChangeQuote {
+    /// Quote a move from `current_fare` to `offer`
+    pub fn new(current_fare: MinorUnits, offer: &FlightOffer, rules: &ChangeRules) -> Self {
+        let new_fare = offer.price.fare();
+        let difference = new_fare.as_i64() - current_fare.as_i64();
+        Self {
+            offer_id: offer.id.clone(),
+            currency: offer.price.currency,
+            current_fare,
+            new_fare,
+            fare_difference: MinorUnits::new(difference),
+            change_fee: rules.change_fee,
+            amount_due: MinorUnits::new(difference.max(0) + rules.change_fee.as_i64()),
+            residual: MinorUnits::new((-difference).max(0)),
+            quoted_at: OffsetDateTime::now_utc().unix_timestamp(),
+            expires_at: offer.expires_at,
+        }
+    }
+
+    /// Check if the quoted price has lapsed
+    pub fn is_expired(&self) -> bool {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.expires_at.map(|e| now > e).unwrap_or(false)
+    }
+}
+
+/// A quoted change awaiting the traveler's decision
+#[derive(Debug, Clone)]
+pub struct PendingChange {
+    /// The quote
+    pub quote: ChangeQuote,
+    /// Offer the booking moves to on confirmation
+    pub offer: FlightOffer,
+    /// Status to return to if the change is declined
+    pub previous_status: BookingStatus,
+    /// Who requested the change
+    pub requested_by: String,
+}
+
+impl Booking {
+    /// Quote moving the booking onto `new_offer` and hold the quote for confirmation
+    pub fn request_change(
+        &mut self,
+        new_offer: FlightOffer,
+        rules: &ChangeRules,
+        actor: &str,
+    ) -> BookResult<&ChangeQuote> {
+        if !self
+            .status
+            .can_transition_to(BookingStatus::ChangeRequested)
+        {
+            return Err(BookError::InvalidStateTransition {
+                from: self.status.as_str().to_string(),
+                to: BookingStatus::ChangeRequested.as_str().to_string(),
+            });
+        }
+        if !self.offer.changeable || !rules.changeable {
+            return Err(BookError::NotChangeable(
+                "Fare rules do not permit changes".into(),
+            ));
+        }
+        if new_offer.price.currency != self.currency {
+            return Err(BookError::NotChangeable(format!(
+                "New offer is priced in {}, booking in {}",
+                new_offer.price.currency, self.currency
+            )));
+        }
+        if new_offer.is_expired() {
+            return Err(BookError::OfferExpired);
+        }
+
+        let quote = ChangeQuote::new(self.offer.price.fare(), &new_offer, rules);
+        let previous_status = self.status;
+        self.transition(
+            BookingStatus::ChangeRequested,
+            &format!("Change to offer {} requested", new_offer.id),
+            actor,
+        )?;
+
+        let pending = self.pending_change.insert(PendingChange {
+            quote,
+            offer: new_offer,
+            previous_status,
+            requested_by: actor.to_string(),
+        });
+        Ok(&pending.quote)
+    }
+
+    /// Accept the pending change
+    ///
+    /// A paid booking must come with a payment covering the amount due.
+    /// Seats and per-segment extras were for the old flights and are
+    /// dropped; trip-wide extras stay on the total.
+    pub fn confirm_change(
+        &mut self,
+        payment: Option<PaymentRecord>,
+        actor: &str,
+    ) -> BookResult<ChangeQuote> {
+        let pending = self.pending_change_or_err(BookingStatus::ChangeConfirmed)?;
+        if pending.quote.is_expired() {
+            return Err(BookError::OfferExpired);
+        }
+
+        let paid = pending.previous_status != BookingStatus::Confirmed;
+        let due = pending.quote.amount_due;
+        let payment = match payment {
+            Some(p) if p.currency != pending.quote.currency => {
+                return Err(BookError::InvalidPayment(format!(
+                    "Payment in {}, change quoted in {}",
+                    p.currency, pending.quote.currency
+                )))
+            }
+            Some(p) if paid && p.amount.as_i64() < due.as_i64() => {
+                return Err(BookError::InsufficientFunds)
+            }
+            None if paid && due.as_i64() > 0 => {
+                return Err(BookError::PaymentFailed(format!(
+                    "Change requires payment of {}",
+                    due.as_i64()
+                )))
+            }
+            payment => payment,
+        };
+
+        let pending = self.pending_change.take().expect("checked above");
+        self.transition(
+            BookingStatus::ChangeConfirmed,
+            &format!("Change to offer {} confirmed", pending.quote.offer_id),
+            actor,
+        )?;
+        if let Some(payment) = payment {
+            self.payments.push(payment);
+        }
+        self.offer = pending.offer;
+        for passenger in &mut self.passengers {
+            passenger.seats.clear();
+        }
+        self.ancillaries.retain(|a| !a.kind.is_per_segment());
+        self.total_price = MinorUnits::new(
+            pending.quote.new_fare.as_i64()
+                + pending.quote.change_fee.as_i64()
+                + self.ancillary_total().as_i64(),
+        );
+        self.add_note(
+            &format!(
+                "Changed to offer {}: fare difference {}, change fee {} ({})",
+                pending.quote.offer_id,
+                pending.quote.fare_difference.as_i64(),
+                pending.quote.change_fee.as_i64(),
+                pending.quote.currency
+            ),
+            actor,
+        );
+
+        if !paid {
+            self.transition(
+                BookingStatus::Confirmed,
+                "Awaiting payment for changed itinerary",
+                actor,
+            )?;
+        }
+        Ok(pending.quote)
+    }
+
+    /// Turn down the pending change, restoring the booking's previous status
+    pub fn decline_change(&mut self, reason: &str, actor: &str) -> BookResult<()> {
+        let previous = self
+            .pending_change_or_err(BookingStatus::Confirmed)?
+            .previous_status;
+
+        // Declining must not extend the original deadlines
+        let (payment_deadline, ticketing_deadline) =
+            (self.payment_deadline, self.ticketing_deadline);
+        self.transition(previous, reason, actor)?;
+        self.payment_deadline = payment_deadline;
+        self.ticketing_deadline = ticketing_deadline;
+        self.pending_change = None;
+        Ok(())
+    }
+
+    fn pending_change_or_err(&self, target: BookingStatus) -> BookResult<&PendingChange> {
+        match (&self.pending_change, self.status) {
+            (Some(pending), BookingStatus::ChangeRequested) => Ok(pending),
+            _ => Err(BookError::InvalidStateTransition {
+                from: self.status.as_str().to_string(),
+                to: target.as_str().to_string(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, paid};
+
+    fn offer(id: &str, base: i64) -> FlightOffer {
+        let mut offer = testing::offer(id, base);
+        offer.price.taxes = MinorUnits::new(2000);
+        offer.refundable = false;
+        offer
+    }
+
+    fn ticketed_booking() -> Booking {
+        let mut booking = Booking::new(
+            "user-1",
+            offer("offer-1", 30000),
+            vec![testing::passenger()],
+        )
+        .unwrap();
+        booking.confirm("PROV-1", "system").unwrap();
+        booking.mark_paid(paid("pay-1", 32000), "system").unwrap();
+        booking.start_ticketing("system").unwrap();
+        booking
+            .mark_ticketed("ABC123", vec!["TKT1".into()], "system")
+            .unwrap();
+        booking
+    }
+
+    #[test]
+    fn test_quote_amounts() {
+        let rules = ChangeRules::with_fee(MinorUnits::new(15000));
+
+        let dearer = ChangeQuote::new(MinorUnits::new(32000), &offer("b", 40000), &rules);
+        assert_eq!(dearer.fare_difference.as_i64(), 10000);
+        assert_eq!(dearer.amount_due.as_i64(), 25000);
+        assert_eq!(dearer.residual.as_i64(), 0);
+
+        let cheaper = ChangeQuote::new(MinorUnits::new(32000), &offer("c", 20000), &rules);
+        assert_eq!(cheaper.fare_difference.as_i64(), -10000);
+        assert_eq!(cheaper.amount_due.as_i64(), 15000);
+        assert_eq!(cheaper.residual.as_i64(), 10000);
+    }
+
+    #[test]
+    fn test_change_ticketed_booking() {
+        let mut booking = ticketed_booking();
+        let rules = ChangeRules::with_fee(MinorUnits::new(15000));
+
+        let quote = booking
+            .request_change(offer("offer-2", 40000), &rules, "user-1")
+            .unwrap()
+            .clone();
+        assert_eq!(booking.status, BookingStatus::ChangeRequested);
+        assert_eq!(quote.amount_due.as_i64(), 25000);
+
+        // A paid booking must pay the amount due
+        assert!(matches!(
+            booking.confirm_change(None, "user-1"),
+            Err(BookError::PaymentFailed(_))
+        ));
+        assert!(matches!(
+            booking.confirm_change(Some(paid("pay-2", 100)), "user-1"),
+            Err(BookError::InsufficientFunds)
+        ));
+
+        booking
+            .confirm_change(Some(paid("pay-2", 25000)), "user-1")
+            .unwrap();
+        assert_eq!(booking.status, BookingStatus::ChangeConfirmed);
+        assert_eq!(booking.offer.id, "offer-2");
+        assert_eq!(booking.total_price.as_i64(), 57000);
+        assert_eq!(booking.payments.len(), 2);
+        assert!(booking.pending_change.is_none());
+
+        // Tickets are reissued from the confirmed change
+        booking.start_ticketing("system").unwrap();
+        booking
+            .mark_ticketed("ABC123", vec!["TKT2".into()], "system")
+            .unwrap();
+        assert_eq!(booking.ticket_numbers, vec!["TKT2".to_string()]);
+    }
+
+    #[test]
+    fn test_change_unpaid_booking_and_decline() {
+        let mut booking = Booking::new(
+            "user-1",
+            offer("offer-1", 30000),
+            vec![testing::passenger()],
+        )
+        .unwrap();
+        let rules = ChangeRules::with_fee(MinorUnits::ZERO);
+
+        // Pending bookings cannot be changed yet
+        assert!(booking
+            .request_change(offer("offer-2", 25000), &rules, "user-1")
+            .is_err());
+
+        booking.confirm("PROV-1", "system").unwrap();
+        let deadline = booking.payment_deadline;
+        booking
+            .request_change(offer("offer-2", 25000), &rules, "user-1")
+            .unwrap();
+        booking
+            .decline_change("Traveler kept original flights", "user-1")
+            .unwrap();
+        assert_eq!(booking.status, BookingStatus::Confirmed);
+        assert_eq!(booking.payment_deadline, deadline);
+        assert_eq!(booking.offer.id, "offer-1");
+
+        booking
+            .request_change(offer("offer-3", 25000), &rules, "user-1")
+            .unwrap();
+        booking.confirm_change(None, "user-1").unwrap();
+        assert_eq!(booking.status, BookingStatus::Confirmed);
+        assert_eq!(booking.total_price.as_i64(), 27000);
+        assert!(booking.status.can_pay());
+    }
+
+    #[test]
+    fn test_change_not_permitted() {
+        let mut booking = ticketed_booking();
+
+        let err = booking
+            .request_change(offer("offer-2", 40000), &ChangeRules::not_changeable(), "u")
+            .unwrap_err();
+        assert!(matches!(err, BookError::NotChangeable(_)));
+
+        let mut other = offer("offer-3", 40000);
+        other.price.currency = CurrencyCode::SGD;
+        let err = booking
+            .request_change(other, &ChangeRules::with_fee(MinorUnits::ZERO), "u")
+            .unwrap_err();
+        assert!(matches!(err, BookError::NotChangeable(_)));
+        assert_eq!(booking.status, BookingStatus::Ticketed);
+
+        assert!(booking.confirm_change(None, "u").is_err());
+        assert!(booking.decline_change("none pending", "u").is_err());
+    }
+}
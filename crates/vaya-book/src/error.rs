@@ -15,8 +15,16 @@ pub enum BookError {
     InvalidContact(String),
     /// Invalid payment data
     InvalidPayment(String),
+    /// Invalid note or tag
+    InvalidNote(String),
+    /// Seat selection not possible
+    InvalidSeat(String),
+    /// Extra cannot be attached or detached
+    InvalidAncillary(String),
     /// Missing required field
     MissingField(String),
+    /// Malformed PNR or booking reference
+    InvalidReference(String),
     /// Passenger count mismatch
     PassengerCountMismatch { expected: u8, got: u8 },
 
@@ -54,6 +62,10 @@ pub enum BookError {
     /// Partial cancellation not allowed
     PartialCancellationNotAllowed,
 
+    // === Change Errors ===
+    /// Booking cannot be changed
+    NotChangeable(String),
+
     // === Ticketing Errors ===
     /// Ticketing failed
     TicketingFailed(String),
@@ -88,7 +100,13 @@ impl fmt::Display for BookError {
             BookError::InvalidPassenger(msg) => write!(f, "Invalid passenger: {}", msg),
             BookError::InvalidContact(msg) => write!(f, "Invalid contact: {}", msg),
             BookError::InvalidPayment(msg) => write!(f, "Invalid payment: {}", msg),
+            BookError::InvalidNote(msg) => write!(f, "Invalid note: {}", msg),
+            BookError::InvalidSeat(msg) => write!(f, "Invalid seat: {}", msg),
+            BookError::InvalidAncillary(msg) => write!(f, "Invalid extra: {}", msg),
             BookError::MissingField(field) => write!(f, "Missing required field: {}", field),
+            BookError::InvalidReference(reference) => {
+                write!(f, "Invalid booking reference: {}", reference)
+            }
             BookError::PassengerCountMismatch { expected, got } => {
                 write!(
                     f,
@@ -121,6 +139,9 @@ impl fmt::Display for BookError {
                 write!(f, "Partial cancellation not allowed")
             }
 
+            // Change
+            BookError::NotChangeable(reason) => write!(f, "Booking not changeable: {}", reason),
+
             // Ticketing
             BookError::TicketingFailed(msg) => write!(f, "Ticketing failed: {}", msg),
             BookError::AlreadyTicketed => write!(f, "Already ticketed"),
@@ -161,7 +182,11 @@ impl BookError {
             BookError::InvalidPassenger(_)
                 | BookError::InvalidContact(_)
                 | BookError::InvalidPayment(_)
+                | BookError::InvalidNote(_)
+                | BookError::InvalidSeat(_)
+                | BookError::InvalidAncillary(_)
                 | BookError::MissingField(_)
+                | BookError::InvalidReference(_)
                 | BookError::PassengerCountMismatch { .. }
         )
     }
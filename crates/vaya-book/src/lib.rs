@@ -3,8 +3,20 @@
 //! This crate provides comprehensive booking lifecycle management for flight reservations:
 //!
 //! - **Passenger management**: Full validation of passenger details, documents, contacts
+//! - **Extras**: Baggage, meals, insurance and priority boarding priced per segment
+//! - **Seat selection**: Per-segment seat assignments, with paid seats added to the price
+//! - **Accessibility assistance**: Wheelchair and sensory needs sent as SSRs, with airline
+//!   confirmation tracking
 //! - **Booking state machine**: Strict state transitions with audit history
-//! - **Payment processing**: Card tokenization, multiple payment methods, refunds
+//! - **Changes**: Rebooking and date changes quoted from the fare difference and change fee
+//! - **Notes and tags**: Categorized internal and customer-visible notes with agent
+//!   mentions; tags for admin filtering
+//! - **Payment processing**: Card tokenization, multiple payment methods
+//! - **References**: Collision-checked PNRs and `VAY-` booking references
+//! - **Refunds**: Fare-rule penalties, pro-rata for flown segments, and an approval
+//!   lifecycle
+//! - **Split payments**: Group bookings funded by several payers, refunded if unfunded
+//!   by the payment deadline
 //! - **Ticketing lifecycle**: From booking to ticket issuance
 //!
 //! # Security Considerations
@@ -14,21 +26,47 @@
 //! - PNR generation uses cryptographically secure random
 //! - Optimistic locking prevents concurrent modification
 
+mod ancillary;
+mod assistance;
 mod booking;
+mod change;
 mod error;
+mod notes;
 mod passenger;
 mod payment;
+mod reference;
+mod refund;
+mod split;
+#[cfg(test)]
+mod testing;
 
+pub use ancillary::{
+    AncillaryCatalogue, AncillaryKind, AncillaryOffer, BookedAncillary, InsuranceCover,
+};
+pub use assistance::{
+    describe_ssr, AssistanceNeeds, AssistanceRequest, MobilityAid, Ssr, SsrStatus, WheelchairNeed,
+};
 pub use booking::{Booking, BookingNote, BookingStatus, StatusChange};
+pub use change::{ChangeQuote, ChangeRules, PendingChange};
 pub use error::{BookError, BookResult};
+pub use notes::{
+    normalize_tag, parse_mentions, validate_note, NoteCategory, NoteVisibility, MAX_NOTE_LEN,
+    MAX_TAGS, MAX_TAG_LEN,
+};
 pub use passenger::{
     ContactDetails, CountryCode, DocumentType, FrequentFlyer, MealPreference, Passenger,
-    SeatPreference, SpecialRequest, Title, TravelDocument,
+    SeatAssignment, SeatPreference, SpecialRequest, Title, TravelDocument,
 };
 pub use payment::{
     CardBrand, CardToken, PaymentMethod, PaymentRecord, PaymentRequest, PaymentStatus,
     RefundRecord, RefundStatus,
 };
+pub use reference::{
+    is_valid_pnr, is_valid_reference, ReferenceGenerator, DEFAULT_MAX_ATTEMPTS, PNR_LEN,
+    REFERENCE_ALPHABET, REFERENCE_CODE_LEN, REFERENCE_PREFIX,
+};
+pub use refund::{RefundCalculator, RefundQuote, RefundRules};
+pub use split::{PayerShare, SplitPayment, SplitStatus};
 
 // Re-export PassengerType from vaya_search for convenience
 pub use vaya_search::PassengerType;
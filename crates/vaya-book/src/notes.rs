@@ -0,0 +1,188 @@
+//! Booking notes and tags
+//!
+//! Notes carry a category that routes them to the right team and a
+//! visibility: internal notes are for agents only, customer notes are
+//! shown to the traveler with the booking. Agents are mentioned by writing
+//! `@agent-id` in the note text.
+//!
+//! Tags are short lowercase labels (`vip`, `chargeback`, `group-desk`) used
+//! to find bookings in admin lists.
+
+use crate::{BookError, BookResult};
+
+/// Maximum note length in characters
+pub const MAX_NOTE_LEN: usize = 2000;
+
+/// Maximum tag length
+pub const MAX_TAG_LEN: usize = 32;
+
+/// Maximum tags per booking
+pub const MAX_TAGS: usize = 20;
+
+/// Team a note is for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NoteCategory {
+    /// Uncategorized, including notes written by the system
+    #[default]
+    General,
+    /// Customer support
+    Support,
+    /// Fraud review
+    FraudReview,
+    /// Operations (ticketing, schedule changes, airline follow-up)
+    Ops,
+}
+
+impl NoteCategory {
+    /// All categories
+    pub const ALL: [NoteCategory; 4] = [
+        NoteCategory::General,
+        NoteCategory::Support,
+        NoteCategory::FraudReview,
+        NoteCategory::Ops,
+    ];
+
+    /// Get the category code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteCategory::General => "general",
+            NoteCategory::Support => "support",
+            NoteCategory::FraudReview => "fraud-review",
+            NoteCategory::Ops => "ops",
+        }
+    }
+
+    /// Parse a category code
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+
+    /// Short label for timelines
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoteCategory::General => "Note",
+            NoteCategory::Support => "Support note",
+            NoteCategory::FraudReview => "Fraud review note",
+            NoteCategory::Ops => "Ops note",
+        }
+    }
+}
+
+/// Who can see a note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NoteVisibility {
+    /// Agents only
+    #[default]
+    Internal,
+    /// Shown to the traveler
+    Customer,
+}
+
+impl NoteVisibility {
+    /// Get the visibility code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteVisibility::Internal => "internal",
+            NoteVisibility::Customer => "customer",
+        }
+    }
+
+    /// Parse a visibility code
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "internal" => Some(NoteVisibility::Internal),
+            "customer" => Some(NoteVisibility::Customer),
+            _ => None,
+        }
+    }
+}
+
+/// Agent IDs mentioned as `@agent-id` in a note, in order, without repeats
+///
+/// A mention starts at the beginning of the text or after whitespace or an
+/// opening bracket, so email addresses are not mistaken for mentions.
+pub fn parse_mentions(content: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut prev = None;
+    for (idx, c) in content.char_indices() {
+        let starts_word = prev.is_none_or(|p: char| p.is_whitespace() || p == '(');
+        prev = Some(c);
+        if c != '@' || !starts_word {
+            continue;
+        }
+        let rest = &content[idx + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "._-".contains(c)))
+            .unwrap_or(rest.len());
+        // Trailing punctuation ends the sentence, not the ID
+        let id = rest[..end].trim_end_matches(['.', '-', '_']);
+        if !id.is_empty() && !mentions.iter().any(|m| m == id) {
+            mentions.push(id.to_string());
+        }
+    }
+    mentions
+}
+
+/// Check note text can be stored
+pub fn validate_note(content: &str) -> BookResult<()> {
+    if content.trim().is_empty() {
+        return Err(BookError::MissingField("note".into()));
+    }
+    if content.chars().count() > MAX_NOTE_LEN {
+        return Err(BookError::InvalidNote(format!(
+            "Note too long (max {} chars)",
+            MAX_NOTE_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Normalize a tag to its stored form (trimmed, lowercase)
+///
+/// Tags may contain ASCII letters, digits and hyphens.
+pub fn normalize_tag(tag: &str) -> BookResult<String> {
+    let tag = tag.trim().to_ascii_lowercase();
+    if tag.is_empty() || tag.len() > MAX_TAG_LEN {
+        return Err(BookError::InvalidNote(format!(
+            "Tag must be 1-{} characters",
+            MAX_TAG_LEN
+        )));
+    }
+    if !tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(BookError::InvalidNote(format!(
+            "Tag '{}' may only contain letters, digits and hyphens",
+            tag
+        )));
+    }
+    Ok(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("@ops.lead please check, cc @amir and (@amir). Mail a@b.com"),
+            ["ops.lead", "amir"]
+        );
+        assert!(parse_mentions("no mentions @ here").is_empty());
+    }
+
+    #[test]
+    fn test_codes_and_tags() {
+        for category in NoteCategory::ALL {
+            assert_eq!(NoteCategory::parse(category.as_str()), Some(category));
+        }
+        assert_eq!(
+            NoteVisibility::parse("customer"),
+            Some(NoteVisibility::Customer)
+        );
+
+        assert_eq!(normalize_tag(" VIP ").unwrap(), "vip");
+        assert!(normalize_tag("group desk").is_err());
+        assert!(normalize_tag("").is_err());
+        assert!(validate_note("  ").is_err());
+    }
+}
@@ -1,13 +1,17 @@
 //! Passenger data types with validation
 
+use std::fmt;
 use time::Date;
-use vaya_common::Gender;
+
+use vaya_common::translit::{self, NameField, TranslitWarning, MAX_NAME_LEN};
+use vaya_common::{Gender, Mask, MinorUnits, Redact};
 use vaya_search::PassengerType;
 
+use crate::assistance::{AssistanceNeeds, AssistanceRequest, SsrStatus};
 use crate::{BookError, BookResult};
 
 /// A passenger in a booking
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Passenger {
     /// Passenger ID within booking
     pub id: u8,
@@ -43,6 +47,45 @@ pub struct Passenger {
     pub redress_number: Option<String>,
     /// Known traveler number
     pub known_traveler_number: Option<String>,
+    /// Accessibility assistance needs
+    pub assistance: AssistanceNeeds,
+    /// Airline status of each assistance SSR
+    pub assistance_requests: Vec<AssistanceRequest>,
+    /// Seats selected, at most one per segment
+    pub seats: Vec<SeatAssignment>,
+}
+
+impl fmt::Debug for Passenger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Passenger")
+            .field("id", &self.id)
+            .field("pax_type", &self.pax_type)
+            .field("title", &self.title)
+            .field("first_name", &self.first_name)
+            .field("last_name", &self.last_name)
+            .field("middle_name", &self.middle_name)
+            .field("date_of_birth", &self.date_of_birth)
+            .field("gender", &self.gender)
+            .field("nationality", &self.nationality)
+            .field("document", &self.document)
+            .field("contact", &self.contact)
+            .field("frequent_flyer", &self.frequent_flyer)
+            .field("special_requests", &self.special_requests)
+            .field("meal_preference", &self.meal_preference)
+            .field("seat_preference", &self.seat_preference)
+            .field(
+                "redress_number",
+                &self.redress_number.redacted(Mask::LastFour),
+            )
+            .field(
+                "known_traveler_number",
+                &self.known_traveler_number.redacted(Mask::LastFour),
+            )
+            .field("assistance", &self.assistance)
+            .field("assistance_requests", &self.assistance_requests)
+            .field("seats", &self.seats)
+            .finish()
+    }
 }
 
 impl Passenger {
@@ -75,9 +118,17 @@ impl Passenger {
             seat_preference: None,
             redress_number: None,
             known_traveler_number: None,
+            assistance: AssistanceNeeds::default(),
+            assistance_requests: Vec::new(),
+            seats: Vec::new(),
         }
     }
 
+    /// Seat selected for a segment
+    pub fn seat_for(&self, segment: usize) -> Option<&SeatAssignment> {
+        self.seats.iter().find(|s| s.segment == segment)
+    }
+
     /// Validate passenger data
     pub fn validate(&self, departure_date: Date) -> BookResult<()> {
         // Validate name
@@ -96,9 +147,56 @@ impl Passenger {
             contact.validate()?;
         }
 
+        self.assistance.validate()?;
+
+        Ok(())
+    }
+
+    /// Set assistance needs, resetting airline tracking to requested
+    pub fn request_assistance(&mut self, needs: AssistanceNeeds) -> BookResult<()> {
+        needs.validate()?;
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        self.assistance_requests = needs
+            .ssrs()
+            .into_iter()
+            .map(|ssr| AssistanceRequest {
+                code: ssr.code,
+                status: SsrStatus::Requested,
+                updated_at: now,
+            })
+            .collect();
+        self.assistance = needs;
         Ok(())
     }
 
+    /// Record the airline's action code for an assistance SSR
+    pub fn update_assistance_status(
+        &mut self,
+        code: &str,
+        action_code: &str,
+    ) -> BookResult<SsrStatus> {
+        let status = SsrStatus::from_action_code(action_code).ok_or_else(|| {
+            BookError::InvalidPassenger(format!("Unknown SSR action code: {}", action_code))
+        })?;
+        let request = self
+            .assistance_requests
+            .iter_mut()
+            .find(|r| r.code.eq_ignore_ascii_case(code))
+            .ok_or_else(|| {
+                BookError::InvalidPassenger(format!("No assistance request for SSR {}", code))
+            })?;
+        request.status = status;
+        request.updated_at = time::OffsetDateTime::now_utc().unix_timestamp();
+        Ok(status)
+    }
+
+    /// Whether every assistance SSR has been confirmed by the airline
+    pub fn assistance_confirmed(&self) -> bool {
+        self.assistance_requests
+            .iter()
+            .all(|r| r.status == SsrStatus::Confirmed)
+    }
+
     /// Validate name fields
     fn validate_name(&self) -> BookResult<()> {
         // First name required
@@ -111,43 +209,72 @@ impl Passenger {
             return Err(BookError::MissingField("last_name".into()));
         }
 
-        // Name length check (airline systems typically max 30 chars)
-        if self.first_name.len() > 30 {
-            return Err(BookError::InvalidPassenger(
-                "First name too long (max 30 chars)".into(),
-            ));
-        }
-
-        if self.last_name.len() > 30 {
-            return Err(BookError::InvalidPassenger(
-                "Last name too long (max 30 chars)".into(),
-            ));
-        }
-
         // Name characters check (letters, space, hyphen, apostrophe only)
         if !is_valid_name(&self.first_name) {
-            return Err(BookError::InvalidPassenger(
-                "First name contains invalid characters".into(),
+            return Err(invalid_name(
+                "First name",
+                &self.first_name,
+                NameField::Given,
             ));
         }
 
         if !is_valid_name(&self.last_name) {
-            return Err(BookError::InvalidPassenger(
-                "Last name contains invalid characters".into(),
+            return Err(invalid_name(
+                "Last name",
+                &self.last_name,
+                NameField::Surname,
             ));
         }
 
         if let Some(ref middle) = self.middle_name {
             if !is_valid_name(middle) {
-                return Err(BookError::InvalidPassenger(
-                    "Middle name contains invalid characters".into(),
-                ));
+                return Err(invalid_name("Middle name", middle, NameField::Given));
             }
         }
 
+        // Name length check (airline systems typically max 30 chars)
+        if self.first_name.len() > MAX_NAME_LEN {
+            return Err(BookError::InvalidPassenger(
+                "First name too long (max 30 chars)".into(),
+            ));
+        }
+
+        if self.last_name.len() > MAX_NAME_LEN {
+            return Err(BookError::InvalidPassenger(
+                "Last name too long (max 30 chars)".into(),
+            ));
+        }
+
         Ok(())
     }
 
+    /// Convert names to passport (MRZ) form in place
+    ///
+    /// Names typed in another script or with diacritics are transliterated;
+    /// the returned warnings should be shown to the user to confirm the
+    /// spelling matches the passport.
+    pub fn normalize_names(&mut self) -> BookResult<Vec<TranslitWarning>> {
+        let mut warnings = Vec::new();
+        let mut convert = |name: &str, field: NameField| {
+            let result = translit::transliterate(name, field)
+                .map_err(|e| BookError::InvalidPassenger(e.message))?;
+            warnings.extend(result.warnings);
+            Ok::<_, BookError>(result.value)
+        };
+
+        let first_name = convert(&self.first_name, NameField::Given)?;
+        let last_name = convert(&self.last_name, NameField::Surname)?;
+        let middle_name = match self.middle_name {
+            Some(ref middle) => Some(convert(middle, NameField::Given)?),
+            None => None,
+        };
+
+        self.first_name = first_name;
+        self.last_name = last_name;
+        self.middle_name = middle_name;
+        Ok(warnings)
+    }
+
     /// Validate age matches passenger type
     fn validate_age(&self, departure_date: Date) -> BookResult<()> {
         let age = calculate_age(self.date_of_birth, departure_date);
@@ -242,7 +369,7 @@ impl CountryCode {
 }
 
 /// Travel document
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct TravelDocument {
     /// Document type
     pub doc_type: DocumentType,
@@ -256,6 +383,18 @@ pub struct TravelDocument {
     pub expiry_date: Date,
 }
 
+impl fmt::Debug for TravelDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TravelDocument")
+            .field("doc_type", &self.doc_type)
+            .field("number", &self.number.redacted(Mask::LastFour))
+            .field("issuing_country", &self.issuing_country)
+            .field("issue_date", &self.issue_date)
+            .field("expiry_date", &self.expiry_date)
+            .finish()
+    }
+}
+
 impl TravelDocument {
     /// Create a passport document
     pub fn passport(number: &str, country: CountryCode, expiry: Date) -> Self {
@@ -321,7 +460,7 @@ impl DocumentType {
 }
 
 /// Contact details
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ContactDetails {
     /// Email address
     pub email: String,
@@ -335,6 +474,21 @@ pub struct ContactDetails {
     pub emergency_phone: Option<String>,
 }
 
+impl fmt::Debug for ContactDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContactDetails")
+            .field("email", &self.email.redacted(Mask::Email))
+            .field("phone_country", &self.phone_country)
+            .field("phone_number", &self.phone_number.redacted(Mask::LastFour))
+            .field("emergency_name", &self.emergency_name.redacted(Mask::Full))
+            .field(
+                "emergency_phone",
+                &self.emergency_phone.redacted(Mask::LastFour),
+            )
+            .finish()
+    }
+}
+
 impl ContactDetails {
     /// Create contact with email and phone
     pub fn new(
@@ -398,7 +552,7 @@ impl SpecialRequest {
     pub fn code(&self) -> &'static str {
         match self {
             SpecialRequest::Wheelchair => "WCHR",
-            SpecialRequest::WheelchairRamp => "WCHC",
+            SpecialRequest::WheelchairRamp => "WCHR",
             SpecialRequest::WheelchairSteps => "WCHS",
             SpecialRequest::WheelchairCabin => "WCHC",
             SpecialRequest::BlindPassenger => "BLND",
@@ -482,8 +636,41 @@ impl SeatPreference {
     }
 }
 
+/// A seat selected for one flight segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeatAssignment {
+    /// Segment position in the itinerary (outbound, then return)
+    pub segment: usize,
+    /// Seat number (e.g. "12A")
+    pub seat_number: String,
+    /// Price charged for the seat (zero for free seats)
+    pub price: MinorUnits,
+}
+
+impl SeatAssignment {
+    /// Create an assignment; the seat number is upper-cased
+    pub fn new(segment: usize, seat_number: &str, price: MinorUnits) -> Self {
+        Self {
+            segment,
+            seat_number: seat_number.trim().to_ascii_uppercase(),
+            price,
+        }
+    }
+}
+
 // === Validation helpers ===
 
+/// Error for a name not in passport form, suggesting a transliteration
+fn invalid_name(label: &str, name: &str, field: NameField) -> BookError {
+    match translit::transliterate(name, field) {
+        Ok(suggestion) => BookError::InvalidPassenger(format!(
+            "{} must be in Latin letters as printed on the passport (suggested: {})",
+            label, suggestion.value
+        )),
+        Err(_) => BookError::InvalidPassenger(format!("{} contains invalid characters", label)),
+    }
+}
+
 /// Check if name contains only valid characters
 fn is_valid_name(name: &str) -> bool {
     name.chars()
@@ -591,6 +778,30 @@ mod tests {
         assert!(expired.validate(dep).is_err());
     }
 
+    #[test]
+    fn test_debug_is_redacted() {
+        let dob = Date::from_calendar_date(1990, time::Month::January, 15).unwrap();
+        let expiry = Date::from_calendar_date(2030, time::Month::January, 1).unwrap();
+        let mut pax = Passenger::adult("John", "Doe", dob, Gender::Male);
+        pax.document = Some(TravelDocument::passport(
+            "E12345678",
+            CountryCode::new("SG"),
+            expiry,
+        ));
+        pax.contact = Some(ContactDetails::new(
+            "john.doe@example.com",
+            "+65",
+            "91234567",
+        ));
+
+        let debug = format!("{:?}", pax);
+        assert!(!debug.contains("E12345678"));
+        assert!(debug.contains("****5678"));
+        assert!(!debug.contains("john.doe@"));
+        assert!(debug.contains("j***@example.com"));
+        assert!(!debug.contains("91234567"));
+    }
+
     #[test]
     fn test_email_validation() {
         assert!(is_valid_email("test@example.com"));
@@ -628,6 +839,54 @@ mod tests {
         assert!(!is_valid_name("Name!"));
     }
 
+    #[test]
+    fn test_name_transliteration() {
+        let dob = Date::from_calendar_date(1990, time::Month::January, 15).unwrap();
+        let dep = Date::from_calendar_date(2025, time::Month::June, 1).unwrap();
+        let mut pax = Passenger::adult("小明", "张", dob, Gender::Male);
+
+        match pax.validate(dep) {
+            Err(BookError::InvalidPassenger(msg)) => assert!(msg.contains("XIAOMING")),
+            other => panic!("expected invalid passenger, got {:?}", other),
+        }
+
+        let warnings = pax.normalize_names().unwrap();
+        assert_eq!(pax.pnr_name(), "ZHANG/XIAOMING");
+        assert!(!warnings.is_empty());
+        assert!(pax.validate(dep).is_ok());
+
+        let mut pax = Passenger::adult("Zoë", "O'Brien-Müller", dob, Gender::Female);
+        pax.normalize_names().unwrap();
+        assert_eq!(pax.pnr_name(), "OBRIEN MUELLER/ZOE");
+    }
+
+    #[test]
+    fn test_assistance_tracking() {
+        use crate::assistance::WheelchairNeed;
+
+        let dob = Date::from_calendar_date(1950, time::Month::March, 2).unwrap();
+        let dep = Date::from_calendar_date(2025, time::Month::June, 1).unwrap();
+        let mut pax = Passenger::adult("Siti", "Aminah", dob, Gender::Female);
+        pax.request_assistance(AssistanceNeeds {
+            wheelchair: Some(WheelchairNeed::Cabin),
+            hearing: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(pax.validate(dep).is_ok());
+        assert_eq!(pax.assistance_requests.len(), 2);
+        assert!(!pax.assistance_confirmed());
+
+        pax.update_assistance_status("WCHC", "HK").unwrap();
+        assert_eq!(
+            pax.update_assistance_status("DEAF", "KK").unwrap(),
+            SsrStatus::Confirmed
+        );
+        assert!(pax.assistance_confirmed());
+        assert!(pax.update_assistance_status("BLND", "HK").is_err());
+        assert!(pax.update_assistance_status("WCHC", "??").is_err());
+    }
+
     #[test]
     fn test_pnr_name() {
         let dob = Date::from_calendar_date(1990, time::Month::January, 15).unwrap();
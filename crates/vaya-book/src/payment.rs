@@ -1,7 +1,9 @@
 //! Payment processing types
 
+use std::fmt;
+
 use time::OffsetDateTime;
-use vaya_common::{CurrencyCode, MinorUnits};
+use vaya_common::{CurrencyCode, Mask, MinorUnits, Redact};
 
 use crate::{BookError, BookResult};
 
@@ -124,6 +126,8 @@ pub struct PaymentRecord {
     pub provider_ref: Option<String>,
     /// Timestamp
     pub timestamp: i64,
+    /// Amount refunded against this payment
+    pub refunded: MinorUnits,
 }
 
 impl PaymentRecord {
@@ -142,9 +146,15 @@ impl PaymentRecord {
             status: PaymentStatus::Pending,
             provider_ref: None,
             timestamp: OffsetDateTime::now_utc().unix_timestamp(),
+            refunded: MinorUnits::ZERO,
         }
     }
 
+    /// Amount not yet refunded
+    pub fn refundable(&self) -> MinorUnits {
+        MinorUnits::new((self.amount.as_i64() - self.refunded.as_i64()).max(0))
+    }
+
     /// Mark as completed
     pub fn complete(&mut self, provider_ref: Option<String>) {
         self.status = PaymentStatus::Completed;
@@ -158,7 +168,7 @@ impl PaymentRecord {
 }
 
 /// Card details (tokenized - never store raw card data)
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct CardToken {
     /// Token from payment provider
     pub token: String,
@@ -174,6 +184,22 @@ pub struct CardToken {
     pub cardholder_name: String,
 }
 
+impl fmt::Debug for CardToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CardToken")
+            .field("token", &self.token.redacted(Mask::Full))
+            .field("last_four", &self.last_four)
+            .field("brand", &self.brand)
+            .field("exp_month", &self.exp_month)
+            .field("exp_year", &self.exp_year)
+            .field(
+                "cardholder_name",
+                &self.cardholder_name.redacted(Mask::Full),
+            )
+            .finish()
+    }
+}
+
 impl CardToken {
     /// Create a new card token
     pub fn new(
@@ -309,21 +335,36 @@ pub struct RefundRecord {
 /// Refund status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RefundStatus {
+    /// Requested, awaiting approval
     Pending,
+    /// Approved, not yet sent to the payment provider
+    Approved,
     Processing,
     Completed,
     Failed,
+    /// Turned down by an agent
+    Rejected,
 }
 
 impl RefundStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
             RefundStatus::Pending => "PENDING",
+            RefundStatus::Approved => "APPROVED",
             RefundStatus::Processing => "PROCESSING",
             RefundStatus::Completed => "COMPLETED",
             RefundStatus::Failed => "FAILED",
+            RefundStatus::Rejected => "REJECTED",
         }
     }
+
+    /// Check if the refund is still in progress
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            RefundStatus::Pending | RefundStatus::Approved | RefundStatus::Processing
+        )
+    }
 }
 
 /// Payment request for initiating a payment
@@ -462,4 +503,20 @@ mod tests {
         payment.complete(Some("stripe-123".into()));
         assert_eq!(payment.status, PaymentStatus::Completed);
     }
+
+    #[test]
+    fn test_card_token_debug_is_redacted() {
+        let card = CardToken::new(
+            "tok_live_9f8e7d",
+            "4242",
+            CardBrand::Visa,
+            12,
+            2030,
+            "John Doe",
+        );
+        let debug = format!("{:?}", card);
+        assert!(!debug.contains("tok_live"));
+        assert!(!debug.contains("John"));
+        assert!(debug.contains("4242"));
+    }
 }
@@ -0,0 +1,193 @@
+//! PNRs and booking references
+//!
+//! Every booking carries two identifiers: the PNR, the six-character record
+//! locator exchanged with airlines and the GDS, and a `VAY-` booking
+//! reference shown to customers. Both are drawn from the same 32-character
+//! alphabet, which leaves out the easily misread 0/O and 1/I, using the
+//! system CSPRNG. 256 is a multiple of 32, so a random byte maps to a
+//! character without bias. PNRs always contain a letter so they cannot be
+//! mistaken for a ticket or order number.
+//!
+//! Random codes can collide; [`ReferenceGenerator::unique_pnr`] and
+//! [`ReferenceGenerator::unique_reference`] redraw until a lookup against
+//! the booking store reports the code unused.
+
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::{BookError, BookResult};
+
+/// Characters used in PNRs and booking references
+pub const REFERENCE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Length of a PNR
+pub const PNR_LEN: usize = 6;
+
+/// Prefix of customer booking references
+pub const REFERENCE_PREFIX: &str = "VAY-";
+
+/// Characters after the prefix in a booking reference
+pub const REFERENCE_CODE_LEN: usize = 8;
+
+/// Default number of draws before giving up on a unique code
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 16;
+
+fn is_reference_code(code: &str, len: usize) -> bool {
+    code.len() == len && code.bytes().all(|b| REFERENCE_ALPHABET.contains(&b))
+}
+
+/// Check a PNR's length, alphabet and that it contains a letter
+pub fn is_valid_pnr(pnr: &str) -> bool {
+    is_reference_code(pnr, PNR_LEN) && pnr.bytes().any(|b| b.is_ascii_alphabetic())
+}
+
+/// Check a `VAY-` booking reference
+pub fn is_valid_reference(reference: &str) -> bool {
+    match reference.strip_prefix(REFERENCE_PREFIX) {
+        Some(code) => is_reference_code(code, REFERENCE_CODE_LEN),
+        None => false,
+    }
+}
+
+/// Random PNR and booking reference generator
+#[derive(Debug, Clone)]
+pub struct ReferenceGenerator {
+    rng: SystemRandom,
+    max_attempts: u32,
+}
+
+impl Default for ReferenceGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReferenceGenerator {
+    /// Create a generator using the system CSPRNG
+    pub fn new() -> Self {
+        Self {
+            rng: SystemRandom::new(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+
+    /// Set how many codes the `unique_*` methods try before failing
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    fn code(&self, len: usize) -> BookResult<String> {
+        let mut bytes = vec![0u8; len];
+        self.rng
+            .fill(&mut bytes)
+            .map_err(|_| BookError::Internal("Failed to generate reference".into()))?;
+        Ok(bytes
+            .iter()
+            .map(|b| REFERENCE_ALPHABET[usize::from(*b) % REFERENCE_ALPHABET.len()] as char)
+            .collect())
+    }
+
+    /// A random PNR, not checked for collisions
+    pub fn pnr(&self) -> BookResult<String> {
+        loop {
+            let pnr = self.code(PNR_LEN)?;
+            if is_valid_pnr(&pnr) {
+                return Ok(pnr);
+            }
+        }
+    }
+
+    /// A random `VAY-` booking reference, not checked for collisions
+    pub fn reference(&self) -> BookResult<String> {
+        Ok(format!(
+            "{}{}",
+            REFERENCE_PREFIX,
+            self.code(REFERENCE_CODE_LEN)?
+        ))
+    }
+
+    /// A PNR for which `taken` returns false
+    pub fn unique_pnr<E: From<BookError>>(
+        &self,
+        taken: impl FnMut(&str) -> Result<bool, E>,
+    ) -> Result<String, E> {
+        self.unique("PNR", || self.pnr(), taken)
+    }
+
+    /// A booking reference for which `taken` returns false
+    pub fn unique_reference<E: From<BookError>>(
+        &self,
+        taken: impl FnMut(&str) -> Result<bool, E>,
+    ) -> Result<String, E> {
+        self.unique("booking reference", || self.reference(), taken)
+    }
+
+    fn unique<E: From<BookError>>(
+        &self,
+        what: &str,
+        draw: impl Fn() -> BookResult<String>,
+        mut taken: impl FnMut(&str) -> Result<bool, E>,
+    ) -> Result<String, E> {
+        for _ in 0..self.max_attempts {
+            let code = draw()?;
+            if !taken(&code)? {
+                return Ok(code);
+            }
+            tracing::debug!("{} {} already in use, redrawing", what, code);
+        }
+        Err(BookError::Internal(format!(
+            "No unused {} after {} attempts",
+            what, self.max_attempts
+        ))
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_codes_are_valid() {
+        let generator = ReferenceGenerator::new();
+        for _ in 0..200 {
+            let pnr = generator.pnr().unwrap();
+            assert!(is_valid_pnr(&pnr), "{}", pnr);
+            let reference = generator.reference().unwrap();
+            assert!(is_valid_reference(&reference), "{}", reference);
+        }
+
+        assert!(is_valid_pnr("ABC234"));
+        assert!(!is_valid_pnr("234567"));
+        assert!(!is_valid_pnr("ABC10O"));
+        assert!(!is_valid_pnr("abc234"));
+        assert!(is_valid_reference("VAY-7KQ2M9XP"));
+        assert!(!is_valid_reference("VAY-7KQ2M9X"));
+        assert!(!is_valid_reference("BKG-7KQ2M9XP"));
+    }
+
+    #[test]
+    fn test_unique_codes_skip_taken() {
+        let generator = ReferenceGenerator::new();
+        let mut lookups = 0;
+        let pnr = generator
+            .unique_pnr(|_| {
+                lookups += 1;
+                // The first two draws collide
+                Ok::<_, BookError>(lookups <= 2)
+            })
+            .unwrap();
+        assert_eq!(lookups, 3);
+        assert!(is_valid_pnr(&pnr));
+
+        let err = generator
+            .clone()
+            .with_max_attempts(3)
+            .unique_reference(|_| Ok::<_, BookError>(true))
+            .unwrap_err();
+        assert!(matches!(err, BookError::Internal(msg) if msg.contains("3 attempts")));
+
+        let lookup_failed = generator.unique_pnr(|_| Err(BookError::LockFailed));
+        assert!(matches!(lookup_failed, Err(BookError::LockFailed)));
+    }
+}
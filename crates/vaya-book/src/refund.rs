@@ -0,0 +1,498 @@
+//! Refund calculation and lifecycle
+//!
+//! [`RefundCalculator`] works out what a cancelled booking gets back from
+//! the fare's refund conditions, the time left before the next unflown
+//! segment, and how many segments were already flown. Flown segments are
+//! deducted pro rata. A refundable fare returns the unused fare less the
+//! cancellation fee plus unused taxes; a non-refundable fare, a fare inside
+//! its no-refund window, or a no-show returns unused taxes only. Seats and
+//! extras are not refunded.
+//!
+//! A refund then moves through `Pending` (requested) → `Approved` →
+//! `Processing` → `Completed`, or is rejected or fails along the way. The
+//! booking is marked refunded once its refund completes.
+
+use time::{OffsetDateTime, PrimitiveDateTime};
+use vaya_common::{CurrencyCode, MinorUnits, TimeZone};
+use vaya_search::PriceBreakdown;
+
+use crate::booking::{Booking, BookingStatus};
+use crate::payment::{RefundRecord, RefundStatus};
+use crate::{BookError, BookResult};
+
+/// Refund conditions of the booked fare
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefundRules {
+    /// Whether the fare itself is refundable (taxes always are)
+    pub refundable: bool,
+    /// Fee kept on cancellation, in the booking currency
+    pub cancellation_fee: MinorUnits,
+    /// Hours before departure inside which only taxes are refunded
+    pub no_refund_within_hours: Option<i64>,
+}
+
+impl RefundRules {
+    /// Refundable for the given fee
+    pub fn with_fee(cancellation_fee: MinorUnits) -> Self {
+        Self {
+            refundable: true,
+            cancellation_fee,
+            no_refund_within_hours: None,
+        }
+    }
+
+    /// Non-refundable fare; only taxes come back
+    pub fn tax_only() -> Self {
+        Self {
+            refundable: false,
+            cancellation_fee: MinorUnits::ZERO,
+            no_refund_within_hours: None,
+        }
+    }
+
+    /// Stop refunding the fare this many hours before departure
+    pub fn with_cutoff(mut self, hours: i64) -> Self {
+        self.no_refund_within_hours = Some(hours);
+        self
+    }
+}
+
+/// Breakdown of a refund
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefundQuote {
+    /// Currency of all amounts
+    pub currency: CurrencyCode,
+    /// Segments on the booking
+    pub segments: usize,
+    /// Segments already flown
+    pub used_segments: usize,
+    /// Hours until the next unflown segment departs (negative once missed)
+    pub hours_to_departure: i64,
+    /// Unused fare returned, after the cancellation fee
+    pub fare_refund: MinorUnits,
+    /// Unused taxes returned
+    pub tax_refund: MinorUnits,
+    /// Cancellation fee deducted
+    pub cancellation_fee: MinorUnits,
+    /// Amount to refund
+    pub refundable_amount: MinorUnits,
+    /// Whether only taxes are refunded
+    pub tax_only: bool,
+}
+
+/// Computes refunds from the fare's refund rules
+#[derive(Debug, Clone, Copy)]
+pub struct RefundCalculator {
+    rules: RefundRules,
+}
+
+impl RefundCalculator {
+    /// Create a calculator for the given rules
+    pub fn new(rules: RefundRules) -> Self {
+        Self { rules }
+    }
+
+    /// Refund for a price of `segments` segments, `used_segments` of them flown
+    pub fn calculate(
+        &self,
+        price: &PriceBreakdown,
+        segments: usize,
+        used_segments: usize,
+        hours_to_departure: i64,
+    ) -> RefundQuote {
+        let segments = segments.max(1);
+        let used_segments = used_segments.min(segments);
+        // Spread each amount over the segments; flown segments take any remainder
+        let unused = |amount: MinorUnits| -> MinorUnits {
+            amount
+                .allocate(segments)
+                .into_iter()
+                .skip(used_segments)
+                .sum()
+        };
+        let unused_fare = unused(price.base_fare + price.surcharges);
+        let tax_refund = unused(price.taxes);
+
+        let no_show = hours_to_departure < 0;
+        let in_cutoff = self
+            .rules
+            .no_refund_within_hours
+            .map(|h| hours_to_departure < h)
+            .unwrap_or(false);
+        let tax_only = !self.rules.refundable || no_show || in_cutoff;
+
+        let (fare_refund, cancellation_fee) = if tax_only {
+            (MinorUnits::ZERO, MinorUnits::ZERO)
+        } else {
+            let fee = self.rules.cancellation_fee.min(unused_fare);
+            (unused_fare - fee, fee)
+        };
+
+        RefundQuote {
+            currency: price.currency,
+            segments,
+            used_segments,
+            hours_to_departure,
+            fare_refund,
+            tax_refund,
+            cancellation_fee,
+            refundable_amount: fare_refund + tax_refund,
+            tax_only,
+        }
+    }
+}
+
+impl Booking {
+    /// Quote a refund with `used_segments` already flown
+    ///
+    /// The refund is capped at what was paid and not already refunded.
+    pub fn quote_refund(&self, rules: &RefundRules, used_segments: usize) -> RefundQuote {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let hours_to_departure = self
+            .segments()
+            .nth(used_segments)
+            .map(|s| {
+                let local = PrimitiveDateTime::new(s.departure_date, s.departure_time);
+                let departs = TimeZone::airport(&s.origin)
+                    .unwrap_or(TimeZone::UTC)
+                    .to_utc(local)
+                    .as_unix();
+                (departs - now).div_euclid(3600)
+            })
+            .unwrap_or(-1);
+
+        let mut quote = RefundCalculator::new(*rules).calculate(
+            &self.offer.price,
+            self.segments().count(),
+            used_segments,
+            hours_to_departure,
+        );
+        let available = self.total_paid().as_i64() - self.total_refunded().as_i64();
+        quote.refundable_amount = MinorUnits::new(quote.refundable_amount.as_i64().min(available));
+        quote
+    }
+
+    /// Request a refund for a quoted amount
+    ///
+    /// A paid booking that has not been cancelled yet moves to
+    /// `RefundPending`.
+    pub fn request_refund(
+        &mut self,
+        quote: &RefundQuote,
+        reason: &str,
+        actor: &str,
+    ) -> BookResult<&RefundRecord> {
+        if self.status != BookingStatus::RefundPending
+            && !self.status.can_transition_to(BookingStatus::RefundPending)
+        {
+            return Err(BookError::InvalidStateTransition {
+                from: self.status.as_str().to_string(),
+                to: BookingStatus::RefundPending.as_str().to_string(),
+            });
+        }
+        if self.refunds.iter().any(|r| r.status.is_open()) {
+            return Err(BookError::RefundFailed(
+                "A refund is already in progress".into(),
+            ));
+        }
+        if quote.currency != self.currency {
+            return Err(BookError::RefundFailed(format!(
+                "Quote is in {}, booking in {}",
+                quote.currency, self.currency
+            )));
+        }
+        let available = self.total_paid().as_i64() - self.total_refunded().as_i64();
+        if quote.refundable_amount.as_i64() <= 0 || quote.refundable_amount.as_i64() > available {
+            return Err(BookError::RefundFailed(format!(
+                "Cannot refund {} of {} available",
+                quote.refundable_amount.format_decimal(self.currency),
+                MinorUnits::new(available.max(0)).format_decimal(self.currency)
+            )));
+        }
+        let payment_id = self
+            .payments
+            .iter()
+            .rev()
+            .find(|p| p.status.is_successful())
+            .map(|p| p.id.clone())
+            .ok_or_else(|| BookError::RefundFailed("No completed payment".into()))?;
+
+        if self.status != BookingStatus::RefundPending {
+            self.transition(BookingStatus::RefundPending, reason, actor)?;
+        }
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.refunds.push(RefundRecord {
+            id: format!("RF-{}-{}", self.pnr, self.refunds.len() + 1),
+            payment_id,
+            amount: quote.refundable_amount,
+            currency: self.currency,
+            status: RefundStatus::Pending,
+            reason: reason.to_string(),
+            provider_ref: None,
+            timestamp: now,
+        });
+        self.updated_at = now;
+        Ok(self.refunds.last().expect("refund just pushed"))
+    }
+
+    /// Approve a requested refund
+    pub fn approve_refund(&mut self, refund_id: &str) -> BookResult<()> {
+        self.advance_refund(refund_id, &[RefundStatus::Pending], RefundStatus::Approved)
+    }
+
+    /// Turn down a requested refund
+    pub fn reject_refund(&mut self, refund_id: &str) -> BookResult<()> {
+        self.advance_refund(refund_id, &[RefundStatus::Pending], RefundStatus::Rejected)
+    }
+
+    /// Mark an approved (or failed) refund as sent to the payment provider
+    pub fn start_refund(&mut self, refund_id: &str) -> BookResult<()> {
+        self.advance_refund(
+            refund_id,
+            &[RefundStatus::Approved, RefundStatus::Failed],
+            RefundStatus::Processing,
+        )
+    }
+
+    /// Record a refund the provider completed, and mark the booking refunded
+    pub fn complete_refund(
+        &mut self,
+        refund_id: &str,
+        provider_ref: Option<String>,
+        actor: &str,
+    ) -> BookResult<()> {
+        self.advance_refund(
+            refund_id,
+            &[RefundStatus::Processing],
+            RefundStatus::Completed,
+        )?;
+        if let Some(refund) = self.refunds.iter_mut().find(|r| r.id == refund_id) {
+            refund.provider_ref = provider_ref;
+        }
+        if self.status == BookingStatus::RefundPending {
+            self.mark_refunded(actor)?;
+        }
+        Ok(())
+    }
+
+    /// Record a refund the provider could not process
+    pub fn fail_refund(&mut self, refund_id: &str) -> BookResult<()> {
+        self.advance_refund(refund_id, &[RefundStatus::Processing], RefundStatus::Failed)
+    }
+
+    /// Record `amount` of a refund as returned against payment `payment_id`
+    pub fn record_payment_refund(
+        &mut self,
+        payment_id: &str,
+        amount: MinorUnits,
+    ) -> BookResult<()> {
+        let payment = self
+            .payments
+            .iter_mut()
+            .find(|p| p.id == payment_id)
+            .ok_or_else(|| BookError::RefundFailed(format!("Unknown payment {}", payment_id)))?;
+        if amount > payment.refundable() {
+            return Err(BookError::RefundFailed(format!(
+                "Cannot refund {} of payment {}, {} left",
+                amount.format_decimal(payment.currency),
+                payment_id,
+                payment.refundable().format_decimal(payment.currency)
+            )));
+        }
+        payment.refunded += amount;
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        Ok(())
+    }
+
+    /// A refund by ID
+    pub fn refund(&self, refund_id: &str) -> Option<&RefundRecord> {
+        self.refunds.iter().find(|r| r.id == refund_id)
+    }
+
+    /// Sum of completed refunds
+    pub fn total_refunded(&self) -> MinorUnits {
+        MinorUnits::new(
+            self.refunds
+                .iter()
+                .filter(|r| r.status == RefundStatus::Completed)
+                .map(|r| r.amount.as_i64())
+                .sum(),
+        )
+    }
+
+    fn advance_refund(
+        &mut self,
+        refund_id: &str,
+        from: &[RefundStatus],
+        to: RefundStatus,
+    ) -> BookResult<()> {
+        let refund = self
+            .refunds
+            .iter_mut()
+            .find(|r| r.id == refund_id)
+            .ok_or_else(|| BookError::RefundFailed(format!("Unknown refund {}", refund_id)))?;
+        if !from.contains(&refund.status) {
+            return Err(BookError::RefundFailed(format!(
+                "Refund {} is {}, cannot become {}",
+                refund_id,
+                refund.status.as_str(),
+                to.as_str()
+            )));
+        }
+        refund.status = to;
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use vaya_common::IataCode;
+
+    fn price() -> PriceBreakdown {
+        PriceBreakdown {
+            base_fare: MinorUnits::new(80_000),
+            taxes: MinorUnits::new(20_000),
+            surcharges: MinorUnits::new(10_000),
+            seats: MinorUnits::new(5_000),
+            currency: CurrencyCode::MYR,
+        }
+    }
+
+    fn booking(days_out: i64) -> Booking {
+        // Schedules are in airport-local time, and KUL is UTC+8
+        let departs = (OffsetDateTime::now_utc() + time::Duration::days(days_out))
+            .to_offset(time::macros::offset!(+8));
+        let mut offer = testing::offer("offer-1", 0);
+        offer.price = price();
+        offer.outbound.segments = vec![
+            testing::segment(IataCode::KUL, IataCode::SIN),
+            testing::segment(IataCode::SIN, IataCode::NRT),
+        ];
+        for segment in &mut offer.outbound.segments {
+            segment.departure_date = departs.date();
+            segment.departure_time = departs.time();
+        }
+        let mut booking = Booking::new("user-1", offer, vec![testing::passenger()]).unwrap();
+        booking.confirm("GDS123", "system").unwrap();
+        let payment = testing::paid("pay-1", booking.total_price.as_i64());
+        booking.mark_paid(payment, "user-1").unwrap();
+        booking
+    }
+
+    #[test]
+    fn test_refundable_fare_less_fee() {
+        let calc = RefundCalculator::new(RefundRules::with_fee(MinorUnits::new(15_000)));
+        let quote = calc.calculate(&price(), 2, 0, 72);
+        assert!(!quote.tax_only);
+        assert_eq!(quote.fare_refund, MinorUnits::new(75_000));
+        assert_eq!(quote.tax_refund, MinorUnits::new(20_000));
+        assert_eq!(quote.refundable_amount, MinorUnits::new(95_000));
+
+        // One of two segments flown: half the fare and taxes, fee still applies
+        let quote = calc.calculate(&price(), 2, 1, 5);
+        assert_eq!(quote.fare_refund, MinorUnits::new(30_000));
+        assert_eq!(quote.tax_refund, MinorUnits::new(10_000));
+        assert_eq!(quote.cancellation_fee, MinorUnits::new(15_000));
+
+        // Taxes that do not divide evenly leave the odd unit with the flown segment
+        let quote = calc.calculate(&price(), 3, 1, 72);
+        assert_eq!(quote.fare_refund, MinorUnits::new(45_000));
+        assert_eq!(quote.tax_refund, MinorUnits::new(13_333));
+    }
+
+    #[test]
+    fn test_tax_only_refunds() {
+        let rules = RefundRules::with_fee(MinorUnits::new(15_000)).with_cutoff(24);
+        let calc = RefundCalculator::new(rules);
+
+        // Inside the cutoff, after a missed departure, and on a non-refundable fare
+        for (calc, hours) in [
+            (calc, 12),
+            (calc, -2),
+            (RefundCalculator::new(RefundRules::tax_only()), 72),
+        ] {
+            let quote = calc.calculate(&price(), 2, 0, hours);
+            assert!(quote.tax_only);
+            assert_eq!(quote.fare_refund, MinorUnits::ZERO);
+            assert_eq!(quote.cancellation_fee, MinorUnits::ZERO);
+            assert_eq!(quote.refundable_amount, MinorUnits::new(20_000));
+        }
+
+        // Fee larger than the unused fare keeps the fare, not the taxes
+        let calc = RefundCalculator::new(RefundRules::with_fee(MinorUnits::new(500_000)));
+        let quote = calc.calculate(&price(), 2, 0, 72);
+        assert_eq!(quote.cancellation_fee, MinorUnits::new(90_000));
+        assert_eq!(quote.refundable_amount, MinorUnits::new(20_000));
+    }
+
+    #[test]
+    fn test_refund_lifecycle() {
+        let mut booking = booking(10);
+        let rules = RefundRules::with_fee(MinorUnits::new(15_000)).with_cutoff(24);
+        let quote = booking.quote_refund(&rules, 0);
+        assert!(quote.hours_to_departure > 200);
+        assert_eq!(quote.refundable_amount, MinorUnits::new(95_000));
+
+        let id = booking
+            .request_refund(&quote, "Plans changed", "user-1")
+            .unwrap()
+            .id
+            .clone();
+        assert_eq!(booking.status, BookingStatus::RefundPending);
+        assert!(booking.request_refund(&quote, "Again", "user-1").is_err());
+
+        // Must be approved before it is sent to the provider
+        assert!(booking.start_refund(&id).is_err());
+        booking.approve_refund(&id).unwrap();
+        booking.start_refund(&id).unwrap();
+        booking.fail_refund(&id).unwrap();
+        booking.start_refund(&id).unwrap();
+        booking
+            .complete_refund(&id, Some("re_1".into()), "agent")
+            .unwrap();
+
+        assert_eq!(booking.status, BookingStatus::Refunded);
+        assert_eq!(booking.total_refunded(), MinorUnits::new(95_000));
+        assert_eq!(booking.refund(&id).unwrap().payment_id, "pay-1");
+    }
+
+    #[test]
+    fn test_departure_read_in_origin_time_zone() {
+        // 10:00 local in Kuala Lumpur is 02:00 UTC
+        let mut booking = booking(0);
+        let in_ten_hours = (OffsetDateTime::now_utc() + time::Duration::hours(10))
+            .to_offset(time::macros::offset!(+8));
+        let first = &mut booking.offer.outbound.segments[0];
+        first.departure_date = in_ten_hours.date();
+        first.departure_time = in_ten_hours.time();
+
+        let quote = booking.quote_refund(&RefundRules::tax_only(), 0);
+        assert!((9..=10).contains(&quote.hours_to_departure), "{:?}", quote);
+    }
+
+    #[test]
+    fn test_rejected_refund_can_be_requested_again() {
+        let mut booking = booking(1);
+        let rules = RefundRules::tax_only();
+        let quote = booking.quote_refund(&rules, 0);
+        let id = booking
+            .request_refund(&quote, "Cancelled", "user-1")
+            .unwrap()
+            .id
+            .clone();
+        booking.reject_refund(&id).unwrap();
+        assert!(booking.approve_refund(&id).is_err());
+
+        let quote = booking.quote_refund(&RefundRules::with_fee(MinorUnits::ZERO), 2);
+        assert_eq!(quote.hours_to_departure, -1);
+        assert!(quote.tax_only);
+        assert_eq!(quote.refundable_amount, MinorUnits::ZERO);
+        assert!(booking.request_refund(&quote, "Again", "user-1").is_err());
+
+        let quote = booking.quote_refund(&rules, 0);
+        assert!(booking.request_refund(&quote, "Again", "user-1").is_ok());
+    }
+}
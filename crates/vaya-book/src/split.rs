@@ -0,0 +1,448 @@
+//! Split payments for group bookings
+//!
+//! A [`SplitPayment`] lets several payers fund one booking. Each payer is
+//! given a share of the amount due and pays it with one or more payments.
+//! The booking moves to payment received only once every share is covered;
+//! until then it stays confirmed and the split is partially paid. If the
+//! payment deadline passes first, the booking expires and every collected
+//! payment gets a pending [`RefundRecord`] for the payment provider to
+//! process.
+
+use time::OffsetDateTime;
+use vaya_common::{CurrencyCode, MinorUnits};
+
+use crate::{
+    BookError, BookResult, Booking, BookingStatus, PaymentRecord, RefundRecord, RefundStatus,
+};
+
+/// Split payment state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitStatus {
+    /// Shares assigned, nothing paid yet
+    Collecting,
+    /// Some shares paid
+    PartiallyPaid,
+    /// Every share paid and the booking marked paid
+    Funded,
+    /// Deadline passed with nothing collected
+    Expired,
+    /// Deadline passed; collected payments are being refunded
+    Refunding,
+    /// Deadline passed and every collected payment refunded
+    Refunded,
+}
+
+impl SplitStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SplitStatus::Collecting => "COLLECTING",
+            SplitStatus::PartiallyPaid => "PARTIALLY_PAID",
+            SplitStatus::Funded => "FUNDED",
+            SplitStatus::Expired => "EXPIRED",
+            SplitStatus::Refunding => "REFUNDING",
+            SplitStatus::Refunded => "REFUNDED",
+        }
+    }
+
+    /// Check if payments are still accepted
+    pub fn is_open(&self) -> bool {
+        matches!(self, SplitStatus::Collecting | SplitStatus::PartiallyPaid)
+    }
+}
+
+/// One payer's part of a split payment
+#[derive(Debug, Clone)]
+pub struct PayerShare {
+    /// Payer (user ID or email)
+    pub payer_id: String,
+    /// Amount this payer owes
+    pub share: MinorUnits,
+    /// Payments made by this payer
+    pub payments: Vec<PaymentRecord>,
+}
+
+impl PayerShare {
+    /// Amount paid so far
+    pub fn paid(&self) -> MinorUnits {
+        MinorUnits::new(self.payments.iter().map(|p| p.amount.as_i64()).sum())
+    }
+
+    /// Amount still owed
+    pub fn outstanding(&self) -> MinorUnits {
+        MinorUnits::new((self.share.as_i64() - self.paid().as_i64()).max(0))
+    }
+
+    /// Check if the share is fully paid
+    pub fn is_settled(&self) -> bool {
+        self.outstanding() == MinorUnits::ZERO
+    }
+}
+
+/// Several payers funding one booking
+#[derive(Debug, Clone)]
+pub struct SplitPayment {
+    /// Booking PNR
+    pub booking_ref: String,
+    /// Amount to collect
+    pub total: MinorUnits,
+    /// Currency
+    pub currency: CurrencyCode,
+    /// Payment deadline (Unix timestamp), taken from the booking
+    pub deadline: i64,
+    /// Payer shares
+    pub shares: Vec<PayerShare>,
+    /// Status
+    pub status: SplitStatus,
+    /// Refunds of collected payments after the deadline
+    pub refunds: Vec<RefundRecord>,
+}
+
+impl SplitPayment {
+    /// Start a split for the amount due on a confirmed booking
+    pub fn new(booking: &Booking) -> BookResult<Self> {
+        if !booking.status.can_pay() {
+            return Err(BookError::InvalidPayment(format!(
+                "Cannot split payment for booking in {} status",
+                booking.status.as_str()
+            )));
+        }
+        let deadline = booking
+            .payment_deadline
+            .ok_or_else(|| BookError::MissingField("payment_deadline".into()))?;
+        let total = booking.amount_due();
+        if total == MinorUnits::ZERO {
+            return Err(BookError::PaymentAlreadyProcessed);
+        }
+
+        Ok(Self {
+            booking_ref: booking.pnr.clone(),
+            total,
+            currency: booking.currency,
+            deadline,
+            shares: Vec::new(),
+            status: SplitStatus::Collecting,
+            refunds: Vec::new(),
+        })
+    }
+
+    /// Split the amount due evenly; the first payer covers any remainder
+    pub fn equal_shares<S: AsRef<str>>(booking: &Booking, payers: &[S]) -> BookResult<Self> {
+        let mut split = Self::new(booking)?;
+        if payers.is_empty() {
+            return Err(BookError::MissingField("payers".into()));
+        }
+        let count = payers.len() as i64;
+        let each = split.total.as_i64() / count;
+        let remainder = split.total.as_i64() % count;
+        for (i, payer) in payers.iter().enumerate() {
+            let share = if i == 0 { each + remainder } else { each };
+            split.add_payer(payer.as_ref(), MinorUnits::new(share))?;
+        }
+        Ok(split)
+    }
+
+    /// Give a payer a share of the total
+    pub fn add_payer(&mut self, payer_id: &str, share: MinorUnits) -> BookResult<()> {
+        if self.status != SplitStatus::Collecting || self.collected() != MinorUnits::ZERO {
+            return Err(BookError::InvalidPayment(
+                "Shares cannot change once payments are collected".into(),
+            ));
+        }
+        let payer_id = payer_id.trim();
+        if payer_id.is_empty() {
+            return Err(BookError::MissingField("payer_id".into()));
+        }
+        if self.share_of(payer_id).is_some() {
+            return Err(BookError::InvalidPayment(format!(
+                "Payer {} already has a share",
+                payer_id
+            )));
+        }
+        if share.as_i64() <= 0 {
+            return Err(BookError::InvalidPayment("Share must be positive".into()));
+        }
+        if self.assigned().as_i64() + share.as_i64() > self.total.as_i64() {
+            return Err(BookError::InvalidPayment(format!(
+                "Shares exceed the amount due of {}",
+                self.total.format_decimal(self.currency)
+            )));
+        }
+
+        self.shares.push(PayerShare {
+            payer_id: payer_id.to_string(),
+            share,
+            payments: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// A payer's share
+    pub fn share_of(&self, payer_id: &str) -> Option<&PayerShare> {
+        self.shares.iter().find(|s| s.payer_id == payer_id)
+    }
+
+    /// Sum of assigned shares
+    pub fn assigned(&self) -> MinorUnits {
+        MinorUnits::new(self.shares.iter().map(|s| s.share.as_i64()).sum())
+    }
+
+    /// Sum of collected payments
+    pub fn collected(&self) -> MinorUnits {
+        MinorUnits::new(self.shares.iter().map(|s| s.paid().as_i64()).sum())
+    }
+
+    /// Amount still to collect
+    pub fn outstanding(&self) -> MinorUnits {
+        MinorUnits::new((self.total.as_i64() - self.collected().as_i64()).max(0))
+    }
+
+    /// Record a completed payment towards a payer's share
+    ///
+    /// When the last share is covered, all collected payments are added to
+    /// the booking and it is marked paid.
+    pub fn record_payment(
+        &mut self,
+        booking: &mut Booking,
+        payer_id: &str,
+        payment: PaymentRecord,
+        actor: &str,
+    ) -> BookResult<SplitStatus> {
+        if booking.pnr != self.booking_ref {
+            return Err(BookError::InvalidPayment(format!(
+                "Split is for booking {}, not {}",
+                self.booking_ref, booking.pnr
+            )));
+        }
+        if !self.status.is_open() {
+            return Err(BookError::InvalidPayment(format!(
+                "Split payment is {}",
+                self.status.as_str()
+            )));
+        }
+        if self.assigned() != self.total {
+            return Err(BookError::InvalidPayment(format!(
+                "Shares cover {} of {}",
+                self.assigned().format_decimal(self.currency),
+                self.total.format_decimal(self.currency)
+            )));
+        }
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        if now > self.deadline {
+            return Err(BookError::PaymentTimeout);
+        }
+        if !payment.status.is_successful() {
+            return Err(BookError::PaymentFailed(format!(
+                "Payment {} is {}",
+                payment.id,
+                payment.status.as_str()
+            )));
+        }
+        if payment.currency != self.currency {
+            return Err(BookError::InvalidPayment(format!(
+                "Payment in {}, split in {}",
+                payment.currency, self.currency
+            )));
+        }
+        let currency = self.currency;
+        let share = self
+            .shares
+            .iter_mut()
+            .find(|s| s.payer_id == payer_id)
+            .ok_or_else(|| BookError::InvalidPayment(format!("No share for payer {}", payer_id)))?;
+        if payment.amount.as_i64() <= 0 || payment.amount > share.outstanding() {
+            return Err(BookError::InvalidPayment(format!(
+                "Payer {} owes {}",
+                payer_id,
+                share.outstanding().format_decimal(currency)
+            )));
+        }
+        share.payments.push(payment);
+
+        if self.outstanding() == MinorUnits::ZERO {
+            let payments = self.shares.iter().flat_map(|s| s.payments.iter().cloned());
+            booking.payments.extend(payments);
+            booking.transition(
+                BookingStatus::PaymentReceived,
+                &format!("Split payment funded by {} payers", self.shares.len()),
+                actor,
+            )?;
+            self.status = SplitStatus::Funded;
+        } else {
+            self.status = SplitStatus::PartiallyPaid;
+        }
+        Ok(self.status)
+    }
+
+    /// Expire an unfunded split once the deadline has passed
+    ///
+    /// Expires the booking and queues a refund for every collected payment.
+    /// Returns the refunds to process; empty if the split is still open or
+    /// nothing was collected.
+    pub fn check_expiry(&mut self, booking: &mut Booking) -> Vec<RefundRecord> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        if !self.status.is_open() || now <= self.deadline {
+            return Vec::new();
+        }
+
+        if booking.status == BookingStatus::Confirmed {
+            let _ = booking.transition(
+                BookingStatus::Expired,
+                "Split payment not funded before deadline",
+                "SYSTEM",
+            );
+        }
+
+        self.refunds = self
+            .shares
+            .iter()
+            .flat_map(|s| &s.payments)
+            .map(|p| RefundRecord {
+                id: format!("RF-{}", p.id),
+                payment_id: p.id.clone(),
+                amount: p.amount,
+                currency: p.currency,
+                status: RefundStatus::Pending,
+                reason: "Split payment not funded before deadline".into(),
+                provider_ref: None,
+                timestamp: now,
+            })
+            .collect();
+        self.status = if self.refunds.is_empty() {
+            SplitStatus::Expired
+        } else {
+            SplitStatus::Refunding
+        };
+        self.refunds.clone()
+    }
+
+    /// Record the outcome of a queued refund
+    pub fn complete_refund(
+        &mut self,
+        refund_id: &str,
+        provider_ref: Option<String>,
+        succeeded: bool,
+    ) -> BookResult<()> {
+        let refund = self
+            .refunds
+            .iter_mut()
+            .find(|r| r.id == refund_id)
+            .ok_or_else(|| BookError::RefundFailed(format!("Unknown refund {}", refund_id)))?;
+        refund.status = if succeeded {
+            RefundStatus::Completed
+        } else {
+            RefundStatus::Failed
+        };
+        refund.provider_ref = provider_ref;
+
+        if self
+            .refunds
+            .iter()
+            .all(|r| r.status == RefundStatus::Completed)
+        {
+            self.status = SplitStatus::Refunded;
+        }
+        Ok(())
+    }
+
+    /// Refunds not yet completed
+    pub fn pending_refunds(&self) -> impl Iterator<Item = &RefundRecord> {
+        self.refunds
+            .iter()
+            .filter(|r| r.status != RefundStatus::Completed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, paid};
+    use crate::PaymentStatus;
+
+    fn booking() -> Booking {
+        let mut offer = testing::offer("offer-1", 80_000);
+        offer.price.taxes = MinorUnits::new(20_001);
+        let mut booking = Booking::new("user-1", offer, vec![testing::passenger()]).unwrap();
+        booking.confirm("GDS123", "system").unwrap();
+        booking
+    }
+
+    #[test]
+    fn test_equal_shares_cover_total() {
+        let booking = booking();
+        let split = SplitPayment::equal_shares(&booking, &["ana", "ben", "chen"]).unwrap();
+        let shares: Vec<_> = split.shares.iter().map(|s| s.share.as_i64()).collect();
+        assert_eq!(shares, vec![33_335, 33_333, 33_333]);
+        assert_eq!(split.assigned(), split.total);
+
+        let mut split = SplitPayment::new(&booking).unwrap();
+        split.add_payer("ana", MinorUnits::new(60_000)).unwrap();
+        assert!(split.add_payer("ana", MinorUnits::new(1)).is_err());
+        assert!(split.add_payer("ben", MinorUnits::new(50_000)).is_err());
+    }
+
+    #[test]
+    fn test_booking_paid_when_all_shares_collected() {
+        let mut booking = booking();
+        let mut split = SplitPayment::equal_shares(&booking, &["ana", "ben"]).unwrap();
+
+        // Overpaying a share is refused
+        assert!(split
+            .record_payment(&mut booking, "ana", paid("p0", 60_000), "ana")
+            .is_err());
+        let mut failed = paid("p1", 10_000);
+        failed.status = PaymentStatus::Failed;
+        assert!(split
+            .record_payment(&mut booking, "ana", failed, "ana")
+            .is_err());
+
+        let status = split
+            .record_payment(&mut booking, "ana", paid("p2", 50_001), "ana")
+            .unwrap();
+        assert_eq!(status, SplitStatus::PartiallyPaid);
+        assert_eq!(booking.status, BookingStatus::Confirmed);
+        assert!(!booking.has_payment());
+        assert!(split.share_of("ana").unwrap().is_settled());
+
+        split
+            .record_payment(&mut booking, "ben", paid("p3", 20_000), "ben")
+            .unwrap();
+        let status = split
+            .record_payment(&mut booking, "ben", paid("p4", 30_000), "ben")
+            .unwrap();
+        assert_eq!(status, SplitStatus::Funded);
+        assert_eq!(booking.status, BookingStatus::PaymentReceived);
+        assert_eq!(booking.payments.len(), 3);
+        assert_eq!(booking.amount_due(), MinorUnits::ZERO);
+    }
+
+    #[test]
+    fn test_unfunded_split_refunded_after_deadline() {
+        let mut booking = booking();
+        let mut split = SplitPayment::equal_shares(&booking, &["ana", "ben"]).unwrap();
+        split
+            .record_payment(&mut booking, "ana", paid("p1", 50_001), "ana")
+            .unwrap();
+
+        // Still open before the deadline
+        assert!(split.check_expiry(&mut booking).is_empty());
+
+        split.deadline = OffsetDateTime::now_utc().unix_timestamp() - 1;
+        assert!(matches!(
+            split.record_payment(&mut booking, "ben", paid("p2", 50_000), "ben"),
+            Err(BookError::PaymentTimeout)
+        ));
+
+        let refunds = split.check_expiry(&mut booking);
+        assert_eq!(refunds.len(), 1);
+        assert_eq!(refunds[0].payment_id, "p1");
+        assert_eq!(refunds[0].amount, MinorUnits::new(50_001));
+        assert_eq!(split.status, SplitStatus::Refunding);
+        assert_eq!(booking.status, BookingStatus::Expired);
+
+        split
+            .complete_refund(&refunds[0].id, Some("re_1".into()), true)
+            .unwrap();
+        assert_eq!(split.status, SplitStatus::Refunded);
+        assert_eq!(split.pending_refunds().count(), 0);
+    }
+}
@@ -0,0 +1,80 @@
+//! Fixtures shared by the unit tests
+//!
+//! Offers are MH88 economy from Kuala Lumpur to Tokyo Narita on 1 June
+//! 2025, with RM200 taxes, in ringgit. Tests change what they need on top.
+
+use time::{Date, Month, Time};
+use vaya_common::{AirlineCode, CurrencyCode, Gender, IataCode, MinorUnits};
+use vaya_search::{CabinClass, FlightLeg, FlightOffer, FlightSegment, PriceBreakdown};
+
+use crate::passenger::Passenger;
+use crate::payment::{PaymentMethod, PaymentRecord};
+
+/// MH88 economy from `origin` to `destination` on 1 June 2025, 23:30 to 07:30
+pub fn segment(origin: IataCode, destination: IataCode) -> FlightSegment {
+    let date = Date::from_calendar_date(2025, Month::June, 1).unwrap();
+    FlightSegment {
+        airline: AirlineCode::MH,
+        flight_number: "88".into(),
+        marketing_airline: None,
+        origin,
+        destination,
+        departure_date: date,
+        departure_time: Time::from_hms(23, 30, 0).unwrap(),
+        arrival_date: date,
+        arrival_time: Time::from_hms(7, 30, 0).unwrap(),
+        duration_minutes: 420,
+        aircraft: None,
+        cabin: CabinClass::Economy,
+        booking_class: 'Y',
+        seats_remaining: None,
+    }
+}
+
+/// One-way KUL-NRT offer with a `base` fare (sen) plus RM200 taxes
+pub fn offer(id: &str, base: i64) -> FlightOffer {
+    FlightOffer {
+        id: id.into(),
+        outbound: FlightLeg {
+            segments: vec![segment(IataCode::KUL, IataCode::NRT)],
+            total_duration_minutes: 420,
+        },
+        inbound: None,
+        price: PriceBreakdown {
+            base_fare: MinorUnits::new(base),
+            taxes: MinorUnits::new(20_000),
+            surcharges: MinorUnits::ZERO,
+            seats: MinorUnits::ZERO,
+            currency: CurrencyCode::MYR,
+        },
+        price_per_pax: vec![],
+        expires_at: None,
+        provider: "test".into(),
+        refundable: true,
+        changeable: true,
+        baggage: None,
+        fare_rules: None,
+        self_transfer: false,
+        variation: None,
+    }
+}
+
+/// Adult passenger 1
+pub fn passenger() -> Passenger {
+    let dob = Date::from_calendar_date(1990, Month::January, 15).unwrap();
+    let mut passenger = Passenger::adult("Aisyah", "Rahman", dob, Gender::Female);
+    passenger.id = 1;
+    passenger
+}
+
+/// Completed card payment `id` of `amount` sen, with provider reference `pi_<id>`
+pub fn paid(id: &str, amount: i64) -> PaymentRecord {
+    let mut payment = PaymentRecord::new(
+        id,
+        MinorUnits::new(amount),
+        CurrencyCode::MYR,
+        PaymentMethod::Card,
+    );
+    payment.complete(Some(format!("pi_{id}")));
+    payment
+}
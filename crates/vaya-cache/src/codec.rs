@@ -0,0 +1,53 @@
+//! Byte encoding of keys and values sent between fleet nodes
+
+/// Encode and decode a cache key or value for the wire
+///
+/// Keys must encode identically on every node: ownership is decided by
+/// hashing the encoded key.
+pub trait CacheCodec: Sized {
+    /// Encode to bytes
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decode from bytes, returning `None` if they are malformed
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl CacheCodec for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+impl CacheCodec for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl CacheCodec for u64 {
+    fn encode(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(u64::from_be_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl CacheCodec for i64 {
+    fn encode(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(i64::from_be_bytes(bytes.try_into().ok()?))
+    }
+}
@@ -0,0 +1,501 @@
+//! Fleet-wide cache tier
+//!
+//! A [`DistributedCache`] spreads one logical cache across the API fleet so
+//! that a search cached by one node is a hit on every node:
+//!
+//! - each key is owned by one node, chosen by consistent hashing of the
+//!   encoded key over the configured peers (see [`HashRing`])
+//! - lookups check the local cache (L1) first, then ask the owner (L2)
+//! - copies of keys owned elsewhere are kept locally for at most
+//!   [`DistributedConfig::l1_ttl`]
+//! - a peer that fails or times out is skipped for
+//!   [`DistributedConfig::retry_after`]; meanwhile its keys are cached
+//!   locally only
+//!
+//! Nodes talk over vaya-net with two binary endpoints per cache, served by
+//! [`DistributedCache::router`]:
+//!
+//! - `POST /_cache/{name}/get`: body is the encoded key; `200` with the
+//!   encoded value, or `404`
+//! - `POST /_cache/{name}/put`: body is `key_len: u32`, key, `ttl_ms: u64`
+//!   (`0` for no TTL), value, all big-endian; `204`
+//!
+//! Remote calls are counted under `vaya_cache_remote_requests_total` with
+//! `cache`, `op` and `outcome` labels.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Mutex, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use vaya_common::metrics;
+use vaya_net::request::RequestBuilder;
+use vaya_net::{Method, Request, Response, Router};
+
+use crate::codec::CacheCodec;
+use crate::ring::HashRing;
+use crate::Cache;
+
+/// Metric counting calls to other nodes
+pub const REMOTE_REQUESTS_METRIC: &str = "vaya_cache_remote_requests_total";
+
+/// Largest response accepted from a peer
+const MAX_RESPONSE_SIZE: usize = vaya_net::MAX_BODY_SIZE + vaya_net::MAX_HEADER_SIZE;
+
+/// Distributed mode settings for one cache
+#[derive(Debug, Clone)]
+pub struct DistributedConfig {
+    /// Cache name, used in the endpoint paths; must match across the fleet
+    pub name: String,
+    /// This node's address as it appears in `peers`
+    pub self_addr: String,
+    /// Addresses (`host:port`) of every node, including this one
+    pub peers: Vec<String>,
+    /// Timeout for one remote get or put
+    pub timeout: Duration,
+    /// Longest a local copy of a key owned by another node is kept
+    pub l1_ttl: Duration,
+    /// How long a failed peer is skipped before it is tried again
+    pub retry_after: Duration,
+}
+
+impl DistributedConfig {
+    /// Create a config for a fleet of one (this node)
+    pub fn new(name: impl Into<String>, self_addr: impl Into<String>) -> Self {
+        let self_addr = self_addr.into();
+        Self {
+            name: name.into(),
+            peers: vec![self_addr.clone()],
+            self_addr,
+            timeout: Duration::from_millis(50),
+            l1_ttl: Duration::from_secs(30),
+            retry_after: Duration::from_secs(10),
+        }
+    }
+
+    /// Set the fleet's nodes; this node is added if missing
+    pub fn with_peers<I, S>(mut self, peers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.peers = peers.into_iter().map(Into::into).collect();
+        if !self.peers.contains(&self.self_addr) {
+            self.peers.push(self.self_addr.clone());
+        }
+        self
+    }
+
+    /// Set the remote call timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set how long local copies of remote keys are kept
+    pub fn with_l1_ttl(mut self, ttl: Duration) -> Self {
+        self.l1_ttl = ttl;
+        self
+    }
+
+    /// Set how long failed peers are skipped
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+}
+
+/// A cache shared across fleet nodes, backed by a local [`Cache`]
+pub struct DistributedCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Local entries: owned keys, plus L1 copies of remote ones
+    local: Arc<Cache<K, V>>,
+    /// Settings
+    config: DistributedConfig,
+    /// Key ownership
+    ring: RwLock<HashRing>,
+    /// Peers skipped until the given time
+    down: Mutex<HashMap<String, Instant>>,
+}
+
+impl<K, V> DistributedCache<K, V>
+where
+    K: Hash + Eq + Clone + CacheCodec + Send + Sync + 'static,
+    V: Clone + CacheCodec + Send + Sync + 'static,
+{
+    /// Create a distributed cache over a local cache
+    pub fn new(local: Cache<K, V>, config: DistributedConfig) -> Self {
+        let ring = HashRing::with_nodes(config.peers.iter().cloned());
+        Self {
+            local: Arc::new(local),
+            config,
+            ring: RwLock::new(ring),
+            down: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The local cache
+    pub fn local(&self) -> &Cache<K, V> {
+        &self.local
+    }
+
+    /// Settings
+    pub fn config(&self) -> &DistributedConfig {
+        &self.config
+    }
+
+    /// Replace the fleet's nodes, e.g. after a membership change
+    pub fn set_peers<I, S>(&self, peers: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut ring = HashRing::with_nodes(peers);
+        ring.add_node(self.config.self_addr.clone());
+        *self.ring.write() = ring;
+    }
+
+    /// Node owning a key
+    pub fn owner(&self, key: &K) -> String {
+        self.ring
+            .read()
+            .owner(&key.encode())
+            .unwrap_or(&self.config.self_addr)
+            .to_string()
+    }
+
+    /// Whether this node owns a key
+    pub fn is_owner(&self, key: &K) -> bool {
+        self.owner(key) == self.config.self_addr
+    }
+
+    /// Get a value from the local cache, or from its owner
+    ///
+    /// Returns `None` on a miss, or if the owner could not be reached.
+    pub async fn get(&self, key: &K) -> Option<V> {
+        if let Some(value) = self.local.get(key) {
+            return Some(value);
+        }
+
+        let key_bytes = key.encode();
+        let owner = self.remote_owner(&key_bytes)?;
+        match self.call(&owner, "get", key_bytes).await {
+            Ok(Some(bytes)) => {
+                let Some(value) = V::decode(&bytes) else {
+                    self.record("get", "error");
+                    return None;
+                };
+                self.record("get", "hit");
+                self.local
+                    .insert(key.clone(), value.clone(), Some(self.config.l1_ttl));
+                Some(value)
+            }
+            Ok(None) => {
+                self.record("get", "miss");
+                None
+            }
+            Err(e) => {
+                self.mark_down(&owner, "get", &e);
+                None
+            }
+        }
+    }
+
+    /// Insert a value locally and on its owner
+    ///
+    /// If the owner cannot be reached the value is only cached locally.
+    pub async fn insert(&self, key: K, value: V, ttl: Option<Duration>) {
+        let key_bytes = key.encode();
+        let Some(owner) = self.remote_owner(&key_bytes) else {
+            self.local.insert(key, value, ttl);
+            return;
+        };
+
+        let l1_ttl = ttl.map_or(self.config.l1_ttl, |ttl| ttl.min(self.config.l1_ttl));
+        let body = encode_put(&key_bytes, ttl, &value.encode());
+        self.local.insert(key, value, Some(l1_ttl));
+        match self.call(&owner, "put", body).await {
+            Ok(_) => self.record("put", "stored"),
+            Err(e) => self.mark_down(&owner, "put", &e),
+        }
+    }
+
+    /// Routes serving this cache to the rest of the fleet
+    ///
+    /// Merge into the node's internal server router.
+    pub fn router(self: &Arc<Self>) -> Router {
+        let mut router = Router::new();
+
+        let cache = self.clone();
+        router.post(&self.path("get"), move |request: Request| {
+            let cache = cache.clone();
+            async move {
+                let response = match K::decode(request.body()) {
+                    Some(key) => match cache.local.get(&key) {
+                        Some(value) => octet_stream(value.encode()),
+                        None => Response::not_found(),
+                    },
+                    None => Response::bad_request().text("Malformed key"),
+                };
+                Ok(response)
+            }
+        });
+
+        let cache = self.clone();
+        router.post(&self.path("put"), move |request: Request| {
+            let cache = cache.clone();
+            async move {
+                let response = match decode_put::<K, V>(request.body()) {
+                    Some((key, ttl, value)) => {
+                        cache.local.insert(key, value, ttl);
+                        Response::no_content()
+                    }
+                    None => Response::bad_request().text("Malformed entry"),
+                };
+                Ok(response)
+            }
+        });
+
+        router
+    }
+
+    /// Owner of a key, unless it is this node or currently skipped
+    fn remote_owner(&self, key: &[u8]) -> Option<String> {
+        let owner = self.ring.read().owner(key)?.to_string();
+        if owner == self.config.self_addr {
+            return None;
+        }
+
+        let mut down = self.down.lock();
+        if let Some(&until) = down.get(&owner) {
+            if Instant::now() < until {
+                return None;
+            }
+            down.remove(&owner);
+        }
+        Some(owner)
+    }
+
+    fn mark_down(&self, peer: &str, op: &str, error: &io::Error) {
+        tracing::warn!(
+            cache = %self.config.name,
+            peer,
+            op,
+            error = %error,
+            "Cache peer unreachable, using local cache only"
+        );
+        self.record(op, "error");
+        self.down
+            .lock()
+            .insert(peer.to_string(), Instant::now() + self.config.retry_after);
+    }
+
+    fn record(&self, op: &str, outcome: &str) {
+        metrics::global()
+            .counter(
+                REMOTE_REQUESTS_METRIC,
+                &[
+                    ("cache", self.config.name.as_str()),
+                    ("op", op),
+                    ("outcome", outcome),
+                ],
+            )
+            .inc();
+    }
+
+    fn path(&self, op: &str) -> String {
+        format!("/_cache/{}/{}", self.config.name, op)
+    }
+
+    /// Call a peer endpoint; `Ok(None)` for a miss
+    async fn call(&self, peer: &str, op: &str, body: Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        let request = RequestBuilder::new(Method::POST, self.path(op))
+            .header("Host", peer)
+            .header("Content-Type", "application/octet-stream")
+            .header("Connection", "close")
+            .header("Content-Length", body.len().to_string())
+            .body(body)
+            .build();
+
+        let exchange = async {
+            let mut stream = TcpStream::connect(peer).await?;
+            stream.write_all(&request.to_bytes()).await?;
+            let mut raw = Vec::new();
+            (&mut stream)
+                .take(MAX_RESPONSE_SIZE as u64)
+                .read_to_end(&mut raw)
+                .await?;
+            parse_response(&raw)
+        };
+        tokio::time::timeout(self.config.timeout, exchange)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "cache peer timed out"))?
+    }
+}
+
+fn octet_stream(body: Vec<u8>) -> Response {
+    Response::ok()
+        .header("Content-Type", "application/octet-stream")
+        .body_bytes(body)
+}
+
+/// Encode a put request body
+fn encode_put(key: &[u8], ttl: Option<Duration>, value: &[u8]) -> Vec<u8> {
+    let ttl_ms = ttl.map_or(0, |ttl| (ttl.as_millis() as u64).max(1));
+    let mut body = Vec::with_capacity(12 + key.len() + value.len());
+    body.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    body.extend_from_slice(key);
+    body.extend_from_slice(&ttl_ms.to_be_bytes());
+    body.extend_from_slice(value);
+    body
+}
+
+/// Decode a put request body
+fn decode_put<K: CacheCodec, V: CacheCodec>(body: &[u8]) -> Option<(K, Option<Duration>, V)> {
+    let (len, rest) = body.split_first_chunk::<4>()?;
+    let len = u32::from_be_bytes(*len) as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (key, rest) = rest.split_at(len);
+    let (ttl_ms, value) = rest.split_first_chunk::<8>()?;
+    let ttl = match u64::from_be_bytes(*ttl_ms) {
+        0 => None,
+        ms => Some(Duration::from_millis(ms)),
+    };
+    Some((K::decode(key)?, ttl, V::decode(value)?))
+}
+
+/// Parse a peer's HTTP response into its body, or `None` for a miss
+fn parse_response(raw: &[u8]) -> io::Result<Option<Vec<u8>>> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed cache response");
+
+    let header_end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&raw[..header_end]).map_err(|_| malformed())?;
+    let mut lines = head.split("\r\n");
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(malformed)?;
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok());
+
+    let body = &raw[header_end + 4..];
+    let body = match content_length {
+        Some(len) if body.len() >= len => &body[..len],
+        Some(_) => return Err(malformed()),
+        None => body,
+    };
+
+    match status {
+        200 => Ok(Some(body.to_vec())),
+        204 | 404 => Ok(None),
+        code => Err(io::Error::other(format!("cache peer answered {}", code))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_net::{Server, ServerConfig};
+
+    /// Start a node's cache server on a free port
+    async fn start_node(
+        peers: &[String],
+        self_addr: &str,
+    ) -> Arc<DistributedCache<String, String>> {
+        let config = DistributedConfig::new("search", self_addr)
+            .with_peers(peers.iter().cloned())
+            .with_timeout(Duration::from_secs(2));
+        let cache = Arc::new(DistributedCache::new(Cache::new(100, 4), config));
+        let server = Server::new(
+            ServerConfig::new(self_addr.parse::<std::net::SocketAddr>().unwrap()),
+            cache.router(),
+        )
+        .unwrap();
+        tokio::spawn(async move { server.run().await });
+        cache
+    }
+
+    fn free_addr() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_shared_through_owner() {
+        let peers = vec![free_addr(), free_addr()];
+        let a = start_node(&peers, &peers[0]).await;
+        let b = start_node(&peers, &peers[1]).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // A key owned by B, written through A
+        let key = (0..)
+            .map(|i| format!("search:SIN:BKK:{}", i))
+            .find(|k| b.is_owner(k))
+            .unwrap();
+        let offers = "\u{2708} 3 offers".to_string();
+        a.insert(key.clone(), offers.clone(), Some(Duration::from_secs(300)))
+            .await;
+        assert_eq!(b.local().get(&key), Some(offers.clone()));
+
+        // A's L1 copy is gone; the value comes back from B
+        a.local().clear();
+        assert_eq!(a.get(&key).await, Some(offers.clone()));
+        assert!(a.local().contains(&key));
+
+        assert_eq!(a.get(&"search:SIN:HKG:x".to_string()).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_owner_falls_back_to_local() {
+        let self_addr = free_addr();
+        let dead = free_addr();
+        let config = DistributedConfig::new("search", self_addr.clone())
+            .with_peers([self_addr.clone(), dead.clone()])
+            .with_retry_after(Duration::from_secs(60));
+        let cache: DistributedCache<String, String> =
+            DistributedCache::new(Cache::new(100, 4), config);
+
+        let key = (0..)
+            .map(|i| format!("search:KUL:NRT:{}", i))
+            .find(|k| cache.owner(k) == dead)
+            .unwrap();
+
+        // The failed put leaves an L1 copy and marks the peer down
+        cache.insert(key.clone(), "offers".into(), None).await;
+        assert!(cache.down.lock().contains_key(&dead));
+        assert_eq!(cache.get(&key).await, Some("offers".to_string()));
+
+        // While it is down, its keys are cached locally with their own TTL
+        cache.local().clear();
+        assert_eq!(cache.get(&key).await, None);
+        cache.insert(key.clone(), "offers".into(), None).await;
+        assert_eq!(cache.local().get(&key), Some("offers".to_string()));
+    }
+
+    #[test]
+    fn test_put_encoding() {
+        let body = encode_put(b"k1", Some(Duration::from_secs(5)), b"v1");
+        let (key, ttl, value) = decode_put::<String, String>(&body).unwrap();
+        assert_eq!(
+            (key.as_str(), ttl, value.as_str()),
+            ("k1", Some(Duration::from_secs(5)), "v1")
+        );
+        assert!(decode_put::<String, String>(&body[..5]).is_none());
+        assert!(decode_put::<String, String>(&encode_put(b"k", None, b"")).is_some());
+    }
+}
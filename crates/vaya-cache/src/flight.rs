@@ -0,0 +1,159 @@
+//! Single-flight loading
+//!
+//! When several callers miss the same key at once, only the first (the
+//! leader) runs the loader; the others wait on its [`Flight`] and share the
+//! result. If the leader fails, panics or is cancelled, waiters are released
+//! with no value and retry, so one of them becomes the next leader.
+
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+enum FlightState<V> {
+    /// Loader running; async waiters to wake when it finishes
+    Running(Vec<Waker>),
+    /// Loader finished; `None` if it produced no value
+    Done(Option<V>),
+}
+
+/// An in-progress load of one key
+pub(crate) struct Flight<V> {
+    state: Mutex<FlightState<V>>,
+    done: Condvar,
+}
+
+impl<V: Clone> Flight<V> {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(FlightState::Running(Vec::new())),
+            done: Condvar::new(),
+        }
+    }
+
+    /// Publish the loader's result and wake all waiters
+    pub(crate) fn complete(&self, value: Option<V>) {
+        let wakers = {
+            let mut state = self.state.lock();
+            match std::mem::replace(&mut *state, FlightState::Done(value)) {
+                FlightState::Running(wakers) => wakers,
+                FlightState::Done(_) => Vec::new(),
+            }
+        };
+        self.done.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Block until the loader finishes
+    pub(crate) fn wait(&self) -> Option<V> {
+        let mut state = self.state.lock();
+        loop {
+            if let FlightState::Done(ref value) = *state {
+                return value.clone();
+            }
+            self.done.wait(&mut state);
+        }
+    }
+
+    /// Wait asynchronously until the loader finishes
+    pub(crate) fn wait_async(self: Arc<Self>) -> FlightWait<V> {
+        FlightWait { flight: self }
+    }
+}
+
+/// Future returned by [`Flight::wait_async`]
+pub(crate) struct FlightWait<V> {
+    flight: Arc<Flight<V>>,
+}
+
+impl<V: Clone> Future for FlightWait<V> {
+    type Output = Option<V>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.flight.state.lock();
+        match *state {
+            FlightState::Done(ref value) => Poll::Ready(value.clone()),
+            FlightState::Running(ref mut wakers) => {
+                if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Keys currently being loaded
+pub(crate) type InFlight<K, V> = Mutex<HashMap<K, Arc<Flight<V>>>>;
+
+/// Outcome of joining the flight for a key
+pub(crate) enum Join<'a, K: Hash + Eq, V: Clone> {
+    /// The value appeared in the cache meanwhile
+    Cached(V),
+    /// This caller runs the loader
+    Leader(LeaderGuard<'a, K, V>),
+    /// Another caller is loading; wait on its flight
+    Follower(Arc<Flight<V>>),
+}
+
+/// Join the flight for `key`, becoming the leader if there is none
+///
+/// `lookup` re-checks the cache under the in-flight lock, so a caller that
+/// missed just before a leader finished does not start a second load.
+pub(crate) fn join<'a, K, V>(
+    in_flight: &'a InFlight<K, V>,
+    key: &K,
+    lookup: impl FnOnce() -> Option<V>,
+) -> Join<'a, K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    let mut flights = in_flight.lock();
+    if let Some(flight) = flights.get(key) {
+        return Join::Follower(flight.clone());
+    }
+    if let Some(value) = lookup() {
+        return Join::Cached(value);
+    }
+    let flight = Arc::new(Flight::new());
+    flights.insert(key.clone(), flight.clone());
+    Join::Leader(LeaderGuard {
+        in_flight,
+        key: key.clone(),
+        flight,
+        finished: false,
+    })
+}
+
+/// Held by the leader; releases waiters empty-handed if dropped before
+/// [`LeaderGuard::finish`] (loader error, panic or cancelled future)
+pub(crate) struct LeaderGuard<'a, K: Hash + Eq, V: Clone> {
+    in_flight: &'a InFlight<K, V>,
+    key: K,
+    flight: Arc<Flight<V>>,
+    finished: bool,
+}
+
+impl<K: Hash + Eq, V: Clone> LeaderGuard<'_, K, V> {
+    /// Unregister the flight and hand the result to waiters
+    pub(crate) fn finish(mut self, value: Option<V>) {
+        self.finished = true;
+        self.in_flight.lock().remove(&self.key);
+        self.flight.complete(value);
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Drop for LeaderGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.in_flight.lock().remove(&self.key);
+            self.flight.complete(None);
+        }
+    }
+}
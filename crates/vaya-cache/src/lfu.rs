@@ -0,0 +1,177 @@
+//! LFU (Least Frequently Used) cache implementation
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// A cached value with its access count
+struct Slot<V> {
+    value: V,
+    /// Number of accesses, including the insert
+    frequency: u64,
+    /// Position among entries with the same frequency (lower is older)
+    tick: u64,
+}
+
+/// A simple LFU cache; ties between equally used entries evict the oldest
+pub struct LfuCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Entries by key
+    map: HashMap<K, Slot<V>>,
+    /// Keys ordered by (frequency, tick), least used first
+    order: BTreeMap<(u64, u64), K>,
+    /// Next tick to hand out
+    next_tick: u64,
+    /// Maximum capacity
+    capacity: usize,
+}
+
+impl<K, V> LfuCache<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    /// Create a new LFU cache with the given capacity
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            map: HashMap::with_capacity(capacity),
+            order: BTreeMap::new(),
+            next_tick: 0,
+            capacity,
+        }
+    }
+
+    /// Get a value, counting the access
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.touch(key)?;
+        self.map.get(key).map(|slot| slot.value.clone())
+    }
+
+    /// Peek at a value without counting the access
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|slot| &slot.value)
+    }
+
+    /// Insert a key-value pair, returning true if an eviction occurred
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        // Updating an existing key counts as an access
+        if self.touch(&key).is_some() {
+            if let Some(slot) = self.map.get_mut(&key) {
+                slot.value = value;
+            }
+            return false;
+        }
+
+        let evicted = self.map.len() >= self.capacity;
+        if evicted {
+            if let Some((_, victim)) = self.order.pop_first() {
+                self.map.remove(&victim);
+            }
+        }
+
+        let tick = self.tick();
+        self.order.insert((1, tick), key.clone());
+        self.map.insert(
+            key,
+            Slot {
+                value,
+                frequency: 1,
+                tick,
+            },
+        );
+        evicted
+    }
+
+    /// Remove a key from the cache
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let slot = self.map.remove(key)?;
+        self.order.remove(&(slot.frequency, slot.tick));
+        Some(slot.value)
+    }
+
+    /// Check if the cache contains a key
+    pub fn contains(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Access count of a key
+    pub fn frequency(&self, key: &K) -> Option<u64> {
+        self.map.get(key).map(|slot| slot.frequency)
+    }
+
+    /// Get the number of items in the cache
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Clear all items from the cache
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.order.clear();
+    }
+
+    /// Iterate over keys, least frequently used first
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.order.values()
+    }
+
+    /// Count an access to `key`, if present
+    fn touch(&mut self, key: &K) -> Option<()> {
+        let tick = self.tick();
+        let slot = self.map.get_mut(key)?;
+        let k = self.order.remove(&(slot.frequency, slot.tick))?;
+        slot.frequency += 1;
+        slot.tick = tick;
+        self.order.insert((slot.frequency, tick), k);
+        Some(())
+    }
+
+    fn tick(&mut self) -> u64 {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        tick
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lfu_eviction() {
+        let mut cache = LfuCache::new(2);
+
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+
+        // "a" is used more often, even though "b" was used last
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"b");
+
+        assert!(cache.insert("c", 3));
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"b"), None); // Evicted
+        assert_eq!(cache.frequency(&"c"), Some(1));
+    }
+
+    #[test]
+    fn test_lfu_ties_evict_oldest() {
+        let mut cache = LfuCache::new(2);
+
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        cache.insert("c", 3);
+
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.remove(&"b"), Some(2));
+        assert_eq!(cache.keys().collect::<Vec<_>>(), [&"c"]);
+    }
+}
@@ -2,8 +2,12 @@
 //!
 //! A high-performance, thread-safe cache with:
 //! - Sharded design for concurrent access
-//! - LRU eviction policy
+//! - LRU eviction by default; LFU, TinyLFU and FIFO via [`CachePolicy`]
 //! - TTL (time-to-live) support
+//! - Request coalescing: concurrent misses on a key run one loader
+//! - Targeted invalidation by key prefix or predicate
+//! - Optional background purging of expired entries
+//! - Optional fleet-wide tier: see [`DistributedCache`]
 //! - Zero external dependencies (no Redis!)
 //!
 //! # Example
@@ -16,23 +20,46 @@
 //!
 //! cache.insert("key1".to_string(), "value1".to_string(), Some(Duration::from_secs(60)));
 //! assert_eq!(cache.get(&"key1".to_string()), Some("value1".to_string()));
+//!
+//! // Load on miss; concurrent callers for the same key share one load
+//! let value = cache.get_or_insert_with("key2".to_string(), None, || "value2".to_string());
+//! assert_eq!(value, "value2");
 //! ```
 
 #![warn(missing_docs)]
 
+mod codec;
+mod distributed;
+mod flight;
+mod lfu;
 mod lru;
+mod policy;
+mod purger;
+mod ring;
 mod shard;
+mod sketch;
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
 use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
+use flight::{InFlight, Join};
+
+pub use codec::CacheCodec;
+pub use distributed::{DistributedCache, DistributedConfig, REMOTE_REQUESTS_METRIC};
+pub use lfu::LfuCache;
 pub use lru::LruCache;
+pub use policy::CachePolicy;
+pub use purger::PurgerHandle;
+pub use ring::HashRing;
 pub use shard::CacheShard;
 
-/// A thread-safe, sharded LRU cache with TTL support
+/// A thread-safe, sharded cache with TTL support
 pub struct Cache<K, V>
 where
     K: Hash + Eq + Clone,
@@ -48,6 +75,10 @@ where
     misses: AtomicU64,
     /// Total items evicted
     evictions: AtomicU64,
+    /// Lookups served by another caller's load
+    coalesced: AtomicU64,
+    /// Keys currently being loaded by `get_or_*insert_with*`
+    in_flight: InFlight<K, V>,
 }
 
 impl<K, V> Cache<K, V>
@@ -75,9 +106,27 @@ where
             hits: AtomicU64::new(0),
             misses: AtomicU64::new(0),
             evictions: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+            in_flight: Mutex::new(HashMap::new()),
         }
     }
 
+    /// Set the eviction policy (LRU by default)
+    ///
+    /// Any entries already cached are dropped.
+    pub fn with_policy(mut self, policy: CachePolicy) -> Self {
+        let per_shard_capacity = self.shards[0].read().capacity();
+        self.shards = (0..self.shard_count)
+            .map(|_| RwLock::new(CacheShard::with_policy(per_shard_capacity, policy)))
+            .collect();
+        self
+    }
+
+    /// Get the eviction policy
+    pub fn policy(&self) -> CachePolicy {
+        self.shards[0].read().policy()
+    }
+
     /// Insert a key-value pair with an optional TTL
     pub fn insert(&self, key: K, value: V, ttl: Option<Duration>) {
         let shard_idx = self.shard_index(&key);
@@ -106,6 +155,96 @@ where
         }
     }
 
+    /// Get a value, loading and caching it with `f` on a miss
+    ///
+    /// Concurrent misses on the same key are coalesced: only one caller runs
+    /// its loader and the others wait for and share its result.
+    pub fn get_or_insert_with<F>(&self, key: K, ttl: Option<Duration>, f: F) -> V
+    where
+        F: FnOnce() -> V,
+    {
+        match self.get_or_try_insert_with(key, ttl, || Ok::<_, Infallible>(f())) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Like [`Cache::get_or_insert_with`], for loaders that can fail
+    ///
+    /// Errors are not cached or shared: if the loading caller fails, one of
+    /// the waiting callers runs its own loader instead.
+    pub fn get_or_try_insert_with<F, E>(&self, key: K, ttl: Option<Duration>, f: F) -> Result<V, E>
+    where
+        F: FnOnce() -> Result<V, E>,
+    {
+        let mut loader = Some(f);
+        loop {
+            if let Some(value) = self.get(&key) {
+                return Ok(value);
+            }
+            match flight::join(&self.in_flight, &key, || self.peek(&key)) {
+                Join::Cached(value) => return Ok(value),
+                Join::Leader(guard) => {
+                    let load = loader.take().expect("only one load per call");
+                    let value = load()?;
+                    self.insert(key, value.clone(), ttl);
+                    guard.finish(Some(value.clone()));
+                    return Ok(value);
+                }
+                Join::Follower(flight) => {
+                    if let Some(value) = flight.wait() {
+                        self.coalesced.fetch_add(1, Ordering::Relaxed);
+                        return Ok(value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Async [`Cache::get_or_try_insert_with`]
+    ///
+    /// Waiting callers yield instead of blocking the thread. If the loading
+    /// future is dropped before it completes, a waiting caller takes over.
+    pub async fn get_or_try_insert_with_async<F, Fut, E>(
+        &self,
+        key: K,
+        ttl: Option<Duration>,
+        f: F,
+    ) -> Result<V, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, E>>,
+    {
+        let mut loader = Some(f);
+        loop {
+            if let Some(value) = self.get(&key) {
+                return Ok(value);
+            }
+            match flight::join(&self.in_flight, &key, || self.peek(&key)) {
+                Join::Cached(value) => return Ok(value),
+                Join::Leader(guard) => {
+                    let load = loader.take().expect("only one load per call");
+                    let value = load().await?;
+                    self.insert(key, value.clone(), ttl);
+                    guard.finish(Some(value.clone()));
+                    return Ok(value);
+                }
+                Join::Follower(flight) => {
+                    if let Some(value) = flight.wait_async().await {
+                        self.coalesced.fetch_add(1, Ordering::Relaxed);
+                        return Ok(value);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Look up a value without touching the hit/miss counters
+    fn peek(&self, key: &K) -> Option<V> {
+        let shard_idx = self.shard_index(key);
+        self.shards[shard_idx].write().get(key)
+    }
+
     /// Check if a key exists and is not expired
     pub fn contains(&self, key: &K) -> bool {
         let shard_idx = self.shard_index(key);
@@ -146,6 +285,31 @@ where
         purged
     }
 
+    /// Remove every entry whose key matches `predicate`
+    ///
+    /// Returns the number of entries removed. Loads already in flight are
+    /// not affected and may re-insert their key when they finish.
+    pub fn invalidate_where<F>(&self, mut predicate: F) -> usize
+    where
+        F: FnMut(&K) -> bool,
+    {
+        self.shards
+            .iter()
+            .map(|shard| shard.write().remove_where(&mut predicate))
+            .sum()
+    }
+
+    /// Remove every entry whose key starts with `prefix`
+    ///
+    /// With keys namespaced like `search:SIN:BKK:...`, this drops a whole
+    /// group of related entries at once.
+    pub fn invalidate_prefix(&self, prefix: &str) -> usize
+    where
+        K: AsRef<str>,
+    {
+        self.invalidate_where(|key| key.as_ref().starts_with(prefix))
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> CacheStats {
         let hits = self.hits.load(Ordering::Relaxed);
@@ -157,6 +321,7 @@ where
             hits,
             misses,
             evictions,
+            coalesced: self.coalesced.load(Ordering::Relaxed),
             size,
             hit_rate: if hits + misses > 0 {
                 hits as f64 / (hits + misses) as f64
@@ -171,6 +336,7 @@ where
         self.hits.store(0, Ordering::Relaxed);
         self.misses.store(0, Ordering::Relaxed);
         self.evictions.store(0, Ordering::Relaxed);
+        self.coalesced.store(0, Ordering::Relaxed);
     }
 
     /// Get the shard index for a key
@@ -190,6 +356,8 @@ pub struct CacheStats {
     pub misses: u64,
     /// Number of items evicted
     pub evictions: u64,
+    /// Number of lookups served by another caller's load
+    pub coalesced: u64,
     /// Current number of items
     pub size: usize,
     /// Hit rate (0.0 to 1.0)
@@ -288,6 +456,135 @@ mod tests {
         assert!(cache.len() > 0);
     }
 
+    #[test]
+    fn test_get_or_insert_with_coalesces_loads() {
+        use std::sync::atomic::AtomicUsize;
+        use std::sync::{Arc, Barrier};
+
+        let cache: Arc<Cache<String, u32>> = Arc::new(Cache::new(100, 4));
+        let loads = Arc::new(AtomicUsize::new(0));
+        let barrier = Arc::new(Barrier::new(50));
+
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                let cache = cache.clone();
+                let loads = loads.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    cache.get_or_insert_with("KUL-NRT".to_string(), None, || {
+                        loads.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(50));
+                        42
+                    })
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 42);
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&"KUL-NRT".to_string()), Some(42));
+    }
+
+    #[test]
+    fn test_failed_load_is_retried_by_waiter() {
+        let cache: Cache<i32, i32> = Cache::new(100, 4);
+
+        let result: Result<i32, &str> = cache.get_or_try_insert_with(1, None, || Err("GDS down"));
+        assert_eq!(result, Err("GDS down"));
+        assert!(!cache.contains(&1));
+
+        let result: Result<i32, &str> = cache.get_or_try_insert_with(1, None, || Ok(10));
+        assert_eq!(result, Ok(10));
+        assert_eq!(cache.get_or_insert_with(1, None, || unreachable!()), 10);
+    }
+
+    #[test]
+    fn test_async_loaders_share_result() {
+        use std::sync::Arc;
+        use std::task::{Wake, Waker};
+
+        struct Noop;
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let cache: Cache<i32, i32> = Cache::new(100, 4);
+        let waker = Waker::from(Arc::new(Noop));
+        let mut cx = std::task::Context::from_waker(&waker);
+
+        // The leader's future is polled first and parks on an unfinished load
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+        let mut leader = Box::pin(cache.get_or_try_insert_with_async(7, None, || async move {
+            std::future::poll_fn(|_| {
+                if rx.try_recv().is_ok() {
+                    std::task::Poll::Ready(())
+                } else {
+                    std::task::Poll::Pending
+                }
+            })
+            .await;
+            Ok::<_, ()>(70)
+        }));
+        assert!(leader.as_mut().poll(&mut cx).is_pending());
+
+        let mut follower =
+            Box::pin(cache.get_or_try_insert_with_async(7, None, || async { Ok::<_, ()>(0) }));
+        assert!(follower.as_mut().poll(&mut cx).is_pending());
+
+        tx.send(()).unwrap();
+        assert_eq!(
+            leader.as_mut().poll(&mut cx),
+            std::task::Poll::Ready(Ok(70))
+        );
+        assert_eq!(
+            follower.as_mut().poll(&mut cx),
+            std::task::Poll::Ready(Ok(70))
+        );
+        assert_eq!(cache.stats().coalesced, 1);
+    }
+
+    #[test]
+    fn test_invalidate_prefix() {
+        let cache: Cache<String, i32> = Cache::new(100, 4);
+        cache.insert("search:SIN:BKK:2026-03-01".to_string(), 1, None);
+        cache.insert("search:SIN:BKK:2026-03-02".to_string(), 2, None);
+        cache.insert("search:SIN:KUL:2026-03-01".to_string(), 3, None);
+        cache.insert("seatmap:SIN:BKK".to_string(), 4, None);
+
+        assert_eq!(cache.invalidate_prefix("search:SIN:BKK:"), 2);
+        assert!(!cache.contains(&"search:SIN:BKK:2026-03-01".to_string()));
+        assert!(cache.contains(&"search:SIN:KUL:2026-03-01".to_string()));
+        assert!(cache.contains(&"seatmap:SIN:BKK".to_string()));
+
+        assert_eq!(cache.invalidate_where(|k| k.ends_with("BKK")), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_with_policy() {
+        let cache: Cache<String, i32> = Cache::new(4, 1).with_policy(CachePolicy::Lfu);
+        assert_eq!(cache.policy(), CachePolicy::Lfu);
+
+        for (i, key) in ["a", "b", "c", "d"].iter().enumerate() {
+            cache.insert(key.to_string(), i as i32, None);
+        }
+        for _ in 0..3 {
+            cache.get(&"a".to_string());
+        }
+        cache.get(&"b".to_string());
+        cache.get(&"c".to_string());
+        cache.get(&"d".to_string());
+
+        // "b" is the oldest of the least used
+        cache.insert("e".to_string(), 4, None);
+        assert!(cache.contains(&"a".to_string()));
+        assert!(!cache.contains(&"b".to_string()));
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
     #[test]
     fn test_stats() {
         let cache: Cache<i32, i32> = Cache::new(100, 4);
@@ -136,11 +136,21 @@ where
         self.map.contains_key(key)
     }
 
+    /// Peek at the least recently used key
+    pub fn peek_lru(&self) -> Option<&K> {
+        self.tail.map(|idx| &self.nodes[idx].key)
+    }
+
     /// Get the number of items in the cache
     pub fn len(&self) -> usize {
         self.map.len()
     }
 
+    /// Get the maximum number of items
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     /// Check if the cache is empty
     pub fn is_empty(&self) -> bool {
         self.map.is_empty()
@@ -0,0 +1,192 @@
+//! Eviction policies
+//!
+//! A shard keeps its entries in a [`Store`] chosen by the cache's
+//! [`CachePolicy`]. All stores share the same operations, so the shard and
+//! its TTL handling do not depend on the policy.
+
+use std::hash::Hash;
+
+use crate::lfu::LfuCache;
+use crate::lru::LruCache;
+use crate::sketch::FrequencySketch;
+
+/// How a full cache chooses which entry to drop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CachePolicy {
+    /// Evict the least recently used entry
+    #[default]
+    Lru,
+    /// Evict the least frequently used entry, oldest first on ties
+    Lfu,
+    /// LRU behind a frequency-based admission filter
+    ///
+    /// A new key only displaces the LRU entry if it has been requested
+    /// more often recently, so a burst of one-off keys cannot flush
+    /// popular ones. Suits skewed workloads such as route searches.
+    TinyLfu,
+    /// Evict the oldest inserted entry; reads do not change the order
+    Fifo,
+}
+
+impl CachePolicy {
+    /// Get the policy name as a string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CachePolicy::Lru => "lru",
+            CachePolicy::Lfu => "lfu",
+            CachePolicy::TinyLfu => "tinylfu",
+            CachePolicy::Fifo => "fifo",
+        }
+    }
+}
+
+/// Entry storage for one shard
+pub(crate) enum Store<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    Lru(LruCache<K, V>),
+    Lfu(LfuCache<K, V>),
+    TinyLfu {
+        lru: LruCache<K, V>,
+        sketch: FrequencySketch,
+    },
+    Fifo(LruCache<K, V>),
+}
+
+impl<K, V> Store<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub(crate) fn new(policy: CachePolicy, capacity: usize) -> Self {
+        match policy {
+            CachePolicy::Lru => Store::Lru(LruCache::new(capacity)),
+            CachePolicy::Lfu => Store::Lfu(LfuCache::new(capacity)),
+            CachePolicy::TinyLfu => Store::TinyLfu {
+                lru: LruCache::new(capacity),
+                sketch: FrequencySketch::new(capacity),
+            },
+            CachePolicy::Fifo => Store::Fifo(LruCache::new(capacity)),
+        }
+    }
+
+    /// Get a value, recording the access
+    pub(crate) fn get(&mut self, key: &K) -> Option<V> {
+        match self {
+            Store::Lru(lru) => lru.get(key),
+            Store::Lfu(lfu) => lfu.get(key),
+            Store::TinyLfu { lru, sketch } => {
+                // Misses count too: they are what earns a key admission
+                sketch.increment(key);
+                lru.get(key)
+            }
+            Store::Fifo(lru) => lru.peek(key).cloned(),
+        }
+    }
+
+    pub(crate) fn peek(&self, key: &K) -> Option<&V> {
+        match self {
+            Store::Lru(lru) | Store::Fifo(lru) | Store::TinyLfu { lru, .. } => lru.peek(key),
+            Store::Lfu(lfu) => lfu.peek(key),
+        }
+    }
+
+    /// Insert a key-value pair, returning true if an entry was dropped
+    ///
+    /// Under TinyLFU the dropped entry may be the one being inserted.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> bool {
+        match self {
+            Store::Lru(lru) | Store::Fifo(lru) => lru.insert(key, value),
+            Store::Lfu(lfu) => lfu.insert(key, value),
+            Store::TinyLfu { lru, sketch } => {
+                sketch.increment(&key);
+                if lru.contains(&key) || lru.len() < lru.capacity() {
+                    return lru.insert(key, value);
+                }
+                let Some(victim) = lru.peek_lru().cloned() else {
+                    return lru.insert(key, value);
+                };
+                if sketch.estimate(&key) > sketch.estimate(&victim) {
+                    lru.remove(&victim);
+                    lru.insert(key, value);
+                }
+                true
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        match self {
+            Store::Lru(lru) | Store::Fifo(lru) | Store::TinyLfu { lru, .. } => lru.remove(key),
+            Store::Lfu(lfu) => lfu.remove(key),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Store::Lru(lru) | Store::Fifo(lru) | Store::TinyLfu { lru, .. } => lru.len(),
+            Store::Lfu(lfu) => lfu.len(),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Store::Lru(lru) | Store::Fifo(lru) | Store::TinyLfu { lru, .. } => lru.clear(),
+            Store::Lfu(lfu) => lfu.clear(),
+        }
+    }
+
+    pub(crate) fn keys(&self) -> Box<dyn Iterator<Item = &K> + '_> {
+        match self {
+            Store::Lru(lru) | Store::Fifo(lru) | Store::TinyLfu { lru, .. } => Box::new(lru.keys()),
+            Store::Lfu(lfu) => Box::new(lfu.keys()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_ignores_reads() {
+        let mut store = Store::new(CachePolicy::Fifo, 2);
+        store.insert("a", 1);
+        store.insert("b", 2);
+        assert_eq!(store.get(&"a"), Some(1));
+
+        // "a" was read, but is still the oldest
+        assert!(store.insert("c", 3));
+        assert!(store.peek(&"a").is_none());
+        assert!(store.peek(&"b").is_some());
+    }
+
+    #[test]
+    fn test_tinylfu_resists_one_off_keys() {
+        let mut store = Store::new(CachePolicy::TinyLfu, 4);
+        for route in ["SIN-BKK", "KUL-SIN", "SIN-HKG", "KUL-BKK"] {
+            store.insert(route, 1);
+            for _ in 0..3 {
+                store.get(&route);
+            }
+        }
+
+        // A scan of one-off routes is refused admission
+        for route in ["AOR-TWU", "BTU-MYY", "LBU-SDK", "KCH-SBW", "MKZ-TGG"] {
+            assert_eq!(store.get(&route), None);
+            assert!(store.insert(route, 1));
+        }
+        assert_eq!(store.len(), 4);
+        assert!(store.peek(&"SIN-BKK").is_some());
+
+        // A key requested often enough is admitted
+        for _ in 0..6 {
+            store.get(&"PEN-CGK");
+        }
+        store.insert("PEN-CGK", 1);
+        assert!(store.peek(&"PEN-CGK").is_some());
+        assert_eq!(store.len(), 4);
+    }
+}
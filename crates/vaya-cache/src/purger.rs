@@ -0,0 +1,100 @@
+//! Background purging of expired entries
+//!
+//! Expired entries are already hidden from reads, but they keep their slot
+//! until the key is read again or the entry is evicted. A purger frees them
+//! on a fixed interval so short-TTL caches do not fill up with dead entries.
+
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::Cache;
+
+impl<K, V> Cache<K, V>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Purge expired entries every `interval` on a background thread
+    ///
+    /// The thread stops when the returned handle is dropped or the cache
+    /// itself is dropped.
+    pub fn spawn_purger(self: &Arc<Self>, interval: Duration) -> PurgerHandle {
+        let cache: Weak<Self> = Arc::downgrade(self);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("vaya-cache-purge".into())
+                .spawn(move || loop {
+                    std::thread::park_timeout(interval);
+                    if stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    match cache.upgrade() {
+                        Some(cache) => {
+                            cache.purge_expired();
+                        }
+                        None => break,
+                    }
+                })
+                .expect("failed to spawn cache purger thread")
+        };
+        PurgerHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Handle to a running cache purger; stops the purger when dropped
+pub struct PurgerHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PurgerHandle {
+    /// Stop the purger and wait for the thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PurgerHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purger_removes_expired_entries() {
+        let cache: Arc<Cache<String, i32>> = Arc::new(Cache::new(100, 4));
+        cache.insert("short".to_string(), 1, Some(Duration::from_millis(20)));
+        cache.insert("long".to_string(), 2, None);
+
+        let purger = cache.spawn_purger(Duration::from_millis(10));
+        std::thread::sleep(Duration::from_millis(150));
+        assert_eq!(cache.len(), 1);
+        purger.stop();
+
+        // The purger exits on its own once the cache is dropped
+        let purger = cache.spawn_purger(Duration::from_millis(10));
+        drop(cache);
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(purger.thread.as_ref().unwrap().is_finished());
+    }
+}
@@ -0,0 +1,150 @@
+//! Consistent hashing of keys to fleet nodes
+
+/// Points placed on the ring per node
+const VIRTUAL_NODES: usize = 64;
+
+/// Consistent-hash ring assigning each key an owner node
+///
+/// Each node is placed at several points on the ring, and a key belongs to
+/// the first node point at or after the key's hash. Adding or removing a
+/// node only moves the keys next to its points.
+///
+/// Hashing is over bytes with a fixed function, so every node in the fleet
+/// agrees on ownership.
+#[derive(Debug, Clone, Default)]
+pub struct HashRing {
+    /// (hash, index into `nodes`), sorted by hash
+    points: Vec<(u64, usize)>,
+    /// Node addresses
+    nodes: Vec<String>,
+}
+
+impl HashRing {
+    /// Create an empty ring
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a ring of the given nodes
+    pub fn with_nodes<I, S>(nodes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut ring = Self::new();
+        for node in nodes {
+            ring.add_node(node);
+        }
+        ring
+    }
+
+    /// Add a node; adding a node twice has no effect
+    pub fn add_node(&mut self, node: impl Into<String>) {
+        let node = node.into();
+        if self.nodes.contains(&node) {
+            return;
+        }
+        self.nodes.push(node);
+        self.rebuild();
+    }
+
+    /// Remove a node, returning whether it was present
+    pub fn remove_node(&mut self, node: &str) -> bool {
+        let before = self.nodes.len();
+        self.nodes.retain(|n| n != node);
+        let removed = self.nodes.len() != before;
+        if removed {
+            self.rebuild();
+        }
+        removed
+    }
+
+    /// Nodes on the ring
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check if the ring has no nodes
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Node owning an encoded key
+    pub fn owner(&self, key: &[u8]) -> Option<&str> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let hash = hash_bytes(key);
+        let idx = self.points.partition_point(|&(point, _)| point < hash);
+        let (_, node) = self.points[idx % self.points.len()];
+        Some(&self.nodes[node])
+    }
+
+    fn rebuild(&mut self) {
+        self.points = self
+            .nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, node)| {
+                (0..VIRTUAL_NODES).map(move |vnode| {
+                    let point = format!("{}#{}", node, vnode);
+                    (hash_bytes(point.as_bytes()), idx)
+                })
+            })
+            .collect();
+        self.points.sort_unstable();
+    }
+}
+
+/// FNV-1a with a final mix, stable across processes and platforms
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    // splitmix64 finalizer spreads FNV's weak low bits
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_spreads_and_rebalances() {
+        let mut ring = HashRing::with_nodes(["10.0.0.1:7100", "10.0.0.2:7100", "10.0.0.3:7100"]);
+        assert_eq!(ring.len(), 3);
+
+        let keys: Vec<String> = (0..3000).map(|i| format!("search:KUL:{}", i)).collect();
+        let owners: Vec<String> = keys
+            .iter()
+            .map(|k| ring.owner(k.as_bytes()).unwrap().to_string())
+            .collect();
+        for node in ring.nodes() {
+            let owned = owners.iter().filter(|o| *o == node).count();
+            assert!(owned > 600, "{} owns only {} keys", node, owned);
+        }
+
+        // Removing a node only moves that node's keys
+        assert!(ring.remove_node("10.0.0.2:7100"));
+        for (key, before) in keys.iter().zip(&owners) {
+            let after = ring.owner(key.as_bytes()).unwrap();
+            if before != "10.0.0.2:7100" {
+                assert_eq!(after, before);
+            }
+        }
+
+        assert!(!ring.remove_node("10.0.0.2:7100"));
+        assert!(HashRing::new().owner(b"key").is_none());
+    }
+}
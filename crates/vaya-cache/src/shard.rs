@@ -1,6 +1,6 @@
 //! Cache shard with TTL support
 
-use crate::lru::LruCache;
+use crate::policy::{CachePolicy, Store};
 use std::hash::Hash;
 use std::time::Instant;
 
@@ -20,14 +20,18 @@ impl<V: Clone> Entry<V> {
     }
 }
 
-/// A single cache shard with LRU eviction and TTL support
+/// A single cache shard with policy-driven eviction and TTL support
 pub struct CacheShard<K, V>
 where
     K: Hash + Eq + Clone,
     V: Clone,
 {
-    /// LRU cache for ordering
-    lru: LruCache<K, Entry<V>>,
+    /// Entries, ordered by the eviction policy
+    store: Store<K, Entry<V>>,
+    /// Eviction policy
+    policy: CachePolicy,
+    /// Maximum number of entries
+    capacity: usize,
 }
 
 impl<K, V> CacheShard<K, V>
@@ -35,26 +39,43 @@ where
     K: Hash + Eq + Clone,
     V: Clone,
 {
-    /// Create a new cache shard with the given capacity
+    /// Create a new LRU cache shard with the given capacity
     pub fn new(capacity: usize) -> Self {
+        Self::with_policy(capacity, CachePolicy::Lru)
+    }
+
+    /// Create a new cache shard with the given capacity and eviction policy
+    pub fn with_policy(capacity: usize, policy: CachePolicy) -> Self {
         Self {
-            lru: LruCache::new(capacity),
+            store: Store::new(policy, capacity),
+            policy,
+            capacity,
         }
     }
 
+    /// Get the eviction policy
+    pub fn policy(&self) -> CachePolicy {
+        self.policy
+    }
+
+    /// Get the maximum number of entries
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     /// Insert a key-value pair with optional expiration, returns true if eviction occurred
     pub fn insert(&mut self, key: K, value: V, expires_at: Option<Instant>) -> bool {
         let entry = Entry { value, expires_at };
-        self.lru.insert(key, entry)
+        self.store.insert(key, entry)
     }
 
     /// Get a value, checking for expiration
     pub fn get(&mut self, key: &K) -> Option<V> {
-        // Get from LRU and check expiration
-        if let Some(entry) = self.lru.get(key) {
+        // Get from the store and check expiration
+        if let Some(entry) = self.store.get(key) {
             if entry.is_expired() {
                 // Remove expired entry
-                self.lru.remove(key);
+                self.store.remove(key);
                 None
             } else {
                 Some(entry.value)
@@ -66,7 +87,7 @@ where
 
     /// Check if a key exists and is not expired
     pub fn contains(&self, key: &K) -> bool {
-        if let Some(entry) = self.lru.peek(key) {
+        if let Some(entry) = self.store.peek(key) {
             !entry.is_expired()
         } else {
             false
@@ -75,32 +96,32 @@ where
 
     /// Remove a key from the shard
     pub fn remove(&mut self, key: &K) -> Option<V> {
-        self.lru.remove(key).map(|entry| entry.value)
+        self.store.remove(key).map(|entry| entry.value)
     }
 
     /// Get the number of items in the shard
     pub fn len(&self) -> usize {
-        self.lru.len()
+        self.store.len()
     }
 
     /// Check if the shard is empty
     pub fn is_empty(&self) -> bool {
-        self.lru.is_empty()
+        self.store.len() == 0
     }
 
     /// Clear all items from the shard
     pub fn clear(&mut self) {
-        self.lru.clear();
+        self.store.clear();
     }
 
     /// Purge expired entries, returning the number purged
     pub fn purge_expired(&mut self) -> usize {
         // Collect keys to remove (we can't mutate while iterating)
         let expired_keys: Vec<K> = self
-            .lru
+            .store
             .keys()
             .filter_map(|k| {
-                if let Some(entry) = self.lru.peek(k) {
+                if let Some(entry) = self.store.peek(k) {
                     if entry.is_expired() {
                         return Some(k.clone());
                     }
@@ -111,7 +132,26 @@ where
 
         let count = expired_keys.len();
         for key in expired_keys {
-            self.lru.remove(&key);
+            self.store.remove(&key);
+        }
+        count
+    }
+
+    /// Remove entries whose key matches `predicate`, returning the number removed
+    pub fn remove_where<F>(&mut self, mut predicate: F) -> usize
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let matching: Vec<K> = self
+            .store
+            .keys()
+            .filter(|k| predicate(k))
+            .cloned()
+            .collect();
+
+        let count = matching.len();
+        for key in matching {
+            self.store.remove(&key);
         }
         count
     }
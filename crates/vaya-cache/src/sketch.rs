@@ -0,0 +1,127 @@
+//! Approximate access frequencies for TinyLFU admission
+//!
+//! A count-min sketch: each key maps to one small counter in each of
+//! [`DEPTH`] rows, and its estimate is the smallest of those counters.
+//! Counters are halved after a sample period so that old popularity fades.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Number of counter rows
+const DEPTH: usize = 4;
+
+/// Largest value a counter holds
+const MAX_COUNT: u8 = 15;
+
+/// Frequency sketch sized for a cache of a given capacity
+pub(crate) struct FrequencySketch {
+    /// `DEPTH` rows of `width` counters
+    counters: Vec<u8>,
+    /// Counters per row (power of 2)
+    width: usize,
+    /// Increments since the last halving
+    additions: usize,
+    /// Increments between halvings
+    sample_size: usize,
+}
+
+impl FrequencySketch {
+    /// Create a sketch for a cache holding `capacity` entries
+    pub(crate) fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let width = capacity.next_power_of_two().max(16);
+        Self {
+            counters: vec![0; DEPTH * width],
+            width,
+            additions: 0,
+            sample_size: capacity.saturating_mul(10),
+        }
+    }
+
+    /// Estimated number of recent accesses to `key`
+    pub(crate) fn estimate<K: Hash>(&self, key: &K) -> u8 {
+        self.indexes(key)
+            .iter()
+            .map(|&idx| self.counters[idx])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Record an access to `key`
+    pub(crate) fn increment<K: Hash>(&mut self, key: &K) {
+        let indexes = self.indexes(key);
+        let min = indexes
+            .iter()
+            .map(|&idx| self.counters[idx])
+            .min()
+            .unwrap_or(0);
+        if min == MAX_COUNT {
+            return;
+        }
+        // Conservative update: only raise the counters holding the estimate
+        for idx in indexes {
+            if self.counters[idx] == min {
+                self.counters[idx] += 1;
+            }
+        }
+
+        self.additions += 1;
+        if self.additions >= self.sample_size {
+            self.halve();
+        }
+    }
+
+    /// Age all counters
+    fn halve(&mut self) {
+        for counter in &mut self.counters {
+            *counter /= 2;
+        }
+        self.additions /= 2;
+    }
+
+    /// Counter index of `key` in each row
+    fn indexes<K: Hash>(&self, key: &K) -> [usize; DEPTH] {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        // Double hashing: row i uses h1 + i * h2
+        let h1 = hash as u32 as usize;
+        let h2 = ((hash >> 32) as usize) | 1;
+        let mut indexes = [0; DEPTH];
+        for (row, index) in indexes.iter_mut().enumerate() {
+            let column = h1.wrapping_add(row.wrapping_mul(h2)) & (self.width - 1);
+            *index = row * self.width + column;
+        }
+        indexes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sketch_counts_and_ages() {
+        let mut sketch = FrequencySketch::new(100);
+
+        for _ in 0..5 {
+            sketch.increment(&"SIN-BKK");
+        }
+        sketch.increment(&"KUL-LHR");
+
+        assert!(sketch.estimate(&"SIN-BKK") >= 5);
+        assert!(sketch.estimate(&"KUL-LHR") >= 1);
+        assert!(sketch.estimate(&"SIN-BKK") > sketch.estimate(&"KUL-LHR"));
+
+        // Counters saturate
+        for _ in 0..50 {
+            sketch.increment(&"hot");
+        }
+        assert!(sketch.estimate(&"hot") <= MAX_COUNT);
+
+        let before = sketch.estimate(&"SIN-BKK");
+        sketch.halve();
+        assert_eq!(sketch.estimate(&"SIN-BKK"), before / 2);
+    }
+}
@@ -1,14 +1,17 @@
 //! HTTP client with TLS support
 
-use std::io::{Read, Write};
+use std::io::Write;
 use std::net::TcpStream;
 use std::sync::Arc;
 use std::time::Duration;
 
 use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
 
+use crate::dns::{Resolver, SystemResolver};
+use crate::proxy::{Proxy, ProxyConfig, ProxyKind};
 use crate::request::{Method, Request, RequestBuilder};
 use crate::response::Response;
+use crate::stream::ResponseStream;
 use crate::url::Url;
 use crate::{CollectError, CollectResult};
 
@@ -23,6 +26,10 @@ pub struct ClientConfig2 {
     pub user_agent: String,
     /// Maximum response body size
     pub max_body_size: usize,
+    /// Outbound proxies (none by default)
+    pub proxy: ProxyConfig,
+    /// Host name resolution for targets and proxies
+    pub resolver: Arc<dyn Resolver>,
 }
 
 impl Default for ClientConfig2 {
@@ -32,6 +39,8 @@ impl Default for ClientConfig2 {
             max_redirects: 5,
             user_agent: "vaya-collect/0.1".to_string(),
             max_body_size: 10 * 1024 * 1024, // 10MB
+            proxy: ProxyConfig::default(),
+            resolver: Arc::new(SystemResolver),
         }
     }
 }
@@ -54,6 +63,18 @@ impl ClientConfig2 {
         self.max_body_size = size;
         self
     }
+
+    /// Send requests through proxies
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Resolve host names with `resolver`
+    pub fn resolver(mut self, resolver: Arc<dyn Resolver>) -> Self {
+        self.resolver = resolver;
+        self
+    }
 }
 
 /// HTTP client
@@ -124,8 +145,26 @@ impl Client {
             .timeout(self.config.timeout_ms)
     }
 
-    /// Execute a request
+    /// Execute a request, buffering a body of up to `max_body_size` bytes
     pub fn execute(&self, request: Request) -> CollectResult<Response> {
+        self.execute_stream(request)?
+            .limit(self.config.max_body_size as u64)
+            .into_response()
+    }
+
+    /// Execute a GET request, returning once the response head arrives
+    pub fn get_stream(&self, url: &str) -> CollectResult<ResponseStream> {
+        let url = Url::parse(url)?;
+        let request = Request::get(url)
+            .user_agent(&self.config.user_agent)
+            .timeout(self.config.timeout_ms);
+        self.execute_stream(request)
+    }
+
+    /// Execute a request, leaving the body to be streamed by the caller
+    ///
+    /// `max_body_size` does not apply; use [`ResponseStream::limit`].
+    pub fn execute_stream(&self, request: Request) -> CollectResult<ResponseStream> {
         self.execute_with_redirects(request, 0)
     }
 
@@ -134,7 +173,7 @@ impl Client {
         &self,
         request: Request,
         redirect_count: u32,
-    ) -> CollectResult<Response> {
+    ) -> CollectResult<ResponseStream> {
         if redirect_count > self.config.max_redirects {
             return Err(CollectError::TooManyRedirects);
         }
@@ -142,8 +181,8 @@ impl Client {
         let response = self.send_request(&request)?;
 
         // Handle redirects
-        if response.is_redirect() {
-            if let Some(location) = response.location() {
+        if (300..400).contains(&response.status) {
+            if let Some(location) = response.headers.get("location") {
                 let new_url = self.resolve_redirect(&request.url, location)?;
                 let new_request = Request::get(new_url)
                     .timeout(request.timeout_ms.unwrap_or(self.config.timeout_ms));
@@ -187,31 +226,68 @@ impl Client {
     }
 
     /// Send a single request
-    fn send_request(&self, request: &Request) -> CollectResult<Response> {
+    fn send_request(&self, request: &Request) -> CollectResult<ResponseStream> {
         let timeout = Duration::from_millis(request.timeout_ms.unwrap_or(self.config.timeout_ms));
-
-        // Connect
-        let addr = format!("{}:{}", request.url.host, request.url.port);
-        let stream = TcpStream::connect(&addr).map_err(|e| {
-            CollectError::ConnectionFailed(format!("Failed to connect to {}: {}", addr, e))
-        })?;
-
-        stream
-            .set_read_timeout(Some(timeout))
-            .map_err(CollectError::Io)?;
-        stream
-            .set_write_timeout(Some(timeout))
-            .map_err(CollectError::Io)?;
-
-        if request.url.is_tls() {
+        let url = &request.url;
+
+        let Some(proxy) = self.config.proxy.for_url(url) else {
+            let stream = self.connect(&url.host, url.port, timeout)?;
+            return if url.is_tls() {
+                self.send_tls_request(stream, request)
+            } else {
+                self.send_plain_request(stream, request, None)
+            };
+        };
+
+        let mut stream = self.connect(&proxy.host, proxy.port, timeout)?;
+        if proxy.kind == ProxyKind::Http && !url.is_tls() {
+            // Plain HTTP is forwarded by the proxy rather than tunnelled
+            return self.send_plain_request(stream, request, Some(proxy));
+        }
+        proxy.tunnel(
+            &mut stream,
+            &url.host,
+            url.port,
+            self.config.resolver.as_ref(),
+        )?;
+        if url.is_tls() {
             self.send_tls_request(stream, request)
         } else {
-            self.send_plain_request(stream, request)
+            self.send_plain_request(stream, request, None)
+        }
+    }
+
+    /// Connect to the first reachable address of `host`
+    fn connect(&self, host: &str, port: u16, timeout: Duration) -> CollectResult<TcpStream> {
+        let mut last_error = None;
+        for addr in self.config.resolver.resolve(host, port)? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => {
+                    stream
+                        .set_read_timeout(Some(timeout))
+                        .map_err(CollectError::Io)?;
+                    stream
+                        .set_write_timeout(Some(timeout))
+                        .map_err(CollectError::Io)?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = Some(e),
+            }
         }
+        Err(CollectError::ConnectionFailed(format!(
+            "Failed to connect to {}:{}: {}",
+            host,
+            port,
+            last_error.map_or_else(|| "no addresses".to_string(), |e| e.to_string())
+        )))
     }
 
     /// Send request over TLS
-    fn send_tls_request(&self, stream: TcpStream, request: &Request) -> CollectResult<Response> {
+    fn send_tls_request(
+        &self,
+        stream: TcpStream,
+        request: &Request,
+    ) -> CollectResult<ResponseStream> {
         let server_name = request
             .url
             .host
@@ -230,32 +306,24 @@ impl Client {
             .write_all(&request_bytes)
             .map_err(CollectError::Io)?;
 
-        // Read response
-        let mut response_data = Vec::new();
-        tls_stream
-            .read_to_end(&mut response_data)
-            .map_err(CollectError::Io)?;
-
-        Response::parse(&response_data)
+        ResponseStream::open(Box::new(tls_stream), request.method)
     }
 
-    /// Send request without TLS
+    /// Send request without TLS, in absolute form when it goes to a proxy
     fn send_plain_request(
         &self,
         mut stream: TcpStream,
         request: &Request,
-    ) -> CollectResult<Response> {
+        proxy: Option<&Proxy>,
+    ) -> CollectResult<ResponseStream> {
         // Send request
-        let request_bytes = request.build();
+        let request_bytes = match proxy {
+            Some(proxy) => request.build_for_proxy(proxy.authorization().as_deref()),
+            None => request.build(),
+        };
         stream.write_all(&request_bytes).map_err(CollectError::Io)?;
 
-        // Read response
-        let mut response_data = Vec::new();
-        stream
-            .read_to_end(&mut response_data)
-            .map_err(CollectError::Io)?;
-
-        Response::parse(&response_data)
+        ResponseStream::open(Box::new(stream), request.method)
     }
 }
 
@@ -319,6 +387,7 @@ impl rustls::client::danger::ServerCertVerifier for NoVerifier {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io::Read;
 
     #[test]
     fn test_client_creation() {
@@ -357,4 +426,102 @@ mod tests {
         assert_eq!(resolved.host, "other.com");
         assert_eq!(resolved.path, "/new");
     }
+
+    const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+
+    /// Serve one connection with `response`, handing the request head to
+    /// `check`
+    fn serve_once(
+        response: &'static [u8],
+        check: impl FnOnce(&str) + Send + 'static,
+    ) -> (std::net::SocketAddr, std::thread::JoinHandle<()>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut head = Vec::new();
+            let mut byte = [0u8; 1];
+            while !head.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                head.push(byte[0]);
+            }
+            check(&String::from_utf8_lossy(&head));
+            stream.write_all(response).unwrap();
+        });
+        (addr, handle)
+    }
+
+    /// Sends every host to one address
+    #[derive(Debug)]
+    struct Fixed(std::net::SocketAddr);
+
+    impl Resolver for Fixed {
+        fn resolve(&self, _host: &str, port: u16) -> CollectResult<Vec<std::net::SocketAddr>> {
+            Ok(vec![std::net::SocketAddr::new(self.0.ip(), port)])
+        }
+    }
+
+    #[test]
+    fn test_custom_resolver() {
+        let (addr, server) = serve_once(OK, |head| {
+            assert!(head.starts_with("GET /status HTTP/1.1\r\n"), "{}", head);
+        });
+        let config = ClientConfig2::default()
+            .timeout(5000)
+            .resolver(Arc::new(Fixed(addr)));
+        let client = Client::with_config(config).unwrap();
+
+        let url = format!("http://collector.internal:{}/status", addr.port());
+        let response = client.get(&url).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_http_proxy_forwarding() {
+        let (addr, server) = serve_once(OK, |head| {
+            assert!(
+                head.starts_with("GET http://api.example.com/v1/rates?page=2 HTTP/1.1\r\n"),
+                "{}",
+                head
+            );
+            assert!(head.contains("Proxy-Authorization: Basic dXNlcjpzZWNyZXQ=\r\n"));
+        });
+        let proxy = Proxy::http("127.0.0.1", addr.port()).basic_auth("user", "secret");
+        let config = ClientConfig2::default()
+            .timeout(5000)
+            .proxy(ProxyConfig::all(proxy).no_proxy("localhost"));
+        let client = Client::with_config(config).unwrap();
+
+        let response = client
+            .get("http://api.example.com/v1/rates?page=2")
+            .unwrap();
+        assert_eq!(response.body, b"ok");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_stream_chunked_body() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            4\r\nrate\r\n6\r\ns.csv\n\r\n0\r\n\r\n";
+        let (addr, server) = serve_once(response, |_| {});
+        let client = Client::with_config(ClientConfig2::default().timeout(5000)).unwrap();
+
+        let stream = client.get_stream(&format!("http://{}/dump", addr)).unwrap();
+        assert_eq!(stream.status, 200);
+        let body: Vec<u8> = stream.chunks().flat_map(Result::unwrap).collect();
+        assert_eq!(body, b"rates.csv\n");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_max_body_size() {
+        let (addr, server) = serve_once(OK, |_| {});
+        let config = ClientConfig2::default().timeout(5000).max_body_size(1);
+        let client = Client::with_config(config).unwrap();
+
+        let result = client.get(&format!("http://{}/", addr));
+        assert!(matches!(result, Err(CollectError::BodyTooLarge(1))));
+        server.join().unwrap();
+    }
 }
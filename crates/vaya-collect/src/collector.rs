@@ -1,13 +1,18 @@
 //! High-level data collector with caching and retry
 
-use std::sync::Mutex;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use vaya_cache::LruCache;
 
 use crate::client::{Client, ClientConfig2};
-use crate::response::Response;
+use crate::dns::Resolver;
+use crate::download::{Download, DownloadOptions};
+use crate::proxy::ProxyConfig;
+use crate::response::{Response, ResponseHeaders};
 use crate::retry::{CircuitBreaker, RateLimiter, RetryStrategy};
+use crate::stream::ResponseStream;
 use crate::url::Url;
 use crate::{CollectError, CollectResult};
 
@@ -52,6 +57,32 @@ struct CachedResponse {
     content_type: Option<String>,
 }
 
+/// What the retry loop needs to see of a response
+trait Received {
+    fn status(&self) -> u16;
+    fn headers(&self) -> &ResponseHeaders;
+}
+
+impl Received for Response {
+    fn status(&self) -> u16 {
+        self.status
+    }
+
+    fn headers(&self) -> &ResponseHeaders {
+        &self.headers
+    }
+}
+
+impl Received for ResponseStream {
+    fn status(&self) -> u16 {
+        self.status
+    }
+
+    fn headers(&self) -> &ResponseHeaders {
+        &self.headers
+    }
+}
+
 /// Data collector with caching, retry, and rate limiting
 pub struct Collector {
     client: Client,
@@ -152,6 +183,11 @@ impl Collector {
 
     /// Fetch with retry logic
     fn fetch_with_retry(&self, url: &str) -> CollectResult<Response> {
+        self.with_retry(|| self.client.get(url))
+    }
+
+    /// Run `send` until it succeeds or the retry strategy gives up
+    fn with_retry<T: Received>(&self, send: impl Fn() -> CollectResult<T>) -> CollectResult<T> {
         let mut last_error = None;
 
         for attempt in 0..=self.retry_strategy.max_retries {
@@ -160,12 +196,12 @@ impl Collector {
                 std::thread::sleep(delay);
             }
 
-            match self.client.get(url) {
+            match send() {
                 Ok(response) => {
                     // Check for rate limit response
-                    if response.status == 429 {
+                    if response.status() == 429 {
                         let retry_after = response
-                            .headers
+                            .headers()
                             .get("retry-after")
                             .and_then(|s| s.parse().ok())
                             .unwrap_or(60);
@@ -187,6 +223,44 @@ impl Collector {
         Err(last_error.unwrap_or(CollectError::Timeout))
     }
 
+    /// Fetch URL without buffering the body
+    ///
+    /// Rate limiting, the circuit breaker and retries apply up to the
+    /// response head; the body is read as the caller consumes it and is
+    /// never cached.
+    pub fn fetch_stream(&self, url: &str) -> CollectResult<ResponseStream> {
+        let parsed = Url::parse(url)?;
+
+        self.circuit_breaker.check(&parsed.host)?;
+        self.rate_limiter.check(&parsed.host)?;
+
+        let result = self.with_retry(|| self.client.get_stream(url));
+
+        match &result {
+            Ok(response) if response.is_success() => {
+                self.circuit_breaker.record_success(&parsed.host);
+            }
+            Err(_) => {
+                self.circuit_breaker.record_failure(&parsed.host);
+            }
+            _ => {}
+        }
+
+        result
+    }
+
+    /// Download URL to `path`, enforcing `options`' size limit and checksum
+    ///
+    /// See [`ResponseStream::download_to`].
+    pub fn download_to(
+        &self,
+        url: &str,
+        path: impl AsRef<Path>,
+        options: &DownloadOptions,
+    ) -> CollectResult<Download> {
+        self.fetch_stream(url)?.download_to(path, options)
+    }
+
     /// Fetch JSON and return as string
     pub fn fetch_json(&self, url: &str) -> CollectResult<String> {
         let response = self.fetch(url)?;
@@ -306,6 +380,18 @@ impl CollectorBuilder {
         self
     }
 
+    /// Send this collector's requests through proxies
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.config.client_config.proxy = proxy;
+        self
+    }
+
+    /// Resolve host names for this collector with `resolver`
+    pub fn resolver(mut self, resolver: Arc<dyn Resolver>) -> Self {
+        self.config.client_config.resolver = resolver;
+        self
+    }
+
     /// Build the collector
     pub fn build(self) -> CollectResult<Collector> {
         Collector::with_config(self.config)
@@ -0,0 +1,195 @@
+//! Pluggable DNS resolution
+//!
+//! The client looks up every host it connects to (the target, or the proxy
+//! in front of it) through a [`Resolver`]. [`SystemResolver`] asks the
+//! operating system; [`CachingResolver`] wraps any resolver and remembers
+//! answers for a fixed TTL, so a collector polling the same few APIs
+//! doesn't pay for a lookup per request.
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use vaya_cache::Cache;
+
+use crate::{CollectError, CollectResult};
+
+/// Resolves host names to socket addresses
+pub trait Resolver: fmt::Debug + Send + Sync {
+    /// Addresses for `host`, in the order they should be tried
+    ///
+    /// Returns [`CollectError::DnsResolution`] if the host has none.
+    fn resolve(&self, host: &str, port: u16) -> CollectResult<Vec<SocketAddr>>;
+}
+
+/// Resolver backed by the operating system (`getaddrinfo`)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemResolver;
+
+impl Resolver for SystemResolver {
+    fn resolve(&self, host: &str, port: u16) -> CollectResult<Vec<SocketAddr>> {
+        // Bracketed IPv6 literals come straight from URLs
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![SocketAddr::new(ip, port)]);
+        }
+        let addrs: Vec<SocketAddr> = (host, port)
+            .to_socket_addrs()
+            .map_err(|_| CollectError::DnsResolution(host.to_string()))?
+            .collect();
+        if addrs.is_empty() {
+            return Err(CollectError::DnsResolution(host.to_string()));
+        }
+        Ok(addrs)
+    }
+}
+
+/// Caches another resolver's answers for a fixed TTL
+///
+/// Concurrent lookups of the same host share one query. Failures are not
+/// cached.
+pub struct CachingResolver {
+    inner: Arc<dyn Resolver>,
+    ttl: Duration,
+    cache: Cache<(String, u16), Vec<SocketAddr>>,
+}
+
+impl CachingResolver {
+    /// Default time answers are kept
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+    /// Default number of hosts remembered
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Cache the system resolver's answers
+    pub fn new() -> Self {
+        Self::wrap(Arc::new(SystemResolver))
+    }
+
+    /// Cache `inner`'s answers
+    pub fn wrap(inner: Arc<dyn Resolver>) -> Self {
+        Self {
+            inner,
+            ttl: Self::DEFAULT_TTL,
+            cache: Cache::new(Self::DEFAULT_CAPACITY, 4),
+        }
+    }
+
+    /// Set how long answers are kept
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set how many hosts are remembered
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.cache = Cache::new(capacity, 4);
+        self
+    }
+
+    /// Forget every cached answer
+    pub fn clear(&self) {
+        self.cache.clear();
+    }
+
+    /// Number of cached answers
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Check if nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+impl Default for CachingResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CachingResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachingResolver")
+            .field("inner", &self.inner)
+            .field("ttl", &self.ttl)
+            .field("cached", &self.cache.len())
+            .finish()
+    }
+}
+
+impl Resolver for CachingResolver {
+    fn resolve(&self, host: &str, port: u16) -> CollectResult<Vec<SocketAddr>> {
+        let key = (host.to_ascii_lowercase(), port);
+        self.cache
+            .get_or_try_insert_with(key, Some(self.ttl), || self.inner.resolve(host, port))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every lookup with 10.0.0.1 and counts the queries
+    #[derive(Debug, Default)]
+    struct Counting(AtomicUsize);
+
+    impl Resolver for Counting {
+        fn resolve(&self, host: &str, port: u16) -> CollectResult<Vec<SocketAddr>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            if host == "missing.test" {
+                return Err(CollectError::DnsResolution(host.into()));
+            }
+            Ok(vec![SocketAddr::from(([10, 0, 0, 1], port))])
+        }
+    }
+
+    #[test]
+    fn test_system_resolver_literals() {
+        let resolver = SystemResolver;
+        assert_eq!(
+            resolver.resolve("127.0.0.1", 80).unwrap(),
+            [SocketAddr::from(([127, 0, 0, 1], 80))]
+        );
+        assert_eq!(
+            resolver.resolve("[::1]", 443).unwrap(),
+            ["[::1]:443".parse::<SocketAddr>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_caching_resolver() {
+        let inner = Arc::new(Counting::default());
+        let resolver = CachingResolver::wrap(inner.clone());
+
+        for _ in 0..3 {
+            let addrs = resolver.resolve("API.example.com", 443).unwrap();
+            assert_eq!(addrs, [SocketAddr::from(([10, 0, 0, 1], 443))]);
+            resolver.resolve("api.example.com", 443).unwrap();
+        }
+        assert_eq!(inner.0.load(Ordering::SeqCst), 1);
+
+        // Ports are cached separately, failures not at all
+        resolver.resolve("api.example.com", 80).unwrap();
+        assert!(resolver.resolve("missing.test", 80).is_err());
+        assert!(resolver.resolve("missing.test", 80).is_err());
+        assert_eq!(inner.0.load(Ordering::SeqCst), 4);
+        assert_eq!(resolver.len(), 2);
+
+        resolver.clear();
+        assert!(resolver.is_empty());
+    }
+
+    #[test]
+    fn test_cached_answers_expire() {
+        let inner = Arc::new(Counting::default());
+        let resolver = CachingResolver::wrap(inner.clone()).ttl(Duration::from_millis(20));
+        resolver.resolve("api.example.com", 443).unwrap();
+        std::thread::sleep(Duration::from_millis(40));
+        resolver.resolve("api.example.com", 443).unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,277 @@
+//! Downloading response bodies to files
+//!
+//! Reference data dumps and model artifacts are too large to buffer, so
+//! [`ResponseStream::download_to`] streams them straight to disk. The body
+//! is written to a `.part` file beside the destination and renamed into
+//! place only once the size limit and checksum have passed, so readers
+//! never see a truncated or corrupt file.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use ring::digest;
+
+use crate::stream::ResponseStream;
+use crate::{CollectError, CollectResult};
+
+/// Expected digest of a download, as hex
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checksum {
+    /// SHA-256
+    Sha256(String),
+    /// SHA-512
+    Sha512(String),
+}
+
+impl Checksum {
+    /// Expect the SHA-256 digest `hex`
+    pub fn sha256(hex: impl Into<String>) -> Self {
+        Checksum::Sha256(hex.into())
+    }
+
+    /// Expect the SHA-512 digest `hex`
+    pub fn sha512(hex: impl Into<String>) -> Self {
+        Checksum::Sha512(hex.into())
+    }
+
+    /// Parse `sha256:<hex>` or `sha512:<hex>`, as found in manifests
+    pub fn parse(checksum: &str) -> CollectResult<Self> {
+        match checksum.trim().split_once(':') {
+            Some((algorithm, hex)) if algorithm.eq_ignore_ascii_case("sha256") => {
+                Ok(Self::sha256(hex))
+            }
+            Some((algorithm, hex)) if algorithm.eq_ignore_ascii_case("sha512") => {
+                Ok(Self::sha512(hex))
+            }
+            _ => Err(CollectError::ParseError(format!(
+                "Unsupported checksum: {}",
+                checksum
+            ))),
+        }
+    }
+
+    fn algorithm(&self) -> &'static digest::Algorithm {
+        match self {
+            Checksum::Sha256(_) => &digest::SHA256,
+            Checksum::Sha512(_) => &digest::SHA512,
+        }
+    }
+
+    fn expected(&self) -> &str {
+        match self {
+            Checksum::Sha256(hex) | Checksum::Sha512(hex) => hex,
+        }
+    }
+}
+
+/// Limits and checks for a download
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    /// Maximum body size in bytes
+    pub max_size: Option<u64>,
+    /// Digest the body must match
+    pub checksum: Option<Checksum>,
+}
+
+impl DownloadOptions {
+    /// No size limit, no checksum
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum body size
+    pub fn max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Require the body to match `checksum`
+    pub fn checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+}
+
+/// A completed download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Download {
+    /// Where the file was written
+    pub path: PathBuf,
+    /// Size in bytes
+    pub size: u64,
+    /// Hex digest of the content: SHA-256, or the checksum's algorithm
+    pub digest: String,
+}
+
+impl ResponseStream {
+    /// Write the body to `path`, replacing any existing file
+    ///
+    /// Fails with [`CollectError::HttpError`] on a non-2xx status,
+    /// [`CollectError::BodyTooLarge`] past `max_size` and
+    /// [`CollectError::ChecksumMismatch`] if the digest differs; the
+    /// destination is left untouched in every case.
+    pub fn download_to(
+        self,
+        path: impl AsRef<Path>,
+        options: &DownloadOptions,
+    ) -> CollectResult<Download> {
+        if !self.is_success() {
+            return Err(CollectError::HttpError(self.status, self.reason));
+        }
+        let stream = match options.max_size {
+            Some(max) => self.limit(max),
+            None => self,
+        };
+
+        let path = path.as_ref();
+        let part = part_path(path);
+        let written = write_part(stream, &part, options);
+        let (size, digest) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = fs::remove_file(&part);
+                return Err(e);
+            }
+        };
+        fs::rename(&part, path)?;
+
+        Ok(Download {
+            path: path.to_path_buf(),
+            size,
+            digest,
+        })
+    }
+}
+
+/// Stream the body into `part`, returning its size and verified digest
+fn write_part(
+    stream: ResponseStream,
+    part: &Path,
+    options: &DownloadOptions,
+) -> CollectResult<(u64, String)> {
+    let algorithm = options
+        .checksum
+        .as_ref()
+        .map_or(&digest::SHA256, Checksum::algorithm);
+    let mut context = digest::Context::new(algorithm);
+    let mut file = File::create(part)?;
+
+    let size = stream.for_each_chunk(|chunk| {
+        context.update(chunk);
+        file.write_all(chunk)?;
+        Ok(())
+    })?;
+    file.sync_all()?;
+
+    let digest = hex(context.finish().as_ref());
+    if let Some(checksum) = &options.checksum {
+        if !checksum.expected().eq_ignore_ascii_case(&digest) {
+            return Err(CollectError::ChecksumMismatch(
+                checksum.expected().to_string(),
+                digest,
+            ));
+        }
+    }
+    Ok((size, digest))
+}
+
+/// `<path>.part`
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::Method;
+    use std::io::Cursor;
+
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    fn response(body: &str) -> ResponseStream {
+        let data = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        ResponseStream::open(Box::new(Cursor::new(data.into_bytes())), Method::Get).unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("vaya-collect-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_checksum_parse() {
+        assert_eq!(
+            Checksum::parse("SHA256:abcd").unwrap(),
+            Checksum::sha256("abcd")
+        );
+        assert_eq!(
+            Checksum::parse("sha512:ef01").unwrap(),
+            Checksum::sha512("ef01")
+        );
+        assert!(Checksum::parse("md5:abcd").is_err());
+        assert!(Checksum::parse("abcd").is_err());
+    }
+
+    #[test]
+    fn test_download_verifies_checksum() {
+        let dir = temp_dir("checksum");
+        let path = dir.join("rates.csv");
+
+        let options =
+            DownloadOptions::new().checksum(Checksum::sha256(HELLO_SHA256.to_uppercase()));
+        let download = response("hello world")
+            .download_to(&path, &options)
+            .unwrap();
+        assert_eq!(download.size, 11);
+        assert_eq!(download.digest, HELLO_SHA256);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello world");
+
+        // A mismatch leaves the previous file in place and no partial file
+        let options = DownloadOptions::new().checksum(Checksum::sha256(HELLO_SHA256));
+        let err = response("tampered")
+            .download_to(&path, &options)
+            .unwrap_err();
+        assert!(matches!(err, CollectError::ChecksumMismatch(..)));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello world");
+        assert!(!part_path(&path).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_download_size_limit() {
+        let dir = temp_dir("limit");
+        let path = dir.join("model.bin");
+
+        let options = DownloadOptions::new().max_size(4);
+        let err = response("hello world")
+            .download_to(&path, &options)
+            .unwrap_err();
+        assert!(matches!(err, CollectError::BodyTooLarge(4)));
+        assert!(!path.exists());
+        assert!(!part_path(&path).exists());
+
+        let data = b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec();
+        let missing = ResponseStream::open(Box::new(Cursor::new(data)), Method::Get).unwrap();
+        assert!(matches!(
+            missing.download_to(&path, &DownloadOptions::new()),
+            Err(CollectError::HttpError(404, _))
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -17,6 +17,8 @@ pub enum CollectError {
     ConnectionFailed(String),
     /// TLS handshake failed
     TlsError(String),
+    /// Proxy refused or failed the connection
+    Proxy(String),
     /// Request timeout
     Timeout,
     /// Invalid URL
@@ -35,6 +37,10 @@ pub enum CollectError {
     PoolExhausted,
     /// Parse error
     ParseError(String),
+    /// Response body larger than the allowed size (bytes)
+    BodyTooLarge(u64),
+    /// Downloaded content did not match its checksum (expected, actual)
+    ChecksumMismatch(String, String),
 }
 
 impl fmt::Display for CollectError {
@@ -44,6 +50,7 @@ impl fmt::Display for CollectError {
             CollectError::DnsResolution(host) => write!(f, "DNS resolution failed for: {}", host),
             CollectError::ConnectionFailed(msg) => write!(f, "Connection failed: {}", msg),
             CollectError::TlsError(msg) => write!(f, "TLS error: {}", msg),
+            CollectError::Proxy(msg) => write!(f, "Proxy error: {}", msg),
             CollectError::Timeout => write!(f, "Request timeout"),
             CollectError::InvalidUrl(url) => write!(f, "Invalid URL: {}", url),
             CollectError::InvalidResponse(msg) => write!(f, "Invalid response: {}", msg),
@@ -55,6 +62,16 @@ impl fmt::Display for CollectError {
             CollectError::Cancelled => write!(f, "Request cancelled"),
             CollectError::PoolExhausted => write!(f, "Connection pool exhausted"),
             CollectError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            CollectError::BodyTooLarge(limit) => {
+                write!(f, "Response body exceeds {} bytes", limit)
+            }
+            CollectError::ChecksumMismatch(expected, actual) => {
+                write!(
+                    f,
+                    "Checksum mismatch: expected {}, got {}",
+                    expected, actual
+                )
+            }
         }
     }
 }
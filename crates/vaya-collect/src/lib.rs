@@ -6,6 +6,9 @@
 //! - Rate limiting per host
 //! - Circuit breaker for failing services
 //! - Response caching with TTL
+//! - Streaming response bodies and checksummed downloads to file
+//! - HTTP and SOCKS5 proxies, including `HTTP(S)_PROXY` from the environment
+//! - Pluggable DNS resolution, with a caching resolver
 //! - URL parsing and encoding
 //!
 //! # Example
@@ -27,16 +30,24 @@
 
 pub mod client;
 pub mod collector;
+pub mod dns;
+pub mod download;
 pub mod error;
+pub mod proxy;
 pub mod request;
 pub mod response;
 pub mod retry;
+pub mod stream;
 pub mod url;
 
 pub use client::{Client, ClientConfig2 as ClientConfig};
 pub use collector::{Collector, CollectorBuilder, CollectorConfig};
+pub use dns::{CachingResolver, Resolver, SystemResolver};
+pub use download::{Checksum, Download, DownloadOptions};
 pub use error::{CollectError, CollectResult};
+pub use proxy::{Proxy, ProxyConfig, ProxyKind};
 pub use request::{Headers, Method, Request, RequestBuilder};
 pub use response::Response;
 pub use retry::{CircuitBreaker, CircuitStatus, RateLimiter, RetryStrategy};
+pub use stream::{Chunks, ResponseStream};
 pub use url::{Scheme, Url};
@@ -205,12 +205,31 @@ impl Request {
 
     /// Build the HTTP request bytes
     pub fn build(&self) -> Vec<u8> {
+        self.encode(&self.url.request_path(), None)
+    }
+
+    /// Build the request for an HTTP proxy: the target in absolute form,
+    /// plus the proxy's credentials
+    pub(crate) fn build_for_proxy(&self, proxy_authorization: Option<&str>) -> Vec<u8> {
+        let target = format!(
+            "{}://{}{}",
+            self.url.scheme.as_str(),
+            self.url.host_port(),
+            self.url.request_path()
+        );
+        self.encode(&target, proxy_authorization)
+    }
+
+    fn encode(&self, target: &str, proxy_authorization: Option<&str>) -> Vec<u8> {
         let mut request = format!(
             "{} {} HTTP/1.1\r\nHost: {}\r\n",
             self.method.as_str(),
-            self.url.request_path(),
+            target,
             self.url.host_port()
         );
+        if let Some(authorization) = proxy_authorization {
+            request.push_str(&format!("Proxy-Authorization: {}\r\n", authorization));
+        }
 
         // Add headers
         for (name, value) in self.headers.iter() {
@@ -244,7 +263,7 @@ impl Request {
 }
 
 /// Simple base64 encoder (no padding, standard alphabet)
-fn base64_encode(data: &[u8]) -> String {
+pub(crate) fn base64_encode(data: &[u8]) -> String {
     const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
     let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
@@ -77,23 +77,7 @@ impl Response {
         let header_str = std::str::from_utf8(header_bytes)
             .map_err(|_| CollectError::InvalidResponse("Invalid UTF-8 in headers".into()))?;
 
-        let mut lines = header_str.lines();
-        let status_line = lines
-            .next()
-            .ok_or_else(|| CollectError::InvalidResponse("Empty response".into()))?;
-
-        let (status, reason) = parse_status_line(status_line)?;
-
-        // Parse headers
-        let mut headers = ResponseHeaders::new();
-        for line in lines {
-            if line.is_empty() {
-                break;
-            }
-            if let Some((name, value)) = parse_header_line(line) {
-                headers.add(name, value);
-            }
-        }
+        let (status, reason, headers) = parse_head(header_str)?;
 
         Ok(Response {
             status,
@@ -156,6 +140,29 @@ impl Response {
     }
 }
 
+/// Parse a response head (status line and headers, without the blank line)
+pub(crate) fn parse_head(head: &str) -> CollectResult<(u16, String, ResponseHeaders)> {
+    let mut lines = head.lines();
+    let status_line = lines
+        .next()
+        .ok_or_else(|| CollectError::InvalidResponse("Empty response".into()))?;
+
+    let (status, reason) = parse_status_line(status_line)?;
+
+    // Parse headers
+    let mut headers = ResponseHeaders::new();
+    for line in lines {
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = parse_header_line(line) {
+            headers.add(name, value);
+        }
+    }
+
+    Ok((status, reason, headers))
+}
+
 /// Find the end of headers (position before \r\n\r\n)
 fn find_header_end(data: &[u8]) -> Option<usize> {
     (0..data.len().saturating_sub(3)).find(|&i| &data[i..i + 4] == b"\r\n\r\n")
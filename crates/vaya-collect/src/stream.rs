@@ -0,0 +1,446 @@
+//! Streaming response bodies
+//!
+//! [`Client::execute_stream`](crate::Client::execute_stream) returns as soon
+//! as the response head has arrived. The body is then read off the
+//! connection as the caller consumes it, through [`Read`],
+//! [`ResponseStream::chunks`] or [`ResponseStream::for_each_chunk`], so a
+//! large payload never has to fit in memory. `Content-Length`, chunked and
+//! read-until-close bodies are all decoded here.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+
+use crate::request::Method;
+use crate::response::{parse_head, Response, ResponseHeaders};
+use crate::{CollectError, CollectResult};
+
+/// An open connection, positioned at the start of the response
+pub(crate) type Connection = Box<dyn Read + Send>;
+
+/// Size of the chunks yielded by [`ResponseStream::chunks`]
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest response head accepted
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// Longest chunk-size or trailer line accepted
+const MAX_LINE: u64 = 4096;
+
+/// How the end of the body is found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// `Content-Length` bytes left
+    Length(u64),
+    /// Chunked: bytes left in the current chunk, `None` before a size line
+    Chunked(Option<u64>),
+    /// Until the server closes the connection
+    UntilClose,
+    /// Nothing left
+    Done,
+}
+
+/// A response whose body is still on the wire
+pub struct ResponseStream {
+    /// HTTP status code
+    pub status: u16,
+    /// Status reason phrase
+    pub reason: String,
+    /// Response headers
+    pub headers: ResponseHeaders,
+    reader: BufReader<Connection>,
+    framing: Framing,
+    received: u64,
+    limit: Option<u64>,
+}
+
+impl ResponseStream {
+    /// Read the response head for a `method` request from `conn`
+    ///
+    /// Interim `1xx` responses are skipped.
+    pub(crate) fn open(conn: Connection, method: Method) -> CollectResult<Self> {
+        let mut reader = BufReader::new(conn);
+        loop {
+            let head = read_head(&mut reader)?;
+            let (status, reason, headers) = parse_head(&head)?;
+            if (100..200).contains(&status) && status != 101 {
+                continue;
+            }
+
+            let chunked = headers
+                .get("transfer-encoding")
+                .and_then(|te| te.rsplit(',').next())
+                .is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked"));
+            let framing = if method == Method::Head || matches!(status, 101 | 204 | 304) {
+                Framing::Done
+            } else if chunked {
+                Framing::Chunked(None)
+            } else if let Some(length) = headers.get("content-length") {
+                let length = length.parse().map_err(|_| {
+                    CollectError::InvalidResponse(format!("Invalid Content-Length: {}", length))
+                })?;
+                Framing::Length(length)
+            } else {
+                Framing::UntilClose
+            };
+
+            return Ok(Self {
+                status,
+                reason,
+                headers,
+                reader,
+                framing,
+                received: 0,
+                limit: None,
+            });
+        }
+    }
+
+    /// Fail with [`CollectError::BodyTooLarge`] once the body exceeds `max`
+    /// bytes; a larger `Content-Length` fails on the first read
+    pub fn limit(mut self, max: u64) -> Self {
+        self.limit = Some(max);
+        self
+    }
+
+    /// Check if response is successful (2xx)
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Declared content length, if any
+    pub fn content_length(&self) -> Option<u64> {
+        self.headers
+            .get("content-length")
+            .and_then(|s| s.parse().ok())
+    }
+
+    /// Body bytes read so far
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// Iterate over the body in chunks of up to [`CHUNK_SIZE`] bytes
+    pub fn chunks(self) -> Chunks {
+        Chunks {
+            stream: self,
+            finished: false,
+        }
+    }
+
+    /// Call `f` with each piece of the body as it arrives, returning the
+    /// body size
+    ///
+    /// Stops at the first error, from the connection or from `f`.
+    pub fn for_each_chunk<F>(mut self, mut f: F) -> CollectResult<u64>
+    where
+        F: FnMut(&[u8]) -> CollectResult<()>,
+    {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = self.read_body(&mut buf)?;
+            if n == 0 {
+                return Ok(self.received);
+            }
+            f(&buf[..n])?;
+        }
+    }
+
+    /// Read the rest of the body into a buffered [`Response`]
+    pub fn into_response(mut self) -> CollectResult<Response> {
+        let mut body = Vec::new();
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = self.read_body(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            body.extend_from_slice(&buf[..n]);
+        }
+        Ok(Response {
+            status: self.status,
+            reason: self.reason,
+            headers: self.headers,
+            body,
+        })
+    }
+
+    /// Read decoded body bytes into `buf`; 0 at the end of the body
+    fn read_body(&mut self, buf: &mut [u8]) -> CollectResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if let (Some(limit), Framing::Length(left)) = (self.limit, self.framing) {
+            if self.received + left > limit {
+                return Err(CollectError::BodyTooLarge(limit));
+            }
+        }
+
+        let n = loop {
+            match self.framing {
+                Framing::Done => return Ok(0),
+                Framing::Length(0) => self.framing = Framing::Done,
+                Framing::Length(left) => {
+                    let n = self.read_exactly_some(buf, left)?;
+                    self.framing = Framing::Length(left - n as u64);
+                    break n;
+                }
+                Framing::Chunked(None) => {
+                    let line = self.read_line()?;
+                    let size = line.split(';').next().unwrap_or_default().trim();
+                    let size = u64::from_str_radix(size, 16).map_err(|_| {
+                        CollectError::InvalidResponse(format!("Invalid chunk size: {}", size))
+                    })?;
+                    if size == 0 {
+                        // Trailers, up to the blank line
+                        while !self.read_line()?.is_empty() {}
+                        self.framing = Framing::Done;
+                    } else {
+                        self.framing = Framing::Chunked(Some(size));
+                    }
+                }
+                Framing::Chunked(Some(0)) => {
+                    if !self.read_line()?.is_empty() {
+                        return Err(CollectError::InvalidResponse(
+                            "Missing CRLF after chunk".into(),
+                        ));
+                    }
+                    self.framing = Framing::Chunked(None);
+                }
+                Framing::Chunked(Some(left)) => {
+                    let n = self.read_exactly_some(buf, left)?;
+                    self.framing = Framing::Chunked(Some(left - n as u64));
+                    break n;
+                }
+                Framing::UntilClose => match self.reader.read(buf) {
+                    Ok(0) => self.framing = Framing::Done,
+                    Ok(n) => break n,
+                    // TLS peers that close without close_notify
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                        self.framing = Framing::Done
+                    }
+                    Err(e) => return Err(CollectError::Io(e)),
+                },
+            }
+        };
+
+        self.received += n as u64;
+        match self.limit {
+            Some(limit) if self.received > limit => Err(CollectError::BodyTooLarge(limit)),
+            _ => Ok(n),
+        }
+    }
+
+    /// Read between 1 and `left` bytes, failing if the connection closes
+    fn read_exactly_some(&mut self, buf: &mut [u8], left: u64) -> CollectResult<usize> {
+        let max = buf.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+        match self.reader.read(&mut buf[..max])? {
+            0 => Err(CollectError::InvalidResponse(
+                "Connection closed before end of body".into(),
+            )),
+            n => Ok(n),
+        }
+    }
+
+    /// Read one CRLF-terminated line, without the terminator
+    fn read_line(&mut self) -> CollectResult<String> {
+        let mut line = Vec::new();
+        (&mut self.reader)
+            .take(MAX_LINE)
+            .read_until(b'\n', &mut line)?;
+        if !line.ends_with(b"\n") {
+            return Err(CollectError::InvalidResponse(
+                "Truncated or oversized chunk line".into(),
+            ));
+        }
+        let line = String::from_utf8_lossy(&line);
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+/// Read a response head, up to and excluding the blank line
+fn read_head(reader: &mut BufReader<Connection>) -> CollectResult<String> {
+    let mut head = Vec::new();
+    loop {
+        let start = head.len();
+        let budget = (MAX_HEAD_SIZE - start) as u64;
+        let n = reader.take(budget).read_until(b'\n', &mut head)?;
+        if n == 0 {
+            return Err(CollectError::InvalidResponse(
+                "Connection closed before response head".into(),
+            ));
+        }
+        if !head.ends_with(b"\n") {
+            return Err(CollectError::InvalidResponse(
+                "Response head too large".into(),
+            ));
+        }
+        if head[start..] == *b"\r\n" || head[start..] == *b"\n" {
+            head.truncate(start);
+            return String::from_utf8(head)
+                .map_err(|_| CollectError::InvalidResponse("Invalid UTF-8 in headers".into()));
+        }
+    }
+}
+
+impl Read for ResponseStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_body(buf).map_err(|e| match e {
+            CollectError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        })
+    }
+}
+
+impl fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseStream")
+            .field("status", &self.status)
+            .field("reason", &self.reason)
+            .field("headers", &self.headers)
+            .field("received", &self.received)
+            .finish()
+    }
+}
+
+/// Iterator over a response body, from [`ResponseStream::chunks`]
+///
+/// Yields each error once, then ends.
+#[derive(Debug)]
+pub struct Chunks {
+    stream: ResponseStream,
+    finished: bool,
+}
+
+impl Chunks {
+    /// The response being read
+    pub fn stream(&self) -> &ResponseStream {
+        &self.stream
+    }
+}
+
+impl Iterator for Chunks {
+    type Item = CollectResult<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        match self.stream.read_body(&mut buf) {
+            Ok(0) => {
+                self.finished = true;
+                None
+            }
+            Ok(n) => {
+                buf.truncate(n);
+                Some(Ok(buf))
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stream over canned bytes, delivered `step` bytes per read
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.step).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+    }
+
+    fn open(data: &[u8], method: Method) -> CollectResult<ResponseStream> {
+        let conn = Trickle {
+            data: data.to_vec(),
+            pos: 0,
+            step: 3,
+        };
+        ResponseStream::open(Box::new(conn), method)
+    }
+
+    fn body(data: &[u8]) -> CollectResult<Vec<u8>> {
+        open(data, Method::Get)?.into_response().map(|r| r.body)
+    }
+
+    #[test]
+    fn test_content_length() {
+        let data = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloEXTRA";
+        assert_eq!(body(data).unwrap(), b"hello");
+
+        let truncated = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nhello";
+        assert!(matches!(
+            body(truncated),
+            Err(CollectError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_chunked() {
+        let data = b"HTTP/1.1 100 Continue\r\n\r\n\
+            HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        let stream = open(data, Method::Get).unwrap();
+        assert_eq!(stream.status, 200);
+        let chunks: Vec<Vec<u8>> = stream.chunks().map(Result::unwrap).collect();
+        assert_eq!(chunks.concat(), b"hello, world");
+
+        let bad = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+        assert!(body(bad).is_err());
+    }
+
+    #[test]
+    fn test_until_close_and_empty_bodies() {
+        let data = b"HTTP/1.0 200 OK\r\n\r\nstreamed until close";
+        assert_eq!(body(data).unwrap(), b"streamed until close");
+
+        let head = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n";
+        let stream = open(head, Method::Head).unwrap();
+        assert_eq!(stream.content_length(), Some(5));
+        assert!(stream.into_response().unwrap().body.is_empty());
+        assert!(body(b"HTTP/1.1 304 Not Modified\r\n\r\n")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_limit() {
+        let declared = b"HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\nhello world";
+        let stream = open(declared, Method::Get).unwrap().limit(10);
+        assert!(matches!(
+            stream.into_response(),
+            Err(CollectError::BodyTooLarge(10))
+        ));
+
+        // Undeclared sizes fail once the limit is crossed
+        let streamed = b"HTTP/1.1 200 OK\r\n\r\nhello world";
+        let mut seen = 0;
+        let result = open(streamed, Method::Get)
+            .unwrap()
+            .limit(10)
+            .for_each_chunk(|chunk| {
+                seen += chunk.len();
+                Ok(())
+            });
+        assert!(matches!(result, Err(CollectError::BodyTooLarge(10))));
+        assert!(seen <= 10);
+
+        let mut reader = open(declared, Method::Get).unwrap().limit(11);
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello world");
+    }
+}
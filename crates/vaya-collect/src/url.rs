@@ -84,7 +84,10 @@ impl Url {
     }
 
     /// Parse authority (host:port)
-    fn parse_authority(authority: &str, default_port: u16) -> CollectResult<(String, u16)> {
+    pub(crate) fn parse_authority(
+        authority: &str,
+        default_port: u16,
+    ) -> CollectResult<(String, u16)> {
         if authority.is_empty() {
             return Err(CollectError::InvalidUrl("Empty host".into()));
         }
@@ -0,0 +1,680 @@
+//! In-process publish/subscribe for domain events
+//!
+//! Services publish typed payloads to a [`Topic`] on the [`EventBus`]
+//! instead of calling each interested subsystem directly. A topic is a
+//! name plus a payload type, so a subscriber always receives the type the
+//! publisher sent.
+//!
+//! Subscribers come in two kinds:
+//!
+//! - Inline subscribers ([`EventBus::subscribe`]) run on the publisher's
+//!   thread and must be quick (counters, the event log). A panicking
+//!   subscriber is contained and counted.
+//! - Buffered subscribers ([`EventBus::subscribe_buffered`]) get a
+//!   [`Receiver`] with a bounded queue, read with blocking or `async`
+//!   calls from their own task. When the queue is full the
+//!   [`OverflowPolicy`] decides which event is dropped, so a slow consumer
+//!   never blocks a publisher.
+//!
+//! The [`global`] bus forwards every built-in domain event to the event
+//! log ([`crate::events::emit`]), so publishing through the bus is the only
+//! call a service needs to make.
+
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock, RwLock};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::events::{self, DomainEvent};
+use crate::metrics;
+
+/// Metric counting published events per topic
+pub const PUBLISHED_METRIC: &str = "vaya_bus_published_total";
+/// Metric counting events delivered per topic and subscriber
+pub const DELIVERED_METRIC: &str = "vaya_bus_delivered_total";
+/// Metric counting events dropped by full buffers
+pub const DROPPED_METRIC: &str = "vaya_bus_dropped_total";
+/// Metric counting panics in inline subscribers
+pub const PANICS_METRIC: &str = "vaya_bus_subscriber_panics_total";
+/// Gauge of events waiting in a subscriber's buffer
+pub const BUFFER_DEPTH_METRIC: &str = "vaya_bus_buffer_depth";
+
+/// A named topic carrying payloads of type `T`
+pub struct Topic<T> {
+    name: &'static str,
+    _payload: PhantomData<fn(T)>,
+}
+
+impl<T> Topic<T> {
+    /// Define a topic
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _payload: PhantomData,
+        }
+    }
+
+    /// Topic name
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<T> Clone for Topic<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Topic<T> {}
+
+impl<T> fmt::Debug for Topic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Topic({})", self.name)
+    }
+}
+
+/// Built-in topics for domain events
+pub mod topics {
+    use super::Topic;
+    use crate::events::{
+        AlertTriggered, BookingCreated, PaymentCaptured, PoolMemberJoined, PoolStatusChanged,
+        PoolWaitlistPromoted, SearchLateResults, SearchPerformed,
+    };
+
+    /// A search was executed
+    pub const SEARCH_PERFORMED: Topic<SearchPerformed> = Topic::new("search_performed");
+    /// A provider answered a search after it returned
+    pub const SEARCH_LATE_RESULTS: Topic<SearchLateResults> = Topic::new("search_late_results");
+    /// A booking was created
+    pub const BOOKING_CREATED: Topic<BookingCreated> = Topic::new("booking_created");
+    /// A payment was captured
+    pub const PAYMENT_CAPTURED: Topic<PaymentCaptured> = Topic::new("payment_captured");
+    /// A price alert fired
+    pub const ALERT_TRIGGERED: Topic<AlertTriggered> = Topic::new("alert_triggered");
+    /// A user joined a pool
+    pub const POOL_MEMBER_JOINED: Topic<PoolMemberJoined> = Topic::new("pool_member_joined");
+    /// A pool changed status
+    pub const POOL_STATUS_CHANGED: Topic<PoolStatusChanged> = Topic::new("pool_status_changed");
+    /// A waitlisted user was moved into a pool
+    pub const POOL_WAITLIST_PROMOTED: Topic<PoolWaitlistPromoted> =
+        Topic::new("pool_waitlist_promoted");
+}
+
+/// What a full buffer does with a new event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Keep the buffered events and drop the new one
+    DropNewest,
+    /// Drop the oldest buffered event to make room
+    DropOldest,
+}
+
+/// Buffered subscriber settings
+#[derive(Debug, Clone, Copy)]
+pub struct BufferConfig {
+    /// Events held before the overflow policy applies
+    pub capacity: usize,
+    /// Which event to drop when full
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for BufferConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            overflow: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+impl BufferConfig {
+    /// Set the capacity
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Set the overflow policy
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// Handle for removing a subscriber
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Handler<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+enum Delivery<T> {
+    Inline(Handler<T>),
+    Buffered(Arc<Buffer<T>>),
+}
+
+struct Subscriber<T> {
+    id: SubscriptionId,
+    name: String,
+    delivery: Delivery<T>,
+}
+
+impl<T> Subscriber<T> {
+    fn is_closed(&self) -> bool {
+        match &self.delivery {
+            Delivery::Inline(_) => false,
+            Delivery::Buffered(buffer) => buffer.is_closed(),
+        }
+    }
+}
+
+struct BufferState<T> {
+    items: VecDeque<T>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+struct Buffer<T> {
+    state: Mutex<BufferState<T>>,
+    ready: Condvar,
+    config: BufferConfig,
+}
+
+impl<T> Buffer<T> {
+    fn new(config: BufferConfig) -> Self {
+        Self {
+            state: Mutex::new(BufferState {
+                items: VecDeque::with_capacity(config.capacity.min(64)),
+                closed: false,
+                waker: None,
+            }),
+            ready: Condvar::new(),
+            config,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BufferState<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Add an event; returns whether an event was dropped and the new depth
+    fn push(&self, item: T) -> (bool, usize) {
+        let mut state = self.lock();
+        let mut dropped = false;
+        if state.items.len() >= self.config.capacity {
+            dropped = true;
+            match self.config.overflow {
+                OverflowPolicy::DropNewest => return (true, state.items.len()),
+                OverflowPolicy::DropOldest => {
+                    state.items.pop_front();
+                }
+            }
+        }
+        state.items.push_back(item);
+        let depth = state.items.len();
+        let waker = state.waker.take();
+        drop(state);
+        self.ready.notify_one();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        (dropped, depth)
+    }
+
+    fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        let waker = state.waker.take();
+        drop(state);
+        self.ready.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+}
+
+/// Receiving end of a buffered subscription
+///
+/// Dropping the receiver unsubscribes it.
+pub struct Receiver<T> {
+    id: SubscriptionId,
+    buffer: Arc<Buffer<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Subscription ID
+    pub fn id(&self) -> SubscriptionId {
+        self.id
+    }
+
+    /// Take the next event without waiting
+    pub fn try_recv(&self) -> Option<T> {
+        self.buffer.lock().items.pop_front()
+    }
+
+    /// Wait up to `timeout` for the next event
+    ///
+    /// Returns `None` on timeout or once the subscription is closed and
+    /// drained.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<T> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.buffer.lock();
+        loop {
+            if let Some(item) = state.items.pop_front() {
+                return Some(item);
+            }
+            let now = Instant::now();
+            if state.closed || now >= deadline {
+                return None;
+            }
+            state = self
+                .buffer
+                .ready
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Wait for the next event from an async task
+    ///
+    /// Resolves to `None` once the subscription is closed and drained.
+    pub fn recv(&self) -> Recv<'_, T> {
+        Recv {
+            buffer: &self.buffer,
+        }
+    }
+
+    /// Events waiting in the buffer
+    pub fn len(&self) -> usize {
+        self.buffer.lock().items.len()
+    }
+
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.buffer.close();
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("id", &self.id)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Future returned by [`Receiver::recv`]
+pub struct Recv<'a, T> {
+    buffer: &'a Buffer<T>,
+}
+
+impl<T> Future for Recv<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut state = self.buffer.lock();
+        if let Some(item) = state.items.pop_front() {
+            return Poll::Ready(Some(item));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Subscribers of one topic, type-erased so topics of any payload type
+/// share one map
+trait TopicSubscribers: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn remove(&self, id: SubscriptionId) -> bool;
+}
+
+struct Subscribers<T> {
+    list: RwLock<Vec<Arc<Subscriber<T>>>>,
+}
+
+impl<T: Send + Sync + 'static> TopicSubscribers for Subscribers<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn remove(&self, id: SubscriptionId) -> bool {
+        let mut list = self.list.write().unwrap_or_else(|e| e.into_inner());
+        let Some(index) = list.iter().position(|s| s.id == id) else {
+            return false;
+        };
+        let removed = list.remove(index);
+        if let Delivery::Buffered(buffer) = &removed.delivery {
+            buffer.close();
+        }
+        true
+    }
+}
+
+type TopicKey = (&'static str, TypeId);
+
+/// Typed publish/subscribe bus
+#[derive(Default)]
+pub struct EventBus {
+    topics: RwLock<HashMap<TopicKey, Arc<dyn TopicSubscribers>>>,
+    next_id: AtomicU64,
+}
+
+impl EventBus {
+    /// Create a bus with no subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a bus that forwards built-in domain events to the event log
+    pub fn with_event_log() -> Self {
+        let bus = Self::new();
+        bus.log_events(&topics::SEARCH_PERFORMED);
+        bus.log_events(&topics::BOOKING_CREATED);
+        bus.log_events(&topics::PAYMENT_CAPTURED);
+        bus.log_events(&topics::ALERT_TRIGGERED);
+        bus.log_events(&topics::POOL_MEMBER_JOINED);
+        bus.log_events(&topics::POOL_STATUS_CHANGED);
+        bus.log_events(&topics::POOL_WAITLIST_PROMOTED);
+        bus
+    }
+
+    /// Forward a topic's domain events to the event log
+    pub fn log_events<E: DomainEvent + Send + Sync + 'static>(
+        &self,
+        topic: &Topic<E>,
+    ) -> SubscriptionId {
+        self.subscribe(topic, "event_log", |event: &E| events::emit(event))
+    }
+
+    /// Call `handler` on the publisher's thread for every event
+    pub fn subscribe<T: Send + Sync + 'static>(
+        &self,
+        topic: &Topic<T>,
+        name: &str,
+        handler: impl Fn(&T) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        self.add(topic, name, Delivery::Inline(Box::new(handler)))
+    }
+
+    /// Queue events for a [`Receiver`] read by the subscriber's own task
+    pub fn subscribe_buffered<T: Send + Sync + 'static>(
+        &self,
+        topic: &Topic<T>,
+        name: &str,
+        config: BufferConfig,
+    ) -> Receiver<T> {
+        let buffer = Arc::new(Buffer::new(config));
+        let id = self.add(topic, name, Delivery::Buffered(buffer.clone()));
+        Receiver { id, buffer }
+    }
+
+    /// Remove a subscriber; buffered receivers see the subscription close
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let topics: Vec<_> = self
+            .topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        topics.iter().any(|t| t.remove(id))
+    }
+
+    /// Number of live subscribers on a topic
+    pub fn subscriber_count<T: Send + Sync + 'static>(&self, topic: &Topic<T>) -> usize {
+        self.subscribers(topic)
+            .iter()
+            .filter(|s| !s.is_closed())
+            .count()
+    }
+
+    /// Publish an event to every subscriber of its topic
+    ///
+    /// Returns the number of subscribers that received it.
+    pub fn publish<T: Clone + Send + Sync + 'static>(&self, topic: &Topic<T>, payload: T) -> usize {
+        metrics::global()
+            .counter(PUBLISHED_METRIC, &[("topic", topic.name)])
+            .inc();
+
+        let subscribers = self.subscribers(topic);
+        let mut delivered = 0;
+        let mut closed = false;
+        for subscriber in &subscribers {
+            let labels = [
+                ("topic", topic.name),
+                ("subscriber", subscriber.name.as_str()),
+            ];
+            match &subscriber.delivery {
+                Delivery::Inline(handler) => {
+                    if catch_unwind(AssertUnwindSafe(|| handler(&payload))).is_err() {
+                        tracing::error!(
+                            topic = topic.name,
+                            subscriber = %subscriber.name,
+                            "Event subscriber panicked"
+                        );
+                        metrics::global().counter(PANICS_METRIC, &labels).inc();
+                        continue;
+                    }
+                }
+                Delivery::Buffered(buffer) => {
+                    if buffer.is_closed() {
+                        closed = true;
+                        continue;
+                    }
+                    let (dropped, depth) = buffer.push(payload.clone());
+                    metrics::global()
+                        .gauge(BUFFER_DEPTH_METRIC, &labels)
+                        .set(depth as f64);
+                    if dropped {
+                        metrics::global().counter(DROPPED_METRIC, &labels).inc();
+                        if buffer.config.overflow == OverflowPolicy::DropNewest {
+                            continue;
+                        }
+                    }
+                }
+            }
+            metrics::global().counter(DELIVERED_METRIC, &labels).inc();
+            delivered += 1;
+        }
+
+        if closed {
+            self.prune(topic);
+        }
+        delivered
+    }
+
+    fn add<T: Send + Sync + 'static>(
+        &self,
+        topic: &Topic<T>,
+        name: &str,
+        delivery: Delivery<T>,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let subscriber = Arc::new(Subscriber {
+            id,
+            name: name.to_string(),
+            delivery,
+        });
+        let entry = self
+            .topics
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry((topic.name, TypeId::of::<T>()))
+            .or_insert_with(|| {
+                Arc::new(Subscribers::<T> {
+                    list: RwLock::new(Vec::new()),
+                })
+            })
+            .clone();
+        if let Some(subscribers) = entry.as_any().downcast_ref::<Subscribers<T>>() {
+            subscribers
+                .list
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(subscriber);
+        }
+        id
+    }
+
+    fn subscribers<T: Send + Sync + 'static>(&self, topic: &Topic<T>) -> Vec<Arc<Subscriber<T>>> {
+        let entry = self
+            .topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(topic.name, TypeId::of::<T>()))
+            .cloned();
+        entry
+            .as_ref()
+            .and_then(|e| e.as_any().downcast_ref::<Subscribers<T>>())
+            .map(|s| s.list.read().unwrap_or_else(|e| e.into_inner()).clone())
+            .unwrap_or_default()
+    }
+
+    fn prune<T: Send + Sync + 'static>(&self, topic: &Topic<T>) {
+        let entry = self
+            .topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(topic.name, TypeId::of::<T>()))
+            .cloned();
+        if let Some(subscribers) = entry
+            .as_ref()
+            .and_then(|e| e.as_any().downcast_ref::<Subscribers<T>>())
+        {
+            subscribers
+                .list
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .retain(|s| !s.is_closed());
+        }
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let topics: Vec<&str> = self
+            .topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .map(|(name, _)| *name)
+            .collect();
+        f.debug_struct("EventBus").field("topics", &topics).finish()
+    }
+}
+
+/// Process-wide bus, logging built-in domain events
+pub fn global() -> &'static EventBus {
+    static BUS: OnceLock<EventBus> = OnceLock::new();
+    BUS.get_or_init(EventBus::with_event_log)
+}
+
+/// Publish an event on the global bus
+pub fn publish<T: Clone + Send + Sync + 'static>(topic: &Topic<T>, payload: T) -> usize {
+    global().publish(topic, payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::task::Wake;
+
+    const NUMBERS: Topic<u32> = Topic::new("numbers");
+
+    #[test]
+    fn test_inline_and_buffered_delivery() {
+        let bus = EventBus::new();
+        let seen = Arc::new(AtomicUsize::new(0));
+        let counter = seen.clone();
+        let id = bus.subscribe(&NUMBERS, "sum", move |n: &u32| {
+            counter.fetch_add(*n as usize, Ordering::SeqCst);
+        });
+        bus.subscribe(&NUMBERS, "broken", |_: &u32| panic!("subscriber bug"));
+        let rx = bus.subscribe_buffered(&NUMBERS, "queue", BufferConfig::default());
+
+        assert_eq!(bus.publish(&NUMBERS, 2), 2);
+        assert_eq!(bus.publish(&NUMBERS, 3), 2);
+        assert_eq!(seen.load(Ordering::SeqCst), 5);
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)), Some(3));
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)), None);
+
+        // Same name, different payload type: a separate topic
+        let other: Topic<String> = Topic::new("numbers");
+        assert_eq!(bus.publish(&other, "x".into()), 0);
+
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        assert_eq!(bus.subscriber_count(&NUMBERS), 2);
+        drop(rx);
+        assert_eq!(bus.subscriber_count(&NUMBERS), 1);
+        bus.publish(&NUMBERS, 1);
+        assert_eq!(seen.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let bus = EventBus::new();
+        let config = BufferConfig::default().with_capacity(2);
+        let oldest = bus.subscribe_buffered(&NUMBERS, "oldest", config);
+        let newest = bus.subscribe_buffered(
+            &NUMBERS,
+            "newest",
+            config.with_overflow(OverflowPolicy::DropNewest),
+        );
+        for n in 1..=4 {
+            bus.publish(&NUMBERS, n);
+        }
+        assert_eq!((oldest.try_recv(), oldest.try_recv()), (Some(3), Some(4)));
+        assert_eq!((newest.try_recv(), newest.try_recv()), (Some(1), Some(2)));
+        assert!(newest.is_empty());
+    }
+
+    #[test]
+    fn test_async_recv() {
+        struct Flag(AtomicUsize);
+        impl Wake for Flag {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let bus = EventBus::new();
+        let rx = bus.subscribe_buffered(&NUMBERS, "task", BufferConfig::default());
+        let flag = Arc::new(Flag(AtomicUsize::new(0)));
+        let waker = Waker::from(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        let mut next = rx.recv();
+        assert!(Pin::new(&mut next).poll(&mut cx).is_pending());
+        bus.publish(&NUMBERS, 7);
+        assert_eq!(flag.0.load(Ordering::SeqCst), 1);
+        assert_eq!(Pin::new(&mut next).poll(&mut cx), Poll::Ready(Some(7)));
+
+        bus.unsubscribe(rx.id());
+        assert_eq!(Pin::new(&mut rx.recv()).poll(&mut cx), Poll::Ready(None));
+    }
+}
@@ -1,6 +1,12 @@
 //! Domain enums for VAYA
 //!
 //! All enums use u8 representation for compact storage.
+//!
+//! [`BookingStatus`] and [`PoolStatus`] are the one state machine for
+//! bookings and pools: `vaya-book` and `vaya-pool` use them directly, so a
+//! stored record, a domain object and an API response always agree on a
+//! status and its allowed transitions. Discriminants are part of the
+//! stored format; new variants are only ever appended.
 
 use rkyv::{Archive, Deserialize, Serialize};
 use std::fmt;
@@ -150,9 +156,39 @@ pub enum BookingStatus {
     Completed = 6,
     /// No-show
     NoShow = 7,
+    /// Payment received, awaiting ticketing
+    PaymentReceived = 8,
+    /// Ticketing in progress
+    Ticketing = 9,
+    /// Not confirmed or paid before its deadline
+    Expired = 10,
+    /// Refund in progress
+    RefundPending = 11,
+    /// Change quoted, awaiting the traveler's decision
+    ChangeRequested = 12,
+    /// Change accepted, awaiting ticket reissue
+    ChangeConfirmed = 13,
 }
 
 impl BookingStatus {
+    /// Every booking status, in discriminant order
+    pub const ALL: [BookingStatus; 14] = [
+        Self::Pending,
+        Self::Confirmed,
+        Self::Ticketed,
+        Self::Cancelled,
+        Self::Refunded,
+        Self::Failed,
+        Self::Completed,
+        Self::NoShow,
+        Self::PaymentReceived,
+        Self::Ticketing,
+        Self::Expired,
+        Self::RefundPending,
+        Self::ChangeRequested,
+        Self::ChangeConfirmed,
+    ];
+
     /// Returns the string representation of the booking status.
     ///
     /// Used for serialization, API responses, and database storage.
@@ -166,34 +202,108 @@ impl BookingStatus {
             Self::Failed => "failed",
             Self::Completed => "completed",
             Self::NoShow => "no_show",
+            Self::PaymentReceived => "payment_received",
+            Self::Ticketing => "ticketing",
+            Self::Expired => "expired",
+            Self::RefundPending => "refund_pending",
+            Self::ChangeRequested => "change_requested",
+            Self::ChangeConfirmed => "change_confirmed",
         }
     }
 
+    /// Parses a status string, ignoring case.
+    ///
+    /// Accepts the upper-case names older `vaya-book` records were stored
+    /// with ("PAYMENT_RECEIVED") as well as [`BookingStatus::as_str`].
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+    }
+
     /// Returns true if the booking has reached a final state.
     ///
     /// Terminal statuses cannot be changed and require no further action.
-    /// Terminal: `Cancelled`, `Refunded`, `Failed`, `Completed`, `NoShow`
+    /// Terminal: `Cancelled`, `Expired`, `Refunded`, `Failed`, `Completed`, `NoShow`
     pub fn is_terminal(&self) -> bool {
         matches!(
             self,
-            Self::Cancelled | Self::Refunded | Self::Failed | Self::Completed | Self::NoShow
+            Self::Cancelled
+                | Self::Expired
+                | Self::Refunded
+                | Self::Failed
+                | Self::Completed
+                | Self::NoShow
         )
     }
 
     /// Returns true if the booking is still in progress.
     ///
     /// Active bookings require monitoring and may need user action.
-    /// Active: `Pending`, `Confirmed`, `Ticketed`
+    /// Active: every status that is not terminal
     pub fn is_active(&self) -> bool {
-        matches!(self, Self::Pending | Self::Confirmed | Self::Ticketed)
+        !self.is_terminal()
     }
 
     /// Returns true if the booking can be cancelled by the user.
     ///
     /// Cancellation is allowed before the flight departs.
-    /// Cancellable: `Pending`, `Confirmed`, `Ticketed`
+    /// Cancellable: `Pending`, `Confirmed`, `PaymentReceived`, `Ticketed`
     pub fn can_cancel(&self) -> bool {
-        matches!(self, Self::Pending | Self::Confirmed | Self::Ticketed)
+        matches!(
+            self,
+            Self::Pending | Self::Confirmed | Self::PaymentReceived | Self::Ticketed
+        )
+    }
+
+    /// Returns true if the booking can be ticketed.
+    pub fn can_ticket(&self) -> bool {
+        matches!(self, Self::PaymentReceived)
+    }
+
+    /// Returns true if the booking can receive payment.
+    pub fn can_pay(&self) -> bool {
+        matches!(self, Self::Confirmed)
+    }
+
+    /// Returns true if seats can be selected (before payment).
+    pub fn can_select_seats(&self) -> bool {
+        matches!(self, Self::Pending | Self::Confirmed)
+    }
+
+    /// Returns true if the booking may move from this status to `target`.
+    pub fn can_transition_to(&self, target: BookingStatus) -> bool {
+        use BookingStatus::*;
+        match (self, target) {
+            // From Pending
+            (Pending, Confirmed | Expired | Cancelled | Failed) => true,
+
+            // From Confirmed
+            (Confirmed, PaymentReceived | Expired | Cancelled | Failed | ChangeRequested) => true,
+
+            // From PaymentReceived
+            (PaymentReceived, Ticketing | Cancelled | RefundPending | Failed | ChangeRequested) => {
+                true
+            }
+
+            // From Ticketing
+            (Ticketing, Ticketed | Failed) => true,
+
+            // From Ticketed (until the flight is flown or missed)
+            (Ticketed, Cancelled | RefundPending | ChangeRequested | Completed | NoShow) => true,
+
+            // From ChangeRequested (confirmed, or declined back)
+            (ChangeRequested, ChangeConfirmed | Confirmed | PaymentReceived | Ticketed) => true,
+
+            // From ChangeConfirmed (awaiting payment, or reissue)
+            (ChangeConfirmed, Confirmed | Ticketing | Failed) => true,
+
+            // From RefundPending
+            (RefundPending, Refunded | Failed) => true,
+
+            // All other transitions invalid
+            _ => false,
+        }
     }
 }
 
@@ -356,11 +466,32 @@ pub enum PoolStatus {
     Expired = 5,
     /// No bids received
     NoBids = 6,
-    /// Pool cancelled by admin
+    /// Pool cancelled by organizer or admin
     Cancelled = 7,
+    /// All contributions received, awaiting booking
+    Locked = 8,
+    /// Booking failed (contributions refunded)
+    Failed = 9,
+    /// Refunding contributions before closing as failed, expired or cancelled
+    Refunding = 10,
 }
 
 impl PoolStatus {
+    /// Every pool status, in discriminant order
+    pub const ALL: [PoolStatus; 11] = [
+        Self::Forming,
+        Self::Active,
+        Self::BiddingClosed,
+        Self::Booking,
+        Self::Completed,
+        Self::Expired,
+        Self::NoBids,
+        Self::Cancelled,
+        Self::Locked,
+        Self::Failed,
+        Self::Refunding,
+    ];
+
     /// Returns the string representation of the pool status.
     ///
     /// Used for API responses and database storage.
@@ -374,9 +505,22 @@ impl PoolStatus {
             Self::Expired => "expired",
             Self::NoBids => "no_bids",
             Self::Cancelled => "cancelled",
+            Self::Locked => "locked",
+            Self::Failed => "failed",
+            Self::Refunding => "refunding",
         }
     }
 
+    /// Parses a status string, ignoring case.
+    ///
+    /// Accepts the upper-case names older `vaya-pool` records were stored
+    /// with ("LOCKED") as well as [`PoolStatus::as_str`].
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+    }
+
     /// Returns true if new members can join the pool.
     ///
     /// Joinable statuses: `Forming`, `Active`
@@ -384,16 +528,49 @@ impl PoolStatus {
         matches!(self, Self::Forming | Self::Active)
     }
 
+    /// Returns true if members can contribute their share.
+    pub fn can_contribute(&self) -> bool {
+        matches!(self, Self::Active)
+    }
+
     /// Returns true if the pool has reached a final state.
     ///
     /// Terminal pools cannot accept new members or process bids.
-    /// Terminal: `Completed`, `Expired`, `NoBids`, `Cancelled`
+    /// Terminal: `Completed`, `Expired`, `NoBids`, `Cancelled`, `Failed`
     pub fn is_terminal(&self) -> bool {
         matches!(
             self,
-            Self::Completed | Self::Expired | Self::NoBids | Self::Cancelled
+            Self::Completed | Self::Expired | Self::NoBids | Self::Cancelled | Self::Failed
         )
     }
+
+    /// Returns true if the pool may move from this status to `target`.
+    pub fn can_transition_to(&self, target: PoolStatus) -> bool {
+        use PoolStatus::*;
+        match (self, target) {
+            // From Forming
+            (Forming, Active | Expired | Cancelled) => true,
+
+            // From Active (back to Forming when a member leaves below minimum)
+            (Active, Forming | Locked | BiddingClosed | Expired | Cancelled) => true,
+
+            // Bidding
+            (BiddingClosed, Booking | NoBids) => true,
+
+            // From Locked
+            (Locked, Booking | Completed | Failed) => true,
+
+            // From Booking
+            (Booking, Completed | Failed) => true,
+
+            // Into and out of Refunding
+            (Forming | Active | Locked | Booking, Refunding) => true,
+            (Refunding, Failed | Expired | Cancelled) => true,
+
+            // All other transitions invalid
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for PoolStatus {
@@ -869,10 +1046,51 @@ mod tests {
         assert!(!BookingStatus::Failed.is_active());
     }
 
+    #[test]
+    fn test_booking_transitions() {
+        assert!(BookingStatus::Confirmed.can_transition_to(BookingStatus::PaymentReceived));
+        assert!(BookingStatus::Ticketed.can_transition_to(BookingStatus::Completed));
+        assert!(!BookingStatus::Ticketed.is_terminal());
+        assert!(!BookingStatus::Pending.can_transition_to(BookingStatus::Ticketed));
+        for status in BookingStatus::ALL {
+            if status.is_terminal() {
+                assert!(BookingStatus::ALL
+                    .iter()
+                    .all(|to| !status.can_transition_to(*to)));
+            }
+        }
+    }
+
     #[test]
     fn test_pool_status() {
         assert!(PoolStatus::Forming.is_joinable());
         assert!(PoolStatus::Completed.is_terminal());
+        assert!(PoolStatus::Locked.can_transition_to(PoolStatus::Failed));
+        assert!(!PoolStatus::Refunding.is_terminal());
+        assert!(!PoolStatus::Forming.can_transition_to(PoolStatus::Completed));
+        for status in PoolStatus::ALL {
+            if status.is_terminal() {
+                assert!(PoolStatus::ALL
+                    .iter()
+                    .all(|to| !status.can_transition_to(*to)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_status_parse_accepts_legacy_names() {
+        for status in BookingStatus::ALL {
+            assert_eq!(BookingStatus::parse(status.as_str()), Some(status));
+        }
+        for status in PoolStatus::ALL {
+            assert_eq!(PoolStatus::parse(status.as_str()), Some(status));
+        }
+        assert_eq!(
+            BookingStatus::parse("PAYMENT_RECEIVED"),
+            Some(BookingStatus::PaymentReceived)
+        );
+        assert_eq!(PoolStatus::parse("LOCKED"), Some(PoolStatus::Locked));
+        assert_eq!(PoolStatus::parse("bidding"), None);
     }
 
     #[test]
@@ -0,0 +1,1049 @@
+//! Structured domain-event logging
+//!
+//! Domain events (searches, bookings, payments, alerts) are consumed by
+//! analytics, so their field names and types form a contract. Each event is
+//! a typed struct implementing [`DomainEvent`] with a versioned
+//! [`EventSchema`]. Events are written as one JSON object per line to a
+//! dedicated [`EventSink`], separate from `tracing` debug logs.
+//!
+//! The accepted schemas are snapshotted in `event_schemas.txt` at the crate
+//! root. A unit test compares the [`EventRegistry::builtin`] schemas against
+//! that snapshot with [`EventRegistry::check_compatibility`], so an
+//! incompatible change (removed field, changed type, new required field
+//! without a version bump) fails CI.
+//!
+//! # Line format
+//!
+//! ```text
+//! {"event":"booking_created","v":1,"ts":"2025-06-01T08:00:00Z",...fields}
+//! ```
+
+use std::fmt;
+use std::io::Write;
+use std::sync::{Arc, Mutex, RwLock};
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Type of an event field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    /// UTF-8 string
+    String,
+    /// Signed integer
+    Int,
+    /// Floating-point number
+    Float,
+    /// Boolean
+    Bool,
+}
+
+impl FieldType {
+    /// Get the type name used in schema snapshots
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Int => "int",
+            FieldType::Float => "float",
+            FieldType::Bool => "bool",
+        }
+    }
+
+    /// Parse a type name from a schema snapshot
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "string" => Some(FieldType::String),
+            "int" => Some(FieldType::Int),
+            "float" => Some(FieldType::Float),
+            "bool" => Some(FieldType::Bool),
+            _ => None,
+        }
+    }
+}
+
+/// Definition of a single event field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// Field name
+    pub name: String,
+    /// Field type
+    pub field_type: FieldType,
+    /// Whether the field is always present (non-null)
+    pub required: bool,
+}
+
+impl FieldSpec {
+    /// A required field
+    pub fn required(name: &str, field_type: FieldType) -> Self {
+        Self {
+            name: name.to_string(),
+            field_type,
+            required: true,
+        }
+    }
+
+    /// An optional (nullable) field
+    pub fn optional(name: &str, field_type: FieldType) -> Self {
+        Self {
+            name: name.to_string(),
+            field_type,
+            required: false,
+        }
+    }
+}
+
+/// Versioned schema of a domain event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventSchema {
+    /// Event name (snake_case)
+    pub name: String,
+    /// Schema version, bumped on incompatible changes
+    pub version: u32,
+    /// Field definitions
+    pub fields: Vec<FieldSpec>,
+}
+
+impl EventSchema {
+    /// Create a schema with no fields
+    pub fn new(name: &str, version: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            version,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Add a field
+    pub fn field(mut self, field: FieldSpec) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Get a field by name
+    pub fn get_field(&self, name: &str) -> Option<&FieldSpec> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Render as a snapshot line: `name v1 field:type field:type? ...`
+    pub fn to_line(&self) -> String {
+        let mut line = format!("{} v{}", self.name, self.version);
+        for field in &self.fields {
+            line.push(' ');
+            line.push_str(&field.name);
+            line.push(':');
+            line.push_str(field.field_type.as_str());
+            if !field.required {
+                line.push('?');
+            }
+        }
+        line
+    }
+
+    /// Parse a snapshot line produced by [`EventSchema::to_line`]
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let name = parts.next()?;
+        let version = parts.next()?.strip_prefix('v')?.parse().ok()?;
+        let mut schema = Self::new(name, version);
+        for part in parts {
+            let (field, ty) = part.split_once(':')?;
+            let (ty, required) = match ty.strip_suffix('?') {
+                Some(ty) => (ty, false),
+                None => (ty, true),
+            };
+            schema.fields.push(FieldSpec {
+                name: field.to_string(),
+                field_type: FieldType::parse(ty)?,
+                required,
+            });
+        }
+        Some(schema)
+    }
+
+    /// Check that this schema can replace `previous` without breaking
+    /// consumers of the same version
+    ///
+    /// Within a version, fields may only be added, and only as optional.
+    pub fn check_compatible_with(&self, previous: &EventSchema) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if self.version < previous.version {
+            problems.push(format!(
+                "{}: version went backwards from v{} to v{}",
+                self.name, previous.version, self.version
+            ));
+        }
+        if self.version != previous.version {
+            return if problems.is_empty() {
+                Ok(())
+            } else {
+                Err(problems)
+            };
+        }
+
+        for old in &previous.fields {
+            match self.get_field(&old.name) {
+                None => problems.push(format!(
+                    "{} v{}: field `{}` was removed",
+                    self.name, self.version, old.name
+                )),
+                Some(new) if new.field_type != old.field_type => problems.push(format!(
+                    "{} v{}: field `{}` changed type from {} to {}",
+                    self.name,
+                    self.version,
+                    old.name,
+                    old.field_type.as_str(),
+                    new.field_type.as_str()
+                )),
+                Some(new) if new.required != old.required => problems.push(format!(
+                    "{} v{}: field `{}` changed requiredness",
+                    self.name, self.version, old.name
+                )),
+                Some(_) => {}
+            }
+        }
+        for new in &self.fields {
+            if new.required && previous.get_field(&new.name).is_none() {
+                problems.push(format!(
+                    "{} v{}: new field `{}` must be optional or the version bumped",
+                    self.name, self.version, new.name
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Value of an event field
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventValue {
+    /// String value
+    String(String),
+    /// Integer value
+    Int(i64),
+    /// Floating-point value
+    Float(f64),
+    /// Boolean value
+    Bool(bool),
+    /// Absent optional value
+    Null,
+}
+
+impl EventValue {
+    /// Type of this value (`None` for null)
+    pub fn field_type(&self) -> Option<FieldType> {
+        match self {
+            EventValue::String(_) => Some(FieldType::String),
+            EventValue::Int(_) => Some(FieldType::Int),
+            EventValue::Float(_) => Some(FieldType::Float),
+            EventValue::Bool(_) => Some(FieldType::Bool),
+            EventValue::Null => None,
+        }
+    }
+
+    fn write_json(&self, out: &mut String) {
+        match self {
+            EventValue::String(s) => write_json_string(out, s),
+            EventValue::Int(i) => out.push_str(&i.to_string()),
+            EventValue::Float(f) if f.is_finite() => out.push_str(&f.to_string()),
+            EventValue::Float(_) | EventValue::Null => out.push_str("null"),
+            EventValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        }
+    }
+}
+
+impl From<&str> for EventValue {
+    fn from(v: &str) -> Self {
+        EventValue::String(v.to_string())
+    }
+}
+
+impl From<String> for EventValue {
+    fn from(v: String) -> Self {
+        EventValue::String(v)
+    }
+}
+
+impl From<i64> for EventValue {
+    fn from(v: i64) -> Self {
+        EventValue::Int(v)
+    }
+}
+
+impl From<u32> for EventValue {
+    fn from(v: u32) -> Self {
+        EventValue::Int(v as i64)
+    }
+}
+
+impl From<u64> for EventValue {
+    fn from(v: u64) -> Self {
+        EventValue::Int(v as i64)
+    }
+}
+
+impl From<f64> for EventValue {
+    fn from(v: f64) -> Self {
+        EventValue::Float(v)
+    }
+}
+
+impl From<bool> for EventValue {
+    fn from(v: bool) -> Self {
+        EventValue::Bool(v)
+    }
+}
+
+impl<T: Into<EventValue>> From<Option<T>> for EventValue {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(EventValue::Null, Into::into)
+    }
+}
+
+/// A typed domain event with a registered schema
+pub trait DomainEvent {
+    /// The event's schema
+    fn schema() -> EventSchema
+    where
+        Self: Sized;
+
+    /// Event name (must match the schema)
+    fn name(&self) -> &'static str;
+
+    /// Field values in schema order
+    fn fields(&self) -> Vec<(&'static str, EventValue)>;
+}
+
+/// A search was executed
+#[derive(Debug, Clone)]
+pub struct SearchPerformed {
+    /// Search ID (cache key)
+    pub search_id: String,
+    /// Origin airport code
+    pub origin: String,
+    /// Destination airport code
+    pub destination: String,
+    /// Departure date (YYYY-MM-DD)
+    pub departure_date: String,
+    /// Return date for round trips
+    pub return_date: Option<String>,
+    /// Total passengers
+    pub passengers: u32,
+    /// Cabin class code
+    pub cabin: String,
+    /// Number of offers returned
+    pub result_count: u32,
+    /// Whether results came from cache
+    pub cached: bool,
+    /// Time taken in milliseconds
+    pub duration_ms: u64,
+}
+
+impl DomainEvent for SearchPerformed {
+    fn schema() -> EventSchema {
+        EventSchema::new("search_performed", 1)
+            .field(FieldSpec::required("search_id", FieldType::String))
+            .field(FieldSpec::required("origin", FieldType::String))
+            .field(FieldSpec::required("destination", FieldType::String))
+            .field(FieldSpec::required("departure_date", FieldType::String))
+            .field(FieldSpec::optional("return_date", FieldType::String))
+            .field(FieldSpec::required("passengers", FieldType::Int))
+            .field(FieldSpec::required("cabin", FieldType::String))
+            .field(FieldSpec::required("result_count", FieldType::Int))
+            .field(FieldSpec::required("cached", FieldType::Bool))
+            .field(FieldSpec::required("duration_ms", FieldType::Int))
+    }
+
+    fn name(&self) -> &'static str {
+        "search_performed"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("search_id", self.search_id.as_str().into()),
+            ("origin", self.origin.as_str().into()),
+            ("destination", self.destination.as_str().into()),
+            ("departure_date", self.departure_date.as_str().into()),
+            ("return_date", self.return_date.clone().into()),
+            ("passengers", self.passengers.into()),
+            ("cabin", self.cabin.as_str().into()),
+            ("result_count", self.result_count.into()),
+            ("cached", self.cached.into()),
+            ("duration_ms", self.duration_ms.into()),
+        ]
+    }
+}
+
+/// A provider answered a search after its first results were returned
+#[derive(Debug, Clone)]
+pub struct SearchLateResults {
+    /// Search ID (cache key)
+    pub search_id: String,
+    /// Origin airport code
+    pub origin: String,
+    /// Destination airport code
+    pub destination: String,
+    /// Provider name
+    pub provider: String,
+    /// Number of offers the provider added
+    pub result_count: u32,
+    /// Cheapest added offer in minor units
+    pub cheapest_minor: Option<i64>,
+    /// Currency of the cheapest offer
+    pub currency: Option<String>,
+    /// Time the provider took to answer in milliseconds
+    pub latency_ms: u64,
+}
+
+impl DomainEvent for SearchLateResults {
+    fn schema() -> EventSchema {
+        EventSchema::new("search_late_results", 1)
+            .field(FieldSpec::required("search_id", FieldType::String))
+            .field(FieldSpec::required("origin", FieldType::String))
+            .field(FieldSpec::required("destination", FieldType::String))
+            .field(FieldSpec::required("provider", FieldType::String))
+            .field(FieldSpec::required("result_count", FieldType::Int))
+            .field(FieldSpec::optional("cheapest_minor", FieldType::Int))
+            .field(FieldSpec::optional("currency", FieldType::String))
+            .field(FieldSpec::required("latency_ms", FieldType::Int))
+    }
+
+    fn name(&self) -> &'static str {
+        "search_late_results"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("search_id", self.search_id.as_str().into()),
+            ("origin", self.origin.as_str().into()),
+            ("destination", self.destination.as_str().into()),
+            ("provider", self.provider.as_str().into()),
+            ("result_count", self.result_count.into()),
+            ("cheapest_minor", self.cheapest_minor.into()),
+            ("currency", self.currency.clone().into()),
+            ("latency_ms", self.latency_ms.into()),
+        ]
+    }
+}
+
+/// A booking was created
+#[derive(Debug, Clone)]
+pub struct BookingCreated {
+    /// Booking ID
+    pub booking_id: String,
+    /// Booking reference
+    pub pnr: String,
+    /// User ID
+    pub user_id: String,
+    /// Offer ID that was booked
+    pub offer_id: String,
+    /// Number of passengers
+    pub passenger_count: u32,
+    /// Total amount in minor units
+    pub amount_minor: i64,
+    /// Currency code
+    pub currency: String,
+}
+
+impl DomainEvent for BookingCreated {
+    fn schema() -> EventSchema {
+        EventSchema::new("booking_created", 1)
+            .field(FieldSpec::required("booking_id", FieldType::String))
+            .field(FieldSpec::required("pnr", FieldType::String))
+            .field(FieldSpec::required("user_id", FieldType::String))
+            .field(FieldSpec::required("offer_id", FieldType::String))
+            .field(FieldSpec::required("passenger_count", FieldType::Int))
+            .field(FieldSpec::required("amount_minor", FieldType::Int))
+            .field(FieldSpec::required("currency", FieldType::String))
+    }
+
+    fn name(&self) -> &'static str {
+        "booking_created"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("booking_id", self.booking_id.as_str().into()),
+            ("pnr", self.pnr.as_str().into()),
+            ("user_id", self.user_id.as_str().into()),
+            ("offer_id", self.offer_id.as_str().into()),
+            ("passenger_count", self.passenger_count.into()),
+            ("amount_minor", self.amount_minor.into()),
+            ("currency", self.currency.as_str().into()),
+        ]
+    }
+}
+
+/// A payment was captured for a booking
+#[derive(Debug, Clone)]
+pub struct PaymentCaptured {
+    /// Booking ID
+    pub booking_id: String,
+    /// Payment provider's payment ID
+    pub payment_id: String,
+    /// Captured amount in minor units
+    pub amount_minor: i64,
+    /// Currency code
+    pub currency: String,
+}
+
+impl DomainEvent for PaymentCaptured {
+    fn schema() -> EventSchema {
+        EventSchema::new("payment_captured", 1)
+            .field(FieldSpec::required("booking_id", FieldType::String))
+            .field(FieldSpec::required("payment_id", FieldType::String))
+            .field(FieldSpec::required("amount_minor", FieldType::Int))
+            .field(FieldSpec::required("currency", FieldType::String))
+    }
+
+    fn name(&self) -> &'static str {
+        "payment_captured"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("booking_id", self.booking_id.as_str().into()),
+            ("payment_id", self.payment_id.as_str().into()),
+            ("amount_minor", self.amount_minor.into()),
+            ("currency", self.currency.as_str().into()),
+        ]
+    }
+}
+
+/// A price alert fired
+#[derive(Debug, Clone)]
+pub struct AlertTriggered {
+    /// Alert ID
+    pub alert_id: String,
+    /// User ID
+    pub user_id: String,
+    /// Origin airport code
+    pub origin: String,
+    /// Destination airport code
+    pub destination: String,
+    /// Alert trigger type
+    pub trigger: String,
+    /// Price that triggered the alert, in minor units
+    pub price_minor: i64,
+    /// Savings versus the alert target, in minor units
+    pub savings_minor: Option<i64>,
+    /// Currency code
+    pub currency: String,
+}
+
+impl DomainEvent for AlertTriggered {
+    fn schema() -> EventSchema {
+        EventSchema::new("alert_triggered", 1)
+            .field(FieldSpec::required("alert_id", FieldType::String))
+            .field(FieldSpec::required("user_id", FieldType::String))
+            .field(FieldSpec::required("origin", FieldType::String))
+            .field(FieldSpec::required("destination", FieldType::String))
+            .field(FieldSpec::required("trigger", FieldType::String))
+            .field(FieldSpec::required("price_minor", FieldType::Int))
+            .field(FieldSpec::optional("savings_minor", FieldType::Int))
+            .field(FieldSpec::required("currency", FieldType::String))
+    }
+
+    fn name(&self) -> &'static str {
+        "alert_triggered"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("alert_id", self.alert_id.as_str().into()),
+            ("user_id", self.user_id.as_str().into()),
+            ("origin", self.origin.as_str().into()),
+            ("destination", self.destination.as_str().into()),
+            ("trigger", self.trigger.as_str().into()),
+            ("price_minor", self.price_minor.into()),
+            ("savings_minor", self.savings_minor.into()),
+            ("currency", self.currency.as_str().into()),
+        ]
+    }
+}
+
+/// A user joined a group-buying pool
+#[derive(Debug, Clone)]
+pub struct PoolMemberJoined {
+    /// Pool ID
+    pub pool_id: String,
+    /// User ID
+    pub user_id: String,
+    /// Spots taken by the user
+    pub spots: u32,
+    /// Members in the pool after joining
+    pub member_count: u32,
+}
+
+impl DomainEvent for PoolMemberJoined {
+    fn schema() -> EventSchema {
+        EventSchema::new("pool_member_joined", 1)
+            .field(FieldSpec::required("pool_id", FieldType::String))
+            .field(FieldSpec::required("user_id", FieldType::String))
+            .field(FieldSpec::required("spots", FieldType::Int))
+            .field(FieldSpec::required("member_count", FieldType::Int))
+    }
+
+    fn name(&self) -> &'static str {
+        "pool_member_joined"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("pool_id", self.pool_id.as_str().into()),
+            ("user_id", self.user_id.as_str().into()),
+            ("spots", self.spots.into()),
+            ("member_count", self.member_count.into()),
+        ]
+    }
+}
+
+/// A group-buying pool changed status
+#[derive(Debug, Clone)]
+pub struct PoolStatusChanged {
+    /// Pool ID
+    pub pool_id: String,
+    /// Previous status code
+    pub from: String,
+    /// New status code
+    pub to: String,
+    /// Members in the pool
+    pub member_count: u32,
+}
+
+impl DomainEvent for PoolStatusChanged {
+    fn schema() -> EventSchema {
+        EventSchema::new("pool_status_changed", 1)
+            .field(FieldSpec::required("pool_id", FieldType::String))
+            .field(FieldSpec::required("from", FieldType::String))
+            .field(FieldSpec::required("to", FieldType::String))
+            .field(FieldSpec::required("member_count", FieldType::Int))
+    }
+
+    fn name(&self) -> &'static str {
+        "pool_status_changed"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("pool_id", self.pool_id.as_str().into()),
+            ("from", self.from.as_str().into()),
+            ("to", self.to.as_str().into()),
+            ("member_count", self.member_count.into()),
+        ]
+    }
+}
+
+/// A waitlisted user was moved into a pool after a member left
+#[derive(Debug, Clone)]
+pub struct PoolWaitlistPromoted {
+    /// Pool ID
+    pub pool_id: String,
+    /// User ID
+    pub user_id: String,
+    /// Spots taken by the user
+    pub spots: u32,
+    /// Users still on the waitlist
+    pub waitlist_remaining: u32,
+}
+
+impl DomainEvent for PoolWaitlistPromoted {
+    fn schema() -> EventSchema {
+        EventSchema::new("pool_waitlist_promoted", 1)
+            .field(FieldSpec::required("pool_id", FieldType::String))
+            .field(FieldSpec::required("user_id", FieldType::String))
+            .field(FieldSpec::required("spots", FieldType::Int))
+            .field(FieldSpec::required("waitlist_remaining", FieldType::Int))
+    }
+
+    fn name(&self) -> &'static str {
+        "pool_waitlist_promoted"
+    }
+
+    fn fields(&self) -> Vec<(&'static str, EventValue)> {
+        vec![
+            ("pool_id", self.pool_id.as_str().into()),
+            ("user_id", self.user_id.as_str().into()),
+            ("spots", self.spots.into()),
+            ("waitlist_remaining", self.waitlist_remaining.into()),
+        ]
+    }
+}
+
+/// Central registry of event schemas
+#[derive(Debug, Clone, Default)]
+pub struct EventRegistry {
+    schemas: Vec<EventSchema>,
+}
+
+impl EventRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry of all built-in domain events
+    pub fn builtin() -> Self {
+        Self::new()
+            .register::<SearchPerformed>()
+            .register::<SearchLateResults>()
+            .register::<BookingCreated>()
+            .register::<PaymentCaptured>()
+            .register::<AlertTriggered>()
+            .register::<PoolMemberJoined>()
+            .register::<PoolStatusChanged>()
+            .register::<PoolWaitlistPromoted>()
+    }
+
+    /// Register an event type
+    pub fn register<E: DomainEvent>(mut self) -> Self {
+        let schema = E::schema();
+        self.schemas.retain(|s| s.name != schema.name);
+        self.schemas.push(schema);
+        self
+    }
+
+    /// Get a schema by event name
+    pub fn get(&self, name: &str) -> Option<&EventSchema> {
+        self.schemas.iter().find(|s| s.name == name)
+    }
+
+    /// All registered schemas
+    pub fn schemas(&self) -> &[EventSchema] {
+        &self.schemas
+    }
+
+    /// Render all schemas as a snapshot (one line per event, sorted)
+    pub fn snapshot(&self) -> String {
+        let mut lines: Vec<String> = self.schemas.iter().map(EventSchema::to_line).collect();
+        lines.sort();
+        let mut out = lines.join("\n");
+        out.push('\n');
+        out
+    }
+
+    /// Check the registered schemas against an accepted snapshot
+    ///
+    /// Fails on incompatible changes within a version, and on any schema
+    /// that differs from the snapshot so that the snapshot is kept current.
+    pub fn check_compatibility(&self, snapshot: &str) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        let previous: Vec<EventSchema> = snapshot
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(|l| {
+                let parsed = EventSchema::parse_line(l);
+                if parsed.is_none() {
+                    problems.push(format!("unparseable snapshot line: {}", l));
+                }
+                parsed
+            })
+            .collect();
+
+        for old in &previous {
+            match self.get(&old.name) {
+                None => problems.push(format!("{}: event was removed", old.name)),
+                Some(new) => {
+                    if let Err(errs) = new.check_compatible_with(old) {
+                        problems.extend(errs);
+                    } else if new != old {
+                        problems.push(format!(
+                            "{}: schema changed compatibly; update the snapshot",
+                            old.name
+                        ));
+                    }
+                }
+            }
+        }
+        for new in &self.schemas {
+            if !previous.iter().any(|p| p.name == new.name) {
+                problems.push(format!("{}: new event missing from snapshot", new.name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Destination for serialized domain events
+pub trait EventSink: Send + Sync {
+    /// Write one serialized event (a single JSON line without newline)
+    fn write_line(&self, line: &str);
+}
+
+/// Sink writing JSON lines to any writer (file, stdout)
+pub struct WriterSink {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl WriterSink {
+    /// Wrap a writer
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Append to a file, creating it if needed
+    pub fn append_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self::new(file))
+    }
+}
+
+impl EventSink for WriterSink {
+    fn write_line(&self, line: &str) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        // Event logging must never fail the operation being logged
+        let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+    }
+}
+
+impl fmt::Debug for WriterSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriterSink").finish_non_exhaustive()
+    }
+}
+
+/// Sink buffering events in memory (for tests)
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    lines: Mutex<Vec<String>>,
+}
+
+impl MemorySink {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lines written so far
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl EventSink for MemorySink {
+    fn write_line(&self, line: &str) {
+        self.lines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(line.to_string());
+    }
+}
+
+static SINK: RwLock<Option<Arc<dyn EventSink>>> = RwLock::new(None);
+
+/// Install the process-wide event sink (`None` disables event logging)
+pub fn set_sink(sink: Option<Arc<dyn EventSink>>) {
+    *SINK.write().unwrap_or_else(|e| e.into_inner()) = sink;
+}
+
+/// Emit a domain event to the installed sink, if any
+pub fn emit<E: DomainEvent>(event: &E) {
+    let sink = SINK.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if let Some(sink) = sink {
+        sink.write_line(&to_json_line(event, OffsetDateTime::now_utc()));
+    }
+}
+
+/// Serialize an event as a single JSON object
+pub fn to_json_line<E: DomainEvent>(event: &E, at: OffsetDateTime) -> String {
+    let schema = E::schema();
+    let mut out = String::with_capacity(256);
+    out.push_str("{\"event\":");
+    write_json_string(&mut out, event.name());
+    out.push_str(&format!(",\"v\":{},\"ts\":", schema.version));
+    write_json_string(&mut out, &at.format(&Rfc3339).unwrap_or_default());
+    for (name, value) in event.fields() {
+        out.push(',');
+        write_json_string(&mut out, name);
+        out.push(':');
+        value.write_json(&mut out);
+    }
+    out.push('}');
+    out
+}
+
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn booking() -> BookingCreated {
+        BookingCreated {
+            booking_id: "b-1".into(),
+            pnr: "ABC123".into(),
+            user_id: "u-1".into(),
+            offer_id: "o-\"1\"".into(),
+            passenger_count: 2,
+            amount_minor: 45000,
+            currency: "MYR".into(),
+        }
+    }
+
+    #[test]
+    fn test_event_schemas_match_snapshot() {
+        let registry = EventRegistry::builtin();
+        if let Err(problems) = registry.check_compatibility(include_str!("../event_schemas.txt")) {
+            panic!(
+                "event schemas are incompatible with event_schemas.txt:\n  {}\n\ncurrent:\n{}",
+                problems.join("\n  "),
+                registry.snapshot()
+            );
+        }
+    }
+
+    #[test]
+    fn test_fields_match_schema() {
+        fn check<E: DomainEvent>(event: &E) {
+            let schema = E::schema();
+            assert_eq!(event.name(), schema.name);
+            let fields = event.fields();
+            assert_eq!(fields.len(), schema.fields.len());
+            for ((name, value), spec) in fields.iter().zip(&schema.fields) {
+                assert_eq!(*name, spec.name);
+                match value.field_type() {
+                    Some(ty) => assert_eq!(ty, spec.field_type, "{}", name),
+                    None => assert!(!spec.required, "{} is required", name),
+                }
+            }
+        }
+
+        check(&booking());
+        check(&SearchPerformed {
+            search_id: "s".into(),
+            origin: "KUL".into(),
+            destination: "SIN".into(),
+            departure_date: "2025-06-01".into(),
+            return_date: None,
+            passengers: 1,
+            cabin: "Y".into(),
+            result_count: 3,
+            cached: false,
+            duration_ms: 12,
+        });
+        check(&SearchLateResults {
+            search_id: "s".into(),
+            origin: "KUL".into(),
+            destination: "SIN".into(),
+            provider: "sabre".into(),
+            result_count: 2,
+            cheapest_minor: Some(45_000),
+            currency: Some("MYR".into()),
+            latency_ms: 2_100,
+        });
+        check(&PaymentCaptured {
+            booking_id: "b".into(),
+            payment_id: "p".into(),
+            amount_minor: 1,
+            currency: "MYR".into(),
+        });
+        check(&AlertTriggered {
+            alert_id: "a".into(),
+            user_id: "u".into(),
+            origin: "KUL".into(),
+            destination: "SIN".into(),
+            trigger: "PRICE_DROPS_BELOW".into(),
+            price_minor: 100,
+            savings_minor: Some(5),
+            currency: "MYR".into(),
+        });
+        check(&PoolMemberJoined {
+            pool_id: "p".into(),
+            user_id: "u".into(),
+            spots: 2,
+            member_count: 5,
+        });
+        check(&PoolStatusChanged {
+            pool_id: "p".into(),
+            from: "forming".into(),
+            to: "active".into(),
+            member_count: 5,
+        });
+        check(&PoolWaitlistPromoted {
+            pool_id: "p".into(),
+            user_id: "u".into(),
+            spots: 1,
+            waitlist_remaining: 3,
+        });
+    }
+
+    #[test]
+    fn test_compatibility_rules() {
+        let v1 = EventSchema::new("e", 1).field(FieldSpec::required("a", FieldType::Int));
+
+        let added_optional = v1.clone().field(FieldSpec::optional("b", FieldType::Bool));
+        assert!(added_optional.check_compatible_with(&v1).is_ok());
+
+        let added_required = v1.clone().field(FieldSpec::required("b", FieldType::Bool));
+        assert!(added_required.check_compatible_with(&v1).is_err());
+
+        let retyped = EventSchema::new("e", 1).field(FieldSpec::required("a", FieldType::String));
+        assert!(retyped.check_compatible_with(&v1).is_err());
+
+        let removed = EventSchema::new("e", 1);
+        assert!(removed.check_compatible_with(&v1).is_err());
+
+        let mut bumped = removed.clone();
+        bumped.version = 2;
+        assert!(bumped.check_compatible_with(&v1).is_ok());
+        assert!(v1.check_compatible_with(&bumped).is_err());
+    }
+
+    #[test]
+    fn test_schema_line_roundtrip() {
+        let schema = AlertTriggered::schema();
+        let line = schema.to_line();
+        assert!(line.contains("savings_minor:int?"));
+        assert_eq!(EventSchema::parse_line(&line), Some(schema));
+    }
+
+    #[test]
+    fn test_json_line() {
+        let at = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let line = to_json_line(&booking(), at);
+        assert!(line
+            .starts_with("{\"event\":\"booking_created\",\"v\":1,\"ts\":\"2023-11-14T22:13:20Z\""));
+        assert!(line.contains("\"offer_id\":\"o-\\\"1\\\"\""));
+        assert!(line.contains("\"amount_minor\":45000"));
+        assert!(line.ends_with("\"currency\":\"MYR\"}"));
+    }
+
+    #[test]
+    fn test_emit_to_sink() {
+        let sink = Arc::new(MemorySink::new());
+        set_sink(Some(sink.clone()));
+        emit(&booking());
+        set_sink(None);
+        emit(&booking());
+        assert_eq!(sink.lines().len(), 1);
+    }
+}
@@ -0,0 +1,228 @@
+//! Foreign exchange rates
+//!
+//! Rates are fixed-point: units of a currency per one unit of the base
+//! currency, scaled by [`RATE_SCALE`]. Conversion is integer arithmetic
+//! with a single half-up rounding into the target currency's minor units,
+//! so a converted price never passes through floating point.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::{ErrorCode, Result, VayaError};
+use crate::money::BPS;
+use crate::types::{CurrencyCode, MinorUnits, Timestamp};
+
+/// Fixed-point scale of a rate (8 decimal places)
+pub const RATE_SCALE: i64 = 100_000_000;
+
+/// A snapshot of exchange rates against one base currency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExchangeRates {
+    /// Currency the rates are quoted against
+    pub base: CurrencyCode,
+    /// When the rates were published
+    pub as_of: Timestamp,
+    /// Margin added to every conversion, in basis points
+    pub margin_bps: u32,
+    rates: HashMap<CurrencyCode, i64>,
+}
+
+impl ExchangeRates {
+    /// Create an empty rate table
+    pub fn new(base: CurrencyCode, as_of: Timestamp) -> Self {
+        Self {
+            base,
+            as_of,
+            margin_bps: 0,
+            rates: HashMap::new(),
+        }
+    }
+
+    /// Add a rate already scaled by [`RATE_SCALE`]
+    ///
+    /// Fails unless the rate is positive, like [`Self::set_rate`].
+    pub fn with_rate(mut self, currency: CurrencyCode, scaled: i64) -> Result<Self> {
+        if scaled <= 0 {
+            return Err(invalid_rate(currency, &scaled));
+        }
+        self.rates.insert(currency, scaled);
+        Ok(self)
+    }
+
+    /// Set the conversion margin
+    pub fn with_margin_bps(mut self, bps: u32) -> Self {
+        self.margin_bps = bps;
+        self
+    }
+
+    /// Set a rate from a decimal string ("4.7125")
+    ///
+    /// Digits past the eighth decimal place are dropped.
+    pub fn set_rate(&mut self, currency: CurrencyCode, rate: &str) -> Result<()> {
+        let scaled = parse_rate(rate).ok_or_else(|| invalid_rate(currency, &rate))?;
+        self.rates.insert(currency, scaled);
+        Ok(())
+    }
+
+    /// Scaled rate of a currency against the base
+    pub fn rate(&self, currency: CurrencyCode) -> Option<i64> {
+        if currency == self.base {
+            return Some(RATE_SCALE);
+        }
+        self.rates.get(&currency).copied()
+    }
+
+    /// Check if an amount can be converted between two currencies
+    pub fn supports(&self, from: CurrencyCode, to: CurrencyCode) -> bool {
+        self.rate(from).is_some() && self.rate(to).is_some()
+    }
+
+    /// Age of the rates at `now` in seconds
+    pub fn age_secs(&self, now: Timestamp) -> i64 {
+        now.as_unix() - self.as_of.as_unix()
+    }
+
+    /// Convert an amount, adding the margin
+    ///
+    /// Amounts already in `to` are returned unchanged, without margin.
+    pub fn convert(
+        &self,
+        amount: MinorUnits,
+        from: CurrencyCode,
+        to: CurrencyCode,
+    ) -> Result<MinorUnits> {
+        if from == to {
+            return Ok(amount);
+        }
+        let missing = |currency: CurrencyCode| {
+            VayaError::new(
+                ErrorCode::InvalidCurrency,
+                format!("No {} rate against {}", currency, self.base),
+            )
+        };
+        let from_rate = self.rate(from).ok_or_else(|| missing(from))?;
+        let to_rate = self.rate(to).ok_or_else(|| missing(to))?;
+
+        // amount / 10^from_dp / from_rate * to_rate * 10^to_dp * (1 + margin)
+        let numerator = i128::from(amount.as_i64())
+            * i128::from(to_rate)
+            * 10i128.pow(u32::from(to.decimals()))
+            * (BPS + i128::from(self.margin_bps));
+        let denominator = i128::from(from_rate) * 10i128.pow(u32::from(from.decimals())) * BPS;
+        let rounded = (numerator.abs() + denominator / 2) / denominator * numerator.signum();
+        i64::try_from(rounded)
+            .map(MinorUnits::new)
+            .map_err(|_| VayaError::new(ErrorCode::InvalidPrice, "Converted amount out of range"))
+    }
+}
+
+/// Error for a rate that is malformed or not positive
+fn invalid_rate(currency: CurrencyCode, rate: &dyn fmt::Debug) -> VayaError {
+    VayaError::new(
+        ErrorCode::InvalidCurrency,
+        format!("Invalid {} rate {:?}", currency, rate),
+    )
+}
+
+/// Parse a positive decimal rate into [`RATE_SCALE`] fixed point
+fn parse_rate(s: &str) -> Option<i64> {
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    if int_part.is_empty()
+        || !int_part.bytes().all(|b| b.is_ascii_digit())
+        || !frac_part.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut value: i64 = 0;
+    let frac_digits = frac_part.bytes().chain(std::iter::repeat(b'0')).take(8);
+    for b in int_part.bytes().chain(frac_digits) {
+        value = value.checked_mul(10)?.checked_add(i64::from(b - b'0'))?;
+    }
+    (value > 0).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates() -> ExchangeRates {
+        let mut rates = ExchangeRates::new(CurrencyCode::MYR, Timestamp::from_unix(1_700_000_000));
+        rates.set_rate(CurrencyCode::SGD, "0.2885").unwrap();
+        rates.set_rate(CurrencyCode::USD, "0.2134").unwrap();
+        rates.set_rate(CurrencyCode::JPY, "31.5").unwrap();
+        rates
+    }
+
+    #[test]
+    fn test_convert() {
+        let rates = rates();
+        // RM 500.00 -> SGD 144.25
+        let sgd = rates
+            .convert(
+                MinorUnits::new(50_000),
+                CurrencyCode::MYR,
+                CurrencyCode::SGD,
+            )
+            .unwrap();
+        assert_eq!(sgd, MinorUnits::new(14_425));
+        // Cross rate through the base: SGD 144.25 -> USD 106.70
+        let usd = rates
+            .convert(sgd, CurrencyCode::SGD, CurrencyCode::USD)
+            .unwrap();
+        assert_eq!(usd, MinorUnits::new(10_670));
+        // Zero-decimal target: RM 500.00 -> JPY 15750
+        let jpy = rates
+            .convert(
+                MinorUnits::new(50_000),
+                CurrencyCode::MYR,
+                CurrencyCode::JPY,
+            )
+            .unwrap();
+        assert_eq!(jpy, MinorUnits::new(15_750));
+
+        assert!(rates
+            .convert(MinorUnits::new(100), CurrencyCode::MYR, CurrencyCode::THB)
+            .is_err());
+    }
+
+    #[test]
+    fn test_convert_with_margin() {
+        let rates = rates().with_margin_bps(200);
+        let sgd = rates
+            .convert(
+                MinorUnits::new(50_000),
+                CurrencyCode::MYR,
+                CurrencyCode::SGD,
+            )
+            .unwrap();
+        assert_eq!(sgd, MinorUnits::new(14_714));
+        // No margin without a conversion
+        let myr = rates
+            .convert(
+                MinorUnits::new(50_000),
+                CurrencyCode::MYR,
+                CurrencyCode::MYR,
+            )
+            .unwrap();
+        assert_eq!(myr, MinorUnits::new(50_000));
+    }
+
+    #[test]
+    fn test_with_rate_rejects_non_positive() {
+        let base = || ExchangeRates::new(CurrencyCode::MYR, Timestamp::from_unix(1_700_000_000));
+        assert!(base().with_rate(CurrencyCode::SGD, 0).is_err());
+        assert!(base().with_rate(CurrencyCode::SGD, -28_850_000).is_err());
+        let rates = base().with_rate(CurrencyCode::SGD, 28_850_000).unwrap();
+        assert_eq!(rates.rate(CurrencyCode::SGD), Some(28_850_000));
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("1"), Some(RATE_SCALE));
+        assert_eq!(parse_rate("0.2885"), Some(28_850_000));
+        assert_eq!(parse_rate("0.123456789"), Some(12_345_678));
+        assert_eq!(parse_rate("0"), None);
+        assert_eq!(parse_rate("-1.2"), None);
+        assert_eq!(parse_rate("1e-5"), None);
+    }
+}
@@ -15,20 +15,52 @@
 //! - `types`: Core primitive types (IataCode, Price, Timestamp, Uuid, etc.)
 //! - `enums`: Domain enums (UserStatus, BookingStatus, PoolStatus, etc.)
 //! - `error`: Error types and error codes
+//! - `fx`: Exchange rates and fixed-point currency conversion
+//! - `money`: Checked money arithmetic, typed currencies and allocation
+//! - `events`: Versioned domain events for analytics logging
+//! - `bus`: Typed in-process publish/subscribe for domain events
+//! - `metrics`: Process-wide counters and gauges
+//! - `logbuf`: Ring buffer of recent log lines for live tailing
+//! - `refdata`: Airport and airline reference data and great-circle distances
+//! - `redact`: Masking of sensitive values in Debug, Display and serde output
+//! - `secret`: `secret://` references to secrets kept outside configuration
+//! - `translit`: Passenger name transliteration to passport (MRZ) form
+//! - `zoned`: Timezone-aware instants for local flight times
+//!
+//! With the `serde` feature, types and enums also implement serde's
+//! `Serialize`/`Deserialize` using the API's string representations.
 
 #![warn(missing_docs)]
 #![warn(rust_2018_idioms)]
 #![forbid(unsafe_op_in_unsafe_fn)]
 
+pub mod bus;
 pub mod codegen;
 pub mod enums;
 pub mod error;
+pub mod events;
+pub mod fx;
+pub mod logbuf;
+pub mod metrics;
+pub mod money;
+pub mod redact;
+pub mod refdata;
+pub mod secret;
+#[cfg(feature = "serde")]
+mod serde_impls;
+pub mod translit;
 pub mod types;
+pub mod zoned;
 
 // Re-export commonly used types at crate root
 pub use enums::*;
 pub use error::{ErrorCode, FieldError, Result, ValidationError, VayaError};
+pub use fx::ExchangeRates;
+pub use money::{Currency, Money};
+pub use redact::{Mask, Redact, Redacted, Sensitive};
+pub use secret::{resolve_secret, SecretSource, UnresolvedSecret, SECRET_SCHEME};
 pub use types::*;
+pub use zoned::{TimeZone, ZonedTime};
 
 /// Version of the VAYA protocol
 pub const VAYA_VERSION: &str = env!("CARGO_PKG_VERSION");
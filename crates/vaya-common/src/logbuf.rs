@@ -0,0 +1,234 @@
+//! In-memory ring buffer of recent log lines
+//!
+//! The logging subscriber copies each event into the process-wide buffer so
+//! operators can tail recent logs over the admin live-tail stream without
+//! shell access. Every entry gets a sequence number; readers keep the last
+//! sequence they saw and ask for what came after it.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries kept by the global buffer
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Log severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Very verbose diagnostics
+    Trace,
+    /// Debug output
+    Debug,
+    /// Normal operation
+    Info,
+    /// Something unexpected but handled
+    Warn,
+    /// Failure
+    Error,
+}
+
+impl LogLevel {
+    /// Lowercase level name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    /// Parse a level name (case-insensitive)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Some(LogLevel::Trace),
+            "debug" => Some(LogLevel::Debug),
+            "info" => Some(LogLevel::Info),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "error" => Some(LogLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+/// A captured log line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// Sequence number, increasing from 1
+    pub seq: u64,
+    /// Capture time in Unix milliseconds
+    pub at_ms: u64,
+    /// Severity
+    pub level: LogLevel,
+    /// Module path or target that emitted the line
+    pub target: String,
+    /// Rendered message and fields
+    pub message: String,
+}
+
+/// Selects log entries by level and module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    /// Lowest level included
+    pub min_level: LogLevel,
+    /// Only targets starting with this prefix
+    pub target_prefix: Option<String>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            min_level: LogLevel::Info,
+            target_prefix: None,
+        }
+    }
+}
+
+impl LogFilter {
+    /// Check if an entry passes the filter
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        entry.level >= self.min_level
+            && self
+                .target_prefix
+                .as_deref()
+                .is_none_or(|p| entry.target.starts_with(p))
+    }
+}
+
+/// Entries read from a [`LogBuffer`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogBatch {
+    /// Matching entries, oldest first
+    pub entries: Vec<LogEntry>,
+    /// Entries the reader missed: evicted before being read, or matching
+    /// entries beyond the batch limit
+    pub dropped: u64,
+    /// Sequence to pass to the next read
+    pub cursor: u64,
+}
+
+/// Fixed-size buffer of recent log entries
+#[derive(Debug)]
+pub struct LogBuffer {
+    capacity: usize,
+    inner: Mutex<Ring>,
+}
+
+#[derive(Debug, Default)]
+struct Ring {
+    entries: VecDeque<LogEntry>,
+    last_seq: u64,
+}
+
+impl LogBuffer {
+    /// Create a buffer holding up to `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(Ring::default()),
+        }
+    }
+
+    /// Append an entry, evicting the oldest when full; returns its sequence
+    pub fn push(&self, level: LogLevel, target: &str, message: impl Into<String>) -> u64 {
+        let at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut ring = self.lock();
+        ring.last_seq += 1;
+        let seq = ring.last_seq;
+        if ring.entries.len() == self.capacity {
+            ring.entries.pop_front();
+        }
+        ring.entries.push_back(LogEntry {
+            seq,
+            at_ms,
+            level,
+            target: target.to_string(),
+            message: message.into(),
+        });
+        seq
+    }
+
+    /// Sequence of the newest entry (0 if empty)
+    pub fn last_seq(&self) -> u64 {
+        self.lock().last_seq
+    }
+
+    /// Read matching entries newer than `cursor`
+    ///
+    /// At most `limit` entries are returned; when more match, the newest
+    /// are kept so a slow reader catches up instead of falling further
+    /// behind.
+    pub fn read_since(&self, cursor: u64, filter: &LogFilter, limit: usize) -> LogBatch {
+        let ring = self.lock();
+        let oldest = ring.entries.front().map_or(ring.last_seq + 1, |e| e.seq);
+        let evicted = oldest.saturating_sub(cursor + 1);
+
+        let mut entries: Vec<LogEntry> = ring
+            .entries
+            .iter()
+            .filter(|e| e.seq > cursor && filter.matches(e))
+            .cloned()
+            .collect();
+        let overflow = entries.len().saturating_sub(limit);
+        entries.drain(..overflow);
+
+        LogBatch {
+            entries,
+            dropped: evicted + overflow as u64,
+            cursor: ring.last_seq,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Ring> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// The process-wide log buffer
+pub fn global() -> &'static LogBuffer {
+    static GLOBAL: OnceLock<LogBuffer> = OnceLock::new();
+    GLOBAL.get_or_init(|| LogBuffer::new(DEFAULT_CAPACITY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_since_filters_and_tracks_cursor() {
+        let buffer = LogBuffer::new(10);
+        buffer.push(LogLevel::Debug, "vaya_api::router", "routing");
+        buffer.push(LogLevel::Info, "vaya_api::router", "request");
+        buffer.push(LogLevel::Error, "vaya_db::wal", "fsync failed");
+
+        let filter = LogFilter {
+            min_level: LogLevel::Info,
+            target_prefix: Some("vaya_api".into()),
+        };
+        let batch = buffer.read_since(0, &filter, 100);
+        assert_eq!(batch.entries.len(), 1);
+        assert_eq!(batch.entries[0].message, "request");
+        assert_eq!(batch.cursor, 3);
+        assert_eq!(batch.dropped, 0);
+
+        assert!(buffer.read_since(3, &filter, 100).entries.is_empty());
+    }
+
+    #[test]
+    fn test_read_since_reports_dropped_entries() {
+        let buffer = LogBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(LogLevel::Info, "vaya", format!("line {i}"));
+        }
+        // Entries 1 and 2 were evicted
+        let batch = buffer.read_since(0, &LogFilter::default(), 2);
+        assert_eq!(batch.dropped, 3);
+        assert_eq!(batch.entries[0].message, "line 3");
+        assert_eq!(batch.entries[1].seq, 5);
+        assert_eq!(LogLevel::parse("WARNING"), Some(LogLevel::Warn));
+    }
+}
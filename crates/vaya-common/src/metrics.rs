@@ -0,0 +1,239 @@
+//! Process-wide metrics registry
+//!
+//! Subsystems register named counters and gauges (optionally with labels)
+//! and update them lock-free through the returned handles. The registry can
+//! be snapshotted or rendered in the Prometheus text exposition format.
+//!
+//! ```
+//! use vaya_common::metrics;
+//!
+//! let hits = metrics::global().counter("vaya_cache_hits_total", &[("cache", "fares")]);
+//! hits.inc();
+//! assert!(metrics::global().render_prometheus().contains("vaya_cache_hits_total"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+/// Monotonically increasing counter
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    /// Increment by one
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    /// Increment by `n`
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Current value
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Gauge holding an arbitrary floating-point value
+#[derive(Debug, Clone, Default)]
+pub struct Gauge(Arc<AtomicU64>);
+
+impl Gauge {
+    /// Set the value
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Current value
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Kind of a registered metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Monotonic counter
+    Counter,
+    /// Point-in-time gauge
+    Gauge,
+}
+
+impl MetricKind {
+    /// Prometheus type name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+/// A single metric value captured by [`MetricsRegistry::snapshot`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    /// Metric name
+    pub name: String,
+    /// Label pairs, sorted by label name
+    pub labels: Vec<(String, String)>,
+    /// Metric kind
+    pub kind: MetricKind,
+    /// Value at snapshot time
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct MetricKey {
+    name: String,
+    labels: Vec<(String, String)>,
+}
+
+impl MetricKey {
+    fn new(name: &str, labels: &[(&str, &str)]) -> Self {
+        let mut labels: Vec<(String, String)> = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        labels.sort();
+        Self {
+            name: name.to_string(),
+            labels,
+        }
+    }
+}
+
+/// Registry of named metrics
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    counters: RwLock<BTreeMap<MetricKey, Counter>>,
+    gauges: RwLock<BTreeMap<MetricKey, Gauge>>,
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get or register a counter
+    pub fn counter(&self, name: &str, labels: &[(&str, &str)]) -> Counter {
+        let key = MetricKey::new(name, labels);
+        if let Some(counter) = read(&self.counters).get(&key) {
+            return counter.clone();
+        }
+        write(&self.counters).entry(key).or_default().clone()
+    }
+
+    /// Get or register a gauge
+    pub fn gauge(&self, name: &str, labels: &[(&str, &str)]) -> Gauge {
+        let key = MetricKey::new(name, labels);
+        if let Some(gauge) = read(&self.gauges).get(&key) {
+            return gauge.clone();
+        }
+        write(&self.gauges).entry(key).or_default().clone()
+    }
+
+    /// Capture the current value of every metric, ordered by name and labels
+    pub fn snapshot(&self) -> Vec<MetricSample> {
+        let mut samples: Vec<MetricSample> = read(&self.counters)
+            .iter()
+            .map(|(key, counter)| MetricSample {
+                name: key.name.clone(),
+                labels: key.labels.clone(),
+                kind: MetricKind::Counter,
+                value: counter.get() as f64,
+            })
+            .chain(read(&self.gauges).iter().map(|(key, gauge)| MetricSample {
+                name: key.name.clone(),
+                labels: key.labels.clone(),
+                kind: MetricKind::Gauge,
+                value: gauge.get(),
+            }))
+            .collect();
+        samples.sort_by(|a, b| (&a.name, &a.labels).cmp(&(&b.name, &b.labels)));
+        samples
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut last_name: Option<String> = None;
+        for sample in self.snapshot() {
+            if last_name.as_deref() != Some(sample.name.as_str()) {
+                let _ = writeln!(out, "# TYPE {} {}", sample.name, sample.kind.as_str());
+                last_name = Some(sample.name.clone());
+            }
+            out.push_str(&sample.name);
+            if !sample.labels.is_empty() {
+                let labels: Vec<String> = sample
+                    .labels
+                    .iter()
+                    .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                    .collect();
+                let _ = write!(out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(out, " {}", sample.value);
+        }
+        out
+    }
+}
+
+/// The process-wide metrics registry
+pub fn global() -> &'static MetricsRegistry {
+    static GLOBAL: OnceLock<MetricsRegistry> = OnceLock::new();
+    GLOBAL.get_or_init(MetricsRegistry::new)
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn read<T>(lock: &RwLock<T>) -> std::sync::RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> std::sync::RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_handles_share_state() {
+        let registry = MetricsRegistry::new();
+        let a = registry.counter("requests_total", &[("route", "/a"), ("method", "GET")]);
+        let b = registry.counter("requests_total", &[("method", "GET"), ("route", "/a")]);
+        a.inc();
+        b.add(2);
+        assert_eq!(a.get(), 3);
+
+        registry.gauge("queue_depth", &[]).set(4.5);
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].name, "queue_depth");
+        assert_eq!(snapshot[0].value, 4.5);
+        assert_eq!(snapshot[1].kind, MetricKind::Counter);
+        assert_eq!(snapshot[1].value, 3.0);
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let registry = MetricsRegistry::new();
+        registry.counter("hits_total", &[("table", "a\"b")]).inc();
+        registry.counter("hits_total", &[("table", "c")]).add(5);
+
+        let text = registry.render_prometheus();
+        assert_eq!(text.matches("# TYPE hits_total counter").count(), 1);
+        assert!(text.contains("hits_total{table=\"a\\\"b\"} 1\n"));
+        assert!(text.contains("hits_total{table=\"c\"} 5\n"));
+    }
+}
@@ -0,0 +1,480 @@
+//! Money arithmetic
+//!
+//! [`MinorUnits`] gets the `std::ops` operators, which panic on overflow
+//! instead of wrapping, alongside `checked_*` variants that report it.
+//! [`Money`] carries its currency in the type, so adding MYR to USD does not
+//! compile; [`Price`] keeps the currency at runtime and its `try_*` methods
+//! reject mismatches. Percentages and allocations round in integer
+//! arithmetic and never gain or lose a minor unit.
+
+use std::fmt;
+use std::hash::Hash;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::error::{ErrorCode, Result, VayaError};
+use crate::types::{CurrencyCode, MinorUnits, Price};
+
+/// Basis points in 100%
+pub(crate) const BPS: i128 = 10_000;
+
+impl MinorUnits {
+    /// Checked addition, `None` on overflow
+    pub fn checked_add(&self, other: Self) -> Option<Self> {
+        self.as_i64().checked_add(other.as_i64()).map(Self::new)
+    }
+
+    /// Checked subtraction, `None` on overflow
+    pub fn checked_sub(&self, other: Self) -> Option<Self> {
+        self.as_i64().checked_sub(other.as_i64()).map(Self::new)
+    }
+
+    /// Checked multiplication, `None` on overflow
+    pub fn checked_mul(&self, factor: i64) -> Option<Self> {
+        self.as_i64().checked_mul(factor).map(Self::new)
+    }
+
+    /// Share of the amount in basis points, rounded half away from zero
+    ///
+    /// 250 bps of 1999 is 49.975, so 50.
+    pub fn bps(&self, bps: i64) -> Self {
+        let exact = i128::from(self.as_i64()) * i128::from(bps);
+        let rounded = (exact + exact.signum() * BPS / 2) / BPS;
+        Self::new(rounded.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64)
+    }
+
+    /// Share of the amount in whole percent, rounded half away from zero
+    pub fn percent(&self, percent: u32) -> Self {
+        self.bps(i64::from(percent) * 100)
+    }
+
+    /// Split into `parts` shares that differ by at most one minor unit
+    ///
+    /// Earlier shares take the remainder, and the shares always sum to the
+    /// amount. Empty if `parts` is zero.
+    pub fn allocate(&self, parts: usize) -> Vec<Self> {
+        self.allocate_by(&vec![1; parts]).unwrap_or_default()
+    }
+
+    /// Split in proportion to `weights` without losing minor units
+    ///
+    /// Each share is rounded down and the leftover units go to the shares
+    /// with the largest remainders, earliest first. `None` if the weights
+    /// sum to zero.
+    pub fn allocate_by(&self, weights: &[u64]) -> Option<Vec<Self>> {
+        let total: i128 = weights.iter().map(|&w| i128::from(w)).sum();
+        if total == 0 {
+            return None;
+        }
+        let amount = i128::from(self.as_i64());
+        let mut shares: Vec<(i128, i128)> = weights
+            .iter()
+            .map(|&w| {
+                let exact = amount * i128::from(w);
+                (exact.div_euclid(total), exact.rem_euclid(total))
+            })
+            .collect();
+
+        let leftover = amount - shares.iter().map(|(share, _)| share).sum::<i128>();
+        let mut order: Vec<usize> = (0..shares.len()).collect();
+        order.sort_by(|&a, &b| shares[b].1.cmp(&shares[a].1).then(a.cmp(&b)));
+        for &i in order.iter().take(leftover as usize) {
+            shares[i].0 += 1;
+        }
+        Some(
+            shares
+                .into_iter()
+                .map(|(share, _)| Self::new(share as i64))
+                .collect(),
+        )
+    }
+}
+
+impl Add for MinorUnits {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.checked_add(other).expect("MinorUnits overflow")
+    }
+}
+
+impl Sub for MinorUnits {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.checked_sub(other).expect("MinorUnits overflow")
+    }
+}
+
+impl Mul<i64> for MinorUnits {
+    type Output = Self;
+
+    fn mul(self, factor: i64) -> Self {
+        self.checked_mul(factor).expect("MinorUnits overflow")
+    }
+}
+
+impl Neg for MinorUnits {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::ZERO - self
+    }
+}
+
+impl AddAssign for MinorUnits {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for MinorUnits {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for MinorUnits {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, |total, amount| total + amount)
+    }
+}
+
+impl<'a> Sum<&'a MinorUnits> for MinorUnits {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl Price {
+    /// Add prices, failing on a currency mismatch or overflow
+    pub fn try_add(&self, other: &Self) -> Result<Self> {
+        self.same_currency(other)?;
+        let amount = self
+            .amount
+            .checked_add(other.amount)
+            .ok_or_else(|| overflow(self.currency))?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    /// Subtract prices, failing on a currency mismatch or overflow
+    pub fn try_sub(&self, other: &Self) -> Result<Self> {
+        self.same_currency(other)?;
+        let amount = self
+            .amount
+            .checked_sub(other.amount)
+            .ok_or_else(|| overflow(self.currency))?;
+        Ok(Self::new(amount, self.currency))
+    }
+
+    fn same_currency(&self, other: &Self) -> Result<()> {
+        if self.currency != other.currency {
+            return Err(VayaError::new(
+                ErrorCode::InvalidCurrency,
+                format!("Cannot combine {} with {}", self.currency, other.currency),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn overflow(currency: CurrencyCode) -> VayaError {
+    VayaError::new(
+        ErrorCode::InvalidPrice,
+        format!("{} amount out of range", currency),
+    )
+}
+
+/// A currency known at compile time
+pub trait Currency:
+    Copy + Default + fmt::Debug + PartialEq + Eq + PartialOrd + Ord + Hash + Send + Sync + 'static
+{
+    /// ISO 4217 code
+    const CODE: CurrencyCode;
+}
+
+macro_rules! currencies {
+    ($($name:ident => $code:ident, $doc:literal;)*) => {
+        $(
+            #[doc = $doc]
+            #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $name;
+
+            impl Currency for $name {
+                const CODE: CurrencyCode = CurrencyCode::$code;
+            }
+        )*
+    };
+}
+
+currencies! {
+    Myr => MYR, "Malaysian Ringgit";
+    Usd => USD, "US Dollar";
+    Sgd => SGD, "Singapore Dollar";
+    Thb => THB, "Thai Baht";
+    Idr => IDR, "Indonesian Rupiah";
+    Php => PHP, "Philippine Peso";
+    Vnd => VND, "Vietnamese Dong";
+    Jpy => JPY, "Japanese Yen";
+    Krw => KRW, "South Korean Won";
+    Cny => CNY, "Chinese Yuan";
+    Hkd => HKD, "Hong Kong Dollar";
+    Twd => TWD, "New Taiwan Dollar";
+    Aud => AUD, "Australian Dollar";
+    Nzd => NZD, "New Zealand Dollar";
+    Eur => EUR, "Euro";
+    Gbp => GBP, "British Pound";
+}
+
+/// An amount whose currency is part of its type
+///
+/// `Money<Myr> + Money<Usd>` does not compile. Convert to and from
+/// [`Price`] at the edges where the currency is only known at runtime.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money<C: Currency> {
+    amount: MinorUnits,
+    currency: PhantomData<C>,
+}
+
+impl<C: Currency> Money<C> {
+    /// Zero in this currency
+    pub const ZERO: Self = Self {
+        amount: MinorUnits::ZERO,
+        currency: PhantomData,
+    };
+
+    /// Create from an amount in minor units
+    pub fn new(amount: MinorUnits) -> Self {
+        Self {
+            amount,
+            currency: PhantomData,
+        }
+    }
+
+    /// Create from a raw minor-unit value
+    pub fn from_minor(amount: i64) -> Self {
+        Self::new(MinorUnits::new(amount))
+    }
+
+    /// Amount in minor units
+    pub fn amount(&self) -> MinorUnits {
+        self.amount
+    }
+
+    /// Currency code
+    pub fn currency(&self) -> CurrencyCode {
+        C::CODE
+    }
+
+    /// Convert a runtime price, failing if the currency differs
+    pub fn from_price(price: Price) -> Result<Self> {
+        if price.currency != C::CODE {
+            return Err(VayaError::new(
+                ErrorCode::InvalidCurrency,
+                format!("Expected {}, got {}", C::CODE, price.currency),
+            ));
+        }
+        Ok(Self::new(price.amount))
+    }
+
+    /// Price with the currency at runtime
+    pub fn to_price(&self) -> Price {
+        Price::new(self.amount, C::CODE)
+    }
+
+    /// Checked addition, `None` on overflow
+    pub fn checked_add(&self, other: Self) -> Option<Self> {
+        self.amount.checked_add(other.amount).map(Self::new)
+    }
+
+    /// Checked subtraction, `None` on overflow
+    pub fn checked_sub(&self, other: Self) -> Option<Self> {
+        self.amount.checked_sub(other.amount).map(Self::new)
+    }
+
+    /// Share in basis points, rounded half away from zero
+    pub fn bps(&self, bps: i64) -> Self {
+        Self::new(self.amount.bps(bps))
+    }
+
+    /// Share in whole percent, rounded half away from zero
+    pub fn percent(&self, percent: u32) -> Self {
+        Self::new(self.amount.percent(percent))
+    }
+
+    /// Split into `parts` shares that sum to the amount
+    pub fn allocate(&self, parts: usize) -> Vec<Self> {
+        self.amount
+            .allocate(parts)
+            .into_iter()
+            .map(Self::new)
+            .collect()
+    }
+}
+
+impl<C: Currency> From<Money<C>> for Price {
+    fn from(money: Money<C>) -> Self {
+        money.to_price()
+    }
+}
+
+impl<C: Currency> Add for Money<C> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.amount + other.amount)
+    }
+}
+
+impl<C: Currency> Sub for Money<C> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.amount - other.amount)
+    }
+}
+
+impl<C: Currency> Mul<i64> for Money<C> {
+    type Output = Self;
+
+    fn mul(self, factor: i64) -> Self {
+        Self::new(self.amount * factor)
+    }
+}
+
+impl<C: Currency> Neg for Money<C> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.amount)
+    }
+}
+
+impl<C: Currency> AddAssign for Money<C> {
+    fn add_assign(&mut self, other: Self) {
+        self.amount += other.amount;
+    }
+}
+
+impl<C: Currency> SubAssign for Money<C> {
+    fn sub_assign(&mut self, other: Self) {
+        self.amount -= other.amount;
+    }
+}
+
+impl<C: Currency> Sum for Money<C> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        Self::new(iter.map(|m| m.amount).sum())
+    }
+}
+
+impl<C: Currency> fmt::Debug for Money<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Money({} {})", C::CODE.as_str(), self.amount.as_i64())
+    }
+}
+
+impl<C: Currency> fmt::Display for Money<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_price().format())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units(values: &[MinorUnits]) -> Vec<i64> {
+        values.iter().map(|m| m.as_i64()).collect()
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut total = MinorUnits::new(1500) + MinorUnits::new(250) - MinorUnits::new(50);
+        total += MinorUnits::new(100);
+        total -= MinorUnits::new(300);
+        assert_eq!(total, MinorUnits::new(1500));
+        assert_eq!(total * 3, MinorUnits::new(4500));
+        assert_eq!(-total, MinorUnits::new(-1500));
+
+        let amounts = [MinorUnits::new(1), MinorUnits::new(2), MinorUnits::new(3)];
+        assert_eq!(amounts.iter().sum::<MinorUnits>(), MinorUnits::new(6));
+    }
+
+    #[test]
+    fn test_checked_overflow() {
+        let max = MinorUnits::new(i64::MAX);
+        assert!(max.checked_add(MinorUnits::new(1)).is_none());
+        assert!(MinorUnits::new(i64::MIN)
+            .checked_sub(MinorUnits::new(1))
+            .is_none());
+        assert!(max.checked_mul(2).is_none());
+        assert_eq!(max.checked_sub(max), Some(MinorUnits::ZERO));
+        // The saturating helpers are unchanged
+        assert_eq!(MinorUnits::add(&max, MinorUnits::new(1)), max);
+    }
+
+    #[test]
+    #[should_panic(expected = "MinorUnits overflow")]
+    fn test_operator_overflow_panics() {
+        let _ = MinorUnits::new(i64::MAX) + MinorUnits::new(1);
+    }
+
+    #[test]
+    fn test_percentages() {
+        assert_eq!(MinorUnits::new(1999).bps(250), MinorUnits::new(50));
+        assert_eq!(MinorUnits::new(-1999).bps(250), MinorUnits::new(-50));
+        assert_eq!(MinorUnits::new(10_000).percent(15), MinorUnits::new(1500));
+        assert_eq!(
+            MinorUnits::new(i64::MAX).percent(200),
+            MinorUnits::new(i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_allocation_keeps_every_unit() {
+        assert_eq!(units(&MinorUnits::new(100).allocate(3)), [34, 33, 33]);
+        assert_eq!(units(&MinorUnits::new(-100).allocate(3)), [-33, -33, -34]);
+        assert!(MinorUnits::new(100).allocate(0).is_empty());
+
+        let shares = MinorUnits::new(1000).allocate_by(&[1, 1, 1, 3]).unwrap();
+        assert_eq!(units(&shares), [167, 167, 166, 500]);
+        assert_eq!(shares.iter().sum::<MinorUnits>(), MinorUnits::new(1000));
+        assert!(MinorUnits::new(1000).allocate_by(&[0, 0]).is_none());
+    }
+
+    #[test]
+    fn test_price_currency_checks() {
+        let myr = Price::myr(1000);
+        assert_eq!(myr.try_add(&Price::myr(500)).unwrap(), Price::myr(1500));
+        assert_eq!(myr.try_sub(&Price::myr(1500)).unwrap(), Price::myr(-500));
+        let err = myr.try_add(&Price::usd(500)).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidCurrency);
+        let err = Price::myr(i64::MAX).try_add(&myr).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidPrice);
+    }
+
+    #[test]
+    fn test_typed_money() {
+        let fare = Money::<Myr>::from_minor(45_000);
+        let tax = Money::<Myr>::from_minor(5_000);
+        let total = fare + tax;
+        assert_eq!(total.to_string(), "MYR 500.00");
+        assert_eq!(total.currency(), CurrencyCode::MYR);
+        assert_eq!(Price::from(total), Price::myr(50_000));
+
+        let shares = total.allocate(3);
+        assert_eq!(shares.iter().copied().sum::<Money<Myr>>(), total);
+        assert_eq!(total.percent(10), Money::from_minor(5_000));
+
+        assert!(Money::<Myr>::from_price(Price::usd(100)).is_err());
+        assert_eq!(
+            Money::<Usd>::from_price(Price::usd(100))
+                .unwrap()
+                .amount()
+                .as_i64(),
+            100
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Redaction of sensitive values in logs and output
+//!
+//! Card tokens, document numbers, contact details and secrets must never
+//! appear in full in logs. [`Sensitive`] wraps such a value so its `Debug`,
+//! `Display` and serde output are masked; the raw value is only reachable
+//! through [`Sensitive::expose`]. Structs that keep plain fields implement
+//! `Debug` by hand and print those fields through [`Redact::redacted`].
+
+use std::fmt;
+
+/// Placeholder printed for fully masked values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// How much of a value stays visible
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mask {
+    /// Hide everything
+    #[default]
+    Full,
+    /// Show only the last four characters (card and document numbers)
+    LastFour,
+    /// Show the first character of the local part and the domain
+    Email,
+}
+
+/// A value that can print itself masked
+pub trait Redact {
+    /// Write the masked form of the value
+    fn fmt_redacted(&self, mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+
+    /// Borrow the value as a masked `Debug`/`Display` view
+    fn redacted(&self, mask: Mask) -> Redacted<'_, Self> {
+        Redacted { value: self, mask }
+    }
+}
+
+impl Redact for str {
+    fn fmt_redacted(&self, mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match mask {
+            Mask::Full => f.write_str(REDACTED),
+            Mask::LastFour => {
+                let count = self.chars().count();
+                if count <= 4 {
+                    // Showing four of four characters would show everything
+                    return f.write_str("****");
+                }
+                let tail: String = self.chars().skip(count - 4).collect();
+                write!(f, "****{}", tail)
+            }
+            Mask::Email => match self.split_once('@') {
+                Some((local, domain)) if !local.is_empty() => {
+                    let first = local.chars().next().unwrap_or('*');
+                    write!(f, "{}***@{}", first, domain)
+                }
+                _ => f.write_str(REDACTED),
+            },
+        }
+    }
+}
+
+impl Redact for String {
+    fn fmt_redacted(&self, mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt_redacted(mask, f)
+    }
+}
+
+impl Redact for [u8] {
+    fn fmt_redacted(&self, _mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Key material: never show any bytes, only whether it is set
+        if self.is_empty() {
+            f.write_str("[EMPTY]")
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+impl Redact for Vec<u8> {
+    fn fmt_redacted(&self, mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt_redacted(mask, f)
+    }
+}
+
+impl<T: Redact> Redact for Option<T> {
+    fn fmt_redacted(&self, mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Some(value) => {
+                f.write_str("Some(")?;
+                value.fmt_redacted(mask, f)?;
+                f.write_str(")")
+            }
+            None => f.write_str("None"),
+        }
+    }
+}
+
+/// Borrowed masked view of a value, for hand-written `Debug` impls
+pub struct Redacted<'a, T: ?Sized> {
+    value: &'a T,
+    mask: Mask,
+}
+
+impl<T: Redact + ?Sized> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_redacted(self.mask, f)
+    }
+}
+
+impl<T: Redact + ?Sized> fmt::Display for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_redacted(self.mask, f)
+    }
+}
+
+/// Owned sensitive value, masked in `Debug`, `Display` and serde output
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Sensitive<T> {
+    value: T,
+    mask: Mask,
+}
+
+impl<T> Sensitive<T> {
+    /// Wrap a fully masked value
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            mask: Mask::Full,
+        }
+    }
+
+    /// Wrap a value with a specific mask
+    pub fn with_mask(value: T, mask: Mask) -> Self {
+        Self { value, mask }
+    }
+
+    /// Wrap a value showing its last four characters
+    pub fn last_four(value: T) -> Self {
+        Self::with_mask(value, Mask::LastFour)
+    }
+
+    /// Wrap an email address
+    pub fn email(value: T) -> Self {
+        Self::with_mask(value, Mask::Email)
+    }
+
+    /// Access the raw value
+    pub fn expose(&self) -> &T {
+        &self.value
+    }
+
+    /// Unwrap the raw value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Mask used for output
+    pub fn mask(&self) -> Mask {
+        self.mask
+    }
+}
+
+impl<T> From<T> for Sensitive<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: Redact> fmt::Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_redacted(self.mask, f)
+    }
+}
+
+impl<T: Redact> fmt::Display for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_redacted(self.mask, f)
+    }
+}
+
+impl<T: Redact> Redact for Sensitive<T> {
+    fn fmt_redacted(&self, _mask: Mask, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt_redacted(self.mask, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: Redact> serde::Serialize for Sensitive<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Sensitive<T> {
+    // Input is accepted in full; only output is masked
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Sensitive::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masks() {
+        assert_eq!("A12345678".redacted(Mask::LastFour).to_string(), "****5678");
+        assert_eq!("123".redacted(Mask::LastFour).to_string(), "****");
+        assert_eq!(
+            "jane.doe@example.com".redacted(Mask::Email).to_string(),
+            "j***@example.com"
+        );
+        assert_eq!("not-an-email".redacted(Mask::Email).to_string(), REDACTED);
+        assert_eq!(
+            b"secret".to_vec().redacted(Mask::Full).to_string(),
+            REDACTED
+        );
+        assert_eq!(
+            Some("4111111111111111".to_string())
+                .redacted(Mask::LastFour)
+                .to_string(),
+            "Some(****1111)"
+        );
+    }
+
+    #[test]
+    fn test_sensitive_hides_value() {
+        let token = Sensitive::new("tok_live_abc".to_string());
+        assert_eq!(format!("{:?}", token), REDACTED);
+        assert_eq!(format!("{}", token), REDACTED);
+        assert_eq!(token.expose(), "tok_live_abc");
+
+        let email = Sensitive::email("ops@vaya.my".to_string());
+        assert_eq!(format!("{:?}", Some(email)), "Some(o***@vaya.my)");
+    }
+}
@@ -0,0 +1,402 @@
+//! Airport and airline reference data
+//!
+//! [`IataCode`] and [`AirlineCode`] are bare codes. This module maps them to
+//! names, cities, countries, IANA timezones and coordinates from an embedded
+//! dataset, so lookups never leave the process. Distances between airports
+//! are great-circle distances on a spherical Earth.
+
+use crate::types::{AirlineCode, IataCode};
+
+/// Mean Earth radius in kilometres
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// An airport in the reference dataset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AirportInfo {
+    /// IATA airport code
+    pub code: &'static str,
+    /// IATA metro (city) code; equals `code` for single-airport cities
+    pub city_code: &'static str,
+    /// Airport name in English
+    pub name: &'static str,
+    /// City name in English
+    pub city: &'static str,
+    /// ISO 3166-1 alpha-2 country code
+    pub country: &'static str,
+    /// IANA timezone name
+    pub timezone: &'static str,
+    /// Latitude in degrees (north positive)
+    pub latitude: f64,
+    /// Longitude in degrees (east positive)
+    pub longitude: f64,
+}
+
+impl AirportInfo {
+    /// Airport code as an [`IataCode`]
+    pub fn iata(&self) -> IataCode {
+        IataCode::new(self.code)
+    }
+
+    /// Great-circle distance to another airport in kilometres
+    pub fn distance_km(&self, other: &AirportInfo) -> f64 {
+        great_circle_km(
+            self.latitude,
+            self.longitude,
+            other.latitude,
+            other.longitude,
+        )
+    }
+}
+
+/// An airline in the reference dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirlineInfo {
+    /// IATA airline designator
+    pub code: &'static str,
+    /// Airline name
+    pub name: &'static str,
+    /// ISO 3166-1 alpha-2 country code
+    pub country: &'static str,
+}
+
+impl AirlineInfo {
+    /// Designator as an [`AirlineCode`]
+    pub fn airline_code(&self) -> AirlineCode {
+        AirlineCode::new(self.code)
+    }
+}
+
+macro_rules! airport {
+    ($code:literal, $city_code:literal, $name:literal, $city:literal, $country:literal, $tz:literal, $lat:literal, $lon:literal) => {
+        AirportInfo {
+            code: $code,
+            city_code: $city_code,
+            name: $name,
+            city: $city,
+            country: $country,
+            timezone: $tz,
+            latitude: $lat,
+            longitude: $lon,
+        }
+    };
+}
+
+macro_rules! airline {
+    ($code:literal, $name:literal, $country:literal) => {
+        AirlineInfo {
+            code: $code,
+            name: $name,
+            country: $country,
+        }
+    };
+}
+
+/// Embedded airport dataset
+#[rustfmt::skip]
+pub static AIRPORTS: &[AirportInfo] = &[
+    // Malaysia
+    airport!("KUL", "KUL", "Kuala Lumpur International", "Kuala Lumpur", "MY", "Asia/Kuala_Lumpur", 2.7456, 101.7099),
+    airport!("SZB", "KUL", "Sultan Abdul Aziz Shah (Subang)", "Kuala Lumpur", "MY", "Asia/Kuala_Lumpur", 3.1306, 101.5493),
+    airport!("PEN", "PEN", "Penang International", "Penang", "MY", "Asia/Kuala_Lumpur", 5.2971, 100.2770),
+    airport!("BKI", "BKI", "Kota Kinabalu International", "Kota Kinabalu", "MY", "Asia/Kuching", 5.9372, 116.0510),
+    airport!("KCH", "KCH", "Kuching International", "Kuching", "MY", "Asia/Kuching", 1.4847, 110.3470),
+    airport!("LGK", "LGK", "Langkawi International", "Langkawi", "MY", "Asia/Kuala_Lumpur", 6.3297, 99.7287),
+    airport!("JHB", "JHB", "Senai International", "Johor Bahru", "MY", "Asia/Kuala_Lumpur", 1.6413, 103.6700),
+    airport!("MYY", "MYY", "Miri", "Miri", "MY", "Asia/Kuching", 4.3220, 113.9870),
+    airport!("KBR", "KBR", "Sultan Ismail Petra", "Kota Bharu", "MY", "Asia/Kuala_Lumpur", 6.1669, 102.2930),
+    airport!("TGG", "TGG", "Sultan Mahmud", "Kuala Terengganu", "MY", "Asia/Kuala_Lumpur", 5.3826, 103.1030),
+    airport!("IPH", "IPH", "Sultan Azlan Shah", "Ipoh", "MY", "Asia/Kuala_Lumpur", 4.5680, 101.0920),
+    // Singapore
+    airport!("SIN", "SIN", "Changi", "Singapore", "SG", "Asia/Singapore", 1.3644, 103.9915),
+    // Thailand
+    airport!("BKK", "BKK", "Suvarnabhumi", "Bangkok", "TH", "Asia/Bangkok", 13.6900, 100.7501),
+    airport!("DMK", "BKK", "Don Mueang International", "Bangkok", "TH", "Asia/Bangkok", 13.9126, 100.6070),
+    airport!("HKT", "HKT", "Phuket International", "Phuket", "TH", "Asia/Bangkok", 8.1132, 98.3169),
+    airport!("CNX", "CNX", "Chiang Mai International", "Chiang Mai", "TH", "Asia/Bangkok", 18.7668, 98.9626),
+    airport!("USM", "USM", "Samui", "Koh Samui", "TH", "Asia/Bangkok", 9.5479, 100.0623),
+    airport!("KBV", "KBV", "Krabi International", "Krabi", "TH", "Asia/Bangkok", 8.0991, 98.9862),
+    // Indonesia
+    airport!("CGK", "JKT", "Soekarno-Hatta International", "Jakarta", "ID", "Asia/Jakarta", -6.1256, 106.6559),
+    airport!("HLP", "JKT", "Halim Perdanakusuma", "Jakarta", "ID", "Asia/Jakarta", -6.2666, 106.8910),
+    airport!("DPS", "DPS", "Ngurah Rai International", "Bali", "ID", "Asia/Makassar", -8.7482, 115.1670),
+    airport!("SUB", "SUB", "Juanda International", "Surabaya", "ID", "Asia/Jakarta", -7.3798, 112.7870),
+    airport!("KNO", "MES", "Kualanamu International", "Medan", "ID", "Asia/Jakarta", 3.6422, 98.8853),
+    airport!("YIA", "JOG", "Yogyakarta International", "Yogyakarta", "ID", "Asia/Jakarta", -7.9000, 110.0570),
+    // Philippines
+    airport!("MNL", "MNL", "Ninoy Aquino International", "Manila", "PH", "Asia/Manila", 14.5086, 121.0194),
+    airport!("CEB", "CEB", "Mactan-Cebu International", "Cebu", "PH", "Asia/Manila", 10.3075, 123.9790),
+    // Vietnam
+    airport!("SGN", "SGN", "Tan Son Nhat International", "Ho Chi Minh City", "VN", "Asia/Ho_Chi_Minh", 10.8188, 106.6520),
+    airport!("HAN", "HAN", "Noi Bai International", "Hanoi", "VN", "Asia/Ho_Chi_Minh", 21.2212, 105.8070),
+    airport!("DAD", "DAD", "Da Nang International", "Da Nang", "VN", "Asia/Ho_Chi_Minh", 16.0439, 108.1990),
+    // Cambodia, Myanmar, Brunei, Laos
+    airport!("PNH", "PNH", "Phnom Penh International", "Phnom Penh", "KH", "Asia/Phnom_Penh", 11.5466, 104.8440),
+    airport!("SAI", "REP", "Siem Reap-Angkor International", "Siem Reap", "KH", "Asia/Phnom_Penh", 13.3708, 104.2240),
+    airport!("RGN", "RGN", "Yangon International", "Yangon", "MM", "Asia/Yangon", 16.9073, 96.1332),
+    airport!("BWN", "BWN", "Brunei International", "Bandar Seri Begawan", "BN", "Asia/Brunei", 4.9442, 114.9280),
+    airport!("VTE", "VTE", "Wattay International", "Vientiane", "LA", "Asia/Vientiane", 17.9883, 102.5630),
+    // East Asia
+    airport!("HKG", "HKG", "Hong Kong International", "Hong Kong", "HK", "Asia/Hong_Kong", 22.3080, 113.9185),
+    airport!("MFM", "MFM", "Macau International", "Macau", "MO", "Asia/Macau", 22.1496, 113.5920),
+    airport!("TPE", "TPE", "Taoyuan International", "Taipei", "TW", "Asia/Taipei", 25.0777, 121.2330),
+    airport!("TSA", "TPE", "Songshan", "Taipei", "TW", "Asia/Taipei", 25.0694, 121.5520),
+    airport!("PVG", "SHA", "Pudong International", "Shanghai", "CN", "Asia/Shanghai", 31.1434, 121.8050),
+    airport!("SHA", "SHA", "Hongqiao International", "Shanghai", "CN", "Asia/Shanghai", 31.1979, 121.3360),
+    airport!("PEK", "BJS", "Capital International", "Beijing", "CN", "Asia/Shanghai", 40.0799, 116.6031),
+    airport!("PKX", "BJS", "Daxing International", "Beijing", "CN", "Asia/Shanghai", 39.5098, 116.4105),
+    airport!("CAN", "CAN", "Baiyun International", "Guangzhou", "CN", "Asia/Shanghai", 23.3924, 113.2990),
+    airport!("SZX", "SZX", "Bao'an International", "Shenzhen", "CN", "Asia/Shanghai", 22.6393, 113.8107),
+    airport!("NRT", "TYO", "Narita International", "Tokyo", "JP", "Asia/Tokyo", 35.7720, 140.3929),
+    airport!("HND", "TYO", "Haneda", "Tokyo", "JP", "Asia/Tokyo", 35.5494, 139.7798),
+    airport!("KIX", "OSA", "Kansai International", "Osaka", "JP", "Asia/Tokyo", 34.4347, 135.2440),
+    airport!("ITM", "OSA", "Itami", "Osaka", "JP", "Asia/Tokyo", 34.7855, 135.4380),
+    airport!("FUK", "FUK", "Fukuoka", "Fukuoka", "JP", "Asia/Tokyo", 33.5859, 130.4510),
+    airport!("CTS", "SPK", "New Chitose", "Sapporo", "JP", "Asia/Tokyo", 42.7752, 141.6920),
+    airport!("OKA", "OKA", "Naha", "Okinawa", "JP", "Asia/Tokyo", 26.1958, 127.6460),
+    airport!("ICN", "SEL", "Incheon International", "Seoul", "KR", "Asia/Seoul", 37.4602, 126.4407),
+    airport!("GMP", "SEL", "Gimpo International", "Seoul", "KR", "Asia/Seoul", 37.5583, 126.7906),
+    airport!("PUS", "PUS", "Gimhae International", "Busan", "KR", "Asia/Seoul", 35.1795, 128.9382),
+    airport!("CJU", "CJU", "Jeju International", "Jeju", "KR", "Asia/Seoul", 33.5113, 126.4930),
+    // South Asia
+    airport!("DEL", "DEL", "Indira Gandhi International", "Delhi", "IN", "Asia/Kolkata", 28.5562, 77.1000),
+    airport!("BOM", "BOM", "Chhatrapati Shivaji Maharaj International", "Mumbai", "IN", "Asia/Kolkata", 19.0896, 72.8656),
+    airport!("MAA", "MAA", "Chennai International", "Chennai", "IN", "Asia/Kolkata", 12.9941, 80.1709),
+    airport!("BLR", "BLR", "Kempegowda International", "Bengaluru", "IN", "Asia/Kolkata", 13.1986, 77.7066),
+    airport!("CMB", "CMB", "Bandaranaike International", "Colombo", "LK", "Asia/Colombo", 7.1808, 79.8841),
+    airport!("DAC", "DAC", "Hazrat Shahjalal International", "Dhaka", "BD", "Asia/Dhaka", 23.8433, 90.3978),
+    airport!("KTM", "KTM", "Tribhuvan International", "Kathmandu", "NP", "Asia/Kathmandu", 27.6966, 85.3591),
+    airport!("MLE", "MLE", "Velana International", "Male", "MV", "Indian/Maldives", 4.1918, 73.5291),
+    // Middle East
+    airport!("DXB", "DXB", "Dubai International", "Dubai", "AE", "Asia/Dubai", 25.2532, 55.3657),
+    airport!("DWC", "DXB", "Al Maktoum International", "Dubai", "AE", "Asia/Dubai", 24.8960, 55.1614),
+    airport!("AUH", "AUH", "Zayed International", "Abu Dhabi", "AE", "Asia/Dubai", 24.4330, 54.6511),
+    airport!("DOH", "DOH", "Hamad International", "Doha", "QA", "Asia/Qatar", 25.2731, 51.6081),
+    airport!("JED", "JED", "King Abdulaziz International", "Jeddah", "SA", "Asia/Riyadh", 21.6796, 39.1565),
+    airport!("MED", "MED", "Prince Mohammad bin Abdulaziz", "Medina", "SA", "Asia/Riyadh", 24.5534, 39.7051),
+    airport!("IST", "IST", "Istanbul", "Istanbul", "TR", "Europe/Istanbul", 41.2753, 28.7519),
+    // Oceania
+    airport!("SYD", "SYD", "Kingsford Smith", "Sydney", "AU", "Australia/Sydney", -33.9399, 151.1753),
+    airport!("MEL", "MEL", "Tullamarine", "Melbourne", "AU", "Australia/Melbourne", -37.6690, 144.8410),
+    airport!("BNE", "BNE", "Brisbane", "Brisbane", "AU", "Australia/Brisbane", -27.3842, 153.1175),
+    airport!("PER", "PER", "Perth", "Perth", "AU", "Australia/Perth", -31.9385, 115.9672),
+    airport!("AKL", "AKL", "Auckland", "Auckland", "NZ", "Pacific/Auckland", -37.0082, 174.7850),
+    // Europe
+    airport!("LHR", "LON", "Heathrow", "London", "GB", "Europe/London", 51.4700, -0.4543),
+    airport!("LGW", "LON", "Gatwick", "London", "GB", "Europe/London", 51.1537, -0.1821),
+    airport!("STN", "LON", "Stansted", "London", "GB", "Europe/London", 51.8860, 0.2389),
+    airport!("CDG", "PAR", "Charles de Gaulle", "Paris", "FR", "Europe/Paris", 49.0097, 2.5479),
+    airport!("ORY", "PAR", "Orly", "Paris", "FR", "Europe/Paris", 48.7262, 2.3652),
+    airport!("AMS", "AMS", "Schiphol", "Amsterdam", "NL", "Europe/Amsterdam", 52.3105, 4.7683),
+    airport!("FRA", "FRA", "Frankfurt", "Frankfurt", "DE", "Europe/Berlin", 50.0379, 8.5622),
+    airport!("MUC", "MUC", "Munich", "Munich", "DE", "Europe/Berlin", 48.3538, 11.7861),
+    airport!("ZRH", "ZRH", "Zurich", "Zurich", "CH", "Europe/Zurich", 47.4582, 8.5555),
+    airport!("FCO", "ROM", "Leonardo da Vinci-Fiumicino", "Rome", "IT", "Europe/Rome", 41.8003, 12.2389),
+    airport!("MAD", "MAD", "Adolfo Suárez Madrid-Barajas", "Madrid", "ES", "Europe/Madrid", 40.4983, -3.5676),
+    airport!("BCN", "BCN", "Josep Tarradellas Barcelona-El Prat", "Barcelona", "ES", "Europe/Madrid", 41.2974, 2.0833),
+    // Americas
+    airport!("JFK", "NYC", "John F. Kennedy International", "New York", "US", "America/New_York", 40.6413, -73.7781),
+    airport!("EWR", "NYC", "Newark Liberty International", "New York", "US", "America/New_York", 40.6895, -74.1745),
+    airport!("LGA", "NYC", "LaGuardia", "New York", "US", "America/New_York", 40.7769, -73.8740),
+    airport!("LAX", "LAX", "Los Angeles International", "Los Angeles", "US", "America/Los_Angeles", 33.9416, -118.4085),
+    airport!("SFO", "SFO", "San Francisco International", "San Francisco", "US", "America/Los_Angeles", 37.6213, -122.3790),
+    airport!("YVR", "YVR", "Vancouver International", "Vancouver", "CA", "America/Vancouver", 49.1967, -123.1815),
+];
+
+/// Embedded airline dataset
+#[rustfmt::skip]
+pub static AIRLINES: &[AirlineInfo] = &[
+    // Southeast Asia
+    airline!("MH", "Malaysia Airlines", "MY"),
+    airline!("AK", "AirAsia", "MY"),
+    airline!("D7", "AirAsia X", "MY"),
+    airline!("OD", "Batik Air Malaysia", "MY"),
+    airline!("FY", "Firefly", "MY"),
+    airline!("SQ", "Singapore Airlines", "SG"),
+    airline!("TR", "Scoot", "SG"),
+    airline!("TG", "Thai Airways", "TH"),
+    airline!("FD", "Thai AirAsia", "TH"),
+    airline!("PG", "Bangkok Airways", "TH"),
+    airline!("DD", "Nok Air", "TH"),
+    airline!("GA", "Garuda Indonesia", "ID"),
+    airline!("QZ", "Indonesia AirAsia", "ID"),
+    airline!("JT", "Lion Air", "ID"),
+    airline!("PR", "Philippine Airlines", "PH"),
+    airline!("5J", "Cebu Pacific", "PH"),
+    airline!("VN", "Vietnam Airlines", "VN"),
+    airline!("VJ", "VietJet Air", "VN"),
+    // East Asia
+    airline!("CX", "Cathay Pacific", "HK"),
+    airline!("UO", "HK Express", "HK"),
+    airline!("CI", "China Airlines", "TW"),
+    airline!("BR", "EVA Air", "TW"),
+    airline!("CA", "Air China", "CN"),
+    airline!("MU", "China Eastern Airlines", "CN"),
+    airline!("CZ", "China Southern Airlines", "CN"),
+    airline!("NH", "All Nippon Airways", "JP"),
+    airline!("JL", "Japan Airlines", "JP"),
+    airline!("MM", "Peach Aviation", "JP"),
+    airline!("KE", "Korean Air", "KR"),
+    airline!("OZ", "Asiana Airlines", "KR"),
+    airline!("7C", "Jeju Air", "KR"),
+    // South Asia and Middle East
+    airline!("AI", "Air India", "IN"),
+    airline!("6E", "IndiGo", "IN"),
+    airline!("UL", "SriLankan Airlines", "LK"),
+    airline!("EK", "Emirates", "AE"),
+    airline!("EY", "Etihad Airways", "AE"),
+    airline!("QR", "Qatar Airways", "QA"),
+    airline!("SV", "Saudia", "SA"),
+    airline!("TK", "Turkish Airlines", "TR"),
+    // Oceania
+    airline!("QF", "Qantas", "AU"),
+    airline!("JQ", "Jetstar", "AU"),
+    airline!("NZ", "Air New Zealand", "NZ"),
+    // Europe and Americas
+    airline!("BA", "British Airways", "GB"),
+    airline!("AF", "Air France", "FR"),
+    airline!("KL", "KLM Royal Dutch Airlines", "NL"),
+    airline!("LH", "Lufthansa", "DE"),
+    airline!("LX", "Swiss International Air Lines", "CH"),
+    airline!("AA", "American Airlines", "US"),
+    airline!("DL", "Delta Air Lines", "US"),
+    airline!("UA", "United Airlines", "US"),
+    airline!("AC", "Air Canada", "CA"),
+];
+
+/// Look up an airport by IATA code (case-insensitive)
+pub fn airport(code: &str) -> Option<&'static AirportInfo> {
+    AIRPORTS.iter().find(|a| a.code.eq_ignore_ascii_case(code))
+}
+
+/// Look up an airline by IATA designator (case-insensitive)
+pub fn airline(code: &str) -> Option<&'static AirlineInfo> {
+    AIRLINES.iter().find(|a| a.code.eq_ignore_ascii_case(code))
+}
+
+/// All airports serving a metro code, e.g. NRT and HND for TYO
+///
+/// A single-airport city code returns that airport.
+pub fn city_airports(city_code: &str) -> Vec<&'static AirportInfo> {
+    AIRPORTS
+        .iter()
+        .filter(|a| a.city_code.eq_ignore_ascii_case(city_code))
+        .collect()
+}
+
+/// All airports in a country
+pub fn country_airports(country: &str) -> Vec<&'static AirportInfo> {
+    AIRPORTS
+        .iter()
+        .filter(|a| a.country.eq_ignore_ascii_case(country))
+        .collect()
+}
+
+/// Airlines whose designator equals the query or whose name has a word
+/// starting with it ("jet" matches "Jetstar" but not "VietJet Air")
+pub fn find_airlines(query: &str, limit: usize) -> Vec<&'static AirlineInfo> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let (mut exact, mut prefix): (Vec<_>, Vec<_>) = AIRLINES
+        .iter()
+        .filter(|a| {
+            a.code.eq_ignore_ascii_case(&query)
+                || a.name
+                    .to_lowercase()
+                    .split_whitespace()
+                    .any(|word| word.starts_with(&query))
+        })
+        .partition(|a| a.code.eq_ignore_ascii_case(&query));
+    exact.append(&mut prefix);
+    exact.truncate(limit);
+    exact
+}
+
+/// Great-circle distance in kilometres between two coordinates in degrees
+///
+/// Uses the haversine formula, which stays accurate for short distances.
+pub fn great_circle_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// Great-circle distance between two airports, if both are known
+pub fn distance_km(from: &IataCode, to: &IataCode) -> Option<f64> {
+    Some(airport(from.as_str())?.distance_km(airport(to.as_str())?))
+}
+
+impl IataCode {
+    /// Reference data for this airport
+    pub fn airport(&self) -> Option<&'static AirportInfo> {
+        airport(self.as_str())
+    }
+}
+
+impl AirlineCode {
+    /// Reference data for this airline
+    pub fn airline(&self) -> Option<&'static AirlineInfo> {
+        airline(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookups() {
+        let kul = IataCode::KUL.airport().unwrap();
+        assert_eq!(kul.name, "Kuala Lumpur International");
+        assert_eq!(kul.timezone, "Asia/Kuala_Lumpur");
+        assert_eq!(kul.iata(), IataCode::KUL);
+        assert_eq!(airport("nrt").unwrap().city, "Tokyo");
+        assert!(airport("XXX").is_none());
+
+        assert_eq!(AirlineCode::MH.airline().unwrap().name, "Malaysia Airlines");
+        assert_eq!(airline("sq").unwrap().airline_code(), AirlineCode::SQ);
+
+        let tokyo: Vec<&str> = city_airports("TYO").iter().map(|a| a.code).collect();
+        assert_eq!(tokyo, ["NRT", "HND"]);
+        assert_eq!(country_airports("sg").len(), 1);
+    }
+
+    #[test]
+    fn test_dataset_is_consistent() {
+        for (i, a) in AIRPORTS.iter().enumerate() {
+            assert!(a.iata().is_valid(), "{}", a.code);
+            assert!((-90.0..=90.0).contains(&a.latitude), "{}", a.code);
+            assert!((-180.0..=180.0).contains(&a.longitude), "{}", a.code);
+            assert!(a.timezone.contains('/'), "{}", a.code);
+            assert!(!city_airports(a.city_code).is_empty());
+            assert!(AIRPORTS[..i].iter().all(|b| b.code != a.code), "{}", a.code);
+        }
+        for (i, a) in AIRLINES.iter().enumerate() {
+            assert_eq!(a.code.len(), 2);
+            assert!(AIRLINES[..i].iter().all(|b| b.code != a.code), "{}", a.code);
+        }
+    }
+
+    #[test]
+    fn test_great_circle_distance() {
+        let km = distance_km(&IataCode::KUL, &IataCode::SIN).unwrap();
+        assert!((290.0..300.0).contains(&km), "{}", km);
+        let km = distance_km(&IataCode::LHR, &IataCode::JFK).unwrap();
+        assert!((5500.0..5600.0).contains(&km), "{}", km);
+        assert_eq!(distance_km(&IataCode::KUL, &IataCode::KUL), Some(0.0));
+        assert!(distance_km(&IataCode::KUL, &IataCode::new("XXX")).is_none());
+    }
+
+    #[test]
+    fn test_find_airlines() {
+        let names: Vec<&str> = find_airlines("air", 3).iter().map(|a| a.name).collect();
+        assert_eq!(names, ["Malaysia Airlines", "AirAsia", "AirAsia X"]);
+        let names: Vec<&str> = find_airlines("jet", 5).iter().map(|a| a.name).collect();
+        assert_eq!(names, ["Jetstar"]);
+        assert_eq!(find_airlines("ek", 5)[0].name, "Emirates");
+        assert!(find_airlines("  ", 5).is_empty());
+    }
+}
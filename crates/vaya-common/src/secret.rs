@@ -0,0 +1,86 @@
+//! References to secrets kept outside configuration
+//!
+//! A config value of the form `secret://name` stands for the secret stored
+//! under `name` rather than for itself, so API keys and signing secrets
+//! need not sit in plain environment variables or config files. Any
+//! [`SecretSource`] can resolve references; the sealed secrets file in
+//! `vaya-crypto` is the usual one.
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Prefix marking a value as a secret reference
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Looks up secrets by name
+pub trait SecretSource {
+    /// The secret stored under `name`, if any
+    fn secret(&self, name: &str) -> Option<String>;
+}
+
+impl SecretSource for HashMap<String, String> {
+    fn secret(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
+
+/// Name of the secret `value` refers to, if it is a reference
+pub fn secret_name(value: &str) -> Option<&str> {
+    value.strip_prefix(SECRET_SCHEME)
+}
+
+/// Replace `value` with the secret it refers to
+///
+/// Values that are not references are left as they are.
+pub fn resolve_secret(
+    value: &mut String,
+    source: &dyn SecretSource,
+) -> Result<(), UnresolvedSecret> {
+    if let Some(name) = secret_name(value) {
+        let secret = source.secret(name).ok_or_else(|| UnresolvedSecret {
+            name: name.to_string(),
+        })?;
+        *value = secret;
+    }
+    Ok(())
+}
+
+/// A secret reference naming a secret that doesn't exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedSecret {
+    /// Name referred to
+    pub name: String,
+}
+
+impl fmt::Display for UnresolvedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no secret named `{}`", self.name)
+    }
+}
+
+impl std::error::Error for UnresolvedSecret {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_secret() {
+        let source: HashMap<String, String> =
+            [("stripe/secret_key".to_string(), "sk_live_abc".to_string())].into();
+
+        let mut value = "secret://stripe/secret_key".to_string();
+        resolve_secret(&mut value, &source).unwrap();
+        assert_eq!(value, "sk_live_abc");
+
+        // Plain values pass through
+        let mut value = "sk_test_123".to_string();
+        resolve_secret(&mut value, &source).unwrap();
+        assert_eq!(value, "sk_test_123");
+
+        let mut value = "secret://missing".to_string();
+        let err = resolve_secret(&mut value, &source).unwrap_err();
+        assert_eq!(err.to_string(), "no secret named `missing`");
+        assert_eq!(value, "secret://missing");
+    }
+}
@@ -0,0 +1,421 @@
+//! Serde support (`serde` feature)
+//!
+//! Types serialize to the strings the API already uses: codes as
+//! upper-case strings, dates as `YYYY-MM-DD`, timestamps as RFC 3339,
+//! routes as `KUL-NRT`, UUIDs hyphenated and enums by their `as_str`
+//! names. Prices are `{"amount": <minor units>, "currency": "MYR"}`.
+//! Deserialization validates and is case-insensitive where the API is.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::enums::*;
+use crate::money::{Currency, Money};
+use crate::types::{
+    AirlineCode, CurrencyCode, Date, IataCode, MinorUnits, Price, Route, Timestamp, Uuid,
+};
+use crate::zoned::{TimeZone, ZonedTime};
+
+/// Deserialize a string and parse it, naming `expected` on failure
+fn parse_str<'de, D, T>(
+    deserializer: D,
+    expected: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    parse(s.trim()).ok_or_else(|| de::Error::custom(format!("invalid {}: {:?}", expected, s)))
+}
+
+/// Serialize and deserialize through `as_str` and a parser
+macro_rules! string_serde {
+    ($ty:ty, $expected:expr, $parse:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                parse_str(deserializer, $expected, $parse)
+            }
+        }
+    };
+}
+
+/// Enums by their `as_str` names, matched case-insensitively
+macro_rules! enum_serde {
+    ($($ty:ident { $($variant:ident),* $(,)? })*) => {
+        $(
+            string_serde!($ty, stringify!($ty), |s: &str| {
+                [$($ty::$variant),*]
+                    .into_iter()
+                    .find(|v| v.as_str().eq_ignore_ascii_case(s))
+            });
+        )*
+    };
+}
+
+string_serde!(IataCode, "IATA code", |s: &str| Some(IataCode::new(s))
+    .filter(|code| s.len() == 3 && code.is_valid()));
+
+string_serde!(CurrencyCode, "currency code", |s: &str| {
+    (s.len() == 3 && s.bytes().all(|b| b.is_ascii_alphabetic())).then(|| CurrencyCode::new(s))
+});
+
+string_serde!(AirlineCode, "airline code", |s: &str| {
+    (s.len() == 2 && s.bytes().all(|b| b.is_ascii_alphanumeric())).then(|| AirlineCode::new(s))
+});
+
+enum_serde! {
+    UserStatus { Anonymous, Registered, Premium, Churned, Suspended, Deleted }
+    UserTier { Free, Premium, Enterprise }
+    TripType { OneWay, RoundTrip, MultiCity }
+    CabinClass { Economy, PremiumEconomy, Business, First }
+    TravelerType { Adult, Child, Infant }
+    PaymentStatus {
+        Pending, Processing, RequiresAction, Completed, Failed, Refunded,
+        PartiallyRefunded, Disputed,
+    }
+    PaymentMethod { Card, Fpx, GrabPay, TouchNGo, Boost, ShopeePay }
+    AlertStatus { Active, Triggered, Paused, Expired, Deleted }
+    OracleRecommendation { BuyNow, Wait, Watch }
+    OfferSource { Kiwi, Travelpayouts, Amadeus, Duffel, Direct }
+    OAuthProvider { Google, Apple, Facebook }
+    Gender { Unknown, Male, Female, Other }
+}
+
+string_serde!(BookingStatus, "BookingStatus", BookingStatus::parse);
+string_serde!(PoolStatus, "PoolStatus", PoolStatus::parse);
+
+impl Serialize for MinorUnits {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.as_i64())
+    }
+}
+
+impl<'de> Deserialize<'de> for MinorUnits {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(MinorUnits::new)
+    }
+}
+
+impl Serialize for Price {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut price = serializer.serialize_struct("Price", 2)?;
+        price.serialize_field("amount", &self.amount)?;
+        price.serialize_field("currency", &self.currency)?;
+        price.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Price {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        const FIELDS: &[&str] = &["amount", "currency"];
+
+        struct PriceVisitor;
+
+        impl<'de> Visitor<'de> for PriceVisitor {
+            type Value = Price;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a price with amount and currency")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Price, A::Error> {
+                let mut amount = None;
+                let mut currency = None;
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "amount" => amount = Some(map.next_value()?),
+                        "currency" => currency = Some(map.next_value()?),
+                        other => return Err(de::Error::unknown_field(other, FIELDS)),
+                    }
+                }
+                let amount = amount.ok_or_else(|| de::Error::missing_field("amount"))?;
+                let currency = currency.ok_or_else(|| de::Error::missing_field("currency"))?;
+                Ok(Price::new(amount, currency))
+            }
+        }
+
+        deserializer.deserialize_struct("Price", FIELDS, PriceVisitor)
+    }
+}
+
+impl<C: Currency> Serialize for Money<C> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_price().serialize(serializer)
+    }
+}
+
+impl<'de, C: Currency> Deserialize<'de> for Money<C> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let price = Price::deserialize(deserializer)?;
+        Money::from_price(price).map_err(|e| de::Error::custom(e.message))
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    /// Accepts RFC 3339 strings or Unix seconds
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TimestampVisitor;
+
+        impl<'de> Visitor<'de> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an RFC 3339 timestamp or Unix seconds")
+            }
+
+            fn visit_i64<E: de::Error>(self, secs: i64) -> Result<Timestamp, E> {
+                Ok(Timestamp::from_unix(secs))
+            }
+
+            fn visit_u64<E: de::Error>(self, secs: u64) -> Result<Timestamp, E> {
+                i64::try_from(secs)
+                    .map(Timestamp::from_unix)
+                    .map_err(|_| E::custom("timestamp out of range"))
+            }
+
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Timestamp, E> {
+                OffsetDateTime::parse(s, &Rfc3339)
+                    .map(|t| Timestamp::from_unix(t.unix_timestamp()))
+                    .map_err(|_| E::custom(format!("invalid timestamp: {:?}", s)))
+            }
+        }
+
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+}
+
+impl Serialize for ZonedTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ZonedTime {
+    /// The zone becomes the fixed offset given in the string
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_str(deserializer, "zoned time", |s| {
+            let t = OffsetDateTime::parse(s, &Rfc3339).ok()?;
+            Some(ZonedTime::new(
+                Timestamp::from_unix(t.unix_timestamp()),
+                TimeZone::Fixed(t.offset().whole_seconds()),
+            ))
+        })
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_str(deserializer, "date", |s| {
+            let mut parts = s.split('-');
+            let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+            if parts.next().is_some() || year.len() != 4 || month.len() != 2 || day.len() != 2 {
+                return None;
+            }
+            let date = Date::new(year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+            date.is_valid().then_some(date)
+        })
+    }
+}
+
+impl Serialize for Route {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string_compact())
+    }
+}
+
+impl<'de> Deserialize<'de> for Route {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_str(deserializer, "route", |s| {
+            let (origin, destination) = s.split_once('-')?;
+            if origin.len() != 3 || destination.len() != 3 {
+                return None;
+            }
+            let route = Route::from_codes(origin, destination);
+            route.is_valid().then_some(route)
+        })
+    }
+}
+
+impl Serialize for Uuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string_hyphenated())
+    }
+}
+
+impl<'de> Deserialize<'de> for Uuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_str(deserializer, "UUID", Uuid::parse)
+    }
+}
+
+/// Channel names, in bit order
+const CHANNELS: [(u8, &str); 3] = [
+    (NotificationChannels::EMAIL, "email"),
+    (NotificationChannels::PUSH, "push"),
+    (NotificationChannels::SMS, "sms"),
+];
+
+impl Serialize for NotificationChannels {
+    /// A list of channel names, e.g. `["email", "sms"]`
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let enabled: Vec<&str> = CHANNELS
+            .iter()
+            .filter(|(bit, _)| self.as_u8() & bit != 0)
+            .map(|&(_, name)| name)
+            .collect();
+        let mut seq = serializer.serialize_seq(Some(enabled.len()))?;
+        for name in enabled {
+            seq.serialize_element(name)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for NotificationChannels {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ChannelsVisitor;
+
+        impl<'de> Visitor<'de> for ChannelsVisitor {
+            type Value = NotificationChannels;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a list of notification channels")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<NotificationChannels, A::Error> {
+                let mut channels = NotificationChannels::new();
+                while let Some(name) = seq.next_element::<String>()? {
+                    channels = match name.to_ascii_lowercase().as_str() {
+                        "email" => channels.with_email(),
+                        "push" => channels.with_push(),
+                        "sms" => channels.with_sms(),
+                        _ => {
+                            return Err(de::Error::unknown_variant(
+                                &name,
+                                &["email", "push", "sms"],
+                            ))
+                        }
+                    };
+                }
+                Ok(channels)
+            }
+        }
+
+        deserializer.deserialize_seq(ChannelsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T>(value: T, json: &str)
+    where
+        T: Serialize + for<'de> Deserialize<'de> + PartialEq + fmt::Debug,
+    {
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        assert_eq!(serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    #[test]
+    fn test_codes_and_ids() {
+        round_trip(IataCode::KUL, r#""KUL""#);
+        round_trip(CurrencyCode::MYR, r#""MYR""#);
+        round_trip(AirlineCode::MH, r#""MH""#);
+        round_trip(Route::new(IataCode::KUL, IataCode::NRT), r#""KUL-NRT""#);
+
+        assert_eq!(
+            serde_json::from_str::<IataCode>(r#""kul""#).unwrap(),
+            IataCode::KUL
+        );
+        assert!(serde_json::from_str::<IataCode>(r#""KULX""#).is_err());
+        assert!(serde_json::from_str::<CurrencyCode>(r#""M1R""#).is_err());
+        assert!(serde_json::from_str::<Route>(r#""KUL""#).is_err());
+
+        let id = Uuid::parse("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        round_trip(id, r#""550e8400-e29b-41d4-a716-446655440000""#);
+    }
+
+    #[test]
+    fn test_money() {
+        round_trip(MinorUnits::new(-250), "-250");
+        round_trip(Price::myr(45_000), r#"{"amount":45000,"currency":"MYR"}"#);
+        assert!(serde_json::from_str::<Price>(r#"{"amount":1}"#).is_err());
+
+        round_trip(
+            Money::<crate::money::Usd>::from_minor(999),
+            r#"{"amount":999,"currency":"USD"}"#,
+        );
+        let err =
+            serde_json::from_str::<Money<crate::money::Myr>>(r#"{"amount":999,"currency":"USD"}"#);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_dates_and_times() {
+        round_trip(Date::new(2030, 6, 1), r#""2030-06-01""#);
+        assert!(serde_json::from_str::<Date>(r#""2030-02-30""#).is_err());
+
+        round_trip(
+            Timestamp::from_unix(1_900_000_000),
+            r#""2030-03-17T17:46:40Z""#,
+        );
+        let ts: Timestamp = serde_json::from_str("1900000000").unwrap();
+        assert_eq!(ts.as_unix(), 1_900_000_000);
+        let ts: Timestamp = serde_json::from_str(r#""2030-03-18T01:46:40+08:00""#).unwrap();
+        assert_eq!(ts.as_unix(), 1_900_000_000);
+
+        let zoned: ZonedTime = serde_json::from_str(r#""2030-03-18T01:46:40+08:00""#).unwrap();
+        assert_eq!(zoned.offset(), 8 * 3600);
+        assert_eq!(
+            serde_json::to_string(&zoned).unwrap(),
+            r#""2030-03-18T01:46:40+08:00""#
+        );
+    }
+
+    #[test]
+    fn test_enums() {
+        round_trip(CabinClass::PremiumEconomy, r#""premium_economy""#);
+        round_trip(BookingStatus::PaymentReceived, r#""payment_received""#);
+        round_trip(PoolStatus::BiddingClosed, r#""bidding_closed""#);
+        round_trip(PaymentMethod::TouchNGo, r#""touchngo""#);
+        assert_eq!(
+            serde_json::from_str::<TripType>(r#""ROUND_TRIP""#).unwrap(),
+            TripType::RoundTrip
+        );
+        assert!(serde_json::from_str::<Gender>(r#""m""#).is_err());
+
+        round_trip(
+            NotificationChannels::new().with_email().with_sms(),
+            r#"["email","sms"]"#,
+        );
+        assert!(serde_json::from_str::<NotificationChannels>(r#"["fax"]"#).is_err());
+    }
+}
@@ -0,0 +1,803 @@
+//! Passenger name transliteration to passport (MRZ) form
+//!
+//! Airline reservations carry names the way the passport's machine-readable
+//! zone prints them: A-Z and spaces only. Names typed in another script, or
+//! with diacritics, are converted here following ICAO Doc 9303 where it has
+//! a rule (Ä → AE, Ж → ZH, apostrophes dropped, hyphens become spaces) and
+//! national conventions elsewhere (Hanyu Pinyin, Revised Romanization,
+//! RTGS). The built-in tables cover common cases; deployments can replace
+//! or extend them per script, e.g. with dialect spellings for Malaysian
+//! Chinese surnames. Every conversion that might not match the passport
+//! comes back with a warning so the user can confirm the spelling.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::error::{ErrorCode, Result, VayaError};
+
+/// Longest name part accepted by airline reservation systems
+pub const MAX_NAME_LEN: usize = 30;
+
+/// Writing system of a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// Latin letters, with or without diacritics
+    Latin,
+    /// Cyrillic
+    Cyrillic,
+    /// Arabic
+    Arabic,
+    /// Thai
+    Thai,
+    /// Chinese characters
+    Han,
+    /// Korean Hangul
+    Hangul,
+    /// Japanese Hiragana and Katakana
+    Kana,
+    /// Any other script
+    Other,
+}
+
+impl Script {
+    /// Script of a letter, or None for spaces, digits and punctuation
+    pub fn of(c: char) -> Option<Self> {
+        if c.is_ascii_alphabetic() {
+            return Some(Script::Latin);
+        }
+        if !c.is_alphabetic() {
+            return None;
+        }
+        Some(match c as u32 {
+            0x00C0..=0x024F | 0x1E00..=0x1EFF => Script::Latin,
+            0x0400..=0x052F => Script::Cyrillic,
+            0x0600..=0x06FF | 0x0750..=0x077F => Script::Arabic,
+            0x0E00..=0x0E7F => Script::Thai,
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => Script::Han,
+            0xAC00..=0xD7A3 | 0x1100..=0x11FF | 0x3130..=0x318F => Script::Hangul,
+            0x3040..=0x30FF => Script::Kana,
+            _ => Script::Other,
+        })
+    }
+
+    /// Get script code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Script::Latin => "latin",
+            Script::Cyrillic => "cyrillic",
+            Script::Arabic => "arabic",
+            Script::Thai => "thai",
+            Script::Han => "han",
+            Script::Hangul => "hangul",
+            Script::Kana => "kana",
+            Script::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Dominant script of a name: the most frequent non-Latin script, if any
+pub fn detect_script(name: &str) -> Script {
+    let mut counts: HashMap<Script, usize> = HashMap::new();
+    for script in name.chars().filter_map(Script::of) {
+        *counts.entry(script).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(s, _)| *s != Script::Latin)
+        .max_by_key(|(_, n)| *n)
+        .map_or(Script::Latin, |(s, _)| s)
+}
+
+/// Which part of the name is being converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameField {
+    /// Family name
+    Surname,
+    /// Given or middle names
+    Given,
+}
+
+/// Something the user should double-check against the passport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranslitWarning {
+    /// The name was converted from another script
+    Transliterated(Script),
+    /// The table for this script is approximate (unwritten vowels, tones)
+    Approximate(Script),
+    /// A letter was spelled out (Ü → UE); passports may use either form
+    Expanded {
+        /// Original letter
+        from: char,
+        /// Replacement
+        to: String,
+    },
+    /// Characters that cannot appear in a passport name were removed
+    Removed(Vec<char>),
+    /// The name was cut to the length limit
+    Truncated {
+        /// Length limit applied
+        max_len: usize,
+    },
+}
+
+/// Result of converting one name part
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transliteration {
+    /// Name in MRZ form (A-Z and single spaces)
+    pub value: String,
+    /// Script the input was written in
+    pub script: Script,
+    /// Differences worth confirming with the user
+    pub warnings: Vec<TranslitWarning>,
+}
+
+impl Transliteration {
+    /// Whether the result differs enough from the input to confirm with the user
+    pub fn needs_review(&self) -> bool {
+        !self.warnings.is_empty()
+    }
+}
+
+/// Character mapping for one script
+#[derive(Debug, Clone)]
+pub struct TransliterationTable {
+    script: Script,
+    chars: HashMap<char, String>,
+    surnames: HashMap<String, String>,
+    approximate: bool,
+}
+
+impl TransliterationTable {
+    /// Create an empty table
+    pub fn new(script: Script) -> Self {
+        Self {
+            script,
+            chars: HashMap::new(),
+            surnames: HashMap::new(),
+            approximate: false,
+        }
+    }
+
+    /// Map a character (matched in uppercase where the script has case)
+    pub fn with_char(mut self, c: char, latin: &str) -> Self {
+        self.chars.insert(c, latin.to_uppercase());
+        self
+    }
+
+    /// Map each character of `chars` to the same value
+    pub fn with_chars(mut self, chars: &str, latin: &str) -> Self {
+        for c in chars.chars() {
+            self.chars.insert(c, latin.to_uppercase());
+        }
+        self
+    }
+
+    /// Conventional spelling of a whole surname ("김" → "KIM", not "GIM")
+    pub fn with_surname(mut self, name: &str, latin: &str) -> Self {
+        self.surnames.insert(name.to_string(), latin.to_uppercase());
+        self
+    }
+
+    /// Mark results from this table as needing review
+    pub fn approximate(mut self) -> Self {
+        self.approximate = true;
+        self
+    }
+
+    /// Script this table converts
+    pub fn script(&self) -> Script {
+        self.script
+    }
+
+    /// Built-in table for a script, if there is one
+    pub fn builtin(script: Script) -> Option<Self> {
+        match script {
+            Script::Latin => Some(latin_table()),
+            Script::Cyrillic => Some(pairs_table(Script::Cyrillic, CYRILLIC)),
+            Script::Arabic => Some(pairs_table(Script::Arabic, ARABIC).approximate()),
+            Script::Thai => Some(pairs_table(Script::Thai, THAI).approximate()),
+            Script::Han => Some(pairs_table(Script::Han, HAN)),
+            Script::Hangul => {
+                let mut table = TransliterationTable::new(Script::Hangul);
+                for (name, latin) in HANGUL_SURNAMES {
+                    table = table.with_surname(name, latin);
+                }
+                Some(table)
+            }
+            Script::Kana | Script::Other => None,
+        }
+    }
+}
+
+/// Converts names to MRZ form using per-script tables
+#[derive(Debug, Clone)]
+pub struct Transliterator {
+    tables: HashMap<Script, TransliterationTable>,
+    max_len: usize,
+}
+
+impl Default for Transliterator {
+    fn default() -> Self {
+        let tables = [
+            Script::Latin,
+            Script::Cyrillic,
+            Script::Arabic,
+            Script::Thai,
+            Script::Han,
+            Script::Hangul,
+        ]
+        .into_iter()
+        .filter_map(TransliterationTable::builtin)
+        .map(|t| (t.script, t))
+        .collect();
+        Self {
+            tables,
+            max_len: MAX_NAME_LEN,
+        }
+    }
+}
+
+impl Transliterator {
+    /// Create a transliterator with the built-in tables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or extend the table for a script
+    ///
+    /// Entries in `table` override the existing ones for the same script.
+    pub fn with_table(mut self, table: TransliterationTable) -> Self {
+        match self.tables.get_mut(&table.script) {
+            Some(existing) => {
+                existing.chars.extend(table.chars);
+                existing.surnames.extend(table.surnames);
+                existing.approximate |= table.approximate;
+            }
+            None => {
+                self.tables.insert(table.script, table);
+            }
+        }
+        self
+    }
+
+    /// Set the length limit
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Convert a name part to MRZ form
+    pub fn transliterate(&self, name: &str, field: NameField) -> Result<Transliteration> {
+        let trimmed = name.trim();
+        let script = detect_script(trimmed);
+        let mut warnings = Vec::new();
+
+        let surname = (field == NameField::Surname)
+            .then(|| self.tables.get(&script))
+            .flatten()
+            .and_then(|t| t.surnames.get(trimmed));
+        let mut out = match surname {
+            Some(latin) => latin.clone(),
+            None => self.convert(trimmed, script, &mut warnings)?,
+        };
+
+        out = out.split_whitespace().collect::<Vec<_>>().join(" ");
+        if out.is_empty() {
+            return Err(VayaError::new(
+                ErrorCode::InvalidInput,
+                format!("Name {:?} has no letters", name),
+            ));
+        }
+        if out.len() > self.max_len {
+            out.truncate(self.max_len);
+            out = out.trim_end().to_string();
+            warnings.push(TranslitWarning::Truncated {
+                max_len: self.max_len,
+            });
+        }
+
+        if script != Script::Latin {
+            warnings.insert(0, TranslitWarning::Transliterated(script));
+            if self.tables.get(&script).is_some_and(|t| t.approximate) {
+                warnings.insert(1, TranslitWarning::Approximate(script));
+            }
+        }
+        Ok(Transliteration {
+            value: out,
+            script,
+            warnings,
+        })
+    }
+
+    fn convert(
+        &self,
+        name: &str,
+        script: Script,
+        warnings: &mut Vec<TranslitWarning>,
+    ) -> Result<String> {
+        let chars: Vec<char> = if script == Script::Thai {
+            reorder_thai(name)
+        } else {
+            name.chars().collect()
+        };
+
+        let mut out = String::with_capacity(chars.len() * 2);
+        let mut removed = Vec::new();
+        for c in chars {
+            if c.is_ascii_alphabetic() {
+                out.push(c.to_ascii_uppercase());
+                continue;
+            }
+            if c.is_whitespace() || c == '-' {
+                // ICAO 9303: hyphens become separators
+                out.push(' ');
+                continue;
+            }
+            if matches!(c, '\'' | '\u{2019}' | '`') {
+                // ICAO 9303: apostrophes are omitted
+                continue;
+            }
+            let Some(char_script) = Script::of(c) else {
+                if !removed.contains(&c) {
+                    removed.push(c);
+                }
+                continue;
+            };
+            if char_script == Script::Hangul && !self.mapped(char_script, c) {
+                if let Some(latin) = romanize_hangul(c) {
+                    out.push_str(&latin);
+                    continue;
+                }
+            }
+            let upper = c.to_uppercase().next().unwrap_or(c);
+            let latin = self
+                .tables
+                .get(&char_script)
+                .and_then(|t| t.chars.get(&upper).or_else(|| t.chars.get(&c)))
+                .ok_or_else(|| {
+                    VayaError::new(
+                        ErrorCode::InvalidInput,
+                        format!(
+                            "No transliteration for '{}' ({} script); enter the name as printed on the passport",
+                            c, char_script
+                        ),
+                    )
+                })?;
+            if char_script == Script::Latin && latin.len() > 1 {
+                warnings.push(TranslitWarning::Expanded {
+                    from: c,
+                    to: latin.clone(),
+                });
+            }
+            out.push_str(latin);
+        }
+        if !removed.is_empty() {
+            warnings.push(TranslitWarning::Removed(removed));
+        }
+        Ok(out)
+    }
+
+    fn mapped(&self, script: Script, c: char) -> bool {
+        self.tables
+            .get(&script)
+            .is_some_and(|t| t.chars.contains_key(&c))
+    }
+}
+
+/// Convert a name part with the built-in tables
+pub fn transliterate(name: &str, field: NameField) -> Result<Transliteration> {
+    static DEFAULT: OnceLock<Transliterator> = OnceLock::new();
+    DEFAULT
+        .get_or_init(Transliterator::new)
+        .transliterate(name, field)
+}
+
+/// Whether a name is already in MRZ form
+pub fn is_mrz_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name.bytes().all(|b| b.is_ascii_uppercase() || b == b' ')
+        && !name.starts_with(' ')
+        && !name.ends_with(' ')
+        && !name.contains("  ")
+}
+
+fn pairs_table(script: Script, pairs: &[(&str, &str)]) -> TransliterationTable {
+    pairs
+        .iter()
+        .fold(TransliterationTable::new(script), |t, (chars, latin)| {
+            t.with_chars(chars, latin)
+        })
+}
+
+fn latin_table() -> TransliterationTable {
+    pairs_table(Script::Latin, LATIN)
+}
+
+/// Thai leading vowels are written before the consonant they follow in speech
+fn reorder_thai(name: &str) -> Vec<char> {
+    let mut chars: Vec<char> = name.chars().collect();
+    let mut i = 0;
+    while i + 1 < chars.len() {
+        if matches!(chars[i], 'เ' | 'แ' | 'โ' | 'ใ' | 'ไ') && ('ก'..='ฮ').contains(&chars[i + 1])
+        {
+            chars.swap(i, i + 1);
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    chars
+}
+
+/// Revised Romanization of one precomposed Hangul syllable
+fn romanize_hangul(c: char) -> Option<String> {
+    const INITIALS: [&str; 19] = [
+        "G", "KK", "N", "D", "TT", "R", "M", "B", "PP", "S", "SS", "", "J", "JJ", "CH", "K", "T",
+        "P", "H",
+    ];
+    const MEDIALS: [&str; 21] = [
+        "A", "AE", "YA", "YAE", "EO", "E", "YEO", "YE", "O", "WA", "WAE", "OE", "YO", "U", "WO",
+        "WE", "WI", "YU", "EU", "UI", "I",
+    ];
+    const FINALS: [&str; 28] = [
+        "", "K", "K", "K", "N", "N", "N", "T", "L", "K", "M", "L", "L", "L", "P", "L", "M", "P",
+        "P", "T", "T", "NG", "T", "T", "K", "T", "P", "T",
+    ];
+    let index = (c as u32).checked_sub(0xAC00)?;
+    if index >= 11172 {
+        return None;
+    }
+    let (initial, medial, last) = (index / 588, (index % 588) / 28, index % 28);
+    Some(format!(
+        "{}{}{}",
+        INITIALS[initial as usize], MEDIALS[medial as usize], FINALS[last as usize]
+    ))
+}
+
+/// ICAO 9303 recommended transliteration of Latin letters with diacritics
+const LATIN: &[(&str, &str)] = &[
+    ("ÀÁÂÃĀĂĄẠẢẤẦẨẪẬẮẰẲẴẶ", "A"),
+    ("Ä", "AE"),
+    ("Å", "AA"),
+    ("Æ", "AE"),
+    ("ÇĆĈĊČ", "C"),
+    ("ĎĐÐ", "D"),
+    ("ÈÉÊËĒĔĖĘĚẸẺẼẾỀỂỄỆ", "E"),
+    ("ĜĞĠĢ", "G"),
+    ("ĤĦ", "H"),
+    ("ÌÍÎÏĨĪĬĮİỈỊ", "I"),
+    ("Ĳ", "IJ"),
+    ("Ĵ", "J"),
+    ("Ķ", "K"),
+    ("ĹĻĽĿŁ", "L"),
+    ("ÑŃŅŇ", "N"),
+    ("ÒÓÔÕŌŎŐƠỌỎỐỒỔỖỘỚỜỞỠỢ", "O"),
+    ("ÖØŒ", "OE"),
+    ("ŔŖŘ", "R"),
+    ("ŚŜŞŠ", "S"),
+    ("ß", "SS"),
+    ("ŢŤŦ", "T"),
+    ("Þ", "TH"),
+    ("ÙÚÛŨŪŬŮŰŲƯỤỦỨỪỬỮỰ", "U"),
+    ("Ü", "UE"),
+    ("Ŵ", "W"),
+    ("ÝŶŸỲỴỶỸ", "Y"),
+    ("ŹŻŽ", "Z"),
+];
+
+/// ICAO 9303 Cyrillic table
+const CYRILLIC: &[(&str, &str)] = &[
+    ("А", "A"),
+    ("Б", "B"),
+    ("В", "V"),
+    ("Г", "G"),
+    ("Ґ", "G"),
+    ("Д", "D"),
+    ("ЕЁЄЭ", "E"),
+    ("Ж", "ZH"),
+    ("З", "Z"),
+    ("ИЙІ", "I"),
+    ("Ї", "I"),
+    ("К", "K"),
+    ("Л", "L"),
+    ("М", "M"),
+    ("Н", "N"),
+    ("О", "O"),
+    ("П", "P"),
+    ("Р", "R"),
+    ("С", "S"),
+    ("Т", "T"),
+    ("У", "U"),
+    ("Ў", "U"),
+    ("Ф", "F"),
+    ("Х", "KH"),
+    ("Ц", "TS"),
+    ("Ч", "CH"),
+    ("Ш", "SH"),
+    ("Щ", "SHCH"),
+    ("Ъ", "IE"),
+    ("Ы", "Y"),
+    ("Ь", ""),
+    ("Ю", "IU"),
+    ("Я", "IA"),
+];
+
+/// Arabic consonants; short vowels are not written, so results need review
+const ARABIC: &[(&str, &str)] = &[
+    ("اأآٱ", "A"),
+    ("إ", "I"),
+    ("ب", "B"),
+    ("ت", "T"),
+    ("ث", "TH"),
+    ("ج", "J"),
+    ("ح", "H"),
+    ("خ", "KH"),
+    ("د", "D"),
+    ("ذ", "DH"),
+    ("ر", "R"),
+    ("ز", "Z"),
+    ("س", "S"),
+    ("ش", "SH"),
+    ("ص", "S"),
+    ("ض", "D"),
+    ("ط", "T"),
+    ("ظ", "Z"),
+    ("ع", ""),
+    ("غ", "GH"),
+    ("ف", "F"),
+    ("ق", "Q"),
+    ("كک", "K"),
+    ("ل", "L"),
+    ("م", "M"),
+    ("ن", "N"),
+    ("هة", "H"),
+    ("و", "W"),
+    ("يیئ", "Y"),
+    ("ى", "A"),
+    ("ءؤ", ""),
+];
+
+/// RTGS consonants (initial values) and vowels; inherent vowels are not written
+const THAI: &[(&str, &str)] = &[
+    ("ก", "K"),
+    ("ขฃคฅฆ", "KH"),
+    ("ง", "NG"),
+    ("จฉชฌ", "CH"),
+    ("ซศษส", "S"),
+    ("ญย", "Y"),
+    ("ฎด", "D"),
+    ("ฏต", "T"),
+    ("ฐฑฒถทธ", "TH"),
+    ("ณน", "N"),
+    ("บ", "B"),
+    ("ป", "P"),
+    ("ผพภ", "PH"),
+    ("ฝฟ", "F"),
+    ("ม", "M"),
+    ("ร", "R"),
+    ("ฤ", "RUE"),
+    ("ลฬ", "L"),
+    ("ฦ", "LUE"),
+    ("ว", "W"),
+    ("หฮ", "H"),
+    ("อ", ""),
+    ("ะัา", "A"),
+    ("ำ", "AM"),
+    ("ิี", "I"),
+    ("ึื", "UE"),
+    ("ุู", "U"),
+    ("เ", "E"),
+    ("แ", "AE"),
+    ("โ", "O"),
+    ("ใไ", "AI"),
+    // Tone marks, silencer, repetition and abbreviation signs
+    ("่้๊๋็์ๅฯๆ", ""),
+];
+
+/// Hanyu Pinyin for common surname and given-name characters
+///
+/// Not a full dictionary: extend it with [`Transliterator::with_table`].
+const HAN: &[(&str, &str)] = &[
+    ("王", "WANG"),
+    ("李", "LI"),
+    ("张張", "ZHANG"),
+    ("刘劉", "LIU"),
+    ("陈陳", "CHEN"),
+    ("杨楊", "YANG"),
+    ("黄黃", "HUANG"),
+    ("赵趙", "ZHAO"),
+    ("吴吳", "WU"),
+    ("周", "ZHOU"),
+    ("徐许許", "XU"),
+    ("孙孫", "SUN"),
+    ("马馬", "MA"),
+    ("朱", "ZHU"),
+    ("胡", "HU"),
+    ("郭国國", "GUO"),
+    ("何", "HE"),
+    ("林", "LIN"),
+    ("高", "GAO"),
+    ("罗羅", "LUO"),
+    ("郑鄭", "ZHENG"),
+    ("梁", "LIANG"),
+    ("谢謝", "XIE"),
+    ("宋", "SONG"),
+    ("唐", "TANG"),
+    ("邓鄧", "DENG"),
+    ("冯馮", "FENG"),
+    ("韩韓", "HAN"),
+    ("曹", "CAO"),
+    ("曾", "ZENG"),
+    ("彭", "PENG"),
+    ("萧蕭小晓曉", "XIAO"),
+    ("蔡", "CAI"),
+    ("潘", "PAN"),
+    ("田", "TIAN"),
+    ("董", "DONG"),
+    ("袁", "YUAN"),
+    ("于余", "YU"),
+    ("叶葉", "YE"),
+    ("蒋蔣", "JIANG"),
+    ("杜", "DU"),
+    ("苏蘇", "SU"),
+    ("魏伟偉", "WEI"),
+    ("程", "CHENG"),
+    ("吕呂", "LYU"),
+    ("芳", "FANG"),
+    ("娜", "NA"),
+    ("敏", "MIN"),
+    ("静靜", "JING"),
+    ("丽麗", "LI"),
+    ("强強", "QIANG"),
+    ("磊", "LEI"),
+    ("军軍", "JUN"),
+    ("洋", "YANG"),
+    ("勇", "YONG"),
+    ("艳艷", "YAN"),
+    ("杰傑", "JIE"),
+    ("娟", "JUAN"),
+    ("涛濤", "TAO"),
+    ("明", "MING"),
+    ("超", "CHAO"),
+    ("秀", "XIU"),
+    ("英", "YING"),
+    ("华華", "HUA"),
+    ("文", "WEN"),
+    ("玲", "LING"),
+    ("平", "PING"),
+    ("红紅", "HONG"),
+    ("建", "JIAN"),
+    ("志", "ZHI"),
+    ("龙龍", "LONG"),
+    ("海", "HAI"),
+    ("美", "MEI"),
+    ("慧", "HUI"),
+    ("婷", "TING"),
+    ("雪", "XUE"),
+];
+
+/// Conventional passport spellings of common Korean surnames
+const HANGUL_SURNAMES: &[(&str, &str)] = &[
+    ("김", "KIM"),
+    ("이", "LEE"),
+    ("박", "PARK"),
+    ("최", "CHOI"),
+    ("정", "JUNG"),
+    ("강", "KANG"),
+    ("조", "CHO"),
+    ("윤", "YOON"),
+    ("장", "JANG"),
+    ("임", "LIM"),
+    ("한", "HAN"),
+    ("오", "OH"),
+    ("서", "SEO"),
+    ("신", "SHIN"),
+    ("권", "KWON"),
+    ("황", "HWANG"),
+    ("안", "AHN"),
+    ("송", "SONG"),
+    ("류", "RYU"),
+    ("홍", "HONG"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(name: &str, field: NameField) -> String {
+        transliterate(name, field).unwrap().value
+    }
+
+    #[test]
+    fn test_latin_and_mrz_rules() {
+        assert_eq!(detect_script("Zhang Wei"), Script::Latin);
+        assert_eq!(value("o'Brien", NameField::Surname), "OBRIEN");
+        assert_eq!(value("Anne-Marie", NameField::Given), "ANNE MARIE");
+        assert_eq!(value("  José  Luis ", NameField::Given), "JOSE LUIS");
+        assert_eq!(value("Nguyễn", NameField::Surname), "NGUYEN");
+
+        let muller = transliterate("Müller", NameField::Surname).unwrap();
+        assert_eq!(muller.value, "MUELLER");
+        assert!(muller.needs_review());
+        assert!(matches!(
+            muller.warnings[0],
+            TranslitWarning::Expanded { from: 'ü', .. }
+        ));
+
+        let dotted = transliterate("Abdul.Rahman2", NameField::Given).unwrap();
+        assert_eq!(dotted.value, "ABDULRAHMAN");
+        assert_eq!(
+            dotted.warnings,
+            vec![TranslitWarning::Removed(vec!['.', '2'])]
+        );
+
+        let long = transliterate(&"A".repeat(40), NameField::Given).unwrap();
+        assert_eq!(long.value.len(), MAX_NAME_LEN);
+        assert!(long
+            .warnings
+            .contains(&TranslitWarning::Truncated { max_len: 30 }));
+
+        assert!(is_mrz_name("MARY JANE"));
+        assert!(!is_mrz_name("Mary"));
+        assert!(!is_mrz_name("O'BRIEN"));
+        assert!(transliterate("--", NameField::Surname).is_err());
+    }
+
+    #[test]
+    fn test_other_scripts() {
+        assert_eq!(detect_script("张伟"), Script::Han);
+        let zhang = transliterate("张", NameField::Surname).unwrap();
+        assert_eq!(zhang.value, "ZHANG");
+        assert_eq!(
+            zhang.warnings,
+            vec![TranslitWarning::Transliterated(Script::Han)]
+        );
+        assert_eq!(value("小明", NameField::Given), "XIAOMING");
+
+        assert_eq!(value("Жуков", NameField::Surname), "ZHUKOV");
+        assert_eq!(value("Юлия", NameField::Given), "IULIIA");
+
+        // Conventional surname spelling, Revised Romanization otherwise
+        assert_eq!(value("김", NameField::Surname), "KIM");
+        assert_eq!(value("김", NameField::Given), "GIM");
+        assert_eq!(value("민준", NameField::Given), "MINJUN");
+
+        let thai = transliterate("ไพโรจน์", NameField::Given).unwrap();
+        assert_eq!(thai.script, Script::Thai);
+        assert!(thai
+            .warnings
+            .contains(&TranslitWarning::Approximate(Script::Thai)));
+        assert!(thai.value.starts_with("PH"));
+
+        let arabic = transliterate("محمد", NameField::Given).unwrap();
+        assert_eq!(arabic.value, "MHMD");
+        assert!(arabic.needs_review());
+
+        // Unknown characters are an error, not a guess
+        assert!(transliterate("龘", NameField::Given).is_err());
+        assert!(transliterate("さくら", NameField::Given).is_err());
+
+        // Deployments can add dialect spellings
+        let hokkien = Transliterator::new()
+            .with_table(TransliterationTable::new(Script::Han).with_surname("陈", "TAN"));
+        assert_eq!(
+            hokkien
+                .transliterate("陈", NameField::Surname)
+                .unwrap()
+                .value,
+            "TAN"
+        );
+        assert_eq!(
+            hokkien.transliterate("陈", NameField::Given).unwrap().value,
+            "CHEN"
+        );
+    }
+}
@@ -7,6 +7,9 @@ use rkyv::{Archive, Deserialize, Serialize};
 use std::fmt;
 use std::hash::Hash;
 
+use crate::error::{ErrorCode, Result, VayaError};
+use crate::fx::ExchangeRates;
+
 // ============================================================================
 // PRIMITIVE TYPES
 // ============================================================================
@@ -210,6 +213,78 @@ impl MinorUnits {
         Self((amount * 10f64.powi(decimals as i32)).round() as i64)
     }
 
+    /// Parse a decimal string ("1234.50") into minor units of `currency`
+    ///
+    /// Strict: no floating point, exponents, separators or surrounding
+    /// text, and no more fraction digits than the currency has (trailing
+    /// zeros excepted), so "1500" JPY is 1500 and "12.345" MYR is an error
+    /// rather than a silent rounding.
+    pub fn parse_decimal(s: &str, currency: CurrencyCode) -> Result<Self> {
+        let invalid = |reason: &str| {
+            VayaError::new(
+                ErrorCode::InvalidPrice,
+                format!("Invalid {} amount {:?}: {}", currency, s, reason),
+            )
+        };
+        let decimals = currency.decimals() as usize;
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if int_part.is_empty() || (digits.contains('.') && frac_part.is_empty()) {
+            return Err(invalid(
+                "expected digits on both sides of the decimal point",
+            ));
+        }
+        if !int_part.bytes().all(|b| b.is_ascii_digit())
+            || !frac_part.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(invalid("not a plain decimal number"));
+        }
+        if frac_part.len() > decimals && frac_part.bytes().skip(decimals).any(|b| b != b'0') {
+            return Err(invalid(&format!(
+                "{} allows {} decimal places",
+                currency, decimals
+            )));
+        }
+
+        let overflow = || invalid("out of range");
+        let mut value: i64 = 0;
+        let frac_digits = frac_part
+            .bytes()
+            .chain(std::iter::repeat(b'0'))
+            .take(decimals);
+        for b in int_part.bytes().chain(frac_digits) {
+            value = value
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(i64::from(b - b'0')))
+                .ok_or_else(overflow)?;
+        }
+        Ok(Self(if negative { -value } else { value }))
+    }
+
+    /// Format as a decimal string with the currency's decimal places
+    ///
+    /// The inverse of [`MinorUnits::parse_decimal`]: 15050 MYR is "150.50",
+    /// 1500 JPY is "1500", 1234 KWD is "1.234".
+    pub fn format_decimal(&self, currency: CurrencyCode) -> String {
+        let decimals = u32::from(currency.decimals());
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        if decimals == 0 {
+            return format!("{}{}", sign, abs);
+        }
+        let scale = 10u64.pow(decimals);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            abs / scale,
+            abs % scale,
+            width = decimals as usize
+        )
+    }
+
     /// Add two amounts
     pub fn add(&self, other: Self) -> Self {
         Self(self.0.saturating_add(other.0))
@@ -279,16 +354,18 @@ impl Price {
 
     /// Format for display
     pub fn format(&self) -> String {
-        let decimals = self.currency.decimals();
-        let major = self.amount.to_major(decimals);
         format!(
-            "{} {:.prec$}",
+            "{} {}",
             self.currency.as_str(),
-            major,
-            prec = decimals as usize
+            self.amount.format_decimal(self.currency)
         )
     }
 
+    /// Parse a decimal amount string in a currency
+    pub fn parse_decimal(amount: &str, currency: CurrencyCode) -> Result<Self> {
+        MinorUnits::parse_decimal(amount, currency).map(|amount| Self::new(amount, currency))
+    }
+
     /// Check if zero
     pub fn is_zero(&self) -> bool {
         self.amount.as_i64() == 0
@@ -304,6 +381,13 @@ impl Price {
             currency: self.currency,
         })
     }
+
+    /// Convert to another currency at `rates`, including their margin
+    pub fn convert_to(&self, currency: CurrencyCode, rates: &ExchangeRates) -> Result<Self> {
+        rates
+            .convert(self.amount, self.currency, currency)
+            .map(|amount| Self::new(amount, currency))
+    }
 }
 
 impl fmt::Debug for Price {
@@ -980,6 +1064,67 @@ mod tests {
         let price = Price::myr(15000); // RM 150.00
         assert_eq!(price.display_amount(), 150.0);
         assert_eq!(price.format(), "MYR 150.00");
+
+        let rates = ExchangeRates::new(CurrencyCode::MYR, Timestamp::now())
+            .with_rate(CurrencyCode::THB, 765_000_000)
+            .unwrap();
+        let thb = price.convert_to(CurrencyCode::THB, &rates).unwrap();
+        assert_eq!(thb.format(), "THB 1147.50");
+    }
+
+    #[test]
+    fn test_minor_units_decimal() {
+        let parse = |s: &str, c: CurrencyCode| MinorUnits::parse_decimal(s, c).map(|m| m.as_i64());
+
+        assert_eq!(parse("150.50", CurrencyCode::MYR).unwrap(), 15050);
+        assert_eq!(parse("150.5", CurrencyCode::MYR).unwrap(), 15050);
+        assert_eq!(parse("150", CurrencyCode::MYR).unwrap(), 15000);
+        assert_eq!(parse("-0.05", CurrencyCode::USD).unwrap(), -5);
+        // Zero-decimal currencies are not scaled by 100
+        assert_eq!(parse("45000", CurrencyCode::JPY).unwrap(), 45000);
+        assert_eq!(parse("45000.00", CurrencyCode::JPY).unwrap(), 45000);
+        assert!(parse("45000.50", CurrencyCode::JPY).is_err());
+        // Three-decimal currencies
+        let kwd = CurrencyCode::new("KWD");
+        assert_eq!(parse("1.234", kwd).unwrap(), 1234);
+        assert_eq!(parse("1.2", kwd).unwrap(), 1200);
+        assert!(parse("1.2345", kwd).is_err());
+
+        for bad in [
+            "", ".", "1.", ".5", "1e3", "1,000.00", "12.34.5", " 1.00", "abc", "--1",
+        ] {
+            assert!(parse(bad, CurrencyCode::MYR).is_err(), "{:?} accepted", bad);
+        }
+        assert!(parse("99999999999999999999", CurrencyCode::MYR).is_err());
+
+        assert_eq!(
+            MinorUnits::new(15050).format_decimal(CurrencyCode::MYR),
+            "150.50"
+        );
+        assert_eq!(
+            MinorUnits::new(-5).format_decimal(CurrencyCode::USD),
+            "-0.05"
+        );
+        assert_eq!(
+            MinorUnits::new(45000).format_decimal(CurrencyCode::JPY),
+            "45000"
+        );
+        assert_eq!(MinorUnits::new(1234).format_decimal(kwd), "1.234");
+        assert_eq!(
+            Price::new(MinorUnits::new(45000), CurrencyCode::JPY).format(),
+            "JPY 45000"
+        );
+        for (amount, currency) in [
+            (15050, CurrencyCode::MYR),
+            (7, kwd),
+            (-12, CurrencyCode::KRW),
+        ] {
+            let m = MinorUnits::new(amount);
+            assert_eq!(
+                MinorUnits::parse_decimal(&m.format_decimal(currency), currency).unwrap(),
+                m
+            );
+        }
     }
 
     #[test]
@@ -0,0 +1,506 @@
+//! Timezone-aware instants
+//!
+//! [`Timestamp`] is a UTC instant and drops the wall-clock time a flight
+//! departs or arrives at. [`ZonedTime`] pairs the instant with a
+//! [`TimeZone`]: either an IANA zone from the embedded rule table or a fixed
+//! UTC offset. The table covers the zones used by the airport reference data
+//! with their current standard offsets and daylight-saving rules; historical
+//! rule changes are not modelled.
+
+use std::fmt;
+
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+use crate::refdata;
+use crate::types::{IataCode, Timestamp};
+
+const HOUR: i32 = 3600;
+
+/// Which Sunday of the month a daylight-saving transition falls on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sunday {
+    First,
+    Second,
+    Last,
+}
+
+/// A daylight-saving transition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transition {
+    month: Month,
+    sunday: Sunday,
+    /// Seconds after midnight, local standard time
+    at: i32,
+}
+
+/// Daylight-saving rule of a zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DstRule {
+    start: Transition,
+    end: Transition,
+    save: i32,
+}
+
+impl DstRule {
+    /// EU: last Sunday of March to last Sunday of October, at 01:00 UTC
+    const fn eu(std_offset: i32) -> Self {
+        let at = HOUR + std_offset;
+        Self {
+            start: Transition {
+                month: Month::March,
+                sunday: Sunday::Last,
+                at,
+            },
+            end: Transition {
+                month: Month::October,
+                sunday: Sunday::Last,
+                at,
+            },
+            save: HOUR,
+        }
+    }
+
+    /// US and Canada: second Sunday of March to first Sunday of November
+    const US: Self = Self {
+        start: Transition {
+            month: Month::March,
+            sunday: Sunday::Second,
+            at: 2 * HOUR,
+        },
+        end: Transition {
+            month: Month::November,
+            sunday: Sunday::First,
+            at: HOUR,
+        },
+        save: HOUR,
+    };
+
+    /// South-east Australia: first Sunday of October to first Sunday of April
+    const AU: Self = Self {
+        start: Transition {
+            month: Month::October,
+            sunday: Sunday::First,
+            at: 2 * HOUR,
+        },
+        end: Transition {
+            month: Month::April,
+            sunday: Sunday::First,
+            at: 2 * HOUR,
+        },
+        save: HOUR,
+    };
+
+    /// New Zealand: last Sunday of September to first Sunday of April
+    const NZ: Self = Self {
+        start: Transition {
+            month: Month::September,
+            sunday: Sunday::Last,
+            at: 2 * HOUR,
+        },
+        end: Transition {
+            month: Month::April,
+            sunday: Sunday::First,
+            at: 2 * HOUR,
+        },
+        save: HOUR,
+    };
+}
+
+/// Offset rules of an IANA zone
+#[derive(Debug, PartialEq, Eq)]
+pub struct ZoneRules {
+    /// IANA zone name
+    pub name: &'static str,
+    /// Standard offset from UTC in seconds
+    pub std_offset: i32,
+    dst: Option<DstRule>,
+}
+
+impl ZoneRules {
+    /// Does the zone observe daylight saving time
+    pub fn has_dst(&self) -> bool {
+        self.dst.is_some()
+    }
+
+    /// UTC offset in seconds at an instant
+    fn offset_at(&self, unix: i64) -> i32 {
+        match self.dst {
+            Some(rule) if in_dst(&rule, self.std_offset, unix) => self.std_offset + rule.save,
+            _ => self.std_offset,
+        }
+    }
+}
+
+const fn zone(name: &'static str, std_offset: i32) -> ZoneRules {
+    ZoneRules {
+        name,
+        std_offset,
+        dst: None,
+    }
+}
+
+const fn zone_dst(name: &'static str, std_offset: i32, rule: DstRule) -> ZoneRules {
+    ZoneRules {
+        name,
+        std_offset,
+        dst: Some(rule),
+    }
+}
+
+/// Embedded zone rules
+#[rustfmt::skip]
+pub static ZONES: &[ZoneRules] = &[
+    zone("UTC", 0),
+    // Asia
+    zone("Asia/Kuala_Lumpur", 8 * HOUR),
+    zone("Asia/Kuching", 8 * HOUR),
+    zone("Asia/Singapore", 8 * HOUR),
+    zone("Asia/Bangkok", 7 * HOUR),
+    zone("Asia/Jakarta", 7 * HOUR),
+    zone("Asia/Makassar", 8 * HOUR),
+    zone("Asia/Manila", 8 * HOUR),
+    zone("Asia/Ho_Chi_Minh", 7 * HOUR),
+    zone("Asia/Phnom_Penh", 7 * HOUR),
+    zone("Asia/Yangon", 6 * HOUR + 1800),
+    zone("Asia/Brunei", 8 * HOUR),
+    zone("Asia/Vientiane", 7 * HOUR),
+    zone("Asia/Hong_Kong", 8 * HOUR),
+    zone("Asia/Macau", 8 * HOUR),
+    zone("Asia/Taipei", 8 * HOUR),
+    zone("Asia/Shanghai", 8 * HOUR),
+    zone("Asia/Tokyo", 9 * HOUR),
+    zone("Asia/Seoul", 9 * HOUR),
+    zone("Asia/Kolkata", 5 * HOUR + 1800),
+    zone("Asia/Colombo", 5 * HOUR + 1800),
+    zone("Asia/Dhaka", 6 * HOUR),
+    zone("Asia/Kathmandu", 5 * HOUR + 2700),
+    zone("Indian/Maldives", 5 * HOUR),
+    zone("Asia/Dubai", 4 * HOUR),
+    zone("Asia/Qatar", 3 * HOUR),
+    zone("Asia/Riyadh", 3 * HOUR),
+    zone("Europe/Istanbul", 3 * HOUR),
+    // Oceania
+    zone_dst("Australia/Sydney", 10 * HOUR, DstRule::AU),
+    zone_dst("Australia/Melbourne", 10 * HOUR, DstRule::AU),
+    zone("Australia/Brisbane", 10 * HOUR),
+    zone("Australia/Perth", 8 * HOUR),
+    zone_dst("Pacific/Auckland", 12 * HOUR, DstRule::NZ),
+    // Europe
+    zone_dst("Europe/London", 0, DstRule::eu(0)),
+    zone_dst("Europe/Paris", HOUR, DstRule::eu(HOUR)),
+    zone_dst("Europe/Amsterdam", HOUR, DstRule::eu(HOUR)),
+    zone_dst("Europe/Berlin", HOUR, DstRule::eu(HOUR)),
+    zone_dst("Europe/Zurich", HOUR, DstRule::eu(HOUR)),
+    zone_dst("Europe/Rome", HOUR, DstRule::eu(HOUR)),
+    zone_dst("Europe/Madrid", HOUR, DstRule::eu(HOUR)),
+    // Americas
+    zone_dst("America/New_York", -5 * HOUR, DstRule::US),
+    zone_dst("America/Los_Angeles", -8 * HOUR, DstRule::US),
+    zone_dst("America/Vancouver", -8 * HOUR, DstRule::US),
+];
+
+/// Unix seconds at midnight UTC of a date
+fn midnight(date: Date) -> i64 {
+    PrimitiveDateTime::new(date, Time::MIDNIGHT)
+        .assume_utc()
+        .unix_timestamp()
+}
+
+/// Date of a transition's Sunday in a year
+fn transition_date(year: i32, transition: &Transition) -> Option<Date> {
+    let sunday = |date: Date| i64::from(date.weekday().number_days_from_sunday());
+    let date = match transition.sunday {
+        Sunday::First | Sunday::Second => {
+            let first = Date::from_calendar_date(year, transition.month, 1).ok()?;
+            let weeks = match transition.sunday {
+                Sunday::Second => 7,
+                _ => 0,
+            };
+            first + time::Duration::days((7 - sunday(first)) % 7 + weeks)
+        }
+        Sunday::Last => {
+            let last = transition.month.length(year);
+            let last = Date::from_calendar_date(year, transition.month, last).ok()?;
+            last - time::Duration::days(sunday(last))
+        }
+    };
+    Some(date)
+}
+
+/// Is daylight saving in effect at a UTC instant
+fn in_dst(rule: &DstRule, std_offset: i32, unix: i64) -> bool {
+    let std_local = unix + i64::from(std_offset);
+    let Some(year) = time::OffsetDateTime::from_unix_timestamp(std_local)
+        .ok()
+        .map(|t| t.year())
+    else {
+        return false;
+    };
+    let instant = |t: &Transition| {
+        transition_date(year, t).map(|d| midnight(d) + i64::from(t.at - std_offset))
+    };
+    let (Some(start), Some(end)) = (instant(&rule.start), instant(&rule.end)) else {
+        return false;
+    };
+    if start < end {
+        (start..end).contains(&unix)
+    } else {
+        // Southern hemisphere: saving time spans the new year
+        unix < end || unix >= start
+    }
+}
+
+/// A timezone: IANA rules or a fixed offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+    /// IANA zone from the embedded rule table
+    Named(&'static ZoneRules),
+    /// Fixed offset from UTC in seconds
+    Fixed(i32),
+}
+
+impl TimeZone {
+    /// Coordinated Universal Time
+    pub const UTC: Self = TimeZone::Fixed(0);
+
+    /// Look up an IANA zone by name
+    pub fn named(name: &str) -> Option<Self> {
+        ZONES.iter().find(|z| z.name == name).map(TimeZone::Named)
+    }
+
+    /// Zone of an airport from the reference data
+    pub fn airport(code: &IataCode) -> Option<Self> {
+        Self::named(refdata::airport(code.as_str())?.timezone)
+    }
+
+    /// IANA name, or the offset as `+HH:MM` for fixed zones
+    pub fn name(&self) -> String {
+        match self {
+            TimeZone::Named(rules) => rules.name.to_string(),
+            TimeZone::Fixed(offset) => format_offset(*offset),
+        }
+    }
+
+    /// UTC offset in seconds at an instant
+    pub fn offset_at(&self, instant: Timestamp) -> i32 {
+        match self {
+            TimeZone::Named(rules) => rules.offset_at(instant.as_unix()),
+            TimeZone::Fixed(offset) => *offset,
+        }
+    }
+
+    /// Instant of a local wall-clock time
+    ///
+    /// A time skipped by a forward transition resolves as standard time, so
+    /// 02:30 on the spring-forward night reads as 03:30 daylight time. A
+    /// repeated time resolves to its first (daylight) occurrence.
+    pub fn to_utc(&self, local: PrimitiveDateTime) -> Timestamp {
+        let wall = local.assume_utc().unix_timestamp();
+        let unix = match self {
+            TimeZone::Named(rules) => match rules.dst {
+                Some(rule) => {
+                    let daylight = wall - i64::from(rules.std_offset + rule.save);
+                    if in_dst(&rule, rules.std_offset, daylight) {
+                        daylight
+                    } else {
+                        wall - i64::from(rules.std_offset)
+                    }
+                }
+                None => wall - i64::from(rules.std_offset),
+            },
+            TimeZone::Fixed(offset) => wall - i64::from(*offset),
+        };
+        Timestamp::from_unix(unix)
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
+
+/// Format an offset in seconds as `+HH:MM`
+fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.abs() / 60;
+    format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+/// A UTC instant with the timezone it is observed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZonedTime {
+    /// The instant (UTC)
+    pub instant: Timestamp,
+    /// Timezone for local wall-clock time
+    pub zone: TimeZone,
+}
+
+impl ZonedTime {
+    /// Create a zoned time from an instant
+    pub fn new(instant: Timestamp, zone: TimeZone) -> Self {
+        Self { instant, zone }
+    }
+
+    /// Create a UTC zoned time
+    pub fn utc(instant: Timestamp) -> Self {
+        Self::new(instant, TimeZone::UTC)
+    }
+
+    /// Create a zoned time from a local wall-clock time
+    pub fn from_local(local: PrimitiveDateTime, zone: TimeZone) -> Self {
+        Self::new(zone.to_utc(local), zone)
+    }
+
+    /// Parse a local ISO 8601 time without offset (`2025-01-15T10:30:00`)
+    ///
+    /// Seconds are optional.
+    pub fn parse_local(value: &str, zone: TimeZone) -> Option<Self> {
+        let with_seconds =
+            time::macros::format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+        let without_seconds =
+            time::macros::format_description!("[year]-[month]-[day]T[hour]:[minute]");
+        let value = value.trim();
+        let local = PrimitiveDateTime::parse(value, with_seconds)
+            .or_else(|_| PrimitiveDateTime::parse(value, without_seconds))
+            .ok()?;
+        Some(Self::from_local(local, zone))
+    }
+
+    /// Same instant observed in another zone
+    pub fn with_zone(self, zone: TimeZone) -> Self {
+        Self::new(self.instant, zone)
+    }
+
+    /// UTC offset in seconds
+    pub fn offset(&self) -> i32 {
+        self.zone.offset_at(self.instant)
+    }
+
+    /// Local wall-clock time
+    pub fn local(&self) -> PrimitiveDateTime {
+        let unix = self.instant.as_unix() + i64::from(self.offset());
+        let utc = time::OffsetDateTime::from_unix_timestamp(unix)
+            .unwrap_or(time::OffsetDateTime::UNIX_EPOCH);
+        PrimitiveDateTime::new(utc.date(), utc.time())
+    }
+
+    /// Local calendar date
+    pub fn local_date(&self) -> Date {
+        self.local().date()
+    }
+
+    /// Whole minutes from this time to a later one, across zones
+    pub fn minutes_until(&self, later: &ZonedTime) -> i64 {
+        (later.instant.as_unix() - self.instant.as_unix()).div_euclid(60)
+    }
+
+    /// Local calendar days between this time and a later one
+    ///
+    /// A flight leaving at 23:00 and landing at 06:00 local time the next
+    /// day returns 1, for "+1" displays.
+    pub fn days_until(&self, later: &ZonedTime) -> i64 {
+        (later.local_date() - self.local_date()).whole_days()
+    }
+}
+
+impl fmt::Display for ZonedTime {
+    /// ISO 8601 local time with offset (`2025-01-15T10:30:00+08:00`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let local = self.local();
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+            local.year(),
+            local.month() as u8,
+            local.day(),
+            local.hour(),
+            local.minute(),
+            local.second(),
+            format_offset(self.offset())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn zone(name: &str) -> TimeZone {
+        TimeZone::named(name).unwrap()
+    }
+
+    #[test]
+    fn test_every_airport_has_a_zone() {
+        for airport in refdata::AIRPORTS {
+            assert!(
+                TimeZone::named(airport.timezone).is_some(),
+                "{}",
+                airport.timezone
+            );
+        }
+        assert_eq!(
+            TimeZone::airport(&IataCode::KUL),
+            Some(zone("Asia/Kuala_Lumpur"))
+        );
+        assert_eq!(TimeZone::Fixed(-9000).name(), "-02:30");
+    }
+
+    #[test]
+    fn test_local_round_trip() {
+        let kul = ZonedTime::parse_local("2025-01-15T23:30", zone("Asia/Kuala_Lumpur")).unwrap();
+        assert_eq!(kul.instant.to_string(), "2025-01-15T15:30:00Z");
+        assert_eq!(kul.to_string(), "2025-01-15T23:30:00+08:00");
+        assert_eq!(kul.local(), datetime!(2025-01-15 23:30));
+
+        let india = kul.with_zone(zone("Asia/Kolkata"));
+        assert_eq!(india.to_string(), "2025-01-15T21:00:00+05:30");
+        assert!(ZonedTime::parse_local("15/01/2025", TimeZone::UTC).is_none());
+    }
+
+    #[test]
+    fn test_daylight_saving() {
+        let london = zone("Europe/London");
+        let winter = ZonedTime::from_local(datetime!(2025-01-15 12:00), london);
+        let summer = ZonedTime::from_local(datetime!(2025-07-15 12:00), london);
+        assert_eq!(winter.offset(), 0);
+        assert_eq!(summer.offset(), 3600);
+        // Clocks go forward at 2025-03-30 01:00 UTC
+        let change = 1_743_296_400;
+        let before = ZonedTime::new(Timestamp::from_unix(change - 1), london);
+        let after = ZonedTime::new(Timestamp::from_unix(change), london);
+        assert_eq!(before.to_string(), "2025-03-30T00:59:59+00:00");
+        assert_eq!(after.to_string(), "2025-03-30T02:00:00+01:00");
+
+        // Southern hemisphere saving spans the new year
+        let sydney = zone("Australia/Sydney");
+        let january = ZonedTime::from_local(datetime!(2025-01-15 12:00), sydney);
+        let july = ZonedTime::from_local(datetime!(2025-07-15 12:00), sydney);
+        assert_eq!(january.to_string(), "2025-01-15T12:00:00+11:00");
+        assert_eq!(july.to_string(), "2025-07-15T12:00:00+10:00");
+
+        // Skipped local time reads forward, repeated time reads as daylight
+        let new_york = zone("America/New_York");
+        let skipped = ZonedTime::from_local(datetime!(2025-03-09 02:30), new_york);
+        assert_eq!(skipped.to_string(), "2025-03-09T03:30:00-04:00");
+        let repeated = ZonedTime::from_local(datetime!(2025-11-02 01:30), new_york);
+        assert_eq!(repeated.to_string(), "2025-11-02T01:30:00-04:00");
+    }
+
+    #[test]
+    fn test_durations_across_zones() {
+        // KUL 23:30 to NRT 07:30 the next morning is 7 hours, not 8
+        let departs =
+            ZonedTime::parse_local("2025-01-15T23:30:00", zone("Asia/Kuala_Lumpur")).unwrap();
+        let arrives = ZonedTime::parse_local("2025-01-16T07:30:00", zone("Asia/Tokyo")).unwrap();
+        assert_eq!(departs.minutes_until(&arrives), 420);
+        assert_eq!(departs.days_until(&arrives), 1);
+
+        // Eastbound over the date line lands earlier by the clock
+        let akl = ZonedTime::parse_local("2025-01-15T10:00", zone("Pacific/Auckland")).unwrap();
+        let lax = ZonedTime::parse_local("2025-01-15T01:00", zone("America/Los_Angeles")).unwrap();
+        assert_eq!(akl.minutes_until(&lax), 12 * 60);
+        assert_eq!(akl.days_until(&lax), 0);
+    }
+}
@@ -0,0 +1,479 @@
+//! Admin user management
+//!
+//! Suspending accounts, merging duplicates, and overriding tiers. Every
+//! operation is recorded in the [`AuditLog`] with the acting admin and a
+//! reason.
+
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+use vaya_auth::SessionStore;
+use vaya_common::{Timestamp, UserTier, Uuid};
+
+use crate::error::{CoreError, CoreResult};
+use crate::user::{TierOverride, User, UserService};
+
+/// Kind of audited admin action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// Account suspended
+    Suspend,
+    /// Suspension lifted
+    Unsuspend,
+    /// Duplicate account merged into another
+    Merge,
+    /// Tier override set
+    TierOverride,
+    /// Tier override cleared
+    TierOverrideCleared,
+}
+
+impl AuditAction {
+    /// Get action code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Suspend => "user.suspend",
+            AuditAction::Unsuspend => "user.unsuspend",
+            AuditAction::Merge => "user.merge",
+            AuditAction::TierOverride => "user.tier_override",
+            AuditAction::TierOverrideCleared => "user.tier_override_cleared",
+        }
+    }
+}
+
+/// An audit log entry
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// Entry ID
+    pub id: String,
+    /// Admin who performed the action
+    pub actor_id: String,
+    /// Action performed
+    pub action: AuditAction,
+    /// Affected user
+    pub target_user_id: String,
+    /// Reason given by the admin
+    pub reason: String,
+    /// Extra details (e.g. merge counts, new tier)
+    pub details: Option<String>,
+    /// When the action happened
+    pub at: Timestamp,
+}
+
+/// Append-only log of admin actions
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    entries: RwLock<Vec<AuditEntry>>,
+}
+
+impl AuditLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an entry
+    pub fn record(
+        &self,
+        actor_id: &str,
+        action: AuditAction,
+        target_user_id: &str,
+        reason: &str,
+        details: Option<String>,
+    ) -> AuditEntry {
+        let entry = AuditEntry {
+            id: Uuid::new_v4().to_string(),
+            actor_id: actor_id.to_string(),
+            action,
+            target_user_id: target_user_id.to_string(),
+            reason: reason.to_string(),
+            details,
+            at: Timestamp::now(),
+        };
+        info!(
+            actor = %entry.actor_id,
+            action = entry.action.as_str(),
+            target = %entry.target_user_id,
+            "Admin action"
+        );
+        self.entries.write().unwrap().push(entry.clone());
+        entry
+    }
+
+    /// Entries affecting a user, oldest first
+    pub fn entries_for(&self, user_id: &str) -> Vec<AuditEntry> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.target_user_id == user_id)
+            .cloned()
+            .collect()
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.read().unwrap().clone()
+    }
+}
+
+/// A store of records owned by a user account (bookings, alerts, points)
+///
+/// Registered with [`AdminService::with_owned_data`] so that merging
+/// accounts re-parents the records to the surviving account.
+pub trait OwnedRecords: Send + Sync {
+    /// Kind of record, e.g. "bookings"
+    fn kind(&self) -> &'static str;
+
+    /// Move all records owned by `from_user` to `to_user`, returning how
+    /// many were moved
+    fn reassign_owner(&self, from_user: &str, to_user: &str) -> CoreResult<usize>;
+}
+
+/// Outcome of merging two accounts
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// The surviving account after the merge
+    pub user: User,
+    /// Records moved per kind
+    pub moved: Vec<(&'static str, usize)>,
+}
+
+/// Admin operations on user accounts
+pub struct AdminService {
+    /// User service
+    users: Arc<UserService>,
+    /// Session store to revoke sessions from
+    sessions: Option<Arc<SessionStore>>,
+    /// Audit log
+    audit: Arc<AuditLog>,
+    /// Stores re-parented on merge
+    owned: Vec<Arc<dyn OwnedRecords>>,
+}
+
+impl AdminService {
+    /// Create an admin service
+    pub fn new(users: Arc<UserService>, audit: Arc<AuditLog>) -> Self {
+        Self {
+            users,
+            sessions: None,
+            audit,
+            owned: Vec::new(),
+        }
+    }
+
+    /// Revoke sessions in this store when suspending or merging accounts
+    pub fn with_sessions(mut self, sessions: Arc<SessionStore>) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    /// Register a store of user-owned records to re-parent on merge
+    pub fn with_owned_data(mut self, records: Arc<dyn OwnedRecords>) -> Self {
+        self.owned.push(records);
+        self
+    }
+
+    /// Get the audit log
+    pub fn audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Suspend an account, revoking all of its sessions and tokens
+    pub async fn suspend_user(
+        &self,
+        actor_id: &str,
+        user_id: &str,
+        reason: &str,
+    ) -> CoreResult<User> {
+        require_reason(reason)?;
+        let user = self.users.suspend(user_id, reason).await?;
+        self.revoke_sessions(user_id);
+        self.audit
+            .record(actor_id, AuditAction::Suspend, user_id, reason, None);
+        Ok(user)
+    }
+
+    /// Lift a suspension
+    pub async fn unsuspend_user(
+        &self,
+        actor_id: &str,
+        user_id: &str,
+        reason: &str,
+    ) -> CoreResult<User> {
+        require_reason(reason)?;
+        let user = self.users.unsuspend(user_id).await?;
+        self.audit
+            .record(actor_id, AuditAction::Unsuspend, user_id, reason, None);
+        Ok(user)
+    }
+
+    /// Merge a duplicate account into a surviving one
+    ///
+    /// Owned records are re-parented before the source is deleted so that a
+    /// failure leaves the source account intact.
+    pub async fn merge_users(
+        &self,
+        actor_id: &str,
+        source_id: &str,
+        target_id: &str,
+        reason: &str,
+    ) -> CoreResult<MergeResult> {
+        require_reason(reason)?;
+        if source_id == target_id {
+            return Err(CoreError::ValidationError(
+                "Cannot merge an account into itself".to_string(),
+            ));
+        }
+        // Fail fast before moving anything
+        self.users.get_user(source_id).await?;
+        self.users.get_user(target_id).await?;
+
+        let mut moved = Vec::with_capacity(self.owned.len());
+        for records in &self.owned {
+            moved.push((
+                records.kind(),
+                records.reassign_owner(source_id, target_id)?,
+            ));
+        }
+
+        let user = self.users.merge_accounts(source_id, target_id).await?;
+        self.revoke_sessions(source_id);
+
+        let details = moved
+            .iter()
+            .map(|(kind, n)| format!("{}={}", kind, n))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.audit.record(
+            actor_id,
+            AuditAction::Merge,
+            source_id,
+            reason,
+            Some(format!("into={};{}", target_id, details)),
+        );
+
+        Ok(MergeResult { user, moved })
+    }
+
+    /// Override a user's tier, optionally until `expires_at`
+    pub async fn override_tier(
+        &self,
+        actor_id: &str,
+        user_id: &str,
+        tier: UserTier,
+        expires_at: Option<Timestamp>,
+        reason: &str,
+    ) -> CoreResult<User> {
+        require_reason(reason)?;
+        let tier_override = TierOverride {
+            tier,
+            expires_at,
+            reason: reason.to_string(),
+            granted_by: actor_id.to_string(),
+        };
+        let user = self
+            .users
+            .set_tier_override(user_id, Some(tier_override))
+            .await?;
+
+        let details = match expires_at {
+            Some(exp) => format!("tier={};expires_at={}", tier.as_str(), exp.as_unix()),
+            None => format!("tier={}", tier.as_str()),
+        };
+        self.audit.record(
+            actor_id,
+            AuditAction::TierOverride,
+            user_id,
+            reason,
+            Some(details),
+        );
+        Ok(user)
+    }
+
+    /// Remove a tier override
+    pub async fn clear_tier_override(
+        &self,
+        actor_id: &str,
+        user_id: &str,
+        reason: &str,
+    ) -> CoreResult<User> {
+        require_reason(reason)?;
+        let user = self.users.set_tier_override(user_id, None).await?;
+        self.audit.record(
+            actor_id,
+            AuditAction::TierOverrideCleared,
+            user_id,
+            reason,
+            None,
+        );
+        Ok(user)
+    }
+
+    fn revoke_sessions(&self, user_id: &str) {
+        if let Some(ref sessions) = self.sessions {
+            sessions.remove_user_sessions(user_id);
+        }
+    }
+}
+
+fn require_reason(reason: &str) -> CoreResult<()> {
+    if reason.trim().is_empty() {
+        return Err(CoreError::MissingField("reason".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::{AuthConfig, LoginRequest, RegisterRequest, UserStatus};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    struct Bookings(Mutex<HashMap<String, String>>);
+
+    impl OwnedRecords for Bookings {
+        fn kind(&self) -> &'static str {
+            "bookings"
+        }
+
+        fn reassign_owner(&self, from_user: &str, to_user: &str) -> CoreResult<usize> {
+            let mut owners = self.0.lock().unwrap();
+            let mut moved = 0;
+            for owner in owners.values_mut().filter(|o| *o == from_user) {
+                *owner = to_user.to_string();
+                moved += 1;
+            }
+            Ok(moved)
+        }
+    }
+
+    async fn register(users: &UserService, email: &str) -> (User, String) {
+        let response = users
+            .register(RegisterRequest {
+                email: email.to_string(),
+                password: "StrongP@ssw0rd!123".to_string(),
+                first_name: "Test".to_string(),
+                last_name: "User".to_string(),
+                phone: None,
+                marketing_opt_in: false,
+            })
+            .await
+            .unwrap();
+        (response.user, response.access_token)
+    }
+
+    fn service() -> (Arc<UserService>, AdminService) {
+        let users = Arc::new(UserService::new(AuthConfig::new(
+            "test-secret-key-32-bytes-long!!",
+        )));
+        let admin = AdminService::new(users.clone(), Arc::new(AuditLog::new()));
+        (users, admin)
+    }
+
+    #[tokio::test]
+    async fn test_suspend_revokes_access() {
+        let (users, admin) = service();
+        let sessions = Arc::new(SessionStore::new());
+        let admin = admin.with_sessions(sessions.clone());
+        let (user, token) = register(&users, "a@example.com").await;
+        sessions.create(&user.id).unwrap();
+
+        assert!(admin.suspend_user("admin", &user.id, "  ").await.is_err());
+        let suspended = admin
+            .suspend_user("admin", &user.id, "fraud")
+            .await
+            .unwrap();
+        assert_eq!(suspended.status, UserStatus::Suspended);
+        assert!(users.verify_token(&token).is_err());
+        assert!(sessions.get_user_sessions(&user.id).is_empty());
+
+        admin
+            .unsuspend_user("admin", &user.id, "appeal accepted")
+            .await
+            .unwrap();
+        // Old tokens stay revoked; a fresh login works
+        assert!(users.verify_token(&token).is_err());
+        let login = users
+            .login(LoginRequest {
+                email: "a@example.com".to_string(),
+                password: "StrongP@ssw0rd!123".to_string(),
+            })
+            .await
+            .unwrap();
+        assert!(users.verify_token(&login.access_token).is_ok());
+
+        let log = admin.audit_log().entries_for(&user.id);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].action, AuditAction::Suspend);
+        assert_eq!(log[0].reason, "fraud");
+    }
+
+    #[tokio::test]
+    async fn test_merge_reparents_records() {
+        let (users, admin) = service();
+        let (source, _) = register(&users, "dup@example.com").await;
+        let (target, _) = register(&users, "main@example.com").await;
+
+        let bookings = Arc::new(Bookings(Mutex::new(HashMap::from([
+            ("b1".to_string(), source.id.clone()),
+            ("b2".to_string(), source.id.clone()),
+            ("b3".to_string(), target.id.clone()),
+        ]))));
+        let admin = admin.with_owned_data(bookings.clone());
+
+        let result = admin
+            .merge_users("admin", &source.id, &target.id, "duplicate signup")
+            .await
+            .unwrap();
+        assert_eq!(result.moved, vec![("bookings", 2)]);
+        assert!(bookings.0.lock().unwrap().values().all(|o| *o == target.id));
+
+        let merged = users.get_user(&source.id).await.unwrap();
+        assert_eq!(merged.status, UserStatus::Deleted);
+        assert_eq!(merged.merged_into.as_deref(), Some(target.id.as_str()));
+
+        assert!(admin
+            .merge_users("admin", &target.id, &target.id, "oops")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tier_override_with_expiry() {
+        let (users, admin) = service();
+        let (user, _) = register(&users, "vip@example.com").await;
+
+        let expires = Timestamp::now().add_days(7);
+        let updated = admin
+            .override_tier(
+                "admin",
+                &user.id,
+                UserTier::Premium,
+                Some(expires),
+                "goodwill",
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.tier, UserTier::Free);
+        assert_eq!(updated.effective_tier(), UserTier::Premium);
+        assert_eq!(
+            updated.effective_tier_at(expires.add_secs(1)),
+            UserTier::Free
+        );
+
+        let past = Timestamp::now().add_days(-1);
+        assert!(admin
+            .override_tier("admin", &user.id, UserTier::Premium, Some(past), "late")
+            .await
+            .is_err());
+
+        let cleared = admin
+            .clear_tier_override("admin", &user.id, "ended")
+            .await
+            .unwrap();
+        assert_eq!(cleared.effective_tier(), UserTier::Free);
+        assert_eq!(admin.audit_log().entries().len(), 2);
+    }
+}
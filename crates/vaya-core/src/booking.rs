@@ -1,14 +1,21 @@
 //! Booking service
 
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, info, warn};
 
+use vaya_common::bus::{self, topics};
+use vaya_common::events::{BookingCreated, PaymentCaptured};
 use vaya_common::{Price, Timestamp, Uuid};
 use vaya_gds::GdsProvider;
 use vaya_notification::{EmailClient, EmailRequest, NotificationConfig, NotificationType};
 use vaya_payment::{PaymentProvider, PaymentRequest, PaymentStatus, RefundReason, RefundRequest};
 
 use crate::error::{CoreError, CoreResult};
+use crate::eticket::{self, ETicket};
+use crate::fare_check::{self, FareCheckOutcome, PriceTolerance, VerifiedFare};
+use crate::price_variance::PriceVarianceMonitor;
+use crate::pricing::PricingContext;
 use crate::search::SearchService;
 use crate::types::*;
 
@@ -23,6 +30,8 @@ pub struct BookingConfig {
     pub send_confirmation_email: bool,
     /// Send confirmation SMS
     pub send_confirmation_sms: bool,
+    /// Tolerance for fare changes found by re-verification
+    pub price_tolerance: PriceTolerance,
 }
 
 impl Default for BookingConfig {
@@ -32,6 +41,7 @@ impl Default for BookingConfig {
             auto_cancel_on_timeout: true,
             send_confirmation_email: true,
             send_confirmation_sms: true,
+            price_tolerance: PriceTolerance::default(),
         }
     }
 }
@@ -39,7 +49,7 @@ impl Default for BookingConfig {
 /// Booking service
 pub struct BookingService<G, P>
 where
-    G: GdsProvider + Send + Sync,
+    G: GdsProvider + Send + Sync + 'static,
     P: PaymentProvider + Send + Sync,
 {
     /// Search service for validating offers
@@ -50,11 +60,13 @@ where
     email: Option<EmailClient>,
     /// Configuration
     config: BookingConfig,
+    /// Displayed vs charged price checks
+    variance: Arc<PriceVarianceMonitor>,
 }
 
 impl<G, P> BookingService<G, P>
 where
-    G: GdsProvider + Send + Sync,
+    G: GdsProvider + Send + Sync + 'static,
     P: PaymentProvider + Send + Sync,
 {
     /// Create new booking service
@@ -73,6 +85,7 @@ where
             payment,
             email,
             config: BookingConfig::default(),
+            variance: Arc::new(PriceVarianceMonitor::default()),
         })
     }
 
@@ -82,6 +95,12 @@ where
         self
     }
 
+    /// Set the monitor checking captured amounts against displayed totals
+    pub fn with_variance_monitor(mut self, monitor: Arc<PriceVarianceMonitor>) -> Self {
+        self.variance = monitor;
+        self
+    }
+
     /// Create a new booking
     pub async fn create_booking(&self, request: BookingRequest) -> CoreResult<Booking> {
         info!(
@@ -89,8 +108,17 @@ where
             request.offer_id, request.user_id
         );
 
-        // Validate the offer is still available
-        let offer = self.search.get_offer(&request.offer_id).await?;
+        // Validate the offer is still available, re-pricing it if we know
+        // what the user was shown
+        let offer = match &request.displayed_price {
+            Some(displayed) => {
+                self.verify_fare(&request.offer_id, displayed)
+                    .await?
+                    .require_accepted()?
+                    .offer
+            }
+            None => self.search.get_offer(&request.offer_id).await?,
+        };
 
         // Validate offer hasn't expired
         if offer.expires_at < Timestamp::now() {
@@ -110,6 +138,20 @@ where
         // Validate passengers
         self.validate_passengers(&request.passengers)?;
 
+        // Re-apply the displayed policy version with the buyer's tier and
+        // payment method so the booking keeps an exact itemization
+        let pricing = match (self.search.pricing(), &offer.retail) {
+            (Some(engine), Some(quote)) => match PricingContext::for_offer(&offer) {
+                Some(ctx) => {
+                    let mut ctx = ctx.with_tier(request.user_tier);
+                    ctx.payment_method = request.payment_method.clone();
+                    Some(engine.requote(quote, &ctx)?)
+                }
+                None => offer.retail.clone(),
+            },
+            _ => offer.retail.clone(),
+        };
+
         // Generate booking ID
         let booking_id = Uuid::new_v4().to_string();
         let pnr = self.generate_pnr();
@@ -118,19 +160,26 @@ where
         let payment_deadline =
             Timestamp::now().add_mins(self.config.payment_timeout_minutes as i64);
 
+        // The review page shows this total; the captured amount is later
+        // checked against it
+        let total_price = pricing.as_ref().map_or(offer.price, |p| p.total);
+
         // Create booking record
         let booking = Booking {
             id: booking_id.clone(),
             pnr: pnr.clone(),
             user_id: request.user_id.clone(),
             status: BookingStatus::PendingPayment,
-            flights: offer,
+            flights: offer.clone(),
             passengers: request.passengers,
             contact: request.contact,
-            total_price: Price::new(
-                vaya_common::MinorUnits::new(0), // Would calculate from offer
-                vaya_common::CurrencyCode::MYR,
-            ),
+            total_price,
+            displayed_total: Some(total_price),
+            settlement_blocked: false,
+            pricing,
+            price_lock: None,
+            promotions: Vec::new(),
+            wallet_credit: None,
             payment_id: None,
             created_at: Timestamp::now(),
             updated_at: Timestamp::now(),
@@ -139,12 +188,76 @@ where
         };
 
         info!("Booking {} created with PNR {}", booking_id, pnr);
+        bus::publish(
+            &topics::BOOKING_CREATED,
+            BookingCreated {
+                booking_id: booking.id.clone(),
+                pnr: booking.pnr.clone(),
+                user_id: booking.user_id.clone(),
+                offer_id: request.offer_id,
+                passenger_count: booking.passengers.len() as u32,
+                amount_minor: booking.total_price.amount.as_i64(),
+                currency: booking.total_price.currency.as_str().to_string(),
+            },
+        );
+
+        // Seats were taken on the booked routes; drop cached searches so
+        // later searches see current availability
+        let journeys = std::iter::once(&offer.outbound)
+            .chain(offer.inbound.as_ref())
+            .chain(&offer.onward);
+        for journey in journeys {
+            if let (Some(first), Some(last)) = (journey.segments.first(), journey.segments.last()) {
+                self.search
+                    .invalidate_route(&first.origin, &last.destination);
+            }
+        }
 
         // In production, would persist to database here
 
         Ok(booking)
     }
 
+    /// Re-price an offer and compare it with the price the user saw
+    ///
+    /// Callers proceeding from review should treat
+    /// [`FareCheckOutcome::NeedsConfirmation`] as a price change to show
+    /// the user (see [`VerifiedFare::require_accepted`]).
+    pub async fn verify_fare(&self, offer_id: &str, displayed: &Price) -> CoreResult<VerifiedFare> {
+        let started = Instant::now();
+        let result = self.search.reprice_offer(offer_id).await;
+        let latency = started.elapsed();
+
+        let offer = match result {
+            Ok(offer) => offer,
+            Err(e) => {
+                fare_check::record_verification("unavailable", latency);
+                return Err(e);
+            }
+        };
+
+        let outcome =
+            fare_check::compare_prices(displayed, &offer.price, &self.config.price_tolerance);
+        fare_check::record_verification(outcome.as_str(), latency);
+        if outcome != FareCheckOutcome::Unchanged {
+            info!(
+                "Fare for offer {} changed from {} to {} ({})",
+                offer_id,
+                displayed.format(),
+                offer.price.format(),
+                outcome.as_str()
+            );
+        }
+
+        Ok(VerifiedFare {
+            current: offer.price,
+            offer,
+            displayed: *displayed,
+            outcome,
+            latency,
+        })
+    }
+
     /// Process payment for a booking
     pub async fn process_payment(
         &self,
@@ -207,6 +320,20 @@ where
                     "Payment successful for booking {}: {}",
                     booking.id, payment_intent.id
                 );
+                // A capture that doesn't match the review total holds
+                // settlement for finance
+                self.variance
+                    .check_capture(booking, &payment_intent.id, &payment_intent.amount)
+                    .await;
+                bus::publish(
+                    &topics::PAYMENT_CAPTURED,
+                    PaymentCaptured {
+                        booking_id: booking.id.clone(),
+                        payment_id: payment_intent.id.clone(),
+                        amount_minor: booking.total_price.amount.as_i64(),
+                        currency: booking.total_price.currency.as_str().to_string(),
+                    },
+                );
 
                 // Send confirmation
                 if self.config.send_confirmation_email {
@@ -409,7 +536,14 @@ where
         .with_context(
             "total_amount",
             format!("{:.2}", booking.total_price.amount.as_i64() as f64 / 100.0),
-        );
+        )
+        .with_context("assistance", assistance_summary(&booking.passengers))
+        .with_context("passengers", passenger_names(&booking.passengers))
+        .with_context("segments", segment_summary(&booking.flights));
+        let email = ETicket::new(booking)
+            .attachments()
+            .into_iter()
+            .fold(email, EmailRequest::with_attachment);
 
         email_client
             .send(&email)
@@ -421,6 +555,54 @@ where
     }
 }
 
+/// Passenger names as printed on tickets, e.g. "RAHMAN/AISYAH MS"
+fn passenger_names(passengers: &[PassengerDetails]) -> Vec<String> {
+    passengers
+        .iter()
+        .map(|p| format!("{}/{} {}", p.last_name, p.first_name, p.title).to_uppercase())
+        .collect()
+}
+
+/// Every segment of the booked journeys with airport local times
+fn segment_summary(offer: &FlightOffer) -> Vec<serde_json::Value> {
+    std::iter::once(&offer.outbound)
+        .chain(offer.inbound.as_ref())
+        .chain(&offer.onward)
+        .flat_map(|journey| &journey.segments)
+        .map(|s| {
+            serde_json::json!({
+                "flight_number": format!("{}{}", s.airline.as_str(), s.flight_number),
+                "origin": s.origin.as_str(),
+                "departure": eticket::local_time(&s.departure_time),
+                "destination": s.destination.as_str(),
+                "arrival": eticket::local_time(&s.arrival_time),
+            })
+        })
+        .collect()
+}
+
+/// Assistance needs per passenger, for booking documents
+///
+/// Passengers without an accessibility SSR are left out.
+fn assistance_summary(passengers: &[PassengerDetails]) -> Vec<serde_json::Value> {
+    passengers
+        .iter()
+        .filter_map(|p| {
+            let needs: Vec<&str> = p
+                .special_requests
+                .iter()
+                .filter_map(|code| vaya_book::describe_ssr(code))
+                .collect();
+            (!needs.is_empty()).then(|| {
+                serde_json::json!({
+                    "name": format!("{}/{}", p.last_name, p.first_name).to_uppercase(),
+                    "needs": needs,
+                })
+            })
+        })
+        .collect()
+}
+
 /// Payment result
 #[derive(Debug, Clone)]
 pub struct PaymentResult {
@@ -465,9 +647,40 @@ mod tests {
             auto_cancel_on_timeout: false,
             send_confirmation_email: true,
             send_confirmation_sms: false,
+            price_tolerance: PriceTolerance::strict(),
         };
 
         assert_eq!(config.payment_timeout_minutes, 60);
         assert!(!config.auto_cancel_on_timeout);
     }
+
+    #[test]
+    fn test_assistance_summary() {
+        let passenger = |first: &str, requests: &[&str]| PassengerDetails {
+            passenger_type: PassengerType::Adult,
+            title: "MS".into(),
+            first_name: first.into(),
+            last_name: "Rahman".into(),
+            date_of_birth: "1950-01-01".into(),
+            gender: Gender::Female,
+            nationality: "MY".into(),
+            passport_number: None,
+            passport_expiry: None,
+            email: None,
+            phone: None,
+            frequent_flyer: None,
+            special_requests: requests.iter().map(|r| r.to_string()).collect(),
+        };
+        let summary = assistance_summary(&[
+            passenger("Aisyah", &["WCHS", "VGML"]),
+            passenger("Nur", &["VGML"]),
+        ]);
+
+        assert_eq!(summary.len(), 1);
+        assert_eq!(summary[0]["name"], "RAHMAN/AISYAH");
+        assert_eq!(
+            summary[0]["needs"],
+            serde_json::json!(["Wheelchair to aircraft seat (cannot climb steps)"])
+        );
+    }
 }
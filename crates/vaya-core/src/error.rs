@@ -10,11 +10,20 @@ pub type CoreResult<T> = Result<T, CoreError>;
 pub enum CoreError {
     // === Search Errors ===
     /// No flights found for search criteria
-    NoFlightsFound { origin: String, destination: String },
+    NoFlightsFound {
+        /// Origin airport code
+        origin: String,
+        /// Destination airport code
+        destination: String,
+    },
     /// Search timeout
     SearchTimeout,
     /// Invalid search parameters
     InvalidSearchParams(String),
+    /// Saved search not found (or expired)
+    SavedSearchNotFound(String),
+    /// No price history to base a prediction on
+    PredictionUnavailable(String),
 
     // === Booking Errors ===
     /// Booking not found
@@ -28,9 +37,21 @@ pub enum CoreError {
     /// Fare no longer available
     FareNotAvailable(String),
     /// Price changed
-    PriceChanged { expected: i64, actual: i64 },
+    PriceChanged {
+        /// Price the caller expected, in minor units
+        expected: i64,
+        /// Current price, in minor units
+        actual: i64,
+    },
     /// Insufficient seats
-    InsufficientSeats { requested: u8, available: u8 },
+    InsufficientSeats {
+        /// Seats requested
+        requested: u8,
+        /// Seats still available
+        available: u8,
+    },
+    /// Selected seat is not on the seat map or not free
+    SeatUnavailable(String),
 
     // === User Errors ===
     /// User not found
@@ -50,9 +71,23 @@ pub enum CoreError {
     /// Refund failed
     RefundFailed(String),
 
+    // === Pool Errors ===
+    /// Pool not found
+    PoolNotFound(String),
+    /// Pool operation rejected in the pool's current state
+    PoolError(String),
+
+    // === Alert Errors ===
+    /// Price alert not found
+    AlertNotFound(String),
+    /// Price alert already exists
+    AlertAlreadyExists(String),
+
     // === Notification Errors ===
     /// Notification failed
     NotificationFailed(String),
+    /// Outbox message not found
+    NotificationNotFound(String),
 
     // === Validation Errors ===
     /// Validation error
@@ -63,6 +98,13 @@ pub enum CoreError {
     // === System Errors ===
     /// Database error
     Database(String),
+    /// Stored record changed since it was read
+    VersionConflict {
+        /// Version the caller read
+        expected: u32,
+        /// Version now stored
+        actual: u32,
+    },
     /// Cache error
     Cache(String),
     /// GDS provider error
@@ -71,6 +113,10 @@ pub enum CoreError {
     Internal(String),
     /// Service unavailable
     ServiceUnavailable(String),
+
+    // Jobs
+    /// Background job not found
+    JobNotFound(String),
 }
 
 impl fmt::Display for CoreError {
@@ -85,6 +131,8 @@ impl fmt::Display for CoreError {
             }
             CoreError::SearchTimeout => write!(f, "Search timed out"),
             CoreError::InvalidSearchParams(msg) => write!(f, "Invalid search parameters: {}", msg),
+            CoreError::SavedSearchNotFound(id) => write!(f, "Saved search not found: {}", id),
+            CoreError::PredictionUnavailable(msg) => write!(f, "Prediction unavailable: {}", msg),
 
             // Booking
             CoreError::BookingNotFound(id) => write!(f, "Booking not found: {}", id),
@@ -106,6 +154,8 @@ impl fmt::Display for CoreError {
                 )
             }
 
+            CoreError::SeatUnavailable(msg) => write!(f, "Seat unavailable: {}", msg),
+
             // User
             CoreError::UserNotFound(id) => write!(f, "User not found: {}", id),
             CoreError::NotAuthenticated => write!(f, "Authentication required"),
@@ -117,8 +167,17 @@ impl fmt::Display for CoreError {
             CoreError::PaymentNotFound(id) => write!(f, "Payment not found: {}", id),
             CoreError::RefundFailed(msg) => write!(f, "Refund failed: {}", msg),
 
+            // Pool
+            CoreError::PoolNotFound(id) => write!(f, "Pool not found: {}", id),
+            CoreError::PoolError(msg) => write!(f, "Pool error: {}", msg),
+
+            // Alert
+            CoreError::AlertNotFound(id) => write!(f, "Price alert not found: {}", id),
+            CoreError::AlertAlreadyExists(id) => write!(f, "Price alert already exists: {}", id),
+
             // Notification
             CoreError::NotificationFailed(msg) => write!(f, "Notification failed: {}", msg),
+            CoreError::NotificationNotFound(id) => write!(f, "Notification not found: {}", id),
 
             // Validation
             CoreError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
@@ -126,10 +185,18 @@ impl fmt::Display for CoreError {
 
             // System
             CoreError::Database(msg) => write!(f, "Database error: {}", msg),
+            CoreError::VersionConflict { expected, actual } => write!(
+                f,
+                "Record changed concurrently (expected version {}, found {})",
+                expected, actual
+            ),
             CoreError::Cache(msg) => write!(f, "Cache error: {}", msg),
             CoreError::GdsError(msg) => write!(f, "GDS error: {}", msg),
             CoreError::Internal(msg) => write!(f, "Internal error: {}", msg),
             CoreError::ServiceUnavailable(msg) => write!(f, "Service unavailable: {}", msg),
+
+            // Jobs
+            CoreError::JobNotFound(id) => write!(f, "Job not found: {}", id),
         }
     }
 }
@@ -150,9 +217,12 @@ impl CoreError {
         matches!(
             self,
             CoreError::NoFlightsFound { .. }
+                | CoreError::SavedSearchNotFound(_)
+                | CoreError::PredictionUnavailable(_)
                 | CoreError::FareNotAvailable(_)
                 | CoreError::PriceChanged { .. }
                 | CoreError::InsufficientSeats { .. }
+                | CoreError::SeatUnavailable(_)
                 | CoreError::ValidationError(_)
                 | CoreError::MissingField(_)
                 | CoreError::NotAuthenticated
@@ -168,15 +238,25 @@ impl CoreError {
             CoreError::BookingNotFound(_)
             | CoreError::UserNotFound(_)
             | CoreError::PaymentNotFound(_)
+            | CoreError::PoolNotFound(_)
+            | CoreError::AlertNotFound(_)
+            | CoreError::SavedSearchNotFound(_)
+            | CoreError::PredictionUnavailable(_)
+            | CoreError::JobNotFound(_)
+            | CoreError::NotificationNotFound(_)
             | CoreError::NoFlightsFound { .. } => 404,
-            CoreError::BookingAlreadyExists(_) => 409,
+            CoreError::BookingAlreadyExists(_)
+            | CoreError::PoolError(_)
+            | CoreError::AlertAlreadyExists(_)
+            | CoreError::VersionConflict { .. } => 409,
             CoreError::ValidationError(_)
             | CoreError::MissingField(_)
             | CoreError::InvalidSearchParams(_)
             | CoreError::InvalidUserData(_) => 400,
             CoreError::PriceChanged { .. }
             | CoreError::FareNotAvailable(_)
-            | CoreError::InsufficientSeats { .. } => 409,
+            | CoreError::InsufficientSeats { .. }
+            | CoreError::SeatUnavailable(_) => 409,
             CoreError::ServiceUnavailable(_) | CoreError::SearchTimeout => 503,
             _ => 500,
         }
@@ -196,6 +276,34 @@ impl From<vaya_payment::PaymentError> for CoreError {
     }
 }
 
+impl From<vaya_book::BookError> for CoreError {
+    fn from(e: vaya_book::BookError) -> Self {
+        use vaya_book::BookError;
+        match e {
+            BookError::BookingNotFound(pnr) => CoreError::BookingNotFound(pnr),
+            BookError::BookingExists(pnr) => CoreError::BookingAlreadyExists(pnr),
+            BookError::MissingField(field) => CoreError::MissingField(field),
+            BookError::NotCancellable(reason) | BookError::NotChangeable(reason) => {
+                CoreError::BookingNotModifiable(reason)
+            }
+            BookError::PaymentFailed(msg) => CoreError::PaymentFailed(msg),
+            BookError::RefundFailed(msg) => CoreError::RefundFailed(msg),
+            e if e.is_validation() => CoreError::ValidationError(e.to_string()),
+            e => CoreError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<vaya_pool::PoolError> for CoreError {
+    fn from(e: vaya_pool::PoolError) -> Self {
+        match e {
+            vaya_pool::PoolError::PoolNotFound(id) => CoreError::PoolNotFound(id),
+            e if e.is_retriable() => CoreError::Internal(e.to_string()),
+            e => CoreError::PoolError(e.to_string()),
+        }
+    }
+}
+
 impl From<vaya_notification::NotificationError> for CoreError {
     fn from(e: vaya_notification::NotificationError) -> Self {
         CoreError::NotificationFailed(e.to_string())
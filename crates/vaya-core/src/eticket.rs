@@ -0,0 +1,1009 @@
+//! E-ticket and itinerary documents
+//!
+//! [`ETicket`] renders a booking as a standalone HTML page and a simple PDF
+//! for the customer: the PNR with a Code 39 barcode, passengers and their
+//! ticket numbers, every journey's segments in airport local time, baggage
+//! allowance and the fare breakdown. Until ticket numbers are issued the
+//! same document is titled as an itinerary.
+//!
+//! Both formats are drawn from one list of titled tables, so they always
+//! carry the same content. The PDF only uses the standard Helvetica fonts
+//! and needs no embedded resources. [`ETicket::attachments`] packages both
+//! for the confirmation email.
+
+use std::fmt::Write as _;
+
+use vaya_common::{Date, Price, Timestamp};
+use vaya_notification::EmailAttachment;
+
+use crate::types::*;
+
+/// A titled table of the document
+struct Table {
+    title: String,
+    /// Column headers with their relative widths in the PDF
+    columns: &'static [(&'static str, u32)],
+    rows: Vec<Vec<String>>,
+}
+
+/// Renders a booking as an e-ticket receipt or itinerary
+#[derive(Debug, Clone, Copy)]
+pub struct ETicket<'a> {
+    booking: &'a Booking,
+    issued_at: Timestamp,
+}
+
+impl<'a> ETicket<'a> {
+    /// Document for a booking, dated by its last update
+    pub fn new(booking: &'a Booking) -> Self {
+        Self {
+            booking,
+            issued_at: booking.updated_at,
+        }
+    }
+
+    /// Set the issue date printed on the document
+    pub fn with_issued_at(mut self, issued_at: Timestamp) -> Self {
+        self.issued_at = issued_at;
+        self
+    }
+
+    /// Ticket numbers have been issued
+    pub fn is_ticketed(&self) -> bool {
+        !self.booking.ticket_numbers.is_empty()
+    }
+
+    /// Document title
+    pub fn title(&self) -> &'static str {
+        if self.is_ticketed() {
+            "E-ticket receipt"
+        } else {
+            "Flight itinerary"
+        }
+    }
+
+    /// Attachment file name without extension, e.g. `e-ticket-ABC234`
+    pub fn file_stem(&self) -> String {
+        let kind = if self.is_ticketed() {
+            "e-ticket"
+        } else {
+            "itinerary"
+        };
+        format!("{}-{}", kind, self.booking.pnr)
+    }
+
+    /// PDF and HTML versions as email attachments
+    pub fn attachments(&self) -> Vec<EmailAttachment> {
+        let stem = self.file_stem();
+        vec![
+            EmailAttachment::new(format!("{}.pdf", stem), "application/pdf", &self.pdf()),
+            EmailAttachment::new(
+                format!("{}.html", stem),
+                "text/html; charset=utf-8",
+                self.html().as_bytes(),
+            ),
+        ]
+    }
+
+    /// Standalone HTML document
+    pub fn html(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>{} {}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            self.title(),
+            escape_html(&self.booking.pnr),
+            HTML_STYLE,
+            self.title()
+        );
+
+        let _ = writeln!(
+            out,
+            "<p class=\"pnr\">Booking reference <strong>{}</strong></p>",
+            escape_html(&self.booking.pnr)
+        );
+        out.push_str(&barcode_svg(&self.booking.pnr));
+        out.push_str("\n<table class=\"summary\">\n");
+        for (label, value) in self.summary() {
+            let _ = writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                label,
+                escape_html(&value)
+            );
+        }
+        out.push_str("</table>\n");
+
+        for table in self.tables() {
+            let _ = write!(
+                out,
+                "<h2>{}</h2>\n<table>\n<thead><tr>",
+                escape_html(&table.title)
+            );
+            for (column, _) in table.columns {
+                let _ = write!(out, "<th>{}</th>", column);
+            }
+            out.push_str("</tr></thead>\n<tbody>\n");
+            for row in &table.rows {
+                out.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(out, "<td>{}</td>", escape_html(cell));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</tbody>\n</table>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    /// A4 PDF document
+    pub fn pdf(&self) -> Vec<u8> {
+        let mut pages = PdfPages::new();
+
+        pages.text(PDF_MARGIN, 18.0, true, self.title());
+        let (bars, modules) = code39(&self.booking.pnr);
+        let module = 1.2;
+        let left = PDF_WIDTH - PDF_MARGIN - f64::from(modules) * module;
+        for (offset, width) in bars {
+            pages.rect(
+                left + f64::from(offset) * module,
+                pages.y - 40.0,
+                f64::from(width) * module,
+                40.0,
+            );
+        }
+        pages.advance(28.0);
+        pages.text(
+            PDF_MARGIN,
+            12.0,
+            true,
+            &format!("Booking reference {}", self.booking.pnr),
+        );
+        pages.advance(26.0);
+        for (label, value) in self.summary() {
+            pages.text(PDF_MARGIN, 9.0, true, label);
+            pages.text(PDF_MARGIN + 110.0, 9.0, false, &value);
+            pages.advance(13.0);
+        }
+
+        for table in self.tables() {
+            pages.table(&table);
+        }
+        pages.finish(&self.booking.pnr)
+    }
+
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        let booking = self.booking;
+        let mut summary = vec![
+            ("Status", status_label(booking.status).to_string()),
+            ("Issued", Date::from_timestamp(self.issued_at).to_string()),
+            ("Contact", booking.contact.email.clone()),
+        ];
+        if let Some(family) = &booking.flights.fare_conditions.fare_family {
+            summary.push(("Fare", family.clone()));
+        }
+        summary
+    }
+
+    fn tables(&self) -> Vec<Table> {
+        let booking = self.booking;
+        let mut tables = vec![self.passengers()];
+
+        let journeys = std::iter::once(("Outbound".to_string(), &booking.flights.outbound))
+            .chain(
+                booking
+                    .flights
+                    .inbound
+                    .iter()
+                    .map(|j| ("Return".to_string(), j)),
+            )
+            .chain(
+                booking
+                    .flights
+                    .onward
+                    .iter()
+                    .enumerate()
+                    .map(|(i, j)| (format!("Onward {}", i + 1), j)),
+            );
+        for (label, journey) in journeys {
+            tables.push(journey_table(label, journey));
+        }
+
+        let baggage = &booking.flights.baggage_included;
+        tables.push(Table {
+            title: "Baggage allowance".to_string(),
+            columns: &[("Cabin", 1), ("Checked", 1), ("Extra baggage", 1)],
+            rows: vec![vec![
+                baggage.cabin.clone(),
+                baggage.checked.clone(),
+                baggage
+                    .extra_cost
+                    .map_or_else(|| "-".to_string(), |p| p.format()),
+            ]],
+        });
+
+        tables.push(self.fare());
+        tables
+    }
+
+    fn passengers(&self) -> Table {
+        let rows = self
+            .booking
+            .passengers
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                vec![
+                    format!("{}/{} {}", p.last_name, p.first_name, p.title).to_uppercase(),
+                    passenger_type_label(p.passenger_type).to_string(),
+                    self.booking
+                        .ticket_numbers
+                        .get(i)
+                        .cloned()
+                        .unwrap_or_else(|| "Pending".to_string()),
+                    p.frequent_flyer.as_ref().map_or_else(
+                        || "-".to_string(),
+                        |f| format!("{} {}", f.airline.as_str(), f.number),
+                    ),
+                ]
+            })
+            .collect();
+        Table {
+            title: "Passengers".to_string(),
+            columns: &[
+                ("Passenger", 3),
+                ("Type", 1),
+                ("Ticket number", 2),
+                ("Frequent flyer", 2),
+            ],
+            rows,
+        }
+    }
+
+    fn fare(&self) -> Table {
+        let booking = self.booking;
+        let mut rows = Vec::new();
+        let mut line = |label: String, price: Price| rows.push(vec![label, price.format()]);
+
+        if let Some(pricing) = &booking.pricing {
+            line("Base fare".to_string(), pricing.net.base);
+            line("Taxes".to_string(), pricing.net.taxes);
+            if pricing.net.fees.amount.as_i64() != 0 {
+                line("Carrier fees".to_string(), pricing.net.fees);
+            }
+            for item in &pricing.lines {
+                line(item.name.clone(), item.amount);
+            }
+        } else if !booking.flights.price_breakdown.is_empty() {
+            for fare in &booking.flights.price_breakdown {
+                line(
+                    format!(
+                        "{} x {}",
+                        passenger_type_label(fare.passenger_type),
+                        fare.count
+                    ),
+                    fare.total,
+                );
+            }
+        } else {
+            line("Fare".to_string(), booking.flights.price);
+        }
+
+        for promotion in &booking.promotions {
+            line(
+                format!("Promo code {}", promotion.code),
+                negate(promotion.discount),
+            );
+        }
+        if let Some(credit) = booking.wallet_credit {
+            line("Points credit".to_string(), negate(credit));
+        }
+        line("Total".to_string(), booking.total_price);
+
+        Table {
+            title: "Fare breakdown".to_string(),
+            columns: &[("Item", 3), ("Amount", 1)],
+            rows,
+        }
+    }
+}
+
+fn journey_table(label: String, journey: &FlightJourney) -> Table {
+    let title = match (journey.segments.first(), journey.segments.last()) {
+        (Some(first), Some(last)) => format!(
+            "{}: {} to {}",
+            label,
+            place_name(&first.origin),
+            place_name(&last.destination)
+        ),
+        _ => label,
+    };
+    let rows = journey
+        .segments
+        .iter()
+        .map(|s| {
+            let mut flight = format!("{} {}", s.airline.as_str(), s.flight_number);
+            if let Some(operator) = s.operating_carrier.filter(|o| *o != s.airline) {
+                let _ = write!(flight, " (op. {})", operator.as_str());
+            }
+            vec![
+                flight,
+                endpoint(
+                    &s.origin,
+                    &s.departure_time,
+                    s.departure_terminal.as_deref(),
+                ),
+                endpoint(
+                    &s.destination,
+                    &s.arrival_time,
+                    s.arrival_terminal.as_deref(),
+                ),
+                format!("{} ({})", s.cabin_class.display_name(), s.booking_class),
+                s.aircraft.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    Table {
+        title,
+        columns: &[
+            ("Flight", 3),
+            ("Departs", 6),
+            ("Arrives", 6),
+            ("Cabin", 3),
+            ("Aircraft", 2),
+        ],
+        rows,
+    }
+}
+
+/// City name for an airport, falling back to the code
+fn place_name(airport: &vaya_common::IataCode) -> String {
+    airport
+        .airport()
+        .map_or_else(|| airport.as_str().to_string(), |a| a.city.to_string())
+}
+
+/// "KUL 2030-06-01 23:30 +08:00 T1"
+fn endpoint(airport: &vaya_common::IataCode, time: &str, terminal: Option<&str>) -> String {
+    let mut out = format!("{} {}", airport.as_str(), local_time(time));
+    if let Some(terminal) = terminal.filter(|t| !t.is_empty()) {
+        let _ = write!(out, " T{}", terminal.trim_start_matches('T'));
+    }
+    out
+}
+
+/// Airport local time from an ISO 8601 string with its UTC offset
+///
+/// "2030-06-01T23:30:00+08:00" becomes "2030-06-01 23:30 +08:00". Strings
+/// in any other shape are shown as they are.
+pub(crate) fn local_time(iso: &str) -> String {
+    let Some((date, rest)) = iso.split_once('T') else {
+        return iso.to_string();
+    };
+    if date.len() != 10 || rest.len() < 5 || !rest.is_char_boundary(5) {
+        return iso.to_string();
+    }
+    let (clock, zone) = rest.split_at(5);
+    let offset = match zone.find(['+', '-', 'Z']) {
+        Some(i) if &zone[i..] == "Z" => " UTC".to_string(),
+        Some(i) => format!(" {}", &zone[i..]),
+        None => String::new(),
+    };
+    format!("{} {}{}", date, clock, offset)
+}
+
+fn negate(price: Price) -> Price {
+    Price::new(-price.amount, price.currency)
+}
+
+fn passenger_type_label(passenger_type: PassengerType) -> &'static str {
+    match passenger_type {
+        PassengerType::Adult => "Adult",
+        PassengerType::Child => "Child",
+        PassengerType::Infant => "Infant",
+    }
+}
+
+fn status_label(status: BookingStatus) -> &'static str {
+    match status {
+        BookingStatus::PendingPayment => "Awaiting payment",
+        BookingStatus::PaymentProcessing => "Payment processing",
+        BookingStatus::Confirmed => "Confirmed",
+        BookingStatus::Ticketed => "Ticketed",
+        BookingStatus::Cancelled => "Cancelled",
+        BookingStatus::Completed => "Completed",
+        BookingStatus::RefundPending => "Refund pending",
+        BookingStatus::Refunded => "Refunded",
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:Helvetica,Arial,sans-serif;color:#222;\
+max-width:760px;margin:24px auto;padding:0 12px}\
+h1{font-size:22px}h2{font-size:16px;margin:24px 0 6px}\
+table{width:100%;border-collapse:collapse}\
+th,td{text-align:left;padding:4px 8px;border-bottom:1px solid #ddd;font-size:14px}\
+thead th{background:#f2f2f2}.summary th{width:140px}";
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Code 39 bar/space widths, starting with a bar; `1` marks a wide element
+const CODE39: &[(char, &str)] = &[
+    ('0', "000110100"),
+    ('1', "100100001"),
+    ('2', "001100001"),
+    ('3', "101100000"),
+    ('4', "000110001"),
+    ('5', "100110000"),
+    ('6', "001110000"),
+    ('7', "000100101"),
+    ('8', "100100100"),
+    ('9', "001100100"),
+    ('A', "100001001"),
+    ('B', "001001001"),
+    ('C', "101001000"),
+    ('D', "000011001"),
+    ('E', "100011000"),
+    ('F', "001011000"),
+    ('G', "000001101"),
+    ('H', "100001100"),
+    ('I', "001001100"),
+    ('J', "000011100"),
+    ('K', "100000011"),
+    ('L', "001000011"),
+    ('M', "101000010"),
+    ('N', "000010011"),
+    ('O', "100010010"),
+    ('P', "001010010"),
+    ('Q', "000000111"),
+    ('R', "100000110"),
+    ('S', "001000110"),
+    ('T', "000010110"),
+    ('U', "110000001"),
+    ('V', "011000001"),
+    ('W', "111000000"),
+    ('X', "010010001"),
+    ('Y', "110010000"),
+    ('Z', "011010000"),
+    ('-', "010000101"),
+    ('*', "010010100"),
+];
+
+/// Wide elements are this many narrow ones
+const CODE39_WIDE: u32 = 3;
+
+/// Bars of a Code 39 barcode as (offset, width) in narrow-bar modules,
+/// with the total width
+///
+/// The text is wrapped in the `*` start/stop character; characters Code 39
+/// cannot encode are left out.
+fn code39(text: &str) -> (Vec<(u32, u32)>, u32) {
+    let mut bars = Vec::new();
+    let mut x = 0;
+    let chars = std::iter::once('*')
+        .chain(text.chars().map(|c| c.to_ascii_uppercase()))
+        .chain(std::iter::once('*'));
+    for (i, c) in chars.enumerate() {
+        let Some((_, pattern)) = CODE39.iter().find(|(k, _)| *k == c) else {
+            continue;
+        };
+        if i > 0 {
+            // Narrow gap between characters
+            x += 1;
+        }
+        for (j, element) in pattern.bytes().enumerate() {
+            let width = if element == b'1' { CODE39_WIDE } else { 1 };
+            if j % 2 == 0 {
+                bars.push((x, width));
+            }
+            x += width;
+        }
+    }
+    (bars, x)
+}
+
+fn barcode_svg(text: &str) -> String {
+    let (bars, modules) = code39(text);
+    let mut svg = format!(
+        "<svg class=\"barcode\" xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" \
+         height=\"60\" viewBox=\"0 0 {} 60\" role=\"img\" aria-label=\"{}\">",
+        modules * 2,
+        modules,
+        escape_html(text)
+    );
+    for (x, width) in bars {
+        let _ = write!(
+            svg,
+            "<rect x=\"{}\" y=\"0\" width=\"{}\" height=\"60\"/>",
+            x, width
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// A4 in points
+const PDF_WIDTH: f64 = 595.0;
+const PDF_HEIGHT: f64 = 842.0;
+const PDF_MARGIN: f64 = 40.0;
+
+/// Content streams of the pages laid out so far
+struct PdfPages {
+    pages: Vec<String>,
+    /// Top of the next line
+    y: f64,
+}
+
+impl PdfPages {
+    fn new() -> Self {
+        Self {
+            pages: vec![String::new()],
+            y: PDF_HEIGHT - PDF_MARGIN,
+        }
+    }
+
+    fn page(&mut self) -> &mut String {
+        self.pages.last_mut().expect("at least one page")
+    }
+
+    /// Start a new page unless `height` still fits above the footer
+    fn reserve(&mut self, height: f64) {
+        if self.y - height < PDF_MARGIN + 20.0 {
+            self.pages.push(String::new());
+            self.y = PDF_HEIGHT - PDF_MARGIN;
+        }
+    }
+
+    fn advance(&mut self, height: f64) {
+        self.y -= height;
+    }
+
+    /// Text hanging from the current line
+    fn text(&mut self, x: f64, size: f64, bold: bool, text: &str) {
+        let y = self.y - size;
+        self.text_at(x, y, size, bold, text);
+    }
+
+    fn text_at(&mut self, x: f64, y: f64, size: f64, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        let text = escape_pdf(text);
+        let _ = writeln!(
+            self.page(),
+            "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET",
+            font,
+            size,
+            x,
+            y,
+            text
+        );
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) {
+        let _ = writeln!(
+            self.page(),
+            "{:.2} {:.2} {:.2} {:.2} re f",
+            x,
+            y,
+            width,
+            height
+        );
+    }
+
+    fn rule(&mut self) {
+        let y = self.y;
+        let _ = writeln!(
+            self.page(),
+            "0.8 G 0.5 w {:.2} {:.2} m {:.2} {:.2} l S 0 G",
+            PDF_MARGIN,
+            y,
+            PDF_WIDTH - PDF_MARGIN,
+            y
+        );
+    }
+
+    fn table(&mut self, table: &Table) {
+        const SIZE: f64 = 8.0;
+        const ROW: f64 = 14.0;
+
+        let total: u32 = table.columns.iter().map(|(_, w)| w).sum();
+        let unit = (PDF_WIDTH - 2.0 * PDF_MARGIN) / f64::from(total.max(1));
+        let mut lefts = Vec::with_capacity(table.columns.len());
+        let mut x = PDF_MARGIN;
+        for (_, weight) in table.columns {
+            lefts.push((x, f64::from(*weight) * unit));
+            x += f64::from(*weight) * unit;
+        }
+
+        // Keep the title with the header and first row
+        self.advance(14.0);
+        self.reserve(20.0 + 2.0 * ROW);
+        self.text(PDF_MARGIN, 11.0, true, &table.title);
+        self.advance(20.0);
+        for ((name, _), (x, width)) in table.columns.iter().zip(&lefts) {
+            self.text(*x, SIZE, true, &fit(name, *width, SIZE));
+        }
+        self.advance(ROW - 3.0);
+        self.rule();
+        self.advance(3.0);
+        for row in &table.rows {
+            self.reserve(ROW);
+            for (cell, (x, width)) in row.iter().zip(&lefts) {
+                self.text(*x, SIZE, false, &fit(cell, *width, SIZE));
+            }
+            self.advance(ROW);
+        }
+    }
+
+    /// Number the pages and assemble the file
+    fn finish(mut self, pnr: &str) -> Vec<u8> {
+        let count = self.pages.len();
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            let footer = escape_pdf(&format!("{}  -  page {} of {}", pnr, i + 1, count));
+            let _ = writeln!(
+                page,
+                "BT /F1 8 Tf {:.2} {:.2} Td ({}) Tj ET",
+                PDF_MARGIN,
+                PDF_MARGIN - 10.0,
+                footer
+            );
+        }
+
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                (0..count)
+                    .map(|i| format!("{} 0 R", 5 + 2 * i))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                count
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+        for (i, content) in self.pages.iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PDF_WIDTH,
+                PDF_HEIGHT,
+                6 + 2 * i
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ));
+        }
+
+        let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+        }
+        let xref = out.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            trailer,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        out.extend_from_slice(trailer.as_bytes());
+        out
+    }
+}
+
+/// Shorten text to roughly fit `width` points of Helvetica at `size`
+fn fit(text: &str, width: f64, size: f64) -> String {
+    // Average Helvetica glyph width is a little over half the font size
+    let max = ((width - 4.0) / (size * 0.55)).max(4.0) as usize;
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut short: String = text.chars().take(max - 3).collect();
+        short.push_str("...");
+        short
+    }
+}
+
+/// PDF string literal body in WinAnsi encoding
+///
+/// Latin-1 characters keep their code; anything else becomes `?`.
+fn escape_pdf(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", u32::from(c));
+            }
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::{FeeCategory, NetFare, PriceLine, RetailPrice};
+    use crate::promotions::AppliedPromotion;
+    use vaya_common::{AirlineCode, IataCode};
+
+    fn segment(
+        flight: &str,
+        origin: IataCode,
+        destination: IataCode,
+        times: (&str, &str),
+    ) -> FlightSegment {
+        FlightSegment {
+            id: format!("seg-{}", flight),
+            airline: AirlineCode::MH,
+            flight_number: flight.into(),
+            operating_carrier: None,
+            origin,
+            departure_time: times.0.into(),
+            departure_terminal: Some("1".into()),
+            destination,
+            arrival_time: times.1.into(),
+            arrival_terminal: None,
+            duration_minutes: 420,
+            aircraft: Some("A350".into()),
+            cabin_class: CabinClass::Economy,
+            booking_class: "Y".into(),
+        }
+    }
+
+    fn passenger(first: &str, last: &str, passenger_type: PassengerType) -> PassengerDetails {
+        PassengerDetails {
+            passenger_type,
+            title: "Ms".into(),
+            first_name: first.into(),
+            last_name: last.into(),
+            date_of_birth: "1990-01-15".into(),
+            gender: Gender::Female,
+            nationality: "MY".into(),
+            passport_number: None,
+            passport_expiry: None,
+            email: None,
+            phone: None,
+            frequent_flyer: None,
+            special_requests: vec![],
+        }
+    }
+
+    fn booking() -> Booking {
+        let outbound = FlightJourney {
+            segments: vec![segment(
+                "88",
+                IataCode::KUL,
+                IataCode::NRT,
+                ("2030-06-01T23:30:00+08:00", "2030-06-02T07:30:00+09:00"),
+            )],
+            duration_minutes: 420,
+            stops: 0,
+        };
+        let inbound = FlightJourney {
+            segments: vec![segment(
+                "89",
+                IataCode::NRT,
+                IataCode::KUL,
+                ("2030-06-08T10:30:00+09:00", "2030-06-08T16:45:00+08:00"),
+            )],
+            duration_minutes: 435,
+            stops: 0,
+        };
+        let offer = FlightOffer {
+            id: "offer-1".into(),
+            airlines: vec![AirlineCode::MH],
+            outbound,
+            inbound: Some(inbound),
+            onward: vec![],
+            price: Price::myr(150_000),
+            retail: None,
+            price_breakdown: vec![],
+            fare_conditions: FareConditions {
+                cancellation: String::new(),
+                changes: String::new(),
+                refund: String::new(),
+                fare_family: Some("Economy Flex".into()),
+            },
+            cabin_class: CabinClass::Economy,
+            seats_remaining: None,
+            refundable: true,
+            baggage_included: BaggageAllowance {
+                cabin: "7kg".into(),
+                checked: "30kg".into(),
+                extra_cost: None,
+            },
+            expires_at: Timestamp::from_unix(0),
+            source: "test".into(),
+        };
+        Booking {
+            id: "booking-1".into(),
+            pnr: "ABC234".into(),
+            user_id: "user-1".into(),
+            status: BookingStatus::Confirmed,
+            flights: offer,
+            passengers: vec![
+                passenger("Aisyah", "Rahman", PassengerType::Adult),
+                passenger("Nur <Iman>", "Rahman", PassengerType::Child),
+            ],
+            contact: ContactDetails {
+                email: "aisyah@example.com".into(),
+                phone: "+60123456789".into(),
+                emergency_contact_name: None,
+                emergency_contact_phone: None,
+            },
+            total_price: Price::myr(140_000),
+            displayed_total: None,
+            settlement_blocked: false,
+            pricing: Some(RetailPrice {
+                policy_version: 1,
+                net: NetFare {
+                    base: Price::myr(120_000),
+                    taxes: Price::myr(25_000),
+                    fees: Price::myr(0),
+                    total: Price::myr(145_000),
+                },
+                lines: vec![PriceLine {
+                    name: "Service fee".into(),
+                    category: FeeCategory::ServiceFee,
+                    amount: Price::myr(5_000),
+                }],
+                total: Price::myr(150_000),
+            }),
+            price_lock: None,
+            promotions: vec![AppliedPromotion {
+                code: "JUNE10".into(),
+                discount: Price::myr(10_000),
+            }],
+            wallet_credit: None,
+            payment_id: None,
+            // 2030-06-01 00:00 UTC
+            created_at: Timestamp::from_unix(1_906_502_400),
+            updated_at: Timestamp::from_unix(1_906_502_400),
+            payment_deadline: None,
+            ticket_numbers: vec![],
+        }
+    }
+
+    #[test]
+    fn test_html_itinerary() {
+        let mut booking = booking();
+        let ticket = ETicket::new(&booking);
+        assert_eq!(ticket.title(), "Flight itinerary");
+        assert_eq!(ticket.file_stem(), "itinerary-ABC234");
+
+        let html = ticket.html();
+        assert!(html.contains("<strong>ABC234</strong>"));
+        assert!(html.contains("<svg class=\"barcode\""));
+        assert!(html.contains("<td>RAHMAN/AISYAH MS</td>"));
+        // Passenger input is escaped
+        assert!(html.contains("RAHMAN/NUR &lt;IMAN&gt; MS"));
+        assert!(!html.contains("<IMAN>"));
+        assert!(html.contains("<td>Pending</td>"));
+        assert!(html.contains("Outbound: Kuala Lumpur to Tokyo"));
+        assert!(html.contains("Return: Tokyo to Kuala Lumpur"));
+        assert!(html.contains("<td>KUL 2030-06-01 23:30 +08:00 T1</td>"));
+        assert!(html.contains("<td>NRT 2030-06-02 07:30 +09:00</td>"));
+        assert!(html.contains("<td>30kg</td>"));
+        assert!(html.contains("<td>Service fee</td><td>MYR 50.00</td>"));
+        assert!(html.contains("<td>Promo code JUNE10</td><td>MYR -100.00</td>"));
+        assert!(html.contains("<td>Total</td><td>MYR 1400.00</td>"));
+        assert!(html.contains("<td>2030-06-01</td>"));
+
+        booking.ticket_numbers = vec!["2321234567890".into()];
+        let ticket = ETicket::new(&booking);
+        assert_eq!(ticket.title(), "E-ticket receipt");
+        let html = ticket.html();
+        assert!(html.contains("<td>2321234567890</td>"));
+        assert!(html.contains("<td>Pending</td>"));
+    }
+
+    #[test]
+    fn test_pdf_structure() {
+        let mut booking = booking();
+        // Enough segments to run onto a second page
+        let segments = booking.flights.outbound.segments.clone();
+        booking.flights.onward = (0..60)
+            .map(|_| FlightJourney {
+                segments: segments.clone(),
+                duration_minutes: 420,
+                stops: 0,
+            })
+            .collect();
+        let pdf = ETicket::new(&booking).pdf();
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("(Booking reference ABC234) Tj"));
+        assert!(text.contains("(RAHMAN/NUR <IMAN> MS) Tj"));
+        assert!(text.contains("page 1 of "));
+        assert!(!text.contains("page 1 of 1)"));
+
+        // startxref points at the cross-reference table
+        let start = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let offset: usize = text[start..].lines().next().unwrap().parse().unwrap();
+        assert!(pdf[offset..].starts_with(b"xref\n"));
+
+        // Every object offset lands on its header
+        let entries = text[offset..].lines().skip(3);
+        for (i, entry) in entries.take_while(|l| l.ends_with(" n ")).enumerate() {
+            let at: usize = entry[..10].parse().unwrap();
+            assert!(pdf[at..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_attachments() {
+        let booking = booking();
+        let attachments = ETicket::new(&booking).attachments();
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(attachments[0].filename, "itinerary-ABC234.pdf");
+        assert_eq!(attachments[0].content_type, "application/pdf");
+        // "%PDF" in base64
+        assert!(attachments[0].content.starts_with("JVBERi"));
+        assert_eq!(attachments[1].filename, "itinerary-ABC234.html");
+    }
+
+    #[test]
+    fn test_code39() {
+        for (c, pattern) in CODE39 {
+            assert_eq!(pattern.len(), 9, "{}", c);
+            assert_eq!(pattern.bytes().filter(|b| *b == b'1').count(), 3, "{}", c);
+        }
+
+        // Each character is 5 bars and 15 modules wide, plus a gap
+        let (bars, width) = code39("AB2");
+        assert_eq!(bars.len(), 5 * 5);
+        assert_eq!(width, 5 * 15 + 4);
+        assert_eq!(bars[0], (0, 1));
+        // Unsupported characters are skipped
+        assert_eq!(code39("A?B2").1, width);
+    }
+
+    #[test]
+    fn test_local_time_and_escaping() {
+        assert_eq!(
+            local_time("2030-06-01T23:30:00+08:00"),
+            "2030-06-01 23:30 +08:00"
+        );
+        assert_eq!(local_time("2030-06-01T23:30:00Z"), "2030-06-01 23:30 UTC");
+        assert_eq!(local_time("2030-06-01T08:15:00"), "2030-06-01 08:15");
+        assert_eq!(local_time("tomorrow"), "tomorrow");
+
+        assert_eq!(escape_pdf("a(b)\\ Zoë → x"), "a\\(b\\)\\\\ Zo\\353 ? x");
+        assert_eq!(fit("Economy", 100.0, 8.0), "Economy");
+        assert_eq!(
+            fit("A very long airport name indeed", 40.0, 8.0),
+            "A ver..."
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Fare re-verification before checkout
+//!
+//! Cached offers go stale. Before a booking is created the offer is
+//! re-priced with the GDS and compared against the price the user saw.
+//! Differences within the configured [`PriceTolerance`] are accepted
+//! automatically; anything else is surfaced as [`CoreError::PriceChanged`]
+//! so the UI can ask the user to confirm.
+
+use std::time::Duration;
+
+use vaya_common::metrics;
+use vaya_common::Price;
+
+use crate::error::{CoreError, CoreResult};
+use crate::types::FlightOffer;
+
+/// How much a re-priced fare may differ before the user must confirm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceTolerance {
+    /// Largest automatically accepted increase, in basis points of the
+    /// displayed price (100 = 1%)
+    pub max_increase_bps: u32,
+    /// Largest automatically accepted increase in minor units, regardless
+    /// of percentage
+    pub max_increase_minor: i64,
+    /// Accept price drops without asking
+    pub accept_decreases: bool,
+}
+
+impl Default for PriceTolerance {
+    fn default() -> Self {
+        Self {
+            max_increase_bps: 100,
+            max_increase_minor: 500,
+            accept_decreases: true,
+        }
+    }
+}
+
+impl PriceTolerance {
+    /// Require confirmation for any change
+    pub fn strict() -> Self {
+        Self {
+            max_increase_bps: 0,
+            max_increase_minor: 0,
+            accept_decreases: false,
+        }
+    }
+
+    /// Largest increase accepted on the given displayed amount
+    fn allowed_increase(&self, displayed_minor: i64) -> i64 {
+        let by_pct = displayed_minor.saturating_mul(self.max_increase_bps as i64) / 10_000;
+        by_pct.min(self.max_increase_minor)
+    }
+}
+
+/// Outcome of comparing a re-priced fare with the displayed one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FareCheckOutcome {
+    /// Price is unchanged
+    Unchanged,
+    /// Price changed within tolerance and was accepted
+    AutoAccepted,
+    /// Price changed beyond tolerance; the user must confirm
+    NeedsConfirmation,
+}
+
+impl FareCheckOutcome {
+    /// Get outcome label (used as a metrics label)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FareCheckOutcome::Unchanged => "unchanged",
+            FareCheckOutcome::AutoAccepted => "auto_accepted",
+            FareCheckOutcome::NeedsConfirmation => "needs_confirmation",
+        }
+    }
+}
+
+/// A re-verified fare
+#[derive(Debug, Clone)]
+pub struct VerifiedFare {
+    /// Offer with current pricing
+    pub offer: FlightOffer,
+    /// Price the user saw
+    pub displayed: Price,
+    /// Current price
+    pub current: Price,
+    /// Comparison outcome
+    pub outcome: FareCheckOutcome,
+    /// Time spent re-pricing
+    pub latency: Duration,
+}
+
+impl VerifiedFare {
+    /// Current minus displayed price, in minor units
+    pub fn difference_minor(&self) -> i64 {
+        self.current.amount.as_i64() - self.displayed.amount.as_i64()
+    }
+
+    /// Convert a change needing confirmation into [`CoreError::PriceChanged`]
+    pub fn require_accepted(self) -> CoreResult<Self> {
+        if self.outcome == FareCheckOutcome::NeedsConfirmation {
+            return Err(CoreError::PriceChanged {
+                expected: self.displayed.amount.as_i64(),
+                actual: self.current.amount.as_i64(),
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// Compare a displayed price with the current one
+pub fn compare_prices(
+    displayed: &Price,
+    current: &Price,
+    tolerance: &PriceTolerance,
+) -> FareCheckOutcome {
+    if displayed.currency != current.currency {
+        return FareCheckOutcome::NeedsConfirmation;
+    }
+    let displayed_minor = displayed.amount.as_i64();
+    let diff = current.amount.as_i64() - displayed_minor;
+    let within_tolerance = if diff < 0 {
+        tolerance.accept_decreases
+    } else {
+        diff <= tolerance.allowed_increase(displayed_minor)
+    };
+    if diff == 0 {
+        FareCheckOutcome::Unchanged
+    } else if within_tolerance {
+        FareCheckOutcome::AutoAccepted
+    } else {
+        FareCheckOutcome::NeedsConfirmation
+    }
+}
+
+/// Record a verification in the global metrics registry
+pub(crate) fn record_verification(outcome: &str, latency: Duration) {
+    let registry = metrics::global();
+    registry
+        .counter(
+            "vaya_core_fare_verifications_total",
+            &[("outcome", outcome)],
+        )
+        .inc();
+    registry
+        .counter("vaya_core_fare_verification_latency_ms_total", &[])
+        .add(latency.as_millis() as u64);
+    registry
+        .gauge("vaya_core_fare_verification_last_latency_seconds", &[])
+        .set(latency.as_secs_f64());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_prices() {
+        let tolerance = PriceTolerance::default();
+        let displayed = Price::myr(40_000);
+
+        assert_eq!(
+            compare_prices(&displayed, &Price::myr(40_000), &tolerance),
+            FareCheckOutcome::Unchanged
+        );
+        assert_eq!(
+            compare_prices(&displayed, &Price::myr(35_000), &tolerance),
+            FareCheckOutcome::AutoAccepted
+        );
+        // 1% of 400.00 is 4.00, under the 5.00 cap
+        assert_eq!(
+            compare_prices(&displayed, &Price::myr(40_400), &tolerance),
+            FareCheckOutcome::AutoAccepted
+        );
+        assert_eq!(
+            compare_prices(&displayed, &Price::myr(40_401), &tolerance),
+            FareCheckOutcome::NeedsConfirmation
+        );
+        assert_eq!(
+            compare_prices(&displayed, &Price::usd(40_000), &tolerance),
+            FareCheckOutcome::NeedsConfirmation
+        );
+    }
+
+    #[test]
+    fn test_strict_tolerance() {
+        let strict = PriceTolerance::strict();
+        assert_eq!(
+            compare_prices(&Price::myr(40_000), &Price::myr(39_999), &strict),
+            FareCheckOutcome::NeedsConfirmation
+        );
+        assert_eq!(
+            compare_prices(&Price::myr(40_000), &Price::myr(40_000), &strict),
+            FareCheckOutcome::Unchanged
+        );
+    }
+}
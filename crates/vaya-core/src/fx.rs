@@ -0,0 +1,362 @@
+//! Multi-currency pricing
+//!
+//! Fares come back from the GDS in MYR. [`CurrencyConverter`] shows and
+//! charges them in the user's currency using rates from a [`RateSource`]
+//! (normally an FX API fetched through `vaya-collect`), with the configured
+//! FX margin added to every conversion.
+//!
+//! Rates are cached and refetched after [`FxConfig::refresh_after_secs`].
+//! If a refresh fails the cached rates keep being used until they are
+//! [`FxConfig::max_age_secs`] old; after that conversions fail rather than
+//! quote a price from stale rates.
+
+use std::sync::{Arc, RwLock};
+
+use tracing::{debug, warn};
+
+use vaya_collect::Collector;
+use vaya_common::{CurrencyCode, ExchangeRates, Price, Timestamp};
+use vaya_search::PriceBreakdown;
+
+use crate::error::{CoreError, CoreResult};
+
+/// Where exchange rates come from
+pub trait RateSource: Send + Sync {
+    /// Fetch current rates against `base`
+    fn fetch(&self, base: CurrencyCode) -> CoreResult<ExchangeRates>;
+}
+
+/// Rates from a JSON FX API fetched through a [`Collector`]
+///
+/// Expects `{"base": "MYR", "timestamp": 1700000000, "rates": {"SGD": 0.2885, ...}}`
+/// from `{url}?base={base}`.
+pub struct CollectorRateSource {
+    collector: Collector,
+    url: String,
+}
+
+impl CollectorRateSource {
+    /// Create a source for an FX API endpoint
+    pub fn new(collector: Collector, url: impl Into<String>) -> Self {
+        Self {
+            collector,
+            url: url.into(),
+        }
+    }
+}
+
+impl RateSource for CollectorRateSource {
+    fn fetch(&self, base: CurrencyCode) -> CoreResult<ExchangeRates> {
+        let url = format!("{}?base={}", self.url, base);
+        let body = self
+            .collector
+            .fetch_json(&url)
+            .map_err(|e| CoreError::ServiceUnavailable(format!("FX rates: {}", e)))?;
+        parse_rates(&body, base)
+    }
+}
+
+/// Parse an FX API response
+fn parse_rates(body: &str, base: CurrencyCode) -> CoreResult<ExchangeRates> {
+    let json: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| CoreError::ServiceUnavailable(format!("Invalid FX response: {}", e)))?;
+    let quoted = json
+        .get("base")
+        .and_then(|v| v.as_str())
+        .map(CurrencyCode::new);
+    if quoted != Some(base) {
+        return Err(CoreError::ServiceUnavailable(format!(
+            "FX response is not quoted against {}",
+            base
+        )));
+    }
+    let as_of = json
+        .get("timestamp")
+        .and_then(|v| v.as_i64())
+        .map_or_else(Timestamp::now, Timestamp::from_unix);
+    let rates = json
+        .get("rates")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| CoreError::ServiceUnavailable("FX response has no rates".into()))?;
+
+    let mut table = ExchangeRates::new(base, as_of);
+    for (code, rate) in rates {
+        let currency = CurrencyCode::new(code);
+        let parsed = match rate {
+            serde_json::Value::Number(n) => table.set_rate(currency, &n.to_string()),
+            serde_json::Value::String(s) => table.set_rate(currency, s),
+            _ => continue,
+        };
+        if let Err(e) = parsed {
+            warn!(currency = %currency, error = %e, "Skipping FX rate");
+        }
+    }
+    Ok(table)
+}
+
+/// Currency conversion settings
+#[derive(Debug, Clone)]
+pub struct FxConfig {
+    /// Currency fares are priced in
+    pub base: CurrencyCode,
+    /// Currencies users can view, search and pay in
+    pub currencies: Vec<CurrencyCode>,
+    /// Margin added to converted prices, in basis points (100 = 1%)
+    pub margin_bps: u32,
+    /// Refetch rates after this long
+    pub refresh_after_secs: i64,
+    /// Refuse to convert with rates older than this
+    pub max_age_secs: i64,
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        Self {
+            base: CurrencyCode::MYR,
+            currencies: vec![
+                CurrencyCode::MYR,
+                CurrencyCode::SGD,
+                CurrencyCode::USD,
+                CurrencyCode::THB,
+            ],
+            margin_bps: 150,
+            refresh_after_secs: 15 * 60,
+            max_age_secs: 24 * 3600,
+        }
+    }
+}
+
+impl FxConfig {
+    /// Set the FX margin
+    pub fn with_margin_bps(mut self, bps: u32) -> Self {
+        self.margin_bps = bps;
+        self
+    }
+
+    /// Set the supported currencies
+    pub fn with_currencies(mut self, currencies: Vec<CurrencyCode>) -> Self {
+        self.currencies = currencies;
+        self
+    }
+}
+
+/// Cached rates and when they were fetched
+struct CachedRates {
+    rates: ExchangeRates,
+    fetched_at: Timestamp,
+}
+
+/// Converts prices into the user's currency
+pub struct CurrencyConverter {
+    source: Arc<dyn RateSource>,
+    config: FxConfig,
+    cached: RwLock<Option<CachedRates>>,
+}
+
+impl CurrencyConverter {
+    /// Create a converter over a rate source
+    pub fn new(source: Arc<dyn RateSource>, config: FxConfig) -> Self {
+        Self {
+            source,
+            config,
+            cached: RwLock::new(None),
+        }
+    }
+
+    /// The converter's settings
+    pub fn config(&self) -> &FxConfig {
+        &self.config
+    }
+
+    /// Check if users can view and pay in a currency
+    pub fn is_supported(&self, currency: CurrencyCode) -> bool {
+        self.config.currencies.contains(&currency)
+    }
+
+    /// Current rates with the margin applied, refreshing if due
+    pub fn rates(&self) -> CoreResult<ExchangeRates> {
+        let now = Timestamp::now();
+        let cached = self
+            .cached
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|c| (c.rates.clone(), c.fetched_at));
+        if let Some((rates, fetched_at)) = &cached {
+            if now.as_unix() - fetched_at.as_unix() < self.config.refresh_after_secs {
+                return self.usable(rates.clone(), now);
+            }
+        }
+
+        match self.source.fetch(self.config.base) {
+            Ok(rates) => {
+                debug!(base = %rates.base, as_of = %rates.as_of, "Fetched FX rates");
+                *self.cached.write().unwrap() = Some(CachedRates {
+                    rates: rates.clone(),
+                    fetched_at: now,
+                });
+                self.usable(rates, now)
+            }
+            Err(e) => {
+                warn!(error = %e, "FX rate refresh failed, using cached rates");
+                match cached {
+                    Some((rates, _)) => self.usable(rates, now),
+                    None => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Reject stale rates and apply the margin
+    fn usable(&self, rates: ExchangeRates, now: Timestamp) -> CoreResult<ExchangeRates> {
+        let age = rates.age_secs(now);
+        if age > self.config.max_age_secs {
+            return Err(CoreError::ServiceUnavailable(format!(
+                "FX rates are {} hours old",
+                age / 3600
+            )));
+        }
+        Ok(rates.with_margin_bps(self.config.margin_bps))
+    }
+
+    /// Convert a price into a supported currency
+    pub fn convert(&self, price: &Price, to: CurrencyCode) -> CoreResult<Price> {
+        if price.currency == to {
+            return Ok(*price);
+        }
+        let rates = self.rates_for(to)?;
+        price
+            .convert_to(to, &rates)
+            .map_err(|e| CoreError::ValidationError(e.to_string()))
+    }
+
+    /// Convert each part of a fare's price breakdown
+    pub fn convert_breakdown(
+        &self,
+        price: &PriceBreakdown,
+        to: CurrencyCode,
+    ) -> CoreResult<PriceBreakdown> {
+        if price.currency == to {
+            return Ok(price.clone());
+        }
+        let rates = self.rates_for(to)?;
+        let convert = |amount| {
+            rates
+                .convert(amount, price.currency, to)
+                .map_err(|e| CoreError::ValidationError(e.to_string()))
+        };
+        Ok(PriceBreakdown {
+            base_fare: convert(price.base_fare)?,
+            taxes: convert(price.taxes)?,
+            surcharges: convert(price.surcharges)?,
+            seats: convert(price.seats)?,
+            currency: to,
+        })
+    }
+
+    /// Rates for converting into `to`, which must be supported
+    fn rates_for(&self, to: CurrencyCode) -> CoreResult<ExchangeRates> {
+        if !self.is_supported(to) {
+            return Err(CoreError::ValidationError(format!(
+                "Currency {} is not supported",
+                to
+            )));
+        }
+        self.rates()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use vaya_common::MinorUnits;
+
+    /// Serves `rates` until `fail` is set, counting fetches
+    struct FakeSource {
+        age_secs: i64,
+        fail: Mutex<bool>,
+        fetches: Mutex<u32>,
+    }
+
+    impl FakeSource {
+        fn new(age_secs: i64) -> Self {
+            Self {
+                age_secs,
+                fail: Mutex::new(false),
+                fetches: Mutex::new(0),
+            }
+        }
+    }
+
+    impl RateSource for FakeSource {
+        fn fetch(&self, base: CurrencyCode) -> CoreResult<ExchangeRates> {
+            *self.fetches.lock().unwrap() += 1;
+            if *self.fail.lock().unwrap() {
+                return Err(CoreError::ServiceUnavailable("FX API down".into()));
+            }
+            let body = format!(
+                r#"{{"base":"{}","timestamp":{},"rates":{{"SGD":0.2885,"USD":0.2134,"THB":7.65,"XXX":"n/a"}}}}"#,
+                base,
+                Timestamp::now().as_unix() - self.age_secs
+            );
+            parse_rates(&body, base)
+        }
+    }
+
+    #[test]
+    fn test_convert_with_margin() {
+        let converter = CurrencyConverter::new(
+            Arc::new(FakeSource::new(0)),
+            FxConfig::default().with_margin_bps(200),
+        );
+        let price = Price::myr(50_000);
+        let sgd = converter.convert(&price, CurrencyCode::SGD).unwrap();
+        assert_eq!(sgd, Price::new(MinorUnits::new(14_714), CurrencyCode::SGD));
+        assert_eq!(converter.convert(&price, CurrencyCode::MYR).unwrap(), price);
+        assert!(converter.convert(&price, CurrencyCode::JPY).is_err());
+
+        let breakdown = PriceBreakdown {
+            base_fare: MinorUnits::new(40_000),
+            taxes: MinorUnits::new(10_000),
+            surcharges: MinorUnits::ZERO,
+            seats: MinorUnits::ZERO,
+            currency: CurrencyCode::MYR,
+        };
+        let thb = converter
+            .convert_breakdown(&breakdown, CurrencyCode::THB)
+            .unwrap();
+        assert_eq!(thb.currency, CurrencyCode::THB);
+        assert_eq!(thb.base_fare, MinorUnits::new(312_120));
+        assert_eq!(thb.taxes, MinorUnits::new(78_030));
+    }
+
+    #[test]
+    fn test_rates_cached_and_stale_rates_refused() {
+        let source = Arc::new(FakeSource::new(0));
+        let converter = CurrencyConverter::new(source.clone(), FxConfig::default());
+        converter.rates().unwrap();
+        converter.rates().unwrap();
+        assert_eq!(*source.fetches.lock().unwrap(), 1);
+
+        // A failed refresh falls back to the cached rates
+        let config = FxConfig {
+            refresh_after_secs: 0,
+            ..Default::default()
+        };
+        let converter = CurrencyConverter::new(source.clone(), config);
+        converter.rates().unwrap();
+        *source.fail.lock().unwrap() = true;
+        assert!(converter
+            .convert(&Price::myr(100), CurrencyCode::USD)
+            .is_ok());
+
+        // Rates older than the limit are refused
+        let old = Arc::new(FakeSource::new(2 * 24 * 3600));
+        let converter = CurrencyConverter::new(old, FxConfig::default());
+        assert!(converter
+            .convert(&Price::myr(100), CurrencyCode::USD)
+            .is_err());
+    }
+}
@@ -0,0 +1,571 @@
+//! Expiry of unpaid booking holds
+//!
+//! A booking holds seats with the airline, and sometimes a fare, until it
+//! is paid. When its confirmation or payment deadline passes the traveler
+//! first gets a reminder and a grace period. If the booking is still
+//! unpaid once that runs out, [`HoldExpirySweeper`] cancels the GDS order,
+//! releases any fare hold and marks the booking expired. A booking whose
+//! GDS order could not be cancelled is marked failed instead so that an
+//! agent follows up. Every step is recorded in the booking's status
+//! history and notes, and the sweeper keeps running totals for the admin
+//! overview.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use vaya_book::{Booking, BookingStatus};
+use vaya_common::{metrics, Timestamp};
+use vaya_gds::GdsProvider;
+
+use crate::error::{CoreError, CoreResult};
+use crate::notify::Notifier;
+use crate::price_lock::FareHold;
+
+/// Actor recorded on status changes and notes made by the sweeper
+pub const SWEEPER_ACTOR: &str = "SYSTEM:hold-expiry";
+
+/// Hold expiry settings
+#[derive(Debug, Clone)]
+pub struct HoldExpiryConfig {
+    /// Time after the reminder before an unpaid booking is cancelled
+    pub grace_period_secs: i64,
+    /// Payment deadline for confirmed bookings that have none recorded
+    pub payment_deadline_secs: i64,
+    /// Maximum bookings handled per sweep
+    pub batch_size: usize,
+    /// Time between sweeps when run in the background
+    pub interval: Duration,
+}
+
+impl Default for HoldExpiryConfig {
+    fn default() -> Self {
+        Self {
+            grace_period_secs: 1800,
+            payment_deadline_secs: 86400,
+            batch_size: 100,
+            interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl HoldExpiryConfig {
+    /// Use the deadlines from the booking configuration
+    pub fn from_booking_config(config: &vaya_book::BookingConfig) -> Self {
+        Self {
+            payment_deadline_secs: config.payment_deadline_secs,
+            ..Self::default()
+        }
+    }
+
+    /// Set the grace period
+    pub fn with_grace_period(mut self, secs: i64) -> Self {
+        self.grace_period_secs = secs;
+        self
+    }
+}
+
+/// An unpaid booking with what is needed to clean it up
+#[derive(Debug, Clone)]
+pub struct ExpiringHold {
+    /// The booking
+    pub booking: Booking,
+    /// Fare hold to release, if the booking has one
+    pub fare_hold_id: Option<String>,
+    /// When the grace reminder was sent (Unix timestamp)
+    pub reminded_at: Option<i64>,
+}
+
+/// Storage for bookings awaiting confirmation or payment
+pub trait HoldStore: Send + Sync {
+    /// Pending or confirmed-unpaid bookings whose deadline is before `cutoff`
+    fn overdue(&self, cutoff: i64, limit: usize) -> CoreResult<Vec<ExpiringHold>>;
+
+    /// Record that the grace reminder was sent
+    fn mark_reminded(&self, pnr: &str, at: i64) -> CoreResult<()>;
+
+    /// Save an updated booking
+    fn save(&self, booking: &Booking) -> CoreResult<()>;
+}
+
+/// Deadline by which a booking must move on, if it is still unpaid
+pub fn hold_deadline(booking: &Booking, payment_deadline_secs: i64) -> Option<i64> {
+    if booking.has_payment() {
+        return None;
+    }
+    match booking.status {
+        BookingStatus::Pending => booking.confirm_deadline,
+        BookingStatus::Confirmed => Some(
+            booking
+                .payment_deadline
+                .unwrap_or(booking.updated_at + payment_deadline_secs),
+        ),
+        _ => None,
+    }
+}
+
+/// Totals from one or more sweeps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HoldSweepReport {
+    /// Overdue bookings looked at
+    pub examined: usize,
+    /// Grace reminders sent
+    pub reminded: usize,
+    /// Bookings still within their grace period
+    pub in_grace: usize,
+    /// Bookings cancelled and marked expired
+    pub expired: usize,
+    /// Bookings marked failed because the GDS order could not be cancelled
+    pub failed: usize,
+    /// Fare holds released
+    pub holds_released: usize,
+    /// Bookings skipped after a storage or notification error
+    pub errors: usize,
+}
+
+impl HoldSweepReport {
+    fn add(&mut self, other: &HoldSweepReport) {
+        self.examined += other.examined;
+        self.reminded += other.reminded;
+        self.in_grace += other.in_grace;
+        self.expired += other.expired;
+        self.failed += other.failed;
+        self.holds_released += other.holds_released;
+        self.errors += other.errors;
+    }
+}
+
+/// What happened to one booking in a sweep
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HoldOutcome {
+    NotDue,
+    Reminded,
+    InGrace,
+    Expired { hold_released: bool },
+    Failed { hold_released: bool },
+}
+
+impl HoldOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HoldOutcome::NotDue => "not_due",
+            HoldOutcome::Reminded => "reminded",
+            HoldOutcome::InGrace => "in_grace",
+            HoldOutcome::Expired { .. } => "expired",
+            HoldOutcome::Failed { .. } => "failed",
+        }
+    }
+}
+
+/// Reminds, then cancels, bookings left unpaid past their deadline
+pub struct HoldExpirySweeper {
+    store: Arc<dyn HoldStore>,
+    gds: Arc<dyn GdsProvider>,
+    holds: Option<Arc<dyn FareHold>>,
+    notifier: Option<Arc<dyn Notifier>>,
+    config: HoldExpiryConfig,
+    totals: RwLock<HoldSweepReport>,
+}
+
+impl HoldExpirySweeper {
+    /// Create a new sweeper
+    pub fn new(store: Arc<dyn HoldStore>, gds: Arc<dyn GdsProvider>) -> Self {
+        Self {
+            store,
+            gds,
+            holds: None,
+            notifier: None,
+            config: HoldExpiryConfig::default(),
+            totals: RwLock::new(HoldSweepReport::default()),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: HoldExpiryConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Release fare holds of expired bookings
+    pub fn with_fare_holds(mut self, holds: Arc<dyn FareHold>) -> Self {
+        self.holds = Some(holds);
+        self
+    }
+
+    /// Send grace reminders
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Totals since the sweeper started, for the admin overview
+    pub fn stats(&self) -> HoldSweepReport {
+        self.totals
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Sweep overdue bookings now
+    pub async fn sweep(&self) -> HoldSweepReport {
+        self.sweep_at(Timestamp::now().as_unix()).await
+    }
+
+    /// Sweep overdue bookings as of `now` (Unix timestamp)
+    pub async fn sweep_at(&self, now: i64) -> HoldSweepReport {
+        let mut report = HoldSweepReport::default();
+
+        let overdue = match self.store.overdue(now, self.config.batch_size) {
+            Ok(overdue) => overdue,
+            Err(e) => {
+                warn!("Failed to list overdue bookings: {}", e);
+                report.errors += 1;
+                return report;
+            }
+        };
+
+        for hold in overdue {
+            let pnr = hold.booking.pnr.clone();
+            match self.process(hold, now).await {
+                Ok(HoldOutcome::NotDue) => continue,
+                Ok(outcome) => {
+                    report.examined += 1;
+                    match outcome {
+                        HoldOutcome::Reminded => report.reminded += 1,
+                        HoldOutcome::InGrace => report.in_grace += 1,
+                        HoldOutcome::Expired { hold_released } => {
+                            report.expired += 1;
+                            report.holds_released += usize::from(hold_released);
+                        }
+                        HoldOutcome::Failed { hold_released } => {
+                            report.failed += 1;
+                            report.holds_released += usize::from(hold_released);
+                        }
+                        HoldOutcome::NotDue => {}
+                    }
+                    metrics::global()
+                        .counter(
+                            "vaya_core_booking_hold_sweeps_total",
+                            &[("outcome", outcome.as_str())],
+                        )
+                        .inc();
+                }
+                Err(e) => {
+                    warn!("Failed to sweep booking {}: {}", pnr, e);
+                    report.examined += 1;
+                    report.errors += 1;
+                }
+            }
+        }
+
+        if report.expired + report.failed > 0 {
+            info!(
+                "Hold sweep expired {} and failed {} of {} overdue bookings",
+                report.expired, report.failed, report.examined
+            );
+        }
+        self.totals
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .add(&report);
+        report
+    }
+
+    /// Sweep every `interval` until the task is aborted
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+            loop {
+                ticker.tick().await;
+                self.sweep().await;
+            }
+        })
+    }
+
+    async fn process(&self, hold: ExpiringHold, now: i64) -> CoreResult<HoldOutcome> {
+        let mut booking = hold.booking;
+        let Some(deadline) = hold_deadline(&booking, self.config.payment_deadline_secs) else {
+            return Ok(HoldOutcome::NotDue);
+        };
+        if deadline > now {
+            return Ok(HoldOutcome::NotDue);
+        }
+
+        // The grace period runs from the reminder, so travelers get all of
+        // it even if the sweeper was not running at the deadline
+        let Some(reminded_at) = hold.reminded_at else {
+            self.remind(&booking).await;
+            self.store.mark_reminded(&booking.pnr, now)?;
+            booking.add_note(
+                &format!(
+                    "Payment reminder sent, cancelling after {}",
+                    now + self.config.grace_period_secs
+                ),
+                SWEEPER_ACTOR,
+            );
+            self.store.save(&booking)?;
+            return Ok(HoldOutcome::Reminded);
+        };
+        if now < reminded_at + self.config.grace_period_secs {
+            return Ok(HoldOutcome::InGrace);
+        }
+
+        let gds_result = match booking.provider_ref.clone() {
+            Some(order) => match self.gds.cancel_booking(&order).await {
+                Ok(()) => {
+                    booking.add_note(&format!("GDS order {} cancelled", order), SWEEPER_ACTOR);
+                    Ok(())
+                }
+                Err(e) => Err(format!("GDS order {} not cancelled: {}", order, e)),
+            },
+            None => Ok(()),
+        };
+
+        let mut hold_released = false;
+        if let (Some(holds), Some(hold_id)) = (&self.holds, &hold.fare_hold_id) {
+            match holds.release_hold(hold_id).await {
+                Ok(()) => {
+                    booking.add_note(&format!("Fare hold {} released", hold_id), SWEEPER_ACTOR);
+                    hold_released = true;
+                }
+                Err(e) => {
+                    warn!("Failed to release fare hold {}: {}", hold_id, e);
+                    booking.add_note(
+                        &format!("Fare hold {} not released: {}", hold_id, e),
+                        SWEEPER_ACTOR,
+                    );
+                }
+            }
+        }
+
+        let outcome = match gds_result {
+            Ok(()) => {
+                booking
+                    .transition(
+                        BookingStatus::Expired,
+                        "Unpaid after payment deadline and grace period",
+                        SWEEPER_ACTOR,
+                    )
+                    .map_err(|e| CoreError::BookingNotModifiable(e.to_string()))?;
+                info!("Booking {} expired", booking.pnr);
+                HoldOutcome::Expired { hold_released }
+            }
+            Err(reason) => {
+                warn!("Booking {} expired but {}", booking.pnr, reason);
+                booking
+                    .transition(BookingStatus::Failed, &reason, SWEEPER_ACTOR)
+                    .map_err(|e| CoreError::BookingNotModifiable(e.to_string()))?;
+                HoldOutcome::Failed { hold_released }
+            }
+        };
+        self.store.save(&booking)?;
+        Ok(outcome)
+    }
+
+    async fn remind(&self, booking: &Booking) {
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+        let Some(email) = booking
+            .passengers
+            .iter()
+            .find_map(|p| p.contact.as_ref().map(|c| c.email.as_str()))
+        else {
+            return;
+        };
+
+        let hours = (self.config.grace_period_secs / 3600).max(1);
+        let subject = format!("Complete your payment to keep booking {}", booking.pnr);
+        let body = format!(
+            "Your booking {} has not been paid yet and will be cancelled in about {} hour(s).\n\n\
+             Complete your payment to keep your seats.",
+            booking.pnr, hours
+        );
+        if let Err(e) = notifier
+            .enqueue_email("payment_reminder", email, &subject, &body)
+            .await
+        {
+            warn!("Failed to send payment reminder for {}: {}", booking.pnr, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use vaya_book::ContactDetails;
+    use vaya_gds::traits::mock::MockGdsProvider;
+
+    use crate::testing;
+
+    #[derive(Default)]
+    struct MemoryHolds {
+        bookings: Mutex<HashMap<String, ExpiringHold>>,
+    }
+
+    impl HoldStore for MemoryHolds {
+        fn overdue(&self, cutoff: i64, limit: usize) -> CoreResult<Vec<ExpiringHold>> {
+            Ok(self
+                .bookings
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|h| hold_deadline(&h.booking, 86400).is_some_and(|d| d <= cutoff))
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+
+        fn mark_reminded(&self, pnr: &str, at: i64) -> CoreResult<()> {
+            if let Some(hold) = self.bookings.lock().unwrap().get_mut(pnr) {
+                hold.reminded_at = Some(at);
+            }
+            Ok(())
+        }
+
+        fn save(&self, booking: &Booking) -> CoreResult<()> {
+            if let Some(hold) = self.bookings.lock().unwrap().get_mut(&booking.pnr) {
+                hold.booking = booking.clone();
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Captured {
+        emails: Mutex<Vec<String>>,
+        released: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Captured {
+        async fn send_email(&self, to: &str, _subject: &str, _body: &str) -> CoreResult<()> {
+            self.emails.lock().unwrap().push(to.to_string());
+            Ok(())
+        }
+
+        async fn send_sms(&self, _to: &str, _body: &str) -> CoreResult<()> {
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl FareHold for Captured {
+        async fn hold_fare(&self, offer_id: &str, _until: Timestamp) -> CoreResult<String> {
+            Ok(format!("hold_{}", offer_id))
+        }
+
+        async fn release_hold(&self, hold_id: &str) -> CoreResult<()> {
+            self.released.lock().unwrap().push(hold_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn confirmed_booking() -> Booking {
+        let mut passenger = testing::passenger();
+        passenger.contact = Some(ContactDetails::new("aisyah@example.com", "60", "123456789"));
+        let mut booking = Booking::new(
+            "user-1",
+            testing::offer("offer-1", 120_000),
+            vec![passenger],
+        )
+        .unwrap();
+        booking.confirm("GDS123", "system").unwrap();
+        booking
+    }
+
+    fn setup(
+        fail_gds: bool,
+    ) -> (
+        Arc<MemoryHolds>,
+        Arc<MockGdsProvider>,
+        Arc<Captured>,
+        HoldExpirySweeper,
+    ) {
+        let store = Arc::new(MemoryHolds::default());
+        let gds = Arc::new(MockGdsProvider::new());
+        gds.set_fail(fail_gds);
+        let captured = Arc::new(Captured::default());
+        let sweeper = HoldExpirySweeper::new(store.clone(), gds.clone())
+            .with_fare_holds(captured.clone())
+            .with_notifier(captured.clone())
+            .with_config(HoldExpiryConfig::default().with_grace_period(600));
+
+        let booking = confirmed_booking();
+        store.bookings.lock().unwrap().insert(
+            booking.pnr.clone(),
+            ExpiringHold {
+                booking,
+                fare_hold_id: Some("hold_1".into()),
+                reminded_at: None,
+            },
+        );
+        (store, gds, captured, sweeper)
+    }
+
+    fn stored(store: &MemoryHolds) -> Booking {
+        store
+            .bookings
+            .lock()
+            .unwrap()
+            .values()
+            .next()
+            .unwrap()
+            .booking
+            .clone()
+    }
+
+    #[tokio::test]
+    async fn test_reminds_then_expires_after_grace() {
+        let (store, gds, captured, sweeper) = setup(false);
+        let deadline = stored(&store).payment_deadline.unwrap();
+
+        // Not yet due
+        assert_eq!(sweeper.sweep_at(deadline - 60).await.examined, 0);
+
+        let report = sweeper.sweep_at(deadline + 1).await;
+        assert_eq!(report.reminded, 1);
+        assert_eq!(*captured.emails.lock().unwrap(), ["aisyah@example.com"]);
+        assert_eq!(stored(&store).status, BookingStatus::Confirmed);
+
+        let report = sweeper.sweep_at(deadline + 300).await;
+        assert_eq!(report.in_grace, 1);
+
+        let report = sweeper.sweep_at(deadline + 601).await;
+        assert_eq!(report.expired, 1);
+        assert_eq!(report.holds_released, 1);
+        assert_eq!(gds.cancelled(), ["GDS123"]);
+        assert_eq!(*captured.released.lock().unwrap(), ["hold_1"]);
+
+        let booking = stored(&store);
+        assert_eq!(booking.status, BookingStatus::Expired);
+        assert_eq!(booking.history.last().unwrap().actor, SWEEPER_ACTOR);
+        assert_eq!(booking.notes.len(), 3);
+
+        // Expired bookings are no longer overdue
+        assert_eq!(sweeper.sweep_at(deadline + 1200).await.examined, 0);
+        let totals = sweeper.stats();
+        assert_eq!((totals.reminded, totals.expired), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_gds_failure_marks_failed() {
+        let (store, _gds, captured, sweeper) = setup(true);
+        let deadline = stored(&store).payment_deadline.unwrap();
+
+        sweeper.sweep_at(deadline + 1).await;
+        let report = sweeper.sweep_at(deadline + 601).await;
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.expired, 0);
+
+        let booking = stored(&store);
+        assert_eq!(booking.status, BookingStatus::Failed);
+        assert!(booking.history.last().unwrap().reason.contains("GDS123"));
+        // The fare hold is released either way
+        assert_eq!(*captured.released.lock().unwrap(), ["hold_1"]);
+    }
+}
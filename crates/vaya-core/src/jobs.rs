@@ -0,0 +1,840 @@
+//! Long-running background jobs
+//!
+//! Admin exports and reports can take minutes, longer than a request
+//! should stay open. A job is submitted, runs in the background, and is
+//! polled for progress. When it succeeds its output is downloaded through
+//! a short-lived signed URL.
+//!
+//! Each job type has its own concurrency limit; jobs beyond it wait in the
+//! queue. Jobs can be cancelled while queued or running (handlers check
+//! [`JobContext::is_cancelled`] between steps), and finished jobs are
+//! removed after the retention period by [`JobManager::cleanup`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use vaya_common::{Timestamp, Uuid};
+use vaya_crypto::{HmacKey, HmacTag};
+use vaya_store::schema::{Record, RecordBuilder, Value};
+use vaya_store::{Column, ColumnType, PiiClass, Schema, StoreError, Table};
+
+use crate::error::{CoreError, CoreResult};
+
+/// Job status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Waiting for a free slot
+    Queued,
+    /// Running
+    Running,
+    /// Finished with output
+    Succeeded,
+    /// Finished with an error
+    Failed,
+    /// Cancelled before finishing
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Get status code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Parse a status code
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(JobStatus::Queued),
+            "running" => Some(JobStatus::Running),
+            "succeeded" => Some(JobStatus::Succeeded),
+            "failed" => Some(JobStatus::Failed),
+            "cancelled" => Some(JobStatus::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Check if the job has finished
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+/// File produced by a job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobOutput {
+    /// Suggested download file name
+    pub file_name: String,
+    /// MIME type
+    pub content_type: String,
+    /// File contents
+    pub data: Vec<u8>,
+}
+
+/// A background job
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    /// Job ID
+    pub id: String,
+    /// Registered job type (e.g. "bookings_export")
+    pub job_type: String,
+    /// Handler-specific parameters (JSON)
+    pub params: String,
+    /// Admin who submitted the job
+    pub submitted_by: String,
+    /// Current status
+    pub status: JobStatus,
+    /// Progress percentage (0-100)
+    pub progress: u8,
+    /// Latest progress message
+    pub progress_message: Option<String>,
+    /// Error message for failed jobs
+    pub error: Option<String>,
+    /// Output of a succeeded job
+    pub output: Option<JobOutput>,
+    /// When the job was submitted
+    pub created_at: Timestamp,
+    /// When the job started running
+    pub started_at: Option<Timestamp>,
+    /// When the job finished
+    pub finished_at: Option<Timestamp>,
+}
+
+/// Job persistence
+pub trait JobRepository: Send + Sync {
+    /// Insert or replace a job
+    fn save(&self, job: &Job) -> CoreResult<()>;
+
+    /// Get a job
+    fn get(&self, id: &str) -> CoreResult<Option<Job>>;
+
+    /// Delete a job; returns false if it did not exist
+    fn delete(&self, id: &str) -> CoreResult<bool>;
+
+    /// All jobs
+    fn list(&self) -> CoreResult<Vec<Job>>;
+}
+
+/// In-memory job repository
+#[derive(Debug, Default)]
+pub struct MemoryJobRepository {
+    jobs: RwLock<HashMap<String, Job>>,
+}
+
+impl MemoryJobRepository {
+    /// Create an empty repository
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl JobRepository for MemoryJobRepository {
+    fn save(&self, job: &Job) -> CoreResult<()> {
+        self.jobs
+            .write()
+            .unwrap()
+            .insert(job.id.clone(), job.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> CoreResult<Option<Job>> {
+        Ok(self.jobs.read().unwrap().get(id).cloned())
+    }
+
+    fn delete(&self, id: &str) -> CoreResult<bool> {
+        Ok(self.jobs.write().unwrap().remove(id).is_some())
+    }
+
+    fn list(&self) -> CoreResult<Vec<Job>> {
+        Ok(self.jobs.read().unwrap().values().cloned().collect())
+    }
+}
+
+/// Job repository backed by a vaya-store table
+///
+/// Table scans are not available yet, so the repository keeps the list of
+/// job IDs in a one-row index table alongside the jobs table.
+pub struct StoreJobRepository {
+    jobs: Table,
+    index: Table,
+    ids: RwLock<Vec<String>>,
+}
+
+/// Primary key of the row holding the job ID list
+const INDEX_ROW: &str = "jobs";
+
+impl StoreJobRepository {
+    /// Open (or create) the job tables
+    pub fn open(db: Arc<vaya_db::VayaDb>) -> CoreResult<Self> {
+        let jobs = open_or_create(Self::jobs_schema(), db.clone())?;
+        let index = open_or_create(
+            Schema::new("job_index")
+                .column(Column::new("id", ColumnType::String).primary_key())
+                .column(Column::new("ids", ColumnType::String).not_null()),
+            db,
+        )?;
+        let ids = index
+            .get(&Value::String(INDEX_ROW.into()))
+            .map_err(store_error)?
+            .and_then(|r| r.get("ids").and_then(Value::as_str).map(str::to_string))
+            .map(|ids| {
+                ids.split(',')
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            jobs,
+            index,
+            ids: RwLock::new(ids),
+        })
+    }
+
+    fn jobs_schema() -> Schema {
+        Schema::new("jobs")
+            .column(Column::new("id", ColumnType::String).primary_key())
+            .column(Column::new("job_type", ColumnType::String).not_null())
+            .column(Column::new("params", ColumnType::String).not_null())
+            .column(
+                Column::new("submitted_by", ColumnType::String)
+                    .not_null()
+                    .pii(PiiClass::Internal),
+            )
+            .column(Column::new("status", ColumnType::String).not_null())
+            .column(Column::new("progress", ColumnType::Int64).not_null())
+            .column(Column::new("progress_message", ColumnType::String))
+            .column(Column::new("error", ColumnType::String))
+            .column(Column::new("output_name", ColumnType::String))
+            .column(Column::new("output_type", ColumnType::String))
+            .column(Column::new("output", ColumnType::Bytes))
+            .column(Column::new("created_at", ColumnType::Int64).not_null())
+            .column(Column::new("started_at", ColumnType::Int64))
+            .column(Column::new("finished_at", ColumnType::Int64))
+    }
+
+    fn write_index(&self, ids: &[String]) -> CoreResult<()> {
+        let record = RecordBuilder::new()
+            .string("id", INDEX_ROW)
+            .string("ids", ids.join(","))
+            .build();
+        upsert(&self.index, &record)
+    }
+}
+
+impl JobRepository for StoreJobRepository {
+    fn save(&self, job: &Job) -> CoreResult<()> {
+        upsert(&self.jobs, &job_to_record(job))?;
+        let mut ids = self.ids.write().unwrap();
+        if !ids.contains(&job.id) {
+            ids.push(job.id.clone());
+            self.write_index(&ids)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> CoreResult<Option<Job>> {
+        self.jobs
+            .get(&Value::String(id.into()))
+            .map_err(store_error)?
+            .map(|r| record_to_job(&r))
+            .transpose()
+    }
+
+    fn delete(&self, id: &str) -> CoreResult<bool> {
+        let deleted = self
+            .jobs
+            .delete(&Value::String(id.into()))
+            .map_err(store_error)?;
+        let mut ids = self.ids.write().unwrap();
+        if let Some(pos) = ids.iter().position(|i| i == id) {
+            ids.remove(pos);
+            self.write_index(&ids)?;
+        }
+        Ok(deleted)
+    }
+
+    fn list(&self) -> CoreResult<Vec<Job>> {
+        let ids = self.ids.read().unwrap().clone();
+        let mut jobs = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(job) = self.get(&id)? {
+                jobs.push(job);
+            }
+        }
+        Ok(jobs)
+    }
+}
+
+fn store_error(e: StoreError) -> CoreError {
+    CoreError::Database(e.to_string())
+}
+
+fn open_or_create(schema: Schema, db: Arc<vaya_db::VayaDb>) -> CoreResult<Table> {
+    match Table::create(schema, db.clone()) {
+        Ok(table) => Ok(table),
+        Err(StoreError::TableExists(name)) => Table::open(name, db).map_err(store_error),
+        Err(e) => Err(store_error(e)),
+    }
+}
+
+fn upsert(table: &Table, record: &Record) -> CoreResult<()> {
+    match table.insert(record) {
+        Ok(()) => Ok(()),
+        Err(StoreError::PrimaryKeyViolation) => {
+            let pk = record.get("id").cloned().unwrap_or(Value::Null);
+            table.update(&pk, record).map_err(store_error)
+        }
+        Err(e) => Err(store_error(e)),
+    }
+}
+
+fn job_to_record(job: &Job) -> Record {
+    let optional = |builder: RecordBuilder, name: &str, value: Option<&str>| match value {
+        Some(v) => builder.string(name, v),
+        None => builder.null(name),
+    };
+    let mut builder = RecordBuilder::new()
+        .string("id", job.id.as_str())
+        .string("job_type", job.job_type.as_str())
+        .string("params", job.params.as_str())
+        .string("submitted_by", job.submitted_by.as_str())
+        .string("status", job.status.as_str())
+        .int64("progress", i64::from(job.progress))
+        .int64("created_at", job.created_at.as_unix());
+    builder = optional(builder, "progress_message", job.progress_message.as_deref());
+    builder = optional(builder, "error", job.error.as_deref());
+    builder = match &job.output {
+        Some(output) => builder
+            .string("output_name", output.file_name.as_str())
+            .string("output_type", output.content_type.as_str())
+            .bytes("output", output.data.clone()),
+        None => builder
+            .null("output_name")
+            .null("output_type")
+            .null("output"),
+    };
+    for (name, at) in [
+        ("started_at", job.started_at),
+        ("finished_at", job.finished_at),
+    ] {
+        builder = match at {
+            Some(at) => builder.int64(name, at.as_unix()),
+            None => builder.null(name),
+        };
+    }
+    builder.build()
+}
+
+fn record_to_job(record: &Record) -> CoreResult<Job> {
+    let text = |name: &str| record.get(name).and_then(Value::as_str).map(str::to_string);
+    let time = |name: &str| {
+        record
+            .get(name)
+            .and_then(Value::as_i64)
+            .map(Timestamp::from_unix)
+    };
+    let corrupt = || CoreError::Database("Corrupt job record".into());
+
+    let output = match (text("output_name"), text("output_type")) {
+        (Some(file_name), Some(content_type)) => Some(JobOutput {
+            file_name,
+            content_type,
+            data: record
+                .get("output")
+                .and_then(Value::as_bytes)
+                .unwrap_or_default()
+                .to_vec(),
+        }),
+        _ => None,
+    };
+    Ok(Job {
+        id: text("id").ok_or_else(corrupt)?,
+        job_type: text("job_type").ok_or_else(corrupt)?,
+        params: text("params").unwrap_or_default(),
+        submitted_by: text("submitted_by").unwrap_or_default(),
+        status: text("status")
+            .and_then(|s| JobStatus::parse(&s))
+            .ok_or_else(corrupt)?,
+        progress: record
+            .get("progress")
+            .and_then(Value::as_i64)
+            .unwrap_or(0)
+            .clamp(0, 100) as u8,
+        progress_message: text("progress_message"),
+        error: text("error"),
+        output,
+        created_at: time("created_at").ok_or_else(corrupt)?,
+        started_at: time("started_at"),
+        finished_at: time("finished_at"),
+    })
+}
+
+/// Handle given to a running job
+pub struct JobContext {
+    job_id: String,
+    repository: Arc<dyn JobRepository>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobContext {
+    /// Job ID
+    pub fn job_id(&self) -> &str {
+        &self.job_id
+    }
+
+    /// Record progress (0-100) with an optional message
+    pub fn set_progress(&self, percent: u8, message: Option<&str>) -> CoreResult<()> {
+        if let Some(mut job) = self.repository.get(&self.job_id)? {
+            job.progress = percent.min(100);
+            job.progress_message = message.map(str::to_string);
+            self.repository.save(&job)?;
+        }
+        Ok(())
+    }
+
+    /// Check if the job was cancelled; handlers should stop promptly
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Runs one type of job
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    /// Job type this handler runs
+    fn job_type(&self) -> &'static str;
+
+    /// Run the job
+    async fn run(&self, params: &str, ctx: &JobContext) -> CoreResult<JobOutput>;
+}
+
+/// Job manager configuration
+#[derive(Debug, Clone)]
+pub struct JobConfig {
+    /// Concurrent jobs per type, unless overridden
+    pub default_concurrency: usize,
+    /// Concurrency per job type
+    pub concurrency: HashMap<String, usize>,
+    /// Most jobs of one type waiting or running at once
+    pub max_pending_per_type: usize,
+    /// Seconds finished jobs are kept
+    pub retention_secs: i64,
+    /// Seconds a download link stays valid
+    pub download_ttl_secs: i64,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            default_concurrency: 2,
+            concurrency: HashMap::new(),
+            max_pending_per_type: 20,
+            retention_secs: 7 * 24 * 3600,
+            download_ttl_secs: 15 * 60,
+        }
+    }
+}
+
+struct Registered {
+    handler: Arc<dyn JobHandler>,
+    slots: Arc<Semaphore>,
+}
+
+/// Submits, tracks and cleans up background jobs
+pub struct JobManager {
+    config: JobConfig,
+    repository: Arc<dyn JobRepository>,
+    handlers: HashMap<String, Registered>,
+    cancel_flags: Arc<RwLock<HashMap<String, Arc<AtomicBool>>>>,
+    signing_key: HmacKey,
+}
+
+impl JobManager {
+    /// Create a manager; `signing_key` signs download URLs
+    pub fn new(repository: Arc<dyn JobRepository>, signing_key: HmacKey) -> Self {
+        Self {
+            config: JobConfig::default(),
+            repository,
+            handlers: HashMap::new(),
+            cancel_flags: Arc::new(RwLock::new(HashMap::new())),
+            signing_key,
+        }
+    }
+
+    /// Set the config (before registering handlers)
+    pub fn with_config(mut self, config: JobConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register a handler for its job type
+    pub fn register(&mut self, handler: Arc<dyn JobHandler>) {
+        let job_type = handler.job_type().to_string();
+        let limit = self
+            .config
+            .concurrency
+            .get(&job_type)
+            .copied()
+            .unwrap_or(self.config.default_concurrency)
+            .max(1);
+        self.handlers.insert(
+            job_type,
+            Registered {
+                handler,
+                slots: Arc::new(Semaphore::new(limit)),
+            },
+        );
+    }
+
+    /// Registered job types
+    pub fn job_types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.handlers.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        types
+    }
+
+    /// Submit a job; it starts when a slot for its type is free
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn submit(&self, job_type: &str, params: &str, submitted_by: &str) -> CoreResult<Job> {
+        let registered = self
+            .handlers
+            .get(job_type)
+            .ok_or_else(|| CoreError::ValidationError(format!("Unknown job type: {}", job_type)))?;
+
+        let pending = self
+            .repository
+            .list()?
+            .iter()
+            .filter(|j| j.job_type == job_type && !j.status.is_terminal())
+            .count();
+        if pending >= self.config.max_pending_per_type {
+            return Err(CoreError::ServiceUnavailable(format!(
+                "Too many pending {} jobs",
+                job_type
+            )));
+        }
+
+        let job = Job {
+            id: Uuid::new_v4().to_string(),
+            job_type: job_type.to_string(),
+            params: params.to_string(),
+            submitted_by: submitted_by.to_string(),
+            status: JobStatus::Queued,
+            progress: 0,
+            progress_message: None,
+            error: None,
+            output: None,
+            created_at: Timestamp::now(),
+            started_at: None,
+            finished_at: None,
+        };
+        self.repository.save(&job)?;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.cancel_flags
+            .write()
+            .unwrap()
+            .insert(job.id.clone(), cancelled.clone());
+
+        let ctx = JobContext {
+            job_id: job.id.clone(),
+            repository: self.repository.clone(),
+            cancelled,
+        };
+        let handler = registered.handler.clone();
+        let slots = registered.slots.clone();
+        let flags = self.cancel_flags.clone();
+        tokio::spawn(async move {
+            let job_id = ctx.job_id.clone();
+            if let Err(e) = run_job(handler, slots, ctx).await {
+                warn!(job_id = %job_id, error = %e, "Failed to record job result");
+            }
+            flags.write().unwrap().remove(&job_id);
+        });
+
+        info!(job_id = %job.id, job_type, submitted_by, "Job submitted");
+        Ok(job)
+    }
+
+    /// Get a job
+    pub fn get(&self, id: &str) -> CoreResult<Job> {
+        self.repository
+            .get(id)?
+            .ok_or_else(|| CoreError::JobNotFound(id.to_string()))
+    }
+
+    /// Cancel a queued or running job
+    pub fn cancel(&self, id: &str) -> CoreResult<Job> {
+        let mut job = self.get(id)?;
+        if job.status.is_terminal() {
+            return Err(CoreError::ValidationError(format!(
+                "Job {} has already {}",
+                id,
+                job.status.as_str()
+            )));
+        }
+        if let Some(flag) = self.cancel_flags.read().unwrap().get(id) {
+            flag.store(true, Ordering::SeqCst);
+        }
+        // A queued job never starts; a running one stops at its next check
+        if job.status == JobStatus::Queued {
+            finish(&mut job, JobStatus::Cancelled);
+            self.repository.save(&job)?;
+        }
+        info!(job_id = %id, "Job cancellation requested");
+        Ok(job)
+    }
+
+    /// Signed download path for a succeeded job's output
+    pub fn download_url(&self, id: &str) -> CoreResult<String> {
+        let job = self.get(id)?;
+        if job.status != JobStatus::Succeeded {
+            return Err(CoreError::ValidationError(format!(
+                "Job {} has no output ({})",
+                id,
+                job.status.as_str()
+            )));
+        }
+        let expires = Timestamp::now().as_unix() + self.config.download_ttl_secs;
+        let signature = self
+            .signing_key
+            .sign(download_payload(id, expires).as_bytes());
+        Ok(format!(
+            "/api/v1/admin/jobs/{}/download?expires={}&signature={}",
+            id,
+            expires,
+            signature.to_hex()
+        ))
+    }
+
+    /// Check a download link and return the job output
+    pub fn download(&self, id: &str, expires: i64, signature: &str) -> CoreResult<JobOutput> {
+        let valid = HmacTag::from_hex(signature).is_ok_and(|tag| {
+            self.signing_key
+                .verify(download_payload(id, expires).as_bytes(), &tag)
+        });
+        if !valid {
+            return Err(CoreError::NotAuthorized(
+                "Invalid download signature".into(),
+            ));
+        }
+        if Timestamp::now().as_unix() > expires {
+            return Err(CoreError::NotAuthorized("Download link expired".into()));
+        }
+        self.get(id)?
+            .output
+            .ok_or_else(|| CoreError::JobNotFound(id.to_string()))
+    }
+
+    /// Delete finished jobs older than the retention period; returns the count
+    pub fn cleanup(&self) -> CoreResult<usize> {
+        let cutoff = Timestamp::now().as_unix() - self.config.retention_secs;
+        let mut removed = 0;
+        for job in self.repository.list()? {
+            let expired =
+                job.status.is_terminal() && job.finished_at.is_some_and(|at| at.as_unix() < cutoff);
+            if expired && self.repository.delete(&job.id)? {
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            info!(removed, "Expired jobs removed");
+        }
+        Ok(removed)
+    }
+}
+
+fn download_payload(id: &str, expires: i64) -> String {
+    format!("job-download:{}:{}", id, expires)
+}
+
+fn finish(job: &mut Job, status: JobStatus) {
+    job.status = status;
+    job.finished_at = Some(Timestamp::now());
+}
+
+async fn run_job(
+    handler: Arc<dyn JobHandler>,
+    slots: Arc<Semaphore>,
+    ctx: JobContext,
+) -> CoreResult<()> {
+    let _permit = slots
+        .acquire_owned()
+        .await
+        .map_err(|_| CoreError::Internal("Job slots closed".into()))?;
+    if ctx.is_cancelled() {
+        // Already marked cancelled while queued
+        return Ok(());
+    }
+
+    let mut job = ctx
+        .repository
+        .get(&ctx.job_id)?
+        .ok_or_else(|| CoreError::JobNotFound(ctx.job_id.clone()))?;
+    job.status = JobStatus::Running;
+    job.started_at = Some(Timestamp::now());
+    ctx.repository.save(&job)?;
+
+    let result = handler.run(&job.params, &ctx).await;
+
+    // Reload to keep the handler's progress updates
+    let mut job = ctx.repository.get(&ctx.job_id)?.unwrap_or(job);
+    match result {
+        _ if ctx.is_cancelled() => finish(&mut job, JobStatus::Cancelled),
+        Ok(output) => {
+            job.progress = 100;
+            job.output = Some(output);
+            finish(&mut job, JobStatus::Succeeded);
+        }
+        Err(e) => {
+            job.error = Some(e.to_string());
+            finish(&mut job, JobStatus::Failed);
+        }
+    }
+    info!(job_id = %job.id, status = job.status.as_str(), "Job finished");
+    ctx.repository.save(&job)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct CsvExport;
+
+    #[async_trait]
+    impl JobHandler for CsvExport {
+        fn job_type(&self) -> &'static str {
+            "bookings_export"
+        }
+
+        async fn run(&self, params: &str, ctx: &JobContext) -> CoreResult<JobOutput> {
+            if params == "fail" {
+                return Err(CoreError::Internal("boom".into()));
+            }
+            for step in 1..=4u8 {
+                if ctx.is_cancelled() {
+                    return Err(CoreError::Internal("cancelled".into()));
+                }
+                ctx.set_progress(step * 25, Some("exporting"))?;
+                tokio::time::sleep(Duration::from_millis(if params == "slow" { 50 } else { 1 }))
+                    .await;
+            }
+            Ok(JobOutput {
+                file_name: "bookings.csv".into(),
+                content_type: "text/csv".into(),
+                data: b"id,status\n".to_vec(),
+            })
+        }
+    }
+
+    fn manager(concurrency: usize) -> JobManager {
+        let mut config = JobConfig::default();
+        config
+            .concurrency
+            .insert("bookings_export".into(), concurrency);
+        let mut manager = JobManager::new(
+            Arc::new(MemoryJobRepository::new()),
+            HmacKey::new(&[7u8; 32]).unwrap(),
+        )
+        .with_config(config);
+        manager.register(Arc::new(CsvExport));
+        manager
+    }
+
+    async fn wait_for(manager: &JobManager, id: &str) -> Job {
+        for _ in 0..200 {
+            let job = manager.get(id).unwrap();
+            if job.status.is_terminal() {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_and_downloads() {
+        let manager = manager(2);
+        let job = manager.submit("bookings_export", "", "admin-1").unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let done = wait_for(&manager, &job.id).await;
+        assert_eq!(done.status, JobStatus::Succeeded);
+        assert_eq!(done.progress, 100);
+
+        let url = manager.download_url(&job.id).unwrap();
+        let query = url.split('?').nth(1).unwrap();
+        let (expires, signature) = query.split_once('&').unwrap();
+        let expires: i64 = expires.trim_start_matches("expires=").parse().unwrap();
+        let signature = signature.trim_start_matches("signature=");
+        let output = manager.download(&job.id, expires, signature).unwrap();
+        assert_eq!(output.content_type, "text/csv");
+
+        // Tampered expiry
+        assert!(manager.download(&job.id, expires + 60, signature).is_err());
+        assert!(manager.submit("unknown", "", "admin-1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failure_cancellation_and_limits() {
+        let manager = manager(1);
+        let failed = manager
+            .submit("bookings_export", "fail", "admin-1")
+            .unwrap();
+        assert_eq!(
+            wait_for(&manager, &failed.id).await.status,
+            JobStatus::Failed
+        );
+
+        // One slot: the second job waits and is cancelled while queued
+        let running = manager
+            .submit("bookings_export", "slow", "admin-1")
+            .unwrap();
+        let queued = manager
+            .submit("bookings_export", "slow", "admin-1")
+            .unwrap();
+        assert_eq!(
+            manager.cancel(&queued.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+        manager.cancel(&running.id).unwrap();
+        assert_eq!(
+            wait_for(&manager, &running.id).await.status,
+            JobStatus::Cancelled
+        );
+        assert!(manager.cancel(&running.id).is_err());
+        assert!(manager.download_url(&running.id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_removes_expired_jobs() {
+        let manager = manager(1);
+        let job = manager.submit("bookings_export", "", "admin-1").unwrap();
+        let mut done = wait_for(&manager, &job.id).await;
+        assert_eq!(manager.cleanup().unwrap(), 0);
+
+        done.finished_at = Some(Timestamp::now().add_days(-8));
+        manager.repository.save(&done).unwrap();
+        assert_eq!(manager.cleanup().unwrap(), 1);
+        assert!(matches!(
+            manager.get(&job.id),
+            Err(CoreError::JobNotFound(_))
+        ));
+    }
+}
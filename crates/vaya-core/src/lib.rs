@@ -4,10 +4,37 @@
 //! all VAYA services including:
 //!
 //! - **Flight search**: Search flights through GDS providers
+//! - **Saved searches**: Shareable search links with offer snapshots
 //! - **Booking**: Create, manage, and cancel bookings
+//! - **Hold expiry**: Reminders and cleanup for bookings left unpaid
+//! - **Fare checks**: Re-pricing offers before checkout with change tolerance
+//! - **Rebooking**: GDS-priced change quotes for date changes and rebooking
+//! - **Seats**: Seat maps and seat selection checked against them
 //! - **User management**: Registration, authentication, profiles
+//! - **Verification**: Re-authenticated email changes and phone OTP
+//! - **Admin**: Account suspension, merging, and tier overrides (audited)
+//! - **Oracle**: Composite price verdicts (prediction, insight, best time to book)
+//! - **Price history**: Observed search prices kept as downsampled series for the oracle
+//! - **Pricing**: Versioned markup and fee policies for retail prices
+//! - **Queue sync**: Airline-initiated booking changes from GDS queues
+//! - **Price locks**: Paid fare holds credited against the booking
+//! - **Promotions**: Promo codes with usage limits and stacking rules at checkout
+//! - **Price variance**: Displayed vs charged checks that hold settlement
+//! - **Currencies**: Prices shown and charged in SGD, USD, THB at cached FX rates
 //! - **Payments**: Payment processing and refunds
+//! - **Wallet**: Loyalty points earned on trips and spent on bookings and pools
+//! - **Refunds**: Fare-rule refund rules and approved refunds sent to the provider
+//! - **Split payments**: Automatic refunds for group payments not funded in time
+//! - **Pool settlement**: Contribution refunds before a failed pool closes
+//! - **Pool lifecycle**: Scheduled expiry, deadline reminders and booking of pools
 //! - **Notifications**: Email and SMS confirmations
+//! - **Outbox**: Persisted notifications with retries, dead letters and delivery tracking
+//! - **Notification preferences**: Channels per category, quiet hours and digests
+//! - **E-tickets**: HTML and PDF itineraries attached to confirmation emails
+//! - **Repositories**: Pools, bookings and price alerts persisted to VayaDb
+//! - **Jobs**: Long-running admin exports with progress polling
+//! - **Timeline**: One ordered view of a booking's events across systems
+//! - **Notes**: Categorized agent notes with mention emails, and booking tags
 //!
 //! # Architecture
 //!
@@ -16,8 +43,9 @@
 //!
 //! - `vaya-gds`: GDS integration (Amadeus, etc.)
 //! - `vaya-payment`: Payment processing (Stripe)
-//! - `vaya-notification`: Email/SMS (SendGrid, Twilio)
+//! - `vaya-notification`: Email/SMS (SendGrid, Mailgun, SMTP, Twilio)
 //! - `vaya-oracle`: Price predictions
+//! - `vaya-pool`: Group buying pools
 //! - `vaya-auth`: Authentication
 //! - `vaya-db`: Database storage
 //! - `vaya-cache`: Caching layer
@@ -44,19 +72,123 @@
 
 #![warn(missing_docs)]
 
+pub mod admin;
 pub mod booking;
 pub mod error;
+pub mod eticket;
+pub mod fare_check;
+pub mod fx;
+pub mod hold_expiry;
+pub mod jobs;
+pub mod notes;
+pub mod notify;
+pub mod oracle;
+pub mod outbox;
+pub mod pool_lifecycle;
+pub mod pool_settlement;
+pub mod preferences;
+pub mod price_history;
+pub mod price_lock;
+pub mod price_variance;
+pub mod pricing;
+pub mod promotions;
+pub mod queue_sync;
+pub mod rebooking;
+pub mod refunds;
+pub mod repository;
+pub mod saved_search;
 pub mod search;
+pub mod seats;
+pub mod split_payment;
+pub mod timeline;
 pub mod types;
 pub mod user;
+pub mod verification;
+pub mod wallet;
 
+#[cfg(test)]
+mod testing;
+
+pub use admin::{AdminService, AuditAction, AuditEntry, AuditLog, MergeResult, OwnedRecords};
 pub use booking::{BookingConfig, BookingService, CancellationResult, PaymentResult};
 pub use error::{CoreError, CoreResult};
-pub use search::{SearchPriceInsight, SearchResponse, SearchService};
+pub use eticket::ETicket;
+pub use fare_check::{FareCheckOutcome, PriceTolerance, VerifiedFare};
+pub use fx::{CollectorRateSource, CurrencyConverter, FxConfig, RateSource};
+pub use hold_expiry::{
+    hold_deadline, ExpiringHold, HoldExpiryConfig, HoldExpirySweeper, HoldStore, HoldSweepReport,
+};
+pub use jobs::{
+    Job, JobConfig, JobContext, JobHandler, JobManager, JobOutput, JobRepository, JobStatus,
+    MemoryJobRepository, StoreJobRepository,
+};
+pub use notes::{AgentDirectory, NoteStore, NotesService};
+pub use notify::{NotificationClients, Notifier, QueueConfig, QueuedNotifier};
+pub use oracle::{
+    trace_to_json, AccuracyStats, OracleRecommendation, OracleService, OracleServiceConfig,
+    OracleVerdict, PriceHistorySource,
+};
+pub use outbox::{
+    DbOutboxStore, DrainReport, MemoryOutboxStore, Outbox, OutboxChannel, OutboxConfig,
+    OutboxMessage, OutboxStore,
+};
+pub use pool_lifecycle::{
+    MemberDirectory, PoolBooker, PoolLifecycleConfig, PoolLifecycleWorker, PoolSweepReport,
+};
+pub use pool_settlement::{settle_pool, SettlementReport};
+pub use preferences::{
+    DigestFrequency, NotificationCategory, NotificationChannel, NotificationPreferences,
+    PreferenceService, QuietHours,
+};
+pub use price_history::{PriceBucket, PriceHistoryConfig, PriceHistoryService, PriceSeries};
+pub use price_lock::{
+    AppliedPriceLock, FareHold, LedgerEntry, LedgerEntryKind, PriceLock, PriceLockPolicy,
+    PriceLockRequest, PriceLockService, PriceLockStatus, UnusedLockFee,
+};
+pub use price_variance::{
+    CurrencyVariance, PriceVariance, PriceVarianceMonitor, VarianceLog, VarianceReport,
+    VarianceTolerance,
+};
+pub use pricing::{
+    FeeAmount, FeeCategory, FeeRule, NetFare, PriceLine, PricingContext, PricingEngine,
+    PricingPolicy, RetailPrice,
+};
+pub use promotions::{
+    AppliedPromotion, Discount, MemoryPromotionStore, PromoCode, PromoContext, PromoQuote,
+    PromotionConfig, PromotionService, PromotionStore, Redemption, Stacking,
+};
+pub use queue_sync::{
+    BookingStatusStore, QueueSyncOutcome, QueueSyncReport, QueueSyncService, QueuedBooking,
+    SupportTicketRequest, SupportTicketSink, TicketPriority,
+};
+pub use rebooking::{change_rules, quote_change};
+pub use refunds::{process_refund, refund_rules};
+pub use repository::{
+    AlertRepository, BookingRepository, DbAlertRepository, DbBookingRepository, DbPoolRepository,
+    DbPreferenceRepository, PoolRepository, PreferenceRepository,
+};
+pub use saved_search::{
+    Freshness, SavedSearch, SavedSearchConfig, SavedSearchService, SearchSnapshot, SharedResults,
+};
+pub use search::{
+    FanOutPolicy, LateResults, SearchPriceInsight, SearchResponse, SearchService, StreamingSearch,
+};
+pub use seats::{seat_maps, select_seat, validate_seat};
+pub use split_payment::refund_unfunded;
+pub use timeline::{
+    booking_events, BookingTimeline, MemoryTimelineSource, TimelineCategory, TimelineEvent,
+    TimelineService, TimelineSource, TimelineSubject,
+};
 pub use types::*;
 pub use user::{
-    AuthConfig, AuthResponse, LoginRequest, ProfileUpdate, RegisterRequest, User, UserService,
-    UserStatus,
+    AuthConfig, AuthResponse, LoginRequest, ProfileUpdate, RegisterRequest, TierOverride, User,
+    UserService, UserStatus,
+};
+pub use verification::{ContactSync, EmailChange, VerificationConfig, VerificationService};
+pub use wallet::{
+    CreditLot, DbWalletStore, LoyaltyConfig, MemoryWalletStore, RedemptionTarget, WalletAccount,
+    WalletBalance, WalletEntry, WalletExpiryReport, WalletService, WalletStore, WalletTransaction,
+    WalletTransactionKind,
 };
 
 /// Core configuration
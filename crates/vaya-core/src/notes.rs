@@ -0,0 +1,304 @@
+//! Booking notes and tags for agents
+//!
+//! [`NotesService`] adds categorized notes and tags to bookings on behalf of
+//! support, fraud-review and ops agents. Agents mentioned in a note as
+//! `@agent-id` are emailed the note; an unknown or unreachable
+//! agent is logged and counted but never fails the note itself.
+//!
+//! Mention emails are counted under `vaya_core_note_mentions_total` with a
+//! `status` label (`sent`, `unknown_agent`, `failed`).
+
+use std::sync::Arc;
+
+use tracing::warn;
+
+use vaya_book::{normalize_tag, Booking, BookingNote, NoteCategory, NoteVisibility};
+use vaya_common::metrics;
+
+use crate::error::{CoreError, CoreResult};
+use crate::notify::Notifier;
+
+/// Metric counting mention notifications
+pub const NOTE_MENTIONS_METRIC: &str = "vaya_core_note_mentions_total";
+
+/// Storage for bookings that agents annotate
+pub trait NoteStore: Send + Sync {
+    /// Load a booking by PNR
+    fn get(&self, pnr: &str) -> CoreResult<Option<Booking>>;
+
+    /// Save an updated booking
+    fn save(&self, booking: &Booking) -> CoreResult<()>;
+
+    /// Bookings carrying every tag in `tags` (normalized), newest first
+    fn find_by_tags(&self, tags: &[String], limit: usize) -> CoreResult<Vec<Booking>>;
+}
+
+/// Looks up agents who can be mentioned in notes
+pub trait AgentDirectory: Send + Sync {
+    /// Email address of an active agent, or `None` if there is no such agent
+    fn agent_email(&self, agent_id: &str) -> Option<String>;
+}
+
+/// Manages booking notes and tags
+pub struct NotesService {
+    store: Arc<dyn NoteStore>,
+    agents: Arc<dyn AgentDirectory>,
+    notifier: Arc<dyn Notifier>,
+}
+
+impl NotesService {
+    /// Create a new notes service
+    pub fn new(
+        store: Arc<dyn NoteStore>,
+        agents: Arc<dyn AgentDirectory>,
+        notifier: Arc<dyn Notifier>,
+    ) -> Self {
+        Self {
+            store,
+            agents,
+            notifier,
+        }
+    }
+
+    /// Add a note to a booking and notify mentioned agents
+    pub async fn add_note(
+        &self,
+        pnr: &str,
+        category: NoteCategory,
+        visibility: NoteVisibility,
+        content: &str,
+        author: &str,
+    ) -> CoreResult<BookingNote> {
+        let mut booking = self.load(pnr)?;
+        let note = booking
+            .add_note_with(category, visibility, content, author)
+            .map_err(|e| CoreError::ValidationError(e.to_string()))?
+            .clone();
+        self.store.save(&booking)?;
+
+        for agent in note.mentions.iter().filter(|m| *m != author) {
+            self.notify_mention(&booking, &note, agent).await;
+        }
+        Ok(note)
+    }
+
+    /// Add tags to a booking; returns the booking's tags afterwards
+    pub fn add_tags(&self, pnr: &str, tags: &[String]) -> CoreResult<Vec<String>> {
+        let mut booking = self.load(pnr)?;
+        let mut changed = false;
+        for tag in tags {
+            changed |= booking
+                .add_tag(tag)
+                .map_err(|e| CoreError::ValidationError(e.to_string()))?;
+        }
+        if changed {
+            self.store.save(&booking)?;
+        }
+        Ok(booking.tags)
+    }
+
+    /// Remove a tag from a booking; returns the booking's tags afterwards
+    pub fn remove_tag(&self, pnr: &str, tag: &str) -> CoreResult<Vec<String>> {
+        let mut booking = self.load(pnr)?;
+        if booking.remove_tag(tag) {
+            self.store.save(&booking)?;
+        }
+        Ok(booking.tags)
+    }
+
+    /// Bookings carrying every one of `tags`
+    pub fn find_by_tags(&self, tags: &[String], limit: usize) -> CoreResult<Vec<Booking>> {
+        let tags = tags
+            .iter()
+            .map(|t| normalize_tag(t).map_err(|e| CoreError::ValidationError(e.to_string())))
+            .collect::<CoreResult<Vec<_>>>()?;
+        self.store.find_by_tags(&tags, limit)
+    }
+
+    fn load(&self, pnr: &str) -> CoreResult<Booking> {
+        self.store
+            .get(pnr)?
+            .ok_or_else(|| CoreError::BookingNotFound(pnr.to_string()))
+    }
+
+    async fn notify_mention(&self, booking: &Booking, note: &BookingNote, agent: &str) {
+        let status = match self.agents.agent_email(agent) {
+            None => {
+                warn!("Note on {} mentions unknown agent {}", booking.pnr, agent);
+                "unknown_agent"
+            }
+            Some(email) => {
+                let subject = format!("{} mentioned you on booking {}", note.author, booking.pnr);
+                let body = format!(
+                    "{} on booking {}:\n\n{}",
+                    note.category.label(),
+                    booking.pnr,
+                    note.content
+                );
+                match self
+                    .notifier
+                    .enqueue_email("note_mention", &email, &subject, &body)
+                    .await
+                {
+                    Ok(()) => "sent",
+                    Err(e) => {
+                        warn!(
+                            "Failed to notify {} of note on {}: {}",
+                            agent, booking.pnr, e
+                        );
+                        "failed"
+                    }
+                }
+            }
+        };
+        metrics::global()
+            .counter(NOTE_MENTIONS_METRIC, &[("status", status)])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use crate::testing::booking;
+
+    #[derive(Default)]
+    struct MemoryBookings {
+        bookings: Mutex<HashMap<String, Booking>>,
+    }
+
+    impl NoteStore for MemoryBookings {
+        fn get(&self, pnr: &str) -> CoreResult<Option<Booking>> {
+            Ok(self.bookings.lock().unwrap().get(pnr).cloned())
+        }
+
+        fn save(&self, booking: &Booking) -> CoreResult<()> {
+            self.bookings
+                .lock()
+                .unwrap()
+                .insert(booking.pnr.clone(), booking.clone());
+            Ok(())
+        }
+
+        fn find_by_tags(&self, tags: &[String], limit: usize) -> CoreResult<Vec<Booking>> {
+            Ok(self
+                .bookings
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|b| b.has_all_tags(tags))
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+    }
+
+    struct Agents;
+
+    impl AgentDirectory for Agents {
+        fn agent_email(&self, agent_id: &str) -> Option<String> {
+            (agent_id == "siti").then(|| "siti@vaya.my".to_string())
+        }
+    }
+
+    #[derive(Default)]
+    struct Captured {
+        emails: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Captured {
+        async fn send_email(&self, to: &str, subject: &str, _body: &str) -> CoreResult<()> {
+            self.emails
+                .lock()
+                .unwrap()
+                .push((to.to_string(), subject.to_string()));
+            Ok(())
+        }
+
+        async fn send_sms(&self, _to: &str, _body: &str) -> CoreResult<()> {
+            Ok(())
+        }
+    }
+
+    fn setup() -> (Arc<MemoryBookings>, Arc<Captured>, NotesService, String) {
+        let store = Arc::new(MemoryBookings::default());
+        let booking = booking();
+        let pnr = booking.pnr.clone();
+        store.save(&booking).unwrap();
+        let notifier = Arc::new(Captured::default());
+        let service = NotesService::new(store.clone(), Arc::new(Agents), notifier.clone());
+        (store, notifier, service, pnr)
+    }
+
+    #[tokio::test]
+    async fn test_note_notifies_mentioned_agents() {
+        let (store, notifier, service, pnr) = setup();
+
+        let note = service
+            .add_note(
+                &pnr,
+                NoteCategory::FraudReview,
+                NoteVisibility::Internal,
+                "@siti @ghost please review the card velocity, cc @amir",
+                "amir",
+            )
+            .await
+            .unwrap();
+        assert_eq!(note.mentions, ["siti", "ghost", "amir"]);
+
+        // Unknown agents and self-mentions are not emailed
+        let emails = notifier.emails.lock().unwrap().clone();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].0, "siti@vaya.my");
+        assert!(emails[0].1.contains(&pnr));
+        assert_eq!(store.get(&pnr).unwrap().unwrap().notes.len(), 1);
+
+        assert!(matches!(
+            service
+                .add_note(
+                    "NOPE00",
+                    NoteCategory::Ops,
+                    NoteVisibility::Internal,
+                    "x",
+                    "amir"
+                )
+                .await,
+            Err(CoreError::BookingNotFound(_))
+        ));
+        assert!(matches!(
+            service
+                .add_note(
+                    &pnr,
+                    NoteCategory::Ops,
+                    NoteVisibility::Internal,
+                    " ",
+                    "amir"
+                )
+                .await,
+            Err(CoreError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_tags() {
+        let (_store, _notifier, service, pnr) = setup();
+
+        let tags = service
+            .add_tags(&pnr, &["VIP".into(), "group-desk".into()])
+            .unwrap();
+        assert_eq!(tags, ["group-desk", "vip"]);
+        assert_eq!(service.find_by_tags(&["vip".into()], 10).unwrap().len(), 1);
+        assert!(service
+            .find_by_tags(&["vip".into(), "chargeback".into()], 10)
+            .unwrap()
+            .is_empty());
+        assert!(service.find_by_tags(&["bad tag".into()], 10).is_err());
+
+        assert_eq!(service.remove_tag(&pnr, "vip").unwrap(), ["group-desk"]);
+    }
+}
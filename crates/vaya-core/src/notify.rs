@@ -0,0 +1,491 @@
+//! Outbound user messaging
+//!
+//! Services that message users (verification codes, booking alerts) depend
+//! on the [`Notifier`] trait rather than on concrete clients, so tests can
+//! capture messages and deployments can choose providers.
+//!
+//! `send_*` delivers before returning and is for messages the user is
+//! waiting on (one-time codes, confirmation links). Everything else goes
+//! through `enqueue_*`: with a [`QueuedNotifier`] the message is handed to
+//! background workers and the caller returns immediately, so provider
+//! latency stays out of request handling.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, warn};
+
+use vaya_common::metrics;
+use vaya_notification::{EmailClient, EmailRequest, SmsClient, SmsRequest};
+
+use crate::error::{CoreError, CoreResult};
+
+/// Delivers email and SMS messages to users
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Send a plain-text email
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> CoreResult<()>;
+
+    /// Send an SMS
+    async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()>;
+
+    /// Send a plain-text email, returning the provider's message ID when
+    /// it reports one (for matching delivery webhooks)
+    async fn deliver_email(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> CoreResult<Option<String>> {
+        self.send_email(to, subject, body).await.map(|()| None)
+    }
+
+    /// Send an SMS, returning the provider's message ID when it reports one
+    async fn deliver_sms(&self, to: &str, body: &str) -> CoreResult<Option<String>> {
+        self.send_sms(to, body).await.map(|()| None)
+    }
+
+    /// Queue a plain-text email for background delivery
+    ///
+    /// `event` names the kind of message (e.g. "schedule_change") for
+    /// metrics. Notifiers without a queue send immediately.
+    async fn enqueue_email(
+        &self,
+        event: &str,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> CoreResult<()> {
+        let _ = event;
+        self.send_email(to, subject, body).await
+    }
+
+    /// Queue an SMS for background delivery
+    async fn enqueue_sms(&self, event: &str, to: &str, body: &str) -> CoreResult<()> {
+        let _ = event;
+        self.send_sms(to, body).await
+    }
+}
+
+/// Notifier backed by the notification crate's email and SMS clients
+pub struct NotificationClients {
+    email: Option<EmailClient>,
+    sms: Option<SmsClient>,
+}
+
+impl NotificationClients {
+    /// Create from optional clients
+    pub fn new(email: Option<EmailClient>, sms: Option<SmsClient>) -> Self {
+        Self { email, sms }
+    }
+}
+
+#[async_trait]
+impl Notifier for NotificationClients {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> CoreResult<()> {
+        self.deliver_email(to, subject, body).await.map(|_| ())
+    }
+
+    async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()> {
+        self.deliver_sms(to, body).await.map(|_| ())
+    }
+
+    async fn deliver_email(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> CoreResult<Option<String>> {
+        let client = self.email.as_ref().ok_or_else(|| {
+            CoreError::NotificationFailed("Email client not configured".to_string())
+        })?;
+        let result = client
+            .send(&EmailRequest::new(to, subject).with_text(body))
+            .await?;
+        Ok(Some(result.message_id))
+    }
+
+    async fn deliver_sms(&self, to: &str, body: &str) -> CoreResult<Option<String>> {
+        let client = self.sms.as_ref().ok_or_else(|| {
+            CoreError::NotificationFailed("SMS client not configured".to_string())
+        })?;
+        let result = client.send(&SmsRequest::new(to, body)).await?;
+        Ok(Some(result.message_sid))
+    }
+}
+
+/// Background delivery settings
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    /// Messages held before enqueue falls back to sending inline
+    pub capacity: usize,
+    /// Concurrent deliveries
+    pub workers: usize,
+    /// Delivery attempts per message
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub retry_backoff: Duration,
+    /// Target time from enqueue to delivery
+    pub default_slo: Duration,
+    /// Targets per event type
+    pub slos: HashMap<String, Duration>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        let slos = [
+            ("booking_confirmation", 30),
+            ("schedule_change", 60),
+            ("payment_reminder", 60),
+            ("price_alert", 300),
+        ]
+        .into_iter()
+        .map(|(event, secs)| (event.to_string(), Duration::from_secs(secs)))
+        .collect();
+        Self {
+            capacity: 10_000,
+            workers: 4,
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(2),
+            default_slo: Duration::from_secs(120),
+            slos,
+        }
+    }
+}
+
+impl QueueConfig {
+    /// Delivery target for an event type
+    pub fn slo(&self, event: &str) -> Duration {
+        self.slos.get(event).copied().unwrap_or(self.default_slo)
+    }
+}
+
+#[derive(Debug)]
+enum Message {
+    Email {
+        to: String,
+        subject: String,
+        body: String,
+    },
+    Sms {
+        to: String,
+        body: String,
+    },
+}
+
+impl Message {
+    fn channel(&self) -> &'static str {
+        match self {
+            Message::Email { .. } => "email",
+            Message::Sms { .. } => "sms",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Queued {
+    event: String,
+    message: Message,
+    enqueued_at: Instant,
+}
+
+/// Notifier that delivers `enqueue_*` messages from background workers
+///
+/// `send_*` still delivers inline through the wrapped notifier. Metrics:
+/// `vaya_notifications_sent_total`, `vaya_notifications_failed_total`
+/// and `vaya_notification_slo_breaches_total` by event and channel,
+/// `vaya_notification_latency_seconds` (last delivery) by event, and
+/// `vaya_notification_queue_depth`.
+pub struct QueuedNotifier {
+    inner: Arc<dyn Notifier>,
+    sender: mpsc::Sender<Queued>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl QueuedNotifier {
+    /// Wrap a notifier and start its delivery workers
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn start(inner: Arc<dyn Notifier>, config: QueueConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.capacity.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let config = Arc::new(config);
+        for _ in 0..config.workers.max(1) {
+            let receiver = receiver.clone();
+            let inner = inner.clone();
+            let pending = pending.clone();
+            let config = config.clone();
+            tokio::spawn(async move {
+                loop {
+                    // Hold the lock only while waiting, not while delivering
+                    let next = receiver.lock().await.recv().await;
+                    let Some(queued) = next else { break };
+                    deliver(inner.as_ref(), &config, queued).await;
+                    let depth = pending.fetch_sub(1, Ordering::SeqCst) - 1;
+                    metrics::global()
+                        .gauge("vaya_notification_queue_depth", &[])
+                        .set(depth as f64);
+                }
+            });
+        }
+        Self {
+            inner,
+            sender,
+            pending,
+        }
+    }
+
+    /// Messages queued or being delivered
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    async fn enqueue(&self, event: &str, message: Message) -> CoreResult<()> {
+        let queued = Queued {
+            event: event.to_string(),
+            message,
+            enqueued_at: Instant::now(),
+        };
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        match self.sender.try_send(queued) {
+            Ok(()) => {
+                metrics::global()
+                    .gauge("vaya_notification_queue_depth", &[])
+                    .set(self.pending() as f64);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Full(queued))
+            | Err(mpsc::error::TrySendError::Closed(queued)) => {
+                // Better slow than lost
+                self.pending.fetch_sub(1, Ordering::SeqCst);
+                metrics::global()
+                    .counter("vaya_notification_queue_full_total", &[])
+                    .inc();
+                warn!(event, "Notification queue full, sending inline");
+                match queued.message {
+                    Message::Email { to, subject, body } => {
+                        self.inner.send_email(&to, &subject, &body).await
+                    }
+                    Message::Sms { to, body } => self.inner.send_sms(&to, &body).await,
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for QueuedNotifier {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> CoreResult<()> {
+        self.inner.send_email(to, subject, body).await
+    }
+
+    async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()> {
+        self.inner.send_sms(to, body).await
+    }
+
+    async fn deliver_email(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> CoreResult<Option<String>> {
+        self.inner.deliver_email(to, subject, body).await
+    }
+
+    async fn deliver_sms(&self, to: &str, body: &str) -> CoreResult<Option<String>> {
+        self.inner.deliver_sms(to, body).await
+    }
+
+    async fn enqueue_email(
+        &self,
+        event: &str,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> CoreResult<()> {
+        self.enqueue(
+            event,
+            Message::Email {
+                to: to.to_string(),
+                subject: subject.to_string(),
+                body: body.to_string(),
+            },
+        )
+        .await
+    }
+
+    async fn enqueue_sms(&self, event: &str, to: &str, body: &str) -> CoreResult<()> {
+        self.enqueue(
+            event,
+            Message::Sms {
+                to: to.to_string(),
+                body: body.to_string(),
+            },
+        )
+        .await
+    }
+}
+
+/// Deliver one queued message with retries and record its metrics
+async fn deliver(inner: &dyn Notifier, config: &QueueConfig, queued: Queued) {
+    let channel = queued.message.channel();
+    let labels = [("event", queued.event.as_str()), ("channel", channel)];
+    let mut backoff = config.retry_backoff;
+    let mut attempt = 1;
+    let result = loop {
+        let result = match &queued.message {
+            Message::Email { to, subject, body } => inner.send_email(to, subject, body).await,
+            Message::Sms { to, body } => inner.send_sms(to, body).await,
+        };
+        match result {
+            Err(e) if attempt < config.max_attempts => {
+                debug!(event = %queued.event, attempt, error = %e, "Retrying notification");
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+            result => break result,
+        }
+    };
+
+    let registry = metrics::global();
+    match result {
+        Ok(()) => {
+            let latency = queued.enqueued_at.elapsed();
+            registry
+                .counter("vaya_notifications_sent_total", &labels)
+                .inc();
+            registry
+                .gauge(
+                    "vaya_notification_latency_seconds",
+                    &[("event", queued.event.as_str())],
+                )
+                .set(latency.as_secs_f64());
+            if latency > config.slo(&queued.event) {
+                registry
+                    .counter("vaya_notification_slo_breaches_total", &labels)
+                    .inc();
+                warn!(
+                    event = %queued.event,
+                    latency_ms = latency.as_millis() as u64,
+                    "Notification delivered outside its SLO"
+                );
+            }
+        }
+        Err(e) => {
+            registry
+                .counter("vaya_notifications_failed_total", &labels)
+                .inc();
+            warn!(
+                event = %queued.event,
+                channel,
+                attempts = attempt,
+                error = %e,
+                "Notification dropped after retries"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    /// Records sends; fails the first `failures` calls and sleeps per send
+    #[derive(Default)]
+    struct Recorder {
+        sent: std::sync::Mutex<Vec<String>>,
+        failures: AtomicU32,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Notifier for Recorder {
+        async fn send_email(&self, to: &str, _subject: &str, _body: &str) -> CoreResult<()> {
+            tokio::time::sleep(self.delay).await;
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(CoreError::NotificationFailed("provider down".into()));
+            }
+            self.sent.lock().unwrap().push(to.to_string());
+            Ok(())
+        }
+
+        async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()> {
+            self.send_email(to, "", body).await
+        }
+    }
+
+    fn config() -> QueueConfig {
+        QueueConfig {
+            retry_backoff: Duration::from_millis(1),
+            ..QueueConfig::default()
+        }
+    }
+
+    async fn drain(notifier: &QueuedNotifier) {
+        for _ in 0..500 {
+            if notifier.pending() == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        panic!("queue did not drain");
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_returns_before_delivery() {
+        let recorder = Arc::new(Recorder {
+            delay: Duration::from_millis(50),
+            ..Recorder::default()
+        });
+        let notifier = QueuedNotifier::start(recorder.clone(), config());
+
+        let started = Instant::now();
+        notifier
+            .enqueue_email("schedule_change", "a@vaya.my", "Schedule change", "...")
+            .await
+            .unwrap();
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(recorder.sent.lock().unwrap().is_empty());
+
+        drain(&notifier).await;
+        assert_eq!(*recorder.sent.lock().unwrap(), vec!["a@vaya.my"]);
+
+        // Must-send path delivers before returning
+        notifier.send_sms("+60123456789", "123456").await.unwrap();
+        assert_eq!(recorder.sent.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried() {
+        let recorder = Arc::new(Recorder::default());
+        recorder.failures.store(2, Ordering::SeqCst);
+        let notifier = QueuedNotifier::start(recorder.clone(), config());
+
+        notifier
+            .enqueue_sms("test_retry_event", "+60123456789", "Gate change")
+            .await
+            .unwrap();
+        drain(&notifier).await;
+        assert_eq!(recorder.sent.lock().unwrap().len(), 1);
+        assert_eq!(
+            metrics::global()
+                .counter(
+                    "vaya_notifications_sent_total",
+                    &[("event", "test_retry_event"), ("channel", "sms")]
+                )
+                .get(),
+            1
+        );
+    }
+}
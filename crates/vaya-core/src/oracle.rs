@@ -0,0 +1,661 @@
+//! Oracle verdicts
+//!
+//! Composes the oracle's individual signals — price prediction, route
+//! insight, best time to book, and the model's track record — into a
+//! single [`OracleVerdict`] with a final recommendation and the reasons
+//! behind it. Verdicts are cached per route and date.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use time::{Month, OffsetDateTime};
+use tracing::debug;
+
+use vaya_cache::Cache;
+use vaya_common::{CurrencyCode, IataCode, MinorUnits, Timestamp};
+use vaya_oracle::{
+    BestBookingTime, BookingRecommendation, PredictionTrace, PriceDataPoint, PriceInsight,
+    PricePrediction, PricePredictor, PriceTrend, FEATURE_NAMES,
+};
+
+use crate::error::{CoreError, CoreResult};
+
+/// Supplies historical price observations for a route
+pub trait PriceHistorySource: Send + Sync {
+    /// Observations for the route and departure date, oldest first
+    fn history(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: time::Date,
+    ) -> CoreResult<Vec<PriceDataPoint>>;
+}
+
+/// How far predictions have been from actual prices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccuracyStats {
+    /// Share of predictions within the tolerance of the actual price (0-1)
+    pub accuracy: f64,
+    /// Mean absolute percentage error
+    pub mape: f64,
+    /// Predictions scored
+    pub samples: u64,
+    /// When the stats last changed
+    pub updated_at: Timestamp,
+}
+
+impl Default for AccuracyStats {
+    fn default() -> Self {
+        Self {
+            accuracy: 0.0,
+            mape: 0.0,
+            samples: 0,
+            updated_at: Timestamp::now(),
+        }
+    }
+}
+
+/// Final recommendation with the reasons that produced it
+#[derive(Debug, Clone)]
+pub struct OracleRecommendation {
+    /// What to do
+    pub action: BookingRecommendation,
+    /// Confidence in the action (0-1)
+    pub confidence: f64,
+    /// Short summary for display
+    pub headline: String,
+    /// Reasons, most important first
+    pub reasons: Vec<String>,
+}
+
+/// Everything the oracle knows about a route and date
+#[derive(Debug, Clone)]
+pub struct OracleVerdict {
+    /// Origin
+    pub origin: IataCode,
+    /// Destination
+    pub destination: IataCode,
+    /// Departure date
+    pub departure_date: time::Date,
+    /// Price prediction (absent when history is too thin or stale)
+    pub prediction: Option<PricePrediction>,
+    /// Current price against recent history
+    pub insight: PriceInsight,
+    /// Best time to book
+    pub best_time: BestBookingTime,
+    /// Model track record
+    pub accuracy: AccuracyStats,
+    /// Final recommendation
+    pub recommendation: OracleRecommendation,
+    /// When the verdict was computed
+    pub generated_at: Timestamp,
+}
+
+impl OracleVerdict {
+    /// Serialize for the API
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "route": format!("{}-{}", self.origin, self.destination),
+            "date": self.departure_date.to_string(),
+            "currency": self.insight.currency.as_str(),
+            "prediction": self.prediction.as_ref().map(|p| serde_json::json!({
+                "predicted_price": p.predicted_price.as_i64(),
+                "price_low": p.price_low.as_i64(),
+                "price_high": p.price_high.as_i64(),
+                "confidence": p.confidence,
+                "confidence_level": p.confidence_level.as_str(),
+                "trend": p.trend.as_str(),
+                "expected_change_percent": p.expected_change_percent,
+                "model_version": p.model_version,
+            })),
+            "insight": {
+                "current_price": self.insight.current_price.as_i64(),
+                "avg_price_30d": self.insight.avg_price_30d.as_i64(),
+                "low_price_30d": self.insight.low_price_30d.as_i64(),
+                "high_price_30d": self.insight.high_price_30d.as_i64(),
+                "trend": self.insight.trend.as_str(),
+                "deal_score": self.insight.deal_score,
+                "position": self.insight.price_position(),
+            },
+            "best_time": {
+                "book_by": self.best_time.book_by_date.to_string(),
+                "days_before": self.best_time.days_before,
+                "season": self.best_time.season.as_str(),
+                "expected_price": self.best_time.expected_price.as_i64(),
+                "confidence": self.best_time.confidence,
+            },
+            "accuracy": {
+                "accuracy": self.accuracy.accuracy,
+                "mape": self.accuracy.mape,
+                "samples": self.accuracy.samples,
+            },
+            "recommendation": {
+                "action": self.recommendation.action.as_str(),
+                "confidence": self.recommendation.confidence,
+                "headline": self.recommendation.headline,
+                "reasons": self.recommendation.reasons,
+            },
+            "generated_at": self.generated_at.as_unix(),
+        })
+    }
+}
+
+/// Serialize a prediction trace for the admin debug API
+pub fn trace_to_json(trace: &PredictionTrace) -> serde_json::Value {
+    let p = &trace.prediction;
+    serde_json::json!({
+        "route": format!("{}-{}", trace.origin, trace.destination),
+        "date": trace.departure_date.to_string(),
+        "as_of": trace.as_of,
+        "model_version": trace.model_version,
+        "days_until_departure": trace.days_until_departure,
+        "season": trace.season.as_str(),
+        "window": {
+            "supplied": trace.window.supplied,
+            "after_as_of": trace.window.after_as_of,
+            "used": trace.window.used,
+            "from": trace.window.from,
+            "to": trace.window.to,
+        },
+        "feature_names": FEATURE_NAMES,
+        "features": trace.features.iter().map(|f| serde_json::json!({
+            "observed_at": f.observed_at,
+            "raw": f.raw,
+            "scaled": f.scaled,
+        })).collect::<Vec<_>>(),
+        "scaling": trace.scaling.as_ref().map(|s| serde_json::json!({
+            "mean": s.mean,
+            "std": s.std,
+        })),
+        "members": trace.members.iter().map(|m| serde_json::json!({
+            "name": m.name,
+            "raw_output": m.raw_output,
+            "predicted_price": m.predicted_price,
+            "confidence": m.confidence,
+            "selected": m.selected,
+        })).collect::<Vec<_>>(),
+        "prediction": {
+            "predicted_price": p.predicted_price.as_i64(),
+            "currency": p.currency.as_str(),
+            "price_low": p.price_low.as_i64(),
+            "price_high": p.price_high.as_i64(),
+            "confidence": p.confidence,
+            "trend": p.trend.as_str(),
+            "expected_change_percent": p.expected_change_percent,
+            "recommendation": p.recommendation.as_str(),
+        },
+    })
+}
+
+/// Oracle service configuration
+#[derive(Debug, Clone)]
+pub struct OracleServiceConfig {
+    /// How long verdicts are cached
+    pub cache_ttl: Duration,
+    /// Cache TTL for departures within a week, when prices move faster
+    pub near_departure_cache_ttl: Duration,
+    /// A prediction within this fraction of the actual price counts as
+    /// accurate
+    pub accuracy_tolerance: f64,
+}
+
+impl Default for OracleServiceConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl: Duration::from_secs(30 * 60),
+            near_departure_cache_ttl: Duration::from_secs(5 * 60),
+            accuracy_tolerance: 0.10,
+        }
+    }
+}
+
+/// Running totals behind [`AccuracyStats`]
+#[derive(Debug, Default)]
+struct AccuracyTotals {
+    samples: u64,
+    within_tolerance: u64,
+    abs_pct_error_sum: f64,
+    updated_at: Option<Timestamp>,
+}
+
+/// Builds oracle verdicts
+pub struct OracleService {
+    history: Arc<dyn PriceHistorySource>,
+    predictor: PricePredictor,
+    config: OracleServiceConfig,
+    cache: Cache<String, OracleVerdict>,
+    accuracy: RwLock<AccuracyTotals>,
+}
+
+impl OracleService {
+    /// Create a new oracle service
+    pub fn new(history: Arc<dyn PriceHistorySource>) -> Self {
+        Self {
+            history,
+            predictor: PricePredictor::new(),
+            config: OracleServiceConfig::default(),
+            cache: Cache::new(10_000, 16),
+            accuracy: RwLock::new(AccuracyTotals::default()),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: OracleServiceConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the price predictor
+    pub fn with_predictor(mut self, predictor: PricePredictor) -> Self {
+        self.predictor = predictor;
+        self
+    }
+
+    /// Get the verdict for a route and departure date (YYYY-MM-DD)
+    pub fn verdict(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        date: &str,
+    ) -> CoreResult<OracleVerdict> {
+        let departure_date = parse_date(date).ok_or_else(|| {
+            CoreError::InvalidSearchParams("Invalid departure date format".to_string())
+        })?;
+        let today = OffsetDateTime::now_utc().date();
+        if departure_date < today {
+            return Err(CoreError::InvalidSearchParams(
+                "Departure date is in the past".to_string(),
+            ));
+        }
+
+        let cache_key = format!("verdict:{}:{}:{}", origin, destination, departure_date);
+        if let Some(cached) = self.cache.get(&cache_key) {
+            debug!("Cache hit for verdict: {}", cache_key);
+            return Ok(cached);
+        }
+
+        let history = self.history.history(origin, destination, departure_date)?;
+        let latest = history.iter().max_by_key(|d| d.timestamp).ok_or_else(|| {
+            CoreError::PredictionUnavailable(format!(
+                "No price history for {}-{}",
+                origin, destination
+            ))
+        })?;
+        let currency: CurrencyCode = latest.currency;
+        let current_price = latest.price;
+
+        // Thin or stale history still yields an insight, just no prediction
+        let prediction =
+            match self
+                .predictor
+                .predict(origin, destination, departure_date, &history, currency)
+            {
+                Ok(prediction) => Some(prediction),
+                Err(e) => {
+                    debug!("No prediction for {}-{}: {}", origin, destination, e);
+                    None
+                }
+            };
+
+        let prices: Vec<MinorUnits> = history.iter().map(|d| d.price).collect();
+        let insight =
+            PriceInsight::from_data(origin, destination, current_price, currency, &prices);
+        let best_time = BestBookingTime::calculate(departure_date, current_price, currency);
+        let accuracy = self.accuracy();
+        let days_until = (departure_date - today).whole_days().max(0) as u32;
+
+        let recommendation = recommend(
+            prediction.as_ref(),
+            &insight,
+            &best_time,
+            &accuracy,
+            today,
+            days_until,
+        );
+
+        let verdict = OracleVerdict {
+            origin,
+            destination,
+            departure_date,
+            prediction,
+            insight,
+            best_time,
+            accuracy,
+            recommendation,
+            generated_at: Timestamp::now(),
+        };
+
+        let ttl = if days_until <= 7 {
+            self.config.near_departure_cache_ttl
+        } else {
+            self.config.cache_ttl
+        };
+        self.cache.insert(cache_key, verdict.clone(), Some(ttl));
+
+        Ok(verdict)
+    }
+
+    /// Rebuild the prediction for a route and date as it was made at `as_of`
+    ///
+    /// Uses only history observed by then, and records the features and
+    /// model outputs behind the prediction. Nothing is cached.
+    pub fn trace(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        date: &str,
+        as_of: Timestamp,
+    ) -> CoreResult<PredictionTrace> {
+        let departure_date = parse_date(date).ok_or_else(|| {
+            CoreError::InvalidSearchParams("Invalid departure date format".to_string())
+        })?;
+        let history = self.history.history(origin, destination, departure_date)?;
+        let currency = history
+            .iter()
+            .filter(|d| d.timestamp <= as_of.as_unix())
+            .max_by_key(|d| d.timestamp)
+            .map(|d| d.currency)
+            .ok_or_else(|| {
+                CoreError::PredictionUnavailable(format!(
+                    "No price history for {}-{} as of {}",
+                    origin,
+                    destination,
+                    as_of.as_unix()
+                ))
+            })?;
+
+        self.predictor
+            .trace(
+                origin,
+                destination,
+                departure_date,
+                &history,
+                currency,
+                as_of.as_unix(),
+            )
+            .map_err(|e| CoreError::PredictionUnavailable(e.to_string()))
+    }
+
+    /// Score a past prediction against the price that actually applied
+    pub fn record_outcome(&self, predicted: MinorUnits, actual: MinorUnits) {
+        let actual_minor = actual.as_i64();
+        if actual_minor <= 0 {
+            return;
+        }
+        let error = (predicted.as_i64() - actual_minor).abs() as f64 / actual_minor as f64;
+
+        let mut totals = self.accuracy.write().unwrap();
+        totals.samples += 1;
+        totals.abs_pct_error_sum += error * 100.0;
+        if error <= self.config.accuracy_tolerance {
+            totals.within_tolerance += 1;
+        }
+        totals.updated_at = Some(Timestamp::now());
+    }
+
+    /// Current accuracy stats
+    pub fn accuracy(&self) -> AccuracyStats {
+        let totals = self.accuracy.read().unwrap();
+        if totals.samples == 0 {
+            return AccuracyStats::default();
+        }
+        AccuracyStats {
+            accuracy: totals.within_tolerance as f64 / totals.samples as f64,
+            mape: totals.abs_pct_error_sum / totals.samples as f64,
+            samples: totals.samples,
+            updated_at: totals.updated_at.unwrap_or_else(Timestamp::now),
+        }
+    }
+}
+
+/// Parse date string (YYYY-MM-DD)
+pub(crate) fn parse_date(s: &str) -> Option<time::Date> {
+    let mut parts = s.split('-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: u8 = parts.next()?.parse().ok()?;
+    let day: u8 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    time::Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+/// Combine the individual signals into a final recommendation
+fn recommend(
+    prediction: Option<&PricePrediction>,
+    insight: &PriceInsight,
+    best_time: &BestBookingTime,
+    accuracy: &AccuracyStats,
+    today: time::Date,
+    days_until: u32,
+) -> OracleRecommendation {
+    let mut reasons = Vec::new();
+
+    let (mut action, mut confidence) = match prediction {
+        Some(p) => {
+            match p.trend {
+                PriceTrend::StrongUp | PriceTrend::Up => reasons.push(format!(
+                    "Prices are expected to rise about {:.0}%",
+                    p.expected_change_percent.abs()
+                )),
+                PriceTrend::StrongDown | PriceTrend::Down => reasons.push(format!(
+                    "Prices are expected to fall about {:.0}%",
+                    p.expected_change_percent.abs()
+                )),
+                PriceTrend::Stable => {
+                    reasons.push("Prices are expected to stay about the same".to_string())
+                }
+            }
+            (p.recommendation, p.confidence)
+        }
+        None => {
+            reasons.push("Not enough recent data for a price forecast".to_string());
+            let action = if insight.is_good_deal {
+                BookingRecommendation::BookSoon
+            } else {
+                BookingRecommendation::Monitor
+            };
+            (action, best_time.confidence * 0.5)
+        }
+    };
+
+    if days_until <= 3 {
+        action = BookingRecommendation::BookNow;
+        reasons.insert(0, "Departure is less than 3 days away".to_string());
+    }
+
+    if insight.is_good_deal {
+        reasons.push(format!(
+            "Current price is {}",
+            insight.price_position().to_lowercase()
+        ));
+        if insight.deal_score >= 90
+            && matches!(
+                action,
+                BookingRecommendation::Wait | BookingRecommendation::Monitor
+            )
+        {
+            action = BookingRecommendation::BookSoon;
+        }
+    } else if insight.deal_score < 30 {
+        reasons.push("Current price is high for this route".to_string());
+    }
+
+    if today > best_time.book_by_date {
+        reasons.push(format!(
+            "Past the usual best time to book for {} season",
+            best_time.season.as_str().to_lowercase().replace('_', "-")
+        ));
+        if action == BookingRecommendation::Wait {
+            action = BookingRecommendation::Monitor;
+        }
+    } else {
+        reasons.push(format!(
+            "Best booked by {} ({} days before departure)",
+            best_time.book_by_date, best_time.days_before
+        ));
+    }
+
+    // Weight confidence by the model's track record once it has one
+    if accuracy.samples >= 30 {
+        confidence *= accuracy.accuracy.clamp(0.5, 1.0);
+        reasons.push(format!(
+            "Recent forecasts were accurate {:.0}% of the time",
+            accuracy.accuracy * 100.0
+        ));
+    }
+
+    let headline = match action {
+        BookingRecommendation::BookNow => "Book now",
+        BookingRecommendation::BookSoon => "Book in the next few days",
+        BookingRecommendation::Wait => "Wait for a better price",
+        BookingRecommendation::Monitor => "Keep watching prices",
+    }
+    .to_string();
+
+    OracleRecommendation {
+        action,
+        confidence: confidence.clamp(0.0, 1.0),
+        headline,
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedHistory(Vec<PriceDataPoint>);
+
+    impl PriceHistorySource for FixedHistory {
+        fn history(
+            &self,
+            _origin: IataCode,
+            _destination: IataCode,
+            _departure_date: time::Date,
+        ) -> CoreResult<Vec<PriceDataPoint>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn point(price: i64, hours_ago: i64, days_before: u32) -> PriceDataPoint {
+        PriceDataPoint {
+            price: MinorUnits::new(price),
+            currency: CurrencyCode::MYR,
+            timestamp: OffsetDateTime::now_utc().unix_timestamp() - hours_ago * 3600,
+            days_before_departure: days_before,
+            day_of_week: 1,
+            is_weekend_departure: false,
+            is_holiday: false,
+        }
+    }
+
+    fn departure_in(days: i64) -> String {
+        (OffsetDateTime::now_utc().date() + time::Duration::days(days)).to_string()
+    }
+
+    #[test]
+    fn test_verdict_composes_signals() {
+        let history: Vec<PriceDataPoint> = (0..20)
+            .map(|i| point(40_000 - i * 200, 40 - i, 30))
+            .collect();
+        let service = OracleService::new(Arc::new(FixedHistory(history)));
+
+        let verdict = service
+            .verdict(IataCode::KUL, IataCode::SIN, &departure_in(30))
+            .unwrap();
+        assert!(verdict.prediction.is_some());
+        assert_eq!(verdict.insight.current_price.as_i64(), 36_200);
+        assert!(!verdict.recommendation.reasons.is_empty());
+
+        let json = verdict.to_json();
+        assert_eq!(json["route"], "KUL-SIN");
+        assert!(json["recommendation"]["action"].is_string());
+    }
+
+    #[test]
+    fn test_trace_reproduces_past_prediction() {
+        let history: Vec<PriceDataPoint> = (0..20)
+            .map(|i| point(40_000 - i * 200, 40 - i, 30))
+            .collect();
+        let service = OracleService::new(Arc::new(FixedHistory(history)));
+        let date = departure_in(30);
+
+        // As of now the trace matches the live prediction
+        let live = service
+            .verdict(IataCode::KUL, IataCode::SIN, &date)
+            .unwrap()
+            .prediction
+            .unwrap();
+        let now = service
+            .trace(IataCode::KUL, IataCode::SIN, &date, Timestamp::now())
+            .unwrap();
+        assert_eq!(now.prediction.predicted_price, live.predicted_price);
+        assert_eq!(now.window.after_as_of, 0);
+
+        // 30.5 hours ago the newest ten observations hadn't been made
+        let past = service
+            .trace(
+                IataCode::KUL,
+                IataCode::SIN,
+                &date,
+                Timestamp::now().add_secs(-(30 * 3600 + 1800)),
+            )
+            .unwrap();
+        assert_eq!(past.window.after_as_of, 10);
+        assert_eq!(past.window.used, 10);
+        assert!(past.prediction.predicted_price.as_i64() > live.predicted_price.as_i64());
+
+        let json = trace_to_json(&past);
+        assert_eq!(json["window"]["used"], 10);
+        assert_eq!(json["feature_names"][4], "holiday");
+        assert_eq!(json["members"][0]["name"], "statistical");
+
+        assert!(matches!(
+            service.trace(
+                IataCode::KUL,
+                IataCode::SIN,
+                &date,
+                Timestamp::now().add_days(-30)
+            ),
+            Err(CoreError::PredictionUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_verdict_without_prediction() {
+        let service = OracleService::new(Arc::new(FixedHistory(vec![point(30_000, 1, 2)])));
+
+        let verdict = service
+            .verdict(IataCode::KUL, IataCode::SIN, &departure_in(2))
+            .unwrap();
+        assert!(verdict.prediction.is_none());
+        assert_eq!(
+            verdict.recommendation.action,
+            BookingRecommendation::BookNow
+        );
+
+        let empty = OracleService::new(Arc::new(FixedHistory(vec![])));
+        assert!(matches!(
+            empty.verdict(IataCode::KUL, IataCode::SIN, &departure_in(10)),
+            Err(CoreError::PredictionUnavailable(_))
+        ));
+        assert!(empty
+            .verdict(IataCode::KUL, IataCode::SIN, "2020-01-01")
+            .is_err());
+    }
+
+    #[test]
+    fn test_accuracy_stats() {
+        let service = OracleService::new(Arc::new(FixedHistory(vec![])));
+        assert_eq!(service.accuracy().samples, 0);
+
+        service.record_outcome(MinorUnits::new(10_500), MinorUnits::new(10_000));
+        service.record_outcome(MinorUnits::new(8_000), MinorUnits::new(10_000));
+
+        let stats = service.accuracy();
+        assert_eq!(stats.samples, 2);
+        assert!((stats.accuracy - 0.5).abs() < f64::EPSILON);
+        assert!((stats.mape - 12.5).abs() < 1e-9);
+    }
+}
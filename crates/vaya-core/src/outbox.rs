@@ -0,0 +1,1036 @@
+//! Notification outbox
+//!
+//! Messages are written to an [`OutboxStore`] before any delivery attempt,
+//! so a crash or provider outage cannot lose them. [`Outbox::drain`] sends
+//! what is due through a [`Notifier`]; a failed attempt is retried with
+//! exponential backoff and, after `max_attempts`, the message is moved to
+//! the dead letters, where an operator can inspect and requeue it.
+//!
+//! Provider webhooks, parsed into [`DeliveryReport`]s, then move sent
+//! messages on to delivered, bounced or failed. [`Outbox::history`] lists a
+//! user's messages with their delivery status.
+//!
+//! With a [`PreferenceRepository`], [`Outbox::push`] applies the user's
+//! [`NotificationPreferences`]: messages on a channel the user turned off
+//! are dropped, and those in quiet hours or a digest are scheduled for
+//! when they may be sent.
+//!
+//! [`Outbox`] is itself a [`Notifier`]: `enqueue_*` persists to the outbox
+//! and `send_*` still delivers inline.
+//!
+//! Metrics: `vaya_outbox_sent_total`, `vaya_outbox_retries_total`,
+//! `vaya_outbox_dead_letters_total` and `vaya_outbox_suppressed_total` by
+//! channel.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use vaya_common::{metrics, Timestamp, Uuid};
+use vaya_db::VayaDb;
+use vaya_notification::{DeliveryReport, NotificationStatus};
+
+use crate::error::{CoreError, CoreResult};
+use crate::notify::Notifier;
+use crate::preferences::{NotificationCategory, NotificationChannel, NotificationPreferences};
+use crate::repository::PreferenceRepository;
+
+/// Delivery channel of an outbox message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutboxChannel {
+    /// Plain-text email
+    Email,
+    /// SMS
+    Sms,
+}
+
+impl OutboxChannel {
+    /// Channel name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxChannel::Email => "email",
+            OutboxChannel::Sms => "sms",
+        }
+    }
+
+    /// Preference channel the message is delivered on
+    pub fn preference_channel(&self) -> NotificationChannel {
+        match self {
+            OutboxChannel::Email => NotificationChannel::Email,
+            OutboxChannel::Sms => NotificationChannel::Sms,
+        }
+    }
+
+    /// Parse a channel name
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "email" => Some(OutboxChannel::Email),
+            "sms" => Some(OutboxChannel::Sms),
+            _ => None,
+        }
+    }
+}
+
+/// A persisted notification and its delivery state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxMessage {
+    /// Message ID
+    pub id: String,
+    /// User the message is for, when known
+    pub user_id: Option<String>,
+    /// Kind of message (e.g. "schedule_change")
+    pub event: String,
+    /// Delivery channel
+    pub channel: OutboxChannel,
+    /// Email address or E.164 phone number
+    pub to: String,
+    /// Email subject (empty for SMS)
+    pub subject: String,
+    /// Message body
+    pub body: String,
+    /// Delivery status
+    pub status: NotificationStatus,
+    /// Delivery attempts made
+    pub attempts: u32,
+    /// Earliest time of the next attempt
+    pub next_attempt_at: Timestamp,
+    /// Last send error, or the provider's bounce/failure reason
+    pub last_error: Option<String>,
+    /// Provider message ID, once sent
+    pub provider_id: Option<String>,
+    /// Gave up after `max_attempts`
+    pub dead_lettered: bool,
+    /// Created at
+    pub created_at: Timestamp,
+    /// Updated at
+    pub updated_at: Timestamp,
+}
+
+impl OutboxMessage {
+    fn new(
+        channel: OutboxChannel,
+        event: &str,
+        to: &str,
+        subject: &str,
+        body: &str,
+        now: Timestamp,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id: None,
+            event: event.to_string(),
+            channel,
+            to: to.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            status: NotificationStatus::Queued,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            provider_id: None,
+            dead_lettered: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// A plain-text email, due now
+    pub fn email(event: &str, to: &str, subject: &str, body: &str) -> Self {
+        Self::new(
+            OutboxChannel::Email,
+            event,
+            to,
+            subject,
+            body,
+            Timestamp::now(),
+        )
+    }
+
+    /// An SMS, due now
+    pub fn sms(event: &str, to: &str, body: &str) -> Self {
+        Self::new(OutboxChannel::Sms, event, to, "", body, Timestamp::now())
+    }
+
+    /// Record the user the message is for
+    pub fn with_user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+
+    /// Waiting for a delivery attempt
+    pub fn is_queued(&self) -> bool {
+        self.status == NotificationStatus::Queued && !self.dead_lettered
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "user_id": self.user_id,
+            "event": self.event,
+            "channel": self.channel.as_str(),
+            "to": self.to,
+            "subject": self.subject,
+            "body": self.body,
+            "status": self.status.as_str(),
+            "attempts": self.attempts,
+            "next_attempt_at": self.next_attempt_at.as_unix(),
+            "last_error": self.last_error,
+            "provider_id": self.provider_id,
+            "dead_lettered": self.dead_lettered,
+            "created_at": self.created_at.as_unix(),
+            "updated_at": self.updated_at.as_unix(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let text = |name: &str| value.get(name)?.as_str().map(str::to_string);
+        let time = |name: &str| value.get(name)?.as_i64().map(Timestamp::from_unix);
+        Some(Self {
+            id: text("id")?,
+            user_id: text("user_id"),
+            event: text("event")?,
+            channel: OutboxChannel::parse(value.get("channel")?.as_str()?)?,
+            to: text("to")?,
+            subject: text("subject").unwrap_or_default(),
+            body: text("body")?,
+            status: NotificationStatus::parse(value.get("status")?.as_str()?)?,
+            attempts: u32::try_from(value.get("attempts")?.as_u64()?).ok()?,
+            next_attempt_at: time("next_attempt_at")?,
+            last_error: text("last_error"),
+            provider_id: text("provider_id"),
+            dead_lettered: value.get("dead_lettered")?.as_bool()?,
+            created_at: time("created_at")?,
+            updated_at: time("updated_at")?,
+        })
+    }
+}
+
+/// Order of delivery statuses; webhook reports never move a message back
+fn status_rank(status: NotificationStatus) -> u8 {
+    match status {
+        NotificationStatus::Queued => 0,
+        NotificationStatus::Sent => 1,
+        NotificationStatus::Delivered => 2,
+        NotificationStatus::Opened => 3,
+        NotificationStatus::Clicked => 4,
+        NotificationStatus::Bounced | NotificationStatus::Failed => 5,
+        NotificationStatus::SpamComplaint => 6,
+    }
+}
+
+/// Storage for outbox messages
+pub trait OutboxStore: Send + Sync {
+    /// Insert or update a message and its indexes
+    fn save(&self, message: &OutboxMessage) -> CoreResult<()>;
+
+    /// Load a message
+    fn get(&self, id: &str) -> CoreResult<Option<OutboxMessage>>;
+
+    /// Message the provider accepted under `provider_id`
+    fn find_by_provider_id(&self, provider_id: &str) -> CoreResult<Option<OutboxMessage>>;
+
+    /// Messages waiting for delivery, oldest first
+    fn queued(&self) -> CoreResult<Vec<OutboxMessage>>;
+
+    /// Dead-lettered messages, oldest first
+    fn dead_letters(&self) -> CoreResult<Vec<OutboxMessage>>;
+
+    /// A user's messages, oldest first
+    fn for_user(&self, user_id: &str) -> CoreResult<Vec<OutboxMessage>>;
+}
+
+/// In-memory outbox store
+#[derive(Default)]
+pub struct MemoryOutboxStore {
+    messages: RwLock<HashMap<String, OutboxMessage>>,
+    /// Message IDs in insertion order
+    order: RwLock<Vec<String>>,
+}
+
+impl MemoryOutboxStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn filtered(&self, keep: impl Fn(&OutboxMessage) -> bool) -> Vec<OutboxMessage> {
+        let messages = self.messages.read().unwrap();
+        self.order
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|id| messages.get(id))
+            .filter(|m| keep(m))
+            .cloned()
+            .collect()
+    }
+}
+
+impl OutboxStore for MemoryOutboxStore {
+    fn save(&self, message: &OutboxMessage) -> CoreResult<()> {
+        let previous = self
+            .messages
+            .write()
+            .unwrap()
+            .insert(message.id.clone(), message.clone());
+        if previous.is_none() {
+            self.order.write().unwrap().push(message.id.clone());
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> CoreResult<Option<OutboxMessage>> {
+        Ok(self.messages.read().unwrap().get(id).cloned())
+    }
+
+    fn find_by_provider_id(&self, provider_id: &str) -> CoreResult<Option<OutboxMessage>> {
+        Ok(self
+            .messages
+            .read()
+            .unwrap()
+            .values()
+            .find(|m| m.provider_id.as_deref() == Some(provider_id))
+            .cloned())
+    }
+
+    fn queued(&self) -> CoreResult<Vec<OutboxMessage>> {
+        Ok(self.filtered(OutboxMessage::is_queued))
+    }
+
+    fn dead_letters(&self) -> CoreResult<Vec<OutboxMessage>> {
+        Ok(self.filtered(|m| m.dead_lettered))
+    }
+
+    fn for_user(&self, user_id: &str) -> CoreResult<Vec<OutboxMessage>> {
+        Ok(self.filtered(|m| m.user_id.as_deref() == Some(user_id)))
+    }
+}
+
+const MESSAGE_PREFIX: &str = "outbox:msg:";
+const USER_PREFIX: &str = "outbox:user:";
+const PROVIDER_PREFIX: &str = "outbox:provider:";
+const QUEUED_KEY: &[u8] = b"outbox:queued";
+const DEAD_LETTERS_KEY: &[u8] = b"outbox:dead";
+
+/// Outbox store persisting to `VayaDb`
+///
+/// Messages are kept as JSON under `outbox:msg:{id}`. `VayaDb` has no key
+/// iteration, so queued messages, dead letters and each user's messages
+/// are listed by ID under `outbox:queued`, `outbox:dead` and
+/// `outbox:user:{user}`, and `outbox:provider:{provider id}` maps webhook
+/// IDs back to messages.
+pub struct DbOutboxStore {
+    db: Arc<VayaDb>,
+    /// Serializes index updates
+    lock: Mutex<()>,
+}
+
+impl DbOutboxStore {
+    /// Create a store over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self, key: &[u8]) -> CoreResult<Option<Value>> {
+        let Some(bytes) = self.db.get(key).map_err(db_error)? else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| CoreError::Database(format!("Corrupt outbox record: {}", e)))
+    }
+
+    fn write(&self, key: &[u8], value: &Value) -> CoreResult<()> {
+        self.db
+            .put(key, value.to_string().as_bytes())
+            .map_err(db_error)
+    }
+
+    fn ids(&self, key: &[u8]) -> CoreResult<Vec<String>> {
+        Ok(self
+            .read(key)?
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// Add or remove `id` from an index list, writing only on change
+    fn set_member(&self, key: &[u8], id: &str, member: bool) -> CoreResult<()> {
+        let mut ids = self.ids(key)?;
+        let present = ids.iter().any(|i| i == id);
+        if member && !present {
+            ids.push(id.to_string());
+        } else if !member && present {
+            ids.retain(|i| i != id);
+        } else {
+            return Ok(());
+        }
+        self.write(key, &json!(ids))
+    }
+
+    fn load(&self, ids: Vec<String>) -> CoreResult<Vec<OutboxMessage>> {
+        let mut messages = Vec::with_capacity(ids.len());
+        for id in ids {
+            messages.extend(self.get(&id)?);
+        }
+        Ok(messages)
+    }
+}
+
+impl OutboxStore for DbOutboxStore {
+    fn save(&self, message: &OutboxMessage) -> CoreResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let key = format!("{}{}", MESSAGE_PREFIX, message.id);
+        self.write(key.as_bytes(), &message.to_json())?;
+
+        self.set_member(QUEUED_KEY, &message.id, message.is_queued())?;
+        self.set_member(DEAD_LETTERS_KEY, &message.id, message.dead_lettered)?;
+        if let Some(user_id) = &message.user_id {
+            let key = format!("{}{}", USER_PREFIX, user_id);
+            self.set_member(key.as_bytes(), &message.id, true)?;
+        }
+        if let Some(provider_id) = &message.provider_id {
+            let key = format!("{}{}", PROVIDER_PREFIX, provider_id);
+            self.write(key.as_bytes(), &json!(message.id))?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> CoreResult<Option<OutboxMessage>> {
+        let key = format!("{}{}", MESSAGE_PREFIX, id);
+        match self.read(key.as_bytes())? {
+            Some(value) => OutboxMessage::from_json(&value)
+                .map(Some)
+                .ok_or_else(|| CoreError::Database("Corrupt outbox message".into())),
+            None => Ok(None),
+        }
+    }
+
+    fn find_by_provider_id(&self, provider_id: &str) -> CoreResult<Option<OutboxMessage>> {
+        let key = format!("{}{}", PROVIDER_PREFIX, provider_id);
+        match self.read(key.as_bytes())?.as_ref().and_then(Value::as_str) {
+            Some(id) => self.get(id),
+            None => Ok(None),
+        }
+    }
+
+    fn queued(&self) -> CoreResult<Vec<OutboxMessage>> {
+        self.load(self.ids(QUEUED_KEY)?)
+    }
+
+    fn dead_letters(&self) -> CoreResult<Vec<OutboxMessage>> {
+        self.load(self.ids(DEAD_LETTERS_KEY)?)
+    }
+
+    fn for_user(&self, user_id: &str) -> CoreResult<Vec<OutboxMessage>> {
+        let key = format!("{}{}", USER_PREFIX, user_id);
+        self.load(self.ids(key.as_bytes())?)
+    }
+}
+
+fn db_error(e: vaya_db::DbError) -> CoreError {
+    CoreError::Database(e.to_string())
+}
+
+/// Outbox delivery settings
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    /// Delivery attempts before a message is dead-lettered
+    pub max_attempts: u32,
+    /// Delay after the first failed attempt, doubled for each further one
+    pub retry_backoff: Duration,
+    /// Longest delay between attempts
+    pub max_backoff: Duration,
+    /// Messages sent per drain
+    pub batch_size: usize,
+    /// Pause between drains in the background worker
+    pub poll_interval: Duration,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            retry_backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(3600),
+            batch_size: 100,
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// Delay before the attempt after `attempts` failed ones
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempts.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.retry_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |d| d.min(self.max_backoff))
+    }
+}
+
+/// Outcome of one drain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrainReport {
+    /// Messages accepted by the provider
+    pub sent: usize,
+    /// Failed attempts scheduled for retry
+    pub retried: usize,
+    /// Messages that used their last attempt
+    pub dead_lettered: usize,
+}
+
+/// Persistent notification queue with retries and delivery tracking
+pub struct Outbox {
+    store: Arc<dyn OutboxStore>,
+    notifier: Arc<dyn Notifier>,
+    config: OutboxConfig,
+    preferences: Option<Arc<dyn PreferenceRepository>>,
+    /// One drain at a time, so a message is never sent twice
+    draining: tokio::sync::Mutex<()>,
+}
+
+impl Outbox {
+    /// Create an outbox delivering through `notifier`
+    pub fn new(store: Arc<dyn OutboxStore>, notifier: Arc<dyn Notifier>) -> Self {
+        Self {
+            store,
+            notifier,
+            config: OutboxConfig::default(),
+            preferences: None,
+            draining: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Set delivery settings
+    pub fn with_config(mut self, config: OutboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Apply users' notification preferences to pushed messages
+    pub fn with_preferences(mut self, preferences: Arc<dyn PreferenceRepository>) -> Self {
+        self.preferences = Some(preferences);
+        self
+    }
+
+    /// Persist a message for delivery
+    ///
+    /// Returns `None` if the user's preferences turn the message's channel
+    /// off for its category; nothing is stored then.
+    pub fn push(&self, mut message: OutboxMessage) -> CoreResult<Option<OutboxMessage>> {
+        if let (Some(repository), Some(user_id)) = (&self.preferences, &message.user_id) {
+            let preferences = repository
+                .get(user_id)?
+                .unwrap_or_else(|| NotificationPreferences::for_user(user_id));
+            let category = NotificationCategory::for_event(&message.event);
+            if !preferences.allows(category, message.channel.preference_channel()) {
+                metrics::global()
+                    .counter(
+                        "vaya_outbox_suppressed_total",
+                        &[("channel", message.channel.as_str())],
+                    )
+                    .inc();
+                debug!(
+                    event = %message.event,
+                    user_id = %user_id,
+                    channel = message.channel.as_str(),
+                    "Notification suppressed by user preferences"
+                );
+                return Ok(None);
+            }
+            message.next_attempt_at = preferences.send_at(category, message.next_attempt_at);
+        }
+        self.store.save(&message)?;
+        debug!(id = %message.id, event = %message.event, "Notification queued in outbox");
+        Ok(Some(message))
+    }
+
+    /// Load a message
+    pub fn get(&self, id: &str) -> CoreResult<Option<OutboxMessage>> {
+        self.store.get(id)
+    }
+
+    /// A user's messages with their delivery status, newest first
+    pub fn history(&self, user_id: &str) -> CoreResult<Vec<OutboxMessage>> {
+        let mut messages = self.store.for_user(user_id)?;
+        messages.reverse();
+        Ok(messages)
+    }
+
+    /// Messages that used all their attempts, oldest first
+    pub fn dead_letters(&self) -> CoreResult<Vec<OutboxMessage>> {
+        self.store.dead_letters()
+    }
+
+    /// Give a dead-lettered message a fresh set of attempts, due now
+    pub fn requeue(&self, id: &str) -> CoreResult<OutboxMessage> {
+        let mut message = self
+            .store
+            .get(id)?
+            .ok_or_else(|| CoreError::NotificationNotFound(id.to_string()))?;
+        if !message.dead_lettered {
+            return Err(CoreError::ValidationError(format!(
+                "Outbox message {} is not dead-lettered",
+                id
+            )));
+        }
+        let now = Timestamp::now();
+        message.dead_lettered = false;
+        message.status = NotificationStatus::Queued;
+        message.attempts = 0;
+        message.next_attempt_at = now;
+        message.updated_at = now;
+        self.store.save(&message)?;
+        info!(id, "Dead-lettered notification requeued");
+        Ok(message)
+    }
+
+    /// Send messages that are due
+    pub async fn drain(&self) -> CoreResult<DrainReport> {
+        self.drain_at(Timestamp::now()).await
+    }
+
+    /// Send messages due at `now`, oldest first, up to `batch_size`
+    pub async fn drain_at(&self, now: Timestamp) -> CoreResult<DrainReport> {
+        let _guard = self.draining.lock().await;
+        let mut report = DrainReport::default();
+        let due = self
+            .store
+            .queued()?
+            .into_iter()
+            .filter(|m| m.next_attempt_at <= now)
+            .take(self.config.batch_size);
+
+        for mut message in due {
+            let result = match message.channel {
+                OutboxChannel::Email => {
+                    self.notifier
+                        .deliver_email(&message.to, &message.subject, &message.body)
+                        .await
+                }
+                OutboxChannel::Sms => self.notifier.deliver_sms(&message.to, &message.body).await,
+            };
+            message.attempts += 1;
+            message.updated_at = now;
+            let channel = [("channel", message.channel.as_str())];
+
+            match result {
+                Ok(provider_id) => {
+                    message.status = NotificationStatus::Sent;
+                    message.provider_id = provider_id;
+                    message.last_error = None;
+                    report.sent += 1;
+                    metrics::global()
+                        .counter("vaya_outbox_sent_total", &channel)
+                        .inc();
+                }
+                Err(e) if message.attempts < self.config.max_attempts => {
+                    let delay = self.config.backoff(message.attempts);
+                    message.next_attempt_at =
+                        now.add_secs(i64::try_from(delay.as_secs()).unwrap_or(i64::MAX));
+                    message.last_error = Some(e.to_string());
+                    report.retried += 1;
+                    metrics::global()
+                        .counter("vaya_outbox_retries_total", &channel)
+                        .inc();
+                    debug!(
+                        id = %message.id,
+                        attempt = message.attempts,
+                        error = %e,
+                        "Notification failed, retrying later"
+                    );
+                }
+                Err(e) => {
+                    message.status = NotificationStatus::Failed;
+                    message.dead_lettered = true;
+                    message.last_error = Some(e.to_string());
+                    report.dead_lettered += 1;
+                    metrics::global()
+                        .counter("vaya_outbox_dead_letters_total", &channel)
+                        .inc();
+                    warn!(
+                        id = %message.id,
+                        event = %message.event,
+                        attempts = message.attempts,
+                        error = %e,
+                        "Notification dead-lettered"
+                    );
+                }
+            }
+            self.store.save(&message)?;
+        }
+        Ok(report)
+    }
+
+    /// Apply a provider's delivery webhook
+    ///
+    /// Returns the updated message, or `None` if no message was sent under
+    /// the report's ID. Reports never move a message back to an earlier
+    /// status, so out-of-order webhooks are harmless.
+    pub fn record_delivery(&self, report: &DeliveryReport) -> CoreResult<Option<OutboxMessage>> {
+        let Some(mut message) = self.store.find_by_provider_id(&report.message_id)? else {
+            debug!(provider_id = %report.message_id, "Delivery report for unknown message");
+            return Ok(None);
+        };
+        if status_rank(report.status) <= status_rank(message.status) {
+            return Ok(Some(message));
+        }
+        message.status = report.status;
+        if let Some(reason) = &report.reason {
+            if matches!(
+                report.status,
+                NotificationStatus::Bounced | NotificationStatus::Failed
+            ) {
+                message.last_error = Some(reason.clone());
+            }
+        }
+        message.updated_at = report.timestamp.unwrap_or_else(Timestamp::now);
+        self.store.save(&message)?;
+        Ok(Some(message))
+    }
+
+    /// Drain every `poll_interval` until the task is aborted
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.drain().await {
+                    warn!(error = %e, "Outbox drain failed");
+                }
+                tokio::time::sleep(self.config.poll_interval).await;
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for Outbox {
+    async fn send_email(&self, to: &str, subject: &str, body: &str) -> CoreResult<()> {
+        self.notifier.send_email(to, subject, body).await
+    }
+
+    async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()> {
+        self.notifier.send_sms(to, body).await
+    }
+
+    async fn enqueue_email(
+        &self,
+        event: &str,
+        to: &str,
+        subject: &str,
+        body: &str,
+    ) -> CoreResult<()> {
+        self.push(OutboxMessage::email(event, to, subject, body))
+            .map(|_| ())
+    }
+
+    async fn enqueue_sms(&self, event: &str, to: &str, body: &str) -> CoreResult<()> {
+        self.push(OutboxMessage::sms(event, to, body)).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use vaya_db::DbConfig;
+
+    /// Fails the first `failures` sends, then returns numbered provider IDs
+    #[derive(Default)]
+    struct Provider {
+        sent: Mutex<Vec<String>>,
+        failures: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Notifier for Provider {
+        async fn send_email(&self, to: &str, subject: &str, body: &str) -> CoreResult<()> {
+            self.deliver_email(to, subject, body).await.map(|_| ())
+        }
+
+        async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()> {
+            self.deliver_sms(to, body).await.map(|_| ())
+        }
+
+        async fn deliver_email(
+            &self,
+            to: &str,
+            _subject: &str,
+            _body: &str,
+        ) -> CoreResult<Option<String>> {
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(CoreError::NotificationFailed("provider down".into()));
+            }
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(to.to_string());
+            Ok(Some(format!("msg-{}", sent.len())))
+        }
+
+        async fn deliver_sms(&self, to: &str, body: &str) -> CoreResult<Option<String>> {
+            self.deliver_email(to, "", body).await
+        }
+    }
+
+    fn config() -> OutboxConfig {
+        OutboxConfig {
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(30),
+            max_backoff: Duration::from_secs(3600),
+            ..OutboxConfig::default()
+        }
+    }
+
+    fn report(provider_id: &str, status: NotificationStatus) -> DeliveryReport {
+        DeliveryReport {
+            message_id: provider_id.to_string(),
+            status,
+            reason: Some("550 User unknown".to_string()),
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let config = config();
+        assert_eq!(config.backoff(1), Duration::from_secs(30));
+        assert_eq!(config.backoff(2), Duration::from_secs(60));
+        assert_eq!(config.backoff(3), Duration::from_secs(120));
+        assert_eq!(config.backoff(8), Duration::from_secs(3600));
+        assert_eq!(config.backoff(200), Duration::from_secs(3600));
+    }
+
+    #[tokio::test]
+    async fn test_retry_then_dead_letter_and_requeue() {
+        let provider = Arc::new(Provider::default());
+        provider.failures.store(3, Ordering::SeqCst);
+        let outbox =
+            Outbox::new(Arc::new(MemoryOutboxStore::new()), provider.clone()).with_config(config());
+
+        let message = outbox
+            .push(
+                OutboxMessage::email("schedule_change", "a@vaya.my", "Change", "...")
+                    .with_user("u1"),
+            )
+            .unwrap()
+            .unwrap();
+        let t0 = message.next_attempt_at;
+
+        let report = outbox.drain_at(t0).await.unwrap();
+        assert_eq!(report.retried, 1);
+        let stored = outbox.get(&message.id).unwrap().unwrap();
+        assert_eq!(stored.attempts, 1);
+        assert_eq!(stored.next_attempt_at, t0.add_secs(30));
+        assert_eq!(
+            stored.last_error.as_deref(),
+            Some("Notification failed: provider down")
+        );
+
+        // Not due yet
+        assert_eq!(
+            outbox.drain_at(t0.add_secs(29)).await.unwrap(),
+            DrainReport::default()
+        );
+
+        assert_eq!(outbox.drain_at(t0.add_secs(30)).await.unwrap().retried, 1);
+        assert_eq!(
+            outbox.get(&message.id).unwrap().unwrap().next_attempt_at,
+            t0.add_secs(90)
+        );
+        let report = outbox.drain_at(t0.add_secs(90)).await.unwrap();
+        assert_eq!(report.dead_lettered, 1);
+
+        let dead = outbox.dead_letters().unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].status, NotificationStatus::Failed);
+        assert!(outbox.drain_at(t0.add_secs(10_000)).await.unwrap() == DrainReport::default());
+
+        // The provider recovers; an operator requeues the message
+        outbox.requeue(&message.id).unwrap();
+        assert!(outbox.dead_letters().unwrap().is_empty());
+        assert_eq!(outbox.drain().await.unwrap().sent, 1);
+        let sent = outbox.get(&message.id).unwrap().unwrap();
+        assert_eq!(sent.status, NotificationStatus::Sent);
+        assert_eq!(sent.provider_id.as_deref(), Some("msg-1"));
+        assert!(matches!(
+            outbox.requeue(&message.id),
+            Err(CoreError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_webhooks_update_user_history() {
+        let provider = Arc::new(Provider::default());
+        let outbox = Outbox::new(Arc::new(MemoryOutboxStore::new()), provider.clone());
+
+        let first = outbox
+            .push(
+                OutboxMessage::email("booking_confirmation", "a@vaya.my", "Booked", "...")
+                    .with_user("u1"),
+            )
+            .unwrap()
+            .unwrap();
+        let second = outbox
+            .push(OutboxMessage::sms("flight_reminder", "+60123456789", "Boarding").with_user("u1"))
+            .unwrap()
+            .unwrap();
+        outbox
+            .push(OutboxMessage::email("welcome", "b@vaya.my", "Hi", "...").with_user("u2"))
+            .unwrap();
+        assert_eq!(outbox.drain().await.unwrap().sent, 3);
+
+        let delivered = outbox
+            .record_delivery(&report("msg-1", NotificationStatus::Delivered))
+            .unwrap()
+            .unwrap();
+        assert_eq!(delivered.id, first.id);
+        assert_eq!(delivered.status, NotificationStatus::Delivered);
+        assert!(delivered.last_error.is_none());
+
+        // A late "sent" never moves a delivered message back
+        outbox
+            .record_delivery(&report("msg-1", NotificationStatus::Sent))
+            .unwrap();
+        outbox
+            .record_delivery(&report("msg-2", NotificationStatus::Bounced))
+            .unwrap();
+        assert!(outbox
+            .record_delivery(&report("msg-99", NotificationStatus::Delivered))
+            .unwrap()
+            .is_none());
+
+        let history = outbox.history("u1").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].id, second.id);
+        assert_eq!(history[0].status, NotificationStatus::Bounced);
+        assert_eq!(history[0].last_error.as_deref(), Some("550 User unknown"));
+        assert_eq!(history[1].status, NotificationStatus::Delivered);
+    }
+
+    #[tokio::test]
+    async fn test_db_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || Arc::new(VayaDb::open(DbConfig::new(dir.path())).unwrap());
+        let provider = Arc::new(Provider::default());
+        provider.failures.store(1, Ordering::SeqCst);
+
+        let id = {
+            let outbox = Outbox::new(Arc::new(DbOutboxStore::new(open())), provider.clone())
+                .with_config(config());
+            // Queued through the Notifier trait, as other services do
+            outbox
+                .enqueue_email("schedule_change", "a@vaya.my", "Change", "...")
+                .await
+                .unwrap();
+            let message = outbox
+                .push(OutboxMessage::sms("gate_change", "+60123456789", "Gate B4").with_user("u1"))
+                .unwrap()
+                .unwrap();
+            // The first send fails and the second goes out
+            let report = outbox.drain().await.unwrap();
+            assert_eq!((report.sent, report.retried), (1, 1));
+            message.id
+        };
+
+        let store = Arc::new(DbOutboxStore::new(open()));
+        let queued = store.queued().unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].attempts, 1);
+        assert_eq!(queued[0].to, "a@vaya.my");
+
+        let outbox = Outbox::new(store.clone(), provider.clone());
+        let updated = outbox
+            .record_delivery(&report("msg-1", NotificationStatus::Delivered))
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.id, id);
+        let history = outbox.history("u1").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, NotificationStatus::Delivered);
+        assert_eq!(history[0].channel, OutboxChannel::Sms);
+    }
+
+    #[tokio::test]
+    async fn test_push_applies_preferences() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(VayaDb::open(DbConfig::new(dir.path())).unwrap());
+        let repository = Arc::new(crate::repository::DbPreferenceRepository::new(db));
+        let mut prefs = NotificationPreferences::for_user("u1")
+            .with_utc_offset(480)
+            .with_quiet_hours(crate::preferences::QuietHours::new(22 * 60, 7 * 60).unwrap());
+        prefs.set(
+            NotificationCategory::Bookings,
+            NotificationChannel::Sms,
+            false,
+        );
+        prefs.version = 1;
+        repository.insert(&prefs).unwrap();
+
+        let outbox = Outbox::new(
+            Arc::new(MemoryOutboxStore::new()),
+            Arc::new(Provider::default()),
+        )
+        .with_preferences(repository);
+
+        // 2030-06-03 15:00 UTC, 23:00 in Kuala Lumpur
+        let night = Timestamp::from_unix(1_906_729_200);
+        let at = |mut message: OutboxMessage| {
+            message.next_attempt_at = night;
+            message.with_user("u1")
+        };
+
+        let suppressed = outbox
+            .push(at(OutboxMessage::sms(
+                "payment_reminder",
+                "+60123456789",
+                "Pay",
+            )))
+            .unwrap();
+        assert!(suppressed.is_none());
+
+        let held = outbox
+            .push(at(OutboxMessage::email(
+                "booking_confirmation",
+                "a@vaya.my",
+                "Booked",
+                "...",
+            )))
+            .unwrap()
+            .unwrap();
+        // Held until 07:00 local
+        assert_eq!(held.next_attempt_at, night.add_hours(8));
+
+        let urgent = outbox
+            .push(at(OutboxMessage::sms(
+                "gate_change",
+                "+60123456789",
+                "Gate B4",
+            )))
+            .unwrap()
+            .unwrap();
+        assert_eq!(urgent.next_attempt_at, night);
+
+        // Users without saved preferences get the defaults: no SMS for pools
+        let other =
+            OutboxMessage::sms("pool_join_reminder", "+60123456789", "Join").with_user("u2");
+        assert!(outbox.push(other).unwrap().is_none());
+        assert_eq!(outbox.history("u1").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,523 @@
+//! Scheduled pool lifecycle
+//!
+//! Pools only move on when something touches them, so nothing expired a
+//! pool whose deadline passed while nobody was looking. [`PoolLifecycleWorker`]
+//! sweeps open pools on an interval and:
+//!
+//! - reminds members a configurable number of hours before the join or
+//!   contribution deadline (once per deadline),
+//! - expires pools past their deadline,
+//! - books Locked pools through a [`PoolBooker`], completing them or, on a
+//!   permanent booking failure, failing them,
+//! - sends the contribution refunds of pools in Refunding (see
+//!   [`settle_pool`]).
+//!
+//! Status changes go through [`PoolService`], so they are published on the
+//! event bus like any other.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use vaya_common::{metrics, Timestamp};
+use vaya_payment::PaymentProvider;
+use vaya_pool::{Pool, PoolDeadline, PoolService, PoolStatus, PoolStore};
+
+use crate::error::CoreResult;
+use crate::notify::Notifier;
+use crate::pool_settlement::settle_pool;
+
+/// Actor recorded on status changes made by the worker
+pub const LIFECYCLE_ACTOR: &str = "SYSTEM:pool-lifecycle";
+
+/// Books the flights of a fully funded pool
+#[async_trait]
+pub trait PoolBooker: Send + Sync {
+    /// Book the pool's flights, returning the booking reference
+    ///
+    /// Retryable errors leave the pool Locked for the next sweep; any other
+    /// error fails the pool and refunds its members.
+    async fn book_pool(&self, pool: &Pool) -> CoreResult<String>;
+}
+
+/// Looks up where to reach pool members
+pub trait MemberDirectory: Send + Sync {
+    /// Email address of a user, or `None` if there is no such user
+    fn member_email(&self, user_id: &str) -> Option<String>;
+}
+
+/// Pool lifecycle settings
+#[derive(Debug, Clone)]
+pub struct PoolLifecycleConfig {
+    /// Hours before a deadline that members are reminded
+    pub reminder_hours: i64,
+    /// Maximum pools handled per sweep
+    pub batch_size: usize,
+    /// Time between sweeps when run in the background
+    pub interval: Duration,
+}
+
+impl Default for PoolLifecycleConfig {
+    fn default() -> Self {
+        Self {
+            reminder_hours: 24,
+            batch_size: 200,
+            interval: Duration::from_secs(300),
+        }
+    }
+}
+
+impl PoolLifecycleConfig {
+    /// Set how many hours before a deadline members are reminded
+    pub fn with_reminder_hours(mut self, hours: i64) -> Self {
+        self.reminder_hours = hours;
+        self
+    }
+}
+
+/// Totals from one or more sweeps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolSweepReport {
+    /// Open pools looked at
+    pub examined: usize,
+    /// Deadline reminders sent (one per member)
+    pub reminded: usize,
+    /// Pools expired
+    pub expired: usize,
+    /// Locked pools booked and completed
+    pub booked: usize,
+    /// Locked pools failed after a permanent booking error
+    pub booking_failed: usize,
+    /// Contribution refunds completed
+    pub refunded: usize,
+    /// Pools skipped after a storage, booking or payment error
+    pub errors: usize,
+}
+
+impl PoolSweepReport {
+    fn add(&mut self, other: &PoolSweepReport) {
+        self.examined += other.examined;
+        self.reminded += other.reminded;
+        self.expired += other.expired;
+        self.booked += other.booked;
+        self.booking_failed += other.booking_failed;
+        self.refunded += other.refunded;
+        self.errors += other.errors;
+    }
+}
+
+/// Expires, reminds, books and settles pools in the background
+pub struct PoolLifecycleWorker<S: PoolStore> {
+    pools: Arc<PoolService<S>>,
+    booker: Option<Arc<dyn PoolBooker>>,
+    payments: Option<Arc<dyn PaymentProvider>>,
+    notifier: Option<(Arc<dyn Notifier>, Arc<dyn MemberDirectory>)>,
+    config: PoolLifecycleConfig,
+    totals: RwLock<PoolSweepReport>,
+}
+
+impl<S: PoolStore + 'static> PoolLifecycleWorker<S> {
+    /// Create a worker over a pool service
+    pub fn new(pools: Arc<PoolService<S>>) -> Self {
+        Self {
+            pools,
+            booker: None,
+            payments: None,
+            notifier: None,
+            config: PoolLifecycleConfig::default(),
+            totals: RwLock::new(PoolSweepReport::default()),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: PoolLifecycleConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Book Locked pools
+    pub fn with_booker(mut self, booker: Arc<dyn PoolBooker>) -> Self {
+        self.booker = Some(booker);
+        self
+    }
+
+    /// Refund contributions of pools that close without a booking
+    pub fn with_payments(mut self, payments: Arc<dyn PaymentProvider>) -> Self {
+        self.payments = Some(payments);
+        self
+    }
+
+    /// Send deadline reminders
+    pub fn with_notifier(
+        mut self,
+        notifier: Arc<dyn Notifier>,
+        members: Arc<dyn MemberDirectory>,
+    ) -> Self {
+        self.notifier = Some((notifier, members));
+        self
+    }
+
+    /// Totals since the worker started, for the admin overview
+    pub fn stats(&self) -> PoolSweepReport {
+        self.totals
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Sweep open pools now
+    pub async fn sweep(&self) -> PoolSweepReport {
+        self.sweep_at(Timestamp::now().as_unix()).await
+    }
+
+    /// Sweep open pools as of `now` (Unix timestamp)
+    pub async fn sweep_at(&self, now: i64) -> PoolSweepReport {
+        let mut report = PoolSweepReport::default();
+
+        let open = match self.pools.store().open_pools(self.config.batch_size) {
+            Ok(open) => open,
+            Err(e) => {
+                warn!("Failed to list open pools: {}", e);
+                report.errors += 1;
+                return report;
+            }
+        };
+
+        for pool in open {
+            report.examined += 1;
+            let pool_id = pool.id.clone();
+            if let Err(e) = self.process(pool, now, &mut report).await {
+                warn!("Failed to sweep pool {}: {}", pool_id, e);
+                report.errors += 1;
+            }
+        }
+
+        if report.expired + report.booked + report.booking_failed > 0 {
+            info!(
+                "Pool sweep expired {}, booked {} and failed {} of {} open pools",
+                report.expired, report.booked, report.booking_failed, report.examined
+            );
+        }
+        self.totals
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .add(&report);
+        report
+    }
+
+    /// Sweep every `interval` until the task is aborted
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+            loop {
+                ticker.tick().await;
+                self.sweep().await;
+            }
+        })
+    }
+
+    async fn process(
+        &self,
+        mut pool: Pool,
+        now: i64,
+        report: &mut PoolSweepReport,
+    ) -> CoreResult<()> {
+        if let Some((deadline, at)) = pool.next_deadline() {
+            if now > at {
+                pool = self.pools.update(&pool.id, |p| {
+                    p.check_expiry_at(now);
+                    Ok(())
+                })?;
+                if matches!(pool.status, PoolStatus::Expired | PoolStatus::Refunding) {
+                    report.expired += 1;
+                    count("expired");
+                }
+            } else if now >= at - self.config.reminder_hours * 3600
+                && !pool.reminders_sent.contains(&deadline)
+            {
+                report.reminded += self.remind(&pool, deadline, at - now).await;
+                pool = self.pools.update(&pool.id, |p| {
+                    p.record_reminder(deadline);
+                    Ok(())
+                })?;
+                count("reminded");
+            }
+        }
+
+        if pool.status == PoolStatus::Locked {
+            if let Some(booker) = &self.booker {
+                pool = match booker.book_pool(&pool).await {
+                    Ok(booking_ref) => {
+                        report.booked += 1;
+                        count("booked");
+                        info!("Pool {} booked as {}", pool.id, booking_ref);
+                        self.pools
+                            .update(&pool.id, |p| p.complete(&booking_ref, LIFECYCLE_ACTOR))?
+                    }
+                    Err(e) if e.is_retryable() => {
+                        warn!("Booking pool {} failed, retrying: {}", pool.id, e);
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        report.booking_failed += 1;
+                        count("booking_failed");
+                        warn!("Booking pool {} failed: {}", pool.id, e);
+                        let reason = format!("Booking failed: {}", e);
+                        self.pools
+                            .update(&pool.id, |p| p.fail(&reason, LIFECYCLE_ACTOR))?
+                    }
+                };
+            }
+        }
+
+        if pool.status == PoolStatus::Refunding {
+            if let Some(payments) = &self.payments {
+                let settled = settle_pool(payments.as_ref(), &self.pools, &pool.id).await?;
+                report.refunded += settled.refunded;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remind the members a deadline applies to; returns how many were sent
+    async fn remind(&self, pool: &Pool, deadline: PoolDeadline, remaining_secs: i64) -> usize {
+        let Some((notifier, members)) = &self.notifier else {
+            return 0;
+        };
+        let hours = (remaining_secs / 3600).max(1);
+        let (subject, body) = match deadline {
+            PoolDeadline::Join => (
+                format!("{} needs more travelers", pool.name),
+                format!(
+                    "Your pool {} has {} of the {} travelers it needs and closes in about {} \
+                     hour(s).\n\nShare it with friends to reach the group price.",
+                    pool.name,
+                    pool.total_spots(),
+                    pool.min_members,
+                    hours
+                ),
+            ),
+            PoolDeadline::Contribution => (
+                format!("Pay your share of {}", pool.name),
+                format!(
+                    "Contributions for {} close in about {} hour(s). Pay your share to keep \
+                     your place and the group price.",
+                    pool.name, hours
+                ),
+            ),
+        };
+        let recipients = pool.members.iter().filter(|m| match deadline {
+            PoolDeadline::Join => true,
+            PoolDeadline::Contribution => !m.has_contributed(),
+        });
+
+        let mut sent = 0;
+        for member in recipients {
+            let Some(email) = members.member_email(&member.user_id) else {
+                continue;
+            };
+            let event = format!("pool_{}_reminder", deadline.as_str());
+            match notifier
+                .enqueue_email(&event, &email, &subject, &body)
+                .await
+            {
+                Ok(()) => sent += 1,
+                Err(e) => warn!(
+                    "Failed to send {} reminder for pool {}: {}",
+                    deadline.as_str(),
+                    pool.id,
+                    e
+                ),
+            }
+        }
+        sent
+    }
+}
+
+fn count(outcome: &str) {
+    metrics::global()
+        .counter("vaya_core_pool_lifecycle_total", &[("outcome", outcome)])
+        .inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use vaya_common::{CurrencyCode, IataCode, MinorUnits};
+    use vaya_payment::mock::MockPaymentProvider;
+    use vaya_pool::{MemoryPoolStore, PoolRoute, TieredPricing};
+
+    use crate::error::CoreError;
+
+    #[derive(Default)]
+    struct Fakes {
+        emails: Mutex<Vec<String>>,
+        payments: Arc<MockPaymentProvider>,
+        booking_error: Mutex<Option<CoreError>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Fakes {
+        async fn send_email(&self, to: &str, _subject: &str, _body: &str) -> CoreResult<()> {
+            self.emails.lock().unwrap().push(to.to_string());
+            Ok(())
+        }
+
+        async fn send_sms(&self, _to: &str, _body: &str) -> CoreResult<()> {
+            Ok(())
+        }
+    }
+
+    impl MemberDirectory for Fakes {
+        fn member_email(&self, user_id: &str) -> Option<String> {
+            Some(format!("{}@example.com", user_id))
+        }
+    }
+
+    #[async_trait]
+    impl PoolBooker for Fakes {
+        async fn book_pool(&self, pool: &Pool) -> CoreResult<String> {
+            match self.booking_error.lock().unwrap().take() {
+                Some(e) => Err(e),
+                None => Ok(format!("BKG-{}", pool.id)),
+            }
+        }
+    }
+
+    fn pool(name: &str) -> Pool {
+        let route = PoolRoute::one_way(
+            IataCode::KUL,
+            IataCode::NRT,
+            time::Date::from_calendar_date(2025, time::Month::June, 1).unwrap(),
+        );
+        let pricing =
+            TieredPricing::with_standard_tiers(MinorUnits::new(150_000), CurrencyCode::MYR)
+                .unwrap();
+        let mut pool = Pool::new(name, route, pricing, "ana", 1).unwrap();
+        pool.min_members = 2;
+        pool
+    }
+
+    fn setup() -> (
+        Arc<Fakes>,
+        Arc<PoolService<MemoryPoolStore>>,
+        PoolLifecycleWorker<MemoryPoolStore>,
+    ) {
+        let fakes = Arc::new(Fakes::default());
+        let pools = Arc::new(PoolService::new(MemoryPoolStore::new()));
+        let worker = PoolLifecycleWorker::new(pools.clone())
+            .with_booker(fakes.clone())
+            .with_payments(fakes.payments.clone())
+            .with_notifier(fakes.clone(), fakes.clone());
+        (fakes, pools, worker)
+    }
+
+    #[tokio::test]
+    async fn test_reminds_then_expires_and_refunds() {
+        let (fakes, pools, worker) = setup();
+
+        // Forming pool short of members
+        let forming = pool("Tokyo in June");
+        pools.create(&forming).unwrap();
+
+        // Active pool where only ana has paid
+        let mut active = pool("Osaka in June");
+        active.join("ben", 1).unwrap();
+        active.contribute("ana", MinorUnits::new(150_000)).unwrap();
+        active.set_payment_ref("ana", "pi_ana").unwrap();
+        active.contribution_deadline = forming.join_deadline;
+        pools.create(&active).unwrap();
+
+        let deadline = forming.join_deadline;
+        let report = worker.sweep_at(deadline - 48 * 3600).await;
+        assert_eq!((report.examined, report.reminded), (2, 0));
+
+        let report = worker.sweep_at(deadline - 3600).await;
+        assert_eq!(report.reminded, 2);
+        let mut emails = fakes.emails.lock().unwrap().clone();
+        emails.sort();
+        assert_eq!(emails, ["ana@example.com", "ben@example.com"]);
+
+        // Reminders go out once per deadline
+        assert_eq!(worker.sweep_at(deadline - 60).await.reminded, 0);
+
+        let report = worker.sweep_at(deadline + 1).await;
+        assert_eq!(report.expired, 2);
+        assert_eq!(report.refunded, 1);
+        let refunds = fakes.payments.refunds();
+        assert_eq!(refunds.len(), 1);
+        assert_eq!(refunds[0].payment_id, "pi_ana");
+        assert_eq!(
+            pools.store().load(&forming.id).unwrap().status,
+            PoolStatus::Expired
+        );
+        assert_eq!(
+            pools.store().load(&active.id).unwrap().status,
+            PoolStatus::Expired
+        );
+
+        // Closed pools drop out of the sweep
+        assert_eq!(worker.sweep_at(deadline + 60).await.examined, 0);
+        let totals = worker.stats();
+        assert_eq!((totals.reminded, totals.expired), (2, 2));
+    }
+
+    fn locked_pool(pools: &PoolService<MemoryPoolStore>) -> String {
+        let mut locked = pool("Seoul in June");
+        locked.join("ben", 1).unwrap();
+        for (user, payment) in [("ana", "pi_ana"), ("ben", "pi_ben")] {
+            locked.contribute(user, MinorUnits::new(150_000)).unwrap();
+            locked.set_payment_ref(user, payment).unwrap();
+        }
+        assert_eq!(locked.status, PoolStatus::Locked);
+        pools.create(&locked).unwrap();
+        locked.id
+    }
+
+    #[tokio::test]
+    async fn test_books_locked_pools() {
+        let (fakes, pools, worker) = setup();
+        let booked = locked_pool(&pools);
+
+        // Retryable errors leave the pool locked
+        *fakes.booking_error.lock().unwrap() = Some(CoreError::ServiceUnavailable("GDS".into()));
+        let report = worker.sweep().await;
+        assert_eq!((report.booked, report.booking_failed), (0, 0));
+        assert_eq!(
+            pools.store().load(&booked).unwrap().status,
+            PoolStatus::Locked
+        );
+
+        let report = worker.sweep().await;
+        assert_eq!(report.booked, 1);
+        let stored = pools.store().load(&booked).unwrap();
+        assert_eq!(stored.status, PoolStatus::Completed);
+        assert_eq!(stored.booking_ref, Some(format!("BKG-{}", booked)));
+        assert_eq!(stored.history.last().unwrap().actor, LIFECYCLE_ACTOR);
+
+        // A permanent failure fails the pool and refunds everyone
+        let failed = locked_pool(&pools);
+        *fakes.booking_error.lock().unwrap() =
+            Some(CoreError::FareNotAvailable("Group fare withdrawn".into()));
+        let report = worker.sweep().await;
+        assert_eq!((report.booking_failed, report.refunded), (1, 2));
+        assert_eq!(
+            pools.store().load(&failed).unwrap().status,
+            PoolStatus::Failed
+        );
+        let refunds: HashMap<_, _> = pools
+            .store()
+            .load(&failed)
+            .unwrap()
+            .refunds
+            .into_iter()
+            .map(|r| (r.user_id, r.status))
+            .collect();
+        assert_eq!(refunds.len(), 2);
+    }
+}
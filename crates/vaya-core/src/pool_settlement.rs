@@ -0,0 +1,173 @@
+//! Pool refund settlement
+//!
+//! A pool that fails, expires or is cancelled after collecting
+//! contributions sits in Refunding with a pending refund per contributed
+//! member. [`settle_pool`] sends those refunds to the payment provider and
+//! records each outcome on the pool; the pool closes as failed, expired or
+//! cancelled once the last refund completes. Refunds that fail stay
+//! pending and are retried on the next call.
+
+use tracing::{info, warn};
+
+use vaya_common::Price;
+use vaya_payment::{PaymentProvider, RefundReason, RefundRequest, RefundStatus};
+use vaya_pool::{PoolService, PoolStatus, PoolStore};
+
+use crate::error::CoreResult;
+
+/// Outcome of a settlement run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlementReport {
+    /// Refunds completed by this run
+    pub refunded: usize,
+    /// Refunds that failed and are still pending
+    pub failed: usize,
+    /// Pool status after the run
+    pub status: PoolStatus,
+}
+
+impl SettlementReport {
+    /// Check if the pool has closed
+    pub fn is_settled(&self) -> bool {
+        self.status != PoolStatus::Refunding
+    }
+}
+
+/// Send a pool's pending refunds and record the outcomes
+pub async fn settle_pool<P, S>(
+    payments: &P,
+    pools: &PoolService<S>,
+    pool_id: &str,
+) -> CoreResult<SettlementReport>
+where
+    P: PaymentProvider + ?Sized,
+    S: PoolStore,
+{
+    let pool = pools.store().load(pool_id)?;
+    let pending: Vec<_> = pool.pending_refunds().cloned().collect();
+
+    let mut refunded = 0;
+    let mut failed = 0;
+    let mut status = pool.status;
+    for refund in pending {
+        let result = match &refund.payment_ref {
+            Some(payment_id) => {
+                let request = RefundRequest {
+                    payment_id: payment_id.clone(),
+                    amount: Some(Price::new(refund.amount, refund.currency)),
+                    reason: RefundReason::BookingCancelled,
+                    idempotency_key: Some(format!("refund_pool_{}", refund.id)),
+                };
+                match payments.create_refund(&request).await {
+                    Ok(r) if matches!(r.status, RefundStatus::Failed | RefundStatus::Cancelled) => {
+                        Err(format!("Refund {} was {:?}", r.id, r.status))
+                    }
+                    Ok(r) => Ok(r.id),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            None => Err("No payment reference for contribution".to_string()),
+        };
+
+        let updated = match &result {
+            Ok(provider_ref) => {
+                refunded += 1;
+                pools.update(pool_id, |pool| {
+                    pool.complete_refund(&refund.id, Some(provider_ref.clone()))
+                })?
+            }
+            Err(error) => {
+                warn!(
+                    pool = %pool_id,
+                    refund = %refund.id,
+                    user = %refund.user_id,
+                    error = %error,
+                    "Pool contribution refund failed"
+                );
+                failed += 1;
+                pools.update(pool_id, |pool| pool.fail_refund(&refund.id, error))?
+            }
+        };
+        status = updated.status;
+    }
+
+    if refunded > 0 {
+        info!(
+            pool = %pool_id,
+            refunded,
+            failed,
+            status = status.as_str(),
+            "Refunded pool contributions"
+        );
+    }
+    Ok(SettlementReport {
+        refunded,
+        failed,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use vaya_common::{CurrencyCode, IataCode, MinorUnits};
+    use vaya_payment::mock::MockPaymentProvider;
+    use vaya_payment::PaymentError;
+    use vaya_pool::{MemoryPoolStore, Pool, PoolRoute, TieredPricing};
+
+    fn failed_pool() -> Pool {
+        let route = PoolRoute::one_way(
+            IataCode::KUL,
+            IataCode::NRT,
+            time::Date::from_calendar_date(2025, time::Month::June, 1).unwrap(),
+        );
+        let pricing =
+            TieredPricing::with_standard_tiers(MinorUnits::new(150_000), CurrencyCode::MYR)
+                .unwrap();
+        let mut pool = Pool::new("Tokyo in June", route, pricing, "ana", 1).unwrap();
+        pool.min_members = 2;
+        pool.join("ben", 1).unwrap();
+        for (user, payment) in [("ana", "pi_ana"), ("ben", "pi_ben")] {
+            pool.contribute(user, MinorUnits::new(150_000)).unwrap();
+            pool.set_payment_ref(user, payment).unwrap();
+        }
+        pool.fail("Group fare withdrawn", "SYSTEM").unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_refunds_contributions_then_closes_pool() {
+        let payments = MockPaymentProvider::new();
+        payments.fail_next(PaymentError::Timeout);
+        let pools = PoolService::new(MemoryPoolStore::new());
+        let pool = failed_pool();
+        pools.create(&pool).unwrap();
+
+        let report = settle_pool(&payments, &pools, &pool.id).await.unwrap();
+        assert_eq!((report.refunded, report.failed), (1, 1));
+        assert_eq!(report.status, PoolStatus::Refunding);
+        assert!(!report.is_settled());
+
+        // The failed refund is retried and the pool closes
+        let report = settle_pool(&payments, &pools, &pool.id).await.unwrap();
+        assert_eq!((report.refunded, report.failed), (1, 0));
+        assert_eq!(report.status, PoolStatus::Failed);
+
+        let mut ids: Vec<_> = payments
+            .refunds()
+            .into_iter()
+            .map(|r| r.payment_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["pi_ana", "pi_ben"]);
+
+        let stored = pools.store().load(&pool.id).unwrap();
+        assert!(stored.is_settled());
+        assert_eq!(stored.refunds[0].attempts, 2);
+
+        // Nothing left to send
+        let report = settle_pool(&payments, &pools, &pool.id).await.unwrap();
+        assert_eq!(report.refunded, 0);
+    }
+}
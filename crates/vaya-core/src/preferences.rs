@@ -0,0 +1,531 @@
+//! Notification preferences
+//!
+//! Each user chooses, per category of notification, the channels they want
+//! it on, plus quiet hours and how often digestible notifications (price
+//! alerts, marketing) are batched. Preferences are stored through
+//! [`PreferenceRepository`]; users who never saved any get
+//! [`NotificationPreferences::for_user`] defaults.
+//!
+//! The [`Outbox`](crate::outbox::Outbox) enforces them: a message on a
+//! channel the user turned off is not queued, and one that falls in quiet
+//! hours or belongs to a digest is held until it may go out. Account and
+//! security messages are always sent by email, and neither they nor flight
+//! updates wait for quiet hours.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use vaya_common::Timestamp;
+
+use crate::error::{CoreError, CoreResult};
+use crate::repository::PreferenceRepository;
+
+const DAY_SECS: i64 = 86_400;
+
+/// Local hour at which digests go out
+pub const DIGEST_HOUR: u16 = 9;
+
+/// What a notification is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotificationCategory {
+    /// Confirmations, payment reminders, e-tickets and refunds
+    Bookings,
+    /// Reminders, schedule and gate changes, cancellations
+    FlightUpdates,
+    /// Price alert matches
+    PriceAlerts,
+    /// Group pool deadlines and outcomes
+    Pools,
+    /// Sign-in, verification and other security messages
+    Account,
+    /// Offers and newsletters
+    Marketing,
+}
+
+impl NotificationCategory {
+    /// Every category
+    pub const ALL: [Self; 6] = [
+        Self::Bookings,
+        Self::FlightUpdates,
+        Self::PriceAlerts,
+        Self::Pools,
+        Self::Account,
+        Self::Marketing,
+    ];
+
+    /// Category name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Bookings => "bookings",
+            Self::FlightUpdates => "flight_updates",
+            Self::PriceAlerts => "price_alerts",
+            Self::Pools => "pools",
+            Self::Account => "account",
+            Self::Marketing => "marketing",
+        }
+    }
+
+    /// Parse a category name
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+
+    /// Category of a notifier event name
+    ///
+    /// Unknown events count as account messages, so they are never dropped.
+    pub fn for_event(event: &str) -> Self {
+        match event {
+            "booking_confirmation"
+            | "payment_confirmation"
+            | "payment_reminder"
+            | "e_ticket"
+            | "refund" => Self::Bookings,
+            "flight_reminder"
+            | "schedule_change"
+            | "flight_change"
+            | "flight_cancellation"
+            | "gate_change" => Self::FlightUpdates,
+            "price_alert" => Self::PriceAlerts,
+            "marketing" | "promotion" | "newsletter" => Self::Marketing,
+            e if e.starts_with("pool_") => Self::Pools,
+            _ => Self::Account,
+        }
+    }
+
+    /// Sent even inside quiet hours
+    pub fn is_urgent(&self) -> bool {
+        matches!(self, Self::FlightUpdates | Self::Account)
+    }
+
+    /// Batched according to the digest frequency
+    pub fn is_digestible(&self) -> bool {
+        matches!(self, Self::PriceAlerts | Self::Marketing)
+    }
+}
+
+/// Channel a notification is delivered on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum NotificationChannel {
+    /// Email
+    Email,
+    /// SMS
+    Sms,
+    /// WhatsApp
+    WhatsApp,
+    /// Telegram
+    Telegram,
+}
+
+impl NotificationChannel {
+    /// Every channel
+    pub const ALL: [Self; 4] = [Self::Email, Self::Sms, Self::WhatsApp, Self::Telegram];
+
+    /// Channel name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Sms => "sms",
+            Self::WhatsApp => "whatsapp",
+            Self::Telegram => "telegram",
+        }
+    }
+
+    /// Parse a channel name
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+}
+
+/// How often digestible notifications go out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DigestFrequency {
+    /// As they happen
+    #[default]
+    Immediate,
+    /// Daily at [`DIGEST_HOUR`]
+    Daily,
+    /// Mondays at [`DIGEST_HOUR`]
+    Weekly,
+}
+
+impl DigestFrequency {
+    /// Every frequency
+    pub const ALL: [Self; 3] = [Self::Immediate, Self::Daily, Self::Weekly];
+
+    /// Frequency name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// Parse a frequency name
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.as_str() == s)
+    }
+}
+
+/// Local time window in which non-urgent notifications are held
+///
+/// Times are minutes after local midnight; a window may wrap past
+/// midnight (22:00 to 07:00).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Start, inclusive
+    pub start_minute: u16,
+    /// End, exclusive
+    pub end_minute: u16,
+}
+
+impl QuietHours {
+    /// Create a window, rejecting times past midnight and empty windows
+    pub fn new(start_minute: u16, end_minute: u16) -> CoreResult<Self> {
+        if start_minute >= 1440 || end_minute >= 1440 || start_minute == end_minute {
+            return Err(CoreError::ValidationError(
+                "Quiet hours must be two different times of day".into(),
+            ));
+        }
+        Ok(Self {
+            start_minute,
+            end_minute,
+        })
+    }
+
+    /// Whether a local minute of the day falls in the window
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start_minute < self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute)
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+}
+
+/// A user's notification preferences
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationPreferences {
+    /// User the preferences belong to
+    pub user_id: String,
+    /// Enabled (category, channel) pairs
+    pub(crate) enabled: BTreeSet<(NotificationCategory, NotificationChannel)>,
+    /// Quiet hours, if any
+    pub quiet_hours: Option<QuietHours>,
+    /// User's offset from UTC, for quiet hours and digests
+    pub utc_offset_minutes: i16,
+    /// Digest frequency for price alerts and marketing
+    pub digest: DigestFrequency,
+    /// Version for compare-and-swap updates (0 until first saved)
+    pub version: u32,
+    /// Last saved at (Unix seconds)
+    pub updated_at: i64,
+}
+
+impl NotificationPreferences {
+    /// Defaults: email for everything except marketing, plus SMS for
+    /// bookings and flight updates
+    pub fn for_user(user_id: impl Into<String>) -> Self {
+        let mut enabled = BTreeSet::new();
+        for category in NotificationCategory::ALL {
+            if category != NotificationCategory::Marketing {
+                enabled.insert((category, NotificationChannel::Email));
+            }
+        }
+        enabled.insert((NotificationCategory::Bookings, NotificationChannel::Sms));
+        enabled.insert((
+            NotificationCategory::FlightUpdates,
+            NotificationChannel::Sms,
+        ));
+        Self {
+            user_id: user_id.into(),
+            enabled,
+            quiet_hours: None,
+            utc_offset_minutes: 0,
+            digest: DigestFrequency::Immediate,
+            version: 0,
+            updated_at: 0,
+        }
+    }
+
+    /// Whether the user wants a category on a channel
+    ///
+    /// Account messages always go out by email.
+    pub fn allows(&self, category: NotificationCategory, channel: NotificationChannel) -> bool {
+        (category == NotificationCategory::Account && channel == NotificationChannel::Email)
+            || self.enabled.contains(&(category, channel))
+    }
+
+    /// Turn a channel on or off for a category
+    pub fn set(
+        &mut self,
+        category: NotificationCategory,
+        channel: NotificationChannel,
+        enabled: bool,
+    ) {
+        if enabled {
+            self.enabled.insert((category, channel));
+        } else {
+            self.enabled.remove(&(category, channel));
+        }
+    }
+
+    /// Replace a category's channels
+    pub fn set_channels(
+        &mut self,
+        category: NotificationCategory,
+        channels: &[NotificationChannel],
+    ) {
+        for channel in NotificationChannel::ALL {
+            self.set(category, channel, channels.contains(&channel));
+        }
+    }
+
+    /// Channels enabled for a category
+    pub fn channels(&self, category: NotificationCategory) -> Vec<NotificationChannel> {
+        NotificationChannel::ALL
+            .into_iter()
+            .filter(|channel| self.allows(category, *channel))
+            .collect()
+    }
+
+    /// Set quiet hours
+    pub fn with_quiet_hours(mut self, quiet_hours: QuietHours) -> Self {
+        self.quiet_hours = Some(quiet_hours);
+        self
+    }
+
+    /// Set the user's UTC offset
+    pub fn with_utc_offset(mut self, minutes: i16) -> Self {
+        self.utc_offset_minutes = minutes;
+        self
+    }
+
+    /// Set the digest frequency
+    pub fn with_digest(mut self, digest: DigestFrequency) -> Self {
+        self.digest = digest;
+        self
+    }
+
+    /// Check the settings are in range
+    pub fn validate(&self) -> CoreResult<()> {
+        // UTC-12:00 to UTC+14:00
+        if !(-720..=840).contains(&self.utc_offset_minutes) {
+            return Err(CoreError::ValidationError(format!(
+                "UTC offset {} minutes is out of range",
+                self.utc_offset_minutes
+            )));
+        }
+        if let Some(quiet) = self.quiet_hours {
+            QuietHours::new(quiet.start_minute, quiet.end_minute)?;
+        }
+        Ok(())
+    }
+
+    /// Earliest time a notification in `category` created at `now` may be sent
+    pub fn send_at(&self, category: NotificationCategory, now: Timestamp) -> Timestamp {
+        let offset = i64::from(self.utc_offset_minutes) * 60;
+        let local = now.as_unix() + offset;
+        let digest_minute = i64::from(DIGEST_HOUR) * 60;
+
+        let mut at = local;
+        if category.is_digestible() {
+            at = match self.digest {
+                DigestFrequency::Immediate => local,
+                DigestFrequency::Daily => next_local_minute(local, digest_minute),
+                DigestFrequency::Weekly => {
+                    let mut at = next_local_minute(local, digest_minute);
+                    // 1970-01-01 was a Thursday
+                    while (at.div_euclid(DAY_SECS) + 3).rem_euclid(7) != 0 {
+                        at += DAY_SECS;
+                    }
+                    at
+                }
+            };
+        }
+        if let Some(quiet) = self.quiet_hours.filter(|_| !category.is_urgent()) {
+            let minute = at.rem_euclid(DAY_SECS) / 60;
+            if quiet.contains(minute as u16) {
+                at = next_local_minute(at, i64::from(quiet.end_minute));
+            }
+        }
+        Timestamp::from_unix(at - offset)
+    }
+}
+
+/// First local time at or after `local` that is `minute` minutes past midnight
+fn next_local_minute(local: i64, minute: i64) -> i64 {
+    let at = local.div_euclid(DAY_SECS) * DAY_SECS + minute * 60;
+    if at < local {
+        at + DAY_SECS
+    } else {
+        at
+    }
+}
+
+/// Reads and saves notification preferences
+pub struct PreferenceService {
+    repository: Arc<dyn PreferenceRepository>,
+}
+
+impl PreferenceService {
+    /// Create a service over a repository
+    pub fn new(repository: Arc<dyn PreferenceRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// A user's preferences, or the defaults if they never saved any
+    pub fn get(&self, user_id: &str) -> CoreResult<NotificationPreferences> {
+        Ok(self
+            .repository
+            .get(user_id)?
+            .unwrap_or_else(|| NotificationPreferences::for_user(user_id)))
+    }
+
+    /// Save preferences read at `preferences.version`
+    ///
+    /// Fails with [`CoreError::VersionConflict`] if they changed since.
+    pub fn update(
+        &self,
+        mut preferences: NotificationPreferences,
+    ) -> CoreResult<NotificationPreferences> {
+        preferences.validate()?;
+        let expected = preferences.version;
+        preferences.version = expected + 1;
+        preferences.updated_at = Timestamp::now().as_unix();
+        if expected == 0 {
+            self.repository.insert(&preferences)?;
+        } else {
+            self.repository.update(&preferences, expected)?;
+        }
+        Ok(preferences)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::DbPreferenceRepository;
+    use vaya_db::{DbConfig, VayaDb};
+
+    /// 2030-06-03 (a Monday) at `hh:mm` UTC
+    fn utc(hh: i64, mm: i64) -> Timestamp {
+        Timestamp::from_unix(1_906_675_200 + hh * 3600 + mm * 60)
+    }
+
+    #[test]
+    fn test_defaults_and_matrix() {
+        let mut prefs = NotificationPreferences::for_user("u1");
+        assert!(prefs.allows(NotificationCategory::Bookings, NotificationChannel::Sms));
+        assert!(!prefs.allows(NotificationCategory::Marketing, NotificationChannel::Email));
+        assert!(!prefs.allows(NotificationCategory::Pools, NotificationChannel::Sms));
+
+        prefs.set_channels(
+            NotificationCategory::PriceAlerts,
+            &[NotificationChannel::WhatsApp],
+        );
+        assert_eq!(
+            prefs.channels(NotificationCategory::PriceAlerts),
+            vec![NotificationChannel::WhatsApp]
+        );
+        // Account email cannot be turned off
+        prefs.set_channels(NotificationCategory::Account, &[]);
+        assert_eq!(
+            prefs.channels(NotificationCategory::Account),
+            vec![NotificationChannel::Email]
+        );
+
+        assert_eq!(
+            NotificationCategory::for_event("schedule_change"),
+            NotificationCategory::FlightUpdates
+        );
+        assert_eq!(
+            NotificationCategory::for_event("pool_join_reminder"),
+            NotificationCategory::Pools
+        );
+        assert_eq!(
+            NotificationCategory::for_event("password_reset"),
+            NotificationCategory::Account
+        );
+    }
+
+    #[test]
+    fn test_send_at_quiet_hours_and_digest() {
+        // Kuala Lumpur, quiet 22:00-07:00 local
+        let prefs = NotificationPreferences::for_user("u1")
+            .with_utc_offset(480)
+            .with_quiet_hours(QuietHours::new(22 * 60, 7 * 60).unwrap());
+
+        // 15:00 UTC is 23:00 local: held until 07:00 local (23:00 UTC)
+        let now = utc(15, 0);
+        assert_eq!(
+            prefs.send_at(NotificationCategory::Bookings, now),
+            utc(23, 0)
+        );
+        assert_eq!(prefs.send_at(NotificationCategory::FlightUpdates, now), now);
+        // 04:00 UTC is 12:00 local
+        assert_eq!(
+            prefs.send_at(NotificationCategory::Bookings, utc(4, 0)),
+            utc(4, 0)
+        );
+
+        // Daily digest at 09:00 local (01:00 UTC)
+        let daily = prefs.clone().with_digest(DigestFrequency::Daily);
+        assert_eq!(
+            daily.send_at(NotificationCategory::PriceAlerts, utc(4, 0)),
+            utc(25, 0)
+        );
+        assert_eq!(
+            daily.send_at(NotificationCategory::Bookings, utc(4, 0)),
+            utc(4, 0)
+        );
+
+        // Weekly digest: 12:00 local on Monday waits for the next Monday
+        let weekly = prefs.with_digest(DigestFrequency::Weekly);
+        assert_eq!(
+            weekly.send_at(NotificationCategory::Marketing, utc(4, 0)),
+            utc(7 * 24 + 1, 0)
+        );
+        assert_eq!(
+            weekly.send_at(NotificationCategory::Marketing, utc(0, 30)),
+            utc(1, 0)
+        );
+
+        assert!(QuietHours::new(60, 60).is_err());
+        assert!(NotificationPreferences::for_user("u1")
+            .with_utc_offset(900)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_service_saves_with_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(VayaDb::open(DbConfig::new(dir.path())).unwrap());
+        let service = PreferenceService::new(Arc::new(DbPreferenceRepository::new(db.clone())));
+
+        let mut prefs = service.get("u1").unwrap();
+        assert_eq!(prefs.version, 0);
+        prefs.set(
+            NotificationCategory::Pools,
+            NotificationChannel::Telegram,
+            true,
+        );
+        prefs.digest = DigestFrequency::Weekly;
+        prefs.quiet_hours = Some(QuietHours::new(1380, 420).unwrap());
+        let saved = service.update(prefs.clone()).unwrap();
+        assert_eq!(saved.version, 1);
+
+        // A second save based on the same read conflicts
+        assert!(matches!(
+            service.update(prefs),
+            Err(CoreError::VersionConflict { .. })
+        ));
+
+        let service = PreferenceService::new(Arc::new(DbPreferenceRepository::new(db)));
+        let loaded = service.get("u1").unwrap();
+        assert_eq!(loaded, saved);
+        assert!(loaded.allows(NotificationCategory::Pools, NotificationChannel::Telegram));
+        assert_eq!(service.update(loaded).unwrap().version, 2);
+    }
+}
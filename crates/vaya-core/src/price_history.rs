@@ -0,0 +1,828 @@
+//! Price history ingestion
+//!
+//! Every search that reaches the providers observes real prices.
+//! [`PriceHistoryService`] reduces a search's offers to the lowest
+//! per-passenger fare in each cabin and appends it to a time series in
+//! VayaDb keyed by route, departure date and cabin:
+//!
+//! - `prices:series:{origin}-{destination}:{date}:{cabin code}`: the series
+//! - `prices:route:{origin}-{destination}`: `{date}:{cabin code}` of each series
+//! - `prices:routes`: routes with at least one series
+//!
+//! Observations are bucketed by hour, keeping the lowest and highest fare
+//! seen. Buckets older than [`PriceHistoryConfig::hourly_window`] are
+//! merged into daily buckets, and buckets older than the retention period
+//! are dropped, both on write and on [`PriceHistoryService::sweep`].
+//!
+//! The series are served back as [`PriceDataPoint`]s: to the oracle as a
+//! [`PriceHistorySource`] and to [`LSTMPredictor::train`] through
+//! [`PriceHistoryService::train`]. Round-trip totals depend on the return
+//! date, so only one-way offers are recorded.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use time::{Date, OffsetDateTime, Weekday};
+use tracing::debug;
+
+use vaya_common::{CurrencyCode, IataCode, MinorUnits, Timestamp};
+use vaya_db::VayaDb;
+use vaya_oracle::{LSTMPredictor, PriceDataPoint, TrainingMetrics};
+
+use crate::error::{CoreError, CoreResult};
+use crate::oracle::{parse_date, PriceHistorySource};
+use crate::types::{CabinClass, FlightOffer, PassengerType, SearchRequest};
+
+const SERIES_PREFIX: &str = "prices:series:";
+const ROUTE_PREFIX: &str = "prices:route:";
+const ROUTES_KEY: &[u8] = b"prices:routes";
+
+const HOUR: i64 = 3600;
+const DAY: i64 = 86400;
+
+const CABINS: [CabinClass; 4] = [
+    CabinClass::Economy,
+    CabinClass::PremiumEconomy,
+    CabinClass::Business,
+    CabinClass::First,
+];
+
+/// Downsampling and retention for price series
+#[derive(Debug, Clone)]
+pub struct PriceHistoryConfig {
+    /// How long observations keep hourly resolution before merging into days
+    pub hourly_window: Duration,
+    /// How long observations are kept at all
+    pub retention: Duration,
+}
+
+impl Default for PriceHistoryConfig {
+    fn default() -> Self {
+        Self {
+            hourly_window: Duration::from_secs(7 * 86400),
+            retention: Duration::from_secs(365 * 86400),
+        }
+    }
+}
+
+/// Prices observed during one hour or day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceBucket {
+    /// Start of the bucket (unix seconds)
+    pub start: i64,
+    /// Latest observation in the bucket (unix seconds)
+    pub observed_at: i64,
+    /// Lowest fare observed
+    pub low: MinorUnits,
+    /// Highest fare observed
+    pub high: MinorUnits,
+    /// Observations merged into the bucket
+    pub count: u32,
+}
+
+impl PriceBucket {
+    fn merge(&mut self, other: &PriceBucket) {
+        self.observed_at = self.observed_at.max(other.observed_at);
+        self.low = self.low.min(other.low);
+        self.high = self.high.max(other.high);
+        self.count += other.count;
+    }
+
+    fn to_json(self) -> Value {
+        json!([
+            self.start,
+            self.observed_at,
+            self.low.as_i64(),
+            self.high.as_i64(),
+            self.count
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let fields = value.as_array()?;
+        let field = |i: usize| fields.get(i).and_then(Value::as_i64);
+        Some(Self {
+            start: field(0)?,
+            observed_at: field(1)?,
+            low: MinorUnits::new(field(2)?),
+            high: MinorUnits::new(field(3)?),
+            count: u32::try_from(field(4)?).ok()?,
+        })
+    }
+}
+
+/// Observed prices for one route, departure date and cabin, oldest first
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSeries {
+    /// Origin
+    pub origin: IataCode,
+    /// Destination
+    pub destination: IataCode,
+    /// Departure date
+    pub departure_date: Date,
+    /// Cabin
+    pub cabin: CabinClass,
+    /// Currency of every bucket
+    pub currency: CurrencyCode,
+    /// Buckets, oldest first
+    pub buckets: Vec<PriceBucket>,
+}
+
+impl PriceSeries {
+    /// The series as oracle data points, one per bucket at its lowest fare
+    pub fn data_points(&self) -> Vec<PriceDataPoint> {
+        let day_of_week = self.departure_date.weekday().number_days_from_sunday();
+        let is_weekend_departure = matches!(
+            self.departure_date.weekday(),
+            Weekday::Saturday | Weekday::Sunday
+        );
+        self.buckets
+            .iter()
+            .map(|bucket| {
+                let observed_on = OffsetDateTime::from_unix_timestamp(bucket.observed_at)
+                    .map(OffsetDateTime::date)
+                    .unwrap_or(self.departure_date);
+                PriceDataPoint {
+                    price: bucket.low,
+                    currency: self.currency,
+                    timestamp: bucket.observed_at,
+                    days_before_departure: (self.departure_date - observed_on).whole_days().max(0)
+                        as u32,
+                    day_of_week,
+                    is_weekend_departure,
+                    // No holiday calendar yet
+                    is_holiday: false,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Encode a series value
+fn encode_series(currency: CurrencyCode, buckets: &[PriceBucket]) -> Value {
+    json!({
+        "currency": currency.as_str(),
+        "buckets": buckets.iter().map(|b| b.to_json()).collect::<Vec<_>>(),
+    })
+}
+
+/// Decode a series value into its currency and buckets
+fn decode_series(key: &str, value: &Value) -> CoreResult<(CurrencyCode, Vec<PriceBucket>)> {
+    let currency = value
+        .get("currency")
+        .and_then(Value::as_str)
+        .ok_or_else(|| corrupt(key))?;
+    let buckets = value
+        .get("buckets")
+        .and_then(Value::as_array)
+        .ok_or_else(|| corrupt(key))?
+        .iter()
+        .map(PriceBucket::from_json)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| corrupt(key))?;
+    Ok((CurrencyCode::new(currency), buckets))
+}
+
+/// Merge buckets past the hourly window into days and drop expired ones
+fn downsample(buckets: &[PriceBucket], now: i64, config: &PriceHistoryConfig) -> Vec<PriceBucket> {
+    let expired_before = now.saturating_sub(config.retention.as_secs() as i64);
+    let daily_before = now.saturating_sub(config.hourly_window.as_secs() as i64);
+    let mut merged: Vec<PriceBucket> = Vec::with_capacity(buckets.len());
+    for bucket in buckets {
+        if bucket.observed_at < expired_before {
+            continue;
+        }
+        let mut bucket = *bucket;
+        if bucket.start < daily_before {
+            bucket.start -= bucket.start.rem_euclid(DAY);
+        }
+        match merged.last_mut() {
+            Some(last) if last.start == bucket.start => last.merge(&bucket),
+            _ => merged.push(bucket),
+        }
+    }
+    merged
+}
+
+/// Records observed prices and serves them as history
+pub struct PriceHistoryService {
+    db: Arc<VayaDb>,
+    config: PriceHistoryConfig,
+    /// Serializes read-modify-write updates of series and index keys
+    write_lock: Mutex<()>,
+}
+
+impl PriceHistoryService {
+    /// Create a service over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            config: PriceHistoryConfig::default(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Set downsampling and retention
+    pub fn with_config(mut self, config: PriceHistoryConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Record the offers a search returned
+    ///
+    /// Direct-only searches are skipped, since their cheapest offer isn't
+    /// the route's. Returns the number of series updated.
+    pub fn record(&self, request: &SearchRequest, offers: &[FlightOffer]) -> CoreResult<usize> {
+        self.record_at(request, offers, Timestamp::now())
+    }
+
+    /// Record the offers a search returned, as observed at `now`
+    pub fn record_at(
+        &self,
+        request: &SearchRequest,
+        offers: &[FlightOffer],
+        now: Timestamp,
+    ) -> CoreResult<usize> {
+        let departure_date = parse_date(&request.departure_date).ok_or_else(|| {
+            CoreError::InvalidSearchParams(format!(
+                "Invalid departure date: {}",
+                request.departure_date
+            ))
+        })?;
+        if request.direct_only {
+            return Ok(0);
+        }
+        let now = now.as_unix();
+
+        let mut updated = 0;
+        for cabin in CABINS {
+            let Some((fare, currency)) = offers
+                .iter()
+                .filter(|o| o.cabin_class == cabin && o.inbound.is_none() && o.onward.is_empty())
+                .filter_map(|o| Some((fare_per_passenger(o, request)?, o.price.currency)))
+                .min_by_key(|(fare, _)| *fare)
+            else {
+                continue;
+            };
+            let observation = PriceBucket {
+                start: now - now.rem_euclid(HOUR),
+                observed_at: now,
+                low: fare,
+                high: fare,
+                count: 1,
+            };
+            if self.append(
+                request.origin,
+                request.destination,
+                departure_date,
+                cabin,
+                currency,
+                observation,
+                now,
+            )? {
+                updated += 1;
+            }
+        }
+        Ok(updated)
+    }
+
+    /// Load a series
+    pub fn series(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        cabin: CabinClass,
+    ) -> CoreResult<Option<PriceSeries>> {
+        let key = series_key(origin, destination, departure_date, cabin);
+        let Some(value) = self.read(key.as_bytes())? else {
+            return Ok(None);
+        };
+        let (currency, buckets) = decode_series(&key, &value)?;
+        Ok(Some(PriceSeries {
+            origin,
+            destination,
+            departure_date,
+            cabin,
+            currency,
+            buckets,
+        }))
+    }
+
+    /// Every series recorded on a route
+    pub fn route_series(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        cabin: CabinClass,
+    ) -> CoreResult<Vec<PriceSeries>> {
+        let mut series = Vec::new();
+        for entry in self.read_list(route_key(origin, destination).as_bytes())? {
+            let Some((date, code)) = entry.split_once(':') else {
+                continue;
+            };
+            if code != cabin.code() {
+                continue;
+            }
+            let Some(date) = parse_date(date) else {
+                continue;
+            };
+            series.extend(self.series(origin, destination, date, cabin)?);
+        }
+        Ok(series)
+    }
+
+    /// Train a predictor on the longest series recorded on a route
+    pub fn train(
+        &self,
+        predictor: &mut LSTMPredictor,
+        origin: IataCode,
+        destination: IataCode,
+        cabin: CabinClass,
+    ) -> CoreResult<TrainingMetrics> {
+        let longest = self
+            .route_series(origin, destination, cabin)?
+            .into_iter()
+            .max_by_key(|s| s.buckets.len())
+            .ok_or_else(|| {
+                CoreError::PredictionUnavailable(format!(
+                    "No price history for {}-{}",
+                    origin, destination
+                ))
+            })?;
+        predictor
+            .train(&longest.data_points())
+            .map_err(|e| CoreError::PredictionUnavailable(e.to_string()))
+    }
+
+    /// Apply downsampling and retention to every stored series
+    ///
+    /// Series with no buckets left are removed. Returns the number removed.
+    pub fn sweep(&self) -> CoreResult<usize> {
+        self.sweep_at(Timestamp::now())
+    }
+
+    /// Apply downsampling and retention as of `now`
+    pub fn sweep_at(&self, now: Timestamp) -> CoreResult<usize> {
+        let _guard = self.write_lock.lock().unwrap();
+        let now = now.as_unix();
+        let mut removed = 0;
+        let mut routes = Vec::new();
+        for route in self.read_list(ROUTES_KEY)? {
+            let route_key = format!("{}{}", ROUTE_PREFIX, route);
+            let mut entries = Vec::new();
+            for entry in self.read_list(route_key.as_bytes())? {
+                let key = format!("{}{}:{}", SERIES_PREFIX, route, entry);
+                if self.sweep_series(&key, now)? {
+                    entries.push(entry);
+                } else {
+                    removed += 1;
+                }
+            }
+            self.write_list(route_key.as_bytes(), &entries)?;
+            if !entries.is_empty() {
+                routes.push(route);
+            }
+        }
+        self.write_list(ROUTES_KEY, &routes)?;
+        if removed > 0 {
+            debug!("Removed {} expired price series", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Downsample one series (lock held), returning whether it still has buckets
+    fn sweep_series(&self, key: &str, now: i64) -> CoreResult<bool> {
+        let Some(value) = self.read(key.as_bytes())? else {
+            return Ok(false);
+        };
+        let (currency, buckets) = decode_series(key, &value)?;
+        let buckets = downsample(&buckets, now, &self.config);
+        if buckets.is_empty() {
+            self.db.put(key.as_bytes(), REMOVED).map_err(db_error)?;
+            return Ok(false);
+        }
+        self.write(key.as_bytes(), &encode_series(currency, &buckets))?;
+        Ok(true)
+    }
+
+    /// Add an observation to a series, creating and indexing it if new
+    ///
+    /// Returns `false` when the observation's currency doesn't match the
+    /// series and it was skipped.
+    #[allow(clippy::too_many_arguments)]
+    fn append(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        cabin: CabinClass,
+        currency: CurrencyCode,
+        observation: PriceBucket,
+        now: i64,
+    ) -> CoreResult<bool> {
+        let _guard = self.write_lock.lock().unwrap();
+        let mut series = match self.series(origin, destination, departure_date, cabin)? {
+            Some(series) if series.currency != currency => {
+                debug!(
+                    "Skipping {} price for {}-{} on {}: series is in {}",
+                    currency, origin, destination, departure_date, series.currency
+                );
+                return Ok(false);
+            }
+            Some(series) => series,
+            None => {
+                self.index(origin, destination, departure_date, cabin)?;
+                PriceSeries {
+                    origin,
+                    destination,
+                    departure_date,
+                    cabin,
+                    currency,
+                    buckets: Vec::new(),
+                }
+            }
+        };
+
+        match series.buckets.last_mut() {
+            Some(last) if last.start == observation.start => last.merge(&observation),
+            _ => series.buckets.push(observation),
+        }
+        let buckets = downsample(&series.buckets, now, &self.config);
+
+        let key = series_key(origin, destination, departure_date, cabin);
+        self.write(key.as_bytes(), &encode_series(currency, &buckets))?;
+        Ok(true)
+    }
+
+    /// Add a new series to the route indexes (lock held)
+    fn index(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        cabin: CabinClass,
+    ) -> CoreResult<()> {
+        let route = format!("{}-{}", origin, destination);
+        let mut routes = self.read_list(ROUTES_KEY)?;
+        if !routes.contains(&route) {
+            routes.push(route);
+            self.write_list(ROUTES_KEY, &routes)?;
+        }
+
+        let key = route_key(origin, destination);
+        let entry = format!("{}:{}", departure_date, cabin.code());
+        let mut entries = self.read_list(key.as_bytes())?;
+        if !entries.contains(&entry) {
+            entries.push(entry);
+            self.write_list(key.as_bytes(), &entries)?;
+        }
+        Ok(())
+    }
+
+    fn read(&self, key: &[u8]) -> CoreResult<Option<Value>> {
+        match self.db.get(key).map_err(db_error)? {
+            Some(bytes) if !bytes.is_empty() => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| CoreError::Database(format!("Corrupt price history: {}", e))),
+            _ => Ok(None),
+        }
+    }
+
+    fn write(&self, key: &[u8], value: &Value) -> CoreResult<()> {
+        self.db
+            .put(key, value.to_string().as_bytes())
+            .map_err(db_error)
+    }
+
+    fn read_list(&self, key: &[u8]) -> CoreResult<Vec<String>> {
+        Ok(self
+            .read(key)?
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect())
+    }
+
+    fn write_list(&self, key: &[u8], items: &[String]) -> CoreResult<()> {
+        if items.is_empty() {
+            return self.db.put(key, REMOVED).map_err(db_error);
+        }
+        self.write(key, &json!(items))
+    }
+}
+
+impl PriceHistorySource for PriceHistoryService {
+    /// The economy series for the route and date
+    fn history(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+    ) -> CoreResult<Vec<PriceDataPoint>> {
+        Ok(self
+            .series(origin, destination, departure_date, CabinClass::Economy)?
+            .map(|s| s.data_points())
+            .unwrap_or_default())
+    }
+}
+
+/// Value written in place of a delete (see the repository module)
+const REMOVED: &[u8] = &[];
+
+/// Fare for one adult, from the breakdown or the total split evenly
+fn fare_per_passenger(offer: &FlightOffer, request: &SearchRequest) -> Option<MinorUnits> {
+    if let Some(adult) = offer
+        .price_breakdown
+        .iter()
+        .find(|p| p.passenger_type == PassengerType::Adult)
+    {
+        return Some(adult.price_per_passenger.amount);
+    }
+    let passengers = i64::from(request.passengers.total());
+    (passengers > 0).then(|| MinorUnits::new(offer.price.amount.as_i64() / passengers))
+}
+
+fn series_key(
+    origin: IataCode,
+    destination: IataCode,
+    departure_date: Date,
+    cabin: CabinClass,
+) -> String {
+    format!(
+        "{}{}-{}:{}:{}",
+        SERIES_PREFIX,
+        origin,
+        destination,
+        departure_date,
+        cabin.code()
+    )
+}
+
+fn route_key(origin: IataCode, destination: IataCode) -> String {
+    format!("{}{}-{}", ROUTE_PREFIX, origin, destination)
+}
+
+fn corrupt(key: &str) -> CoreError {
+    CoreError::Database(format!("Corrupt price series: {}", key))
+}
+
+fn db_error(e: vaya_db::DbError) -> CoreError {
+    CoreError::Database(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_common::Price;
+    use vaya_db::DbConfig;
+    use vaya_oracle::LSTMConfig;
+
+    use crate::types::{BaggageAllowance, FareConditions, FlightJourney, PassengerCount, TripType};
+
+    /// 2030-06-01 00:00 UTC
+    const T0: i64 = 1_906_502_400;
+
+    fn service(dir: &std::path::Path) -> PriceHistoryService {
+        PriceHistoryService::new(Arc::new(VayaDb::open(DbConfig::new(dir)).unwrap()))
+    }
+
+    fn request() -> SearchRequest {
+        SearchRequest::one_way(IataCode::KUL, IataCode::SIN, "2030-06-15")
+            .with_passengers(PassengerCount::adults(2))
+    }
+
+    fn departure() -> Date {
+        parse_date("2030-06-15").unwrap()
+    }
+
+    fn offer(total: i64, cabin: CabinClass) -> FlightOffer {
+        let journey = FlightJourney {
+            segments: vec![],
+            duration_minutes: 60,
+            stops: 0,
+        };
+        FlightOffer {
+            id: format!("offer-{}", total),
+            airlines: vec![],
+            outbound: journey,
+            inbound: None,
+            onward: vec![],
+            price: Price::myr(total),
+            retail: None,
+            price_breakdown: vec![],
+            fare_conditions: FareConditions {
+                cancellation: String::new(),
+                changes: String::new(),
+                refund: String::new(),
+                fare_family: None,
+            },
+            cabin_class: cabin,
+            seats_remaining: None,
+            refundable: false,
+            baggage_included: BaggageAllowance {
+                cabin: "7kg".into(),
+                checked: "None".into(),
+                extra_cost: None,
+            },
+            expires_at: Timestamp::from_unix(T0),
+            source: "test".into(),
+        }
+    }
+
+    fn lows(series: &PriceSeries) -> Vec<i64> {
+        series.buckets.iter().map(|b| b.low.as_i64()).collect()
+    }
+
+    #[test]
+    fn test_record_keeps_lowest_fare_per_cabin() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = service(dir.path());
+        let offers = vec![
+            offer(60_000, CabinClass::Economy),
+            offer(40_000, CabinClass::Economy),
+            offer(200_000, CabinClass::Business),
+        ];
+        let at = Timestamp::from_unix(T0);
+        assert_eq!(history.record_at(&request(), &offers, at).unwrap(), 2);
+
+        // Same hour: merged into one bucket
+        let later = Timestamp::from_unix(T0 + 600);
+        history
+            .record_at(&request(), &[offer(50_000, CabinClass::Economy)], later)
+            .unwrap();
+
+        let economy = history
+            .series(
+                IataCode::KUL,
+                IataCode::SIN,
+                departure(),
+                CabinClass::Economy,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(economy.currency, CurrencyCode::MYR);
+        assert_eq!(economy.buckets.len(), 1);
+        // Per passenger: totals are for two adults
+        assert_eq!(economy.buckets[0].low, MinorUnits::new(20_000));
+        assert_eq!(economy.buckets[0].high, MinorUnits::new(25_000));
+        assert_eq!(economy.buckets[0].count, 2);
+        assert_eq!(economy.buckets[0].observed_at, T0 + 600);
+
+        let business = history
+            .route_series(IataCode::KUL, IataCode::SIN, CabinClass::Business)
+            .unwrap();
+        assert_eq!(business.len(), 1);
+        assert_eq!(lows(&business[0]), vec![100_000]);
+
+        // Served to the oracle as the economy series
+        let points = history
+            .history(IataCode::KUL, IataCode::SIN, departure())
+            .unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].price, MinorUnits::new(20_000));
+        assert_eq!(points[0].days_before_departure, 14);
+        assert_eq!(points[0].day_of_week, 6);
+        assert!(points[0].is_weekend_departure);
+    }
+
+    #[test]
+    fn test_record_skips_unrepresentative_offers() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = service(dir.path());
+        let at = Timestamp::from_unix(T0);
+
+        let mut round_trip = offer(30_000, CabinClass::Economy);
+        round_trip.inbound = Some(round_trip.outbound.clone());
+        assert_eq!(history.record_at(&request(), &[round_trip], at).unwrap(), 0);
+
+        let direct = request().direct_only();
+        let offers = [offer(30_000, CabinClass::Economy)];
+        assert_eq!(history.record_at(&direct, &offers, at).unwrap(), 0);
+
+        // A series keeps the currency it started with
+        history.record_at(&request(), &offers, at).unwrap();
+        let mut usd = offer(10_000, CabinClass::Economy);
+        usd.price = Price::new(MinorUnits::new(10_000), CurrencyCode::USD);
+        let usd_request = SearchRequest {
+            trip_type: TripType::OneWay,
+            ..request()
+        };
+        assert_eq!(history.record_at(&usd_request, &[usd], at).unwrap(), 0);
+
+        let series = history
+            .series(
+                IataCode::KUL,
+                IataCode::SIN,
+                departure(),
+                CabinClass::Economy,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(lows(&series), vec![15_000]);
+    }
+
+    #[test]
+    fn test_downsampling_and_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = service(dir.path()).with_config(PriceHistoryConfig {
+            hourly_window: Duration::from_secs(2 * 86400),
+            retention: Duration::from_secs(10 * 86400),
+        });
+
+        // Three hours on day one, one hour on day two
+        for (offset, total) in [(0, 50_000), (3600, 44_000), (7200, 48_000), (86400, 46_000)] {
+            let at = Timestamp::from_unix(T0 + offset);
+            history
+                .record_at(&request(), &[offer(total, CabinClass::Economy)], at)
+                .unwrap();
+        }
+        let load = || {
+            history
+                .series(
+                    IataCode::KUL,
+                    IataCode::SIN,
+                    departure(),
+                    CabinClass::Economy,
+                )
+                .unwrap()
+        };
+        assert_eq!(lows(&load().unwrap()), vec![25_000, 22_000, 24_000, 23_000]);
+
+        // Four days later day one is past the hourly window
+        let at = Timestamp::from_unix(T0 + 4 * 86400);
+        history
+            .record_at(&request(), &[offer(52_000, CabinClass::Economy)], at)
+            .unwrap();
+        let series = load().unwrap();
+        assert_eq!(lows(&series), vec![22_000, 23_000, 26_000]);
+        assert_eq!(series.buckets[0].start, T0);
+        assert_eq!(series.buckets[0].high, MinorUnits::new(25_000));
+        assert_eq!(series.buckets[0].count, 3);
+
+        // Nothing has expired yet
+        assert_eq!(
+            history
+                .sweep_at(Timestamp::from_unix(T0 + 5 * 86400))
+                .unwrap(),
+            0
+        );
+        assert_eq!(load().unwrap().buckets.len(), 3);
+
+        // Past retention the series and its index entries are removed
+        assert_eq!(
+            history
+                .sweep_at(Timestamp::from_unix(T0 + 30 * 86400))
+                .unwrap(),
+            1
+        );
+        assert!(load().is_none());
+        assert!(history
+            .route_series(IataCode::KUL, IataCode::SIN, CabinClass::Economy)
+            .unwrap()
+            .is_empty());
+        assert!(history.read_list(ROUTES_KEY).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_train_on_recorded_series() {
+        let dir = tempfile::tempdir().unwrap();
+        let history = service(dir.path());
+        let mut predictor = LSTMPredictor::with_config(LSTMConfig {
+            hidden_size: 4,
+            num_layers: 1,
+            sequence_length: 4,
+            min_samples: 4,
+            ..Default::default()
+        });
+        assert!(matches!(
+            history.train(
+                &mut predictor,
+                IataCode::KUL,
+                IataCode::SIN,
+                CabinClass::Economy
+            ),
+            Err(CoreError::PredictionUnavailable(_))
+        ));
+
+        for hour in 0..12 {
+            let at = Timestamp::from_unix(T0 + hour * 3600);
+            let total = 40_000 + (hour % 4) * 2_000;
+            history
+                .record_at(&request(), &[offer(total, CabinClass::Economy)], at)
+                .unwrap();
+        }
+        let metrics = history
+            .train(
+                &mut predictor,
+                IataCode::KUL,
+                IataCode::SIN,
+                CabinClass::Economy,
+            )
+            .unwrap();
+        assert_eq!(metrics.samples_used, 12);
+        assert!(predictor.is_trained());
+    }
+}
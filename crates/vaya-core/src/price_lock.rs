@@ -0,0 +1,669 @@
+//! Price lock fee product
+//!
+//! A user pays a small fee to hold a fare for a fixed window. The fare is
+//! held through the [`FareHold`] service and the fee is charged through the
+//! payment provider. Booking within the window credits the fee against the
+//! fare and guarantees the locked price; otherwise the fee is forfeited or
+//! refunded according to [`UnusedLockFee`]. Every money movement is
+//! recorded as a [`LedgerEntry`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use vaya_common::{MinorUnits, Price, Timestamp, Uuid};
+use vaya_payment::{PaymentProvider, PaymentRequest, PaymentStatus, RefundReason, RefundRequest};
+
+use crate::error::{CoreError, CoreResult};
+use crate::types::Booking;
+
+/// Holds fares with the airline or GDS for the lock window
+#[async_trait]
+pub trait FareHold: Send + Sync {
+    /// Hold the fare for an offer until the given time, returning a hold ID
+    async fn hold_fare(&self, offer_id: &str, until: Timestamp) -> CoreResult<String>;
+
+    /// Release a hold
+    async fn release_hold(&self, hold_id: &str) -> CoreResult<()>;
+}
+
+/// What happens to the fee when a lock is not used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnusedLockFee {
+    /// Keep the whole fee
+    Forfeit,
+    /// Refund the whole fee
+    Refund,
+    /// Refund this percentage of the fee and keep the rest
+    RefundPercent(u8),
+}
+
+/// Price lock product terms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceLockPolicy {
+    /// Fee in basis points of the locked fare (100 = 1%)
+    pub fee_bps: u32,
+    /// Smallest fee, in minor units
+    pub min_fee_minor: i64,
+    /// Largest fee, in minor units
+    pub max_fee_minor: i64,
+    /// How long the price is held
+    pub window_hours: u32,
+    /// Fee handling for locks that expire or are cancelled
+    pub unused: UnusedLockFee,
+}
+
+impl Default for PriceLockPolicy {
+    fn default() -> Self {
+        Self {
+            fee_bps: 200,
+            min_fee_minor: 1_000,
+            max_fee_minor: 5_000,
+            window_hours: 72,
+            unused: UnusedLockFee::Forfeit,
+        }
+    }
+}
+
+impl PriceLockPolicy {
+    /// Fee for locking a fare
+    pub fn fee_for(&self, fare: &Price) -> Price {
+        let by_pct = fare.amount.as_i64().saturating_mul(self.fee_bps as i64) / 10_000;
+        let fee = by_pct.clamp(self.min_fee_minor, self.max_fee_minor);
+        Price::new(MinorUnits::new(fee), fare.currency)
+    }
+
+    /// Part of the fee refunded when a lock goes unused
+    fn unused_refund(&self, fee: &Price) -> Price {
+        let refund = match self.unused {
+            UnusedLockFee::Forfeit => 0,
+            UnusedLockFee::Refund => fee.amount.as_i64(),
+            UnusedLockFee::RefundPercent(pct) => fee.amount.as_i64() * pct.min(100) as i64 / 100,
+        };
+        Price::new(MinorUnits::new(refund), fee.currency)
+    }
+}
+
+/// Price lock state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceLockStatus {
+    /// Fare is held and the lock can be used
+    Active,
+    /// Used for a booking; the fee was credited
+    Applied,
+    /// Expired or cancelled; the fee was kept
+    Forfeited,
+    /// Expired or cancelled; some or all of the fee was refunded
+    Refunded,
+}
+
+impl PriceLockStatus {
+    /// Get status code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceLockStatus::Active => "active",
+            PriceLockStatus::Applied => "applied",
+            PriceLockStatus::Forfeited => "forfeited",
+            PriceLockStatus::Refunded => "refunded",
+        }
+    }
+}
+
+/// A held fare
+#[derive(Debug, Clone)]
+pub struct PriceLock {
+    /// Lock ID
+    pub id: String,
+    /// Owner
+    pub user_id: String,
+    /// Offer the price applies to
+    pub offer_id: String,
+    /// Guaranteed fare
+    pub locked_price: Price,
+    /// Fee charged
+    pub fee: Price,
+    /// Fare hold reference
+    pub hold_id: String,
+    /// Fee payment
+    pub payment_id: String,
+    /// Status
+    pub status: PriceLockStatus,
+    /// Booking the lock was used for
+    pub booking_id: Option<String>,
+    /// When the lock was bought
+    pub created_at: Timestamp,
+    /// When the lock expires
+    pub expires_at: Timestamp,
+}
+
+impl PriceLock {
+    /// Whether the lock can still be used
+    pub fn is_usable(&self) -> bool {
+        self.status == PriceLockStatus::Active && !self.expires_at.is_past()
+    }
+}
+
+/// A request to lock a fare
+#[derive(Debug, Clone)]
+pub struct PriceLockRequest {
+    /// Buyer
+    pub user_id: String,
+    /// Buyer email for the fee receipt
+    pub email: String,
+    /// Offer to lock
+    pub offer_id: String,
+    /// Fare shown to the user
+    pub price: Price,
+    /// Redirect after payment authentication
+    pub return_url: Option<String>,
+}
+
+/// Price lock details recorded on a booking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedPriceLock {
+    /// Lock ID
+    pub lock_id: String,
+    /// Guaranteed fare
+    pub locked_price: Price,
+    /// Fee credited against the fare
+    pub fee_credit: Price,
+    /// Amount left to pay
+    pub amount_due: Price,
+}
+
+/// Kind of ledger entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerEntryKind {
+    /// Fee collected
+    FeeCharged,
+    /// Fee credited against a booking
+    FeeCredited,
+    /// Fee kept after the lock went unused
+    FeeForfeited,
+    /// Fee returned after the lock went unused
+    FeeRefunded,
+}
+
+impl LedgerEntryKind {
+    /// Get entry kind code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LedgerEntryKind::FeeCharged => "fee_charged",
+            LedgerEntryKind::FeeCredited => "fee_credited",
+            LedgerEntryKind::FeeForfeited => "fee_forfeited",
+            LedgerEntryKind::FeeRefunded => "fee_refunded",
+        }
+    }
+}
+
+/// A money movement for a price lock
+#[derive(Debug, Clone)]
+pub struct LedgerEntry {
+    /// Entry ID
+    pub id: String,
+    /// Lock the entry belongs to
+    pub lock_id: String,
+    /// Kind
+    pub kind: LedgerEntryKind,
+    /// Amount
+    pub amount: Price,
+    /// Payment, refund, or booking reference
+    pub reference: Option<String>,
+    /// When the entry was recorded
+    pub recorded_at: Timestamp,
+}
+
+/// Sells and settles price locks
+pub struct PriceLockService<P: PaymentProvider + Send + Sync> {
+    payment: Arc<P>,
+    holds: Arc<dyn FareHold>,
+    policy: PriceLockPolicy,
+    locks: RwLock<HashMap<String, PriceLock>>,
+    ledger: RwLock<Vec<LedgerEntry>>,
+}
+
+impl<P: PaymentProvider + Send + Sync> PriceLockService<P> {
+    /// Create a new price lock service
+    pub fn new(payment: Arc<P>, holds: Arc<dyn FareHold>) -> Self {
+        Self {
+            payment,
+            holds,
+            policy: PriceLockPolicy::default(),
+            locks: RwLock::new(HashMap::new()),
+            ledger: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Set the product terms
+    pub fn with_policy(mut self, policy: PriceLockPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Get the product terms
+    pub fn policy(&self) -> &PriceLockPolicy {
+        &self.policy
+    }
+
+    /// Hold a fare and charge the lock fee
+    ///
+    /// The hold is released again if the fee cannot be collected.
+    pub async fn lock(&self, request: PriceLockRequest) -> CoreResult<PriceLock> {
+        let fee = self.policy.fee_for(&request.price);
+        let lock_id = Uuid::new_v4().to_string();
+        let expires_at = Timestamp::now().add_hours(self.policy.window_hours as i64);
+
+        let hold_id = self.holds.hold_fare(&request.offer_id, expires_at).await?;
+
+        let mut payment_request = PaymentRequest::new(fee, &lock_id, &request.email)
+            .with_description(format!(
+                "Price lock for {} ({} hours)",
+                request.price.format(),
+                self.policy.window_hours
+            ))
+            .with_idempotency_key(format!("price_lock_{}", lock_id))
+            .with_metadata("price_lock_id", &lock_id)
+            .with_metadata("offer_id", &request.offer_id);
+        if let Some(url) = &request.return_url {
+            payment_request = payment_request.with_return_url(url);
+        }
+
+        let charged = match self.payment.create_payment(&payment_request).await {
+            Ok(intent) if intent.status == PaymentStatus::Succeeded => Ok(intent),
+            Ok(intent) => Err(CoreError::PaymentFailed(
+                intent
+                    .error_message
+                    .unwrap_or_else(|| "Price lock fee was not collected".to_string()),
+            )),
+            Err(e) => Err(CoreError::PaymentFailed(e.to_string())),
+        };
+        let intent = match charged {
+            Ok(intent) => intent,
+            Err(e) => {
+                if let Err(release) = self.holds.release_hold(&hold_id).await {
+                    warn!("Failed to release fare hold {}: {}", hold_id, release);
+                }
+                return Err(e);
+            }
+        };
+
+        let lock = PriceLock {
+            id: lock_id,
+            user_id: request.user_id,
+            offer_id: request.offer_id,
+            locked_price: request.price,
+            fee,
+            hold_id,
+            payment_id: intent.id.clone(),
+            status: PriceLockStatus::Active,
+            booking_id: None,
+            created_at: Timestamp::now(),
+            expires_at,
+        };
+        self.record(&lock.id, LedgerEntryKind::FeeCharged, fee, Some(intent.id));
+        info!(
+            "Price lock {} on offer {} at {} (fee {})",
+            lock.id,
+            lock.offer_id,
+            lock.locked_price.format(),
+            fee.format()
+        );
+
+        self.locks
+            .write()
+            .unwrap()
+            .insert(lock.id.clone(), lock.clone());
+        Ok(lock)
+    }
+
+    /// Use a lock for a booking
+    ///
+    /// The user pays the lower of the locked and current fare, less the
+    /// lock fee.
+    pub async fn redeem(
+        &self,
+        lock_id: &str,
+        user_id: &str,
+        offer_id: &str,
+        booking_id: &str,
+        current: &Price,
+    ) -> CoreResult<AppliedPriceLock> {
+        let lock = {
+            let mut locks = self.locks.write().unwrap();
+            let lock = locks
+                .get_mut(lock_id)
+                .filter(|l| l.user_id == user_id)
+                .ok_or_else(|| CoreError::FareNotAvailable("Price lock not found".to_string()))?;
+            if !lock.is_usable() {
+                return Err(CoreError::FareNotAvailable(format!(
+                    "Price lock is {}",
+                    if lock.status == PriceLockStatus::Active {
+                        "expired"
+                    } else {
+                        lock.status.as_str()
+                    }
+                )));
+            }
+            if lock.offer_id != offer_id {
+                return Err(CoreError::ValidationError(
+                    "Price lock is for a different offer".to_string(),
+                ));
+            }
+            if lock.locked_price.currency != current.currency {
+                return Err(CoreError::ValidationError(
+                    "Price lock currency does not match the fare".to_string(),
+                ));
+            }
+            lock.status = PriceLockStatus::Applied;
+            lock.booking_id = Some(booking_id.to_string());
+            lock.clone()
+        };
+
+        let fare = lock
+            .locked_price
+            .amount
+            .as_i64()
+            .min(current.amount.as_i64());
+        let due = (fare - lock.fee.amount.as_i64()).max(0);
+        let applied = AppliedPriceLock {
+            lock_id: lock.id.clone(),
+            locked_price: lock.locked_price,
+            fee_credit: lock.fee,
+            amount_due: Price::new(MinorUnits::new(due), current.currency),
+        };
+        self.record(
+            &lock.id,
+            LedgerEntryKind::FeeCredited,
+            lock.fee,
+            Some(booking_id.to_string()),
+        );
+
+        // The booking now holds the seats
+        if let Err(e) = self.holds.release_hold(&lock.hold_id).await {
+            warn!("Failed to release fare hold {}: {}", lock.hold_id, e);
+        }
+        info!(
+            "Price lock {} applied to booking {}, {} due",
+            lock.id,
+            booking_id,
+            applied.amount_due.format()
+        );
+        Ok(applied)
+    }
+
+    /// Use a lock for a booking and record it on the booking
+    pub async fn apply_to_booking(&self, lock_id: &str, booking: &mut Booking) -> CoreResult<()> {
+        let applied = self
+            .redeem(
+                lock_id,
+                &booking.user_id,
+                &booking.flights.id,
+                &booking.id,
+                &booking.total_price,
+            )
+            .await?;
+        booking.total_price = applied.amount_due;
+        booking.price_lock = Some(applied);
+        booking.updated_at = Timestamp::now();
+        Ok(())
+    }
+
+    /// Cancel an unused lock, settling the fee per policy
+    pub async fn cancel(&self, lock_id: &str, user_id: &str) -> CoreResult<PriceLock> {
+        let lock = self
+            .get(lock_id)
+            .filter(|l| l.user_id == user_id)
+            .ok_or_else(|| CoreError::FareNotAvailable("Price lock not found".to_string()))?;
+        if lock.status != PriceLockStatus::Active {
+            return Err(CoreError::ValidationError(format!(
+                "Price lock is already {}",
+                lock.status.as_str()
+            )));
+        }
+        self.settle_unused(lock_id).await
+    }
+
+    /// Settle every lock whose window has passed
+    pub async fn expire_locks(&self) -> Vec<PriceLock> {
+        let expired: Vec<String> = self
+            .locks
+            .read()
+            .unwrap()
+            .values()
+            .filter(|l| l.status == PriceLockStatus::Active && l.expires_at.is_past())
+            .map(|l| l.id.clone())
+            .collect();
+
+        let mut settled = Vec::with_capacity(expired.len());
+        for lock_id in expired {
+            match self.settle_unused(&lock_id).await {
+                Ok(lock) => settled.push(lock),
+                Err(e) => warn!("Failed to settle expired price lock {}: {}", lock_id, e),
+            }
+        }
+        settled
+    }
+
+    /// Forfeit or refund the fee of an unused lock and release its hold
+    async fn settle_unused(&self, lock_id: &str) -> CoreResult<PriceLock> {
+        let lock = self
+            .get(lock_id)
+            .ok_or_else(|| CoreError::FareNotAvailable("Price lock not found".to_string()))?;
+
+        let refund = self.policy.unused_refund(&lock.fee);
+        let refund_id = if refund.is_zero() {
+            None
+        } else {
+            let request = RefundRequest {
+                payment_id: lock.payment_id.clone(),
+                amount: Some(refund),
+                reason: RefundReason::CustomerRequest,
+                idempotency_key: Some(format!("refund_price_lock_{}", lock.id)),
+            };
+            let refund = self
+                .payment
+                .create_refund(&request)
+                .await
+                .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+            Some(refund.id)
+        };
+
+        let kept = lock.fee.amount.as_i64() - refund.amount.as_i64();
+        if kept > 0 {
+            self.record(
+                &lock.id,
+                LedgerEntryKind::FeeForfeited,
+                Price::new(MinorUnits::new(kept), lock.fee.currency),
+                None,
+            );
+        }
+        if let Some(refund_id) = &refund_id {
+            self.record(
+                &lock.id,
+                LedgerEntryKind::FeeRefunded,
+                refund,
+                Some(refund_id.clone()),
+            );
+        }
+
+        if let Err(e) = self.holds.release_hold(&lock.hold_id).await {
+            warn!("Failed to release fare hold {}: {}", lock.hold_id, e);
+        }
+
+        let mut locks = self.locks.write().unwrap();
+        let stored = locks
+            .get_mut(lock_id)
+            .ok_or_else(|| CoreError::FareNotAvailable("Price lock not found".to_string()))?;
+        stored.status = if refund_id.is_some() {
+            PriceLockStatus::Refunded
+        } else {
+            PriceLockStatus::Forfeited
+        };
+        info!(
+            "Price lock {} settled as {}",
+            lock_id,
+            stored.status.as_str()
+        );
+        Ok(stored.clone())
+    }
+
+    /// Get a lock by ID
+    pub fn get(&self, lock_id: &str) -> Option<PriceLock> {
+        self.locks.read().unwrap().get(lock_id).cloned()
+    }
+
+    /// Ledger entries for a lock, oldest first
+    pub fn ledger_for(&self, lock_id: &str) -> Vec<LedgerEntry> {
+        self.ledger
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.lock_id == lock_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Append a ledger entry
+    fn record(
+        &self,
+        lock_id: &str,
+        kind: LedgerEntryKind,
+        amount: Price,
+        reference: Option<String>,
+    ) {
+        self.ledger.write().unwrap().push(LedgerEntry {
+            id: Uuid::new_v4().to_string(),
+            lock_id: lock_id.to_string(),
+            kind,
+            amount,
+            reference,
+            recorded_at: Timestamp::now(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use vaya_payment::mock::MockPaymentProvider;
+
+    #[derive(Default)]
+    struct FakeHolds {
+        released: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl FareHold for FakeHolds {
+        async fn hold_fare(&self, offer_id: &str, _until: Timestamp) -> CoreResult<String> {
+            Ok(format!("hold_{}", offer_id))
+        }
+
+        async fn release_hold(&self, hold_id: &str) -> CoreResult<()> {
+            self.released.lock().unwrap().push(hold_id.to_string());
+            Ok(())
+        }
+    }
+
+    fn request(price: i64) -> PriceLockRequest {
+        PriceLockRequest {
+            user_id: "user_1".to_string(),
+            email: "user@example.com".to_string(),
+            offer_id: "offer_1".to_string(),
+            price: Price::myr(price),
+            return_url: None,
+        }
+    }
+
+    #[test]
+    fn test_fee_for() {
+        let policy = PriceLockPolicy::default();
+        assert_eq!(policy.fee_for(&Price::myr(100_000)).amount.as_i64(), 2_000);
+        assert_eq!(policy.fee_for(&Price::myr(10_000)).amount.as_i64(), 1_000);
+        assert_eq!(policy.fee_for(&Price::myr(900_000)).amount.as_i64(), 5_000);
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_redeem_credits_fee() {
+        let holds = Arc::new(FakeHolds::default());
+        let service = PriceLockService::new(Arc::new(MockPaymentProvider::new()), holds.clone());
+
+        let lock = service.lock(request(100_000)).await.unwrap();
+        assert_eq!(lock.fee.amount.as_i64(), 2_000);
+
+        // Fare went up; the locked price applies
+        let applied = service
+            .redeem(&lock.id, "user_1", "offer_1", "BK1", &Price::myr(120_000))
+            .await
+            .unwrap();
+        assert_eq!(applied.amount_due.amount.as_i64(), 98_000);
+        assert_eq!(
+            service.get(&lock.id).unwrap().status,
+            PriceLockStatus::Applied
+        );
+        assert_eq!(holds.released.lock().unwrap().len(), 1);
+
+        let kinds: Vec<_> = service
+            .ledger_for(&lock.id)
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![LedgerEntryKind::FeeCharged, LedgerEntryKind::FeeCredited]
+        );
+
+        // A used lock cannot be redeemed again
+        assert!(service
+            .redeem(&lock.id, "user_1", "offer_1", "BK2", &Price::myr(90_000))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unused_lock_settles_per_policy() {
+        let payments = Arc::new(MockPaymentProvider::new());
+        let service = PriceLockService::new(payments.clone(), Arc::new(FakeHolds::default()))
+            .with_policy(PriceLockPolicy {
+                unused: UnusedLockFee::RefundPercent(50),
+                ..Default::default()
+            });
+
+        let lock = service.lock(request(100_000)).await.unwrap();
+        assert!(service.cancel(&lock.id, "someone_else").await.is_err());
+
+        let settled = service.cancel(&lock.id, "user_1").await.unwrap();
+        assert_eq!(settled.status, PriceLockStatus::Refunded);
+        assert_eq!(payments.refunds()[0].amount.amount.as_i64(), 1_000);
+
+        let kinds: Vec<_> = service
+            .ledger_for(&lock.id)
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LedgerEntryKind::FeeCharged,
+                LedgerEntryKind::FeeForfeited,
+                LedgerEntryKind::FeeRefunded
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_declined_fee_releases_hold() {
+        let holds = Arc::new(FakeHolds::default());
+        let payments = Arc::new(MockPaymentProvider::new());
+        payments.set_decline(true);
+        let service = PriceLockService::new(payments, holds.clone());
+
+        assert!(matches!(
+            service.lock(request(100_000)).await,
+            Err(CoreError::PaymentFailed(_))
+        ));
+        assert_eq!(*holds.released.lock().unwrap(), vec!["hold_offer_1"]);
+    }
+}
@@ -0,0 +1,479 @@
+//! Displayed vs charged price monitoring
+//!
+//! The total shown to the user at review is recorded on the booking as
+//! [`Booking::displayed_total`]. When the payment is captured,
+//! [`PriceVarianceMonitor`] compares the captured amount with it. A
+//! difference beyond the [`VarianceTolerance`] is a violation: it is logged,
+//! counted, emailed to finance, and the booking's settlement is blocked until
+//! finance releases it.
+//!
+//! Every capture is kept in the [`VarianceLog`] so finance can pull a
+//! [`VarianceReport`] for any day. Checks are counted under
+//! `vaya_core_price_variance_checks_total` with an `outcome` label (`match`,
+//! `within_tolerance`, `violation`, `unrecorded`).
+
+use std::sync::{Arc, RwLock};
+
+use tracing::{error, info, warn};
+
+use vaya_common::{metrics, CurrencyCode, Date, Price, Timestamp};
+
+use crate::error::{CoreError, CoreResult};
+use crate::notify::Notifier;
+use crate::types::Booking;
+
+/// Metric counting capture checks
+pub const PRICE_VARIANCE_METRIC: &str = "vaya_core_price_variance_checks_total";
+
+/// How far a captured amount may differ from the displayed total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VarianceTolerance {
+    /// Largest accepted difference in either direction, in basis points of
+    /// the displayed total (100 = 1%)
+    pub max_bps: u32,
+    /// Largest accepted difference in minor units, whatever the percentage
+    pub max_minor: i64,
+}
+
+impl Default for VarianceTolerance {
+    /// Allow one minor unit either way for rounding
+    fn default() -> Self {
+        Self {
+            max_bps: 0,
+            max_minor: 1,
+        }
+    }
+}
+
+impl VarianceTolerance {
+    /// Require an exact match
+    pub fn exact() -> Self {
+        Self {
+            max_bps: 0,
+            max_minor: 0,
+        }
+    }
+
+    /// Check a captured amount against the displayed one
+    pub fn accepts(&self, displayed: &Price, charged: &Price) -> bool {
+        if displayed.currency != charged.currency {
+            return false;
+        }
+        let displayed_minor = displayed.amount.as_i64();
+        let by_pct = displayed_minor.saturating_mul(self.max_bps as i64) / 10_000;
+        let difference = charged.amount.as_i64() - displayed_minor;
+        difference.abs() <= by_pct.max(self.max_minor)
+    }
+}
+
+/// A captured payment compared with the total the user saw
+#[derive(Debug, Clone)]
+pub struct PriceVariance {
+    /// Booking ID
+    pub booking_id: String,
+    /// Booking PNR
+    pub pnr: String,
+    /// Payment ID
+    pub payment_id: String,
+    /// Total shown at review
+    pub displayed: Price,
+    /// Amount captured
+    pub charged: Price,
+    /// Difference exceeded the tolerance
+    pub violation: bool,
+    /// When the capture was checked
+    pub checked_at: Timestamp,
+}
+
+impl PriceVariance {
+    /// Charged minus displayed, in minor units (0 when currencies differ)
+    pub fn difference_minor(&self) -> i64 {
+        if self.displayed.currency != self.charged.currency {
+            return 0;
+        }
+        self.charged.amount.as_i64() - self.displayed.amount.as_i64()
+    }
+
+    /// Outcome label (used as a metrics label)
+    pub fn outcome(&self) -> &'static str {
+        if self.violation {
+            "violation"
+        } else if self.displayed == self.charged {
+            "match"
+        } else {
+            "within_tolerance"
+        }
+    }
+}
+
+/// Variance totals for one displayed currency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrencyVariance {
+    /// Displayed currency
+    pub currency: CurrencyCode,
+    /// Captures checked
+    pub captures: u64,
+    /// Captures that differed from the displayed total at all
+    pub mismatches: u64,
+    /// Captures beyond tolerance
+    pub violations: u64,
+    /// Sum of displayed totals, in minor units
+    pub displayed_minor: i64,
+    /// Sum of captured amounts in the same currency, in minor units
+    pub charged_minor: i64,
+    /// Sum of absolute differences, in minor units
+    pub absolute_variance_minor: i64,
+}
+
+impl CurrencyVariance {
+    fn new(currency: CurrencyCode) -> Self {
+        Self {
+            currency,
+            captures: 0,
+            mismatches: 0,
+            violations: 0,
+            displayed_minor: 0,
+            charged_minor: 0,
+            absolute_variance_minor: 0,
+        }
+    }
+
+    /// Charged minus displayed across all captures, in minor units
+    pub fn net_variance_minor(&self) -> i64 {
+        self.charged_minor - self.displayed_minor
+    }
+}
+
+/// Daily variance report for finance
+#[derive(Debug, Clone)]
+pub struct VarianceReport {
+    /// Day covered (UTC)
+    pub date: Date,
+    /// Totals per displayed currency, ordered by currency code
+    pub currencies: Vec<CurrencyVariance>,
+    /// Every violation on the day, oldest first
+    pub violations: Vec<PriceVariance>,
+}
+
+impl VarianceReport {
+    /// Build a report from the captures checked on `date`
+    pub fn build(date: Date, variances: &[PriceVariance]) -> Self {
+        let mut currencies: Vec<CurrencyVariance> = Vec::new();
+        for variance in variances {
+            let currency = variance.displayed.currency;
+            let idx = match currencies.iter().position(|c| c.currency == currency) {
+                Some(idx) => idx,
+                None => {
+                    currencies.push(CurrencyVariance::new(currency));
+                    currencies.len() - 1
+                }
+            };
+            let totals = &mut currencies[idx];
+            totals.captures += 1;
+            if variance.displayed != variance.charged {
+                totals.mismatches += 1;
+            }
+            if variance.violation {
+                totals.violations += 1;
+            }
+            totals.displayed_minor += variance.displayed.amount.as_i64();
+            // A capture in another currency has no comparable amount
+            totals.charged_minor += if variance.charged.currency == currency {
+                variance.charged.amount.as_i64()
+            } else {
+                variance.displayed.amount.as_i64()
+            };
+            totals.absolute_variance_minor += variance.difference_minor().abs();
+        }
+        currencies.sort_by(|a, b| a.currency.as_str().cmp(b.currency.as_str()));
+
+        Self {
+            date,
+            currencies,
+            violations: variances.iter().filter(|v| v.violation).cloned().collect(),
+        }
+    }
+
+    /// Total captures checked
+    pub fn captures(&self) -> u64 {
+        self.currencies.iter().map(|c| c.captures).sum()
+    }
+}
+
+/// In-memory record of checked captures
+#[derive(Default)]
+pub struct VarianceLog {
+    entries: RwLock<Vec<PriceVariance>>,
+}
+
+impl VarianceLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a checked capture
+    pub fn record(&self, variance: PriceVariance) {
+        self.entries
+            .write()
+            .expect("variance log poisoned")
+            .push(variance);
+    }
+
+    /// Captures checked in `[from, to)`, oldest first
+    pub fn between(&self, from: Timestamp, to: Timestamp) -> Vec<PriceVariance> {
+        self.entries
+            .read()
+            .expect("variance log poisoned")
+            .iter()
+            .filter(|v| v.checked_at >= from && v.checked_at < to)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Checks captured amounts against displayed totals
+pub struct PriceVarianceMonitor {
+    tolerance: VarianceTolerance,
+    log: Arc<VarianceLog>,
+    alerts: Option<(Arc<dyn Notifier>, String)>,
+}
+
+impl Default for PriceVarianceMonitor {
+    fn default() -> Self {
+        Self::new(VarianceTolerance::default(), Arc::new(VarianceLog::new()))
+    }
+}
+
+impl PriceVarianceMonitor {
+    /// Create a monitor recording into `log`
+    pub fn new(tolerance: VarianceTolerance, log: Arc<VarianceLog>) -> Self {
+        Self {
+            tolerance,
+            log,
+            alerts: None,
+        }
+    }
+
+    /// Email violations to finance
+    pub fn with_alerts(mut self, notifier: Arc<dyn Notifier>, finance_email: &str) -> Self {
+        self.alerts = Some((notifier, finance_email.to_string()));
+        self
+    }
+
+    /// Check a captured payment against the booking's displayed total
+    ///
+    /// Blocks the booking's settlement on a violation. Returns `None` for
+    /// bookings with no displayed total recorded.
+    pub async fn check_capture(
+        &self,
+        booking: &mut Booking,
+        payment_id: &str,
+        charged: &Price,
+    ) -> Option<PriceVariance> {
+        let Some(displayed) = booking.displayed_total else {
+            warn!(
+                "Booking {} has no displayed total to check payment {} against",
+                booking.id, payment_id
+            );
+            metrics::global()
+                .counter(PRICE_VARIANCE_METRIC, &[("outcome", "unrecorded")])
+                .inc();
+            return None;
+        };
+
+        let variance = PriceVariance {
+            booking_id: booking.id.clone(),
+            pnr: booking.pnr.clone(),
+            payment_id: payment_id.to_string(),
+            displayed,
+            charged: *charged,
+            violation: !self.tolerance.accepts(&displayed, charged),
+            checked_at: Timestamp::now(),
+        };
+        if variance.violation {
+            booking.settlement_blocked = true;
+            booking.updated_at = Timestamp::now();
+        }
+        self.record(variance.clone()).await;
+        Some(variance)
+    }
+
+    /// Record a checked capture, alerting finance on a violation
+    pub async fn record(&self, variance: PriceVariance) {
+        metrics::global()
+            .counter(PRICE_VARIANCE_METRIC, &[("outcome", variance.outcome())])
+            .inc();
+        if variance.violation {
+            error!(
+                "Price variance on booking {}: displayed {}, charged {} (payment {}); settlement blocked",
+                variance.pnr,
+                variance.displayed.format(),
+                variance.charged.format(),
+                variance.payment_id
+            );
+            self.alert(&variance).await;
+        }
+        self.log.record(variance);
+    }
+
+    /// Release a settlement blocked by a violation after finance review
+    pub fn release_settlement(&self, booking: &mut Booking, actor: &str) -> CoreResult<()> {
+        if !booking.settlement_blocked {
+            return Err(CoreError::BookingNotModifiable(format!(
+                "Booking {} has no settlement hold",
+                booking.id
+            )));
+        }
+        booking.settlement_blocked = false;
+        booking.updated_at = Timestamp::now();
+        info!(
+            "Settlement of booking {} released by {}",
+            booking.pnr, actor
+        );
+        Ok(())
+    }
+
+    /// Variance report for one UTC day
+    pub fn daily_report(&self, date: Date) -> VarianceReport {
+        let from = date.to_timestamp();
+        let variances = self.log.between(from, from.add_days(1));
+        VarianceReport::build(date, &variances)
+    }
+
+    async fn alert(&self, variance: &PriceVariance) {
+        let Some((notifier, to)) = &self.alerts else {
+            return;
+        };
+        let subject = format!("Price variance on booking {}", variance.pnr);
+        let body = format!(
+            "Booking {} was charged {} but the user was shown {} at review.\n\
+             Payment: {}\n\n\
+             Settlement is blocked until finance releases it.",
+            variance.pnr,
+            variance.charged.format(),
+            variance.displayed.format(),
+            variance.payment_id
+        );
+        if let Err(e) = notifier
+            .enqueue_email("price_variance", to, &subject, &body)
+            .await
+        {
+            warn!(
+                "Failed to alert finance of price variance on {}: {}",
+                variance.pnr, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Captured {
+        emails: Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Captured {
+        async fn send_email(&self, to: &str, subject: &str, _body: &str) -> CoreResult<()> {
+            self.emails
+                .lock()
+                .unwrap()
+                .push((to.to_string(), subject.to_string()));
+            Ok(())
+        }
+
+        async fn send_sms(&self, _to: &str, _body: &str) -> CoreResult<()> {
+            Ok(())
+        }
+    }
+
+    fn variance(pnr: &str, displayed: Price, charged: Price, violation: bool) -> PriceVariance {
+        PriceVariance {
+            booking_id: format!("bk-{}", pnr),
+            pnr: pnr.to_string(),
+            payment_id: format!("pi-{}", pnr),
+            displayed,
+            charged,
+            violation,
+            checked_at: Timestamp::now(),
+        }
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let tolerance = VarianceTolerance::default();
+        assert!(tolerance.accepts(&Price::myr(45000), &Price::myr(45000)));
+        assert!(tolerance.accepts(&Price::myr(45000), &Price::myr(44999)));
+        assert!(!tolerance.accepts(&Price::myr(45000), &Price::myr(45100)));
+        assert!(!tolerance.accepts(&Price::myr(45000), &Price::usd(45000)));
+
+        let one_pct = VarianceTolerance {
+            max_bps: 100,
+            max_minor: 0,
+        };
+        assert!(one_pct.accepts(&Price::myr(45000), &Price::myr(44550)));
+        assert!(!one_pct.accepts(&Price::myr(45000), &Price::myr(44549)));
+        assert!(!VarianceTolerance::exact().accepts(&Price::myr(45000), &Price::myr(45001)));
+    }
+
+    #[tokio::test]
+    async fn test_violation_alerts_and_reports() {
+        let notifier = Arc::new(Captured::default());
+        let monitor =
+            PriceVarianceMonitor::default().with_alerts(notifier.clone(), "finance@vaya.my");
+
+        monitor
+            .record(variance(
+                "AAA111",
+                Price::myr(45000),
+                Price::myr(45000),
+                false,
+            ))
+            .await;
+        monitor
+            .record(variance(
+                "BBB222",
+                Price::myr(45000),
+                Price::myr(46500),
+                true,
+            ))
+            .await;
+        monitor
+            .record(variance(
+                "CCC333",
+                Price::usd(9900),
+                Price::usd(9899),
+                false,
+            ))
+            .await;
+
+        let emails = notifier.emails.lock().unwrap().clone();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].0, "finance@vaya.my");
+        assert!(emails[0].1.contains("BBB222"));
+
+        let report = monitor.daily_report(Date::today());
+        assert_eq!(report.captures(), 3);
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].difference_minor(), 1500);
+
+        let myr = &report.currencies[0];
+        assert_eq!(myr.currency.as_str(), "MYR");
+        assert_eq!((myr.captures, myr.mismatches, myr.violations), (2, 1, 1));
+        assert_eq!(myr.net_variance_minor(), 1500);
+        let usd = &report.currencies[1];
+        assert_eq!((usd.mismatches, usd.violations), (1, 0));
+        assert_eq!(usd.absolute_variance_minor, 1);
+
+        assert_eq!(
+            monitor.daily_report(Date::today().add_days(-1)).captures(),
+            0
+        );
+    }
+}
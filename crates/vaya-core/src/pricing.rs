@@ -0,0 +1,517 @@
+//! Retail pricing policy engine
+//!
+//! GDS fares are net prices. A [`PricingPolicy`] turns them into retail
+//! prices by applying markups, service fees, tier adjustments, and payment
+//! surcharges selected by route, carrier, cabin, payment method, and user
+//! tier. Every line is itemized in the resulting [`RetailPrice`].
+//!
+//! Policies are versioned: publishing new rules creates a new version and
+//! old versions stay available, so a booking keeps the policy it was priced
+//! with.
+
+use std::sync::{Arc, RwLock};
+
+use tracing::info;
+
+use vaya_common::{AirlineCode, CurrencyCode, IataCode, MinorUnits, Price, Timestamp, UserTier};
+use vaya_payment::PaymentMethodType;
+
+use crate::error::{CoreError, CoreResult};
+use crate::types::{CabinClass, FlightOffer};
+
+/// Kind of pricing adjustment, in the order they are applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FeeCategory {
+    /// Margin added to the fare
+    Markup,
+    /// Booking service fee
+    ServiceFee,
+    /// Tier discount or premium (may be negative)
+    TierAdjustment,
+    /// Payment method surcharge (applied last, on the running total)
+    PaymentSurcharge,
+}
+
+impl FeeCategory {
+    /// Get category code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeCategory::Markup => "markup",
+            FeeCategory::ServiceFee => "service_fee",
+            FeeCategory::TierAdjustment => "tier_adjustment",
+            FeeCategory::PaymentSurcharge => "payment_surcharge",
+        }
+    }
+}
+
+/// How a fee amount is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeAmount {
+    /// Fixed amount per booking, in minor units of the fare currency
+    Fixed(i64),
+    /// Basis points of the GDS base fare (100 = 1%)
+    PercentOfBase(i32),
+    /// Basis points of the running total at the point the rule applies
+    PercentOfTotal(i32),
+}
+
+/// A pricing rule
+///
+/// Empty selector lists match anything.
+#[derive(Debug, Clone)]
+pub struct FeeRule {
+    /// Label shown to the user
+    pub name: String,
+    /// Category
+    pub category: FeeCategory,
+    /// Amount
+    pub amount: FeeAmount,
+    /// Origin/destination pairs
+    pub routes: Vec<(IataCode, IataCode)>,
+    /// Validating carriers
+    pub carriers: Vec<AirlineCode>,
+    /// Cabins
+    pub cabins: Vec<CabinClass>,
+    /// Payment methods
+    pub payment_methods: Vec<PaymentMethodType>,
+    /// User tiers
+    pub tiers: Vec<UserTier>,
+}
+
+impl FeeRule {
+    /// Create a rule that applies everywhere
+    pub fn new(name: impl Into<String>, category: FeeCategory, amount: FeeAmount) -> Self {
+        Self {
+            name: name.into(),
+            category,
+            amount,
+            routes: Vec::new(),
+            carriers: Vec::new(),
+            cabins: Vec::new(),
+            payment_methods: Vec::new(),
+            tiers: Vec::new(),
+        }
+    }
+
+    /// Restrict to a route
+    pub fn for_route(mut self, origin: IataCode, destination: IataCode) -> Self {
+        self.routes.push((origin, destination));
+        self
+    }
+
+    /// Restrict to a carrier
+    pub fn for_carrier(mut self, carrier: AirlineCode) -> Self {
+        self.carriers.push(carrier);
+        self
+    }
+
+    /// Restrict to a cabin
+    pub fn for_cabin(mut self, cabin: CabinClass) -> Self {
+        self.cabins.push(cabin);
+        self
+    }
+
+    /// Restrict to a payment method
+    pub fn for_payment_method(mut self, method: PaymentMethodType) -> Self {
+        self.payment_methods.push(method);
+        self
+    }
+
+    /// Restrict to a user tier
+    pub fn for_tier(mut self, tier: UserTier) -> Self {
+        self.tiers.push(tier);
+        self
+    }
+
+    /// Check if the rule applies in a context
+    ///
+    /// Rules restricted to payment methods only apply once a method is
+    /// known.
+    pub fn matches(&self, ctx: &PricingContext) -> bool {
+        let route = self.routes.is_empty()
+            || self
+                .routes
+                .iter()
+                .any(|&(o, d)| o == ctx.origin && d == ctx.destination);
+        let carrier =
+            self.carriers.is_empty() || ctx.carrier.is_some_and(|c| self.carriers.contains(&c));
+        let cabin = self.cabins.is_empty() || self.cabins.contains(&ctx.cabin);
+        let method = self.payment_methods.is_empty()
+            || ctx
+                .payment_method
+                .as_ref()
+                .is_some_and(|m| self.payment_methods.contains(m));
+        let tier = self.tiers.is_empty() || self.tiers.contains(&ctx.tier);
+        route && carrier && cabin && method && tier
+    }
+}
+
+/// What is being priced
+#[derive(Debug, Clone)]
+pub struct PricingContext {
+    /// Journey origin
+    pub origin: IataCode,
+    /// Journey destination
+    pub destination: IataCode,
+    /// Validating carrier
+    pub carrier: Option<AirlineCode>,
+    /// Cabin
+    pub cabin: CabinClass,
+    /// Payment method, once chosen
+    pub payment_method: Option<PaymentMethodType>,
+    /// Buyer's tier
+    pub tier: UserTier,
+}
+
+impl PricingContext {
+    /// Build a context for an offer, for an anonymous buyer
+    pub fn for_offer(offer: &FlightOffer) -> Option<Self> {
+        let first = offer.outbound.segments.first()?;
+        let last = offer.outbound.segments.last()?;
+        Some(Self {
+            origin: first.origin,
+            destination: last.destination,
+            carrier: offer.airlines.first().copied(),
+            cabin: offer.cabin_class,
+            payment_method: None,
+            tier: UserTier::Free,
+        })
+    }
+
+    /// Set payment method
+    pub fn with_payment_method(mut self, method: PaymentMethodType) -> Self {
+        self.payment_method = Some(method);
+        self
+    }
+
+    /// Set buyer tier
+    pub fn with_tier(mut self, tier: UserTier) -> Self {
+        self.tier = tier;
+        self
+    }
+}
+
+/// Net fare as priced by the GDS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetFare {
+    /// Base fare
+    pub base: Price,
+    /// Taxes
+    pub taxes: Price,
+    /// Carrier fees and surcharges
+    pub fees: Price,
+    /// Total
+    pub total: Price,
+}
+
+impl From<&vaya_gds::PriceBreakdown> for NetFare {
+    fn from(p: &vaya_gds::PriceBreakdown) -> Self {
+        Self {
+            base: p.base,
+            taxes: p.taxes,
+            fees: p.fees,
+            total: p.total,
+        }
+    }
+}
+
+/// An itemized retail adjustment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceLine {
+    /// Rule label
+    pub name: String,
+    /// Category
+    pub category: FeeCategory,
+    /// Amount (negative for discounts)
+    pub amount: Price,
+}
+
+/// Retail price with itemization
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetailPrice {
+    /// Policy version used
+    pub policy_version: u32,
+    /// Net GDS fare
+    pub net: NetFare,
+    /// Adjustments applied on top of the net fare
+    pub lines: Vec<PriceLine>,
+    /// Retail total
+    pub total: Price,
+}
+
+impl RetailPrice {
+    /// Sum of adjustments in one category
+    pub fn total_for(&self, category: FeeCategory) -> i64 {
+        self.lines
+            .iter()
+            .filter(|l| l.category == category)
+            .map(|l| l.amount.amount.as_i64())
+            .sum()
+    }
+
+    /// Itemization for API responses (amounts in minor units)
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "policy_version": self.policy_version,
+            "currency": self.total.currency.as_str(),
+            "base_fare": self.net.base.amount.as_i64(),
+            "taxes": self.net.taxes.amount.as_i64(),
+            "carrier_fees": self.net.fees.amount.as_i64(),
+            "items": self.lines.iter().map(|l| serde_json::json!({
+                "name": l.name,
+                "category": l.category.as_str(),
+                "amount": l.amount.amount.as_i64(),
+            })).collect::<Vec<_>>(),
+            "total": self.total.amount.as_i64(),
+        })
+    }
+}
+
+/// A versioned set of pricing rules
+#[derive(Debug, Clone)]
+pub struct PricingPolicy {
+    /// Version number (starts at 1)
+    pub version: u32,
+    /// When the version was published
+    pub published_at: Timestamp,
+    /// Rules
+    pub rules: Vec<FeeRule>,
+}
+
+impl PricingPolicy {
+    /// Apply the policy to a net fare
+    ///
+    /// Rules are applied by category, in declaration order within a
+    /// category. Discounts never take the price below the net fare.
+    pub fn apply(&self, net: &NetFare, ctx: &PricingContext) -> RetailPrice {
+        let currency: CurrencyCode = net.total.currency;
+        let floor = net.total.amount.as_i64();
+        let mut running = floor;
+
+        let mut rules: Vec<&FeeRule> = self.rules.iter().filter(|r| r.matches(ctx)).collect();
+        rules.sort_by_key(|r| r.category);
+
+        let mut lines = Vec::with_capacity(rules.len());
+        for rule in rules {
+            let mut amount = match rule.amount {
+                FeeAmount::Fixed(minor) => minor,
+                FeeAmount::PercentOfBase(bps) => bps_of(net.base.amount.as_i64(), bps),
+                FeeAmount::PercentOfTotal(bps) => bps_of(running, bps),
+            };
+            amount = amount.max(floor - running);
+            if amount == 0 {
+                continue;
+            }
+            running += amount;
+            lines.push(PriceLine {
+                name: rule.name.clone(),
+                category: rule.category,
+                amount: Price::new(MinorUnits::new(amount), currency),
+            });
+        }
+
+        RetailPrice {
+            policy_version: self.version,
+            net: *net,
+            lines,
+            total: Price::new(MinorUnits::new(running), currency),
+        }
+    }
+}
+
+/// Basis points of an amount, rounded half away from zero
+fn bps_of(amount: i64, bps: i32) -> i64 {
+    let product = amount.saturating_mul(bps as i64);
+    let rounded = product.abs().saturating_add(5_000) / 10_000;
+    if product < 0 {
+        -rounded
+    } else {
+        rounded
+    }
+}
+
+/// Holds every published pricing policy version
+#[derive(Debug)]
+pub struct PricingEngine {
+    versions: RwLock<Vec<Arc<PricingPolicy>>>,
+}
+
+impl PricingEngine {
+    /// Create an engine whose first version has the given rules
+    pub fn new(rules: Vec<FeeRule>) -> Self {
+        Self {
+            versions: RwLock::new(vec![Arc::new(PricingPolicy {
+                version: 1,
+                published_at: Timestamp::now(),
+                rules,
+            })]),
+        }
+    }
+
+    /// Publish a new policy version, returning its number
+    pub fn publish(&self, rules: Vec<FeeRule>) -> u32 {
+        let mut versions = self.versions.write().unwrap();
+        let version = versions.len() as u32 + 1;
+        versions.push(Arc::new(PricingPolicy {
+            version,
+            published_at: Timestamp::now(),
+            rules,
+        }));
+        info!("Published pricing policy v{}", version);
+        version
+    }
+
+    /// Get the current policy
+    pub fn current(&self) -> Arc<PricingPolicy> {
+        self.versions
+            .read()
+            .unwrap()
+            .last()
+            .cloned()
+            .expect("engine always has a version")
+    }
+
+    /// Get a specific policy version
+    pub fn version(&self, version: u32) -> CoreResult<Arc<PricingPolicy>> {
+        let index = (version as usize).checked_sub(1);
+        index
+            .and_then(|i| self.versions.read().unwrap().get(i).cloned())
+            .ok_or_else(|| CoreError::Internal(format!("Unknown pricing policy v{}", version)))
+    }
+
+    /// Price a net fare with the current policy
+    pub fn price(&self, net: &NetFare, ctx: &PricingContext) -> RetailPrice {
+        self.current().apply(net, ctx)
+    }
+
+    /// Re-apply the policy version an earlier quote used, e.g. once the
+    /// buyer's tier and payment method are known
+    pub fn requote(&self, quote: &RetailPrice, ctx: &PricingContext) -> CoreResult<RetailPrice> {
+        Ok(self.version(quote.policy_version)?.apply(&quote.net, ctx))
+    }
+}
+
+impl Default for PricingEngine {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net() -> NetFare {
+        NetFare {
+            base: Price::myr(30_000),
+            taxes: Price::myr(8_000),
+            fees: Price::myr(2_000),
+            total: Price::myr(40_000),
+        }
+    }
+
+    fn ctx() -> PricingContext {
+        PricingContext {
+            origin: IataCode::KUL,
+            destination: IataCode::SIN,
+            carrier: Some(AirlineCode::new("MH")),
+            cabin: CabinClass::Economy,
+            payment_method: None,
+            tier: UserTier::Free,
+        }
+    }
+
+    fn rules() -> Vec<FeeRule> {
+        vec![
+            FeeRule::new(
+                "Card surcharge",
+                FeeCategory::PaymentSurcharge,
+                FeeAmount::PercentOfTotal(150),
+            )
+            .for_payment_method(PaymentMethodType::Card),
+            FeeRule::new(
+                "Service fee",
+                FeeCategory::ServiceFee,
+                FeeAmount::Fixed(1_500),
+            ),
+            FeeRule::new(
+                "Route markup",
+                FeeCategory::Markup,
+                FeeAmount::PercentOfBase(200),
+            )
+            .for_route(IataCode::KUL, IataCode::SIN),
+            FeeRule::new(
+                "Business markup",
+                FeeCategory::Markup,
+                FeeAmount::Fixed(5_000),
+            )
+            .for_cabin(CabinClass::Business),
+            FeeRule::new(
+                "Premium waiver",
+                FeeCategory::TierAdjustment,
+                FeeAmount::Fixed(-1_500),
+            )
+            .for_tier(UserTier::Premium),
+        ]
+    }
+
+    #[test]
+    fn test_apply_itemizes_matching_rules() {
+        let engine = PricingEngine::new(rules());
+        let quote = engine.price(&net(), &ctx());
+
+        // 2% of 300.00 base + 15.00 service fee
+        assert_eq!(quote.lines.len(), 2);
+        assert_eq!(quote.lines[0].category, FeeCategory::Markup);
+        assert_eq!(quote.total_for(FeeCategory::Markup), 600);
+        assert_eq!(quote.total.amount.as_i64(), 40_000 + 600 + 1_500);
+
+        let card = engine
+            .requote(
+                &quote,
+                &ctx()
+                    .with_tier(UserTier::Premium)
+                    .with_payment_method(PaymentMethodType::Card),
+            )
+            .unwrap();
+        // Waiver cancels the service fee; 1.5% surcharge on 406.00
+        assert_eq!(card.total_for(FeeCategory::TierAdjustment), -1_500);
+        assert_eq!(card.total_for(FeeCategory::PaymentSurcharge), 609);
+        assert_eq!(card.total.amount.as_i64(), 40_600 + 609);
+
+        let json = card.to_json();
+        assert_eq!(json["policy_version"], 1);
+        assert_eq!(json["items"].as_array().unwrap().len(), 4);
+        assert_eq!(json["items"][3]["category"], "payment_surcharge");
+    }
+
+    #[test]
+    fn test_discount_never_below_net() {
+        let engine = PricingEngine::new(vec![FeeRule::new(
+            "Launch promo",
+            FeeCategory::TierAdjustment,
+            FeeAmount::Fixed(-10_000),
+        )]);
+        let quote = engine.price(&net(), &ctx());
+        assert!(quote.lines.is_empty());
+        assert_eq!(quote.total, net().total);
+    }
+
+    #[test]
+    fn test_versions_are_retained() {
+        let engine = PricingEngine::new(rules());
+        let old = engine.price(&net(), &ctx());
+
+        let v2 = engine.publish(vec![FeeRule::new(
+            "Service fee",
+            FeeCategory::ServiceFee,
+            FeeAmount::Fixed(2_500),
+        )]);
+        assert_eq!(v2, 2);
+        assert_eq!(engine.price(&net(), &ctx()).total.amount.as_i64(), 42_500);
+
+        let requoted = engine.requote(&old, &ctx()).unwrap();
+        assert_eq!(requoted, old);
+        assert!(engine.version(3).is_err());
+    }
+}
@@ -0,0 +1,827 @@
+//! Promo codes and vouchers
+//!
+//! A [`PromoCode`] takes a percentage (optionally capped) or a fixed amount
+//! off the booking total. Codes can be limited to a validity window, a
+//! number of uses overall and per user, a minimum spend, cabins and routes.
+//!
+//! At checkout [`PromotionService::quote`] checks the codes a user entered
+//! and prices the discount without using them up;
+//! [`PromotionService::apply_to_booking`] checks them again and records a
+//! redemption for each. Several codes can be combined only if all of them
+//! are [`Stacking::Stackable`], up to [`PromotionConfig::max_codes`]; every
+//! discount is taken from the undiscounted total and the sum never exceeds
+//! it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use tracing::info;
+
+use vaya_common::{CurrencyCode, MinorUnits, Price, Route, Timestamp};
+
+use crate::error::{CoreError, CoreResult};
+use crate::types::{Booking, CabinClass};
+
+/// What a code takes off the total
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discount {
+    /// Percentage of the total in basis points (1000 = 10%), optionally
+    /// capped at an amount in the total's currency
+    Percentage {
+        /// Discount in basis points
+        bps: u32,
+        /// Largest discount
+        cap: Option<MinorUnits>,
+    },
+    /// Fixed amount, only for totals in the same currency
+    Fixed(Price),
+}
+
+impl Discount {
+    /// Discount on `total`, or `None` if the currencies don't match
+    pub fn amount_off(&self, total: &Price) -> Option<MinorUnits> {
+        match self {
+            Self::Percentage { bps, cap } => {
+                let off = total.amount.as_i64() * i64::from(*bps) / 10_000;
+                Some(MinorUnits::new(cap.map_or(off, |c| off.min(c.as_i64()))))
+            }
+            Self::Fixed(price) if price.currency == total.currency => Some(price.amount),
+            Self::Fixed(_) => None,
+        }
+    }
+}
+
+/// Whether a code combines with others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stacking {
+    /// Must be the only code used
+    #[default]
+    Exclusive,
+    /// Combines with other stackable codes
+    Stackable,
+}
+
+/// A promo code and its restrictions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromoCode {
+    /// Code entered at checkout (uppercase)
+    pub code: String,
+    /// Campaign the code belongs to
+    pub campaign: String,
+    /// Discount given
+    pub discount: Discount,
+    /// First moment the code can be used
+    pub valid_from: Timestamp,
+    /// Last moment the code can be used
+    pub valid_until: Timestamp,
+    /// Uses allowed across all users
+    pub max_uses: Option<u32>,
+    /// Uses allowed per user
+    pub max_uses_per_user: Option<u32>,
+    /// Smallest total the code applies to
+    pub min_spend: Option<Price>,
+    /// Cabins the code applies to (empty = any)
+    pub cabins: Vec<CabinClass>,
+    /// Routes the code applies to (empty = any)
+    pub routes: Vec<Route>,
+    /// Whether the code combines with others
+    pub stacking: Stacking,
+    /// Switched off by an admin
+    pub active: bool,
+    /// Admin who created the code
+    pub created_by: String,
+    /// Created at
+    pub created_at: Timestamp,
+    /// Last changed at
+    pub updated_at: Timestamp,
+}
+
+impl PromoCode {
+    /// Create an active, exclusive code without restrictions
+    pub fn new(
+        code: &str,
+        campaign: impl Into<String>,
+        discount: Discount,
+        valid_from: Timestamp,
+        valid_until: Timestamp,
+        created_by: impl Into<String>,
+    ) -> Self {
+        let now = Timestamp::now();
+        Self {
+            code: code.trim().to_ascii_uppercase(),
+            campaign: campaign.into(),
+            discount,
+            valid_from,
+            valid_until,
+            max_uses: None,
+            max_uses_per_user: None,
+            min_spend: None,
+            cabins: Vec::new(),
+            routes: Vec::new(),
+            stacking: Stacking::Exclusive,
+            active: true,
+            created_by: created_by.into(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Limit total uses
+    pub fn with_max_uses(mut self, uses: u32) -> Self {
+        self.max_uses = Some(uses);
+        self
+    }
+
+    /// Limit uses per user
+    pub fn with_max_uses_per_user(mut self, uses: u32) -> Self {
+        self.max_uses_per_user = Some(uses);
+        self
+    }
+
+    /// Require a minimum total
+    pub fn with_min_spend(mut self, min: Price) -> Self {
+        self.min_spend = Some(min);
+        self
+    }
+
+    /// Restrict to cabins
+    pub fn with_cabins(mut self, cabins: Vec<CabinClass>) -> Self {
+        self.cabins = cabins;
+        self
+    }
+
+    /// Restrict to routes
+    pub fn with_routes(mut self, routes: Vec<Route>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    /// Allow combining with other stackable codes
+    pub fn stackable(mut self) -> Self {
+        self.stacking = Stacking::Stackable;
+        self
+    }
+
+    /// Check the code's own settings
+    pub fn validate(&self) -> CoreResult<()> {
+        let len = self.code.len();
+        if !(3..=20).contains(&len)
+            || !self
+                .code
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'-')
+        {
+            return Err(CoreError::ValidationError(
+                "Promo code must be 3-20 letters, digits or dashes".to_string(),
+            ));
+        }
+        if self.valid_until <= self.valid_from {
+            return Err(CoreError::ValidationError(
+                "Promo code must end after it starts".to_string(),
+            ));
+        }
+        match self.discount {
+            Discount::Percentage { bps, .. } if bps == 0 || bps > 10_000 => Err(
+                CoreError::ValidationError("Percentage must be above 0 and at most 100%".into()),
+            ),
+            Discount::Fixed(price) if price.amount.as_i64() <= 0 => Err(
+                CoreError::ValidationError("Fixed discount must be positive".to_string()),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// Why the code can't be used at this checkout, if it can't
+    ///
+    /// `uses` and `user_uses` are the redemptions so far overall and by
+    /// the checkout's user.
+    fn check(&self, checkout: &PromoContext, uses: u32, user_uses: u32) -> Result<(), String> {
+        if !self.active || checkout.now < self.valid_from {
+            return Err(format!("Promo code {} is not active", self.code));
+        }
+        if checkout.now > self.valid_until {
+            return Err(format!("Promo code {} has expired", self.code));
+        }
+        if self.max_uses.is_some_and(|max| uses >= max) {
+            return Err(format!("Promo code {} has been fully redeemed", self.code));
+        }
+        if self.max_uses_per_user.is_some_and(|max| user_uses >= max) {
+            return Err(format!("You have already used promo code {}", self.code));
+        }
+        if let Some(min) = &self.min_spend {
+            if min.currency != checkout.total.currency || checkout.total.amount < min.amount {
+                return Err(format!(
+                    "Promo code {} needs a minimum spend of {}",
+                    self.code, min
+                ));
+            }
+        }
+        if !self.cabins.is_empty() && !self.cabins.contains(&checkout.cabin) {
+            return Err(format!(
+                "Promo code {} is not valid for this cabin",
+                self.code
+            ));
+        }
+        if !self.routes.is_empty() && !self.routes.contains(&checkout.route) {
+            return Err(format!(
+                "Promo code {} is not valid on this route",
+                self.code
+            ));
+        }
+        if self.discount.amount_off(&checkout.total).is_none() {
+            return Err(format!(
+                "Promo code {} is not valid for {} prices",
+                self.code, checkout.total.currency
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What a checkout looks like to the promo rules
+#[derive(Debug, Clone)]
+pub struct PromoContext {
+    /// Buyer
+    pub user_id: String,
+    /// Total before discounts
+    pub total: Price,
+    /// Booked cabin
+    pub cabin: CabinClass,
+    /// Outbound origin and final destination
+    pub route: Route,
+    /// Time of the checkout
+    pub now: Timestamp,
+}
+
+impl PromoContext {
+    /// Context for paying a booking
+    pub fn for_booking(booking: &Booking) -> CoreResult<Self> {
+        let segments = &booking.flights.outbound.segments;
+        let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+            return Err(CoreError::ValidationError(
+                "Booking has no flights".to_string(),
+            ));
+        };
+        Ok(Self {
+            user_id: booking.user_id.clone(),
+            total: booking.total_price,
+            cabin: booking.flights.cabin_class,
+            route: Route::new(first.origin, last.destination),
+            now: Timestamp::now(),
+        })
+    }
+}
+
+/// One code's discount at checkout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedPromotion {
+    /// Promo code
+    pub code: String,
+    /// Amount taken off
+    pub discount: Price,
+}
+
+/// Priced promo codes for a checkout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromoQuote {
+    /// Codes and their discounts, in the order entered
+    pub applied: Vec<AppliedPromotion>,
+    /// Total before discounts
+    pub subtotal: Price,
+    /// Sum of the discounts
+    pub discount: Price,
+    /// Amount left to pay
+    pub total: Price,
+}
+
+/// A recorded use of a code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redemption {
+    /// Promo code
+    pub code: String,
+    /// Buyer
+    pub user_id: String,
+    /// Booking the code was used on
+    pub booking_id: String,
+    /// Amount taken off
+    pub discount: Price,
+    /// When the code was used
+    pub redeemed_at: Timestamp,
+}
+
+/// Storage for promo codes and their redemptions
+pub trait PromotionStore: Send + Sync {
+    /// A code by its (uppercase) name
+    fn get(&self, code: &str) -> CoreResult<Option<PromoCode>>;
+
+    /// All codes
+    fn list(&self) -> CoreResult<Vec<PromoCode>>;
+
+    /// Insert or replace a code
+    fn save(&self, promo: &PromoCode) -> CoreResult<()>;
+
+    /// Delete a code; false if there was none
+    fn delete(&self, code: &str) -> CoreResult<bool>;
+
+    /// Redemptions of a code
+    fn redemptions(&self, code: &str) -> CoreResult<Vec<Redemption>>;
+
+    /// Record a redemption
+    fn redeem(&self, redemption: Redemption) -> CoreResult<()>;
+
+    /// Remove the redemptions of a booking, returning how many there were
+    fn release(&self, booking_id: &str) -> CoreResult<usize>;
+}
+
+/// In-memory promotion store
+#[derive(Default)]
+pub struct MemoryPromotionStore {
+    codes: RwLock<HashMap<String, PromoCode>>,
+    redemptions: RwLock<Vec<Redemption>>,
+}
+
+impl MemoryPromotionStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PromotionStore for MemoryPromotionStore {
+    fn get(&self, code: &str) -> CoreResult<Option<PromoCode>> {
+        Ok(self.codes.read().unwrap().get(code).cloned())
+    }
+
+    fn list(&self) -> CoreResult<Vec<PromoCode>> {
+        let mut codes: Vec<_> = self.codes.read().unwrap().values().cloned().collect();
+        codes.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(codes)
+    }
+
+    fn save(&self, promo: &PromoCode) -> CoreResult<()> {
+        self.codes
+            .write()
+            .unwrap()
+            .insert(promo.code.clone(), promo.clone());
+        Ok(())
+    }
+
+    fn delete(&self, code: &str) -> CoreResult<bool> {
+        Ok(self.codes.write().unwrap().remove(code).is_some())
+    }
+
+    fn redemptions(&self, code: &str) -> CoreResult<Vec<Redemption>> {
+        Ok(self
+            .redemptions
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.code == code)
+            .cloned()
+            .collect())
+    }
+
+    fn redeem(&self, redemption: Redemption) -> CoreResult<()> {
+        self.redemptions.write().unwrap().push(redemption);
+        Ok(())
+    }
+
+    fn release(&self, booking_id: &str) -> CoreResult<usize> {
+        let mut redemptions = self.redemptions.write().unwrap();
+        let before = redemptions.len();
+        redemptions.retain(|r| r.booking_id != booking_id);
+        Ok(before - redemptions.len())
+    }
+}
+
+/// Promotion settings
+#[derive(Debug, Clone, Copy)]
+pub struct PromotionConfig {
+    /// Most codes usable on one booking
+    pub max_codes: usize,
+}
+
+impl Default for PromotionConfig {
+    fn default() -> Self {
+        Self { max_codes: 2 }
+    }
+}
+
+/// Promo code management and checkout validation
+pub struct PromotionService {
+    store: Arc<dyn PromotionStore>,
+    config: PromotionConfig,
+    /// Serializes limit checks with the redemptions they allow
+    redeem_lock: Mutex<()>,
+}
+
+impl PromotionService {
+    /// Create a service over a store
+    pub fn new(store: Arc<dyn PromotionStore>) -> Self {
+        Self {
+            store,
+            config: PromotionConfig::default(),
+            redeem_lock: Mutex::new(()),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: PromotionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Create a new code
+    pub fn create(&self, promo: PromoCode) -> CoreResult<PromoCode> {
+        promo.validate()?;
+        if self.store.get(&promo.code)?.is_some() {
+            return Err(CoreError::ValidationError(format!(
+                "Promo code {} already exists",
+                promo.code
+            )));
+        }
+        self.store.save(&promo)?;
+        info!("Promo code {} created by {}", promo.code, promo.created_by);
+        Ok(promo)
+    }
+
+    /// Replace an existing code's settings
+    pub fn update(&self, mut promo: PromoCode) -> CoreResult<PromoCode> {
+        promo.validate()?;
+        let existing = self.require(&promo.code)?;
+        promo.created_by = existing.created_by;
+        promo.created_at = existing.created_at;
+        promo.updated_at = Timestamp::now();
+        self.store.save(&promo)?;
+        Ok(promo)
+    }
+
+    /// Switch a code on or off
+    pub fn set_active(&self, code: &str, active: bool) -> CoreResult<PromoCode> {
+        let mut promo = self.require(code)?;
+        promo.active = active;
+        promo.updated_at = Timestamp::now();
+        self.store.save(&promo)?;
+        Ok(promo)
+    }
+
+    /// Delete a code that was never used
+    ///
+    /// Used codes are kept for their redemption history; deactivate them
+    /// instead.
+    pub fn delete(&self, code: &str) -> CoreResult<()> {
+        let code = self.require(code)?.code;
+        if !self.store.redemptions(&code)?.is_empty() {
+            return Err(CoreError::ValidationError(format!(
+                "Promo code {} has been used; deactivate it instead",
+                code
+            )));
+        }
+        self.store.delete(&code)?;
+        Ok(())
+    }
+
+    /// A code by name (any case)
+    pub fn get(&self, code: &str) -> CoreResult<Option<PromoCode>> {
+        self.store.get(&code.trim().to_ascii_uppercase())
+    }
+
+    /// All codes, optionally only one campaign's
+    pub fn list(&self, campaign: Option<&str>) -> CoreResult<Vec<PromoCode>> {
+        let mut codes = self.store.list()?;
+        if let Some(campaign) = campaign {
+            codes.retain(|p| p.campaign == campaign);
+        }
+        Ok(codes)
+    }
+
+    /// Number of times a code has been used
+    pub fn uses(&self, code: &str) -> CoreResult<usize> {
+        Ok(self
+            .store
+            .redemptions(&code.trim().to_ascii_uppercase())?
+            .len())
+    }
+
+    /// Check codes for a checkout and price their discount
+    pub fn quote(&self, codes: &[String], checkout: &PromoContext) -> CoreResult<PromoQuote> {
+        let promos = self.resolve(codes)?;
+        let mut applied = Vec::with_capacity(promos.len());
+        let mut discount = 0i64;
+        for promo in &promos {
+            let redemptions = self.store.redemptions(&promo.code)?;
+            let user_uses = redemptions
+                .iter()
+                .filter(|r| r.user_id == checkout.user_id)
+                .count();
+            promo
+                .check(checkout, redemptions.len() as u32, user_uses as u32)
+                .map_err(CoreError::ValidationError)?;
+
+            let off = promo
+                .discount
+                .amount_off(&checkout.total)
+                .map_or(0, |a| a.as_i64())
+                .min(checkout.total.amount.as_i64() - discount);
+            discount += off;
+            applied.push(AppliedPromotion {
+                code: promo.code.clone(),
+                discount: Price::new(MinorUnits::new(off), checkout.total.currency),
+            });
+        }
+        Ok(quote(applied, checkout.total, discount))
+    }
+
+    /// Check codes again and record their use on a booking
+    pub fn redeem(
+        &self,
+        codes: &[String],
+        checkout: &PromoContext,
+        booking_id: &str,
+    ) -> CoreResult<PromoQuote> {
+        let _guard = self.redeem_lock.lock().unwrap();
+        let quote = self.quote(codes, checkout)?;
+        for applied in &quote.applied {
+            self.store.redeem(Redemption {
+                code: applied.code.clone(),
+                user_id: checkout.user_id.clone(),
+                booking_id: booking_id.to_string(),
+                discount: applied.discount,
+                redeemed_at: checkout.now,
+            })?;
+        }
+        if !quote.applied.is_empty() {
+            info!(
+                "Booking {} redeemed {} off with {} promo code(s)",
+                booking_id,
+                quote.discount.format(),
+                quote.applied.len()
+            );
+        }
+        Ok(quote)
+    }
+
+    /// Use codes on a booking and take the discount off its total
+    pub fn apply_to_booking(&self, codes: &[String], booking: &mut Booking) -> CoreResult<()> {
+        if !booking.promotions.is_empty() {
+            return Err(CoreError::BookingNotModifiable(
+                "Promo codes already applied".to_string(),
+            ));
+        }
+        let checkout = PromoContext::for_booking(booking)?;
+        let quote = self.redeem(codes, &checkout, &booking.id)?;
+        booking.total_price = quote.total;
+        booking.promotions = quote.applied;
+        booking.updated_at = Timestamp::now();
+        Ok(())
+    }
+
+    /// Give back the uses of a cancelled booking's codes
+    pub fn release(&self, booking_id: &str) -> CoreResult<usize> {
+        self.store.release(booking_id)
+    }
+
+    /// Look up entered codes, enforcing the stacking rules
+    fn resolve(&self, codes: &[String]) -> CoreResult<Vec<PromoCode>> {
+        let mut names: Vec<String> = Vec::with_capacity(codes.len());
+        for code in codes {
+            let name = code.trim().to_ascii_uppercase();
+            if !name.is_empty() && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if names.len() > self.config.max_codes {
+            return Err(CoreError::ValidationError(format!(
+                "At most {} promo codes can be used together",
+                self.config.max_codes
+            )));
+        }
+
+        let mut promos = Vec::with_capacity(names.len());
+        for name in &names {
+            let promo = self.store.get(name)?.ok_or_else(|| {
+                CoreError::ValidationError(format!("Unknown promo code {}", name))
+            })?;
+            if names.len() > 1 && promo.stacking == Stacking::Exclusive {
+                return Err(CoreError::ValidationError(format!(
+                    "Promo code {} can't be combined with other codes",
+                    promo.code
+                )));
+            }
+            promos.push(promo);
+        }
+        Ok(promos)
+    }
+
+    fn require(&self, code: &str) -> CoreResult<PromoCode> {
+        self.get(code)?
+            .ok_or_else(|| CoreError::ValidationError(format!("Unknown promo code {}", code)))
+    }
+}
+
+fn quote(applied: Vec<AppliedPromotion>, subtotal: Price, discount: i64) -> PromoQuote {
+    let currency: CurrencyCode = subtotal.currency;
+    PromoQuote {
+        applied,
+        subtotal,
+        discount: Price::new(MinorUnits::new(discount), currency),
+        total: Price::new(
+            MinorUnits::new(subtotal.amount.as_i64() - discount),
+            currency,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_common::IataCode;
+
+    fn service() -> PromotionService {
+        PromotionService::new(Arc::new(MemoryPromotionStore::new()))
+    }
+
+    fn promo(code: &str, discount: Discount) -> PromoCode {
+        let now = Timestamp::now();
+        PromoCode::new(
+            code,
+            "merdeka-2025",
+            discount,
+            now.add_days(-1),
+            now.add_days(30),
+            "admin-1",
+        )
+    }
+
+    fn percent(bps: u32) -> Discount {
+        Discount::Percentage { bps, cap: None }
+    }
+
+    fn checkout(user_id: &str, total: i64) -> PromoContext {
+        PromoContext {
+            user_id: user_id.to_string(),
+            total: Price::myr(total),
+            cabin: CabinClass::Economy,
+            route: Route::new(IataCode::KUL, IataCode::SIN),
+            now: Timestamp::now(),
+        }
+    }
+
+    fn codes(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_create_and_manage_codes() {
+        let service = service();
+        assert!(service.create(promo("X", percent(1000))).is_err());
+        assert!(service.create(promo("SALE10", percent(20_000))).is_err());
+        service.create(promo("sale10", percent(1000))).unwrap();
+        assert!(service.create(promo("SALE10", percent(500))).is_err());
+
+        let updated = service
+            .update(promo("SALE10", percent(1500)).with_max_uses(100))
+            .unwrap();
+        assert_eq!(updated.max_uses, Some(100));
+        assert!(!service.set_active("sale10", false).unwrap().active);
+        assert_eq!(service.list(Some("merdeka-2025")).unwrap().len(), 1);
+        assert!(service.list(Some("raya")).unwrap().is_empty());
+
+        service.delete("SALE10").unwrap();
+        assert!(service.get("SALE10").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_quote_checks_restrictions() {
+        let service = service();
+        service
+            .create(
+                promo("BIZ20", percent(2000))
+                    .with_cabins(vec![CabinClass::Business])
+                    .with_min_spend(Price::myr(100_000)),
+            )
+            .unwrap();
+        service
+            .create(
+                promo("SIN50", Discount::Fixed(Price::myr(5_000)))
+                    .with_routes(vec![Route::new(IataCode::KUL, IataCode::SIN)]),
+            )
+            .unwrap();
+        let mut expired = promo("OLD", percent(1000));
+        expired.valid_from = Timestamp::now().add_days(-10);
+        expired.valid_until = Timestamp::now().add_days(-1);
+        service.create(expired).unwrap();
+
+        let mut business = checkout("user-1", 200_000);
+        business.cabin = CabinClass::Business;
+        let quote = service.quote(&codes(&["biz20"]), &business).unwrap();
+        assert_eq!(quote.discount, Price::myr(40_000));
+        assert_eq!(quote.total, Price::myr(160_000));
+
+        // Wrong cabin, under the minimum spend
+        assert!(service
+            .quote(&codes(&["BIZ20"]), &checkout("user-1", 200_000))
+            .is_err());
+        business.total = Price::myr(50_000);
+        assert!(service.quote(&codes(&["BIZ20"]), &business).is_err());
+
+        // Route restriction and fixed discounts only in their currency
+        let mut other_route = checkout("user-1", 20_000);
+        assert!(service.quote(&codes(&["SIN50"]), &other_route).is_ok());
+        other_route.route = Route::new(IataCode::KUL, IataCode::BKK);
+        assert!(service.quote(&codes(&["SIN50"]), &other_route).is_err());
+        let mut usd = checkout("user-1", 20_000);
+        usd.total = Price::usd(20_000);
+        assert!(service.quote(&codes(&["SIN50"]), &usd).is_err());
+
+        assert!(service
+            .quote(&codes(&["OLD"]), &checkout("user-1", 20_000))
+            .is_err());
+        assert!(service
+            .quote(&codes(&["NOPE"]), &checkout("user-1", 20_000))
+            .is_err());
+    }
+
+    #[test]
+    fn test_stacking_rules() {
+        let service = service();
+        service
+            .create(
+                promo(
+                    "APP10",
+                    Discount::Percentage {
+                        bps: 1000,
+                        cap: Some(MinorUnits::new(3_000)),
+                    },
+                )
+                .stackable(),
+            )
+            .unwrap();
+        service
+            .create(promo("WELCOME", Discount::Fixed(Price::myr(10_000))).stackable())
+            .unwrap();
+        service.create(promo("SOLO", percent(500))).unwrap();
+        service
+            .create(promo("BIG", Discount::Fixed(Price::myr(90_000))).stackable())
+            .unwrap();
+
+        let quote = service
+            .quote(&codes(&["APP10", "WELCOME"]), &checkout("user-1", 50_000))
+            .unwrap();
+        // 10% capped at RM 30 plus RM 100 off
+        assert_eq!(quote.discount, Price::myr(13_000));
+        assert_eq!(quote.applied[0].discount, Price::myr(3_000));
+
+        assert!(service
+            .quote(&codes(&["APP10", "SOLO"]), &checkout("user-1", 50_000))
+            .is_err());
+        assert!(service
+            .quote(
+                &codes(&["APP10", "WELCOME", "BIG"]),
+                &checkout("user-1", 50_000)
+            )
+            .is_err());
+
+        // Discounts never exceed the total
+        let quote = service
+            .quote(&codes(&["BIG", "WELCOME"]), &checkout("user-1", 95_000))
+            .unwrap();
+        assert_eq!(quote.total, Price::myr(0));
+        assert_eq!(quote.applied[1].discount, Price::myr(5_000));
+    }
+
+    #[test]
+    fn test_usage_limits() {
+        let service = service();
+        service
+            .create(
+                promo("FIRST", percent(1000))
+                    .with_max_uses(2)
+                    .with_max_uses_per_user(1),
+            )
+            .unwrap();
+
+        service
+            .redeem(&codes(&["FIRST"]), &checkout("user-1", 10_000), "bk-1")
+            .unwrap();
+        // Per-user limit
+        assert!(service
+            .redeem(&codes(&["FIRST"]), &checkout("user-1", 10_000), "bk-2")
+            .is_err());
+        service
+            .redeem(&codes(&["FIRST"]), &checkout("user-2", 10_000), "bk-3")
+            .unwrap();
+        // Overall limit
+        assert!(service
+            .quote(&codes(&["FIRST"]), &checkout("user-3", 10_000))
+            .is_err());
+        assert!(service.delete("FIRST").is_err());
+
+        // A cancelled booking gives its use back
+        assert_eq!(service.release("bk-1").unwrap(), 1);
+        assert_eq!(service.uses("first").unwrap(), 1);
+        service
+            .redeem(&codes(&["FIRST"]), &checkout("user-1", 10_000), "bk-4")
+            .unwrap();
+    }
+}
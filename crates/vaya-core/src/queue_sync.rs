@@ -0,0 +1,444 @@
+//! Booking status sync from GDS queues
+//!
+//! Airline-initiated changes arrive as messages on GDS queues. This module
+//! matches each classified message to a booking by PNR, applies the
+//! resulting status change, tells the traveler, and opens a support ticket
+//! when an agent has to act (rebooking, refunds, unrecognized messages).
+
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use vaya_common::metrics;
+use vaya_gds::{ClassifiedMessage, QueueEventKind, QueuePoller, QueueSource};
+
+use crate::error::CoreResult;
+use crate::notify::Notifier;
+use crate::types::BookingStatus;
+
+/// Booking details needed to act on a queue message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedBooking {
+    /// Booking ID
+    pub booking_id: String,
+    /// Owner
+    pub user_id: String,
+    /// Current status
+    pub status: BookingStatus,
+    /// Contact email
+    pub contact_email: String,
+}
+
+/// Booking lookup and status updates by PNR
+pub trait BookingStatusStore: Send + Sync {
+    /// Find the booking with the given PNR
+    fn find_by_pnr(&self, pnr: &str) -> CoreResult<Option<QueuedBooking>>;
+
+    /// Set a booking's status
+    fn update_status(&self, booking_id: &str, status: BookingStatus) -> CoreResult<()>;
+}
+
+/// Support ticket priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TicketPriority {
+    /// Handle during normal hours
+    Normal,
+    /// Handle before the traveler's next contact
+    High,
+    /// Handle immediately
+    Urgent,
+}
+
+/// A request to open a support ticket
+#[derive(Debug, Clone)]
+pub struct SupportTicketRequest {
+    /// Booking the ticket concerns, if one was matched
+    pub booking_id: Option<String>,
+    /// Record locator
+    pub pnr: String,
+    /// Event that raised the ticket
+    pub kind: QueueEventKind,
+    /// Priority
+    pub priority: TicketPriority,
+    /// Ticket subject
+    pub subject: String,
+    /// Original queue message text
+    pub details: String,
+}
+
+/// Destination for tickets that need an agent
+pub trait SupportTicketSink: Send + Sync {
+    /// Open a ticket, returning its ID
+    fn open_ticket(&self, request: &SupportTicketRequest) -> CoreResult<String>;
+}
+
+/// Result of processing one queue message
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueSyncOutcome {
+    /// Matched booking
+    pub booking_id: Option<String>,
+    /// Status the booking was moved to
+    pub new_status: Option<BookingStatus>,
+    /// Whether the traveler was notified
+    pub notified: bool,
+    /// Support ticket opened
+    pub ticket_id: Option<String>,
+}
+
+/// Totals from one sync run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueSyncReport {
+    /// Messages read
+    pub processed: usize,
+    /// Booking statuses changed
+    pub status_updates: usize,
+    /// Travelers notified
+    pub notifications: usize,
+    /// Support tickets opened
+    pub tickets: usize,
+    /// Messages whose PNR matched no booking
+    pub unmatched: usize,
+    /// Messages left on the queue after a processing failure
+    pub failed: usize,
+}
+
+/// Applies GDS queue messages to bookings
+pub struct QueueSyncService {
+    bookings: Arc<dyn BookingStatusStore>,
+    notifier: Option<Arc<dyn Notifier>>,
+    tickets: Option<Arc<dyn SupportTicketSink>>,
+}
+
+impl QueueSyncService {
+    /// Create a new sync service
+    pub fn new(bookings: Arc<dyn BookingStatusStore>) -> Self {
+        Self {
+            bookings,
+            notifier: None,
+            tickets: None,
+        }
+    }
+
+    /// Notify travelers of changes
+    pub fn with_notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Open support tickets for changes needing an agent
+    pub fn with_ticket_sink(mut self, tickets: Arc<dyn SupportTicketSink>) -> Self {
+        self.tickets = Some(tickets);
+        self
+    }
+
+    /// Apply one classified message
+    pub async fn process(&self, classified: &ClassifiedMessage) -> CoreResult<QueueSyncOutcome> {
+        let message = &classified.message;
+        let kind = classified.kind;
+        let mut outcome = QueueSyncOutcome::default();
+
+        let booking = self.bookings.find_by_pnr(&message.pnr)?;
+        if booking.is_none() {
+            warn!(
+                "Queue message {} references unknown PNR {}",
+                message.id, message.pnr
+            );
+        }
+
+        if let Some(booking) = &booking {
+            outcome.booking_id = Some(booking.booking_id.clone());
+            if let Some(status) = status_after(kind, booking.status) {
+                self.bookings.update_status(&booking.booking_id, status)?;
+                info!(
+                    "Booking {} moved to {:?} by {} on queue {}",
+                    booking.booking_id,
+                    status,
+                    kind.as_str(),
+                    message.queue
+                );
+                outcome.new_status = Some(status);
+            }
+
+            if kind.notifies_traveler() {
+                if let Some(notifier) = &self.notifier {
+                    let (subject, body) = traveler_message(kind, &message.pnr);
+                    // A failed email should not keep the message on the queue
+                    match notifier
+                        .enqueue_email("schedule_change", &booking.contact_email, &subject, &body)
+                        .await
+                    {
+                        Ok(()) => outcome.notified = true,
+                        Err(e) => warn!(
+                            "Failed to notify traveler of {} on {}: {}",
+                            kind.as_str(),
+                            booking.booking_id,
+                            e
+                        ),
+                    }
+                }
+            }
+        }
+
+        // Unmatched PNRs always need an agent to look at them
+        if kind.needs_human_action() || booking.is_none() {
+            if let Some(tickets) = &self.tickets {
+                let request = SupportTicketRequest {
+                    booking_id: outcome.booking_id.clone(),
+                    pnr: message.pnr.clone(),
+                    kind,
+                    priority: ticket_priority(kind),
+                    subject: format!("{} on PNR {}", kind.as_str(), message.pnr),
+                    details: message.text.clone(),
+                };
+                outcome.ticket_id = Some(tickets.open_ticket(&request)?);
+            }
+        }
+
+        metrics::global()
+            .counter(
+                "vaya_core_gds_queue_messages_total",
+                &[("kind", kind.as_str())],
+            )
+            .inc();
+
+        Ok(outcome)
+    }
+
+    /// Poll queues once and apply every new message
+    ///
+    /// Messages are removed from the GDS queue only after they were
+    /// processed; failures are released so the next run retries them.
+    pub async fn run_once<S: QueueSource>(
+        &self,
+        poller: &QueuePoller<S>,
+    ) -> CoreResult<QueueSyncReport> {
+        let mut report = QueueSyncReport::default();
+
+        for classified in poller.poll_once().await {
+            report.processed += 1;
+            match self.process(&classified).await {
+                Ok(outcome) => {
+                    report.status_updates += usize::from(outcome.new_status.is_some());
+                    report.notifications += usize::from(outcome.notified);
+                    report.tickets += usize::from(outcome.ticket_id.is_some());
+                    report.unmatched += usize::from(outcome.booking_id.is_none());
+                    if let Err(e) = poller.acknowledge(&classified.message).await {
+                        warn!(
+                            "Failed to remove message {} from queue {}: {}",
+                            classified.message.id, classified.message.queue, e
+                        );
+                        poller.release(&classified.message);
+                    }
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to process queue message {}: {}",
+                        classified.message.id, e
+                    );
+                    poller.release(&classified.message);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Status a booking moves to after an event, if any
+fn status_after(kind: QueueEventKind, current: BookingStatus) -> Option<BookingStatus> {
+    match kind {
+        QueueEventKind::BookingCancelled
+            if current.can_cancel() || current == BookingStatus::PaymentProcessing =>
+        {
+            Some(BookingStatus::Cancelled)
+        }
+        _ => None,
+    }
+}
+
+/// Ticket priority for an event
+fn ticket_priority(kind: QueueEventKind) -> TicketPriority {
+    match kind {
+        QueueEventKind::FlightCancelled
+        | QueueEventKind::BookingCancelled
+        | QueueEventKind::TicketingDeadline => TicketPriority::Urgent,
+        QueueEventKind::InvoluntaryRebooking => TicketPriority::High,
+        _ => TicketPriority::Normal,
+    }
+}
+
+/// Email subject and body for a traveler-facing event
+fn traveler_message(kind: QueueEventKind, pnr: &str) -> (String, String) {
+    let (subject, detail) = match kind {
+        QueueEventKind::ScheduleChange => (
+            "Your flight schedule has changed",
+            "The airline has changed the times of a flight in your booking. Please review the new schedule.",
+        ),
+        QueueEventKind::FlightCancelled => (
+            "Your flight has been cancelled",
+            "The airline has cancelled a flight in your booking. Our team will contact you with options.",
+        ),
+        QueueEventKind::InvoluntaryRebooking => (
+            "You have been moved to a different flight",
+            "The airline has moved you to a different flight. Please review your updated itinerary.",
+        ),
+        QueueEventKind::BookingCancelled => (
+            "Your booking has been cancelled",
+            "The airline has cancelled your booking. Our team will contact you about a refund.",
+        ),
+        _ => ("Update to your booking", "There is an update to your booking."),
+    };
+    (
+        format!("{} ({})", subject, pnr),
+        format!("{}\n\nBooking reference: {}", detail, pnr),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, RwLock};
+    use vaya_common::Timestamp;
+    use vaya_gds::{GdsResult, QueueMessage};
+
+    #[derive(Default)]
+    struct MemoryBookings(RwLock<HashMap<String, QueuedBooking>>);
+
+    impl BookingStatusStore for MemoryBookings {
+        fn find_by_pnr(&self, pnr: &str) -> CoreResult<Option<QueuedBooking>> {
+            Ok(self.0.read().unwrap().get(pnr).cloned())
+        }
+
+        fn update_status(&self, booking_id: &str, status: BookingStatus) -> CoreResult<()> {
+            for booking in self.0.write().unwrap().values_mut() {
+                if booking.booking_id == booking_id {
+                    booking.status = status;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Captured {
+        emails: Mutex<Vec<(String, String)>>,
+        tickets: Mutex<Vec<SupportTicketRequest>>,
+    }
+
+    #[async_trait]
+    impl Notifier for Captured {
+        async fn send_email(&self, to: &str, subject: &str, _body: &str) -> CoreResult<()> {
+            self.emails
+                .lock()
+                .unwrap()
+                .push((to.to_string(), subject.to_string()));
+            Ok(())
+        }
+
+        async fn send_sms(&self, _to: &str, _body: &str) -> CoreResult<()> {
+            Ok(())
+        }
+    }
+
+    impl SupportTicketSink for Captured {
+        fn open_ticket(&self, request: &SupportTicketRequest) -> CoreResult<String> {
+            let mut tickets = self.tickets.lock().unwrap();
+            tickets.push(request.clone());
+            Ok(format!("TKT-{}", tickets.len()))
+        }
+    }
+
+    struct StaticQueue(Mutex<Vec<QueueMessage>>);
+
+    #[async_trait]
+    impl QueueSource for StaticQueue {
+        async fn list_queue(&self, queue: u16) -> GdsResult<Vec<QueueMessage>> {
+            let messages = self.0.lock().unwrap();
+            Ok(messages
+                .iter()
+                .filter(|m| m.queue == queue)
+                .cloned()
+                .collect())
+        }
+
+        async fn remove_queue_item(&self, queue: u16, id: &str) -> GdsResult<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .retain(|m| !(m.queue == queue && m.id == id));
+            Ok(())
+        }
+    }
+
+    fn message(id: &str, pnr: &str, text: &str) -> QueueMessage {
+        QueueMessage {
+            id: id.to_string(),
+            queue: 7,
+            category: 0,
+            pnr: pnr.to_string(),
+            text: text.to_string(),
+            received_at: Timestamp::now(),
+        }
+    }
+
+    fn setup() -> (Arc<MemoryBookings>, Arc<Captured>, QueueSyncService) {
+        let bookings = Arc::new(MemoryBookings::default());
+        bookings.0.write().unwrap().insert(
+            "ABC123".to_string(),
+            QueuedBooking {
+                booking_id: "BK1".to_string(),
+                user_id: "user_1".to_string(),
+                status: BookingStatus::Ticketed,
+                contact_email: "traveler@example.com".to_string(),
+            },
+        );
+        let captured = Arc::new(Captured::default());
+        let service = QueueSyncService::new(bookings.clone())
+            .with_notifier(captured.clone())
+            .with_ticket_sink(captured.clone());
+        (bookings, captured, service)
+    }
+
+    #[tokio::test]
+    async fn test_schedule_change_notifies_without_ticket() {
+        let (_, captured, service) = setup();
+        let msg = message("1", "ABC123", "MH 603 KUL SIN 15JUN TK1 0900 1000");
+        let classified = ClassifiedMessage {
+            kind: vaya_gds::classify(&msg),
+            message: msg,
+        };
+
+        let outcome = service.process(&classified).await.unwrap();
+        assert_eq!(outcome.booking_id.as_deref(), Some("BK1"));
+        assert!(outcome.notified);
+        assert!(outcome.new_status.is_none());
+        assert!(outcome.ticket_id.is_none());
+        assert_eq!(captured.emails.lock().unwrap()[0].0, "traveler@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_run_once_cancels_and_opens_tickets() {
+        let (bookings, captured, service) = setup();
+        let source = Arc::new(StaticQueue(Mutex::new(vec![
+            message("1", "ABC123", "PNR CANCELLED BY AIRLINE"),
+            message("2", "ZZZ999", "MH 603 KUL SIN 15JUN KK1"),
+        ])));
+        let poller = QueuePoller::new(source.clone());
+
+        let report = service.run_once(&poller).await.unwrap();
+        assert_eq!(report.processed, 2);
+        assert_eq!(report.status_updates, 1);
+        assert_eq!(report.tickets, 2);
+        assert_eq!(report.unmatched, 1);
+        assert!(source.0.lock().unwrap().is_empty());
+
+        let status = bookings.find_by_pnr("ABC123").unwrap().unwrap().status;
+        assert_eq!(status, BookingStatus::Cancelled);
+        let tickets = captured.tickets.lock().unwrap();
+        assert_eq!(tickets[0].priority, TicketPriority::Urgent);
+        assert!(tickets[1].booking_id.is_none());
+    }
+}
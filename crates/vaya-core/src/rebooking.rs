@@ -0,0 +1,120 @@
+//! Rebooking and date changes
+//!
+//! Quotes a change of a booked itinerary. The new offer is re-priced by the
+//! GDS holding the booking as an exchange of the booked flights, and the
+//! change fee is taken from the exchange's fare rules. The quote is then
+//! held on the booking (see [`vaya_book::Booking::request_change`]) until
+//! the traveler confirms or declines it.
+
+use vaya_book::{Booking, ChangeQuote, ChangeRules};
+use vaya_common::{CurrencyCode, MinorUnits};
+use vaya_gds::{FareRules, GdsProvider};
+use vaya_search::FlightOffer;
+
+use crate::error::{CoreError, CoreResult};
+
+/// Convert GDS fare rules into change rules for a booking in `currency`
+///
+/// Missing fare rules are treated as not changeable.
+pub fn change_rules(rules: Option<&FareRules>, currency: CurrencyCode) -> CoreResult<ChangeRules> {
+    let Some(rules) = rules else {
+        return Ok(ChangeRules::not_changeable());
+    };
+    if !rules.changeable {
+        return Ok(ChangeRules::not_changeable());
+    }
+    match &rules.change_fee {
+        Some(fee) if fee.currency != currency => Err(CoreError::BookingNotModifiable(format!(
+            "Change fee is in {}, booking in {}",
+            fee.currency, currency
+        ))),
+        Some(fee) => Ok(ChangeRules::with_fee(fee.amount)),
+        None => Ok(ChangeRules::with_fee(MinorUnits::ZERO)),
+    }
+}
+
+/// Re-price `new_offer` as an exchange of the booking and request the change
+///
+/// The offer's price and expiry are replaced with the GDS exchange pricing
+/// before the quote is built.
+pub async fn quote_change<G: GdsProvider + ?Sized>(
+    gds: &G,
+    booking: &mut Booking,
+    mut new_offer: FlightOffer,
+    actor: &str,
+) -> CoreResult<ChangeQuote> {
+    let pnr = booking.provider_ref.clone().ok_or_else(|| {
+        CoreError::BookingNotModifiable(format!("Booking {} has no GDS record", booking.pnr))
+    })?;
+
+    let repriced = gds.reprice_booking(&pnr, &new_offer.id).await?;
+    let price = &repriced.price;
+    new_offer.price.base_fare = price.base.amount;
+    new_offer.price.taxes = price.taxes.amount;
+    new_offer.price.surcharges = price.fees.amount;
+    new_offer.price.currency = price.total.currency;
+    new_offer.expires_at = repriced.expires_at.map(|t| t.as_unix());
+    if let Some(fare_rules) = &repriced.fare_rules {
+        new_offer.changeable = fare_rules.changeable;
+        new_offer.refundable = fare_rules.refundable;
+    }
+
+    let rules = change_rules(repriced.fare_rules.as_ref(), booking.currency)?;
+    booking
+        .request_change(new_offer, &rules, actor)
+        .cloned()
+        .map_err(|e| CoreError::BookingNotModifiable(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_book::BookingStatus;
+    use vaya_common::Price;
+    use vaya_gds::traits::mock::MockGdsProvider;
+
+    use crate::testing::{booking, offer};
+
+    #[test]
+    fn test_change_rules() {
+        assert!(!change_rules(None, CurrencyCode::MYR).unwrap().changeable);
+
+        let mut rules = FareRules {
+            refundable: false,
+            changeable: true,
+            change_fee: Some(Price::myr(15_000)),
+            cancellation_fee: None,
+            baggage: None,
+        };
+        let converted = change_rules(Some(&rules), CurrencyCode::MYR).unwrap();
+        assert_eq!(converted, ChangeRules::with_fee(MinorUnits::new(15_000)));
+        assert!(change_rules(Some(&rules), CurrencyCode::USD).is_err());
+
+        rules.changeable = false;
+        assert_eq!(
+            change_rules(Some(&rules), CurrencyCode::MYR).unwrap(),
+            ChangeRules::not_changeable()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quote_change_uses_exchange_pricing() {
+        let gds = MockGdsProvider::new().with_reprice_fare(150_000);
+        let mut booking = booking();
+
+        // Not yet held with the GDS
+        let err = quote_change(&gds, &mut booking, offer("offer-2", 1), "user-1").await;
+        assert!(matches!(err, Err(CoreError::BookingNotModifiable(_))));
+
+        booking.confirm("GDS123", "system").unwrap();
+        let quote = quote_change(&gds, &mut booking, offer("offer-2", 1), "user-1")
+            .await
+            .unwrap();
+        // 1,650.00 exchange fare against 1,500.00 booked, plus 150.00 fee
+        assert_eq!(quote.new_fare, MinorUnits::new(165_000));
+        assert_eq!(quote.fare_difference, MinorUnits::new(15_000));
+        assert_eq!(quote.amount_due, MinorUnits::new(30_000));
+        assert_eq!(booking.status, BookingStatus::ChangeRequested);
+        assert!(!booking.pending_change.as_ref().unwrap().offer.refundable);
+    }
+}
@@ -0,0 +1,276 @@
+//! Booking refunds
+//!
+//! Converts GDS fare rules into [`RefundRules`] for the refund calculator,
+//! and sends approved refunds to the payment provider. The calculation and
+//! the approval steps live on [`vaya_book::Booking`]; see
+//! [`Booking::quote_refund`] and [`Booking::request_refund`].
+
+use tracing::{info, warn};
+
+use vaya_book::{Booking, RefundRecord, RefundRules, RefundStatus};
+use vaya_common::{CurrencyCode, MinorUnits, Price};
+use vaya_gds::FareRules;
+use vaya_payment::{PaymentProvider, RefundReason, RefundRequest};
+
+use crate::error::{CoreError, CoreResult};
+
+/// Convert GDS fare rules into refund rules for a booking in `currency`
+///
+/// Missing fare rules are treated as non-refundable, which still returns
+/// taxes.
+pub fn refund_rules(rules: Option<&FareRules>, currency: CurrencyCode) -> CoreResult<RefundRules> {
+    let Some(rules) = rules else {
+        return Ok(RefundRules::tax_only());
+    };
+    if !rules.refundable {
+        return Ok(RefundRules::tax_only());
+    }
+    match &rules.cancellation_fee {
+        Some(fee) if fee.currency != currency => Err(CoreError::BookingNotModifiable(format!(
+            "Cancellation fee is in {}, booking in {}",
+            fee.currency, currency
+        ))),
+        Some(fee) => Ok(RefundRules::with_fee(fee.amount)),
+        None => Ok(RefundRules::with_fee(MinorUnits::ZERO)),
+    }
+}
+
+/// Send an approved refund to the payment provider
+///
+/// The refund is spread over the booking's completed payments, newest
+/// first, one provider refund per payment, each capped at what that payment
+/// has left after earlier refunds. Each has its own idempotency key, so a
+/// failed refund can be processed again without refunding twice; what went
+/// to each payment is recorded only once the whole refund went through.
+pub async fn process_refund<P: PaymentProvider + ?Sized>(
+    payments: &P,
+    booking: &mut Booking,
+    refund_id: &str,
+    actor: &str,
+) -> CoreResult<RefundRecord> {
+    let refund = booking
+        .refund(refund_id)
+        .cloned()
+        .ok_or_else(|| CoreError::RefundFailed(format!("Unknown refund {}", refund_id)))?;
+    if !matches!(refund.status, RefundStatus::Approved | RefundStatus::Failed) {
+        return Err(CoreError::RefundFailed(format!(
+            "Refund {} is {}",
+            refund_id,
+            refund.status.as_str()
+        )));
+    }
+    let sources: Vec<_> = booking
+        .payments
+        .iter()
+        .rev()
+        .filter(|p| p.status.is_successful() && p.refundable() > MinorUnits::ZERO)
+        .map(|p| {
+            (
+                p.id.clone(),
+                p.provider_ref.clone().unwrap_or_else(|| p.id.clone()),
+                p.refundable(),
+            )
+        })
+        .collect();
+
+    booking
+        .start_refund(refund_id)
+        .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+
+    let mut remaining = refund.amount.as_i64();
+    let mut provider_refs = Vec::new();
+    let mut allocations = Vec::new();
+    for (record_id, payment_id, refundable) in sources {
+        if remaining == 0 {
+            break;
+        }
+        let amount = remaining.min(refundable.as_i64());
+        let request = RefundRequest {
+            payment_id: payment_id.clone(),
+            amount: Some(Price::new(MinorUnits::new(amount), refund.currency)),
+            reason: RefundReason::BookingCancelled,
+            idempotency_key: Some(format!("refund_{}_{}", refund.id, payment_id)),
+        };
+        match payments.create_refund(&request).await {
+            Ok(result) => {
+                provider_refs.push(result.id);
+                allocations.push((record_id, MinorUnits::new(amount)));
+                remaining -= amount;
+            }
+            Err(e) => {
+                warn!(
+                    booking = %booking.pnr,
+                    refund = %refund.id,
+                    error = %e,
+                    "Refund failed at payment provider"
+                );
+                booking
+                    .fail_refund(refund_id)
+                    .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+                return Err(CoreError::RefundFailed(e.to_string()));
+            }
+        }
+    }
+    if remaining > 0 {
+        booking
+            .fail_refund(refund_id)
+            .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+        return Err(CoreError::RefundFailed(format!(
+            "Refund {} exceeds the booking's payments",
+            refund.id
+        )));
+    }
+
+    for (record_id, amount) in allocations {
+        booking
+            .record_payment_refund(&record_id, amount)
+            .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+    }
+    booking
+        .complete_refund(refund_id, Some(provider_refs.join(",")), actor)
+        .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+    info!(
+        booking = %booking.pnr,
+        refund = %refund.id,
+        amount = refund.amount.as_i64(),
+        "Refund processed"
+    );
+    booking
+        .refund(refund_id)
+        .cloned()
+        .ok_or_else(|| CoreError::RefundFailed(format!("Unknown refund {}", refund_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use vaya_book::{BookingStatus, RefundQuote};
+    use vaya_payment::mock::MockPaymentProvider;
+    use vaya_payment::PaymentError;
+
+    use crate::testing;
+
+    fn booking() -> Booking {
+        let mut booking = testing::booking();
+        booking.confirm("GDS123", "system").unwrap();
+        // Paid in two parts
+        booking.payments.push(testing::paid("pay-1", 100_000));
+        booking.payments.push(testing::paid("pay-2", 50_000));
+        booking
+            .transition(BookingStatus::PaymentReceived, "Paid", "user-1")
+            .unwrap();
+        booking
+    }
+
+    /// (payment, amount) of each refund sent
+    fn refunded(payments: &MockPaymentProvider) -> Vec<(String, i64)> {
+        payments
+            .refunds()
+            .into_iter()
+            .map(|r| (r.payment_id, r.amount.amount.as_i64()))
+            .collect()
+    }
+
+    fn quote(amount: i64) -> RefundQuote {
+        RefundQuote {
+            currency: CurrencyCode::MYR,
+            segments: 1,
+            used_segments: 0,
+            hours_to_departure: 100,
+            fare_refund: MinorUnits::new(amount - 30_000),
+            tax_refund: MinorUnits::new(30_000),
+            cancellation_fee: MinorUnits::new(150_000 - amount),
+            refundable_amount: MinorUnits::new(amount),
+            tax_only: false,
+        }
+    }
+
+    #[test]
+    fn test_refund_rules_from_fare_rules() {
+        let mut fare_rules = FareRules {
+            refundable: true,
+            changeable: true,
+            change_fee: None,
+            cancellation_fee: Some(Price::myr(20_000)),
+            baggage: None,
+        };
+        let rules = refund_rules(Some(&fare_rules), CurrencyCode::MYR).unwrap();
+        assert_eq!(rules, RefundRules::with_fee(MinorUnits::new(20_000)));
+        assert!(refund_rules(Some(&fare_rules), CurrencyCode::USD).is_err());
+        assert!(!refund_rules(None, CurrencyCode::MYR).unwrap().refundable);
+
+        fare_rules.refundable = false;
+        let rules = refund_rules(Some(&fare_rules), CurrencyCode::MYR).unwrap();
+        assert_eq!(rules, RefundRules::tax_only());
+    }
+
+    #[tokio::test]
+    async fn test_process_refund_across_payments() {
+        let payments = MockPaymentProvider::new();
+        payments.fail_next(PaymentError::ServiceUnavailable("down".into()));
+        let mut booking = booking();
+        let id = booking
+            .request_refund(&quote(130_000), "Cancelled", "user-1")
+            .unwrap()
+            .id
+            .clone();
+
+        // Not yet approved
+        assert!(process_refund(&payments, &mut booking, &id, "agent")
+            .await
+            .is_err());
+        booking.approve_refund(&id).unwrap();
+
+        assert!(process_refund(&payments, &mut booking, &id, "agent")
+            .await
+            .is_err());
+        assert_eq!(booking.refund(&id).unwrap().status, RefundStatus::Failed);
+
+        let refund = process_refund(&payments, &mut booking, &id, "agent")
+            .await
+            .unwrap();
+        assert_eq!(refund.status, RefundStatus::Completed);
+        assert_eq!(
+            refund.provider_ref.as_deref(),
+            Some("re_pi_pay-2,re_pi_pay-1")
+        );
+        assert_eq!(booking.status, BookingStatus::Refunded);
+        assert_eq!(
+            refunded(&payments),
+            vec![
+                ("pi_pay-2".to_string(), 50_000),
+                ("pi_pay-1".to_string(), 80_000)
+            ]
+        );
+        let refunded: Vec<_> = booking
+            .payments
+            .iter()
+            .map(|p| p.refunded.as_i64())
+            .collect();
+        assert_eq!(refunded, [80_000, 50_000]);
+    }
+
+    #[tokio::test]
+    async fn test_process_refund_skips_refunded_payments() {
+        let payments = MockPaymentProvider::new();
+        let mut booking = booking();
+        // pay-2 was refunded in full and pay-1 in part, e.g. after a change
+        booking.payments[0].refunded = MinorUnits::new(10_000);
+        booking.payments[1].refunded = MinorUnits::new(50_000);
+        let id = booking
+            .request_refund(&quote(90_000), "Cancelled", "user-1")
+            .unwrap()
+            .id
+            .clone();
+        booking.approve_refund(&id).unwrap();
+
+        let refund = process_refund(&payments, &mut booking, &id, "agent")
+            .await
+            .unwrap();
+        assert_eq!(refund.provider_ref.as_deref(), Some("re_pi_pay-1"));
+        assert_eq!(refunded(&payments), vec![("pi_pay-1".to_string(), 90_000)]);
+        assert_eq!(booking.payments[0].refunded, MinorUnits::new(100_000));
+        assert_eq!(booking.payments[1].refunded, MinorUnits::new(50_000));
+    }
+}
@@ -0,0 +1,966 @@
+//! VayaDb-backed repositories for pools, bookings, price alerts and
+//! notification preferences
+//!
+//! Records are stored rkyv-serialized (see `schema`) under `{kind}:id:{id}`,
+//! where kind is `pool`, `booking`, `alert` or `notification_prefs` (keyed
+//! by user ID). VayaDb has no key iteration,
+//! so each repository keeps ID-list index keys next to its records:
+//!
+//! - `{kind}:user:{user_id}`: the user's records (every member, for pools)
+//! - `{kind}:route:{origin}-{destination}`: records on a route
+//! - `booking:ref:{reference}`: the booking with a `VAY-` reference
+//! - `pool:open`: pools not yet closed, for [`PoolStore::open_pools`]
+//!
+//! Updates are a compare-and-swap on the record's `version`: a write based
+//! on a stale read fails with [`CoreError::VersionConflict`] instead of
+//! overwriting the newer record, and the caller reloads and retries.
+//! Index keys are diffed on every write, so a member leaving a pool or a
+//! pool closing drops out of the matching indexes. Removed records and
+//! emptied indexes are overwritten with an empty value rather than deleted.
+
+mod schema;
+
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use rkyv::AlignedVec;
+use vaya_book::{Booking, ReferenceGenerator};
+use vaya_common::IataCode;
+use vaya_db::{DbError, VayaDb};
+use vaya_oracle::PriceAlert;
+use vaya_pool::{Pool, PoolError, PoolResult, PoolStore};
+
+use crate::error::{CoreError, CoreResult};
+use crate::preferences::NotificationPreferences;
+use schema::{StoredAlert, StoredBooking, StoredIds, StoredPool, StoredPreferences};
+
+/// Pool persistence
+pub trait PoolRepository: Send + Sync {
+    /// Load a pool
+    fn get(&self, pool_id: &str) -> CoreResult<Option<Pool>>;
+
+    /// Store a new pool
+    fn insert(&self, pool: &Pool) -> CoreResult<()>;
+
+    /// Replace a pool only if the stored version is still `expected_version`
+    fn update(&self, pool: &Pool, expected_version: u32) -> CoreResult<()>;
+
+    /// Delete a pool, returning whether it existed
+    fn delete(&self, pool_id: &str) -> CoreResult<bool>;
+
+    /// Pools the user is a member of
+    fn find_by_user(&self, user_id: &str) -> CoreResult<Vec<Pool>>;
+
+    /// Pools on a route
+    fn find_by_route(&self, origin: IataCode, destination: IataCode) -> CoreResult<Vec<Pool>>;
+}
+
+/// Booking persistence, keyed by PNR
+pub trait BookingRepository: Send + Sync {
+    /// Load a booking
+    fn get(&self, pnr: &str) -> CoreResult<Option<Booking>>;
+
+    /// Store a new booking
+    fn insert(&self, booking: &Booking) -> CoreResult<()>;
+
+    /// Replace a booking only if the stored version is still `expected_version`
+    fn update(&self, booking: &Booking, expected_version: u32) -> CoreResult<()>;
+
+    /// Delete a booking, returning whether it existed
+    fn delete(&self, pnr: &str) -> CoreResult<bool>;
+
+    /// Bookings made by the user
+    fn find_by_user(&self, user_id: &str) -> CoreResult<Vec<Booking>>;
+
+    /// Bookings whose outbound leg flies the route
+    fn find_by_route(&self, origin: IataCode, destination: IataCode) -> CoreResult<Vec<Booking>>;
+
+    /// Load a booking by its `VAY-` booking reference
+    fn find_by_reference(&self, reference: &str) -> CoreResult<Option<Booking>>;
+
+    /// A PNR no stored booking uses
+    ///
+    /// [`insert`](Self::insert) still rejects a PNR taken in the meantime.
+    fn new_pnr(&self, generator: &ReferenceGenerator) -> CoreResult<String> {
+        generator.unique_pnr(|pnr| Ok(self.get(pnr)?.is_some()))
+    }
+
+    /// A booking reference no stored booking uses
+    fn new_reference(&self, generator: &ReferenceGenerator) -> CoreResult<String> {
+        generator.unique_reference(|reference| Ok(self.find_by_reference(reference)?.is_some()))
+    }
+}
+
+/// Price alert persistence
+pub trait AlertRepository: Send + Sync {
+    /// Load an alert
+    fn get(&self, alert_id: &str) -> CoreResult<Option<PriceAlert>>;
+
+    /// Store a new alert
+    fn insert(&self, alert: &PriceAlert) -> CoreResult<()>;
+
+    /// Replace an alert only if the stored version is still `expected_version`
+    fn update(&self, alert: &PriceAlert, expected_version: u32) -> CoreResult<()>;
+
+    /// Delete an alert, returning whether it existed
+    fn delete(&self, alert_id: &str) -> CoreResult<bool>;
+
+    /// Alerts set by the user
+    fn find_by_user(&self, user_id: &str) -> CoreResult<Vec<PriceAlert>>;
+
+    /// Alerts watching a route
+    fn find_by_route(&self, origin: IataCode, destination: IataCode)
+        -> CoreResult<Vec<PriceAlert>>;
+}
+
+/// Notification preference persistence, keyed by user ID
+pub trait PreferenceRepository: Send + Sync {
+    /// Load a user's preferences
+    fn get(&self, user_id: &str) -> CoreResult<Option<NotificationPreferences>>;
+
+    /// Store a user's first preferences
+    fn insert(&self, preferences: &NotificationPreferences) -> CoreResult<()>;
+
+    /// Replace preferences only if the stored version is still `expected_version`
+    fn update(
+        &self,
+        preferences: &NotificationPreferences,
+        expected_version: u32,
+    ) -> CoreResult<()>;
+
+    /// Delete a user's preferences, returning whether they existed
+    fn delete(&self, user_id: &str) -> CoreResult<bool>;
+}
+
+/// A record type stored by a [`Collection`]
+trait Entity: Sized {
+    /// Key prefix
+    const KIND: &'static str;
+
+    fn id(&self) -> &str;
+
+    fn version(&self) -> u32;
+
+    /// Index keys (without the kind prefix) the record belongs to
+    fn indexes(&self) -> Vec<String>;
+
+    fn encode(&self) -> CoreResult<AlignedVec>;
+
+    fn decode(bytes: &[u8]) -> CoreResult<Self>;
+
+    fn not_found(id: &str) -> CoreError;
+
+    fn already_exists(id: &str) -> CoreError;
+}
+
+fn user_index(user_id: &str) -> String {
+    format!("user:{}", user_id)
+}
+
+fn reference_index(reference: &str) -> String {
+    format!("ref:{}", reference)
+}
+
+fn route_index(origin: &IataCode, destination: &IataCode) -> String {
+    format!("route:{}-{}", origin.as_str(), destination.as_str())
+}
+
+/// Index of pools not yet closed
+const OPEN_INDEX: &str = "open";
+
+/// Value written in place of a delete
+///
+/// A VayaDb delete doesn't yet shadow a value already flushed to an
+/// SSTable, so removed records and emptied indexes would come back after a
+/// reopen. Overwriting them with an empty value and reading that as absent
+/// keeps removals durable.
+const REMOVED: &[u8] = &[];
+
+impl Entity for Pool {
+    const KIND: &'static str = "pool";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn indexes(&self) -> Vec<String> {
+        let mut indexes: Vec<String> = self
+            .members
+            .iter()
+            .map(|m| user_index(&m.user_id))
+            .collect();
+        indexes.push(route_index(&self.route.origin, &self.route.destination));
+        if !self.status.is_terminal() {
+            indexes.push(OPEN_INDEX.to_string());
+        }
+        indexes
+    }
+
+    fn encode(&self) -> CoreResult<AlignedVec> {
+        schema::encode(&StoredPool::from(self))
+    }
+
+    fn decode(bytes: &[u8]) -> CoreResult<Self> {
+        schema::decode::<StoredPool>(bytes)?.try_into()
+    }
+
+    fn not_found(id: &str) -> CoreError {
+        CoreError::PoolNotFound(id.to_string())
+    }
+
+    fn already_exists(id: &str) -> CoreError {
+        PoolError::PoolExists(id.to_string()).into()
+    }
+}
+
+impl Entity for Booking {
+    const KIND: &'static str = "booking";
+
+    fn id(&self) -> &str {
+        &self.pnr
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn indexes(&self) -> Vec<String> {
+        let mut indexes = vec![user_index(&self.user_id), reference_index(&self.reference)];
+        if let (Some(origin), Some(destination)) = (
+            self.offer.outbound.origin(),
+            self.offer.outbound.destination(),
+        ) {
+            indexes.push(route_index(origin, destination));
+        }
+        indexes
+    }
+
+    fn encode(&self) -> CoreResult<AlignedVec> {
+        schema::encode(&StoredBooking::from(self))
+    }
+
+    fn decode(bytes: &[u8]) -> CoreResult<Self> {
+        schema::decode::<StoredBooking>(bytes)?.try_into()
+    }
+
+    fn not_found(id: &str) -> CoreError {
+        CoreError::BookingNotFound(id.to_string())
+    }
+
+    fn already_exists(id: &str) -> CoreError {
+        CoreError::BookingAlreadyExists(id.to_string())
+    }
+}
+
+impl Entity for PriceAlert {
+    const KIND: &'static str = "alert";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn indexes(&self) -> Vec<String> {
+        vec![
+            user_index(&self.user_id),
+            route_index(&self.origin, &self.destination),
+        ]
+    }
+
+    fn encode(&self) -> CoreResult<AlignedVec> {
+        schema::encode(&StoredAlert::from(self))
+    }
+
+    fn decode(bytes: &[u8]) -> CoreResult<Self> {
+        schema::decode::<StoredAlert>(bytes)?.try_into()
+    }
+
+    fn not_found(id: &str) -> CoreError {
+        CoreError::AlertNotFound(id.to_string())
+    }
+
+    fn already_exists(id: &str) -> CoreError {
+        CoreError::AlertAlreadyExists(id.to_string())
+    }
+}
+
+impl Entity for NotificationPreferences {
+    const KIND: &'static str = "notification_prefs";
+
+    fn id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    fn indexes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn encode(&self) -> CoreResult<AlignedVec> {
+        schema::encode(&StoredPreferences::from(self))
+    }
+
+    fn decode(bytes: &[u8]) -> CoreResult<Self> {
+        schema::decode::<StoredPreferences>(bytes)?.try_into()
+    }
+
+    fn not_found(id: &str) -> CoreError {
+        CoreError::UserNotFound(id.to_string())
+    }
+
+    fn already_exists(_id: &str) -> CoreError {
+        // Inserts are saves of never-stored (version 0) preferences
+        CoreError::VersionConflict {
+            expected: 0,
+            actual: 1,
+        }
+    }
+}
+
+/// Versioned, indexed records of one kind
+struct Collection<E> {
+    db: Arc<VayaDb>,
+    /// Serializes version checks and read-modify-write updates of index keys
+    write_lock: Mutex<()>,
+    _entity: PhantomData<fn() -> E>,
+}
+
+impl<E: Entity> Collection<E> {
+    fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            write_lock: Mutex::new(()),
+            _entity: PhantomData,
+        }
+    }
+
+    fn record_key(id: &str) -> Vec<u8> {
+        format!("{}:id:{}", E::KIND, id).into_bytes()
+    }
+
+    fn index_key(index: &str) -> Vec<u8> {
+        format!("{}:{}", E::KIND, index).into_bytes()
+    }
+
+    fn get(&self, id: &str) -> CoreResult<Option<E>> {
+        self.db
+            .get(&Self::record_key(id))
+            .map_err(db_error)?
+            .filter(|bytes| !bytes.is_empty())
+            .map(|bytes| E::decode(&bytes))
+            .transpose()
+    }
+
+    fn insert(&self, record: &E) -> CoreResult<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        if self.get(record.id())?.is_some() {
+            return Err(E::already_exists(record.id()));
+        }
+        self.write(record)?;
+        self.reindex(record.id(), &[], &record.indexes())
+    }
+
+    fn update(&self, record: &E, expected_version: u32) -> CoreResult<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        let stored = self
+            .get(record.id())?
+            .ok_or_else(|| E::not_found(record.id()))?;
+        if stored.version() != expected_version {
+            return Err(CoreError::VersionConflict {
+                expected: expected_version,
+                actual: stored.version(),
+            });
+        }
+        self.write(record)?;
+        self.reindex(record.id(), &stored.indexes(), &record.indexes())
+    }
+
+    fn delete(&self, id: &str) -> CoreResult<bool> {
+        let _guard = self.write_lock.lock().unwrap();
+        let Some(stored) = self.get(id)? else {
+            return Ok(false);
+        };
+        self.db
+            .put(&Self::record_key(id), REMOVED)
+            .map_err(db_error)?;
+        self.reindex(id, &stored.indexes(), &[])?;
+        Ok(true)
+    }
+
+    /// Records in an index, in the order they joined it
+    fn find(&self, index: &str) -> CoreResult<Vec<E>> {
+        let mut records = Vec::new();
+        for id in self.read_ids(&Self::index_key(index))? {
+            if let Some(record) = self.get(&id)? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    fn write(&self, record: &E) -> CoreResult<()> {
+        let bytes = record.encode()?;
+        self.db
+            .put(&Self::record_key(record.id()), &bytes)
+            .map_err(db_error)
+    }
+
+    /// Move a record from the `old` indexes to the `new` ones (lock held)
+    fn reindex(&self, id: &str, old: &[String], new: &[String]) -> CoreResult<()> {
+        for index in old.iter().filter(|i| !new.contains(i)) {
+            let key = Self::index_key(index);
+            let mut ids = self.read_ids(&key)?;
+            ids.retain(|i| i != id);
+            self.write_ids(&key, ids)?;
+        }
+        for index in new.iter().filter(|i| !old.contains(i)) {
+            let key = Self::index_key(index);
+            let mut ids = self.read_ids(&key)?;
+            if !ids.iter().any(|i| i == id) {
+                ids.push(id.to_string());
+                self.write_ids(&key, ids)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_ids(&self, key: &[u8]) -> CoreResult<Vec<String>> {
+        match self.db.get(key).map_err(db_error)? {
+            Some(bytes) if !bytes.is_empty() => Ok(schema::decode::<StoredIds>(&bytes)?.ids),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn write_ids(&self, key: &[u8], ids: Vec<String>) -> CoreResult<()> {
+        if ids.is_empty() {
+            return self.db.put(key, REMOVED).map_err(db_error);
+        }
+        let bytes = schema::encode(&StoredIds { ids })?;
+        self.db.put(key, &bytes).map_err(db_error)
+    }
+}
+
+/// Pool repository persisting to VayaDb
+///
+/// Also a [`PoolStore`], so a [`vaya_pool::PoolService`] can run on it.
+pub struct DbPoolRepository {
+    pools: Collection<Pool>,
+}
+
+impl DbPoolRepository {
+    /// Create a repository over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            pools: Collection::new(db),
+        }
+    }
+}
+
+impl PoolRepository for DbPoolRepository {
+    fn get(&self, pool_id: &str) -> CoreResult<Option<Pool>> {
+        self.pools.get(pool_id)
+    }
+
+    fn insert(&self, pool: &Pool) -> CoreResult<()> {
+        self.pools.insert(pool)
+    }
+
+    fn update(&self, pool: &Pool, expected_version: u32) -> CoreResult<()> {
+        self.pools.update(pool, expected_version)
+    }
+
+    fn delete(&self, pool_id: &str) -> CoreResult<bool> {
+        self.pools.delete(pool_id)
+    }
+
+    fn find_by_user(&self, user_id: &str) -> CoreResult<Vec<Pool>> {
+        self.pools.find(&user_index(user_id))
+    }
+
+    fn find_by_route(&self, origin: IataCode, destination: IataCode) -> CoreResult<Vec<Pool>> {
+        self.pools.find(&route_index(&origin, &destination))
+    }
+}
+
+impl PoolStore for DbPoolRepository {
+    fn load(&self, pool_id: &str) -> PoolResult<Pool> {
+        self.pools
+            .get(pool_id)
+            .map_err(pool_error)?
+            .ok_or_else(|| PoolError::PoolNotFound(pool_id.to_string()))
+    }
+
+    fn insert(&self, pool: &Pool) -> PoolResult<()> {
+        self.pools.insert(pool).map_err(|e| match e {
+            CoreError::PoolError(_) => PoolError::PoolExists(pool.id.clone()),
+            e => pool_error(e),
+        })
+    }
+
+    fn open_pools(&self, limit: usize) -> PoolResult<Vec<Pool>> {
+        let mut open = self.pools.find(OPEN_INDEX).map_err(pool_error)?;
+        open.sort_by_key(|p| p.created_at);
+        open.truncate(limit);
+        Ok(open)
+    }
+
+    fn compare_and_swap(&self, pool: &Pool, expected_version: u32) -> PoolResult<()> {
+        self.pools
+            .update(pool, expected_version)
+            .map_err(pool_error)
+    }
+}
+
+/// Booking repository persisting to VayaDb
+pub struct DbBookingRepository {
+    bookings: Collection<Booking>,
+}
+
+impl DbBookingRepository {
+    /// Create a repository over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            bookings: Collection::new(db),
+        }
+    }
+}
+
+impl BookingRepository for DbBookingRepository {
+    fn get(&self, pnr: &str) -> CoreResult<Option<Booking>> {
+        self.bookings.get(pnr)
+    }
+
+    fn insert(&self, booking: &Booking) -> CoreResult<()> {
+        self.bookings.insert(booking)
+    }
+
+    fn update(&self, booking: &Booking, expected_version: u32) -> CoreResult<()> {
+        self.bookings.update(booking, expected_version)
+    }
+
+    fn delete(&self, pnr: &str) -> CoreResult<bool> {
+        self.bookings.delete(pnr)
+    }
+
+    fn find_by_user(&self, user_id: &str) -> CoreResult<Vec<Booking>> {
+        self.bookings.find(&user_index(user_id))
+    }
+
+    fn find_by_route(&self, origin: IataCode, destination: IataCode) -> CoreResult<Vec<Booking>> {
+        self.bookings.find(&route_index(&origin, &destination))
+    }
+
+    fn find_by_reference(&self, reference: &str) -> CoreResult<Option<Booking>> {
+        Ok(self
+            .bookings
+            .find(&reference_index(reference))?
+            .into_iter()
+            .next())
+    }
+}
+
+/// Price alert repository persisting to VayaDb
+pub struct DbAlertRepository {
+    alerts: Collection<PriceAlert>,
+}
+
+impl DbAlertRepository {
+    /// Create a repository over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            alerts: Collection::new(db),
+        }
+    }
+}
+
+impl AlertRepository for DbAlertRepository {
+    fn get(&self, alert_id: &str) -> CoreResult<Option<PriceAlert>> {
+        self.alerts.get(alert_id)
+    }
+
+    fn insert(&self, alert: &PriceAlert) -> CoreResult<()> {
+        self.alerts.insert(alert)
+    }
+
+    fn update(&self, alert: &PriceAlert, expected_version: u32) -> CoreResult<()> {
+        self.alerts.update(alert, expected_version)
+    }
+
+    fn delete(&self, alert_id: &str) -> CoreResult<bool> {
+        self.alerts.delete(alert_id)
+    }
+
+    fn find_by_user(&self, user_id: &str) -> CoreResult<Vec<PriceAlert>> {
+        self.alerts.find(&user_index(user_id))
+    }
+
+    fn find_by_route(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+    ) -> CoreResult<Vec<PriceAlert>> {
+        self.alerts.find(&route_index(&origin, &destination))
+    }
+}
+
+/// Notification preference repository persisting to VayaDb
+pub struct DbPreferenceRepository {
+    preferences: Collection<NotificationPreferences>,
+}
+
+impl DbPreferenceRepository {
+    /// Create a repository over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            preferences: Collection::new(db),
+        }
+    }
+}
+
+impl PreferenceRepository for DbPreferenceRepository {
+    fn get(&self, user_id: &str) -> CoreResult<Option<NotificationPreferences>> {
+        self.preferences.get(user_id)
+    }
+
+    fn insert(&self, preferences: &NotificationPreferences) -> CoreResult<()> {
+        self.preferences.insert(preferences)
+    }
+
+    fn update(
+        &self,
+        preferences: &NotificationPreferences,
+        expected_version: u32,
+    ) -> CoreResult<()> {
+        self.preferences.update(preferences, expected_version)
+    }
+
+    fn delete(&self, user_id: &str) -> CoreResult<bool> {
+        self.preferences.delete(user_id)
+    }
+}
+
+fn db_error(e: DbError) -> CoreError {
+    CoreError::Database(e.to_string())
+}
+
+fn pool_error(e: CoreError) -> PoolError {
+    match e {
+        CoreError::PoolNotFound(id) => PoolError::PoolNotFound(id),
+        CoreError::VersionConflict { expected, actual } => {
+            PoolError::VersionConflict { expected, actual }
+        }
+        e => PoolError::Internal(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_book::{AncillaryKind, BookedAncillary, Passenger, SeatAssignment, WheelchairNeed};
+    use vaya_common::{AirlineCode, CurrencyCode, Gender, MinorUnits};
+    use vaya_db::DbConfig;
+    use vaya_oracle::{AlertCondition, PriceTrend};
+    use vaya_pool::{PoolRoute, PoolService, PoolStatus, TieredPricing};
+    use vaya_search::CabinClass;
+
+    use crate::testing;
+
+    fn open(dir: &std::path::Path) -> Arc<VayaDb> {
+        Arc::new(VayaDb::open(DbConfig::new(dir)).unwrap())
+    }
+
+    fn date(day: u8) -> time::Date {
+        time::Date::from_calendar_date(2025, time::Month::June, day).unwrap()
+    }
+
+    fn pool() -> Pool {
+        let pricing =
+            TieredPricing::with_standard_tiers(MinorUnits::new(150_000), CurrencyCode::MYR)
+                .unwrap();
+        let route = PoolRoute::round_trip(IataCode::KUL, IataCode::NRT, date(1), date(8));
+        let mut pool = Pool::new("Tokyo in June", route, pricing, "ana", 2).unwrap();
+        pool.min_members = 2;
+        pool
+    }
+
+    fn sample_booking(user_id: &str) -> Booking {
+        // Every optional field set, so a round trip covers the whole schema
+        let mut offer = testing::offer("offer-1", 420_000);
+        let segment = &mut offer.outbound.segments[0];
+        segment.marketing_airline = Some(AirlineCode::NH);
+        segment.departure_date = date(1);
+        segment.arrival_date = date(2);
+        segment.aircraft = Some("A350".into());
+        segment.cabin = CabinClass::Business;
+        segment.booking_class = 'J';
+        segment.seats_remaining = Some(4);
+        let dob = time::Date::from_calendar_date(1948, time::Month::May, 9).unwrap();
+        let mut passenger = Passenger::adult("Tan", "Ah Kow", dob, Gender::Male);
+        passenger.id = 1;
+        passenger.assistance.wheelchair = Some(WheelchairNeed::Steps);
+        passenger
+            .seats
+            .push(SeatAssignment::new(0, "2a", MinorUnits::new(9_000)));
+        let mut booking = Booking::new(user_id, offer, vec![passenger]).unwrap();
+        booking.ancillaries.push(BookedAncillary {
+            id: 1,
+            kind: AncillaryKind::ExtraBaggage { weight_kg: 23 },
+            passenger_id: 1,
+            segment: Some(0),
+            price: MinorUnits::new(12_000),
+            added_at: booking.created_at,
+        });
+        booking
+    }
+
+    #[test]
+    fn test_pools_survive_reopen_with_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pool = pool();
+        {
+            let repo = DbPoolRepository::new(open(dir.path()));
+            PoolRepository::insert(&repo, &pool).unwrap();
+            let version = pool.version;
+            pool.join("ben", 1).unwrap();
+            PoolRepository::update(&repo, &pool, version).unwrap();
+        }
+
+        let repo = DbPoolRepository::new(open(dir.path()));
+        let stored = PoolRepository::get(&repo, &pool.id).unwrap().unwrap();
+        assert_eq!(stored.version, pool.version);
+        assert_eq!(stored.members.len(), 2);
+        assert_eq!(stored.route.return_date, Some(date(8)));
+        assert_eq!(stored.pricing.tiers.len(), pool.pricing.tiers.len());
+        assert_eq!(stored.history.len(), pool.history.len());
+
+        assert_eq!(repo.find_by_user("ben").unwrap().len(), 1);
+        assert_eq!(
+            repo.find_by_route(IataCode::KUL, IataCode::NRT)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(repo
+            .find_by_route(IataCode::NRT, IataCode::KUL)
+            .unwrap()
+            .is_empty());
+        assert_eq!(repo.open_pools(10).unwrap().len(), 1);
+
+        // Leaving and closing drop the pool out of the matching indexes
+        let version = stored.version;
+        let mut pool = stored;
+        pool.leave("ben").unwrap();
+        pool.cancel("ana", "Organizer cancelled").unwrap();
+        PoolRepository::update(&repo, &pool, version).unwrap();
+        assert!(repo.find_by_user("ben").unwrap().is_empty());
+        assert!(repo.open_pools(10).unwrap().is_empty());
+        assert_eq!(
+            repo.find_by_user("ana").unwrap()[0].status,
+            PoolStatus::Cancelled
+        );
+
+        assert!(PoolRepository::delete(&repo, &pool.id).unwrap());
+        assert!(repo.find_by_user("ana").unwrap().is_empty());
+        assert!(!PoolRepository::delete(&repo, &pool.id).unwrap());
+    }
+
+    #[test]
+    fn test_stale_update_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = DbBookingRepository::new(open(dir.path()));
+        let booking = sample_booking("user-1");
+        repo.insert(&booking).unwrap();
+        assert!(matches!(
+            repo.insert(&booking),
+            Err(CoreError::BookingAlreadyExists(_))
+        ));
+
+        let mut first = repo.get(&booking.pnr).unwrap().unwrap();
+        let mut second = first.clone();
+        first.confirm("GDS123", "system").unwrap();
+        repo.update(&first, booking.version).unwrap();
+
+        second.cancel("Changed plans", "user-1").unwrap();
+        let err = repo.update(&second, booking.version).unwrap_err();
+        assert!(matches!(
+            err,
+            CoreError::VersionConflict {
+                expected: 1,
+                actual: 2
+            }
+        ));
+        assert_eq!(err.http_status_code(), 409);
+
+        let stored = repo.get(&booking.pnr).unwrap().unwrap();
+        assert_eq!(stored.status, first.status);
+        assert_eq!(stored.provider_ref.as_deref(), Some("GDS123"));
+        let passenger = &stored.passengers[0];
+        assert_eq!(passenger.assistance.wheelchair, Some(WheelchairNeed::Steps));
+        assert_eq!(passenger.seats[0].seat_number, "2A");
+        assert_eq!(stored.ancillaries, booking.ancillaries);
+        let segment = &stored.offer.outbound.segments[0];
+        assert_eq!(segment.marketing_airline, Some(AirlineCode::NH));
+        assert_eq!(
+            segment.arrival_time,
+            time::Time::from_hms(7, 30, 0).unwrap()
+        );
+        assert_eq!(segment.booking_class, 'J');
+
+        assert_eq!(repo.find_by_user("user-1").unwrap().len(), 1);
+        assert_eq!(
+            repo.find_by_route(IataCode::KUL, IataCode::NRT)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(matches!(
+            repo.update(&sample_booking("user-2"), 1),
+            Err(CoreError::BookingNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_booking_references() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = DbBookingRepository::new(open(dir.path()));
+        let booking = sample_booking("user-1");
+        repo.insert(&booking).unwrap();
+
+        let found = repo.find_by_reference(&booking.reference).unwrap().unwrap();
+        assert_eq!(found.pnr, booking.pnr);
+        assert!(repo.find_by_reference("VAY-AAAAAAAA").unwrap().is_none());
+
+        let generator = ReferenceGenerator::new();
+        let pnr = repo.new_pnr(&generator).unwrap();
+        let reference = repo.new_reference(&generator).unwrap();
+        assert_ne!(pnr, booking.pnr);
+        let second = Booking::with_references(
+            pnr.clone(),
+            reference.clone(),
+            "user-1",
+            booking.offer.clone(),
+            vec![],
+        )
+        .unwrap();
+        repo.insert(&second).unwrap();
+        assert_eq!(
+            repo.find_by_reference(&reference).unwrap().unwrap().pnr,
+            pnr
+        );
+
+        // Every draw colliding is reported rather than looping forever
+        let generator = generator.with_max_attempts(2);
+        let err = generator
+            .unique_pnr(|_| Ok::<_, CoreError>(true))
+            .unwrap_err();
+        assert!(matches!(err, CoreError::Internal(_)));
+    }
+
+    #[test]
+    fn test_alerts_by_user_and_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = DbAlertRepository::new(open(dir.path()));
+        let mut tokyo = PriceAlert::price_below(
+            "alert-1",
+            "ana",
+            IataCode::KUL,
+            IataCode::NRT,
+            date(1),
+            MinorUnits::new(120_000),
+            CurrencyCode::MYR,
+        );
+        let bangkok = PriceAlert::any_price(
+            "alert-2",
+            "ana",
+            IataCode::KUL,
+            IataCode::BKK,
+            date(1),
+            CurrencyCode::MYR,
+        );
+        repo.insert(&tokyo).unwrap();
+        repo.insert(&bangkok).unwrap();
+
+        let version = tokyo.version;
+        tokyo.trigger(MinorUnits::new(110_000)).unwrap();
+        repo.update(&tokyo, version).unwrap();
+        assert!(repo.update(&tokyo, version).is_err());
+
+        let alerts = repo.find_by_user("ana").unwrap();
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].triggered_price, Some(MinorUnits::new(110_000)));
+        assert_eq!(alerts[0].status, tokyo.status);
+        let bkk = repo.find_by_route(IataCode::KUL, IataCode::BKK).unwrap();
+        assert_eq!(bkk.len(), 1);
+        assert_eq!(bkk[0].id, "alert-2");
+
+        assert!(repo.delete("alert-2").unwrap());
+        assert!(repo
+            .find_by_route(IataCode::KUL, IataCode::BKK)
+            .unwrap()
+            .is_empty());
+        assert!(repo.get("alert-2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_alert_conditions_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = DbAlertRepository::new(open(dir.path()));
+        let conditions = [
+            AlertCondition::DropsByPercent {
+                reference: MinorUnits::new(150_000),
+                percent: 15,
+            },
+            AlertCondition::BelowAverage,
+            AlertCondition::TrendTurnsFalling,
+            AlertCondition::CabinBelow {
+                cabin: vaya_common::CabinClass::Business,
+                threshold: MinorUnits::new(400_000),
+            },
+        ];
+        for (i, condition) in conditions.into_iter().enumerate() {
+            let mut alert = PriceAlert::new(
+                format!("alert-{}", i),
+                "ana",
+                IataCode::KUL,
+                IataCode::NRT,
+                date(1),
+                condition,
+                CurrencyCode::MYR,
+            );
+            alert.last_trend = Some(PriceTrend::Up);
+            repo.insert(&alert).unwrap();
+
+            let stored = repo.get(&alert.id).unwrap().unwrap();
+            assert_eq!(stored.condition, condition);
+            assert_eq!(stored.last_trend, Some(PriceTrend::Up));
+        }
+    }
+
+    #[test]
+    fn test_pool_service_runs_on_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = PoolService::new(DbPoolRepository::new(open(dir.path())));
+        let pool = pool();
+        service.create(&pool).unwrap();
+        assert!(matches!(
+            service.create(&pool),
+            Err(PoolError::PoolExists(_))
+        ));
+
+        let outcome = service.join(&pool.id, "ben", 1, "op-1").unwrap();
+        let replay = service.join(&pool.id, "ben", 1, "op-1").unwrap();
+        assert!(replay.replayed);
+        assert_eq!(outcome.pool.version, replay.pool.version);
+        assert_eq!(service.store().find_by_user("ben").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,1747 @@
+//! rkyv schemas for persisted pools, bookings, alerts and notification
+//! preferences
+//!
+//! The domain types carry `time` dates, `&'static str` SSR codes and
+//! fixed-size code newtypes that rkyv can't validate, so each is stored
+//! through a `Stored*` mirror built from primitives. Codes (airports,
+//! currencies, airlines) are stored as strings, dates as Julian days and
+//! times as seconds since midnight.
+//!
+//! Fieldless enums without a string form are stored as their position in
+//! a variant list below. The lists are part of the schema: append new
+//! variants, never reorder or remove them.
+
+use rkyv::ser::serializers::AllocSerializer;
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{AlignedVec, Archive, CheckBytes, Deserialize, Serialize};
+use time::{Date, Time};
+
+use vaya_book::{
+    AncillaryKind, AssistanceNeeds, AssistanceRequest, BookedAncillary, Booking, BookingNote,
+    BookingStatus, ChangeQuote, ContactDetails, CountryCode, DocumentType, FrequentFlyer,
+    InsuranceCover, MealPreference, MobilityAid, NoteCategory, NoteVisibility, Passenger,
+    PaymentMethod, PaymentRecord, PaymentStatus, PendingChange, RefundRecord, RefundStatus,
+    SeatAssignment, SeatPreference, SpecialRequest, SsrStatus, Title, TravelDocument,
+    WheelchairNeed,
+};
+use vaya_common::{AirlineCode, CurrencyCode, Gender, IataCode, MinorUnits};
+use vaya_oracle::{AlertCondition, AlertStatus, AlertTrigger, PriceAlert, PriceTrend};
+use vaya_pool::{
+    AppliedOperation, MemberRefund, MemberRefundStatus, Pool, PoolDeadline, PoolMember,
+    PoolOperation, PoolRoute, PoolStatus, PriceLock, PricingTier, TieredPricing, WaitlistEntry,
+};
+use vaya_search::{
+    BaggageAllowance, CabinClass, FlightLeg, FlightOffer, FlightSegment, PassengerType,
+    PriceBreakdown,
+};
+
+use crate::error::{CoreError, CoreResult};
+use crate::preferences::{
+    DigestFrequency, NotificationCategory, NotificationChannel, NotificationPreferences, QuietHours,
+};
+
+const CABINS: [CabinClass; 4] = [
+    CabinClass::Economy,
+    CabinClass::PremiumEconomy,
+    CabinClass::Business,
+    CabinClass::First,
+];
+
+const PASSENGER_TYPES: [PassengerType; 3] = [
+    PassengerType::Adult,
+    PassengerType::Child,
+    PassengerType::Infant,
+];
+
+const TITLES: [Title; 7] = [
+    Title::Mr,
+    Title::Mrs,
+    Title::Ms,
+    Title::Miss,
+    Title::Mstr,
+    Title::Dr,
+    Title::Prof,
+];
+
+const GENDERS: [Gender; 4] = [Gender::Unknown, Gender::Male, Gender::Female, Gender::Other];
+
+const DOCUMENT_TYPES: [DocumentType; 4] = [
+    DocumentType::Passport,
+    DocumentType::NationalId,
+    DocumentType::DrivingLicense,
+    DocumentType::Other,
+];
+
+const SPECIAL_REQUESTS: [SpecialRequest; 13] = [
+    SpecialRequest::Wheelchair,
+    SpecialRequest::WheelchairRamp,
+    SpecialRequest::WheelchairSteps,
+    SpecialRequest::WheelchairCabin,
+    SpecialRequest::BlindPassenger,
+    SpecialRequest::DeafPassenger,
+    SpecialRequest::UnaccompaniedMinor,
+    SpecialRequest::MeetAssist,
+    SpecialRequest::ExtraLegroom,
+    SpecialRequest::BassinetRequired,
+    SpecialRequest::OxygenRequired,
+    SpecialRequest::StretcherRequired,
+    SpecialRequest::ServiceAnimal,
+];
+
+const MEALS: [MealPreference; 14] = [
+    MealPreference::Regular,
+    MealPreference::Vegetarian,
+    MealPreference::VeganMeal,
+    MealPreference::Kosher,
+    MealPreference::Halal,
+    MealPreference::Hindu,
+    MealPreference::GlutenFree,
+    MealPreference::LowSodium,
+    MealPreference::LowFat,
+    MealPreference::Diabetic,
+    MealPreference::ChildMeal,
+    MealPreference::InfantMeal,
+    MealPreference::SeafoodMeal,
+    MealPreference::FruitPlatter,
+];
+
+const SEAT_PREFERENCES: [SeatPreference; 8] = [
+    SeatPreference::Window,
+    SeatPreference::Aisle,
+    SeatPreference::Middle,
+    SeatPreference::FrontOfCabin,
+    SeatPreference::RearOfCabin,
+    SeatPreference::ExitRow,
+    SeatPreference::Bulkhead,
+    SeatPreference::NoPreference,
+];
+
+const WHEELCHAIR_NEEDS: [WheelchairNeed; 3] = [
+    WheelchairNeed::Ramp,
+    WheelchairNeed::Steps,
+    WheelchairNeed::Cabin,
+];
+
+const SSR_STATUSES: [SsrStatus; 3] = [
+    SsrStatus::Requested,
+    SsrStatus::Confirmed,
+    SsrStatus::Declined,
+];
+
+/// Assistance SSR codes a passenger can be tracked against
+const SSR_CODES: [&str; 11] = [
+    "WCHR", "WCHS", "WCHC", "WCMP", "WCBD", "WCBW", "WCLB", "BLND", "DEAF", "DPNA", "SVAN",
+];
+
+const PAYMENT_METHODS: [PaymentMethod; 8] = [
+    PaymentMethod::Card,
+    PaymentMethod::BankTransfer,
+    PaymentMethod::PayNow,
+    PaymentMethod::GrabPay,
+    PaymentMethod::ShopeePay,
+    PaymentMethod::Wallet,
+    PaymentMethod::Points,
+    PaymentMethod::Invoice,
+];
+
+const PAYMENT_STATUSES: [PaymentStatus; 9] = [
+    PaymentStatus::Pending,
+    PaymentStatus::AwaitingConfirmation,
+    PaymentStatus::Authorized,
+    PaymentStatus::Completed,
+    PaymentStatus::Failed,
+    PaymentStatus::Refunded,
+    PaymentStatus::PartiallyRefunded,
+    PaymentStatus::Disputed,
+    PaymentStatus::Cancelled,
+];
+
+const REFUND_STATUSES: [RefundStatus; 6] = [
+    RefundStatus::Pending,
+    RefundStatus::Approved,
+    RefundStatus::Processing,
+    RefundStatus::Completed,
+    RefundStatus::Failed,
+    RefundStatus::Rejected,
+];
+
+const INSURANCE_COVERS: [InsuranceCover; 3] = [
+    InsuranceCover::Basic,
+    InsuranceCover::Standard,
+    InsuranceCover::Premium,
+];
+
+const MEMBER_REFUND_STATUSES: [MemberRefundStatus; 2] =
+    [MemberRefundStatus::Pending, MemberRefundStatus::Refunded];
+
+const POOL_DEADLINES: [PoolDeadline; 2] = [PoolDeadline::Join, PoolDeadline::Contribution];
+
+const ALERT_TRIGGERS: [AlertTrigger; 7] = [
+    AlertTrigger::PriceDropsBelow,
+    AlertTrigger::PriceDropsBy,
+    AlertTrigger::AnyPrice,
+    AlertTrigger::BestPrice,
+    AlertTrigger::PriceDropsBelowAverage,
+    AlertTrigger::TrendFalling,
+    AlertTrigger::CabinPriceBelow,
+];
+
+/// Cabins watched by alerts (the oracle's cabin type, not the search one)
+const ALERT_CABINS: [vaya_common::CabinClass; 4] = [
+    vaya_common::CabinClass::Economy,
+    vaya_common::CabinClass::PremiumEconomy,
+    vaya_common::CabinClass::Business,
+    vaya_common::CabinClass::First,
+];
+
+const PRICE_TRENDS: [PriceTrend; 5] = [
+    PriceTrend::StrongUp,
+    PriceTrend::Up,
+    PriceTrend::Stable,
+    PriceTrend::Down,
+    PriceTrend::StrongDown,
+];
+
+const ALERT_STATUSES: [AlertStatus; 5] = [
+    AlertStatus::Active,
+    AlertStatus::Triggered,
+    AlertStatus::Paused,
+    AlertStatus::Expired,
+    AlertStatus::Cancelled,
+];
+
+const NOTIFICATION_CATEGORIES: [NotificationCategory; 6] = NotificationCategory::ALL;
+
+const NOTIFICATION_CHANNELS: [NotificationChannel; 4] = NotificationChannel::ALL;
+
+const DIGEST_FREQUENCIES: [DigestFrequency; 3] = DigestFrequency::ALL;
+
+/// Serialize a stored record
+pub(super) fn encode<T>(value: &T) -> CoreResult<AlignedVec>
+where
+    T: Serialize<AllocSerializer<1024>>,
+{
+    rkyv::to_bytes::<_, 1024>(value)
+        .map_err(|e| CoreError::Database(format!("Failed to serialize record: {}", e)))
+}
+
+/// Validate and deserialize a stored record
+pub(super) fn decode<T>(bytes: &[u8]) -> CoreResult<T>
+where
+    T: Archive,
+    for<'a> T::Archived: CheckBytes<DefaultValidator<'a>> + Deserialize<T, rkyv::Infallible>,
+{
+    // Values come back from the database without alignment guarantees
+    let mut aligned = AlignedVec::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    let archived = rkyv::check_archived_root::<T>(&aligned)
+        .map_err(|e| CoreError::Database(format!("Corrupt record: {}", e)))?;
+    archived
+        .deserialize(&mut rkyv::Infallible)
+        .map_err(|_| CoreError::Database("Corrupt record".into()))
+}
+
+fn corrupt(what: &str) -> CoreError {
+    CoreError::Database(format!("Corrupt {} in stored record", what))
+}
+
+/// Position of `value` in a variant list
+fn code<T: Copy + PartialEq>(variants: &[T], value: T) -> u8 {
+    variants.iter().position(|v| *v == value).unwrap_or(0) as u8
+}
+
+fn variant<T: Copy>(variants: &[T], code: u8, what: &str) -> CoreResult<T> {
+    variants
+        .get(code as usize)
+        .copied()
+        .ok_or_else(|| corrupt(what))
+}
+
+fn date_from(days: i32) -> CoreResult<Date> {
+    Date::from_julian_day(days).map_err(|_| corrupt("date"))
+}
+
+fn time_from(secs: u32) -> CoreResult<Time> {
+    Time::from_hms(
+        (secs / 3600) as u8,
+        (secs / 60 % 60) as u8,
+        (secs % 60) as u8,
+    )
+    .map_err(|_| corrupt("time"))
+}
+
+fn time_to(time: Time) -> u32 {
+    let (h, m, s) = time.as_hms();
+    u32::from(h) * 3600 + u32::from(m) * 60 + u32::from(s)
+}
+
+fn money(amount: Option<MinorUnits>) -> Option<i64> {
+    amount.map(|a| a.as_i64())
+}
+
+fn booking_status(s: &str) -> CoreResult<BookingStatus> {
+    BookingStatus::parse(s).ok_or_else(|| corrupt("booking status"))
+}
+
+fn pool_status(s: &str) -> CoreResult<PoolStatus> {
+    PoolStatus::parse(s).ok_or_else(|| corrupt("pool status"))
+}
+
+/// Serialized ID list of an index key
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub(super) struct StoredIds {
+    pub ids: Vec<String>,
+}
+
+// ============================================================================
+// Flight offers (shared by pools and bookings)
+// ============================================================================
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub(super) struct StoredOffer {
+    id: String,
+    outbound: StoredLeg,
+    inbound: Option<StoredLeg>,
+    base_fare: i64,
+    taxes: i64,
+    surcharges: i64,
+    seats: i64,
+    currency: String,
+    price_per_pax: Vec<StoredPaxPrice>,
+    expires_at: Option<i64>,
+    provider: String,
+    refundable: bool,
+    changeable: bool,
+    baggage: Option<StoredBaggage>,
+    fare_rules: Option<String>,
+    self_transfer: bool,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredLeg {
+    segments: Vec<StoredSegment>,
+    total_duration_minutes: u16,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredSegment {
+    airline: String,
+    flight_number: String,
+    marketing_airline: Option<String>,
+    origin: String,
+    destination: String,
+    departure_date: i32,
+    departure_time: u32,
+    arrival_date: i32,
+    arrival_time: u32,
+    duration_minutes: u16,
+    aircraft: Option<String>,
+    cabin: u8,
+    booking_class: u32,
+    seats_remaining: Option<u8>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredPaxPrice {
+    pax_type: u8,
+    amount: i64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredBaggage {
+    carry_on: u8,
+    carry_on_weight_kg: Option<u8>,
+    checked_bags: u8,
+    checked_weight_kg: Option<u8>,
+}
+
+impl From<&FlightOffer> for StoredOffer {
+    fn from(offer: &FlightOffer) -> Self {
+        Self {
+            id: offer.id.clone(),
+            outbound: StoredLeg::from(&offer.outbound),
+            inbound: offer.inbound.as_ref().map(StoredLeg::from),
+            base_fare: offer.price.base_fare.as_i64(),
+            taxes: offer.price.taxes.as_i64(),
+            surcharges: offer.price.surcharges.as_i64(),
+            seats: offer.price.seats.as_i64(),
+            currency: offer.price.currency.as_str().to_string(),
+            price_per_pax: offer
+                .price_per_pax
+                .iter()
+                .map(|(pax_type, amount)| StoredPaxPrice {
+                    pax_type: code(&PASSENGER_TYPES, *pax_type),
+                    amount: amount.as_i64(),
+                })
+                .collect(),
+            expires_at: offer.expires_at,
+            provider: offer.provider.clone(),
+            refundable: offer.refundable,
+            changeable: offer.changeable,
+            baggage: offer.baggage.as_ref().map(|b| StoredBaggage {
+                carry_on: b.carry_on,
+                carry_on_weight_kg: b.carry_on_weight_kg,
+                checked_bags: b.checked_bags,
+                checked_weight_kg: b.checked_weight_kg,
+            }),
+            fare_rules: offer.fare_rules.clone(),
+            self_transfer: offer.self_transfer,
+        }
+    }
+}
+
+impl TryFrom<StoredOffer> for FlightOffer {
+    type Error = CoreError;
+
+    fn try_from(stored: StoredOffer) -> CoreResult<Self> {
+        Ok(Self {
+            id: stored.id,
+            outbound: stored.outbound.try_into()?,
+            inbound: stored.inbound.map(TryInto::try_into).transpose()?,
+            price: PriceBreakdown {
+                base_fare: MinorUnits::new(stored.base_fare),
+                taxes: MinorUnits::new(stored.taxes),
+                surcharges: MinorUnits::new(stored.surcharges),
+                seats: MinorUnits::new(stored.seats),
+                currency: CurrencyCode::new(&stored.currency),
+            },
+            price_per_pax: stored
+                .price_per_pax
+                .into_iter()
+                .map(|p| {
+                    Ok((
+                        variant(&PASSENGER_TYPES, p.pax_type, "passenger type")?,
+                        MinorUnits::new(p.amount),
+                    ))
+                })
+                .collect::<CoreResult<_>>()?,
+            expires_at: stored.expires_at,
+            provider: stored.provider,
+            refundable: stored.refundable,
+            changeable: stored.changeable,
+            baggage: stored.baggage.map(|b| BaggageAllowance {
+                carry_on: b.carry_on,
+                carry_on_weight_kg: b.carry_on_weight_kg,
+                checked_bags: b.checked_bags,
+                checked_weight_kg: b.checked_weight_kg,
+            }),
+            fare_rules: stored.fare_rules,
+            self_transfer: stored.self_transfer,
+            // Search-time annotation only
+            variation: None,
+        })
+    }
+}
+
+impl From<&FlightLeg> for StoredLeg {
+    fn from(leg: &FlightLeg) -> Self {
+        Self {
+            segments: leg
+                .segments
+                .iter()
+                .map(|s| StoredSegment {
+                    airline: s.airline.as_str().to_string(),
+                    flight_number: s.flight_number.clone(),
+                    marketing_airline: s.marketing_airline.map(|a| a.as_str().to_string()),
+                    origin: s.origin.as_str().to_string(),
+                    destination: s.destination.as_str().to_string(),
+                    departure_date: s.departure_date.to_julian_day(),
+                    departure_time: time_to(s.departure_time),
+                    arrival_date: s.arrival_date.to_julian_day(),
+                    arrival_time: time_to(s.arrival_time),
+                    duration_minutes: s.duration_minutes,
+                    aircraft: s.aircraft.clone(),
+                    cabin: code(&CABINS, s.cabin),
+                    booking_class: u32::from(s.booking_class),
+                    seats_remaining: s.seats_remaining,
+                })
+                .collect(),
+            total_duration_minutes: leg.total_duration_minutes,
+        }
+    }
+}
+
+impl TryFrom<StoredLeg> for FlightLeg {
+    type Error = CoreError;
+
+    fn try_from(stored: StoredLeg) -> CoreResult<Self> {
+        let segments = stored
+            .segments
+            .into_iter()
+            .map(|s| {
+                Ok(FlightSegment {
+                    airline: AirlineCode::new(&s.airline),
+                    flight_number: s.flight_number,
+                    marketing_airline: s.marketing_airline.as_deref().map(AirlineCode::new),
+                    origin: IataCode::new(&s.origin),
+                    destination: IataCode::new(&s.destination),
+                    departure_date: date_from(s.departure_date)?,
+                    departure_time: time_from(s.departure_time)?,
+                    arrival_date: date_from(s.arrival_date)?,
+                    arrival_time: time_from(s.arrival_time)?,
+                    duration_minutes: s.duration_minutes,
+                    aircraft: s.aircraft,
+                    cabin: variant(&CABINS, s.cabin, "cabin")?,
+                    booking_class: char::from_u32(s.booking_class)
+                        .ok_or_else(|| corrupt("booking class"))?,
+                    seats_remaining: s.seats_remaining,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        Ok(Self {
+            segments,
+            total_duration_minutes: stored.total_duration_minutes,
+        })
+    }
+}
+
+// ============================================================================
+// Bookings
+// ============================================================================
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub(super) struct StoredBooking {
+    pnr: String,
+    reference: String,
+    user_id: String,
+    status: String,
+    offer: StoredOffer,
+    passengers: Vec<StoredPassenger>,
+    payments: Vec<StoredPayment>,
+    refunds: Vec<StoredBookingRefund>,
+    total_price: i64,
+    currency: String,
+    created_at: i64,
+    updated_at: i64,
+    confirm_deadline: Option<i64>,
+    payment_deadline: Option<i64>,
+    ticketing_deadline: Option<i64>,
+    provider_ref: Option<String>,
+    airline_pnr: Option<String>,
+    ticket_numbers: Vec<String>,
+    history: Vec<StoredStatusChange>,
+    version: u32,
+    notes: Vec<StoredNote>,
+    tags: Vec<String>,
+    pending_change: Option<StoredPendingChange>,
+    ancillaries: Vec<StoredAncillary>,
+}
+
+/// Status change of a booking or pool, statuses by name
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredStatusChange {
+    from: Option<String>,
+    to: String,
+    timestamp: i64,
+    reason: String,
+    actor: String,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredPassenger {
+    id: u8,
+    pax_type: u8,
+    title: u8,
+    first_name: String,
+    last_name: String,
+    middle_name: Option<String>,
+    date_of_birth: i32,
+    gender: u8,
+    nationality: String,
+    document: Option<StoredDocument>,
+    contact: Option<StoredContact>,
+    frequent_flyer: Vec<StoredFrequentFlyer>,
+    special_requests: Vec<u8>,
+    meal_preference: Option<u8>,
+    seat_preference: Option<u8>,
+    redress_number: Option<String>,
+    known_traveler_number: Option<String>,
+    assistance: StoredAssistance,
+    assistance_requests: Vec<StoredAssistanceRequest>,
+    seats: Vec<StoredSeat>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredDocument {
+    doc_type: u8,
+    number: String,
+    issuing_country: String,
+    issue_date: Option<i32>,
+    expiry_date: i32,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredContact {
+    email: String,
+    phone_country: String,
+    phone_number: String,
+    emergency_name: Option<String>,
+    emergency_phone: Option<String>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredFrequentFlyer {
+    airline: String,
+    number: String,
+}
+
+/// Assistance needs; `mobility_aid` is 0 manual, 1 dry, 2 wet, 3 lithium
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredAssistance {
+    wheelchair: Option<u8>,
+    mobility_aid: Option<u8>,
+    watt_hours: u16,
+    visual: bool,
+    hearing: bool,
+    cognitive: bool,
+    service_animal: bool,
+    remarks: Option<String>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredAssistanceRequest {
+    code: String,
+    status: u8,
+    updated_at: i64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredSeat {
+    segment: u64,
+    seat_number: String,
+    price: i64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredPayment {
+    id: String,
+    amount: i64,
+    currency: String,
+    method: u8,
+    status: u8,
+    provider_ref: Option<String>,
+    timestamp: i64,
+    refunded: i64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredBookingRefund {
+    id: String,
+    payment_id: String,
+    amount: i64,
+    currency: String,
+    status: u8,
+    reason: String,
+    provider_ref: Option<String>,
+    timestamp: i64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredNote {
+    content: String,
+    author: String,
+    timestamp: i64,
+    category: String,
+    visibility: String,
+    mentions: Vec<String>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredPendingChange {
+    offer_id: String,
+    currency: String,
+    current_fare: i64,
+    new_fare: i64,
+    fare_difference: i64,
+    change_fee: i64,
+    amount_due: i64,
+    residual: i64,
+    quoted_at: i64,
+    expires_at: Option<i64>,
+    offer: StoredOffer,
+    previous_status: String,
+    requested_by: String,
+}
+
+/// Booked extra; `kind` is 0 baggage, 1 meal, 2 insurance, 3 priority
+/// boarding, with `detail` holding the weight, meal or cover
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredAncillary {
+    id: u32,
+    kind: u8,
+    detail: u8,
+    passenger_id: u8,
+    segment: Option<u64>,
+    price: i64,
+    added_at: i64,
+}
+
+impl From<&Booking> for StoredBooking {
+    fn from(booking: &Booking) -> Self {
+        Self {
+            pnr: booking.pnr.clone(),
+            reference: booking.reference.clone(),
+            user_id: booking.user_id.clone(),
+            status: booking.status.as_str().to_string(),
+            offer: StoredOffer::from(&booking.offer),
+            passengers: booking
+                .passengers
+                .iter()
+                .map(StoredPassenger::from)
+                .collect(),
+            payments: booking
+                .payments
+                .iter()
+                .map(|p| StoredPayment {
+                    id: p.id.clone(),
+                    amount: p.amount.as_i64(),
+                    currency: p.currency.as_str().to_string(),
+                    method: code(&PAYMENT_METHODS, p.method),
+                    status: code(&PAYMENT_STATUSES, p.status),
+                    provider_ref: p.provider_ref.clone(),
+                    timestamp: p.timestamp,
+                    refunded: p.refunded.as_i64(),
+                })
+                .collect(),
+            refunds: booking
+                .refunds
+                .iter()
+                .map(|r| StoredBookingRefund {
+                    id: r.id.clone(),
+                    payment_id: r.payment_id.clone(),
+                    amount: r.amount.as_i64(),
+                    currency: r.currency.as_str().to_string(),
+                    status: code(&REFUND_STATUSES, r.status),
+                    reason: r.reason.clone(),
+                    provider_ref: r.provider_ref.clone(),
+                    timestamp: r.timestamp,
+                })
+                .collect(),
+            total_price: booking.total_price.as_i64(),
+            currency: booking.currency.as_str().to_string(),
+            created_at: booking.created_at,
+            updated_at: booking.updated_at,
+            confirm_deadline: booking.confirm_deadline,
+            payment_deadline: booking.payment_deadline,
+            ticketing_deadline: booking.ticketing_deadline,
+            provider_ref: booking.provider_ref.clone(),
+            airline_pnr: booking.airline_pnr.clone(),
+            ticket_numbers: booking.ticket_numbers.clone(),
+            history: booking
+                .history
+                .iter()
+                .map(|h| StoredStatusChange {
+                    from: h.from.map(|s| s.as_str().to_string()),
+                    to: h.to.as_str().to_string(),
+                    timestamp: h.timestamp,
+                    reason: h.reason.clone(),
+                    actor: h.actor.clone(),
+                })
+                .collect(),
+            version: booking.version,
+            notes: booking
+                .notes
+                .iter()
+                .map(|n| StoredNote {
+                    content: n.content.clone(),
+                    author: n.author.clone(),
+                    timestamp: n.timestamp,
+                    category: n.category.as_str().to_string(),
+                    visibility: n.visibility.as_str().to_string(),
+                    mentions: n.mentions.clone(),
+                })
+                .collect(),
+            tags: booking.tags.clone(),
+            pending_change: booking
+                .pending_change
+                .as_ref()
+                .map(|c| StoredPendingChange {
+                    offer_id: c.quote.offer_id.clone(),
+                    currency: c.quote.currency.as_str().to_string(),
+                    current_fare: c.quote.current_fare.as_i64(),
+                    new_fare: c.quote.new_fare.as_i64(),
+                    fare_difference: c.quote.fare_difference.as_i64(),
+                    change_fee: c.quote.change_fee.as_i64(),
+                    amount_due: c.quote.amount_due.as_i64(),
+                    residual: c.quote.residual.as_i64(),
+                    quoted_at: c.quote.quoted_at,
+                    expires_at: c.quote.expires_at,
+                    offer: StoredOffer::from(&c.offer),
+                    previous_status: c.previous_status.as_str().to_string(),
+                    requested_by: c.requested_by.clone(),
+                }),
+            ancillaries: booking
+                .ancillaries
+                .iter()
+                .map(|a| {
+                    let (kind, detail) = match a.kind {
+                        AncillaryKind::ExtraBaggage { weight_kg } => (0, weight_kg),
+                        AncillaryKind::Meal(meal) => (1, code(&MEALS, meal)),
+                        AncillaryKind::Insurance(cover) => (2, code(&INSURANCE_COVERS, cover)),
+                        AncillaryKind::PriorityBoarding => (3, 0),
+                    };
+                    StoredAncillary {
+                        id: a.id,
+                        kind,
+                        detail,
+                        passenger_id: a.passenger_id,
+                        segment: a.segment.map(|s| s as u64),
+                        price: a.price.as_i64(),
+                        added_at: a.added_at,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredBooking> for Booking {
+    type Error = CoreError;
+
+    fn try_from(stored: StoredBooking) -> CoreResult<Self> {
+        let payments = stored
+            .payments
+            .into_iter()
+            .map(|p| {
+                Ok(PaymentRecord {
+                    id: p.id,
+                    amount: MinorUnits::new(p.amount),
+                    currency: CurrencyCode::new(&p.currency),
+                    method: variant(&PAYMENT_METHODS, p.method, "payment method")?,
+                    status: variant(&PAYMENT_STATUSES, p.status, "payment status")?,
+                    provider_ref: p.provider_ref,
+                    timestamp: p.timestamp,
+                    refunded: MinorUnits::new(p.refunded),
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        let refunds = stored
+            .refunds
+            .into_iter()
+            .map(|r| {
+                Ok(RefundRecord {
+                    id: r.id,
+                    payment_id: r.payment_id,
+                    amount: MinorUnits::new(r.amount),
+                    currency: CurrencyCode::new(&r.currency),
+                    status: variant(&REFUND_STATUSES, r.status, "refund status")?,
+                    reason: r.reason,
+                    provider_ref: r.provider_ref,
+                    timestamp: r.timestamp,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        let history = stored
+            .history
+            .into_iter()
+            .map(|h| {
+                Ok(vaya_book::StatusChange {
+                    from: h.from.as_deref().map(booking_status).transpose()?,
+                    to: booking_status(&h.to)?,
+                    timestamp: h.timestamp,
+                    reason: h.reason,
+                    actor: h.actor,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        let notes = stored
+            .notes
+            .into_iter()
+            .map(|n| {
+                Ok(BookingNote {
+                    content: n.content,
+                    author: n.author,
+                    timestamp: n.timestamp,
+                    category: NoteCategory::parse(&n.category)
+                        .ok_or_else(|| corrupt("note category"))?,
+                    visibility: NoteVisibility::parse(&n.visibility)
+                        .ok_or_else(|| corrupt("note visibility"))?,
+                    mentions: n.mentions,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        let pending_change = stored
+            .pending_change
+            .map(|c| {
+                Ok::<_, CoreError>(PendingChange {
+                    quote: ChangeQuote {
+                        offer_id: c.offer_id,
+                        currency: CurrencyCode::new(&c.currency),
+                        current_fare: MinorUnits::new(c.current_fare),
+                        new_fare: MinorUnits::new(c.new_fare),
+                        fare_difference: MinorUnits::new(c.fare_difference),
+                        change_fee: MinorUnits::new(c.change_fee),
+                        amount_due: MinorUnits::new(c.amount_due),
+                        residual: MinorUnits::new(c.residual),
+                        quoted_at: c.quoted_at,
+                        expires_at: c.expires_at,
+                    },
+                    offer: c.offer.try_into()?,
+                    previous_status: booking_status(&c.previous_status)?,
+                    requested_by: c.requested_by,
+                })
+            })
+            .transpose()?;
+        let ancillaries = stored
+            .ancillaries
+            .into_iter()
+            .map(|a| {
+                let kind = match a.kind {
+                    0 => AncillaryKind::ExtraBaggage {
+                        weight_kg: a.detail,
+                    },
+                    1 => AncillaryKind::Meal(variant(&MEALS, a.detail, "meal")?),
+                    2 => AncillaryKind::Insurance(variant(
+                        &INSURANCE_COVERS,
+                        a.detail,
+                        "insurance cover",
+                    )?),
+                    3 => AncillaryKind::PriorityBoarding,
+                    _ => return Err(corrupt("ancillary kind")),
+                };
+                Ok(BookedAncillary {
+                    id: a.id,
+                    kind,
+                    passenger_id: a.passenger_id,
+                    segment: a.segment.map(|s| s as usize),
+                    price: MinorUnits::new(a.price),
+                    added_at: a.added_at,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+
+        Ok(Self {
+            pnr: stored.pnr,
+            reference: stored.reference,
+            user_id: stored.user_id,
+            status: booking_status(&stored.status)?,
+            offer: stored.offer.try_into()?,
+            passengers: stored
+                .passengers
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<CoreResult<_>>()?,
+            payments,
+            refunds,
+            total_price: MinorUnits::new(stored.total_price),
+            currency: CurrencyCode::new(&stored.currency),
+            created_at: stored.created_at,
+            updated_at: stored.updated_at,
+            confirm_deadline: stored.confirm_deadline,
+            payment_deadline: stored.payment_deadline,
+            ticketing_deadline: stored.ticketing_deadline,
+            provider_ref: stored.provider_ref,
+            airline_pnr: stored.airline_pnr,
+            ticket_numbers: stored.ticket_numbers,
+            history,
+            version: stored.version,
+            notes,
+            tags: stored.tags,
+            pending_change,
+            ancillaries,
+        })
+    }
+}
+
+impl From<&Passenger> for StoredPassenger {
+    fn from(p: &Passenger) -> Self {
+        let (mobility_aid, watt_hours) = match p.assistance.mobility_aid {
+            None => (None, 0),
+            Some(MobilityAid::Manual) => (Some(0), 0),
+            Some(MobilityAid::DryBattery) => (Some(1), 0),
+            Some(MobilityAid::WetBattery) => (Some(2), 0),
+            Some(MobilityAid::Lithium { watt_hours }) => (Some(3), watt_hours),
+        };
+        Self {
+            id: p.id,
+            pax_type: code(&PASSENGER_TYPES, p.pax_type),
+            title: code(&TITLES, p.title),
+            first_name: p.first_name.clone(),
+            last_name: p.last_name.clone(),
+            middle_name: p.middle_name.clone(),
+            date_of_birth: p.date_of_birth.to_julian_day(),
+            gender: code(&GENDERS, p.gender),
+            nationality: p.nationality.as_str().to_string(),
+            document: p.document.as_ref().map(|d| StoredDocument {
+                doc_type: code(&DOCUMENT_TYPES, d.doc_type),
+                number: d.number.clone(),
+                issuing_country: d.issuing_country.as_str().to_string(),
+                issue_date: d.issue_date.map(Date::to_julian_day),
+                expiry_date: d.expiry_date.to_julian_day(),
+            }),
+            contact: p.contact.as_ref().map(|c| StoredContact {
+                email: c.email.clone(),
+                phone_country: c.phone_country.clone(),
+                phone_number: c.phone_number.clone(),
+                emergency_name: c.emergency_name.clone(),
+                emergency_phone: c.emergency_phone.clone(),
+            }),
+            frequent_flyer: p
+                .frequent_flyer
+                .iter()
+                .map(|f| StoredFrequentFlyer {
+                    airline: f.airline.clone(),
+                    number: f.number.clone(),
+                })
+                .collect(),
+            special_requests: p
+                .special_requests
+                .iter()
+                .map(|r| code(&SPECIAL_REQUESTS, *r))
+                .collect(),
+            meal_preference: p.meal_preference.map(|m| code(&MEALS, m)),
+            seat_preference: p.seat_preference.map(|s| code(&SEAT_PREFERENCES, s)),
+            redress_number: p.redress_number.clone(),
+            known_traveler_number: p.known_traveler_number.clone(),
+            assistance: StoredAssistance {
+                wheelchair: p.assistance.wheelchair.map(|w| code(&WHEELCHAIR_NEEDS, w)),
+                mobility_aid,
+                watt_hours,
+                visual: p.assistance.visual,
+                hearing: p.assistance.hearing,
+                cognitive: p.assistance.cognitive,
+                service_animal: p.assistance.service_animal,
+                remarks: p.assistance.remarks.clone(),
+            },
+            assistance_requests: p
+                .assistance_requests
+                .iter()
+                .map(|r| StoredAssistanceRequest {
+                    code: r.code.to_string(),
+                    status: code(&SSR_STATUSES, r.status),
+                    updated_at: r.updated_at,
+                })
+                .collect(),
+            seats: p
+                .seats
+                .iter()
+                .map(|s| StoredSeat {
+                    segment: s.segment as u64,
+                    seat_number: s.seat_number.clone(),
+                    price: s.price.as_i64(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredPassenger> for Passenger {
+    type Error = CoreError;
+
+    fn try_from(p: StoredPassenger) -> CoreResult<Self> {
+        let document = p
+            .document
+            .map(|d| {
+                Ok::<_, CoreError>(TravelDocument {
+                    doc_type: variant(&DOCUMENT_TYPES, d.doc_type, "document type")?,
+                    number: d.number,
+                    issuing_country: CountryCode::new(&d.issuing_country),
+                    issue_date: d.issue_date.map(date_from).transpose()?,
+                    expiry_date: date_from(d.expiry_date)?,
+                })
+            })
+            .transpose()?;
+        let mobility_aid = match p.assistance.mobility_aid {
+            None => None,
+            Some(0) => Some(MobilityAid::Manual),
+            Some(1) => Some(MobilityAid::DryBattery),
+            Some(2) => Some(MobilityAid::WetBattery),
+            Some(3) => Some(MobilityAid::Lithium {
+                watt_hours: p.assistance.watt_hours,
+            }),
+            Some(_) => return Err(corrupt("mobility aid")),
+        };
+        let assistance_requests = p
+            .assistance_requests
+            .into_iter()
+            .map(|r| {
+                Ok(AssistanceRequest {
+                    code: SSR_CODES
+                        .into_iter()
+                        .find(|c| *c == r.code)
+                        .ok_or_else(|| corrupt("SSR code"))?,
+                    status: variant(&SSR_STATUSES, r.status, "SSR status")?,
+                    updated_at: r.updated_at,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+
+        Ok(Self {
+            id: p.id,
+            pax_type: variant(&PASSENGER_TYPES, p.pax_type, "passenger type")?,
+            title: variant(&TITLES, p.title, "title")?,
+            first_name: p.first_name,
+            last_name: p.last_name,
+            middle_name: p.middle_name,
+            date_of_birth: date_from(p.date_of_birth)?,
+            gender: variant(&GENDERS, p.gender, "gender")?,
+            nationality: CountryCode::new(&p.nationality),
+            document,
+            contact: p.contact.map(|c| ContactDetails {
+                email: c.email,
+                phone_country: c.phone_country,
+                phone_number: c.phone_number,
+                emergency_name: c.emergency_name,
+                emergency_phone: c.emergency_phone,
+            }),
+            frequent_flyer: p
+                .frequent_flyer
+                .into_iter()
+                .map(|f| FrequentFlyer {
+                    airline: f.airline,
+                    number: f.number,
+                })
+                .collect(),
+            special_requests: p
+                .special_requests
+                .into_iter()
+                .map(|r| variant(&SPECIAL_REQUESTS, r, "special request"))
+                .collect::<CoreResult<_>>()?,
+            meal_preference: p
+                .meal_preference
+                .map(|m| variant(&MEALS, m, "meal"))
+                .transpose()?,
+            seat_preference: p
+                .seat_preference
+                .map(|s| variant(&SEAT_PREFERENCES, s, "seat preference"))
+                .transpose()?,
+            redress_number: p.redress_number,
+            known_traveler_number: p.known_traveler_number,
+            assistance: AssistanceNeeds {
+                wheelchair: p
+                    .assistance
+                    .wheelchair
+                    .map(|w| variant(&WHEELCHAIR_NEEDS, w, "wheelchair need"))
+                    .transpose()?,
+                mobility_aid,
+                visual: p.assistance.visual,
+                hearing: p.assistance.hearing,
+                cognitive: p.assistance.cognitive,
+                service_animal: p.assistance.service_animal,
+                remarks: p.assistance.remarks,
+            },
+            assistance_requests,
+            seats: p
+                .seats
+                .into_iter()
+                .map(|s| SeatAssignment {
+                    segment: s.segment as usize,
+                    seat_number: s.seat_number,
+                    price: MinorUnits::new(s.price),
+                })
+                .collect(),
+        })
+    }
+}
+
+// ============================================================================
+// Pools
+// ============================================================================
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub(super) struct StoredPool {
+    id: String,
+    name: String,
+    description: Option<String>,
+    status: String,
+    origin: String,
+    destination: String,
+    departure_date: i32,
+    return_date: Option<i32>,
+    base_price: i64,
+    currency: String,
+    tiers: Vec<StoredTier>,
+    max_pool_size: u32,
+    min_members: u32,
+    max_members: u32,
+    members: Vec<StoredMember>,
+    waitlist: Vec<StoredWaitlistEntry>,
+    waitlist_cap: u32,
+    offer: Option<StoredOffer>,
+    created_at: i64,
+    updated_at: i64,
+    join_deadline: i64,
+    contribution_deadline: i64,
+    booking_ref: Option<String>,
+    history: Vec<StoredStatusChange>,
+    version: u32,
+    applied_operations: Vec<StoredOperation>,
+    refunds: Vec<StoredMemberRefund>,
+    settlement_outcome: Option<String>,
+    reminders_sent: Vec<u8>,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredTier {
+    name: String,
+    min_members: u32,
+    max_members: Option<u32>,
+    price_per_person: i64,
+    discount_percent: u8,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredMember {
+    user_id: String,
+    spots: u32,
+    joined_at: i64,
+    contribution: Option<i64>,
+    contributed_at: Option<i64>,
+    payment_ref: Option<String>,
+    price_lock: Option<StoredPriceLock>,
+    is_organizer: bool,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredPriceLock {
+    price_per_person: i64,
+    currency: String,
+    tier_name: Option<String>,
+    member_count: u32,
+    locked_at: i64,
+    expires_at: i64,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredWaitlistEntry {
+    user_id: String,
+    spots: u32,
+    joined_at: i64,
+}
+
+/// Applied operation; `kind` is 0 join, 1 contribute, 2 join waitlist,
+/// with `value` holding the spots or amount
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredOperation {
+    operation_id: String,
+    user_id: String,
+    kind: u8,
+    value: i64,
+    version: u32,
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct StoredMemberRefund {
+    id: String,
+    user_id: String,
+    payment_ref: Option<String>,
+    amount: i64,
+    currency: String,
+    status: u8,
+    provider_ref: Option<String>,
+    attempts: u32,
+    last_error: Option<String>,
+    updated_at: i64,
+}
+
+impl From<&Pool> for StoredPool {
+    fn from(pool: &Pool) -> Self {
+        Self {
+            id: pool.id.clone(),
+            name: pool.name.clone(),
+            description: pool.description.clone(),
+            status: pool.status.as_str().to_string(),
+            origin: pool.route.origin.as_str().to_string(),
+            destination: pool.route.destination.as_str().to_string(),
+            departure_date: pool.route.departure_date.to_julian_day(),
+            return_date: pool.route.return_date.map(Date::to_julian_day),
+            base_price: pool.pricing.base_price.as_i64(),
+            currency: pool.pricing.currency.as_str().to_string(),
+            tiers: pool
+                .pricing
+                .tiers
+                .iter()
+                .map(|t| StoredTier {
+                    name: t.name.clone(),
+                    min_members: t.min_members,
+                    max_members: t.max_members,
+                    price_per_person: t.price_per_person.as_i64(),
+                    discount_percent: t.discount_percent,
+                })
+                .collect(),
+            max_pool_size: pool.pricing.max_pool_size,
+            min_members: pool.min_members,
+            max_members: pool.max_members,
+            members: pool
+                .members
+                .iter()
+                .map(|m| StoredMember {
+                    user_id: m.user_id.clone(),
+                    spots: m.spots,
+                    joined_at: m.joined_at,
+                    contribution: money(m.contribution),
+                    contributed_at: m.contributed_at,
+                    payment_ref: m.payment_ref.clone(),
+                    price_lock: m.price_lock.as_ref().map(|l| StoredPriceLock {
+                        price_per_person: l.price_per_person.as_i64(),
+                        currency: l.currency.as_str().to_string(),
+                        tier_name: l.tier_name.clone(),
+                        member_count: l.member_count,
+                        locked_at: l.locked_at,
+                        expires_at: l.expires_at,
+                    }),
+                    is_organizer: m.is_organizer,
+                })
+                .collect(),
+            waitlist: pool
+                .waitlist
+                .iter()
+                .map(|w| StoredWaitlistEntry {
+                    user_id: w.user_id.clone(),
+                    spots: w.spots,
+                    joined_at: w.joined_at,
+                })
+                .collect(),
+            waitlist_cap: pool.waitlist_cap,
+            offer: pool.offer.as_ref().map(StoredOffer::from),
+            created_at: pool.created_at,
+            updated_at: pool.updated_at,
+            join_deadline: pool.join_deadline,
+            contribution_deadline: pool.contribution_deadline,
+            booking_ref: pool.booking_ref.clone(),
+            history: pool
+                .history
+                .iter()
+                .map(|h| StoredStatusChange {
+                    from: h.from.map(|s| s.as_str().to_string()),
+                    to: h.to.as_str().to_string(),
+                    timestamp: h.timestamp,
+                    reason: h.reason.clone(),
+                    actor: h.actor.clone(),
+                })
+                .collect(),
+            version: pool.version,
+            applied_operations: pool
+                .applied_operations
+                .iter()
+                .map(|o| {
+                    let (kind, value) = match o.operation {
+                        PoolOperation::Join { spots } => (0, i64::from(spots)),
+                        PoolOperation::Contribute { amount } => (1, amount.as_i64()),
+                        PoolOperation::JoinWaitlist { spots } => (2, i64::from(spots)),
+                    };
+                    StoredOperation {
+                        operation_id: o.operation_id.clone(),
+                        user_id: o.user_id.clone(),
+                        kind,
+                        value,
+                        version: o.version,
+                    }
+                })
+                .collect(),
+            refunds: pool
+                .refunds
+                .iter()
+                .map(|r| StoredMemberRefund {
+                    id: r.id.clone(),
+                    user_id: r.user_id.clone(),
+                    payment_ref: r.payment_ref.clone(),
+                    amount: r.amount.as_i64(),
+                    currency: r.currency.as_str().to_string(),
+                    status: code(&MEMBER_REFUND_STATUSES, r.status),
+                    provider_ref: r.provider_ref.clone(),
+                    attempts: r.attempts,
+                    last_error: r.last_error.clone(),
+                    updated_at: r.updated_at,
+                })
+                .collect(),
+            settlement_outcome: pool.settlement_outcome.map(|s| s.as_str().to_string()),
+            reminders_sent: pool
+                .reminders_sent
+                .iter()
+                .map(|d| code(&POOL_DEADLINES, *d))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredPool> for Pool {
+    type Error = CoreError;
+
+    fn try_from(stored: StoredPool) -> CoreResult<Self> {
+        let spots = |value: i64| u32::try_from(value).map_err(|_| corrupt("spots"));
+        let applied_operations = stored
+            .applied_operations
+            .into_iter()
+            .map(|o| {
+                let operation = match o.kind {
+                    0 => PoolOperation::Join {
+                        spots: spots(o.value)?,
+                    },
+                    1 => PoolOperation::Contribute {
+                        amount: MinorUnits::new(o.value),
+                    },
+                    2 => PoolOperation::JoinWaitlist {
+                        spots: spots(o.value)?,
+                    },
+                    _ => return Err(corrupt("pool operation")),
+                };
+                Ok(AppliedOperation {
+                    operation_id: o.operation_id,
+                    user_id: o.user_id,
+                    operation,
+                    version: o.version,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        let history = stored
+            .history
+            .into_iter()
+            .map(|h| {
+                Ok(vaya_pool::StatusChange {
+                    from: h.from.as_deref().map(pool_status).transpose()?,
+                    to: pool_status(&h.to)?,
+                    timestamp: h.timestamp,
+                    reason: h.reason,
+                    actor: h.actor,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+        let refunds = stored
+            .refunds
+            .into_iter()
+            .map(|r| {
+                Ok(MemberRefund {
+                    id: r.id,
+                    user_id: r.user_id,
+                    payment_ref: r.payment_ref,
+                    amount: MinorUnits::new(r.amount),
+                    currency: CurrencyCode::new(&r.currency),
+                    status: variant(&MEMBER_REFUND_STATUSES, r.status, "refund status")?,
+                    provider_ref: r.provider_ref,
+                    attempts: r.attempts,
+                    last_error: r.last_error,
+                    updated_at: r.updated_at,
+                })
+            })
+            .collect::<CoreResult<_>>()?;
+
+        Ok(Self {
+            id: stored.id,
+            name: stored.name,
+            description: stored.description,
+            status: pool_status(&stored.status)?,
+            route: PoolRoute {
+                origin: IataCode::new(&stored.origin),
+                destination: IataCode::new(&stored.destination),
+                departure_date: date_from(stored.departure_date)?,
+                return_date: stored.return_date.map(date_from).transpose()?,
+            },
+            pricing: TieredPricing {
+                base_price: MinorUnits::new(stored.base_price),
+                currency: CurrencyCode::new(&stored.currency),
+                tiers: stored
+                    .tiers
+                    .into_iter()
+                    .map(|t| PricingTier {
+                        name: t.name,
+                        min_members: t.min_members,
+                        max_members: t.max_members,
+                        price_per_person: MinorUnits::new(t.price_per_person),
+                        discount_percent: t.discount_percent,
+                    })
+                    .collect(),
+                max_pool_size: stored.max_pool_size,
+            },
+            min_members: stored.min_members,
+            max_members: stored.max_members,
+            members: stored
+                .members
+                .into_iter()
+                .map(|m| PoolMember {
+                    user_id: m.user_id,
+                    spots: m.spots,
+                    joined_at: m.joined_at,
+                    contribution: m.contribution.map(MinorUnits::new),
+                    contributed_at: m.contributed_at,
+                    payment_ref: m.payment_ref,
+                    price_lock: m.price_lock.map(|l| PriceLock {
+                        price_per_person: MinorUnits::new(l.price_per_person),
+                        currency: CurrencyCode::new(&l.currency),
+                        tier_name: l.tier_name,
+                        member_count: l.member_count,
+                        locked_at: l.locked_at,
+                        expires_at: l.expires_at,
+                    }),
+                    is_organizer: m.is_organizer,
+                })
+                .collect(),
+            waitlist: stored
+                .waitlist
+                .into_iter()
+                .map(|w| WaitlistEntry {
+                    user_id: w.user_id,
+                    spots: w.spots,
+                    joined_at: w.joined_at,
+                })
+                .collect(),
+            waitlist_cap: stored.waitlist_cap,
+            offer: stored.offer.map(TryInto::try_into).transpose()?,
+            created_at: stored.created_at,
+            updated_at: stored.updated_at,
+            join_deadline: stored.join_deadline,
+            contribution_deadline: stored.contribution_deadline,
+            booking_ref: stored.booking_ref,
+            history,
+            version: stored.version,
+            applied_operations,
+            refunds,
+            settlement_outcome: stored
+                .settlement_outcome
+                .as_deref()
+                .map(pool_status)
+                .transpose()?,
+            reminders_sent: stored
+                .reminders_sent
+                .into_iter()
+                .map(|d| variant(&POOL_DEADLINES, d, "pool deadline"))
+                .collect::<CoreResult<_>>()?,
+        })
+    }
+}
+
+// ============================================================================
+// Price alerts
+// ============================================================================
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub(super) struct StoredAlert {
+    id: String,
+    user_id: String,
+    origin: String,
+    destination: String,
+    departure_date: i32,
+    departure_date_end: Option<i32>,
+    trigger: u8,
+    threshold_price: Option<i64>,
+    threshold_percent: Option<u8>,
+    reference_price: Option<i64>,
+    cabin: Option<u8>,
+    last_trend: Option<u8>,
+    currency: String,
+    status: u8,
+    created_at: i64,
+    last_checked_at: Option<i64>,
+    triggered_at: Option<i64>,
+    triggered_price: Option<i64>,
+    expires_at: i64,
+    notification_count: u32,
+    max_notifications: u32,
+    version: u32,
+}
+
+impl From<&PriceAlert> for StoredAlert {
+    fn from(alert: &PriceAlert) -> Self {
+        let (threshold_price, threshold_percent, reference_price, cabin) = match alert.condition {
+            AlertCondition::PriceBelow { threshold } => {
+                (Some(threshold.as_i64()), None, None, None)
+            }
+            AlertCondition::DropsByPercent { reference, percent } => {
+                (None, Some(percent), Some(reference.as_i64()), None)
+            }
+            AlertCondition::CabinBelow { cabin, threshold } => (
+                Some(threshold.as_i64()),
+                None,
+                None,
+                Some(code(&ALERT_CABINS, cabin)),
+            ),
+            _ => (None, None, None, None),
+        };
+        Self {
+            id: alert.id.clone(),
+            user_id: alert.user_id.clone(),
+            origin: alert.origin.as_str().to_string(),
+            destination: alert.destination.as_str().to_string(),
+            departure_date: alert.departure_date.to_julian_day(),
+            departure_date_end: alert.departure_date_end.map(Date::to_julian_day),
+            trigger: code(&ALERT_TRIGGERS, alert.condition.trigger()),
+            threshold_price,
+            threshold_percent,
+            reference_price,
+            cabin,
+            last_trend: alert.last_trend.map(|t| code(&PRICE_TRENDS, t)),
+            currency: alert.currency.as_str().to_string(),
+            status: code(&ALERT_STATUSES, alert.status),
+            created_at: alert.created_at,
+            last_checked_at: alert.last_checked_at,
+            triggered_at: alert.triggered_at,
+            triggered_price: money(alert.triggered_price),
+            expires_at: alert.expires_at,
+            notification_count: alert.notification_count,
+            max_notifications: alert.max_notifications,
+            version: alert.version,
+        }
+    }
+}
+
+impl StoredAlert {
+    fn condition(&self) -> CoreResult<AlertCondition> {
+        let threshold = || {
+            self.threshold_price
+                .map(MinorUnits::new)
+                .ok_or_else(|| corrupt("alert threshold"))
+        };
+        Ok(
+            match variant(&ALERT_TRIGGERS, self.trigger, "alert trigger")? {
+                AlertTrigger::PriceDropsBelow => AlertCondition::PriceBelow {
+                    threshold: threshold()?,
+                },
+                AlertTrigger::PriceDropsBy => AlertCondition::DropsByPercent {
+                    reference: self
+                        .reference_price
+                        .map(MinorUnits::new)
+                        .ok_or_else(|| corrupt("alert reference price"))?,
+                    percent: self
+                        .threshold_percent
+                        .ok_or_else(|| corrupt("alert percentage"))?,
+                },
+                AlertTrigger::CabinPriceBelow => AlertCondition::CabinBelow {
+                    cabin: variant(
+                        &ALERT_CABINS,
+                        self.cabin.ok_or_else(|| corrupt("alert cabin"))?,
+                        "alert cabin",
+                    )?,
+                    threshold: threshold()?,
+                },
+                AlertTrigger::PriceDropsBelowAverage => AlertCondition::BelowAverage,
+                AlertTrigger::TrendFalling => AlertCondition::TrendTurnsFalling,
+                AlertTrigger::AnyPrice => AlertCondition::AnyPrice,
+                AlertTrigger::BestPrice => AlertCondition::BestPrice,
+            },
+        )
+    }
+}
+
+impl TryFrom<StoredAlert> for PriceAlert {
+    type Error = CoreError;
+
+    fn try_from(stored: StoredAlert) -> CoreResult<Self> {
+        let condition = stored.condition()?;
+        Ok(Self {
+            id: stored.id,
+            user_id: stored.user_id,
+            origin: IataCode::new(&stored.origin),
+            destination: IataCode::new(&stored.destination),
+            departure_date: date_from(stored.departure_date)?,
+            departure_date_end: stored.departure_date_end.map(date_from).transpose()?,
+            condition,
+            last_trend: stored
+                .last_trend
+                .map(|t| variant(&PRICE_TRENDS, t, "price trend"))
+                .transpose()?,
+            currency: CurrencyCode::new(&stored.currency),
+            status: variant(&ALERT_STATUSES, stored.status, "alert status")?,
+            created_at: stored.created_at,
+            last_checked_at: stored.last_checked_at,
+            triggered_at: stored.triggered_at,
+            triggered_price: stored.triggered_price.map(MinorUnits::new),
+            expires_at: stored.expires_at,
+            notification_count: stored.notification_count,
+            max_notifications: stored.max_notifications,
+            version: stored.version,
+        })
+    }
+}
+
+// ============================================================================
+// Notification preferences
+// ============================================================================
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub(super) struct StoredPreferences {
+    user_id: String,
+    /// Enabled (category, channel) pairs as variant codes
+    enabled: Vec<[u8; 2]>,
+    quiet_start: Option<u16>,
+    quiet_end: Option<u16>,
+    utc_offset_minutes: i16,
+    digest: u8,
+    version: u32,
+    updated_at: i64,
+}
+
+impl From<&NotificationPreferences> for StoredPreferences {
+    fn from(prefs: &NotificationPreferences) -> Self {
+        Self {
+            user_id: prefs.user_id.clone(),
+            enabled: prefs
+                .enabled
+                .iter()
+                .map(|(category, channel)| {
+                    [
+                        code(&NOTIFICATION_CATEGORIES, *category),
+                        code(&NOTIFICATION_CHANNELS, *channel),
+                    ]
+                })
+                .collect(),
+            quiet_start: prefs.quiet_hours.map(|q| q.start_minute),
+            quiet_end: prefs.quiet_hours.map(|q| q.end_minute),
+            utc_offset_minutes: prefs.utc_offset_minutes,
+            digest: code(&DIGEST_FREQUENCIES, prefs.digest),
+            version: prefs.version,
+            updated_at: prefs.updated_at,
+        }
+    }
+}
+
+impl TryFrom<StoredPreferences> for NotificationPreferences {
+    type Error = CoreError;
+
+    fn try_from(stored: StoredPreferences) -> CoreResult<Self> {
+        let enabled = stored
+            .enabled
+            .iter()
+            .map(|[category, channel]| {
+                Ok((
+                    variant(&NOTIFICATION_CATEGORIES, *category, "notification category")?,
+                    variant(&NOTIFICATION_CHANNELS, *channel, "notification channel")?,
+                ))
+            })
+            .collect::<CoreResult<_>>()?;
+        let quiet_hours = match (stored.quiet_start, stored.quiet_end) {
+            (Some(start), Some(end)) => Some(QuietHours {
+                start_minute: start,
+                end_minute: end,
+            }),
+            _ => None,
+        };
+        Ok(Self {
+            user_id: stored.user_id,
+            enabled,
+            quiet_hours,
+            utc_offset_minutes: stored.utc_offset_minutes,
+            digest: variant(&DIGEST_FREQUENCIES, stored.digest, "digest frequency")?,
+            version: stored.version,
+            updated_at: stored.updated_at,
+        })
+    }
+}
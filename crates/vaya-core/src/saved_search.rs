@@ -0,0 +1,462 @@
+//! Saved searches and share links
+//!
+//! A saved search keeps the search criteria, and optionally a snapshot of
+//! the top offers, under a short ID that can be shared. Opening it returns
+//! the snapshot labelled with its age, or re-runs the search when there is
+//! no usable snapshot. Snapshots older than the configured TTL are dropped,
+//! as are anonymous share links nobody has opened for a while.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use tracing::{debug, info};
+
+use vaya_common::Timestamp;
+use vaya_crypto::random_alphanumeric;
+use vaya_gds::GdsProvider;
+
+use crate::error::{CoreError, CoreResult};
+use crate::search::{SearchResponse, SearchService};
+use crate::types::{FlightOffer, SearchRequest};
+
+/// Length of generated share IDs
+const SHARE_ID_LEN: usize = 8;
+
+/// Saved search configuration
+#[derive(Debug, Clone)]
+pub struct SavedSearchConfig {
+    /// Offers kept in a snapshot
+    pub snapshot_size: usize,
+    /// Age (seconds) up to which a snapshot is labelled fresh
+    pub fresh_secs: i64,
+    /// Age (seconds) after which a snapshot is dropped
+    pub snapshot_ttl_secs: i64,
+    /// Idle time (seconds) after which an anonymous share link is removed
+    pub anonymous_ttl_secs: i64,
+    /// Maximum saved searches per user
+    pub max_per_user: usize,
+}
+
+impl Default for SavedSearchConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_size: 10,
+            fresh_secs: 15 * 60,
+            snapshot_ttl_secs: 24 * 3600,
+            anonymous_ttl_secs: 30 * 24 * 3600,
+            max_per_user: 50,
+        }
+    }
+}
+
+/// Offers captured when a search was saved
+#[derive(Debug, Clone)]
+pub struct SearchSnapshot {
+    /// Top offers, cheapest first
+    pub offers: Vec<FlightOffer>,
+    /// When the offers were captured
+    pub captured_at: Timestamp,
+}
+
+/// A saved search
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    /// Short shareable ID
+    pub id: String,
+    /// Owning user (anonymous share links have none)
+    pub owner_id: Option<String>,
+    /// Display name
+    pub name: Option<String>,
+    /// Search criteria
+    pub request: SearchRequest,
+    /// Whether re-runs refresh the snapshot
+    pub keep_snapshot: bool,
+    /// Snapshot of top offers
+    pub snapshot: Option<SearchSnapshot>,
+    /// Created at
+    pub created_at: Timestamp,
+    /// Last opened at
+    pub last_opened_at: Timestamp,
+}
+
+/// How current a set of shared results is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Search was just re-run
+    Live,
+    /// Snapshot is recent
+    Fresh,
+    /// Snapshot is old enough that prices may have changed
+    Stale,
+}
+
+impl Freshness {
+    /// Get freshness label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Freshness::Live => "live",
+            Freshness::Fresh => "fresh",
+            Freshness::Stale => "stale",
+        }
+    }
+}
+
+/// Results returned when opening a saved search
+#[derive(Debug, Clone)]
+pub struct SharedResults {
+    /// The saved search
+    pub search: SavedSearch,
+    /// Offers, cheapest first
+    pub offers: Vec<FlightOffer>,
+    /// When the offers were obtained
+    pub captured_at: Timestamp,
+    /// Freshness label
+    pub freshness: Freshness,
+}
+
+/// Saved search store
+pub struct SavedSearchService {
+    /// Configuration
+    config: SavedSearchConfig,
+    /// Saved searches by ID
+    searches: RwLock<HashMap<String, SavedSearch>>,
+}
+
+impl SavedSearchService {
+    /// Create new saved search service
+    pub fn new(config: SavedSearchConfig) -> Self {
+        Self {
+            config,
+            searches: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Save search criteria, with a snapshot of the top offers if `results`
+    /// is given
+    pub fn save(
+        &self,
+        owner_id: Option<&str>,
+        name: Option<String>,
+        request: SearchRequest,
+        results: Option<&SearchResponse>,
+    ) -> CoreResult<SavedSearch> {
+        request.validate().map_err(CoreError::InvalidSearchParams)?;
+
+        let mut searches = self.searches.write().unwrap();
+        if let Some(owner) = owner_id {
+            let owned = searches
+                .values()
+                .filter(|s| s.owner_id.as_deref() == Some(owner))
+                .count();
+            if owned >= self.config.max_per_user {
+                return Err(CoreError::ValidationError(format!(
+                    "At most {} saved searches allowed",
+                    self.config.max_per_user
+                )));
+            }
+        }
+
+        let id = loop {
+            let id = random_alphanumeric(SHARE_ID_LEN)
+                .map_err(|e| CoreError::Internal(e.to_string()))?;
+            if !searches.contains_key(&id) {
+                break id;
+            }
+        };
+        let now = Timestamp::now();
+        let saved = SavedSearch {
+            id: id.clone(),
+            owner_id: owner_id.map(str::to_string),
+            name,
+            request,
+            keep_snapshot: results.is_some(),
+            snapshot: results.map(|r| self.snapshot_of(&r.offers, now)),
+            created_at: now,
+            last_opened_at: now,
+        };
+        searches.insert(id, saved.clone());
+
+        debug!("Saved search {}", saved.id);
+
+        Ok(saved)
+    }
+
+    /// Get a saved search
+    pub fn get(&self, id: &str) -> CoreResult<SavedSearch> {
+        self.searches
+            .read()
+            .unwrap()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| CoreError::SavedSearchNotFound(id.to_string()))
+    }
+
+    /// Return the snapshot of a saved search labelled with its freshness,
+    /// or `None` if it has no usable snapshot
+    pub fn snapshot_results(&self, id: &str) -> CoreResult<Option<SharedResults>> {
+        let now = Timestamp::now();
+        let mut searches = self.searches.write().unwrap();
+        let saved = searches
+            .get_mut(id)
+            .ok_or_else(|| CoreError::SavedSearchNotFound(id.to_string()))?;
+        saved.last_opened_at = now;
+
+        let Some(snapshot) = &saved.snapshot else {
+            return Ok(None);
+        };
+        let age = now.as_unix() - snapshot.captured_at.as_unix();
+        if age > self.config.snapshot_ttl_secs {
+            saved.snapshot = None;
+            return Ok(None);
+        }
+
+        Ok(Some(SharedResults {
+            offers: snapshot.offers.clone(),
+            captured_at: snapshot.captured_at,
+            freshness: if age <= self.config.fresh_secs {
+                Freshness::Fresh
+            } else {
+                Freshness::Stale
+            },
+            search: saved.clone(),
+        }))
+    }
+
+    /// Open a saved search
+    ///
+    /// Returns the snapshot when one is usable and `refresh` is false;
+    /// otherwise re-runs the search and, if the search keeps snapshots,
+    /// replaces its snapshot with the new results.
+    pub async fn open<G: GdsProvider + Send + Sync + 'static>(
+        &self,
+        id: &str,
+        search: &SearchService<G>,
+        refresh: bool,
+    ) -> CoreResult<SharedResults> {
+        if !refresh {
+            if let Some(results) = self.snapshot_results(id)? {
+                return Ok(results);
+            }
+        }
+
+        let saved = self.get(id)?;
+        let response = search.search(&saved.request).await?;
+        let now = Timestamp::now();
+
+        let saved = {
+            let mut searches = self.searches.write().unwrap();
+            let stored = searches
+                .get_mut(id)
+                .ok_or_else(|| CoreError::SavedSearchNotFound(id.to_string()))?;
+            if stored.keep_snapshot {
+                stored.snapshot = Some(self.snapshot_of(&response.offers, now));
+            }
+            stored.last_opened_at = now;
+            stored.clone()
+        };
+
+        Ok(SharedResults {
+            search: saved,
+            offers: response.offers,
+            captured_at: now,
+            freshness: Freshness::Live,
+        })
+    }
+
+    /// List a user's saved searches, newest first
+    pub fn list_for(&self, owner_id: &str) -> Vec<SavedSearch> {
+        let mut searches: Vec<SavedSearch> = self
+            .searches
+            .read()
+            .unwrap()
+            .values()
+            .filter(|s| s.owner_id.as_deref() == Some(owner_id))
+            .cloned()
+            .collect();
+        searches.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        searches
+    }
+
+    /// Rename a user's saved search
+    pub fn rename(
+        &self,
+        id: &str,
+        owner_id: &str,
+        name: Option<String>,
+    ) -> CoreResult<SavedSearch> {
+        let mut searches = self.searches.write().unwrap();
+        let saved = owned_mut(&mut searches, id, owner_id)?;
+        saved.name = name;
+        Ok(saved.clone())
+    }
+
+    /// Delete a user's saved search
+    pub fn delete(&self, id: &str, owner_id: &str) -> CoreResult<()> {
+        let mut searches = self.searches.write().unwrap();
+        owned_mut(&mut searches, id, owner_id)?;
+        searches.remove(id);
+        Ok(())
+    }
+
+    /// Drop expired snapshots and idle anonymous share links
+    ///
+    /// Returns the number of snapshots dropped and share links removed.
+    pub fn purge_expired(&self) -> (usize, usize) {
+        let now = Timestamp::now().as_unix();
+        let mut searches = self.searches.write().unwrap();
+
+        let before = searches.len();
+        searches.retain(|_, s| {
+            s.owner_id.is_some()
+                || now - s.last_opened_at.as_unix() <= self.config.anonymous_ttl_secs
+        });
+        let removed = before - searches.len();
+
+        let mut dropped = 0;
+        for saved in searches.values_mut() {
+            if saved
+                .snapshot
+                .as_ref()
+                .is_some_and(|s| now - s.captured_at.as_unix() > self.config.snapshot_ttl_secs)
+            {
+                saved.snapshot = None;
+                dropped += 1;
+            }
+        }
+
+        if dropped > 0 || removed > 0 {
+            info!(
+                "Purged {} stale snapshots and {} idle share links",
+                dropped, removed
+            );
+        }
+
+        (dropped, removed)
+    }
+
+    /// Capture the top offers
+    fn snapshot_of(&self, offers: &[FlightOffer], now: Timestamp) -> SearchSnapshot {
+        SearchSnapshot {
+            offers: offers
+                .iter()
+                .take(self.config.snapshot_size)
+                .cloned()
+                .collect(),
+            captured_at: now,
+        }
+    }
+}
+
+impl Default for SavedSearchService {
+    fn default() -> Self {
+        Self::new(SavedSearchConfig::default())
+    }
+}
+
+/// Look up a saved search for modification by its owner
+fn owned_mut<'a>(
+    searches: &'a mut HashMap<String, SavedSearch>,
+    id: &str,
+    owner_id: &str,
+) -> CoreResult<&'a mut SavedSearch> {
+    let saved = searches
+        .get_mut(id)
+        .ok_or_else(|| CoreError::SavedSearchNotFound(id.to_string()))?;
+    if saved.owner_id.as_deref() != Some(owner_id) {
+        return Err(CoreError::NotAuthorized(
+            "Saved search belongs to another user".to_string(),
+        ));
+    }
+    Ok(saved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_common::IataCode;
+
+    fn request() -> SearchRequest {
+        SearchRequest::one_way(IataCode::KUL, IataCode::SIN, "2030-06-15")
+    }
+
+    fn response() -> SearchResponse {
+        SearchResponse {
+            offers: Vec::new(),
+            search_id: "search".to_string(),
+            cached: false,
+            price_insight: None,
+        }
+    }
+
+    fn age_snapshot(service: &SavedSearchService, id: &str, secs: i64) {
+        let mut searches = service.searches.write().unwrap();
+        let snapshot = searches.get_mut(id).unwrap().snapshot.as_mut().unwrap();
+        snapshot.captured_at = Timestamp::from_unix(snapshot.captured_at.as_unix() - secs);
+    }
+
+    #[test]
+    fn test_snapshot_staleness() {
+        let service = SavedSearchService::default();
+        let saved = service
+            .save(None, None, request(), Some(&response()))
+            .unwrap();
+        assert_eq!(saved.id.len(), SHARE_ID_LEN);
+
+        let results = service.snapshot_results(&saved.id).unwrap().unwrap();
+        assert_eq!(results.freshness, Freshness::Fresh);
+
+        age_snapshot(&service, &saved.id, 3600);
+        let results = service.snapshot_results(&saved.id).unwrap().unwrap();
+        assert_eq!(results.freshness, Freshness::Stale);
+
+        age_snapshot(&service, &saved.id, 2 * 24 * 3600);
+        assert!(service.snapshot_results(&saved.id).unwrap().is_none());
+        assert!(service.get(&saved.id).unwrap().snapshot.is_none());
+    }
+
+    #[test]
+    fn test_owner_management() {
+        let service = SavedSearchService::default();
+        let saved = service
+            .save(Some("user_1"), Some("Bali".to_string()), request(), None)
+            .unwrap();
+        service.save(None, None, request(), None).unwrap();
+
+        assert_eq!(service.list_for("user_1").len(), 1);
+        assert!(matches!(
+            service.delete(&saved.id, "user_2"),
+            Err(CoreError::NotAuthorized(_))
+        ));
+
+        let renamed = service
+            .rename(&saved.id, "user_1", Some("Singapore".to_string()))
+            .unwrap();
+        assert_eq!(renamed.name.as_deref(), Some("Singapore"));
+
+        service.delete(&saved.id, "user_1").unwrap();
+        assert!(matches!(
+            service.get(&saved.id),
+            Err(CoreError::SavedSearchNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let service = SavedSearchService::default();
+        let anonymous = service.save(None, None, request(), None).unwrap();
+        let owned = service
+            .save(Some("user_1"), None, request(), Some(&response()))
+            .unwrap();
+
+        {
+            let mut searches = service.searches.write().unwrap();
+            searches.get_mut(&anonymous.id).unwrap().last_opened_at =
+                Timestamp::from_unix(Timestamp::now().as_unix() - 31 * 24 * 3600);
+        }
+        age_snapshot(&service, &owned.id, 2 * 24 * 3600);
+
+        assert_eq!(service.purge_expired(), (1, 1));
+        assert!(service.get(&anonymous.id).is_err());
+        assert!(service.get(&owned.id).unwrap().snapshot.is_none());
+    }
+}
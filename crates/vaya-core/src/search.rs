@@ -1,17 +1,25 @@
 //! Flight search service
 
 use std::sync::Arc;
-use std::time::Duration;
-use tracing::{debug, info};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
 
 use vaya_cache::Cache;
-use vaya_common::{Date, Timestamp};
-use vaya_gds::{FlightSearchRequest, GdsProvider};
+use vaya_common::bus::{self, topics};
+use vaya_common::events::{SearchLateResults, SearchPerformed};
+use vaya_common::{metrics, Date, IataCode, Timestamp};
+use vaya_gds::{FlightSearchRequest, GdsProvider, GdsResult, SearchLeg};
 use vaya_oracle::LSTMPredictor;
 
 use crate::error::{CoreError, CoreResult};
+use crate::price_history::PriceHistoryService;
+use crate::pricing::{NetFare, PricingContext, PricingEngine};
 use crate::types::*;
 
+/// How long search results are cached
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(300);
+
 /// Parse date string (YYYY-MM-DD) into Date
 fn parse_date(s: &str) -> Option<Date> {
     let parts: Vec<&str> = s.split('-').collect();
@@ -29,10 +37,127 @@ fn parse_date(s: &str) -> Option<Date> {
     }
 }
 
+/// When a search fanned out to several providers stops waiting
+///
+/// The search returns as soon as any condition is met; providers that
+/// have not answered keep running and their offers are delivered through
+/// [`SearchService::next_late`] or [`SearchService::publish_late`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanOutPolicy {
+    /// Return with whatever has arrived after this long (as long as at
+    /// least one provider has returned offers)
+    pub soft_deadline: Duration,
+    /// Return once this percentage of providers has responded
+    pub quorum_percent: u8,
+    /// Return as soon as the primary provider has returned offers
+    pub return_on_primary: bool,
+}
+
+impl Default for FanOutPolicy {
+    fn default() -> Self {
+        Self {
+            soft_deadline: Duration::from_millis(1500),
+            quorum_percent: 50,
+            return_on_primary: true,
+        }
+    }
+}
+
+impl FanOutPolicy {
+    /// Wait for every provider (up to the search timeout)
+    pub fn wait_for_all() -> Self {
+        Self {
+            soft_deadline: Duration::MAX,
+            quorum_percent: 100,
+            return_on_primary: false,
+        }
+    }
+
+    /// Whether enough providers have answered to return
+    ///
+    /// `responded` counts successes and failures; `succeeded` only
+    /// providers that returned offers.
+    pub fn is_satisfied(
+        &self,
+        providers: usize,
+        responded: usize,
+        succeeded: usize,
+        primary_succeeded: bool,
+    ) -> bool {
+        if responded >= providers {
+            return true;
+        }
+        if succeeded == 0 {
+            return false;
+        }
+        (self.return_on_primary && primary_succeeded)
+            || responded * 100 >= providers * self.quorum_percent as usize
+    }
+}
+
+/// Reply from one provider in a fanned-out search
+struct ProviderReply {
+    /// Position in the provider list (0 is the primary)
+    index: usize,
+    /// Provider name
+    provider: &'static str,
+    /// Offers, or the provider's error
+    result: GdsResult<Vec<vaya_gds::FlightOffer>>,
+    /// Time the provider took to answer
+    latency: Duration,
+}
+
+/// Offers from a provider that answered after the search returned
+#[derive(Debug, Clone)]
+pub struct LateResults {
+    /// Search the offers belong to
+    pub search_id: String,
+    /// Provider name
+    pub provider: &'static str,
+    /// Offers, already converted, filtered, and priced
+    pub offers: Vec<FlightOffer>,
+    /// Time the provider took to answer
+    pub latency: Duration,
+}
+
+/// A search response with providers still outstanding
+///
+/// Pass to [`SearchService::next_late`] until it returns `None` to drain
+/// late offers, or hand it to [`SearchService::publish_late`] to push them
+/// to live-update clients.
+pub struct StreamingSearch {
+    /// Results available when the search returned
+    pub response: SearchResponse,
+    /// Providers that had not answered yet, by position in the provider list
+    pending: Vec<(usize, &'static str)>,
+    /// Replies from outstanding providers
+    replies: Option<mpsc::UnboundedReceiver<ProviderReply>>,
+    /// Request the search was made for
+    request: SearchRequest,
+    /// When outstanding providers are given up on
+    hard_deadline: tokio::time::Instant,
+}
+
+impl StreamingSearch {
+    /// Providers that have not answered yet
+    pub fn pending_providers(&self) -> Vec<&'static str> {
+        self.pending.iter().map(|(_, provider)| *provider).collect()
+    }
+
+    /// Whether late results may still arrive
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
 /// Flight search service
 pub struct SearchService<G: GdsProvider + Send + Sync> {
     /// GDS provider
     gds: Arc<G>,
+    /// Additional providers searched in parallel with the primary
+    providers: Vec<Arc<dyn GdsProvider>>,
+    /// When a multi-provider search returns
+    fan_out: FanOutPolicy,
     /// Cache for search results
     cache: Arc<Cache<String, Vec<FlightOffer>>>,
     /// Price predictor
@@ -41,17 +166,25 @@ pub struct SearchService<G: GdsProvider + Send + Sync> {
     timeout: Duration,
     /// Maximum results
     max_results: usize,
+    /// Retail pricing policy
+    pricing: Option<Arc<PricingEngine>>,
+    /// Where observed prices are recorded
+    price_history: Option<Arc<PriceHistoryService>>,
 }
 
-impl<G: GdsProvider + Send + Sync> SearchService<G> {
+impl<G: GdsProvider + Send + Sync + 'static> SearchService<G> {
     /// Create new search service
     pub fn new(gds: Arc<G>, cache: Arc<Cache<String, Vec<FlightOffer>>>) -> Self {
         Self {
             gds,
+            providers: Vec::new(),
+            fan_out: FanOutPolicy::default(),
             cache,
             predictor: LSTMPredictor::new(),
             timeout: Duration::from_secs(30),
             max_results: 100,
+            pricing: None,
+            price_history: None,
         }
     }
 
@@ -67,8 +200,46 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
         self
     }
 
+    /// Add a provider searched in parallel with the primary
+    pub fn with_provider(mut self, provider: Arc<dyn GdsProvider>) -> Self {
+        self.providers.push(provider);
+        self
+    }
+
+    /// Set when multi-provider searches return
+    pub fn with_fan_out(mut self, policy: FanOutPolicy) -> Self {
+        self.fan_out = policy;
+        self
+    }
+
+    /// Set retail pricing policy
+    pub fn with_pricing(mut self, pricing: Arc<PricingEngine>) -> Self {
+        self.pricing = Some(pricing);
+        self
+    }
+
+    /// Record the prices of every search that reaches the providers
+    pub fn with_price_history(mut self, history: Arc<PriceHistoryService>) -> Self {
+        self.price_history = Some(history);
+        self
+    }
+
+    /// Get the retail pricing policy
+    pub fn pricing(&self) -> Option<&Arc<PricingEngine>> {
+        self.pricing.as_ref()
+    }
+
     /// Search for flights
+    ///
+    /// With several providers configured, offers from providers that miss
+    /// the [`FanOutPolicy`] are dropped; use [`Self::search_streaming`] to
+    /// receive them.
     pub async fn search(&self, request: &SearchRequest) -> CoreResult<SearchResponse> {
+        Ok(self.search_streaming(request).await?.response)
+    }
+
+    /// Search all providers, returning once the fan-out policy is met
+    pub async fn search_streaming(&self, request: &SearchRequest) -> CoreResult<StreamingSearch> {
         // Validate request
         request.validate().map_err(CoreError::InvalidSearchParams)?;
 
@@ -77,75 +248,360 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
             request.origin, request.destination, request.departure_date
         );
 
-        // Check cache first
+        let started = Instant::now();
+        let hard_deadline = tokio::time::Instant::now() + self.timeout;
+
+        // Concurrent identical searches share one fan-out: the first caller
+        // runs it and the others wait for its results
         let cache_key = self.build_cache_key(request);
-        if let Some(cached) = self.cache.get(&cache_key) {
+        let mut fan_out = None;
+        let slot = &mut fan_out;
+        let offers = self
+            .cache
+            .get_or_try_insert_with_async(
+                cache_key.clone(),
+                Some(SEARCH_CACHE_TTL),
+                || async move {
+                    let (offers, pending, replies) =
+                        self.fan_out_search(request, hard_deadline).await?;
+                    *slot = Some((pending, replies));
+                    Ok::<_, CoreError>(offers)
+                },
+            )
+            .await?;
+
+        let Some((pending, replies)) = fan_out else {
             debug!("Cache hit for search: {}", cache_key);
-            return Ok(SearchResponse {
-                offers: cached,
-                search_id: cache_key,
-                cached: true,
-                price_insight: None,
+            self.emit_search_event(request, &cache_key, offers.len(), true, started);
+            return Ok(StreamingSearch {
+                response: SearchResponse {
+                    offers,
+                    search_id: cache_key,
+                    cached: true,
+                    price_insight: None,
+                },
+                pending: Vec::new(),
+                replies: None,
+                request: request.clone(),
+                hard_deadline,
             });
-        }
+        };
+
+        self.record_prices(request, &offers);
+
+        // Calculate price insight
+        let price_insight = self.calculate_insight(request, &offers);
+        self.emit_search_event(request, &cache_key, offers.len(), false, started);
+
+        Ok(StreamingSearch {
+            response: SearchResponse {
+                offers,
+                search_id: cache_key,
+                cached: false,
+                price_insight,
+            },
+            replies: (!pending.is_empty()).then_some(replies),
+            pending,
+            request: request.clone(),
+            hard_deadline,
+        })
+    }
 
+    /// Query every provider until the fan-out policy is met
+    ///
+    /// Returns the sorted offers, the providers still outstanding (keyed by
+    /// index, since two providers may share a name), and the channel their
+    /// replies will arrive on.
+    async fn fan_out_search(
+        &self,
+        request: &SearchRequest,
+        hard_deadline: tokio::time::Instant,
+    ) -> CoreResult<(
+        Vec<FlightOffer>,
+        Vec<(usize, &'static str)>,
+        mpsc::UnboundedReceiver<ProviderReply>,
+    )> {
         // Build GDS search params
         let gds_params = self.build_gds_params(request)?;
 
-        // Execute search with timeout
-        let search_result =
-            tokio::time::timeout(self.timeout, self.gds.search_flights(&gds_params))
-                .await
-                .map_err(|_| CoreError::SearchTimeout)?
-                .map_err(|e| CoreError::GdsError(e.to_string()))?;
+        // Fan out to every provider; each call runs to the hard deadline
+        // even if the search returns earlier
+        let mut pending: Vec<(usize, &'static str)> = Vec::with_capacity(self.providers.len() + 1);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let primary: Arc<dyn GdsProvider> = self.gds.clone();
+        for (index, provider) in std::iter::once(primary)
+            .chain(self.providers.iter().cloned())
+            .enumerate()
+        {
+            pending.push((index, provider.provider_name()));
+            let tx = tx.clone();
+            let params = gds_params.clone();
+            let timeout = self.timeout;
+            tokio::spawn(async move {
+                let call_started = Instant::now();
+                let result =
+                    match tokio::time::timeout(timeout, provider.search_flights(&params)).await {
+                        Ok(result) => result,
+                        Err(_) => Err(vaya_gds::GdsError::ServiceUnavailable(
+                            "Search timed out".to_string(),
+                        )),
+                    };
+                let _ = tx.send(ProviderReply {
+                    index,
+                    provider: provider.provider_name(),
+                    result,
+                    latency: call_started.elapsed(),
+                });
+            });
+        }
+        drop(tx);
+
+        let provider_count = pending.len();
+        let soft_deadline = tokio::time::Instant::now()
+            .checked_add(self.fan_out.soft_deadline)
+            .unwrap_or(hard_deadline)
+            .min(hard_deadline);
+        let mut replies: Vec<ProviderReply> = Vec::with_capacity(provider_count);
+        loop {
+            let succeeded = replies.iter().filter(|r| r.result.is_ok()).count();
+            let primary_succeeded = replies.iter().any(|r| r.index == 0 && r.result.is_ok());
+            if self.fan_out.is_satisfied(
+                provider_count,
+                replies.len(),
+                succeeded,
+                primary_succeeded,
+            ) {
+                break;
+            }
+            // Past the soft deadline, keep waiting only until the first offers
+            let wait_until = if succeeded > 0 {
+                soft_deadline
+            } else {
+                hard_deadline
+            };
+            match tokio::time::timeout_at(wait_until, rx.recv()).await {
+                Ok(Some(reply)) => replies.push(reply),
+                Ok(None) | Err(_) => break,
+            }
+        }
 
         // Convert GDS results to our types
-        let mut offers = self.convert_gds_offers(&search_result)?;
+        let mut offers = Vec::new();
+        let mut first_error = None;
+        for reply in replies {
+            pending.retain(|(index, _)| *index != reply.index);
+            match self.convert_reply(&reply, request) {
+                Ok(converted) => {
+                    record_provider_reply(
+                        reply.provider,
+                        "on_time",
+                        converted.len(),
+                        reply.latency,
+                    );
+                    offers.extend(converted);
+                }
+                Err(e) => {
+                    record_provider_reply(reply.provider, "failed", 0, reply.latency);
+                    if reply.index == 0 || first_error.is_none() {
+                        first_error = Some(e);
+                    }
+                }
+            }
+        }
 
-        // Filter and sort
-        offers = self.filter_offers(offers, request);
+        // Sort and limit
         offers.sort_by(|a, b| a.price.amount.cmp(&b.price.amount));
-
-        // Limit results
         if offers.len() > self.max_results {
             offers.truncate(self.max_results);
         }
 
         if offers.is_empty() {
+            if !pending.is_empty() {
+                // Nobody answered before the hard deadline
+                for (_, provider) in &pending {
+                    record_provider_reply(provider, "timeout", 0, self.timeout);
+                }
+                return Err(CoreError::SearchTimeout);
+            }
+            if let Some(e) = first_error {
+                return Err(e);
+            }
             return Err(CoreError::NoFlightsFound {
                 origin: request.origin.as_str().to_string(),
                 destination: request.destination.as_str().to_string(),
             });
         }
 
-        // Cache the results (5 minute TTL)
-        self.cache.insert(
-            cache_key.clone(),
-            offers.clone(),
-            Some(Duration::from_secs(300)),
-        );
+        Ok((offers, pending, rx))
+    }
 
-        // Calculate price insight
-        let price_insight = self.calculate_insight(request, &offers);
+    /// Drop cached searches for a route, e.g. after a booking changes availability
+    ///
+    /// Returns the number of cached searches removed.
+    pub fn invalidate_route(&self, origin: &IataCode, destination: &IataCode) -> usize {
+        let removed = self
+            .cache
+            .invalidate_prefix(&format!("search:{}:{}:", origin, destination));
+        if removed > 0 {
+            debug!(
+                "Invalidated {} cached searches for {} -> {}",
+                removed, origin, destination
+            );
+        }
+        removed
+    }
 
-        Ok(SearchResponse {
-            offers,
-            search_id: cache_key,
-            cached: false,
-            price_insight,
-        })
+    /// Wait for the next provider that missed the initial response
+    ///
+    /// Late offers are merged into the cached results before being
+    /// returned. Returns `None` once every provider has answered or the
+    /// search timeout has passed.
+    pub async fn next_late(&self, stream: &mut StreamingSearch) -> Option<LateResults> {
+        loop {
+            let replies = stream.replies.as_mut()?;
+            let reply = match tokio::time::timeout_at(stream.hard_deadline, replies.recv()).await {
+                Ok(Some(reply)) => reply,
+                Ok(None) | Err(_) => {
+                    for (_, provider) in stream.pending.drain(..) {
+                        record_provider_reply(provider, "timeout", 0, self.timeout);
+                    }
+                    stream.replies = None;
+                    return None;
+                }
+            };
+            stream.pending.retain(|(index, _)| *index != reply.index);
+            if stream.pending.is_empty() {
+                stream.replies = None;
+            }
+
+            let offers = match self.convert_reply(&reply, &stream.request) {
+                Ok(offers) => offers,
+                Err(e) => {
+                    warn!("Late search results from {} failed: {}", reply.provider, e);
+                    record_provider_reply(reply.provider, "failed", 0, reply.latency);
+                    continue;
+                }
+            };
+            record_provider_reply(reply.provider, "late", offers.len(), reply.latency);
+            self.record_prices(&stream.request, &offers);
+
+            // Merge into the cached results so later pages include them
+            let search_id = stream.response.search_id.clone();
+            let mut merged = self.cache.get(&search_id).unwrap_or_default();
+            merged.extend(offers.iter().cloned());
+            merged.sort_by_key(|o| o.price.amount);
+            merged.truncate(self.max_results);
+            self.cache
+                .insert(search_id.clone(), merged, Some(SEARCH_CACHE_TTL));
+
+            return Some(LateResults {
+                search_id,
+                provider: reply.provider,
+                offers,
+                latency: reply.latency,
+            });
+        }
+    }
+
+    /// Drain late results, publishing each as a `search_late_results` event
+    ///
+    /// The live-update hub forwards these to clients watching the route.
+    /// Returns the number of late offers published.
+    pub async fn publish_late(&self, mut stream: StreamingSearch) -> usize {
+        let mut published = 0;
+        while let Some(late) = self.next_late(&mut stream).await {
+            let cheapest = late.offers.iter().min_by_key(|o| o.price.amount);
+            published += late.offers.len();
+            bus::publish(
+                &topics::SEARCH_LATE_RESULTS,
+                SearchLateResults {
+                    search_id: late.search_id,
+                    origin: stream.request.origin.as_str().to_string(),
+                    destination: stream.request.destination.as_str().to_string(),
+                    provider: late.provider.to_string(),
+                    result_count: late.offers.len() as u32,
+                    cheapest_minor: cheapest.map(|o| o.price.amount.as_i64()),
+                    currency: cheapest.map(|o| o.price.currency.as_str().to_string()),
+                    latency_ms: late.latency.as_millis() as u64,
+                },
+            );
+        }
+        published
+    }
+
+    /// Convert and filter one provider's offers
+    fn convert_reply(
+        &self,
+        reply: &ProviderReply,
+        request: &SearchRequest,
+    ) -> CoreResult<Vec<FlightOffer>> {
+        let gds_offers = reply
+            .result
+            .as_ref()
+            .map_err(|e| CoreError::GdsError(e.to_string()))?;
+        let mut offers = self.convert_gds_offers(gds_offers)?;
+        for offer in &mut offers {
+            offer.source = reply.provider.to_string();
+        }
+        Ok(self.filter_offers(offers, request))
+    }
+
+    /// Record observed prices, if price history is configured
+    ///
+    /// A failed write is logged rather than failing the search.
+    fn record_prices(&self, request: &SearchRequest, offers: &[FlightOffer]) {
+        if let Some(history) = &self.price_history {
+            if let Err(e) = history.record(request, offers) {
+                warn!(
+                    "Failed to record prices for {} -> {}: {}",
+                    request.origin, request.destination, e
+                );
+            }
+        }
+    }
+
+    /// Log a `search_performed` domain event
+    fn emit_search_event(
+        &self,
+        request: &SearchRequest,
+        search_id: &str,
+        result_count: usize,
+        cached: bool,
+        started: Instant,
+    ) {
+        bus::publish(
+            &topics::SEARCH_PERFORMED,
+            SearchPerformed {
+                search_id: search_id.to_string(),
+                origin: request.origin.as_str().to_string(),
+                destination: request.destination.as_str().to_string(),
+                departure_date: request.departure_date.clone(),
+                return_date: request.return_date.clone(),
+                passengers: request.passengers.total() as u32,
+                cabin: request.cabin_class.code().to_string(),
+                result_count: result_count as u32,
+                cached,
+                duration_ms: started.elapsed().as_millis() as u64,
+            },
+        );
     }
 
     /// Build cache key from search request
     fn build_cache_key(&self, request: &SearchRequest) -> String {
+        let legs: Vec<String> = request
+            .legs
+            .iter()
+            .map(|l| format!("{}-{}-{}", l.origin, l.destination, l.departure_date))
+            .collect();
         format!(
-            "search:{}:{}:{}:{}:{}:{}",
+            "search:{}:{}:{}:{}:{}:{}:{}",
             request.origin,
             request.destination,
             request.departure_date,
             request.return_date.as_deref().unwrap_or(""),
             request.passengers.total(),
             request.cabin_class.code(),
+            legs.join(","),
         )
     }
 
@@ -164,6 +620,18 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
             None => None,
         };
 
+        // Parse multi-city legs
+        let legs = request
+            .legs
+            .iter()
+            .map(|leg| {
+                let date = parse_date(&leg.departure_date).ok_or_else(|| {
+                    CoreError::InvalidSearchParams("Invalid leg departure date format".to_string())
+                })?;
+                Ok(SearchLeg::new(leg.origin, leg.destination, date))
+            })
+            .collect::<CoreResult<Vec<_>>>()?;
+
         // Map cabin class
         let cabin_class = match request.cabin_class {
             CabinClass::Economy => vaya_gds::CabinClass::Economy,
@@ -184,6 +652,7 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
             direct_only: request.direct_only,
             max_results: request.max_results.unwrap_or(50) as u32,
             currency: request.currency,
+            legs,
         })
     }
 
@@ -210,6 +679,11 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
             .as_ref()
             .map(|r| self.convert_journey(r))
             .transpose()?;
+        let onward = gds
+            .onward_itineraries
+            .iter()
+            .map(|i| self.convert_journey(i))
+            .collect::<CoreResult<Vec<_>>>()?;
 
         // Get cabin class from first segment
         let cabin_class = gds
@@ -279,12 +753,14 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
             .map(|r| r.refundable)
             .unwrap_or(false);
 
-        Ok(FlightOffer {
+        let mut offer = FlightOffer {
             id: gds.id.clone(),
             airlines: gds.airlines(),
             outbound,
             inbound,
+            onward,
             price: gds.price.total,
+            retail: None,
             price_breakdown: vec![], // Would convert from gds.price per passenger
             fare_conditions,
             cabin_class,
@@ -295,7 +771,18 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
                 .expires_at
                 .unwrap_or_else(|| Timestamp::now().add_mins(30)),
             source: "amadeus".to_string(),
-        })
+        };
+
+        // Apply retail pricing to the net GDS fare
+        if let Some(engine) = &self.pricing {
+            if let Some(ctx) = PricingContext::for_offer(&offer) {
+                let retail = engine.price(&NetFare::from(&gds.price), &ctx);
+                offer.price = retail.total;
+                offer.retail = Some(retail);
+            }
+        }
+
+        Ok(offer)
     }
 
     /// Convert GDS journey (itinerary) to core journey
@@ -317,10 +804,10 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
                     flight_number: s.flight_number.clone(),
                     operating_carrier: None, // GDS segment doesn't have this field
                     origin: s.departure.airport,
-                    departure_time: s.departure.datetime.to_string(),
+                    departure_time: s.departure.zoned().to_string(),
                     departure_terminal: s.departure.terminal.clone(),
                     destination: s.arrival.airport,
-                    arrival_time: s.arrival.datetime.to_string(),
+                    arrival_time: s.arrival.zoned().to_string(),
                     arrival_terminal: s.arrival.terminal.clone(),
                     duration_minutes: s.duration_minutes,
                     aircraft: s.aircraft.clone(),
@@ -379,6 +866,23 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
         })
     }
 
+    /// Re-price an offer with the GDS
+    ///
+    /// Confirms the offer is still bookable and returns it with current
+    /// pricing.
+    pub async fn reprice_offer(&self, offer_id: &str) -> CoreResult<FlightOffer> {
+        let priced = tokio::time::timeout(self.timeout, self.gds.price_offer(offer_id))
+            .await
+            .map_err(|_| CoreError::SearchTimeout)?
+            .map_err(|e| match e {
+                vaya_gds::GdsError::OfferExpired { .. }
+                | vaya_gds::GdsError::FlightUnavailable(_)
+                | vaya_gds::GdsError::NotFound { .. } => CoreError::FareNotAvailable(e.to_string()),
+                e => CoreError::GdsError(e.to_string()),
+            })?;
+        self.convert_single_offer(&priced)
+    }
+
     /// Get offer by ID
     pub async fn get_offer(&self, offer_id: &str) -> CoreResult<FlightOffer> {
         // Search all cached results for the offer
@@ -390,6 +894,29 @@ impl<G: GdsProvider + Send + Sync> SearchService<G> {
     }
 }
 
+/// Record a provider's contribution to a search
+fn record_provider_reply(provider: &str, outcome: &str, offers: usize, latency: Duration) {
+    let registry = metrics::global();
+    registry
+        .counter(
+            "vaya_core_search_provider_responses_total",
+            &[("provider", provider), ("outcome", outcome)],
+        )
+        .inc();
+    registry
+        .counter(
+            "vaya_core_search_provider_offers_total",
+            &[("provider", provider)],
+        )
+        .add(offers as u64);
+    registry
+        .gauge(
+            "vaya_core_search_provider_last_latency_seconds",
+            &[("provider", provider)],
+        )
+        .set(latency.as_secs_f64());
+}
+
 /// Search response
 #[derive(Debug, Clone)]
 pub struct SearchResponse {
@@ -421,9 +948,87 @@ pub struct SearchPriceInsight {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use vaya_gds::traits::mock::MockGdsProvider;
+
+    /// A fast primary and a slow secondary that share a provider name
+    fn same_named_providers() -> SearchService<MockGdsProvider> {
+        let primary = MockGdsProvider::new().with_fares(&[50_000]);
+        let secondary = MockGdsProvider::new()
+            .with_fares(&[40_000])
+            .with_delay(Duration::from_millis(50));
+        SearchService::new(Arc::new(primary), Arc::new(Cache::new(64, 4)))
+            .with_provider(Arc::new(secondary))
+    }
+
+    #[tokio::test]
+    async fn test_late_provider_sharing_primary_name() {
+        let service = same_named_providers();
+        let request = SearchRequest::one_way(IataCode::KUL, IataCode::SIN, "2030-06-15");
+        let mut stream = service.search_streaming(&request).await.unwrap();
+        assert_eq!(stream.response.offers.len(), 1);
+        assert_eq!(stream.pending_providers(), vec!["MockGDS"]);
+
+        let late = service.next_late(&mut stream).await.unwrap();
+        assert_eq!(late.offers.len(), 1);
+        assert!(!stream.has_pending());
+        assert!(service.next_late(&mut stream).await.is_none());
+
+        // Late offers are merged into the cached results
+        let cached = service.search(&request).await.unwrap();
+        assert!(cached.cached);
+        assert_eq!(cached.offers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_publish_late() {
+        let late = bus::global().subscribe_buffered(
+            &topics::SEARCH_LATE_RESULTS,
+            "test",
+            bus::BufferConfig::default(),
+        );
+        let service = same_named_providers();
+        let request = SearchRequest::one_way(IataCode::KUL, IataCode::NRT, "2030-06-15");
+        let stream = service.search_streaming(&request).await.unwrap();
+        let search_id = stream.response.search_id.clone();
+        assert_eq!(service.publish_late(stream).await, 1);
+
+        // Other tests publish on the global bus too
+        let event = std::iter::from_fn(|| late.try_recv())
+            .find(|e| e.search_id == search_id)
+            .unwrap();
+        assert_eq!(
+            (event.origin.as_str(), event.destination.as_str()),
+            ("KUL", "NRT")
+        );
+        assert_eq!(event.result_count, 1);
+        // RM400 fare plus RM40 taxes
+        assert_eq!(event.cheapest_minor, Some(44_000));
+    }
 
     #[test]
     fn test_cache_key_generation() {
         // Would test cache key generation
     }
+
+    #[test]
+    fn test_fan_out_policy() {
+        let policy = FanOutPolicy::default();
+
+        // Single provider: wait for it
+        assert!(!policy.is_satisfied(1, 0, 0, false));
+        assert!(policy.is_satisfied(1, 1, 0, false));
+
+        // Primary answered first
+        assert!(policy.is_satisfied(3, 1, 1, true));
+        // A secondary answered, but under quorum
+        assert!(!policy.is_satisfied(3, 1, 1, false));
+        // Quorum reached
+        assert!(policy.is_satisfied(4, 2, 1, false));
+        // Quorum of failures alone does not return
+        assert!(!policy.is_satisfied(4, 2, 0, false));
+
+        let all = FanOutPolicy::wait_for_all();
+        assert!(!all.is_satisfied(3, 2, 2, true));
+        assert!(all.is_satisfied(3, 3, 0, false));
+    }
 }
@@ -0,0 +1,134 @@
+//! Seat selection
+//!
+//! Seat maps come from the GDS holding the booking. A selection is checked
+//! against the map of its segment: the seat must exist, be free, and be
+//! priced in the booking currency. Paid seats are then added to the
+//! booking's price breakdown by [`Booking::assign_seat`].
+
+use vaya_book::{BookError, Booking, SeatAssignment};
+use vaya_common::MinorUnits;
+use vaya_gds::{GdsProvider, SeatMap};
+
+use crate::error::{CoreError, CoreResult};
+
+/// Seat maps for a booking's flights
+pub async fn seat_maps<G: GdsProvider + ?Sized>(
+    gds: &G,
+    booking: &Booking,
+) -> CoreResult<Vec<SeatMap>> {
+    let pnr = booking.provider_ref.as_deref().ok_or_else(|| {
+        CoreError::BookingNotModifiable(format!("Booking {} has no GDS record", booking.pnr))
+    })?;
+    Ok(gds.seat_maps(pnr).await?)
+}
+
+/// Check a seat against the seat map of its segment
+pub fn validate_seat(
+    maps: &[SeatMap],
+    booking: &Booking,
+    segment: usize,
+    seat_number: &str,
+) -> CoreResult<SeatAssignment> {
+    let flight = booking
+        .segments()
+        .nth(segment)
+        .ok_or_else(|| CoreError::SeatUnavailable(format!("Booking has no segment {segment}")))?;
+    let map = maps
+        .iter()
+        .find(|m| m.segment == segment)
+        .filter(|m| m.origin == flight.origin && m.destination == flight.destination)
+        .ok_or_else(|| {
+            CoreError::SeatUnavailable(format!("No seat map for {}", flight.designator()))
+        })?;
+
+    let seat = map.seat(seat_number.trim()).ok_or_else(|| {
+        CoreError::SeatUnavailable(format!(
+            "Seat {} does not exist on {}",
+            seat_number, map.flight
+        ))
+    })?;
+    if !seat.is_available() {
+        return Err(CoreError::SeatUnavailable(format!(
+            "Seat {} on {} is not available",
+            seat.number, map.flight
+        )));
+    }
+    let price = match seat.price {
+        Some(price) if price.currency != booking.currency => {
+            return Err(CoreError::SeatUnavailable(format!(
+                "Seat {} is priced in {}, booking in {}",
+                seat.number, price.currency, booking.currency
+            )))
+        }
+        Some(price) => price.amount,
+        None => MinorUnits::ZERO,
+    };
+
+    Ok(SeatAssignment::new(segment, &seat.number, price))
+}
+
+/// Fetch the seat maps and assign a checked seat to a passenger
+pub async fn select_seat<G: GdsProvider + ?Sized>(
+    gds: &G,
+    booking: &mut Booking,
+    passenger_id: u8,
+    segment: usize,
+    seat_number: &str,
+) -> CoreResult<SeatAssignment> {
+    let maps = seat_maps(gds, booking).await?;
+    let seat = validate_seat(&maps, booking, segment, seat_number)?;
+    booking
+        .assign_seat(passenger_id, seat.clone())
+        .map_err(|e| match e {
+            BookError::InvalidSeat(msg) => CoreError::SeatUnavailable(msg),
+            e if e.is_validation() => CoreError::ValidationError(e.to_string()),
+            e => CoreError::BookingNotModifiable(e.to_string()),
+        })?;
+    Ok(seat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_gds::traits::mock::MockGdsProvider;
+
+    use crate::testing;
+
+    fn booking() -> Booking {
+        let mut booking = testing::booking();
+        booking.confirm("GDS123", "system").unwrap();
+        booking
+    }
+
+    #[tokio::test]
+    async fn test_select_paid_seat() {
+        let mut booking = booking();
+        let seat = select_seat(&MockGdsProvider::new(), &mut booking, 1, 0, "1a")
+            .await
+            .unwrap();
+        assert_eq!(seat.seat_number, "1A");
+        assert_eq!(booking.offer.price.seats, MinorUnits::new(9_000));
+        assert_eq!(booking.total_price, MinorUnits::new(159_000));
+    }
+
+    #[tokio::test]
+    async fn test_seat_must_be_on_map_and_free() {
+        let mut booking = booking();
+        let maps = seat_maps(&MockGdsProvider::new(), &booking).await.unwrap();
+
+        for (segment, number) in [(0, "40K"), (0, "12B"), (0, "14C"), (1, "12A")] {
+            assert!(matches!(
+                validate_seat(&maps, &booking, segment, number),
+                Err(CoreError::SeatUnavailable(_))
+            ));
+        }
+        let free = validate_seat(&maps, &booking, 0, "12A").unwrap();
+        assert_eq!(free.price, MinorUnits::ZERO);
+
+        // Unknown passenger
+        assert!(matches!(
+            select_seat(&MockGdsProvider::new(), &mut booking, 9, 0, "12A").await,
+            Err(CoreError::ValidationError(_))
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+//! Split payment refunds
+//!
+//! A [`SplitPayment`] queues refunds when its deadline passes before every
+//! share is paid. [`refund_unfunded`] expires such a split and sends the
+//! queued refunds to the payment provider. Refunds that fail stay pending
+//! and are retried on the next call.
+
+use tracing::{info, warn};
+
+use vaya_book::{Booking, SplitPayment};
+use vaya_common::Price;
+use vaya_payment::{PaymentProvider, RefundReason, RefundRequest};
+
+use crate::error::{CoreError, CoreResult};
+
+/// Expire an unfunded split and refund its collected payments
+///
+/// Returns the number of refunds completed by this call.
+pub async fn refund_unfunded<P: PaymentProvider + ?Sized>(
+    payments: &P,
+    split: &mut SplitPayment,
+    booking: &mut Booking,
+) -> CoreResult<usize> {
+    if booking.pnr != split.booking_ref {
+        return Err(CoreError::ValidationError(format!(
+            "Split is for booking {}, not {}",
+            split.booking_ref, booking.pnr
+        )));
+    }
+    split.check_expiry(booking);
+
+    let pending: Vec<_> = split.pending_refunds().cloned().collect();
+    let mut refunded = 0;
+    for refund in pending {
+        // Refund against the provider's payment, not our record ID
+        let payment_id = split
+            .shares
+            .iter()
+            .flat_map(|s| &s.payments)
+            .find(|p| p.id == refund.payment_id)
+            .and_then(|p| p.provider_ref.clone())
+            .unwrap_or_else(|| refund.payment_id.clone());
+        let request = RefundRequest {
+            payment_id,
+            amount: Some(Price::new(refund.amount, refund.currency)),
+            reason: RefundReason::BookingCancelled,
+            idempotency_key: Some(format!("refund_split_{}", refund.id)),
+        };
+
+        match payments.create_refund(&request).await {
+            Ok(result) => {
+                split
+                    .complete_refund(&refund.id, Some(result.id), true)
+                    .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+                refunded += 1;
+            }
+            Err(e) => {
+                warn!(
+                    booking = %split.booking_ref,
+                    refund = %refund.id,
+                    error = %e,
+                    "Split payment refund failed"
+                );
+                split
+                    .complete_refund(&refund.id, None, false)
+                    .map_err(|e| CoreError::RefundFailed(e.to_string()))?;
+            }
+        }
+    }
+
+    if refunded > 0 {
+        info!(
+            booking = %split.booking_ref,
+            refunded,
+            status = split.status.as_str(),
+            "Refunded unfunded split payment"
+        );
+    }
+    Ok(refunded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use vaya_book::{BookingStatus, SplitStatus};
+    use vaya_common::Timestamp;
+    use vaya_payment::mock::MockPaymentProvider;
+    use vaya_payment::PaymentError;
+
+    use crate::testing::{self, paid};
+
+    fn booking() -> Booking {
+        let offer = testing::offer("offer-1", 270_000);
+        let mut booking = Booking::new("user-1", offer, vec![testing::passenger()]).unwrap();
+        booking.confirm("GDS123", "system").unwrap();
+        booking
+    }
+
+    #[tokio::test]
+    async fn test_refunds_collected_shares_after_deadline() {
+        let payments = MockPaymentProvider::new();
+        payments.fail_next(PaymentError::Timeout);
+        let mut booking = booking();
+        let mut split = SplitPayment::equal_shares(&booking, &["ana", "ben", "chen"]).unwrap();
+        split
+            .record_payment(&mut booking, "ana", paid("p1", 100_000), "ana")
+            .unwrap();
+        split
+            .record_payment(&mut booking, "ben", paid("p2", 100_000), "ben")
+            .unwrap();
+
+        // Before the deadline nothing happens
+        let refunded = refund_unfunded(&payments, &mut split, &mut booking)
+            .await
+            .unwrap();
+        assert_eq!(refunded, 0);
+        assert_eq!(split.status, SplitStatus::PartiallyPaid);
+
+        split.deadline = Timestamp::now().as_unix() - 1;
+        let refunded = refund_unfunded(&payments, &mut split, &mut booking)
+            .await
+            .unwrap();
+        assert_eq!(refunded, 1);
+        assert_eq!(split.status, SplitStatus::Refunding);
+        assert_eq!(booking.status, BookingStatus::Expired);
+
+        // The failed refund is retried
+        let refunded = refund_unfunded(&payments, &mut split, &mut booking)
+            .await
+            .unwrap();
+        assert_eq!(refunded, 1);
+        assert_eq!(split.status, SplitStatus::Refunded);
+        let mut ids: Vec<_> = payments
+            .refunds()
+            .into_iter()
+            .map(|r| r.payment_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["pi_p1", "pi_p2"]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Fixtures shared by the unit tests
+//!
+//! The standard booking is MH88 from Kuala Lumpur to Tokyo Narita on
+//! 1 June 2025, RM1,200 plus RM300 taxes, for one adult. Tests change what
+//! they need on top.
+
+use time::{Date, Month, Time};
+use vaya_book::{Booking, Passenger, PaymentMethod, PaymentRecord};
+use vaya_common::{AirlineCode, CurrencyCode, Gender, IataCode, MinorUnits};
+use vaya_search::{CabinClass, FlightLeg, FlightOffer, FlightSegment, PriceBreakdown};
+
+/// Departure date of the standard flight
+pub fn departure_date() -> Date {
+    Date::from_calendar_date(2025, Month::June, 1).unwrap()
+}
+
+/// MH88 economy from `origin` to `destination`, 23:30 to 07:30
+pub fn segment(origin: IataCode, destination: IataCode) -> FlightSegment {
+    FlightSegment {
+        airline: AirlineCode::MH,
+        flight_number: "88".into(),
+        marketing_airline: None,
+        origin,
+        destination,
+        departure_date: departure_date(),
+        departure_time: Time::from_hms(23, 30, 0).unwrap(),
+        arrival_date: departure_date(),
+        arrival_time: Time::from_hms(7, 30, 0).unwrap(),
+        duration_minutes: 420,
+        aircraft: None,
+        cabin: CabinClass::Economy,
+        booking_class: 'Y',
+        seats_remaining: None,
+    }
+}
+
+/// One-way MH88 KUL-NRT offer with a `base` fare (sen) plus RM300 taxes
+pub fn offer(id: &str, base: i64) -> FlightOffer {
+    FlightOffer {
+        id: id.into(),
+        outbound: FlightLeg {
+            segments: vec![segment(IataCode::KUL, IataCode::NRT)],
+            total_duration_minutes: 420,
+        },
+        inbound: None,
+        price: PriceBreakdown {
+            base_fare: MinorUnits::new(base),
+            taxes: MinorUnits::new(30_000),
+            surcharges: MinorUnits::ZERO,
+            seats: MinorUnits::ZERO,
+            currency: CurrencyCode::MYR,
+        },
+        price_per_pax: vec![],
+        expires_at: None,
+        provider: "test".into(),
+        refundable: true,
+        changeable: true,
+        baggage: None,
+        fare_rules: None,
+        self_transfer: false,
+        variation: None,
+    }
+}
+
+/// Adult passenger 1
+pub fn passenger() -> Passenger {
+    let dob = Date::from_calendar_date(1990, Month::January, 15).unwrap();
+    let mut passenger = Passenger::adult("Aisyah", "Rahman", dob, Gender::Female);
+    passenger.id = 1;
+    passenger
+}
+
+/// The standard booking for `user-1`, not yet held with the GDS
+pub fn booking() -> Booking {
+    Booking::new("user-1", offer("offer-1", 120_000), vec![passenger()]).unwrap()
+}
+
+/// Completed card payment `id` of `amount` sen, with provider reference `pi_<id>`
+pub fn paid(id: &str, amount: i64) -> PaymentRecord {
+    let mut payment = PaymentRecord::new(
+        id,
+        MinorUnits::new(amount),
+        CurrencyCode::MYR,
+        PaymentMethod::Card,
+    );
+    payment.complete(Some(format!("pi_{id}")));
+    payment
+}
@@ -0,0 +1,478 @@
+//! Booking timeline for support
+//!
+//! A booking's story is spread over several systems: its status history,
+//! payment and refund records, GDS calls, notifications sent, queue
+//! messages and admin actions. [`TimelineService`] asks each registered
+//! [`TimelineSource`] for its events and merges them into one ordered,
+//! typed timeline. A source that fails is reported on the timeline rather
+//! than hiding the events from the others.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use tracing::warn;
+
+use vaya_book::{Booking, BookingNote, NoteVisibility, PaymentRecord, RefundRecord, StatusChange};
+use vaya_common::Timestamp;
+
+use crate::admin::{AuditEntry, AuditLog};
+use crate::error::CoreResult;
+
+/// Kind of timeline event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimelineCategory {
+    /// Booking created
+    Created,
+    /// Booking status changed
+    Status,
+    /// Payment attempt or result
+    Payment,
+    /// Refund
+    Refund,
+    /// Call to a GDS
+    Gds,
+    /// Ticket issued or voided
+    Ticketing,
+    /// Email or SMS sent
+    Notification,
+    /// Airline schedule change
+    ScheduleChange,
+    /// Support ticket or note
+    Support,
+    /// Admin action
+    Admin,
+}
+
+impl TimelineCategory {
+    /// Get category code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimelineCategory::Created => "created",
+            TimelineCategory::Status => "status",
+            TimelineCategory::Payment => "payment",
+            TimelineCategory::Refund => "refund",
+            TimelineCategory::Gds => "gds",
+            TimelineCategory::Ticketing => "ticketing",
+            TimelineCategory::Notification => "notification",
+            TimelineCategory::ScheduleChange => "schedule_change",
+            TimelineCategory::Support => "support",
+            TimelineCategory::Admin => "admin",
+        }
+    }
+}
+
+/// One entry on a booking timeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEvent {
+    /// When it happened
+    pub at: Timestamp,
+    /// Kind of event
+    pub category: TimelineCategory,
+    /// Source system that recorded it (e.g. "booking", "payments")
+    pub source: String,
+    /// One-line description
+    pub summary: String,
+    /// Who caused it (user, admin, "system")
+    pub actor: Option<String>,
+    /// Related record ID (payment ID, message ID, ticket ID)
+    pub reference: Option<String>,
+}
+
+impl TimelineEvent {
+    /// Create an event
+    pub fn new(
+        at: Timestamp,
+        category: TimelineCategory,
+        source: impl Into<String>,
+        summary: impl Into<String>,
+    ) -> Self {
+        Self {
+            at,
+            category,
+            source: source.into(),
+            summary: summary.into(),
+            actor: None,
+            reference: None,
+        }
+    }
+
+    /// Set the actor
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Set the related record ID
+    pub fn with_reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    /// Event for a booking status change
+    pub fn from_status_change(change: &StatusChange) -> Self {
+        let at = Timestamp::from_unix(change.timestamp);
+        let event = match change.from {
+            None => TimelineEvent::new(
+                at,
+                TimelineCategory::Created,
+                "booking",
+                format!("Booking created ({})", change.to.as_str()),
+            ),
+            Some(from) => {
+                let category = match change.to {
+                    vaya_book::BookingStatus::Ticketed => TimelineCategory::Ticketing,
+                    _ => TimelineCategory::Status,
+                };
+                TimelineEvent::new(
+                    at,
+                    category,
+                    "booking",
+                    format!(
+                        "{} -> {}: {}",
+                        from.as_str(),
+                        change.to.as_str(),
+                        change.reason
+                    ),
+                )
+            }
+        };
+        event.with_actor(change.actor.as_str())
+    }
+
+    /// Event for a payment record
+    pub fn from_payment(payment: &PaymentRecord) -> Self {
+        TimelineEvent::new(
+            Timestamp::from_unix(payment.timestamp),
+            TimelineCategory::Payment,
+            "payments",
+            format!(
+                "{} payment of {} {} {}",
+                payment.method.as_str(),
+                payment.amount.as_i64(),
+                payment.currency.as_str(),
+                payment.status.as_str()
+            ),
+        )
+        .with_reference(payment.id.as_str())
+    }
+
+    /// Event for a refund record
+    pub fn from_refund(refund: &RefundRecord) -> Self {
+        TimelineEvent::new(
+            Timestamp::from_unix(refund.timestamp),
+            TimelineCategory::Refund,
+            "payments",
+            format!(
+                "Refund of {} {} {}: {}",
+                refund.amount.as_i64(),
+                refund.currency.as_str(),
+                refund.status.as_str(),
+                refund.reason
+            ),
+        )
+        .with_reference(refund.id.as_str())
+    }
+
+    /// Event for a note on the booking
+    ///
+    /// Customer-visible notes are marked so agents know the traveler saw them.
+    pub fn from_note(note: &BookingNote) -> Self {
+        let visibility = match note.visibility {
+            NoteVisibility::Customer => " (customer-visible)",
+            NoteVisibility::Internal => "",
+        };
+        TimelineEvent::new(
+            Timestamp::from_unix(note.timestamp),
+            TimelineCategory::Support,
+            "booking",
+            format!("{}{}: {}", note.category.label(), visibility, note.content),
+        )
+        .with_actor(note.author.as_str())
+    }
+
+    /// Event for an admin audit entry
+    pub fn from_audit(entry: &AuditEntry) -> Self {
+        let mut summary = format!("{} ({})", entry.action.as_str(), entry.reason);
+        if let Some(details) = &entry.details {
+            summary.push_str(": ");
+            summary.push_str(details);
+        }
+        TimelineEvent::new(entry.at, TimelineCategory::Admin, "audit", summary)
+            .with_actor(entry.actor_id.as_str())
+            .with_reference(entry.id.as_str())
+    }
+}
+
+/// Events recorded on a booking itself: status history, payments and notes
+pub fn booking_events(booking: &Booking) -> Vec<TimelineEvent> {
+    booking
+        .history
+        .iter()
+        .map(TimelineEvent::from_status_change)
+        .chain(booking.payments.iter().map(TimelineEvent::from_payment))
+        .chain(booking.notes.iter().map(TimelineEvent::from_note))
+        .collect()
+}
+
+/// Booking a timeline is built for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineSubject {
+    /// Booking ID
+    pub booking_id: String,
+    /// Record locator
+    pub pnr: String,
+    /// Booking owner
+    pub user_id: String,
+}
+
+/// A system that can contribute events to booking timelines
+pub trait TimelineSource: Send + Sync {
+    /// Source name, shown when the source fails
+    fn name(&self) -> &'static str;
+
+    /// Events for a booking, in any order
+    fn events(&self, subject: &TimelineSubject) -> CoreResult<Vec<TimelineEvent>>;
+}
+
+/// Admin actions on the booking's owner
+impl TimelineSource for AuditLog {
+    fn name(&self) -> &'static str {
+        "audit"
+    }
+
+    fn events(&self, subject: &TimelineSubject) -> CoreResult<Vec<TimelineEvent>> {
+        Ok(self
+            .entries_for(&subject.user_id)
+            .iter()
+            .map(TimelineEvent::from_audit)
+            .collect())
+    }
+}
+
+/// Events recorded against booking IDs in memory
+///
+/// For systems without their own store yet (GDS call logs, sent
+/// notifications, support tickets); they record events here as they
+/// happen.
+#[derive(Debug)]
+pub struct MemoryTimelineSource {
+    name: &'static str,
+    events: RwLock<HashMap<String, Vec<TimelineEvent>>>,
+}
+
+impl MemoryTimelineSource {
+    /// Create an empty source
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            events: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record an event for a booking
+    pub fn record(&self, booking_id: &str, event: TimelineEvent) {
+        self.events
+            .write()
+            .unwrap()
+            .entry(booking_id.to_string())
+            .or_default()
+            .push(event);
+    }
+}
+
+impl TimelineSource for MemoryTimelineSource {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn events(&self, subject: &TimelineSubject) -> CoreResult<Vec<TimelineEvent>> {
+        Ok(self
+            .events
+            .read()
+            .unwrap()
+            .get(&subject.booking_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// A booking's merged timeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookingTimeline {
+    /// Booking
+    pub subject: TimelineSubject,
+    /// Events, oldest first
+    pub events: Vec<TimelineEvent>,
+    /// Sources that could not be read; the timeline may be missing events
+    pub unavailable_sources: Vec<String>,
+}
+
+impl BookingTimeline {
+    /// Events of one category
+    pub fn of_category(&self, category: TimelineCategory) -> impl Iterator<Item = &TimelineEvent> {
+        self.events.iter().filter(move |e| e.category == category)
+    }
+}
+
+/// Builds booking timelines from registered sources
+#[derive(Default)]
+pub struct TimelineService {
+    sources: Vec<Arc<dyn TimelineSource>>,
+}
+
+impl TimelineService {
+    /// Create a service with no sources
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source
+    pub fn with_source(mut self, source: Arc<dyn TimelineSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Build the timeline for a booking
+    ///
+    /// `booking` supplies the booking's own history; the registered sources
+    /// supply the rest. Events with the same time keep source order.
+    pub fn timeline(&self, subject: TimelineSubject, booking: &Booking) -> BookingTimeline {
+        let mut events = booking_events(booking);
+        let mut unavailable_sources = Vec::new();
+        for source in &self.sources {
+            match source.events(&subject) {
+                Ok(found) => events.extend(found),
+                Err(e) => {
+                    warn!(
+                        booking_id = %subject.booking_id,
+                        source = source.name(),
+                        error = %e,
+                        "Timeline source unavailable"
+                    );
+                    unavailable_sources.push(source.name().to_string());
+                }
+            }
+        }
+        events.sort_by_key(|e| e.at);
+        BookingTimeline {
+            subject,
+            events,
+            unavailable_sources,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CoreError;
+    use crate::testing;
+    use vaya_book::BookingStatus;
+
+    struct Down;
+
+    impl TimelineSource for Down {
+        fn name(&self) -> &'static str {
+            "support_desk"
+        }
+
+        fn events(&self, _subject: &TimelineSubject) -> CoreResult<Vec<TimelineEvent>> {
+            Err(CoreError::ServiceUnavailable("support desk".into()))
+        }
+    }
+
+    fn booking() -> Booking {
+        let mut booking = testing::booking();
+        let created = booking.created_at;
+        let mut payment = testing::paid("pay-1", 150_000);
+        payment.timestamp = created + 60;
+        booking.payments.push(payment);
+        booking
+            .transition(BookingStatus::Confirmed, "Payment received", "system")
+            .unwrap();
+        booking.history[1].timestamp = created + 120;
+        booking
+    }
+
+    #[test]
+    fn test_timeline_merges_sources_in_order() {
+        let booking = booking();
+        let created = booking.created_at;
+        let subject = TimelineSubject {
+            booking_id: "bk-1".into(),
+            pnr: booking.pnr.clone(),
+            user_id: "user-1".into(),
+        };
+
+        let gds = Arc::new(MemoryTimelineSource::new("gds"));
+        gds.record(
+            "bk-1",
+            TimelineEvent::new(
+                Timestamp::from_unix(created + 30),
+                TimelineCategory::Gds,
+                "gds",
+                "Amadeus PNR_AddMultiElements OK",
+            ),
+        );
+        gds.record(
+            "bk-2",
+            TimelineEvent::new(
+                Timestamp::from_unix(created),
+                TimelineCategory::Gds,
+                "gds",
+                "other booking",
+            ),
+        );
+        let service = TimelineService::new()
+            .with_source(gds)
+            .with_source(Arc::new(Down));
+
+        let timeline = service.timeline(subject, &booking);
+        let categories: Vec<_> = timeline.events.iter().map(|e| e.category).collect();
+        assert_eq!(
+            categories,
+            vec![
+                TimelineCategory::Created,
+                TimelineCategory::Gds,
+                TimelineCategory::Payment,
+                TimelineCategory::Status,
+            ]
+        );
+        assert_eq!(timeline.unavailable_sources, vec!["support_desk"]);
+        assert_eq!(
+            timeline
+                .of_category(TimelineCategory::Payment)
+                .next()
+                .unwrap()
+                .reference,
+            Some("pay-1".into())
+        );
+    }
+
+    #[test]
+    fn test_note_events_show_category_and_visibility() {
+        let mut booking = booking();
+        booking
+            .add_note_with(
+                vaya_book::NoteCategory::Support,
+                NoteVisibility::Customer,
+                "Seats moved together",
+                "agent-1",
+            )
+            .unwrap();
+        booking.add_note("Hold extended", "SYSTEM:hold");
+
+        let summaries: Vec<_> = booking_events(&booking)
+            .into_iter()
+            .filter(|e| e.category == TimelineCategory::Support)
+            .map(|e| e.summary)
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                "Support note (customer-visible): Seats moved together",
+                "Note: Hold extended"
+            ]
+        );
+    }
+}
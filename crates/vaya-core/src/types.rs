@@ -1,6 +1,11 @@
 //! Core business types
 
-use vaya_common::{AirlineCode, CurrencyCode, IataCode, Price, Timestamp};
+use vaya_common::{AirlineCode, CurrencyCode, IataCode, Price, Timestamp, UserTier};
+use vaya_payment::PaymentMethodType;
+
+use crate::price_lock::AppliedPriceLock;
+use crate::pricing::RetailPrice;
+use crate::promotions::AppliedPromotion;
 
 /// Passenger type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -119,6 +124,31 @@ pub enum TripType {
     MultiCity,
 }
 
+/// Maximum legs in a multi-city search
+pub const MAX_TRIP_LEGS: usize = 6;
+
+/// One leg of a multi-city trip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TripLeg {
+    /// Origin airport code
+    pub origin: IataCode,
+    /// Destination airport code
+    pub destination: IataCode,
+    /// Departure date (YYYY-MM-DD)
+    pub departure_date: String,
+}
+
+impl TripLeg {
+    /// Create a new leg
+    pub fn new(origin: IataCode, destination: IataCode, departure_date: &str) -> Self {
+        Self {
+            origin,
+            destination,
+            departure_date: departure_date.to_string(),
+        }
+    }
+}
+
 /// Search request
 #[derive(Debug, Clone)]
 pub struct SearchRequest {
@@ -144,6 +174,8 @@ pub struct SearchRequest {
     pub flexible_dates: bool,
     /// Maximum number of results
     pub max_results: Option<u16>,
+    /// Multi-city legs in travel order (empty for one-way and round trips)
+    pub legs: Vec<TripLeg>,
 }
 
 impl SearchRequest {
@@ -161,6 +193,7 @@ impl SearchRequest {
             direct_only: false,
             flexible_dates: false,
             max_results: Some(50),
+            legs: Vec::new(),
         }
     }
 
@@ -183,6 +216,34 @@ impl SearchRequest {
             direct_only: false,
             flexible_dates: false,
             max_results: Some(50),
+            legs: Vec::new(),
+        }
+    }
+
+    /// Create a new multi-city search request
+    ///
+    /// `origin`, `destination` and `departure_date` mirror the trip's first
+    /// departure and final arrival.
+    pub fn multi_city(legs: Vec<TripLeg>) -> Self {
+        let origin = legs.first().map(|l| l.origin).unwrap_or_default();
+        let destination = legs.last().map(|l| l.destination).unwrap_or_default();
+        let departure_date = legs
+            .first()
+            .map(|l| l.departure_date.clone())
+            .unwrap_or_default();
+        Self {
+            origin,
+            destination,
+            departure_date,
+            return_date: None,
+            trip_type: TripType::MultiCity,
+            passengers: PassengerCount::default(),
+            cabin_class: CabinClass::default(),
+            currency: CurrencyCode::MYR,
+            direct_only: false,
+            flexible_dates: false,
+            max_results: Some(50),
+            legs,
         }
     }
 
@@ -212,7 +273,9 @@ impl SearchRequest {
 
     /// Validate the search request
     pub fn validate(&self) -> Result<(), String> {
-        if self.origin == self.destination {
+        if self.trip_type == TripType::MultiCity {
+            self.validate_legs()?;
+        } else if self.origin == self.destination {
             return Err("Origin and destination must be different".to_string());
         }
 
@@ -224,6 +287,26 @@ impl SearchRequest {
 
         Ok(())
     }
+
+    /// Multi-city legs must be distinct routes in date order
+    fn validate_legs(&self) -> Result<(), String> {
+        if self.legs.len() < 2 || self.legs.len() > MAX_TRIP_LEGS {
+            return Err(format!("Multi-city trips need 2 to {} legs", MAX_TRIP_LEGS));
+        }
+        for (i, leg) in self.legs.iter().enumerate() {
+            if leg.origin == leg.destination {
+                return Err(format!(
+                    "Leg {} origin and destination must be different",
+                    i + 1
+                ));
+            }
+            // YYYY-MM-DD sorts chronologically
+            if i > 0 && leg.departure_date < self.legs[i - 1].departure_date {
+                return Err(format!("Leg {} departs before the previous leg", i + 1));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Search result flight offer
@@ -237,8 +320,12 @@ pub struct FlightOffer {
     pub outbound: FlightJourney,
     /// Return segments (for round trips)
     pub inbound: Option<FlightJourney>,
-    /// Total price
+    /// Further multi-city journeys after the outbound, in order
+    pub onward: Vec<FlightJourney>,
+    /// Total price (retail when a pricing policy is configured)
     pub price: Price,
+    /// Itemized retail pricing
+    pub retail: Option<RetailPrice>,
     /// Price per passenger type
     pub price_breakdown: Vec<PricePerPassenger>,
     /// Fare conditions
@@ -293,13 +380,13 @@ pub struct FlightSegment {
     pub operating_carrier: Option<AirlineCode>,
     /// Departure airport
     pub origin: IataCode,
-    /// Departure time (ISO 8601)
+    /// Departure time (ISO 8601, local with UTC offset)
     pub departure_time: String,
     /// Departure terminal
     pub departure_terminal: Option<String>,
     /// Arrival airport
     pub destination: IataCode,
-    /// Arrival time (ISO 8601)
+    /// Arrival time (ISO 8601, local with UTC offset)
     pub arrival_time: String,
     /// Arrival terminal
     pub arrival_terminal: Option<String>,
@@ -418,7 +505,7 @@ pub struct PassengerDetails {
     pub phone: Option<String>,
     /// Frequent flyer number
     pub frequent_flyer: Option<FrequentFlyer>,
-    /// Special requests
+    /// Special service request codes ("WCHR", "VGML")
     pub special_requests: Vec<String>,
 }
 
@@ -468,6 +555,13 @@ pub struct BookingRequest {
     pub contact: ContactDetails,
     /// Special remarks
     pub remarks: Option<String>,
+    /// Price shown to the user at review; when set, the offer is
+    /// re-priced and compared before booking
+    pub displayed_price: Option<Price>,
+    /// Chosen payment method (for payment surcharges)
+    pub payment_method: Option<PaymentMethodType>,
+    /// Buyer's tier (for tier adjustments)
+    pub user_tier: UserTier,
 }
 
 /// Booking confirmation
@@ -489,6 +583,19 @@ pub struct Booking {
     pub contact: ContactDetails,
     /// Total price
     pub total_price: Price,
+    /// Total shown to the user at review, checked against the amount
+    /// captured by the payment provider
+    pub displayed_total: Option<Price>,
+    /// Settlement is held until finance reviews a price variance
+    pub settlement_blocked: bool,
+    /// Itemized retail pricing, including the policy version used
+    pub pricing: Option<RetailPrice>,
+    /// Price lock used for this booking
+    pub price_lock: Option<AppliedPriceLock>,
+    /// Promo codes used on this booking
+    pub promotions: Vec<AppliedPromotion>,
+    /// Loyalty points credit spent on this booking
+    pub wallet_credit: Option<Price>,
     /// Payment ID
     pub payment_id: Option<String>,
     /// Created at
@@ -501,6 +608,17 @@ pub struct Booking {
     pub ticket_numbers: Vec<String>,
 }
 
+impl Booking {
+    /// Check if the captured payment can be settled
+    pub fn can_settle(&self) -> bool {
+        !self.settlement_blocked
+            && matches!(
+                self.status,
+                BookingStatus::Confirmed | BookingStatus::Ticketed | BookingStatus::Completed
+            )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -542,6 +660,33 @@ mod tests {
         assert_eq!(search.cabin_class, CabinClass::Business);
     }
 
+    #[test]
+    fn test_multi_city_request() {
+        let search = SearchRequest::multi_city(vec![
+            TripLeg::new(IataCode::KUL, IataCode::NRT, "2030-07-01"),
+            TripLeg::new(IataCode::NRT, IataCode::ICN, "2030-07-08"),
+            TripLeg::new(IataCode::ICN, IataCode::KUL, "2030-07-15"),
+        ]);
+        assert!(search.validate().is_ok());
+        assert_eq!(search.trip_type, TripType::MultiCity);
+        assert_eq!(search.origin, IataCode::KUL);
+        assert_eq!(search.destination, IataCode::KUL);
+        assert_eq!(search.departure_date, "2030-07-01");
+
+        let single = SearchRequest::multi_city(vec![TripLeg::new(
+            IataCode::KUL,
+            IataCode::NRT,
+            "2030-07-01",
+        )]);
+        assert!(single.validate().is_err());
+
+        let backwards = SearchRequest::multi_city(vec![
+            TripLeg::new(IataCode::KUL, IataCode::NRT, "2030-07-08"),
+            TripLeg::new(IataCode::NRT, IataCode::ICN, "2030-07-01"),
+        ]);
+        assert!(backwards.validate().is_err());
+    }
+
     #[test]
     fn test_booking_status() {
         assert!(BookingStatus::Confirmed.can_cancel());
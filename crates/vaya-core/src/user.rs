@@ -1,10 +1,11 @@
 //! User management service
 
 use std::collections::HashMap;
+use std::fmt;
 use tracing::{debug, info};
 
 use vaya_auth::{Claims, JwtTokenizer, PasswordHasher};
-use vaya_common::{Timestamp, Uuid};
+use vaya_common::{Mask, Redact, Timestamp, UserTier, Uuid};
 
 use crate::error::{CoreError, CoreResult};
 
@@ -47,6 +48,49 @@ pub struct User {
     pub phone_verified: bool,
     /// Account status
     pub status: UserStatus,
+    /// Subscription tier
+    pub tier: UserTier,
+    /// Admin tier override (takes precedence over `tier` until it expires)
+    pub tier_override: Option<TierOverride>,
+    /// Suspension reason (set while suspended)
+    pub suspension_reason: Option<String>,
+    /// Account this one was merged into
+    pub merged_into: Option<String>,
+}
+
+impl User {
+    /// Tier in effect now, taking an unexpired override into account
+    pub fn effective_tier(&self) -> UserTier {
+        self.effective_tier_at(Timestamp::now())
+    }
+
+    /// Tier in effect at the given time
+    pub fn effective_tier_at(&self, now: Timestamp) -> UserTier {
+        match &self.tier_override {
+            Some(o) if o.is_active_at(now) => o.tier,
+            _ => self.tier,
+        }
+    }
+}
+
+/// Admin-assigned tier that overrides the subscription tier
+#[derive(Debug, Clone)]
+pub struct TierOverride {
+    /// Overriding tier
+    pub tier: UserTier,
+    /// When the override lapses (`None` = until cleared)
+    pub expires_at: Option<Timestamp>,
+    /// Why the override was granted
+    pub reason: String,
+    /// Admin who granted it
+    pub granted_by: String,
+}
+
+impl TierOverride {
+    /// Check if the override applies at the given time
+    pub fn is_active_at(&self, now: Timestamp) -> bool {
+        self.expires_at.is_none_or(|exp| now < exp)
+    }
 }
 
 /// User status
@@ -74,7 +118,7 @@ pub struct FrequentFlyerAccount {
 }
 
 /// User registration request
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct RegisterRequest {
     /// Email
     pub email: String,
@@ -90,6 +134,19 @@ pub struct RegisterRequest {
     pub marketing_opt_in: bool,
 }
 
+impl fmt::Debug for RegisterRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisterRequest")
+            .field("email", &self.email.redacted(Mask::Email))
+            .field("password", &self.password.redacted(Mask::Full))
+            .field("first_name", &self.first_name)
+            .field("last_name", &self.last_name)
+            .field("phone", &self.phone.redacted(Mask::LastFour))
+            .field("marketing_opt_in", &self.marketing_opt_in)
+            .finish()
+    }
+}
+
 impl RegisterRequest {
     /// Validate registration request
     pub fn validate(&self) -> CoreResult<()> {
@@ -114,7 +171,7 @@ impl RegisterRequest {
 }
 
 /// Login request
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct LoginRequest {
     /// Email
     pub email: String,
@@ -122,6 +179,15 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+impl fmt::Debug for LoginRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoginRequest")
+            .field("email", &self.email.redacted(Mask::Email))
+            .field("password", &self.password.redacted(Mask::Full))
+            .finish()
+    }
+}
+
 /// Authentication response
 #[derive(Debug, Clone)]
 pub struct AuthResponse {
@@ -138,7 +204,7 @@ pub struct AuthResponse {
 }
 
 /// Auth configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct AuthConfig {
     /// JWT secret key
     pub jwt_secret: String,
@@ -150,6 +216,17 @@ pub struct AuthConfig {
     pub refresh_token_expiry_secs: u64,
 }
 
+impl fmt::Debug for AuthConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthConfig")
+            .field("jwt_secret", &self.jwt_secret.redacted(Mask::Full))
+            .field("issuer", &self.issuer)
+            .field("access_token_expiry_secs", &self.access_token_expiry_secs)
+            .field("refresh_token_expiry_secs", &self.refresh_token_expiry_secs)
+            .finish()
+    }
+}
+
 impl AuthConfig {
     /// Create new auth config with secret
     pub fn new(jwt_secret: &str) -> Self {
@@ -162,10 +239,17 @@ impl AuthConfig {
     }
 }
 
+/// Custom claim carrying the user's token generation
+const TOKEN_GENERATION_CLAIM: &str = "tgen";
+
 /// User service
 pub struct UserService {
     /// JWT tokenizer
     tokenizer: JwtTokenizer,
+    /// Token issuer
+    issuer: String,
+    /// Token lifetime
+    token_ttl: time::Duration,
     /// Password hasher
     hasher: PasswordHasher,
     /// In-memory user store (would be database in production)
@@ -176,6 +260,8 @@ pub struct UserService {
 struct StoredUser {
     user: User,
     password_hash: String,
+    /// Bumped to revoke all outstanding tokens
+    token_generation: u32,
 }
 
 impl UserService {
@@ -183,6 +269,8 @@ impl UserService {
     pub fn new(config: AuthConfig) -> Self {
         Self {
             tokenizer: JwtTokenizer::new(config.jwt_secret.as_bytes(), &config.issuer),
+            issuer: config.issuer.clone(),
+            token_ttl: time::Duration::hours(24),
             hasher: PasswordHasher::new(),
             users: std::sync::RwLock::new(HashMap::new()),
         }
@@ -233,6 +321,10 @@ impl UserService {
             email_verified: false,
             phone_verified: false,
             status: UserStatus::PendingVerification,
+            tier: UserTier::Free,
+            tier_override: None,
+            suspension_reason: None,
+            merged_into: None,
         };
 
         // Store user
@@ -243,6 +335,7 @@ impl UserService {
                 StoredUser {
                     user: user.clone(),
                     password_hash,
+                    token_generation: 0,
                 },
             );
         }
@@ -354,6 +447,10 @@ impl UserService {
             stored.user.last_name = last_name;
         }
         if let Some(phone) = updates.phone {
+            // A new number must be verified again
+            if stored.user.phone.as_deref() != Some(phone.as_str()) {
+                stored.user.phone_verified = false;
+            }
             stored.user.phone = Some(phone);
         }
         if let Some(dob) = updates.date_of_birth {
@@ -419,23 +516,290 @@ impl UserService {
         Ok(())
     }
 
+    /// Re-authenticate a user with their current password
+    ///
+    /// Required before sensitive account changes such as the login email or
+    /// phone number.
+    pub fn reauthenticate(&self, user_id: &str, password: &str) -> CoreResult<User> {
+        let users = self.users.read().unwrap();
+        let stored = users
+            .get(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+
+        let valid = self
+            .hasher
+            .verify(password, &stored.password_hash)
+            .map_err(|e| CoreError::Internal(e.to_string()))?;
+        if !valid {
+            return Err(CoreError::NotAuthenticated);
+        }
+        if matches!(
+            stored.user.status,
+            UserStatus::Suspended | UserStatus::Deleted
+        ) {
+            return Err(CoreError::NotAuthorized("Account is suspended".to_string()));
+        }
+
+        Ok(stored.user.clone())
+    }
+
+    /// Check whether an email address belongs to an account other than
+    /// `except_user_id`
+    pub fn email_taken(&self, email: &str, except_user_id: &str) -> bool {
+        let users = self.users.read().unwrap();
+        users
+            .values()
+            .any(|u| u.user.id != except_user_id && u.user.email.eq_ignore_ascii_case(email))
+    }
+
+    /// Replace a user's login email with a confirmed address
+    pub(crate) fn set_email(&self, user_id: &str, email: &str) -> CoreResult<User> {
+        if self.email_taken(email, user_id) {
+            return Err(CoreError::InvalidUserData(
+                "Email already registered".to_string(),
+            ));
+        }
+
+        let mut users = self.users.write().unwrap();
+        let stored = users
+            .get_mut(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+
+        stored.user.email = email.to_string();
+        stored.user.email_verified = true;
+        stored.user.updated_at = Timestamp::now();
+
+        info!("Email changed for user {}", user_id);
+
+        Ok(stored.user.clone())
+    }
+
+    /// Set a phone number proven by a one-time code
+    pub(crate) fn set_verified_phone(&self, user_id: &str, phone: &str) -> CoreResult<User> {
+        let mut users = self.users.write().unwrap();
+        let stored = users
+            .get_mut(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+
+        stored.user.phone = Some(phone.to_string());
+        stored.user.phone_verified = true;
+        stored.user.updated_at = Timestamp::now();
+
+        Ok(stored.user.clone())
+    }
+
+    /// Revoke all outstanding tokens of a user
+    pub fn revoke_tokens(&self, user_id: &str) -> CoreResult<()> {
+        let mut users = self.users.write().unwrap();
+        let stored = users
+            .get_mut(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+        stored.token_generation += 1;
+        Ok(())
+    }
+
     /// Verify token and get claims
+    ///
+    /// Tokens of suspended, deleted, or merged accounts and tokens issued
+    /// before a revocation are rejected.
     pub fn verify_token(&self, token: &str) -> CoreResult<Claims> {
-        self.tokenizer
+        let claims = self
+            .tokenizer
             .validate(token)
-            .map_err(|_| CoreError::NotAuthenticated)
+            .map_err(|_| CoreError::NotAuthenticated)?;
+
+        let users = self.users.read().unwrap();
+        if let Some(stored) = users.get(&claims.sub) {
+            if matches!(
+                stored.user.status,
+                UserStatus::Suspended | UserStatus::Deleted
+            ) {
+                return Err(CoreError::NotAuthorized("Account is suspended".to_string()));
+            }
+            let generation = claims
+                .custom
+                .iter()
+                .find(|(k, _)| k == TOKEN_GENERATION_CLAIM)
+                .and_then(|(_, v)| v.parse::<u32>().ok())
+                .unwrap_or(0);
+            if generation != stored.token_generation {
+                return Err(CoreError::NotAuthenticated);
+            }
+        }
+        Ok(claims)
+    }
+
+    /// Suspend an account and revoke its outstanding tokens
+    pub async fn suspend(&self, user_id: &str, reason: &str) -> CoreResult<User> {
+        let mut users = self.users.write().unwrap();
+        let stored = users
+            .get_mut(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+
+        match stored.user.status {
+            UserStatus::Suspended => {
+                return Err(CoreError::InvalidUserData(
+                    "Account is already suspended".to_string(),
+                ))
+            }
+            UserStatus::Deleted => {
+                return Err(CoreError::InvalidUserData("Account is deleted".to_string()))
+            }
+            _ => {}
+        }
+
+        stored.user.status = UserStatus::Suspended;
+        stored.user.suspension_reason = Some(reason.to_string());
+        stored.user.updated_at = Timestamp::now();
+        stored.token_generation += 1;
+
+        info!("Suspended user {}: {}", user_id, reason);
+
+        Ok(stored.user.clone())
+    }
+
+    /// Lift a suspension
+    ///
+    /// Tokens revoked by the suspension stay invalid; the user must log in
+    /// again.
+    pub async fn unsuspend(&self, user_id: &str) -> CoreResult<User> {
+        let mut users = self.users.write().unwrap();
+        let stored = users
+            .get_mut(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+
+        if stored.user.status != UserStatus::Suspended {
+            return Err(CoreError::InvalidUserData(
+                "Account is not suspended".to_string(),
+            ));
+        }
+
+        stored.user.status = if stored.user.email_verified {
+            UserStatus::Active
+        } else {
+            UserStatus::PendingVerification
+        };
+        stored.user.suspension_reason = None;
+        stored.user.updated_at = Timestamp::now();
+
+        info!("Unsuspended user {}", user_id);
+
+        Ok(stored.user.clone())
+    }
+
+    /// Set or clear (`None`) a user's tier override
+    pub async fn set_tier_override(
+        &self,
+        user_id: &str,
+        tier_override: Option<TierOverride>,
+    ) -> CoreResult<User> {
+        let mut users = self.users.write().unwrap();
+        let stored = users
+            .get_mut(user_id)
+            .ok_or_else(|| CoreError::UserNotFound(user_id.to_string()))?;
+
+        if let Some(ref o) = tier_override {
+            if o.expires_at.is_some_and(|exp| exp <= Timestamp::now()) {
+                return Err(CoreError::ValidationError(
+                    "Tier override expiry must be in the future".to_string(),
+                ));
+            }
+        }
+
+        stored.user.tier_override = tier_override;
+        stored.user.updated_at = Timestamp::now();
+
+        Ok(stored.user.clone())
+    }
+
+    /// Fold a duplicate account into a surviving one
+    ///
+    /// Profile gaps in the target are filled from the source, frequent flyer
+    /// accounts are combined, and the source is marked deleted with its
+    /// tokens revoked. Owned records (bookings, alerts, points) are moved by
+    /// [`crate::AdminService::merge_users`].
+    pub async fn merge_accounts(&self, source_id: &str, target_id: &str) -> CoreResult<User> {
+        if source_id == target_id {
+            return Err(CoreError::ValidationError(
+                "Cannot merge an account into itself".to_string(),
+            ));
+        }
+
+        let mut users = self.users.write().unwrap();
+        let source = users
+            .get(source_id)
+            .map(|s| s.user.clone())
+            .ok_or_else(|| CoreError::UserNotFound(source_id.to_string()))?;
+        if source.status == UserStatus::Deleted {
+            return Err(CoreError::InvalidUserData(
+                "Source account is already deleted".to_string(),
+            ));
+        }
+
+        let target = users
+            .get_mut(target_id)
+            .ok_or_else(|| CoreError::UserNotFound(target_id.to_string()))?;
+        if target.user.status == UserStatus::Deleted {
+            return Err(CoreError::InvalidUserData(
+                "Target account is deleted".to_string(),
+            ));
+        }
+
+        let t = &mut target.user;
+        t.phone = t.phone.take().or(source.phone);
+        t.date_of_birth = t.date_of_birth.take().or(source.date_of_birth);
+        t.nationality = t.nationality.take().or(source.nationality);
+        if t.passport_number.is_none() {
+            t.passport_number = source.passport_number;
+            t.passport_expiry = source.passport_expiry;
+        }
+        for account in source.frequent_flyer_accounts {
+            let exists = t
+                .frequent_flyer_accounts
+                .iter()
+                .any(|a| a.airline == account.airline && a.number == account.number);
+            if !exists {
+                t.frequent_flyer_accounts.push(account);
+            }
+        }
+        if source.tier as u8 > t.tier as u8 {
+            t.tier = source.tier;
+        }
+        t.updated_at = Timestamp::now();
+        let merged = t.clone();
+
+        let source = users.get_mut(source_id).expect("source checked above");
+        source.user.status = UserStatus::Deleted;
+        source.user.merged_into = Some(target_id.to_string());
+        source.user.updated_at = Timestamp::now();
+        source.token_generation += 1;
+
+        info!("Merged user {} into {}", source_id, target_id);
+
+        Ok(merged)
     }
 
     /// Generate access and refresh tokens
     fn generate_tokens(&self, user: &User) -> CoreResult<(String, String)> {
+        let generation = self
+            .users
+            .read()
+            .unwrap()
+            .get(&user.id)
+            .map_or(0, |s| s.token_generation);
+        let claims = || {
+            Claims::new(&user.id, &self.issuer, self.token_ttl)
+                .claim(TOKEN_GENERATION_CLAIM, generation.to_string())
+        };
+
         let access_token = self
             .tokenizer
-            .generate(&user.id)
+            .generate_with_claims(claims())
             .map_err(|e| CoreError::Internal(e.to_string()))?;
 
         let refresh_token = self
             .tokenizer
-            .generate(&user.id)
+            .generate_with_claims(claims())
             .map_err(|e| CoreError::Internal(e.to_string()))?;
 
         Ok((access_token, refresh_token))
@@ -469,6 +833,7 @@ impl Clone for StoredUser {
         Self {
             user: self.user.clone(),
             password_hash: self.password_hash.clone(),
+            token_generation: self.token_generation,
         }
     }
 }
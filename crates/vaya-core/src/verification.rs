@@ -0,0 +1,722 @@
+//! Email change and phone verification
+//!
+//! Changing the login email requires the current password. The new address
+//! receives a confirmation link and the old address a notice with a revert
+//! link; after confirmation the old address can still undo the change during
+//! a grace period, which also revokes all outstanding tokens. Phone numbers
+//! are verified with a one-time code sent by SMS.
+//!
+//! Confirmed contact details are pushed to existing bookings through
+//! [`ContactSync`] implementations.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use tracing::{info, warn};
+
+use vaya_common::{Timestamp, Uuid};
+use vaya_crypto::{constant_time_eq, random, random_hex, sha256};
+
+use crate::error::{CoreError, CoreResult};
+use crate::notify::Notifier;
+use crate::user::{User, UserService};
+
+/// Verification configuration
+#[derive(Debug, Clone)]
+pub struct VerificationConfig {
+    /// Base URL for confirmation and revert links
+    pub link_base_url: String,
+    /// How long a confirmation link stays valid (hours)
+    pub confirm_link_hours: i64,
+    /// Grace period during which the old address can revert (days)
+    pub revert_window_days: i64,
+    /// One-time code lifetime (minutes)
+    pub otp_ttl_minutes: i64,
+    /// Wrong guesses allowed before a code is discarded
+    pub otp_max_attempts: u32,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            link_base_url: "https://vaya.my".to_string(),
+            confirm_link_hours: 24,
+            revert_window_days: 7,
+            otp_ttl_minutes: 10,
+            otp_max_attempts: 5,
+        }
+    }
+}
+
+impl VerificationConfig {
+    /// Create new configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set link base URL
+    pub fn with_link_base_url(mut self, url: impl Into<String>) -> Self {
+        self.link_base_url = url.into();
+        self
+    }
+
+    /// Set revert grace period
+    pub fn with_revert_window_days(mut self, days: i64) -> Self {
+        self.revert_window_days = days;
+        self
+    }
+}
+
+/// Keeps booking contact details in step with verified account details
+pub trait ContactSync: Send + Sync {
+    /// Update the contact email on the user's bookings, returning how many
+    /// were changed
+    fn update_email(&self, user_id: &str, email: &str) -> CoreResult<usize>;
+
+    /// Update the contact phone on the user's bookings, returning how many
+    /// were changed
+    fn update_phone(&self, user_id: &str, phone: &str) -> CoreResult<usize>;
+}
+
+/// An email change request
+#[derive(Debug, Clone)]
+pub struct EmailChange {
+    /// Change ID
+    pub id: String,
+    /// User ID
+    pub user_id: String,
+    /// Address before the change
+    pub old_email: String,
+    /// Requested address
+    pub new_email: String,
+    /// When the change was requested
+    pub requested_at: Timestamp,
+    /// When the confirmation link expires
+    pub confirm_expires_at: Timestamp,
+    /// When the new address was confirmed
+    pub confirmed_at: Option<Timestamp>,
+    /// Until when the old address can revert (set on confirmation)
+    pub revert_until: Option<Timestamp>,
+    /// When the change was reverted or cancelled
+    pub reverted_at: Option<Timestamp>,
+}
+
+impl EmailChange {
+    /// Check if the change still awaits confirmation
+    pub fn is_pending(&self) -> bool {
+        self.confirmed_at.is_none() && self.reverted_at.is_none()
+    }
+}
+
+/// Stored email change with hashed link tokens
+struct StoredChange {
+    change: EmailChange,
+    confirm_hash: String,
+    revert_hash: String,
+}
+
+/// Outstanding phone verification code
+struct PhoneChallenge {
+    phone: String,
+    code_hash: [u8; 32],
+    expires_at: Timestamp,
+    attempts: u32,
+}
+
+/// Email change and phone verification service
+pub struct VerificationService {
+    /// User service
+    users: Arc<UserService>,
+    /// Message delivery
+    notifier: Arc<dyn Notifier>,
+    /// Booking contact updaters
+    contacts: Vec<Arc<dyn ContactSync>>,
+    /// Configuration
+    config: VerificationConfig,
+    /// Email changes by ID
+    changes: RwLock<HashMap<String, StoredChange>>,
+    /// Phone challenges by user ID
+    challenges: RwLock<HashMap<String, PhoneChallenge>>,
+}
+
+impl VerificationService {
+    /// Create new verification service
+    pub fn new(users: Arc<UserService>, notifier: Arc<dyn Notifier>) -> Self {
+        Self {
+            users,
+            notifier,
+            contacts: Vec::new(),
+            config: VerificationConfig::default(),
+            changes: RwLock::new(HashMap::new()),
+            challenges: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: VerificationConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Register a booking contact updater
+    pub fn with_contact_sync(mut self, contacts: Arc<dyn ContactSync>) -> Self {
+        self.contacts.push(contacts);
+        self
+    }
+
+    /// Start an email change
+    ///
+    /// Requires the current password. Any earlier unconfirmed change for the
+    /// user is cancelled.
+    pub async fn request_email_change(
+        &self,
+        user_id: &str,
+        password: &str,
+        new_email: &str,
+    ) -> CoreResult<EmailChange> {
+        let new_email = new_email.trim();
+        if new_email.is_empty() || !new_email.contains('@') {
+            return Err(CoreError::InvalidUserData(
+                "Invalid email address".to_string(),
+            ));
+        }
+
+        let user = self.users.reauthenticate(user_id, password)?;
+        if user.email.eq_ignore_ascii_case(new_email) {
+            return Err(CoreError::ValidationError(
+                "New email is the same as the current one".to_string(),
+            ));
+        }
+        if self.users.email_taken(new_email, user_id) {
+            return Err(CoreError::InvalidUserData(
+                "Email already registered".to_string(),
+            ));
+        }
+
+        let confirm_token = new_token()?;
+        let revert_token = new_token()?;
+        let now = Timestamp::now();
+        let change = EmailChange {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            old_email: user.email.clone(),
+            new_email: new_email.to_string(),
+            requested_at: now,
+            confirm_expires_at: now.add_hours(self.config.confirm_link_hours),
+            confirmed_at: None,
+            revert_until: None,
+            reverted_at: None,
+        };
+
+        {
+            let mut changes = self.changes.write().unwrap();
+            for stored in changes.values_mut() {
+                if stored.change.user_id == user_id && stored.change.is_pending() {
+                    stored.change.reverted_at = Some(now);
+                }
+            }
+            changes.insert(
+                change.id.clone(),
+                StoredChange {
+                    change: change.clone(),
+                    confirm_hash: hash_token(&confirm_token),
+                    revert_hash: hash_token(&revert_token),
+                },
+            );
+        }
+
+        let sent = self
+            .send_change_links(&change, &confirm_token, &revert_token)
+            .await;
+        if let Err(e) = sent {
+            self.changes.write().unwrap().remove(&change.id);
+            return Err(e);
+        }
+
+        info!("Email change requested for user {}", user_id);
+
+        Ok(change)
+    }
+
+    /// Confirm an email change with the link sent to the new address
+    pub async fn confirm_email_change(&self, token: &str) -> CoreResult<User> {
+        let hash = hash_token(token);
+        let change = {
+            let changes = self.changes.read().unwrap();
+            changes
+                .values()
+                .find(|s| constant_time_eq(s.confirm_hash.as_bytes(), hash.as_bytes()))
+                .map(|s| s.change.clone())
+                .ok_or_else(|| {
+                    CoreError::ValidationError("Invalid confirmation link".to_string())
+                })?
+        };
+
+        if !change.is_pending() {
+            return Err(CoreError::ValidationError(
+                "Email change is no longer pending".to_string(),
+            ));
+        }
+        if change.confirm_expires_at.is_past() {
+            return Err(CoreError::ValidationError(
+                "Confirmation link has expired".to_string(),
+            ));
+        }
+
+        let user = self.users.set_email(&change.user_id, &change.new_email)?;
+
+        let now = Timestamp::now();
+        if let Some(stored) = self.changes.write().unwrap().get_mut(&change.id) {
+            stored.change.confirmed_at = Some(now);
+            stored.change.revert_until = Some(now.add_days(self.config.revert_window_days));
+        }
+
+        self.propagate_email(&user);
+
+        Ok(user)
+    }
+
+    /// Undo or cancel an email change with the link sent to the old address
+    ///
+    /// Works before confirmation (cancelling the change) and during the grace
+    /// period after it (restoring the old address). Either way all of the
+    /// user's tokens are revoked, since an unexpected change suggests the
+    /// account is compromised.
+    pub async fn revert_email_change(&self, token: &str) -> CoreResult<User> {
+        let hash = hash_token(token);
+        let change = {
+            let changes = self.changes.read().unwrap();
+            changes
+                .values()
+                .find(|s| constant_time_eq(s.revert_hash.as_bytes(), hash.as_bytes()))
+                .map(|s| s.change.clone())
+                .ok_or_else(|| CoreError::ValidationError("Invalid revert link".to_string()))?
+        };
+
+        if change.reverted_at.is_some() {
+            return Err(CoreError::ValidationError(
+                "Email change was already reverted".to_string(),
+            ));
+        }
+
+        let user = if change.confirmed_at.is_some() {
+            if change.revert_until.is_some_and(|until| until.is_past()) {
+                return Err(CoreError::ValidationError(
+                    "Revert period has ended".to_string(),
+                ));
+            }
+            let user = self.users.set_email(&change.user_id, &change.old_email)?;
+            self.propagate_email(&user);
+            user
+        } else {
+            self.users.get_user(&change.user_id).await?
+        };
+
+        self.users.revoke_tokens(&change.user_id)?;
+        if let Some(stored) = self.changes.write().unwrap().get_mut(&change.id) {
+            stored.change.reverted_at = Some(Timestamp::now());
+        }
+
+        warn!("Email change reverted for user {}", change.user_id);
+
+        Ok(user)
+    }
+
+    /// Get a user's email change history
+    pub fn email_changes_for(&self, user_id: &str) -> Vec<EmailChange> {
+        let mut changes: Vec<EmailChange> = self
+            .changes
+            .read()
+            .unwrap()
+            .values()
+            .filter(|s| s.change.user_id == user_id)
+            .map(|s| s.change.clone())
+            .collect();
+        changes.sort_by_key(|c| c.requested_at);
+        changes
+    }
+
+    /// Send a one-time code to a phone number
+    ///
+    /// Requires the current password. Returns when the code expires.
+    pub async fn start_phone_verification(
+        &self,
+        user_id: &str,
+        password: &str,
+        phone: &str,
+    ) -> CoreResult<Timestamp> {
+        let phone = phone.trim();
+        if !is_valid_phone(phone) {
+            return Err(CoreError::InvalidUserData(
+                "Phone number must be in international format, e.g. +60123456789".to_string(),
+            ));
+        }
+
+        self.users.reauthenticate(user_id, password)?;
+
+        let code = format!(
+            "{:06}",
+            random()
+                .range(1_000_000)
+                .map_err(|e| CoreError::Internal(e.to_string()))?
+        );
+        let expires_at = Timestamp::now().add_mins(self.config.otp_ttl_minutes);
+
+        self.notifier
+            .send_sms(
+                phone,
+                &format!(
+                    "Your VAYA verification code is {}. It expires in {} minutes.",
+                    code, self.config.otp_ttl_minutes
+                ),
+            )
+            .await?;
+
+        self.challenges.write().unwrap().insert(
+            user_id.to_string(),
+            PhoneChallenge {
+                phone: phone.to_string(),
+                code_hash: code_hash(user_id, phone, &code),
+                expires_at,
+                attempts: 0,
+            },
+        );
+
+        info!("Phone verification code sent for user {}", user_id);
+
+        Ok(expires_at)
+    }
+
+    /// Check a one-time code and mark the phone number verified
+    pub async fn verify_phone(&self, user_id: &str, code: &str) -> CoreResult<User> {
+        let phone = {
+            let mut challenges = self.challenges.write().unwrap();
+            let challenge = challenges.get_mut(user_id).ok_or_else(|| {
+                CoreError::ValidationError("No phone verification in progress".to_string())
+            })?;
+
+            if challenge.expires_at.is_past() {
+                challenges.remove(user_id);
+                return Err(CoreError::ValidationError(
+                    "Verification code has expired".to_string(),
+                ));
+            }
+
+            let expected = code_hash(user_id, &challenge.phone, code.trim());
+            if !constant_time_eq(&expected, &challenge.code_hash) {
+                challenge.attempts += 1;
+                if challenge.attempts >= self.config.otp_max_attempts {
+                    challenges.remove(user_id);
+                    return Err(CoreError::NotAuthorized(
+                        "Too many incorrect codes; request a new one".to_string(),
+                    ));
+                }
+                return Err(CoreError::ValidationError(
+                    "Invalid verification code".to_string(),
+                ));
+            }
+
+            let challenge = challenges.remove(user_id).expect("challenge checked above");
+            challenge.phone
+        };
+
+        let user = self.users.set_verified_phone(user_id, &phone)?;
+        for sync in &self.contacts {
+            if let Err(e) = sync.update_phone(user_id, &phone) {
+                warn!("Failed to update booking contact phone: {}", e);
+            }
+        }
+
+        info!("Phone verified for user {}", user_id);
+
+        Ok(user)
+    }
+
+    /// Email the confirmation link to the new address and the revert link
+    /// to the old one
+    async fn send_change_links(
+        &self,
+        change: &EmailChange,
+        confirm_token: &str,
+        revert_token: &str,
+    ) -> CoreResult<()> {
+        let base = self.config.link_base_url.trim_end_matches('/');
+        self.notifier
+            .send_email(
+                &change.new_email,
+                "Confirm your new VAYA email address",
+                &format!(
+                    "Confirm that you want to use this address for your VAYA account:\n\n\
+                     {}/account/email/confirm?token={}\n\n\
+                     The link expires in {} hours.",
+                    base, confirm_token, self.config.confirm_link_hours
+                ),
+            )
+            .await?;
+        self.notifier
+            .send_email(
+                &change.old_email,
+                "Your VAYA email address is being changed",
+                &format!(
+                    "A request was made to change your VAYA login email to {}.\n\n\
+                     If this wasn't you, undo the change and sign out everywhere \
+                     within {} days:\n\n{}/account/email/revert?token={}",
+                    change.new_email, self.config.revert_window_days, base, revert_token
+                ),
+            )
+            .await
+    }
+
+    /// Push a confirmed email to booking contacts
+    fn propagate_email(&self, user: &User) {
+        for sync in &self.contacts {
+            if let Err(e) = sync.update_email(&user.id, &user.email) {
+                warn!("Failed to update booking contact email: {}", e);
+            }
+        }
+    }
+}
+
+/// Generate a random link token
+fn new_token() -> CoreResult<String> {
+    random_hex(32).map_err(|e| CoreError::Internal(e.to_string()))
+}
+
+/// Hash a link token for storage
+fn hash_token(token: &str) -> String {
+    sha256(token.as_bytes()).to_hex()
+}
+
+/// Hash a one-time code bound to the user and phone number
+fn code_hash(user_id: &str, phone: &str, code: &str) -> [u8; 32] {
+    *sha256(format!("{}:{}:{}", user_id, phone, code).as_bytes()).as_bytes()
+}
+
+/// Check for an E.164 phone number
+fn is_valid_phone(phone: &str) -> bool {
+    phone.strip_prefix('+').is_some_and(|digits| {
+        (8..=15).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::{AuthConfig, RegisterRequest};
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    const PASSWORD: &str = "StrongP@ssw0rd!123";
+
+    /// Notifier that records messages instead of sending them
+    #[derive(Default)]
+    struct Outbox(Mutex<Vec<(String, String)>>);
+
+    impl Outbox {
+        /// Extract the token from the last link sent to `to`
+        fn token_for(&self, to: &str) -> String {
+            let sent = self.0.lock().unwrap();
+            let (_, body) = sent.iter().rev().find(|(t, _)| t == to).unwrap();
+            let start = body.find("token=").unwrap() + "token=".len();
+            body[start..start + 64].to_string()
+        }
+
+        /// Extract the code from the last SMS sent to `to`
+        fn code_for(&self, to: &str) -> String {
+            let sent = self.0.lock().unwrap();
+            let (_, body) = sent.iter().rev().find(|(t, _)| t == to).unwrap();
+            body.split_whitespace()
+                .find(|w| w.len() == 7 && w.ends_with('.'))
+                .unwrap()
+                .trim_end_matches('.')
+                .to_string()
+        }
+    }
+
+    #[async_trait]
+    impl Notifier for Outbox {
+        async fn send_email(&self, to: &str, _subject: &str, body: &str) -> CoreResult<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((to.to_string(), body.to_string()));
+            Ok(())
+        }
+
+        async fn send_sms(&self, to: &str, body: &str) -> CoreResult<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((to.to_string(), body.to_string()));
+            Ok(())
+        }
+    }
+
+    /// Booking contacts keyed by user ID
+    #[derive(Default)]
+    struct Contacts(Mutex<HashMap<String, (String, String)>>);
+
+    impl ContactSync for Contacts {
+        fn update_email(&self, user_id: &str, email: &str) -> CoreResult<usize> {
+            let mut contacts = self.0.lock().unwrap();
+            contacts.entry(user_id.to_string()).or_default().0 = email.to_string();
+            Ok(1)
+        }
+
+        fn update_phone(&self, user_id: &str, phone: &str) -> CoreResult<usize> {
+            let mut contacts = self.0.lock().unwrap();
+            contacts.entry(user_id.to_string()).or_default().1 = phone.to_string();
+            Ok(1)
+        }
+    }
+
+    async fn setup() -> (
+        Arc<UserService>,
+        VerificationService,
+        Arc<Outbox>,
+        Arc<Contacts>,
+        User,
+    ) {
+        let users = Arc::new(UserService::new(AuthConfig::new(
+            "test-secret-key-32-bytes-long!!",
+        )));
+        let user = users
+            .register(RegisterRequest {
+                email: "old@example.com".to_string(),
+                password: PASSWORD.to_string(),
+                first_name: "Test".to_string(),
+                last_name: "User".to_string(),
+                phone: None,
+                marketing_opt_in: false,
+            })
+            .await
+            .unwrap()
+            .user;
+        let outbox = Arc::new(Outbox::default());
+        let contacts = Arc::new(Contacts::default());
+        let service = VerificationService::new(users.clone(), outbox.clone())
+            .with_contact_sync(contacts.clone());
+        (users, service, outbox, contacts, user)
+    }
+
+    #[tokio::test]
+    async fn test_email_change_requires_password() {
+        let (_, service, outbox, _, user) = setup().await;
+
+        let result = service
+            .request_email_change(&user.id, "wrong-password", "new@example.com")
+            .await;
+        assert!(matches!(result, Err(CoreError::NotAuthenticated)));
+        assert!(outbox.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_email_change_confirm_and_revert() {
+        let (users, service, outbox, contacts, user) = setup().await;
+        let session = users
+            .login(crate::user::LoginRequest {
+                email: "old@example.com".to_string(),
+                password: PASSWORD.to_string(),
+            })
+            .await
+            .unwrap();
+
+        service
+            .request_email_change(&user.id, PASSWORD, "new@example.com")
+            .await
+            .unwrap();
+        assert_eq!(outbox.0.lock().unwrap().len(), 2);
+
+        let confirmed = service
+            .confirm_email_change(&outbox.token_for("new@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(confirmed.email, "new@example.com");
+        assert_eq!(contacts.0.lock().unwrap()[&user.id].0, "new@example.com");
+        assert!(service
+            .confirm_email_change(&outbox.token_for("new@example.com"))
+            .await
+            .is_err());
+
+        let reverted = service
+            .revert_email_change(&outbox.token_for("old@example.com"))
+            .await
+            .unwrap();
+        assert_eq!(reverted.email, "old@example.com");
+        assert_eq!(contacts.0.lock().unwrap()[&user.id].0, "old@example.com");
+        assert!(users.verify_token(&session.access_token).is_err());
+        assert!(service.email_changes_for(&user.id)[0].reverted_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_revert_after_grace_period_fails() {
+        let (users, service, outbox, _, user) = setup().await;
+        let service = service.with_config(VerificationConfig::new().with_revert_window_days(-1));
+
+        service
+            .request_email_change(&user.id, PASSWORD, "new@example.com")
+            .await
+            .unwrap();
+        service
+            .confirm_email_change(&outbox.token_for("new@example.com"))
+            .await
+            .unwrap();
+
+        let result = service
+            .revert_email_change(&outbox.token_for("old@example.com"))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            users.get_user(&user.id).await.unwrap().email,
+            "new@example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_phone_verification() {
+        let (users, service, outbox, contacts, user) = setup().await;
+
+        assert!(service
+            .start_phone_verification(&user.id, PASSWORD, "0123456789")
+            .await
+            .is_err());
+        service
+            .start_phone_verification(&user.id, PASSWORD, "+60123456789")
+            .await
+            .unwrap();
+
+        let code = outbox.code_for("+60123456789");
+        let wrong = if code == "000000" { "111111" } else { "000000" };
+        assert!(service.verify_phone(&user.id, wrong).await.is_err());
+
+        let verified = service.verify_phone(&user.id, &code).await.unwrap();
+        assert_eq!(verified.phone.as_deref(), Some("+60123456789"));
+        assert!(verified.phone_verified);
+        assert_eq!(contacts.0.lock().unwrap()[&user.id].1, "+60123456789");
+        assert!(users.get_user(&user.id).await.unwrap().phone_verified);
+        assert!(service.verify_phone(&user.id, &code).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_phone_verification_attempt_limit() {
+        let (_, service, outbox, _, user) = setup().await;
+        service
+            .start_phone_verification(&user.id, PASSWORD, "+60123456789")
+            .await
+            .unwrap();
+        let code = outbox.code_for("+60123456789");
+        let wrong = if code == "000000" { "111111" } else { "000000" };
+
+        for _ in 0..4 {
+            assert!(matches!(
+                service.verify_phone(&user.id, wrong).await,
+                Err(CoreError::ValidationError(_))
+            ));
+        }
+        assert!(matches!(
+            service.verify_phone(&user.id, wrong).await,
+            Err(CoreError::NotAuthorized(_))
+        ));
+        assert!(service.verify_phone(&user.id, &code).await.is_err());
+    }
+}
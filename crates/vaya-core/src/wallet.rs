@@ -0,0 +1,949 @@
+//! Loyalty points wallet
+//!
+//! Users earn points on completed bookings and spend them toward new
+//! bookings or pool contributions. One point is worth one minor unit of
+//! [`LoyaltyConfig::currency`] (1 point = RM 0.01).
+//!
+//! Every change is a balanced double-entry [`WalletTransaction`]: points
+//! move between a user's wallet and the system accounts for points issued,
+//! redeemed and expired, so the accounts always sum to zero. Transactions
+//! carry an idempotency key; posting the same key again returns the
+//! original transaction instead of moving points twice.
+//!
+//! Earned points are kept as [`CreditLot`]s that expire after
+//! [`LoyaltyConfig::expiry_days`]. Redemptions spend the soonest-expiring
+//! lots first and [`WalletService::expire_due`] moves what is left of
+//! expired lots out of the wallet.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use serde_json::{json, Value};
+use tracing::info;
+
+use vaya_common::{CurrencyCode, MinorUnits, Price, Timestamp, Uuid};
+use vaya_db::VayaDb;
+
+use crate::error::{CoreError, CoreResult};
+use crate::types::{Booking, BookingStatus};
+
+/// A ledger account
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WalletAccount {
+    /// A user's wallet
+    User(String),
+    /// Points given out
+    Issued,
+    /// Points spent on bookings and pools
+    Redeemed,
+    /// Points that expired unused
+    Expired,
+}
+
+impl WalletAccount {
+    /// Account key ("user:{id}", "issued", "redeemed", "expired")
+    pub fn key(&self) -> String {
+        match self {
+            Self::User(id) => format!("user:{}", id),
+            Self::Issued => "issued".to_string(),
+            Self::Redeemed => "redeemed".to_string(),
+            Self::Expired => "expired".to_string(),
+        }
+    }
+
+    /// Parse an account key
+    pub fn parse(key: &str) -> Option<Self> {
+        match key {
+            "issued" => Some(Self::Issued),
+            "redeemed" => Some(Self::Redeemed),
+            "expired" => Some(Self::Expired),
+            _ => key
+                .strip_prefix("user:")
+                .map(|id| Self::User(id.to_string())),
+        }
+    }
+}
+
+/// Kind of wallet transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletTransactionKind {
+    /// Points earned
+    Earn,
+    /// Points spent
+    Redeem,
+    /// Points expired
+    Expire,
+}
+
+impl WalletTransactionKind {
+    /// Get kind code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Earn => "earn",
+            Self::Redeem => "redeem",
+            Self::Expire => "expire",
+        }
+    }
+
+    /// Parse a kind code
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "earn" => Some(Self::Earn),
+            "redeem" => Some(Self::Redeem),
+            "expire" => Some(Self::Expire),
+            _ => None,
+        }
+    }
+}
+
+/// One side of a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletEntry {
+    /// Account
+    pub account: WalletAccount,
+    /// Points added to (positive) or taken from (negative) the account
+    pub points: i64,
+}
+
+/// A balanced movement of points between accounts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletTransaction {
+    /// Transaction ID
+    pub id: String,
+    /// Caller-supplied key; posting it again is a no-op
+    pub idempotency_key: String,
+    /// Kind
+    pub kind: WalletTransactionKind,
+    /// Booking, pool or lot the transaction is for
+    pub reference: String,
+    /// Entries, summing to zero
+    pub entries: Vec<WalletEntry>,
+    /// When the transaction was posted
+    pub posted_at: Timestamp,
+}
+
+impl WalletTransaction {
+    fn new(
+        idempotency_key: &str,
+        kind: WalletTransactionKind,
+        reference: &str,
+        from: WalletAccount,
+        to: WalletAccount,
+        points: i64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            idempotency_key: idempotency_key.to_string(),
+            kind,
+            reference: reference.to_string(),
+            entries: vec![
+                WalletEntry {
+                    account: from,
+                    points: -points,
+                },
+                WalletEntry {
+                    account: to,
+                    points,
+                },
+            ],
+            posted_at: Timestamp::now(),
+        }
+    }
+
+    /// Check the entries sum to zero
+    pub fn is_balanced(&self) -> bool {
+        self.entries.iter().map(|e| e.points).sum::<i64>() == 0
+    }
+
+    /// Points this transaction added to (or took from) an account
+    pub fn points_for(&self, account: &WalletAccount) -> i64 {
+        self.entries
+            .iter()
+            .filter(|e| &e.account == account)
+            .map(|e| e.points)
+            .sum()
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "idempotency_key": self.idempotency_key,
+            "kind": self.kind.as_str(),
+            "reference": self.reference,
+            "entries": self
+                .entries
+                .iter()
+                .map(|e| json!({"account": e.account.key(), "points": e.points}))
+                .collect::<Vec<_>>(),
+            "posted_at": self.posted_at.as_unix(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let text = |name: &str| value.get(name)?.as_str().map(str::to_string);
+        let entries = value
+            .get("entries")?
+            .as_array()?
+            .iter()
+            .map(|e| {
+                Some(WalletEntry {
+                    account: WalletAccount::parse(e.get("account")?.as_str()?)?,
+                    points: e.get("points")?.as_i64()?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            id: text("id")?,
+            idempotency_key: text("idempotency_key")?,
+            kind: WalletTransactionKind::parse(&text("kind")?)?,
+            reference: text("reference")?,
+            entries,
+            posted_at: Timestamp::from_unix(value.get("posted_at")?.as_i64()?),
+        })
+    }
+}
+
+/// Points earned together, expiring together
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreditLot {
+    /// Lot ID (the earning transaction's idempotency key)
+    pub id: String,
+    /// Points earned
+    pub points: i64,
+    /// Points not yet spent or expired
+    pub remaining: i64,
+    /// When the points were earned
+    pub earned_at: Timestamp,
+    /// When unspent points expire
+    pub expires_at: Timestamp,
+}
+
+impl CreditLot {
+    fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "points": self.points,
+            "remaining": self.remaining,
+            "earned_at": self.earned_at.as_unix(),
+            "expires_at": self.expires_at.as_unix(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let int = |name: &str| value.get(name)?.as_i64();
+        Some(Self {
+            id: value.get("id")?.as_str()?.to_string(),
+            points: int("points")?,
+            remaining: int("remaining")?,
+            earned_at: Timestamp::from_unix(int("earned_at")?),
+            expires_at: Timestamp::from_unix(int("expires_at")?),
+        })
+    }
+}
+
+/// What redeemed points pay for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedemptionTarget {
+    /// A new booking
+    Booking(String),
+    /// A contribution to a group pool
+    PoolContribution(String),
+}
+
+impl RedemptionTarget {
+    /// Reference recorded on the transaction
+    pub fn reference(&self) -> String {
+        match self {
+            Self::Booking(id) => format!("booking:{}", id),
+            Self::PoolContribution(id) => format!("pool:{}", id),
+        }
+    }
+}
+
+/// A user's wallet balance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletBalance {
+    /// User ID
+    pub user_id: String,
+    /// Points available
+    pub points: i64,
+    /// What the points are worth
+    pub value: Price,
+    /// Unspent lots, soonest to expire first
+    pub lots: Vec<CreditLot>,
+}
+
+/// Result of an expiry sweep
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WalletExpiryReport {
+    /// Wallets that lost points
+    pub wallets: usize,
+    /// Points expired
+    pub points: i64,
+}
+
+/// Loyalty programme settings
+#[derive(Debug, Clone)]
+pub struct LoyaltyConfig {
+    /// Currency points are worth one minor unit of
+    pub currency: CurrencyCode,
+    /// Points earned per 100% of the booking total, in basis points
+    /// (100 = 1 point per RM 1.00 spent)
+    pub earn_bps: u32,
+    /// Days before earned points expire
+    pub expiry_days: i64,
+    /// Fewest points that can be redeemed at once
+    pub min_redeem_points: i64,
+}
+
+impl Default for LoyaltyConfig {
+    fn default() -> Self {
+        Self {
+            currency: CurrencyCode::MYR,
+            earn_bps: 100,
+            expiry_days: 365,
+            min_redeem_points: 500,
+        }
+    }
+}
+
+/// Storage for wallet transactions, balances and credit lots
+///
+/// [`WalletService`] serializes all writes, so stores need not make
+/// `append` and `save_lots` atomic with each other.
+pub trait WalletStore: Send + Sync {
+    /// Transaction posted with an idempotency key
+    fn find(&self, idempotency_key: &str) -> CoreResult<Option<WalletTransaction>>;
+
+    /// Record a transaction and apply it to the account balances
+    fn append(&self, txn: &WalletTransaction) -> CoreResult<()>;
+
+    /// Balance of an account
+    fn balance(&self, account: &WalletAccount) -> CoreResult<i64>;
+
+    /// Transactions touching an account, oldest first
+    fn transactions(&self, account: &WalletAccount) -> CoreResult<Vec<WalletTransaction>>;
+
+    /// A user's unspent credit lots
+    fn lots(&self, user_id: &str) -> CoreResult<Vec<CreditLot>>;
+
+    /// Replace a user's unspent credit lots
+    fn save_lots(&self, user_id: &str, lots: &[CreditLot]) -> CoreResult<()>;
+
+    /// Users holding unspent lots
+    fn lot_holders(&self) -> CoreResult<Vec<String>>;
+}
+
+/// In-memory wallet store
+#[derive(Default)]
+pub struct MemoryWalletStore {
+    transactions: RwLock<HashMap<String, WalletTransaction>>,
+    /// Transaction keys per account, oldest first
+    history: RwLock<HashMap<WalletAccount, Vec<String>>>,
+    lots: RwLock<HashMap<String, Vec<CreditLot>>>,
+}
+
+impl MemoryWalletStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WalletStore for MemoryWalletStore {
+    fn find(&self, idempotency_key: &str) -> CoreResult<Option<WalletTransaction>> {
+        Ok(self
+            .transactions
+            .read()
+            .unwrap()
+            .get(idempotency_key)
+            .cloned())
+    }
+
+    fn append(&self, txn: &WalletTransaction) -> CoreResult<()> {
+        self.transactions
+            .write()
+            .unwrap()
+            .insert(txn.idempotency_key.clone(), txn.clone());
+        let mut history = self.history.write().unwrap();
+        for entry in &txn.entries {
+            history
+                .entry(entry.account.clone())
+                .or_default()
+                .push(txn.idempotency_key.clone());
+        }
+        Ok(())
+    }
+
+    fn balance(&self, account: &WalletAccount) -> CoreResult<i64> {
+        Ok(self
+            .transactions(account)?
+            .iter()
+            .map(|t| t.points_for(account))
+            .sum())
+    }
+
+    fn transactions(&self, account: &WalletAccount) -> CoreResult<Vec<WalletTransaction>> {
+        let transactions = self.transactions.read().unwrap();
+        Ok(self
+            .history
+            .read()
+            .unwrap()
+            .get(account)
+            .into_iter()
+            .flatten()
+            .filter_map(|key| transactions.get(key).cloned())
+            .collect())
+    }
+
+    fn lots(&self, user_id: &str) -> CoreResult<Vec<CreditLot>> {
+        Ok(self
+            .lots
+            .read()
+            .unwrap()
+            .get(user_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn save_lots(&self, user_id: &str, lots: &[CreditLot]) -> CoreResult<()> {
+        let mut all = self.lots.write().unwrap();
+        if lots.is_empty() {
+            all.remove(user_id);
+        } else {
+            all.insert(user_id.to_string(), lots.to_vec());
+        }
+        Ok(())
+    }
+
+    fn lot_holders(&self) -> CoreResult<Vec<String>> {
+        Ok(self.lots.read().unwrap().keys().cloned().collect())
+    }
+}
+
+const TXN_PREFIX: &str = "wallet:txn:";
+const ACCOUNT_PREFIX: &str = "wallet:account:";
+const LOTS_PREFIX: &str = "wallet:lots:";
+const LOT_HOLDERS_KEY: &[u8] = b"wallet:lot_holders";
+
+/// Wallet store persisting to `VayaDb`
+///
+/// Transactions are kept as JSON under `wallet:txn:{idempotency key}`.
+/// Each account has a row under `wallet:account:{account}` with its
+/// balance and transaction keys, and each user's lots are one row under
+/// `wallet:lots:{user}`. `VayaDb` has no key iteration, so users holding
+/// lots are also listed under `wallet:lot_holders`.
+pub struct DbWalletStore {
+    db: Arc<VayaDb>,
+    /// Serializes account and index updates
+    lock: Mutex<()>,
+}
+
+impl DbWalletStore {
+    /// Create a store over an open database
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self, key: &[u8]) -> CoreResult<Option<Value>> {
+        let Some(bytes) = self.db.get(key).map_err(db_error)? else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| CoreError::Database(format!("Corrupt wallet record: {}", e)))
+    }
+
+    fn write(&self, key: &[u8], value: &Value) -> CoreResult<()> {
+        self.db
+            .put(key, value.to_string().as_bytes())
+            .map_err(db_error)
+    }
+
+    fn account(&self, account: &WalletAccount) -> CoreResult<(i64, Vec<String>)> {
+        let key = format!("{}{}", ACCOUNT_PREFIX, account.key());
+        let Some(row) = self.read(key.as_bytes())? else {
+            return Ok((0, Vec::new()));
+        };
+        let balance = row.get("balance").and_then(Value::as_i64).unwrap_or(0);
+        let keys = row
+            .get("transactions")
+            .and_then(Value::as_array)
+            .map(|keys| {
+                keys.iter()
+                    .filter_map(|k| k.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok((balance, keys))
+    }
+
+    fn strings(&self, key: &[u8]) -> CoreResult<Vec<String>> {
+        Ok(self
+            .read(key)?
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect())
+    }
+}
+
+impl WalletStore for DbWalletStore {
+    fn find(&self, idempotency_key: &str) -> CoreResult<Option<WalletTransaction>> {
+        let key = format!("{}{}", TXN_PREFIX, idempotency_key);
+        match self.read(key.as_bytes())? {
+            Some(value) => WalletTransaction::from_json(&value)
+                .map(Some)
+                .ok_or_else(|| CoreError::Database("Corrupt wallet transaction".into())),
+            None => Ok(None),
+        }
+    }
+
+    fn append(&self, txn: &WalletTransaction) -> CoreResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let key = format!("{}{}", TXN_PREFIX, txn.idempotency_key);
+        self.write(key.as_bytes(), &txn.to_json())?;
+        for entry in &txn.entries {
+            let (balance, mut keys) = self.account(&entry.account)?;
+            keys.push(txn.idempotency_key.clone());
+            let row = json!({"balance": balance + entry.points, "transactions": keys});
+            let key = format!("{}{}", ACCOUNT_PREFIX, entry.account.key());
+            self.write(key.as_bytes(), &row)?;
+        }
+        Ok(())
+    }
+
+    fn balance(&self, account: &WalletAccount) -> CoreResult<i64> {
+        Ok(self.account(account)?.0)
+    }
+
+    fn transactions(&self, account: &WalletAccount) -> CoreResult<Vec<WalletTransaction>> {
+        let mut transactions = Vec::new();
+        for key in self.account(account)?.1 {
+            transactions.extend(self.find(&key)?);
+        }
+        Ok(transactions)
+    }
+
+    fn lots(&self, user_id: &str) -> CoreResult<Vec<CreditLot>> {
+        let key = format!("{}{}", LOTS_PREFIX, user_id);
+        self.read(key.as_bytes())?
+            .and_then(|v| v.as_array().cloned())
+            .unwrap_or_default()
+            .iter()
+            .map(|lot| {
+                CreditLot::from_json(lot)
+                    .ok_or_else(|| CoreError::Database("Corrupt credit lot".into()))
+            })
+            .collect()
+    }
+
+    fn save_lots(&self, user_id: &str, lots: &[CreditLot]) -> CoreResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let key = format!("{}{}", LOTS_PREFIX, user_id);
+        let mut holders = self.strings(LOT_HOLDERS_KEY)?;
+        let held = holders.iter().any(|h| h == user_id);
+        if lots.is_empty() {
+            self.db.delete(key.as_bytes()).map_err(db_error)?;
+            if held {
+                holders.retain(|h| h != user_id);
+                self.write(LOT_HOLDERS_KEY, &json!(holders))?;
+            }
+            return Ok(());
+        }
+        let rows: Vec<Value> = lots.iter().map(CreditLot::to_json).collect();
+        self.write(key.as_bytes(), &Value::Array(rows))?;
+        if !held {
+            holders.push(user_id.to_string());
+            self.write(LOT_HOLDERS_KEY, &json!(holders))?;
+        }
+        Ok(())
+    }
+
+    fn lot_holders(&self) -> CoreResult<Vec<String>> {
+        self.strings(LOT_HOLDERS_KEY)
+    }
+}
+
+fn db_error(e: vaya_db::DbError) -> CoreError {
+    CoreError::Database(e.to_string())
+}
+
+/// Earns, spends and expires loyalty points
+pub struct WalletService {
+    store: Arc<dyn WalletStore>,
+    config: LoyaltyConfig,
+    /// Serializes balance checks with the postings they allow
+    post_lock: Mutex<()>,
+}
+
+impl WalletService {
+    /// Create a wallet service over a store
+    pub fn new(store: Arc<dyn WalletStore>) -> Self {
+        Self {
+            store,
+            config: LoyaltyConfig::default(),
+            post_lock: Mutex::new(()),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: LoyaltyConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The programme settings
+    pub fn config(&self) -> &LoyaltyConfig {
+        &self.config
+    }
+
+    /// What a number of points is worth
+    pub fn value_of(&self, points: i64) -> Price {
+        Price::new(MinorUnits::new(points), self.config.currency)
+    }
+
+    /// Credit points to a user's wallet
+    pub fn earn(
+        &self,
+        user_id: &str,
+        points: i64,
+        reference: &str,
+        idempotency_key: &str,
+    ) -> CoreResult<WalletTransaction> {
+        if points <= 0 {
+            return Err(CoreError::ValidationError(
+                "Points earned must be positive".to_string(),
+            ));
+        }
+        let _guard = self.post_lock.lock().unwrap();
+        if let Some(existing) = self.store.find(idempotency_key)? {
+            return Ok(existing);
+        }
+
+        let txn = WalletTransaction::new(
+            idempotency_key,
+            WalletTransactionKind::Earn,
+            reference,
+            WalletAccount::Issued,
+            WalletAccount::User(user_id.to_string()),
+            points,
+        );
+        let mut lots = self.store.lots(user_id)?;
+        lots.push(CreditLot {
+            id: idempotency_key.to_string(),
+            points,
+            remaining: points,
+            earned_at: txn.posted_at,
+            expires_at: txn.posted_at.add_days(self.config.expiry_days),
+        });
+        self.post(&txn)?;
+        self.store.save_lots(user_id, &lots)?;
+        info!("User {} earned {} points ({})", user_id, points, reference);
+        Ok(txn)
+    }
+
+    /// Credit the points for a completed booking
+    ///
+    /// Only bookings priced in the loyalty currency earn points; convert
+    /// other totals and call [`earn`](Self::earn) directly.
+    pub fn earn_for_booking(&self, booking: &Booking) -> CoreResult<WalletTransaction> {
+        if booking.status != BookingStatus::Completed {
+            return Err(CoreError::BookingNotModifiable(
+                "Points are earned once the trip is completed".to_string(),
+            ));
+        }
+        if booking.total_price.currency != self.config.currency {
+            return Err(CoreError::ValidationError(format!(
+                "Booking is priced in {}, points are earned in {}",
+                booking.total_price.currency, self.config.currency
+            )));
+        }
+        let points = booking.total_price.amount.as_i64() * i64::from(self.config.earn_bps) / 10_000;
+        self.earn(
+            &booking.user_id,
+            points,
+            &format!("booking:{}", booking.id),
+            &format!("earn:booking:{}", booking.id),
+        )
+    }
+
+    /// Spend points, returning the credit they are worth
+    ///
+    /// Expired lots are swept first, then the soonest-expiring points are
+    /// spent. Repeating a redemption with the same idempotency key returns
+    /// the original credit.
+    pub fn redeem(
+        &self,
+        user_id: &str,
+        points: i64,
+        target: &RedemptionTarget,
+        idempotency_key: &str,
+    ) -> CoreResult<Price> {
+        let account = WalletAccount::User(user_id.to_string());
+        let _guard = self.post_lock.lock().unwrap();
+        if let Some(existing) = self.store.find(idempotency_key)? {
+            return Ok(self.value_of(-existing.points_for(&account)));
+        }
+        if points < self.config.min_redeem_points {
+            return Err(CoreError::ValidationError(format!(
+                "At least {} points must be redeemed",
+                self.config.min_redeem_points
+            )));
+        }
+
+        self.expire_wallet(user_id, Timestamp::now())?;
+        let mut lots = self.store.lots(user_id)?;
+        let available: i64 = lots.iter().map(|l| l.remaining).sum();
+        if available < points {
+            return Err(CoreError::ValidationError(format!(
+                "Only {} points available",
+                available
+            )));
+        }
+
+        lots.sort_by_key(|l| l.expires_at);
+        let mut left = points;
+        for lot in &mut lots {
+            let spent = lot.remaining.min(left);
+            lot.remaining -= spent;
+            left -= spent;
+        }
+        lots.retain(|l| l.remaining > 0);
+
+        let txn = WalletTransaction::new(
+            idempotency_key,
+            WalletTransactionKind::Redeem,
+            &target.reference(),
+            account,
+            WalletAccount::Redeemed,
+            points,
+        );
+        self.post(&txn)?;
+        self.store.save_lots(user_id, &lots)?;
+        info!(
+            "User {} redeemed {} points ({})",
+            user_id, points, txn.reference
+        );
+        Ok(self.value_of(points))
+    }
+
+    /// Spend points toward a booking and take the credit off its total
+    pub fn apply_to_booking(&self, points: i64, booking: &mut Booking) -> CoreResult<()> {
+        if booking.wallet_credit.is_some() {
+            return Err(CoreError::BookingNotModifiable(
+                "Wallet credit already applied".to_string(),
+            ));
+        }
+        if booking.total_price.currency != self.config.currency {
+            return Err(CoreError::ValidationError(format!(
+                "Points can only pay for {} bookings",
+                self.config.currency
+            )));
+        }
+        if points > booking.total_price.amount.as_i64() {
+            return Err(CoreError::ValidationError(
+                "Points exceed the booking total".to_string(),
+            ));
+        }
+        let credit = self.redeem(
+            &booking.user_id,
+            points,
+            &RedemptionTarget::Booking(booking.id.clone()),
+            &format!("redeem:booking:{}", booking.id),
+        )?;
+        booking.total_price = Price::new(
+            MinorUnits::new(booking.total_price.amount.as_i64() - credit.amount.as_i64()),
+            booking.total_price.currency,
+        );
+        booking.wallet_credit = Some(credit);
+        booking.updated_at = Timestamp::now();
+        Ok(())
+    }
+
+    /// A user's balance and unspent lots
+    pub fn balance(&self, user_id: &str) -> CoreResult<WalletBalance> {
+        let points = self
+            .store
+            .balance(&WalletAccount::User(user_id.to_string()))?;
+        let mut lots = self.store.lots(user_id)?;
+        lots.sort_by_key(|l| l.expires_at);
+        Ok(WalletBalance {
+            user_id: user_id.to_string(),
+            points,
+            value: self.value_of(points),
+            lots,
+        })
+    }
+
+    /// A user's wallet transactions, newest first
+    pub fn history(&self, user_id: &str) -> CoreResult<Vec<WalletTransaction>> {
+        let mut transactions = self
+            .store
+            .transactions(&WalletAccount::User(user_id.to_string()))?;
+        transactions.reverse();
+        Ok(transactions)
+    }
+
+    /// Expire unspent points past their expiry in every wallet
+    pub fn expire_due(&self, now: Timestamp) -> CoreResult<WalletExpiryReport> {
+        let _guard = self.post_lock.lock().unwrap();
+        let mut report = WalletExpiryReport::default();
+        for user_id in self.store.lot_holders()? {
+            let expired = self.expire_wallet(&user_id, now)?;
+            if expired > 0 {
+                report.wallets += 1;
+                report.points += expired;
+            }
+        }
+        if report.points > 0 {
+            info!(
+                "Expired {} points from {} wallets",
+                report.points, report.wallets
+            );
+        }
+        Ok(report)
+    }
+
+    /// Expire one user's due lots; the caller holds the post lock
+    fn expire_wallet(&self, user_id: &str, now: Timestamp) -> CoreResult<i64> {
+        let mut lots = self.store.lots(user_id)?;
+        let mut expired = 0;
+        for lot in lots
+            .iter()
+            .filter(|l| l.expires_at <= now && l.remaining > 0)
+        {
+            let key = format!("expire:{}", lot.id);
+            if self.store.find(&key)?.is_none() {
+                self.post(&WalletTransaction::new(
+                    &key,
+                    WalletTransactionKind::Expire,
+                    &format!("lot:{}", lot.id),
+                    WalletAccount::User(user_id.to_string()),
+                    WalletAccount::Expired,
+                    lot.remaining,
+                ))?;
+            }
+            expired += lot.remaining;
+        }
+        if expired > 0 {
+            lots.retain(|l| l.expires_at > now);
+            self.store.save_lots(user_id, &lots)?;
+        }
+        Ok(expired)
+    }
+
+    fn post(&self, txn: &WalletTransaction) -> CoreResult<()> {
+        if !txn.is_balanced() {
+            return Err(CoreError::Internal(format!(
+                "Unbalanced wallet transaction {}",
+                txn.idempotency_key
+            )));
+        }
+        self.store.append(txn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_db::DbConfig;
+
+    fn service() -> WalletService {
+        WalletService::new(Arc::new(MemoryWalletStore::new()))
+    }
+
+    fn pool(id: &str) -> RedemptionTarget {
+        RedemptionTarget::PoolContribution(id.to_string())
+    }
+
+    #[test]
+    fn test_earn_and_redeem_are_idempotent() {
+        let service = service();
+        service.earn("u1", 1_000, "booking:b1", "earn:b1").unwrap();
+        service.earn("u1", 1_000, "booking:b1", "earn:b1").unwrap();
+        assert_eq!(service.balance("u1").unwrap().points, 1_000);
+
+        let credit = service.redeem("u1", 600, &pool("p1"), "redeem:p1").unwrap();
+        assert_eq!(credit, Price::myr(600));
+        let again = service.redeem("u1", 600, &pool("p1"), "redeem:p1").unwrap();
+        assert_eq!(again, credit);
+
+        let balance = service.balance("u1").unwrap();
+        assert_eq!(balance.points, 400);
+        assert_eq!(balance.value, Price::myr(400));
+        assert_eq!(service.history("u1").unwrap().len(), 2);
+
+        // Too few points, and more than the wallet holds
+        assert!(service.redeem("u1", 100, &pool("p2"), "redeem:p2").is_err());
+        assert!(service.redeem("u1", 500, &pool("p2"), "redeem:p2").is_err());
+
+        // The system accounts balance the wallets
+        let store = &service.store;
+        let issued = store.balance(&WalletAccount::Issued).unwrap();
+        let redeemed = store.balance(&WalletAccount::Redeemed).unwrap();
+        assert_eq!(issued + redeemed + balance.points, 0);
+    }
+
+    #[test]
+    fn test_redeem_spends_soonest_expiring_and_expiry() {
+        let service = service();
+        service.earn("u1", 500, "booking:b1", "earn:b1").unwrap();
+        service.earn("u1", 800, "booking:b2", "earn:b2").unwrap();
+        let mut lots = service.store.lots("u1").unwrap();
+        lots[1].expires_at = Timestamp::now().add_days(30);
+        service.store.save_lots("u1", &lots).unwrap();
+
+        service.redeem("u1", 600, &pool("p1"), "redeem:p1").unwrap();
+        let balance = service.balance("u1").unwrap();
+        assert_eq!(balance.lots.len(), 2);
+        assert_eq!(balance.lots[0].id, "earn:b2");
+        assert_eq!(balance.lots[0].remaining, 200);
+        assert_eq!(balance.lots[1].remaining, 500);
+
+        let report = service.expire_due(Timestamp::now().add_days(60)).unwrap();
+        assert_eq!(report.wallets, 1);
+        assert_eq!(report.points, 200);
+        assert_eq!(service.balance("u1").unwrap().points, 500);
+        assert_eq!(service.store.balance(&WalletAccount::Expired).unwrap(), 200);
+        // Nothing left to expire
+        assert_eq!(
+            service
+                .expire_due(Timestamp::now().add_days(60))
+                .unwrap()
+                .points,
+            0
+        );
+    }
+
+    #[test]
+    fn test_db_store_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || Arc::new(VayaDb::open(DbConfig::new(dir.path())).unwrap());
+
+        {
+            let db = open();
+            let service = WalletService::new(Arc::new(DbWalletStore::new(db.clone())));
+            service.earn("u1", 2_000, "booking:b1", "earn:b1").unwrap();
+            service.redeem("u1", 700, &pool("p1"), "redeem:p1").unwrap();
+            db.close().unwrap();
+        }
+
+        let service = WalletService::new(Arc::new(DbWalletStore::new(open())));
+        let balance = service.balance("u1").unwrap();
+        assert_eq!(balance.points, 1_300);
+        assert_eq!(balance.lots[0].remaining, 1_300);
+        assert_eq!(
+            service.history("u1").unwrap()[0].kind,
+            WalletTransactionKind::Redeem
+        );
+        assert_eq!(service.store.lot_holders().unwrap(), vec!["u1".to_string()]);
+        // The earlier posting is still recognized
+        service.earn("u1", 2_000, "booking:b1", "earn:b1").unwrap();
+        assert_eq!(service.balance("u1").unwrap().points, 1_300);
+    }
+}
@@ -148,6 +148,18 @@ impl AsRef<[u8]> for HmacTag512 {
     }
 }
 
+/// HMAC-SHA1, for HOTP/TOTP one-time passwords (RFC 4226/6238) only
+///
+/// Authenticator apps only reliably support SHA-1; use [`HmacKey`] for
+/// anything else.
+pub fn hmac_sha1(key: &[u8], data: &[u8]) -> [u8; 20] {
+    let key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+    let tag = hmac::sign(&key, data);
+    let mut bytes = [0u8; 20];
+    bytes.copy_from_slice(tag.as_ref());
+    bytes
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,4 +200,11 @@ mod tests {
         let parsed = HmacTag::from_hex(&hex).unwrap();
         assert_eq!(tag.as_bytes(), parsed.as_bytes());
     }
+
+    #[test]
+    fn test_hmac_sha1() {
+        // RFC 2202 test case 1
+        let tag = hmac_sha1(&[0x0b; 20], b"Hi There");
+        assert_eq!(hex_encode(&tag), "b617318655057264e28bc0b6fb378c8ef146be00");
+    }
 }
@@ -0,0 +1,206 @@
+//! Versioned key store for AES-256-GCM keys
+//!
+//! Keys are identified by a numeric ID that is persisted next to the data
+//! they protect (e.g. in file headers), so old data stays readable after a
+//! rotation while new data is written under the active key.
+//!
+//! Key ID `0` is reserved to mean "not encrypted".
+
+use crate::aead::AeadKey;
+use crate::random::base64_decode;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::RwLock;
+use vaya_common::{ErrorCode, Result, VayaError};
+
+/// Reserved key ID meaning "no encryption"
+pub const NO_KEY_ID: u32 = 0;
+
+/// Store of AEAD keys with a single active key for new writes
+pub struct KeyStore {
+    inner: RwLock<KeyStoreInner>,
+}
+
+struct KeyStoreInner {
+    keys: BTreeMap<u32, AeadKey>,
+    active: u32,
+}
+
+impl KeyStore {
+    /// Create an empty key store
+    pub fn new() -> Self {
+        Self {
+            inner: RwLock::new(KeyStoreInner {
+                keys: BTreeMap::new(),
+                active: NO_KEY_ID,
+            }),
+        }
+    }
+
+    /// Create a key store with a single active key
+    pub fn with_key(id: u32, key: AeadKey) -> Result<Self> {
+        let store = Self::new();
+        store.insert(id, key)?;
+        store.set_active(id)?;
+        Ok(store)
+    }
+
+    /// Parse a key list of the form `id:base64key,id:base64key`
+    ///
+    /// The highest ID becomes the active key.
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let store = Self::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (id, key) = entry.split_once(':').ok_or_else(|| {
+                VayaError::new(ErrorCode::CryptoError, "Key entry must be id:base64key")
+            })?;
+            let id: u32 = id
+                .trim()
+                .parse()
+                .map_err(|_| VayaError::new(ErrorCode::CryptoError, "Invalid key ID"))?;
+            store.insert(id, AeadKey::new(&base64_decode(key.trim())?)?)?;
+        }
+        if let Some(max) = store.key_ids().last().copied() {
+            store.set_active(max)?;
+        }
+        Ok(store)
+    }
+
+    /// Add a key under the given ID (does not change the active key)
+    pub fn insert(&self, id: u32, key: AeadKey) -> Result<()> {
+        if id == NO_KEY_ID {
+            return Err(VayaError::new(
+                ErrorCode::CryptoError,
+                "Key ID 0 is reserved for unencrypted data",
+            ));
+        }
+        self.write().keys.insert(id, key);
+        Ok(())
+    }
+
+    /// Make an existing key the active key for new writes
+    pub fn set_active(&self, id: u32) -> Result<()> {
+        let mut inner = self.write();
+        if !inner.keys.contains_key(&id) {
+            return Err(VayaError::new(
+                ErrorCode::CryptoError,
+                format!("Unknown key ID {}", id),
+            ));
+        }
+        inner.active = id;
+        Ok(())
+    }
+
+    /// Generate a new key, make it active, and return its ID
+    pub fn rotate(&self) -> Result<u32> {
+        let key = AeadKey::generate()?;
+        let mut inner = self.write();
+        let id = inner.keys.keys().last().copied().unwrap_or(NO_KEY_ID) + 1;
+        inner.keys.insert(id, key);
+        inner.active = id;
+        Ok(id)
+    }
+
+    /// Remove a retired key; the active key cannot be removed
+    pub fn remove(&self, id: u32) -> Result<()> {
+        let mut inner = self.write();
+        if inner.active == id {
+            return Err(VayaError::new(
+                ErrorCode::CryptoError,
+                "Cannot remove the active key",
+            ));
+        }
+        inner.keys.remove(&id);
+        Ok(())
+    }
+
+    /// Get the active key and its ID, if any key is active
+    pub fn active(&self) -> Option<(u32, AeadKey)> {
+        let inner = self.read();
+        inner
+            .keys
+            .get(&inner.active)
+            .map(|key| (inner.active, key.clone()))
+    }
+
+    /// Get the active key ID (`NO_KEY_ID` if none)
+    pub fn active_id(&self) -> u32 {
+        self.read().active
+    }
+
+    /// Look up a key by ID
+    pub fn get(&self, id: u32) -> Option<AeadKey> {
+        self.read().keys.get(&id).cloned()
+    }
+
+    /// All known key IDs in ascending order
+    pub fn key_ids(&self) -> Vec<u32> {
+        self.read().keys.keys().copied().collect()
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, KeyStoreInner> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, KeyStoreInner> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for KeyStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for KeyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print key material
+        f.debug_struct("KeyStore")
+            .field("key_ids", &self.key_ids())
+            .field("active", &self.active_id())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::base64_encode;
+
+    #[test]
+    fn test_rotate_keeps_old_keys() {
+        let store = KeyStore::new();
+        assert!(store.active().is_none());
+
+        let first = store.rotate().unwrap();
+        let second = store.rotate().unwrap();
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(store.active_id(), 2);
+        assert!(store.get(1).is_some());
+
+        assert!(store.remove(2).is_err());
+        store.remove(1).unwrap();
+        assert_eq!(store.key_ids(), vec![2]);
+    }
+
+    #[test]
+    fn test_from_spec() {
+        let k1 = base64_encode(&[1u8; 32]);
+        let k7 = base64_encode(&[7u8; 32]);
+        let store = KeyStore::from_spec(&format!("7:{}, 1:{}", k7, k1)).unwrap();
+        assert_eq!(store.active_id(), 7);
+        assert_eq!(store.get(1).unwrap().as_bytes(), &[1u8; 32]);
+
+        assert!(KeyStore::from_spec("0:abc").is_err());
+        assert!(KeyStore::from_spec("nonsense").is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_keys() {
+        let store = KeyStore::with_key(3, AeadKey::new(&[9u8; 32]).unwrap()).unwrap();
+        let debug = format!("{:?}", store);
+        assert!(debug.contains("active: 3"));
+        assert!(!debug.contains("9, 9"));
+    }
+}
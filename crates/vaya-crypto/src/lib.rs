@@ -6,6 +6,8 @@
 //! - Random number generation
 //! - HMAC
 //! - AES-GCM encryption
+//! - Versioned key store for key rotation
+//! - Secrets sealed in a file under a master key
 //! - SHA-256/384/512 hashing
 //!
 //! # Architecture
@@ -19,15 +21,19 @@ pub mod aead;
 pub mod hash;
 pub mod hmac;
 pub mod jwt;
+pub mod keystore;
 pub mod password;
 pub mod random;
+pub mod secrets;
 
 pub use aead::*;
 pub use hash::*;
 pub use hmac::*;
 pub use jwt::*;
+pub use keystore::*;
 pub use password::*;
 pub use random::*;
+pub use secrets::{MasterKey, SecretStore};
 
 use vaya_common::{ErrorCode, VayaError};
 
@@ -152,6 +152,57 @@ fn hex_char_to_nibble(c: u8) -> Result<u8> {
     }
 }
 
+/// Encode bytes to base32 (RFC 4648 alphabet, no padding)
+pub fn base32_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut result = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0u8;
+
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        result.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    result
+}
+
+/// Decode base32 (RFC 4648 alphabet, any case, padding and spaces ignored)
+pub fn base32_decode(input: &str) -> Result<Vec<u8>> {
+    let mut result = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0u8;
+
+    for c in input.chars().filter(|c| !matches!(c, '=' | ' ' | '-')) {
+        let val = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => (c as u32) - ('A' as u32),
+            c @ '2'..='7' => (c as u32) - ('2' as u32) + 26,
+            _ => {
+                return Err(VayaError::new(
+                    ErrorCode::CryptoError,
+                    "Invalid base32 character",
+                ))
+            }
+        };
+        buffer = (buffer << 5) | val;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(result)
+}
+
 /// Encode bytes to base64 (URL-safe, no padding)
 pub fn base64_encode(bytes: &[u8]) -> String {
     const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
@@ -258,4 +309,13 @@ mod tests {
         assert!(!encoded.contains('+'));
         assert!(!encoded.contains('/'));
     }
+
+    #[test]
+    fn test_base32_encode_decode() {
+        // RFC 4648 test vectors
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("mzxw 6ytb oi======").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_err());
+    }
 }
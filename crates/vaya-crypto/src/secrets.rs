@@ -0,0 +1,324 @@
+//! Secrets sealed in a file under a master key
+//!
+//! A [`SecretStore`] keeps named secrets (API keys, signing secrets) in one
+//! file, encrypted as a whole with AES-256-GCM, so configuration can refer
+//! to them as `secret://name` instead of holding them in plain text.
+//!
+//! The master key comes either directly, as base64, or from an external
+//! command that prints it, so it can be fetched from a KMS or vault at
+//! startup and never written to disk. File layout:
+//!
+//! ```text
+//! "VAYASEC1" | nonce (12) | ciphertext | tag (16)
+//! ```
+//!
+//! The plaintext is a sequence of entries, each a big-endian `u16` name
+//! length, the name, a `u32` value length and the value. The magic is the
+//! associated data, so a file can't be passed off as another format.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::aead::AeadKey;
+use crate::random::base64_decode;
+use vaya_common::{ErrorCode, Result, SecretSource, VayaError};
+
+/// Magic at the start of a secrets file
+const MAGIC: &[u8; 8] = b"VAYASEC1";
+
+/// Longest secret name
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Where the master key comes from
+pub enum MasterKey {
+    /// The key itself
+    Key(AeadKey),
+    /// A shell command printing the base64 key on stdout
+    Command(String),
+}
+
+impl MasterKey {
+    /// Master key given as base64
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        Ok(Self::Key(AeadKey::new(&base64_decode(encoded.trim())?)?))
+    }
+
+    /// Master key printed by `command` (run with `sh -c`)
+    pub fn command(command: impl Into<String>) -> Self {
+        Self::Command(command.into())
+    }
+
+    /// Get the key, running the command if there is one
+    pub fn load(&self) -> Result<AeadKey> {
+        match self {
+            MasterKey::Key(key) => Ok(key.clone()),
+            MasterKey::Command(command) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()
+                    .map_err(|e| {
+                        secrets_error(format!("Failed to run master key command: {}", e))
+                    })?;
+                if !output.status.success() {
+                    return Err(secrets_error(format!(
+                        "Master key command failed ({}): {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    )));
+                }
+                let encoded = String::from_utf8(output.stdout)
+                    .map_err(|_| secrets_error("Master key command printed invalid UTF-8"))?;
+                AeadKey::new(&base64_decode(encoded.trim())?)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print key material; the command is fine
+        match self {
+            MasterKey::Key(_) => f.write_str("MasterKey::Key(..)"),
+            MasterKey::Command(command) => {
+                f.debug_tuple("MasterKey::Command").field(command).finish()
+            }
+        }
+    }
+}
+
+/// Named secrets sealed in a file
+pub struct SecretStore {
+    path: PathBuf,
+    key: AeadKey,
+    entries: BTreeMap<String, String>,
+}
+
+impl SecretStore {
+    /// Open the store at `path`, starting empty if the file doesn't exist
+    pub fn open(path: impl Into<PathBuf>, key: AeadKey) -> Result<Self> {
+        let path = path.into();
+        let entries = match std::fs::read(&path) {
+            Ok(sealed) => unseal(&sealed, &key)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(secrets_error(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        Ok(Self { path, key, entries })
+    }
+
+    /// Path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get a secret
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(|v| v.as_str())
+    }
+
+    /// Set a secret (call [`save`](Self::save) to keep it)
+    pub fn set(&mut self, name: &str, value: impl Into<String>) -> Result<()> {
+        validate_name(name)?;
+        self.entries.insert(name.to_string(), value.into());
+        Ok(())
+    }
+
+    /// Remove a secret, returning whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    /// Names of every secret, sorted
+    pub fn names(&self) -> Vec<&str> {
+        self.entries.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// Number of secrets
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if there are no secrets
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Seal the secrets and replace the file
+    ///
+    /// Writes to a temporary file first (owner-only on Unix) and renames
+    /// it over the old one, so a crash never leaves a half-written store.
+    pub fn save(&self) -> Result<()> {
+        let sealed = seal(&self.entries, &self.key)?;
+        let io_error = |e: std::io::Error| {
+            secrets_error(format!("Failed to write {}: {}", self.path.display(), e))
+        };
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&tmp).map_err(io_error)?;
+        std::io::Write::write_all(&mut file, &sealed).map_err(io_error)?;
+        file.sync_all().map_err(io_error)?;
+        std::fs::rename(&tmp, &self.path).map_err(io_error)
+    }
+}
+
+impl SecretSource for SecretStore {
+    fn secret(&self, name: &str) -> Option<String> {
+        self.get(name).map(str::to_string)
+    }
+}
+
+impl fmt::Debug for SecretStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Names only, never values
+        f.debug_struct("SecretStore")
+            .field("path", &self.path)
+            .field("names", &self.names())
+            .finish()
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err(secrets_error(format!(
+            "Invalid secret name {:?} (use letters, digits, `_`, `-`, `.` and `/`)",
+            name
+        )))
+    }
+}
+
+fn seal(entries: &BTreeMap<String, String>, key: &AeadKey) -> Result<Vec<u8>> {
+    let mut plaintext = Vec::new();
+    for (name, value) in entries {
+        let value_len = u32::try_from(value.len())
+            .map_err(|_| secrets_error(format!("Secret {} is too large", name)))?;
+        plaintext.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        plaintext.extend_from_slice(name.as_bytes());
+        plaintext.extend_from_slice(&value_len.to_be_bytes());
+        plaintext.extend_from_slice(value.as_bytes());
+    }
+    let mut sealed = MAGIC.to_vec();
+    sealed.extend_from_slice(&key.encrypt(&plaintext, MAGIC)?);
+    Ok(sealed)
+}
+
+fn unseal(sealed: &[u8], key: &AeadKey) -> Result<BTreeMap<String, String>> {
+    let body = sealed
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or_else(|| secrets_error("Not a secrets file"))?;
+    let plaintext = key
+        .decrypt(body, MAGIC)
+        .map_err(|_| secrets_error("Wrong master key or corrupted secrets file"))?;
+
+    let mut entries = BTreeMap::new();
+    let mut rest = plaintext.as_slice();
+    while !rest.is_empty() {
+        let name = take_field(&mut rest, 2)?;
+        let value = take_field(&mut rest, 4)?;
+        entries.insert(name, value);
+    }
+    Ok(entries)
+}
+
+/// Take a length-prefixed UTF-8 field whose length takes `width` bytes
+fn take_field(rest: &mut &[u8], width: usize) -> Result<String> {
+    let truncated = || secrets_error("Truncated secrets file");
+    if rest.len() < width {
+        return Err(truncated());
+    }
+    let (len, tail) = rest.split_at(width);
+    let len = len.iter().fold(0usize, |n, &b| (n << 8) | usize::from(b));
+    if tail.len() < len {
+        return Err(truncated());
+    }
+    let (field, tail) = tail.split_at(len);
+    *rest = tail;
+    String::from_utf8(field.to_vec()).map_err(|_| secrets_error("Secret is not valid UTF-8"))
+}
+
+fn secrets_error(msg: impl Into<String>) -> VayaError {
+    VayaError::new(ErrorCode::CryptoError, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::base64_encode;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("vaya-secrets-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_store_round_trip() {
+        let path = temp_path("round-trip");
+        let _ = std::fs::remove_file(&path);
+        let key = AeadKey::generate().unwrap();
+
+        let mut store = SecretStore::open(&path, key.clone()).unwrap();
+        assert!(store.is_empty());
+        store.set("stripe/secret_key", "sk_live_abc").unwrap();
+        store.set("jwt", "ünïcode ✓").unwrap();
+        assert!(store.set("bad name", "x").is_err());
+        store.save().unwrap();
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(raw.starts_with(MAGIC));
+        assert!(!raw.windows(11).any(|w| w == b"sk_live_abc"));
+
+        let mut store = SecretStore::open(&path, key.clone()).unwrap();
+        assert_eq!(store.names(), ["jwt", "stripe/secret_key"]);
+        assert_eq!(
+            store.secret("stripe/secret_key").as_deref(),
+            Some("sk_live_abc")
+        );
+        assert_eq!(store.get("jwt"), Some("ünïcode ✓"));
+        assert!(store.remove("jwt"));
+        assert!(!store.remove("jwt"));
+        assert!(!format!("{:?}", store).contains("sk_live"));
+
+        // Any other key is turned away
+        let err = SecretStore::open(&path, AeadKey::generate().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("Wrong master key"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_master_key_sources() {
+        let encoded = base64_encode(&[5u8; 32]);
+        let direct = MasterKey::from_base64(&encoded).unwrap();
+        assert_eq!(direct.load().unwrap().as_bytes(), &[5u8; 32]);
+        assert_eq!(format!("{:?}", direct), "MasterKey::Key(..)");
+
+        let command = MasterKey::command(format!("echo {}", encoded));
+        assert_eq!(command.load().unwrap().as_bytes(), &[5u8; 32]);
+
+        let failing = MasterKey::command("echo denied >&2; exit 3");
+        let Err(err) = failing.load() else {
+            panic!("expected the command to fail");
+        };
+        assert!(err.to_string().contains("denied"), "{}", err);
+        assert!(MasterKey::from_base64("c2hvcnQ=").is_err());
+    }
+}
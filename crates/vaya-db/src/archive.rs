@@ -0,0 +1,221 @@
+//! WAL archiving for point-in-time recovery
+//!
+//! Before the WAL is truncated after a memtable flush, its contents are
+//! copied to an [`ArchiveSink`] as an immutable *segment*. Replaying the
+//! archived segments on top of a base checkpoint reconstructs the database
+//! as of any sequence number (or wall-clock time) after the checkpoint.
+//!
+//! # Segment naming
+//!
+//! ```text
+//! <first sequence, 16 hex digits>-<last sequence, 16 hex digits>.wal
+//! ```
+//!
+//! Segment contents use the same layout as the live WAL file, including
+//! encryption when the database encrypts data at rest.
+
+use crate::encryption::BlockCipher;
+use crate::error::{DbError, DbResult};
+use crate::wal::{decode_segment, encode_segment, WalRecord};
+use std::fs;
+use std::path::{Path, PathBuf};
+use vaya_crypto::KeyStore;
+
+/// File extension for archived WAL segments
+pub const SEGMENT_EXTENSION: &str = "wal";
+
+/// Metadata about an archived WAL segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentInfo {
+    /// Segment name within the sink
+    pub name: String,
+    /// Smallest sequence number in the segment
+    pub first_sequence: u64,
+    /// Largest sequence number in the segment
+    pub last_sequence: u64,
+}
+
+impl SegmentInfo {
+    /// Build the canonical segment name for a sequence range
+    pub fn name_for(first_sequence: u64, last_sequence: u64) -> String {
+        format!(
+            "{:016x}-{:016x}.{}",
+            first_sequence, last_sequence, SEGMENT_EXTENSION
+        )
+    }
+
+    /// Parse a segment name produced by [`SegmentInfo::name_for`]
+    pub fn parse(name: &str) -> Option<Self> {
+        let stem = name.strip_suffix(SEGMENT_EXTENSION)?.strip_suffix('.')?;
+        let (first, last) = stem.split_once('-')?;
+        if first.len() != 16 || last.len() != 16 {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            first_sequence: u64::from_str_radix(first, 16).ok()?,
+            last_sequence: u64::from_str_radix(last, 16).ok()?,
+        })
+    }
+}
+
+/// Destination for archived WAL segments
+///
+/// The built-in [`DirectoryArchive`] writes to a local (or mounted network)
+/// directory; remote object stores can be plugged in by implementing this
+/// trait and installing it with [`crate::VayaDb::set_archive_sink`].
+pub trait ArchiveSink: Send + Sync {
+    /// Store a segment under the given name
+    fn store(&self, name: &str, data: &[u8]) -> DbResult<()>;
+
+    /// Load a segment by name
+    fn load(&self, name: &str) -> DbResult<Vec<u8>>;
+
+    /// List the names of all stored segments
+    fn list(&self) -> DbResult<Vec<String>>;
+
+    /// List all well-formed segments ordered by first sequence number
+    fn segments(&self) -> DbResult<Vec<SegmentInfo>> {
+        let mut segments: Vec<SegmentInfo> = self
+            .list()?
+            .iter()
+            .filter_map(|name| SegmentInfo::parse(name))
+            .collect();
+        segments.sort_by_key(|s| (s.first_sequence, s.last_sequence));
+        Ok(segments)
+    }
+
+    /// Archive a batch of WAL records as a single segment, sealed with
+    /// `cipher` if given
+    ///
+    /// Returns `None` if there was nothing to archive.
+    fn archive(
+        &self,
+        records: &[WalRecord],
+        cipher: Option<&BlockCipher>,
+    ) -> DbResult<Option<SegmentInfo>> {
+        let Some(first) = records.iter().map(|r| r.sequence).min() else {
+            return Ok(None);
+        };
+        let last = records.iter().map(|r| r.sequence).max().unwrap_or(first);
+        let name = SegmentInfo::name_for(first, last);
+        self.store(&name, &encode_segment(records, cipher)?)?;
+        Ok(Some(SegmentInfo {
+            name,
+            first_sequence: first,
+            last_sequence: last,
+        }))
+    }
+
+    /// Load and decode the records of a segment
+    fn read_segment(
+        &self,
+        segment: &SegmentInfo,
+        keys: Option<&KeyStore>,
+    ) -> DbResult<Vec<WalRecord>> {
+        decode_segment(&self.load(&segment.name)?, keys)
+    }
+}
+
+/// Archive sink backed by a directory on the filesystem
+#[derive(Debug, Clone)]
+pub struct DirectoryArchive {
+    /// Directory holding the segment files
+    dir: PathBuf,
+}
+
+impl DirectoryArchive {
+    /// Create an archive in the given directory, creating it if needed
+    pub fn new(dir: impl Into<PathBuf>) -> DbResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Get the archive directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn segment_path(&self, name: &str) -> DbResult<PathBuf> {
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(DbError::InvalidKey(format!(
+                "Invalid segment name: {}",
+                name
+            )));
+        }
+        Ok(self.dir.join(name))
+    }
+}
+
+impl ArchiveSink for DirectoryArchive {
+    fn store(&self, name: &str, data: &[u8]) -> DbResult<()> {
+        let path = self.segment_path(name)?;
+        // Write to a temporary file and rename so readers never observe a
+        // partially written segment
+        let tmp = self.dir.join(format!(".{}.tmp", name));
+        fs::write(&tmp, data)?;
+        fs::File::open(&tmp)?.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn load(&self, name: &str) -> DbResult<Vec<u8>> {
+        Ok(fs::read(self.segment_path(name)?)?)
+    }
+
+    fn list(&self) -> DbResult<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == SEGMENT_EXTENSION) {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_segment_name_roundtrip() {
+        let name = SegmentInfo::name_for(1, 255);
+        assert_eq!(name, "0000000000000001-00000000000000ff.wal");
+        let info = SegmentInfo::parse(&name).unwrap();
+        assert_eq!(info.first_sequence, 1);
+        assert_eq!(info.last_sequence, 255);
+        assert!(SegmentInfo::parse("garbage.wal").is_none());
+    }
+
+    #[test]
+    fn test_directory_archive() {
+        let tmp = TempDir::new().unwrap();
+        let archive = DirectoryArchive::new(tmp.path().join("archive")).unwrap();
+
+        assert!(archive.archive(&[], None).unwrap().is_none());
+        archive
+            .archive(
+                &[
+                    WalRecord::put(b"k3".to_vec(), b"v".to_vec(), 3),
+                    WalRecord::delete(b"k3".to_vec(), 4),
+                ],
+                None,
+            )
+            .unwrap();
+        archive
+            .archive(&[WalRecord::put(b"k1".to_vec(), b"v".to_vec(), 1)], None)
+            .unwrap();
+
+        let segments = archive.segments().unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].first_sequence, 1);
+        assert_eq!(segments[1].last_sequence, 4);
+        assert_eq!(archive.read_segment(&segments[1], None).unwrap().len(), 2);
+    }
+}
@@ -1,6 +1,8 @@
 //! Database configuration
 
 use std::path::PathBuf;
+use std::sync::Arc;
+use vaya_crypto::KeyStore;
 
 /// Database configuration
 #[derive(Debug, Clone)]
@@ -27,6 +29,15 @@ pub struct DbConfig {
     pub max_value_size: usize,
     /// Bloom filter false positive rate
     pub bloom_fp_rate: f64,
+    /// Directory to archive WAL segments into before they are truncated
+    /// (enables point-in-time recovery; `None` disables archiving)
+    pub wal_archive_dir: Option<PathBuf>,
+    /// Keys for encrypting SSTables and WAL at rest (`None` disables
+    /// encryption; files are sealed with the store's active key)
+    pub encryption: Option<Arc<KeyStore>>,
+    /// Maximum number of SSTables re-encrypted under the active key after
+    /// each flush (0 leaves it all to [`crate::VayaDb::reencrypt`])
+    pub reencrypt_per_flush: usize,
 }
 
 impl Default for DbConfig {
@@ -43,6 +54,9 @@ impl Default for DbConfig {
             wal_sync: false,                  // fsync on commit, not every write
             max_value_size: 10 * 1024 * 1024, // 10 MB
             bloom_fp_rate: 0.01,              // 1% false positive rate
+            wal_archive_dir: None,
+            encryption: None,
+            reencrypt_per_flush: 1,
         }
     }
 }
@@ -80,6 +94,18 @@ impl DbConfig {
         self
     }
 
+    /// Archive WAL segments into the given directory
+    pub fn wal_archive_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.wal_archive_dir = Some(dir.into());
+        self
+    }
+
+    /// Encrypt data at rest with the given key store
+    pub fn encryption(mut self, keys: Arc<KeyStore>) -> Self {
+        self.encryption = Some(keys);
+        self
+    }
+
     /// Get the WAL file path
     pub fn wal_path(&self) -> PathBuf {
         self.path.join("wal")
@@ -0,0 +1,97 @@
+//! Encryption at rest
+//!
+//! SSTable data/index blocks and WAL records can be sealed with AES-256-GCM
+//! (via `vaya-crypto`). The ID of the key used is stored in each file header
+//! so that files written before a key rotation remain readable, and
+//! [`crate::VayaDb::reencrypt`] rewrites old tables under the active key.
+//!
+//! Each sealed block is bound to its position through the AEAD associated
+//! data, so blocks cannot be swapped or replayed between offsets.
+
+use crate::error::{DbError, DbResult};
+use vaya_crypto::{AeadKey, KeyStore, NO_KEY_ID};
+
+/// A key bound to its ID, used to seal and open blocks
+#[derive(Clone)]
+pub struct BlockCipher {
+    key_id: u32,
+    key: AeadKey,
+}
+
+impl BlockCipher {
+    /// Cipher for the key store's active key (`None` if no key is active)
+    pub fn active(keys: &KeyStore) -> Option<Self> {
+        keys.active().map(|(key_id, key)| Self { key_id, key })
+    }
+
+    /// Cipher for a key ID recorded in a file header
+    ///
+    /// Returns `None` for [`NO_KEY_ID`] (plaintext files) and an error if the
+    /// file is encrypted but the key is unavailable.
+    pub fn for_file(keys: Option<&KeyStore>, key_id: u32) -> DbResult<Option<Self>> {
+        if key_id == NO_KEY_ID {
+            return Ok(None);
+        }
+        let key = keys.and_then(|k| k.get(key_id)).ok_or_else(|| {
+            DbError::Encryption(format!("Encryption key {} is not available", key_id))
+        })?;
+        Ok(Some(Self { key_id, key }))
+    }
+
+    /// The ID of this cipher's key
+    pub fn key_id(&self) -> u32 {
+        self.key_id
+    }
+
+    /// Encrypt a block, binding it to `aad`
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> DbResult<Vec<u8>> {
+        self.key
+            .encrypt(plaintext, aad)
+            .map_err(|e| DbError::Encryption(e.to_string()))
+    }
+
+    /// Decrypt and authenticate a block sealed with the same `aad`
+    pub fn open(&self, ciphertext: &[u8], aad: &[u8]) -> DbResult<Vec<u8>> {
+        self.key
+            .decrypt(ciphertext, aad)
+            .map_err(|e| DbError::Encryption(e.to_string()))
+    }
+}
+
+impl std::fmt::Debug for BlockCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockCipher")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Key ID of an optional cipher (`NO_KEY_ID` when unencrypted)
+pub fn key_id_of(cipher: Option<&BlockCipher>) -> u32 {
+    cipher.map_or(NO_KEY_ID, BlockCipher::key_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open_and_missing_key() {
+        let keys = KeyStore::new();
+        assert!(BlockCipher::active(&keys).is_none());
+        keys.rotate().unwrap();
+
+        let cipher = BlockCipher::active(&keys).unwrap();
+        let sealed = cipher.seal(b"block", b"offset:8").unwrap();
+        assert_eq!(cipher.open(&sealed, b"offset:8").unwrap(), b"block");
+        assert!(cipher.open(&sealed, b"offset:16").is_err());
+
+        assert!(BlockCipher::for_file(Some(&keys), NO_KEY_ID)
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            BlockCipher::for_file(None, 1),
+            Err(DbError::Encryption(_))
+        ));
+    }
+}
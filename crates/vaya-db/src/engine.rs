@@ -3,15 +3,19 @@
 //! This module provides the main `VayaDb` struct that coordinates all
 //! database operations across the memtable, WAL, and SSTables.
 
+use crate::archive::{ArchiveSink, DirectoryArchive};
 use crate::config::DbConfig;
+use crate::encryption::{key_id_of, BlockCipher};
 use crate::error::{DbError, DbResult};
-use crate::memtable::MemTable;
-use crate::sstable::{flush_memtable, SsTableMeta, SsTableReader};
-use crate::wal::{Wal, WalRecord};
-use parking_lot::{Mutex, RwLock};
+use crate::memtable::{InternalKey, MemTable, ValueType};
+use crate::recovery::Checkpoint;
+use crate::sstable::{flush_memtable, SsTableBuilder, SsTableMeta, SsTableReader};
+use crate::wal::{RecordType, Wal, WalRecord};
+use parking_lot::{Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -35,6 +39,10 @@ pub struct VayaDb {
     sequence: AtomicU64,
     /// Whether the database is closed
     closed: AtomicBool,
+    /// Where WAL segments are archived before truncation
+    archive: RwLock<Option<Arc<dyn ArchiveSink>>>,
+    /// Held while tables are re-encrypted, so only one pass replaces files
+    rewriting: Mutex<()>,
 }
 
 impl VayaDb {
@@ -48,7 +56,11 @@ impl VayaDb {
 
         // Open or create WAL
         let wal = if config.wal_enabled {
-            Some(Wal::open(config.wal_path(), config.wal_sync)?)
+            Some(Wal::open_with_keys(
+                config.wal_path(),
+                config.wal_sync,
+                config.encryption.clone(),
+            )?)
         } else {
             None
         };
@@ -56,9 +68,20 @@ impl VayaDb {
         // Load existing SSTables
         let (levels, next_sst_id, readers) = Self::load_sstables(&config)?;
 
+        let archive: Option<Arc<dyn ArchiveSink>> = match config.wal_archive_dir {
+            Some(ref dir) => Some(Arc::new(DirectoryArchive::new(dir)?)),
+            None => None,
+        };
+
         // Recover from WAL if needed
         let memtable = Arc::new(RwLock::new(MemTable::new()));
-        let sequence = AtomicU64::new(1);
+        let max_flushed = levels
+            .iter()
+            .flatten()
+            .map(|meta| meta.max_sequence)
+            .max()
+            .unwrap_or(0);
+        let sequence = AtomicU64::new(max_flushed + 1);
 
         if let Some(ref wal) = wal {
             let records = wal.read_all()?;
@@ -66,10 +89,10 @@ impl VayaDb {
             for record in records {
                 sequence.fetch_max(record.sequence + 1, Ordering::SeqCst);
                 match record.record_type {
-                    crate::wal::RecordType::Put => {
+                    RecordType::Put => {
                         mt.put(&record.key, &record.value, record.sequence);
                     }
-                    crate::wal::RecordType::Delete => {
+                    RecordType::Delete => {
                         mt.delete(&record.key, record.sequence);
                     }
                 }
@@ -86,9 +109,17 @@ impl VayaDb {
             next_sst_id: AtomicU64::new(next_sst_id),
             sequence,
             closed: AtomicBool::new(false),
+            archive: RwLock::new(archive),
+            rewriting: Mutex::new(()),
         })
     }
 
+    /// Install a custom WAL archive sink (e.g. a remote object store),
+    /// replacing any directory archive from the configuration
+    pub fn set_archive_sink(&self, sink: Arc<dyn ArchiveSink>) {
+        *self.archive.write() = Some(sink);
+    }
+
     /// Put a key-value pair
     pub fn put(&self, key: &[u8], value: &[u8]) -> DbResult<()> {
         self.check_closed()?;
@@ -159,7 +190,8 @@ impl VayaDb {
                     r
                 } else {
                     let path = self.sstable_path(meta.id);
-                    let r = SsTableReader::open(&path)?;
+                    let r =
+                        SsTableReader::open_with_keys(&path, self.config.encryption.as_deref())?;
                     readers.insert(meta.id, r);
                     readers.get_mut(&meta.id).unwrap()
                 };
@@ -173,6 +205,68 @@ impl VayaDb {
         Ok(None)
     }
 
+    /// Get every live key starting with `prefix`, with its value, in key order
+    ///
+    /// Merges the memtables and SSTables, keeping the newest version of each
+    /// key and leaving out deleted ones.
+    pub fn scan_prefix(&self, prefix: &[u8]) -> DbResult<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.check_closed()?;
+
+        // Newest (sequence, value) per key; `None` marks a tombstone
+        let mut newest: BTreeMap<Vec<u8>, (u64, Option<Vec<u8>>)> = BTreeMap::new();
+        let mut merge = |key: InternalKey, value: Vec<u8>| {
+            if !key.user_key.starts_with(prefix) {
+                return;
+            }
+            let value = (key.value_type == ValueType::Put).then_some(value);
+            match newest.entry(key.user_key) {
+                Entry::Vacant(slot) => {
+                    slot.insert((key.sequence, value));
+                }
+                Entry::Occupied(mut slot) => {
+                    if key.sequence > slot.get().0 {
+                        slot.insert((key.sequence, value));
+                    }
+                }
+            }
+        };
+
+        for (key, value) in self.memtable.read().iter() {
+            merge(key, value);
+        }
+        for mt in self.immutable_memtables.lock().iter() {
+            for (key, value) in mt.iter() {
+                merge(key, value);
+            }
+        }
+
+        let levels = self.levels.read();
+        let mut readers = self.readers.write();
+        for meta in levels.iter().flatten() {
+            // Skip tables whose key range can't hold the prefix
+            if meta.largest_key.as_slice() < prefix
+                || (meta.smallest_key.as_slice() > prefix && !meta.smallest_key.starts_with(prefix))
+            {
+                continue;
+            }
+            let reader = match readers.entry(meta.id) {
+                Entry::Occupied(slot) => slot.into_mut(),
+                Entry::Vacant(slot) => slot.insert(SsTableReader::open_with_keys(
+                    self.sstable_path(meta.id),
+                    self.config.encryption.as_deref(),
+                )?),
+            };
+            for (key, value) in reader.entries()? {
+                merge(key, value);
+            }
+        }
+
+        Ok(newest
+            .into_iter()
+            .filter_map(|(key, (_, value))| value.map(|v| (key, v)))
+            .collect())
+    }
+
     /// Delete a key
     pub fn delete(&self, key: &[u8]) -> DbResult<()> {
         self.check_closed()?;
@@ -226,6 +320,7 @@ impl VayaDb {
             0, // Level 0
             self.config.block_size,
             self.config.compression,
+            self.active_cipher(),
         )?;
 
         // Add to level 0
@@ -243,8 +338,15 @@ impl VayaDb {
             immutables.retain(|mt| !Arc::ptr_eq(mt, &memtable));
         }
 
-        // Truncate WAL
+        // Archive then truncate WAL
         if let Some(ref mut wal) = *self.wal.lock() {
+            if let Some(ref sink) = *self.archive.read() {
+                wal.sync()?;
+                let cipher = self.active_cipher();
+                if let Some(segment) = sink.archive(&wal.read_all()?, cipher.as_ref())? {
+                    tracing::debug!(segment = %segment.name, "Archived WAL segment");
+                }
+            }
             wal.truncate()?;
         }
 
@@ -254,6 +356,86 @@ impl VayaDb {
         Ok(())
     }
 
+    /// Write a consistent base checkpoint into `dest`
+    ///
+    /// The memtable is flushed first so that every acknowledged write is in
+    /// an SSTable, then the SSTables are copied alongside a `CHECKPOINT`
+    /// marker recording the last included sequence number. Archived WAL
+    /// segments after that sequence can be replayed on top with
+    /// [`crate::recovery::restore`].
+    pub fn checkpoint(&self, dest: impl AsRef<Path>) -> DbResult<Checkpoint> {
+        self.check_closed()?;
+        self.flush()?;
+
+        let dest = dest.as_ref();
+        let sst_dest = dest.join("sst");
+        fs::create_dir_all(&sst_dest)?;
+
+        // Hold the level lock so the table set can't change under the copy
+        let levels = self.levels.read();
+        for meta in levels.iter().flatten() {
+            let name = format!("{:016x}.sst", meta.id);
+            fs::copy(self.sstable_path(meta.id), sst_dest.join(name))?;
+        }
+
+        let checkpoint = Checkpoint::now(self.sequence.load(Ordering::SeqCst) - 1);
+        checkpoint.write(dest)?;
+        Ok(checkpoint)
+    }
+
+    /// Take a consistent snapshot of the store's files
+    ///
+    /// Unlike [`checkpoint`](Self::checkpoint) nothing is flushed: the
+    /// snapshot lists the SSTables and the synced WAL as they are. Writes
+    /// and flushes wait until it is dropped, so copy the files promptly.
+    pub fn snapshot(&self) -> DbResult<Snapshot<'_>> {
+        self.check_closed()?;
+        let mut wal = self.wal.lock();
+        let mut sequence = 0;
+        if let Some(ref mut wal) = *wal {
+            wal.sync()?;
+            sequence = wal
+                .read_all()?
+                .iter()
+                .map(|r| r.sequence)
+                .max()
+                .unwrap_or(0);
+        }
+        let levels = self.levels.read();
+        for meta in levels.iter().flatten() {
+            sequence = sequence.max(meta.max_sequence);
+        }
+        Ok(Snapshot {
+            db: self,
+            _wal: wal,
+            levels,
+            sequence,
+        })
+    }
+
+    /// Apply a WAL record with its original sequence number and timestamp
+    ///
+    /// Used when replaying archived segments during point-in-time recovery.
+    pub(crate) fn apply_record(&self, record: &WalRecord) -> DbResult<()> {
+        self.check_closed()?;
+        self.sequence
+            .fetch_max(record.sequence + 1, Ordering::SeqCst);
+
+        if let Some(ref mut wal) = *self.wal.lock() {
+            wal.append(record)?;
+        }
+
+        {
+            let memtable = self.memtable.read();
+            match record.record_type {
+                RecordType::Put => memtable.put(&record.key, &record.value, record.sequence),
+                RecordType::Delete => memtable.delete(&record.key, record.sequence),
+            }
+        }
+
+        self.maybe_flush()
+    }
+
     /// Sync WAL to disk
     pub fn sync(&self) -> DbResult<()> {
         if let Some(ref mut wal) = *self.wal.lock() {
@@ -308,9 +490,139 @@ impl VayaDb {
             tracing::info!("Compaction needed but not yet implemented");
         }
 
+        // Tables written under a retired key are rewritten a few at a time
+        // as part of compaction, so key rotation eventually reaches every
+        // file without stalling a flush on all of them
+        if self.config.reencrypt_per_flush > 0 {
+            let rewritten = self.reencrypt_tables(self.config.reencrypt_per_flush)?;
+            if rewritten > 0 {
+                tracing::info!(
+                    tables = rewritten,
+                    remaining = self.stale_table_count(),
+                    "Re-encrypted SSTables under active key"
+                );
+            }
+        }
+
         Ok(())
     }
 
+    /// Cipher for new files (`None` when encryption is disabled)
+    fn active_cipher(&self) -> Option<BlockCipher> {
+        self.config
+            .encryption
+            .as_deref()
+            .and_then(BlockCipher::active)
+    }
+
+    /// Number of SSTables not sealed with the active key
+    pub fn stale_table_count(&self) -> usize {
+        let _rewriting = self.rewriting.lock();
+        let target = key_id_of(self.active_cipher().as_ref());
+        match self.stale_tables(target, usize::MAX) {
+            Ok(stale) => stale.len(),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to check SSTable keys");
+                0
+            }
+        }
+    }
+
+    /// Rewrite every SSTable that isn't sealed with the active key
+    ///
+    /// This migrates plaintext tables to encrypted ones when encryption is
+    /// first enabled, and tables under a rotated-out key to the new key.
+    /// Returns the number of tables rewritten.
+    pub fn reencrypt(&self) -> DbResult<usize> {
+        self.reencrypt_tables(usize::MAX)
+    }
+
+    /// Rewrite up to `limit` SSTables under the active key
+    ///
+    /// Each table is copied without holding the level or reader locks, so
+    /// reads and flushes carry on; the locks are only taken to swap the
+    /// new table in for the old one.
+    fn reencrypt_tables(&self, limit: usize) -> DbResult<usize> {
+        self.check_closed()?;
+        let _rewriting = self.rewriting.lock();
+        let cipher = self.active_cipher();
+        let target = key_id_of(cipher.as_ref());
+
+        let mut rewritten = 0;
+        for (old_id, level) in self.stale_tables(target, limit)? {
+            let entries = SsTableReader::open_with_keys(
+                self.sstable_path(old_id),
+                self.config.encryption.as_deref(),
+            )?
+            .entries()?;
+            let new_id = self.next_sst_id.fetch_add(1, Ordering::SeqCst);
+            let new_path = self.sstable_path(new_id);
+            let mut builder = SsTableBuilder::with_cipher(
+                &new_path,
+                self.config.block_size,
+                self.config.compression,
+                entries.len(),
+                cipher.clone(),
+            )?;
+            for (key, value) in &entries {
+                builder.add(key, value)?;
+            }
+            let new_meta = builder.finish(new_id, level)?;
+            let new_reader =
+                SsTableReader::open_with_keys(&new_path, self.config.encryption.as_deref())?;
+
+            {
+                let mut levels = self.levels.write();
+                let mut readers = self.readers.write();
+                if let Some(meta) = levels.iter_mut().flatten().find(|m| m.id == old_id) {
+                    *meta = new_meta;
+                }
+                readers.remove(&old_id);
+                readers.insert(new_id, new_reader);
+            }
+            fs::remove_file(self.sstable_path(old_id))?;
+            rewritten += 1;
+        }
+
+        Ok(rewritten)
+    }
+
+    /// ID and level of up to `limit` tables not sealed with `target`, in
+    /// level order; the caller holds `rewriting`
+    fn stale_tables(&self, target: u32, limit: usize) -> DbResult<Vec<(u64, u32)>> {
+        let tables: Vec<(u64, u32)> = self
+            .levels
+            .read()
+            .iter()
+            .flatten()
+            .map(|meta| (meta.id, meta.level))
+            .collect();
+
+        let mut stale = Vec::new();
+        for (id, level) in tables {
+            if stale.len() == limit {
+                break;
+            }
+            let open = self.readers.read().get(&id).map(SsTableReader::key_id);
+            let key_id = match open {
+                Some(key_id) => key_id,
+                None => {
+                    let reader = SsTableReader::open_with_keys(
+                        self.sstable_path(id),
+                        self.config.encryption.as_deref(),
+                    )?;
+                    let key_id = reader.key_id();
+                    self.readers.write().entry(id).or_insert(reader);
+                    key_id
+                }
+            };
+            if key_id != target {
+                stale.push((id, level));
+            }
+        }
+        Ok(stale)
+    }
+
     /// Check if the database is closed
     fn check_closed(&self) -> DbResult<()> {
         if self.closed.load(Ordering::SeqCst) {
@@ -359,7 +671,8 @@ impl VayaDb {
                         max_id = max_id.max(id);
 
                         // Read SSTable and extract metadata
-                        let reader = SsTableReader::open(&path)?;
+                        let reader =
+                            SsTableReader::open_with_keys(&path, config.encryption.as_deref())?;
                         let meta = reader.metadata(id, 0)?; // Put in level 0
 
                         // Store metadata in levels
@@ -408,6 +721,52 @@ impl Drop for VayaDb {
     }
 }
 
+/// A consistent view of a store's files, from [`VayaDb::snapshot`]
+pub struct Snapshot<'a> {
+    db: &'a VayaDb,
+    // Held so no write or flush changes the files while they are copied
+    _wal: MutexGuard<'a, Option<Wal>>,
+    levels: RwLockReadGuard<'a, Vec<Vec<SsTableMeta>>>,
+    sequence: u64,
+}
+
+/// A file in a [`Snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotFile {
+    /// Path relative to the database directory (`sst/<id>.sst` or `wal`)
+    pub name: String,
+    /// Where the file is now
+    pub path: PathBuf,
+}
+
+impl Snapshot<'_> {
+    /// Highest sequence number in the snapshot
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// SSTables, then the WAL if there is one
+    pub fn files(&self) -> Vec<SnapshotFile> {
+        let mut files: Vec<SnapshotFile> = self
+            .levels
+            .iter()
+            .flatten()
+            .map(|meta| SnapshotFile {
+                name: format!("sst/{:016x}.sst", meta.id),
+                path: self.db.sstable_path(meta.id),
+            })
+            .collect();
+        let wal = self.db.config.wal_path();
+        if self.db.config.wal_enabled && wal.exists() {
+            files.push(SnapshotFile {
+                name: "wal".to_string(),
+                path: wal,
+            });
+        }
+        files
+    }
+}
+
 /// Database statistics
 #[derive(Debug, Clone)]
 pub struct DbStats {
@@ -512,6 +871,143 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sequence_survives_reopen_after_flush() {
+        let tmp = TempDir::new().unwrap();
+        let config = test_config(tmp.path());
+
+        {
+            let db = VayaDb::open(config.clone()).unwrap();
+            db.put(b"a", b"1").unwrap();
+            db.put(b"b", b"2").unwrap();
+            db.close().unwrap();
+        }
+
+        let db = VayaDb::open(config).unwrap();
+        assert_eq!(db.stats().sequence, 3);
+    }
+
+    #[test]
+    fn test_flush_archives_wal() {
+        let tmp = TempDir::new().unwrap();
+        let archive_dir = tmp.path().join("archive");
+        let config = test_config(&tmp.path().join("db")).wal_archive_dir(&archive_dir);
+        let db = VayaDb::open(config).unwrap();
+
+        db.put(b"a", b"1").unwrap();
+        db.delete(b"a").unwrap();
+        db.flush().unwrap();
+
+        let archive = DirectoryArchive::new(&archive_dir).unwrap();
+        let segments = archive.segments().unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].first_sequence, 1);
+        assert_eq!(segments[0].last_sequence, 2);
+    }
+
+    #[test]
+    fn test_encryption_at_rest_and_reencrypt() {
+        let tmp = TempDir::new().unwrap();
+        let plain_config = test_config(tmp.path());
+
+        // Start with a plaintext table
+        {
+            let db = VayaDb::open(plain_config.clone()).unwrap();
+            db.put(b"plain", b"old-data").unwrap();
+            db.close().unwrap();
+        }
+
+        let keys = Arc::new(vaya_crypto::KeyStore::new());
+        keys.rotate().unwrap();
+        let config = plain_config.encryption(keys.clone());
+
+        {
+            let db = VayaDb::open(config.clone()).unwrap();
+            assert_eq!(db.stale_table_count(), 1);
+            db.put(b"secret", b"new-data").unwrap();
+            db.flush().unwrap();
+
+            // The flush triggers compaction, which migrates the plaintext table
+            assert_eq!(db.stale_table_count(), 0);
+            assert_eq!(db.get(b"plain").unwrap(), Some(b"old-data".to_vec()));
+
+            // Rotate and migrate explicitly
+            keys.rotate().unwrap();
+            assert_eq!(db.stale_table_count(), 2);
+            assert_eq!(db.reencrypt().unwrap(), 2);
+            db.close().unwrap();
+        }
+
+        for entry in fs::read_dir(config.sstables_path()).unwrap() {
+            let raw = fs::read(entry.unwrap().path()).unwrap();
+            assert!(!raw.windows(8).any(|w| w == b"old-data" || w == b"new-data"));
+        }
+
+        // Old key can be retired once everything is rewritten
+        keys.remove(1).unwrap();
+        let db = VayaDb::open(config).unwrap();
+        assert_eq!(db.get(b"secret").unwrap(), Some(b"new-data".to_vec()));
+        assert_eq!(db.get(b"plain").unwrap(), Some(b"old-data".to_vec()));
+    }
+
+    #[test]
+    fn test_flush_reencrypts_a_bounded_number_of_tables() {
+        let tmp = TempDir::new().unwrap();
+        let plain_config = test_config(tmp.path());
+        {
+            let db = VayaDb::open(plain_config.clone()).unwrap();
+            for key in [b"a", b"b", b"c"] {
+                db.put(key, b"old-data").unwrap();
+                db.flush().unwrap();
+            }
+        }
+
+        let keys = Arc::new(vaya_crypto::KeyStore::new());
+        keys.rotate().unwrap();
+        let mut config = plain_config.encryption(keys);
+        config.reencrypt_per_flush = 2;
+        let db = VayaDb::open(config).unwrap();
+        assert_eq!(db.stale_table_count(), 3);
+
+        db.put(b"d", b"new-data").unwrap();
+        db.flush().unwrap();
+        assert_eq!(db.stale_table_count(), 1);
+        db.put(b"e", b"new-data").unwrap();
+        db.flush().unwrap();
+        assert_eq!(db.stale_table_count(), 0);
+
+        for key in [b"a", b"b", b"c"] {
+            assert_eq!(db.get(key).unwrap(), Some(b"old-data".to_vec()));
+        }
+        assert_eq!(db.stats().levels[0].table_count, 5);
+    }
+
+    #[test]
+    fn test_scan_prefix() {
+        let tmp = TempDir::new().unwrap();
+        let db = VayaDb::open(test_config(tmp.path())).unwrap();
+
+        db.put(b"a/1", b"old").unwrap();
+        db.put(b"a/2", b"gone").unwrap();
+        db.put(b"b/1", b"other").unwrap();
+        db.flush().unwrap();
+
+        // Newer versions and tombstones in the memtable win over the SSTable
+        db.put(b"a/1", b"new").unwrap();
+        db.delete(b"a/2").unwrap();
+        db.put(b"a/3", b"fresh").unwrap();
+
+        assert_eq!(
+            db.scan_prefix(b"a/").unwrap(),
+            vec![
+                (b"a/1".to_vec(), b"new".to_vec()),
+                (b"a/3".to_vec(), b"fresh".to_vec()),
+            ]
+        );
+        assert_eq!(db.scan_prefix(b"b/").unwrap().len(), 1);
+        assert!(db.scan_prefix(b"c/").unwrap().is_empty());
+    }
+
     #[test]
     fn test_stats() {
         let tmp = TempDir::new().unwrap();
@@ -32,6 +32,8 @@ pub enum DbError {
     ValueTooLarge { size: usize, max: usize },
     /// Database version mismatch
     VersionMismatch { expected: u32, found: u32 },
+    /// Encryption or decryption failure (including missing keys)
+    Encryption(String),
 }
 
 impl fmt::Display for DbError {
@@ -56,6 +58,7 @@ impl fmt::Display for DbError {
                     expected, found
                 )
             }
+            DbError::Encryption(msg) => write!(f, "Encryption error: {}", msg),
         }
     }
 }
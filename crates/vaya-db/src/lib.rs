@@ -12,6 +12,16 @@
 //! Reads:  MemTable -> L0 SSTables -> L1 SSTables -> ...
 //! ```
 //!
+//! Flushed WAL segments can optionally be archived (see [`archive`]) and
+//! replayed on top of a checkpoint for point-in-time recovery (see
+//! [`recovery`]).
+//!
+//! Full and incremental backups of a live database, stored by content
+//! hash, are taken and restored with a [`BackupEngine`] (see [`backup`]).
+//!
+//! SSTables and WAL records can be encrypted at rest with AES-256-GCM keys
+//! from a `vaya_crypto::KeyStore` (see [`encryption`]).
+//!
 //! # NO external database dependencies
 //! - NO PostgreSQL
 //! - NO SQLite
@@ -22,16 +32,23 @@
 #![warn(missing_docs)]
 #![forbid(unsafe_op_in_unsafe_fn)]
 
+pub mod archive;
+pub mod backup;
 pub mod config;
+pub mod encryption;
 pub mod engine;
 pub mod error;
 pub mod memtable;
+pub mod recovery;
 pub mod sstable;
 pub mod wal;
 
+pub use archive::{ArchiveSink, DirectoryArchive};
+pub use backup::{BackupEngine, BackupInfo, BackupReport, BackupRestore};
 pub use config::DbConfig;
-pub use engine::VayaDb;
+pub use engine::{Snapshot, VayaDb};
 pub use error::{DbError, DbResult};
+pub use recovery::{Checkpoint, RecoveryTarget, RestoreReport};
 
 /// Database version for compatibility checks
 pub const DB_VERSION: u32 = 1;
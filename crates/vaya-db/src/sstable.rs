@@ -8,6 +8,9 @@
 //! +----------------+
 //! | Version (4B)   |
 //! +----------------+
+//! | Key ID (4B)    |  (encrypted tables only)
+//! | Reserved (4B)  |
+//! +----------------+
 //! | Data Blocks    |
 //! | ...            |
 //! +----------------+
@@ -20,7 +23,12 @@
 //! ```
 //!
 //! Data blocks are compressed with LZ4.
+//!
+//! Encrypted tables use format version [`ENCRYPTED_SSTABLE_VERSION`]; their
+//! data blocks and index block are sealed (after compression) with the key
+//! named in the header, using the block's file offset as associated data.
 
+use crate::encryption::{key_id_of, BlockCipher};
 use crate::error::{DbError, DbResult};
 use crate::memtable::{InternalKey, MemTable, ValueType};
 use crate::{DB_VERSION, MAGIC_BYTES};
@@ -28,6 +36,15 @@ use lz4_flex::{compress_prepend_size, decompress_size_prepended};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use vaya_crypto::KeyStore;
+
+/// Format version of SSTables with encrypted blocks
+pub const ENCRYPTED_SSTABLE_VERSION: u32 = 2;
+
+/// Associated data binding a sealed block to its file offset
+fn block_aad(offset: u64) -> [u8; 8] {
+    offset.to_le_bytes()
+}
 
 /// SSTable file metadata
 #[derive(Debug, Clone)]
@@ -216,6 +233,8 @@ pub struct SsTableBuilder {
     min_sequence: u64,
     /// Max sequence
     max_sequence: u64,
+    /// Cipher sealing the blocks (`None` for plaintext tables)
+    cipher: Option<BlockCipher>,
 }
 
 impl SsTableBuilder {
@@ -225,6 +244,17 @@ impl SsTableBuilder {
         block_size: usize,
         compression: bool,
         expected_entries: usize,
+    ) -> DbResult<Self> {
+        Self::with_cipher(path, block_size, compression, expected_entries, None)
+    }
+
+    /// Create a new SSTable builder whose blocks are sealed with `cipher`
+    pub fn with_cipher(
+        path: impl AsRef<Path>,
+        block_size: usize,
+        compression: bool,
+        expected_entries: usize,
+        cipher: Option<BlockCipher>,
     ) -> DbResult<Self> {
         let path = path.as_ref().to_path_buf();
 
@@ -238,7 +268,18 @@ impl SsTableBuilder {
 
         // Write header
         writer.write_all(&MAGIC_BYTES)?;
-        writer.write_all(&DB_VERSION.to_le_bytes())?;
+        let header_size = match cipher {
+            Some(ref cipher) => {
+                writer.write_all(&ENCRYPTED_SSTABLE_VERSION.to_le_bytes())?;
+                writer.write_all(&cipher.key_id().to_le_bytes())?;
+                writer.write_all(&[0u8; 4])?;
+                16
+            }
+            None => {
+                writer.write_all(&DB_VERSION.to_le_bytes())?;
+                8
+            }
+        };
 
         Ok(Self {
             writer,
@@ -249,12 +290,13 @@ impl SsTableBuilder {
             index: Vec::new(),
             bloom: BloomFilter::new(expected_entries.max(1), 0.01),
             block_first_key: None,
-            offset: 8, // After header
+            offset: header_size,
             smallest_key: None,
             largest_key: None,
             entry_count: 0,
             min_sequence: u64::MAX,
             max_sequence: 0,
+            cipher,
         })
     }
 
@@ -316,6 +358,10 @@ impl SsTableBuilder {
         } else {
             self.current_block.clone()
         };
+        let block_data = match self.cipher {
+            Some(ref cipher) => cipher.seal(&block_data, &block_aad(self.offset))?,
+            None => block_data,
+        };
 
         // Write block
         self.writer.write_all(&block_data)?;
@@ -342,6 +388,10 @@ impl SsTableBuilder {
         let index_offset = self.offset;
         let index_data = self.encode_index();
         let compressed_index = compress_prepend_size(&index_data);
+        let compressed_index = match self.cipher {
+            Some(ref cipher) => cipher.seal(&compressed_index, &block_aad(index_offset))?,
+            None => compressed_index,
+        };
         self.writer.write_all(&compressed_index)?;
         let index_size = compressed_index.len() as u64;
         self.offset += index_size;
@@ -408,11 +458,18 @@ pub struct SsTableReader {
     bloom: BloomFilter,
     /// Footer info
     footer: Footer,
+    /// Cipher for sealed blocks (`None` for plaintext tables)
+    cipher: Option<BlockCipher>,
 }
 
 impl SsTableReader {
     /// Open an existing SSTable
     pub fn open(path: impl AsRef<Path>) -> DbResult<Self> {
+        Self::open_with_keys(path, None)
+    }
+
+    /// Open an existing SSTable, looking up its key in `keys` if encrypted
+    pub fn open_with_keys(path: impl AsRef<Path>, keys: Option<&KeyStore>) -> DbResult<Self> {
         let path = path.as_ref().to_path_buf();
         let file = File::open(&path)?;
         let _file_len = file.metadata()?.len();
@@ -426,12 +483,21 @@ impl SsTableReader {
             return Err(DbError::Corruption("Invalid SSTable magic bytes".into()));
         }
         let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
-        if version != DB_VERSION {
-            return Err(DbError::VersionMismatch {
-                expected: DB_VERSION,
-                found: version,
-            });
-        }
+        let cipher = match version {
+            DB_VERSION => None,
+            ENCRYPTED_SSTABLE_VERSION => {
+                let mut ext = [0u8; 8];
+                reader.read_exact(&mut ext)?;
+                let key_id = u32::from_le_bytes(ext[0..4].try_into().unwrap());
+                BlockCipher::for_file(keys, key_id)?
+            }
+            _ => {
+                return Err(DbError::VersionMismatch {
+                    expected: DB_VERSION,
+                    found: version,
+                })
+            }
+        };
 
         // Read footer
         reader.seek(SeekFrom::End(-(Footer::SIZE as i64)))?;
@@ -443,6 +509,10 @@ impl SsTableReader {
         reader.seek(SeekFrom::Start(footer.index_offset))?;
         let mut index_compressed = vec![0u8; footer.index_size as usize];
         reader.read_exact(&mut index_compressed)?;
+        let index_compressed = match cipher {
+            Some(ref cipher) => cipher.open(&index_compressed, &block_aad(footer.index_offset))?,
+            None => index_compressed,
+        };
         let index_data = decompress_size_prepended(&index_compressed)
             .map_err(|e| DbError::Corruption(format!("Index decompression failed: {}", e)))?;
         let index = Self::decode_index(&index_data)?;
@@ -459,9 +529,25 @@ impl SsTableReader {
             index,
             bloom,
             footer,
+            cipher,
         })
     }
 
+    /// ID of the key sealing this table (`NO_KEY_ID` if plaintext)
+    pub fn key_id(&self) -> u32 {
+        key_id_of(self.cipher.as_ref())
+    }
+
+    /// Open (if sealed) and decompress a raw block read from `offset`
+    fn decode_block(&self, raw: Vec<u8>, offset: u64) -> DbResult<Vec<u8>> {
+        let compressed = match self.cipher {
+            Some(ref cipher) => cipher.open(&raw, &block_aad(offset))?,
+            None => raw,
+        };
+        decompress_size_prepended(&compressed)
+            .map_err(|e| DbError::Corruption(format!("Block decompression failed: {}", e)))
+    }
+
     /// Get a value by key
     pub fn get(&mut self, user_key: &[u8]) -> DbResult<Option<Vec<u8>>> {
         // Check bloom filter first
@@ -489,8 +575,7 @@ impl SsTableReader {
             let mut compressed = vec![0u8; entry.size as usize];
             self.reader.read_exact(&mut compressed)?;
 
-            let block_data = decompress_size_prepended(&compressed)
-                .map_err(|e| DbError::Corruption(format!("Block decompression failed: {}", e)))?;
+            let block_data = self.decode_block(compressed, entry.offset)?;
 
             // Search within block
             if let Some(value) = self.search_block(&block_data, user_key)? {
@@ -643,8 +728,23 @@ impl SsTableReader {
         reader.seek(SeekFrom::Start(entry.offset))?;
         let mut compressed = vec![0u8; entry.size as usize];
         reader.read_exact(&mut compressed)?;
-        decompress_size_prepended(&compressed)
-            .map_err(|e| DbError::Corruption(format!("Block decompression failed: {}", e)))
+        self.decode_block(compressed, entry.offset)
+    }
+
+    /// Read every entry in key order
+    ///
+    /// Used to rewrite a table (e.g. under a new encryption key).
+    pub fn entries(&self) -> DbResult<Vec<(InternalKey, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for entry in &self.index {
+            let block_data = self.read_block(entry)?;
+            for (key, value) in Self::decode_block_entries(&block_data)? {
+                let key = InternalKey::decode(&key)
+                    .ok_or_else(|| DbError::Corruption("Invalid internal key".into()))?;
+                entries.push((key, value));
+            }
+        }
+        Ok(entries)
     }
 
     /// Decode block entries
@@ -686,7 +786,7 @@ impl SsTableReader {
     }
 }
 
-/// Flush a memtable to an SSTable
+/// Flush a memtable to an SSTable, sealing its blocks with `cipher` if given
 pub fn flush_memtable(
     memtable: &MemTable,
     path: impl AsRef<Path>,
@@ -694,9 +794,11 @@ pub fn flush_memtable(
     level: u32,
     block_size: usize,
     compression: bool,
+    cipher: Option<BlockCipher>,
 ) -> DbResult<SsTableMeta> {
     let entry_count = memtable.len();
-    let mut builder = SsTableBuilder::new(path, block_size, compression, entry_count)?;
+    let mut builder =
+        SsTableBuilder::with_cipher(path, block_size, compression, entry_count, cipher)?;
 
     for (key, value) in memtable.iter() {
         builder.add(&key, &value)?;
@@ -752,4 +854,38 @@ mod tests {
         assert_eq!(reader.get(b"ccc").unwrap(), Some(b"val_c".to_vec()));
         assert_eq!(reader.get(b"ddd").unwrap(), None);
     }
+
+    #[test]
+    fn test_encrypted_sstable() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("enc.sst");
+        let keys = KeyStore::new();
+        keys.rotate().unwrap();
+
+        {
+            let cipher = BlockCipher::active(&keys);
+            let mut builder = SsTableBuilder::with_cipher(&path, 4096, true, 2, cipher).unwrap();
+            builder
+                .add(&InternalKey::put(b"card".to_vec(), 1), b"4111111111111111")
+                .unwrap();
+            builder
+                .add(&InternalKey::put(b"name".to_vec(), 2), b"ALICE")
+                .unwrap();
+            builder.finish(1, 0).unwrap();
+        }
+
+        let raw = std::fs::read(&path).unwrap();
+        assert!(!raw.windows(4).any(|w| w == b"card"));
+
+        assert!(matches!(
+            SsTableReader::open(&path),
+            Err(DbError::Encryption(_))
+        ));
+
+        let mut reader = SsTableReader::open_with_keys(&path, Some(&keys)).unwrap();
+        assert_eq!(reader.key_id(), 1);
+        assert_eq!(reader.get(b"name").unwrap(), Some(b"ALICE".to_vec()));
+        assert_eq!(reader.entries().unwrap().len(), 2);
+        assert_eq!(reader.metadata(1, 0).unwrap().max_sequence, 2);
+    }
 }
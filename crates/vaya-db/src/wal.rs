@@ -6,19 +6,109 @@
 //!
 //! # Format
 //!
-//! Each record in the WAL has the format:
+//! The file starts with a header:
 //! ```text
-//! +----------+----------+----------+----------+----------+
-//! | CRC (4B) | Len (4B) | Type (1B)| Key (var)| Val (var)|
-//! +----------+----------+----------+----------+----------+
+//! +------------+--------------+-------------+
+//! | Magic (4B) | Version (4B) | Key ID (4B) |
+//! +------------+--------------+-------------+
 //! ```
+//!
+//! followed by records:
+//! ```text
+//! +----------+----------+----------+----------+----------+----------+----------+
+//! | CRC (4B) | Len (4B) | Type (1B)| Seq (8B) | Ts (8B)  | Key (var)| Val (var)|
+//! +----------+----------+----------+----------+----------+----------+----------+
+//! ```
+//!
+//! The timestamp (milliseconds since the Unix epoch) was added in WAL
+//! version 2 to support point-in-time recovery. Version 1 files are still
+//! readable; their records report a timestamp of zero.
+//!
+//! Version 3 added the key ID to the header. When it is non-zero, everything
+//! after the length field of each record is sealed with that key (see
+//! [`crate::encryption`]) and the CRC covers the ciphertext.
 
+use crate::encryption::{key_id_of, BlockCipher};
 use crate::error::{DbError, DbResult};
 use crate::memtable::ValueType;
 use crate::MAGIC_BYTES;
 use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use vaya_crypto::{KeyStore, NO_KEY_ID};
+
+/// Current WAL format version
+pub const WAL_VERSION: u32 = 3;
+
+/// Associated data binding sealed record bodies to the WAL
+const WAL_AAD: &[u8] = b"vaya-wal";
+
+/// WAL file header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalHeader {
+    /// Format version
+    pub version: u32,
+    /// ID of the key sealing the records (`NO_KEY_ID` if plaintext)
+    pub key_id: u32,
+}
+
+impl WalHeader {
+    /// Header for a new file in the current format
+    pub fn current(key_id: u32) -> Self {
+        Self {
+            version: WAL_VERSION,
+            key_id,
+        }
+    }
+
+    /// Encoded size of this header
+    pub fn size(&self) -> usize {
+        if self.version >= 3 {
+            12
+        } else {
+            8
+        }
+    }
+
+    /// Encode the header
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.size());
+        buf.extend_from_slice(&MAGIC_BYTES);
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        if self.version >= 3 {
+            buf.extend_from_slice(&self.key_id.to_le_bytes());
+        }
+        buf
+    }
+
+    /// Decode a header from the start of a WAL image
+    pub fn decode(data: &[u8]) -> DbResult<Self> {
+        if data.len() < 8 {
+            return Err(DbError::WalCorruption("Header too short".into()));
+        }
+        if data[0..4] != MAGIC_BYTES {
+            return Err(DbError::WalCorruption("Invalid WAL magic bytes".into()));
+        }
+        let version = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        if version == 0 || version > WAL_VERSION {
+            return Err(DbError::VersionMismatch {
+                expected: WAL_VERSION,
+                found: version,
+            });
+        }
+        let key_id = if version >= 3 {
+            if data.len() < 12 {
+                return Err(DbError::WalCorruption("Header too short".into()));
+            }
+            u32::from_le_bytes(data[8..12].try_into().unwrap())
+        } else {
+            NO_KEY_ID
+        };
+        Ok(Self { version, key_id })
+    }
+}
 
 /// WAL record type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -65,6 +155,8 @@ pub struct WalRecord {
     pub value: Vec<u8>,
     /// Sequence number
     pub sequence: u64,
+    /// Wall-clock time the record was written (milliseconds since Unix epoch)
+    pub timestamp_ms: u64,
 }
 
 impl WalRecord {
@@ -75,6 +167,7 @@ impl WalRecord {
             key,
             value,
             sequence,
+            timestamp_ms: now_millis(),
         }
     }
 
@@ -85,22 +178,32 @@ impl WalRecord {
             key,
             value: Vec::new(),
             sequence,
+            timestamp_ms: now_millis(),
         }
     }
 
-    /// Encode the record to bytes
-    pub fn encode(&self) -> Vec<u8> {
-        let key_len = self.key.len() as u32;
-        let val_len = self.value.len() as u32;
-        let total_len = 1 + 8 + 4 + key_len as usize + 4 + val_len as usize;
+    /// Override the record timestamp
+    pub fn with_timestamp(mut self, timestamp_ms: u64) -> Self {
+        self.timestamp_ms = timestamp_ms;
+        self
+    }
 
-        let mut buf = Vec::with_capacity(4 + 4 + total_len);
+    /// Encode the record to bytes (plaintext)
+    pub fn encode(&self) -> Vec<u8> {
+        frame(&self.encode_body())
+    }
 
-        // Placeholder for CRC (will be calculated later)
-        buf.extend_from_slice(&[0u8; 4]);
+    /// Encode the record, sealing the body if a cipher is given
+    pub fn encode_with(&self, cipher: Option<&BlockCipher>) -> DbResult<Vec<u8>> {
+        match cipher {
+            Some(cipher) => Ok(frame(&cipher.seal(&self.encode_body(), WAL_AAD)?)),
+            None => Ok(self.encode()),
+        }
+    }
 
-        // Length (excluding CRC and length fields)
-        buf.extend_from_slice(&(total_len as u32).to_le_bytes());
+    /// Encode everything after the CRC and length fields
+    fn encode_body(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.encoded_size() - 8);
 
         // Record type
         buf.push(self.record_type as u8);
@@ -108,23 +211,32 @@ impl WalRecord {
         // Sequence number
         buf.extend_from_slice(&self.sequence.to_le_bytes());
 
+        // Timestamp
+        buf.extend_from_slice(&self.timestamp_ms.to_le_bytes());
+
         // Key length and key
-        buf.extend_from_slice(&key_len.to_le_bytes());
+        buf.extend_from_slice(&(self.key.len() as u32).to_le_bytes());
         buf.extend_from_slice(&self.key);
 
         // Value length and value
-        buf.extend_from_slice(&val_len.to_le_bytes());
+        buf.extend_from_slice(&(self.value.len() as u32).to_le_bytes());
         buf.extend_from_slice(&self.value);
 
-        // Calculate and set CRC
-        let crc = crc32_hash(&buf[8..]);
-        buf[0..4].copy_from_slice(&crc.to_le_bytes());
-
         buf
     }
 
     /// Decode a record from bytes
     pub fn decode(data: &[u8]) -> DbResult<Self> {
+        Self::decode_version(data, WAL_VERSION)
+    }
+
+    /// Decode a plaintext record written by the given WAL format version
+    pub fn decode_version(data: &[u8], version: u32) -> DbResult<Self> {
+        Self::decode_with(data, version, None)
+    }
+
+    /// Decode a record, opening the body with `cipher` if given
+    pub fn decode_with(data: &[u8], version: u32, cipher: Option<&BlockCipher>) -> DbResult<Self> {
         if data.len() < 8 {
             return Err(DbError::WalCorruption("Record too short".into()));
         }
@@ -142,7 +254,24 @@ impl WalRecord {
             return Err(DbError::WalCorruption("CRC mismatch".into()));
         }
 
-        let mut offset = 8;
+        match cipher {
+            Some(cipher) => Self::decode_body(&cipher.open(&data[8..8 + len], WAL_AAD)?, version),
+            None => Self::decode_body(&data[8..8 + len], version),
+        }
+    }
+
+    /// Decode everything after the CRC and length fields
+    fn decode_body(data: &[u8], version: u32) -> DbResult<Self> {
+        let min_len = if version >= 2 {
+            1 + 8 + 8 + 4 + 4
+        } else {
+            1 + 8 + 4 + 4
+        };
+        if data.len() < min_len {
+            return Err(DbError::WalCorruption("Record body too short".into()));
+        }
+
+        let mut offset = 0;
 
         // Record type
         let record_type = RecordType::try_from(data[offset])?;
@@ -152,15 +281,30 @@ impl WalRecord {
         let sequence = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
         offset += 8;
 
+        // Timestamp (absent in version 1)
+        let timestamp_ms = if version >= 2 {
+            let ts = u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+            offset += 8;
+            ts
+        } else {
+            0
+        };
+
         // Key
         let key_len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
         offset += 4;
+        if offset + key_len + 4 > data.len() {
+            return Err(DbError::WalCorruption("Key length out of bounds".into()));
+        }
         let key = data[offset..offset + key_len].to_vec();
         offset += key_len;
 
         // Value
         let val_len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
         offset += 4;
+        if offset + val_len > data.len() {
+            return Err(DbError::WalCorruption("Value length out of bounds".into()));
+        }
         let value = data[offset..offset + val_len].to_vec();
 
         Ok(Self {
@@ -168,13 +312,74 @@ impl WalRecord {
             key,
             value,
             sequence,
+            timestamp_ms,
         })
     }
 
-    /// Get the encoded size of this record
+    /// Get the encoded size of this record (plaintext)
     pub fn encoded_size(&self) -> usize {
-        4 + 4 + 1 + 8 + 4 + self.key.len() + 4 + self.value.len()
+        4 + 4 + 1 + 8 + 8 + 4 + self.key.len() + 4 + self.value.len()
+    }
+}
+
+/// Prefix a record body with its CRC and length
+fn frame(body: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(8 + body.len());
+    buf.extend_from_slice(&crc32_hash(body).to_le_bytes());
+    buf.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    buf.extend_from_slice(body);
+    buf
+}
+
+/// Decode a complete WAL image (header followed by records)
+///
+/// Used both for the live WAL file and for archived segments, which share
+/// the same on-disk layout. Encrypted images need the key named in their
+/// header to be present in `keys`.
+pub fn decode_segment(data: &[u8], keys: Option<&KeyStore>) -> DbResult<Vec<WalRecord>> {
+    let header = WalHeader::decode(data)?;
+    let cipher = BlockCipher::for_file(keys, header.key_id)?;
+
+    let mut records = Vec::new();
+    let mut offset = header.size();
+    while offset < data.len() {
+        if offset + 8 > data.len() {
+            return Err(DbError::WalCorruption("Truncated record header".into()));
+        }
+        let len = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let end = offset + 8 + len;
+        if end > data.len() {
+            return Err(DbError::WalCorruption("Incomplete record".into()));
+        }
+        records.push(WalRecord::decode_with(
+            &data[offset..end],
+            header.version,
+            cipher.as_ref(),
+        )?);
+        offset = end;
+    }
+
+    Ok(records)
+}
+
+/// Encode records as a standalone WAL image (header followed by records)
+pub fn encode_segment(records: &[WalRecord], cipher: Option<&BlockCipher>) -> DbResult<Vec<u8>> {
+    let header = WalHeader::current(key_id_of(cipher));
+    let size: usize = records.iter().map(WalRecord::encoded_size).sum();
+    let mut buf = Vec::with_capacity(header.size() + size);
+    buf.extend_from_slice(&header.encode());
+    for record in records {
+        buf.extend_from_slice(&record.encode_with(cipher)?);
     }
+    Ok(buf)
+}
+
+/// Current wall-clock time in milliseconds since the Unix epoch
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
 }
 
 /// Write-Ahead Log
@@ -187,11 +392,29 @@ pub struct Wal {
     sync_on_write: bool,
     /// Current file size
     size: u64,
+    /// Header of the file currently on disk
+    header: WalHeader,
+    /// Cipher for the records in the current file
+    cipher: Option<BlockCipher>,
+    /// Key store for encryption at rest (new files use its active key)
+    keys: Option<Arc<KeyStore>>,
 }
 
 impl Wal {
     /// Create a new WAL file or open an existing one
     pub fn open(path: impl AsRef<Path>, sync_on_write: bool) -> DbResult<Self> {
+        Self::open_with_keys(path, sync_on_write, None)
+    }
+
+    /// Open a WAL that encrypts records with the key store's active key
+    ///
+    /// An existing file written under another key (or in plaintext) stays
+    /// readable, and is rewritten under the active key on the next append.
+    pub fn open_with_keys(
+        path: impl AsRef<Path>,
+        sync_on_write: bool,
+        keys: Option<Arc<KeyStore>>,
+    ) -> DbResult<Self> {
         let path = path.as_ref().to_path_buf();
 
         // Create parent directories if they don't exist
@@ -207,26 +430,56 @@ impl Wal {
 
         let size = file.metadata()?.len();
 
-        // If this is a new file, write the header
-        let mut writer = BufWriter::new(file);
-        if size == 0 {
-            // Write magic bytes and version
-            writer.write_all(&MAGIC_BYTES)?;
-            writer.write_all(&1u32.to_le_bytes())?; // Version 1
-            writer.flush()?;
-        }
-
-        Ok(Self {
+        let writer = BufWriter::new(file);
+        let header = if size == 0 {
+            WalHeader::current(NO_KEY_ID)
+        } else {
+            Self::read_header(&path)?
+        };
+
+        // A file holding only a header is restamped below, so its key
+        // doesn't need to be available
+        let empty = size <= header.size() as u64;
+        let cipher = if empty {
+            None
+        } else {
+            BlockCipher::for_file(keys.as_deref(), header.key_id)?
+        };
+
+        let mut wal = Self {
             writer,
             path,
             sync_on_write,
-            size: if size == 0 { 8 } else { size },
-        })
+            size,
+            header,
+            cipher,
+            keys,
+        };
+
+        // New (or empty) files get a header for the active key
+        if empty {
+            wal.truncate()?;
+        }
+
+        Ok(wal)
+    }
+
+    /// Cipher new files should be written with
+    fn target_cipher(keys: &Option<Arc<KeyStore>>) -> Option<BlockCipher> {
+        keys.as_deref().and_then(BlockCipher::active)
+    }
+
+    /// Read the header from an existing WAL file
+    fn read_header(path: &Path) -> DbResult<WalHeader> {
+        let mut header = Vec::with_capacity(12);
+        File::open(path)?.take(12).read_to_end(&mut header)?;
+        WalHeader::decode(&header)
     }
 
     /// Append a record to the WAL
     pub fn append(&mut self, record: &WalRecord) -> DbResult<()> {
-        let data = record.encode();
+        self.rewrite_if_stale()?;
+        let data = record.encode_with(self.cipher.as_ref())?;
         self.writer.write_all(&data)?;
         self.size += data.len() as u64;
 
@@ -239,8 +492,9 @@ impl Wal {
 
     /// Append multiple records atomically
     pub fn append_batch(&mut self, records: &[WalRecord]) -> DbResult<()> {
+        self.rewrite_if_stale()?;
         for record in records {
-            let data = record.encode();
+            let data = record.encode_with(self.cipher.as_ref())?;
             self.writer.write_all(&data)?;
             self.size += data.len() as u64;
         }
@@ -264,13 +518,40 @@ impl Wal {
         self.size
     }
 
+    /// Get the path of the WAL file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the header of the file currently on disk
+    pub fn header(&self) -> WalHeader {
+        self.header
+    }
+
+    /// Rewrite the file in the current format under the active key before
+    /// appending, so a single file never mixes record layouts or keys.
+    fn rewrite_if_stale(&mut self) -> DbResult<()> {
+        let target = Self::target_cipher(&self.keys);
+        if self.header == WalHeader::current(key_id_of(target.as_ref())) {
+            return Ok(());
+        }
+        let records = self.read_all()?;
+        self.truncate()?;
+        for record in &records {
+            let data = record.encode_with(self.cipher.as_ref())?;
+            self.writer.write_all(&data)?;
+            self.size += data.len() as u64;
+        }
+        self.sync()
+    }
+
     /// Read all records from the WAL
     pub fn read_all(&self) -> DbResult<Vec<WalRecord>> {
         let file = File::open(&self.path)?;
         let mut reader = BufReader::new(file);
 
         // Skip header
-        reader.seek(SeekFrom::Start(8))?;
+        reader.seek(SeekFrom::Start(self.header.size() as u64))?;
 
         let mut records = Vec::new();
         let mut buf = [0u8; 8];
@@ -290,7 +571,8 @@ impl Wal {
             record_data[0..8].copy_from_slice(&buf);
             reader.read_exact(&mut record_data[8..])?;
 
-            let record = WalRecord::decode(&record_data)?;
+            let record =
+                WalRecord::decode_with(&record_data, self.header.version, self.cipher.as_ref())?;
             records.push(record);
         }
 
@@ -298,6 +580,9 @@ impl Wal {
     }
 
     /// Truncate the WAL (used after successful flush to SSTable)
+    ///
+    /// The fresh file is written under the key store's current active key,
+    /// which is how key rotation reaches the WAL.
     pub fn truncate(&mut self) -> DbResult<()> {
         // Close the current file
         self.writer.flush()?;
@@ -312,11 +597,12 @@ impl Wal {
         self.writer = BufWriter::new(file);
 
         // Write header
-        self.writer.write_all(&MAGIC_BYTES)?;
-        self.writer.write_all(&1u32.to_le_bytes())?;
+        self.cipher = Self::target_cipher(&self.keys);
+        self.header = WalHeader::current(key_id_of(self.cipher.as_ref()));
+        self.writer.write_all(&self.header.encode())?;
         self.writer.flush()?;
 
-        self.size = 8;
+        self.size = self.header.size() as u64;
 
         Ok(())
     }
@@ -383,6 +669,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_segment_roundtrip_and_legacy_upgrade() {
+        let records = vec![
+            WalRecord::put(b"a".to_vec(), b"1".to_vec(), 7).with_timestamp(1_000),
+            WalRecord::delete(b"a".to_vec(), 8).with_timestamp(2_000),
+        ];
+        let decoded = decode_segment(&encode_segment(&records, None).unwrap(), None).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].timestamp_ms, 2_000);
+
+        // Hand-build a version 1 file (no timestamps) and append to it
+        let tmp = TempDir::new().unwrap();
+        let wal_path = tmp.path().join("legacy.wal");
+        let mut v1 = Vec::new();
+        v1.extend_from_slice(&MAGIC_BYTES);
+        v1.extend_from_slice(&1u32.to_le_bytes());
+        let mut body = vec![RecordType::Put as u8];
+        body.extend_from_slice(&5u64.to_le_bytes());
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(b"k");
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(b"v");
+        v1.extend_from_slice(&crc32_hash(&body).to_le_bytes());
+        v1.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        v1.extend_from_slice(&body);
+        std::fs::write(&wal_path, &v1).unwrap();
+
+        let mut wal = Wal::open(&wal_path, false).unwrap();
+        assert_eq!(wal.read_all().unwrap()[0].timestamp_ms, 0);
+        wal.append(&WalRecord::put(b"k2".to_vec(), b"v2".to_vec(), 6))
+            .unwrap();
+        wal.sync().unwrap();
+
+        let records = decode_segment(&std::fs::read(&wal_path).unwrap(), None).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].sequence, 5);
+        assert!(records[1].timestamp_ms > 0);
+    }
+
+    #[test]
+    fn test_encrypted_wal_and_rotation() {
+        let tmp = TempDir::new().unwrap();
+        let wal_path = tmp.path().join("enc.wal");
+        let keys = Arc::new(KeyStore::new());
+        let first = keys.rotate().unwrap();
+
+        let mut wal = Wal::open_with_keys(&wal_path, false, Some(keys.clone())).unwrap();
+        wal.append(&WalRecord::put(
+            b"secret-key".to_vec(),
+            b"secret-value".to_vec(),
+            1,
+        ))
+        .unwrap();
+        wal.sync().unwrap();
+        assert_eq!(wal.header().key_id, first);
+
+        let raw = std::fs::read(&wal_path).unwrap();
+        assert!(!raw.windows(12).any(|w| w == b"secret-value"));
+        assert!(matches!(
+            decode_segment(&raw, None),
+            Err(DbError::Encryption(_))
+        ));
+
+        // After rotation the next append rewrites the file under the new key
+        let second = keys.rotate().unwrap();
+        wal.append(&WalRecord::delete(b"secret-key".to_vec(), 2))
+            .unwrap();
+        wal.sync().unwrap();
+        assert_eq!(wal.header().key_id, second);
+
+        let records = decode_segment(&std::fs::read(&wal_path).unwrap(), Some(&keys)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].value, b"secret-value");
+    }
+
     #[test]
     fn test_crc32() {
         assert_eq!(crc32_hash(b"hello"), 0x3610a686);
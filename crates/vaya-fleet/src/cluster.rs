@@ -0,0 +1,876 @@
+//! Cluster runtime: bootstrap, join/leave, heartbeats and eviction
+//!
+//! A [`Cluster`] listens on the fleet `bind_addr` and drives a
+//! [`RaftNode`] over the [`transport`](crate::transport) protocol:
+//!
+//! - With no seed nodes it bootstraps a single-node cluster and leads it.
+//! - Otherwise it asks the seeds to join, following redirects to the
+//!   leader, which appends an `Add` membership change and answers with
+//!   the configuration the new node replays the log from.
+//! - The leader heartbeats (AppendEntries) every `heartbeat_ms`; members
+//!   that haven't answered within `eviction_timeout_ms` are removed, one
+//!   change at a time.
+//! - On shutdown a node asks to be removed; a leader removes itself and
+//!   steps down once that commits.
+//!
+//! The Raft log is kept in memory, so a restarted node joins afresh.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::consensus::{MembershipChange, VoteResponse};
+use crate::discovery::DiscoveryClient;
+use crate::jobs::JobScheduler;
+use crate::membership::{now_ms, Membership, MembershipView};
+use crate::transport::{self, Message};
+use crate::{defaults, FleetConfig, FleetError, FleetResult, NodeId, RaftConfig, RaftNode};
+
+/// Redirects followed while looking for the leader
+const MAX_REDIRECTS: usize = 5;
+
+/// Rounds through the seed list before giving up on joining
+const JOIN_ROUNDS: u32 = 10;
+
+/// Heartbeats without an answer before a member is suspect
+const SUSPECT_HEARTBEATS: u64 = 3;
+
+struct State {
+    raft: RaftNode,
+    membership: Membership,
+    /// When this node starts an election if no leader is heard from
+    election_deadline: Instant,
+    /// Last time a current leader was heard from
+    leader_contact: Option<Instant>,
+}
+
+/// This node's membership in the fleet
+pub struct Cluster {
+    config: FleetConfig,
+    address: String,
+    listener: Mutex<Option<TcpListener>>,
+    state: Mutex<State>,
+    /// Wakes the heartbeat loop early, e.g. after a membership change
+    wake: Notify,
+    /// Answers job messages from other nodes
+    jobs: OnceLock<Weak<JobScheduler>>,
+    /// Answers service discovery messages from other nodes
+    discovery: OnceLock<Weak<DiscoveryClient>>,
+}
+
+impl Cluster {
+    /// Bind the cluster listener on `config.bind_addr`
+    ///
+    /// Peers reach this node at `config.advertise_addr`, or at the bound
+    /// address when that is not a wildcard.
+    pub async fn bind(config: FleetConfig) -> FleetResult<Arc<Self>> {
+        let listener = TcpListener::bind(&config.bind_addr).await.map_err(|e| {
+            FleetError::NetworkError(format!("Failed to bind {}: {}", config.bind_addr, e))
+        })?;
+        let local = listener
+            .local_addr()
+            .map_err(|e| FleetError::NetworkError(e.to_string()))?;
+        let address = match &config.advertise_addr {
+            Some(address) => address.clone(),
+            None if local.ip().is_unspecified() => {
+                return Err(FleetError::ConfigError(format!(
+                    "advertise_addr is required when binding {}",
+                    config.bind_addr
+                )))
+            }
+            None => local.to_string(),
+        };
+
+        let raft_config = RaftConfig {
+            election_timeout: (config.election_timeout_ms, config.election_timeout_ms * 2),
+            heartbeat_interval: config.heartbeat_ms,
+            ..RaftConfig::default()
+        };
+        let mut membership = Membership::new(
+            config.heartbeat_ms * SUSPECT_HEARTBEATS,
+            config.eviction_timeout_ms,
+        );
+        membership.learn(config.node_id.clone(), address.clone());
+
+        let state = State {
+            raft: RaftNode::new(config.node_id.clone(), raft_config),
+            membership,
+            election_deadline: Instant::now(),
+            leader_contact: None,
+        };
+        let cluster = Self {
+            config,
+            address,
+            listener: Mutex::new(Some(listener)),
+            state: Mutex::new(state),
+            wake: Notify::new(),
+            jobs: OnceLock::new(),
+            discovery: OnceLock::new(),
+        };
+        cluster.reset_election_deadline(&mut cluster.state.lock().unwrap());
+        Ok(Arc::new(cluster))
+    }
+
+    /// This node's ID
+    pub fn node_id(&self) -> &NodeId {
+        &self.config.node_id
+    }
+
+    /// The address peers reach this node at
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Check if this node leads the cluster
+    pub fn is_leader(&self) -> bool {
+        self.state.lock().unwrap().raft.is_leader()
+    }
+
+    /// This node's view of the membership
+    pub fn membership(&self) -> MembershipView {
+        let state = self.state.lock().unwrap();
+        state.membership.view(&state.raft, now_ms())
+    }
+
+    /// Ask the node at `address` for its view of the membership
+    pub async fn status(
+        address: &str,
+        cluster_name: &str,
+        timeout: Duration,
+    ) -> FleetResult<MembershipView> {
+        match transport::call(address, cluster_name, &Message::Status, timeout).await? {
+            Message::StatusReply(view) => Ok(view),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    /// Join the cluster and take part in it until `shutdown` resolves, then
+    /// leave
+    pub async fn run(self: Arc<Self>, shutdown: impl Future<Output = ()>) -> FleetResult<()> {
+        let listener = self
+            .listener
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| FleetError::ConfigError("Cluster is already running".into()))?;
+        // Serving stops with `run`, so a dropped cluster stops answering
+        let server = Arc::clone(&self).serve(listener);
+        let main = async {
+            self.bootstrap().await?;
+            tokio::pin!(shutdown);
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = self.tick() => {}
+                }
+                let interval = Duration::from_millis(self.config.heartbeat_ms);
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = self.wake.notified() => {}
+                }
+            }
+            self.leave().await
+        };
+
+        tokio::select! {
+            result = main => result,
+            _ = server => Ok(()),
+        }
+    }
+
+    /// Remove `id` from the cluster (leader only)
+    pub fn remove_node(&self, id: &NodeId) -> FleetResult<()> {
+        let mut state = self.state.lock().unwrap();
+        state
+            .raft
+            .propose_membership(MembershipChange::Remove(id.clone()))?;
+        drop(state);
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Start a single-node cluster, or join through the seed nodes
+    async fn bootstrap(&self) -> FleetResult<()> {
+        let seeds: Vec<&String> = self
+            .config
+            .seed_nodes
+            .iter()
+            .filter(|seed| **seed != self.address)
+            .collect();
+        if seeds.is_empty() {
+            let mut state = self.state.lock().unwrap();
+            let request = state.raft.start_election();
+            let id = self.config.node_id.clone();
+            state.raft.handle_vote_response(
+                id,
+                VoteResponse {
+                    term: request.term,
+                    vote_granted: true,
+                },
+            );
+            self.became_leader(&mut state);
+            tracing::info!(
+                "Bootstrapped cluster {} at {}",
+                self.config.cluster_name,
+                self.address
+            );
+            return Ok(());
+        }
+
+        let join = Message::Join {
+            node_id: self.config.node_id.clone(),
+            address: self.address.clone(),
+        };
+        let retry = Duration::from_millis(self.config.election_timeout_ms);
+        for round in 0..JOIN_ROUNDS {
+            if round > 0 {
+                tokio::time::sleep(retry).await;
+            }
+            for seed in &seeds {
+                match self.send_to_leader(seed, &join).await {
+                    Ok(Message::Welcome {
+                        leader,
+                        base,
+                        members,
+                    }) => {
+                        self.welcome(base, members);
+                        tracing::info!(
+                            "Joined cluster {} led by {}",
+                            self.config.cluster_name,
+                            leader.as_str()
+                        );
+                        return Ok(());
+                    }
+                    Ok(Message::Rejected(reason)) => return Err(FleetError::JoinRejected(reason)),
+                    Err(e @ FleetError::JoinRejected(_)) => return Err(e),
+                    Ok(other) => tracing::debug!("Join via {} not accepted: {:?}", seed, other),
+                    Err(e) => tracing::debug!("Join via {} failed: {}", seed, e),
+                }
+            }
+        }
+        Err(FleetError::JoinRejected(format!(
+            "No seed of cluster {} accepted the join",
+            self.config.cluster_name
+        )))
+    }
+
+    /// Adopt the leader's configuration after joining
+    fn welcome(&self, base: Vec<NodeId>, members: Vec<(NodeId, String)>) {
+        let mut state = self.state.lock().unwrap();
+        let own = self.config.node_id.clone();
+        if !base.contains(&own) {
+            state.raft.remove_member(&own);
+        }
+        for id in base {
+            state.raft.add_member(id);
+        }
+        for (id, address) in members {
+            state.membership.learn(id, address);
+        }
+        self.reset_election_deadline(&mut state);
+    }
+
+    /// Send `message` to `address`, following redirects to the leader
+    async fn send_to_leader(&self, address: &str, message: &Message) -> FleetResult<Message> {
+        let mut address = address.to_string();
+        for _ in 0..MAX_REDIRECTS {
+            match self.call(&address, message).await? {
+                Message::Redirect(Some((_, leader))) if leader != address => address = leader,
+                reply => return Ok(reply),
+            }
+        }
+        Err(FleetError::NetworkError("Too many redirects".into()))
+    }
+
+    /// Leave the cluster: ask the leader to remove this node, or, as leader,
+    /// remove ourselves and hand over
+    async fn leave(&self) -> FleetResult<()> {
+        let own = self.config.node_id.clone();
+        let deadline = Instant::now() + Duration::from_millis(self.config.election_timeout_ms * 2);
+        while Instant::now() < deadline {
+            let leader_address = {
+                let mut state = self.state.lock().unwrap();
+                if !state.raft.is_member(&own) && !state.raft.membership_pending() {
+                    return Ok(());
+                }
+                if state.raft.members().count() == 1 {
+                    // Last member; nobody to hand over to
+                    return Ok(());
+                }
+                if state.raft.is_leader() {
+                    if state.raft.is_member(&own) {
+                        let _ = state
+                            .raft
+                            .propose_membership(MembershipChange::Remove(own.clone()));
+                    }
+                    None
+                } else {
+                    let leader = state.raft.leader_id().cloned();
+                    leader.and_then(|id| state.membership.address(&id).map(String::from))
+                }
+            };
+
+            match leader_address {
+                // Keep replicating until the removal commits and we step down
+                None => self.tick().await,
+                Some(address) => {
+                    let leave = Message::Leave {
+                        node_id: own.clone(),
+                    };
+                    if let Ok(Message::Ok) = self.send_to_leader(&address, &leave).await {
+                        tracing::info!("Left cluster {}", self.config.cluster_name);
+                        return Ok(());
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(self.config.heartbeat_ms)).await;
+        }
+        tracing::warn!("Timed out leaving cluster {}", self.config.cluster_name);
+        Ok(())
+    }
+
+    /// One round of leader heartbeats or follower election checks
+    async fn tick(&self) {
+        let is_leader = self.state.lock().unwrap().raft.is_leader();
+        if is_leader {
+            self.heartbeat().await;
+            self.evict_stale();
+        } else {
+            let due = {
+                let state = self.state.lock().unwrap();
+                state.raft.is_member(&self.config.node_id)
+                    && Instant::now() >= state.election_deadline
+            };
+            if due {
+                self.elect().await;
+            }
+        }
+    }
+
+    /// Send AppendEntries to every peer and apply the answers
+    async fn heartbeat(&self) {
+        let requests: Vec<(NodeId, String, Message)> = {
+            let state = self.state.lock().unwrap();
+            state
+                .raft
+                .members()
+                .filter(|id| *id != &self.config.node_id)
+                .filter_map(|id| {
+                    let request = state.raft.append_request(id)?;
+                    let address = state.membership.address(id)?.to_string();
+                    Some((id.clone(), address, Message::Append(request)))
+                })
+                .collect()
+        };
+
+        let mut calls = JoinSet::new();
+        for (id, address, request) in requests {
+            let cluster = self.config.cluster_name.clone();
+            let timeout = self.rpc_timeout();
+            calls.spawn(async move {
+                let reply = transport::call(&address, &cluster, &request, timeout).await;
+                (id, reply)
+            });
+        }
+        while let Some(joined) = calls.join_next().await {
+            let Ok((id, Ok(Message::AppendReply(response)))) = joined else {
+                continue;
+            };
+            let mut state = self.state.lock().unwrap();
+            state.membership.seen(&id, now_ms());
+            state.raft.handle_append_response(&id, response);
+        }
+    }
+
+    /// Propose removing one member that stopped answering
+    fn evict_stale(&self) {
+        let mut state = self.state.lock().unwrap();
+        if !state.raft.is_leader() || state.raft.membership_pending() {
+            return;
+        }
+        let own = &self.config.node_id;
+        let stale = state
+            .membership
+            .stale(state.raft.members().filter(|id| *id != own), now_ms());
+        if let Some(id) = stale.into_iter().next() {
+            tracing::warn!(
+                "Evicting node {} after {}ms without heartbeats",
+                id.as_str(),
+                self.config.eviction_timeout_ms
+            );
+            if let Err(e) = state
+                .raft
+                .propose_membership(MembershipChange::Remove(id.clone()))
+            {
+                tracing::warn!("Failed to evict node {}: {}", id.as_str(), e);
+            }
+        }
+    }
+
+    /// Stand for election and collect votes
+    async fn elect(&self) {
+        let (request, peers) = {
+            let mut state = self.state.lock().unwrap();
+            let request = state.raft.start_election();
+            let own = self.config.node_id.clone();
+            let won = state.raft.handle_vote_response(
+                own,
+                VoteResponse {
+                    term: request.term,
+                    vote_granted: true,
+                },
+            );
+            self.reset_election_deadline(&mut state);
+            if won {
+                // A single-member configuration elects itself
+                self.became_leader(&mut state);
+                return;
+            }
+            let peers: Vec<(NodeId, String)> = state
+                .raft
+                .members()
+                .filter(|id| *id != &self.config.node_id)
+                .filter_map(|id| Some((id.clone(), state.membership.address(id)?.to_string())))
+                .collect();
+            (request, peers)
+        };
+        tracing::debug!(
+            "Node {} standing for election in term {}",
+            self.config.node_id.as_str(),
+            request.term
+        );
+
+        let mut calls = JoinSet::new();
+        for (id, address) in peers {
+            let cluster = self.config.cluster_name.clone();
+            let timeout = self.rpc_timeout();
+            let message = Message::Vote(request.clone());
+            calls.spawn(async move {
+                let reply = transport::call(&address, &cluster, &message, timeout).await;
+                (id, reply)
+            });
+        }
+        while let Some(joined) = calls.join_next().await {
+            let Ok((id, Ok(Message::VoteReply(response)))) = joined else {
+                continue;
+            };
+            let mut state = self.state.lock().unwrap();
+            if state.raft.handle_vote_response(id, response) {
+                self.became_leader(&mut state);
+            }
+        }
+    }
+
+    /// Give every member a full eviction timeout from now
+    fn became_leader(&self, state: &mut State) {
+        state
+            .membership
+            .reset_liveness(state.raft.members(), now_ms());
+        state.leader_contact = None;
+        self.wake.notify_one();
+    }
+
+    /// Accept cluster connections until dropped
+    async fn serve(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let cluster = Arc::clone(&self);
+            tokio::spawn(async move {
+                if let Err(e) = cluster.handle_connection(stream).await {
+                    tracing::debug!("Cluster connection failed: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Route job messages from other nodes to `jobs`
+    pub(crate) fn attach_jobs(&self, jobs: &Arc<JobScheduler>) {
+        if self.jobs.set(Arc::downgrade(jobs)).is_err() {
+            tracing::warn!("A job scheduler is already attached to this cluster");
+        }
+    }
+
+    /// Route service discovery messages from other nodes to `discovery`
+    pub(crate) fn attach_discovery(&self, discovery: &Arc<DiscoveryClient>) {
+        if self.discovery.set(Arc::downgrade(discovery)).is_err() {
+            tracing::warn!("A discovery client is already attached to this cluster");
+        }
+    }
+
+    /// Send `message` to every other member at once and collect the replies
+    pub(crate) async fn call_peers(
+        &self,
+        message: &Message,
+    ) -> Vec<(NodeId, FleetResult<Message>)> {
+        let peers: Vec<(NodeId, String)> = {
+            let state = self.state.lock().unwrap();
+            state
+                .raft
+                .members()
+                .filter(|id| *id != &self.config.node_id)
+                .filter_map(|id| Some((id.clone(), state.membership.address(id)?.to_string())))
+                .collect()
+        };
+
+        let mut calls = JoinSet::new();
+        for (id, address) in peers {
+            let cluster = self.config.cluster_name.clone();
+            let message = message.clone();
+            let timeout = self.rpc_timeout();
+            calls.spawn(async move {
+                let reply = transport::call(&address, &cluster, &message, timeout).await;
+                (id, reply)
+            });
+        }
+        let mut replies = Vec::new();
+        while let Some(joined) = calls.join_next().await {
+            if let Ok(reply) = joined {
+                replies.push(reply);
+            }
+        }
+        replies
+    }
+
+    /// Send `message` to the leader
+    pub(crate) async fn call_leader(&self, message: &Message) -> FleetResult<Message> {
+        let leader = {
+            let state = self.state.lock().unwrap();
+            state
+                .raft
+                .leader_id()
+                .and_then(|id| state.membership.address(id).map(String::from))
+        };
+        match leader {
+            Some(address) => self.call(&address, message).await,
+            None => Err(FleetError::NotLeader { leader_id: None }),
+        }
+    }
+
+    async fn handle_connection(&self, mut stream: TcpStream) -> FleetResult<()> {
+        let (cluster, request) = transport::read_message(&mut stream).await?;
+        let reply = if cluster != self.config.cluster_name {
+            Message::Rejected(format!(
+                "This node belongs to cluster {}",
+                self.config.cluster_name
+            ))
+        } else if matches!(request, Message::Steal { .. } | Message::Report(_)) {
+            self.handle_jobs(request)
+        } else if matches!(request, Message::Services | Message::Withdraw { .. }) {
+            self.handle_discovery(request)
+        } else {
+            self.handle(request)
+        };
+        transport::write_message(&mut stream, &self.config.cluster_name, &reply).await
+    }
+
+    /// Answer one request
+    fn handle(&self, request: Message) -> Message {
+        let mut state = self.state.lock().unwrap();
+        let reply = match request {
+            Message::Join { node_id, address } => self.handle_join(&mut state, node_id, address),
+            Message::Leave { node_id } => self.handle_leave(&mut state, node_id),
+            Message::Vote(request) => {
+                // Ignore candidates while a leader is heard from, so removed
+                // nodes can't disrupt the cluster
+                let min_timeout = Duration::from_millis(self.config.election_timeout_ms);
+                let leader_alive = state.raft.is_leader()
+                    || state
+                        .leader_contact
+                        .is_some_and(|at| at.elapsed() < min_timeout);
+                if leader_alive {
+                    Message::VoteReply(VoteResponse {
+                        term: state.raft.current_term(),
+                        vote_granted: false,
+                    })
+                } else {
+                    let response = state.raft.handle_vote_request(request);
+                    if response.vote_granted {
+                        self.reset_election_deadline(&mut state);
+                    }
+                    Message::VoteReply(response)
+                }
+            }
+            Message::Append(request) => {
+                for entry in &request.entries {
+                    if let Some(MembershipChange::Add(id, address)) = &entry.membership {
+                        state.membership.learn(id.clone(), address.clone());
+                    }
+                }
+                let leader = request.leader_id.clone();
+                let response = state.raft.handle_append(request);
+                if response.term == state.raft.current_term()
+                    && state.raft.leader_id() == Some(&leader)
+                {
+                    state.membership.seen(&leader, now_ms());
+                    state.leader_contact = Some(Instant::now());
+                    self.reset_election_deadline(&mut state);
+                }
+                Message::AppendReply(response)
+            }
+            Message::Status => Message::StatusReply(state.membership.view(&state.raft, now_ms())),
+            other => Message::Rejected(format!("Unexpected request: {:?}", other)),
+        };
+        drop(state);
+        if matches!(reply, Message::Welcome { .. } | Message::Ok) {
+            self.wake.notify_one();
+        }
+        reply
+    }
+
+    /// Answer a job message; these don't touch the Raft state
+    fn handle_jobs(&self, request: Message) -> Message {
+        let Some(jobs) = self.jobs.get().and_then(Weak::upgrade) else {
+            return Message::Rejected("No job scheduler on this node".into());
+        };
+        match request {
+            Message::Steal { node_id, capacity } => {
+                Message::Tasks(jobs.handle_steal(&node_id, capacity))
+            }
+            Message::Report(run) => {
+                jobs.handle_report(run);
+                Message::Ok
+            }
+            other => Message::Rejected(format!("Unexpected request: {:?}", other)),
+        }
+    }
+
+    /// Answer a service discovery message
+    fn handle_discovery(&self, request: Message) -> Message {
+        let Some(discovery) = self.discovery.get().and_then(Weak::upgrade) else {
+            return Message::Rejected("No discovery client on this node".into());
+        };
+        match request {
+            Message::Services => Message::ServiceList(discovery.advertisements()),
+            Message::Withdraw { node_id } => {
+                discovery.handle_withdraw(&node_id);
+                Message::Ok
+            }
+            other => Message::Rejected(format!("Unexpected request: {:?}", other)),
+        }
+    }
+
+    fn handle_join(&self, state: &mut State, node_id: NodeId, address: String) -> Message {
+        if !state.raft.is_leader() {
+            return self.redirect(state);
+        }
+        if !state.raft.is_member(&node_id) {
+            if state.raft.members().count() >= defaults::MAX_NODES {
+                return Message::Rejected(
+                    FleetError::ClusterFull {
+                        max_nodes: defaults::MAX_NODES,
+                    }
+                    .to_string(),
+                );
+            }
+            let change = MembershipChange::Add(node_id.clone(), address.clone());
+            if let Err(e) = state.raft.propose_membership(change) {
+                return Message::Busy(e.to_string());
+            }
+        }
+        tracing::info!("Node {} joined from {}", node_id.as_str(), address);
+        state.membership.learn(node_id.clone(), address);
+        state.membership.seen(&node_id, now_ms());
+
+        let members = state
+            .raft
+            .members()
+            .filter_map(|id| Some((id.clone(), state.membership.address(id)?.to_string())))
+            .collect();
+        Message::Welcome {
+            leader: self.config.node_id.clone(),
+            base: state.raft.base_members().cloned().collect(),
+            members,
+        }
+    }
+
+    fn handle_leave(&self, state: &mut State, node_id: NodeId) -> Message {
+        if !state.raft.is_leader() {
+            return self.redirect(state);
+        }
+        if !state.raft.is_member(&node_id) {
+            return Message::Ok;
+        }
+        match state
+            .raft
+            .propose_membership(MembershipChange::Remove(node_id.clone()))
+        {
+            Ok(_) => {
+                tracing::info!("Node {} left", node_id.as_str());
+                Message::Ok
+            }
+            Err(e) => Message::Busy(e.to_string()),
+        }
+    }
+
+    fn redirect(&self, state: &State) -> Message {
+        let leader = state.raft.leader_id().and_then(|id| {
+            let address = state.membership.address(id)?;
+            Some((id.clone(), address.to_string()))
+        });
+        Message::Redirect(leader)
+    }
+
+    async fn call(&self, address: &str, message: &Message) -> FleetResult<Message> {
+        transport::call(
+            address,
+            &self.config.cluster_name,
+            message,
+            self.rpc_timeout(),
+        )
+        .await
+    }
+
+    /// How long to wait for a peer to answer
+    fn rpc_timeout(&self) -> Duration {
+        Duration::from_millis((self.config.election_timeout_ms / 2).max(50))
+    }
+
+    /// Pick a new randomized election deadline
+    fn reset_election_deadline(&self, state: &mut State) {
+        let base = self.config.election_timeout_ms;
+        let jitter = vaya_crypto::random::random().range(base).unwrap_or(0);
+        state.election_deadline = Instant::now() + Duration::from_millis(base + jitter);
+    }
+}
+
+impl std::fmt::Debug for Cluster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cluster")
+            .field("cluster_name", &self.config.cluster_name)
+            .field("node_id", &self.config.node_id)
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+fn unexpected(message: &Message) -> FleetError {
+    FleetError::NetworkError(format!("Unexpected reply: {:?}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RaftState;
+    use tokio::sync::oneshot;
+    use tokio::task::JoinHandle;
+
+    fn config(id: &str, seeds: &[&str]) -> FleetConfig {
+        FleetConfig {
+            node_id: NodeId::new(id),
+            bind_addr: "127.0.0.1:0".into(),
+            seed_nodes: seeds.iter().map(|s| s.to_string()).collect(),
+            heartbeat_ms: 20,
+            election_timeout_ms: 200,
+            eviction_timeout_ms: 600,
+            ..FleetConfig::default()
+        }
+    }
+
+    struct Running {
+        cluster: Arc<Cluster>,
+        stop: oneshot::Sender<()>,
+        task: JoinHandle<FleetResult<()>>,
+    }
+
+    async fn start(id: &str, seeds: &[&str]) -> Running {
+        let cluster = Cluster::bind(config(id, seeds)).await.unwrap();
+        let (stop, stopped) = oneshot::channel();
+        let task = tokio::spawn(Arc::clone(&cluster).run(async {
+            let _ = stopped.await;
+        }));
+        Running {
+            cluster,
+            stop,
+            task,
+        }
+    }
+
+    /// Poll `cluster` until its view satisfies `check`
+    async fn wait_for(
+        cluster: &Cluster,
+        check: impl Fn(&MembershipView) -> bool,
+    ) -> MembershipView {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let view = cluster.membership();
+            if check(&view) {
+                return view;
+            }
+            assert!(Instant::now() < deadline, "timed out: {:?}", view);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    fn ids(view: &MembershipView) -> Vec<&str> {
+        view.members.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_join_leave_and_evict() {
+        let a = start("node-a", &[]).await;
+        wait_for(&a.cluster, |v| v.state == RaftState::Leader).await;
+
+        let b = start("node-b", &[a.cluster.address()]).await;
+        wait_for(&a.cluster, |v| v.members.len() == 2 && !v.change_pending).await;
+
+        // Joining through a follower is redirected to the leader
+        let c = start("node-c", &[b.cluster.address()]).await;
+        let view = wait_for(&a.cluster, |v| v.members.len() == 3 && !v.change_pending).await;
+        assert_eq!(ids(&view), ["node-a", "node-b", "node-c"]);
+        assert!(view.member(&NodeId::new("node-a")).unwrap().is_leader);
+
+        // Followers learn the configuration and every address
+        let view = wait_for(&c.cluster, |v| v.members.len() == 3).await;
+        assert_eq!(view.leader, Some(NodeId::new("node-a")));
+        let b_info = view.member(&NodeId::new("node-b")).unwrap();
+        assert_eq!(b_info.address, b.cluster.address());
+
+        let remote = Cluster::status(b.cluster.address(), "vaya", Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(remote.node_id, NodeId::new("node-b"));
+        assert_eq!(remote.members.len(), 3);
+
+        // A graceful leave removes the node
+        c.stop.send(()).unwrap();
+        c.task.await.unwrap().unwrap();
+        let view = wait_for(&a.cluster, |v| v.members.len() == 2 && !v.change_pending).await;
+        assert_eq!(ids(&view), ["node-a", "node-b"]);
+
+        // A node that stops answering is evicted
+        b.task.abort();
+        let _ = b.task.await;
+        let view = wait_for(&a.cluster, |v| v.members.len() == 1 && !v.change_pending).await;
+        assert_eq!(ids(&view), ["node-a"]);
+        assert_eq!(view.state, RaftState::Leader);
+
+        a.stop.send(()).unwrap();
+        a.task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wrong_cluster_is_rejected() {
+        let a = start("node-a", &[]).await;
+        let err = Cluster::status(a.cluster.address(), "other", Duration::from_secs(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FleetError::JoinRejected(_)));
+
+        let mut config = config("node-x", &[a.cluster.address()]);
+        config.cluster_name = "other".into();
+        let x = Cluster::bind(config).await.unwrap();
+        let err = x.run(std::future::pending()).await.unwrap_err();
+        assert!(matches!(err, FleetError::JoinRejected(_)));
+
+        a.stop.send(()).unwrap();
+        a.task.await.unwrap().unwrap();
+    }
+}
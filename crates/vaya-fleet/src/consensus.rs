@@ -1,4 +1,11 @@
 //! Raft consensus implementation
+//!
+//! Membership changes go through the log one server at a time (Raft
+//! dissertation, section 4.1): a [`MembershipChange`] entry takes effect on
+//! each node as soon as it is appended, and the leader accepts the next
+//! change only once the previous one has committed. Adding or removing a
+//! single node means any majority of the old configuration overlaps any
+//! majority of the new one, so no joint configuration is needed.
 
 use std::collections::{HashMap, HashSet};
 use time::OffsetDateTime;
@@ -37,8 +44,26 @@ impl Default for RaftConfig {
     }
 }
 
+/// A change to the cluster configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MembershipChange {
+    /// Add a voting node, reachable at the address
+    Add(NodeId, String),
+    /// Remove a node
+    Remove(NodeId),
+}
+
+impl MembershipChange {
+    /// The node being added or removed
+    pub fn node_id(&self) -> &NodeId {
+        match self {
+            MembershipChange::Add(id, _) | MembershipChange::Remove(id) => id,
+        }
+    }
+}
+
 /// Log entry
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LogEntry {
     /// Term when entry was created
     pub term: u64,
@@ -46,10 +71,12 @@ pub struct LogEntry {
     pub index: u64,
     /// Command data
     pub command: Vec<u8>,
+    /// Configuration change carried instead of a command
+    pub membership: Option<MembershipChange>,
 }
 
 /// Vote request
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VoteRequest {
     /// Candidate's term
     pub term: u64,
@@ -62,7 +89,7 @@ pub struct VoteRequest {
 }
 
 /// Vote response
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VoteResponse {
     /// Current term
     pub term: u64,
@@ -71,7 +98,7 @@ pub struct VoteResponse {
 }
 
 /// Append entries request
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AppendRequest {
     /// Leader's term
     pub term: u64,
@@ -88,7 +115,7 @@ pub struct AppendRequest {
 }
 
 /// Append entries response
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AppendResponse {
     /// Current term
     pub term: u64,
@@ -119,7 +146,9 @@ pub struct RaftNode {
     leader_id: Option<NodeId>,
     /// Configuration
     config: RaftConfig,
-    /// Cluster members
+    /// Configuration before any membership entry in the log
+    base_members: HashSet<NodeId>,
+    /// Cluster members: `base_members` with the log's changes applied
     members: HashSet<NodeId>,
     /// Next index for each follower (leader only)
     next_index: HashMap<NodeId, u64>,
@@ -144,6 +173,7 @@ impl RaftNode {
             last_applied: 0,
             leader_id: None,
             config,
+            base_members: HashSet::from([id.clone()]),
             members: HashSet::from([id]),
             next_index: HashMap::new(),
             match_index: HashMap::new(),
@@ -177,16 +207,122 @@ impl RaftNode {
         self.leader_id.as_ref()
     }
 
-    /// Add member to cluster
+    /// Get commit index
+    pub fn commit_index(&self) -> u64 {
+        self.commit_index
+    }
+
+    /// Index of the last log entry
+    pub fn last_log_index(&self) -> u64 {
+        self.last_log_info().0
+    }
+
+    /// Get log entry by index
+    pub fn entry(&self, index: u64) -> Option<&LogEntry> {
+        index.checked_sub(1).and_then(|i| self.log.get(i as usize))
+    }
+
+    /// Current cluster members, including uncommitted changes
+    pub fn members(&self) -> impl Iterator<Item = &NodeId> {
+        self.members.iter()
+    }
+
+    /// The configuration the log's changes apply to
+    pub fn base_members(&self) -> impl Iterator<Item = &NodeId> {
+        self.base_members.iter()
+    }
+
+    /// Check if `id` is in the current configuration
+    pub fn is_member(&self, id: &NodeId) -> bool {
+        self.members.contains(id)
+    }
+
+    /// Add member to the initial configuration, bypassing the log
+    ///
+    /// For bootstrapping; running clusters use [`RaftNode::propose_membership`].
     pub fn add_member(&mut self, id: NodeId) {
-        self.members.insert(id);
+        self.base_members.insert(id);
+        self.rebuild_members();
     }
 
-    /// Remove member from cluster
+    /// Remove member from the initial configuration, bypassing the log
     pub fn remove_member(&mut self, id: &NodeId) {
-        self.members.remove(id);
-        self.next_index.remove(id);
-        self.match_index.remove(id);
+        self.base_members.remove(id);
+        self.rebuild_members();
+    }
+
+    /// Whether a membership change has been appended but not committed
+    pub fn membership_pending(&self) -> bool {
+        self.log
+            .iter()
+            .skip(self.commit_index as usize)
+            .any(|entry| entry.membership.is_some())
+    }
+
+    /// Append a membership change (leader only), returning its index
+    ///
+    /// The change takes effect immediately. Fails if another change has not
+    /// committed yet, or if it would not change the configuration.
+    pub fn propose_membership(&mut self, change: MembershipChange) -> FleetResult<u64> {
+        if !self.is_leader() {
+            return Err(FleetError::NotLeader {
+                leader_id: self.leader_id.as_ref().map(|id| id.as_str().to_string()),
+            });
+        }
+        if self.membership_pending() {
+            return Err(FleetError::MembershipChange(
+                "Previous membership change has not committed yet".into(),
+            ));
+        }
+        match &change {
+            MembershipChange::Add(id, _) if self.members.contains(id) => {
+                return Err(FleetError::MembershipChange(format!(
+                    "{} is already a member",
+                    id.as_str()
+                )));
+            }
+            MembershipChange::Remove(id) if !self.members.contains(id) => {
+                return Err(FleetError::NodeNotFound(id.as_str().to_string()));
+            }
+            MembershipChange::Remove(_) if self.members.len() == 1 => {
+                return Err(FleetError::MembershipChange(
+                    "Can't remove the last member".into(),
+                ));
+            }
+            _ => {}
+        }
+
+        tracing::info!(
+            "Node {} proposing membership change {:?} in term {}",
+            self.id.as_str(),
+            change,
+            self.current_term
+        );
+        self.append_entry(Vec::new(), Some(change))
+    }
+
+    /// Recompute `members` from the base configuration and the log
+    fn rebuild_members(&mut self) {
+        let mut members = self.base_members.clone();
+        for change in self.log.iter().filter_map(|e| e.membership.as_ref()) {
+            match change {
+                MembershipChange::Add(id, _) => members.insert(id.clone()),
+                MembershipChange::Remove(id) => members.remove(id),
+            };
+        }
+
+        self.next_index.retain(|id, _| members.contains(id));
+        self.match_index.retain(|id, _| members.contains(id));
+        if self.is_leader() {
+            let next = self.log.len() as u64 + 1;
+            for member in &members {
+                if member != &self.id && !self.next_index.contains_key(member) {
+                    self.next_index.insert(member.clone(), next);
+                    self.match_index.insert(member.clone(), 0);
+                }
+            }
+        }
+        self.members = members;
     }
 
     /// Start election
@@ -247,8 +383,13 @@ impl RaftNode {
         if resp.vote_granted {
             self.votes_received.insert(from);
 
-            // Check if we have majority
-            if self.votes_received.len() > self.members.len() / 2 {
+            // Check if we have majority of the current configuration
+            let votes = self
+                .votes_received
+                .iter()
+                .filter(|id| self.members.contains(*id))
+                .count();
+            if votes > self.members.len() / 2 {
                 self.become_leader();
                 return true;
             }
@@ -264,6 +405,8 @@ impl RaftNode {
 
         // Initialize leader state
         let last_index = self.log.len() as u64;
+        self.next_index.clear();
+        self.match_index.clear();
         for member in &self.members {
             if member != &self.id {
                 self.next_index.insert(member.clone(), last_index + 1);
@@ -276,6 +419,10 @@ impl RaftNode {
             self.id.as_str(),
             self.current_term
         );
+
+        // A no-op of our own term lets earlier entries, including a pending
+        // membership change, commit
+        let _ = self.append_entry(Vec::new(), None);
     }
 
     /// Become follower
@@ -302,6 +449,8 @@ impl RaftNode {
             };
         }
 
+        // A candidate that hears from this term's leader lost the election
+        self.state = RaftState::Follower;
         self.leader_id = Some(req.leader_id);
 
         // Check log consistency
@@ -317,6 +466,7 @@ impl RaftNode {
             if let Some(entry) = self.log.get(req.prev_log_index as usize - 1) {
                 if entry.term != req.prev_log_term {
                     self.log.truncate(req.prev_log_index as usize - 1);
+                    self.rebuild_members();
                     return AppendResponse {
                         term: self.current_term,
                         success: false,
@@ -327,11 +477,16 @@ impl RaftNode {
         }
 
         // Append new entries
+        let mut membership_changed = false;
         for entry in req.entries {
             if entry.index as usize > self.log.len() {
+                membership_changed |= entry.membership.is_some();
                 self.log.push(entry);
             }
         }
+        if membership_changed {
+            self.rebuild_members();
+        }
 
         // Update commit index
         if req.leader_commit > self.commit_index {
@@ -353,17 +508,119 @@ impl RaftNode {
             });
         }
 
+        self.append_entry(command, None)
+    }
+
+    fn append_entry(
+        &mut self,
+        command: Vec<u8>,
+        membership: Option<MembershipChange>,
+    ) -> FleetResult<u64> {
         let index = self.log.len() as u64 + 1;
-        let entry = LogEntry {
+        let changes_membership = membership.is_some();
+        self.log.push(LogEntry {
             term: self.current_term,
             index,
             command,
-        };
-        self.log.push(entry);
+            membership,
+        });
+        if changes_membership {
+            self.rebuild_members();
+        }
+        self.advance_commit();
 
         Ok(index)
     }
 
+    /// Build the append request for `peer` (leader only)
+    ///
+    /// Carries up to `max_batch_size` entries from the peer's next index;
+    /// with none it is a heartbeat.
+    pub fn append_request(&self, peer: &NodeId) -> Option<AppendRequest> {
+        if !self.is_leader() {
+            return None;
+        }
+        let next = *self.next_index.get(peer)?;
+        let prev_log_index = next.saturating_sub(1);
+        let prev_log_term = self.entry(prev_log_index).map_or(0, |e| e.term);
+        let entries = self
+            .log
+            .iter()
+            .skip(prev_log_index as usize)
+            .take(self.config.max_batch_size)
+            .cloned()
+            .collect();
+
+        Some(AppendRequest {
+            term: self.current_term,
+            leader_id: self.id.clone(),
+            prev_log_index,
+            prev_log_term,
+            entries,
+            leader_commit: self.commit_index,
+        })
+    }
+
+    /// Handle a peer's answer to an append request (leader only)
+    pub fn handle_append_response(&mut self, from: &NodeId, resp: AppendResponse) {
+        if resp.term > self.current_term {
+            self.become_follower(resp.term);
+            return;
+        }
+        if !self.is_leader() || resp.term != self.current_term {
+            return;
+        }
+        let Some(next) = self.next_index.get_mut(from) else {
+            return;
+        };
+
+        if resp.success {
+            *next = resp.match_index + 1;
+            self.match_index.insert(from.clone(), resp.match_index);
+            self.advance_commit();
+        } else {
+            // Back off, skipping straight past the end of a short log
+            *next = (*next - 1).min(resp.match_index + 1).max(1);
+        }
+    }
+
+    /// Commit the highest index of the current term stored on a majority
+    fn advance_commit(&mut self) {
+        if !self.is_leader() {
+            return;
+        }
+        let last_index = self.log.len() as u64;
+        let mut committed = self.commit_index;
+        for index in (self.commit_index + 1)..=last_index {
+            if self.log[index as usize - 1].term != self.current_term {
+                continue;
+            }
+            let replicas = self
+                .members
+                .iter()
+                .filter(|member| {
+                    *member == &self.id
+                        || self.match_index.get(*member).is_some_and(|m| *m >= index)
+                })
+                .count();
+            if replicas > self.members.len() / 2 {
+                committed = index;
+            }
+        }
+        self.commit_index = committed;
+
+        // A leader removed from the configuration steps down once that
+        // removal has committed
+        if !self.members.contains(&self.id) && !self.membership_pending() {
+            tracing::info!(
+                "Node {} stepping down after leaving the cluster",
+                self.id.as_str()
+            );
+            self.state = RaftState::Follower;
+            self.leader_id = None;
+        }
+    }
+
     fn last_log_info(&self) -> (u64, u64) {
         self.log.last().map(|e| (e.index, e.term)).unwrap_or((0, 0))
     }
@@ -426,4 +683,141 @@ mod tests {
         node.handle_vote_response(NodeId::new("node-2"), resp.clone());
         assert!(node.is_leader());
     }
+
+    /// A single-node cluster led by `id`
+    fn leader(id: &str) -> RaftNode {
+        let mut node = RaftNode::new(NodeId::new(id), RaftConfig::default());
+        let req = node.start_election();
+        node.handle_vote_response(
+            NodeId::new(id),
+            VoteResponse {
+                term: req.term,
+                vote_granted: true,
+            },
+        );
+        assert!(node.is_leader());
+        node
+    }
+
+    /// Exchange append requests until `follower` accepts one, as heartbeats
+    /// would
+    fn replicate(leader: &mut RaftNode, follower: &mut RaftNode) {
+        let id = follower.id().clone();
+        for _ in 0..10 {
+            let req = leader.append_request(&id).unwrap();
+            let resp = follower.handle_append(req);
+            let success = resp.success;
+            leader.handle_append_response(&id, resp);
+            if success {
+                return;
+            }
+        }
+        panic!("follower never caught up");
+    }
+
+    #[test]
+    fn test_single_server_changes() {
+        let mut leader = leader("node-1");
+        // Joining nodes start from the leader's base configuration
+        let mut follower = RaftNode::new(NodeId::new("node-2"), RaftConfig::default());
+        follower.remove_member(&NodeId::new("node-2"));
+        follower.add_member(NodeId::new("node-1"));
+        assert!(!follower.is_member(&NodeId::new("node-2")));
+        let node_2 = MembershipChange::Add(NodeId::new("node-2"), "10.0.0.2:7000".into());
+        let node_3 = MembershipChange::Add(NodeId::new("node-3"), "10.0.0.3:7000".into());
+
+        // Takes effect on append, before committing
+        let index = leader.propose_membership(node_2.clone()).unwrap();
+        assert!(leader.is_member(&NodeId::new("node-2")));
+        assert!(leader.membership_pending());
+        assert!(leader.commit_index() < index);
+
+        // One change at a time, and no duplicates
+        assert!(matches!(
+            leader.propose_membership(node_3.clone()),
+            Err(FleetError::MembershipChange(_))
+        ));
+
+        replicate(&mut leader, &mut follower);
+        assert_eq!(leader.commit_index(), index);
+        assert!(!leader.membership_pending());
+        assert!(follower.is_member(&NodeId::new("node-1")));
+        assert_eq!(
+            follower.entry(index).unwrap().membership,
+            Some(node_2.clone())
+        );
+        assert!(matches!(
+            leader.propose_membership(node_2),
+            Err(FleetError::MembershipChange(_))
+        ));
+
+        // With two members, commits need the follower
+        let index = leader.propose_membership(node_3).unwrap();
+        assert_eq!(leader.members().count(), 3);
+        assert!(leader.commit_index() < index);
+        replicate(&mut leader, &mut follower);
+        assert_eq!(leader.commit_index(), index);
+    }
+
+    #[test]
+    fn test_truncated_change_is_reverted() {
+        let mut node = RaftNode::new(NodeId::new("node-2"), RaftConfig::default());
+        node.add_member(NodeId::new("node-1"));
+        let change = LogEntry {
+            term: 1,
+            index: 1,
+            command: Vec::new(),
+            membership: Some(MembershipChange::Add(NodeId::new("node-3"), "n3".into())),
+        };
+        node.handle_append(AppendRequest {
+            term: 1,
+            leader_id: NodeId::new("node-1"),
+            prev_log_index: 0,
+            prev_log_term: 0,
+            entries: vec![change],
+            leader_commit: 0,
+        });
+        assert!(node.is_member(&NodeId::new("node-3")));
+
+        // A new leader without the entry overwrites it
+        node.handle_append(AppendRequest {
+            term: 2,
+            leader_id: NodeId::new("node-1"),
+            prev_log_index: 1,
+            prev_log_term: 2,
+            entries: Vec::new(),
+            leader_commit: 0,
+        });
+        assert!(!node.is_member(&NodeId::new("node-3")));
+        assert_eq!(node.members().count(), 2);
+    }
+
+    #[test]
+    fn test_removed_leader_steps_down() {
+        let mut leader = leader("node-1");
+        let mut follower = RaftNode::new(NodeId::new("node-2"), RaftConfig::default());
+        follower.remove_member(&NodeId::new("node-2"));
+        follower.add_member(NodeId::new("node-1"));
+        leader
+            .propose_membership(MembershipChange::Add(NodeId::new("node-2"), "n2".into()))
+            .unwrap();
+        replicate(&mut leader, &mut follower);
+
+        assert!(matches!(
+            follower.propose_membership(MembershipChange::Remove(NodeId::new("node-1"))),
+            Err(FleetError::NotLeader { .. })
+        ));
+        leader
+            .propose_membership(MembershipChange::Remove(NodeId::new("node-1")))
+            .unwrap();
+        assert!(leader.is_leader());
+
+        // Only node-2 counts now, so its acknowledgement commits the removal
+        replicate(&mut leader, &mut follower);
+        assert!(!leader.is_leader());
+        assert_eq!(
+            follower.members().collect::<Vec<_>>(),
+            [&NodeId::new("node-2")]
+        );
+    }
 }
@@ -0,0 +1,301 @@
+//! Cron expressions for recurring jobs
+//!
+//! The standard five fields, `minute hour day-of-month month day-of-week`,
+//! each a `*`, a value, a range `a-b` or a list of those, optionally with a
+//! `/step`. Months and weekdays may be written as three-letter names and
+//! Sunday is both `0` and `7`. As in Vixie cron, when both day fields are
+//! restricted a day matches if either does. The `@yearly`, `@monthly`,
+//! `@weekly`, `@daily` (`@midnight`) and `@hourly` shorthands are accepted.
+//!
+//! Schedules are evaluated in UTC at minute precision.
+
+use std::fmt;
+use std::str::FromStr;
+
+use time::{Date, Duration, Month, OffsetDateTime, Time};
+
+use crate::{FleetError, FleetResult};
+
+/// How far ahead [`CronSchedule::next_after`] looks before giving up, for
+/// expressions like `0 0 30 2 *` that never match
+const SEARCH_YEARS: i32 = 5;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression
+    pub fn parse(expression: &str) -> FleetResult<Self> {
+        let expression = expression.trim();
+        let expanded = match expression.to_ascii_lowercase().as_str() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            _ => expression,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(expression, "expected 5 fields"));
+        };
+        let mut weekdays =
+            parse_field(weekday, 0, 7, &WEEKDAYS).map_err(|reason| invalid(expression, &reason))?;
+        // Sunday is both 0 and 7
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+
+        let field = |value, min, max, names: &[&str]| {
+            parse_field(value, min, max, names).map_err(|reason| invalid(expression, &reason))
+        };
+        Ok(Self {
+            expression: expression.to_string(),
+            minutes: field(minute, 0, 59, &[])?,
+            hours: field(hour, 0, 23, &[])?,
+            days: field(day, 1, 31, &[])?,
+            months: field(month, 1, 12, &MONTHS)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    /// The expression as written
+    pub fn as_str(&self) -> &str {
+        &self.expression
+    }
+
+    /// The first time strictly after `after` that matches, if any within
+    /// the next few years
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let after = after.to_offset(time::UtcOffset::UTC);
+        let mut t = after.replace_time(Time::from_hms(after.hour(), after.minute(), 0).ok()?)
+            + Duration::minutes(1);
+        let last_year = after.year() + SEARCH_YEARS;
+
+        while t.year() <= last_year {
+            if !has(self.months, u8::from(t.month())) {
+                t = start_of_next_month(t.date())?;
+            } else if !self.day_matches(t.date()) {
+                t = t.date().next_day()?.midnight().assume_utc();
+            } else if !has(self.hours, t.hour()) {
+                t = t.replace_time(Time::from_hms(t.hour(), 0, 0).ok()?) + Duration::hours(1);
+            } else if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    /// Check if `at` (to the minute) matches
+    pub fn matches(&self, at: OffsetDateTime) -> bool {
+        let at = at.to_offset(time::UtcOffset::UTC);
+        has(self.minutes, at.minute())
+            && has(self.hours, at.hour())
+            && has(self.months, u8::from(at.month()))
+            && self.day_matches(at.date())
+    }
+
+    fn day_matches(&self, date: Date) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().number_days_from_sunday());
+        if self.any_day || self.any_weekday {
+            day && weekday
+        } else {
+            day || weekday
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = FleetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+fn has(set: u64, value: u8) -> bool {
+    set & (1 << value) != 0
+}
+
+fn start_of_next_month(date: Date) -> Option<OffsetDateTime> {
+    let (year, month) = match date.month() {
+        Month::December => (date.year() + 1, Month::January),
+        month => (date.year(), month.next()),
+    };
+    Some(
+        Date::from_calendar_date(year, month, 1)
+            .ok()?
+            .midnight()
+            .assume_utc(),
+    )
+}
+
+fn invalid(expression: &str, reason: &str) -> FleetError {
+    FleetError::InvalidSchedule(format!("{:?}: {}", expression, reason))
+}
+
+/// Parse one field into a bit set of the values it allows
+fn parse_field(field: &str, min: u8, max: u8, names: &[&str]) -> Result<u64, String> {
+    let mut set = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u8 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step in {:?}", item))?;
+                (range, step)
+            }
+            None => (item, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, max, names)?,
+                parse_value(end, min, max, names)?,
+            )
+        } else {
+            let start = parse_value(range, min, max, names)?;
+            // `a/step` runs from a to the end of the range
+            (start, if item.contains('/') { max } else { start })
+        };
+        if start > end {
+            return Err(format!("empty range {:?}", item));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u8, max: u8, names: &[&str]) -> Result<u8, String> {
+    let lower = value.to_ascii_lowercase();
+    let parsed = match names.iter().position(|name| *name == lower) {
+        Some(i) => Some(i as u8 + min),
+        None => value.parse::<u8>().ok(),
+    };
+    parsed
+        .filter(|v| (min..=max).contains(v))
+        .ok_or_else(|| format!("{:?} is not in {}-{}", value, min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn next(expression: &str, after: OffsetDateTime) -> OffsetDateTime {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(after)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_after() {
+        let at = datetime!(2026-10-16 13:07:42 UTC);
+        assert_eq!(next("*/5 * * * *", at), datetime!(2026-10-16 13:10 UTC));
+        assert_eq!(next("0 3 * * *", at), datetime!(2026-10-17 03:00 UTC));
+        assert_eq!(next("@daily", at), datetime!(2026-10-17 00:00 UTC));
+        assert_eq!(next("@hourly", at), datetime!(2026-10-16 14:00 UTC));
+        assert_eq!(
+            next("30 9 * * mon-fri", at),
+            datetime!(2026-10-19 09:30 UTC)
+        );
+        assert_eq!(next("0 0 1 jan *", at), datetime!(2027-01-01 00:00 UTC));
+        assert_eq!(
+            next("15,45 2-4/2 * * *", at),
+            datetime!(2026-10-17 02:15 UTC)
+        );
+
+        // Strictly after, even when `at` itself matches
+        let on_the_dot = datetime!(2026-10-16 13:10 UTC);
+        assert_eq!(
+            next("*/5 * * * *", on_the_dot),
+            datetime!(2026-10-16 13:15 UTC)
+        );
+
+        // Leap day
+        assert_eq!(next("0 12 29 2 *", at), datetime!(2028-02-29 12:00 UTC));
+        assert!(CronSchedule::parse("0 0 30 2 *")
+            .unwrap()
+            .next_after(at)
+            .is_none());
+    }
+
+    #[test]
+    fn test_day_fields() {
+        // Both restricted: either matches (the 20th, or the next Sunday)
+        let at = datetime!(2026-10-16 13:00 UTC);
+        assert_eq!(next("0 0 20 * 0", at), datetime!(2026-10-18 00:00 UTC));
+        assert_eq!(next("0 0 20 * 7", at), datetime!(2026-10-18 00:00 UTC));
+        // Only one restricted: it alone decides
+        assert_eq!(next("0 0 20 * *", at), datetime!(2026-10-20 00:00 UTC));
+        assert_eq!(next("0 0 * * sun", at), datetime!(2026-10-18 00:00 UTC));
+
+        let schedule = CronSchedule::parse("0 0 * * 0").unwrap();
+        assert!(schedule.matches(datetime!(2026-10-18 00:00:30 UTC)));
+        assert!(!schedule.matches(datetime!(2026-10-19 00:00 UTC)));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for expression in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * foo *",
+            "@fortnightly",
+        ] {
+            assert!(
+                matches!(
+                    CronSchedule::parse(expression),
+                    Err(FleetError::InvalidSchedule(_))
+                ),
+                "{:?}",
+                expression
+            );
+        }
+        assert_eq!(
+            "*/5 * * * *".parse::<CronSchedule>().unwrap().to_string(),
+            "*/5 * * * *"
+        );
+    }
+}
@@ -0,0 +1,490 @@
+//! Discovery client: finding healthy instances of services across the fleet
+//!
+//! A [`DiscoveryClient`] keeps a [`ServiceRegistry`](crate::ServiceRegistry)
+//! of the services this node offers and, given a [`Cluster`], of those the
+//! other members offer. Every `refresh_interval_ms` it asks each member
+//! which services it runs. An answer counts as a passed health check of
+//! that member's endpoints and no answer as a failed one; endpoints of
+//! nodes that left the cluster are dropped. Callers feed the same health
+//! tracking with [`DiscoveryClient::report`], and
+//! [`DiscoveryClient::resolve`] spreads calls over the endpoints by weight,
+//! favouring healthy ones.
+//!
+//! When [`DiscoveryClient::run`] shuts down it withdraws this node's
+//! services and tells the other members, so they stop routing to the node
+//! before it stops serving.
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use crate::transport::Message;
+use crate::{
+    Cluster, DiscoveryConfig, FleetResult, NodeId, RegistryEvent, Service, ServiceDiscovery,
+    ServiceEndpoint,
+};
+
+/// One endpoint a node offers, as sent to other members
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Advertisement {
+    pub(crate) service: String,
+    pub(crate) version: String,
+    pub(crate) tags: Vec<String>,
+    pub(crate) address: String,
+    pub(crate) port: u16,
+    pub(crate) weight: u32,
+    /// Sorted by key
+    pub(crate) metadata: Vec<(String, String)>,
+}
+
+impl Advertisement {
+    fn new(service: &Service, endpoint: &ServiceEndpoint) -> Self {
+        let mut metadata: Vec<(String, String)> = endpoint
+            .metadata
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        metadata.sort();
+        Self {
+            service: service.name.clone(),
+            version: service.version.clone(),
+            tags: service.tags.clone(),
+            address: endpoint.address.clone(),
+            port: endpoint.port,
+            weight: endpoint.weight,
+            metadata,
+        }
+    }
+
+    /// Check if `endpoint` is what this advertises
+    fn describes(&self, endpoint: &ServiceEndpoint) -> bool {
+        self.address == endpoint.address
+            && self.port == endpoint.port
+            && self.weight == endpoint.weight
+            && self.metadata.len() == endpoint.metadata.len()
+            && self
+                .metadata
+                .iter()
+                .all(|(k, v)| endpoint.metadata.get(k) == Some(v))
+    }
+
+    fn endpoint(&self, node_id: &NodeId) -> ServiceEndpoint {
+        let mut endpoint = ServiceEndpoint::new(node_id.clone(), self.address.clone(), self.port)
+            .with_weight(self.weight);
+        endpoint.metadata = self.metadata.iter().cloned().collect();
+        endpoint
+    }
+}
+
+/// Finds, load-balances and health-tracks service endpoints
+pub struct DiscoveryClient {
+    discovery: Mutex<ServiceDiscovery>,
+    cluster: Option<Arc<Cluster>>,
+}
+
+impl DiscoveryClient {
+    /// Create a client that knows only this node's services
+    pub fn new(local_node: NodeId, config: DiscoveryConfig) -> Self {
+        Self {
+            discovery: Mutex::new(ServiceDiscovery::new(local_node, config)),
+            cluster: None,
+        }
+    }
+
+    /// Share services with the other members of `cluster`
+    ///
+    /// Local services are registered under the cluster's node ID, at the
+    /// host of its advertised address.
+    pub fn with_cluster(mut self, cluster: Arc<Cluster>) -> Self {
+        let config = self.discovery.get_mut().unwrap().config().clone();
+        let address = cluster.address();
+        let host = address.rsplit_once(':').map_or(address, |(host, _)| host);
+        let discovery =
+            ServiceDiscovery::new(cluster.node_id().clone(), config).with_local_address(host);
+        self.discovery = Mutex::new(discovery);
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// This node's ID
+    pub fn node_id(&self) -> NodeId {
+        self.discovery().local_node().clone()
+    }
+
+    /// Offer `service` from this node on `port`
+    pub fn register_local(&self, service: &str, port: u16) -> FleetResult<()> {
+        self.discovery().register_local(service, port)
+    }
+
+    /// Stop offering `service` from this node
+    ///
+    /// Other members notice at their next refresh; [`withdraw`](Self::withdraw)
+    /// tells them right away.
+    pub fn deregister_local(&self, service: &str) -> FleetResult<()> {
+        self.discovery().deregister_local(service)
+    }
+
+    /// Pick an endpoint of `service`
+    pub fn resolve(&self, service: &str) -> FleetResult<ServiceEndpoint> {
+        self.discovery().discover(service).cloned()
+    }
+
+    /// Every usable endpoint of `service`
+    pub fn resolve_all(&self, service: &str) -> FleetResult<Vec<ServiceEndpoint>> {
+        let discovery = self.discovery();
+        let endpoints = discovery.discover_all(service)?;
+        Ok(endpoints.into_iter().cloned().collect())
+    }
+
+    /// Record whether a call to `node_id`'s endpoint of `service` succeeded
+    ///
+    /// Endpoints that keep failing are taken out of rotation until a
+    /// refresh finds their node answering again.
+    pub fn report(&self, service: &str, node_id: &NodeId, ok: bool) {
+        // The endpoint may have gone since it was resolved
+        let _ = self
+            .discovery()
+            .registry_mut()
+            .record_check(service, node_id, ok);
+    }
+
+    /// Subscribe to changes in the services known
+    pub fn subscribe(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.discovery().registry().subscribe()
+    }
+
+    /// Every service known, sorted by name
+    pub fn services(&self) -> Vec<Service> {
+        let discovery = self.discovery();
+        let mut services: Vec<Service> = discovery.registry().list().into_iter().cloned().collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        services
+    }
+
+    /// Keep in step with the cluster until `shutdown` resolves, then
+    /// withdraw this node's services
+    pub async fn run(self: Arc<Self>, shutdown: impl Future<Output = ()>) {
+        if let Some(cluster) = &self.cluster {
+            cluster.attach_discovery(&self);
+        }
+        tokio::pin!(shutdown);
+        let interval = Duration::from_millis(self.discovery().config().refresh_interval_ms);
+        loop {
+            self.refresh().await;
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+        self.withdraw().await;
+    }
+
+    /// Ask every other member for its services
+    pub async fn refresh(&self) {
+        let Some(cluster) = &self.cluster else {
+            return;
+        };
+        let members: HashSet<NodeId> = cluster
+            .membership()
+            .members
+            .into_iter()
+            .map(|member| member.id)
+            .collect();
+        for gone in self.remote_nodes().difference(&members) {
+            self.forget(gone);
+        }
+
+        for (node_id, reply) in cluster.call_peers(&Message::Services).await {
+            match reply {
+                Ok(Message::ServiceList(advertised)) => self.sync(&node_id, advertised),
+                // Not running discovery, so offering nothing
+                Ok(Message::Rejected(_)) => self.sync(&node_id, Vec::new()),
+                Ok(other) => {
+                    tracing::debug!("Unexpected services reply: {:?}", other);
+                    self.checks_failed(&node_id);
+                }
+                Err(e) => {
+                    tracing::debug!("No services from {}: {}", node_id.as_str(), e);
+                    self.checks_failed(&node_id);
+                }
+            }
+        }
+    }
+
+    /// Stop offering every local service and tell the other members
+    pub async fn withdraw(&self) {
+        let withdrawn: Vec<String> = {
+            let mut discovery = self.discovery();
+            let local = discovery.local_node().clone();
+            let names: Vec<String> = discovery
+                .registry()
+                .list()
+                .into_iter()
+                .filter(|service| service.endpoints.iter().any(|e| e.node_id == local))
+                .map(|service| service.name.clone())
+                .collect();
+            for name in &names {
+                let _ = discovery.deregister_local(name);
+            }
+            names
+        };
+        if withdrawn.is_empty() {
+            return;
+        }
+        tracing::info!("Withdrew services: {}", withdrawn.join(", "));
+
+        if let Some(cluster) = &self.cluster {
+            let message = Message::Withdraw {
+                node_id: self.node_id(),
+            };
+            for (node_id, reply) in cluster.call_peers(&message).await {
+                if let Err(e) = reply {
+                    tracing::debug!("Failed to tell {} of withdrawal: {}", node_id.as_str(), e);
+                }
+            }
+        }
+    }
+
+    /// This node's endpoints, for another member
+    pub(crate) fn advertisements(&self) -> Vec<Advertisement> {
+        let discovery = self.discovery();
+        let local = discovery.local_node();
+        discovery
+            .registry()
+            .list()
+            .into_iter()
+            .flat_map(|service| {
+                service
+                    .endpoints
+                    .iter()
+                    .filter(|e| &e.node_id == local)
+                    .map(|e| Advertisement::new(service, e))
+            })
+            .collect()
+    }
+
+    /// `node_id` stopped offering its services
+    pub(crate) fn handle_withdraw(&self, node_id: &NodeId) {
+        if *node_id != self.node_id() {
+            self.forget(node_id);
+        }
+    }
+
+    /// Replace `node_id`'s endpoints with what it advertised
+    fn sync(&self, node_id: &NodeId, advertised: Vec<Advertisement>) {
+        let mut discovery = self.discovery();
+        let registry = discovery.registry_mut();
+
+        let offered: HashSet<&str> = advertised.iter().map(|ad| ad.service.as_str()).collect();
+        let dropped: Vec<String> = registry
+            .list()
+            .into_iter()
+            .filter(|service| !offered.contains(service.name.as_str()))
+            .filter(|service| service.endpoints.iter().any(|e| &e.node_id == node_id))
+            .map(|service| service.name.clone())
+            .collect();
+        for name in dropped {
+            let _ = registry.remove_endpoint(&name, node_id);
+        }
+
+        for ad in &advertised {
+            let current = match registry.get(&ad.service) {
+                Some(service) => service
+                    .endpoints
+                    .iter()
+                    .find(|e| &e.node_id == node_id)
+                    .map(|e| ad.describes(e)),
+                None => {
+                    let mut service = Service::new(ad.service.clone(), ad.version.clone());
+                    service.tags = ad.tags.clone();
+                    registry.register(service);
+                    None
+                }
+            };
+            if current == Some(false) {
+                let _ = registry.remove_endpoint(&ad.service, node_id);
+            }
+            if current != Some(true) {
+                let _ = registry.add_endpoint(&ad.service, ad.endpoint(node_id));
+            }
+            let _ = registry.record_check(&ad.service, node_id, true);
+        }
+    }
+
+    /// Count a failed check against each of `node_id`'s endpoints
+    fn checks_failed(&self, node_id: &NodeId) {
+        let mut discovery = self.discovery();
+        let registry = discovery.registry_mut();
+        for name in services_on(registry.list(), node_id) {
+            let _ = registry.record_check(&name, node_id, false);
+        }
+    }
+
+    /// Drop `node_id`'s endpoints, and services left without any
+    fn forget(&self, node_id: &NodeId) {
+        let mut discovery = self.discovery();
+        let registry = discovery.registry_mut();
+        for name in services_on(registry.list(), node_id) {
+            let _ = registry.remove_endpoint(&name, node_id);
+            if registry.get(&name).is_some_and(|s| s.endpoints.is_empty()) {
+                registry.deregister(&name);
+            }
+        }
+    }
+
+    /// Nodes other than this one with endpoints in the registry
+    fn remote_nodes(&self) -> HashSet<NodeId> {
+        let discovery = self.discovery();
+        let local = discovery.local_node();
+        discovery
+            .registry()
+            .list()
+            .into_iter()
+            .flat_map(|service| service.endpoints.iter())
+            .filter(|e| &e.node_id != local)
+            .map(|e| e.node_id.clone())
+            .collect()
+    }
+
+    fn discovery(&self) -> MutexGuard<'_, ServiceDiscovery> {
+        self.discovery.lock().unwrap()
+    }
+}
+
+impl std::fmt::Debug for DiscoveryClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let services: HashMap<String, usize> = self
+            .services()
+            .into_iter()
+            .map(|service| (service.name, service.endpoints.len()))
+            .collect();
+        f.debug_struct("DiscoveryClient")
+            .field("node_id", &self.node_id())
+            .field("services", &services)
+            .finish()
+    }
+}
+
+/// Names of the services `node_id` has an endpoint in
+fn services_on(services: Vec<&Service>, node_id: &NodeId) -> Vec<String> {
+    services
+        .into_iter()
+        .filter(|service| service.endpoints.iter().any(|e| &e.node_id == node_id))
+        .map(|service| service.name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FleetConfig, FleetError, ServiceHealth};
+    use tokio::time::Instant;
+
+    fn fleet(id: &str, seeds: Vec<String>) -> FleetConfig {
+        FleetConfig {
+            node_id: NodeId::new(id),
+            bind_addr: "127.0.0.1:0".into(),
+            seed_nodes: seeds,
+            heartbeat_ms: 20,
+            election_timeout_ms: 200,
+            ..FleetConfig::default()
+        }
+    }
+
+    /// A client that refreshes only when told to
+    async fn client(cluster: &Arc<Cluster>) -> Arc<DiscoveryClient> {
+        let config = DiscoveryConfig {
+            refresh_interval_ms: 60_000,
+            ..DiscoveryConfig::default()
+        };
+        let client =
+            DiscoveryClient::new(NodeId::new("unused"), config).with_cluster(Arc::clone(cluster));
+        let client = Arc::new(client);
+        tokio::spawn(Arc::clone(&client).run(std::future::pending()));
+        // Let it attach to the cluster
+        tokio::task::yield_now().await;
+        client
+    }
+
+    fn health(client: &DiscoveryClient, service: &str) -> ServiceHealth {
+        client
+            .services()
+            .iter()
+            .find(|s| s.name == service)
+            .unwrap()
+            .endpoints[0]
+            .health
+    }
+
+    #[tokio::test]
+    async fn test_discovery_across_nodes() {
+        let a = Cluster::bind(fleet("node-a", Vec::new())).await.unwrap();
+        tokio::spawn(Arc::clone(&a).run(std::future::pending()));
+        let b = Cluster::bind(fleet("node-b", vec![a.address().to_string()]))
+            .await
+            .unwrap();
+        tokio::spawn(Arc::clone(&b).run(std::future::pending()));
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while a.membership().members.len() < 2 || b.membership().members.len() < 2 {
+            assert!(Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let on_a = client(&a).await;
+        let on_b = client(&b).await;
+        let mut events = on_a.subscribe();
+        on_b.register_local("oracle-trainer", 9100).unwrap();
+
+        // A refresh finds the trainer on node-b, healthy since it answered
+        on_a.refresh().await;
+        let endpoint = on_a.resolve("oracle-trainer").unwrap();
+        assert_eq!(endpoint.node_id, NodeId::new("node-b"));
+        assert_eq!(endpoint.full_address(), "127.0.0.1:9100");
+        assert_eq!(endpoint.health, ServiceHealth::Healthy);
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::Registered {
+                service: "oracle-trainer".into()
+            }
+        );
+        assert_eq!(
+            events.recv().await.unwrap(),
+            RegistryEvent::EndpointAdded {
+                service: "oracle-trainer".into(),
+                node_id: NodeId::new("node-b"),
+            }
+        );
+
+        // Failed calls take it out of rotation, until refreshes find it
+        // answering again
+        let node_b = NodeId::new("node-b");
+        on_a.report("oracle-trainer", &node_b, false);
+        assert_eq!(health(&on_a, "oracle-trainer"), ServiceHealth::Degraded);
+        assert!(on_a.resolve("oracle-trainer").is_ok());
+        on_a.report("oracle-trainer", &node_b, false);
+        on_a.report("oracle-trainer", &node_b, false);
+        assert!(matches!(
+            on_a.resolve("oracle-trainer"),
+            Err(FleetError::ServiceNotFound(_))
+        ));
+        on_a.refresh().await;
+        on_a.refresh().await;
+        assert_eq!(health(&on_a, "oracle-trainer"), ServiceHealth::Healthy);
+
+        // Withdrawing tells the other members right away
+        on_b.withdraw().await;
+        assert!(on_b.resolve("oracle-trainer").is_err());
+        assert!(on_a.services().is_empty());
+        let mut removed = false;
+        while let Ok(event) = events.try_recv() {
+            removed |= event
+                == RegistryEvent::Deregistered {
+                    service: "oracle-trainer".into(),
+                };
+        }
+        assert!(removed);
+    }
+}
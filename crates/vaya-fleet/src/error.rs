@@ -30,6 +30,16 @@ pub enum FleetError {
     NetworkError(String),
     /// Cluster full
     ClusterFull { max_nodes: usize },
+    /// Membership change refused
+    MembershipChange(String),
+    /// Join refused by the cluster
+    JoinRejected(String),
+    /// Invalid cron expression
+    InvalidSchedule(String),
+    /// Job not registered
+    JobNotFound(String),
+    /// Failed to persist or load job runs
+    StorageError(String),
 }
 
 impl fmt::Display for FleetError {
@@ -53,6 +63,11 @@ impl fmt::Display for FleetError {
             FleetError::ClusterFull { max_nodes } => {
                 write!(f, "Cluster full, max nodes: {}", max_nodes)
             }
+            FleetError::MembershipChange(msg) => write!(f, "Membership change refused: {}", msg),
+            FleetError::JoinRejected(msg) => write!(f, "Join rejected: {}", msg),
+            FleetError::InvalidSchedule(msg) => write!(f, "Invalid schedule: {}", msg),
+            FleetError::JobNotFound(name) => write!(f, "Job not found: {}", name),
+            FleetError::StorageError(msg) => write!(f, "Storage error: {}", msg),
         }
     }
 }
@@ -0,0 +1,1179 @@
+//! Recurring and on-demand jobs, run across the fleet
+//!
+//! Services register named [`Job`]s and their handlers with a
+//! [`JobScheduler`] on every node: `expire_pools` every five minutes
+//! (`*/5 * * * *`), say, or `retrain_oracle` nightly (`0 3 * * *`). Only the
+//! cluster leader schedules. It fires cron jobs when they are due and queues
+//! them, along with triggered ones, in a [`Scheduler`]. Execution is spread
+//! by work stealing: every node with a free slot pulls queued tasks from the
+//! leader (the leader from itself), runs them under the job's timeout and
+//! reports the result back.
+//!
+//! The leader retries failed and timed out runs per the job's
+//! [`RetryPolicy`], possibly on another node, and records every attempt in
+//! a [`RunStore`]. A task whose node stops answering times out on the
+//! leader after its timeout plus a grace period.
+//!
+//! A new leader starts every cron schedule afresh from when it took over,
+//! so a run due during a failover may be skipped; tasks still queued on the
+//! old leader are lost with it.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::Duration;
+
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+
+use crate::cron::CronSchedule;
+use crate::membership::now_ms;
+use crate::transport::Message;
+use crate::{
+    Cluster, FleetError, FleetResult, NodeId, RetryPolicy, Scheduler, SchedulerConfig, Task,
+    TaskId, TaskPriority, TaskResult, TaskStatus,
+};
+
+/// What a job handler returns: its output, or why it failed
+pub type JobOutcome = Result<Vec<u8>, String>;
+
+type Handler =
+    Arc<dyn Fn(JobContext) -> Pin<Box<dyn Future<Output = JobOutcome> + Send>> + Send + Sync>;
+
+/// One attempt at a job, as handed to its handler
+#[derive(Debug, Clone)]
+pub struct JobContext {
+    /// Task ID, shared by all attempts
+    pub task_id: TaskId,
+    /// Job name
+    pub job: String,
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// Job payload
+    pub payload: Vec<u8>,
+}
+
+/// A job definition
+#[derive(Debug, Clone)]
+pub struct Job {
+    name: String,
+    schedule: Option<CronSchedule>,
+    payload: Vec<u8>,
+    priority: TaskPriority,
+    timeout_ms: u64,
+    retry_policy: RetryPolicy,
+    allow_overlap: bool,
+}
+
+impl Job {
+    /// A job run on the schedule of a cron `expression`
+    pub fn cron(name: impl Into<String>, expression: &str) -> FleetResult<Self> {
+        Ok(Self {
+            schedule: Some(CronSchedule::parse(expression)?),
+            ..Self::on_demand(name)
+        })
+    }
+
+    /// A job run only when triggered
+    pub fn on_demand(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            schedule: None,
+            payload: Vec::new(),
+            priority: TaskPriority::Normal,
+            timeout_ms: SchedulerConfig::default().default_timeout_ms,
+            retry_policy: RetryPolicy::default(),
+            allow_overlap: false,
+        }
+    }
+
+    /// Set the payload passed to every run
+    pub fn with_payload(mut self, payload: Vec<u8>) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Set priority
+    pub fn with_priority(mut self, priority: TaskPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the timeout of each attempt
+    pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Set retry policy
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Fire even while the previous run is still queued or running
+    pub fn allow_overlap(mut self) -> Self {
+        self.allow_overlap = true;
+        self
+    }
+
+    /// Job name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Cron schedule, if recurring
+    pub fn schedule(&self) -> Option<&CronSchedule> {
+        self.schedule.as_ref()
+    }
+}
+
+/// A finished attempt at a job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRun {
+    /// Job name
+    pub job: String,
+    /// Node that ran it
+    pub node_id: NodeId,
+    /// Attempt number, starting at 1
+    pub attempt: u32,
+    /// When the attempt started (Unix milliseconds)
+    pub started_at: i64,
+    /// Outcome
+    pub result: TaskResult,
+}
+
+/// Job run persistence
+pub trait RunStore: Send + Sync {
+    /// Record a finished attempt
+    fn record(&self, run: &JobRun) -> FleetResult<()>;
+
+    /// Latest runs of `job`, newest first
+    fn history(&self, job: &str, limit: usize) -> FleetResult<Vec<JobRun>>;
+}
+
+/// In-memory run store, keeping the latest runs of each job
+#[derive(Debug)]
+pub struct MemoryRunStore {
+    keep: usize,
+    runs: RwLock<HashMap<String, VecDeque<JobRun>>>,
+}
+
+impl MemoryRunStore {
+    /// Keep the latest `keep` runs of each job
+    pub fn new(keep: usize) -> Self {
+        Self {
+            keep,
+            runs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Every run kept, oldest first
+    fn all(&self) -> Vec<JobRun> {
+        let mut runs: Vec<JobRun> = self
+            .runs
+            .read()
+            .unwrap()
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+        runs.sort_by_key(|run| run.started_at);
+        runs
+    }
+}
+
+impl Default for MemoryRunStore {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl RunStore for MemoryRunStore {
+    fn record(&self, run: &JobRun) -> FleetResult<()> {
+        let mut runs = self.runs.write().unwrap();
+        let history = runs.entry(run.job.clone()).or_default();
+        history.push_front(run.clone());
+        history.truncate(self.keep);
+        Ok(())
+    }
+
+    fn history(&self, job: &str, limit: usize) -> FleetResult<Vec<JobRun>> {
+        Ok(self
+            .runs
+            .read()
+            .unwrap()
+            .get(job)
+            .map(|runs| runs.iter().take(limit).cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Run store backed by an append-only log file
+///
+/// Runs are kept in memory as well; on open the log is replayed and
+/// rewritten without the runs that no longer fit in the history.
+#[derive(Debug)]
+pub struct FileRunStore {
+    path: PathBuf,
+    file: Mutex<File>,
+    runs: MemoryRunStore,
+}
+
+impl FileRunStore {
+    /// Open (or create) the log at `path`, keeping the latest `keep` runs of
+    /// each job
+    pub fn open(path: impl AsRef<Path>, keep: usize) -> FleetResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let storage = |e: std::io::Error| storage_error(&path, e);
+        let runs = MemoryRunStore::new(keep);
+
+        let mut lines = 0;
+        if path.exists() {
+            let reader = BufReader::new(File::open(&path).map_err(storage)?);
+            for line in reader.lines() {
+                let line = line.map_err(storage)?;
+                lines += 1;
+                match decode_run(&line) {
+                    Some(run) => runs.record(&run)?,
+                    None => tracing::warn!("Skipping malformed job run in {}", path.display()),
+                }
+            }
+        }
+
+        let kept = runs.all();
+        if lines > kept.len() {
+            let compacted = path.with_extension("compact");
+            let mut out = File::create(&compacted).map_err(storage)?;
+            for run in &kept {
+                writeln!(out, "{}", encode_run(run)).map_err(storage)?;
+            }
+            out.sync_all().map_err(storage)?;
+            fs::rename(&compacted, &path).map_err(storage)?;
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(storage)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+            runs,
+        })
+    }
+}
+
+impl RunStore for FileRunStore {
+    fn record(&self, run: &JobRun) -> FleetResult<()> {
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", encode_run(run))
+            .and_then(|_| file.flush())
+            .map_err(|e| storage_error(&self.path, e))?;
+        self.runs.record(run)
+    }
+
+    fn history(&self, job: &str, limit: usize) -> FleetResult<Vec<JobRun>> {
+        self.runs.history(job, limit)
+    }
+}
+
+fn storage_error(path: &Path, e: std::io::Error) -> FleetError {
+    FleetError::StorageError(format!("{}: {}", path.display(), e))
+}
+
+/// One tab-separated line per run; optional fields are `-` or `+value`
+fn encode_run(run: &JobRun) -> String {
+    let result = &run.result;
+    [
+        run.started_at.to_string(),
+        escape(&run.job),
+        escape(result.task_id.as_str()),
+        escape(run.node_id.as_str()),
+        run.attempt.to_string(),
+        result.status.as_str().to_string(),
+        result.execution_time_ms.to_string(),
+        optional(result.data.as_ref().map(|data| hex(data))),
+        optional(result.error.as_deref().map(escape)),
+    ]
+    .join("\t")
+}
+
+fn decode_run(line: &str) -> Option<JobRun> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [started_at, job, task_id, node_id, attempt, status, execution_time_ms, data, error] =
+        fields[..]
+    else {
+        return None;
+    };
+    Some(JobRun {
+        job: unescape(job)?,
+        node_id: NodeId::new(unescape(node_id)?),
+        attempt: attempt.parse().ok()?,
+        started_at: started_at.parse().ok()?,
+        result: TaskResult {
+            task_id: TaskId::new(unescape(task_id)?),
+            status: TaskStatus::parse(status)?,
+            data: parse_optional(data, unhex)?,
+            error: parse_optional(error, unescape)?,
+            execution_time_ms: execution_time_ms.parse().ok()?,
+        },
+    })
+}
+
+fn optional(value: Option<String>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("+{}", v))
+}
+
+fn parse_optional<T>(field: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Option<T>> {
+    match field {
+        "-" => Some(None),
+        _ => parse(field.strip_prefix('+')?).map(Some),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// A task handed to a node to run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Assignment {
+    pub(crate) task_id: TaskId,
+    pub(crate) job: String,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) timeout_ms: u64,
+    pub(crate) attempt: u32,
+}
+
+impl From<&Task> for Assignment {
+    fn from(task: &Task) -> Self {
+        Self {
+            task_id: task.id.clone(),
+            job: task.task_type.clone(),
+            payload: task.payload.clone(),
+            timeout_ms: task.timeout_ms,
+            attempt: task.retries + 1,
+        }
+    }
+}
+
+/// Job scheduler configuration
+#[derive(Debug, Clone)]
+pub struct JobSchedulerConfig {
+    /// How often the leader checks schedules and nodes pull work
+    pub tick_ms: u64,
+    /// Jobs this node runs at once
+    pub concurrency: usize,
+    /// How long past its timeout the leader waits for a node's result
+    pub report_grace_ms: u64,
+    /// Queue settings; `work_stealing` off keeps every run on the leader
+    pub scheduler: SchedulerConfig,
+}
+
+impl Default for JobSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: 1000,
+            concurrency: 4,
+            report_grace_ms: 5000,
+            scheduler: SchedulerConfig::default(),
+        }
+    }
+}
+
+/// The leader's queue
+#[derive(Debug)]
+struct Queue {
+    /// Whether this node was leading when the queue was last used
+    leading: bool,
+    scheduler: Scheduler,
+    /// When each cron job fires next
+    next_runs: HashMap<String, OffsetDateTime>,
+    /// Job of every task not yet finished for good
+    active: HashMap<TaskId, String>,
+    /// When the leader stops waiting for each handed-out task
+    deadlines: HashMap<TaskId, i64>,
+}
+
+impl Queue {
+    fn new(config: &SchedulerConfig, leading: bool) -> Self {
+        Self {
+            leading,
+            scheduler: Scheduler::new(config.clone()),
+            next_runs: HashMap::new(),
+            active: HashMap::new(),
+            deadlines: HashMap::new(),
+        }
+    }
+}
+
+struct Registered {
+    job: Job,
+    handler: Handler,
+}
+
+/// Schedules jobs on the leader and runs them on every node
+pub struct JobScheduler {
+    node_id: NodeId,
+    config: JobSchedulerConfig,
+    cluster: Option<Arc<Cluster>>,
+    store: Arc<dyn RunStore>,
+    jobs: RwLock<HashMap<String, Registered>>,
+    queue: Mutex<Queue>,
+    running: AtomicUsize,
+    /// Wakes the tick loop early, e.g. when a slot frees up
+    wake: Notify,
+}
+
+impl JobScheduler {
+    /// Create a scheduler for a single node, which is always the leader
+    pub fn new(node_id: NodeId) -> Self {
+        let config = JobSchedulerConfig::default();
+        Self {
+            node_id,
+            queue: Mutex::new(Queue::new(&config.scheduler, false)),
+            config,
+            cluster: None,
+            store: Arc::new(MemoryRunStore::default()),
+            jobs: RwLock::new(HashMap::new()),
+            running: AtomicUsize::new(0),
+            wake: Notify::new(),
+        }
+    }
+
+    /// Set configuration
+    pub fn with_config(mut self, config: JobSchedulerConfig) -> Self {
+        self.queue = Mutex::new(Queue::new(&config.scheduler, false));
+        self.config = config;
+        self
+    }
+
+    /// Set where runs are recorded
+    pub fn with_store(mut self, store: Arc<dyn RunStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Schedule only while `cluster`'s node leads, and share runs with its
+    /// other nodes
+    pub fn with_cluster(mut self, cluster: Arc<Cluster>) -> Self {
+        self.node_id = cluster.node_id().clone();
+        self.cluster = Some(cluster);
+        self
+    }
+
+    /// Register a job and its handler
+    ///
+    /// Every node must register the same jobs, since any of them may be
+    /// handed a run.
+    pub fn register<F, Fut>(&self, job: Job, handler: F) -> FleetResult<()>
+    where
+        F: Fn(JobContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = JobOutcome> + Send + 'static,
+    {
+        let mut jobs = self.jobs.write().unwrap();
+        if jobs.contains_key(&job.name) {
+            return Err(FleetError::ConfigError(format!(
+                "Job {} is already registered",
+                job.name
+            )));
+        }
+        let handler: Handler = Arc::new(move |ctx| Box::pin(handler(ctx)));
+        tracing::info!(
+            "Registered job {} ({})",
+            job.name,
+            job.schedule.as_ref().map_or("on demand", |s| s.as_str())
+        );
+        jobs.insert(job.name.clone(), Registered { job, handler });
+        Ok(())
+    }
+
+    /// Check if this node schedules jobs
+    pub fn is_leader(&self) -> bool {
+        self.cluster.as_ref().map_or(true, |c| c.is_leader())
+    }
+
+    /// Queue a run of `name` now (leader only)
+    pub fn trigger(&self, name: &str) -> FleetResult<TaskId> {
+        if !self.is_leader() {
+            return Err(FleetError::NotLeader {
+                leader_id: self
+                    .cluster
+                    .as_ref()
+                    .and_then(|c| c.membership().leader)
+                    .map(|id| id.as_str().to_string()),
+            });
+        }
+        let job = self
+            .jobs
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|registered| registered.job.clone())
+            .ok_or_else(|| FleetError::JobNotFound(name.to_string()))?;
+
+        let mut queue = self.queue(true);
+        let task_id = submit(&mut queue, &job);
+        drop(queue);
+        self.wake.notify_one();
+        Ok(task_id)
+    }
+
+    /// When the cron job `name` fires next (leader only)
+    pub fn next_run(&self, name: &str) -> Option<OffsetDateTime> {
+        self.queue.lock().unwrap().next_runs.get(name).copied()
+    }
+
+    /// Latest runs of `name`, newest first
+    pub fn history(&self, name: &str, limit: usize) -> FleetResult<Vec<JobRun>> {
+        self.store.history(name, limit)
+    }
+
+    /// Latest run of `name`
+    pub fn last_run(&self, name: &str) -> FleetResult<Option<JobRun>> {
+        Ok(self.store.history(name, 1)?.into_iter().next())
+    }
+
+    /// Jobs running on this node
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Schedule and run jobs until `shutdown` resolves
+    ///
+    /// Runs in progress at shutdown are left to finish in the background.
+    pub async fn run(self: Arc<Self>, shutdown: impl Future<Output = ()>) {
+        if let Some(cluster) = &self.cluster {
+            cluster.attach_jobs(&self);
+        }
+        tokio::pin!(shutdown);
+        let interval = Duration::from_millis(self.config.tick_ms);
+        loop {
+            self.tick(OffsetDateTime::now_utc()).await;
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(interval) => {}
+                _ = self.wake.notified() => {}
+            }
+        }
+    }
+
+    async fn tick(self: &Arc<Self>, now: OffsetDateTime) {
+        if self.is_leader() {
+            let assignments = {
+                let mut queue = self.queue(true);
+                self.fire_due(&mut queue, now);
+                self.expire_overdue(&mut queue, now_ms());
+                let free = self.free_slots();
+                self.hand_out(&mut queue, &self.node_id.clone(), free)
+            };
+            for assignment in assignments {
+                self.spawn(assignment);
+            }
+        } else {
+            drop(self.queue(false));
+            self.steal().await;
+        }
+    }
+
+    /// Lock the queue, starting afresh if leadership changed since its
+    /// last use
+    fn queue(&self, leading: bool) -> MutexGuard<'_, Queue> {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.leading != leading {
+            if leading {
+                tracing::info!("Node {} now schedules jobs", self.node_id.as_str());
+            }
+            *queue = Queue::new(&self.config.scheduler, leading);
+        }
+        queue
+    }
+
+    /// Queue the cron jobs due at `now`
+    fn fire_due(&self, queue: &mut Queue, now: OffsetDateTime) {
+        let jobs = self.jobs.read().unwrap();
+        for (name, registered) in jobs.iter() {
+            let Some(schedule) = &registered.job.schedule else {
+                continue;
+            };
+            let due = match queue.next_runs.get(name) {
+                Some(next) => *next <= now,
+                None => {
+                    // Newly registered, or newly leading: start from now
+                    if let Some(next) = schedule.next_after(now) {
+                        queue.next_runs.insert(name.clone(), next);
+                    }
+                    false
+                }
+            };
+            if !due {
+                continue;
+            }
+
+            match schedule.next_after(now) {
+                Some(next) => queue.next_runs.insert(name.clone(), next),
+                None => queue.next_runs.remove(name),
+            };
+            if !registered.job.allow_overlap && queue.active.values().any(|job| job == name) {
+                tracing::warn!("Skipping job {}: previous run still active", name);
+                continue;
+            }
+            submit(queue, &registered.job);
+        }
+    }
+
+    /// Time out handed-out tasks whose node never reported back
+    fn expire_overdue(&self, queue: &mut Queue, now_ms: i64) {
+        let overdue: Vec<TaskId> = queue
+            .deadlines
+            .iter()
+            .filter(|(_, deadline)| **deadline < now_ms)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for task_id in overdue {
+            let Some(task) = queue.scheduler.get_task(&task_id) else {
+                queue.deadlines.remove(&task_id);
+                continue;
+            };
+            let node_id = task
+                .assigned_node
+                .clone()
+                .unwrap_or_else(|| self.node_id.clone());
+            let waited = task.timeout_ms + self.config.report_grace_ms;
+            let run = JobRun {
+                job: task.task_type.clone(),
+                attempt: task.retries + 1,
+                started_at: queue.deadlines[&task_id] - waited as i64,
+                result: TaskResult {
+                    task_id: task_id.clone(),
+                    status: TaskStatus::TimedOut,
+                    data: None,
+                    error: Some(format!("No result from node {}", node_id.as_str())),
+                    execution_time_ms: waited,
+                },
+                node_id,
+            };
+            self.finish(queue, run);
+        }
+    }
+
+    /// Assign up to `max` queued tasks to `node_id`
+    fn hand_out(&self, queue: &mut Queue, node_id: &NodeId, max: usize) -> Vec<Assignment> {
+        if max == 0 {
+            return Vec::new();
+        }
+        let now = now_ms();
+        let tasks = queue.scheduler.steal(node_id, max);
+        for task in &tasks {
+            let deadline = now + (task.timeout_ms + self.config.report_grace_ms) as i64;
+            queue.deadlines.insert(task.id.clone(), deadline);
+        }
+        tasks.iter().map(Assignment::from).collect()
+    }
+
+    /// Record a finished attempt and retry or retire its task
+    fn finish(&self, queue: &mut Queue, run: JobRun) {
+        let task_id = run.result.task_id.clone();
+        let current = queue.scheduler.get_task(&task_id).is_some_and(|task| {
+            task.status == TaskStatus::Running
+                && task.assigned_node.as_ref() == Some(&run.node_id)
+                && task.retries + 1 == run.attempt
+        });
+        if !current {
+            tracing::debug!("Ignoring stale result for task {}", task_id.as_str());
+            return;
+        }
+        queue.deadlines.remove(&task_id);
+
+        let status = run.result.status;
+        let _ = queue.scheduler.complete(&task_id, run.result.clone());
+        if let Err(e) = self.store.record(&run) {
+            tracing::warn!("Failed to record run of job {}: {}", run.job, e);
+        }
+
+        let retried =
+            status != TaskStatus::Completed && queue.scheduler.retry(&task_id).unwrap_or(false);
+        if retried {
+            tracing::warn!(
+                "Job {} attempt {} on {} ended {}: {}",
+                run.job,
+                run.attempt,
+                run.node_id.as_str(),
+                status.as_str(),
+                run.result.error.as_deref().unwrap_or_default()
+            );
+        } else {
+            queue.scheduler.remove(&task_id);
+            queue.active.remove(&task_id);
+            match status {
+                TaskStatus::Completed => tracing::info!(
+                    "Job {} completed on {} in {}ms",
+                    run.job,
+                    run.node_id.as_str(),
+                    run.result.execution_time_ms
+                ),
+                _ => tracing::error!(
+                    "Job {} gave up after {} attempts: {}",
+                    run.job,
+                    run.attempt,
+                    run.result.error.as_deref().unwrap_or_default()
+                ),
+            }
+        }
+    }
+
+    fn free_slots(&self) -> usize {
+        self.config.concurrency.saturating_sub(self.running())
+    }
+
+    /// Pull queued tasks from the leader
+    async fn steal(self: &Arc<Self>) {
+        let free = self.free_slots();
+        let Some(cluster) = &self.cluster else {
+            return;
+        };
+        if free == 0 || !self.config.scheduler.work_stealing {
+            return;
+        }
+        let steal = Message::Steal {
+            node_id: self.node_id.clone(),
+            capacity: free as u32,
+        };
+        match cluster.call_leader(&steal).await {
+            Ok(Message::Tasks(assignments)) => {
+                for assignment in assignments {
+                    self.spawn(assignment);
+                }
+            }
+            Ok(other) => tracing::debug!("Unexpected reply to steal: {:?}", other),
+            Err(e) => tracing::debug!("Failed to pull jobs: {}", e),
+        }
+    }
+
+    /// Run an assignment in the background and report the result
+    fn spawn(self: &Arc<Self>, assignment: Assignment) {
+        self.running.fetch_add(1, Ordering::SeqCst);
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            let run = scheduler.execute(assignment).await;
+            scheduler.running.fetch_sub(1, Ordering::SeqCst);
+            scheduler.report(run).await;
+            scheduler.wake.notify_one();
+        });
+    }
+
+    async fn execute(&self, assignment: Assignment) -> JobRun {
+        let started_at = now_ms();
+        let handler = self
+            .jobs
+            .read()
+            .unwrap()
+            .get(&assignment.job)
+            .map(|registered| Arc::clone(&registered.handler));
+        let context = JobContext {
+            task_id: assignment.task_id.clone(),
+            job: assignment.job.clone(),
+            attempt: assignment.attempt,
+            payload: assignment.payload,
+        };
+
+        let (status, data, error) = match handler {
+            None => (
+                TaskStatus::Failed,
+                None,
+                Some(format!(
+                    "Job {} is not registered on node {}",
+                    assignment.job,
+                    self.node_id.as_str()
+                )),
+            ),
+            Some(handler) => {
+                let timeout = Duration::from_millis(assignment.timeout_ms);
+                let mut running = tokio::spawn(handler(context));
+                match tokio::time::timeout(timeout, &mut running).await {
+                    Ok(Ok(Ok(data))) => (TaskStatus::Completed, Some(data), None),
+                    Ok(Ok(Err(e))) => (TaskStatus::Failed, None, Some(e)),
+                    Ok(Err(e)) => (
+                        TaskStatus::Failed,
+                        None,
+                        Some(format!("Job panicked: {}", e)),
+                    ),
+                    Err(_) => {
+                        running.abort();
+                        (
+                            TaskStatus::TimedOut,
+                            None,
+                            Some(format!("Timed out after {}ms", assignment.timeout_ms)),
+                        )
+                    }
+                }
+            }
+        };
+
+        JobRun {
+            job: assignment.job,
+            node_id: self.node_id.clone(),
+            attempt: assignment.attempt,
+            started_at,
+            result: TaskResult {
+                task_id: assignment.task_id,
+                status,
+                data,
+                error,
+                execution_time_ms: (now_ms() - started_at).max(0) as u64,
+            },
+        }
+    }
+
+    /// Hand a finished attempt to the leader
+    async fn report(&self, run: JobRun) {
+        if self.is_leader() {
+            let mut queue = self.queue(true);
+            self.finish(&mut queue, run);
+            return;
+        }
+        let Some(cluster) = &self.cluster else {
+            return;
+        };
+        let job = run.job.clone();
+        if let Err(e) = cluster.call_leader(&Message::Report(run)).await {
+            // The leader times the task out and retries it
+            tracing::warn!("Failed to report run of job {}: {}", job, e);
+        }
+    }
+
+    /// Answer another node's [`Message::Steal`]
+    pub(crate) fn handle_steal(&self, node_id: &NodeId, capacity: u32) -> Vec<Assignment> {
+        if !self.config.scheduler.work_stealing || !self.is_leader() {
+            return Vec::new();
+        }
+        let mut queue = self.queue(true);
+        self.hand_out(&mut queue, node_id, capacity as usize)
+    }
+
+    /// Answer another node's [`Message::Report`]
+    pub(crate) fn handle_report(&self, run: JobRun) {
+        if self.is_leader() {
+            let mut queue = self.queue(true);
+            self.finish(&mut queue, run);
+        }
+    }
+}
+
+impl std::fmt::Debug for JobScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobScheduler")
+            .field("node_id", &self.node_id)
+            .field("jobs", &self.jobs.read().unwrap().len())
+            .field("running", &self.running())
+            .finish()
+    }
+}
+
+/// Queue a run of `job`
+fn submit(queue: &mut Queue, job: &Job) -> TaskId {
+    let task = Task::new(job.name.clone(), job.payload.clone())
+        .with_priority(job.priority)
+        .with_timeout(job.timeout_ms)
+        .with_retry_policy(job.retry_policy);
+    let task_id = queue.scheduler.submit(task);
+    queue.active.insert(task_id.clone(), job.name.clone());
+    task_id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use time::macros::datetime;
+    use tokio::time::Instant;
+
+    fn config() -> JobSchedulerConfig {
+        JobSchedulerConfig {
+            tick_ms: 10,
+            ..JobSchedulerConfig::default()
+        }
+    }
+
+    fn start(scheduler: &Arc<JobScheduler>) -> tokio::sync::oneshot::Sender<()> {
+        let (stop, stopped) = tokio::sync::oneshot::channel();
+        tokio::spawn(Arc::clone(scheduler).run(async {
+            let _ = stopped.await;
+        }));
+        stop
+    }
+
+    /// Wait until `job` has `count` recorded runs, newest first
+    async fn runs(scheduler: &JobScheduler, job: &str, count: usize) -> Vec<JobRun> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let runs = scheduler.history(job, 100).unwrap();
+            if runs.len() >= count {
+                return runs;
+            }
+            assert!(Instant::now() < deadline, "timed out: {:?}", runs);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trigger_retry_and_timeout() {
+        let scheduler = Arc::new(JobScheduler::new(NodeId::new("node-1")).with_config(config()));
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&attempts);
+        scheduler
+            .register(
+                Job::on_demand("retrain_oracle").with_retry_policy(RetryPolicy::exponential(2, 10)),
+                move |ctx| {
+                    let counter = Arc::clone(&counter);
+                    async move {
+                        counter.fetch_add(1, Ordering::SeqCst);
+                        match ctx.attempt {
+                            1 => Err("history unavailable".to_string()),
+                            _ => Ok(b"trained".to_vec()),
+                        }
+                    }
+                },
+            )
+            .unwrap();
+        scheduler
+            .register(
+                Job::on_demand("expire_pools")
+                    .with_timeout(20)
+                    .with_retry_policy(RetryPolicy::none()),
+                |_| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    Ok(Vec::new())
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            scheduler.register(Job::on_demand("expire_pools"), |_| async { Ok(Vec::new()) }),
+            Err(FleetError::ConfigError(_))
+        ));
+        assert!(matches!(
+            scheduler.trigger("missing"),
+            Err(FleetError::JobNotFound(_))
+        ));
+
+        let stop = start(&scheduler);
+        let task_id = scheduler.trigger("retrain_oracle").unwrap();
+        let history = runs(&scheduler, "retrain_oracle", 2).await;
+        assert_eq!(history[1].result.status, TaskStatus::Failed);
+        assert_eq!(
+            history[1].result.error.as_deref(),
+            Some("history unavailable")
+        );
+        assert_eq!(history[0].result.status, TaskStatus::Completed);
+        assert_eq!(history[0].result.data.as_deref(), Some(&b"trained"[..]));
+        assert_eq!(history[0].attempt, 2);
+        assert_eq!(history[0].result.task_id, task_id);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        scheduler.trigger("expire_pools").unwrap();
+        let history = runs(&scheduler, "expire_pools", 1).await;
+        assert_eq!(history[0].result.status, TaskStatus::TimedOut);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(scheduler.history("expire_pools", 10).unwrap().len(), 1);
+        assert_eq!(scheduler.running(), 0);
+
+        stop.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cron_firing() {
+        let scheduler = JobScheduler::new(NodeId::new("node-1"));
+        let job = Job::cron("expire_pools", "*/5 * * * *").unwrap();
+        scheduler
+            .register(job, |_| async { Ok(Vec::new()) })
+            .unwrap();
+        scheduler
+            .register(
+                Job::cron("retrain_oracle", "0 3 * * *").unwrap(),
+                |_| async { Ok(Vec::new()) },
+            )
+            .unwrap();
+
+        let mut queue = scheduler.queue(true);
+        scheduler.fire_due(&mut queue, datetime!(2026-10-16 13:07 UTC));
+        assert!(queue.active.is_empty());
+        drop(queue);
+        assert_eq!(
+            scheduler.next_run("expire_pools"),
+            Some(datetime!(2026-10-16 13:10 UTC))
+        );
+        assert_eq!(
+            scheduler.next_run("retrain_oracle"),
+            Some(datetime!(2026-10-17 03:00 UTC))
+        );
+
+        let mut queue = scheduler.queue(true);
+        scheduler.fire_due(&mut queue, datetime!(2026-10-16 13:10 UTC));
+        assert_eq!(queue.active.len(), 1);
+        // Still queued at the next firing: skipped rather than stacked up
+        scheduler.fire_due(&mut queue, datetime!(2026-10-16 13:15 UTC));
+        assert_eq!(queue.active.len(), 1);
+        assert_eq!(
+            queue.next_runs["expire_pools"],
+            datetime!(2026-10-16 13:20 UTC)
+        );
+        drop(queue);
+
+        // Losing leadership drops the queue and the schedule
+        drop(scheduler.queue(false));
+        assert!(scheduler.next_run("expire_pools").is_none());
+    }
+
+    #[test]
+    fn test_file_run_store() {
+        let path = std::env::temp_dir().join(format!("vaya-fleet-runs-{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let run = |attempt: u32, error: Option<&str>| JobRun {
+            job: "expire_pools".into(),
+            node_id: NodeId::new("node-1"),
+            attempt,
+            started_at: 1_792_143_000_000 + attempt as i64,
+            result: TaskResult {
+                task_id: TaskId::new("task-1"),
+                status: match error {
+                    Some(_) => TaskStatus::Failed,
+                    None => TaskStatus::Completed,
+                },
+                data: error.is_none().then(|| vec![0, 255]),
+                error: error.map(String::from),
+                execution_time_ms: 7,
+            },
+        };
+
+        let store = FileRunStore::open(&path, 2).unwrap();
+        store.record(&run(1, Some("tab\tand\nnewline"))).unwrap();
+        store.record(&run(2, Some("-"))).unwrap();
+        store.record(&run(3, None)).unwrap();
+        drop(store);
+
+        // Reopened: the latest two survive, and the log is compacted
+        let store = FileRunStore::open(&path, 2).unwrap();
+        assert_eq!(
+            store.history("expire_pools", 10).unwrap(),
+            [run(3, None), run(2, Some("-"))]
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        assert!(decode_run(&encode_run(&run(1, Some("a\\b"))))
+            .is_some_and(|r| r == run(1, Some("a\\b"))));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_work_stealing_across_nodes() {
+        use crate::FleetConfig;
+
+        let fleet = |id: &str, seeds: Vec<String>| FleetConfig {
+            node_id: NodeId::new(id),
+            bind_addr: "127.0.0.1:0".into(),
+            seed_nodes: seeds,
+            heartbeat_ms: 20,
+            election_timeout_ms: 200,
+            ..FleetConfig::default()
+        };
+        let a = Cluster::bind(fleet("node-a", Vec::new())).await.unwrap();
+        tokio::spawn(Arc::clone(&a).run(std::future::pending()));
+        let b = Cluster::bind(fleet("node-b", vec![a.address().to_string()]))
+            .await
+            .unwrap();
+        tokio::spawn(Arc::clone(&b).run(std::future::pending()));
+
+        let jobs = |cluster: &Arc<Cluster>| {
+            let scheduler = JobScheduler::new(NodeId::new("unused"))
+                .with_config(JobSchedulerConfig {
+                    concurrency: 1,
+                    ..config()
+                })
+                .with_cluster(Arc::clone(cluster));
+            scheduler
+                .register(
+                    Job::on_demand("expire_pools").allow_overlap(),
+                    |ctx| async move {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        Ok(ctx.payload)
+                    },
+                )
+                .unwrap();
+            Arc::new(scheduler)
+        };
+        let on_a = jobs(&a);
+        let on_b = jobs(&b);
+        let _stop_a = start(&on_a);
+        let _stop_b = start(&on_b);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while b.membership().leader.is_none() {
+            assert!(Instant::now() < deadline);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(matches!(
+            on_b.trigger("expire_pools"),
+            Err(FleetError::NotLeader { leader_id: Some(_) })
+        ));
+        for _ in 0..4 {
+            on_a.trigger("expire_pools").unwrap();
+        }
+
+        let history = runs(&on_a, "expire_pools", 4).await;
+        assert!(history
+            .iter()
+            .all(|run| run.result.status == TaskStatus::Completed));
+        let on = |id: &str| {
+            history
+                .iter()
+                .filter(|run| run.node_id.as_str() == id)
+                .count()
+        };
+        assert!(on("node-a") > 0 && on("node-b") > 0, "{:?}", history);
+        // Only the leader records runs
+        assert!(on_b.history("expire_pools", 10).unwrap().is_empty());
+    }
+}
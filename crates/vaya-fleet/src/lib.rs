@@ -2,23 +2,49 @@
 //!
 //! Custom orchestration for VAYA with:
 //! - Node management and health monitoring
-//! - Task scheduling and distribution
+//! - Task scheduling and distribution, with cron jobs spread across nodes
+//!   by work stealing
 //! - Raft consensus for leader election
-//! - Service discovery and routing
+//! - Service discovery and routing, with a client that finds healthy
+//!   instances of services on other nodes
+//! - Rate limiter state shared between nodes
+//! - Dynamic membership: seed-node join, graceful leave and eviction of
+//!   unresponsive nodes
 //!
 //! NO KUBERNETES. NO DOCKER. ALL CUSTOM.
 
+mod cluster;
 mod consensus;
+mod cron;
+mod discovery;
 mod error;
+mod jobs;
+mod membership;
 mod node;
+mod ratelimit;
 mod scheduler;
 mod service;
+mod transport;
 
-pub use consensus::{RaftConfig, RaftNode, RaftState};
+pub use cluster::Cluster;
+pub use consensus::{MembershipChange, RaftConfig, RaftNode, RaftState};
+pub use cron::CronSchedule;
+pub use discovery::DiscoveryClient;
 pub use error::{FleetError, FleetResult};
+pub use jobs::{
+    FileRunStore, Job, JobContext, JobOutcome, JobRun, JobScheduler, JobSchedulerConfig,
+    MemoryRunStore, RunStore,
+};
+pub use membership::{MemberHealth, MemberInfo, MembershipView};
 pub use node::{Node, NodeId, NodeInfo, NodePool, NodeStatus};
-pub use scheduler::{Scheduler, Task, TaskId, TaskResult, TaskStatus};
-pub use service::{Service, ServiceConfig, ServiceDiscovery, ServiceRegistry};
+pub use ratelimit::{RateLimitSnapshot, SharedRateLimits};
+pub use scheduler::{
+    RetryPolicy, Scheduler, SchedulerConfig, Task, TaskId, TaskPriority, TaskResult, TaskStatus,
+};
+pub use service::{
+    DiscoveryConfig, RegistryEvent, Service, ServiceConfig, ServiceDiscovery, ServiceEndpoint,
+    ServiceHealth, ServiceRegistry,
+};
 
 /// Fleet version
 pub const FLEET_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -33,6 +59,8 @@ pub mod defaults {
     pub const HEALTH_CHECK_INTERVAL_MS: u64 = 5000;
     /// Maximum nodes in cluster
     pub const MAX_NODES: usize = 100;
+    /// Default time without heartbeats before a node is evicted
+    pub const EVICTION_TIMEOUT_MS: u64 = 30_000;
 }
 
 /// Fleet configuration
@@ -44,12 +72,16 @@ pub struct FleetConfig {
     pub node_id: NodeId,
     /// Bind address
     pub bind_addr: String,
+    /// Address peers reach this node at, if not the bind address
+    pub advertise_addr: Option<String>,
     /// Seed nodes for discovery
     pub seed_nodes: Vec<String>,
     /// Heartbeat interval
     pub heartbeat_ms: u64,
     /// Election timeout
     pub election_timeout_ms: u64,
+    /// Time without heartbeats before the leader evicts a node
+    pub eviction_timeout_ms: u64,
 }
 
 impl Default for FleetConfig {
@@ -58,9 +90,11 @@ impl Default for FleetConfig {
             cluster_name: "vaya".into(),
             node_id: NodeId::generate(),
             bind_addr: "0.0.0.0:7000".into(),
+            advertise_addr: None,
             seed_nodes: Vec::new(),
             heartbeat_ms: defaults::HEARTBEAT_INTERVAL_MS,
             election_timeout_ms: defaults::ELECTION_TIMEOUT_MS,
+            eviction_timeout_ms: defaults::EVICTION_TIMEOUT_MS,
         }
     }
 }
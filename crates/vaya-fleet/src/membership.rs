@@ -0,0 +1,228 @@
+//! Cluster membership: who is in the cluster, where, and whether they answer
+//!
+//! The configuration itself lives in the Raft log as
+//! [`MembershipChange`](crate::MembershipChange) entries. [`Membership`]
+//! keeps what the log doesn't: each member's address and when it last
+//! answered. The leader evicts members it hasn't heard from within the
+//! eviction timeout.
+
+use std::collections::HashMap;
+
+use time::OffsetDateTime;
+
+use crate::{NodeId, RaftNode, RaftState};
+
+/// How recently a member answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberHealth {
+    /// Answered within a few heartbeats
+    Alive,
+    /// Missed several heartbeats
+    Suspect,
+    /// Past the eviction timeout; the leader is removing it
+    Unreachable,
+    /// Not tracked by this node (followers only hear from the leader)
+    Unknown,
+}
+
+/// One member, as seen by a node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberInfo {
+    /// Node ID
+    pub id: NodeId,
+    /// Cluster address (`host:port`)
+    pub address: String,
+    /// Whether it is the current leader
+    pub is_leader: bool,
+    /// Liveness
+    pub health: MemberHealth,
+    /// When it last answered (Unix milliseconds)
+    pub last_seen_ms: Option<i64>,
+}
+
+/// A node's view of the cluster, for the admin API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipView {
+    /// The node reporting
+    pub node_id: NodeId,
+    /// Its Raft state
+    pub state: RaftState,
+    /// Its current term
+    pub term: u64,
+    /// The leader it knows of
+    pub leader: Option<NodeId>,
+    /// Its commit index
+    pub commit_index: u64,
+    /// Whether a membership change is still uncommitted
+    pub change_pending: bool,
+    /// Current members, sorted by ID
+    pub members: Vec<MemberInfo>,
+}
+
+impl MembershipView {
+    /// Look up a member
+    pub fn member(&self, id: &NodeId) -> Option<&MemberInfo> {
+        self.members.iter().find(|member| &member.id == id)
+    }
+
+    /// Check if `id` is a member
+    pub fn contains(&self, id: &NodeId) -> bool {
+        self.member(id).is_some()
+    }
+}
+
+/// Addresses and liveness of cluster members
+#[derive(Debug)]
+pub(crate) struct Membership {
+    addresses: HashMap<NodeId, String>,
+    last_seen: HashMap<NodeId, i64>,
+    suspect_after_ms: i64,
+    evict_after_ms: i64,
+}
+
+impl Membership {
+    pub(crate) fn new(suspect_after_ms: u64, evict_after_ms: u64) -> Self {
+        Self {
+            addresses: HashMap::new(),
+            last_seen: HashMap::new(),
+            suspect_after_ms: suspect_after_ms as i64,
+            evict_after_ms: evict_after_ms as i64,
+        }
+    }
+
+    /// Record where `id` listens
+    pub(crate) fn learn(&mut self, id: NodeId, address: impl Into<String>) {
+        self.addresses.insert(id, address.into());
+    }
+
+    pub(crate) fn address(&self, id: &NodeId) -> Option<&str> {
+        self.addresses.get(id).map(|a| a.as_str())
+    }
+
+    /// Record that `id` answered
+    pub(crate) fn seen(&mut self, id: &NodeId, now_ms: i64) {
+        self.last_seen.insert(id.clone(), now_ms);
+    }
+
+    /// Treat every node in `ids` as just seen, giving them a full eviction
+    /// timeout from now (for a new leader, which hasn't heard from anyone)
+    pub(crate) fn reset_liveness<'a>(
+        &mut self,
+        ids: impl Iterator<Item = &'a NodeId>,
+        now_ms: i64,
+    ) {
+        self.last_seen.clear();
+        for id in ids {
+            self.last_seen.insert(id.clone(), now_ms);
+        }
+    }
+
+    /// Liveness of `id` and when it last answered
+    pub(crate) fn health(&self, id: &NodeId, now_ms: i64) -> (MemberHealth, Option<i64>) {
+        let Some(&seen) = self.last_seen.get(id) else {
+            return (MemberHealth::Unknown, None);
+        };
+        let silent = now_ms - seen;
+        let health = if silent > self.evict_after_ms {
+            MemberHealth::Unreachable
+        } else if silent > self.suspect_after_ms {
+            MemberHealth::Suspect
+        } else {
+            MemberHealth::Alive
+        };
+        (health, Some(seen))
+    }
+
+    /// Members of `ids` silent for longer than the eviction timeout
+    pub(crate) fn stale<'a>(
+        &self,
+        ids: impl Iterator<Item = &'a NodeId>,
+        now_ms: i64,
+    ) -> Vec<NodeId> {
+        ids.filter(|id| self.health(id, now_ms).0 == MemberHealth::Unreachable)
+            .cloned()
+            .collect()
+    }
+
+    /// `raft`'s view of the cluster
+    pub(crate) fn view(&self, raft: &RaftNode, now_ms: i64) -> MembershipView {
+        let leader = raft.leader_id().cloned();
+        let mut members: Vec<MemberInfo> = raft
+            .members()
+            .map(|id| {
+                let (health, last_seen_ms) = if id == raft.id() {
+                    (MemberHealth::Alive, Some(now_ms))
+                } else {
+                    self.health(id, now_ms)
+                };
+                MemberInfo {
+                    id: id.clone(),
+                    address: self.address(id).unwrap_or_default().to_string(),
+                    is_leader: leader.as_ref() == Some(id),
+                    health,
+                    last_seen_ms,
+                }
+            })
+            .collect();
+        members.sort_by(|a, b| a.id.as_str().cmp(b.id.as_str()));
+
+        MembershipView {
+            node_id: raft.id().clone(),
+            state: raft.state(),
+            term: raft.current_term(),
+            leader,
+            commit_index: raft.commit_index(),
+            change_pending: raft.membership_pending(),
+            members,
+        }
+    }
+}
+
+/// Current time in Unix milliseconds
+pub(crate) fn now_ms() -> i64 {
+    (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1_000_000) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RaftConfig;
+
+    #[test]
+    fn test_health_thresholds() {
+        let mut membership = Membership::new(100, 1000);
+        let a = NodeId::new("node-a");
+        let b = NodeId::new("node-b");
+        membership.seen(&a, 10_000);
+
+        assert_eq!(membership.health(&a, 10_050).0, MemberHealth::Alive);
+        assert_eq!(membership.health(&a, 10_500).0, MemberHealth::Suspect);
+        assert_eq!(membership.health(&a, 11_001).0, MemberHealth::Unreachable);
+        assert_eq!(membership.health(&b, 11_001), (MemberHealth::Unknown, None));
+        assert_eq!(
+            membership.stale([&a, &b].into_iter(), 11_001),
+            vec![a.clone()]
+        );
+
+        membership.reset_liveness([&a, &b].into_iter(), 20_000);
+        assert!(membership.stale([&a, &b].into_iter(), 20_500).is_empty());
+    }
+
+    #[test]
+    fn test_view() {
+        let mut raft = RaftNode::new(NodeId::new("node-1"), RaftConfig::default());
+        raft.add_member(NodeId::new("node-0"));
+        let mut membership = Membership::new(100, 1000);
+        membership.learn(NodeId::new("node-0"), "10.0.0.1:7000");
+        membership.learn(NodeId::new("node-1"), "10.0.0.2:7000");
+
+        let view = membership.view(&raft, 5_000);
+        assert_eq!(view.state, RaftState::Follower);
+        assert_eq!(view.members.len(), 2);
+        assert_eq!(view.members[0].address, "10.0.0.1:7000");
+        assert_eq!(view.members[0].health, MemberHealth::Unknown);
+        assert_eq!(view.members[1].health, MemberHealth::Alive);
+        assert!(!view.change_pending);
+        assert!(view.contains(&NodeId::new("node-1")));
+    }
+}
@@ -0,0 +1,202 @@
+//! Rate limiter state shared across the fleet
+//!
+//! Each node runs GCRA locally and records, per key, its own theoretical
+//! arrival time (TAT). Nodes exchange [`RateLimitSnapshot`]s of their TATs
+//! (e.g. alongside heartbeats); since a node's TAT for a key only ever
+//! grows, merging keeps the maximum seen per node. The summed backlog of
+//! all nodes is an upper bound on the cluster-wide backlog, so admitting a
+//! request only when that sum fits the quota enforces the limit globally.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::NodeId;
+
+/// A node's rate limiter TATs, for replication to peers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitSnapshot {
+    /// Node the TATs belong to
+    pub node_id: NodeId,
+    /// Key and TAT (milliseconds) of each live entry
+    pub entries: Vec<(String, i64)>,
+}
+
+/// Per-key TATs of every known node
+#[derive(Debug)]
+pub struct SharedRateLimits {
+    node_id: NodeId,
+    tats: Mutex<HashMap<String, HashMap<NodeId, i64>>>,
+}
+
+impl SharedRateLimits {
+    /// Create the shared state for this node
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            tats: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// This node's ID
+    pub fn node_id(&self) -> &NodeId {
+        &self.node_id
+    }
+
+    /// Update this node's TAT for `key`
+    ///
+    /// `decide` receives this node's TAT and the summed backlog (time still
+    /// to drain, in ms) of all other nodes at `now_ms`, and returns the new
+    /// TAT, or `None` to leave it unchanged. Its result is returned.
+    pub fn update<R>(
+        &self,
+        key: &str,
+        now_ms: i64,
+        decide: impl FnOnce(Option<i64>, i64) -> (Option<i64>, R),
+    ) -> R {
+        let mut tats = self.tats.lock().unwrap();
+        let nodes = tats.entry(key.to_string()).or_default();
+        let peer_backlog = nodes
+            .iter()
+            .filter(|(node, _)| **node != self.node_id)
+            .map(|(_, tat)| (tat - now_ms).max(0))
+            .sum();
+        let local = nodes.get(&self.node_id).copied();
+
+        let (tat, result) = decide(local, peer_backlog);
+        if let Some(tat) = tat {
+            nodes.insert(self.node_id.clone(), tat);
+        } else if nodes.is_empty() {
+            tats.remove(key);
+        }
+        result
+    }
+
+    /// Summed backlog of all nodes for `key` at `now_ms`
+    pub fn backlog(&self, key: &str, now_ms: i64) -> i64 {
+        self.tats
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|nodes| nodes.values().map(|tat| (tat - now_ms).max(0)).sum())
+            .unwrap_or(0)
+    }
+
+    /// This node's TATs still in the future at `now_ms`
+    pub fn snapshot(&self, now_ms: i64) -> RateLimitSnapshot {
+        let tats = self.tats.lock().unwrap();
+        let entries = tats
+            .iter()
+            .filter_map(|(key, nodes)| {
+                let tat = *nodes.get(&self.node_id)?;
+                (tat > now_ms).then(|| (key.clone(), tat))
+            })
+            .collect();
+
+        RateLimitSnapshot {
+            node_id: self.node_id.clone(),
+            entries,
+        }
+    }
+
+    /// Merge a peer's snapshot, keeping the latest TAT per key
+    ///
+    /// Snapshots of this node (echoed back by a peer) are ignored.
+    pub fn merge(&self, snapshot: &RateLimitSnapshot) {
+        if snapshot.node_id == self.node_id {
+            return;
+        }
+        let mut tats = self.tats.lock().unwrap();
+        for (key, tat) in &snapshot.entries {
+            let current = tats
+                .entry(key.clone())
+                .or_default()
+                .entry(snapshot.node_id.clone())
+                .or_insert(*tat);
+            *current = (*current).max(*tat);
+        }
+    }
+
+    /// Forget a node that left the fleet
+    pub fn remove_node(&self, node_id: &NodeId) {
+        let mut tats = self.tats.lock().unwrap();
+        tats.retain(|_, nodes| {
+            nodes.remove(node_id);
+            !nodes.is_empty()
+        });
+    }
+
+    /// Drop TATs that have fully drained by `now_ms`
+    pub fn prune(&self, now_ms: i64) {
+        let mut tats = self.tats.lock().unwrap();
+        tats.retain(|_, nodes| {
+            nodes.retain(|_, tat| *tat > now_ms);
+            !nodes.is_empty()
+        });
+    }
+
+    /// Number of keys with state
+    pub fn len(&self) -> usize {
+        self.tats.lock().unwrap().len()
+    }
+
+    /// Check if there is no state
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance(limits: &SharedRateLimits, key: &str, now: i64, by: i64) {
+        limits.update(key, now, |tat, _| {
+            (Some(tat.unwrap_or(now).max(now) + by), ())
+        });
+    }
+
+    #[test]
+    fn test_snapshot_and_merge() {
+        let a = SharedRateLimits::new(NodeId::new("a"));
+        let b = SharedRateLimits::new(NodeId::new("b"));
+
+        advance(&a, "user-1", 1_000, 500);
+        advance(&b, "user-1", 1_000, 300);
+        advance(&b, "user-2", 1_000, 100);
+
+        a.merge(&b.snapshot(1_000));
+        b.merge(&a.snapshot(1_000));
+        assert_eq!(a.backlog("user-1", 1_000), 800);
+        assert_eq!(b.backlog("user-1", 1_000), 800);
+        assert_eq!(a.backlog("user-2", 1_000), 100);
+
+        // Stale snapshots never move a TAT backwards
+        let stale = RateLimitSnapshot {
+            node_id: NodeId::new("b"),
+            entries: vec![("user-1".into(), 1_100)],
+        };
+        a.merge(&stale);
+        assert_eq!(a.backlog("user-1", 1_000), 800);
+
+        // Peers' backlog is passed to the decision
+        let peer = a.update("user-1", 1_000, |_, peer| (None, peer));
+        assert_eq!(peer, 300);
+    }
+
+    #[test]
+    fn test_prune_and_remove_node() {
+        let a = SharedRateLimits::new(NodeId::new("a"));
+        let b = SharedRateLimits::new(NodeId::new("b"));
+        advance(&a, "user-1", 0, 100);
+        advance(&b, "user-2", 0, 1_000);
+        a.merge(&b.snapshot(0));
+        assert_eq!(a.len(), 2);
+
+        a.prune(500);
+        assert_eq!(a.len(), 1);
+        assert!(a.snapshot(500).entries.is_empty());
+
+        a.remove_node(&NodeId::new("b"));
+        assert!(a.is_empty());
+    }
+}
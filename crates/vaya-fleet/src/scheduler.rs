@@ -80,10 +80,86 @@ impl TaskStatus {
     pub fn is_active(&self) -> bool {
         matches!(self, TaskStatus::Queued | TaskStatus::Running)
     }
+
+    /// Get status code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::Queued => "queued",
+            TaskStatus::Running => "running",
+            TaskStatus::Completed => "completed",
+            TaskStatus::Failed => "failed",
+            TaskStatus::Cancelled => "cancelled",
+            TaskStatus::TimedOut => "timed_out",
+        }
+    }
+
+    /// Parse a status code
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(TaskStatus::Pending),
+            "queued" => Some(TaskStatus::Queued),
+            "running" => Some(TaskStatus::Running),
+            "completed" => Some(TaskStatus::Completed),
+            "failed" => Some(TaskStatus::Failed),
+            "cancelled" => Some(TaskStatus::Cancelled),
+            "timed_out" => Some(TaskStatus::TimedOut),
+            _ => None,
+        }
+    }
+}
+
+/// How a failed or timed out task is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub backoff_ms: u64,
+    /// Longest delay between retries
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff_ms: 1000,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Retry up to `max_retries` times, backing off exponentially from
+    /// `backoff_ms`
+    pub fn exponential(max_retries: u32, backoff_ms: u64) -> Self {
+        Self {
+            max_retries,
+            backoff_ms,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 1)
+    pub fn delay_ms(&self, retry: u32) -> u64 {
+        let doublings = retry.saturating_sub(1).min(32);
+        self.backoff_ms
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff_ms)
+    }
 }
 
 /// Task result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TaskResult {
     /// Task ID
     pub task_id: TaskId,
@@ -120,8 +196,10 @@ pub struct Task {
     pub timeout_ms: u64,
     /// Retry count
     pub retries: u32,
-    /// Max retries
-    pub max_retries: u32,
+    /// Retry policy
+    pub retry_policy: RetryPolicy,
+    /// Earliest start (Unix milliseconds) of a retry waiting out its backoff
+    pub not_before: Option<i64>,
     /// Dependencies (tasks that must complete first)
     pub dependencies: Vec<TaskId>,
 }
@@ -140,7 +218,8 @@ impl Task {
             started_at: None,
             timeout_ms: 30000,
             retries: 0,
-            max_retries: 3,
+            retry_policy: RetryPolicy::default(),
+            not_before: None,
             dependencies: Vec::new(),
         }
     }
@@ -159,7 +238,13 @@ impl Task {
 
     /// Set max retries
     pub fn with_max_retries(mut self, max_retries: u32) -> Self {
-        self.max_retries = max_retries;
+        self.retry_policy.max_retries = max_retries;
+        self
+    }
+
+    /// Set retry policy
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
         self
     }
 
@@ -179,6 +264,11 @@ impl Task {
         })
     }
 
+    /// Check if a retry's backoff has passed
+    pub fn is_due(&self, now_ms: i64) -> bool {
+        self.not_before.map_or(true, |at| at <= now_ms)
+    }
+
     /// Check if timed out
     pub fn is_timed_out(&self) -> bool {
         if let Some(started) = self.started_at {
@@ -294,26 +384,36 @@ impl Scheduler {
     }
 
     /// Get next task to execute
+    ///
+    /// Retries still backing off stay queued.
     pub fn next_task(&mut self) -> Option<&Task> {
-        // First check ready queue
-        while let Some(task_id) = self.ready_queue.pop_front() {
-            if let Some(task) = self.tasks.get(&task_id) {
-                if task.status == TaskStatus::Pending && task.can_run(&self.completed) {
-                    return Some(task);
-                }
+        let now = OffsetDateTime::now_utc().unix_timestamp_nanos() as i64 / 1_000_000;
+        let mut backing_off = Vec::new();
+
+        // First check ready queue, then priority queue
+        let next = loop {
+            let Some(task_id) = self
+                .ready_queue
+                .pop_front()
+                .or_else(|| self.priority_queue.pop().map(|pt| pt.task_id))
+            else {
+                break None;
+            };
+            let Some(task) = self.tasks.get(&task_id) else {
+                continue;
+            };
+            if task.status != TaskStatus::Pending || !task.can_run(&self.completed) {
+                continue;
             }
-        }
-
-        // Then check priority queue
-        while let Some(pt) = self.priority_queue.pop() {
-            if let Some(task) = self.tasks.get(&pt.task_id) {
-                if task.status == TaskStatus::Pending && task.can_run(&self.completed) {
-                    return Some(task);
-                }
+            if !task.is_due(now) {
+                backing_off.push(task_id);
+                continue;
             }
-        }
+            break Some(task_id);
+        };
 
-        None
+        self.ready_queue.extend(backing_off);
+        self.tasks.get(&next?)
     }
 
     /// Assign task to node
@@ -385,7 +485,7 @@ impl Scheduler {
                 reason: "Task not found".into(),
             })?;
 
-        if task.retries >= task.max_retries {
+        if task.retries >= task.retry_policy.max_retries {
             return Ok(false);
         }
 
@@ -393,6 +493,8 @@ impl Scheduler {
         task.status = TaskStatus::Pending;
         task.assigned_node = None;
         task.started_at = None;
+        let now = OffsetDateTime::now_utc().unix_timestamp_nanos() as i64 / 1_000_000;
+        task.not_before = Some(now + task.retry_policy.delay_ms(task.retries) as i64);
 
         // Re-queue
         self.ready_queue.push_back(task_id.clone());
@@ -401,11 +503,57 @@ impl Scheduler {
             "Task {} queued for retry ({}/{})",
             task_id.as_str(),
             task.retries,
-            task.max_retries
+            task.retry_policy.max_retries
         );
         Ok(true)
     }
 
+    /// Take up to `max` runnable tasks for `node_id`, as far as its
+    /// `max_tasks_per_node` allows
+    ///
+    /// Idle nodes call this to pull work instead of waiting for
+    /// [`Scheduler::schedule`] to push it to them. The tasks are assigned
+    /// to the node and returned for it to run.
+    pub fn steal(&mut self, node_id: &NodeId, max: usize) -> Vec<Task> {
+        let running = self.node_tasks.get(node_id).map_or(0, Vec::len);
+        let room = self
+            .config
+            .max_tasks_per_node
+            .saturating_sub(running)
+            .min(max);
+
+        let mut stolen = Vec::new();
+        while stolen.len() < room {
+            let Some(task) = self.next_task() else {
+                break;
+            };
+            let task_id = task.id.clone();
+            if self.assign(&task_id, node_id.clone()).is_ok() {
+                stolen.extend(self.tasks.get(&task_id).cloned());
+            }
+        }
+        stolen
+    }
+
+    /// Forget a task and its result
+    pub fn remove(&mut self, task_id: &TaskId) -> Option<Task> {
+        let task = self.tasks.remove(task_id)?;
+        if let Some(tasks) = task
+            .assigned_node
+            .as_ref()
+            .and_then(|node_id| self.node_tasks.get_mut(node_id))
+        {
+            tasks.retain(|t| t != task_id);
+        }
+        self.completed.remove(task_id);
+        Some(task)
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
     /// Get task
     pub fn get_task(&self, id: &TaskId) -> Option<&Task> {
         self.tasks.get(id)
@@ -530,11 +678,63 @@ mod tests {
         assert_eq!(scheduler.pending_count(), 1);
     }
 
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy::exponential(5, 100);
+        assert_eq!(policy.delay_ms(1), 100);
+        assert_eq!(policy.delay_ms(3), 400);
+        assert_eq!(policy.delay_ms(40), policy.max_backoff_ms);
+
+        let mut scheduler = Scheduler::new(SchedulerConfig::default());
+        let task = Task::new("test", vec![]).with_retry_policy(RetryPolicy::exponential(1, 60_000));
+        let id = scheduler.submit(task);
+        let node = NodeId::new("node-1");
+        assert_eq!(scheduler.steal(&node, 10).len(), 1);
+
+        assert!(scheduler.retry(&id).unwrap());
+        // Still backing off: queued, but not handed out
+        assert!(scheduler.steal(&node, 10).is_empty());
+        assert_eq!(scheduler.pending_count(), 1);
+        assert!(!scheduler.retry(&id).unwrap());
+    }
+
+    #[test]
+    fn test_steal_respects_capacity() {
+        let config = SchedulerConfig {
+            max_tasks_per_node: 2,
+            ..SchedulerConfig::default()
+        };
+        let mut scheduler = Scheduler::new(config);
+        let low = scheduler.submit(Task::new("low", vec![]).with_priority(TaskPriority::Low));
+        let high = scheduler.submit(Task::new("high", vec![]).with_priority(TaskPriority::High));
+        scheduler.submit(Task::new("normal", vec![]));
+
+        let node = NodeId::new("node-1");
+        let stolen = scheduler.steal(&node, 10);
+        assert_eq!(stolen.len(), 2);
+        assert_eq!(stolen[0].id, high);
+        assert_eq!(stolen[0].status, TaskStatus::Running);
+        assert_eq!(stolen[0].assigned_node, Some(node.clone()));
+        assert!(scheduler.steal(&node, 10).is_empty());
+
+        // Another node picks up the rest
+        let other = NodeId::new("node-2");
+        assert_eq!(scheduler.steal(&other, 10)[0].id, low);
+
+        assert!(scheduler.remove(&high).is_some());
+        assert_eq!(scheduler.tasks_for_node(&node).len(), 1);
+    }
+
     #[test]
     fn test_task_status() {
         assert!(TaskStatus::Completed.is_terminal());
         assert!(TaskStatus::Failed.is_terminal());
         assert!(!TaskStatus::Running.is_terminal());
         assert!(TaskStatus::Running.is_active());
+        assert_eq!(TaskStatus::parse("timed_out"), Some(TaskStatus::TimedOut));
+        assert_eq!(
+            TaskStatus::parse(TaskStatus::Cancelled.as_str()),
+            Some(TaskStatus::Cancelled)
+        );
     }
 }
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use time::OffsetDateTime;
+use tokio::sync::broadcast;
 
 use crate::{FleetError, FleetResult, NodeId};
 
@@ -36,6 +37,10 @@ pub struct ServiceEndpoint {
     pub last_check: i64,
     /// Metadata
     pub metadata: HashMap<String, String>,
+    /// Consecutive failed checks
+    failures: u32,
+    /// Consecutive passed checks
+    successes: u32,
 }
 
 impl ServiceEndpoint {
@@ -49,6 +54,8 @@ impl ServiceEndpoint {
             health: ServiceHealth::Unknown,
             last_check: 0,
             metadata: HashMap::new(),
+            failures: 0,
+            successes: 0,
         }
     }
 
@@ -76,8 +83,47 @@ impl ServiceEndpoint {
     }
 
     /// Check if endpoint is usable
+    ///
+    /// Degraded endpoints still take traffic, at a reduced share.
     pub fn is_usable(&self) -> bool {
-        matches!(self.health, ServiceHealth::Healthy | ServiceHealth::Unknown)
+        self.health != ServiceHealth::Unhealthy
+    }
+
+    /// Weight after accounting for health: full when healthy, half when
+    /// unchecked, a quarter when degraded and none when unhealthy
+    pub fn effective_weight(&self) -> u32 {
+        match self.health {
+            ServiceHealth::Healthy => self.weight,
+            ServiceHealth::Unknown => self.weight / 2,
+            ServiceHealth::Degraded => self.weight / 4,
+            ServiceHealth::Unhealthy => 0,
+        }
+    }
+
+    /// Apply the outcome of a check, returning the new health
+    fn record(&mut self, ok: bool, config: &ServiceConfig) -> ServiceHealth {
+        let health = if ok {
+            self.failures = 0;
+            self.successes += 1;
+            // A first answer is enough to trust an unchecked endpoint
+            if self.health == ServiceHealth::Unknown || self.successes >= config.healthy_threshold {
+                ServiceHealth::Healthy
+            } else {
+                self.health
+            }
+        } else {
+            self.successes = 0;
+            self.failures += 1;
+            if self.failures >= config.unhealthy_threshold {
+                ServiceHealth::Unhealthy
+            } else if self.health == ServiceHealth::Unhealthy {
+                self.health
+            } else {
+                ServiceHealth::Degraded
+            }
+        };
+        self.update_health(health);
+        health
     }
 }
 
@@ -163,6 +209,10 @@ impl Service {
     }
 
     /// Get next endpoint (weighted round-robin)
+    ///
+    /// Weights are scaled by health (see
+    /// [`ServiceEndpoint::effective_weight`]), so healthy endpoints take
+    /// most of the traffic.
     pub fn next_endpoint(&self) -> Option<&ServiceEndpoint> {
         static COUNTER: AtomicU64 = AtomicU64::new(0);
 
@@ -172,17 +222,17 @@ impl Service {
         }
 
         // Simple weighted selection
-        let total_weight: u32 = healthy.iter().map(|e| e.weight).sum();
+        let total_weight: u32 = healthy.iter().map(|e| e.effective_weight()).sum();
         if total_weight == 0 {
             return healthy.first().copied();
         }
 
         let counter = COUNTER.fetch_add(1, Ordering::SeqCst);
-        let selection = (counter as u32) % total_weight;
+        let selection = (counter % total_weight as u64) as u32;
 
         let mut cumulative = 0;
         for endpoint in &healthy {
-            cumulative += endpoint.weight;
+            cumulative += endpoint.effective_weight();
             if selection < cumulative {
                 return Some(endpoint);
             }
@@ -197,11 +247,35 @@ impl Service {
     }
 }
 
+/// Events buffered per subscriber before the oldest are dropped
+const EVENT_CAPACITY: usize = 256;
+
+/// A change to the registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryEvent {
+    /// A service was registered
+    Registered { service: String },
+    /// A service was deregistered
+    Deregistered { service: String },
+    /// An endpoint was added to a service
+    EndpointAdded { service: String, node_id: NodeId },
+    /// An endpoint was removed from a service
+    EndpointRemoved { service: String, node_id: NodeId },
+    /// An endpoint's health changed
+    HealthChanged {
+        service: String,
+        node_id: NodeId,
+        health: ServiceHealth,
+    },
+}
+
 /// Service registry
 #[derive(Debug)]
 pub struct ServiceRegistry {
     /// Registered services
     services: HashMap<String, Service>,
+    /// Change notifications
+    events: broadcast::Sender<RegistryEvent>,
 }
 
 impl ServiceRegistry {
@@ -209,19 +283,40 @@ impl ServiceRegistry {
     pub fn new() -> Self {
         Self {
             services: HashMap::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
         }
     }
 
+    /// Subscribe to changes
+    ///
+    /// Changes made through a [`Service`] borrowed with
+    /// [`get_mut`](Self::get_mut) are not announced. A subscriber that
+    /// falls more than a few hundred events behind misses the oldest.
+    pub fn subscribe(&self) -> broadcast::Receiver<RegistryEvent> {
+        self.events.subscribe()
+    }
+
+    fn notify(&self, event: RegistryEvent) {
+        // No subscribers is fine
+        let _ = self.events.send(event);
+    }
+
     /// Register service
     pub fn register(&mut self, service: Service) {
         tracing::info!("Registering service: {} v{}", service.name, service.version);
-        self.services.insert(service.name.clone(), service);
+        let name = service.name.clone();
+        self.services.insert(name.clone(), service);
+        self.notify(RegistryEvent::Registered { service: name });
     }
 
     /// Deregister service
     pub fn deregister(&mut self, name: &str) -> Option<Service> {
         tracing::info!("Deregistering service: {}", name);
-        self.services.remove(name)
+        let service = self.services.remove(name)?;
+        self.notify(RegistryEvent::Deregistered {
+            service: name.to_string(),
+        });
+        Some(service)
     }
 
     /// Get service
@@ -257,7 +352,12 @@ impl ServiceRegistry {
             .services
             .get_mut(service_name)
             .ok_or_else(|| FleetError::ServiceNotFound(service_name.to_string()))?;
+        let node_id = endpoint.node_id.clone();
         service.add_endpoint(endpoint);
+        self.notify(RegistryEvent::EndpointAdded {
+            service: service_name.to_string(),
+            node_id,
+        });
         Ok(())
     }
 
@@ -267,7 +367,14 @@ impl ServiceRegistry {
             .services
             .get_mut(service_name)
             .ok_or_else(|| FleetError::ServiceNotFound(service_name.to_string()))?;
+        let before = service.endpoints.len();
         service.remove_endpoint(node_id);
+        if service.endpoints.len() < before {
+            self.notify(RegistryEvent::EndpointRemoved {
+                service: service_name.to_string(),
+                node_id: node_id.clone(),
+            });
+        }
         Ok(())
     }
 
@@ -283,14 +390,55 @@ impl ServiceRegistry {
             .get_mut(service_name)
             .ok_or_else(|| FleetError::ServiceNotFound(service_name.to_string()))?;
 
-        for endpoint in &mut service.endpoints {
-            if &endpoint.node_id == node_id {
-                endpoint.update_health(health);
-                return Ok(());
-            }
+        let endpoint = service
+            .endpoints
+            .iter_mut()
+            .find(|e| &e.node_id == node_id)
+            .ok_or_else(|| FleetError::NodeNotFound(node_id.as_str().to_string()))?;
+        let previous = endpoint.health;
+        endpoint.update_health(health);
+        if health != previous {
+            self.notify(RegistryEvent::HealthChanged {
+                service: service_name.to_string(),
+                node_id: node_id.clone(),
+                health,
+            });
         }
+        Ok(())
+    }
 
-        Err(FleetError::NodeNotFound(node_id.as_str().to_string()))
+    /// Record a passed or failed health check of an endpoint, such as a
+    /// call to it, returning its health afterwards
+    ///
+    /// A failure degrades the endpoint, and the service's
+    /// `unhealthy_threshold` failures in a row make it unhealthy; its
+    /// `healthy_threshold` successes in a row make it healthy again.
+    pub fn record_check(
+        &mut self,
+        service_name: &str,
+        node_id: &NodeId,
+        ok: bool,
+    ) -> FleetResult<ServiceHealth> {
+        let service = self
+            .services
+            .get_mut(service_name)
+            .ok_or_else(|| FleetError::ServiceNotFound(service_name.to_string()))?;
+        let config = &service.config;
+        let endpoint = service
+            .endpoints
+            .iter_mut()
+            .find(|e| &e.node_id == node_id)
+            .ok_or_else(|| FleetError::NodeNotFound(node_id.as_str().to_string()))?;
+        let previous = endpoint.health;
+        let health = endpoint.record(ok, config);
+        if health != previous {
+            self.notify(RegistryEvent::HealthChanged {
+                service: service_name.to_string(),
+                node_id: node_id.clone(),
+                health,
+            });
+        }
+        Ok(health)
     }
 }
 
@@ -307,6 +455,8 @@ pub struct ServiceDiscovery {
     registry: ServiceRegistry,
     /// Local node ID
     local_node: NodeId,
+    /// Host other nodes reach local services at
+    local_address: String,
     /// Discovery configuration
     config: DiscoveryConfig,
 }
@@ -335,10 +485,27 @@ impl ServiceDiscovery {
         Self {
             registry: ServiceRegistry::new(),
             local_node,
+            local_address: "127.0.0.1".into(),
             config,
         }
     }
 
+    /// Set the host local services are registered at (default `127.0.0.1`)
+    pub fn with_local_address(mut self, address: impl Into<String>) -> Self {
+        self.local_address = address.into();
+        self
+    }
+
+    /// Local node ID
+    pub fn local_node(&self) -> &NodeId {
+        &self.local_node
+    }
+
+    /// Get configuration
+    pub fn config(&self) -> &DiscoveryConfig {
+        &self.config
+    }
+
     /// Get registry
     pub fn registry(&self) -> &ServiceRegistry {
         &self.registry
@@ -373,17 +540,13 @@ impl ServiceDiscovery {
 
     /// Register local service
     pub fn register_local(&mut self, service_name: &str, port: u16) -> FleetResult<()> {
-        let endpoint = ServiceEndpoint::new(self.local_node.clone(), "127.0.0.1", port);
+        let endpoint =
+            ServiceEndpoint::new(self.local_node.clone(), self.local_address.clone(), port);
 
-        if let Some(service) = self.registry.get_mut(service_name) {
-            service.add_endpoint(endpoint);
-        } else {
-            let mut service = Service::new(service_name, "1.0.0");
-            service.add_endpoint(endpoint);
-            self.registry.register(service);
+        if self.registry.get(service_name).is_none() {
+            self.registry.register(Service::new(service_name, "1.0.0"));
         }
-
-        Ok(())
+        self.registry.add_endpoint(service_name, endpoint)
     }
 
     /// Deregister local service
@@ -453,4 +616,62 @@ mod tests {
         let service = registry.get("api").unwrap();
         assert_eq!(service.endpoints[0].health, ServiceHealth::Healthy);
     }
+
+    #[test]
+    fn test_record_check() {
+        let mut registry = ServiceRegistry::new();
+        let mut events = registry.subscribe();
+        let a = NodeId::new("node-a");
+        let b = NodeId::new("node-b");
+        registry.register(Service::new("oracle-trainer", "1.0.0"));
+        for node in [&a, &b] {
+            registry
+                .add_endpoint(
+                    "oracle-trainer",
+                    ServiceEndpoint::new(node.clone(), "localhost", 9100),
+                )
+                .unwrap();
+        }
+
+        let check = |registry: &mut ServiceRegistry, node: &NodeId, ok: bool| {
+            registry.record_check("oracle-trainer", node, ok).unwrap()
+        };
+        assert_eq!(check(&mut registry, &a, true), ServiceHealth::Healthy);
+        assert_eq!(check(&mut registry, &b, false), ServiceHealth::Degraded);
+        assert_eq!(check(&mut registry, &b, false), ServiceHealth::Degraded);
+
+        let service = registry.get("oracle-trainer").unwrap();
+        // Healthy endpoints take the larger share (four to one over a
+        // full round, give or take other tests sharing the counter)
+        let on_a = (0..125)
+            .filter(|_| service.next_endpoint().unwrap().node_id == a)
+            .count();
+        assert!(on_a >= 90, "{}", on_a);
+
+        assert_eq!(check(&mut registry, &b, false), ServiceHealth::Unhealthy);
+        assert_eq!(check(&mut registry, &b, true), ServiceHealth::Unhealthy);
+        assert_eq!(check(&mut registry, &b, true), ServiceHealth::Healthy);
+        assert!(registry
+            .record_check("oracle-trainer", &NodeId::new("node-c"), true)
+            .is_err());
+
+        let mut changes = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let RegistryEvent::HealthChanged {
+                node_id, health, ..
+            } = event
+            {
+                changes.push((node_id, health));
+            }
+        }
+        assert_eq!(
+            changes,
+            [
+                (a.clone(), ServiceHealth::Healthy),
+                (b.clone(), ServiceHealth::Degraded),
+                (b.clone(), ServiceHealth::Unhealthy),
+                (b.clone(), ServiceHealth::Healthy),
+            ]
+        );
+    }
 }
@@ -0,0 +1,785 @@
+//! Cluster wire protocol
+//!
+//! Nodes talk to each other on their `bind_addr`, one request and one reply
+//! per connection. Every message is a frame: a big-endian `u32` length, the
+//! cluster name, a tag byte and the message's fields. Strings and byte
+//! strings are `u32`-length-prefixed; integers are big-endian. A node
+//! answers a frame for another cluster with [`Message::Rejected`].
+
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::consensus::{
+    AppendRequest, AppendResponse, LogEntry, MembershipChange, VoteRequest, VoteResponse,
+};
+use crate::discovery::Advertisement;
+use crate::jobs::{Assignment, JobRun};
+use crate::membership::{MemberHealth, MemberInfo, MembershipView};
+use crate::{FleetError, FleetResult, NodeId, RaftState, TaskId, TaskResult, TaskStatus};
+
+/// Largest frame accepted
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+/// A request or reply between nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Message {
+    /// Ask the leader to add a node
+    Join { node_id: NodeId, address: String },
+    /// Ask the leader to remove a node
+    Leave { node_id: NodeId },
+    /// Raft RequestVote
+    Vote(VoteRequest),
+    /// Raft AppendEntries, also the leader's heartbeat
+    Append(AppendRequest),
+    /// Ask for the receiver's view of the membership
+    Status,
+    /// Ask the leader for up to `capacity` queued jobs
+    Steal { node_id: NodeId, capacity: u32 },
+    /// Report a finished job run to the leader
+    Report(JobRun),
+    /// Ask for the services the receiver offers
+    Services,
+    /// The sender stopped offering its services
+    Withdraw { node_id: NodeId },
+    /// Join accepted: the leader's base configuration and every member's
+    /// address
+    Welcome {
+        leader: NodeId,
+        base: Vec<NodeId>,
+        members: Vec<(NodeId, String)>,
+    },
+    /// Not the leader; the leader, if known, with its address
+    Redirect(Option<(NodeId, String)>),
+    /// Can't be done right now; try again shortly
+    Busy(String),
+    /// Refused for good
+    Rejected(String),
+    /// Done
+    Ok,
+    /// Answer to [`Message::Vote`]
+    VoteReply(VoteResponse),
+    /// Answer to [`Message::Append`]
+    AppendReply(AppendResponse),
+    /// Answer to [`Message::Status`]
+    StatusReply(MembershipView),
+    /// Answer to [`Message::Steal`]
+    Tasks(Vec<Assignment>),
+    /// Answer to [`Message::Services`]
+    ServiceList(Vec<Advertisement>),
+}
+
+/// Send `message` to `addr` and wait for the reply
+pub(crate) async fn call(
+    addr: &str,
+    cluster: &str,
+    message: &Message,
+    timeout: Duration,
+) -> FleetResult<Message> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(addr)
+            .await
+            .map_err(|e| FleetError::NodeUnreachable(format!("{}: {}", addr, e)))?;
+        write_message(&mut stream, cluster, message).await?;
+        let (their_cluster, reply) = read_message(&mut stream).await?;
+        if their_cluster != cluster {
+            return Err(FleetError::JoinRejected(format!(
+                "{} belongs to cluster {}",
+                addr, their_cluster
+            )));
+        }
+        Ok(reply)
+    };
+    tokio::time::timeout(timeout, exchange)
+        .await
+        .map_err(|_| FleetError::NodeUnreachable(format!("{}: timed out", addr)))?
+}
+
+/// Write one frame
+pub(crate) async fn write_message(
+    stream: &mut TcpStream,
+    cluster: &str,
+    message: &Message,
+) -> FleetResult<()> {
+    let payload = encode(cluster, message);
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+    stream.write_all(&frame).await.map_err(network)?;
+    stream.flush().await.map_err(network)
+}
+
+/// Read one frame, returning the sender's cluster name and the message
+pub(crate) async fn read_message(stream: &mut TcpStream) -> FleetResult<(String, Message)> {
+    let mut len = [0u8; 4];
+    stream.read_exact(&mut len).await.map_err(network)?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME {
+        return Err(FleetError::NetworkError(format!(
+            "Frame of {} bytes exceeds the limit",
+            len
+        )));
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await.map_err(network)?;
+    decode(&payload)
+}
+
+fn network(e: std::io::Error) -> FleetError {
+    FleetError::NetworkError(e.to_string())
+}
+
+const TAG_JOIN: u8 = 1;
+const TAG_LEAVE: u8 = 2;
+const TAG_VOTE: u8 = 3;
+const TAG_APPEND: u8 = 4;
+const TAG_STATUS: u8 = 5;
+const TAG_WELCOME: u8 = 6;
+const TAG_REDIRECT: u8 = 7;
+const TAG_BUSY: u8 = 8;
+const TAG_REJECTED: u8 = 9;
+const TAG_OK: u8 = 10;
+const TAG_VOTE_REPLY: u8 = 11;
+const TAG_APPEND_REPLY: u8 = 12;
+const TAG_STATUS_REPLY: u8 = 13;
+const TAG_STEAL: u8 = 14;
+const TAG_REPORT: u8 = 15;
+const TAG_TASKS: u8 = 16;
+const TAG_SERVICES: u8 = 17;
+const TAG_WITHDRAW: u8 = 18;
+const TAG_SERVICE_LIST: u8 = 19;
+
+/// Encode a frame's payload
+fn encode(cluster: &str, message: &Message) -> Vec<u8> {
+    let mut w = Writer::default();
+    w.str(cluster);
+    match message {
+        Message::Join { node_id, address } => {
+            w.u8(TAG_JOIN);
+            w.str(node_id.as_str());
+            w.str(address);
+        }
+        Message::Leave { node_id } => {
+            w.u8(TAG_LEAVE);
+            w.str(node_id.as_str());
+        }
+        Message::Vote(req) => {
+            w.u8(TAG_VOTE);
+            w.u64(req.term);
+            w.str(req.candidate_id.as_str());
+            w.u64(req.last_log_index);
+            w.u64(req.last_log_term);
+        }
+        Message::Append(req) => {
+            w.u8(TAG_APPEND);
+            w.u64(req.term);
+            w.str(req.leader_id.as_str());
+            w.u64(req.prev_log_index);
+            w.u64(req.prev_log_term);
+            w.u64(req.leader_commit);
+            w.u32(req.entries.len() as u32);
+            for entry in &req.entries {
+                w.entry(entry);
+            }
+        }
+        Message::Status => w.u8(TAG_STATUS),
+        Message::Welcome {
+            leader,
+            base,
+            members,
+        } => {
+            w.u8(TAG_WELCOME);
+            w.str(leader.as_str());
+            w.u32(base.len() as u32);
+            for id in base {
+                w.str(id.as_str());
+            }
+            w.u32(members.len() as u32);
+            for (id, address) in members {
+                w.str(id.as_str());
+                w.str(address);
+            }
+        }
+        Message::Redirect(leader) => {
+            w.u8(TAG_REDIRECT);
+            match leader {
+                Some((id, address)) => {
+                    w.u8(1);
+                    w.str(id.as_str());
+                    w.str(address);
+                }
+                None => w.u8(0),
+            }
+        }
+        Message::Busy(reason) => {
+            w.u8(TAG_BUSY);
+            w.str(reason);
+        }
+        Message::Rejected(reason) => {
+            w.u8(TAG_REJECTED);
+            w.str(reason);
+        }
+        Message::Ok => w.u8(TAG_OK),
+        Message::VoteReply(resp) => {
+            w.u8(TAG_VOTE_REPLY);
+            w.u64(resp.term);
+            w.u8(resp.vote_granted as u8);
+        }
+        Message::AppendReply(resp) => {
+            w.u8(TAG_APPEND_REPLY);
+            w.u64(resp.term);
+            w.u8(resp.success as u8);
+            w.u64(resp.match_index);
+        }
+        Message::StatusReply(view) => {
+            w.u8(TAG_STATUS_REPLY);
+            w.view(view);
+        }
+        Message::Steal { node_id, capacity } => {
+            w.u8(TAG_STEAL);
+            w.str(node_id.as_str());
+            w.u32(*capacity);
+        }
+        Message::Report(run) => {
+            w.u8(TAG_REPORT);
+            w.run(run);
+        }
+        Message::Tasks(assignments) => {
+            w.u8(TAG_TASKS);
+            w.u32(assignments.len() as u32);
+            for assignment in assignments {
+                w.str(assignment.task_id.as_str());
+                w.str(&assignment.job);
+                w.bytes(&assignment.payload);
+                w.u64(assignment.timeout_ms);
+                w.u32(assignment.attempt);
+            }
+        }
+        Message::Services => w.u8(TAG_SERVICES),
+        Message::Withdraw { node_id } => {
+            w.u8(TAG_WITHDRAW);
+            w.str(node_id.as_str());
+        }
+        Message::ServiceList(advertised) => {
+            w.u8(TAG_SERVICE_LIST);
+            w.u32(advertised.len() as u32);
+            for ad in advertised {
+                w.advertisement(ad);
+            }
+        }
+    }
+    w.0
+}
+
+/// Decode a frame's payload
+fn decode(payload: &[u8]) -> FleetResult<(String, Message)> {
+    let mut r = Reader { data: payload };
+    let cluster = r.str()?;
+    let message = match r.u8()? {
+        TAG_JOIN => Message::Join {
+            node_id: r.node_id()?,
+            address: r.str()?,
+        },
+        TAG_LEAVE => Message::Leave {
+            node_id: r.node_id()?,
+        },
+        TAG_VOTE => Message::Vote(VoteRequest {
+            term: r.u64()?,
+            candidate_id: r.node_id()?,
+            last_log_index: r.u64()?,
+            last_log_term: r.u64()?,
+        }),
+        TAG_APPEND => {
+            let term = r.u64()?;
+            let leader_id = r.node_id()?;
+            let prev_log_index = r.u64()?;
+            let prev_log_term = r.u64()?;
+            let leader_commit = r.u64()?;
+            let entries = (0..r.count()?)
+                .map(|_| r.entry())
+                .collect::<FleetResult<_>>()?;
+            Message::Append(AppendRequest {
+                term,
+                leader_id,
+                prev_log_index,
+                prev_log_term,
+                entries,
+                leader_commit,
+            })
+        }
+        TAG_STATUS => Message::Status,
+        TAG_WELCOME => {
+            let leader = r.node_id()?;
+            let base = (0..r.count()?)
+                .map(|_| r.node_id())
+                .collect::<FleetResult<_>>()?;
+            let members = (0..r.count()?)
+                .map(|_| Ok((r.node_id()?, r.str()?)))
+                .collect::<FleetResult<_>>()?;
+            Message::Welcome {
+                leader,
+                base,
+                members,
+            }
+        }
+        TAG_REDIRECT => Message::Redirect(match r.u8()? {
+            0 => None,
+            _ => Some((r.node_id()?, r.str()?)),
+        }),
+        TAG_BUSY => Message::Busy(r.str()?),
+        TAG_REJECTED => Message::Rejected(r.str()?),
+        TAG_OK => Message::Ok,
+        TAG_VOTE_REPLY => Message::VoteReply(VoteResponse {
+            term: r.u64()?,
+            vote_granted: r.u8()? != 0,
+        }),
+        TAG_APPEND_REPLY => Message::AppendReply(AppendResponse {
+            term: r.u64()?,
+            success: r.u8()? != 0,
+            match_index: r.u64()?,
+        }),
+        TAG_STATUS_REPLY => Message::StatusReply(r.view()?),
+        TAG_STEAL => Message::Steal {
+            node_id: r.node_id()?,
+            capacity: r.u32()?,
+        },
+        TAG_REPORT => Message::Report(r.run()?),
+        TAG_TASKS => Message::Tasks(
+            (0..r.count()?)
+                .map(|_| {
+                    Ok(Assignment {
+                        task_id: TaskId(r.str()?),
+                        job: r.str()?,
+                        payload: r.bytes()?,
+                        timeout_ms: r.u64()?,
+                        attempt: r.u32()?,
+                    })
+                })
+                .collect::<FleetResult<_>>()?,
+        ),
+        TAG_SERVICES => Message::Services,
+        TAG_WITHDRAW => Message::Withdraw {
+            node_id: r.node_id()?,
+        },
+        TAG_SERVICE_LIST => Message::ServiceList(
+            (0..r.count()?)
+                .map(|_| r.advertisement())
+                .collect::<FleetResult<_>>()?,
+        ),
+        tag => {
+            return Err(FleetError::NetworkError(format!(
+                "Unknown message tag {}",
+                tag
+            )))
+        }
+    };
+    if !r.data.is_empty() {
+        return Err(malformed());
+    }
+    Ok((cluster, message))
+}
+
+fn malformed() -> FleetError {
+    FleetError::NetworkError("Malformed message".into())
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn i64(&mut self, v: i64) {
+        self.0.extend_from_slice(&v.to_be_bytes());
+    }
+
+    fn bytes(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.0.extend_from_slice(v);
+    }
+
+    fn str(&mut self, v: &str) {
+        self.bytes(v.as_bytes());
+    }
+
+    fn entry(&mut self, entry: &LogEntry) {
+        self.u64(entry.term);
+        self.u64(entry.index);
+        self.bytes(&entry.command);
+        match &entry.membership {
+            None => self.u8(0),
+            Some(MembershipChange::Add(id, address)) => {
+                self.u8(1);
+                self.str(id.as_str());
+                self.str(address);
+            }
+            Some(MembershipChange::Remove(id)) => {
+                self.u8(2);
+                self.str(id.as_str());
+            }
+        }
+    }
+
+    fn run(&mut self, run: &JobRun) {
+        self.str(&run.job);
+        self.str(run.node_id.as_str());
+        self.u32(run.attempt);
+        self.i64(run.started_at);
+        self.str(run.result.task_id.as_str());
+        self.str(run.result.status.as_str());
+        match &run.result.data {
+            Some(data) => {
+                self.u8(1);
+                self.bytes(data);
+            }
+            None => self.u8(0),
+        }
+        match &run.result.error {
+            Some(error) => {
+                self.u8(1);
+                self.str(error);
+            }
+            None => self.u8(0),
+        }
+        self.u64(run.result.execution_time_ms);
+    }
+
+    fn advertisement(&mut self, ad: &Advertisement) {
+        self.str(&ad.service);
+        self.str(&ad.version);
+        self.u32(ad.tags.len() as u32);
+        for tag in &ad.tags {
+            self.str(tag);
+        }
+        self.str(&ad.address);
+        self.u16(ad.port);
+        self.u32(ad.weight);
+        self.u32(ad.metadata.len() as u32);
+        for (key, value) in &ad.metadata {
+            self.str(key);
+            self.str(value);
+        }
+    }
+
+    fn view(&mut self, view: &MembershipView) {
+        self.str(view.node_id.as_str());
+        self.u8(match view.state {
+            RaftState::Follower => 0,
+            RaftState::Candidate => 1,
+            RaftState::Leader => 2,
+        });
+        self.u64(view.term);
+        match &view.leader {
+            Some(id) => {
+                self.u8(1);
+                self.str(id.as_str());
+            }
+            None => self.u8(0),
+        }
+        self.u64(view.commit_index);
+        self.u8(view.change_pending as u8);
+        self.u32(view.members.len() as u32);
+        for member in &view.members {
+            self.str(member.id.as_str());
+            self.str(&member.address);
+            self.u8(member.is_leader as u8);
+            self.u8(match member.health {
+                MemberHealth::Alive => 0,
+                MemberHealth::Suspect => 1,
+                MemberHealth::Unreachable => 2,
+                MemberHealth::Unknown => 3,
+            });
+            match member.last_seen_ms {
+                Some(ms) => {
+                    self.u8(1);
+                    self.i64(ms);
+                }
+                None => self.u8(0),
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> FleetResult<&'a [u8]> {
+        if self.data.len() < n {
+            return Err(malformed());
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> FleetResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> FleetResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> FleetResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> FleetResult<u64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    fn i64(&mut self) -> FleetResult<i64> {
+        Ok(self.u64()? as i64)
+    }
+
+    /// A collection length, bounded by the bytes left
+    fn count(&mut self) -> FleetResult<usize> {
+        let count = self.u32()? as usize;
+        if count > self.data.len() {
+            return Err(malformed());
+        }
+        Ok(count)
+    }
+
+    fn bytes(&mut self) -> FleetResult<Vec<u8>> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn str(&mut self) -> FleetResult<String> {
+        String::from_utf8(self.bytes()?).map_err(|_| malformed())
+    }
+
+    fn node_id(&mut self) -> FleetResult<NodeId> {
+        self.str().map(NodeId)
+    }
+
+    fn entry(&mut self) -> FleetResult<LogEntry> {
+        Ok(LogEntry {
+            term: self.u64()?,
+            index: self.u64()?,
+            command: self.bytes()?,
+            membership: match self.u8()? {
+                0 => None,
+                1 => Some(MembershipChange::Add(self.node_id()?, self.str()?)),
+                2 => Some(MembershipChange::Remove(self.node_id()?)),
+                _ => return Err(malformed()),
+            },
+        })
+    }
+
+    fn run(&mut self) -> FleetResult<JobRun> {
+        Ok(JobRun {
+            job: self.str()?,
+            node_id: self.node_id()?,
+            attempt: self.u32()?,
+            started_at: self.i64()?,
+            result: TaskResult {
+                task_id: TaskId(self.str()?),
+                status: TaskStatus::parse(&self.str()?).ok_or_else(malformed)?,
+                data: match self.u8()? {
+                    0 => None,
+                    _ => Some(self.bytes()?),
+                },
+                error: match self.u8()? {
+                    0 => None,
+                    _ => Some(self.str()?),
+                },
+                execution_time_ms: self.u64()?,
+            },
+        })
+    }
+
+    fn advertisement(&mut self) -> FleetResult<Advertisement> {
+        Ok(Advertisement {
+            service: self.str()?,
+            version: self.str()?,
+            tags: (0..self.count()?)
+                .map(|_| self.str())
+                .collect::<FleetResult<_>>()?,
+            address: self.str()?,
+            port: self.u16()?,
+            weight: self.u32()?,
+            metadata: (0..self.count()?)
+                .map(|_| Ok((self.str()?, self.str()?)))
+                .collect::<FleetResult<_>>()?,
+        })
+    }
+
+    fn view(&mut self) -> FleetResult<MembershipView> {
+        let node_id = self.node_id()?;
+        let state = match self.u8()? {
+            0 => RaftState::Follower,
+            1 => RaftState::Candidate,
+            2 => RaftState::Leader,
+            _ => return Err(malformed()),
+        };
+        let term = self.u64()?;
+        let leader = match self.u8()? {
+            0 => None,
+            _ => Some(self.node_id()?),
+        };
+        let commit_index = self.u64()?;
+        let change_pending = self.u8()? != 0;
+        let members = (0..self.count()?)
+            .map(|_| {
+                Ok(MemberInfo {
+                    id: self.node_id()?,
+                    address: self.str()?,
+                    is_leader: self.u8()? != 0,
+                    health: match self.u8()? {
+                        0 => MemberHealth::Alive,
+                        1 => MemberHealth::Suspect,
+                        2 => MemberHealth::Unreachable,
+                        3 => MemberHealth::Unknown,
+                        _ => return Err(malformed()),
+                    },
+                    last_seen_ms: match self.u8()? {
+                        0 => None,
+                        _ => Some(self.i64()?),
+                    },
+                })
+            })
+            .collect::<FleetResult<_>>()?;
+        Ok(MembershipView {
+            node_id,
+            state,
+            term,
+            leader,
+            commit_index,
+            change_pending,
+            members,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: Message) {
+        let (cluster, decoded) = decode(&encode("vaya", &message)).unwrap();
+        assert_eq!(cluster, "vaya");
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(Message::Join {
+            node_id: NodeId::new("node-2"),
+            address: "10.0.0.2:7000".into(),
+        });
+        round_trip(Message::Append(AppendRequest {
+            term: 3,
+            leader_id: NodeId::new("node-1"),
+            prev_log_index: 4,
+            prev_log_term: 2,
+            entries: vec![
+                LogEntry {
+                    term: 3,
+                    index: 5,
+                    command: b"set x".to_vec(),
+                    membership: None,
+                },
+                LogEntry {
+                    term: 3,
+                    index: 6,
+                    command: Vec::new(),
+                    membership: Some(MembershipChange::Remove(NodeId::new("node-3"))),
+                },
+            ],
+            leader_commit: 4,
+        }));
+        round_trip(Message::Welcome {
+            leader: NodeId::new("node-1"),
+            base: vec![NodeId::new("node-1")],
+            members: vec![(NodeId::new("node-2"), "10.0.0.2:7000".into())],
+        });
+        round_trip(Message::Redirect(None));
+        round_trip(Message::ServiceList(vec![Advertisement {
+            service: "oracle-trainer".into(),
+            version: "1.0.0".into(),
+            tags: vec!["internal".into()],
+            address: "10.0.0.2".into(),
+            port: 9100,
+            weight: 100,
+            metadata: vec![("zone".into(), "sin-1".into())],
+        }]));
+        round_trip(Message::Tasks(vec![Assignment {
+            task_id: TaskId::new("task-1"),
+            job: "expire_pools".into(),
+            payload: vec![1, 2],
+            timeout_ms: 30_000,
+            attempt: 2,
+        }]));
+        round_trip(Message::Report(JobRun {
+            job: "expire_pools".into(),
+            node_id: NodeId::new("node-2"),
+            attempt: 2,
+            started_at: 1_792_143_005_000,
+            result: TaskResult {
+                task_id: TaskId::new("task-1"),
+                status: TaskStatus::Failed,
+                data: None,
+                error: Some("pool store unavailable".into()),
+                execution_time_ms: 12,
+            },
+        }));
+        round_trip(Message::StatusReply(MembershipView {
+            node_id: NodeId::new("node-1"),
+            state: RaftState::Leader,
+            term: 7,
+            leader: Some(NodeId::new("node-1")),
+            commit_index: 12,
+            change_pending: true,
+            members: vec![MemberInfo {
+                id: NodeId::new("node-2"),
+                address: "10.0.0.2:7000".into(),
+                is_leader: false,
+                health: MemberHealth::Suspect,
+                last_seen_ms: Some(1_792_143_005_000),
+            }],
+        }));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let payload = encode(
+            "vaya",
+            &Message::Leave {
+                node_id: NodeId::new("node-2"),
+            },
+        );
+        assert!(decode(&payload[..payload.len() - 1]).is_err());
+        assert!(decode(&[payload.as_slice(), &[0]].concat()).is_err());
+
+        // Counts larger than the frame are refused before allocating
+        let mut w = Writer::default();
+        w.str("vaya");
+        w.u8(TAG_WELCOME);
+        w.str("node-1");
+        w.u32(u32::MAX);
+        assert!(decode(&w.0).is_err());
+    }
+}
@@ -0,0 +1,705 @@
+//! Multi-provider aggregation and failover
+//!
+//! [`GdsAggregator`] wraps several [`GdsProvider`]s behind the same trait.
+//! Searches fan out to every healthy provider concurrently and the results
+//! are merged, keeping the cheapest offer for each itinerary. Offer IDs are
+//! prefixed with the provider name so pricing and booking go back to the
+//! provider that produced the offer. PNR operations go to the provider that
+//! created the booking, and operations that are not tied to a provider
+//! fall back through the providers in priority order.
+//!
+//! A provider is skipped while its last `health_check()` failed; health is
+//! cached for [`AggregatorConfig::health_ttl`]. Retryable errors (timeouts,
+//! rate limits, outages) are retried on the same provider before giving up
+//! on it.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use tokio::task::JoinSet;
+use tracing::{debug, warn};
+
+use crate::error::{GdsError, GdsResult};
+use crate::traits::{AirportInfo, GdsProvider};
+use crate::types::{
+    BookingConfirmation, ContactDetails, FlightOffer, FlightSearchRequest, Itinerary,
+    PassengerDetails, SeatMap,
+};
+
+/// Separator between provider name and provider offer ID
+const OFFER_ID_SEPARATOR: char = ':';
+
+/// Aggregator configuration
+#[derive(Debug, Clone)]
+pub struct AggregatorConfig {
+    /// Per-provider search timeout
+    pub search_timeout: Duration,
+    /// How long a health check result is trusted
+    pub health_ttl: Duration,
+    /// Attempts per provider for retryable errors
+    pub max_attempts: u32,
+    /// Delay between attempts (multiplied by the attempt number)
+    pub retry_backoff: Duration,
+}
+
+impl Default for AggregatorConfig {
+    fn default() -> Self {
+        Self {
+            search_timeout: Duration::from_secs(15),
+            health_ttl: Duration::from_secs(30),
+            max_attempts: 2,
+            retry_backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+/// A provider that failed during an aggregated call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderFailure {
+    /// Provider name
+    pub provider: &'static str,
+    /// Error message
+    pub error: String,
+}
+
+/// Merged search results
+#[derive(Debug, Clone)]
+pub struct AggregatedSearch {
+    /// Deduplicated offers, IDs prefixed with the provider name
+    pub offers: Vec<FlightOffer>,
+    /// Providers that returned results
+    pub providers: Vec<&'static str>,
+    /// Providers that were skipped or failed
+    pub failures: Vec<ProviderFailure>,
+}
+
+struct Member {
+    provider: Arc<dyn GdsProvider>,
+    health: Mutex<Option<(bool, Instant)>>,
+}
+
+impl Member {
+    async fn is_healthy(&self, ttl: Duration) -> bool {
+        if let Some((healthy, checked)) = *self.health.lock() {
+            if checked.elapsed() < ttl {
+                return healthy;
+            }
+        }
+        let healthy = self.provider.health_check().await;
+        if !healthy {
+            warn!(
+                provider = self.provider.provider_name(),
+                "GDS provider failed health check"
+            );
+        }
+        *self.health.lock() = Some((healthy, Instant::now()));
+        healthy
+    }
+
+    fn mark_unhealthy(&self) {
+        *self.health.lock() = Some((false, Instant::now()));
+    }
+}
+
+/// Fans out to several GDS providers with deduplication and failover
+pub struct GdsAggregator {
+    members: Vec<Arc<Member>>,
+    config: AggregatorConfig,
+    pnr_owners: Mutex<HashMap<String, usize>>,
+}
+
+impl GdsAggregator {
+    /// Create an aggregator; providers are in priority order, primary first
+    #[must_use]
+    pub fn new(providers: Vec<Arc<dyn GdsProvider>>) -> Self {
+        Self {
+            members: providers
+                .into_iter()
+                .map(|provider| {
+                    Arc::new(Member {
+                        provider,
+                        health: Mutex::new(None),
+                    })
+                })
+                .collect(),
+            config: AggregatorConfig::default(),
+            pnr_owners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set configuration
+    #[must_use]
+    pub fn with_config(mut self, config: AggregatorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Provider names in priority order
+    #[must_use]
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.members
+            .iter()
+            .map(|m| m.provider.provider_name())
+            .collect()
+    }
+
+    /// Search all healthy providers concurrently and merge the results
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if no provider produced results.
+    pub async fn search(&self, request: &FlightSearchRequest) -> GdsResult<AggregatedSearch> {
+        let mut tasks = JoinSet::new();
+        for (index, member) in self.members.iter().enumerate() {
+            let member = Arc::clone(member);
+            let request = request.clone();
+            let config = self.config.clone();
+            tasks.spawn(async move {
+                let result = search_one(&member, &request, &config).await;
+                (index, result)
+            });
+        }
+
+        let mut results: Vec<(usize, GdsResult<Vec<FlightOffer>>)> = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(result) => results.push(result),
+                Err(e) => warn!(error = %e, "GDS search task panicked"),
+            }
+        }
+        // Merge in priority order so ties go to the primary provider
+        results.sort_by_key(|(index, _)| *index);
+
+        let mut search = AggregatedSearch {
+            offers: Vec::new(),
+            providers: Vec::new(),
+            failures: Vec::new(),
+        };
+        let mut by_itinerary: HashMap<String, usize> = HashMap::new();
+        for (index, result) in results {
+            let name = self.members[index].provider.provider_name();
+            match result {
+                Ok(offers) => {
+                    search.providers.push(name);
+                    for mut offer in offers {
+                        offer.id = format!("{name}{OFFER_ID_SEPARATOR}{}", offer.id);
+                        let key = itinerary_key(&offer);
+                        match by_itinerary.get(&key) {
+                            Some(&existing) if cheaper(&offer, &search.offers[existing]) => {
+                                search.offers[existing] = offer;
+                            }
+                            Some(_) => {}
+                            None => {
+                                by_itinerary.insert(key, search.offers.len());
+                                search.offers.push(offer);
+                            }
+                        }
+                    }
+                }
+                Err(e) => search.failures.push(ProviderFailure {
+                    provider: name,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
+        if search.providers.is_empty() {
+            return Err(GdsError::ServiceUnavailable(format!(
+                "All GDS providers failed: {}",
+                describe(&search.failures)
+            )));
+        }
+        debug!(
+            offers = search.offers.len(),
+            providers = ?search.providers,
+            failed = search.failures.len(),
+            "Aggregated GDS search"
+        );
+        Ok(search)
+    }
+
+    /// Find the member that produced an aggregated offer ID
+    fn offer_owner<'a>(&self, offer_id: &'a str) -> GdsResult<(usize, &'a str)> {
+        let (name, raw_id) = offer_id
+            .split_once(OFFER_ID_SEPARATOR)
+            .ok_or_else(|| GdsError::InvalidRequest(format!("Unknown offer ID: {offer_id}")))?;
+        self.members
+            .iter()
+            .position(|m| m.provider.provider_name() == name)
+            .map(|index| (index, raw_id))
+            .ok_or_else(|| GdsError::InvalidRequest(format!("Unknown GDS provider: {name}")))
+    }
+
+    /// Find the member holding a PNR, asking each provider if unknown
+    async fn pnr_owner(&self, pnr: &str) -> GdsResult<usize> {
+        if let Some(&index) = self.pnr_owners.lock().get(pnr) {
+            return Ok(index);
+        }
+        let mut failures = Vec::new();
+        for (index, member) in self.members.iter().enumerate() {
+            if !member.is_healthy(self.config.health_ttl).await {
+                continue;
+            }
+            match with_retry(member, &self.config, || member.provider.get_booking(pnr)).await {
+                Ok(_) => {
+                    self.pnr_owners.lock().insert(pnr.to_string(), index);
+                    return Ok(index);
+                }
+                Err(e) => failures.push(ProviderFailure {
+                    provider: member.provider.provider_name(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Err(GdsError::NotFound {
+            resource: "booking".to_string(),
+            id: format!("{pnr} ({})", describe(&failures)),
+        })
+    }
+}
+
+#[async_trait]
+impl GdsProvider for GdsAggregator {
+    async fn search_flights(&self, request: &FlightSearchRequest) -> GdsResult<Vec<FlightOffer>> {
+        Ok(self.search(request).await?.offers)
+    }
+
+    async fn price_offer(&self, offer_id: &str) -> GdsResult<FlightOffer> {
+        let (index, raw_id) = self.offer_owner(offer_id)?;
+        let member = &self.members[index];
+        let mut offer =
+            with_retry(member, &self.config, || member.provider.price_offer(raw_id)).await?;
+        offer.id = offer_id.to_string();
+        Ok(offer)
+    }
+
+    async fn seat_maps(&self, pnr: &str) -> GdsResult<Vec<SeatMap>> {
+        let index = self.pnr_owner(pnr).await?;
+        let member = &self.members[index];
+        with_retry(member, &self.config, || member.provider.seat_maps(pnr)).await
+    }
+
+    async fn create_booking(
+        &self,
+        offer_id: &str,
+        passengers: &[PassengerDetails],
+        contact: &ContactDetails,
+    ) -> GdsResult<BookingConfirmation> {
+        let (index, raw_id) = self.offer_owner(offer_id)?;
+        // Not retried: a timed-out booking may still have been created
+        let mut confirmation = self.members[index]
+            .provider
+            .create_booking(raw_id, passengers, contact)
+            .await?;
+        confirmation.offer_id = offer_id.to_string();
+        self.pnr_owners
+            .lock()
+            .insert(confirmation.pnr.clone(), index);
+        Ok(confirmation)
+    }
+
+    async fn issue_ticket(&self, pnr: &str) -> GdsResult<BookingConfirmation> {
+        let index = self.pnr_owner(pnr).await?;
+        self.members[index].provider.issue_ticket(pnr).await
+    }
+
+    async fn cancel_booking(&self, pnr: &str) -> GdsResult<()> {
+        let index = self.pnr_owner(pnr).await?;
+        self.members[index].provider.cancel_booking(pnr).await
+    }
+
+    async fn get_booking(&self, pnr: &str) -> GdsResult<BookingConfirmation> {
+        let index = self.pnr_owner(pnr).await?;
+        let member = &self.members[index];
+        with_retry(member, &self.config, || member.provider.get_booking(pnr)).await
+    }
+
+    async fn reprice_booking(&self, pnr: &str, offer_id: &str) -> GdsResult<FlightOffer> {
+        let index = self.pnr_owner(pnr).await?;
+        let (offer_index, raw_id) = self.offer_owner(offer_id)?;
+        // An exchange stays with the GDS holding the booking
+        if offer_index != index {
+            return Err(GdsError::InvalidRequest(format!(
+                "Offer {offer_id} is not from the GDS holding booking {pnr}"
+            )));
+        }
+        let member = &self.members[index];
+        let mut offer = with_retry(member, &self.config, || {
+            member.provider.reprice_booking(pnr, raw_id)
+        })
+        .await?;
+        offer.id = offer_id.to_string();
+        Ok(offer)
+    }
+
+    async fn search_airports(&self, query: &str) -> GdsResult<Vec<AirportInfo>> {
+        let mut failures = Vec::new();
+        for member in &self.members {
+            if !member.is_healthy(self.config.health_ttl).await {
+                failures.push(ProviderFailure {
+                    provider: member.provider.provider_name(),
+                    error: "unhealthy".to_string(),
+                });
+                continue;
+            }
+            match with_retry(member, &self.config, || {
+                member.provider.search_airports(query)
+            })
+            .await
+            {
+                Ok(airports) => return Ok(airports),
+                Err(e) => failures.push(ProviderFailure {
+                    provider: member.provider.provider_name(),
+                    error: e.to_string(),
+                }),
+            }
+        }
+        Err(GdsError::ServiceUnavailable(format!(
+            "All GDS providers failed: {}",
+            describe(&failures)
+        )))
+    }
+
+    async fn health_check(&self) -> bool {
+        for member in &self.members {
+            if member.is_healthy(self.config.health_ttl).await {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "Aggregator"
+    }
+}
+
+/// Search one provider, skipping it when unhealthy
+async fn search_one(
+    member: &Member,
+    request: &FlightSearchRequest,
+    config: &AggregatorConfig,
+) -> GdsResult<Vec<FlightOffer>> {
+    if !member.is_healthy(config.health_ttl).await {
+        return Err(GdsError::ServiceUnavailable(
+            "failed health check".to_string(),
+        ));
+    }
+    with_retry(member, config, || async {
+        tokio::time::timeout(
+            config.search_timeout,
+            member.provider.search_flights(request),
+        )
+        .await
+        .unwrap_or(Err(GdsError::Timeout {
+            timeout_secs: config.search_timeout.as_secs(),
+        }))
+    })
+    .await
+}
+
+/// Run an operation, retrying retryable errors on the same provider
+///
+/// A provider that is still failing after the last attempt is marked
+/// unhealthy so later calls skip it until its health is rechecked.
+async fn with_retry<T, F, Fut>(member: &Member, config: &AggregatorConfig, op: F) -> GdsResult<T>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = GdsResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if e.is_retryable() && attempt < config.max_attempts => {
+                debug!(
+                    provider = member.provider.provider_name(),
+                    attempt,
+                    error = %e,
+                    "Retrying GDS call"
+                );
+                tokio::time::sleep(config.retry_backoff * attempt).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                if e.is_retryable() {
+                    member.mark_unhealthy();
+                }
+                return Err(e);
+            }
+            ok => return ok,
+        }
+    }
+}
+
+/// Key identifying the same flights regardless of provider
+fn itinerary_key(offer: &FlightOffer) -> String {
+    fn push_itinerary(key: &mut String, itinerary: &Itinerary) {
+        for segment in &itinerary.segments {
+            let _ = write!(
+                key,
+                "{}{}@{}-{}/{}|",
+                segment.airline,
+                segment.flight_number,
+                segment.departure.airport.as_str(),
+                segment.arrival.airport.as_str(),
+                segment.departure.datetime.as_unix()
+            );
+        }
+    }
+    let mut key = String::new();
+    push_itinerary(&mut key, &offer.outbound);
+    if let Some(inbound) = &offer.return_itinerary {
+        key.push_str("||");
+        push_itinerary(&mut key, inbound);
+    }
+    key
+}
+
+/// Whether `offer` beats `existing`; prices in different currencies are
+/// not compared and the earlier (higher priority) offer is kept
+fn cheaper(offer: &FlightOffer, existing: &FlightOffer) -> bool {
+    offer.price.total.currency == existing.price.total.currency
+        && offer.price.total.amount.as_i64() < existing.price.total.amount.as_i64()
+}
+
+fn describe(failures: &[ProviderFailure]) -> String {
+    failures
+        .iter()
+        .map(|f| format!("{}: {}", f.provider, f.error))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BookingStatus, CabinClass, FlightPoint, FlightSegment, PriceBreakdown};
+    use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+    use vaya_common::{AirlineCode, CurrencyCode, Date, IataCode, MinorUnits, Price, Timestamp};
+
+    struct TestProvider {
+        name: &'static str,
+        /// (offer ID, flight number, price)
+        offers: Vec<(&'static str, &'static str, i64)>,
+        healthy: AtomicBool,
+        /// Retryable failures to return before succeeding
+        transient_failures: AtomicU32,
+        calls: AtomicU32,
+    }
+
+    impl TestProvider {
+        fn new(name: &'static str, offers: Vec<(&'static str, &'static str, i64)>) -> Self {
+            Self {
+                name,
+                offers,
+                healthy: AtomicBool::new(true),
+                transient_failures: AtomicU32::new(0),
+                calls: AtomicU32::new(0),
+            }
+        }
+
+        fn offer(id: &str, flight: &str, price: i64) -> FlightOffer {
+            let departs = Timestamp::from_unix(1_750_000_000);
+            let base = Price::new(MinorUnits::new(price), CurrencyCode::MYR);
+            FlightOffer {
+                id: id.to_string(),
+                outbound: Itinerary {
+                    segments: vec![FlightSegment {
+                        departure: FlightPoint::new(IataCode::KUL, departs),
+                        arrival: FlightPoint::new(IataCode::NRT, departs.add_hours(7)),
+                        airline: AirlineCode::MH,
+                        flight_number: flight.to_string(),
+                        duration_minutes: 420,
+                        aircraft: None,
+                        cabin_class: CabinClass::Economy,
+                        booking_class: None,
+                        stops: 0,
+                    }],
+                    total_duration_minutes: 420,
+                },
+                return_itinerary: None,
+                onward_itineraries: Vec::new(),
+                price: PriceBreakdown::simple(base, Price::new(MinorUnits::ZERO, base.currency)),
+                validating_airline: AirlineCode::MH,
+                available_seats: None,
+                created_at: Timestamp::now(),
+                expires_at: None,
+                instant_ticketing: true,
+                fare_rules: None,
+            }
+        }
+
+        fn confirmation(pnr: &str, offer_id: &str) -> BookingConfirmation {
+            BookingConfirmation {
+                pnr: pnr.to_string(),
+                booking_reference: pnr.to_string(),
+                status: BookingStatus::Confirmed,
+                created_at: Timestamp::now(),
+                ticketing_deadline: None,
+                passengers: Vec::new(),
+                offer_id: offer_id.to_string(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl GdsProvider for TestProvider {
+        async fn search_flights(&self, _: &FlightSearchRequest) -> GdsResult<Vec<FlightOffer>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self
+                .transient_failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(GdsError::Timeout { timeout_secs: 1 });
+            }
+            Ok(self
+                .offers
+                .iter()
+                .map(|(id, flight, price)| Self::offer(id, flight, *price))
+                .collect())
+        }
+
+        async fn price_offer(&self, offer_id: &str) -> GdsResult<FlightOffer> {
+            let (id, flight, price) = self
+                .offers
+                .iter()
+                .find(|(id, _, _)| *id == offer_id)
+                .ok_or(GdsError::OfferExpired {
+                    offer_id: offer_id.to_string(),
+                })?;
+            Ok(Self::offer(id, flight, *price))
+        }
+
+        async fn create_booking(
+            &self,
+            offer_id: &str,
+            _: &[PassengerDetails],
+            _: &ContactDetails,
+        ) -> GdsResult<BookingConfirmation> {
+            Ok(Self::confirmation(&format!("{}1", self.name), offer_id))
+        }
+
+        async fn issue_ticket(&self, pnr: &str) -> GdsResult<BookingConfirmation> {
+            self.get_booking(pnr).await
+        }
+
+        async fn cancel_booking(&self, _: &str) -> GdsResult<()> {
+            Ok(())
+        }
+
+        async fn get_booking(&self, pnr: &str) -> GdsResult<BookingConfirmation> {
+            if pnr.starts_with(self.name) {
+                Ok(Self::confirmation(pnr, ""))
+            } else {
+                Err(GdsError::NotFound {
+                    resource: "booking".to_string(),
+                    id: pnr.to_string(),
+                })
+            }
+        }
+
+        async fn reprice_booking(&self, pnr: &str, offer_id: &str) -> GdsResult<FlightOffer> {
+            self.get_booking(pnr).await?;
+            self.price_offer(offer_id).await
+        }
+
+        async fn seat_maps(&self, pnr: &str) -> GdsResult<Vec<SeatMap>> {
+            self.get_booking(pnr).await?;
+            Ok(Vec::new())
+        }
+
+        async fn search_airports(&self, _: &str) -> GdsResult<Vec<AirportInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn health_check(&self) -> bool {
+            self.healthy.load(Ordering::SeqCst)
+        }
+
+        fn provider_name(&self) -> &'static str {
+            self.name
+        }
+    }
+
+    fn request() -> FlightSearchRequest {
+        FlightSearchRequest::one_way(IataCode::KUL, IataCode::NRT, Date::today())
+    }
+
+    fn config() -> AggregatorConfig {
+        AggregatorConfig {
+            retry_backoff: Duration::ZERO,
+            ..AggregatorConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_merges_and_dedupes() {
+        let primary = Arc::new(TestProvider::new(
+            "A",
+            vec![("1", "88", 50_000), ("2", "90", 60_000)],
+        ));
+        let secondary = Arc::new(TestProvider::new(
+            "B",
+            vec![("x", "88", 45_000), ("y", "70", 70_000)],
+        ));
+        let aggregator = GdsAggregator::new(vec![primary, secondary.clone()]).with_config(config());
+
+        let search = aggregator.search(&request()).await.expect("call succeeds");
+        assert_eq!(search.providers, vec!["A", "B"]);
+        let ids: Vec<&str> = search.offers.iter().map(|o| o.id.as_str()).collect();
+        // MH88 is cheaper at B and replaces A's offer in place
+        assert_eq!(ids, vec!["B:x", "A:2", "B:y"]);
+
+        let priced = aggregator.price_offer("B:x").await.expect("call succeeds");
+        assert_eq!(priced.id, "B:x");
+        assert_eq!(priced.price.total.amount.as_i64(), 45_000);
+
+        let passenger = PassengerDetails::adult("Ana", "Lee", Date::new(1990, 1, 1));
+        let contact = ContactDetails::new("ana@example.com", "+60123456789");
+        let booking = aggregator
+            .create_booking("B:x", &[passenger], &contact)
+            .await
+            .expect("booking succeeds");
+        assert_eq!(booking.pnr, "B1");
+        assert!(aggregator.issue_ticket("B1").await.is_ok());
+        assert!(aggregator.price_offer("C:1").await.is_err());
+
+        let repriced = aggregator
+            .reprice_booking("B1", "B:y")
+            .await
+            .expect("reprice succeeds");
+        assert_eq!(repriced.id, "B:y");
+        // Exchanges cannot move a booking to another provider
+        assert!(aggregator.reprice_booking("B1", "A:2").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failover_and_retry() {
+        let primary = Arc::new(TestProvider::new("A", vec![("1", "88", 50_000)]));
+        let secondary = Arc::new(TestProvider::new("B", vec![("x", "70", 45_000)]));
+        primary.healthy.store(false, Ordering::SeqCst);
+        secondary.transient_failures.store(1, Ordering::SeqCst);
+        let aggregator =
+            GdsAggregator::new(vec![primary.clone(), secondary.clone()]).with_config(config());
+
+        let search = aggregator.search(&request()).await.expect("call succeeds");
+        assert_eq!(search.providers, vec!["B"]);
+        assert_eq!(search.failures[0].provider, "A");
+        assert_eq!(primary.calls.load(Ordering::SeqCst), 0);
+        // One timeout, then success
+        assert_eq!(secondary.calls.load(Ordering::SeqCst), 2);
+
+        // Unknown PNR is found by asking the healthy providers
+        assert!(aggregator.get_booking("B1").await.is_ok());
+        assert!(aggregator.get_booking("Z9").await.is_err());
+
+        secondary.transient_failures.store(5, Ordering::SeqCst);
+        assert!(aggregator.search(&request()).await.is_err());
+        assert!(!aggregator.health_check().await);
+    }
+}
@@ -5,23 +5,26 @@ use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
+use vaya_common::translit::{self, NameField};
 use vaya_common::{AirlineCode, CurrencyCode, IataCode, MinorUnits, Price, Timestamp};
 
 use crate::cache::GdsCache;
 use crate::error::{GdsError, GdsResult};
+use crate::queue::{QueueMessage, QueueSource};
 use crate::traits::{AirportInfo, GdsProvider};
 use crate::types::{
     BaggageAllowance, BookingConfirmation, BookingStatus, CabinClass, ContactDetails, FareRules,
     FlightOffer, FlightPoint, FlightSearchRequest, FlightSegment, Itinerary, PassengerDetails,
-    PriceBreakdown,
+    PriceBreakdown, Seat, SeatAvailability, SeatMap, SpecialService,
 };
 use crate::GdsConfig;
 
 use super::auth::TokenManager;
 use super::response::{
-    AirportSearchResponse, AmadeusError, AmadeusFlightOffer, AmadeusItinerary, AmadeusSegment,
-    ContactRequest, Dictionaries, FlightOffersResponse, FlightOrderRequest, FlightOrderResponse,
-    Phone, TravelerContact, TravelerDocument, TravelerName, TravelerPricing, TravelerRequest,
+    AirportSearchResponse, AmadeusError, AmadeusFlightOffer, AmadeusItinerary, AmadeusSeatMap,
+    AmadeusSegment, ContactRequest, Dictionaries, FlightOffersResponse, FlightOrderRequest,
+    FlightOrderResponse, Phone, QueueItemsResponse, SeatMapResponse, SpecialServiceRequest,
+    TravelerContact, TravelerDocument, TravelerName, TravelerPricing, TravelerRequest,
 };
 
 /// Amadeus GDS client
@@ -63,8 +66,13 @@ impl AmadeusClient {
 
     /// Build cache key for search request
     fn build_cache_key(request: &FlightSearchRequest) -> String {
+        let legs: Vec<String> = request
+            .legs
+            .iter()
+            .map(|l| format!("{}{}{}", l.origin, l.destination, l.departure_date))
+            .collect();
         format!(
-            "{}-{}-{}-{:?}-{}-{}-{:?}",
+            "{}-{}-{}-{:?}-{}-{}-{:?}-{}",
             request.origin,
             request.destination,
             request.departure_date,
@@ -72,6 +80,7 @@ impl AmadeusClient {
             request.adults,
             request.children + request.infants,
             request.cabin_class,
+            legs.join("+"),
         )
     }
 
@@ -193,38 +202,49 @@ impl AmadeusClient {
     }
 
     /// Convert Amadeus flight offer to internal type
+    ///
+    /// Multi-city offers carry one itinerary per leg; otherwise a second
+    /// itinerary is the return.
     fn convert_offer(
         &self,
         amadeus_offer: &AmadeusFlightOffer,
         _dictionaries: &Option<Dictionaries>,
+        multi_city: bool,
     ) -> GdsResult<FlightOffer> {
-        let outbound = self.convert_itinerary(&amadeus_offer.itineraries[0])?;
-
-        let return_itinerary = if amadeus_offer.itineraries.len() > 1 {
-            Some(self.convert_itinerary(&amadeus_offer.itineraries[1])?)
+        let mut itineraries = amadeus_offer
+            .itineraries
+            .iter()
+            .map(|i| self.convert_itinerary(i))
+            .collect::<GdsResult<Vec<_>>>()?
+            .into_iter();
+        let outbound = itineraries.next().ok_or_else(|| {
+            GdsError::InvalidResponse(format!("Offer {} has no itineraries", amadeus_offer.id))
+        })?;
+
+        let (return_itinerary, onward_itineraries) = if multi_city {
+            (None, itineraries.collect())
         } else {
-            None
+            (itineraries.next(), Vec::new())
         };
 
-        // Parse price
-        let total_cents: i64 = amadeus_offer
-            .price
-            .total
-            .parse::<f64>()
-            .map(|v| (v * 100.0) as i64)
-            .unwrap_or(0);
-
-        let base_cents: i64 = amadeus_offer
-            .price
-            .base
-            .as_ref()
-            .and_then(|b| b.parse::<f64>().ok())
-            .map_or(total_cents, |v| (v * 100.0) as i64);
-
+        // Parse price in the currency's own minor units (JPY has none, KWD has three)
         let currency = CurrencyCode::new(&amadeus_offer.price.currency);
+        let parse_amount = |amount: &str| {
+            MinorUnits::parse_decimal(amount, currency).map_err(|e| {
+                GdsError::InvalidResponse(format!(
+                    "Offer {} has an invalid price: {}",
+                    amadeus_offer.id, e.message
+                ))
+            })
+        };
+        let total = parse_amount(&amadeus_offer.price.total)?;
+        let base = match amadeus_offer.price.base.as_deref() {
+            Some(base) => parse_amount(base)?,
+            None => total,
+        };
 
-        let base_price = Price::new(MinorUnits::new(base_cents), currency);
-        let taxes = Price::new(MinorUnits::new(total_cents - base_cents), currency);
+        let base_price = Price::new(base, currency);
+        let taxes = Price::new(total.sub(base), currency);
 
         // Get validating airline
         let validating_airline = amadeus_offer
@@ -240,6 +260,7 @@ impl AmadeusClient {
             id: amadeus_offer.id.clone(),
             outbound,
             return_itinerary,
+            onward_itineraries,
             price: PriceBreakdown::simple(base_price, taxes),
             validating_airline,
             available_seats: amadeus_offer.number_of_bookable_seats,
@@ -268,16 +289,19 @@ impl AmadeusClient {
 
     /// Convert Amadeus segment
     fn convert_segment(&self, segment: &AmadeusSegment) -> GdsResult<FlightSegment> {
-        let departure_time = self.parse_iso_datetime(&segment.departure.at);
-        let arrival_time = self.parse_iso_datetime(&segment.arrival.at);
+        // Amadeus times are local to each airport, without an offset
+        let point = |airport: &str, at: &str| {
+            let airport = IataCode::new(airport);
+            FlightPoint::from_local(airport, at)
+                .unwrap_or_else(|| FlightPoint::new(airport, self.parse_iso_datetime(at)))
+        };
 
-        let mut departure =
-            FlightPoint::new(IataCode::new(&segment.departure.iata_code), departure_time);
+        let mut departure = point(&segment.departure.iata_code, &segment.departure.at);
         if let Some(ref term) = segment.departure.terminal {
             departure = departure.with_terminal(term.clone());
         }
 
-        let mut arrival = FlightPoint::new(IataCode::new(&segment.arrival.iata_code), arrival_time);
+        let mut arrival = point(&segment.arrival.iata_code, &segment.arrival.at);
         if let Some(ref term) = segment.arrival.terminal {
             arrival = arrival.with_terminal(term.clone());
         }
@@ -403,25 +427,23 @@ impl AmadeusClient {
 
     /// Build search request body
     fn build_search_request(&self, request: &FlightSearchRequest) -> serde_json::Value {
-        let mut origin_destinations = vec![serde_json::json!({
-            "id": "1",
-            "originLocationCode": request.origin.as_str(),
-            "destinationLocationCode": request.destination.as_str(),
-            "departureDateTimeRange": {
-                "date": self.format_date(&request.departure_date)
-            }
-        })];
-
-        if let Some(return_date) = &request.return_date {
-            origin_destinations.push(serde_json::json!({
-                "id": "2",
-                "originLocationCode": request.destination.as_str(),
-                "destinationLocationCode": request.origin.as_str(),
-                "departureDateTimeRange": {
-                    "date": self.format_date(return_date)
-                }
-            }));
-        }
+        // One originDestination per leg: outbound and return, or every multi-city leg
+        let legs = request.origin_destinations();
+        let ids: Vec<String> = (1..=legs.len()).map(|id| id.to_string()).collect();
+        let origin_destinations: Vec<serde_json::Value> = legs
+            .iter()
+            .zip(&ids)
+            .map(|(leg, id)| {
+                serde_json::json!({
+                    "id": id,
+                    "originLocationCode": leg.origin.as_str(),
+                    "destinationLocationCode": leg.destination.as_str(),
+                    "departureDateTimeRange": {
+                        "date": self.format_date(&leg.departure_date)
+                    }
+                })
+            })
+            .collect();
 
         let mut travelers = Vec::new();
         let mut traveler_id = 1;
@@ -468,7 +490,7 @@ impl AmadeusClient {
                     "cabinRestrictions": [{
                         "cabin": cabin_code,
                         "coverage": "MOST_SEGMENTS",
-                        "originDestinationIds": ["1"]
+                        "originDestinationIds": ids
                     }]
                 }
             }
@@ -497,7 +519,10 @@ impl GdsProvider for AmadeusClient {
         let offers: Vec<FlightOffer> = response
             .data
             .iter()
-            .filter_map(|o| self.convert_offer(o, &response.dictionaries).ok())
+            .filter_map(|o| {
+                self.convert_offer(o, &response.dictionaries, request.is_multi_city())
+                    .ok()
+            })
             .collect();
 
         info!(
@@ -538,42 +563,49 @@ impl GdsProvider for AmadeusClient {
         let travelers: Vec<TravelerRequest> = passengers
             .iter()
             .enumerate()
-            .map(|(i, p)| TravelerRequest {
-                id: (i + 1).to_string(),
-                date_of_birth: format!("{}", p.date_of_birth),
-                gender: p.gender.amadeus_code().to_string(),
-                name: TravelerName {
-                    first_name: p.first_name.clone(),
-                    last_name: p.last_name.clone(),
-                },
-                documents: p.passport_number.as_ref().map(|num| {
-                    vec![TravelerDocument {
-                        document_type: "PASSPORT".to_string(),
-                        birth_place: None,
-                        issuance_location: None,
-                        issuance_date: None,
-                        number: num.clone(),
-                        expiry_date: p
-                            .passport_expiry
-                            .as_ref()
-                            .map(|d| format!("{d}"))
-                            .unwrap_or_default(),
-                        issuance_country: p.nationality.clone().unwrap_or_else(|| "MY".to_string()),
-                        validity_country: None,
-                        nationality: p.nationality.clone().unwrap_or_else(|| "MY".to_string()),
-                        holder: true,
-                    }]
-                }),
-                contact: Some(TravelerContact {
-                    email_address: Some(contact.email.clone()),
-                    phones: Some(vec![Phone {
-                        device_type: "MOBILE".to_string(),
-                        country_calling_code: "60".to_string(),
-                        number: contact.phone.clone(),
-                    }]),
-                }),
+            .map(|(i, p)| -> GdsResult<TravelerRequest> {
+                Ok(TravelerRequest {
+                    id: (i + 1).to_string(),
+                    date_of_birth: format!("{}", p.date_of_birth),
+                    gender: p.gender.amadeus_code().to_string(),
+                    name: traveler_name(p)?,
+                    documents: p.passport_number.as_ref().map(|num| {
+                        vec![TravelerDocument {
+                            document_type: "PASSPORT".to_string(),
+                            birth_place: None,
+                            issuance_location: None,
+                            issuance_date: None,
+                            number: num.clone(),
+                            expiry_date: p
+                                .passport_expiry
+                                .as_ref()
+                                .map(|d| format!("{d}"))
+                                .unwrap_or_default(),
+                            issuance_country: p
+                                .nationality
+                                .clone()
+                                .unwrap_or_else(|| "MY".to_string()),
+                            validity_country: None,
+                            nationality: p.nationality.clone().unwrap_or_else(|| "MY".to_string()),
+                            holder: true,
+                        }]
+                    }),
+                    contact: Some(TravelerContact {
+                        email_address: Some(contact.email.clone()),
+                        phones: Some(vec![Phone {
+                            device_type: "MOBILE".to_string(),
+                            country_calling_code: "60".to_string(),
+                            number: contact.phone.clone(),
+                        }]),
+                    }),
+                    special_service_requests: p
+                        .special_services
+                        .iter()
+                        .map(special_service_request)
+                        .collect::<GdsResult<_>>()?,
+                })
             })
-            .collect();
+            .collect::<GdsResult<_>>()?;
 
         let contact_request = ContactRequest {
             address_eename: None,
@@ -660,6 +692,28 @@ impl GdsProvider for AmadeusClient {
         }
     }
 
+    async fn reprice_booking(&self, pnr: &str, offer_id: &str) -> GdsResult<FlightOffer> {
+        // Self-service APIs have no exchange pricing: check the order exists,
+        // then price the new offer as a fresh sale
+        self.get_booking(pnr).await?;
+        self.price_offer(offer_id).await
+    }
+
+    async fn seat_maps(&self, pnr: &str) -> GdsResult<Vec<SeatMap>> {
+        let url = format!(
+            "{}/v1/shopping/seatmaps?flightOrderId={}",
+            self.base_url, pnr
+        );
+        let response: SeatMapResponse = self.get(&url).await?;
+
+        response
+            .data
+            .iter()
+            .enumerate()
+            .map(|(index, map)| seat_map(index, map))
+            .collect()
+    }
+
     async fn get_booking(&self, pnr: &str) -> GdsResult<BookingConfirmation> {
         let url = format!("{}/v1/booking/flight-orders/{}", self.base_url, pnr);
         let response: FlightOrderResponse = self.get(&url).await?;
@@ -756,20 +810,267 @@ impl GdsProvider for AmadeusClient {
     }
 }
 
+/// Traveler name in passport (MRZ) form, as airlines match it at check-in
+fn traveler_name(passenger: &PassengerDetails) -> GdsResult<TravelerName> {
+    let mrz = |name: &str, field: NameField| {
+        translit::transliterate(name, field)
+            .map(|t| t.value)
+            .map_err(|e| GdsError::InvalidRequest(format!("Passenger name: {}", e.message)))
+    };
+    Ok(TravelerName {
+        first_name: mrz(&passenger.first_name, NameField::Given)?,
+        last_name: mrz(&passenger.last_name, NameField::Surname)?,
+    })
+}
+
+/// SSR in the form airline systems accept: a four-letter code and short
+/// upper-case free text
+fn special_service_request(service: &SpecialService) -> GdsResult<SpecialServiceRequest> {
+    let code = service.code.trim().to_ascii_uppercase();
+    if code.len() != 4 || !code.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return Err(GdsError::InvalidRequest(format!(
+            "Invalid SSR code: {}",
+            service.code
+        )));
+    }
+    let text = match service.text.as_deref().map(str::trim) {
+        Some(text) if !text.is_empty() => {
+            if text.len() > 70 || !text.is_ascii() {
+                return Err(GdsError::InvalidRequest(format!(
+                    "SSR {code} text must be at most 70 ASCII characters"
+                )));
+            }
+            Some(text.to_ascii_uppercase())
+        }
+        _ => None,
+    };
+    Ok(SpecialServiceRequest { code, text })
+}
+
+/// Convert the seat map of the segment at `index`
+///
+/// Availability and price are those of the first traveler; seats the
+/// response does not price for anyone are reported as blocked.
+fn seat_map(index: usize, map: &AmadeusSeatMap) -> GdsResult<SeatMap> {
+    let seats = map
+        .decks
+        .iter()
+        .flat_map(|deck| &deck.seats)
+        .map(|seat| {
+            let pricing = seat.traveler_pricing.first();
+            let availability = match pricing.map(|p| p.seat_availability_status.as_str()) {
+                Some("AVAILABLE") => SeatAvailability::Available,
+                Some("OCCUPIED") => SeatAvailability::Occupied,
+                _ => SeatAvailability::Blocked,
+            };
+            let price = match pricing.and_then(|p| p.price.as_ref()) {
+                Some(price) => {
+                    let currency = CurrencyCode::new(&price.currency);
+                    let price = Price::parse_decimal(&price.total, currency).map_err(|e| {
+                        GdsError::InvalidResponse(format!(
+                            "Seat {} has an invalid price: {}",
+                            seat.number, e.message
+                        ))
+                    })?;
+                    // Free seats are sometimes priced at zero
+                    (!price.is_zero()).then_some(price)
+                }
+                None => None,
+            };
+            let cabin = match seat.cabin.as_deref() {
+                Some("PREMIUM_ECONOMY") => CabinClass::PremiumEconomy,
+                Some("BUSINESS") => CabinClass::Business,
+                Some("FIRST") => CabinClass::First,
+                _ => CabinClass::Economy,
+            };
+            Ok(Seat {
+                number: seat.number.to_ascii_uppercase(),
+                cabin,
+                availability,
+                characteristics: seat.characteristics_codes.clone(),
+                price,
+            })
+        })
+        .collect::<GdsResult<Vec<_>>>()?;
+
+    Ok(SeatMap {
+        segment: index,
+        flight: format!("{}{}", map.carrier_code, map.number),
+        origin: IataCode::new(&map.departure.iata_code),
+        destination: IataCode::new(&map.arrival.iata_code),
+        seats,
+    })
+}
+
+/// Queue access through the Amadeus Enterprise queue API
+#[async_trait]
+impl QueueSource for AmadeusClient {
+    async fn list_queue(&self, queue: u16) -> GdsResult<Vec<QueueMessage>> {
+        let url = format!("{}/v1/queues/{}/items", self.base_url, queue);
+        let response: QueueItemsResponse = self.get(&url).await?;
+
+        Ok(response
+            .data
+            .into_iter()
+            .map(|item| QueueMessage {
+                received_at: item
+                    .queued_at
+                    .as_deref()
+                    .map_or_else(Timestamp::now, |dt| self.parse_iso_datetime(dt)),
+                id: item.id,
+                queue,
+                category: item.category,
+                pnr: item.reference,
+                text: item.text,
+            })
+            .collect())
+    }
+
+    async fn remove_queue_item(&self, queue: u16, id: &str) -> GdsResult<()> {
+        let url = format!("{}/v1/queues/{}/items/{}", self.base_url, queue, id);
+
+        let token = self.token_manager.get_token().await?;
+        let response = self
+            .http_client
+            .delete(&url)
+            .header("Authorization", format!("Bearer {token}"))
+            .send()
+            .await
+            .map_err(GdsError::from)?;
+
+        if response.status().is_success() {
+            debug!("Removed item {} from queue {}", id, queue);
+            Ok(())
+        } else {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            Err(GdsError::InvalidResponse(format!("HTTP {status}: {body}")))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_parse_duration() {
+    fn test_client() -> AmadeusClient {
         let config = GdsConfig::default();
-        let client = AmadeusClient {
+        AmadeusClient {
             http_client: reqwest::Client::new(),
             token_manager: Arc::new(TokenManager::new(&config, reqwest::Client::new())),
             cache: GdsCache::new(),
             base_url: config.amadeus_base_url.clone(),
             max_retries: 3,
+        }
+    }
+
+    fn offer_json(currency: &str, total: &str, base: &str) -> AmadeusFlightOffer {
+        let json = format!(
+            r#"{{"type":"flight-offer","id":"1","itineraries":[{{"duration":"PT7H","segments":[{{
+                "departure":{{"iataCode":"KUL","at":"2025-01-15T10:30:00"}},
+                "arrival":{{"iataCode":"NRT","at":"2025-01-15T17:30:00"}},
+                "carrierCode":"MH","number":"88","duration":"PT7H"}}]}}],
+                "price":{{"currency":"{currency}","total":"{total}","base":"{base}"}}}}"#
+        );
+        serde_json::from_str(&json).expect("valid offer JSON")
+    }
+
+    #[test]
+    fn test_convert_offer_uses_currency_decimals() {
+        let client = test_client();
+
+        let myr = client
+            .convert_offer(&offer_json("MYR", "1234.50", "1000.00"), &None, false)
+            .expect("MYR offer");
+        assert_eq!(myr.price.total.amount.as_i64(), 123_450);
+
+        let jpy = client
+            .convert_offer(&offer_json("JPY", "45000", "40000"), &None, false)
+            .expect("JPY offer");
+        assert_eq!(jpy.price.total.amount.as_i64(), 45_000);
+
+        let kwd = client
+            .convert_offer(&offer_json("KWD", "120.250", "100.000"), &None, false)
+            .expect("KWD offer");
+        assert_eq!(kwd.price.total.amount.as_i64(), 120_250);
+
+        // Sub-yen fares are malformed, not rounded
+        assert!(client
+            .convert_offer(&offer_json("JPY", "45000.50", "40000"), &None, false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_multi_city_search() {
+        use crate::types::SearchLeg;
+        use vaya_common::Date;
+
+        let client = test_client();
+        let request = FlightSearchRequest::multi_city(vec![
+            SearchLeg::new(IataCode::KUL, IataCode::NRT, Date::new(2030, 7, 1)),
+            SearchLeg::new(IataCode::NRT, IataCode::ICN, Date::new(2030, 7, 8)),
+            SearchLeg::new(IataCode::ICN, IataCode::KUL, Date::new(2030, 7, 15)),
+        ]);
+        assert_eq!(request.destination, IataCode::KUL);
+
+        let body = client.build_search_request(&request);
+        let legs = body["originDestinations"].as_array().expect("legs");
+        assert_eq!(legs.len(), 3);
+        assert_eq!(legs[1]["id"], "2");
+        assert_eq!(legs[1]["originLocationCode"], "NRT");
+        assert_eq!(legs[2]["destinationLocationCode"], "KUL");
+        assert_eq!(
+            body["searchCriteria"]["flightFilters"]["cabinRestrictions"][0]["originDestinationIds"],
+            serde_json::json!(["1", "2", "3"])
+        );
+        assert_ne!(
+            AmadeusClient::build_cache_key(&request),
+            AmadeusClient::build_cache_key(&FlightSearchRequest::one_way(
+                IataCode::KUL,
+                IataCode::KUL,
+                Date::new(2030, 7, 1)
+            ))
+        );
+
+        let itinerary = |from: &str, to: &str| {
+            format!(
+                r#"{{"duration":"PT7H","segments":[{{
+                    "departure":{{"iataCode":"{from}","at":"2030-07-01T10:30:00"}},
+                    "arrival":{{"iataCode":"{to}","at":"2030-07-01T17:30:00"}},
+                    "carrierCode":"MH","number":"88","duration":"PT7H"}}]}}"#
+            )
         };
+        let json = format!(
+            r#"{{"type":"flight-offer","id":"1","itineraries":[{},{},{}],
+                "price":{{"currency":"MYR","total":"3000.00","base":"2500.00"}}}}"#,
+            itinerary("KUL", "NRT"),
+            itinerary("NRT", "ICN"),
+            itinerary("ICN", "KUL")
+        );
+        let offer: AmadeusFlightOffer = serde_json::from_str(&json).expect("valid offer JSON");
+
+        let converted = client
+            .convert_offer(&offer, &None, true)
+            .expect("multi-city offer");
+        assert!(converted.is_multi_city());
+        assert!(converted.return_itinerary.is_none());
+        assert_eq!(converted.itineraries().count(), 3);
+        assert_eq!(
+            converted.onward_itineraries[1].arrival().map(|p| p.airport),
+            Some(IataCode::KUL)
+        );
+
+        // Without the multi-city flag the second itinerary is the return
+        let round_trip = client
+            .convert_offer(&offer, &None, false)
+            .expect("round-trip offer");
+        assert!(round_trip.is_round_trip());
+        assert!(!round_trip.is_multi_city());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let client = test_client();
 
         assert_eq!(client.parse_duration(&Some("PT7H30M".to_string())), 450);
         assert_eq!(client.parse_duration(&Some("PT2H".to_string())), 120);
@@ -787,4 +1088,83 @@ mod tests {
         assert!(key.contains("KUL"));
         assert!(key.contains("NRT"));
     }
+
+    #[test]
+    fn test_traveler_name_is_mrz() {
+        use vaya_common::Date;
+
+        let passenger = PassengerDetails::adult("Zoë", "Жуков", Date::today());
+        let name = traveler_name(&passenger).expect("transliterable name");
+        assert_eq!(name.first_name, "ZOE");
+        assert_eq!(name.last_name, "ZHUKOV");
+
+        let passenger = PassengerDetails::adult("さくら", "Tanaka", Date::today());
+        assert!(matches!(
+            traveler_name(&passenger),
+            Err(GdsError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_special_service_requests() {
+        let ssr = special_service_request(&SpecialService {
+            code: "wchs".into(),
+            text: Some(" travels with companion ".into()),
+        })
+        .expect("valid SSR");
+        assert_eq!(ssr.code, "WCHS");
+        assert_eq!(ssr.text.as_deref(), Some("TRAVELS WITH COMPANION"));
+
+        let json = serde_json::to_value(&ssr).expect("serializable");
+        assert_eq!(json["code"], "WCHS");
+
+        assert!(special_service_request(&SpecialService {
+            code: "WHEELCHAIR".into(),
+            text: None,
+        })
+        .is_err());
+        assert!(special_service_request(&SpecialService {
+            code: "BLND".into(),
+            text: Some("x".repeat(71)),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_seat_map_conversion() {
+        let response: SeatMapResponse = serde_json::from_str(
+            r#"{"data":[{"segmentId":"1","carrierCode":"MH","number":"88",
+                "departure":{"iataCode":"KUL","at":"2025-01-15T10:30:00"},
+                "arrival":{"iataCode":"NRT","at":"2025-01-15T17:30:00"},
+                "decks":[{"deckType":"MAIN","seats":[
+                    {"cabin":"ECONOMY","number":"12a","characteristicsCodes":["W","CH"],
+                     "travelerPricing":[{"travelerId":"1","seatAvailabilityStatus":"AVAILABLE",
+                        "price":{"currency":"MYR","total":"45.00"}}]},
+                    {"cabin":"ECONOMY","number":"12B","characteristicsCodes":["9"],
+                     "travelerPricing":[{"travelerId":"1","seatAvailabilityStatus":"OCCUPIED",
+                        "price":{"currency":"MYR","total":"0.00"}}]},
+                    {"cabin":"BUSINESS","number":"2A","characteristicsCodes":[]}]}]}]}"#,
+        )
+        .expect("valid seat map JSON");
+
+        let map = seat_map(0, &response.data[0]).expect("seat map");
+        assert_eq!(map.flight, "MH88");
+        assert_eq!(map.origin, IataCode::KUL);
+
+        let window = map.seat("12A").expect("seat exists");
+        assert!(window.is_available());
+        assert!(window.has_characteristic("w"));
+        assert_eq!(window.price, Some(Price::myr(4500)));
+
+        let middle = map.seat("12B").expect("seat exists");
+        assert_eq!(middle.availability, SeatAvailability::Occupied);
+        assert_eq!(middle.price, None);
+
+        // No traveler pricing: not offered
+        let business = map.seat("2A").expect("seat exists");
+        assert_eq!(business.availability, SeatAvailability::Blocked);
+        assert_eq!(business.cabin, CabinClass::Business);
+        assert_eq!(map.available_seats().count(), 1);
+        assert!(map.seat("40K").is_none());
+    }
 }
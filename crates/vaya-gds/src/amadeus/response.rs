@@ -271,6 +271,20 @@ pub struct TravelerRequest {
     pub documents: Option<Vec<TravelerDocument>>,
     /// Contact
     pub contact: Option<TravelerContact>,
+    /// Special service requests
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub special_service_requests: Vec<SpecialServiceRequest>,
+}
+
+/// Special service request (SSR) for a traveler
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpecialServiceRequest {
+    /// SSR code
+    pub code: String,
+    /// Free text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
 }
 
 /// Traveler name
@@ -459,3 +473,88 @@ pub struct AirportAddress {
     /// Country code
     pub country_code: Option<String>,
 }
+
+/// Queue items response (Enterprise queue API)
+#[derive(Debug, Deserialize)]
+pub struct QueueItemsResponse {
+    /// Data
+    #[serde(default)]
+    pub data: Vec<QueueItemData>,
+}
+
+/// Queue item
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItemData {
+    /// Item ID
+    pub id: String,
+    /// Queue category
+    #[serde(default)]
+    pub category: u16,
+    /// Record locator
+    pub reference: String,
+    /// Message text
+    #[serde(default)]
+    pub text: String,
+    /// Queued at (ISO 8601)
+    pub queued_at: Option<String>,
+}
+
+/// Seat map display response
+#[derive(Debug, Deserialize)]
+pub struct SeatMapResponse {
+    /// One seat map per segment
+    #[serde(default)]
+    pub data: Vec<AmadeusSeatMap>,
+}
+
+/// Seat map of a segment
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmadeusSeatMap {
+    /// Carrier code
+    pub carrier_code: String,
+    /// Flight number
+    pub number: String,
+    /// Departure info
+    pub departure: AmadeusFlightEndpoint,
+    /// Arrival info
+    pub arrival: AmadeusFlightEndpoint,
+    /// Decks
+    #[serde(default)]
+    pub decks: Vec<Deck>,
+}
+
+/// Aircraft deck
+#[derive(Debug, Deserialize)]
+pub struct Deck {
+    /// Seats
+    #[serde(default)]
+    pub seats: Vec<AmadeusSeat>,
+}
+
+/// Seat on a deck
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmadeusSeat {
+    /// Cabin (ECONOMY, BUSINESS, ...)
+    pub cabin: Option<String>,
+    /// Seat number
+    pub number: String,
+    /// Characteristic codes
+    #[serde(default)]
+    pub characteristics_codes: Vec<String>,
+    /// Per-traveler availability and price
+    #[serde(default)]
+    pub traveler_pricing: Vec<SeatTravelerPricing>,
+}
+
+/// Seat availability and price for a traveler
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeatTravelerPricing {
+    /// AVAILABLE, BLOCKED or OCCUPIED
+    pub seat_availability_status: String,
+    /// Price (paid seats only)
+    pub price: Option<AmadeusPrice>,
+}
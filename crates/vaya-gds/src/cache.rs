@@ -1,13 +1,14 @@
 //! GDS Response caching using `VayaCache`
 
 use std::time::Duration;
-use vaya_cache::Cache;
+use vaya_cache::{Cache, CachePolicy};
 
 use crate::types::FlightOffer;
 
 /// GDS response cache using `VayaCache` (sharded LRU with TTL)
 pub struct GdsCache {
-    /// Flight search results cache
+    /// Flight search results cache; `TinyLFU` keeps one-off routes from
+    /// pushing out popular ones
     search_cache: Cache<String, Vec<FlightOffer>>,
     /// Pricing cache (`offer_id` -> priced offer)
     pricing_cache: Cache<String, FlightOffer>,
@@ -21,14 +22,14 @@ impl GdsCache {
     /// Create new GDS cache with default settings
     ///
     /// Defaults:
-    /// - 1000 search results, 16 shards
+    /// - 1000 search results, 16 shards, `TinyLFU` admission
     /// - 500 pricing results, 8 shards
     /// - 5 minute search TTL
     /// - 1 minute pricing TTL
     #[must_use]
     pub fn new() -> Self {
         Self {
-            search_cache: Cache::new(1000, 16),
+            search_cache: Cache::new(1000, 16).with_policy(CachePolicy::TinyLfu),
             pricing_cache: Cache::new(500, 8),
             search_ttl: Duration::from_secs(300),
             pricing_ttl: Duration::from_secs(60),
@@ -39,7 +40,7 @@ impl GdsCache {
     #[must_use]
     pub fn with_capacity(search_capacity: usize, pricing_capacity: usize) -> Self {
         Self {
-            search_cache: Cache::new(search_capacity, 16),
+            search_cache: Cache::new(search_capacity, 16).with_policy(CachePolicy::TinyLfu),
             pricing_cache: Cache::new(pricing_capacity, 8),
             search_ttl: Duration::from_secs(300),
             pricing_ttl: Duration::from_secs(60),
@@ -205,6 +206,7 @@ mod tests {
                 total_duration_minutes: 420,
             },
             return_itinerary: None,
+            onward_itineraries: Vec::new(),
             price: PriceBreakdown::simple(
                 Price::new(MinorUnits::new(50000), CurrencyCode::MYR),
                 Price::new(MinorUnits::new(5000), CurrencyCode::MYR),
@@ -12,6 +12,9 @@
 //! - **Amadeus**: Primary GDS for APAC region
 //! - **Travelport**: Secondary/fallback (future)
 //!
+//! [`GdsAggregator`] combines several providers: concurrent search with
+//! deduplication, and failover when a provider is unhealthy or erroring.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -36,20 +39,30 @@
 #![warn(missing_docs)]
 #![warn(clippy::pedantic)]
 
+pub mod aggregator;
 pub mod amadeus;
 pub mod cache;
 pub mod error;
+pub mod queue;
 pub mod traits;
 pub mod types;
 
+use std::fmt;
+
+use vaya_common::{resolve_secret, Mask, Redact, SecretSource};
+
+pub use aggregator::{AggregatedSearch, AggregatorConfig, GdsAggregator, ProviderFailure};
 pub use amadeus::AmadeusClient;
 pub use cache::GdsCache;
 pub use error::{GdsError, GdsResult};
+pub use queue::{
+    classify, ClassifiedMessage, QueueEventKind, QueueMessage, QueuePoller, QueueSource,
+};
 pub use traits::GdsProvider;
 pub use types::*;
 
 /// GDS configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct GdsConfig {
     /// Amadeus API key
     pub amadeus_api_key: String,
@@ -67,6 +80,26 @@ pub struct GdsConfig {
     pub max_retries: u32,
 }
 
+impl fmt::Debug for GdsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GdsConfig")
+            .field(
+                "amadeus_api_key",
+                &self.amadeus_api_key.redacted(Mask::LastFour),
+            )
+            .field(
+                "amadeus_api_secret",
+                &self.amadeus_api_secret.redacted(Mask::Full),
+            )
+            .field("amadeus_base_url", &self.amadeus_base_url)
+            .field("search_cache_ttl_secs", &self.search_cache_ttl_secs)
+            .field("pricing_cache_ttl_secs", &self.pricing_cache_ttl_secs)
+            .field("request_timeout_secs", &self.request_timeout_secs)
+            .field("max_retries", &self.max_retries)
+            .finish()
+    }
+}
+
 impl Default for GdsConfig {
     fn default() -> Self {
         Self {
@@ -112,6 +145,26 @@ impl GdsConfig {
         self
     }
 
+    /// Replace `secret://` references in the Amadeus credentials with the
+    /// secrets they name
+    ///
+    /// Call once at startup, before [`validate`](Self::validate).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GdsError::Configuration`] naming the field if a reference
+    /// names a secret that doesn't exist or can't be read.
+    pub fn resolve_secrets(&mut self, secrets: &dyn SecretSource) -> GdsResult<()> {
+        for (field, value) in [
+            ("amadeus_api_key", &mut self.amadeus_api_key),
+            ("amadeus_api_secret", &mut self.amadeus_api_secret),
+        ] {
+            resolve_secret(value, secrets)
+                .map_err(|e| GdsError::Configuration(format!("{field}: {e}")))?;
+        }
+        Ok(())
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> GdsResult<()> {
         if self.amadeus_api_key.is_empty() {
@@ -151,6 +204,22 @@ mod tests {
         assert_eq!(config.request_timeout_secs, 60);
     }
 
+    #[test]
+    fn test_resolve_secrets() {
+        let secrets: std::collections::HashMap<String, String> =
+            [("amadeus/secret".to_string(), "s3cret".to_string())].into();
+        let mut config = GdsConfig::new("key123", "secret://amadeus/secret");
+        config.resolve_secrets(&secrets).expect("secrets resolve");
+        assert_eq!(config.amadeus_api_key, "key123");
+        assert_eq!(config.amadeus_api_secret, "s3cret");
+
+        let mut config = GdsConfig::new("secret://amadeus/key", "secret");
+        let err = config
+            .resolve_secrets(&secrets)
+            .expect_err("missing secret");
+        assert!(err.to_string().contains("amadeus_api_key"));
+    }
+
     #[test]
     fn test_config_validation() {
         let config = GdsConfig::default();
@@ -0,0 +1,337 @@
+//! GDS queue polling and message classification
+//!
+//! Airlines report schedule changes, cancellations, and involuntary
+//! rebookings by placing PNRs on agency queues. A [`QueuePoller`] reads the
+//! configured queues from a [`QueueSource`], classifies each message into a
+//! [`QueueEventKind`], and removes messages once the caller has handled
+//! them.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use tracing::{debug, warn};
+
+use vaya_common::Timestamp;
+
+use crate::error::GdsResult;
+
+/// Queues read by default: general (0), schedule changes (7), and
+/// ticketing time limits (8)
+pub const DEFAULT_QUEUES: [u16; 3] = [0, 7, 8];
+
+/// A message taken from a GDS queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueMessage {
+    /// Message ID within the queue
+    pub id: String,
+    /// Queue number
+    pub queue: u16,
+    /// Queue category
+    pub category: u16,
+    /// Record locator the message refers to
+    pub pnr: String,
+    /// Message text (segment status lines, remarks)
+    pub text: String,
+    /// When the message was queued
+    pub received_at: Timestamp,
+}
+
+/// What a queue message reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueueEventKind {
+    /// Departure or arrival times changed
+    ScheduleChange,
+    /// A flight in the booking was cancelled by the airline
+    FlightCancelled,
+    /// The airline moved passengers to a different flight
+    InvoluntaryRebooking,
+    /// The whole booking was cancelled by the airline
+    BookingCancelled,
+    /// The ticketing deadline is approaching or has passed
+    TicketingDeadline,
+    /// Confirmation or other informational message
+    Informational,
+    /// Message could not be classified
+    Unrecognized,
+}
+
+impl QueueEventKind {
+    /// Get event code
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::ScheduleChange => "schedule_change",
+            Self::FlightCancelled => "flight_cancelled",
+            Self::InvoluntaryRebooking => "involuntary_rebooking",
+            Self::BookingCancelled => "booking_cancelled",
+            Self::TicketingDeadline => "ticketing_deadline",
+            Self::Informational => "informational",
+            Self::Unrecognized => "unrecognized",
+        }
+    }
+
+    /// Whether an agent needs to act on the event
+    #[must_use]
+    pub const fn needs_human_action(&self) -> bool {
+        matches!(
+            self,
+            Self::FlightCancelled
+                | Self::InvoluntaryRebooking
+                | Self::BookingCancelled
+                | Self::TicketingDeadline
+                | Self::Unrecognized
+        )
+    }
+
+    /// Whether the traveler should be told about the event
+    #[must_use]
+    pub const fn notifies_traveler(&self) -> bool {
+        matches!(
+            self,
+            Self::ScheduleChange
+                | Self::FlightCancelled
+                | Self::InvoluntaryRebooking
+                | Self::BookingCancelled
+        )
+    }
+}
+
+/// Classify a queue message
+///
+/// Uses free-text keywords first, then IATA segment status codes (`UN`,
+/// `UC`, `HX` for cancelled segments, `TK` for schedule changes, `HK`/`KK`
+/// for confirmations).
+#[must_use]
+pub fn classify(message: &QueueMessage) -> QueueEventKind {
+    let text = message.text.to_ascii_uppercase();
+    let has = |needles: &[&str]| needles.iter().any(|n| text.contains(n));
+    // Segment status appears as the code followed by the seat count (`HK1`)
+    let codes: HashSet<&str> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| {
+            t.len() > 2
+                && t.as_bytes()[..2].iter().all(u8::is_ascii_alphabetic)
+                && t.as_bytes()[2..].iter().all(u8::is_ascii_digit)
+        })
+        .filter_map(|t| t.get(..2))
+        .collect();
+    let has_code = |wanted: &[&str]| wanted.iter().any(|c| codes.contains(c));
+
+    if has(&["PNR CANCELLED", "BOOKING CANCELLED", "XXL", "AUTO CANCEL"]) {
+        QueueEventKind::BookingCancelled
+    } else if has(&["REACCOMMODATED", "REBOOKED", "REPROTECTED", "PROTECTED ON"]) {
+        QueueEventKind::InvoluntaryRebooking
+    } else if has(&["FLIGHT CANCELLED", "FLT CNLD", "CANCELLED BY AIRLINE"])
+        || has_code(&["UN", "UC", "HX", "NO"])
+    {
+        QueueEventKind::FlightCancelled
+    } else if has(&["SCHEDULE CHANGE", "SKCHG", "TIME CHANGE"]) || has_code(&["TK"]) {
+        QueueEventKind::ScheduleChange
+    } else if has(&["TKTL", "TICKETING TIME LIMIT", "ADTK", "TICKET BY"]) {
+        QueueEventKind::TicketingDeadline
+    } else if has_code(&["HK", "KK", "KL"]) || has(&["CONFIRMED"]) {
+        QueueEventKind::Informational
+    } else {
+        QueueEventKind::Unrecognized
+    }
+}
+
+/// A classified queue message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassifiedMessage {
+    /// The raw message
+    pub message: QueueMessage,
+    /// Classification
+    pub kind: QueueEventKind,
+}
+
+/// Read access to GDS queues
+#[async_trait]
+pub trait QueueSource: Send + Sync {
+    /// List the messages currently on a queue
+    async fn list_queue(&self, queue: u16) -> GdsResult<Vec<QueueMessage>>;
+
+    /// Remove a handled message from its queue
+    async fn remove_queue_item(&self, queue: u16, id: &str) -> GdsResult<()>;
+}
+
+/// Polls GDS queues for airline-initiated changes
+pub struct QueuePoller<S: QueueSource> {
+    /// Queue source
+    source: Arc<S>,
+    /// Queues to read
+    queues: Vec<u16>,
+    /// Messages returned but not yet acknowledged
+    in_flight: Mutex<HashSet<(u16, String)>>,
+}
+
+impl<S: QueueSource> QueuePoller<S> {
+    /// Create a poller for the default queues
+    pub fn new(source: Arc<S>) -> Self {
+        Self {
+            source,
+            queues: DEFAULT_QUEUES.to_vec(),
+            in_flight: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Set the queues to read
+    #[must_use]
+    pub fn with_queues(mut self, queues: Vec<u16>) -> Self {
+        self.queues = queues;
+        self
+    }
+
+    /// Get the queues being read
+    pub fn queues(&self) -> &[u16] {
+        &self.queues
+    }
+
+    /// Read and classify new messages from every queue
+    ///
+    /// Messages returned by an earlier poll and not yet acknowledged are
+    /// skipped. A queue that fails to load is logged and skipped so the
+    /// others are still read.
+    pub async fn poll_once(&self) -> Vec<ClassifiedMessage> {
+        let mut classified = Vec::new();
+        for &queue in &self.queues {
+            let messages = match self.source.list_queue(queue).await {
+                Ok(messages) => messages,
+                Err(e) => {
+                    warn!("Failed to read GDS queue {}: {}", queue, e);
+                    continue;
+                }
+            };
+
+            let mut in_flight = self
+                .in_flight
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            for message in messages {
+                if !in_flight.insert((queue, message.id.clone())) {
+                    continue;
+                }
+                let kind = classify(&message);
+                debug!(
+                    "Queue {} message {} for {}: {}",
+                    queue,
+                    message.id,
+                    message.pnr,
+                    kind.as_str()
+                );
+                classified.push(ClassifiedMessage { message, kind });
+            }
+        }
+        classified
+    }
+
+    /// Remove a handled message from its queue
+    ///
+    /// # Errors
+    ///
+    /// Returns the source error if the message could not be removed; the
+    /// message stays in flight until released.
+    pub async fn acknowledge(&self, message: &QueueMessage) -> GdsResult<()> {
+        self.source
+            .remove_queue_item(message.queue, &message.id)
+            .await?;
+        self.release(message);
+        Ok(())
+    }
+
+    /// Return a message to the pool so the next poll retries it
+    pub fn release(&self, message: &QueueMessage) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .remove(&(message.queue, message.id.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, queue: u16, text: &str) -> QueueMessage {
+        QueueMessage {
+            id: id.to_string(),
+            queue,
+            category: 0,
+            pnr: "ABC123".to_string(),
+            text: text.to_string(),
+            received_at: Timestamp::now(),
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            (
+                "MH 603 KUL SIN 15JUN TK1 0900 1000",
+                QueueEventKind::ScheduleChange,
+            ),
+            ("MH 603 KUL SIN 15JUN UN1", QueueEventKind::FlightCancelled),
+            (
+                "PAX REACCOMMODATED ON MH 605",
+                QueueEventKind::InvoluntaryRebooking,
+            ),
+            (
+                "PNR CANCELLED DUE TO TKTL EXPIRY",
+                QueueEventKind::BookingCancelled,
+            ),
+            (
+                "ADTK BY 1200 14JUN OR WILL BE XLD",
+                QueueEventKind::TicketingDeadline,
+            ),
+            ("MH 603 KUL SIN 15JUN KK1", QueueEventKind::Informational),
+            ("PLEASE CALL", QueueEventKind::Unrecognized),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(classify(&message("1", 0, text)), expected, "{text}");
+        }
+        assert!(QueueEventKind::FlightCancelled.needs_human_action());
+        assert!(!QueueEventKind::ScheduleChange.needs_human_action());
+    }
+
+    struct StaticQueue(Mutex<Vec<QueueMessage>>);
+
+    #[async_trait]
+    impl QueueSource for StaticQueue {
+        async fn list_queue(&self, queue: u16) -> GdsResult<Vec<QueueMessage>> {
+            Ok(self
+                .0
+                .lock()
+                .map(|m| m.iter().filter(|m| m.queue == queue).cloned().collect())
+                .unwrap_or_default())
+        }
+
+        async fn remove_queue_item(&self, queue: u16, id: &str) -> GdsResult<()> {
+            if let Ok(mut messages) = self.0.lock() {
+                messages.retain(|m| !(m.queue == queue && m.id == id));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poller_tracks_unacknowledged_messages() {
+        let source = Arc::new(StaticQueue(Mutex::new(vec![
+            message("1", 7, "MH 603 KUL SIN 15JUN TK1"),
+            message("2", 8, "ADTK BY 1200 14JUN"),
+            message("3", 12, "IGNORED QUEUE"),
+        ])));
+        let poller = QueuePoller::new(source.clone());
+
+        let first = poller.poll_once().await;
+        assert_eq!(first.len(), 2);
+        assert!(poller.poll_once().await.is_empty());
+
+        poller.acknowledge(&first[0].message).await.ok();
+        poller.release(&first[1].message);
+
+        let retried = poller.poll_once().await;
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].kind, QueueEventKind::TicketingDeadline);
+    }
+}
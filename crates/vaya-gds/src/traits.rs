@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use crate::error::GdsResult;
 use crate::types::{
     BookingConfirmation, ContactDetails, FlightOffer, FlightSearchRequest, PassengerDetails,
+    SeatMap,
 };
 
 /// GDS Provider trait - implement for each GDS system
@@ -53,6 +54,19 @@ pub trait GdsProvider: Send + Sync {
     /// Retrieves the current status of a booking.
     async fn get_booking(&self, pnr: &str) -> GdsResult<BookingConfirmation>;
 
+    /// Re-price a booking onto a new offer
+    ///
+    /// Prices an exchange of the booked flights for the given offer
+    /// (rebooking or date change) without changing the booking. The
+    /// returned offer's fare rules carry the fee for the change.
+    async fn reprice_booking(&self, pnr: &str, offer_id: &str) -> GdsResult<FlightOffer>;
+
+    /// Get seat maps for a booking
+    ///
+    /// Returns one map per flight segment, in itinerary order, with the
+    /// availability and price of each seat.
+    async fn seat_maps(&self, pnr: &str) -> GdsResult<Vec<SeatMap>>;
+
     /// Get available airports
     ///
     /// Returns airports matching the search query.
@@ -83,15 +97,23 @@ pub struct AirportInfo {
 }
 
 /// Mock GDS provider for testing
-#[cfg(test)]
+///
+/// Other crates enable it with the `test-util` feature.
+#[cfg(any(test, feature = "test-util"))]
 pub mod mock {
-    use super::*;
+    use super::{
+        async_trait, AirportInfo, BookingConfirmation, ContactDetails, FlightOffer,
+        FlightSearchRequest, GdsProvider, GdsResult, PassengerDetails, SeatMap,
+    };
     use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use parking_lot::Mutex;
     use vaya_common::{AirlineCode, CurrencyCode, Date, IataCode, MinorUnits, Price, Timestamp};
 
     use crate::{
-        BaggageAllowance, BookingStatus, FareRules, FlightPoint, FlightSegment, Itinerary,
-        PriceBreakdown,
+        BaggageAllowance, BookingStatus, CabinClass, FareRules, FlightPoint, FlightSegment,
+        Itinerary, PriceBreakdown, Seat, SeatAvailability,
     };
 
     /// Mock GDS provider for testing
@@ -100,6 +122,14 @@ pub mod mock {
         pub return_empty: AtomicBool,
         /// Should operations fail
         pub should_fail: AtomicBool,
+        /// Base fares (sen) of the offers a search returns
+        fares: Vec<i64>,
+        /// Base fare (sen) of priced and repriced offers
+        reprice_fare: i64,
+        /// How long a search takes
+        delay: Duration,
+        /// PNRs cancelled so far
+        cancelled: Mutex<Vec<String>>,
     }
 
     impl MockGdsProvider {
@@ -109,9 +139,40 @@ pub mod mock {
             Self {
                 return_empty: AtomicBool::new(false),
                 should_fail: AtomicBool::new(false),
+                fares: vec![50000, 55000, 48000],
+                reprice_fare: 51000,
+                delay: Duration::ZERO,
+                cancelled: Mutex::new(Vec::new()),
             }
         }
 
+        /// Return one offer per base fare (sen) from searches
+        #[must_use]
+        pub fn with_fares(mut self, fares: &[i64]) -> Self {
+            self.fares = fares.to_vec();
+            self
+        }
+
+        /// Price and reprice offers at this base fare (sen)
+        #[must_use]
+        pub fn with_reprice_fare(mut self, fare: i64) -> Self {
+            self.reprice_fare = fare;
+            self
+        }
+
+        /// Answer searches after `delay`
+        #[must_use]
+        pub fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
+        /// PNRs cancelled so far, oldest first
+        #[must_use]
+        pub fn cancelled(&self) -> Vec<String> {
+            self.cancelled.lock().clone()
+        }
+
         /// Set to return empty results
         pub fn set_empty(&self, empty: bool) {
             self.return_empty.store(empty, Ordering::SeqCst);
@@ -141,16 +202,18 @@ pub mod mock {
                 ));
             }
 
+            tokio::time::sleep(self.delay).await;
             if self.return_empty.load(Ordering::SeqCst) {
                 return Ok(Vec::new());
             }
 
             // Generate mock offers
-            let offers = vec![
-                create_mock_offer("OFFER1", request, 50000),
-                create_mock_offer("OFFER2", request, 55000),
-                create_mock_offer("OFFER3", request, 48000),
-            ];
+            let offers = self
+                .fares
+                .iter()
+                .enumerate()
+                .map(|(i, &fare)| create_mock_offer(&format!("OFFER{}", i + 1), request, fare))
+                .collect();
 
             Ok(offers)
         }
@@ -164,7 +227,7 @@ pub mod mock {
 
             // Return mock offer with updated price
             let request = FlightSearchRequest::one_way(IataCode::KUL, IataCode::NRT, Date::today());
-            Ok(create_mock_offer(offer_id, &request, 51000))
+            Ok(create_mock_offer(offer_id, &request, self.reprice_fare))
         }
 
         async fn create_booking(
@@ -186,7 +249,7 @@ pub mod mock {
                 status: BookingStatus::Confirmed,
                 created_at: Timestamp::now(),
                 ticketing_deadline: Some(Timestamp::now().add_hours(24)),
-                passengers: passengers.iter().map(|p| p.full_name()).collect(),
+                passengers: passengers.iter().map(PassengerDetails::full_name).collect(),
                 offer_id: offer_id.to_string(),
             })
         }
@@ -209,12 +272,13 @@ pub mod mock {
             })
         }
 
-        async fn cancel_booking(&self, _pnr: &str) -> GdsResult<()> {
+        async fn cancel_booking(&self, pnr: &str) -> GdsResult<()> {
             if self.should_fail.load(Ordering::SeqCst) {
                 return Err(crate::error::GdsError::CancellationFailed(
                     "Mock cancellation failure".to_string(),
                 ));
             }
+            self.cancelled.lock().push(pnr.to_string());
             Ok(())
         }
 
@@ -237,6 +301,36 @@ pub mod mock {
             })
         }
 
+        async fn reprice_booking(&self, pnr: &str, offer_id: &str) -> GdsResult<FlightOffer> {
+            self.get_booking(pnr).await?;
+            self.price_offer(offer_id).await
+        }
+
+        async fn seat_maps(&self, pnr: &str) -> GdsResult<Vec<SeatMap>> {
+            self.get_booking(pnr).await?;
+
+            let seat = |number: &str, availability, price: Option<Price>| Seat {
+                number: number.to_string(),
+                cabin: CabinClass::Economy,
+                availability,
+                characteristics: vec![number[number.len() - 1..].to_string()],
+                price,
+            };
+            Ok(vec![SeatMap {
+                segment: 0,
+                flight: "MH88".to_string(),
+                origin: IataCode::KUL,
+                destination: IataCode::NRT,
+                seats: vec![
+                    seat("1A", SeatAvailability::Available, Some(Price::myr(9000))),
+                    seat("12A", SeatAvailability::Available, None),
+                    seat("12B", SeatAvailability::Occupied, None),
+                    // Sold by the operating carrier in its own currency
+                    seat("14C", SeatAvailability::Available, Some(Price::usd(2000))),
+                ],
+            }])
+        }
+
         async fn search_airports(&self, query: &str) -> GdsResult<Vec<AirportInfo>> {
             let airports = vec![
                 AirportInfo {
@@ -300,6 +394,7 @@ pub mod mock {
                 total_duration_minutes: 420,
             },
             return_itinerary: None,
+            onward_itineraries: Vec::new(),
             price: PriceBreakdown::simple(base_price, taxes),
             validating_airline: AirlineCode::MH,
             available_seats: Some(9),
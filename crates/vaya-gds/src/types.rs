@@ -1,7 +1,9 @@
 //! GDS types - Built on vaya-common types
 
 use serde::{Deserialize, Serialize};
-use vaya_common::{AirlineCode, CurrencyCode, Date, IataCode, MinorUnits, Price, Timestamp};
+use vaya_common::{
+    AirlineCode, CurrencyCode, Date, IataCode, MinorUnits, Price, TimeZone, Timestamp, ZonedTime,
+};
 
 /// Cabin class for flights
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
@@ -42,6 +44,29 @@ impl CabinClass {
     }
 }
 
+/// One origin-destination pair of a multi-city search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SearchLeg {
+    /// Origin airport
+    pub origin: IataCode,
+    /// Destination airport
+    pub destination: IataCode,
+    /// Departure date
+    pub departure_date: Date,
+}
+
+impl SearchLeg {
+    /// Create a new leg
+    #[must_use]
+    pub const fn new(origin: IataCode, destination: IataCode, departure_date: Date) -> Self {
+        Self {
+            origin,
+            destination,
+            departure_date,
+        }
+    }
+}
+
 /// Flight search request
 #[derive(Debug, Clone)]
 pub struct FlightSearchRequest {
@@ -67,6 +92,8 @@ pub struct FlightSearchRequest {
     pub max_results: u32,
     /// Preferred currency for prices
     pub currency: CurrencyCode,
+    /// Multi-city legs in travel order (empty for one-way and round trips)
+    pub legs: Vec<SearchLeg>,
 }
 
 impl Default for FlightSearchRequest {
@@ -83,6 +110,7 @@ impl Default for FlightSearchRequest {
             direct_only: false,
             max_results: 50,
             currency: CurrencyCode::MYR,
+            legs: Vec::new(),
         }
     }
 }
@@ -116,6 +144,23 @@ impl FlightSearchRequest {
         }
     }
 
+    /// Create a multi-city search
+    ///
+    /// `origin`, `destination` and `departure_date` mirror the trip's first
+    /// departure and final arrival.
+    #[must_use]
+    pub fn multi_city(legs: Vec<SearchLeg>) -> Self {
+        let first = legs.first().copied().unwrap_or_default();
+        let destination = legs.last().map_or(first.destination, |l| l.destination);
+        Self {
+            origin: first.origin,
+            destination,
+            departure_date: first.departure_date,
+            legs,
+            ..Default::default()
+        }
+    }
+
     /// Set number of passengers
     #[must_use]
     pub const fn with_passengers(mut self, adults: u8, children: u8, infants: u8) -> Self {
@@ -148,16 +193,47 @@ impl FlightSearchRequest {
     /// Is round trip?
     #[must_use]
     pub const fn is_round_trip(&self) -> bool {
-        self.return_date.is_some()
+        self.return_date.is_some() && self.legs.is_empty()
+    }
+
+    /// Is multi-city?
+    #[must_use]
+    pub fn is_multi_city(&self) -> bool {
+        !self.legs.is_empty()
+    }
+
+    /// Origin-destination pairs to search, in travel order
+    #[must_use]
+    pub fn origin_destinations(&self) -> Vec<SearchLeg> {
+        if self.is_multi_city() {
+            return self.legs.clone();
+        }
+        let mut legs = vec![SearchLeg::new(
+            self.origin,
+            self.destination,
+            self.departure_date,
+        )];
+        if let Some(return_date) = self.return_date {
+            legs.push(SearchLeg::new(self.destination, self.origin, return_date));
+        }
+        legs
     }
 
     /// Generate cache key for this request
     #[must_use]
     pub fn cache_key(&self) -> String {
+        let trip = if self.is_multi_city() {
+            self.legs
+                .iter()
+                .map(|l| format!("{}-{}-{}", l.origin, l.destination, l.departure_date))
+                .collect::<Vec<_>>()
+                .join(",")
+        } else {
+            format!("{}:{}", self.origin, self.destination)
+        };
         format!(
-            "search:{}:{}:{}:{}:{}:{}:{}:{}:{}",
-            self.origin,
-            self.destination,
+            "search:{}:{}:{}:{}:{}:{}:{}:{}",
+            trip,
             self.departure_date,
             self.return_date.map_or("OW".to_string(), |d| d.to_string()),
             self.adults,
@@ -178,6 +254,8 @@ pub struct FlightPoint {
     pub terminal: Option<String>,
     /// Date and time (UTC)
     pub datetime: Timestamp,
+    /// Airport timezone, UTC if the airport is not in the reference data
+    pub zone: TimeZone,
 }
 
 impl FlightPoint {
@@ -188,9 +266,24 @@ impl FlightPoint {
             airport,
             terminal: None,
             datetime,
+            zone: TimeZone::airport(&airport).unwrap_or(TimeZone::UTC),
         }
     }
 
+    /// Create a flight point from a local airport time (`2025-01-15T10:30:00`)
+    #[must_use]
+    pub fn from_local(airport: IataCode, local: &str) -> Option<Self> {
+        let zone = TimeZone::airport(&airport).unwrap_or(TimeZone::UTC);
+        let time = ZonedTime::parse_local(local, zone)?;
+        Some(Self::new(airport, time.instant))
+    }
+
+    /// Date and time in the airport's timezone
+    #[must_use]
+    pub fn zoned(&self) -> ZonedTime {
+        ZonedTime::new(self.datetime, self.zone)
+    }
+
     /// With terminal
     #[must_use]
     pub fn with_terminal(mut self, terminal: impl Into<String>) -> Self {
@@ -297,6 +390,29 @@ impl Itinerary {
         self.segments.iter().map(|s| s.airline).collect()
     }
 
+    /// Layover in minutes at each connection, measured between UTC instants
+    #[must_use]
+    pub fn layovers(&self) -> Vec<i64> {
+        self.segments
+            .windows(2)
+            .map(|pair| {
+                pair[0]
+                    .arrival
+                    .zoned()
+                    .minutes_until(&pair[1].departure.zoned())
+            })
+            .collect()
+    }
+
+    /// Local calendar days between departure and arrival ("+1" for next day)
+    #[must_use]
+    pub fn arrival_day_offset(&self) -> i64 {
+        match (self.departure(), self.arrival()) {
+            (Some(departure), Some(arrival)) => departure.zoned().days_until(&arrival.zoned()),
+            _ => 0,
+        }
+    }
+
     /// Duration display string
     #[must_use]
     pub fn duration_display(&self) -> String {
@@ -351,6 +467,8 @@ pub struct FlightOffer {
     pub outbound: Itinerary,
     /// Return itinerary (if round trip)
     pub return_itinerary: Option<Itinerary>,
+    /// Further multi-city itineraries after the outbound, in order
+    pub onward_itineraries: Vec<Itinerary>,
     /// Price breakdown
     pub price: PriceBreakdown,
     /// Validating/ticketing airline
@@ -386,33 +504,36 @@ impl FlightOffer {
         self.return_itinerary.is_some()
     }
 
-    /// Total stops (outbound + return)
+    /// Is multi-city?
+    #[must_use]
+    pub fn is_multi_city(&self) -> bool {
+        !self.onward_itineraries.is_empty()
+    }
+
+    /// All itineraries in travel order
+    pub fn itineraries(&self) -> impl Iterator<Item = &Itinerary> {
+        std::iter::once(&self.outbound)
+            .chain(self.return_itinerary.as_ref())
+            .chain(&self.onward_itineraries)
+    }
+
+    /// Total stops across all itineraries
     #[must_use]
     pub fn total_stops(&self) -> usize {
-        self.outbound.total_stops()
-            + self
-                .return_itinerary
-                .as_ref()
-                .map_or(0, Itinerary::total_stops)
+        self.itineraries().map(Itinerary::total_stops).sum()
     }
 
     /// Is direct flight(s)?
     #[must_use]
     pub fn is_direct(&self) -> bool {
-        self.outbound.is_direct()
-            && self
-                .return_itinerary
-                .as_ref()
-                .is_none_or(Itinerary::is_direct)
+        self.itineraries().all(Itinerary::is_direct)
     }
 
     /// Get all airlines involved (unique)
     #[must_use]
     pub fn airlines(&self) -> Vec<AirlineCode> {
-        let mut airlines = self.outbound.airlines();
-        if let Some(ret) = &self.return_itinerary {
-            airlines.extend(ret.airlines());
-        }
+        let mut airlines: Vec<AirlineCode> =
+            self.itineraries().flat_map(Itinerary::airlines).collect();
         // Deduplicate without sorting (AirlineCode doesn't implement Ord)
         let mut seen = std::collections::HashSet::new();
         airlines.retain(|a| seen.insert(*a));
@@ -516,6 +637,8 @@ pub struct PassengerDetails {
     pub email: Option<String>,
     /// Phone
     pub phone: Option<String>,
+    /// Special service requests (assistance, meals)
+    pub special_services: Vec<SpecialService>,
 }
 
 impl PassengerDetails {
@@ -535,9 +658,20 @@ impl PassengerDetails {
             passport_country: None,
             email: None,
             phone: None,
+            special_services: Vec::new(),
         }
     }
 
+    /// Add a special service request
+    #[must_use]
+    pub fn with_special_service(mut self, code: impl Into<String>, text: Option<String>) -> Self {
+        self.special_services.push(SpecialService {
+            code: code.into(),
+            text,
+        });
+        self
+    }
+
     /// Full name
     #[must_use]
     pub fn full_name(&self) -> String {
@@ -554,6 +688,15 @@ impl PassengerDetails {
     }
 }
 
+/// IATA special service request (SSR) for a passenger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecialService {
+    /// Four-letter SSR code ("WCHR", "BLND")
+    pub code: String,
+    /// Free text sent with the request
+    pub text: Option<String>,
+}
+
 /// Contact details for booking
 #[derive(Debug, Clone)]
 pub struct ContactDetails {
@@ -641,6 +784,78 @@ impl BookingConfirmation {
     }
 }
 
+/// Seat availability on a seat map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeatAvailability {
+    /// Free to select
+    Available,
+    /// Not sold to this traveler (e.g. crew rest, restricted)
+    Blocked,
+    /// Already taken
+    Occupied,
+}
+
+/// A seat on a seat map
+#[derive(Debug, Clone)]
+pub struct Seat {
+    /// Seat number (row and letter, e.g. "12A")
+    pub number: String,
+    /// Cabin the seat is in
+    pub cabin: CabinClass,
+    /// Availability
+    pub availability: SeatAvailability,
+    /// IATA seat characteristic codes (W window, A aisle, E exit row, ...)
+    pub characteristics: Vec<String>,
+    /// Price for paid seats
+    pub price: Option<Price>,
+}
+
+impl Seat {
+    /// Check if the seat can be selected
+    #[must_use]
+    pub fn is_available(&self) -> bool {
+        self.availability == SeatAvailability::Available
+    }
+
+    /// Check if the seat has a characteristic code
+    #[must_use]
+    pub fn has_characteristic(&self, code: &str) -> bool {
+        self.characteristics
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(code))
+    }
+}
+
+/// Seat map of one flight segment
+#[derive(Debug, Clone)]
+pub struct SeatMap {
+    /// Segment position in the itinerary (outbound, then return)
+    pub segment: usize,
+    /// Flight designator (e.g. "MH88")
+    pub flight: String,
+    /// Departure airport
+    pub origin: IataCode,
+    /// Arrival airport
+    pub destination: IataCode,
+    /// Seats across all decks
+    pub seats: Vec<Seat>,
+}
+
+impl SeatMap {
+    /// Find a seat by number
+    #[must_use]
+    pub fn seat(&self, number: &str) -> Option<&Seat> {
+        self.seats
+            .iter()
+            .find(|s| s.number.eq_ignore_ascii_case(number))
+    }
+
+    /// Seats that can be selected
+    pub fn available_seats(&self) -> impl Iterator<Item = &Seat> {
+        self.seats.iter().filter(|s| s.is_available())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -691,6 +906,50 @@ mod tests {
         assert!(segment.is_direct());
     }
 
+    #[test]
+    fn test_local_times_and_layovers() {
+        let segment = |from, departs, to, arrives| FlightSegment {
+            departure: FlightPoint::from_local(from, departs).expect("valid local time"),
+            arrival: FlightPoint::from_local(to, arrives).expect("valid local time"),
+            airline: AirlineCode::MH,
+            flight_number: "1".to_string(),
+            duration_minutes: 0,
+            aircraft: None,
+            cabin_class: CabinClass::Economy,
+            booking_class: None,
+            stops: 0,
+        };
+        // SIN is UTC+8, DXB UTC+4 and LHR UTC+0 in January
+        let itinerary = Itinerary {
+            segments: vec![
+                segment(
+                    IataCode::SIN,
+                    "2025-01-15T20:00",
+                    IataCode::DXB,
+                    "2025-01-15T23:30",
+                ),
+                segment(
+                    IataCode::DXB,
+                    "2025-01-16T02:30",
+                    IataCode::LHR,
+                    "2025-01-16T06:30",
+                ),
+            ],
+            total_duration_minutes: 1110,
+        };
+
+        let departure = &itinerary.segments[0].departure;
+        assert_eq!(departure.datetime.to_string(), "2025-01-15T12:00:00Z");
+        assert_eq!(departure.zoned().to_string(), "2025-01-15T20:00:00+08:00");
+        assert_eq!(itinerary.layovers(), vec![180]);
+        assert_eq!(itinerary.arrival_day_offset(), 1);
+        let last = &itinerary.segments[1];
+        assert_eq!(
+            last.departure.zoned().minutes_until(&last.arrival.zoned()),
+            480
+        );
+    }
+
     #[test]
     fn test_passenger_passport_valid() {
         let mut pax = PassengerDetails::adult("John", "Doe", Date::new(1990, 1, 1));
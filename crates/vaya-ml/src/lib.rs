@@ -17,6 +17,7 @@
 //! - `activation` - Activation functions (ReLU, sigmoid, tanh, etc.)
 //! - `layer` - Neural network layers
 //! - `network` - Neural network architectures
+//! - `optim` - Adam optimizer and gradient clipping
 //! - `xgboost` - Gradient boosting implementation
 //! - `scaler` - Feature scaling utilities
 
@@ -27,16 +28,20 @@ pub mod layer;
 pub mod lstm;
 pub mod matrix;
 pub mod network;
+pub mod optim;
 pub mod scaler;
 pub mod tree;
+pub mod xgboost;
 
 pub use activation::Activation;
 pub use layer::Layer;
-pub use lstm::{LSTMCell, LSTMState, PriceLSTM, LSTM};
+pub use lstm::{LSTMCell, LSTMState, LSTMTrainConfig, PriceLSTM, TrainingHistory, LSTM};
 pub use matrix::Matrix;
 pub use network::NeuralNetwork;
+pub use optim::{Adam, AdamConfig};
 pub use scaler::StandardScaler;
 pub use tree::DecisionTree;
+pub use xgboost::{BoostingConfig, BoostingLoss, GradientBoostingRegressor};
 
 /// Machine learning error types
 #[derive(Debug)]
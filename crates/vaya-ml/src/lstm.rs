@@ -12,8 +12,19 @@
 //! o_t = σ(W_o · [h_{t-1}, x_t] + b_o)  // Output gate
 //! h_t = o_t ⊙ tanh(c_t)  // Hidden state
 //! ```
+//!
+//! # Training
+//!
+//! [`PriceLSTM::train`] runs backpropagation through time: each sequence is
+//! run forward with the gate activations cached, the squared error of the
+//! output is propagated back through every timestep and layer, and the
+//! averaged mini-batch gradients are clipped to a global norm before an
+//! [`Adam`] update.
+
+use rand::seq::SliceRandom;
 
 use crate::matrix::Matrix;
+use crate::optim::{clip_grad_norm, Adam, AdamConfig};
 use crate::{MlError, MlResult};
 
 /// Sigmoid activation function
@@ -105,6 +116,16 @@ impl LSTMCell {
 
     /// Forward pass through the LSTM cell
     pub fn forward(&self, input: &Matrix, prev_state: &LSTMState) -> MlResult<LSTMState> {
+        self.forward_cached(input, prev_state)
+            .map(|(state, _)| state)
+    }
+
+    /// Forward pass keeping the activations needed for the backward pass
+    fn forward_cached(
+        &self,
+        input: &Matrix,
+        prev_state: &LSTMState,
+    ) -> MlResult<(LSTMState, CellCache)> {
         // Concatenate [h_{t-1}, x_t]
         let combined = prev_state.hidden.concat_vertical(input)?;
 
@@ -137,7 +158,84 @@ impl LSTMCell {
         let cell_tanh = cell.map(|x| x.tanh());
         let hidden = o_t.hadamard(&cell_tanh)?;
 
-        Ok(LSTMState { hidden, cell })
+        let cache = CellCache {
+            combined,
+            prev_cell: prev_state.cell.clone(),
+            f_t,
+            i_t,
+            c_tilde,
+            o_t,
+            cell_tanh,
+        };
+        Ok((LSTMState { hidden, cell }, cache))
+    }
+
+    /// Backward pass for one timestep
+    ///
+    /// Takes the gradients flowing into h_t and c_t, adds the weight
+    /// gradients to `grads` and returns the gradients for x_t, h_{t-1} and
+    /// c_{t-1}.
+    fn backward(
+        &self,
+        cache: &CellCache,
+        d_hidden: &Matrix,
+        d_cell_next: &Matrix,
+        grads: &mut CellGradients,
+    ) -> MlResult<(Matrix, Matrix, Matrix)> {
+        // h_t = o_t ⊙ tanh(c_t)
+        let d_o = d_hidden.hadamard(&cache.cell_tanh)?;
+        let d_cell = d_hidden
+            .hadamard(&cache.o_t)?
+            .hadamard(&cache.cell_tanh.map(|t| 1.0 - t * t))?
+            .add(d_cell_next)?;
+
+        // c_t = f_t ⊙ c_{t-1} + i_t ⊙ c̃_t
+        let d_f = d_cell.hadamard(&cache.prev_cell)?;
+        let d_i = d_cell.hadamard(&cache.c_tilde)?;
+        let d_c_tilde = d_cell.hadamard(&cache.i_t)?;
+        let d_prev_cell = d_cell.hadamard(&cache.f_t)?;
+
+        // Back through the gate activations
+        let sigmoid_grad = |s: &Matrix| s.map(|v| v * (1.0 - v));
+        let dz_f = d_f.hadamard(&sigmoid_grad(&cache.f_t))?;
+        let dz_i = d_i.hadamard(&sigmoid_grad(&cache.i_t))?;
+        let dz_c = d_c_tilde.hadamard(&cache.c_tilde.map(|t| 1.0 - t * t))?;
+        let dz_o = d_o.hadamard(&sigmoid_grad(&cache.o_t))?;
+
+        let combined_t = cache.combined.transpose();
+        let mut d_combined = Matrix::zeros(cache.combined.rows(), 1);
+        for (dz, w, d_w, d_b) in [
+            (&dz_f, &self.w_f, &mut grads.w_f, &mut grads.b_f),
+            (&dz_i, &self.w_i, &mut grads.w_i, &mut grads.b_i),
+            (&dz_c, &self.w_c, &mut grads.w_c, &mut grads.b_c),
+            (&dz_o, &self.w_o, &mut grads.w_o, &mut grads.b_o),
+        ] {
+            *d_w = d_w.add(&dz.matmul(&combined_t)?)?;
+            *d_b = d_b.add(dz)?;
+            d_combined = d_combined.add(&w.transpose_matmul(dz)?)?;
+        }
+
+        // Split d[h_{t-1}, x_t]
+        let (d_prev_hidden, d_input) = d_combined.data().split_at(self.hidden_size);
+        Ok((
+            Matrix::from_slice(d_input),
+            Matrix::from_slice(d_prev_hidden),
+            d_prev_cell,
+        ))
+    }
+
+    /// Parameters in a fixed order, matching [`CellGradients::into_vec`]
+    fn params_mut(&mut self) -> [&mut Matrix; 8] {
+        [
+            &mut self.w_f,
+            &mut self.b_f,
+            &mut self.w_i,
+            &mut self.b_i,
+            &mut self.w_c,
+            &mut self.b_c,
+            &mut self.w_o,
+            &mut self.b_o,
+        ]
     }
 
     /// Initialize state for this cell
@@ -146,6 +244,63 @@ impl LSTMCell {
     }
 }
 
+/// Activations of one cell step, kept for the backward pass
+#[derive(Debug, Clone)]
+struct CellCache {
+    /// [h_{t-1}, x_t]
+    combined: Matrix,
+    /// c_{t-1}
+    prev_cell: Matrix,
+    /// Forget gate activation
+    f_t: Matrix,
+    /// Input gate activation
+    i_t: Matrix,
+    /// Candidate activation
+    c_tilde: Matrix,
+    /// Output gate activation
+    o_t: Matrix,
+    /// tanh(c_t)
+    cell_tanh: Matrix,
+}
+
+/// Weight gradients of one LSTM cell
+#[derive(Debug, Clone)]
+struct CellGradients {
+    w_f: Matrix,
+    b_f: Matrix,
+    w_i: Matrix,
+    b_i: Matrix,
+    w_c: Matrix,
+    b_c: Matrix,
+    w_o: Matrix,
+    b_o: Matrix,
+}
+
+impl CellGradients {
+    /// Zero gradients shaped like `cell`'s parameters
+    fn zeros(cell: &LSTMCell) -> Self {
+        let w = || Matrix::zeros(cell.hidden_size, cell.input_size + cell.hidden_size);
+        let b = || Matrix::zeros(cell.hidden_size, 1);
+        Self {
+            w_f: w(),
+            b_f: b(),
+            w_i: w(),
+            b_i: b(),
+            w_c: w(),
+            b_c: b(),
+            w_o: w(),
+            b_o: b(),
+        }
+    }
+
+    /// Gradients in the order of [`LSTMCell::params_mut`]
+    fn into_vec(self) -> Vec<Matrix> {
+        vec![
+            self.w_f, self.b_f, self.w_i, self.b_i, self.w_c, self.b_c, self.w_o, self.b_o,
+        ]
+    }
+}
+
 /// LSTM layer - processes sequences
 #[derive(Debug, Clone)]
 pub struct LSTM {
@@ -263,6 +418,61 @@ impl LSTM {
         let final_hidden = states.last().unwrap().hidden.clone();
         Ok((final_hidden, states))
     }
+
+    /// Forward pass keeping every cell's activations, indexed [timestep][layer]
+    fn forward_cached(&self, sequence: &[Matrix]) -> MlResult<(Matrix, Vec<Vec<CellCache>>)> {
+        let mut states: Vec<LSTMState> = self.cells.iter().map(|c| c.init_state()).collect();
+        let mut caches = Vec::with_capacity(sequence.len());
+
+        for input in sequence {
+            let mut layer_input = input.clone();
+            let mut step = Vec::with_capacity(self.num_layers);
+            for (i, cell) in self.cells.iter().enumerate() {
+                let (state, cache) = cell.forward_cached(&layer_input, &states[i])?;
+                layer_input = state.hidden.clone();
+                states[i] = state;
+                step.push(cache);
+            }
+            caches.push(step);
+        }
+
+        let hidden = states.last().unwrap().hidden.clone();
+        Ok((hidden, caches))
+    }
+
+    /// Backpropagate a gradient on the final hidden state through time
+    fn backward(
+        &self,
+        caches: &[Vec<CellCache>],
+        d_output: &Matrix,
+        grads: &mut [CellGradients],
+    ) -> MlResult<()> {
+        let zeros = || Matrix::zeros(self.hidden_size, 1);
+        let mut d_hidden_next: Vec<Matrix> = (0..self.num_layers).map(|_| zeros()).collect();
+        let mut d_cell_next = d_hidden_next.clone();
+
+        for (t, step) in caches.iter().enumerate().rev() {
+            // Only the last timestep's top layer feeds the output
+            let mut d_from_above = if t + 1 == caches.len() {
+                d_output.clone()
+            } else {
+                zeros()
+            };
+            for layer in (0..self.num_layers).rev() {
+                let d_hidden = d_hidden_next[layer].add(&d_from_above)?;
+                let (d_input, d_prev_hidden, d_prev_cell) = self.cells[layer].backward(
+                    &step[layer],
+                    &d_hidden,
+                    &d_cell_next[layer],
+                    &mut grads[layer],
+                )?;
+                d_hidden_next[layer] = d_prev_hidden;
+                d_cell_next[layer] = d_prev_cell;
+                d_from_above = d_input;
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Add column bias to a column vector
@@ -308,6 +518,215 @@ impl PriceLSTM {
         let output = self.output_weights.matmul(&hidden)?;
         add_column_bias(&output, &self.output_bias)
     }
+
+    /// Mean squared error over a set of sequences
+    pub fn loss(&self, sequences: &[Vec<Matrix>], targets: &[Matrix]) -> MlResult<f32> {
+        check_samples(sequences, targets)?;
+        let mut total = 0.0;
+        for (sequence, target) in sequences.iter().zip(targets) {
+            total += squared_error(&self.predict(sequence)?, target)?;
+        }
+        Ok(total / sequences.len() as f32)
+    }
+
+    /// Train with backpropagation through time
+    ///
+    /// The last `validation_split` of the samples is held out (they are
+    /// usually the most recent windows of a time series) and the rest is
+    /// shuffled into mini-batches every epoch. Returns the per-epoch loss
+    /// curves.
+    pub fn train(
+        &mut self,
+        sequences: &[Vec<Matrix>],
+        targets: &[Matrix],
+        config: &LSTMTrainConfig,
+    ) -> MlResult<TrainingHistory> {
+        check_samples(sequences, targets)?;
+        if config.batch_size == 0 || config.epochs == 0 {
+            return Err(MlError::InvalidParameter(
+                "Epochs and batch size must be positive".into(),
+            ));
+        }
+        if !(0.0..1.0).contains(&config.validation_split) {
+            return Err(MlError::InvalidParameter(format!(
+                "Validation split must be in [0, 1), got {}",
+                config.validation_split
+            )));
+        }
+
+        let n = sequences.len();
+        let n_validation = ((n as f32 * config.validation_split).round() as usize).min(n - 1);
+        let n_train = n - n_validation;
+        let (validation_x, validation_y) = (&sequences[n_train..], &targets[n_train..]);
+
+        let mut optimizer = Adam::new(config.optimizer, &self.param_shapes());
+        let mut order: Vec<usize> = (0..n_train).collect();
+        let mut rng = rand::thread_rng();
+        let mut history = TrainingHistory::default();
+
+        for epoch in 0..config.epochs {
+            order.shuffle(&mut rng);
+            let mut epoch_loss = 0.0;
+
+            for batch in order.chunks(config.batch_size) {
+                let mut batch_grads: Option<Vec<Matrix>> = None;
+                for &i in batch {
+                    let (loss, grads) = self.gradients(&sequences[i], &targets[i])?;
+                    epoch_loss += loss;
+                    batch_grads = Some(match batch_grads {
+                        Some(sum) => sum
+                            .iter()
+                            .zip(&grads)
+                            .map(|(a, b)| a.add(b))
+                            .collect::<MlResult<_>>()?,
+                        None => grads,
+                    });
+                }
+
+                let mut grads: Vec<Matrix> = batch_grads
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|g| g.scale(1.0 / batch.len() as f32))
+                    .collect();
+                clip_grad_norm(&mut grads, config.clip_norm);
+                optimizer.step(&mut self.params_mut(), &grads)?;
+            }
+
+            let train_loss = epoch_loss / n_train as f32;
+            if !train_loss.is_finite() {
+                return Err(MlError::TrainingFailed(format!(
+                    "Loss diverged at epoch {}",
+                    epoch
+                )));
+            }
+            history.train_loss.push(train_loss);
+            if n_validation > 0 {
+                history
+                    .validation_loss
+                    .push(self.loss(validation_x, validation_y)?);
+            }
+
+            if epoch % 10 == 0 {
+                tracing::debug!("Epoch {}: loss = {:.6}", epoch, train_loss);
+            }
+        }
+
+        Ok(history)
+    }
+
+    /// Loss and parameter gradients for one sample
+    fn gradients(&self, sequence: &[Matrix], target: &Matrix) -> MlResult<(f32, Vec<Matrix>)> {
+        let (hidden, caches) = self.lstm.forward_cached(sequence)?;
+        let output = add_column_bias(&self.output_weights.matmul(&hidden)?, &self.output_bias)?;
+        let loss = squared_error(&output, target)?;
+
+        // d(MSE)/d(output)
+        let d_output = output.sub(target)?.scale(2.0 / output.rows() as f32);
+        let d_output_weights = d_output.matmul(&hidden.transpose())?;
+        let d_hidden = self.output_weights.transpose_matmul(&d_output)?;
+
+        let mut cell_grads: Vec<CellGradients> =
+            self.lstm.cells.iter().map(CellGradients::zeros).collect();
+        self.lstm.backward(&caches, &d_hidden, &mut cell_grads)?;
+
+        let mut grads: Vec<Matrix> = cell_grads
+            .into_iter()
+            .flat_map(CellGradients::into_vec)
+            .collect();
+        grads.push(d_output_weights);
+        grads.push(d_output);
+        Ok((loss, grads))
+    }
+
+    /// Trainable parameters, cell by cell and then the output projection
+    fn params_mut(&mut self) -> Vec<&mut Matrix> {
+        let mut params: Vec<&mut Matrix> = self
+            .lstm
+            .cells
+            .iter_mut()
+            .flat_map(LSTMCell::params_mut)
+            .collect();
+        params.push(&mut self.output_weights);
+        params.push(&mut self.output_bias);
+        params
+    }
+
+    /// Shapes of the parameters in [`Self::params_mut`] order
+    fn param_shapes(&mut self) -> Vec<(usize, usize)> {
+        self.params_mut().iter().map(|p| p.shape()).collect()
+    }
+}
+
+/// Training settings for [`PriceLSTM::train`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LSTMTrainConfig {
+    /// Passes over the training samples
+    pub epochs: usize,
+    /// Samples per gradient update
+    pub batch_size: usize,
+    /// Maximum global gradient norm
+    pub clip_norm: f32,
+    /// Fraction of samples (taken from the end) held out for validation
+    pub validation_split: f32,
+    /// Optimizer settings
+    pub optimizer: AdamConfig,
+}
+
+impl Default for LSTMTrainConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 50,
+            batch_size: 16,
+            clip_norm: 1.0,
+            validation_split: 0.2,
+            optimizer: AdamConfig {
+                learning_rate: 0.01,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Per-epoch loss curves from [`PriceLSTM::train`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrainingHistory {
+    /// Mean training loss per epoch
+    pub train_loss: Vec<f32>,
+    /// Validation loss after each epoch (empty without a validation split)
+    pub validation_loss: Vec<f32>,
+}
+
+impl TrainingHistory {
+    /// Training loss of the last epoch
+    pub fn final_loss(&self) -> Option<f32> {
+        self.train_loss.last().copied()
+    }
+
+    /// Validation loss of the last epoch
+    pub fn final_validation_loss(&self) -> Option<f32> {
+        self.validation_loss.last().copied()
+    }
+}
+
+/// Check that there is one target per sequence
+fn check_samples(sequences: &[Vec<Matrix>], targets: &[Matrix]) -> MlResult<()> {
+    if sequences.is_empty() {
+        return Err(MlError::InvalidParameter("No training sequences".into()));
+    }
+    if sequences.len() != targets.len() {
+        return Err(MlError::InvalidParameter(format!(
+            "{} sequences but {} targets",
+            sequences.len(),
+            targets.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Mean squared error between an output and its target
+fn squared_error(output: &Matrix, target: &Matrix) -> MlResult<f32> {
+    let diff = output.sub(target)?;
+    Ok(diff.hadamard(&diff)?.mean())
 }
 
 #[cfg(test)]
@@ -384,6 +803,91 @@ mod tests {
         assert_eq!(prediction.cols(), 1);
     }
 
+    /// Sequences of a sine wave and the value that follows each
+    fn sine_samples(count: usize, len: usize) -> (Vec<Vec<Matrix>>, Vec<Matrix>) {
+        let value = |t: usize| (t as f32 * 0.3).sin();
+        let sequences = (0..count)
+            .map(|start| {
+                (start..start + len)
+                    .map(|t| Matrix::from_slice(&[value(t)]))
+                    .collect()
+            })
+            .collect();
+        let targets = (0..count)
+            .map(|start| Matrix::from_slice(&[value(start + len)]))
+            .collect();
+        (sequences, targets)
+    }
+
+    #[test]
+    fn test_gradients_match_finite_differences() {
+        let mut model = PriceLSTM::new(2, 3, 2, 1);
+        let sequence: Vec<Matrix> = (0..4)
+            .map(|t| Matrix::from_slice(&[t as f32 * 0.5 - 0.7, 0.3 - t as f32 * 0.2]))
+            .collect();
+        let target = Matrix::from_slice(&[0.4]);
+        let (_, grads) = model.gradients(&sequence, &target).unwrap();
+
+        let (sequences, targets) = (vec![sequence], vec![target]);
+        let eps = 1e-2;
+        for (p, grad) in grads.iter().enumerate() {
+            for k in [0, grad.data().len() - 1] {
+                let original = model.params_mut()[p].data()[k];
+                model.params_mut()[p].data_mut()[k] = original + eps;
+                let plus = model.loss(&sequences, &targets).unwrap();
+                model.params_mut()[p].data_mut()[k] = original - eps;
+                let minus = model.loss(&sequences, &targets).unwrap();
+                model.params_mut()[p].data_mut()[k] = original;
+
+                let numeric = (plus - minus) / (2.0 * eps);
+                let analytic = grad.data()[k];
+                assert!(
+                    (numeric - analytic).abs() < 1e-3 + 0.05 * numeric.abs(),
+                    "param {} element {}: numeric {} vs analytic {}",
+                    p,
+                    k,
+                    numeric,
+                    analytic
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_training_reduces_loss() {
+        let (sequences, targets) = sine_samples(40, 6);
+        let mut model = PriceLSTM::new(1, 8, 1, 1);
+        let before = model.loss(&sequences, &targets).unwrap();
+
+        let config = LSTMTrainConfig {
+            epochs: 60,
+            batch_size: 8,
+            ..Default::default()
+        };
+        let history = model.train(&sequences, &targets, &config).unwrap();
+
+        assert_eq!(history.train_loss.len(), 60);
+        assert_eq!(history.validation_loss.len(), 60);
+        assert!(history.final_loss().unwrap() < history.train_loss[0]);
+        assert!(history.final_validation_loss().unwrap() < before);
+        assert!(model.loss(&sequences, &targets).unwrap() < before * 0.5);
+    }
+
+    #[test]
+    fn test_training_rejects_bad_input() {
+        let (sequences, targets) = sine_samples(4, 3);
+        let mut model = PriceLSTM::new(1, 4, 1, 1);
+        let config = LSTMTrainConfig::default();
+
+        assert!(model.train(&[], &[], &config).is_err());
+        assert!(model.train(&sequences, &targets[..2], &config).is_err());
+        let zero_batch = LSTMTrainConfig {
+            batch_size: 0,
+            ..config
+        };
+        assert!(model.train(&sequences, &targets, &zero_batch).is_err());
+    }
+
     #[test]
     fn test_lstm_state() {
         let state = LSTMState::zeros(15);
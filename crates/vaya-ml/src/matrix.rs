@@ -134,13 +134,41 @@ impl Matrix {
 
         let mut result = Matrix::zeros(self.rows, other.cols);
 
+        // i-k-j order walks both operands row by row
         for i in 0..self.rows {
-            for j in 0..other.cols {
-                let mut sum = 0.0;
-                for k in 0..self.cols {
-                    sum += self.get(i, k) * other.get(k, j);
+            let out = &mut result.data[i * other.cols..(i + 1) * other.cols];
+            for k in 0..self.cols {
+                let a = self.data[i * self.cols + k];
+                let row = &other.data[k * other.cols..(k + 1) * other.cols];
+                for (o, b) in out.iter_mut().zip(row) {
+                    *o += a * b;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Multiply the transpose of this matrix by `other` (selfᵀ · other)
+    ///
+    /// Avoids materializing the transpose.
+    pub fn transpose_matmul(&self, other: &Matrix) -> MlResult<Matrix> {
+        if self.rows != other.rows {
+            return Err(MlError::DimensionMismatch {
+                expected: (self.cols, other.cols),
+                actual: (self.rows, other.rows),
+            });
+        }
+
+        let mut result = Matrix::zeros(self.cols, other.cols);
+        for k in 0..self.rows {
+            let lhs = &self.data[k * self.cols..(k + 1) * self.cols];
+            let rhs = &other.data[k * other.cols..(k + 1) * other.cols];
+            for (i, a) in lhs.iter().enumerate() {
+                let out = &mut result.data[i * other.cols..(i + 1) * other.cols];
+                for (o, b) in out.iter_mut().zip(rhs) {
+                    *o += a * b;
                 }
-                result.set(i, j, sum);
             }
         }
 
@@ -403,6 +431,16 @@ mod tests {
         assert_eq!(c.get(1, 1), 50.0); // 3*6 + 4*8
     }
 
+    #[test]
+    fn test_transpose_matmul() {
+        let a = Matrix::from_vec(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        let b = Matrix::from_vec(vec![vec![1.0], vec![2.0]]);
+
+        let expected = a.transpose().matmul(&b).unwrap();
+        assert_eq!(a.transpose_matmul(&b).unwrap(), expected);
+        assert!(a.transpose_matmul(&a.transpose()).is_err());
+    }
+
     #[test]
     fn test_transpose() {
         let a = Matrix::from_vec(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
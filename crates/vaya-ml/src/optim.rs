@@ -0,0 +1,191 @@
+//! Optimizers and gradient utilities
+//!
+//! [`Adam`] keeps per-parameter first and second moment estimates and
+//! applies bias-corrected updates. [`clip_grad_norm`] rescales a set of
+//! gradients so their combined L2 norm stays under a limit, which keeps
+//! recurrent networks from blowing up on long sequences.
+
+use crate::matrix::Matrix;
+use crate::{MlError, MlResult};
+
+/// Adam hyperparameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdamConfig {
+    /// Step size
+    pub learning_rate: f32,
+    /// Decay rate of the first moment estimate
+    pub beta1: f32,
+    /// Decay rate of the second moment estimate
+    pub beta2: f32,
+    /// Term added to the denominator for numerical stability
+    pub epsilon: f32,
+}
+
+impl Default for AdamConfig {
+    fn default() -> Self {
+        Self {
+            learning_rate: 0.001,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+/// Adam optimizer
+#[derive(Debug, Clone)]
+pub struct Adam {
+    /// Hyperparameters
+    config: AdamConfig,
+    /// Updates applied so far
+    step: u32,
+    /// First moment estimate per parameter
+    m: Vec<Matrix>,
+    /// Second moment estimate per parameter
+    v: Vec<Matrix>,
+}
+
+impl Adam {
+    /// Create an optimizer for parameters with the given shapes
+    pub fn new(config: AdamConfig, shapes: &[(usize, usize)]) -> Self {
+        let zeros: Vec<Matrix> = shapes
+            .iter()
+            .map(|&(rows, cols)| Matrix::zeros(rows, cols))
+            .collect();
+        Self {
+            config,
+            step: 0,
+            m: zeros.clone(),
+            v: zeros,
+        }
+    }
+
+    /// Get the hyperparameters
+    pub fn config(&self) -> &AdamConfig {
+        &self.config
+    }
+
+    /// Apply one update to `params` from `grads`
+    ///
+    /// Parameters and gradients must be in the order the optimizer was
+    /// created with.
+    pub fn step(&mut self, params: &mut [&mut Matrix], grads: &[Matrix]) -> MlResult<()> {
+        if params.len() != self.m.len() || grads.len() != self.m.len() {
+            return Err(MlError::InvalidParameter(format!(
+                "Expected {} parameters, got {} parameters and {} gradients",
+                self.m.len(),
+                params.len(),
+                grads.len()
+            )));
+        }
+
+        self.step += 1;
+        let AdamConfig {
+            learning_rate,
+            beta1,
+            beta2,
+            epsilon,
+        } = self.config;
+        let bias1 = 1.0 - beta1.powi(self.step as i32);
+        let bias2 = 1.0 - beta2.powi(self.step as i32);
+
+        for (((param, grad), m), v) in params
+            .iter_mut()
+            .zip(grads)
+            .zip(&mut self.m)
+            .zip(&mut self.v)
+        {
+            if param.shape() != m.shape() || grad.shape() != m.shape() {
+                return Err(MlError::DimensionMismatch {
+                    expected: m.shape(),
+                    actual: grad.shape(),
+                });
+            }
+            let updates = param
+                .data_mut()
+                .iter_mut()
+                .zip(grad.data())
+                .zip(m.data_mut().iter_mut().zip(v.data_mut()));
+            for ((p, &g), (m, v)) in updates {
+                *m = beta1 * *m + (1.0 - beta1) * g;
+                *v = beta2 * *v + (1.0 - beta2) * g * g;
+                let m_hat = *m / bias1;
+                let v_hat = *v / bias2;
+                *p -= learning_rate * m_hat / (v_hat.sqrt() + epsilon);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scale `grads` down so their global L2 norm is at most `max_norm`
+///
+/// Returns the norm before clipping.
+pub fn clip_grad_norm(grads: &mut [Matrix], max_norm: f32) -> f32 {
+    let norm = grads
+        .iter()
+        .flat_map(|g| g.data())
+        .map(|x| x * x)
+        .sum::<f32>()
+        .sqrt();
+    if norm > max_norm && norm > 0.0 {
+        let scale = max_norm / norm;
+        for grad in grads.iter_mut() {
+            for x in grad.data_mut() {
+                *x *= scale;
+            }
+        }
+    }
+    norm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adam_minimizes_quadratic() {
+        // Minimize (x - 3)^2 + (y + 1)^2
+        let mut param = Matrix::from_slice(&[0.0, 0.0]);
+        let mut adam = Adam::new(
+            AdamConfig {
+                learning_rate: 0.1,
+                ..Default::default()
+            },
+            &[param.shape()],
+        );
+
+        for _ in 0..500 {
+            let grad =
+                Matrix::from_slice(&[2.0 * (param.get(0, 0) - 3.0), 2.0 * (param.get(1, 0) + 1.0)]);
+            adam.step(&mut [&mut param], &[grad]).unwrap();
+        }
+
+        assert!((param.get(0, 0) - 3.0).abs() < 0.05);
+        assert!((param.get(1, 0) + 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_adam_rejects_mismatched_gradients() {
+        let mut param = Matrix::zeros(2, 1);
+        let mut adam = Adam::new(AdamConfig::default(), &[param.shape()]);
+        assert!(adam.step(&mut [&mut param], &[]).is_err());
+        assert!(adam
+            .step(&mut [&mut param], &[Matrix::zeros(3, 1)])
+            .is_err());
+    }
+
+    #[test]
+    fn test_clip_grad_norm() {
+        let mut grads = vec![Matrix::from_slice(&[3.0]), Matrix::from_slice(&[4.0])];
+        let norm = clip_grad_norm(&mut grads, 1.0);
+        assert!((norm - 5.0).abs() < 1e-6);
+        assert!((grads[0].get(0, 0) - 0.6).abs() < 1e-6);
+        assert!((grads[1].get(0, 0) - 0.8).abs() < 1e-6);
+
+        // Already within the limit
+        let norm = clip_grad_norm(&mut grads, 2.0);
+        assert!((norm - 1.0).abs() < 1e-6);
+        assert!((grads[1].get(0, 0) - 0.8).abs() < 1e-6);
+    }
+}
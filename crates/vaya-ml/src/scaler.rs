@@ -103,6 +103,16 @@ impl StandardScaler {
     pub fn is_fitted(&self) -> bool {
         self.mean.is_some() && self.std.is_some()
     }
+
+    /// Fitted mean of each feature
+    pub fn mean(&self) -> Option<&[f32]> {
+        self.mean.as_deref()
+    }
+
+    /// Fitted standard deviation of each feature
+    pub fn std(&self) -> Option<&[f32]> {
+        self.std.as_deref()
+    }
 }
 
 impl Default for StandardScaler {
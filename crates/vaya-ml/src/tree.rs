@@ -40,13 +40,17 @@ impl DecisionTree {
 
     /// Fit the tree to data
     pub fn fit(&mut self, x: &Matrix, y: &Matrix) -> MlResult<()> {
-        self.nodes.clear();
-
         // Collect all indices
         let indices: Vec<usize> = (0..x.rows()).collect();
+        self.fit_indices(x, y, &indices)
+    }
+
+    /// Fit the tree to the rows of `x` and `y` listed in `indices`
+    pub fn fit_indices(&mut self, x: &Matrix, y: &Matrix, indices: &[usize]) -> MlResult<()> {
+        self.nodes.clear();
 
         // Build tree recursively
-        self.build_tree(x, y, &indices, 0);
+        self.build_tree(x, y, indices, 0);
 
         Ok(())
     }
@@ -220,96 +224,6 @@ impl DecisionTree {
     }
 }
 
-/// Gradient Boosting Regressor
-#[derive(Debug, Clone)]
-pub struct GradientBoostingRegressor {
-    /// Ensemble of trees
-    trees: Vec<DecisionTree>,
-    /// Learning rate
-    learning_rate: f32,
-    /// Number of trees
-    n_estimators: usize,
-    /// Maximum depth per tree
-    max_depth: usize,
-    /// Initial prediction (mean of training data)
-    initial_prediction: f32,
-}
-
-impl GradientBoostingRegressor {
-    /// Create a new gradient boosting regressor
-    pub fn new(n_estimators: usize, learning_rate: f32, max_depth: usize) -> Self {
-        Self {
-            trees: Vec::new(),
-            learning_rate,
-            n_estimators,
-            max_depth,
-            initial_prediction: 0.0,
-        }
-    }
-
-    /// Fit the model to training data
-    pub fn fit(&mut self, x: &Matrix, y: &Matrix) -> MlResult<()> {
-        self.trees.clear();
-
-        // Initial prediction is the mean
-        self.initial_prediction = y.mean();
-
-        // Current predictions
-        let mut predictions = Matrix::zeros(y.rows(), 1);
-        for i in 0..y.rows() {
-            predictions.set(i, 0, self.initial_prediction);
-        }
-
-        for _ in 0..self.n_estimators {
-            // Calculate residuals (negative gradient for MSE loss)
-            let residuals = y.sub(&predictions)?;
-
-            // Fit a tree to the residuals
-            let mut tree = DecisionTree::new(self.max_depth, 2);
-            tree.fit(x, &residuals)?;
-
-            // Update predictions
-            let tree_predictions = tree.predict(x);
-            for i in 0..y.rows() {
-                let new_pred =
-                    predictions.get(i, 0) + self.learning_rate * tree_predictions.get(i, 0);
-                predictions.set(i, 0, new_pred);
-            }
-
-            self.trees.push(tree);
-        }
-
-        Ok(())
-    }
-
-    /// Predict values for input data
-    pub fn predict(&self, x: &Matrix) -> Matrix {
-        let mut predictions = Matrix::zeros(x.rows(), 1);
-
-        // Start with initial prediction
-        for i in 0..x.rows() {
-            predictions.set(i, 0, self.initial_prediction);
-        }
-
-        // Add contributions from each tree
-        for tree in &self.trees {
-            let tree_predictions = tree.predict(x);
-            for i in 0..x.rows() {
-                let new_pred =
-                    predictions.get(i, 0) + self.learning_rate * tree_predictions.get(i, 0);
-                predictions.set(i, 0, new_pred);
-            }
-        }
-
-        predictions
-    }
-
-    /// Get the number of trees
-    pub fn num_trees(&self) -> usize {
-        self.trees.len()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -327,20 +241,13 @@ mod tests {
     }
 
     #[test]
-    fn test_gradient_boosting() {
-        let x = Matrix::from_vec(vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]);
-        let y = Matrix::from_vec(vec![vec![2.0], vec![4.0], vec![6.0], vec![8.0], vec![10.0]]);
-
-        let mut gbr = GradientBoostingRegressor::new(10, 0.1, 3);
-        gbr.fit(&x, &y).unwrap();
+    fn test_fit_indices() {
+        let x = Matrix::from_vec(vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0]]);
+        let y = Matrix::from_vec(vec![vec![1.0], vec![1.0], vec![9.0], vec![9.0]]);
 
-        let predictions = gbr.predict(&x);
-
-        // Predictions should be reasonably close to actual values
-        for i in 0..5 {
-            let pred = predictions.get(i, 0);
-            let actual = y.get(i, 0);
-            assert!((pred - actual).abs() < 2.0);
-        }
+        // Only the first two rows are seen, so everything predicts 1
+        let mut tree = DecisionTree::new(3, 1);
+        tree.fit_indices(&x, &y, &[0, 1]).unwrap();
+        assert_eq!(tree.predict(&x).get(3, 0), 1.0);
     }
 }
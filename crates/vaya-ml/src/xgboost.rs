@@ -0,0 +1,457 @@
+//! Gradient boosted regression trees
+//!
+//! Each round fits a [`DecisionTree`] to the negative gradient of the loss
+//! (the residuals for squared loss, clipped residuals for Huber loss) and
+//! adds a learning-rate-scaled copy of it to the ensemble. Rounds can fit
+//! on a random subsample of the rows (stochastic gradient boosting), and
+//! training stops early once the validation loss has not improved for a
+//! number of rounds; the ensemble is then cut back to its best round.
+
+use rand::seq::index::sample;
+
+use crate::matrix::Matrix;
+use crate::tree::DecisionTree;
+use crate::{MlError, MlResult};
+
+/// Loss minimized by the boosted ensemble
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoostingLoss {
+    /// Squared error
+    Squared,
+    /// Squared error for residuals up to `delta`, linear beyond it
+    Huber {
+        /// Residual size where the loss turns linear
+        delta: f32,
+    },
+}
+
+impl BoostingLoss {
+    /// Loss for one residual (target - prediction)
+    fn loss(&self, residual: f32) -> f32 {
+        match *self {
+            BoostingLoss::Squared => residual * residual,
+            BoostingLoss::Huber { delta } => {
+                let abs = residual.abs();
+                if abs <= delta {
+                    0.5 * residual * residual
+                } else {
+                    delta * (abs - 0.5 * delta)
+                }
+            }
+        }
+    }
+
+    /// Negative gradient of the loss for one residual
+    fn negative_gradient(&self, residual: f32) -> f32 {
+        match *self {
+            BoostingLoss::Squared => residual,
+            BoostingLoss::Huber { delta } => residual.clamp(-delta, delta),
+        }
+    }
+
+    /// Constant prediction that minimizes the loss before any tree
+    fn initial_prediction(&self, y: &[f32]) -> f32 {
+        match self {
+            BoostingLoss::Squared => y.iter().sum::<f32>() / y.len() as f32,
+            BoostingLoss::Huber { .. } => {
+                let mut sorted = y.to_vec();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                // Median; the two indices meet for odd lengths
+                let len = sorted.len();
+                (sorted[(len - 1) / 2] + sorted[len / 2]) / 2.0
+            }
+        }
+    }
+}
+
+/// Gradient boosting settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoostingConfig {
+    /// Maximum number of trees
+    pub n_estimators: usize,
+    /// Shrinkage applied to each tree's output
+    pub learning_rate: f32,
+    /// Maximum depth per tree
+    pub max_depth: usize,
+    /// Minimum samples required to split a node
+    pub min_samples_split: usize,
+    /// Fraction of rows each tree is fitted on (1.0 uses every row)
+    pub subsample: f32,
+    /// Loss function
+    pub loss: BoostingLoss,
+    /// Stop after this many rounds without a validation improvement
+    pub early_stopping_rounds: Option<usize>,
+    /// Fraction of rows (taken from the end) held out by [`GradientBoostingRegressor::fit`]
+    /// when early stopping is enabled
+    pub validation_split: f32,
+}
+
+impl Default for BoostingConfig {
+    fn default() -> Self {
+        Self {
+            n_estimators: 100,
+            learning_rate: 0.1,
+            max_depth: 3,
+            min_samples_split: 2,
+            subsample: 1.0,
+            loss: BoostingLoss::Squared,
+            early_stopping_rounds: None,
+            validation_split: 0.2,
+        }
+    }
+}
+
+/// Gradient Boosting Regressor
+#[derive(Debug, Clone)]
+pub struct GradientBoostingRegressor {
+    /// Ensemble of trees
+    trees: Vec<DecisionTree>,
+    /// Training settings
+    config: BoostingConfig,
+    /// Initial prediction (minimizer of the loss over the training targets)
+    initial_prediction: f32,
+    /// Training loss after each round
+    train_loss: Vec<f32>,
+    /// Validation loss after each round
+    validation_loss: Vec<f32>,
+}
+
+impl GradientBoostingRegressor {
+    /// Create a new gradient boosting regressor
+    pub fn new(n_estimators: usize, learning_rate: f32, max_depth: usize) -> Self {
+        Self::with_config(BoostingConfig {
+            n_estimators,
+            learning_rate,
+            max_depth,
+            ..Default::default()
+        })
+    }
+
+    /// Create a regressor with custom settings
+    pub fn with_config(config: BoostingConfig) -> Self {
+        Self {
+            trees: Vec::new(),
+            config,
+            initial_prediction: 0.0,
+            train_loss: Vec::new(),
+            validation_loss: Vec::new(),
+        }
+    }
+
+    /// Get the training settings
+    pub fn config(&self) -> &BoostingConfig {
+        &self.config
+    }
+
+    /// Fit the model to training data
+    ///
+    /// With early stopping enabled, the last `validation_split` of the rows
+    /// is held out as the validation set.
+    pub fn fit(&mut self, x: &Matrix, y: &Matrix) -> MlResult<()> {
+        check_data(x, y)?;
+        let n_validation = match self.config.early_stopping_rounds {
+            Some(_) => ((x.rows() as f32 * self.config.validation_split).round() as usize)
+                .min(x.rows() - 1),
+            None => 0,
+        };
+        if n_validation == 0 {
+            return self.fit_inner(x, y, None);
+        }
+
+        let n_train = x.rows() - n_validation;
+        let (x_train, x_val) = split_rows(x, n_train)?;
+        let (y_train, y_val) = split_rows(y, n_train)?;
+        self.fit_inner(&x_train, &y_train, Some((&x_val, &y_val)))
+    }
+
+    /// Fit the model, tracking loss on a separate validation set
+    ///
+    /// Early stopping, when enabled, watches this set.
+    pub fn fit_with_validation(
+        &mut self,
+        x: &Matrix,
+        y: &Matrix,
+        x_val: &Matrix,
+        y_val: &Matrix,
+    ) -> MlResult<()> {
+        check_data(x, y)?;
+        check_data(x_val, y_val)?;
+        if x_val.cols() != x.cols() {
+            return Err(MlError::DimensionMismatch {
+                expected: (x_val.rows(), x.cols()),
+                actual: x_val.shape(),
+            });
+        }
+        self.fit_inner(x, y, Some((x_val, y_val)))
+    }
+
+    fn fit_inner(
+        &mut self,
+        x: &Matrix,
+        y: &Matrix,
+        validation: Option<(&Matrix, &Matrix)>,
+    ) -> MlResult<()> {
+        let config = self.config;
+        if !(config.subsample > 0.0 && config.subsample <= 1.0) {
+            return Err(MlError::InvalidParameter(format!(
+                "Subsample must be in (0, 1], got {}",
+                config.subsample
+            )));
+        }
+        if config.learning_rate.is_nan() || config.learning_rate <= 0.0 {
+            return Err(MlError::InvalidParameter(format!(
+                "Learning rate must be positive, got {}",
+                config.learning_rate
+            )));
+        }
+
+        self.trees.clear();
+        self.train_loss.clear();
+        self.validation_loss.clear();
+        self.initial_prediction = config.loss.initial_prediction(y.data());
+
+        let n = x.rows();
+        let sample_size = ((n as f32 * config.subsample).round() as usize).clamp(1, n);
+        let mut rng = rand::thread_rng();
+        let mut predictions = vec![self.initial_prediction; n];
+        let mut val_predictions =
+            validation.map(|(x_val, _)| vec![self.initial_prediction; x_val.rows()]);
+        let mut best: Option<(usize, f32)> = None;
+
+        for round in 0..config.n_estimators {
+            // Pseudo-residuals for this round
+            let gradients: Vec<f32> = y
+                .data()
+                .iter()
+                .zip(&predictions)
+                .map(|(t, p)| config.loss.negative_gradient(t - p))
+                .collect();
+            let gradients = Matrix::from_slice(&gradients);
+
+            let rows = if sample_size < n {
+                let mut rows = sample(&mut rng, n, sample_size).into_vec();
+                rows.sort_unstable();
+                rows
+            } else {
+                (0..n).collect()
+            };
+            let mut tree = DecisionTree::new(config.max_depth, config.min_samples_split);
+            tree.fit_indices(x, &gradients, &rows)?;
+
+            add_scaled(&mut predictions, &tree.predict(x), config.learning_rate);
+            self.train_loss
+                .push(mean_loss(config.loss, y.data(), &predictions));
+
+            if let (Some((x_val, y_val)), Some(val_predictions)) =
+                (validation, val_predictions.as_mut())
+            {
+                add_scaled(val_predictions, &tree.predict(x_val), config.learning_rate);
+                let loss = mean_loss(config.loss, y_val.data(), val_predictions);
+                self.validation_loss.push(loss);
+                if !matches!(best, Some((_, best_loss)) if loss >= best_loss) {
+                    best = Some((round, loss));
+                }
+            }
+            self.trees.push(tree);
+
+            if let (Some(patience), Some((best_round, _))) = (config.early_stopping_rounds, best) {
+                if round - best_round >= patience {
+                    tracing::debug!(
+                        "Stopping at round {}, best validation loss at round {}",
+                        round,
+                        best_round
+                    );
+                    break;
+                }
+            }
+        }
+
+        // Keep the ensemble as it was at its best validation loss
+        if let (Some(_), Some((best_round, _))) = (config.early_stopping_rounds, best) {
+            self.trees.truncate(best_round + 1);
+        }
+
+        Ok(())
+    }
+
+    /// Predict values for input data
+    pub fn predict(&self, x: &Matrix) -> Matrix {
+        let mut predictions = vec![self.initial_prediction; x.rows()];
+
+        // Add contributions from each tree
+        for tree in &self.trees {
+            add_scaled(
+                &mut predictions,
+                &tree.predict(x),
+                self.config.learning_rate,
+            );
+        }
+
+        Matrix::from_slice(&predictions)
+    }
+
+    /// Get the number of trees
+    pub fn num_trees(&self) -> usize {
+        self.trees.len()
+    }
+
+    /// Training loss after each round
+    pub fn train_loss(&self) -> &[f32] {
+        &self.train_loss
+    }
+
+    /// Validation loss after each round (empty without a validation set)
+    pub fn validation_loss(&self) -> &[f32] {
+        &self.validation_loss
+    }
+
+    /// Lowest validation loss reached
+    pub fn best_validation_loss(&self) -> Option<f32> {
+        self.validation_loss.iter().copied().reduce(f32::min)
+    }
+}
+
+/// Check that `y` is a column with one target per row of `x`
+fn check_data(x: &Matrix, y: &Matrix) -> MlResult<()> {
+    if x.rows() == 0 {
+        return Err(MlError::InvalidParameter("No training rows".into()));
+    }
+    if y.shape() != (x.rows(), 1) {
+        return Err(MlError::DimensionMismatch {
+            expected: (x.rows(), 1),
+            actual: y.shape(),
+        });
+    }
+    Ok(())
+}
+
+/// Split a matrix into its first `at` rows and the rest
+fn split_rows(m: &Matrix, at: usize) -> MlResult<(Matrix, Matrix)> {
+    let (head, tail) = m.data().split_at(at * m.cols());
+    Ok((
+        Matrix::from_flat(head.to_vec(), at, m.cols())?,
+        Matrix::from_flat(tail.to_vec(), m.rows() - at, m.cols())?,
+    ))
+}
+
+/// Add `scale` times a tree's output column to running predictions
+fn add_scaled(predictions: &mut [f32], tree_output: &Matrix, scale: f32) {
+    for (p, t) in predictions.iter_mut().zip(tree_output.data()) {
+        *p += scale * t;
+    }
+}
+
+/// Mean loss of predictions against targets
+fn mean_loss(loss: BoostingLoss, targets: &[f32], predictions: &[f32]) -> f32 {
+    let total: f32 = targets
+        .iter()
+        .zip(predictions)
+        .map(|(t, p)| loss.loss(t - p))
+        .sum();
+    total / targets.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// y = 2x on 0..n with an outlier every 10th row
+    fn linear_with_outliers(n: usize) -> (Matrix, Matrix) {
+        let x: Vec<f32> = (0..n).map(|i| i as f32).collect();
+        let y: Vec<f32> = (0..n)
+            .map(|i| if i % 10 == 5 { 500.0 } else { 2.0 * i as f32 })
+            .collect();
+        (Matrix::from_slice(&x), Matrix::from_slice(&y))
+    }
+
+    #[test]
+    fn test_gradient_boosting() {
+        let x = Matrix::from_vec(vec![vec![1.0], vec![2.0], vec![3.0], vec![4.0], vec![5.0]]);
+        let y = Matrix::from_vec(vec![vec![2.0], vec![4.0], vec![6.0], vec![8.0], vec![10.0]]);
+
+        let mut gbr = GradientBoostingRegressor::new(10, 0.1, 3);
+        gbr.fit(&x, &y).unwrap();
+
+        let predictions = gbr.predict(&x);
+
+        // Predictions should be reasonably close to actual values
+        for i in 0..5 {
+            let pred = predictions.get(i, 0);
+            let actual = y.get(i, 0);
+            assert!((pred - actual).abs() < 2.0);
+        }
+        assert_eq!(gbr.train_loss().len(), 10);
+        assert!(gbr.train_loss()[9] < gbr.train_loss()[0]);
+    }
+
+    #[test]
+    fn test_huber_loss_resists_outliers() {
+        let (x, y) = linear_with_outliers(60);
+        let config = BoostingConfig {
+            n_estimators: 50,
+            max_depth: 2,
+            ..Default::default()
+        };
+
+        let mut squared = GradientBoostingRegressor::with_config(config);
+        squared.fit(&x, &y).unwrap();
+        let mut huber = GradientBoostingRegressor::with_config(BoostingConfig {
+            loss: BoostingLoss::Huber { delta: 5.0 },
+            ..config
+        });
+        huber.fit(&x, &y).unwrap();
+
+        // Error on the clean rows only
+        let clean_error = |model: &GradientBoostingRegressor| {
+            let predictions = model.predict(&x);
+            (0..60)
+                .filter(|i| i % 10 != 5)
+                .map(|i| (predictions.get(i, 0) - y.get(i, 0)).abs())
+                .sum::<f32>()
+        };
+        assert!(clean_error(&huber) < clean_error(&squared));
+    }
+
+    #[test]
+    fn test_early_stopping_keeps_best_round() {
+        let x: Vec<f32> = (0..80).map(|i| i as f32).collect();
+        let y: Vec<f32> = x.iter().map(|v| (v * 0.2).sin()).collect();
+        let (x, y) = (Matrix::from_slice(&x), Matrix::from_slice(&y));
+
+        // The held-out tail lies past every training x, so extra trees can't help it
+        let mut model = GradientBoostingRegressor::with_config(BoostingConfig {
+            n_estimators: 200,
+            subsample: 0.8,
+            early_stopping_rounds: Some(5),
+            ..Default::default()
+        });
+        model.fit(&x, &y).unwrap();
+
+        let rounds = model.validation_loss().len();
+        assert!(rounds < 200);
+        assert_eq!(model.train_loss().len(), rounds);
+        assert_eq!(model.num_trees(), rounds - 5);
+        assert_eq!(
+            model.best_validation_loss(),
+            Some(model.validation_loss()[model.num_trees() - 1])
+        );
+    }
+
+    #[test]
+    fn test_fit_with_validation_set() {
+        let (x, y) = linear_with_outliers(40);
+        let mut model = GradientBoostingRegressor::new(20, 0.1, 3);
+        model.fit_with_validation(&x, &y, &x, &y).unwrap();
+        assert_eq!(model.num_trees(), 20);
+        assert_eq!(model.validation_loss().len(), 20);
+
+        let x_bad = Matrix::zeros(40, 2);
+        assert!(model.fit_with_validation(&x, &y, &x_bad, &y).is_err());
+        assert!(model.fit(&x, &Matrix::zeros(3, 1)).is_err());
+        let mut bad = GradientBoostingRegressor::with_config(BoostingConfig {
+            subsample: 0.0,
+            ..Default::default()
+        });
+        assert!(bad.fit(&x, &y).is_err());
+    }
+}
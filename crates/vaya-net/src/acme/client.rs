@@ -0,0 +1,500 @@
+//! ACME protocol client (RFC 8555)
+//!
+//! Talks to the CA over a minimal HTTP/1.1 client: one request per
+//! connection, bodies framed by `Content-Length`, chunked encoding or EOF.
+//! Every POST is signed with the account key and carries a fresh nonce; a
+//! `badNonce` rejection is retried once with the nonce that came back with it.
+
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+use super::jose::{base64url, EcKey};
+use super::json::{self, Json};
+use super::AcmeConfig;
+use crate::tls::{self, ClientStream, TlsConnector};
+use crate::{NetError, NetResult};
+
+/// Largest response body accepted from the CA
+const MAX_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// Most header lines accepted in a response
+const MAX_HEADERS: usize = 100;
+
+const BAD_NONCE: &str = "urn:ietf:params:acme:error:badNonce";
+
+/// A parsed HTTP response
+#[derive(Debug)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    /// First header named `name` (case-insensitive)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn json(&self) -> NetResult<Json> {
+        Json::parse(&self.body)
+    }
+}
+
+/// Scheme, authority and path of a URL
+struct Url<'a> {
+    tls: bool,
+    host: &'a str,
+    port: u16,
+    path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    fn parse(url: &'a str) -> NetResult<Self> {
+        let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(NetError::InvalidUrl(url.to_string()));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| NetError::InvalidUrl(url.to_string()))?,
+            ),
+            None => (authority, if tls { 443 } else { 80 }),
+        };
+        if host.is_empty() {
+            return Err(NetError::InvalidUrl(url.to_string()));
+        }
+        Ok(Self {
+            tls,
+            host,
+            port,
+            path,
+        })
+    }
+}
+
+/// One-request-per-connection HTTP/1.1 client
+struct HttpClient {
+    /// Only built when the CA is reached over HTTPS
+    connector: Option<TlsConnector>,
+    timeout: Duration,
+}
+
+impl HttpClient {
+    fn new(config: &AcmeConfig) -> NetResult<Self> {
+        let connector = if config.directory_url.starts_with("https://") {
+            Some(match &config.ca_bundle {
+                Some(path) => TlsConnector::from_pem_file(path)?,
+                None => TlsConnector::system()?,
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            connector,
+            timeout: config.request_timeout,
+        })
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&[u8]>,
+    ) -> NetResult<HttpResponse> {
+        tokio::time::timeout(self.timeout, self.exchange(method, url, body))
+            .await
+            .map_err(|_| NetError::Timeout)?
+    }
+
+    async fn exchange(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&[u8]>,
+    ) -> NetResult<HttpResponse> {
+        let target = Url::parse(url)?;
+        let mut stream =
+            ClientStream::Plain(tls::connect(target.host, target.port, self.timeout).await?);
+        if target.tls {
+            let connector = self
+                .connector
+                .as_ref()
+                .ok_or_else(|| NetError::Acme(format!("No TLS trust roots for {}", url)))?;
+            stream = stream.upgrade(connector, target.host).await?;
+        }
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: vaya-net-acme\r\nAccept: */*\r\nConnection: close\r\n",
+            method, target.path, target.host
+        );
+        if let Some(body) = body {
+            head.push_str(&format!(
+                "Content-Type: application/jose+json\r\nContent-Length: {}\r\n",
+                body.len()
+            ));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes()).await?;
+        if let Some(body) = body {
+            stream.write_all(body).await?;
+        }
+        stream.flush().await?;
+
+        read_response(&mut BufReader::new(stream), method == "HEAD").await
+    }
+}
+
+/// Read a response head and its body
+async fn read_response<R>(reader: &mut R, head_only: bool) -> NetResult<HttpResponse>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    let status_line = read_line(reader).await?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .filter(|_| status_line.starts_with("HTTP/1."))
+        .ok_or_else(|| NetError::InvalidResponse(format!("Bad status line: {}", status_line)))?;
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader).await?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(NetError::HeaderTooLarge);
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| NetError::InvalidResponse(format!("Bad header: {}", line)))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    let mut response = HttpResponse {
+        status,
+        headers,
+        body: Vec::new(),
+    };
+    if head_only || status == 204 || status == 304 {
+        return Ok(response);
+    }
+
+    if response
+        .header("Transfer-Encoding")
+        .is_some_and(|te| te.eq_ignore_ascii_case("chunked"))
+    {
+        loop {
+            let line = read_line(reader).await?;
+            let size = usize::from_str_radix(line.split(';').next().unwrap_or("").trim(), 16)
+                .map_err(|_| NetError::InvalidResponse(format!("Bad chunk size: {}", line)))?;
+            if size == 0 {
+                break;
+            }
+            if response.body.len() + size > MAX_RESPONSE_SIZE {
+                return Err(NetError::RequestTooLarge);
+            }
+            let start = response.body.len();
+            response.body.resize(start + size, 0);
+            reader.read_exact(&mut response.body[start..]).await?;
+            read_line(reader).await?;
+        }
+    } else if let Some(length) = response.header("Content-Length") {
+        let length: usize = length
+            .parse()
+            .map_err(|_| NetError::InvalidResponse(format!("Bad Content-Length: {}", length)))?;
+        if length > MAX_RESPONSE_SIZE {
+            return Err(NetError::RequestTooLarge);
+        }
+        response.body.resize(length, 0);
+        reader.read_exact(&mut response.body).await?;
+    } else {
+        reader
+            .take(MAX_RESPONSE_SIZE as u64 + 1)
+            .read_to_end(&mut response.body)
+            .await?;
+        if response.body.len() > MAX_RESPONSE_SIZE {
+            return Err(NetError::RequestTooLarge);
+        }
+    }
+    Ok(response)
+}
+
+/// A CRLF-terminated line, without the terminator
+async fn read_line<R>(reader: &mut R) -> NetResult<String>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    let mut line = Vec::new();
+    let n = (&mut *reader)
+        .take(crate::MAX_HEADER_SIZE as u64)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if n == 0 {
+        return Err(NetError::ConnectionClosed);
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(NetError::HeaderTooLarge);
+    }
+    String::from_utf8(line)
+        .map(|l| l.trim_end_matches(['\r', '\n']).to_string())
+        .map_err(|_| NetError::InvalidResponse("Response head is not UTF-8".into()))
+}
+
+/// Endpoints from the CA's directory
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+/// An ACME account session with one CA
+pub struct AcmeClient {
+    http: HttpClient,
+    key: EcKey,
+    directory: Directory,
+    nonce: Option<String>,
+    /// Account URL, once registered
+    kid: Option<String>,
+    poll_interval: Duration,
+    poll_timeout: Duration,
+}
+
+impl AcmeClient {
+    /// Fetch the CA's directory
+    pub async fn connect(config: &AcmeConfig, key: EcKey) -> NetResult<Self> {
+        let http = HttpClient::new(config)?;
+        let response = http.request("GET", &config.directory_url, None).await?;
+        if response.status != 200 {
+            return Err(NetError::Acme(format!(
+                "Directory {} returned HTTP {}",
+                config.directory_url, response.status
+            )));
+        }
+        let directory = response.json()?;
+        let endpoint = |name: &str| {
+            directory
+                .str_field(name)
+                .map(str::to_string)
+                .ok_or_else(|| NetError::Acme(format!("Directory has no {} endpoint", name)))
+        };
+
+        Ok(Self {
+            directory: Directory {
+                new_nonce: endpoint("newNonce")?,
+                new_account: endpoint("newAccount")?,
+                new_order: endpoint("newOrder")?,
+            },
+            http,
+            key,
+            nonce: None,
+            kid: None,
+            poll_interval: config.poll_interval,
+            poll_timeout: config.poll_timeout,
+        })
+    }
+
+    /// The account key
+    pub fn key(&self) -> &EcKey {
+        &self.key
+    }
+
+    /// Register the account, or look it up if the key is already known to
+    /// the CA. Returns the account URL.
+    pub async fn register(&mut self, contact: &[String]) -> NetResult<String> {
+        let contact: Vec<String> = contact
+            .iter()
+            .map(|c| {
+                if c.contains(':') {
+                    json::string(c)
+                } else {
+                    json::string(&format!("mailto:{}", c))
+                }
+            })
+            .collect();
+        let payload = format!(
+            r#"{{"termsOfServiceAgreed":true,"contact":[{}]}}"#,
+            contact.join(",")
+        );
+        let url = self.directory.new_account.clone();
+        let response = self.post(&url, &payload).await?;
+        let kid = response
+            .header("Location")
+            .map(str::to_string)
+            .ok_or_else(|| NetError::Acme("Account response has no Location".into()))?;
+        self.kid = Some(kid.clone());
+        Ok(kid)
+    }
+
+    /// Open an order for `domains`, returning its URL and body
+    pub async fn new_order(&mut self, domains: &[String]) -> NetResult<(String, Json)> {
+        let identifiers: Vec<String> = domains
+            .iter()
+            .map(|d| format!(r#"{{"type":"dns","value":{}}}"#, json::string(d)))
+            .collect();
+        let payload = format!(r#"{{"identifiers":[{}]}}"#, identifiers.join(","));
+        let url = self.directory.new_order.clone();
+        let response = self.post(&url, &payload).await?;
+        let location = response
+            .header("Location")
+            .map(str::to_string)
+            .ok_or_else(|| NetError::Acme("Order response has no Location".into()))?;
+        Ok((location, response.json()?))
+    }
+
+    /// POST-as-GET a resource and parse it
+    pub async fn fetch(&mut self, url: &str) -> NetResult<Json> {
+        self.post(url, "").await?.json()
+    }
+
+    /// Tell the CA a challenge is ready to be validated
+    pub async fn respond(&mut self, challenge_url: &str) -> NetResult<()> {
+        self.post(challenge_url, "{}").await.map(|_| ())
+    }
+
+    /// Submit the CSR for a ready order
+    pub async fn finalize(&mut self, finalize_url: &str, csr_der: &[u8]) -> NetResult<Json> {
+        let payload = format!(r#"{{"csr":"{}"}}"#, base64url(csr_der));
+        self.post(finalize_url, &payload).await?.json()
+    }
+
+    /// Download an issued certificate chain as PEM
+    pub async fn download(&mut self, certificate_url: &str) -> NetResult<Vec<u8>> {
+        Ok(self.post(certificate_url, "").await?.body)
+    }
+
+    /// Re-fetch `url` until its status leaves `pending`
+    pub async fn poll(&mut self, url: &str, pending: &[&str]) -> NetResult<Json> {
+        let deadline = Instant::now() + self.poll_timeout;
+        loop {
+            let resource = self.fetch(url).await?;
+            let status = resource.str_field("status").unwrap_or_default();
+            if !pending.contains(&status) {
+                return Ok(resource);
+            }
+            if Instant::now() + self.poll_interval > deadline {
+                return Err(NetError::Acme(format!(
+                    "{} still {} after {:?}",
+                    url, status, self.poll_timeout
+                )));
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// A signed POST, retried once on a stale nonce
+    async fn post(&mut self, url: &str, payload: &str) -> NetResult<HttpResponse> {
+        let mut retried = false;
+        loop {
+            let nonce = match self.nonce.take() {
+                Some(nonce) => nonce,
+                None => self.fresh_nonce().await?,
+            };
+            let body = self.key.sign(url, &nonce, self.kid.as_deref(), payload)?;
+            let response = self
+                .http
+                .request("POST", url, Some(body.as_bytes()))
+                .await?;
+            self.nonce = response.header("Replay-Nonce").map(str::to_string);
+            if response.status < 400 {
+                return Ok(response);
+            }
+
+            let problem = response.json().ok();
+            let kind = problem
+                .as_ref()
+                .and_then(|p| p.str_field("type"))
+                .unwrap_or_default();
+            if kind == BAD_NONCE && !retried {
+                retried = true;
+                continue;
+            }
+            let detail = problem
+                .as_ref()
+                .and_then(|p| p.str_field("detail"))
+                .unwrap_or_default();
+            return Err(NetError::Acme(format!(
+                "{} returned HTTP {}: {} {}",
+                url, response.status, kind, detail
+            )));
+        }
+    }
+
+    async fn fresh_nonce(&mut self) -> NetResult<String> {
+        let url = self.directory.new_nonce.clone();
+        let response = self.http.request("HEAD", &url, None).await?;
+        response
+            .header("Replay-Nonce")
+            .map(str::to_string)
+            .ok_or_else(|| NetError::Acme("CA returned no Replay-Nonce".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let url = Url::parse("https://acme-v02.api.letsencrypt.org/directory").unwrap();
+        assert!(url.tls);
+        assert_eq!(url.host, "acme-v02.api.letsencrypt.org");
+        assert_eq!(url.port, 443);
+        assert_eq!(url.path, "/directory");
+
+        let url = Url::parse("http://127.0.0.1:14000").unwrap();
+        assert!(!url.tls);
+        assert_eq!((url.host, url.port, url.path), ("127.0.0.1", 14000, "/"));
+
+        assert!(Url::parse("ftp://ca.test/").is_err());
+        assert!(Url::parse("https://:443/").is_err());
+        assert!(Url::parse("https://ca.test:port/").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_chunked_response() {
+        let raw = b"HTTP/1.1 200 OK\r\nReplay-Nonce: abc\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6;x=y\r\n world\r\n0\r\n\r\n";
+        let response = read_response(&mut &raw[..], false).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.header("replay-nonce"), Some("abc"));
+        assert_eq!(response.body, b"hello world");
+    }
+
+    #[tokio::test]
+    async fn test_read_sized_and_eof_responses() {
+        let raw = b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\n{}trailing";
+        let response = read_response(&mut &raw[..], false).await.unwrap();
+        assert_eq!((response.status, &response.body[..]), (201, &b"{}"[..]));
+
+        let raw = b"HTTP/1.1 200 OK\r\n\r\nuntil close";
+        let response = read_response(&mut &raw[..], false).await.unwrap();
+        assert_eq!(response.body, b"until close");
+
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n";
+        let response = read_response(&mut &raw[..], true).await.unwrap();
+        assert!(response.body.is_empty());
+
+        assert!(read_response(&mut &b"SMTP 220\r\n\r\n"[..], false)
+            .await
+            .is_err());
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 99999999\r\n\r\n";
+        assert!(matches!(
+            read_response(&mut &raw[..], false).await,
+            Err(NetError::RequestTooLarge)
+        ));
+    }
+}
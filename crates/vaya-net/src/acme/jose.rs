@@ -0,0 +1,255 @@
+//! JSON Web Signatures for ACME requests (RFC 7515, RFC 8555 §6.2)
+//!
+//! Every ACME POST is a flattened JWS signed with the account key. The
+//! account is P-256 / ES256, the algorithm every ACME server supports.
+
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{
+    EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P256_SHA256_FIXED_SIGNING,
+};
+
+use super::json;
+use crate::{NetError, NetResult};
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Unpadded base64url, as used throughout JOSE
+pub fn base64url(data: &[u8]) -> String {
+    encode(data, BASE64URL, false)
+}
+
+/// Padded standard base64, as used in PEM
+pub fn base64(data: &[u8]) -> String {
+    encode(data, BASE64, true)
+}
+
+fn encode(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let triple = (chunk[0] as u32) << 16
+            | (chunk.get(1).copied().unwrap_or(0) as u32) << 8
+            | chunk.get(2).copied().unwrap_or(0) as u32;
+        for i in 0..=chunk.len() {
+            out.push(alphabet[(triple >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decode unpadded base64url
+#[cfg(test)]
+pub(super) fn base64url_decode(s: &str) -> Vec<u8> {
+    let mut bits = 0u32;
+    let mut n = 0;
+    let mut out = Vec::new();
+    for c in s.bytes() {
+        let v = BASE64URL.iter().position(|&a| a == c).unwrap() as u32;
+        bits = bits << 6 | v;
+        n += 6;
+        if n >= 8 {
+            n -= 8;
+            out.push((bits >> n) as u8);
+        }
+    }
+    out
+}
+
+/// A P-256 key pair, used for the ACME account and for certificate keys
+pub struct EcKey {
+    pkcs8: Vec<u8>,
+    /// Signs JWS payloads (raw r || s)
+    fixed: EcdsaKeyPair,
+    /// Signs CSRs and certificates (DER `Ecdsa-Sig-Value`)
+    asn1: EcdsaKeyPair,
+    rng: SystemRandom,
+}
+
+impl EcKey {
+    /// Generate a new key
+    pub fn generate() -> NetResult<Self> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+            .map_err(|_| NetError::Tls("Failed to generate P-256 key".into()))?;
+        Self::from_pkcs8(pkcs8.as_ref())
+    }
+
+    /// Load a key from its PKCS#8 DER encoding
+    pub fn from_pkcs8(pkcs8: &[u8]) -> NetResult<Self> {
+        let rng = SystemRandom::new();
+        let invalid = |e: ring::error::KeyRejected| {
+            NetError::Tls(format!("Invalid P-256 private key: {}", e))
+        };
+        Ok(Self {
+            pkcs8: pkcs8.to_vec(),
+            fixed: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8, &rng)
+                .map_err(invalid)?,
+            asn1: EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8, &rng)
+                .map_err(invalid)?,
+            rng,
+        })
+    }
+
+    /// The PKCS#8 DER encoding of the private key
+    pub fn pkcs8(&self) -> &[u8] {
+        &self.pkcs8
+    }
+
+    /// The uncompressed public point (`04 || x || y`)
+    pub fn public_key(&self) -> &[u8] {
+        self.fixed.public_key().as_ref()
+    }
+
+    /// ES256 signature as raw `r || s`, for JWS
+    pub fn sign_jws(&self, message: &[u8]) -> NetResult<Vec<u8>> {
+        self.fixed
+            .sign(&self.rng, message)
+            .map(|sig| sig.as_ref().to_vec())
+            .map_err(|_| NetError::Tls("ECDSA signing failed".into()))
+    }
+
+    /// ECDSA-SHA256 signature in DER, for X.509 structures
+    pub fn sign_der(&self, message: &[u8]) -> NetResult<Vec<u8>> {
+        self.asn1
+            .sign(&self.rng, message)
+            .map(|sig| sig.as_ref().to_vec())
+            .map_err(|_| NetError::Tls("ECDSA signing failed".into()))
+    }
+
+    /// The public key as a JWK, members in the order RFC 7638 hashes them
+    pub fn jwk(&self) -> String {
+        let point = self.public_key();
+        format!(
+            r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+            base64url(&point[1..33]),
+            base64url(&point[33..65])
+        )
+    }
+
+    /// RFC 7638 JWK thumbprint, the account's part of a key authorization
+    pub fn thumbprint(&self) -> String {
+        base64url(digest(&SHA256, self.jwk().as_bytes()).as_ref())
+    }
+
+    /// Key authorization for a challenge token (RFC 8555 §8.1)
+    pub fn key_authorization(&self, token: &str) -> String {
+        format!("{}.{}", token, self.thumbprint())
+    }
+
+    /// A flattened JWS for `url`
+    ///
+    /// Until the account exists requests carry the public key itself
+    /// (`kid` is `None`); afterwards they name the account URL. An empty
+    /// payload makes a POST-as-GET.
+    pub fn sign(
+        &self,
+        url: &str,
+        nonce: &str,
+        kid: Option<&str>,
+        payload: &str,
+    ) -> NetResult<String> {
+        let key = match kid {
+            Some(kid) => format!(r#""kid":{}"#, json::string(kid)),
+            None => format!(r#""jwk":{}"#, self.jwk()),
+        };
+        let protected = base64url(
+            format!(
+                r#"{{"alg":"ES256",{},"nonce":{},"url":{}}}"#,
+                key,
+                json::string(nonce),
+                json::string(url)
+            )
+            .as_bytes(),
+        );
+        let payload = base64url(payload.as_bytes());
+        let signature = self.sign_jws(format!("{}.{}", protected, payload).as_bytes())?;
+        Ok(format!(
+            r#"{{"protected":"{}","payload":"{}","signature":"{}"}}"#,
+            protected,
+            payload,
+            base64url(&signature)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acme::json::Json;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64url(&[0xfb, 0xff]), "-_8");
+        assert_eq!(base64url_decode("-_8"), [0xfb, 0xff]);
+    }
+
+    #[test]
+    fn test_key_round_trip() {
+        let key = EcKey::generate().unwrap();
+        let loaded = EcKey::from_pkcs8(key.pkcs8()).unwrap();
+        assert_eq!(key.public_key(), loaded.public_key());
+        assert_eq!(key.public_key().len(), 65);
+        assert_eq!(key.thumbprint(), loaded.thumbprint());
+        assert!(EcKey::from_pkcs8(b"not a key").is_err());
+    }
+
+    #[test]
+    fn test_signed_request_verifies() {
+        let key = EcKey::generate().unwrap();
+        let jws = key
+            .sign(
+                "https://acme.test/new-order",
+                "nonce-1",
+                Some("https://acme.test/acct/7"),
+                r#"{"identifiers":[]}"#,
+            )
+            .unwrap();
+
+        let jws = Json::parse(jws.as_bytes()).unwrap();
+        let protected = jws.str_field("protected").unwrap();
+        let payload = jws.str_field("payload").unwrap();
+        let header = Json::parse(&base64url_decode(protected)).unwrap();
+        assert_eq!(header.str_field("alg"), Some("ES256"));
+        assert_eq!(header.str_field("kid"), Some("https://acme.test/acct/7"));
+        assert_eq!(header.str_field("nonce"), Some("nonce-1"));
+        assert_eq!(header.str_field("url"), Some("https://acme.test/new-order"));
+        assert_eq!(base64url_decode(payload), br#"{"identifiers":[]}"#);
+
+        let signature = base64url_decode(jws.str_field("signature").unwrap());
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, key.public_key())
+            .verify(format!("{}.{}", protected, payload).as_bytes(), &signature)
+            .expect("signature verifies");
+    }
+
+    #[test]
+    fn test_new_account_carries_jwk() {
+        let key = EcKey::generate().unwrap();
+        let jws = Json::parse(
+            key.sign("https://acme.test/new-acct", "n", None, "")
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+        let header = Json::parse(&base64url_decode(jws.str_field("protected").unwrap())).unwrap();
+        let jwk = header.get("jwk").unwrap();
+        assert_eq!(jwk.str_field("kty"), Some("EC"));
+        assert_eq!(
+            base64url_decode(jwk.str_field("x").unwrap()),
+            &key.public_key()[1..33]
+        );
+        assert!(header.get("kid").is_none());
+        assert_eq!(jws.str_field("payload"), Some(""));
+        assert!(key.key_authorization("tok").starts_with("tok."));
+    }
+}
@@ -0,0 +1,413 @@
+//! The bits of X.509 ACME needs
+//!
+//! A PKCS#10 request for the order's domains, the self-signed certificate
+//! answering a TLS-ALPN-01 challenge (RFC 8737), and reading `notAfter` back
+//! out of an issued certificate to schedule renewal. Only DER writing is
+//! general; reading walks exactly the path to the validity field.
+
+use ring::rand::{SecureRandom, SystemRandom};
+use vaya_common::{Date, Timestamp};
+
+use super::jose::{base64, EcKey};
+use crate::{NetError, NetResult};
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+
+/// id-ecPublicKey
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// prime256v1
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+/// ecdsa-with-SHA256
+const OID_ECDSA_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+/// commonName
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+/// subjectAltName
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+/// PKCS#9 extensionRequest
+const OID_EXTENSION_REQUEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x0e];
+/// id-pe-acmeIdentifier (RFC 8737)
+const OID_ACME_IDENTIFIER: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x1f];
+
+/// A DER element: tag, definite length, contents
+fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(contents);
+    out
+}
+
+fn sequence(items: &[Vec<u8>]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &items.concat())
+}
+
+/// A non-negative INTEGER from big-endian bytes
+fn integer(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes
+        .iter()
+        .take_while(|&&b| b == 0)
+        .count()
+        .min(bytes.len() - 1);
+    let bytes = &bytes[start..];
+    if bytes[0] & 0x80 != 0 {
+        tlv(TAG_INTEGER, &[&[0][..], bytes].concat())
+    } else {
+        tlv(TAG_INTEGER, bytes)
+    }
+}
+
+fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    tlv(TAG_BIT_STRING, &[&[0][..], bytes].concat())
+}
+
+fn oid(encoded: &[u8]) -> Vec<u8> {
+    tlv(TAG_OID, encoded)
+}
+
+fn ecdsa_sha256() -> Vec<u8> {
+    sequence(&[oid(OID_ECDSA_SHA256)])
+}
+
+/// `CN=<name>`
+fn common_name(name: &str) -> Vec<u8> {
+    sequence(&[tlv(
+        TAG_SET,
+        &sequence(&[oid(OID_COMMON_NAME), tlv(TAG_UTF8_STRING, name.as_bytes())]),
+    )])
+}
+
+fn subject_public_key_info(key: &EcKey) -> Vec<u8> {
+    sequence(&[
+        sequence(&[oid(OID_EC_PUBLIC_KEY), oid(OID_P256)]),
+        bit_string(key.public_key()),
+    ])
+}
+
+/// subjectAltName extension listing `domains` as dNSNames
+fn subject_alt_name(domains: &[String]) -> Vec<u8> {
+    let names: Vec<Vec<u8>> = domains.iter().map(|d| tlv(0x82, d.as_bytes())).collect();
+    sequence(&[
+        oid(OID_SUBJECT_ALT_NAME),
+        tlv(TAG_OCTET_STRING, &sequence(&names)),
+    ])
+}
+
+/// Sign `tbs` and wrap it with the algorithm and signature
+fn signed(key: &EcKey, tbs: Vec<u8>) -> NetResult<Vec<u8>> {
+    let signature = key.sign_der(&tbs)?;
+    Ok(sequence(&[tbs, ecdsa_sha256(), bit_string(&signature)]))
+}
+
+/// A PKCS#10 certificate request for `domains`, the first one as subject
+pub fn csr(key: &EcKey, domains: &[String]) -> NetResult<Vec<u8>> {
+    let first = domains
+        .first()
+        .ok_or_else(|| NetError::Acme("A certificate needs at least one domain".into()))?;
+    let extension_request = sequence(&[
+        oid(OID_EXTENSION_REQUEST),
+        tlv(TAG_SET, &sequence(&[subject_alt_name(domains)])),
+    ]);
+    let info = sequence(&[
+        integer(&[0]),
+        common_name(first),
+        subject_public_key_info(key),
+        tlv(0xa0, &extension_request),
+    ]);
+    signed(key, info)
+}
+
+/// The self-signed certificate answering a TLS-ALPN-01 challenge for
+/// `domain`, carrying the SHA-256 of the key authorization in a critical
+/// acmeIdentifier extension
+pub fn tls_alpn01_certificate(
+    key: &EcKey,
+    domain: &str,
+    key_authorization: &str,
+) -> NetResult<Vec<u8>> {
+    let digest = ring::digest::digest(&ring::digest::SHA256, key_authorization.as_bytes());
+    let acme_identifier = sequence(&[
+        oid(OID_ACME_IDENTIFIER),
+        tlv(TAG_BOOLEAN, &[0xff]),
+        tlv(TAG_OCTET_STRING, &tlv(TAG_OCTET_STRING, digest.as_ref())),
+    ]);
+
+    let mut serial = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut serial)
+        .map_err(|_| NetError::Tls("Failed to generate serial number".into()))?;
+    serial[0] &= 0x7f;
+
+    let now = Timestamp::now();
+    let tbs = sequence(&[
+        tlv(0xa0, &integer(&[2])),
+        integer(&serial),
+        ecdsa_sha256(),
+        common_name(domain),
+        sequence(&[utc_time(now.add_days(-1))?, utc_time(now.add_days(7))?]),
+        common_name(domain),
+        subject_public_key_info(key),
+        tlv(
+            0xa3,
+            &sequence(&[subject_alt_name(&[domain.to_string()]), acme_identifier]),
+        ),
+    ]);
+    signed(key, tbs)
+}
+
+/// UTCTime (`YYMMDDHHMMSSZ`), valid for 1950 through 2049
+fn utc_time(at: Timestamp) -> NetResult<Vec<u8>> {
+    let secs = at.as_unix();
+    let date = Date::from_timestamp(Timestamp::from_unix(secs - secs.rem_euclid(86400)));
+    if !(1950..2050).contains(&date.year) {
+        return Err(NetError::Acme(format!(
+            "Year {} can't be a UTCTime",
+            date.year
+        )));
+    }
+    let time = secs.rem_euclid(86400);
+    let text = format!(
+        "{:02}{:02}{:02}{:02}{:02}{:02}Z",
+        date.year % 100,
+        date.month,
+        date.day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    Ok(tlv(TAG_UTC_TIME, text.as_bytes()))
+}
+
+/// Reads DER elements from a buffer
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn invalid() -> NetError {
+        NetError::Tls("Malformed certificate".into())
+    }
+
+    /// The next element's tag and contents
+    fn next(&mut self) -> NetResult<(u8, &'a [u8])> {
+        let tag = *self.data.first().ok_or_else(Self::invalid)?;
+        let first = *self.data.get(1).ok_or_else(Self::invalid)?;
+        let (len, header) = if first < 0x80 {
+            (first as usize, 2)
+        } else {
+            let n = (first & 0x7f) as usize;
+            if n == 0 || n > 4 {
+                return Err(Self::invalid());
+            }
+            let bytes = self.data.get(2..2 + n).ok_or_else(Self::invalid)?;
+            (
+                bytes.iter().fold(0usize, |acc, &b| acc << 8 | b as usize),
+                2 + n,
+            )
+        };
+        let contents = self
+            .data
+            .get(header..header + len)
+            .ok_or_else(Self::invalid)?;
+        self.data = &self.data[header + len..];
+        Ok((tag, contents))
+    }
+
+    /// Step into the next element, which must have tag `tag`
+    fn enter(&mut self, tag: u8) -> NetResult<Reader<'a>> {
+        match self.next()? {
+            (t, data) if t == tag => Ok(Reader { data }),
+            _ => Err(Self::invalid()),
+        }
+    }
+}
+
+/// The end of a certificate's validity period
+pub fn not_after(der: &[u8]) -> NetResult<Timestamp> {
+    let mut tbs = Reader { data: der }
+        .enter(TAG_SEQUENCE)?
+        .enter(TAG_SEQUENCE)?;
+    if tbs.data.first() == Some(&0xa0) {
+        tbs.next()?; // version
+    }
+    tbs.next()?; // serialNumber
+    tbs.next()?; // signature
+    tbs.next()?; // issuer
+    let mut validity = tbs.enter(TAG_SEQUENCE)?;
+    validity.next()?; // notBefore
+    let (tag, value) = validity.next()?;
+    parse_time(tag, value).ok_or_else(Reader::invalid)
+}
+
+fn parse_time(tag: u8, value: &[u8]) -> Option<Timestamp> {
+    let text = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        TAG_UTC_TIME => {
+            let yy: i64 = text.get(..2)?.parse().ok()?;
+            (if yy < 50 { 2000 + yy } else { 1900 + yy }, &text[2..])
+        }
+        TAG_GENERALIZED_TIME => (text.get(..4)?.parse().ok()?, &text[4..]),
+        _ => return None,
+    };
+    if rest.len() != 10 || !rest.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let field = |i: usize| rest[i..i + 2].parse::<i64>().unwrap_or(0);
+    let date = Date::new(year as i16, field(0) as u8, field(2) as u8);
+    if !date.is_valid() {
+        return None;
+    }
+    Some(
+        date.to_timestamp()
+            .add_secs(field(4) * 3600 + field(6) * 60 + field(8)),
+    )
+}
+
+/// PEM armour for a DER blob
+pub fn pem(label: &str, der: &[u8]) -> String {
+    let encoded = base64(der);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(line).unwrap_or_default());
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+
+    /// Split a signed structure into (to-be-signed DER, signature)
+    fn split_signed(der: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut outer = Reader { data: der }.enter(TAG_SEQUENCE).unwrap();
+        let before = outer.data;
+        outer.next().unwrap();
+        let tbs_len = before.len() - outer.data.len();
+        outer.next().unwrap();
+        let (tag, signature) = outer.next().unwrap();
+        assert_eq!(tag, TAG_BIT_STRING);
+        (before[..tbs_len].to_vec(), signature[1..].to_vec())
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn test_der_lengths() {
+        assert_eq!(tlv(TAG_OCTET_STRING, &[1, 2]), [0x04, 0x02, 1, 2]);
+        assert_eq!(&tlv(TAG_OCTET_STRING, &[0; 200])[..3], [0x04, 0x81, 200]);
+        assert_eq!(
+            &tlv(TAG_OCTET_STRING, &[0; 300])[..4],
+            [0x04, 0x82, 0x01, 0x2c]
+        );
+        assert_eq!(integer(&[0, 0, 5]), [0x02, 0x01, 5]);
+        assert_eq!(integer(&[0x80]), [0x02, 0x02, 0, 0x80]);
+        assert_eq!(integer(&[0]), [0x02, 0x01, 0]);
+    }
+
+    #[test]
+    fn test_csr_is_signed_by_key() {
+        let key = EcKey::generate().unwrap();
+        let domains = vec!["vaya.my".to_string(), "api.vaya.my".to_string()];
+        let request = csr(&key, &domains).unwrap();
+
+        let (info, signature) = split_signed(&request);
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, key.public_key())
+            .verify(&info, &signature)
+            .expect("CSR signature verifies");
+        assert!(contains(&request, &tlv(0x82, b"api.vaya.my")));
+        assert!(contains(&request, &oid(OID_EXTENSION_REQUEST)));
+        assert!(csr(&key, &[]).is_err());
+    }
+
+    #[test]
+    fn test_tls_alpn01_certificate() {
+        let key = EcKey::generate().unwrap();
+        let cert = tls_alpn01_certificate(&key, "vaya.my", "token.thumb").unwrap();
+        let digest = ring::digest::digest(&ring::digest::SHA256, b"token.thumb");
+
+        assert!(contains(&cert, &tlv(TAG_OCTET_STRING, digest.as_ref())));
+        assert!(contains(
+            &cert,
+            &[&oid(OID_ACME_IDENTIFIER)[..], &[TAG_BOOLEAN, 1, 0xff]].concat()
+        ));
+        let (tbs, signature) = split_signed(&cert);
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, key.public_key())
+            .verify(&tbs, &signature)
+            .expect("certificate signature verifies");
+
+        // Valid now, for a week
+        let expires = not_after(&cert).unwrap().as_unix() - Timestamp::now().as_unix();
+        assert!(
+            (7 * 86400 - 5..=7 * 86400).contains(&expires),
+            "{}",
+            expires
+        );
+
+        // rustls accepts it as a certificate for a P-256 key
+        let signing = rustls::crypto::ring::sign::any_ecdsa_type(
+            &rustls::pki_types::PrivateKeyDer::Pkcs8(key.pkcs8().to_vec().into()),
+        );
+        assert!(signing.is_ok());
+    }
+
+    #[test]
+    fn test_parse_time() {
+        // 2026-10-16T09:30:05Z
+        assert_eq!(
+            parse_time(TAG_UTC_TIME, b"261016093005Z").map(|t| t.as_unix()),
+            Some(1792143005)
+        );
+        assert_eq!(
+            parse_time(TAG_GENERALIZED_TIME, b"20261016093005Z").map(|t| t.as_unix()),
+            Some(1792143005)
+        );
+        assert_eq!(
+            parse_time(TAG_UTC_TIME, b"991231235959Z").map(|t| t.as_unix()),
+            Some(946684799)
+        );
+        assert!(parse_time(TAG_UTC_TIME, b"261316093005Z").is_none());
+        assert!(parse_time(TAG_UTC_TIME, b"2610160930Z").is_none());
+        assert!(not_after(b"\x30\x03\x02\x01\x00").is_err());
+    }
+
+    #[test]
+    fn test_utc_time_round_trip() {
+        let at = Timestamp::from_unix(1792143005);
+        let encoded = utc_time(at).unwrap();
+        assert_eq!(&encoded[2..], b"261016093005Z");
+        assert_eq!(
+            parse_time(TAG_UTC_TIME, &encoded[2..]).unwrap().as_unix(),
+            1792143005
+        );
+    }
+
+    #[test]
+    fn test_pem() {
+        let pem = pem("CERTIFICATE", &[0u8; 60]);
+        let lines: Vec<&str> = pem.lines().collect();
+        assert_eq!(lines[0], "-----BEGIN CERTIFICATE-----");
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[2].len(), 16);
+        assert_eq!(lines[3], "-----END CERTIFICATE-----");
+    }
+}
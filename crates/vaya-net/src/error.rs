@@ -35,6 +35,8 @@ pub enum NetError {
     WebSocket(String),
     /// Protocol error
     Protocol(String),
+    /// ACME certificate management error
+    Acme(String),
 }
 
 impl fmt::Display for NetError {
@@ -53,6 +55,7 @@ impl fmt::Display for NetError {
             NetError::MethodNotAllowed => write!(f, "Method not allowed"),
             NetError::WebSocket(e) => write!(f, "WebSocket error: {}", e),
             NetError::Protocol(e) => write!(f, "Protocol error: {}", e),
+            NetError::Acme(e) => write!(f, "ACME error: {}", e),
         }
     }
 }
@@ -0,0 +1,566 @@
+//! HPACK header compression for HTTP/2 (RFC 7541)
+//!
+//! The [`Decoder`] keeps the dynamic table the client indexes into. The
+//! encoder only refers to the static table and never adds entries, so it has
+//! no state to keep in step with the client; custom response headers are
+//! resent in full, Huffman coded, on every response.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use crate::{NetError, NetResult};
+
+/// Dynamic table size both sides start with
+pub const DEFAULT_TABLE_SIZE: usize = 4096;
+
+/// Per-entry overhead counted towards the dynamic table size
+const ENTRY_OVERHEAD: usize = 32;
+
+/// The static table (RFC 7541 Appendix A); index 1 is the first entry
+const STATIC_TABLE: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// Huffman code lengths for bytes 0-255 and EOS (RFC 7541 Appendix B).
+/// The code is canonical, so the codes themselves follow from the lengths.
+const HUFFMAN_CODE_LENGTHS: [u8; 257] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 30, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6, 5, 5,
+    5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10, 13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6, 15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6,
+    6, 5, 6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28, 20, 22, 20, 20, 22, 22, 22, 23, 22,
+    23, 23, 23, 23, 23, 24, 23, 24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24, 22,
+    21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23, 21, 21, 22, 21, 23, 22, 23, 23, 20,
+    22, 22, 22, 23, 22, 22, 23, 26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25, 19,
+    21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27, 20, 24, 20, 21, 22, 21, 21, 23, 22,
+    22, 25, 25, 24, 24, 26, 23, 26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26, 30,
+];
+
+/// Longest Huffman code, in bits
+const MAX_CODE_LENGTH: usize = 30;
+
+/// The end-of-string symbol, which must never appear in a decoded string
+const EOS: u16 = 256;
+
+/// Huffman codes for encoding, and per-length ranges for decoding
+struct Huffman {
+    /// (code, length) for each symbol
+    codes: [(u32, u8); 257],
+    /// First code of each length
+    first_code: [u32; MAX_CODE_LENGTH + 1],
+    /// Number of codes of each length
+    count: [u32; MAX_CODE_LENGTH + 1],
+    /// Index into `symbols` of the first code of each length
+    offset: [usize; MAX_CODE_LENGTH + 1],
+    /// Symbols ordered by code
+    symbols: Vec<u16>,
+}
+
+fn huffman() -> &'static Huffman {
+    static HUFFMAN: OnceLock<Huffman> = OnceLock::new();
+    HUFFMAN.get_or_init(|| {
+        let mut symbols: Vec<u16> = (0..=EOS).collect();
+        symbols.sort_by_key(|&sym| (HUFFMAN_CODE_LENGTHS[sym as usize], sym));
+
+        let mut huffman = Huffman {
+            codes: [(0, 0); 257],
+            first_code: [0; MAX_CODE_LENGTH + 1],
+            count: [0; MAX_CODE_LENGTH + 1],
+            offset: [0; MAX_CODE_LENGTH + 1],
+            symbols: Vec::with_capacity(symbols.len()),
+        };
+        let mut code = 0u32;
+        let mut prev_len = 0u8;
+        for (i, &sym) in symbols.iter().enumerate() {
+            let len = HUFFMAN_CODE_LENGTHS[sym as usize];
+            code <<= len - prev_len;
+            if len != prev_len {
+                huffman.first_code[len as usize] = code;
+                huffman.offset[len as usize] = i;
+            }
+            huffman.codes[sym as usize] = (code, len);
+            huffman.count[len as usize] += 1;
+            code += 1;
+            prev_len = len;
+        }
+        huffman.symbols = symbols;
+        huffman
+    })
+}
+
+/// Huffman-encoded length of `data` in bytes
+fn huffman_len(data: &[u8]) -> usize {
+    let codes = &huffman().codes;
+    let bits: usize = data.iter().map(|&b| codes[b as usize].1 as usize).sum();
+    bits.div_ceil(8)
+}
+
+/// Huffman-encode `data`, padding the last byte with the EOS prefix
+fn huffman_encode(data: &[u8], out: &mut Vec<u8>) {
+    let codes = &huffman().codes;
+    let mut acc = 0u64;
+    let mut bits = 0u32;
+    for &b in data {
+        let (code, len) = codes[b as usize];
+        acc = (acc << len) | code as u64;
+        bits += len as u32;
+        while bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        out.push(((acc << (8 - bits)) as u8) | (0xff >> bits));
+    }
+}
+
+/// Decode a Huffman-encoded string
+fn huffman_decode(data: &[u8]) -> NetResult<Vec<u8>> {
+    let huffman = huffman();
+    let mut out = Vec::with_capacity(data.len() * 8 / 5);
+    let mut code = 0u32;
+    let mut len = 0usize;
+    for &byte in data {
+        for shift in (0..8).rev() {
+            code = (code << 1) | ((byte >> shift) & 1) as u32;
+            len += 1;
+            if len > MAX_CODE_LENGTH {
+                return Err(hpack_error("invalid Huffman code"));
+            }
+            let index = code.wrapping_sub(huffman.first_code[len]);
+            if huffman.count[len] > 0
+                && code >= huffman.first_code[len]
+                && index < huffman.count[len]
+            {
+                let sym = huffman.symbols[huffman.offset[len] + index as usize];
+                if sym == EOS {
+                    return Err(hpack_error("EOS in Huffman string"));
+                }
+                out.push(sym as u8);
+                code = 0;
+                len = 0;
+            }
+        }
+    }
+    // Padding is at most 7 bits, all ones (a prefix of EOS)
+    if len > 7 || code != (1 << len) - 1 {
+        return Err(hpack_error("invalid Huffman padding"));
+    }
+    Ok(out)
+}
+
+fn hpack_error(message: &str) -> NetError {
+    NetError::Protocol(format!("HPACK: {}", message))
+}
+
+/// Encode an integer with an `n`-bit prefix, OR-ing `flags` into the first byte
+fn encode_integer(value: usize, n: u8, flags: u8, out: &mut Vec<u8>) {
+    let max = (1usize << n) - 1;
+    if value < max {
+        out.push(flags | value as u8);
+        return;
+    }
+    out.push(flags | max as u8);
+    let mut rest = value - max;
+    while rest >= 0x80 {
+        out.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    out.push(rest as u8);
+}
+
+/// Encode a string literal, Huffman coded when that is shorter
+fn encode_string(value: &str, out: &mut Vec<u8>) {
+    let bytes = value.as_bytes();
+    let huffman_len = huffman_len(bytes);
+    if huffman_len < bytes.len() {
+        encode_integer(huffman_len, 7, 0x80, out);
+        huffman_encode(bytes, out);
+    } else {
+        encode_integer(bytes.len(), 7, 0, out);
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// Encode a header block
+///
+/// Fields that match a static table entry are sent as an index; everything
+/// else is a literal that is not added to the client's dynamic table.
+pub fn encode<'a>(headers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (name, value) in headers {
+        if let Some(index) = STATIC_TABLE
+            .iter()
+            .position(|&entry| entry == (name, value))
+        {
+            encode_integer(index + 1, 7, 0x80, &mut out);
+            continue;
+        }
+        // Literal header field without indexing
+        match STATIC_TABLE.iter().position(|&(n, _)| n == name) {
+            Some(index) => encode_integer(index + 1, 4, 0, &mut out),
+            None => {
+                out.push(0);
+                encode_string(name, &mut out);
+            }
+        }
+        encode_string(value, &mut out);
+    }
+    out
+}
+
+/// Decodes header blocks, tracking the dynamic table across them
+pub struct Decoder {
+    /// Dynamic table, newest entry first
+    table: VecDeque<(String, String)>,
+    /// Current size of the dynamic table
+    size: usize,
+    /// Maximum size the encoder has chosen
+    max_size: usize,
+    /// Maximum size we allow the encoder to choose (our `SETTINGS_HEADER_TABLE_SIZE`)
+    limit: usize,
+}
+
+impl Decoder {
+    /// Create a decoder allowing a dynamic table of up to `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        Self {
+            table: VecDeque::new(),
+            size: 0,
+            max_size: limit,
+            limit,
+        }
+    }
+
+    /// Decode a complete header block into (name, value) pairs
+    ///
+    /// Any error leaves the dynamic table out of step with the encoder, so
+    /// the connection can't continue.
+    pub fn decode(&mut self, block: &[u8]) -> NetResult<Vec<(String, String)>> {
+        let mut headers = Vec::new();
+        let mut pos = 0;
+
+        while pos < block.len() {
+            let byte = block[pos];
+            if byte & 0x80 != 0 {
+                // Indexed header field
+                let index = decode_integer(block, &mut pos, 7)?;
+                headers.push(self.entry(index)?);
+            } else if byte & 0x40 != 0 {
+                // Literal header field with incremental indexing
+                let header = self.decode_literal(block, &mut pos, 6)?;
+                self.insert(header.clone());
+                headers.push(header);
+            } else if byte & 0x20 != 0 {
+                // Dynamic table size update, only allowed before any field
+                if !headers.is_empty() {
+                    return Err(hpack_error("table size update after a header field"));
+                }
+                let size = decode_integer(block, &mut pos, 5)?;
+                if size > self.limit {
+                    return Err(hpack_error("table size update above the limit"));
+                }
+                self.max_size = size;
+                self.evict();
+            } else {
+                // Literal header field without indexing, or never indexed
+                headers.push(self.decode_literal(block, &mut pos, 4)?);
+            }
+        }
+
+        Ok(headers)
+    }
+
+    /// A literal field whose name is either indexed or a literal
+    fn decode_literal(
+        &self,
+        block: &[u8],
+        pos: &mut usize,
+        prefix: u8,
+    ) -> NetResult<(String, String)> {
+        let index = decode_integer(block, pos, prefix)?;
+        let name = if index == 0 {
+            decode_string(block, pos)?
+        } else {
+            self.entry(index)?.0
+        };
+        let value = decode_string(block, pos)?;
+        Ok((name, value))
+    }
+
+    /// Look up an entry in the static table, then the dynamic table
+    fn entry(&self, index: usize) -> NetResult<(String, String)> {
+        if index == 0 {
+            return Err(hpack_error("index 0"));
+        }
+        if let Some(&(name, value)) = STATIC_TABLE.get(index - 1) {
+            return Ok((name.to_string(), value.to_string()));
+        }
+        self.table
+            .get(index - STATIC_TABLE.len() - 1)
+            .cloned()
+            .ok_or_else(|| hpack_error(&format!("index {} out of range", index)))
+    }
+
+    fn insert(&mut self, header: (String, String)) {
+        self.size += header.0.len() + header.1.len() + ENTRY_OVERHEAD;
+        self.table.push_front(header);
+        // An entry larger than the table empties it, itself included
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.size > self.max_size {
+            match self.table.pop_back() {
+                Some((name, value)) => self.size -= name.len() + value.len() + ENTRY_OVERHEAD,
+                None => break,
+            }
+        }
+    }
+}
+
+/// Decode an integer with an `n`-bit prefix
+fn decode_integer(block: &[u8], pos: &mut usize, n: u8) -> NetResult<usize> {
+    let max = (1usize << n) - 1;
+    let first = *block
+        .get(*pos)
+        .ok_or_else(|| hpack_error("truncated integer"))?;
+    *pos += 1;
+    let mut value = first as usize & max;
+    if value < max {
+        return Ok(value);
+    }
+
+    let mut shift = 0;
+    loop {
+        let byte = *block
+            .get(*pos)
+            .ok_or_else(|| hpack_error("truncated integer"))?;
+        *pos += 1;
+        // Anything past 28 bits is far beyond any size we accept
+        if shift > 21 {
+            return Err(hpack_error("integer overflow"));
+        }
+        value += ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// Decode a string literal
+fn decode_string(block: &[u8], pos: &mut usize) -> NetResult<String> {
+    let huffman = block.get(*pos).is_some_and(|b| b & 0x80 != 0);
+    let len = decode_integer(block, pos, 7)?;
+    let end = pos
+        .checked_add(len)
+        .filter(|&end| end <= block.len())
+        .ok_or_else(|| hpack_error("truncated string"))?;
+    let raw = &block[*pos..end];
+    *pos = end;
+
+    let bytes = if huffman {
+        huffman_decode(raw)?
+    } else {
+        raw.to_vec()
+    };
+    String::from_utf8(bytes).map_err(|_| hpack_error("header is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn pairs(headers: &[(String, String)]) -> Vec<(&str, &str)> {
+        headers
+            .iter()
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_integer_coding() {
+        // RFC 7541 C.1: 1337 with a 5-bit prefix
+        let mut out = Vec::new();
+        encode_integer(1337, 5, 0, &mut out);
+        assert_eq!(out, [0x1f, 0x9a, 0x0a]);
+        let mut pos = 0;
+        assert_eq!(decode_integer(&out, &mut pos, 5).unwrap(), 1337);
+        assert_eq!(pos, 3);
+
+        let mut pos = 0;
+        assert!(decode_integer(&[0x1f, 0xff, 0xff, 0xff, 0xff, 0xff], &mut pos, 5).is_err());
+    }
+
+    #[test]
+    fn test_huffman_round_trip() {
+        assert_eq!(
+            huffman_decode(&hex("f1e3 c2e5 f23a 6ba0 ab90 f4ff")).unwrap(),
+            b"www.example.com"
+        );
+
+        let text = "Mon, 21 Oct 2013 20:13:21 GMT /api/v1/flights?from=KUL&to=SIN";
+        let mut out = Vec::new();
+        huffman_encode(text.as_bytes(), &mut out);
+        assert_eq!(out.len(), huffman_len(text.as_bytes()));
+        assert_eq!(huffman_decode(&out).unwrap(), text.as_bytes());
+
+        let all: Vec<u8> = (0..=255).collect();
+        let mut out = Vec::new();
+        huffman_encode(&all, &mut out);
+        assert_eq!(huffman_decode(&out).unwrap(), all);
+
+        // Padding longer than 7 bits, or not all ones, is an error
+        assert!(huffman_decode(&[0xff, 0xff]).is_err());
+        assert!(huffman_decode(&[0x00]).is_err());
+    }
+
+    #[test]
+    fn test_decode_rfc_requests() {
+        // RFC 7541 C.4: three requests on one connection, Huffman coded
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE);
+
+        let first = decoder
+            .decode(&hex("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff"))
+            .unwrap();
+        assert_eq!(
+            pairs(&first),
+            [
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ]
+        );
+        assert_eq!(decoder.size, 57);
+
+        let second = decoder
+            .decode(&hex("8286 84be 5886 a8eb 1064 9cbf"))
+            .unwrap();
+        assert_eq!(pairs(&second)[3], (":authority", "www.example.com"));
+        assert_eq!(pairs(&second)[4], ("cache-control", "no-cache"));
+        assert_eq!(decoder.size, 110);
+
+        let third = decoder
+            .decode(&hex(
+                "8287 85bf 4088 25a8 49e9 5ba9 7d7f 8925 a849 e95b b8e8 b4bf",
+            ))
+            .unwrap();
+        assert_eq!(
+            pairs(&third),
+            [
+                (":method", "GET"),
+                (":scheme", "https"),
+                (":path", "/index.html"),
+                (":authority", "www.example.com"),
+                ("custom-key", "custom-value"),
+            ]
+        );
+        assert_eq!(decoder.size, 164);
+    }
+
+    #[test]
+    fn test_table_size_updates() {
+        let mut decoder = Decoder::new(DEFAULT_TABLE_SIZE);
+        decoder
+            .decode(&hex("8286 8441 8cf1 e3c2 e5f2 3a6b a0ab 90f4 ff"))
+            .unwrap();
+        assert_eq!(decoder.table.len(), 1);
+
+        // Shrinking the table evicts what no longer fits
+        decoder.decode(&[0x20]).unwrap();
+        assert!(decoder.table.is_empty());
+        assert!(decoder.decode(&[0xbe]).is_err());
+
+        // Updates above our limit, or after a field, are rejected
+        assert!(decoder.decode(&[0x3f, 0xe2, 0x1f]).is_err());
+        assert!(decoder.decode(&[0x82, 0x20]).is_err());
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let headers = [
+            (":status", "200"),
+            (":status", "201"),
+            ("content-type", "application/json"),
+            ("x-request-id", "req_7KQ2M9XP"),
+            ("x-empty", ""),
+        ];
+        let block = encode(headers);
+        // An exact static match is a single byte
+        assert_eq!(block[0], 0x88);
+
+        let decoded = Decoder::new(DEFAULT_TABLE_SIZE).decode(&block).unwrap();
+        assert_eq!(pairs(&decoded), headers);
+    }
+}
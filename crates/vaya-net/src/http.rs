@@ -71,6 +71,8 @@ impl FromStr for Method {
 pub enum Version {
     Http10,
     Http11,
+    /// HTTP/2, negotiated per connection rather than named in a request line
+    Http2,
 }
 
 impl Version {
@@ -79,6 +81,7 @@ impl Version {
         match self {
             Version::Http10 => "HTTP/1.0",
             Version::Http11 => "HTTP/1.1",
+            Version::Http2 => "HTTP/2",
         }
     }
 }
@@ -156,6 +159,45 @@ impl StatusCode {
         *self as u16
     }
 
+    /// Look up a status by its numeric code
+    pub fn from_code(code: u16) -> Option<Self> {
+        const ALL: [StatusCode; 32] = [
+            StatusCode::Continue,
+            StatusCode::SwitchingProtocols,
+            StatusCode::Ok,
+            StatusCode::Created,
+            StatusCode::Accepted,
+            StatusCode::NoContent,
+            StatusCode::MovedPermanently,
+            StatusCode::Found,
+            StatusCode::SeeOther,
+            StatusCode::NotModified,
+            StatusCode::TemporaryRedirect,
+            StatusCode::PermanentRedirect,
+            StatusCode::BadRequest,
+            StatusCode::Unauthorized,
+            StatusCode::Forbidden,
+            StatusCode::NotFound,
+            StatusCode::MethodNotAllowed,
+            StatusCode::NotAcceptable,
+            StatusCode::RequestTimeout,
+            StatusCode::Conflict,
+            StatusCode::Gone,
+            StatusCode::LengthRequired,
+            StatusCode::PayloadTooLarge,
+            StatusCode::UriTooLong,
+            StatusCode::UnsupportedMediaType,
+            StatusCode::UnprocessableEntity,
+            StatusCode::TooManyRequests,
+            StatusCode::InternalServerError,
+            StatusCode::NotImplemented,
+            StatusCode::BadGateway,
+            StatusCode::ServiceUnavailable,
+            StatusCode::GatewayTimeout,
+        ];
+        ALL.into_iter().find(|status| status.code() == code)
+    }
+
     /// Get the reason phrase for this status
     pub fn reason(&self) -> &'static str {
         match self {
@@ -305,9 +347,15 @@ impl Headers {
 
     /// Check if connection should be kept alive
     pub fn is_keep_alive(&self) -> bool {
-        self.get("connection")
-            .map(|v| v.to_lowercase() != "close")
-            .unwrap_or(true)
+        !self.has_connection_option("close")
+    }
+
+    /// Check if any Connection header lists the given option
+    pub fn has_connection_option(&self, option: &str) -> bool {
+        self.get_all("connection").iter().any(|v| {
+            v.split(',')
+                .any(|token| token.trim().eq_ignore_ascii_case(option))
+        })
     }
 }
 
@@ -335,6 +383,11 @@ mod tests {
         assert!(StatusCode::Ok.is_success());
         assert!(StatusCode::NotFound.is_client_error());
         assert!(StatusCode::InternalServerError.is_server_error());
+        assert_eq!(
+            StatusCode::from_code(429),
+            Some(StatusCode::TooManyRequests)
+        );
+        assert_eq!(StatusCode::from_code(418), None);
     }
 
     #[test]
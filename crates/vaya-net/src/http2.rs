@@ -0,0 +1,1508 @@
+//! HTTP/2 connections (RFC 9113)
+//!
+//! HTTP/2 is negotiated with ALPN on TLS listeners, or used directly by a
+//! client that opens a plaintext connection with the HTTP/2 preface (prior
+//! knowledge). Each request stream is routed on its own task, so a slow
+//! handler doesn't hold up the other requests on the connection. Responses
+//! are funnelled back to the connection task, which owns the socket and the
+//! HPACK state, and bodies are sent as fast as the client's flow-control
+//! windows allow.
+//!
+//! Request bodies are buffered in full before routing, as they are for
+//! HTTP/1.1, so the receive windows are topped up as soon as data arrives.
+//! Server push and stream priorities are not used.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+use crate::hpack::{self, Decoder};
+use crate::server::{ConnectionLimits, Drain, Server};
+use crate::{
+    Method, NetError, NetResult, Request, Response, Router, Version, MAX_BODY_SIZE,
+    MAX_HEADER_SIZE, STREAM_CHUNK_SIZE,
+};
+
+/// The client connection preface
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+// Frame types
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const PRIORITY: u8 = 0x2;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PUSH_PROMISE: u8 = 0x5;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+const WINDOW_UPDATE: u8 = 0x8;
+const CONTINUATION: u8 = 0x9;
+
+// Frame flags
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const FLAG_PRIORITY: u8 = 0x20;
+
+// Settings identifiers
+const SETTINGS_HEADER_TABLE_SIZE: u16 = 0x1;
+const SETTINGS_ENABLE_PUSH: u16 = 0x2;
+const SETTINGS_MAX_CONCURRENT_STREAMS: u16 = 0x3;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+const SETTINGS_MAX_HEADER_LIST_SIZE: u16 = 0x6;
+
+/// Frame header length
+const FRAME_HEADER_LEN: usize = 9;
+
+/// Largest frame payload we accept, and the most a client may send us
+const MAX_FRAME_SIZE: usize = 16_384;
+
+/// Largest frame payload a client may ask us to accept
+const MAX_FRAME_SIZE_LIMIT: u32 = 16_777_215;
+
+/// Flow-control window both sides start with
+const DEFAULT_WINDOW: i64 = 65_535;
+
+/// Largest a flow-control window may grow
+const MAX_WINDOW: i64 = (1 << 31) - 1;
+
+/// Receive window we offer for each stream and for the connection
+const RECV_WINDOW: i64 = 1 << 20;
+
+/// Streams a client may have open at once
+const MAX_CONCURRENT_STREAMS: usize = 100;
+
+/// Largest compressed header block, across CONTINUATION frames
+const MAX_HEADER_BLOCK: usize = 2 * MAX_HEADER_SIZE;
+
+/// Headers that only apply to a single HTTP/1.1 hop; not allowed in HTTP/2
+const CONNECTION_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// HTTP/2 error codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorCode {
+    NoError = 0x0,
+    ProtocolError = 0x1,
+    InternalError = 0x2,
+    FlowControlError = 0x3,
+    StreamClosed = 0x5,
+    FrameSizeError = 0x6,
+    RefusedStream = 0x7,
+    CompressionError = 0x9,
+    EnhanceYourCalm = 0xb,
+}
+
+/// An error that ends the whole connection with GOAWAY
+#[derive(Debug)]
+struct ConnectionError {
+    code: ErrorCode,
+    reason: String,
+}
+
+fn connection_error(code: ErrorCode, reason: impl Into<String>) -> ConnectionError {
+    ConnectionError {
+        code,
+        reason: reason.into(),
+    }
+}
+
+/// A frame as read from the client
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+/// Serialize a frame
+fn encode_frame(kind: u8, flags: u8, stream_id: u32, payload: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&(payload.len() as u32).to_be_bytes()[1..]);
+    out.push(kind);
+    out.push(flags);
+    out.extend_from_slice(&(stream_id & 0x7fff_ffff).to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+/// Remove the padding from a PADDED frame's payload
+fn strip_padding(flags: u8, payload: &[u8]) -> Result<&[u8], ConnectionError> {
+    if flags & FLAG_PADDED == 0 {
+        return Ok(payload);
+    }
+    let (&pad, rest) = payload
+        .split_first()
+        .ok_or_else(|| connection_error(ErrorCode::FrameSizeError, "missing pad length"))?;
+    rest.len()
+        .checked_sub(pad as usize)
+        .map(|len| &rest[..len])
+        .ok_or_else(|| connection_error(ErrorCode::ProtocolError, "padding exceeds payload"))
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Work for the connection task from a stream task
+enum Outbound {
+    Headers {
+        stream_id: u32,
+        fields: Vec<(String, String)>,
+        end_stream: bool,
+    },
+    Data {
+        stream_id: u32,
+        data: Vec<u8>,
+        end_stream: bool,
+    },
+    Reset {
+        stream_id: u32,
+        code: ErrorCode,
+    },
+}
+
+/// The client's flow-control windows, shared with the stream tasks so they
+/// can wait for room before queueing DATA
+struct SendWindows {
+    state: Mutex<WindowState>,
+    /// Bumped whenever a window grows or a stream closes
+    changed: watch::Sender<()>,
+}
+
+struct WindowState {
+    connection: i64,
+    streams: HashMap<u32, i64>,
+    /// Window new streams start with (`SETTINGS_INITIAL_WINDOW_SIZE`)
+    initial: i64,
+    /// Largest frame payload the client accepts
+    max_frame_size: usize,
+}
+
+impl SendWindows {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new(WindowState {
+                connection: DEFAULT_WINDOW,
+                streams: HashMap::new(),
+                initial: DEFAULT_WINDOW,
+                max_frame_size: MAX_FRAME_SIZE,
+            }),
+            changed: watch::channel(()).0,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WindowState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify(&self) {
+        self.changed.send_replace(());
+    }
+
+    fn open(&self, stream_id: u32) {
+        let mut state = self.lock();
+        let initial = state.initial;
+        state.streams.insert(stream_id, initial);
+    }
+
+    fn close(&self, stream_id: u32) {
+        self.lock().streams.remove(&stream_id);
+        self.notify();
+    }
+
+    fn max_frame_size(&self) -> usize {
+        self.lock().max_frame_size
+    }
+
+    fn set_max_frame_size(&self, size: usize) {
+        self.lock().max_frame_size = size;
+        self.notify();
+    }
+
+    /// Apply a new initial window size to every open stream
+    fn set_initial(&self, initial: i64) -> Result<(), ConnectionError> {
+        let mut state = self.lock();
+        let delta = initial - state.initial;
+        state.initial = initial;
+        for window in state.streams.values_mut() {
+            *window += delta;
+            if *window > MAX_WINDOW {
+                return Err(connection_error(
+                    ErrorCode::FlowControlError,
+                    "initial window size overflows a stream window",
+                ));
+            }
+        }
+        drop(state);
+        self.notify();
+        Ok(())
+    }
+
+    /// Grow a window (stream 0 is the connection); false if it overflows
+    fn grow(&self, stream_id: u32, increment: i64) -> bool {
+        let mut state = self.lock();
+        let window = if stream_id == 0 {
+            &mut state.connection
+        } else {
+            match state.streams.get_mut(&stream_id) {
+                Some(window) => window,
+                // Already closed on our side; nothing left to send
+                None => return true,
+            }
+        };
+        *window += increment;
+        let ok = *window <= MAX_WINDOW;
+        drop(state);
+        self.notify();
+        ok
+    }
+
+    /// Wait until up to `want` bytes may be sent on a stream and take them
+    /// from both windows. `None` once the stream or connection is gone.
+    async fn reserve(
+        &self,
+        stream_id: u32,
+        want: usize,
+        changed: &mut watch::Receiver<()>,
+    ) -> Option<usize> {
+        loop {
+            changed.borrow_and_update();
+            {
+                let mut state = self.lock();
+                let stream = *state.streams.get(&stream_id)?;
+                let available = stream
+                    .min(state.connection)
+                    .min(state.max_frame_size as i64)
+                    .min(want as i64);
+                if available > 0 {
+                    state.connection -= available;
+                    state.streams.insert(stream_id, stream - available);
+                    return Some(available as usize);
+                }
+            }
+            changed.changed().await.ok()?;
+        }
+    }
+}
+
+/// A request stream
+struct Stream {
+    /// Request waiting for its body; taken when it is routed
+    request: Option<Request>,
+    /// Body received so far
+    body: Vec<u8>,
+    /// Body length the client declared
+    content_length: Option<usize>,
+    /// Window the client may still send into
+    recv_window: i64,
+    /// The client has finished sending
+    recv_closed: bool,
+    /// Task producing the response
+    task: Option<JoinHandle<()>>,
+}
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        // A reset stream's handler has nobody to answer
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Serve HTTP/2 on a connection whose next bytes are the client preface
+pub(crate) async fn serve<R, W>(
+    reader: R,
+    writer: W,
+    peer: Option<SocketAddr>,
+    router: Arc<Router>,
+    limits: ConnectionLimits,
+    drain: Drain,
+) -> NetResult<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (outbound_tx, outbound_rx) = mpsc::unbounded_channel();
+    let mut connection = Connection {
+        reader,
+        writer,
+        read_buf: Vec::new(),
+        write_buf: Vec::new(),
+        peer,
+        router,
+        limits,
+        drain,
+        decoder: Decoder::new(hpack::DEFAULT_TABLE_SIZE),
+        streams: HashMap::new(),
+        windows: Arc::new(SendWindows::new()),
+        outbound_tx,
+        outbound_rx,
+        recv_window: RECV_WINDOW,
+        last_stream_id: 0,
+        continuation: None,
+        served: 0,
+        draining: false,
+        goaway_sent: false,
+        read_closed: false,
+    };
+    connection.run().await
+}
+
+/// What woke the connection task
+enum Event {
+    Read(std::io::Result<usize>),
+    Outbound(Outbound),
+    Idle,
+    Shutdown,
+}
+
+struct Connection<R, W> {
+    reader: R,
+    writer: W,
+    /// Bytes read but not yet parsed into frames
+    read_buf: Vec<u8>,
+    /// Frames waiting to be written
+    write_buf: Vec<u8>,
+    /// Address of the connection's other end
+    peer: Option<SocketAddr>,
+    router: Arc<Router>,
+    limits: ConnectionLimits,
+    /// Fires when the server shuts down
+    drain: Drain,
+    decoder: Decoder,
+    streams: HashMap<u32, Stream>,
+    windows: Arc<SendWindows>,
+    outbound_tx: mpsc::UnboundedSender<Outbound>,
+    outbound_rx: mpsc::UnboundedReceiver<Outbound>,
+    /// Connection-level window the client may still send into
+    recv_window: i64,
+    /// Highest stream the client has opened
+    last_stream_id: u32,
+    /// Header block awaiting CONTINUATION: stream, END_STREAM, fragments so far
+    continuation: Option<(u32, bool, Vec<u8>)>,
+    /// Streams opened, counted against `max_requests`
+    served: usize,
+    /// No new streams are accepted; close once the open ones finish
+    draining: bool,
+    goaway_sent: bool,
+    /// The client has closed its side of the connection
+    read_closed: bool,
+}
+
+impl<R, W> Connection<R, W>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    async fn run(&mut self) -> NetResult<()> {
+        let mut preface = [0u8; PREFACE.len()];
+        tokio::time::timeout(
+            self.limits.read_timeout,
+            self.reader.read_exact(&mut preface),
+        )
+        .await
+        .map_err(|_| NetError::Timeout)??;
+        if preface != PREFACE {
+            return Err(NetError::InvalidRequest("Invalid HTTP/2 preface".into()));
+        }
+
+        self.send_settings();
+        let mut buf = vec![0u8; MAX_FRAME_SIZE + FRAME_HEADER_LEN];
+
+        loop {
+            if let Err(e) = self.process_frames() {
+                self.go_away(e.code, &e.reason);
+                self.flush().await?;
+                return Err(NetError::Protocol(format!(
+                    "HTTP/2 {:?}: {}",
+                    e.code, e.reason
+                )));
+            }
+            self.flush().await?;
+
+            if self.draining && self.streams.is_empty() {
+                break;
+            }
+
+            // Idle connections are closed like HTTP/1.1 keep-alive ones
+            let idle = self.streams.is_empty();
+            let wait = if self.served == 0 {
+                self.limits.read_timeout
+            } else {
+                self.limits.keep_alive_timeout
+            };
+            let event = tokio::select! {
+                read = self.reader.read(&mut buf), if !self.read_closed => Event::Read(read),
+                Some(outbound) = self.outbound_rx.recv() => Event::Outbound(outbound),
+                _ = tokio::time::sleep(wait), if idle => Event::Idle,
+                _ = self.drain.wait(), if !self.goaway_sent => Event::Shutdown,
+            };
+
+            match event {
+                Event::Read(Ok(0)) => {
+                    // Answer the requests already received, then close
+                    self.read_closed = true;
+                    self.draining = true;
+                    self.streams.retain(|_, stream| stream.recv_closed);
+                }
+                Event::Read(Ok(n)) => self.read_buf.extend_from_slice(&buf[..n]),
+                Event::Read(Err(e)) => return Err(e.into()),
+                Event::Outbound(outbound) => {
+                    self.handle_outbound(outbound);
+                    while let Ok(outbound) = self.outbound_rx.try_recv() {
+                        self.handle_outbound(outbound);
+                    }
+                }
+                Event::Idle | Event::Shutdown => self.go_away(ErrorCode::NoError, ""),
+            }
+        }
+
+        self.go_away(ErrorCode::NoError, "");
+        self.flush().await?;
+        let _ = self.writer.shutdown().await;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> NetResult<()> {
+        if !self.write_buf.is_empty() {
+            self.writer.write_all(&self.write_buf).await?;
+            self.writer.flush().await?;
+            self.write_buf.clear();
+        }
+        Ok(())
+    }
+
+    fn queue(&mut self, kind: u8, flags: u8, stream_id: u32, payload: &[u8]) {
+        encode_frame(kind, flags, stream_id, payload, &mut self.write_buf);
+    }
+
+    /// Our settings, and a larger connection receive window
+    fn send_settings(&mut self) {
+        let mut payload = Vec::new();
+        for (id, value) in [
+            (
+                SETTINGS_MAX_CONCURRENT_STREAMS,
+                MAX_CONCURRENT_STREAMS as u32,
+            ),
+            (SETTINGS_INITIAL_WINDOW_SIZE, RECV_WINDOW as u32),
+            (SETTINGS_MAX_HEADER_LIST_SIZE, MAX_HEADER_SIZE as u32),
+        ] {
+            payload.extend_from_slice(&id.to_be_bytes());
+            payload.extend_from_slice(&value.to_be_bytes());
+        }
+        self.queue(SETTINGS, 0, 0, &payload);
+        let increment = (RECV_WINDOW - DEFAULT_WINDOW) as u32;
+        self.queue(WINDOW_UPDATE, 0, 0, &increment.to_be_bytes());
+    }
+
+    /// Stop accepting streams, telling the client the last one we'll serve
+    fn go_away(&mut self, code: ErrorCode, reason: &str) {
+        self.draining = true;
+        if self.goaway_sent {
+            return;
+        }
+        self.goaway_sent = true;
+        let mut payload = self.last_stream_id.to_be_bytes().to_vec();
+        payload.extend_from_slice(&(code as u32).to_be_bytes());
+        payload.extend_from_slice(reason.as_bytes());
+        self.queue(GOAWAY, 0, 0, &payload);
+    }
+
+    /// End a stream with RST_STREAM
+    fn reset(&mut self, stream_id: u32, code: ErrorCode) {
+        self.queue(RST_STREAM, 0, stream_id, &(code as u32).to_be_bytes());
+        self.streams.remove(&stream_id);
+        self.windows.close(stream_id);
+    }
+
+    /// Handle every complete frame in the read buffer
+    fn process_frames(&mut self) -> Result<(), ConnectionError> {
+        let mut pos = 0;
+        let result = loop {
+            let buffered = &self.read_buf[pos..];
+            if buffered.len() < FRAME_HEADER_LEN {
+                break Ok(());
+            }
+            let len = read_u32(&[0, buffered[0], buffered[1], buffered[2]]) as usize;
+            if len > MAX_FRAME_SIZE {
+                break Err(connection_error(
+                    ErrorCode::FrameSizeError,
+                    "frame larger than SETTINGS_MAX_FRAME_SIZE",
+                ));
+            }
+            if buffered.len() < FRAME_HEADER_LEN + len {
+                break Ok(());
+            }
+            let frame = Frame {
+                kind: buffered[3],
+                flags: buffered[4],
+                stream_id: read_u32(&buffered[5..9]) & 0x7fff_ffff,
+                payload: buffered[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len].to_vec(),
+            };
+            pos += FRAME_HEADER_LEN + len;
+            if let Err(e) = self.handle_frame(frame) {
+                break Err(e);
+            }
+        };
+        self.read_buf.drain(..pos);
+        result
+    }
+
+    fn handle_frame(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        // A header block must be finished before anything else arrives
+        if let Some((stream_id, _, _)) = self.continuation {
+            if frame.kind != CONTINUATION || frame.stream_id != stream_id {
+                return Err(connection_error(
+                    ErrorCode::ProtocolError,
+                    "expected CONTINUATION",
+                ));
+            }
+        }
+
+        match frame.kind {
+            DATA => self.on_data(frame),
+            HEADERS => self.on_headers(frame),
+            CONTINUATION => self.on_continuation(frame),
+            PRIORITY => {
+                if frame.stream_id == 0 {
+                    return Err(connection_error(
+                        ErrorCode::ProtocolError,
+                        "PRIORITY on stream 0",
+                    ));
+                }
+                if frame.payload.len() != 5 {
+                    self.reset(frame.stream_id, ErrorCode::FrameSizeError);
+                }
+                Ok(())
+            }
+            RST_STREAM => {
+                if frame.stream_id == 0 || frame.stream_id > self.last_stream_id {
+                    return Err(connection_error(
+                        ErrorCode::ProtocolError,
+                        "RST_STREAM on an idle stream",
+                    ));
+                }
+                if frame.payload.len() != 4 {
+                    return Err(connection_error(
+                        ErrorCode::FrameSizeError,
+                        "bad RST_STREAM",
+                    ));
+                }
+                self.streams.remove(&frame.stream_id);
+                self.windows.close(frame.stream_id);
+                Ok(())
+            }
+            SETTINGS => self.on_settings(frame),
+            PUSH_PROMISE => Err(connection_error(
+                ErrorCode::ProtocolError,
+                "clients can't push",
+            )),
+            PING => {
+                if frame.stream_id != 0 {
+                    return Err(connection_error(
+                        ErrorCode::ProtocolError,
+                        "PING on a stream",
+                    ));
+                }
+                if frame.payload.len() != 8 {
+                    return Err(connection_error(ErrorCode::FrameSizeError, "bad PING"));
+                }
+                if frame.flags & FLAG_ACK == 0 {
+                    self.queue(PING, FLAG_ACK, 0, &frame.payload);
+                }
+                Ok(())
+            }
+            GOAWAY => {
+                if frame.stream_id != 0 {
+                    return Err(connection_error(
+                        ErrorCode::ProtocolError,
+                        "GOAWAY on a stream",
+                    ));
+                }
+                // The client opens no more streams; finish the open ones
+                self.draining = true;
+                Ok(())
+            }
+            WINDOW_UPDATE => self.on_window_update(frame),
+            // Unknown frame types are ignored
+            _ => Ok(()),
+        }
+    }
+
+    fn on_settings(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        if frame.stream_id != 0 {
+            return Err(connection_error(
+                ErrorCode::ProtocolError,
+                "SETTINGS on a stream",
+            ));
+        }
+        if frame.flags & FLAG_ACK != 0 {
+            if !frame.payload.is_empty() {
+                return Err(connection_error(
+                    ErrorCode::FrameSizeError,
+                    "SETTINGS ACK with payload",
+                ));
+            }
+            return Ok(());
+        }
+        let settings = frame.payload.chunks_exact(6);
+        if !settings.remainder().is_empty() {
+            return Err(connection_error(ErrorCode::FrameSizeError, "bad SETTINGS"));
+        }
+
+        for setting in settings {
+            let id = u16::from_be_bytes([setting[0], setting[1]]);
+            let value = read_u32(&setting[2..]);
+            match id {
+                SETTINGS_ENABLE_PUSH if value > 1 => {
+                    return Err(connection_error(
+                        ErrorCode::ProtocolError,
+                        "bad ENABLE_PUSH",
+                    ));
+                }
+                SETTINGS_INITIAL_WINDOW_SIZE => {
+                    if value as i64 > MAX_WINDOW {
+                        return Err(connection_error(
+                            ErrorCode::FlowControlError,
+                            "initial window size too large",
+                        ));
+                    }
+                    self.windows.set_initial(value as i64)?;
+                }
+                SETTINGS_MAX_FRAME_SIZE => {
+                    if !(MAX_FRAME_SIZE as u32..=MAX_FRAME_SIZE_LIMIT).contains(&value) {
+                        return Err(connection_error(
+                            ErrorCode::ProtocolError,
+                            "bad MAX_FRAME_SIZE",
+                        ));
+                    }
+                    self.windows.set_max_frame_size(value as usize);
+                }
+                // Our encoder never uses the dynamic table, and we never push
+                SETTINGS_HEADER_TABLE_SIZE | SETTINGS_MAX_CONCURRENT_STREAMS => {}
+                // Unknown settings are ignored
+                _ => {}
+            }
+        }
+
+        self.queue(SETTINGS, FLAG_ACK, 0, &[]);
+        Ok(())
+    }
+
+    fn on_window_update(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        if frame.payload.len() != 4 {
+            return Err(connection_error(
+                ErrorCode::FrameSizeError,
+                "bad WINDOW_UPDATE",
+            ));
+        }
+        let increment = (read_u32(&frame.payload) & 0x7fff_ffff) as i64;
+        let stream_id = frame.stream_id;
+
+        if stream_id == 0 {
+            if increment == 0 {
+                return Err(connection_error(
+                    ErrorCode::ProtocolError,
+                    "zero WINDOW_UPDATE",
+                ));
+            }
+            if !self.windows.grow(0, increment) {
+                return Err(connection_error(
+                    ErrorCode::FlowControlError,
+                    "connection window overflow",
+                ));
+            }
+            return Ok(());
+        }
+
+        if stream_id > self.last_stream_id {
+            return Err(connection_error(
+                ErrorCode::ProtocolError,
+                "WINDOW_UPDATE on an idle stream",
+            ));
+        }
+        if increment == 0 {
+            self.reset(stream_id, ErrorCode::ProtocolError);
+        } else if !self.windows.grow(stream_id, increment) {
+            self.reset(stream_id, ErrorCode::FlowControlError);
+        }
+        Ok(())
+    }
+
+    fn on_headers(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        if frame.stream_id == 0 {
+            return Err(connection_error(
+                ErrorCode::ProtocolError,
+                "HEADERS on stream 0",
+            ));
+        }
+        let mut block = strip_padding(frame.flags, &frame.payload)?;
+        if frame.flags & FLAG_PRIORITY != 0 {
+            if block.len() < 5 {
+                return Err(connection_error(
+                    ErrorCode::FrameSizeError,
+                    "short HEADERS priority",
+                ));
+            }
+            block = &block[5..];
+        }
+
+        let end_stream = frame.flags & FLAG_END_STREAM != 0;
+        if frame.flags & FLAG_END_HEADERS != 0 {
+            self.on_header_block(frame.stream_id, end_stream, block)
+        } else {
+            self.continuation = Some((frame.stream_id, end_stream, block.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn on_continuation(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        let Some((stream_id, end_stream, mut block)) = self.continuation.take() else {
+            return Err(connection_error(
+                ErrorCode::ProtocolError,
+                "unexpected CONTINUATION",
+            ));
+        };
+        block.extend_from_slice(&frame.payload);
+        if block.len() > MAX_HEADER_BLOCK {
+            return Err(connection_error(
+                ErrorCode::EnhanceYourCalm,
+                "header block too large",
+            ));
+        }
+
+        if frame.flags & FLAG_END_HEADERS != 0 {
+            self.on_header_block(stream_id, end_stream, &block)
+        } else {
+            self.continuation = Some((stream_id, end_stream, block));
+            Ok(())
+        }
+    }
+
+    /// A complete header block: a new request, or trailers ending one
+    fn on_header_block(
+        &mut self,
+        stream_id: u32,
+        end_stream: bool,
+        block: &[u8],
+    ) -> Result<(), ConnectionError> {
+        // Decoded even for streams we refuse, to keep the table in step
+        let fields = self
+            .decoder
+            .decode(block)
+            .map_err(|e| connection_error(ErrorCode::CompressionError, e.to_string()))?;
+
+        if let Some(stream) = self.streams.get(&stream_id) {
+            // Trailers carry nothing we use, but must end the stream
+            if stream.recv_closed {
+                self.reset(stream_id, ErrorCode::StreamClosed);
+            } else if !end_stream {
+                self.reset(stream_id, ErrorCode::ProtocolError);
+            } else {
+                self.end_of_request(stream_id);
+            }
+            return Ok(());
+        }
+
+        if stream_id & 1 == 0 || stream_id <= self.last_stream_id {
+            return Err(connection_error(
+                ErrorCode::ProtocolError,
+                "stream IDs must be odd and increasing",
+            ));
+        }
+        if self.goaway_sent {
+            // Past the last stream we promised to serve; the client retries it
+            return Ok(());
+        }
+        self.last_stream_id = stream_id;
+        if self.streams.len() >= MAX_CONCURRENT_STREAMS {
+            self.reset(stream_id, ErrorCode::RefusedStream);
+            return Ok(());
+        }
+        self.served += 1;
+
+        let request = match build_request(fields) {
+            Ok(request) => request,
+            Err(reason) => {
+                tracing::debug!(
+                    "Malformed HTTP/2 request on stream {}: {}",
+                    stream_id,
+                    reason
+                );
+                self.reset(stream_id, ErrorCode::ProtocolError);
+                return Ok(());
+            }
+        };
+        let content_length = match request.headers().get("content-length") {
+            Some(value) => match value.parse::<usize>() {
+                Ok(length) => Some(length),
+                Err(_) => {
+                    self.reset(stream_id, ErrorCode::ProtocolError);
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+
+        self.streams.insert(
+            stream_id,
+            Stream {
+                request: Some(request),
+                body: Vec::new(),
+                content_length,
+                recv_window: RECV_WINDOW,
+                recv_closed: false,
+                task: None,
+            },
+        );
+        self.windows.open(stream_id);
+
+        if content_length.is_some_and(|length| length > MAX_BODY_SIZE) {
+            self.reject(stream_id, NetError::RequestTooLarge);
+        }
+        if !self.limits.allows_another(self.served) {
+            self.go_away(ErrorCode::NoError, "");
+        }
+        if end_stream {
+            self.end_of_request(stream_id);
+        }
+        Ok(())
+    }
+
+    fn on_data(&mut self, frame: Frame) -> Result<(), ConnectionError> {
+        let stream_id = frame.stream_id;
+        if stream_id == 0 {
+            return Err(connection_error(
+                ErrorCode::ProtocolError,
+                "DATA on stream 0",
+            ));
+        }
+        // Padding counts against flow control too
+        let len = frame.payload.len() as i64;
+        self.recv_window -= len;
+        if self.recv_window < 0 {
+            return Err(connection_error(
+                ErrorCode::FlowControlError,
+                "connection window exceeded",
+            ));
+        }
+        let data = strip_padding(frame.flags, &frame.payload)?;
+        let end_stream = frame.flags & FLAG_END_STREAM != 0;
+
+        // The body is buffered whole, so what arrived is consumed at once
+        if len > 0 {
+            self.recv_window += len;
+            self.queue(WINDOW_UPDATE, 0, 0, &(len as u32).to_be_bytes());
+        }
+
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            if stream_id > self.last_stream_id && !self.goaway_sent {
+                return Err(connection_error(
+                    ErrorCode::ProtocolError,
+                    "DATA on an idle stream",
+                ));
+            }
+            // Already reset; data in flight is dropped
+            return Ok(());
+        };
+        if stream.recv_closed {
+            self.reset(stream_id, ErrorCode::StreamClosed);
+            return Ok(());
+        }
+        stream.recv_window -= len;
+        if stream.recv_window < 0 {
+            self.reset(stream_id, ErrorCode::FlowControlError);
+            return Ok(());
+        }
+
+        let too_large = stream.request.is_some() && stream.body.len() + data.len() > MAX_BODY_SIZE;
+        if stream.request.is_some() && !too_large {
+            stream.body.extend_from_slice(data);
+        }
+        if len > 0 && !end_stream {
+            stream.recv_window += len;
+            self.queue(WINDOW_UPDATE, 0, stream_id, &(len as u32).to_be_bytes());
+        }
+
+        if too_large {
+            self.reject(stream_id, NetError::RequestTooLarge);
+        }
+        if end_stream {
+            self.end_of_request(stream_id);
+        }
+        Ok(())
+    }
+
+    /// The client has sent the whole request; route it
+    fn end_of_request(&mut self, stream_id: u32) {
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return;
+        };
+        stream.recv_closed = true;
+        let Some(mut request) = stream.request.take() else {
+            return;
+        };
+        if stream
+            .content_length
+            .is_some_and(|length| length != stream.body.len())
+        {
+            self.reset(stream_id, ErrorCode::ProtocolError);
+            return;
+        }
+
+        request.set_body(std::mem::take(&mut stream.body));
+        request.set_peer_addr(self.peer);
+        let router = self.router.clone();
+        let outbound = self.outbound_tx.clone();
+        let windows = self.windows.clone();
+        stream.task = Some(tokio::spawn(async move {
+            let head = request.method() == Method::HEAD;
+            let response = match router.handle(request).await {
+                Ok(response) => response,
+                Err(e) => Server::error_response(&e),
+            };
+            send_response(stream_id, response, head, &outbound, &windows).await;
+        }));
+    }
+
+    /// Answer a stream with an error before its request is complete
+    fn reject(&mut self, stream_id: u32, error: NetError) {
+        let Some(stream) = self.streams.get_mut(&stream_id) else {
+            return;
+        };
+        stream.request = None;
+        stream.body = Vec::new();
+        let outbound = self.outbound_tx.clone();
+        let windows = self.windows.clone();
+        let response = Server::error_response(&error);
+        stream.task = Some(tokio::spawn(async move {
+            send_response(stream_id, response, false, &outbound, &windows).await;
+        }));
+    }
+
+    fn handle_outbound(&mut self, outbound: Outbound) {
+        match outbound {
+            Outbound::Headers {
+                stream_id,
+                fields,
+                end_stream,
+            } => {
+                if !self.streams.contains_key(&stream_id) {
+                    return;
+                }
+                let block = hpack::encode(fields.iter().map(|(n, v)| (n.as_str(), v.as_str())));
+                let max_frame_size = self.windows.max_frame_size();
+                let mut chunks = block.chunks(max_frame_size).peekable();
+                let mut kind = HEADERS;
+                let mut flags = if end_stream { FLAG_END_STREAM } else { 0 };
+                // An empty block still needs its HEADERS frame
+                if chunks.peek().is_none() {
+                    self.queue(kind, flags | FLAG_END_HEADERS, stream_id, &[]);
+                }
+                while let Some(chunk) = chunks.next() {
+                    if chunks.peek().is_none() {
+                        flags |= FLAG_END_HEADERS;
+                    }
+                    self.queue(kind, flags, stream_id, chunk);
+                    kind = CONTINUATION;
+                    flags = 0;
+                }
+                if end_stream {
+                    self.end_of_response(stream_id);
+                }
+            }
+            Outbound::Data {
+                stream_id,
+                data,
+                end_stream,
+            } => {
+                if !self.streams.contains_key(&stream_id) {
+                    return;
+                }
+                let flags = if end_stream { FLAG_END_STREAM } else { 0 };
+                self.queue(DATA, flags, stream_id, &data);
+                if end_stream {
+                    self.end_of_response(stream_id);
+                }
+            }
+            Outbound::Reset { stream_id, code } => {
+                if self.streams.contains_key(&stream_id) {
+                    self.reset(stream_id, code);
+                }
+            }
+        }
+    }
+
+    /// The response is sent in full; the stream closes
+    fn end_of_response(&mut self, stream_id: u32) {
+        let recv_closed = self
+            .streams
+            .get(&stream_id)
+            .is_some_and(|stream| stream.recv_closed);
+        if recv_closed {
+            self.streams.remove(&stream_id);
+            self.windows.close(stream_id);
+        } else {
+            // Answered early; the rest of the request isn't needed
+            self.reset(stream_id, ErrorCode::NoError);
+        }
+    }
+}
+
+/// Build a request from a decoded header block
+fn build_request(fields: Vec<(String, String)>) -> Result<Request, String> {
+    let mut method = None;
+    let mut scheme = None;
+    let mut path = None;
+    let mut authority = None;
+    let mut headers = Vec::new();
+    let mut cookies = Vec::new();
+
+    for (name, value) in fields {
+        if let Some(pseudo) = name.strip_prefix(':') {
+            if !headers.is_empty() || !cookies.is_empty() {
+                return Err("pseudo-header after a regular header".into());
+            }
+            let slot = match pseudo {
+                "method" => &mut method,
+                "scheme" => &mut scheme,
+                "path" => &mut path,
+                "authority" => &mut authority,
+                _ => return Err(format!("unknown pseudo-header {}", name)),
+            };
+            if slot.replace(value).is_some() {
+                return Err(format!("duplicate {}", name));
+            }
+            continue;
+        }
+
+        if name.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Err(format!("uppercase header name {}", name));
+        }
+        if CONNECTION_HEADERS.contains(&name.as_str()) || (name == "te" && value != "trailers") {
+            return Err(format!("connection-specific header {}", name));
+        }
+        // Cookies may be split across fields to compress better
+        if name == "cookie" {
+            cookies.push(value);
+        } else {
+            headers.push((name, value));
+        }
+    }
+
+    let method: Method = method
+        .ok_or("missing :method")?
+        .parse()
+        .map_err(|e: NetError| e.to_string())?;
+    if method == Method::CONNECT {
+        return Err("CONNECT is not supported".into());
+    }
+    scheme.ok_or("missing :scheme")?;
+    let path = path.filter(|p| !p.is_empty()).ok_or("missing :path")?;
+
+    let mut request = Request::new(method, path);
+    request.set_version(Version::Http2);
+    let request_headers = request.headers_mut();
+    if let Some(authority) = authority {
+        if !headers.iter().any(|(name, _)| name == "host") {
+            request_headers.append("host", authority);
+        }
+    }
+    for (name, value) in headers {
+        request_headers.append(name, value);
+    }
+    if !cookies.is_empty() {
+        request_headers.append("cookie", cookies.join("; "));
+    }
+    Ok(request)
+}
+
+/// Send a response on a stream, waiting on flow control for the body
+async fn send_response(
+    stream_id: u32,
+    response: Response,
+    head: bool,
+    outbound: &mpsc::UnboundedSender<Outbound>,
+    windows: &SendWindows,
+) {
+    let (status, headers, body, stream) = response.into_parts();
+    let expected = headers.content_length();
+
+    let mut fields = vec![(":status".to_string(), status.code().to_string())];
+    fields.extend(
+        headers
+            .iter()
+            .filter(|(name, _)| !CONNECTION_HEADERS.contains(name))
+            .map(|(name, value)| (name.to_string(), value.to_string())),
+    );
+    if stream.is_none() && expected.is_none() {
+        fields.push(("content-length".to_string(), body.len().to_string()));
+    }
+
+    let end_stream = head || (stream.is_none() && body.is_empty());
+    let sent = outbound.send(Outbound::Headers {
+        stream_id,
+        fields,
+        end_stream,
+    });
+    if sent.is_err() || end_stream {
+        return;
+    }
+
+    let mut changed = windows.changed.subscribe();
+    let Some(mut reader) = stream else {
+        send_data(stream_id, &body, true, outbound, windows, &mut changed).await;
+        return;
+    };
+
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    let mut written = 0usize;
+    loop {
+        let limit = match expected {
+            Some(len) => STREAM_CHUNK_SIZE.min(len - written),
+            None => STREAM_CHUNK_SIZE,
+        };
+        let n = if limit == 0 {
+            0
+        } else {
+            match reader.read(&mut buf[..limit]).await {
+                Ok(n) => n,
+                Err(e) => {
+                    tracing::debug!("Streamed body failed on stream {}: {}", stream_id, e);
+                    0
+                }
+            }
+        };
+        if n == 0 {
+            if expected.is_some_and(|len| written < len) {
+                let _ = outbound.send(Outbound::Reset {
+                    stream_id,
+                    code: ErrorCode::InternalError,
+                });
+            } else {
+                send_data(stream_id, &[], true, outbound, windows, &mut changed).await;
+            }
+            return;
+        }
+        if !send_data(stream_id, &buf[..n], false, outbound, windows, &mut changed).await {
+            return;
+        }
+        written += n;
+    }
+}
+
+/// Queue DATA frames as the windows allow; false if the stream is gone
+async fn send_data(
+    stream_id: u32,
+    data: &[u8],
+    end_stream: bool,
+    outbound: &mpsc::UnboundedSender<Outbound>,
+    windows: &SendWindows,
+    changed: &mut watch::Receiver<()>,
+) -> bool {
+    if data.is_empty() {
+        return outbound
+            .send(Outbound::Data {
+                stream_id,
+                data: Vec::new(),
+                end_stream,
+            })
+            .is_ok();
+    }
+
+    let mut sent = 0;
+    while sent < data.len() {
+        let Some(n) = windows.reserve(stream_id, data.len() - sent, changed).await else {
+            return false;
+        };
+        let frame = Outbound::Data {
+            stream_id,
+            data: data[sent..sent + n].to_vec(),
+            end_stream: end_stream && sent + n == data.len(),
+        };
+        if outbound.send(frame).is_err() {
+            return false;
+        }
+        sent += n;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ServerConfig;
+    use std::time::Duration;
+    use tokio::io::DuplexStream;
+
+    fn router() -> Arc<Router> {
+        let mut router = Router::new();
+        router.get("/ping", |_req| async { Ok(Response::ok().text("pong")) });
+        router.post("/echo", |req| async move {
+            Ok(Response::ok().body_bytes(req.body().to_vec()))
+        });
+        router.get("/slow", |_req| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(Response::ok().text("slow"))
+        });
+        router.get("/big", |_req| async {
+            Ok(Response::ok().stream(std::io::Cursor::new(vec![b'x'; 100])))
+        });
+        router.get("/whoami", |req| async move {
+            let host = req.headers().get("host").unwrap_or("").to_string();
+            let cookie = req.headers().get("cookie").unwrap_or("").to_string();
+            Ok(Response::ok().text(format!("{} {} {}", req.version(), host, cookie)))
+        });
+        Arc::new(router)
+    }
+
+    struct Client {
+        io: DuplexStream,
+        decoder: Decoder,
+    }
+
+    impl Client {
+        async fn connect(settings: &[(u16, u32)]) -> (Self, JoinHandle<NetResult<()>>) {
+            Self::connect_draining(settings, Drain::never()).await
+        }
+
+        async fn connect_draining(
+            settings: &[(u16, u32)],
+            drain: Drain,
+        ) -> (Self, JoinHandle<NetResult<()>>) {
+            let (client, server) = tokio::io::duplex(256 * 1024);
+            let (reader, writer) = tokio::io::split(server);
+            let limits = ConnectionLimits::from_config(&ServerConfig::default());
+            let handle = tokio::spawn(serve(reader, writer, None, router(), limits, drain));
+
+            let mut client = Self {
+                io: client,
+                decoder: Decoder::new(hpack::DEFAULT_TABLE_SIZE),
+            };
+            client.io.write_all(PREFACE).await.unwrap();
+            let mut payload = Vec::new();
+            for (id, value) in settings {
+                payload.extend_from_slice(&id.to_be_bytes());
+                payload.extend_from_slice(&value.to_be_bytes());
+            }
+            client.send(SETTINGS, 0, 0, &payload).await;
+            (client, handle)
+        }
+
+        async fn send(&mut self, kind: u8, flags: u8, stream_id: u32, payload: &[u8]) {
+            let mut out = Vec::new();
+            encode_frame(kind, flags, stream_id, payload, &mut out);
+            self.io.write_all(&out).await.unwrap();
+        }
+
+        async fn request(&mut self, stream_id: u32, method: &str, path: &str, end_stream: bool) {
+            let block = hpack::encode([
+                (":method", method),
+                (":scheme", "https"),
+                (":path", path),
+                (":authority", "api.vaya.my"),
+                ("cookie", "a=1"),
+                ("cookie", "b=2"),
+            ]);
+            let flags = FLAG_END_HEADERS | if end_stream { FLAG_END_STREAM } else { 0 };
+            self.send(HEADERS, flags, stream_id, &block).await;
+        }
+
+        async fn frame(&mut self) -> Frame {
+            let mut header = [0u8; FRAME_HEADER_LEN];
+            self.io.read_exact(&mut header).await.unwrap();
+            let len = read_u32(&[0, header[0], header[1], header[2]]) as usize;
+            let mut payload = vec![0u8; len];
+            self.io.read_exact(&mut payload).await.unwrap();
+            Frame {
+                kind: header[3],
+                flags: header[4],
+                stream_id: read_u32(&header[5..]),
+                payload,
+            }
+        }
+
+        /// Read frames until `count` streams have ended, collecting each
+        /// stream's status and body in the order they finished
+        async fn responses(&mut self, count: usize) -> Vec<(u32, String, String)> {
+            let mut statuses = HashMap::new();
+            let mut bodies: HashMap<u32, Vec<u8>> = HashMap::new();
+            let mut done = Vec::new();
+            while done.len() < count {
+                let frame = self.frame().await;
+                let id = frame.stream_id;
+                match frame.kind {
+                    HEADERS => {
+                        let fields = self.decoder.decode(&frame.payload).unwrap();
+                        statuses.insert(id, fields[0].1.clone());
+                    }
+                    DATA => bodies.entry(id).or_default().extend(&frame.payload),
+                    _ => continue,
+                }
+                if frame.flags & FLAG_END_STREAM != 0 {
+                    let body = bodies.remove(&id).unwrap_or_default();
+                    done.push((id, statuses[&id].clone(), String::from_utf8(body).unwrap()));
+                }
+            }
+            done
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests() {
+        let (mut client, _handle) = Client::connect(&[]).await;
+
+        client.request(1, "GET", "/ping", true).await;
+        client.request(3, "POST", "/echo", false).await;
+        client.send(DATA, 0, 3, b"hello ").await;
+        client.send(DATA, FLAG_END_STREAM, 3, b"world").await;
+        client.request(5, "GET", "/whoami", true).await;
+        client.request(7, "DELETE", "/ping", true).await;
+
+        let mut responses = client.responses(4).await;
+        responses.sort();
+        assert_eq!(
+            responses,
+            [
+                (1, "200".into(), "pong".into()),
+                (3, "200".into(), "hello world".into()),
+                (5, "200".into(), "HTTP/2 api.vaya.my a=1; b=2".into()),
+                (7, "405".into(), "Method Not Allowed".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streams_are_multiplexed() {
+        let (mut client, _handle) = Client::connect(&[]).await;
+
+        // The slow handler doesn't hold up the request behind it
+        client.request(1, "GET", "/slow", true).await;
+        client.request(3, "GET", "/ping", true).await;
+        let responses = client.responses(2).await;
+        assert_eq!(responses[0], (3, "200".into(), "pong".into()));
+        assert_eq!(responses[1], (1, "200".into(), "slow".into()));
+    }
+
+    #[tokio::test]
+    async fn test_flow_control() {
+        let (mut client, _handle) = Client::connect(&[(SETTINGS_INITIAL_WINDOW_SIZE, 16)]).await;
+        client.request(1, "GET", "/big", true).await;
+
+        // Only the initial window is sent until the client makes room
+        let mut received = 0;
+        while received < 16 {
+            let frame = client.frame().await;
+            if frame.kind == DATA {
+                received += frame.payload.len();
+            }
+        }
+        assert_eq!(received, 16);
+        let more = tokio::time::timeout(Duration::from_millis(100), client.frame()).await;
+        assert!(more.is_err());
+
+        client
+            .send(WINDOW_UPDATE, 0, 1, &1000u32.to_be_bytes())
+            .await;
+        let mut end = false;
+        while !end {
+            let frame = client.frame().await;
+            assert_eq!(frame.kind, DATA);
+            received += frame.payload.len();
+            end = frame.flags & FLAG_END_STREAM != 0;
+        }
+        assert_eq!(received, 100);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_finishes_open_streams() {
+        let (drain_tx, drain_rx) = tokio::sync::watch::channel(false);
+        let (mut client, handle) = Client::connect_draining(&[], Drain(drain_rx)).await;
+        client.request(1, "GET", "/slow", true).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drain_tx.send(true).unwrap();
+
+        // GOAWAY names stream 1 as the last one served
+        let goaway = loop {
+            let frame = client.frame().await;
+            if frame.kind == GOAWAY {
+                break frame;
+            }
+        };
+        assert_eq!(read_u32(&goaway.payload[..4]), 1);
+        assert_eq!(read_u32(&goaway.payload[4..8]), ErrorCode::NoError as u32);
+
+        // Streams opened afterwards are ignored; the open one completes
+        client.request(3, "GET", "/ping", true).await;
+        assert_eq!(
+            client.responses(1).await,
+            [(1, "200".into(), "slow".into())]
+        );
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ping_and_protocol_errors() {
+        let (mut client, handle) = Client::connect(&[]).await;
+
+        client.send(PING, 0, 0, b"vaya1234").await;
+        let pong = loop {
+            let frame = client.frame().await;
+            if frame.kind == PING {
+                break frame;
+            }
+        };
+        assert_eq!(pong.flags, FLAG_ACK);
+        assert_eq!(pong.payload, b"vaya1234");
+
+        // DATA on the connection stream ends the connection
+        client.send(DATA, 0, 0, b"oops").await;
+        let goaway = loop {
+            let frame = client.frame().await;
+            if frame.kind == GOAWAY {
+                break frame;
+            }
+        };
+        assert_eq!(
+            read_u32(&goaway.payload[4..8]),
+            ErrorCode::ProtocolError as u32
+        );
+        assert!(matches!(handle.await.unwrap(), Err(NetError::Protocol(_))));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_request_resets_stream() {
+        let (mut client, _handle) = Client::connect(&[]).await;
+
+        let block = hpack::encode([(":method", "GET"), (":path", "/ping")]);
+        client
+            .send(HEADERS, FLAG_END_HEADERS | FLAG_END_STREAM, 1, &block)
+            .await;
+        let reset = loop {
+            let frame = client.frame().await;
+            if frame.kind == RST_STREAM {
+                break frame;
+            }
+        };
+        assert_eq!(reset.stream_id, 1);
+        assert_eq!(read_u32(&reset.payload), ErrorCode::ProtocolError as u32);
+
+        // The connection carries on
+        client.request(3, "GET", "/ping", true).await;
+        assert_eq!(
+            client.responses(1).await[0],
+            (3, "200".into(), "pong".into())
+        );
+    }
+}
@@ -1,22 +1,32 @@
 //! vaya-net: Zero-dependency HTTP server with TLS and WebSocket support
 //!
-//! This crate provides a custom HTTP/1.1 server built directly on tokio and rustls,
-//! without relying on external HTTP frameworks like hyper or axum.
+//! This crate provides a custom HTTP/1.1 and HTTP/2 server built directly on tokio
+//! and rustls, without relying on external HTTP frameworks like hyper or axum.
 
+pub mod acme;
 pub mod error;
+mod hpack;
 pub mod http;
+pub mod http2;
+pub mod live;
 pub mod request;
 pub mod response;
 pub mod router;
 pub mod server;
+pub mod tail;
+pub mod tls;
 pub mod websocket;
 
+pub use acme::{AcmeConfig, AcmeManager, CertResolver, ChallengeType};
 pub use error::{NetError, NetResult};
 pub use http::{Method, StatusCode, Version};
+pub use live::{LiveConfig, LiveHub, LiveRejection, LiveSession};
 pub use request::Request;
-pub use response::Response;
+pub use response::{BodyStream, Response, STREAM_CHUNK_SIZE};
 pub use router::Router;
 pub use server::{Server, ServerConfig};
+pub use tail::{LiveTail, TailConfig, TailRejection, TailSession};
+pub use tls::{ClientStream, TlsAcceptor, TlsConnector, TlsStream};
 pub use websocket::WebSocket;
 
 /// HTTP protocol version
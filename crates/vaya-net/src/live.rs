@@ -0,0 +1,695 @@
+//! Live updates over WebSocket
+//!
+//! A [`LiveHub`] fans server-side events out to WebSocket clients by topic,
+//! so the UI updates live instead of polling. Topics are `kind:key`
+//! strings, e.g. `pool:{id}` for pool membership and status and
+//! `route:KUL-NRT` for price drops and late search results on a route.
+//!
+//! Clients pick their initial topics with the `topics` query parameter
+//! (comma-separated) and change them with `subscribe <topic>` and
+//! `unsubscribe <topic>` text frames. Events arrive as JSON text frames:
+//! `{"type":"event","topic":..,"event":..,"data":{..}}`.
+//!
+//! [`LiveHub::attach`] forwards pool, price-drop and late-result events
+//! from the [`EventBus`] to their topics. Each client has a bounded queue;
+//! when a client falls behind, new events for it are dropped and it is sent
+//! a `{"type":"lagged","dropped":n}` frame so it can refetch.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+use vaya_common::bus::{topics, EventBus, SubscriptionId};
+use vaya_common::events::EventValue;
+use vaya_common::metrics;
+
+use crate::tail::escape_json;
+use crate::websocket::Message;
+use crate::{NetError, NetResult, Request, Response, StatusCode, WebSocket};
+
+/// Metric counting events queued for clients, by topic kind
+pub const LIVE_DELIVERED_METRIC: &str = "vaya_net_live_delivered_total";
+/// Metric counting events dropped for lagging clients, by topic kind
+pub const LIVE_DROPPED_METRIC: &str = "vaya_net_live_dropped_total";
+
+/// Longest accepted topic
+const MAX_TOPIC_LEN: usize = 128;
+
+/// Live update settings
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    /// Maximum concurrent clients
+    pub max_clients: usize,
+    /// Maximum topics one client may subscribe to
+    pub max_topics_per_client: usize,
+    /// Events queued per client before new ones are dropped
+    pub queue_capacity: usize,
+    /// Longest a single send may take before the client is dropped
+    pub send_timeout: Duration,
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        Self {
+            max_clients: 10_000,
+            max_topics_per_client: 32,
+            queue_capacity: 256,
+            send_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Why a live update request or subscription was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveRejection {
+    /// Not a WebSocket upgrade request
+    NotUpgrade,
+    /// Topic is not of the form `kind:key`
+    InvalidTopic,
+    /// Client topic cap reached
+    TooManyTopics,
+    /// Client cap reached
+    TooManyClients,
+}
+
+impl LiveRejection {
+    /// Error message
+    pub fn message(&self) -> &'static str {
+        match self {
+            LiveRejection::NotUpgrade => "WebSocket upgrade required",
+            LiveRejection::InvalidTopic => "topics must look like pool:{id} or route:KUL-NRT",
+            LiveRejection::TooManyTopics => "Too many topics",
+            LiveRejection::TooManyClients => "Too many live update clients",
+        }
+    }
+
+    /// HTTP response for the rejected upgrade
+    pub fn to_response(&self) -> Response {
+        let status = match self {
+            LiveRejection::TooManyClients => StatusCode::ServiceUnavailable,
+            _ => StatusCode::BadRequest,
+        };
+        Response::new(status).json(format!(r#"{{"error":"{}"}}"#, self.message()))
+    }
+}
+
+/// Topic for a pool's membership and status
+pub fn pool_topic(pool_id: &str) -> String {
+    format!("pool:{}", pool_id)
+}
+
+/// Topic for price drops on a route
+pub fn route_topic(origin: &str, destination: &str) -> String {
+    format!(
+        "route:{}-{}",
+        origin.to_ascii_uppercase(),
+        destination.to_ascii_uppercase()
+    )
+}
+
+/// Check that a topic is `kind:key` with a lowercase kind and a simple key
+pub fn is_valid_topic(topic: &str) -> bool {
+    let Some((kind, key)) = topic.split_once(':') else {
+        return false;
+    };
+    topic.len() <= MAX_TOPIC_LEN
+        && !kind.is_empty()
+        && kind.bytes().all(|b| b.is_ascii_lowercase() || b == b'_')
+        && !key.is_empty()
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+/// A client's queue, shared by every topic it subscribes to
+#[derive(Clone)]
+struct ClientQueue {
+    tx: mpsc::Sender<Arc<str>>,
+    dropped: Arc<AtomicU64>,
+}
+
+#[derive(Default)]
+struct HubState {
+    topics: RwLock<HashMap<String, HashMap<u64, ClientQueue>>>,
+    clients: AtomicUsize,
+    next_id: AtomicU64,
+}
+
+impl HubState {
+    fn add(&self, topic: &str, id: u64, queue: &ClientQueue) {
+        self.topics
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(topic.to_string())
+            .or_default()
+            .insert(id, queue.clone());
+    }
+
+    fn remove(&self, topic: &str, id: u64) {
+        let mut topics = self.topics.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(clients) = topics.get_mut(topic) {
+            clients.remove(&id);
+            if clients.is_empty() {
+                topics.remove(topic);
+            }
+        }
+    }
+}
+
+/// Routes published events to subscribed WebSocket clients
+#[derive(Clone)]
+pub struct LiveHub {
+    config: LiveConfig,
+    state: Arc<HubState>,
+}
+
+impl LiveHub {
+    /// Create a hub with no clients
+    pub fn new() -> Self {
+        Self {
+            config: LiveConfig::default(),
+            state: Arc::new(HubState::default()),
+        }
+    }
+
+    /// Set the config
+    pub fn with_config(mut self, config: LiveConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Number of connected clients
+    pub fn active_clients(&self) -> usize {
+        self.state.clients.load(Ordering::SeqCst)
+    }
+
+    /// Number of clients subscribed to a topic
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.state
+            .topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(topic)
+            .map_or(0, HashMap::len)
+    }
+
+    /// Send an event to every client subscribed to `topic`
+    ///
+    /// `data` must be a JSON value. Returns the number of clients it was
+    /// queued for; lagging clients are skipped.
+    pub fn publish(&self, topic: &str, event: &str, data: &str) -> usize {
+        let clients: Vec<ClientQueue> = match self
+            .state
+            .topics
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(topic)
+        {
+            Some(clients) => clients.values().cloned().collect(),
+            None => return 0,
+        };
+
+        let frame: Arc<str> = format!(
+            r#"{{"type":"event","topic":"{}","event":"{}","data":{}}}"#,
+            escape_json(topic),
+            escape_json(event),
+            data
+        )
+        .into();
+        let kind = topic.split_once(':').map_or(topic, |(kind, _)| kind);
+
+        let mut delivered = 0;
+        for client in clients {
+            match client.tx.try_send(frame.clone()) {
+                Ok(()) => delivered += 1,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    client.dropped.fetch_add(1, Ordering::Relaxed);
+                    metrics::global()
+                        .counter(LIVE_DROPPED_METRIC, &[("kind", kind)])
+                        .inc();
+                }
+                // Session is shutting down and will unsubscribe itself
+                Err(mpsc::error::TrySendError::Closed(_)) => {}
+            }
+        }
+        if delivered > 0 {
+            metrics::global()
+                .counter(LIVE_DELIVERED_METRIC, &[("kind", kind)])
+                .add(delivered as u64);
+        }
+        delivered
+    }
+
+    /// Forward pool, price-drop and late-result events from the bus to their topics
+    ///
+    /// User and alert IDs are left out: anyone may watch a pool or route.
+    /// Late search results carry the search ID so a client can match them
+    /// to the search it is showing.
+    pub fn attach(&self, bus: &EventBus) -> Vec<SubscriptionId> {
+        let hub = self.clone();
+        let joined = bus.subscribe(&topics::POOL_MEMBER_JOINED, "live_hub", move |e| {
+            hub.publish(
+                &pool_topic(&e.pool_id),
+                "member_joined",
+                &data_json(&[
+                    ("pool_id", e.pool_id.as_str().into()),
+                    ("spots", e.spots.into()),
+                    ("member_count", e.member_count.into()),
+                ]),
+            );
+        });
+
+        let hub = self.clone();
+        let status = bus.subscribe(&topics::POOL_STATUS_CHANGED, "live_hub", move |e| {
+            hub.publish(
+                &pool_topic(&e.pool_id),
+                "status_changed",
+                &data_json(&[
+                    ("pool_id", e.pool_id.as_str().into()),
+                    ("from", e.from.as_str().into()),
+                    ("to", e.to.as_str().into()),
+                    ("member_count", e.member_count.into()),
+                ]),
+            );
+        });
+
+        let hub = self.clone();
+        let price = bus.subscribe(&topics::ALERT_TRIGGERED, "live_hub", move |e| {
+            if !e.trigger.starts_with("PRICE_DROPS") {
+                return;
+            }
+            hub.publish(
+                &route_topic(&e.origin, &e.destination),
+                "price_drop",
+                &data_json(&[
+                    ("origin", e.origin.as_str().into()),
+                    ("destination", e.destination.as_str().into()),
+                    ("price_minor", e.price_minor.into()),
+                    ("currency", e.currency.as_str().into()),
+                ]),
+            );
+        });
+
+        let hub = self.clone();
+        let late = bus.subscribe(&topics::SEARCH_LATE_RESULTS, "live_hub", move |e| {
+            hub.publish(
+                &route_topic(&e.origin, &e.destination),
+                "late_results",
+                &data_json(&[
+                    ("search_id", e.search_id.as_str().into()),
+                    ("provider", e.provider.as_str().into()),
+                    ("result_count", e.result_count.into()),
+                    ("cheapest_minor", e.cheapest_minor.into()),
+                    ("currency", e.currency.clone().into()),
+                ]),
+            );
+        });
+
+        vec![joined, status, price, late]
+    }
+
+    /// Validate an upgrade request and register the client
+    ///
+    /// On success, send the returned handshake response and pass the
+    /// upgraded stream to [`LiveSession::run`].
+    pub fn open(&self, request: &Request) -> Result<(Response, LiveSession), LiveRejection> {
+        if !request.is_websocket_upgrade() {
+            return Err(LiveRejection::NotUpgrade);
+        }
+        let initial: Vec<&str> = request
+            .query("topics")
+            .map(|t| {
+                t.split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(topic) = initial.iter().find(|t| !is_valid_topic(t)) {
+            debug!("Live update request for invalid topic {:?}", topic);
+            return Err(LiveRejection::InvalidTopic);
+        }
+        if initial.len() > self.config.max_topics_per_client {
+            return Err(LiveRejection::TooManyTopics);
+        }
+
+        let reserved = self
+            .state
+            .clients
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.config.max_clients).then_some(n + 1)
+            })
+            .is_ok();
+        if !reserved {
+            return Err(LiveRejection::TooManyClients);
+        }
+
+        let (tx, rx) = mpsc::channel(self.config.queue_capacity.max(1));
+        let mut session = LiveSession {
+            config: self.config.clone(),
+            state: self.state.clone(),
+            id: self.state.next_id.fetch_add(1, Ordering::Relaxed),
+            queue: ClientQueue {
+                tx,
+                dropped: Arc::new(AtomicU64::new(0)),
+            },
+            rx,
+            topics: HashSet::new(),
+        };
+        for topic in initial {
+            session.subscribe(topic)?;
+        }
+
+        // The client slot is released when `session` drops
+        let response = WebSocket::<tokio::net::TcpStream>::handshake_response(request)
+            .map_err(|_| LiveRejection::NotUpgrade)?;
+        Ok((response, session))
+    }
+}
+
+impl Default for LiveHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A connected live update client
+pub struct LiveSession {
+    config: LiveConfig,
+    state: Arc<HubState>,
+    id: u64,
+    queue: ClientQueue,
+    rx: mpsc::Receiver<Arc<str>>,
+    topics: HashSet<String>,
+}
+
+impl LiveSession {
+    /// Topics this client is subscribed to
+    pub fn topics(&self) -> impl Iterator<Item = &str> {
+        self.topics.iter().map(String::as_str)
+    }
+
+    /// Subscribe to a topic
+    pub fn subscribe(&mut self, topic: &str) -> Result<(), LiveRejection> {
+        if !is_valid_topic(topic) {
+            return Err(LiveRejection::InvalidTopic);
+        }
+        if self.topics.contains(topic) {
+            return Ok(());
+        }
+        if self.topics.len() >= self.config.max_topics_per_client {
+            return Err(LiveRejection::TooManyTopics);
+        }
+        self.state.add(topic, self.id, &self.queue);
+        self.topics.insert(topic.to_string());
+        Ok(())
+    }
+
+    /// Unsubscribe from a topic; returns whether it was subscribed
+    pub fn unsubscribe(&mut self, topic: &str) -> bool {
+        let removed = self.topics.remove(topic);
+        if removed {
+            self.state.remove(topic, self.id);
+        }
+        removed
+    }
+
+    /// Apply a `subscribe <topic>` or `unsubscribe <topic>` command
+    ///
+    /// Returns the frame to send back.
+    pub fn handle_command(&mut self, command: &str) -> String {
+        let (action, topic) = command
+            .trim()
+            .split_once(char::is_whitespace)
+            .map_or((command.trim(), ""), |(a, t)| (a, t.trim()));
+        let result = match action {
+            "subscribe" => self.subscribe(topic).map(|_| "subscribed"),
+            "unsubscribe" => {
+                self.unsubscribe(topic);
+                Ok("unsubscribed")
+            }
+            _ => return error_frame("expected subscribe <topic> or unsubscribe <topic>"),
+        };
+        match result {
+            Ok(kind) => format!(r#"{{"type":"{}","topic":"{}"}}"#, kind, escape_json(topic)),
+            Err(rejection) => error_frame(rejection.message()),
+        }
+    }
+
+    /// Push events and answer commands until the client disconnects
+    pub async fn run<S>(mut self, ws: WebSocket<S>) -> NetResult<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (mut reader, mut writer) = ws.split();
+
+        // Frame reads aren't cancel-safe, so one long-lived future reads
+        // client messages into a channel
+        let (incoming_tx, mut incoming) = mpsc::channel(8);
+        let read_loop = async move {
+            while let Ok(message) = reader.read().await {
+                if incoming_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        };
+        tokio::pin!(read_loop);
+
+        info!("Live update session {} opened", self.id);
+        loop {
+            let frames = tokio::select! {
+                _ = &mut read_loop => None,
+                message = incoming.recv() => match message {
+                    Some(Message::Text(command)) => Some(vec![self.handle_command(&command)]),
+                    Some(Message::Ping(data)) => {
+                        writer.pong(data).await?;
+                        continue;
+                    }
+                    Some(Message::Close(_)) => {
+                        let _ = writer.close(Some(1000), None).await;
+                        None
+                    }
+                    Some(_) => continue,
+                    None => None,
+                },
+                event = self.rx.recv() => event.map(|event| {
+                    let dropped = self.queue.dropped.swap(0, Ordering::Relaxed);
+                    let mut frames = Vec::with_capacity(2);
+                    if dropped > 0 {
+                        frames.push(format!(r#"{{"type":"lagged","dropped":{}}}"#, dropped));
+                    }
+                    frames.push(event.to_string());
+                    frames
+                }),
+            };
+            let Some(frames) = frames else {
+                debug!("Live update session {} closed", self.id);
+                return Ok(());
+            };
+
+            for frame in frames {
+                match tokio::time::timeout(self.config.send_timeout, writer.send_text(frame)).await
+                {
+                    Ok(result) => result?,
+                    Err(_) => {
+                        info!("Live update client too slow, closing session");
+                        let _ = writer.close(Some(1008), Some("client too slow")).await;
+                        return Err(NetError::Timeout);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for LiveSession {
+    fn drop(&mut self) {
+        for topic in &self.topics {
+            self.state.remove(topic, self.id);
+        }
+        self.state.clients.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn error_frame(message: &str) -> String {
+    format!(r#"{{"type":"error","message":"{}"}}"#, escape_json(message))
+}
+
+fn data_json(fields: &[(&str, EventValue)]) -> String {
+    let mut out = String::from("{");
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, r#""{}":"#, escape_json(name));
+        match value {
+            EventValue::String(s) => {
+                let _ = write!(out, r#""{}""#, escape_json(s));
+            }
+            EventValue::Int(n) => {
+                let _ = write!(out, "{}", n);
+            }
+            EventValue::Float(f) if f.is_finite() => {
+                let _ = write!(out, "{}", f);
+            }
+            EventValue::Bool(b) => {
+                let _ = write!(out, "{}", b);
+            }
+            EventValue::Float(_) | EventValue::Null => out.push_str("null"),
+        }
+    }
+    out.push('}');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_common::events::{AlertTriggered, PoolMemberJoined, SearchLateResults};
+
+    fn upgrade_request(query: &str) -> Request {
+        let raw = format!(
+            "GET /live{} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            query
+        );
+        Request::parse(raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_subscriptions_and_lag() {
+        let hub = LiveHub::new().with_config(LiveConfig {
+            max_clients: 1,
+            max_topics_per_client: 2,
+            queue_capacity: 2,
+            ..Default::default()
+        });
+        assert_eq!(
+            hub.open(&upgrade_request("?topics=pool:p1,bad")).err(),
+            Some(LiveRejection::InvalidTopic)
+        );
+        assert_eq!(hub.active_clients(), 0);
+
+        let (response, mut session) = hub.open(&upgrade_request("?topics=pool:p1")).unwrap();
+        assert_eq!(response.status(), StatusCode::SwitchingProtocols);
+        assert_eq!(
+            hub.open(&upgrade_request("")).err(),
+            Some(LiveRejection::TooManyClients)
+        );
+
+        assert_eq!(
+            session.handle_command("subscribe route:KUL-NRT"),
+            r#"{"type":"subscribed","topic":"route:KUL-NRT"}"#
+        );
+        assert!(session
+            .handle_command("subscribe route:KUL-HND")
+            .contains("Too many topics"));
+        assert!(session
+            .handle_command("listen")
+            .starts_with(r#"{"type":"error""#));
+
+        // Events reach subscribers only; a full queue drops and counts them
+        assert_eq!(hub.publish("pool:p2", "member_joined", "{}"), 0);
+        assert_eq!(hub.publish("pool:p1", "member_joined", r#"{"n":1}"#), 1);
+        assert_eq!(hub.publish("route:KUL-NRT", "price_drop", "{}"), 1);
+        assert_eq!(hub.publish("pool:p1", "member_joined", "{}"), 0);
+        assert_eq!(session.queue.dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            &*session.rx.try_recv().unwrap(),
+            r#"{"type":"event","topic":"pool:p1","event":"member_joined","data":{"n":1}}"#
+        );
+
+        session.handle_command("unsubscribe pool:p1");
+        assert_eq!(hub.subscriber_count("pool:p1"), 0);
+        drop(session);
+        assert_eq!(hub.subscriber_count("route:KUL-NRT"), 0);
+        assert_eq!(hub.active_clients(), 0);
+    }
+
+    #[test]
+    fn test_attach_forwards_bus_events() {
+        let bus = EventBus::new();
+        let hub = LiveHub::new();
+        hub.attach(&bus);
+        let (_, mut session) = hub
+            .open(&upgrade_request("?topics=pool:p1,route:KUL-NRT"))
+            .unwrap();
+
+        bus.publish(
+            &topics::POOL_MEMBER_JOINED,
+            PoolMemberJoined {
+                pool_id: "p1".into(),
+                user_id: "user-9".into(),
+                spots: 2,
+                member_count: 5,
+            },
+        );
+        let alert = |trigger: &str| AlertTriggered {
+            alert_id: "alert-1".into(),
+            user_id: "user-9".into(),
+            origin: "kul".into(),
+            destination: "nrt".into(),
+            trigger: trigger.into(),
+            price_minor: 89_900,
+            savings_minor: Some(10_000),
+            currency: "MYR".into(),
+        };
+        bus.publish(&topics::ALERT_TRIGGERED, alert("PRICE_RISES_ABOVE"));
+        bus.publish(&topics::ALERT_TRIGGERED, alert("PRICE_DROPS_BELOW"));
+        bus.publish(
+            &topics::SEARCH_LATE_RESULTS,
+            SearchLateResults {
+                search_id: "search-1".into(),
+                origin: "KUL".into(),
+                destination: "NRT".into(),
+                provider: "sabre".into(),
+                result_count: 2,
+                cheapest_minor: Some(85_000),
+                currency: Some("MYR".into()),
+                latency_ms: 2_100,
+            },
+        );
+
+        let joined = session.rx.try_recv().unwrap();
+        assert!(joined.contains(
+            r#""event":"member_joined","data":{"pool_id":"p1","spots":2,"member_count":5}"#
+        ));
+        assert!(!joined.contains("user-9"));
+        let drop = session.rx.try_recv().unwrap();
+        assert!(drop.contains(r#""topic":"route:KUL-NRT","event":"price_drop""#));
+        assert!(drop.contains(r#""price_minor":89900"#));
+        let late = session.rx.try_recv().unwrap();
+        assert!(late.contains(r#""topic":"route:KUL-NRT","event":"late_results""#));
+        assert!(late.contains(r#""search_id":"search-1","provider":"sabre""#));
+        assert!(session.rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_session() {
+        let hub = LiveHub::new();
+        let (_, session) = hub.open(&upgrade_request("")).unwrap();
+        let (client, server) = tokio::io::duplex(4096);
+        let task = tokio::spawn(session.run(WebSocket::new(server)));
+        let mut client = WebSocket::new(client);
+
+        client.send_text("subscribe pool:p1").await.unwrap();
+        let Message::Text(reply) = client.read().await.unwrap() else {
+            panic!("expected text frame");
+        };
+        assert!(reply.contains("subscribed"));
+
+        assert_eq!(hub.publish("pool:p1", "status_changed", "{}"), 1);
+        let Message::Text(event) = client.read().await.unwrap() else {
+            panic!("expected text frame");
+        };
+        assert!(event.contains(r#""event":"status_changed""#));
+
+        client.close(Some(1000), None).await.unwrap();
+        task.await.unwrap().unwrap();
+        assert_eq!(hub.active_clients(), 0);
+    }
+}
@@ -1,6 +1,7 @@
 //! HTTP request parsing and representation
 
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::str::FromStr;
 
 use crate::http::{Headers, Method, Version};
@@ -23,6 +24,8 @@ pub struct Request {
     body: Vec<u8>,
     /// Path parameters (from router)
     params: HashMap<String, String>,
+    /// Address of the connection's other end (set by the server)
+    peer_addr: Option<SocketAddr>,
 }
 
 impl Request {
@@ -39,6 +42,7 @@ impl Request {
             headers: Headers::new(),
             body: Vec::new(),
             params: HashMap::new(),
+            peer_addr: None,
         }
     }
 
@@ -98,6 +102,7 @@ impl Request {
             headers,
             body,
             params: HashMap::new(),
+            peer_addr: None,
         })
     }
 
@@ -201,6 +206,23 @@ impl Request {
         self.version
     }
 
+    /// Set the HTTP version
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    /// Check if the client wants the connection kept open after this request
+    ///
+    /// HTTP/1.1 connections are persistent unless the client sends
+    /// `Connection: close`; HTTP/1.0 ones only with `Connection: keep-alive`.
+    pub fn is_keep_alive(&self) -> bool {
+        match self.version {
+            Version::Http11 => self.headers.is_keep_alive(),
+            Version::Http2 => true,
+            Version::Http10 => self.headers.has_connection_option("keep-alive"),
+        }
+    }
+
     /// Get the headers
     pub fn headers(&self) -> &Headers {
         &self.headers
@@ -226,6 +248,16 @@ impl Request {
         self.body = body;
     }
 
+    /// Address of the connection's other end: the client, or a proxy in front of it
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Set the address of the connection's other end
+    pub fn set_peer_addr(&mut self, addr: Option<SocketAddr>) {
+        self.peer_addr = addr;
+    }
+
     /// Get a query parameter
     pub fn query(&self, key: &str) -> Option<&str> {
         self.query.get(key).map(|s| s.as_str())
@@ -353,6 +385,18 @@ mod tests {
         assert_eq!(req.headers().get("host"), Some("localhost"));
     }
 
+    #[test]
+    fn test_keep_alive() {
+        let req = Request::parse(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        assert!(req.is_keep_alive());
+        let req = Request::parse(b"GET / HTTP/1.1\r\nConnection: Upgrade, close\r\n\r\n").unwrap();
+        assert!(!req.is_keep_alive());
+        let req = Request::parse(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+        assert!(!req.is_keep_alive());
+        let req = Request::parse(b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n").unwrap();
+        assert!(req.is_keep_alive());
+    }
+
     #[test]
     fn test_parse_request_with_query() {
         let raw = b"GET /search?q=hello&limit=10 HTTP/1.1\r\nHost: localhost\r\n\r\n";
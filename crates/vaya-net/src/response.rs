@@ -1,9 +1,25 @@
 //! HTTP response building and serialization
+//!
+//! Bodies are either buffered or streamed from an [`AsyncRead`]. A streamed
+//! body with a known length is sent as-is after a `Content-Length` header;
+//! otherwise it is sent with chunked transfer encoding (or, to HTTP/1.0
+//! clients, delimited by closing the connection).
+
+use std::fmt;
+use std::path::Path;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::http::{Headers, StatusCode, Version};
+use crate::{NetError, NetResult};
+
+/// Bytes read from a streamed body per chunk
+pub const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A response body read incrementally
+pub type BodyStream = Box<dyn AsyncRead + Send + Unpin>;
 
 /// An HTTP response
-#[derive(Debug, Clone)]
 pub struct Response {
     /// HTTP version
     version: Version,
@@ -13,6 +29,20 @@ pub struct Response {
     headers: Headers,
     /// Response body
     body: Vec<u8>,
+    /// Streamed body, sent instead of `body`
+    stream: Option<BodyStream>,
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("version", &self.version)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("body_len", &self.body.len())
+            .field("streaming", &self.stream.is_some())
+            .finish()
+    }
 }
 
 impl Response {
@@ -23,6 +53,7 @@ impl Response {
             status,
             headers: Headers::new(),
             body: Vec::new(),
+            stream: None,
         }
     }
 
@@ -66,6 +97,16 @@ impl Response {
         Self::new(StatusCode::InternalServerError)
     }
 
+    /// Get the HTTP version
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Set the HTTP version (the server answers in the request's version)
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
     /// Get the status code
     pub fn status(&self) -> StatusCode {
         self.status
@@ -86,11 +127,38 @@ impl Response {
         &mut self.headers
     }
 
-    /// Get the body
+    /// Get the body (empty for streamed responses)
     pub fn body(&self) -> &[u8] {
         &self.body
     }
 
+    /// Split into status, headers, buffered body and streamed body
+    pub(crate) fn into_parts(self) -> (StatusCode, Headers, Vec<u8>, Option<BodyStream>) {
+        (self.status, self.headers, self.body, self.stream)
+    }
+
+    /// Check if the body is streamed
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Check if the body will be sent with chunked transfer encoding
+    pub fn is_chunked(&self) -> bool {
+        self.is_streaming()
+            && self.version == Version::Http11
+            && self.headers.content_length().is_none()
+    }
+
+    /// Check if the end of the body is marked by closing the connection
+    ///
+    /// True for streamed bodies of unknown length sent to HTTP/1.0 clients,
+    /// which don't support chunked encoding.
+    pub fn is_close_delimited(&self) -> bool {
+        self.is_streaming()
+            && self.version == Version::Http10
+            && self.headers.content_length().is_none()
+    }
+
     /// Set a header
     pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
         self.headers.set(name, value);
@@ -100,9 +168,27 @@ impl Response {
     /// Set the body
     pub fn body_bytes(mut self, body: impl Into<Vec<u8>>) -> Self {
         self.body = body.into();
+        self.stream = None;
         self
     }
 
+    /// Stream the body from a reader instead of buffering it
+    ///
+    /// Set a `Content-Length` header if the length is known up front;
+    /// otherwise the body is sent chunked.
+    pub fn stream(mut self, reader: impl AsyncRead + Send + Unpin + 'static) -> Self {
+        self.body.clear();
+        self.stream = Some(Box::new(reader));
+        self
+    }
+
+    /// Stream a file as the body, with its length as `Content-Length`
+    pub async fn file(self, path: impl AsRef<Path>) -> NetResult<Self> {
+        let file = tokio::fs::File::open(path).await?;
+        let len = file.metadata().await?.len();
+        Ok(self.header("Content-Length", len.to_string()).stream(file))
+    }
+
     /// Set text body
     pub fn text(self, body: impl Into<String>) -> Self {
         self.header("Content-Type", "text/plain; charset=utf-8")
@@ -131,8 +217,8 @@ impl Response {
         Self::new(status).header("Location", location)
     }
 
-    /// Serialize the response to bytes
-    pub fn to_bytes(&self) -> Vec<u8> {
+    /// Serialize the status line and headers
+    fn head_bytes(&self) -> Vec<u8> {
         let mut result = Vec::new();
 
         // Status line
@@ -151,19 +237,86 @@ impl Response {
             result.extend_from_slice(format!("{}: {}\r\n", name, value).as_bytes());
         }
 
-        // Content-Length if not already set
-        if self.headers.get("content-length").is_none() {
+        // Framing, unless a Content-Length was set
+        if self.is_chunked() {
+            if !self.headers.contains("transfer-encoding") {
+                result.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+            }
+        } else if self.is_close_delimited() {
+            if !self.headers.contains("connection") {
+                result.extend_from_slice(b"Connection: close\r\n");
+            }
+        } else if self.headers.get("content-length").is_none() {
             result.extend_from_slice(format!("Content-Length: {}\r\n", self.body.len()).as_bytes());
         }
 
         // End of headers
         result.extend_from_slice(b"\r\n");
 
-        // Body
-        result.extend_from_slice(&self.body);
+        result
+    }
 
+    /// Serialize the response to bytes
+    ///
+    /// A streamed body can't be serialized up front; only the status line
+    /// and headers are returned for it. Use [`Response::write_to`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = self.head_bytes();
+        result.extend_from_slice(&self.body);
         result
     }
+
+    /// Write the response, streaming the body if it is streamed
+    pub async fn write_to<W>(mut self, writer: &mut W) -> NetResult<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        writer.write_all(&self.head_bytes()).await?;
+
+        let chunked = self.is_chunked();
+        let expected = self.headers.content_length();
+        match self.stream.take() {
+            None => writer.write_all(&self.body).await?,
+            Some(mut reader) => {
+                let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+                let mut written = 0usize;
+                loop {
+                    let limit = match expected {
+                        Some(len) => STREAM_CHUNK_SIZE.min(len - written),
+                        None => STREAM_CHUNK_SIZE,
+                    };
+                    if limit == 0 {
+                        break;
+                    }
+                    let n = reader.read(&mut buf[..limit]).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    if chunked {
+                        writer.write_all(format!("{:X}\r\n", n).as_bytes()).await?;
+                        writer.write_all(&buf[..n]).await?;
+                        writer.write_all(b"\r\n").await?;
+                    } else {
+                        writer.write_all(&buf[..n]).await?;
+                    }
+                    written += n;
+                }
+
+                if chunked {
+                    writer.write_all(b"0\r\n\r\n").await?;
+                }
+                if let Some(len) = expected.filter(|len| written < *len) {
+                    return Err(NetError::Protocol(format!(
+                        "Streamed body ended after {} of {} bytes",
+                        written, len
+                    )));
+                }
+            }
+        }
+
+        writer.flush().await?;
+        Ok(())
+    }
 }
 
 impl Default for Response {
@@ -228,6 +381,93 @@ mod tests {
         assert!(text.ends_with("{\"status\":\"ok\"}"));
     }
 
+    /// Decode a chunked body, checking the framing
+    fn dechunk(mut data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        loop {
+            let line_end = data.windows(2).position(|w| w == b"\r\n").unwrap();
+            let size =
+                usize::from_str_radix(std::str::from_utf8(&data[..line_end]).unwrap(), 16).unwrap();
+            data = &data[line_end + 2..];
+            if size == 0 {
+                assert_eq!(data, b"\r\n");
+                return body;
+            }
+            body.extend_from_slice(&data[..size]);
+            assert_eq!(&data[size..size + 2], b"\r\n");
+            data = &data[size + 2..];
+        }
+    }
+
+    fn split_head(bytes: &[u8]) -> (String, &[u8]) {
+        let end = bytes.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        (
+            String::from_utf8_lossy(&bytes[..end]).to_string(),
+            &bytes[end..],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_chunked_stream() {
+        let csv: Vec<u8> = (0..20_000)
+            .flat_map(|i| format!("BK{:06},MYR,450.00\n", i).into_bytes())
+            .collect();
+        assert!(csv.len() > STREAM_CHUNK_SIZE * 2);
+
+        let res = Response::ok()
+            .header("Content-Type", "text/csv")
+            .stream(std::io::Cursor::new(csv.clone()));
+        assert!(res.is_chunked());
+        let mut out = Vec::new();
+        res.write_to(&mut out).await.unwrap();
+
+        let (head, body) = split_head(&out);
+        assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!head.to_lowercase().contains("content-length"));
+        assert_eq!(dechunk(body), csv);
+
+        // An empty stream is just the terminating chunk
+        let mut out = Vec::new();
+        Response::ok()
+            .stream(std::io::Cursor::new(Vec::new()))
+            .write_to(&mut out)
+            .await
+            .unwrap();
+        assert_eq!(split_head(&out).1, b"0\r\n\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_stream_with_length() {
+        let data = b"sstable-backup-bytes".to_vec();
+        let mut out = Vec::new();
+        Response::ok()
+            .header("Content-Length", "8")
+            .stream(std::io::Cursor::new(data.clone()))
+            .write_to(&mut out)
+            .await
+            .unwrap();
+        let (head, body) = split_head(&out);
+        assert!(!head.contains("chunked"));
+        assert_eq!(body, &data[..8]);
+
+        // A stream shorter than its declared length is an error
+        let res = Response::ok()
+            .header("Content-Length", "100")
+            .stream(std::io::Cursor::new(data));
+        assert!(res.write_to(&mut Vec::new()).await.is_err());
+
+        // HTTP/1.0 clients get the raw body, ended by closing the connection
+        let mut res = Response::ok().stream(std::io::Cursor::new(b"legacy".to_vec()));
+        res.set_version(Version::Http10);
+        assert!(res.is_close_delimited());
+        let mut out = Vec::new();
+        res.write_to(&mut out).await.unwrap();
+        let (head, body) = split_head(&out);
+        assert!(head.starts_with("HTTP/1.0 200 OK\r\n"));
+        assert!(head.contains("Connection: close\r\n"));
+        assert_eq!(body, b"legacy");
+    }
+
     #[test]
     fn test_redirect() {
         let res = Response::redirect("/new-location", false);
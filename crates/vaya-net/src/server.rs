@@ -1,17 +1,50 @@
 //! HTTP server implementation
+//!
+//! Connections are persistent (HTTP/1.1 keep-alive): requests are read and
+//! answered in order on the same connection, so pipelined requests work,
+//! until the client asks to close, the connection has been idle for
+//! [`ServerConfig::keep_alive_timeout`], or it has served
+//! [`ServerConfig::max_requests_per_connection`] requests.
+//!
+//! HTTP/2 is offered through ALPN on TLS listeners, and spoken on plaintext
+//! connections that open with the HTTP/2 preface. The same timeouts and
+//! request limits apply to its connections; see [`crate::http2`].
+//!
+//! Certificates are picked per handshake by a [`CertResolver`], either
+//! loaded from [`ServerConfig::cert_path`] (reloaded with
+//! [`Server::reload_tls`]) or managed over ACME with
+//! [`ServerConfig::with_acme`]. Replacing a certificate never touches
+//! connections that are already open.
+//!
+//! [`Server::run_until`] shuts down gracefully: once its signal fires the
+//! listener closes, idle connections are closed, HTTP/1.1 responses in
+//! flight are sent with `Connection: close` and HTTP/2 connections get a
+//! GOAWAY and finish their open streams. Whatever is still running after
+//! [`ServerConfig::drain_timeout`] is aborted.
 
 use std::fs::File;
+use std::future::Future;
 use std::io::BufReader;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader as TokioBufReader};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader as TokioBufReader};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
 
-use self::tokio_rustls::TlsAcceptor;
+use crate::acme::{AcmeManager, CertResolver, ChallengeType, ACME_TLS_ALPN};
+use crate::http2;
+use crate::tls::TlsAcceptor;
+use crate::{NetError, NetResult, Request, Response, Router, StatusCode, Version, MAX_HEADER_SIZE};
 
-use crate::{NetError, NetResult, Request, Response, Router, StatusCode, MAX_HEADER_SIZE};
+/// ALPN protocol ID for HTTP/2
+const ALPN_H2: &[u8] = b"h2";
+
+/// ALPN protocol ID for HTTP/1.1
+const ALPN_HTTP11: &[u8] = b"http/1.1";
 
 /// Server configuration
 #[derive(Clone)]
@@ -28,6 +61,16 @@ pub struct ServerConfig {
     pub read_timeout: u64,
     /// Write timeout in seconds
     pub write_timeout: u64,
+    /// Seconds an idle keep-alive connection is held open for the next request
+    pub keep_alive_timeout: u64,
+    /// Requests served on one connection before closing it (0 for no limit)
+    pub max_requests_per_connection: usize,
+    /// Accept HTTP/2 connections
+    pub http2: bool,
+    /// Certificates obtained and renewed over ACME (overrides the paths)
+    pub acme: Option<Arc<AcmeManager>>,
+    /// Seconds open connections get to finish once shutdown begins
+    pub drain_timeout: u64,
 }
 
 impl ServerConfig {
@@ -40,6 +83,11 @@ impl ServerConfig {
             max_connections: 10000,
             read_timeout: 30,
             write_timeout: 30,
+            keep_alive_timeout: 5,
+            max_requests_per_connection: 1000,
+            http2: true,
+            acme: None,
+            drain_timeout: 30,
         }
     }
 
@@ -50,6 +98,12 @@ impl ServerConfig {
         self
     }
 
+    /// Enable TLS with certificates managed over ACME
+    pub fn with_acme(mut self, acme: Arc<AcmeManager>) -> Self {
+        self.acme = Some(acme);
+        self
+    }
+
     /// Set maximum connections
     pub fn max_connections(mut self, max: usize) -> Self {
         self.max_connections = max;
@@ -68,9 +122,33 @@ impl ServerConfig {
         self
     }
 
+    /// Set keep-alive idle timeout (0 closes after every request)
+    pub fn keep_alive_timeout(mut self, seconds: u64) -> Self {
+        self.keep_alive_timeout = seconds;
+        self
+    }
+
+    /// Set maximum requests per connection (0 for no limit)
+    pub fn max_requests_per_connection(mut self, max: usize) -> Self {
+        self.max_requests_per_connection = max;
+        self
+    }
+
+    /// Enable or disable HTTP/2
+    pub fn http2(mut self, enabled: bool) -> Self {
+        self.http2 = enabled;
+        self
+    }
+
+    /// Set how long shutdown waits for open connections (0 aborts them)
+    pub fn drain_timeout(mut self, seconds: u64) -> Self {
+        self.drain_timeout = seconds;
+        self
+    }
+
     /// Check if TLS is enabled
     pub fn is_tls(&self) -> bool {
-        self.cert_path.is_some() && self.key_path.is_some()
+        self.acme.is_some() || (self.cert_path.is_some() && self.key_path.is_some())
     }
 }
 
@@ -80,43 +158,132 @@ impl Default for ServerConfig {
     }
 }
 
+/// Per-connection limits taken from [`ServerConfig`]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ConnectionLimits {
+    pub(crate) read_timeout: Duration,
+    pub(crate) keep_alive_timeout: Duration,
+    pub(crate) max_requests: usize,
+    pub(crate) http2: bool,
+}
+
+impl ConnectionLimits {
+    pub(crate) fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            read_timeout: Duration::from_secs(config.read_timeout),
+            keep_alive_timeout: Duration::from_secs(config.keep_alive_timeout),
+            max_requests: config.max_requests_per_connection,
+            http2: config.http2,
+        }
+    }
+
+    /// Whether another request may follow the `served`th one
+    pub(crate) fn allows_another(&self, served: usize) -> bool {
+        !self.keep_alive_timeout.is_zero() && (self.max_requests == 0 || served < self.max_requests)
+    }
+}
+
+/// Tells connections that the server is shutting down
+#[derive(Clone)]
+pub(crate) struct Drain(pub(crate) watch::Receiver<bool>);
+
+impl Drain {
+    /// A signal that never fires
+    #[cfg(test)]
+    pub(crate) fn never() -> Self {
+        Self(watch::channel(false).1)
+    }
+
+    /// Whether shutdown has begun
+    pub(crate) fn is_draining(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolve once shutdown begins
+    pub(crate) async fn wait(&mut self) {
+        if self.0.wait_for(|draining| *draining).await.is_err() {
+            // The server is gone without draining; nothing will fire
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
 /// HTTP server
 pub struct Server {
     config: ServerConfig,
     router: Arc<Router>,
     tls_acceptor: Option<TlsAcceptor>,
+    cert_resolver: Option<Arc<CertResolver>>,
 }
 
 impl Server {
     /// Create a new server with the given config and router
     pub fn new(config: ServerConfig, router: Router) -> NetResult<Self> {
-        let tls_acceptor = if config.is_tls() {
-            Some(Self::create_tls_acceptor(&config)?)
-        } else {
-            None
+        let cert_resolver = match &config.acme {
+            Some(acme) => Some(acme.resolver()),
+            None if config.is_tls() => {
+                let resolver = Arc::new(CertResolver::new());
+                Self::load_cert_files(&config, &resolver)?;
+                Some(resolver)
+            }
+            None => None,
         };
+        let tls_acceptor = cert_resolver
+            .as_ref()
+            .map(|resolver| Self::create_tls_acceptor(&config, resolver.clone()));
 
         Ok(Self {
             config,
             router: Arc::new(router),
             tls_acceptor,
+            cert_resolver,
         })
     }
 
     /// Create TLS acceptor from config
-    fn create_tls_acceptor(config: &ServerConfig) -> NetResult<TlsAcceptor> {
-        let cert_path = config.cert_path.as_ref().unwrap();
-        let key_path = config.key_path.as_ref().unwrap();
+    fn create_tls_acceptor(config: &ServerConfig, resolver: Arc<CertResolver>) -> TlsAcceptor {
+        let mut server_config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(resolver);
+        server_config.alpn_protocols = if config.http2 {
+            vec![ALPN_H2.to_vec(), ALPN_HTTP11.to_vec()]
+        } else {
+            vec![ALPN_HTTP11.to_vec()]
+        };
+        let alpn_challenge = config
+            .acme
+            .as_ref()
+            .is_some_and(|acme| acme.config().challenge == ChallengeType::TlsAlpn01);
+        if alpn_challenge {
+            server_config.alpn_protocols.push(ACME_TLS_ALPN.to_vec());
+        }
 
+        TlsAcceptor::new(Arc::new(server_config))
+    }
+
+    /// Load the configured certificate and key files into `resolver`
+    fn load_cert_files(config: &ServerConfig, resolver: &CertResolver) -> NetResult<()> {
+        let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) else {
+            return Err(NetError::Tls("No certificate files configured".into()));
+        };
         let certs = Self::load_certs(cert_path)?;
         let key = Self::load_key(key_path)?;
-
-        let server_config = rustls::ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(certs, key)
-            .map_err(|e| NetError::Tls(e.to_string()))?;
-
-        Ok(TlsAcceptor::from(Arc::new(server_config)))
+        resolver.set_certificate(certs, &key)
+    }
+
+    /// Re-read the certificate and key files
+    ///
+    /// New handshakes use the new certificate; open connections are left
+    /// alone. Certificates managed over ACME are reloaded automatically.
+    pub fn reload_tls(&self) -> NetResult<()> {
+        match &self.cert_resolver {
+            Some(resolver) if self.config.acme.is_none() => {
+                Self::load_cert_files(&self.config, resolver)?;
+                tracing::info!("Reloaded TLS certificate from {:?}", self.config.cert_path);
+                Ok(())
+            }
+            _ => Err(NetError::Tls("No certificate files to reload".into())),
+        }
     }
 
     /// Load certificates from a PEM file
@@ -165,28 +332,81 @@ impl Server {
 
     /// Run the server
     pub async fn run(&self) -> NetResult<()> {
+        self.run_until(std::future::pending()).await
+    }
+
+    /// Run the server until `shutdown` resolves, then drain it
+    ///
+    /// New connections stop being accepted at once; open ones get
+    /// [`ServerConfig::drain_timeout`] to finish their requests before they
+    /// are aborted. Returns when every connection is gone.
+    pub async fn run_until(&self, shutdown: impl Future<Output = ()>) -> NetResult<()> {
         let listener = TcpListener::bind(self.config.addr).await?;
         tracing::info!("Server listening on {}", self.config.addr);
-
+        let limits = ConnectionLimits::from_config(&self.config);
+        let (drain_tx, drain_rx) = watch::channel(false);
+        let mut connections = JoinSet::new();
+
+        // Renewals need the listener up to answer challenges
+        let _renewals = self
+            .config
+            .acme
+            .clone()
+            .map(|acme| AbortOnDrop(tokio::spawn(acme.run())));
+
+        tokio::pin!(shutdown);
         loop {
-            match listener.accept().await {
-                Ok((stream, addr)) => {
-                    let router = self.router.clone();
-                    let tls_acceptor = self.tls_acceptor.clone();
-
-                    tokio::spawn(async move {
-                        if let Err(e) =
-                            Self::handle_connection(stream, addr, router, tls_acceptor).await
-                        {
-                            tracing::debug!("Connection error from {}: {}", addr, e);
-                        }
-                    });
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to accept connection: {}", e);
-                }
+            tokio::select! {
+                _ = &mut shutdown => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, addr)) => {
+                        let router = self.router.clone();
+                        let tls_acceptor = self.tls_acceptor.clone();
+                        let drain = Drain(drain_rx.clone());
+
+                        connections.spawn(async move {
+                            if let Err(e) = Self::handle_connection(
+                                stream,
+                                addr,
+                                router,
+                                tls_acceptor,
+                                limits,
+                                drain,
+                            )
+                            .await
+                            {
+                                tracing::debug!("Connection error from {}: {}", addr, e);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to accept connection: {}", e);
+                    }
+                },
+                // Reap finished connections so the set doesn't grow
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
             }
         }
+
+        drop(listener);
+        tracing::info!("Server draining {} connections", connections.len());
+        let _ = drain_tx.send(true);
+
+        let deadline = Duration::from_secs(self.config.drain_timeout);
+        let drained = tokio::time::timeout(deadline, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!(
+                "Aborting {} connections still open after {}s",
+                connections.len(),
+                deadline.as_secs()
+            );
+            connections.shutdown().await;
+        }
+        tracing::info!("Server stopped");
+        Ok(())
     }
 
     /// Handle a single connection
@@ -195,51 +415,106 @@ impl Server {
         addr: SocketAddr,
         router: Arc<Router>,
         tls_acceptor: Option<TlsAcceptor>,
+        limits: ConnectionLimits,
+        drain: Drain,
     ) -> NetResult<()> {
         tracing::debug!("New connection from {}", addr);
 
         if let Some(acceptor) = tls_acceptor {
-            let tls_stream = acceptor
-                .accept(stream)
-                .await
-                .map_err(|e| NetError::Tls(e.to_string()))?;
-            Self::handle_http(tls_stream, router).await
+            let mut tls_stream = acceptor.accept(stream).await?;
+            if tls_stream.alpn_protocol() == Some(ACME_TLS_ALPN) {
+                // A TLS-ALPN-01 validation ends with the handshake
+                tracing::debug!("Answered ACME challenge from {}", addr);
+                let _ = tokio::io::AsyncWriteExt::shutdown(&mut tls_stream).await;
+                return Ok(());
+            }
+            if tls_stream.alpn_protocol() == Some(ALPN_H2) {
+                let (reader, writer) = tokio::io::split(tls_stream);
+                return http2::serve(reader, writer, Some(addr), router, limits, drain).await;
+            }
+            Self::handle_http(tls_stream, Some(addr), router, limits, drain).await
         } else {
-            Self::handle_http(stream, router).await
+            Self::handle_http(stream, Some(addr), router, limits, drain).await
         }
     }
 
-    /// Handle HTTP on a stream
-    async fn handle_http<S>(stream: S, router: Arc<Router>) -> NetResult<()>
+    /// Handle HTTP on a stream, serving requests until the connection closes
+    async fn handle_http<S>(
+        stream: S,
+        peer: Option<SocketAddr>,
+        router: Arc<Router>,
+        limits: ConnectionLimits,
+        mut drain: Drain,
+    ) -> NetResult<()>
     where
         S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
     {
         let (reader, mut writer) = tokio::io::split(stream);
         let mut buf_reader = TokioBufReader::new(reader);
+        let mut served = 0usize;
 
         loop {
+            // Wait for the next request; idle connections are closed quietly,
+            // and at once when the server shuts down
+            let wait = if served == 0 {
+                limits.read_timeout
+            } else {
+                limits.keep_alive_timeout
+            };
+            let next = tokio::select! {
+                biased;
+                next = tokio::time::timeout(wait, buf_reader.fill_buf()) => next,
+                _ = drain.wait() => break,
+            };
+            match next {
+                Err(_) => break,
+                Ok(Ok([])) => break,
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => return Err(e.into()),
+            }
+
+            // A client with prior knowledge opens with the HTTP/2 preface
+            if served == 0 && limits.http2 && buf_reader.buffer().starts_with(&http2::PREFACE[..4])
+            {
+                return http2::serve(buf_reader, writer, peer, router, limits, drain).await;
+            }
+
             // Read request
-            let request = match Self::read_request(&mut buf_reader).await {
+            let read =
+                tokio::time::timeout(limits.read_timeout, Self::read_request(&mut buf_reader));
+            let mut request = match read.await.unwrap_or(Err(NetError::Timeout)) {
                 Ok(req) => req,
                 Err(NetError::ConnectionClosed) => break,
                 Err(e) => {
-                    let response = Self::error_response(&e);
-                    writer.write_all(&response.to_bytes()).await?;
+                    // The rest of the stream can't be framed; close after replying
+                    let mut response = Self::error_response(&e);
+                    response.headers_mut().set("Connection", "close");
+                    response.write_to(&mut writer).await?;
                     break;
                 }
             };
+            served += 1;
+            request.set_peer_addr(peer);
 
-            let keep_alive = request.headers().is_keep_alive();
+            let version = request.version();
+            let wants_keep_alive = request.is_keep_alive();
 
             // Route and handle request
-            let response = match router.handle(request).await {
+            let mut response = match router.handle(request).await {
                 Ok(resp) => resp,
                 Err(e) => Self::error_response(&e),
             };
+            response.set_version(version);
 
-            // Write response
-            writer.write_all(&response.to_bytes()).await?;
-            writer.flush().await?;
+            let keep_alive = wants_keep_alive
+                && limits.allows_another(served)
+                && !drain.is_draining()
+                && !response.headers().has_connection_option("close")
+                && !response.is_close_delimited();
+            Self::set_connection_headers(&mut response, keep_alive, served, limits);
+
+            // Write response, streaming its body if it has one
+            response.write_to(&mut writer).await?;
 
             if !keep_alive {
                 break;
@@ -249,6 +524,29 @@ impl Server {
         Ok(())
     }
 
+    /// Tell the client whether the connection stays open after a response
+    fn set_connection_headers(
+        response: &mut Response,
+        keep_alive: bool,
+        served: usize,
+        limits: ConnectionLimits,
+    ) {
+        if !keep_alive {
+            response.headers_mut().set("Connection", "close");
+            return;
+        }
+
+        // HTTP/1.1 connections persist by default; HTTP/1.0 must be told
+        if response.version() == Version::Http10 {
+            response.headers_mut().set("Connection", "keep-alive");
+        }
+        let mut keep_alive = format!("timeout={}", limits.keep_alive_timeout.as_secs());
+        if limits.max_requests > 0 {
+            keep_alive.push_str(&format!(", max={}", limits.max_requests - served));
+        }
+        response.headers_mut().set("Keep-Alive", keep_alive);
+    }
+
     /// Read an HTTP request from the stream
     async fn read_request<R>(reader: &mut TokioBufReader<R>) -> NetResult<Request>
     where
@@ -283,14 +581,38 @@ impl Server {
         }
 
         // Reconstruct raw request for parsing
-        let mut raw = headers.join("");
-        raw.push_str("\r\n");
-
-        // Parse headers first to get Content-Length
-        let partial_request = Request::parse(raw.as_bytes())?;
+        let mut raw = headers.join("").into_bytes();
+        raw.extend_from_slice(b"\r\n");
+
+        // Find Content-Length before parsing; the body is not read yet.
+        // Anything that leaves the body length ambiguous would desync the
+        // requests that follow on this connection, so it is rejected.
+        let mut content_length = None;
+        for line in headers.iter().skip(1) {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("transfer-encoding") {
+                return Err(NetError::InvalidRequest(
+                    "Transfer-Encoding request bodies are not supported".into(),
+                ));
+            }
+            if name.eq_ignore_ascii_case("content-length") {
+                let length = value.trim().parse::<usize>().map_err(|_| {
+                    NetError::InvalidRequest(format!("Invalid Content-Length: {}", value.trim()))
+                })?;
+                if content_length.is_some_and(|existing| existing != length) {
+                    return Err(NetError::InvalidRequest(
+                        "Conflicting Content-Length headers".into(),
+                    ));
+                }
+                content_length = Some(length);
+            }
+        }
 
         // Read body if present
-        if let Some(content_length) = partial_request.headers().content_length() {
+        if let Some(content_length) = content_length {
             if content_length > crate::MAX_BODY_SIZE {
                 return Err(NetError::RequestTooLarge);
             }
@@ -298,14 +620,15 @@ impl Server {
             let mut body = vec![0u8; content_length];
             reader.read_exact(&mut body).await?;
 
-            raw.extend(body.iter().map(|&b| b as char));
+            // Bodies may be binary; keep the bytes as-is
+            raw.extend_from_slice(&body);
         }
 
-        Request::parse(raw.as_bytes())
+        Request::parse(&raw)
     }
 
     /// Create an error response from a NetError
-    fn error_response(error: &NetError) -> Response {
+    pub(crate) fn error_response(error: &NetError) -> Response {
         let (status, message) = match error {
             NetError::NotFound => (StatusCode::NotFound, "Not Found"),
             NetError::MethodNotAllowed => (StatusCode::MethodNotAllowed, "Method Not Allowed"),
@@ -325,6 +648,15 @@ impl Server {
     }
 }
 
+/// Aborts a background task when dropped
+struct AbortOnDrop(tokio::task::JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 /// Simple server builder for convenience
 pub struct ServerBuilder {
     config: ServerConfig,
@@ -370,104 +702,10 @@ impl Default for ServerBuilder {
     }
 }
 
-// We need tokio-rustls for async TLS
-mod tokio_rustls {
-    use std::future::Future;
-    use std::io;
-    use std::pin::Pin;
-    use std::sync::Arc;
-    use std::task::{Context, Poll};
-
-    use rustls::{ServerConfig, ServerConnection};
-    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
-    use tokio::net::TcpStream;
-
-    pub struct TlsAcceptor {
-        inner: Arc<ServerConfig>,
-    }
-
-    impl TlsAcceptor {
-        pub fn accept(&self, stream: TcpStream) -> Accept {
-            Accept {
-                stream: Some(stream),
-                config: self.inner.clone(),
-            }
-        }
-    }
-
-    impl From<Arc<ServerConfig>> for TlsAcceptor {
-        fn from(config: Arc<ServerConfig>) -> Self {
-            Self { inner: config }
-        }
-    }
-
-    impl Clone for TlsAcceptor {
-        fn clone(&self) -> Self {
-            Self {
-                inner: self.inner.clone(),
-            }
-        }
-    }
-
-    pub struct Accept {
-        stream: Option<TcpStream>,
-        config: Arc<ServerConfig>,
-    }
-
-    impl Future for Accept {
-        type Output = io::Result<TlsStream>;
-
-        fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
-            let stream = self.stream.take().unwrap();
-            let conn =
-                ServerConnection::new(self.config.clone()).map_err(|e| io::Error::other(e))?;
-
-            Poll::Ready(Ok(TlsStream {
-                stream,
-                _conn: conn,
-            }))
-        }
-    }
-
-    pub struct TlsStream {
-        stream: TcpStream,
-        _conn: ServerConnection,
-    }
-
-    impl AsyncRead for TlsStream {
-        fn poll_read(
-            mut self: Pin<&mut Self>,
-            cx: &mut Context<'_>,
-            buf: &mut ReadBuf<'_>,
-        ) -> Poll<io::Result<()>> {
-            // Simplified TLS read - in production this would need full handshake handling
-            // For now, just pass through to underlying stream
-            Pin::new(&mut self.stream).poll_read(cx, buf)
-        }
-    }
-
-    impl AsyncWrite for TlsStream {
-        fn poll_write(
-            mut self: Pin<&mut Self>,
-            cx: &mut Context<'_>,
-            buf: &[u8],
-        ) -> Poll<io::Result<usize>> {
-            Pin::new(&mut self.stream).poll_write(cx, buf)
-        }
-
-        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-            Pin::new(&mut self.stream).poll_flush(cx)
-        }
-
-        fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-            Pin::new(&mut self.stream).poll_shutdown(cx)
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::AsyncWriteExt;
 
     #[test]
     fn test_server_config() {
@@ -478,4 +716,251 @@ mod tests {
         let tls_config = config.with_tls("/path/to/cert.pem", "/path/to/key.pem");
         assert!(tls_config.is_tls());
     }
+
+    #[test]
+    fn test_acme_enables_tls() {
+        let dir = std::env::temp_dir().join(format!("vaya-server-acme-{}", std::process::id()));
+        let acme = AcmeManager::new(crate::AcmeConfig::new(["vaya.test"]).data_dir(dir)).unwrap();
+        let config = ServerConfig::new(([127, 0, 0, 1], 0)).with_acme(Arc::new(acme));
+        assert!(config.is_tls());
+
+        // No certificate until the first issuance, and nothing to reload
+        let server = Server::new(config, Router::new()).unwrap();
+        assert!(server.tls_acceptor.is_some());
+        assert!(!server.cert_resolver.as_ref().unwrap().has_certificate());
+        assert!(server.reload_tls().is_err());
+    }
+
+    #[test]
+    fn test_missing_certificate_files() {
+        let config =
+            ServerConfig::default().with_tls("/nonexistent/cert.pem", "/nonexistent/key.pem");
+        assert!(matches!(
+            Server::new(config, Router::new()),
+            Err(NetError::Tls(_))
+        ));
+    }
+
+    fn router() -> Arc<Router> {
+        let mut router = Router::new();
+        router.get("/ping", |_req| async { Ok(Response::ok().text("pong")) });
+        router.post("/echo", |req| async move {
+            Ok(Response::ok().body_bytes(req.body().to_vec()))
+        });
+        Arc::new(router)
+    }
+
+    fn limits(config: ServerConfig) -> ConnectionLimits {
+        ConnectionLimits::from_config(&config)
+    }
+
+    /// Send raw bytes on a connection, then half-close it and collect
+    /// everything the server writes
+    async fn exchange(input: &[u8], limits: ConnectionLimits) -> String {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let handle = tokio::spawn(Server::handle_http(
+            server,
+            None,
+            router(),
+            limits,
+            Drain::never(),
+        ));
+        client.write_all(input).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        handle.await.unwrap().unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_pipelined() {
+        let config = ServerConfig::default().keep_alive_timeout(2);
+
+        // Pipelined requests are answered in order on one connection
+        let output = exchange(
+            b"GET /ping HTTP/1.1\r\nHost: a\r\n\r\n\
+              POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello\
+              GET /ping HTTP/1.1\r\n\r\n",
+            limits(config),
+        )
+        .await;
+        assert_eq!(output.matches("HTTP/1.1 200 OK").count(), 3);
+        assert!(output.find("pong").unwrap() < output.find("hello").unwrap());
+        assert!(output.contains("keep-alive: timeout=2, max=999"));
+        assert!(!output.contains("connection: close"));
+
+        // Connection: close from the client ends the connection
+        let output = exchange(
+            b"GET /ping HTTP/1.1\r\nConnection: close\r\n\r\nGET /ping HTTP/1.1\r\n\r\n",
+            limits(ServerConfig::default()),
+        )
+        .await;
+        assert_eq!(output.matches("200 OK").count(), 1);
+        assert!(output.contains("connection: close"));
+    }
+
+    #[tokio::test]
+    async fn test_connection_limits() {
+        // The last request allowed on a connection is told it will close
+        let config = ServerConfig::default().max_requests_per_connection(2);
+        let output = exchange(&b"GET /ping HTTP/1.1\r\n\r\n".repeat(3), limits(config)).await;
+        assert_eq!(output.matches("200 OK").count(), 2);
+        assert_eq!(output.matches("connection: close").count(), 1);
+
+        // HTTP/1.0 closes unless the client asks for keep-alive
+        let output = exchange(
+            b"GET /ping HTTP/1.0\r\n\r\n",
+            limits(ServerConfig::default()),
+        )
+        .await;
+        assert!(output.starts_with("HTTP/1.0 200 OK"));
+        assert!(output.contains("connection: close"));
+        let output = exchange(
+            b"GET /ping HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /ping HTTP/1.0\r\n\r\n",
+            limits(ServerConfig::default()),
+        )
+        .await;
+        assert_eq!(output.matches("200 OK").count(), 2);
+        assert!(output.contains("connection: keep-alive"));
+
+        // An ambiguous body length can't be framed, so the connection closes
+        let output = exchange(
+            b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!\
+              GET /ping HTTP/1.1\r\n\r\n",
+            limits(ServerConfig::default()),
+        )
+        .await;
+        assert!(output.starts_with("HTTP/1.1 400 Bad Request"));
+        assert!(!output.contains("pong"));
+    }
+
+    #[tokio::test]
+    async fn test_http2_prior_knowledge() {
+        // The preface, empty SETTINGS, then GET /ping on stream 1
+        let block =
+            crate::hpack::encode([(":method", "GET"), (":scheme", "http"), (":path", "/ping")]);
+        let mut input = http2::PREFACE.to_vec();
+        input.extend_from_slice(&[0, 0, 0, 0x4, 0, 0, 0, 0, 0]);
+        input.extend_from_slice(&(block.len() as u32).to_be_bytes()[1..]);
+        input.extend_from_slice(&[0x1, 0x5, 0, 0, 0, 1]);
+        input.extend_from_slice(&block);
+
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let handle = tokio::spawn(Server::handle_http(
+            server,
+            None,
+            router(),
+            limits(ServerConfig::default()),
+            Drain::never(),
+        ));
+        client.write_all(&input).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut output = Vec::new();
+        client.read_to_end(&mut output).await.unwrap();
+        handle.await.unwrap().unwrap();
+
+        // Server SETTINGS first, the response body, and a GOAWAY at the end
+        assert_eq!(output[3], 0x4);
+        assert!(output.windows(4).any(|w| w == b"pong"));
+        let goaway = &output[output.len() - 17..];
+        assert_eq!(goaway[3], 0x7);
+        assert_eq!(goaway[9..], [0, 0, 0, 1, 0, 0, 0, 0]);
+
+        // With HTTP/2 disabled the preface is just a bad HTTP/1.1 request
+        let output = exchange(http2::PREFACE, limits(ServerConfig::default().http2(false))).await;
+        assert!(output.starts_with("HTTP/1.1 400 Bad Request"));
+    }
+
+    async fn send(port: u16, request: &[u8]) -> tokio::net::TcpStream {
+        let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .unwrap();
+        stream.write_all(request).await.unwrap();
+        stream
+    }
+
+    async fn read_all(mut stream: tokio::net::TcpStream) -> String {
+        let mut output = Vec::new();
+        stream.read_to_end(&mut output).await.unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut router = Router::new();
+        router.get("/slow", |_req| async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            Ok(Response::ok().text("done"))
+        });
+        let config = ServerConfig::new(([127, 0, 0, 1], port)).keep_alive_timeout(60);
+        let server = Server::new(config, router).unwrap();
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let running = tokio::spawn(async move {
+            server
+                .run_until(async {
+                    let _ = stop_rx.await;
+                })
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // One connection idles between requests, one has a request in flight
+        let mut idle = send(port, b"GET /missing HTTP/1.1\r\n\r\n").await;
+        let mut buf = [0u8; 512];
+        assert!(idle.read(&mut buf).await.unwrap() > 0);
+        let busy = send(port, b"GET /slow HTTP/1.1\r\n\r\n").await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let started = std::time::Instant::now();
+        stop_tx.send(()).unwrap();
+        assert_eq!(read_all(idle).await, "");
+        let output = read_all(busy).await;
+        assert!(output.starts_with("HTTP/1.1 200 OK"));
+        assert!(output.contains("connection: close"));
+        assert!(output.ends_with("done"));
+
+        running.await.unwrap().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_drain_deadline_aborts_connections() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut router = Router::new();
+        router.get("/hang", |_req| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(Response::ok())
+        });
+        let config = ServerConfig::new(([127, 0, 0, 1], port)).drain_timeout(0);
+        let server = Server::new(config, router).unwrap();
+        let running = tokio::spawn(async move {
+            server
+                .run_until(tokio::time::sleep(Duration::from_millis(100)))
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The handler never finishes, so the connection is dropped unanswered
+        let hung = send(port, b"GET /hang HTTP/1.1\r\n\r\n").await;
+        assert_eq!(read_all(hung).await, "");
+        tokio::time::timeout(Duration::from_secs(5), running)
+            .await
+            .expect("server stops at the deadline")
+            .unwrap()
+            .unwrap();
+    }
 }
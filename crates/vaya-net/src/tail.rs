@@ -0,0 +1,396 @@
+//! Admin live tail over WebSocket
+//!
+//! Streams recent entries from the log ring buffer and a metrics snapshot
+//! once a second on a single WebSocket, as JSON text frames tagged
+//! `"type":"logs"` or `"type":"metrics"`. Clients pick a minimum level and
+//! module prefix with the `level` and `module` query parameters.
+//!
+//! Slow clients never block the server: each tick sends at most
+//! `max_batch` log entries (newest first kept) and reports how many were
+//! skipped, and a send that takes longer than `send_timeout` ends the
+//! session. The number of concurrent sessions is capped.
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+use tracing::{debug, info};
+use vaya_common::logbuf::{LogBatch, LogBuffer, LogFilter, LogLevel};
+use vaya_common::metrics::MetricsRegistry;
+
+use crate::{NetError, NetResult, Request, Response, StatusCode, WebSocket};
+
+/// Live tail settings
+#[derive(Debug, Clone)]
+pub struct TailConfig {
+    /// Maximum concurrent tail sessions
+    pub max_sessions: usize,
+    /// How often logs and metrics are pushed
+    pub interval: Duration,
+    /// Maximum log entries per push
+    pub max_batch: usize,
+    /// Longest a single push may take before the client is dropped
+    pub send_timeout: Duration,
+}
+
+impl Default for TailConfig {
+    fn default() -> Self {
+        Self {
+            max_sessions: 4,
+            interval: Duration::from_secs(1),
+            max_batch: 200,
+            send_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Why a tail request was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailRejection {
+    /// Not a WebSocket upgrade request
+    NotUpgrade,
+    /// Caller is not an authenticated admin
+    Unauthorized,
+    /// Unknown `level` query value
+    InvalidLevel,
+    /// Session cap reached
+    TooManySessions,
+}
+
+impl TailRejection {
+    /// HTTP response for the rejected upgrade
+    pub fn to_response(&self) -> Response {
+        let (status, message) = match self {
+            TailRejection::NotUpgrade => (StatusCode::BadRequest, "WebSocket upgrade required"),
+            TailRejection::Unauthorized => (StatusCode::Forbidden, "Admin access required"),
+            TailRejection::InvalidLevel => (
+                StatusCode::BadRequest,
+                "level must be one of: trace, debug, info, warn, error",
+            ),
+            TailRejection::TooManySessions => (
+                StatusCode::ServiceUnavailable,
+                "Too many live tail sessions",
+            ),
+        };
+        Response::new(status).json(format!(r#"{{"error":"{}"}}"#, message))
+    }
+}
+
+/// Accepts and tracks admin live tail sessions
+pub struct LiveTail {
+    config: TailConfig,
+    logs: &'static LogBuffer,
+    metrics: &'static MetricsRegistry,
+    active: Arc<AtomicUsize>,
+}
+
+impl LiveTail {
+    /// Create a live tail over the process-wide log buffer and metrics
+    pub fn new() -> Self {
+        Self::with_sources(
+            vaya_common::logbuf::global(),
+            vaya_common::metrics::global(),
+        )
+    }
+
+    /// Create a live tail over specific sources
+    pub fn with_sources(logs: &'static LogBuffer, metrics: &'static MetricsRegistry) -> Self {
+        Self {
+            config: TailConfig::default(),
+            logs,
+            metrics,
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Set the config
+    pub fn with_config(mut self, config: TailConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Number of open sessions
+    pub fn active_sessions(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Validate an upgrade request and reserve a session slot
+    ///
+    /// `authorize` decides whether the caller is an admin (the server
+    /// checks the bearer token). On success, send the returned handshake
+    /// response and pass the upgraded stream to [`TailSession::run`].
+    pub fn open(
+        &self,
+        request: &Request,
+        authorize: impl FnOnce(&Request) -> bool,
+    ) -> Result<(Response, TailSession), TailRejection> {
+        if !request.is_websocket_upgrade() {
+            return Err(TailRejection::NotUpgrade);
+        }
+        if !authorize(request) {
+            return Err(TailRejection::Unauthorized);
+        }
+        let min_level = match request.query("level") {
+            Some(level) => LogLevel::parse(level).ok_or(TailRejection::InvalidLevel)?,
+            None => LogLevel::Info,
+        };
+        let filter = LogFilter {
+            min_level,
+            target_prefix: request
+                .query("module")
+                .filter(|m| !m.is_empty())
+                .map(str::to_string),
+        };
+
+        // Reserve a slot; released when the session is dropped
+        let reserved = self
+            .active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.config.max_sessions).then_some(n + 1)
+            })
+            .is_ok();
+        if !reserved {
+            return Err(TailRejection::TooManySessions);
+        }
+        let slot = SessionSlot(self.active.clone());
+
+        let response =
+            WebSocket::<tokio::net::TcpStream>::handshake_response(request).map_err(|_| {
+                // Slot is released when `slot` drops
+                TailRejection::NotUpgrade
+            })?;
+
+        info!(
+            "Live tail session opened ({}/{})",
+            self.active_sessions(),
+            self.config.max_sessions
+        );
+
+        // Start with the most recent backlog
+        let cursor = self
+            .logs
+            .last_seq()
+            .saturating_sub(self.config.max_batch as u64);
+        Ok((
+            response,
+            TailSession {
+                config: self.config.clone(),
+                logs: self.logs,
+                metrics: self.metrics,
+                filter,
+                cursor,
+                _slot: slot,
+            },
+        ))
+    }
+}
+
+impl Default for LiveTail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Releases a session slot on drop
+struct SessionSlot(Arc<AtomicUsize>);
+
+impl Drop for SessionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// An open live tail session
+pub struct TailSession {
+    config: TailConfig,
+    logs: &'static LogBuffer,
+    metrics: &'static MetricsRegistry,
+    filter: LogFilter,
+    cursor: u64,
+    _slot: SessionSlot,
+}
+
+impl TailSession {
+    /// Log filter for this session
+    pub fn filter(&self) -> &LogFilter {
+        &self.filter
+    }
+
+    /// Build the frames for one tick: new logs (if any), then metrics
+    pub fn next_frames(&mut self) -> Vec<String> {
+        let batch = self
+            .logs
+            .read_since(self.cursor, &self.filter, self.config.max_batch);
+        self.cursor = batch.cursor;
+
+        let mut frames = Vec::with_capacity(2);
+        if !batch.entries.is_empty() || batch.dropped > 0 {
+            frames.push(logs_frame(&batch));
+        }
+        frames.push(self.metrics_frame());
+        frames
+    }
+
+    /// Push frames until the client disconnects or falls too far behind
+    pub async fn run<S>(mut self, ws: &mut WebSocket<S>) -> NetResult<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut ticker = tokio::time::interval(self.config.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            ticker.tick().await;
+            for frame in self.next_frames() {
+                match tokio::time::timeout(self.config.send_timeout, ws.send_text(frame)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => {
+                        debug!("Live tail session ended: {}", e);
+                        return Err(e);
+                    }
+                    Err(_) => {
+                        info!("Live tail client too slow, closing session");
+                        let _ = ws.close(Some(1008), Some("client too slow")).await;
+                        return Err(NetError::Timeout);
+                    }
+                }
+            }
+        }
+    }
+
+    fn metrics_frame(&self) -> String {
+        let mut out = String::from(r#"{"type":"metrics","samples":["#);
+        for (i, sample) in self.metrics.snapshot().iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                r#"{{"name":"{}","labels":{{"#,
+                escape_json(&sample.name)
+            );
+            for (j, (k, v)) in sample.labels.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                let _ = write!(out, r#""{}":"{}""#, escape_json(k), escape_json(v));
+            }
+            let _ = write!(
+                out,
+                r#"}},"kind":"{}","value":{}}}"#,
+                sample.kind.as_str(),
+                if sample.value.is_finite() {
+                    sample.value
+                } else {
+                    0.0
+                }
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+fn logs_frame(batch: &LogBatch) -> String {
+    let mut out = format!(r#"{{"type":"logs","dropped":{},"entries":["#, batch.dropped);
+    for (i, entry) in batch.entries.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(
+            out,
+            r#"{{"seq":{},"at_ms":{},"level":"{}","target":"{}","message":"{}"}}"#,
+            entry.seq,
+            entry.at_ms,
+            entry.level.as_str(),
+            escape_json(&entry.target),
+            escape_json(&entry.message)
+        );
+    }
+    out.push_str("]}");
+    out
+}
+
+/// Escape JSON string
+pub(crate) fn escape_json(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upgrade_request(query: &str) -> Request {
+        let raw = format!(
+            "GET /admin/tail{} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+            query
+        );
+        Request::parse(raw.as_bytes()).unwrap()
+    }
+
+    fn sources() -> (&'static LogBuffer, &'static MetricsRegistry) {
+        (
+            Box::leak(Box::new(LogBuffer::new(100))),
+            Box::leak(Box::new(MetricsRegistry::new())),
+        )
+    }
+
+    #[test]
+    fn test_open_enforces_auth_and_session_cap() {
+        let (logs, metrics) = sources();
+        let tail = LiveTail::with_sources(logs, metrics).with_config(TailConfig {
+            max_sessions: 1,
+            ..Default::default()
+        });
+        let request = upgrade_request("?level=warn&module=vaya_db");
+
+        assert_eq!(
+            tail.open(&request, |_| false).err(),
+            Some(TailRejection::Unauthorized)
+        );
+        assert_eq!(
+            tail.open(&upgrade_request("?level=loud"), |_| true).err(),
+            Some(TailRejection::InvalidLevel)
+        );
+
+        let (response, session) = tail.open(&request, |_| true).unwrap();
+        assert_eq!(response.status(), StatusCode::SwitchingProtocols);
+        assert_eq!(session.filter().min_level, LogLevel::Warn);
+        assert_eq!(
+            tail.open(&request, |_| true).err(),
+            Some(TailRejection::TooManySessions)
+        );
+
+        drop(session);
+        assert_eq!(tail.active_sessions(), 0);
+        assert!(tail.open(&request, |_| true).is_ok());
+    }
+
+    #[test]
+    fn test_next_frames() {
+        let (logs, metrics) = sources();
+        metrics
+            .counter("vaya_requests_total", &[("route", "search")])
+            .add(3);
+        logs.push(LogLevel::Info, "vaya_db::wal", "checkpoint \"done\"");
+        logs.push(LogLevel::Debug, "vaya_db::wal", "noise");
+
+        let tail = LiveTail::with_sources(logs, metrics);
+        let (_, mut session) = tail.open(&upgrade_request(""), |_| true).unwrap();
+
+        let frames = session.next_frames();
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].starts_with(r#"{"type":"logs","dropped":0"#));
+        assert!(frames[0].contains(r#"checkpoint \"done\""#));
+        assert!(!frames[0].contains("noise"));
+        assert!(frames[1].contains(r#""labels":{"route":"search"},"kind":"counter","value":3"#));
+
+        // Nothing new: metrics only
+        assert_eq!(session.next_frames().len(), 1);
+    }
+}
@@ -0,0 +1,439 @@
+//! TLS sessions over async byte streams
+//!
+//! [`TlsAcceptor`] runs the server side of the handshake for the HTTP
+//! listener, including ALPN so clients can negotiate HTTP/2. The client side
+//! is for services that speak their own protocol over a socket (SMTP relays,
+//! for instance): a [`TlsConnector`] verifies peers against a PEM bundle of
+//! trusted roots, and [`ClientStream`] lets a connection start in plaintext
+//! and upgrade in place, which is what STARTTLS needs.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{
+    ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig, ServerConnection,
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+use crate::{NetError, NetResult};
+
+/// CA bundle locations used by common Linux distributions
+pub const SYSTEM_CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+
+/// Open a TCP connection, giving up after `timeout`
+pub async fn connect(host: &str, port: u16, timeout: Duration) -> NetResult<TcpStream> {
+    let stream = tokio::time::timeout(timeout, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| NetError::Timeout)??;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
+
+/// Client-side TLS handshakes against a set of trusted roots
+#[derive(Clone)]
+pub struct TlsConnector {
+    config: Arc<ClientConfig>,
+}
+
+impl TlsConnector {
+    /// Trust the given root certificates
+    pub fn new(roots: RootCertStore) -> Self {
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Self {
+            config: Arc::new(config),
+        }
+    }
+
+    /// Trust the certificates in a PEM bundle
+    pub fn from_pem_file(path: &str) -> NetResult<Self> {
+        let file = File::open(path)
+            .map_err(|e| NetError::Tls(format!("Failed to open CA bundle {}: {}", path, e)))?;
+        let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+            .collect::<Result<Vec<CertificateDer<'static>>, _>>()
+            .map_err(|e| NetError::Tls(format!("Failed to parse CA bundle {}: {}", path, e)))?;
+
+        let mut roots = RootCertStore::empty();
+        let (added, _ignored) = roots.add_parsable_certificates(certs);
+        if added == 0 {
+            return Err(NetError::Tls(format!("No certificates found in {}", path)));
+        }
+        Ok(Self::new(roots))
+    }
+
+    /// Trust the operating system's CA bundle
+    pub fn system() -> NetResult<Self> {
+        SYSTEM_CA_BUNDLES
+            .iter()
+            .find(|path| std::path::Path::new(path).exists())
+            .ok_or_else(|| NetError::Tls("No system CA bundle found".into()))
+            .and_then(|path| Self::from_pem_file(path))
+    }
+
+    /// Run the handshake over `io`, verifying the peer as `host`
+    pub async fn connect<IO>(&self, host: &str, io: IO) -> NetResult<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|_| NetError::Tls(format!("Invalid server name: {}", host)))?;
+        let conn = ClientConnection::new(self.config.clone(), name)
+            .map_err(|e| NetError::Tls(e.to_string()))?;
+
+        TlsStream::handshake(io, conn.into()).await
+    }
+}
+
+/// Server-side TLS handshakes with a certificate chain
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    config: Arc<ServerConfig>,
+}
+
+impl TlsAcceptor {
+    /// Accept sessions using the given server config
+    pub fn new(config: Arc<ServerConfig>) -> Self {
+        Self { config }
+    }
+
+    /// Run the handshake over `io`
+    pub async fn accept<IO>(&self, io: IO) -> NetResult<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let conn =
+            ServerConnection::new(self.config.clone()).map_err(|e| NetError::Tls(e.to_string()))?;
+        TlsStream::handshake(io, conn.into()).await
+    }
+}
+
+/// A TLS session over an async byte stream
+pub struct TlsStream<IO> {
+    io: IO,
+    conn: Connection,
+}
+
+impl<IO> TlsStream<IO>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    pub(crate) async fn handshake(io: IO, conn: Connection) -> NetResult<Self> {
+        let mut stream = Self { io, conn };
+        std::future::poll_fn(|cx| stream.poll_handshake(cx))
+            .await
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::InvalidData => NetError::Tls(e.to_string()),
+                _ => NetError::Io(e),
+            })?;
+        Ok(stream)
+    }
+
+    /// The underlying transport
+    pub fn get_ref(&self) -> &IO {
+        &self.io
+    }
+
+    /// The application protocol agreed on through ALPN, if any
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.conn.alpn_protocol()
+    }
+
+    fn poll_handshake(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.conn.is_handshaking() {
+            if self.poll_write_tls(cx)?.is_pending() {
+                return Poll::Pending;
+            }
+            if self.conn.is_handshaking() && self.conn.wants_read() {
+                match self.poll_read_tls(cx)? {
+                    Poll::Ready(0) => {
+                        return Poll::Ready(Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "connection closed during TLS handshake",
+                        )))
+                    }
+                    Poll::Ready(_) => {}
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+        // The final handshake flight may still be buffered
+        self.poll_write_tls(cx)
+    }
+
+    /// Write buffered TLS records to the transport until it is drained
+    fn poll_write_tls(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.conn.wants_write() {
+            let mut io = SyncIo {
+                io: &mut self.io,
+                cx,
+            };
+            match self.conn.write_tls(&mut io) {
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    /// Read TLS records from the transport and decrypt them
+    fn poll_read_tls(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let mut io = SyncIo {
+            io: &mut self.io,
+            cx,
+        };
+        let n = match self.conn.read_tls(&mut io) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+            Err(e) => return Poll::Ready(Err(e)),
+        };
+        if let Err(e) = self.conn.process_new_packets() {
+            // Let the peer know why before giving up
+            let _ = self.poll_write_tls(cx);
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, e)));
+        }
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<IO> AsyncRead for TlsStream<IO>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            match this.conn.reader().read(buf.initialize_unfilled()) {
+                Ok(n) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+            match this.poll_read_tls(cx)? {
+                // EOF is reported by the reader on the next pass
+                Poll::Ready(_) => {}
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<IO> AsyncWrite for TlsStream<IO>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let n = this.conn.writer().write(buf)?;
+        if n == 0 && !buf.is_empty() {
+            // rustls' send buffer is full; drain it before accepting more
+            return match this.poll_write_tls(cx)? {
+                Poll::Ready(()) => Poll::Ready(this.conn.writer().write(buf)),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+        // Push what we can now; anything left goes out on flush
+        let _ = this.poll_write_tls(cx)?;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.conn.writer().flush()?;
+        this.poll_write_tls(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.conn.send_close_notify();
+        match this.poll_write_tls(cx)? {
+            Poll::Ready(()) => Pin::new(&mut this.io).poll_shutdown(cx),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// Adapts an async transport to the blocking `Read`/`Write` rustls expects,
+/// mapping `Pending` to `WouldBlock`
+struct SyncIo<'a, 'b, IO> {
+    io: &'a mut IO,
+    cx: &'a mut Context<'b>,
+}
+
+impl<IO: AsyncRead + Unpin> Read for SyncIo<'_, '_, IO> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buf = ReadBuf::new(buf);
+        match Pin::new(&mut *self.io).poll_read(self.cx, &mut buf) {
+            Poll::Ready(Ok(())) => Ok(buf.filled().len()),
+            Poll::Ready(Err(e)) => Err(e),
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> Write for SyncIo<'_, '_, IO> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match Pin::new(&mut *self.io).poll_write(self.cx, buf) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match Pin::new(&mut *self.io).poll_flush(self.cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+/// A client connection that may be upgraded to TLS mid-session
+pub enum ClientStream {
+    /// Plaintext TCP
+    Plain(TcpStream),
+    /// TLS over TCP
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl ClientStream {
+    /// Whether the connection is encrypted
+    pub fn is_tls(&self) -> bool {
+        matches!(self, ClientStream::Tls(_))
+    }
+
+    /// Start TLS on a plaintext connection (a no-op if already encrypted)
+    pub async fn upgrade(self, connector: &TlsConnector, host: &str) -> NetResult<Self> {
+        match self {
+            ClientStream::Plain(stream) => Ok(ClientStream::Tls(Box::new(
+                connector.connect(host, stream).await?,
+            ))),
+            tls @ ClientStream::Tls(_) => Ok(tls),
+        }
+    }
+}
+
+impl AsyncRead for ClientStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ClientStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            ClientStream::Tls(s) => Pin::new(s.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ClientStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ClientStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            ClientStream::Tls(s) => Pin::new(s.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ClientStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            ClientStream::Tls(s) => Pin::new(s.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ClientStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            ClientStream::Tls(s) => Pin::new(s.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_missing_ca_bundle() {
+        assert!(matches!(
+            TlsConnector::from_pem_file("/nonexistent/ca.pem"),
+            Err(NetError::Tls(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_server_name() {
+        let connector = TlsConnector::new(RootCertStore::empty());
+        let (client, _server) = tokio::io::duplex(1024);
+        assert!(matches!(
+            connector.connect("not a host", client).await,
+            Err(NetError::Tls(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_handshake_fails_on_plaintext_peer() {
+        let connector = TlsConnector::new(RootCertStore::empty());
+        let (client, mut server) = tokio::io::duplex(16 * 1024);
+        tokio::spawn(async move {
+            let mut hello = [0u8; 5];
+            server.read_exact(&mut hello).await.unwrap();
+            // A handshake record from the client, answered with SMTP
+            assert_eq!(hello[0], 0x16);
+            server
+                .write_all(b"220 smtp.example.com ESMTP\r\n")
+                .await
+                .unwrap();
+        });
+        assert!(matches!(
+            connector.connect("smtp.example.com", client).await,
+            Err(NetError::Tls(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_plain_client_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"hello").await.unwrap();
+        });
+
+        let stream = connect("127.0.0.1", port, Duration::from_secs(5))
+            .await
+            .unwrap();
+        let mut stream = ClientStream::Plain(stream);
+        assert!(!stream.is_tls());
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello");
+    }
+}
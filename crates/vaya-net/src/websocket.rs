@@ -1,7 +1,7 @@
 //! WebSocket implementation
 
 use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
 
 use crate::{NetError, NetResult, Request, Response, StatusCode};
 
@@ -91,10 +91,7 @@ pub struct WebSocket<S> {
     closed: bool,
 }
 
-impl<S> WebSocket<S>
-where
-    S: AsyncRead + AsyncWrite + Unpin,
-{
+impl<S> WebSocket<S> {
     /// Create a WebSocket from an existing stream after handshake
     pub fn new(stream: S) -> Self {
         Self {
@@ -124,6 +121,16 @@ where
         base64_encode(hash.as_ref())
     }
 
+    /// Check if the connection is closed
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+impl<S> WebSocket<S>
+where
+    S: AsyncRead + Unpin,
+{
     /// Read a message from the WebSocket
     pub async fn read(&mut self) -> NetResult<Message> {
         if self.closed {
@@ -157,50 +164,6 @@ where
         }
     }
 
-    /// Write a message to the WebSocket
-    pub async fn write(&mut self, message: Message) -> NetResult<()> {
-        if self.closed && !matches!(message, Message::Close(_)) {
-            return Err(NetError::ConnectionClosed);
-        }
-
-        let frame = Frame {
-            fin: true,
-            opcode: message.opcode(),
-            mask: None, // Server frames are not masked
-            payload: message.payload(),
-        };
-
-        self.write_frame(&frame).await
-    }
-
-    /// Send a text message
-    pub async fn send_text(&mut self, text: impl Into<String>) -> NetResult<()> {
-        self.write(Message::Text(text.into())).await
-    }
-
-    /// Send a binary message
-    pub async fn send_binary(&mut self, data: impl Into<Vec<u8>>) -> NetResult<()> {
-        self.write(Message::Binary(data.into())).await
-    }
-
-    /// Send a ping
-    pub async fn ping(&mut self, data: impl Into<Vec<u8>>) -> NetResult<()> {
-        self.write(Message::Ping(data.into())).await
-    }
-
-    /// Send a pong
-    pub async fn pong(&mut self, data: impl Into<Vec<u8>>) -> NetResult<()> {
-        self.write(Message::Pong(data.into())).await
-    }
-
-    /// Close the connection
-    pub async fn close(&mut self, code: Option<u16>, reason: Option<&str>) -> NetResult<()> {
-        let close_data = code.map(|c| (c, reason.unwrap_or("").to_string()));
-        self.write(Message::Close(close_data)).await?;
-        self.closed = true;
-        Ok(())
-    }
-
     /// Read a WebSocket frame
     async fn read_frame(&mut self) -> NetResult<Frame> {
         // Read first two bytes
@@ -251,6 +214,55 @@ where
             payload,
         })
     }
+}
+
+impl<S> WebSocket<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    /// Write a message to the WebSocket
+    pub async fn write(&mut self, message: Message) -> NetResult<()> {
+        if self.closed && !matches!(message, Message::Close(_)) {
+            return Err(NetError::ConnectionClosed);
+        }
+
+        let frame = Frame {
+            fin: true,
+            opcode: message.opcode(),
+            mask: None, // Server frames are not masked
+            payload: message.payload(),
+        };
+
+        self.write_frame(&frame).await
+    }
+
+    /// Send a text message
+    pub async fn send_text(&mut self, text: impl Into<String>) -> NetResult<()> {
+        self.write(Message::Text(text.into())).await
+    }
+
+    /// Send a binary message
+    pub async fn send_binary(&mut self, data: impl Into<Vec<u8>>) -> NetResult<()> {
+        self.write(Message::Binary(data.into())).await
+    }
+
+    /// Send a ping
+    pub async fn ping(&mut self, data: impl Into<Vec<u8>>) -> NetResult<()> {
+        self.write(Message::Ping(data.into())).await
+    }
+
+    /// Send a pong
+    pub async fn pong(&mut self, data: impl Into<Vec<u8>>) -> NetResult<()> {
+        self.write(Message::Pong(data.into())).await
+    }
+
+    /// Close the connection
+    pub async fn close(&mut self, code: Option<u16>, reason: Option<&str>) -> NetResult<()> {
+        let close_data = code.map(|c| (c, reason.unwrap_or("").to_string()));
+        self.write(Message::Close(close_data)).await?;
+        self.closed = true;
+        Ok(())
+    }
 
     /// Write a WebSocket frame
     async fn write_frame(&mut self, frame: &Frame) -> NetResult<()> {
@@ -299,10 +311,28 @@ where
         self.stream.flush().await?;
         Ok(())
     }
+}
 
-    /// Check if the connection is closed
-    pub fn is_closed(&self) -> bool {
-        self.closed
+impl<S> WebSocket<S>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    /// Split into a reading and a writing half
+    ///
+    /// The halves can be used from different tasks, so a server can wait
+    /// for client messages while pushing its own.
+    pub fn split(self) -> (WebSocket<ReadHalf<S>>, WebSocket<WriteHalf<S>>) {
+        let (reader, writer) = tokio::io::split(self.stream);
+        (
+            WebSocket {
+                stream: reader,
+                closed: self.closed,
+            },
+            WebSocket {
+                stream: writer,
+                closed: self.closed,
+            },
+        )
     }
 }
 
@@ -1,72 +1,184 @@
-//! Email client (`SendGrid`)
-
-use std::time::Duration;
-use tracing::{debug, info, warn};
+//! Email delivery across providers
+//!
+//! An [`EmailProvider`] hands a rendered email to one ESP:
+//! [`SendGridProvider`], [`MailgunProvider`], or [`SmtpProvider`] for any
+//! relay reachable over TCP and TLS. [`EmailClient`] renders the template once
+//! and tries its providers in order, so an outage at one ESP fails over to the
+//! next rather than losing a booking confirmation. Permanent failures such as
+//! an invalid recipient are returned without trying the remaining providers.
+//!
+//! Sandboxing happens at two levels. `NotificationConfig::sandbox_mode`
+//! short-circuits every send before any provider is called. A provider listed
+//! in `NotificationConfig::email_sandbox` instead runs in its own test mode:
+//! `SendGrid`'s sandbox mail setting, Mailgun's `o:testmode`, and for SMTP a
+//! session that ends once the relay has accepted the recipient.
+//!
+//! [`SendGridProvider`]: crate::sendgrid::SendGridProvider
+//! [`MailgunProvider`]: crate::mailgun::MailgunProvider
+//! [`SmtpProvider`]: crate::smtp::SmtpProvider
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
 
 use vaya_common::{Timestamp, Uuid};
 
 use crate::error::{NotificationError, NotificationResult};
+use crate::mailgun::MailgunProvider;
+use crate::sendgrid::SendGridProvider;
+use crate::smtp::SmtpProvider;
 use crate::templates::TemplateEngine;
-use crate::types::{AttachmentDisposition, EmailRequest, EmailResult, NotificationStatus};
+use crate::types::{EmailRequest, EmailResult, NotificationStatus};
 use crate::NotificationConfig;
 
-/// `SendGrid` API base URL
-const SENDGRID_API_BASE: &str = "https://api.sendgrid.com/v3";
+/// Built-in email providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmailProviderKind {
+    /// `SendGrid` v3 API
+    SendGrid,
+    /// Mailgun messages API
+    Mailgun,
+    /// SMTP relay
+    Smtp,
+}
+
+impl EmailProviderKind {
+    /// Every built-in provider, in the default failover order
+    pub const ALL: [Self; 3] = [Self::SendGrid, Self::Mailgun, Self::Smtp];
+
+    /// Provider name
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::SendGrid => "sendgrid",
+            Self::Mailgun => "mailgun",
+            Self::Smtp => "smtp",
+        }
+    }
+
+    /// Parse a provider name
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(s.trim()))
+    }
+}
+
+/// An email with its template rendered, ready to hand to a provider
+#[derive(Debug, Clone)]
+pub struct RenderedEmail<'a> {
+    /// The original request (recipient, subject, attachments, headers, tags)
+    pub request: &'a EmailRequest,
+    /// Sender email address
+    pub from_email: &'a str,
+    /// Sender name
+    pub from_name: &'a str,
+    /// Plain text body
+    pub text_body: Option<String>,
+    /// HTML body
+    pub html_body: Option<String>,
+}
+
+/// Delivers rendered emails through one ESP
+#[async_trait]
+pub trait EmailProvider: Send + Sync {
+    /// Provider name, recorded on the [`EmailResult`]
+    fn name(&self) -> &'static str;
+
+    /// Whether sends run in the provider's own test mode
+    fn is_sandbox(&self) -> bool;
 
-/// Email client using `SendGrid`
+    /// Send `email`, retrying transient errors against this provider only
+    async fn send(&self, email: &RenderedEmail<'_>) -> NotificationResult<EmailResult>;
+}
+
+/// Email client that fails over across providers
 pub struct EmailClient {
-    /// HTTP client
-    http_client: reqwest::Client,
-    /// API key
-    api_key: String,
+    /// Providers in the order they are tried
+    providers: Vec<Arc<dyn EmailProvider>>,
     /// Sender email
     from_email: String,
     /// Sender name
     from_name: String,
     /// Template engine
     templates: TemplateEngine,
-    /// Max retries
-    max_retries: u32,
     /// Sandbox mode
     sandbox_mode: bool,
 }
 
 impl EmailClient {
-    /// Create new email client
+    /// Create an email client with every configured provider, in the
+    /// configured failover order
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if no provider is configured or a provider
+    /// cannot be set up.
     pub fn new(config: &NotificationConfig) -> NotificationResult<Self> {
         config.validate_email()?;
 
-        let http_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.request_timeout_secs))
-            .build()
-            .map_err(|e| {
-                NotificationError::Configuration(format!("Failed to create HTTP client: {e}"))
-            })?;
+        let mut client = Self::empty(config);
+        for kind in config.email_provider_order() {
+            let provider: Arc<dyn EmailProvider> = match kind {
+                EmailProviderKind::SendGrid => Arc::new(SendGridProvider::new(config)?),
+                EmailProviderKind::Mailgun => Arc::new(MailgunProvider::new(config)?),
+                EmailProviderKind::Smtp => Arc::new(SmtpProvider::new(config)?),
+            };
+            client = client.with_provider(provider);
+        }
+        Ok(client)
+    }
 
-        Ok(Self {
-            http_client,
-            api_key: config.sendgrid_api_key.clone(),
+    /// Create an email client with the sender and sandbox settings from
+    /// `config` and no providers
+    #[must_use]
+    pub fn empty(config: &NotificationConfig) -> Self {
+        Self {
+            providers: Vec::new(),
             from_email: config.from_email.clone(),
             from_name: config.from_name.clone(),
             templates: TemplateEngine::new(),
-            max_retries: config.max_retries,
             sandbox_mode: config.sandbox_mode,
-        })
+        }
+    }
+
+    /// Add a provider after the existing ones
+    #[must_use]
+    pub fn with_provider(mut self, provider: Arc<dyn EmailProvider>) -> Self {
+        self.providers.push(provider);
+        self
     }
 
-    /// Send an email
+    /// Provider names in failover order
+    #[must_use]
+    pub fn providers(&self) -> Vec<&'static str> {
+        self.providers.iter().map(|p| p.name()).collect()
+    }
+
+    /// Send an email through the first provider that accepts it
+    ///
+    /// # Errors
+    ///
+    /// Returns the request's validation or rendering error, a permanent
+    /// delivery error from the provider that raised it, or the last
+    /// provider's error once every provider has failed.
     pub async fn send(&self, request: &EmailRequest) -> NotificationResult<EmailResult> {
         request.validate()?;
 
         // Render template if needed
         let (text_body, html_body) = if let Some(ref template) = request.template {
+            let locale = request.locale.as_deref();
             let text = self
                 .templates
-                .render(&format!("{template}_text"), &request.context)
+                .render_localized(&format!("{template}_text"), locale, &request.context)
                 .ok();
-            let html = self
-                .templates
-                .render(&format!("{template}_html"), &request.context)?;
+            let html = self.templates.render_localized(
+                &format!("{template}_html"),
+                locale,
+                &request.context,
+            )?;
             (text, Some(html))
         } else {
             (request.text_body.clone(), request.html_body.clone())
@@ -79,203 +191,44 @@ impl EmailClient {
             );
             return Ok(EmailResult {
                 message_id: format!("sandbox_{}", Uuid::new_v4()),
+                provider: "sandbox".to_string(),
                 status: NotificationStatus::Sent,
                 sent_at: Timestamp::now(),
             });
         }
 
-        let payload = self.build_sendgrid_payload(request, text_body, html_body)?;
-
-        let result = self.send_with_retry(&payload).await?;
-
-        Ok(result)
-    }
-
-    /// Build `SendGrid` API payload
-    fn build_sendgrid_payload(
-        &self,
-        request: &EmailRequest,
-        text_body: Option<String>,
-        html_body: Option<String>,
-    ) -> NotificationResult<serde_json::Value> {
-        let mut to = serde_json::json!({
-            "email": request.to_email
-        });
-        if let Some(ref name) = request.to_name {
-            to["name"] = serde_json::json!(name);
-        }
-
-        let mut personalizations = serde_json::json!({
-            "to": [to]
-        });
-
-        // Add custom headers
-        if !request.headers.is_empty() {
-            personalizations["headers"] =
-                serde_json::to_value(&request.headers).unwrap_or_default();
-        }
-
-        let mut payload = serde_json::json!({
-            "personalizations": [personalizations],
-            "from": {
-                "email": self.from_email,
-                "name": self.from_name
-            },
-            "subject": request.subject
-        });
-
-        // Add content
-        let mut content = Vec::new();
-        if let Some(text) = text_body {
-            content.push(serde_json::json!({
-                "type": "text/plain",
-                "value": text
-            }));
-        }
-        if let Some(html) = html_body {
-            content.push(serde_json::json!({
-                "type": "text/html",
-                "value": html
-            }));
-        }
-        payload["content"] = serde_json::json!(content);
-
-        // Add reply-to
-        if let Some(ref reply_to) = request.reply_to {
-            payload["reply_to"] = serde_json::json!({
-                "email": reply_to
-            });
-        }
-
-        // Add categories/tags
-        if !request.tags.is_empty() {
-            payload["categories"] = serde_json::to_value(&request.tags).unwrap_or_default();
-        }
+        let email = RenderedEmail {
+            request,
+            from_email: &self.from_email,
+            from_name: &self.from_name,
+            text_body,
+            html_body,
+        };
 
-        // Add attachments
-        if !request.attachments.is_empty() {
-            let attachments: Vec<serde_json::Value> = request
-                .attachments
-                .iter()
-                .map(|a| {
-                    let mut attachment = serde_json::json!({
-                        "content": a.content,
-                        "filename": a.filename,
-                        "type": a.content_type,
-                        "disposition": match a.disposition {
-                            AttachmentDisposition::Attachment => "attachment",
-                            AttachmentDisposition::Inline => "inline",
-                        }
-                    });
-                    if let Some(ref content_id) = a.content_id {
-                        attachment["content_id"] = serde_json::json!(content_id);
+        let mut last_error = None;
+        for provider in &self.providers {
+            match provider.send(&email).await {
+                Ok(result) => {
+                    if last_error.is_some() {
+                        info!("Email to {} sent via {}", request.to_email, provider.name());
                     }
-                    attachment
-                })
-                .collect();
-            payload["attachments"] = serde_json::json!(attachments);
-        }
-
-        // Add tracking settings
-        payload["tracking_settings"] = serde_json::json!({
-            "click_tracking": {"enable": true},
-            "open_tracking": {"enable": true}
-        });
-
-        Ok(payload)
-    }
-
-    /// Send with retry
-    async fn send_with_retry(
-        &self,
-        payload: &serde_json::Value,
-    ) -> NotificationResult<EmailResult> {
-        let mut last_error = NotificationError::ServiceUnavailable("No attempts made".to_string());
-
-        for attempt in 0..=self.max_retries {
-            if attempt > 0 {
-                let delay = Duration::from_millis(100 * 2_u64.pow(attempt - 1));
-                tokio::time::sleep(delay).await;
-                debug!("Retry attempt {} after {:?}", attempt, delay);
-            }
-
-            match self.send_request(payload).await {
-                Ok(result) => return Ok(result),
+                    return Ok(result);
+                }
+                Err(e) if e.is_permanent() => return Err(e),
                 Err(e) => {
-                    if e.is_retryable() && attempt < self.max_retries {
-                        warn!("Retryable error on attempt {}: {:?}", attempt + 1, e);
-                        last_error = e;
-                        continue;
-                    }
-                    return Err(e);
+                    warn!(
+                        "{} delivery failed, trying next provider: {}",
+                        provider.name(),
+                        e
+                    );
+                    last_error = Some(e);
                 }
             }
         }
 
-        Err(last_error)
-    }
-
-    /// Send single request
-    async fn send_request(&self, payload: &serde_json::Value) -> NotificationResult<EmailResult> {
-        let response = self
-            .http_client
-            .post(format!("{SENDGRID_API_BASE}/mail/send"))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(payload)
-            .send()
-            .await
-            .map_err(NotificationError::from)?;
-
-        let status = response.status();
-
-        // SendGrid returns 202 Accepted on success
-        if status.as_u16() == 202 {
-            let message_id = response
-                .headers()
-                .get("X-Message-Id")
-                .and_then(|v| v.to_str().ok())
-                .unwrap_or("unknown")
-                .to_string();
-
-            info!("Email sent successfully: {}", message_id);
-
-            return Ok(EmailResult {
-                message_id,
-                status: NotificationStatus::Sent,
-                sent_at: Timestamp::now(),
-            });
-        }
-
-        // Handle errors
-        let error_body = response.text().await.unwrap_or_default();
-
-        match status.as_u16() {
-            401 => Err(NotificationError::Configuration(
-                "Invalid API key".to_string(),
-            )),
-            429 => Err(NotificationError::RateLimited {
-                retry_after_secs: 60,
-            }),
-            400 => {
-                // Parse SendGrid error
-                if let Ok(error) = serde_json::from_str::<serde_json::Value>(&error_body) {
-                    let message = error
-                        .get("errors")
-                        .and_then(|e| e.as_array())
-                        .and_then(|arr| arr.first())
-                        .and_then(|e| e.get("message"))
-                        .and_then(|m| m.as_str())
-                        .unwrap_or("Invalid request");
-                    Err(NotificationError::DeliveryFailed(message.to_string()))
-                } else {
-                    Err(NotificationError::DeliveryFailed(error_body))
-                }
-            }
-            _ => Err(NotificationError::ServiceUnavailable(format!(
-                "HTTP {status}: {error_body}"
-            ))),
-        }
+        Err(last_error.unwrap_or_else(|| {
+            NotificationError::Configuration("No email provider configured".to_string())
+        }))
     }
 
     /// Send bulk emails
@@ -296,12 +249,57 @@ impl EmailClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use wiremock::matchers::{body_partial_json, body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Counts sends and fails with the given error
+    struct FailingProvider {
+        error: fn() -> NotificationError,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmailProvider for FailingProvider {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn is_sandbox(&self) -> bool {
+            false
+        }
+
+        async fn send(&self, _email: &RenderedEmail<'_>) -> NotificationResult<EmailResult> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err((self.error)())
+        }
+    }
+
+    fn config() -> NotificationConfig {
+        let mut config = NotificationConfig::with_sendgrid("SG.test", "noreply@vaya.my")
+            .with_mailgun("key-test", "mg.vaya.my");
+        config.max_retries = 0;
+        config
+    }
+
+    fn request() -> EmailRequest {
+        EmailRequest::new("user@example.com", "Your booking VAY-7KQ2M9XP")
+            .with_text("Booking confirmed")
+    }
 
     #[test]
     fn test_email_client_creation() {
-        let config = NotificationConfig::with_sendgrid("SG.test", "noreply@vaya.my");
-        let client = EmailClient::new(&config);
-        assert!(client.is_ok());
+        let sendgrid = NotificationConfig::with_sendgrid("SG.test", "noreply@vaya.my");
+        let client = EmailClient::new(&sendgrid).expect("Should create");
+        assert_eq!(client.providers(), ["sendgrid"]);
+
+        let client = EmailClient::new(
+            &config()
+                .with_email_providers(&[EmailProviderKind::Mailgun, EmailProviderKind::SendGrid]),
+        )
+        .expect("Should create");
+        assert_eq!(client.providers(), ["mailgun", "sendgrid"]);
     }
 
     #[test]
@@ -312,20 +310,152 @@ mod tests {
     }
 
     #[test]
-    fn test_build_payload() {
-        let config = NotificationConfig::with_sendgrid("SG.test", "noreply@vaya.my");
-        let client = EmailClient::new(&config).expect("Should create");
+    fn test_provider_kind_names() {
+        for kind in EmailProviderKind::ALL {
+            assert_eq!(EmailProviderKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(
+            EmailProviderKind::parse("SendGrid"),
+            Some(EmailProviderKind::SendGrid)
+        );
+        assert_eq!(EmailProviderKind::parse("postmark"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_to_next_provider() {
+        let sendgrid = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/mail/send"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("outage"))
+            .expect(1)
+            .mount(&sendgrid)
+            .await;
+        let mailgun = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/mg.vaya.my/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "<20261016.1@mg.vaya.my>",
+                "message": "Queued. Thank you."
+            })))
+            .expect(1)
+            .mount(&mailgun)
+            .await;
+
+        let config = config();
+        let client = EmailClient::empty(&config)
+            .with_provider(Arc::new(
+                SendGridProvider::new(&config)
+                    .expect("sendgrid")
+                    .with_api_base(sendgrid.uri()),
+            ))
+            .with_provider(Arc::new(
+                MailgunProvider::new(&config)
+                    .expect("mailgun")
+                    .with_api_base(mailgun.uri()),
+            ));
+
+        let result = client.send(&request()).await.expect("failover");
+        assert_eq!(result.provider, "mailgun");
+        assert_eq!(result.message_id, "20261016.1@mg.vaya.my");
+    }
 
-        let request = EmailRequest::new("user@example.com", "Test Subject")
-            .with_name("John Doe")
-            .with_text("Hello, world!");
+    #[tokio::test]
+    async fn test_permanent_error_stops_failover() {
+        let first = Arc::new(FailingProvider {
+            error: || NotificationError::InvalidRecipient("no such user".to_string()),
+            calls: AtomicUsize::new(0),
+        });
+        let second = Arc::new(FailingProvider {
+            error: || NotificationError::ServiceUnavailable("down".to_string()),
+            calls: AtomicUsize::new(0),
+        });
+        let client = EmailClient::empty(&config())
+            .with_provider(first.clone())
+            .with_provider(second.clone());
+
+        let err = client.send(&request()).await.expect_err("permanent");
+        assert!(matches!(err, NotificationError::InvalidRecipient(_)));
+        assert_eq!(first.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(second.calls.load(Ordering::SeqCst), 0);
+    }
 
-        let payload = client.build_sendgrid_payload(&request, request.text_body.clone(), None);
+    #[tokio::test]
+    async fn test_all_providers_failing_returns_last_error() {
+        let client = EmailClient::empty(&config())
+            .with_provider(Arc::new(FailingProvider {
+                error: || NotificationError::Timeout,
+                calls: AtomicUsize::new(0),
+            }))
+            .with_provider(Arc::new(FailingProvider {
+                error: || NotificationError::ServiceUnavailable("down".to_string()),
+                calls: AtomicUsize::new(0),
+            }));
+        assert!(matches!(
+            client.send(&request()).await,
+            Err(NotificationError::ServiceUnavailable(_))
+        ));
+
+        let empty = EmailClient::empty(&config());
+        assert!(matches!(
+            empty.send(&request()).await,
+            Err(NotificationError::Configuration(_))
+        ));
+    }
 
-        assert!(payload.is_ok());
-        let payload = payload.expect("Should build");
-        assert!(payload.get("personalizations").is_some());
-        assert!(payload.get("from").is_some());
-        assert!(payload.get("subject").is_some());
+    #[tokio::test]
+    async fn test_sandbox_levels() {
+        // Global sandbox mode never reaches a provider
+        let provider = Arc::new(FailingProvider {
+            error: || NotificationError::Timeout,
+            calls: AtomicUsize::new(0),
+        });
+        let client = EmailClient::empty(&config().sandbox()).with_provider(provider.clone());
+        let result = client.send(&request()).await.expect("sandboxed");
+        assert_eq!(result.provider, "sandbox");
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 0);
+
+        // Provider sandboxes use the ESP's own test mode
+        let sendgrid = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/mail/send"))
+            .and(body_partial_json(serde_json::json!({
+                "mail_settings": {"sandbox_mode": {"enable": true}}
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&sendgrid)
+            .await;
+        let mailgun = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("o:testmode"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "<test@mg.vaya.my>"
+            })))
+            .expect(1)
+            .mount(&mailgun)
+            .await;
+
+        let config = config()
+            .with_provider_sandbox(EmailProviderKind::SendGrid)
+            .with_provider_sandbox(EmailProviderKind::Mailgun);
+        let sendgrid = SendGridProvider::new(&config)
+            .expect("sendgrid")
+            .with_api_base(sendgrid.uri());
+        let mailgun = MailgunProvider::new(&config)
+            .expect("mailgun")
+            .with_api_base(mailgun.uri());
+        assert!(sendgrid.is_sandbox() && mailgun.is_sandbox());
+
+        let result = EmailClient::empty(&config)
+            .with_provider(Arc::new(sendgrid))
+            .send(&request())
+            .await
+            .expect("sendgrid sandbox");
+        assert!(result.message_id.starts_with("sandbox_"));
+        EmailClient::empty(&config)
+            .with_provider(Arc::new(mailgun))
+            .send(&request())
+            .await
+            .expect("mailgun sandbox");
     }
 }
@@ -72,6 +72,10 @@ pub enum NotificationError {
     /// Invalid response
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
+
+    /// Malformed delivery webhook
+    #[error("Invalid webhook payload: {0}")]
+    InvalidWebhook(String),
 }
 
 impl NotificationError {
@@ -116,6 +120,7 @@ impl NotificationError {
             Self::Configuration(_) | Self::TemplateError(_) => 500,
             Self::InvalidRecipient(_)
             | Self::InvalidPhoneNumber(_)
+            | Self::InvalidWebhook(_)
             | Self::Bounced { .. }
             | Self::SpamComplaint { .. } => 400,
             Self::TemplateNotFound(_) => 404,
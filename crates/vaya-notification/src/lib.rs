@@ -9,8 +9,10 @@
 //!
 //! # Supported Providers
 //!
-//! - **Email**: `SendGrid`, Mailgun (via HTTP API)
+//! - **Email**: `SendGrid`, Mailgun (via HTTP API), any SMTP relay (via `vaya-net`),
+//!   tried in order so one provider's outage fails over to the next
 //! - **SMS**: Twilio (via HTTP API)
+//! - **Chat**: `WhatsApp` Business Cloud API, Telegram bots
 //!
 //! # Example
 //!
@@ -35,21 +37,52 @@
 
 pub mod email;
 pub mod error;
+pub mod mailgun;
+pub mod messaging;
+pub mod preview;
+pub mod sendgrid;
 pub mod sms;
+pub mod smtp;
 pub mod templates;
 pub mod types;
+pub mod webhook;
 
-pub use email::EmailClient;
+use std::fmt;
+
+use vaya_common::{resolve_secret, Mask, Redact, SecretSource};
+
+pub use email::{EmailClient, EmailProvider, EmailProviderKind, RenderedEmail};
 pub use error::{NotificationError, NotificationResult};
+pub use mailgun::MailgunProvider;
+pub use messaging::{
+    ChatMessage, ChatRecipient, MessagingChannel, MessagingProvider, MessagingResult, Messenger,
+    TelegramClient, WhatsAppClient,
+};
+pub use preview::{TemplateLint, TemplatePreview, TemplateUsage, TestSendWhitelist};
+pub use sendgrid::SendGridProvider;
 pub use sms::SmsClient;
-pub use templates::TemplateEngine;
+pub use smtp::{SmtpConfig, SmtpProvider, SmtpSecurity};
+pub use templates::{TemplateEngine, DEFAULT_LOCALE, SUPPORTED_LOCALES};
 pub use types::*;
+pub use webhook::{parse_sendgrid_events, parse_twilio_status, DeliveryReport};
 
 /// Notification configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct NotificationConfig {
     /// `SendGrid` API key
     pub sendgrid_api_key: String,
+    /// Mailgun API key
+    pub mailgun_api_key: String,
+    /// Mailgun sending domain
+    pub mailgun_domain: String,
+    /// Mailgun API base URL ([`mailgun::MAILGUN_EU_API_BASE`] for EU domains)
+    pub mailgun_api_base: String,
+    /// SMTP relay
+    pub smtp: Option<SmtpConfig>,
+    /// Email providers in failover order; unconfigured ones are skipped
+    pub email_providers: Vec<EmailProviderKind>,
+    /// Email providers that send in their own test mode
+    pub email_sandbox: Vec<EmailProviderKind>,
     /// Sender email address
     pub from_email: String,
     /// Sender name
@@ -60,6 +93,12 @@ pub struct NotificationConfig {
     pub twilio_auth_token: String,
     /// Twilio phone number
     pub twilio_phone_number: String,
+    /// `WhatsApp` Business Cloud API access token
+    pub whatsapp_access_token: String,
+    /// `WhatsApp` Business phone number ID
+    pub whatsapp_phone_number_id: String,
+    /// Telegram bot token
+    pub telegram_bot_token: String,
     /// Request timeout in seconds
     pub request_timeout_secs: u64,
     /// Maximum retry attempts
@@ -68,15 +107,64 @@ pub struct NotificationConfig {
     pub sandbox_mode: bool,
 }
 
+impl fmt::Debug for NotificationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NotificationConfig")
+            .field(
+                "sendgrid_api_key",
+                &self.sendgrid_api_key.redacted(Mask::Full),
+            )
+            .field(
+                "mailgun_api_key",
+                &self.mailgun_api_key.redacted(Mask::Full),
+            )
+            .field("mailgun_domain", &self.mailgun_domain)
+            .field("mailgun_api_base", &self.mailgun_api_base)
+            .field("smtp", &self.smtp)
+            .field("email_providers", &self.email_providers)
+            .field("email_sandbox", &self.email_sandbox)
+            .field("from_email", &self.from_email)
+            .field("from_name", &self.from_name)
+            .field("twilio_account_sid", &self.twilio_account_sid)
+            .field(
+                "twilio_auth_token",
+                &self.twilio_auth_token.redacted(Mask::Full),
+            )
+            .field("twilio_phone_number", &self.twilio_phone_number)
+            .field(
+                "whatsapp_access_token",
+                &self.whatsapp_access_token.redacted(Mask::Full),
+            )
+            .field("whatsapp_phone_number_id", &self.whatsapp_phone_number_id)
+            .field(
+                "telegram_bot_token",
+                &self.telegram_bot_token.redacted(Mask::Full),
+            )
+            .field("request_timeout_secs", &self.request_timeout_secs)
+            .field("max_retries", &self.max_retries)
+            .field("sandbox_mode", &self.sandbox_mode)
+            .finish()
+    }
+}
+
 impl Default for NotificationConfig {
     fn default() -> Self {
         Self {
             sendgrid_api_key: String::new(),
+            mailgun_api_key: String::new(),
+            mailgun_domain: String::new(),
+            mailgun_api_base: mailgun::MAILGUN_API_BASE.to_string(),
+            smtp: None,
+            email_providers: EmailProviderKind::ALL.to_vec(),
+            email_sandbox: Vec::new(),
             from_email: String::new(),
             from_name: "VAYA Flights".to_string(),
             twilio_account_sid: String::new(),
             twilio_auth_token: String::new(),
             twilio_phone_number: String::new(),
+            whatsapp_access_token: String::new(),
+            whatsapp_phone_number_id: String::new(),
+            telegram_bot_token: String::new(),
             request_timeout_secs: 30,
             max_retries: 3,
             sandbox_mode: false,
@@ -94,6 +182,60 @@ impl NotificationConfig {
         }
     }
 
+    /// Add Mailgun configuration
+    #[must_use]
+    pub fn with_mailgun(mut self, api_key: impl Into<String>, domain: impl Into<String>) -> Self {
+        self.mailgun_api_key = api_key.into();
+        self.mailgun_domain = domain.into();
+        self
+    }
+
+    /// Add an SMTP relay
+    #[must_use]
+    pub fn with_smtp(mut self, smtp: SmtpConfig) -> Self {
+        self.smtp = Some(smtp);
+        self
+    }
+
+    /// Set the order email providers are tried in
+    #[must_use]
+    pub fn with_email_providers(mut self, order: &[EmailProviderKind]) -> Self {
+        self.email_providers = order.to_vec();
+        self
+    }
+
+    /// Send through `provider` in its own test mode
+    #[must_use]
+    pub fn with_provider_sandbox(mut self, provider: EmailProviderKind) -> Self {
+        if !self.email_sandbox.contains(&provider) {
+            self.email_sandbox.push(provider);
+        }
+        self
+    }
+
+    /// Configured email providers in failover order
+    #[must_use]
+    pub fn email_provider_order(&self) -> Vec<EmailProviderKind> {
+        let mut order = Vec::new();
+        for &kind in &self.email_providers {
+            let configured = match kind {
+                EmailProviderKind::SendGrid => self.validate_sendgrid().is_ok(),
+                EmailProviderKind::Mailgun => self.validate_mailgun().is_ok(),
+                EmailProviderKind::Smtp => self.validate_smtp().is_ok(),
+            };
+            if configured && !order.contains(&kind) {
+                order.push(kind);
+            }
+        }
+        order
+    }
+
+    /// Whether `provider` sends in its own test mode
+    #[must_use]
+    pub fn provider_sandbox(&self, provider: EmailProviderKind) -> bool {
+        self.email_sandbox.contains(&provider)
+    }
+
     /// Add Twilio configuration
     #[must_use]
     pub fn with_twilio(
@@ -108,6 +250,25 @@ impl NotificationConfig {
         self
     }
 
+    /// Add `WhatsApp` Business Cloud API configuration
+    #[must_use]
+    pub fn with_whatsapp(
+        mut self,
+        access_token: impl Into<String>,
+        phone_number_id: impl Into<String>,
+    ) -> Self {
+        self.whatsapp_access_token = access_token.into();
+        self.whatsapp_phone_number_id = phone_number_id.into();
+        self
+    }
+
+    /// Add Telegram bot configuration
+    #[must_use]
+    pub fn with_telegram(mut self, bot_token: impl Into<String>) -> Self {
+        self.telegram_bot_token = bot_token.into();
+        self
+    }
+
     /// Set sender name
     #[must_use]
     pub fn with_sender_name(mut self, name: impl Into<String>) -> Self {
@@ -129,21 +290,102 @@ impl NotificationConfig {
         self
     }
 
+    /// Replace `secret://` references in provider credentials with the
+    /// secrets they name
+    ///
+    /// Call once at startup, before validating.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` naming the field whose secret is missing.
+    pub fn resolve_secrets(&mut self, secrets: &dyn SecretSource) -> NotificationResult<()> {
+        let smtp_password = self.smtp.as_mut().map(|smtp| &mut smtp.password);
+        for (field, value) in [
+            ("sendgrid_api_key", Some(&mut self.sendgrid_api_key)),
+            ("mailgun_api_key", Some(&mut self.mailgun_api_key)),
+            ("smtp.password", smtp_password),
+            ("twilio_auth_token", Some(&mut self.twilio_auth_token)),
+            (
+                "whatsapp_access_token",
+                Some(&mut self.whatsapp_access_token),
+            ),
+            ("telegram_bot_token", Some(&mut self.telegram_bot_token)),
+        ] {
+            if let Some(value) = value {
+                resolve_secret(value, secrets)
+                    .map_err(|e| NotificationError::Configuration(format!("{field}: {e}")))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Validate email configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if the sender address is missing or no email
+    /// provider in the failover order is configured.
     pub fn validate_email(&self) -> NotificationResult<()> {
+        if self.from_email.is_empty() {
+            return Err(NotificationError::Configuration(
+                "From email is required".to_string(),
+            ));
+        }
+        if self.email_provider_order().is_empty() {
+            return Err(NotificationError::Configuration(
+                "No email provider configured".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate `SendGrid` configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if the API key is missing.
+    pub fn validate_sendgrid(&self) -> NotificationResult<()> {
         if self.sendgrid_api_key.is_empty() {
             return Err(NotificationError::Configuration(
                 "SendGrid API key is required".to_string(),
             ));
         }
-        if self.from_email.is_empty() {
+        Ok(())
+    }
+
+    /// Validate Mailgun configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if the API key or sending domain is missing.
+    pub fn validate_mailgun(&self) -> NotificationResult<()> {
+        if self.mailgun_api_key.is_empty() {
             return Err(NotificationError::Configuration(
-                "From email is required".to_string(),
+                "Mailgun API key is required".to_string(),
+            ));
+        }
+        if self.mailgun_domain.is_empty() {
+            return Err(NotificationError::Configuration(
+                "Mailgun domain is required".to_string(),
             ));
         }
         Ok(())
     }
 
+    /// Validate SMTP configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if no relay host is set.
+    pub fn validate_smtp(&self) -> NotificationResult<()> {
+        match self.smtp {
+            Some(ref smtp) if !smtp.host.is_empty() && smtp.port != 0 => Ok(()),
+            _ => Err(NotificationError::Configuration(
+                "SMTP relay host is required".to_string(),
+            )),
+        }
+    }
+
     /// Validate SMS configuration
     pub fn validate_sms(&self) -> NotificationResult<()> {
         if self.twilio_account_sid.is_empty() {
@@ -163,6 +405,39 @@ impl NotificationConfig {
         }
         Ok(())
     }
+
+    /// Validate `WhatsApp` configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if the access token or phone number ID is missing.
+    pub fn validate_whatsapp(&self) -> NotificationResult<()> {
+        if self.whatsapp_access_token.is_empty() {
+            return Err(NotificationError::Configuration(
+                "WhatsApp access token is required".to_string(),
+            ));
+        }
+        if self.whatsapp_phone_number_id.is_empty() {
+            return Err(NotificationError::Configuration(
+                "WhatsApp phone number ID is required".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validate Telegram configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if the bot token is missing.
+    pub fn validate_telegram(&self) -> NotificationResult<()> {
+        if self.telegram_bot_token.is_empty() {
+            return Err(NotificationError::Configuration(
+                "Telegram bot token is required".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -206,5 +481,65 @@ mod tests {
 
         let config = NotificationConfig::with_sendgrid("SG.key", "test@vaya.my");
         assert!(config.validate_email().is_ok());
+        assert!(config.validate_whatsapp().is_err());
+        assert!(config.validate_telegram().is_err());
+
+        let config = config
+            .with_whatsapp("EAAG-token", "1234567890")
+            .with_telegram("123:bot-token");
+        assert!(config.validate_whatsapp().is_ok());
+        assert!(config.validate_telegram().is_ok());
+        let debug = format!("{config:?}");
+        assert!(!debug.contains("EAAG-token"));
+        assert!(!debug.contains("bot-token"));
+    }
+
+    #[test]
+    fn test_resolve_secrets() {
+        let secrets: std::collections::HashMap<String, String> = [
+            ("sendgrid".to_string(), "SG.real".to_string()),
+            ("smtp".to_string(), "hunter2".to_string()),
+        ]
+        .into();
+        let mut config = NotificationConfig::with_sendgrid("secret://sendgrid", "noreply@vaya.my")
+            .with_smtp(SmtpConfig::new("smtp.vaya.my").with_credentials("mailer", "secret://smtp"));
+        config.resolve_secrets(&secrets).expect("secrets resolve");
+        assert_eq!(config.sendgrid_api_key, "SG.real");
+        assert_eq!(
+            config.smtp.as_ref().map(|s| s.password.as_str()),
+            Some("hunter2")
+        );
+
+        let mut config = NotificationConfig::default().with_telegram("secret://telegram");
+        let err = config
+            .resolve_secrets(&secrets)
+            .expect_err("missing secret");
+        assert!(err.to_string().contains("telegram_bot_token"));
+    }
+
+    #[test]
+    fn test_email_provider_order() {
+        let config = NotificationConfig::default();
+        assert!(config.email_provider_order().is_empty());
+
+        // SMTP only, no SendGrid key
+        let mut config = NotificationConfig::default()
+            .with_smtp(SmtpConfig::new("smtp.vaya.my").with_credentials("mailer", "hunter2"));
+        config.from_email = "noreply@vaya.my".to_string();
+        assert!(config.validate_email().is_ok());
+        assert_eq!(config.email_provider_order(), [EmailProviderKind::Smtp]);
+        assert!(!format!("{config:?}").contains("hunter2"));
+
+        let config = config
+            .with_mailgun("key-123", "mg.vaya.my")
+            .with_email_providers(&[EmailProviderKind::Mailgun, EmailProviderKind::Smtp])
+            .with_provider_sandbox(EmailProviderKind::Smtp);
+        assert_eq!(
+            config.email_provider_order(),
+            [EmailProviderKind::Mailgun, EmailProviderKind::Smtp]
+        );
+        assert!(config.provider_sandbox(EmailProviderKind::Smtp));
+        assert!(!config.provider_sandbox(EmailProviderKind::Mailgun));
+        assert!(!format!("{config:?}").contains("key-123"));
     }
 }
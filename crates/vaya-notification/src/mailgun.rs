@@ -0,0 +1,339 @@
+//! Mailgun email provider
+
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use tracing::info;
+
+use vaya_common::Timestamp;
+
+use crate::email::{EmailProvider, EmailProviderKind, RenderedEmail};
+use crate::error::{NotificationError, NotificationResult};
+use crate::messaging::{http_client, with_retry};
+use crate::types::{base64_decode, AttachmentDisposition, EmailResult, NotificationStatus};
+use crate::NotificationConfig;
+
+/// Mailgun API base URL for domains in the US region
+pub const MAILGUN_API_BASE: &str = "https://api.mailgun.net/v3";
+
+/// Mailgun API base URL for domains in the EU region
+pub const MAILGUN_EU_API_BASE: &str = "https://api.eu.mailgun.net/v3";
+
+/// Mailgun accepts at most three tags per message
+const MAX_TAGS: usize = 3;
+
+/// Email provider using the Mailgun messages API
+pub struct MailgunProvider {
+    /// HTTP client
+    http_client: reqwest::Client,
+    /// API base URL
+    api_base: String,
+    /// API key
+    api_key: String,
+    /// Sending domain
+    domain: String,
+    /// Max retries
+    max_retries: u32,
+    /// Send in Mailgun's test mode
+    sandbox: bool,
+}
+
+impl MailgunProvider {
+    /// Create new Mailgun provider
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if the Mailgun API key or domain is missing.
+    pub fn new(config: &NotificationConfig) -> NotificationResult<Self> {
+        config.validate_mailgun()?;
+
+        Ok(Self {
+            http_client: http_client(config)?,
+            api_base: config.mailgun_api_base.clone(),
+            api_key: config.mailgun_api_key.clone(),
+            domain: config.mailgun_domain.clone(),
+            max_retries: config.max_retries,
+            sandbox: config.provider_sandbox(EmailProviderKind::Mailgun),
+        })
+    }
+
+    /// Use a different API base URL (a proxy or test server)
+    #[must_use]
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Form fields for a message, in the order they are sent
+    fn build_fields(&self, email: &RenderedEmail<'_>) -> Vec<(String, String)> {
+        let request = email.request;
+        let mut fields = vec![
+            (
+                "from".to_string(),
+                format!("{} <{}>", email.from_name, email.from_email),
+            ),
+            (
+                "to".to_string(),
+                match request.to_name {
+                    Some(ref name) => format!("{name} <{}>", request.to_email),
+                    None => request.to_email.clone(),
+                },
+            ),
+            ("subject".to_string(), request.subject.clone()),
+        ];
+        if let Some(ref text) = email.text_body {
+            fields.push(("text".to_string(), text.clone()));
+        }
+        if let Some(ref html) = email.html_body {
+            fields.push(("html".to_string(), html.clone()));
+        }
+        if let Some(ref reply_to) = request.reply_to {
+            fields.push(("h:Reply-To".to_string(), reply_to.clone()));
+        }
+
+        let mut headers: Vec<_> = request.headers.iter().collect();
+        headers.sort();
+        for (name, value) in headers {
+            fields.push((format!("h:{name}"), value.clone()));
+        }
+        for tag in request.tags.iter().take(MAX_TAGS) {
+            fields.push(("o:tag".to_string(), tag.clone()));
+        }
+        if self.sandbox {
+            fields.push(("o:testmode".to_string(), "yes".to_string()));
+        }
+        fields
+    }
+
+    /// Multipart form with the message fields and attachments
+    fn build_form(
+        fields: &[(String, String)],
+        email: &RenderedEmail<'_>,
+    ) -> NotificationResult<Form> {
+        let mut form = Form::new();
+        for (name, value) in fields {
+            form = form.text(name.clone(), value.clone());
+        }
+
+        for attachment in &email.request.attachments {
+            let content = base64_decode(&attachment.content).ok_or_else(|| {
+                NotificationError::Configuration(format!(
+                    "Attachment {} is not valid base64",
+                    attachment.filename
+                ))
+            })?;
+            // Inline parts are referenced by file name in `cid:` links
+            let (field, filename) = match attachment.disposition {
+                AttachmentDisposition::Attachment => ("attachment", attachment.filename.clone()),
+                AttachmentDisposition::Inline => (
+                    "inline",
+                    attachment
+                        .content_id
+                        .clone()
+                        .unwrap_or_else(|| attachment.filename.clone()),
+                ),
+            };
+            let part = Part::bytes(content)
+                .file_name(filename)
+                .mime_str(&attachment.content_type)
+                .map_err(|e| {
+                    NotificationError::Configuration(format!(
+                        "Invalid attachment content type {}: {e}",
+                        attachment.content_type
+                    ))
+                })?;
+            form = form.part(field, part);
+        }
+
+        Ok(form)
+    }
+
+    /// Send single request
+    async fn send_request(&self, form: Form) -> NotificationResult<EmailResult> {
+        let response = self
+            .http_client
+            .post(format!("{}/{}/messages", self.api_base, self.domain))
+            .basic_auth("api", Some(&self.api_key))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(NotificationError::from)?;
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let json = serde_json::from_str::<serde_json::Value>(&body).ok();
+        let message = json
+            .as_ref()
+            .and_then(|j| j.get("message"))
+            .and_then(|m| m.as_str());
+
+        if status.is_success() {
+            let message_id = json
+                .as_ref()
+                .and_then(|j| j.get("id"))
+                .and_then(|id| id.as_str())
+                .ok_or_else(|| {
+                    NotificationError::InvalidResponse("Mailgun response has no id".to_string())
+                })?
+                .trim_matches(|c| c == '<' || c == '>')
+                .to_string();
+
+            info!("Email sent successfully: {}", message_id);
+
+            return Ok(EmailResult {
+                message_id,
+                provider: self.name().to_string(),
+                status: NotificationStatus::Sent,
+                sent_at: Timestamp::now(),
+            });
+        }
+
+        match status.as_u16() {
+            401 => Err(NotificationError::Configuration(
+                "Invalid Mailgun API key".to_string(),
+            )),
+            429 => Err(NotificationError::RateLimited {
+                retry_after_secs: 60,
+            }),
+            400 => Err(NotificationError::DeliveryFailed(
+                message.unwrap_or("Invalid request").to_string(),
+            )),
+            _ => Err(NotificationError::ServiceUnavailable(format!(
+                "HTTP {status}: {body}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailProvider for MailgunProvider {
+    fn name(&self) -> &'static str {
+        EmailProviderKind::Mailgun.as_str()
+    }
+
+    fn is_sandbox(&self) -> bool {
+        self.sandbox
+    }
+
+    async fn send(&self, email: &RenderedEmail<'_>) -> NotificationResult<EmailResult> {
+        let fields = self.build_fields(email);
+        // Validate attachments once; a multipart body can only be sent once
+        Self::build_form(&fields, email)?;
+        with_retry(self.max_retries, || async {
+            self.send_request(Self::build_form(&fields, email)?).await
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EmailAttachment, EmailRequest};
+
+    use wiremock::matchers::{basic_auth, body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config() -> NotificationConfig {
+        let mut config = NotificationConfig::default().with_mailgun("key-test", "mg.vaya.my");
+        config.from_email = "noreply@vaya.my".to_string();
+        config.max_retries = 0;
+        config
+    }
+
+    fn rendered(request: &EmailRequest) -> RenderedEmail<'_> {
+        RenderedEmail {
+            request,
+            from_email: "noreply@vaya.my",
+            from_name: "VAYA Flights",
+            text_body: request.text_body.clone(),
+            html_body: Some("<p>Booking confirmed</p>".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_mailgun_requires_domain() {
+        let mut config = config();
+        config.mailgun_domain.clear();
+        assert!(MailgunProvider::new(&config).is_err());
+    }
+
+    #[test]
+    fn test_build_fields() {
+        let provider = MailgunProvider::new(&config()).expect("provider");
+        let request = EmailRequest::new("user@example.com", "Booking VAY-7KQ2M9XP")
+            .with_name("Aisyah")
+            .with_text("Booking confirmed")
+            .with_tag("booking_confirmation")
+            .with_tag("b")
+            .with_tag("c")
+            .with_tag("d");
+
+        let fields = provider.build_fields(&rendered(&request));
+        let field = |name: &str| {
+            fields
+                .iter()
+                .filter(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(field("from"), ["VAYA Flights <noreply@vaya.my>"]);
+        assert_eq!(field("to"), ["Aisyah <user@example.com>"]);
+        assert_eq!(field("text"), ["Booking confirmed"]);
+        assert_eq!(field("o:tag").len(), MAX_TAGS);
+        assert!(field("o:testmode").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_with_attachment() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/mg.vaya.my/messages"))
+            .and(basic_auth("api", "key-test"))
+            .and(body_string_contains("filename=\"eticket.pdf\""))
+            .and(body_string_contains("%PDF-1.4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "<20261016.42@mg.vaya.my>",
+                "message": "Queued. Thank you."
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = MailgunProvider::new(&config())
+            .expect("provider")
+            .with_api_base(server.uri());
+        let request = EmailRequest::new("user@example.com", "Your e-ticket")
+            .with_text("Attached")
+            .with_attachment(EmailAttachment::new(
+                "eticket.pdf",
+                "application/pdf",
+                b"%PDF-1.4",
+            ));
+
+        let result = provider.send(&rendered(&request)).await.expect("sent");
+        assert_eq!(result.message_id, "20261016.42@mg.vaya.my");
+        assert_eq!(result.provider, "mailgun");
+    }
+
+    #[tokio::test]
+    async fn test_error_mapping() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "message": "to parameter is not a valid address"
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = MailgunProvider::new(&config())
+            .expect("provider")
+            .with_api_base(server.uri());
+        let request = EmailRequest::new("user@example.com", "Hello").with_text("Hi");
+        match provider.send(&rendered(&request)).await {
+            Err(NotificationError::DeliveryFailed(message)) => {
+                assert_eq!(message, "to parameter is not a valid address");
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,845 @@
+//! Chat messaging (`WhatsApp`, Telegram)
+//!
+//! Many users in the region read `WhatsApp` long before email. A
+//! [`MessagingProvider`] delivers a [`ChatMessage`] over one chat channel:
+//!
+//! - [`WhatsAppClient`] sends pre-approved template messages through the
+//!   `WhatsApp` Business Cloud API. Business-initiated conversations must
+//!   use templates, so each supported notification type maps to an approved
+//!   template whose body parameters are filled from the message context.
+//! - [`TelegramClient`] sends the notification's `_text` template through
+//!   the Telegram Bot API.
+//!
+//! [`Messenger`] picks the channel per user: the recipient's preferred
+//! channel first, then any other channel they have linked, so a failed
+//! `WhatsApp` send falls back to Telegram.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tracing::{debug, info, warn};
+
+use vaya_common::{Timestamp, Uuid};
+
+use crate::error::{NotificationError, NotificationResult};
+use crate::templates::TemplateEngine;
+use crate::types::{NotificationStatus, NotificationType};
+use crate::NotificationConfig;
+
+/// `WhatsApp` Cloud API base URL
+const WHATSAPP_API_BASE: &str = "https://graph.facebook.com/v19.0";
+
+/// Telegram Bot API base URL
+const TELEGRAM_API_BASE: &str = "https://api.telegram.org";
+
+/// Chat channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessagingChannel {
+    /// `WhatsApp` Business
+    WhatsApp,
+    /// Telegram bot
+    Telegram,
+}
+
+impl MessagingChannel {
+    /// Every channel, in fallback order
+    pub const ALL: [Self; 2] = [Self::WhatsApp, Self::Telegram];
+
+    /// Channel name
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::WhatsApp => "whatsapp",
+            Self::Telegram => "telegram",
+        }
+    }
+
+    /// Parse a name produced by [`MessagingChannel::as_str`]
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|channel| channel.as_str() == s)
+    }
+}
+
+/// A notification to send over chat
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    /// Notification type, which selects the template
+    pub notification_type: NotificationType,
+    /// Template context
+    pub context: HashMap<String, Value>,
+    /// Template locale; `None` uses the default locale
+    pub locale: Option<String>,
+}
+
+impl ChatMessage {
+    /// Create a message of the given type
+    #[must_use]
+    pub fn new(notification_type: NotificationType) -> Self {
+        Self {
+            notification_type,
+            context: HashMap::new(),
+            locale: None,
+        }
+    }
+
+    /// Add context
+    #[must_use]
+    pub fn with_context(mut self, key: impl Into<String>, value: impl serde::Serialize) -> Self {
+        if let Ok(v) = serde_json::to_value(value) {
+            self.context.insert(key.into(), v);
+        }
+        self
+    }
+
+    /// Set the template locale
+    #[must_use]
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// A context value as template parameter text
+    fn param(&self, key: &str) -> NotificationResult<String> {
+        match self.context.get(key) {
+            Some(Value::String(s)) => Ok(s.clone()),
+            Some(Value::Null) | None => Err(NotificationError::TemplateError(format!(
+                "Missing template variable: {key}"
+            ))),
+            Some(value) => Ok(value.to_string()),
+        }
+    }
+}
+
+/// Chat delivery result
+#[derive(Debug, Clone)]
+pub struct MessagingResult {
+    /// Channel the message went out on
+    pub channel: MessagingChannel,
+    /// Message ID from the provider
+    pub message_id: String,
+    /// Status
+    pub status: NotificationStatus,
+    /// Sent timestamp
+    pub sent_at: Timestamp,
+}
+
+/// Delivers chat messages over one channel
+#[async_trait]
+pub trait MessagingProvider: Send + Sync {
+    /// Channel this provider sends on
+    fn channel(&self) -> MessagingChannel;
+
+    /// Send `message` to `to`, a phone number or chat ID for the channel
+    async fn send(&self, to: &str, message: &ChatMessage) -> NotificationResult<MessagingResult>;
+}
+
+/// A user's chat addresses and preferred channel
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChatRecipient {
+    /// `WhatsApp` number (E.164 format)
+    pub whatsapp_phone: Option<String>,
+    /// Telegram chat ID from the bot's `/start` handshake
+    pub telegram_chat_id: Option<String>,
+    /// Channel the user asked to be messaged on
+    pub preferred: Option<MessagingChannel>,
+}
+
+impl ChatRecipient {
+    /// Create a recipient with no linked channels
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Link a `WhatsApp` number
+    #[must_use]
+    pub fn with_whatsapp(mut self, phone: impl Into<String>) -> Self {
+        self.whatsapp_phone = Some(phone.into());
+        self
+    }
+
+    /// Link a Telegram chat
+    #[must_use]
+    pub fn with_telegram(mut self, chat_id: impl Into<String>) -> Self {
+        self.telegram_chat_id = Some(chat_id.into());
+        self
+    }
+
+    /// Set the preferred channel
+    #[must_use]
+    pub fn with_preferred(mut self, channel: MessagingChannel) -> Self {
+        self.preferred = Some(channel);
+        self
+    }
+
+    /// Address on a channel, if linked
+    #[must_use]
+    pub fn address(&self, channel: MessagingChannel) -> Option<&str> {
+        match channel {
+            MessagingChannel::WhatsApp => self.whatsapp_phone.as_deref(),
+            MessagingChannel::Telegram => self.telegram_chat_id.as_deref(),
+        }
+        .filter(|a| !a.is_empty())
+    }
+
+    /// Linked channels, preferred first
+    #[must_use]
+    pub fn channels(&self) -> Vec<MessagingChannel> {
+        self.preferred
+            .into_iter()
+            .chain(MessagingChannel::ALL)
+            .fold(Vec::new(), |mut channels, channel| {
+                if self.address(channel).is_some() && !channels.contains(&channel) {
+                    channels.push(channel);
+                }
+                channels
+            })
+    }
+}
+
+/// Sends chat messages on the best channel for each user
+#[derive(Default, Clone)]
+pub struct Messenger {
+    providers: HashMap<MessagingChannel, Arc<dyn MessagingProvider>>,
+}
+
+impl Messenger {
+    /// Create a messenger with no providers
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a provider, replacing any for the same channel
+    #[must_use]
+    pub fn with_provider(mut self, provider: Arc<dyn MessagingProvider>) -> Self {
+        self.providers.insert(provider.channel(), provider);
+        self
+    }
+
+    /// Channels with a provider
+    #[must_use]
+    pub fn has_channel(&self, channel: MessagingChannel) -> bool {
+        self.providers.contains_key(&channel)
+    }
+
+    /// Send on the recipient's channels in order until one succeeds
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidRecipient` if the recipient has no linked channel
+    /// with a provider, otherwise the error from the last channel tried.
+    pub async fn send(
+        &self,
+        recipient: &ChatRecipient,
+        message: &ChatMessage,
+    ) -> NotificationResult<MessagingResult> {
+        let mut last_error = None;
+        for channel in recipient.channels() {
+            let (Some(provider), Some(to)) =
+                (self.providers.get(&channel), recipient.address(channel))
+            else {
+                continue;
+            };
+            match provider.send(to, message).await {
+                Ok(result) => return Ok(result),
+                Err(e) => {
+                    warn!(
+                        "{} delivery failed, trying next channel: {}",
+                        channel.as_str(),
+                        e
+                    );
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            NotificationError::InvalidRecipient("No chat channel linked".to_string())
+        }))
+    }
+}
+
+/// Approved `WhatsApp` template and its ordered body parameters
+struct WhatsAppTemplate {
+    name: &'static str,
+    params: &'static [&'static str],
+}
+
+/// Template for a notification type; other types are not sent on `WhatsApp`
+fn whatsapp_template(notification_type: NotificationType) -> Option<WhatsAppTemplate> {
+    let (name, params): (&'static str, &'static [&'static str]) = match notification_type {
+        NotificationType::BookingConfirmation => (
+            "vaya_booking_confirmation",
+            &[
+                "passenger_name",
+                "booking_ref",
+                "origin",
+                "destination",
+                "departure_date",
+                "flight_number",
+            ],
+        ),
+        NotificationType::PriceAlert => (
+            "vaya_price_alert",
+            &["origin", "destination", "currency", "new_price", "savings"],
+        ),
+        NotificationType::FlightReminder => (
+            "vaya_flight_reminder",
+            &["flight_number", "origin", "destination", "hours_until"],
+        ),
+        _ => return None,
+    };
+    Some(WhatsAppTemplate { name, params })
+}
+
+/// `WhatsApp` template language code for a locale (`ms`, `zh-CN` -> `zh_CN`)
+fn whatsapp_language(locale: Option<&str>) -> String {
+    let locale = locale.unwrap_or(crate::DEFAULT_LOCALE).trim();
+    let mut parts = locale.split(['-', '_']);
+    let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+    match (language.as_str(), parts.next()) {
+        ("", _) => crate::DEFAULT_LOCALE.to_string(),
+        ("zh", None) => "zh_CN".to_string(),
+        (_, Some(region)) => format!("{language}_{}", region.to_ascii_uppercase()),
+        (_, None) => language,
+    }
+}
+
+/// Run `send`, retrying retryable errors with exponential backoff
+pub(crate) async fn with_retry<T, F, Fut>(max_retries: u32, mut send: F) -> NotificationResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = NotificationResult<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match send().await {
+            Err(e) if e.is_retryable() && attempt < max_retries => {
+                attempt += 1;
+                let delay = Duration::from_millis(100 * 2_u64.pow(attempt - 1));
+                warn!("Retryable error on attempt {}: {:?}", attempt, e);
+                tokio::time::sleep(delay).await;
+                debug!("Retry attempt {} after {:?}", attempt, delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+pub(crate) fn http_client(config: &NotificationConfig) -> NotificationResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.request_timeout_secs))
+        .build()
+        .map_err(|e| NotificationError::Configuration(format!("Failed to create HTTP client: {e}")))
+}
+
+/// `WhatsApp` Business Cloud API client
+pub struct WhatsAppClient {
+    /// HTTP client
+    http_client: reqwest::Client,
+    /// API base URL
+    api_base: String,
+    /// Access token
+    access_token: String,
+    /// Sending phone number ID
+    phone_number_id: String,
+    /// Max retries
+    max_retries: u32,
+    /// Sandbox mode
+    sandbox_mode: bool,
+}
+
+impl WhatsAppClient {
+    /// Create new `WhatsApp` client
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if `WhatsApp` is not configured.
+    pub fn new(config: &NotificationConfig) -> NotificationResult<Self> {
+        config.validate_whatsapp()?;
+        Ok(Self {
+            http_client: http_client(config)?,
+            api_base: WHATSAPP_API_BASE.to_string(),
+            access_token: config.whatsapp_access_token.clone(),
+            phone_number_id: config.whatsapp_phone_number_id.clone(),
+            max_retries: config.max_retries,
+            sandbox_mode: config.sandbox_mode,
+        })
+    }
+
+    /// Use a different API base URL (a proxy or test server)
+    #[must_use]
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Cloud API request body for a template message
+    fn payload(to: &str, message: &ChatMessage) -> NotificationResult<Value> {
+        let template = whatsapp_template(message.notification_type).ok_or_else(|| {
+            NotificationError::TemplateNotFound(format!(
+                "No WhatsApp template for {}",
+                message.notification_type.template_name()
+            ))
+        })?;
+        let parameters = template
+            .params
+            .iter()
+            .map(|key| Ok(json!({ "type": "text", "text": message.param(key)? })))
+            .collect::<NotificationResult<Vec<_>>>()?;
+
+        Ok(json!({
+            "messaging_product": "whatsapp",
+            "to": to.trim_start_matches('+'),
+            "type": "template",
+            "template": {
+                "name": template.name,
+                "language": { "code": whatsapp_language(message.locale.as_deref()) },
+                "components": [{ "type": "body", "parameters": parameters }],
+            },
+        }))
+    }
+
+    /// Send single request
+    async fn send_request(&self, payload: &Value) -> NotificationResult<String> {
+        let url = format!("{}/{}/messages", self.api_base, self.phone_number_id);
+        let response = self
+            .http_client
+            .post(&url)
+            .bearer_auth(&self.access_token)
+            .json(payload)
+            .send()
+            .await
+            .map_err(NotificationError::from)?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+
+        if status.is_success() {
+            return body
+                .pointer("/messages/0/id")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    NotificationError::InvalidResponse("WhatsApp response has no message ID".into())
+                });
+        }
+
+        let error_message = body
+            .pointer("/error/message")
+            .and_then(Value::as_str)
+            .unwrap_or("Unknown error")
+            .to_string();
+        let error_code = body
+            .pointer("/error/code")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+
+        match (status.as_u16(), error_code) {
+            (401, _) | (_, 190) => Err(NotificationError::Configuration(
+                "Invalid WhatsApp access token".to_string(),
+            )),
+            (429, _) | (_, 130_429 | 131_056) => Err(NotificationError::RateLimited {
+                retry_after_secs: 60,
+            }),
+            (_, 131_026) => Err(NotificationError::InvalidRecipient(error_message)),
+            (500..=599, _) => Err(NotificationError::ServiceUnavailable(error_message)),
+            _ => Err(NotificationError::DeliveryFailed(error_message)),
+        }
+    }
+}
+
+#[async_trait]
+impl MessagingProvider for WhatsAppClient {
+    fn channel(&self) -> MessagingChannel {
+        MessagingChannel::WhatsApp
+    }
+
+    async fn send(&self, to: &str, message: &ChatMessage) -> NotificationResult<MessagingResult> {
+        if !to.starts_with('+') {
+            return Err(NotificationError::InvalidPhoneNumber(
+                "Phone number must be in E.164 format (e.g., +60123456789)".to_string(),
+            ));
+        }
+        let payload = Self::payload(to, message)?;
+
+        let message_id = if self.sandbox_mode {
+            info!(
+                "Sandbox mode: would send WhatsApp template {} to {}",
+                payload["template"]["name"], to
+            );
+            format!("sandbox_{}", Uuid::new_v4())
+        } else {
+            let id = with_retry(self.max_retries, || self.send_request(&payload)).await?;
+            info!("WhatsApp message sent successfully: {} to {}", id, to);
+            id
+        };
+
+        Ok(MessagingResult {
+            channel: MessagingChannel::WhatsApp,
+            message_id,
+            status: NotificationStatus::Sent,
+            sent_at: Timestamp::now(),
+        })
+    }
+}
+
+/// Telegram bot client
+pub struct TelegramClient {
+    /// HTTP client
+    http_client: reqwest::Client,
+    /// API base URL
+    api_base: String,
+    /// Bot token
+    bot_token: String,
+    /// Template engine
+    templates: TemplateEngine,
+    /// Max retries
+    max_retries: u32,
+    /// Sandbox mode
+    sandbox_mode: bool,
+}
+
+impl TelegramClient {
+    /// Create new Telegram client
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if Telegram is not configured.
+    pub fn new(config: &NotificationConfig) -> NotificationResult<Self> {
+        config.validate_telegram()?;
+        Ok(Self {
+            http_client: http_client(config)?,
+            api_base: TELEGRAM_API_BASE.to_string(),
+            bot_token: config.telegram_bot_token.clone(),
+            templates: TemplateEngine::new(),
+            max_retries: config.max_retries,
+            sandbox_mode: config.sandbox_mode,
+        })
+    }
+
+    /// Use a different API base URL (a proxy or test server)
+    #[must_use]
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Send single request
+    async fn send_request(&self, chat_id: &str, text: &str) -> NotificationResult<String> {
+        let url = format!("{}/bot{}/sendMessage", self.api_base, self.bot_token);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&json!({ "chat_id": chat_id, "text": text }))
+            .send()
+            .await
+            .map_err(NotificationError::from)?;
+
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+
+        if body.get("ok").and_then(Value::as_bool) == Some(true) {
+            return body
+                .pointer("/result/message_id")
+                .and_then(Value::as_i64)
+                .map(|id| format!("{chat_id}:{id}"))
+                .ok_or_else(|| {
+                    NotificationError::InvalidResponse("Telegram response has no message ID".into())
+                });
+        }
+
+        let description = body
+            .get("description")
+            .and_then(Value::as_str)
+            .unwrap_or("Unknown error")
+            .to_string();
+
+        match status.as_u16() {
+            401 | 404 => Err(NotificationError::Configuration(
+                "Invalid Telegram bot token".to_string(),
+            )),
+            // Blocked by the user, or the chat no longer exists
+            400 | 403 => Err(NotificationError::InvalidRecipient(description)),
+            429 => Err(NotificationError::RateLimited {
+                retry_after_secs: body
+                    .pointer("/parameters/retry_after")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(60),
+            }),
+            500..=599 => Err(NotificationError::ServiceUnavailable(description)),
+            _ => Err(NotificationError::DeliveryFailed(description)),
+        }
+    }
+}
+
+#[async_trait]
+impl MessagingProvider for TelegramClient {
+    fn channel(&self) -> MessagingChannel {
+        MessagingChannel::Telegram
+    }
+
+    async fn send(
+        &self,
+        chat_id: &str,
+        message: &ChatMessage,
+    ) -> NotificationResult<MessagingResult> {
+        let template = format!("{}_text", message.notification_type.template_name());
+        let text = self.templates.render_localized(
+            &template,
+            message.locale.as_deref(),
+            &message.context,
+        )?;
+
+        let message_id = if self.sandbox_mode {
+            info!(
+                "Sandbox mode: would send Telegram {} to {}",
+                template, chat_id
+            );
+            format!("sandbox_{}", Uuid::new_v4())
+        } else {
+            let id = with_retry(self.max_retries, || self.send_request(chat_id, &text)).await?;
+            info!("Telegram message sent successfully: {}", id);
+            id
+        };
+
+        Ok(MessagingResult {
+            channel: MessagingChannel::Telegram,
+            message_id,
+            status: NotificationStatus::Sent,
+            sent_at: Timestamp::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn booking_confirmation() -> ChatMessage {
+        ChatMessage::new(NotificationType::BookingConfirmation)
+            .with_context("passenger_name", "Aisyah")
+            .with_context("booking_ref", "VAY-7KQ2M9XP")
+            .with_context("origin", "KUL")
+            .with_context("destination", "NRT")
+            .with_context("departure_date", "2030-06-01")
+            .with_context("flight_number", "MH88")
+            .with_context("currency", "MYR")
+            .with_context("total_amount", "1,500.00")
+    }
+
+    fn config() -> NotificationConfig {
+        NotificationConfig::default()
+            .with_whatsapp("EAAG-token", "1055")
+            .with_telegram("123:abc")
+    }
+
+    /// Records sends and fails when told to
+    struct FakeProvider {
+        channel: MessagingChannel,
+        fail: bool,
+        sent: Mutex<Vec<String>>,
+    }
+
+    impl FakeProvider {
+        fn new(channel: MessagingChannel, fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                channel,
+                fail,
+                sent: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl MessagingProvider for FakeProvider {
+        fn channel(&self) -> MessagingChannel {
+            self.channel
+        }
+
+        async fn send(
+            &self,
+            to: &str,
+            _message: &ChatMessage,
+        ) -> NotificationResult<MessagingResult> {
+            self.sent.lock().expect("lock").push(to.to_string());
+            if self.fail {
+                return Err(NotificationError::DeliveryFailed("down".to_string()));
+            }
+            Ok(MessagingResult {
+                channel: self.channel,
+                message_id: format!("{}-1", self.channel.as_str()),
+                status: NotificationStatus::Sent,
+                sent_at: Timestamp::now(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_recipient_channels() {
+        let recipient = ChatRecipient::new()
+            .with_whatsapp("+60123456789")
+            .with_telegram("42");
+        assert_eq!(
+            recipient.channels(),
+            vec![MessagingChannel::WhatsApp, MessagingChannel::Telegram]
+        );
+        assert_eq!(
+            recipient
+                .clone()
+                .with_preferred(MessagingChannel::Telegram)
+                .channels(),
+            vec![MessagingChannel::Telegram, MessagingChannel::WhatsApp]
+        );
+        // A preference for an unlinked channel is ignored
+        let recipient = ChatRecipient::new()
+            .with_telegram("42")
+            .with_preferred(MessagingChannel::WhatsApp);
+        assert_eq!(recipient.channels(), vec![MessagingChannel::Telegram]);
+
+        assert_eq!(
+            MessagingChannel::parse("whatsapp"),
+            Some(MessagingChannel::WhatsApp)
+        );
+        assert_eq!(MessagingChannel::parse("signal"), None);
+    }
+
+    #[tokio::test]
+    async fn test_messenger_falls_back() {
+        let whatsapp = FakeProvider::new(MessagingChannel::WhatsApp, true);
+        let telegram = FakeProvider::new(MessagingChannel::Telegram, false);
+        let messenger = Messenger::new()
+            .with_provider(whatsapp.clone())
+            .with_provider(telegram.clone());
+
+        let recipient = ChatRecipient::new()
+            .with_whatsapp("+60123456789")
+            .with_telegram("42");
+        let result = messenger
+            .send(&recipient, &booking_confirmation())
+            .await
+            .expect("send");
+        assert_eq!(result.channel, MessagingChannel::Telegram);
+        assert_eq!(*whatsapp.sent.lock().expect("lock"), vec!["+60123456789"]);
+        assert_eq!(*telegram.sent.lock().expect("lock"), vec!["42"]);
+
+        let err = messenger
+            .send(&ChatRecipient::new(), &booking_confirmation())
+            .await
+            .expect_err("no channel");
+        assert!(matches!(err, NotificationError::InvalidRecipient(_)));
+
+        let only_whatsapp = ChatRecipient::new().with_whatsapp("+60123456789");
+        let err = messenger
+            .send(&only_whatsapp, &booking_confirmation())
+            .await
+            .expect_err("whatsapp down");
+        assert!(matches!(err, NotificationError::DeliveryFailed(_)));
+    }
+
+    #[test]
+    fn test_whatsapp_payload() {
+        let payload =
+            WhatsAppClient::payload("+60123456789", &booking_confirmation().with_locale("zh"))
+                .expect("payload");
+        assert_eq!(payload["to"], "60123456789");
+        assert_eq!(payload["template"]["name"], "vaya_booking_confirmation");
+        assert_eq!(payload["template"]["language"]["code"], "zh_CN");
+        let params = &payload["template"]["components"][0]["parameters"];
+        assert_eq!(params[0]["text"], "Aisyah");
+        assert_eq!(params[5]["text"], "MH88");
+
+        let missing = ChatMessage::new(NotificationType::PriceAlert).with_context("origin", "KUL");
+        assert!(matches!(
+            WhatsAppClient::payload("+60123456789", &missing),
+            Err(NotificationError::TemplateError(_))
+        ));
+        assert!(matches!(
+            WhatsAppClient::payload(
+                "+60123456789",
+                &ChatMessage::new(NotificationType::Marketing)
+            ),
+            Err(NotificationError::TemplateNotFound(_))
+        ));
+
+        assert_eq!(whatsapp_language(None), "en");
+        assert_eq!(whatsapp_language(Some("ms")), "ms");
+        assert_eq!(whatsapp_language(Some("en-gb")), "en_GB");
+    }
+
+    #[tokio::test]
+    async fn test_whatsapp_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/1055/messages"))
+            .and(header("authorization", "Bearer EAAG-token"))
+            .and(body_partial_json(json!({
+                "type": "template",
+                "template": { "name": "vaya_booking_confirmation" },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "messaging_product": "whatsapp",
+                "messages": [{ "id": "wamid.HBgM" }],
+            })))
+            .mount(&server)
+            .await;
+
+        let client = WhatsAppClient::new(&config())
+            .expect("client")
+            .with_api_base(server.uri());
+        let result = client
+            .send("+60123456789", &booking_confirmation())
+            .await
+            .expect("send");
+        assert_eq!(result.message_id, "wamid.HBgM");
+        assert_eq!(result.channel, MessagingChannel::WhatsApp);
+    }
+
+    #[tokio::test]
+    async fn test_telegram_send() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendMessage"))
+            .and(body_partial_json(json!({ "chat_id": "42" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": true,
+                "result": { "message_id": 7 },
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/bot123:abc/sendMessage"))
+            .and(body_partial_json(json!({ "chat_id": "99" })))
+            .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+                "ok": false,
+                "error_code": 403,
+                "description": "Forbidden: bot was blocked by the user",
+            })))
+            .mount(&server)
+            .await;
+
+        let client = TelegramClient::new(&config())
+            .expect("client")
+            .with_api_base(server.uri());
+        let result = client
+            .send("42", &booking_confirmation())
+            .await
+            .expect("send");
+        assert_eq!(result.message_id, "42:7");
+
+        let err = client
+            .send("99", &booking_confirmation())
+            .await
+            .expect_err("blocked");
+        assert!(matches!(err, NotificationError::InvalidRecipient(_)));
+    }
+}
@@ -0,0 +1,435 @@
+//! Template previews, linting, and test sends
+//!
+//! Lets admins see what a template renders to before it reaches customers:
+//! [`TemplateEngine::preview`] renders the HTML and text variants with a
+//! sample or supplied context, [`TemplateEngine::lint`] checks templates
+//! against the context each [`NotificationType`] supplies, and
+//! [`TemplateEngine::test_email`] builds a test send restricted to a
+//! [`TestSendWhitelist`].
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::error::{NotificationError, NotificationResult};
+use crate::templates::{split_locale, TemplateEngine};
+use crate::types::{EmailRequest, NotificationType};
+
+/// Variables referenced by a template
+///
+/// Returns the root name of each `{{expression}}` and of each block helper
+/// argument (`{{#if name}}`). Paths inside `{{#each}}` blocks are relative
+/// to the item and are reported as-is.
+#[must_use]
+pub fn template_variables(source: &str) -> BTreeSet<String> {
+    let mut variables = BTreeSet::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("}}") else {
+            break;
+        };
+        let expression = rest[..end].trim_start_matches(['{', '~']).trim();
+        rest = &rest[end + 2..];
+
+        let mut tokens = expression.split_whitespace();
+        let name = match tokens.next() {
+            Some(t) if t.starts_with('#') => tokens.next(),
+            Some(t) if t.starts_with(['/', '!', '>']) || t == "else" => None,
+            other => other,
+        };
+        let Some(name) = name else {
+            continue;
+        };
+        let root = name.split(['.', '/']).next().unwrap_or(name);
+        if !root.is_empty()
+            && root != "this"
+            && !root.starts_with(['@', '"', '\''])
+            && !root.starts_with(|c: char| c.is_ascii_digit())
+        {
+            variables.insert(root.to_string());
+        }
+    }
+    variables
+}
+
+/// Sample value for a context variable
+#[must_use]
+pub fn sample_value(variable: &str) -> serde_json::Value {
+    match variable {
+        "passenger_name" => serde_json::json!("Aisyah Rahman"),
+        "name" => serde_json::json!("Aisyah"),
+        "booking_ref" => serde_json::json!("VAY123"),
+        "ticket_number" => serde_json::json!("2321234567890"),
+        "origin" => serde_json::json!("KUL"),
+        "destination" => serde_json::json!("NRT"),
+        "departure_date" => serde_json::json!("2025-02-15"),
+        "flight_number" => serde_json::json!("MH88"),
+        "currency" => serde_json::json!("MYR"),
+        "total_amount" | "amount" => serde_json::json!("1,500.00"),
+        "new_price" => serde_json::json!("899.00"),
+        "old_price" => serde_json::json!("1,099.00"),
+        "savings" => serde_json::json!("200.00"),
+        "hours_until" => serde_json::json!(24),
+        "airport_terminal" => serde_json::json!("KLIA Terminal 1"),
+        "booking_url" => serde_json::json!("https://vaya.my/book/sample"),
+        "reset_link" => serde_json::json!("https://vaya.my/reset/sample"),
+        "assistance" => serde_json::json!([{
+            "name": "RAHMAN/AISYAH",
+            "needs": ["Wheelchair to aircraft seat (cannot climb steps)"],
+        }]),
+        "passengers" => serde_json::json!(["RAHMAN/AISYAH MS", "RAHMAN/IMAN MSTR"]),
+        "segments" => serde_json::json!([{
+            "flight_number": "MH88",
+            "origin": "KUL",
+            "departure": "2025-02-15 23:30 +08:00",
+            "destination": "NRT",
+            "arrival": "2025-02-16 07:30 +09:00",
+        }]),
+        other => serde_json::json!(format!("[{other}]")),
+    }
+}
+
+/// Sample context for a notification type
+#[must_use]
+pub fn sample_context(notification_type: NotificationType) -> HashMap<String, serde_json::Value> {
+    notification_type
+        .context_variables()
+        .iter()
+        .map(|v| ((*v).to_string(), sample_value(v)))
+        .collect()
+}
+
+/// Strip the locale and `_html`/`_text` variant suffixes from a template
+/// name
+fn base_name(template: &str) -> &str {
+    let (template, _) = split_locale(template);
+    template
+        .strip_suffix("_html")
+        .or_else(|| template.strip_suffix("_text"))
+        .unwrap_or(template)
+}
+
+/// Notification types that send a template
+fn event_types(base: &str) -> Vec<NotificationType> {
+    NotificationType::ALL
+        .into_iter()
+        .filter(|t| t.template_name() == base)
+        .collect()
+}
+
+/// Rendered template preview
+#[derive(Debug, Clone)]
+pub struct TemplatePreview {
+    /// Template base name (without `_html`/`_text`)
+    pub template: String,
+    /// Subject line
+    pub subject: String,
+    /// Rendered HTML variant
+    pub html: Option<String>,
+    /// Rendered text variant
+    pub text: Option<String>,
+    /// Context used for rendering
+    pub context: HashMap<String, serde_json::Value>,
+}
+
+/// Lint result for one template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateLint {
+    /// Registered template name
+    pub template: String,
+    /// Notification types that send this template
+    pub event_types: Vec<NotificationType>,
+    /// Variables the template uses
+    pub variables: Vec<String>,
+    /// Variables used but not supplied by every sending type
+    pub missing: Vec<String>,
+}
+
+impl TemplateLint {
+    /// Whether every variable is supplied
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Notification types that reference a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateUsage {
+    /// Registered template name
+    pub template: String,
+    /// Notification types that send it (empty if unused)
+    pub event_types: Vec<NotificationType>,
+}
+
+/// Addresses allowed to receive test sends
+#[derive(Debug, Clone, Default)]
+pub struct TestSendWhitelist {
+    entries: Vec<String>,
+}
+
+impl TestSendWhitelist {
+    /// Create an empty whitelist (every address is rejected)
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow an exact address, or a whole domain as `@example.com`
+    #[must_use]
+    pub fn allow(mut self, entry: impl Into<String>) -> Self {
+        self.entries.push(entry.into().to_ascii_lowercase());
+        self
+    }
+
+    /// Check if an address may receive test sends
+    #[must_use]
+    pub fn is_allowed(&self, email: &str) -> bool {
+        let email = email.trim().to_ascii_lowercase();
+        self.entries.iter().any(|entry| {
+            if entry.starts_with('@') {
+                email.ends_with(entry.as_str())
+            } else {
+                *entry == email
+            }
+        })
+    }
+}
+
+impl TemplateEngine {
+    /// Render the HTML and text variants of a template
+    ///
+    /// Variables missing from `context` are filled with sample values, so
+    /// a preview with no context shows the template as a customer would
+    /// see it with typical data.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TemplateNotFound` if neither variant is registered, or a
+    /// template error if rendering fails.
+    pub fn preview(
+        &self,
+        template: &str,
+        context: Option<&HashMap<String, serde_json::Value>>,
+    ) -> NotificationResult<TemplatePreview> {
+        let base = base_name(template);
+        let (_, locale) = split_locale(template);
+        let html_name = self.resolve(&format!("{base}_html"), locale);
+        let text_name = self.resolve(&format!("{base}_text"), locale);
+        let plain_name = self.resolve(base, locale);
+        let variants: Vec<&str> = [&html_name, &text_name, &plain_name]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if variants.is_empty() {
+            return Err(NotificationError::TemplateNotFound(base.to_string()));
+        }
+
+        let types = event_types(base);
+        let mut full_context: HashMap<String, serde_json::Value> = HashMap::new();
+        for t in &types {
+            full_context.extend(sample_context(*t));
+        }
+        for name in &variants {
+            for variable in template_variables(self.source(name).unwrap_or_default()) {
+                let value = sample_value(&variable);
+                full_context.entry(variable).or_insert(value);
+            }
+        }
+        if let Some(context) = context {
+            full_context.extend(context.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+
+        let render = |name: Option<&String>| -> NotificationResult<Option<String>> {
+            name.map(|name| self.render(name, &full_context))
+                .transpose()
+        };
+        let html = render(html_name.as_ref())?;
+        let text = match render(text_name.as_ref())? {
+            Some(text) => Some(text),
+            None if html.is_none() => render(plain_name.as_ref())?,
+            None => None,
+        };
+
+        Ok(TemplatePreview {
+            template: base.to_string(),
+            subject: types.first().map_or_else(
+                || base.replace('_', " "),
+                |t| t.default_subject().to_string(),
+            ),
+            html,
+            text,
+            context: full_context,
+        })
+    }
+
+    /// Lint one template against the context its notification types supply
+    ///
+    /// Templates no notification type sends have nothing to check against
+    /// and always lint clean.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TemplateNotFound` if the template is not registered.
+    pub fn lint_template(&self, template: &str) -> NotificationResult<TemplateLint> {
+        let source = self
+            .source(template)
+            .ok_or_else(|| NotificationError::TemplateNotFound(template.to_string()))?;
+        let variables = template_variables(source);
+        let types = event_types(base_name(template));
+        let missing = variables
+            .iter()
+            .filter(|v| {
+                types
+                    .iter()
+                    .any(|t| !t.context_variables().contains(&v.as_str()))
+            })
+            .cloned()
+            .collect();
+
+        Ok(TemplateLint {
+            template: template.to_string(),
+            event_types: types,
+            variables: variables.into_iter().collect(),
+            missing,
+        })
+    }
+
+    /// Lint every registered template, sorted by name
+    #[must_use]
+    pub fn lint(&self) -> Vec<TemplateLint> {
+        let mut names = self.list_templates();
+        names.sort();
+        names
+            .iter()
+            .filter_map(|name| self.lint_template(name).ok())
+            .collect()
+    }
+
+    /// Notification types that reference each registered template, sorted
+    /// by name
+    #[must_use]
+    pub fn usage(&self) -> Vec<TemplateUsage> {
+        let mut names = self.list_templates();
+        names.sort();
+        names
+            .into_iter()
+            .map(|template| TemplateUsage {
+                event_types: event_types(base_name(&template)),
+                template,
+            })
+            .collect()
+    }
+
+    /// Build a test email for a template
+    ///
+    /// The subject is prefixed with `[TEST]` and the email is tagged
+    /// `test_send`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidRecipient` if the address is not whitelisted, or any
+    /// error from [`TemplateEngine::preview`].
+    pub fn test_email(
+        &self,
+        template: &str,
+        to_email: &str,
+        context: Option<&HashMap<String, serde_json::Value>>,
+        whitelist: &TestSendWhitelist,
+    ) -> NotificationResult<EmailRequest> {
+        if !whitelist.is_allowed(to_email) {
+            return Err(NotificationError::InvalidRecipient(format!(
+                "{to_email} is not on the test-send whitelist"
+            )));
+        }
+        let preview = self.preview(template, context)?;
+
+        let mut email = EmailRequest::new(to_email, format!("[TEST] {}", preview.subject))
+            .with_tag("test_send");
+        if let Some(html) = preview.html {
+            email = email.with_html(html);
+        }
+        if let Some(text) = preview.text {
+            email = email.with_text(text);
+        }
+        Ok(email)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_variables() {
+        let vars = template_variables(
+            "Hi {{name}}, {{#if vip}}{{{badge.html}}}{{else}}-{{/if}} {{! note}} {{@index}}",
+        );
+        let vars: Vec<&str> = vars.iter().map(String::as_str).collect();
+        assert_eq!(vars, vec!["badge", "name", "vip"]);
+    }
+
+    #[test]
+    fn test_preview_with_sample_and_supplied_context() {
+        let engine = TemplateEngine::new();
+
+        let preview = engine
+            .preview("booking_confirmation", None)
+            .expect("preview");
+        assert_eq!(preview.subject, "Your Booking Confirmation");
+        assert!(preview.html.expect("html").contains("VAY123"));
+        assert!(preview.text.expect("text").contains("MH88"));
+
+        let mut context = HashMap::new();
+        context.insert("booking_ref".to_string(), serde_json::json!("ZZZ999"));
+        let preview = engine
+            .preview("booking_confirmation_html", Some(&context))
+            .expect("preview");
+        assert!(preview.html.expect("html").contains("ZZZ999"));
+
+        assert!(matches!(
+            engine.preview("no_such_template", None),
+            Err(NotificationError::TemplateNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_lint_and_usage() {
+        let mut engine = TemplateEngine::new();
+        assert!(engine.lint().iter().all(TemplateLint::is_clean));
+
+        engine
+            .register("welcome_html", "Hi {{name}}, your code is {{promo_code}}")
+            .expect("register");
+        let lint = engine.lint_template("welcome_html").expect("lint");
+        assert_eq!(lint.event_types, vec![NotificationType::Welcome]);
+        assert_eq!(lint.missing, vec!["promo_code".to_string()]);
+
+        let usage = engine.usage();
+        let reminder = usage
+            .iter()
+            .find(|u| u.template == "flight_reminder_text")
+            .expect("usage");
+        assert_eq!(reminder.event_types, vec![NotificationType::FlightReminder]);
+    }
+
+    #[test]
+    fn test_test_email_requires_whitelist() {
+        let engine = TemplateEngine::new();
+        let whitelist = TestSendWhitelist::new()
+            .allow("@vaya.my")
+            .allow("qa@example.com");
+
+        assert!(matches!(
+            engine.test_email("welcome", "someone@gmail.com", None, &whitelist),
+            Err(NotificationError::InvalidRecipient(_))
+        ));
+
+        let email = engine
+            .test_email("welcome", "Ops@Vaya.my", None, &whitelist)
+            .expect("test email");
+        assert_eq!(email.subject, "[TEST] Welcome to VAYA");
+        assert!(email.html_body.is_some());
+        assert!(whitelist.is_allowed("qa@example.com"));
+    }
+}
@@ -0,0 +1,275 @@
+//! `SendGrid` email provider
+
+use async_trait::async_trait;
+use tracing::info;
+
+use vaya_common::{Timestamp, Uuid};
+
+use crate::email::{EmailProvider, EmailProviderKind, RenderedEmail};
+use crate::error::{NotificationError, NotificationResult};
+use crate::messaging::{http_client, with_retry};
+use crate::types::{AttachmentDisposition, EmailResult, NotificationStatus};
+use crate::NotificationConfig;
+
+/// `SendGrid` API base URL
+const SENDGRID_API_BASE: &str = "https://api.sendgrid.com/v3";
+
+/// Email provider using the `SendGrid` v3 mail send API
+pub struct SendGridProvider {
+    /// HTTP client
+    http_client: reqwest::Client,
+    /// API base URL
+    api_base: String,
+    /// API key
+    api_key: String,
+    /// Max retries
+    max_retries: u32,
+    /// Send with `SendGrid`'s sandbox mail setting
+    sandbox: bool,
+}
+
+impl SendGridProvider {
+    /// Create new `SendGrid` provider
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if no `SendGrid` API key is configured.
+    pub fn new(config: &NotificationConfig) -> NotificationResult<Self> {
+        config.validate_sendgrid()?;
+
+        Ok(Self {
+            http_client: http_client(config)?,
+            api_base: SENDGRID_API_BASE.to_string(),
+            api_key: config.sendgrid_api_key.clone(),
+            max_retries: config.max_retries,
+            sandbox: config.provider_sandbox(EmailProviderKind::SendGrid),
+        })
+    }
+
+    /// Use a different API base URL (a proxy or test server)
+    #[must_use]
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.api_base = api_base.into();
+        self
+    }
+
+    /// Build `SendGrid` API payload
+    fn build_payload(&self, email: &RenderedEmail<'_>) -> serde_json::Value {
+        let request = email.request;
+        let mut to = serde_json::json!({
+            "email": request.to_email
+        });
+        if let Some(ref name) = request.to_name {
+            to["name"] = serde_json::json!(name);
+        }
+
+        let mut personalizations = serde_json::json!({
+            "to": [to]
+        });
+
+        // Add custom headers
+        if !request.headers.is_empty() {
+            personalizations["headers"] =
+                serde_json::to_value(&request.headers).unwrap_or_default();
+        }
+
+        let mut payload = serde_json::json!({
+            "personalizations": [personalizations],
+            "from": {
+                "email": email.from_email,
+                "name": email.from_name
+            },
+            "subject": request.subject
+        });
+
+        // Add content
+        let mut content = Vec::new();
+        if let Some(ref text) = email.text_body {
+            content.push(serde_json::json!({
+                "type": "text/plain",
+                "value": text
+            }));
+        }
+        if let Some(ref html) = email.html_body {
+            content.push(serde_json::json!({
+                "type": "text/html",
+                "value": html
+            }));
+        }
+        payload["content"] = serde_json::json!(content);
+
+        // Add reply-to
+        if let Some(ref reply_to) = request.reply_to {
+            payload["reply_to"] = serde_json::json!({
+                "email": reply_to
+            });
+        }
+
+        // Add categories/tags
+        if !request.tags.is_empty() {
+            payload["categories"] = serde_json::to_value(&request.tags).unwrap_or_default();
+        }
+
+        // Add attachments
+        if !request.attachments.is_empty() {
+            let attachments: Vec<serde_json::Value> = request
+                .attachments
+                .iter()
+                .map(|a| {
+                    let mut attachment = serde_json::json!({
+                        "content": a.content,
+                        "filename": a.filename,
+                        "type": a.content_type,
+                        "disposition": match a.disposition {
+                            AttachmentDisposition::Attachment => "attachment",
+                            AttachmentDisposition::Inline => "inline",
+                        }
+                    });
+                    if let Some(ref content_id) = a.content_id {
+                        attachment["content_id"] = serde_json::json!(content_id);
+                    }
+                    attachment
+                })
+                .collect();
+            payload["attachments"] = serde_json::json!(attachments);
+        }
+
+        // Add tracking settings
+        payload["tracking_settings"] = serde_json::json!({
+            "click_tracking": {"enable": true},
+            "open_tracking": {"enable": true}
+        });
+
+        // Validated by SendGrid but never delivered
+        if self.sandbox {
+            payload["mail_settings"] = serde_json::json!({
+                "sandbox_mode": {"enable": true}
+            });
+        }
+
+        payload
+    }
+
+    /// Send single request
+    async fn send_request(&self, payload: &serde_json::Value) -> NotificationResult<EmailResult> {
+        let response = self
+            .http_client
+            .post(format!("{}/mail/send", self.api_base))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(payload)
+            .send()
+            .await
+            .map_err(NotificationError::from)?;
+
+        let status = response.status();
+
+        // SendGrid returns 202 Accepted on success, 200 OK in sandbox mode
+        if status.as_u16() == 202 || (self.sandbox && status.as_u16() == 200) {
+            let message_id = response
+                .headers()
+                .get("X-Message-Id")
+                .and_then(|v| v.to_str().ok())
+                .map_or_else(
+                    || {
+                        if self.sandbox {
+                            format!("sandbox_{}", Uuid::new_v4())
+                        } else {
+                            "unknown".to_string()
+                        }
+                    },
+                    str::to_string,
+                );
+
+            info!("Email sent successfully: {}", message_id);
+
+            return Ok(EmailResult {
+                message_id,
+                provider: self.name().to_string(),
+                status: NotificationStatus::Sent,
+                sent_at: Timestamp::now(),
+            });
+        }
+
+        // Handle errors
+        let error_body = response.text().await.unwrap_or_default();
+
+        match status.as_u16() {
+            401 => Err(NotificationError::Configuration(
+                "Invalid API key".to_string(),
+            )),
+            429 => Err(NotificationError::RateLimited {
+                retry_after_secs: 60,
+            }),
+            400 => {
+                // Parse SendGrid error
+                if let Ok(error) = serde_json::from_str::<serde_json::Value>(&error_body) {
+                    let message = error
+                        .get("errors")
+                        .and_then(|e| e.as_array())
+                        .and_then(|arr| arr.first())
+                        .and_then(|e| e.get("message"))
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("Invalid request");
+                    Err(NotificationError::DeliveryFailed(message.to_string()))
+                } else {
+                    Err(NotificationError::DeliveryFailed(error_body))
+                }
+            }
+            _ => Err(NotificationError::ServiceUnavailable(format!(
+                "HTTP {status}: {error_body}"
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailProvider for SendGridProvider {
+    fn name(&self) -> &'static str {
+        EmailProviderKind::SendGrid.as_str()
+    }
+
+    fn is_sandbox(&self) -> bool {
+        self.sandbox
+    }
+
+    async fn send(&self, email: &RenderedEmail<'_>) -> NotificationResult<EmailResult> {
+        let payload = self.build_payload(email);
+        with_retry(self.max_retries, || self.send_request(&payload)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EmailRequest;
+
+    #[test]
+    fn test_sendgrid_requires_key() {
+        let config = NotificationConfig::default();
+        assert!(SendGridProvider::new(&config).is_err());
+    }
+
+    #[test]
+    fn test_build_payload() {
+        let config = NotificationConfig::with_sendgrid("SG.test", "noreply@vaya.my");
+        let provider = SendGridProvider::new(&config).expect("Should create");
+
+        let request = EmailRequest::new("user@example.com", "Test Subject")
+            .with_name("John Doe")
+            .with_text("Hello, world!");
+        let email = RenderedEmail {
+            request: &request,
+            from_email: &config.from_email,
+            from_name: &config.from_name,
+            text_body: request.text_body.clone(),
+            html_body: None,
+        };
+
+        let payload = provider.build_payload(&email);
+        assert!(payload.get("personalizations").is_some());
+        assert!(payload.get("from").is_some());
+        assert!(payload.get("subject").is_some());
+        assert!(payload.get("mail_settings").is_none());
+    }
+}
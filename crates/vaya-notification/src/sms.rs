@@ -60,7 +60,11 @@ impl SmsClient {
 
         // Render template if needed
         let message = if let Some(ref template) = request.template {
-            self.templates.render(template, &request.context)?
+            self.templates.render_localized(
+                template,
+                request.locale.as_deref(),
+                &request.context,
+            )?
         } else {
             request.message.clone()
         };
@@ -0,0 +1,944 @@
+//! SMTP email provider
+//!
+//! Speaks ESMTP directly to a relay over `vaya-net` TCP and TLS: implicit
+//! TLS (port 465) or STARTTLS (port 587), then `AUTH PLAIN` or `AUTH LOGIN`
+//! when credentials are configured. The message is built here as MIME with
+//! base64 bodies and RFC 2047 encoded headers, so Malay and Chinese subjects
+//! survive relays that only accept 7-bit mail.
+//!
+//! In sandbox mode the session stops after the relay accepts the recipient
+//! and is reset instead of sending `DATA`, which checks credentials and the
+//! address without delivering anything.
+
+use std::fmt;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tracing::{debug, info};
+
+use vaya_common::{Mask, Redact, Timestamp, Uuid, ZonedTime};
+use vaya_net::tls::{self, ClientStream, TlsConnector};
+use vaya_net::NetError;
+
+use crate::email::{EmailProvider, EmailProviderKind, RenderedEmail};
+use crate::error::{NotificationError, NotificationResult};
+use crate::messaging::with_retry;
+use crate::types::{
+    base64_encode, AttachmentDisposition, EmailAttachment, EmailResult, NotificationStatus,
+};
+use crate::NotificationConfig;
+
+/// Longest reply line accepted from a relay (RFC 5321 allows 512)
+const MAX_REPLY_LINE: usize = 1024;
+
+/// Most lines accepted in one multiline reply
+const MAX_REPLY_LINES: usize = 128;
+
+/// Base64 line length in MIME bodies
+const MIME_LINE: usize = 76;
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Plaintext connection upgraded with STARTTLS (required)
+    StartTls,
+    /// TLS from the first byte
+    Implicit,
+    /// No encryption, for a relay on a trusted local network
+    Plaintext,
+}
+
+/// SMTP relay settings
+#[derive(Clone)]
+pub struct SmtpConfig {
+    /// Relay host name (also the name its certificate is checked against)
+    pub host: String,
+    /// Relay port
+    pub port: u16,
+    /// Username; empty to skip authentication
+    pub username: String,
+    /// Password
+    pub password: String,
+    /// Connection security
+    pub security: SmtpSecurity,
+    /// Name sent in EHLO; defaults to the sender's domain
+    pub hello_name: Option<String>,
+    /// PEM bundle of trusted roots; defaults to the system bundle
+    pub ca_bundle: Option<String>,
+}
+
+impl fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.redacted(Mask::Full))
+            .field("security", &self.security)
+            .field("hello_name", &self.hello_name)
+            .field("ca_bundle", &self.ca_bundle)
+            .finish()
+    }
+}
+
+impl SmtpConfig {
+    /// Relay on the submission port (587) with STARTTLS
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port: 587,
+            username: String::new(),
+            password: String::new(),
+            security: SmtpSecurity::StartTls,
+            hello_name: None,
+            ca_bundle: None,
+        }
+    }
+
+    /// Authenticate with a username and password
+    #[must_use]
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = username.into();
+        self.password = password.into();
+        self
+    }
+
+    /// Set connection security and port
+    #[must_use]
+    pub fn with_security(mut self, security: SmtpSecurity, port: u16) -> Self {
+        self.security = security;
+        self.port = port;
+        self
+    }
+
+    /// Set the EHLO name
+    #[must_use]
+    pub fn with_hello_name(mut self, name: impl Into<String>) -> Self {
+        self.hello_name = Some(name.into());
+        self
+    }
+
+    /// Trust the roots in a PEM bundle instead of the system bundle
+    #[must_use]
+    pub fn with_ca_bundle(mut self, path: impl Into<String>) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+}
+
+/// Email provider speaking SMTP to a relay
+pub struct SmtpProvider {
+    /// Relay settings
+    config: SmtpConfig,
+    /// TLS connector (absent for plaintext relays)
+    tls: Option<TlsConnector>,
+    /// Timeout for a whole SMTP session
+    timeout: Duration,
+    /// Max retries
+    max_retries: u32,
+    /// Stop before `DATA`
+    sandbox: bool,
+}
+
+impl SmtpProvider {
+    /// Create new SMTP provider
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` if no relay is configured or its trusted
+    /// roots cannot be loaded.
+    pub fn new(config: &NotificationConfig) -> NotificationResult<Self> {
+        config.validate_smtp()?;
+        let smtp = config
+            .smtp
+            .clone()
+            .ok_or_else(|| NotificationError::Configuration("SMTP relay is required".into()))?;
+
+        let tls = match (smtp.security, &smtp.ca_bundle) {
+            (SmtpSecurity::Plaintext, _) => None,
+            (_, Some(path)) => Some(TlsConnector::from_pem_file(path)),
+            (_, None) => Some(TlsConnector::system()),
+        }
+        .transpose()
+        .map_err(|e| NotificationError::Configuration(e.to_string()))?;
+
+        Ok(Self {
+            config: smtp,
+            tls,
+            timeout: Duration::from_secs(config.request_timeout_secs),
+            max_retries: config.max_retries,
+            sandbox: config.provider_sandbox(EmailProviderKind::Smtp),
+        })
+    }
+
+    /// Use a specific TLS connector (custom roots, tests)
+    #[must_use]
+    pub fn with_tls_connector(mut self, connector: TlsConnector) -> Self {
+        self.tls = Some(connector);
+        self
+    }
+
+    fn connector(&self) -> NotificationResult<&TlsConnector> {
+        self.tls
+            .as_ref()
+            .ok_or_else(|| NotificationError::Configuration("No TLS connector".to_string()))
+    }
+
+    /// Run one SMTP session for `email`
+    async fn deliver(&self, email: &RenderedEmail<'_>) -> NotificationResult<EmailResult> {
+        let request = email.request;
+        let to = address(&request.to_email).ok_or_else(|| {
+            NotificationError::InvalidRecipient("Invalid email address".to_string())
+        })?;
+        let from = address(email.from_email).ok_or_else(|| {
+            NotificationError::Configuration("Invalid sender address".to_string())
+        })?;
+        let domain = from.rsplit('@').next().unwrap_or("localhost");
+        let hello = self.config.hello_name.as_deref().unwrap_or(domain);
+
+        let stream = tls::connect(&self.config.host, self.config.port, self.timeout)
+            .await
+            .map_err(net_error)?;
+        let mut stream = ClientStream::Plain(stream);
+        if self.config.security == SmtpSecurity::Implicit {
+            stream = stream
+                .upgrade(self.connector()?, &self.config.host)
+                .await
+                .map_err(net_error)?;
+        }
+
+        let mut session = Session::new(stream);
+        session.expect(Stage::Greeting, &[220]).await?;
+        let mut extensions = session.ehlo(hello).await?;
+
+        if self.config.security == SmtpSecurity::StartTls {
+            if !extensions.starttls {
+                return Err(NotificationError::DeliveryFailed(format!(
+                    "{} does not offer STARTTLS",
+                    self.config.host
+                )));
+            }
+            session.command(Stage::StartTls, "STARTTLS", &[220]).await?;
+            session = session
+                .upgrade(self.connector()?, &self.config.host)
+                .await?;
+            extensions = session.ehlo(hello).await?;
+        }
+
+        if !self.config.username.is_empty() {
+            session
+                .authenticate(&extensions, &self.config.username, &self.config.password)
+                .await?;
+        }
+
+        session
+            .command(Stage::Envelope, &format!("MAIL FROM:<{from}>"), &[250])
+            .await?;
+        session
+            .command(Stage::Recipient, &format!("RCPT TO:<{to}>"), &[250, 251])
+            .await?;
+
+        let message_id = format!("{}@{domain}", Uuid::new_v4());
+        if self.sandbox {
+            session.command(Stage::Envelope, "RSET", &[250]).await?;
+            session.quit().await;
+            info!("SMTP sandbox: {} accepted {}", self.config.host, to);
+            return Ok(EmailResult {
+                message_id: format!("sandbox_{message_id}"),
+                provider: self.name().to_string(),
+                status: NotificationStatus::Sent,
+                sent_at: Timestamp::now(),
+            });
+        }
+
+        session.command(Stage::Data, "DATA", &[354]).await?;
+        let message = build_message(email, &message_id, Timestamp::now())?;
+        session.send_data(&message).await?;
+        session.quit().await;
+
+        info!("Email sent successfully: {}", message_id);
+
+        Ok(EmailResult {
+            message_id,
+            provider: self.name().to_string(),
+            status: NotificationStatus::Sent,
+            sent_at: Timestamp::now(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmailProvider for SmtpProvider {
+    fn name(&self) -> &'static str {
+        EmailProviderKind::Smtp.as_str()
+    }
+
+    fn is_sandbox(&self) -> bool {
+        self.sandbox
+    }
+
+    async fn send(&self, email: &RenderedEmail<'_>) -> NotificationResult<EmailResult> {
+        with_retry(self.max_retries, || async {
+            tokio::time::timeout(self.timeout, self.deliver(email))
+                .await
+                .map_err(|_| NotificationError::Timeout)?
+        })
+        .await
+    }
+}
+
+/// Point in the session a reply belongs to, for error mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Greeting,
+    Hello,
+    StartTls,
+    Auth,
+    Envelope,
+    Recipient,
+    Data,
+}
+
+/// A relay reply
+#[derive(Debug)]
+struct Reply {
+    code: u16,
+    lines: Vec<String>,
+}
+
+impl Reply {
+    fn text(&self) -> String {
+        self.lines.join(" ")
+    }
+}
+
+/// What the relay advertised in its EHLO reply
+#[derive(Debug, Default)]
+struct Extensions {
+    starttls: bool,
+    auth_plain: bool,
+    auth_login: bool,
+}
+
+impl Extensions {
+    fn parse(reply: &Reply) -> Self {
+        let mut extensions = Self::default();
+        // The first line is the relay's greeting, not an extension
+        for line in reply.lines.iter().skip(1) {
+            let mut words = line.split_ascii_whitespace();
+            match words.next().map(str::to_ascii_uppercase).as_deref() {
+                Some("STARTTLS") => extensions.starttls = true,
+                Some("AUTH") => {
+                    for mechanism in words {
+                        match mechanism.to_ascii_uppercase().as_str() {
+                            "PLAIN" => extensions.auth_plain = true,
+                            "LOGIN" => extensions.auth_login = true,
+                            _ => {}
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        extensions
+    }
+}
+
+/// An SMTP conversation with a relay
+struct Session {
+    stream: BufReader<ClientStream>,
+}
+
+impl Session {
+    fn new(stream: ClientStream) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Switch to TLS after the relay accepted STARTTLS
+    async fn upgrade(self, connector: &TlsConnector, host: &str) -> NotificationResult<Self> {
+        let stream = self
+            .stream
+            .into_inner()
+            .upgrade(connector, host)
+            .await
+            .map_err(net_error)?;
+        Ok(Self::new(stream))
+    }
+
+    async fn read_reply(&mut self) -> NotificationResult<Reply> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            let n = (&mut self.stream)
+                .take(MAX_REPLY_LINE as u64)
+                .read_line(&mut line)
+                .await
+                .map_err(|e| NotificationError::Network(e.to_string()))?;
+            if n == 0 {
+                return Err(NotificationError::Network(
+                    "SMTP connection closed".to_string(),
+                ));
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            let (code, separator, text) = match (line.get(..3), line.get(3..4)) {
+                (Some(code), separator) => (code, separator.unwrap_or(" "), line.get(4..)),
+                _ => ("", " ", None),
+            };
+            let code: u16 = code.parse().map_err(|_| {
+                NotificationError::InvalidResponse(format!("Malformed SMTP reply: {line}"))
+            })?;
+            lines.push(text.unwrap_or_default().to_string());
+
+            if separator != "-" {
+                debug!("SMTP <- {} {}", code, lines.join(" | "));
+                return Ok(Reply { code, lines });
+            }
+            if lines.len() >= MAX_REPLY_LINES {
+                return Err(NotificationError::InvalidResponse(
+                    "SMTP reply too long".to_string(),
+                ));
+            }
+        }
+    }
+
+    async fn write_line(&mut self, line: &str) -> NotificationResult<()> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{line}\r\n").as_bytes())
+            .await
+            .map_err(|e| NotificationError::Network(e.to_string()))?;
+        stream
+            .flush()
+            .await
+            .map_err(|e| NotificationError::Network(e.to_string()))
+    }
+
+    async fn expect(&mut self, stage: Stage, codes: &[u16]) -> NotificationResult<Reply> {
+        let reply = self.read_reply().await?;
+        if codes.contains(&reply.code) {
+            Ok(reply)
+        } else {
+            Err(reply_error(stage, &reply))
+        }
+    }
+
+    async fn command(
+        &mut self,
+        stage: Stage,
+        line: &str,
+        codes: &[u16],
+    ) -> NotificationResult<Reply> {
+        if stage == Stage::Auth {
+            debug!("SMTP -> (credentials)");
+        } else {
+            debug!("SMTP -> {}", line);
+        }
+        self.write_line(line).await?;
+        self.expect(stage, codes).await
+    }
+
+    async fn ehlo(&mut self, hello: &str) -> NotificationResult<Extensions> {
+        let reply = self
+            .command(Stage::Hello, &format!("EHLO {hello}"), &[250])
+            .await?;
+        Ok(Extensions::parse(&reply))
+    }
+
+    async fn authenticate(
+        &mut self,
+        extensions: &Extensions,
+        username: &str,
+        password: &str,
+    ) -> NotificationResult<()> {
+        if extensions.auth_plain {
+            let token = base64_encode(format!("\0{username}\0{password}").as_bytes());
+            self.command(Stage::Auth, &format!("AUTH PLAIN {token}"), &[235])
+                .await?;
+        } else if extensions.auth_login {
+            self.command(Stage::Auth, "AUTH LOGIN", &[334]).await?;
+            self.command(Stage::Auth, &base64_encode(username.as_bytes()), &[334])
+                .await?;
+            self.command(Stage::Auth, &base64_encode(password.as_bytes()), &[235])
+                .await?;
+        } else {
+            return Err(NotificationError::Configuration(
+                "SMTP relay offers no supported AUTH mechanism".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Send the message body, dot-stuffed and terminated
+    async fn send_data(&mut self, message: &str) -> NotificationResult<()> {
+        let mut data = String::with_capacity(message.len() + 16);
+        for line in message.split_terminator("\r\n") {
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push_str(".\r\n");
+
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(data.as_bytes())
+            .await
+            .map_err(|e| NotificationError::Network(e.to_string()))?;
+        stream
+            .flush()
+            .await
+            .map_err(|e| NotificationError::Network(e.to_string()))?;
+        self.expect(Stage::Data, &[250]).await?;
+        Ok(())
+    }
+
+    /// End the session; the message is already accepted, so errors are ignored
+    async fn quit(mut self) {
+        if self.write_line("QUIT").await.is_ok() {
+            let _ = self.read_reply().await;
+        }
+        let _ = self.stream.get_mut().shutdown().await;
+    }
+}
+
+/// Map a rejected reply to a notification error
+fn reply_error(stage: Stage, reply: &Reply) -> NotificationError {
+    let text = format!("SMTP {} {}", reply.code, reply.text());
+    match (reply.code / 100, stage) {
+        // Transient: greylisting, relay busy, mailbox temporarily unavailable
+        (4, _) => NotificationError::ServiceUnavailable(text),
+        (5, Stage::Recipient) => NotificationError::InvalidRecipient(text),
+        (5, Stage::Auth) => NotificationError::Configuration(text),
+        _ => NotificationError::DeliveryFailed(text),
+    }
+}
+
+fn net_error(err: NetError) -> NotificationError {
+    match err {
+        NetError::Timeout => NotificationError::Timeout,
+        NetError::Io(e) => NotificationError::Network(e.to_string()),
+        other => NotificationError::DeliveryFailed(other.to_string()),
+    }
+}
+
+/// An address safe to put in an SMTP command or header
+fn address(email: &str) -> Option<&str> {
+    let email = email.trim();
+    let valid = email.contains('@')
+        && !email
+            .chars()
+            .any(|c| c.is_control() || c.is_whitespace() || matches!(c, '<' | '>' | ','));
+    valid.then_some(email)
+}
+
+/// RFC 2047 encode a header value if it is not plain printable ASCII
+fn encode_header(value: &str) -> String {
+    let value: String = value.chars().filter(|c| !c.is_control()).collect();
+    if value.is_ascii() {
+        return value;
+    }
+
+    // Encoded words are limited to 75 characters; 45 input bytes fit
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in value.chars() {
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", base64_encode(chunk.as_bytes())));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    if !chunk.is_empty() {
+        words.push(format!("=?UTF-8?B?{}?=", base64_encode(chunk.as_bytes())));
+    }
+    words.join("\r\n ")
+}
+
+/// `"Name" <address>`, or the bare address without a name
+fn mailbox(name: Option<&str>, email: &str) -> String {
+    match name.filter(|n| !n.trim().is_empty()) {
+        Some(name) if name.is_ascii() => {
+            let name: String = name
+                .chars()
+                .filter(|c| !c.is_control() && *c != '"' && *c != '\\')
+                .collect();
+            format!("\"{name}\" <{email}>")
+        }
+        Some(name) => format!("{} <{email}>", encode_header(name)),
+        None => email.to_string(),
+    }
+}
+
+/// RFC 5322 date (`Fri, 16 Oct 2026 09:00:00 +0000`)
+fn rfc5322_date(at: Timestamp) -> String {
+    let dt = ZonedTime::utc(at).local();
+    let weekday = dt.weekday().to_string();
+    let month = dt.month().to_string();
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        &weekday[..3],
+        dt.day(),
+        &month[..3],
+        dt.year(),
+        dt.hour(),
+        dt.minute(),
+        dt.second()
+    )
+}
+
+/// Base64 split into MIME-length lines
+fn wrap_base64(encoded: &str) -> String {
+    let clean: Vec<u8> = encoded
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    clean
+        .chunks(MIME_LINE)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+fn text_part(subtype: &str, body: &str) -> String {
+    format!(
+        "Content-Type: text/{subtype}; charset=utf-8\r\n\
+         Content-Transfer-Encoding: base64\r\n\r\n{}",
+        wrap_base64(&base64_encode(body.as_bytes()))
+    )
+}
+
+fn attachment_part(attachment: &EmailAttachment) -> String {
+    let filename: String = attachment
+        .filename
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"' && *c != '\\')
+        .collect();
+    let content_type: String = attachment
+        .content_type
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect();
+    let disposition = match attachment.disposition {
+        AttachmentDisposition::Attachment => "attachment",
+        AttachmentDisposition::Inline => "inline",
+    };
+    let mut headers = vec![
+        format!("Content-Type: {content_type}; name=\"{filename}\""),
+        "Content-Transfer-Encoding: base64".to_string(),
+        format!("Content-Disposition: {disposition}; filename=\"{filename}\""),
+    ];
+    let content_id = attachment.content_id.as_deref().filter(|id| {
+        !id.is_empty()
+            && id
+                .bytes()
+                .all(|b| b.is_ascii_graphic() && b != b'<' && b != b'>')
+    });
+    if let Some(id) = content_id {
+        headers.push(format!("Content-ID: <{id}>"));
+    }
+    format!(
+        "{}\r\n\r\n{}",
+        headers.join("\r\n"),
+        wrap_base64(&attachment.content)
+    )
+}
+
+fn multipart(subtype: &str, parts: &[String]) -> String {
+    let boundary = format!("=_vaya_{}", Uuid::new_v4());
+    let delimiter = format!("--{boundary}\r\n");
+    let parts = parts
+        .iter()
+        .map(|part| format!("{delimiter}{part}\r\n"))
+        .collect::<Vec<_>>()
+        .concat();
+    format!(
+        "Content-Type: multipart/{subtype}; boundary=\"{boundary}\"\r\n\r\n{parts}--{boundary}--"
+    )
+}
+
+/// Build the MIME message sent after `DATA`
+fn build_message(
+    email: &RenderedEmail<'_>,
+    message_id: &str,
+    date: Timestamp,
+) -> NotificationResult<String> {
+    let request = email.request;
+    let to = address(&request.to_email)
+        .ok_or_else(|| NotificationError::InvalidRecipient("Invalid email address".to_string()))?;
+
+    let mut headers = vec![
+        format!("From: {}", mailbox(Some(email.from_name), email.from_email)),
+        format!("To: {}", mailbox(request.to_name.as_deref(), to)),
+    ];
+    if let Some(reply_to) = request.reply_to.as_deref().and_then(address) {
+        headers.push(format!("Reply-To: {reply_to}"));
+    }
+    headers.push(format!("Subject: {}", encode_header(&request.subject)));
+    headers.push(format!("Date: {}", rfc5322_date(date)));
+    headers.push(format!("Message-ID: <{message_id}>"));
+    headers.push("MIME-Version: 1.0".to_string());
+
+    let mut custom: Vec<_> = request
+        .headers
+        .iter()
+        .filter(|(name, _)| {
+            !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic() && b != b':')
+        })
+        .collect();
+    custom.sort();
+    for (name, value) in custom {
+        headers.push(format!("{name}: {}", encode_header(value)));
+    }
+
+    let mut bodies = Vec::new();
+    if let Some(ref text) = email.text_body {
+        bodies.push(text_part("plain", text));
+    }
+    if let Some(ref html) = email.html_body {
+        bodies.push(text_part("html", html));
+    }
+    let body = match bodies.len() {
+        0 => text_part("plain", ""),
+        1 => bodies.remove(0),
+        _ => multipart("alternative", &bodies),
+    };
+    let body = if request.attachments.is_empty() {
+        body
+    } else {
+        let mut parts = vec![body];
+        parts.extend(request.attachments.iter().map(attachment_part));
+        multipart("mixed", &parts)
+    };
+
+    Ok(format!("{}\r\n{body}\r\n", headers.join("\r\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{base64_decode, EmailRequest};
+    use std::sync::{Arc, Mutex};
+
+    use tokio::net::TcpListener;
+
+    fn rendered(request: &EmailRequest) -> RenderedEmail<'_> {
+        RenderedEmail {
+            request,
+            from_email: "noreply@vaya.my",
+            from_name: "VAYA Flights",
+            text_body: Some("Booking confirmed".to_string()),
+            html_body: Some("<p>Booking confirmed</p>".to_string()),
+        }
+    }
+
+    /// A scripted relay: replies to each command from the script and
+    /// records what the client sent
+    async fn relay(
+        script: &'static [(&'static str, &'static str)],
+    ) -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let port = listener.local_addr().expect("addr").port();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.expect("accept");
+            let mut socket = BufReader::new(socket);
+            socket
+                .get_mut()
+                .write_all(b"220 relay.test ESMTP\r\n")
+                .await
+                .expect("greet");
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if socket.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                log.lock().expect("log").push(line.clone());
+                if in_data {
+                    if line == "." {
+                        in_data = false;
+                        socket
+                            .get_mut()
+                            .write_all(b"250 2.0.0 queued\r\n")
+                            .await
+                            .ok();
+                    }
+                    continue;
+                }
+                let reply = script
+                    .iter()
+                    .find(|(prefix, _)| line.starts_with(prefix))
+                    .map_or("500 unrecognized\r\n", |(_, reply)| reply);
+                in_data = line == "DATA" && reply.starts_with("354");
+                socket.get_mut().write_all(reply.as_bytes()).await.ok();
+                if line == "QUIT" {
+                    break;
+                }
+            }
+        });
+        (port, received)
+    }
+
+    const RELAY: &[(&str, &str)] = &[
+        (
+            "EHLO",
+            "250-relay.test\r\n250-AUTH LOGIN PLAIN\r\n250 8BITMIME\r\n",
+        ),
+        ("AUTH PLAIN", "235 2.7.0 accepted\r\n"),
+        ("MAIL FROM", "250 ok\r\n"),
+        ("RCPT TO:<gone@", "550 5.1.1 no such user\r\n"),
+        ("RCPT TO", "250 ok\r\n"),
+        ("DATA", "354 go ahead\r\n"),
+        ("RSET", "250 ok\r\n"),
+        ("QUIT", "221 bye\r\n"),
+    ];
+
+    fn provider(port: u16, security: SmtpSecurity) -> NotificationConfig {
+        let mut config = NotificationConfig::default().with_smtp(
+            SmtpConfig::new("127.0.0.1")
+                .with_security(security, port)
+                .with_credentials("mailer", "s3cret"),
+        );
+        config.from_email = "noreply@vaya.my".to_string();
+        config.max_retries = 0;
+        config.request_timeout_secs = 5;
+        config
+    }
+
+    #[tokio::test]
+    async fn test_smtp_delivery() {
+        let (port, received) = relay(RELAY).await;
+        let provider = SmtpProvider::new(&provider(port, SmtpSecurity::Plaintext)).expect("smtp");
+        let request = EmailRequest::new("user@example.com", "Booking VAY-7KQ2M9XP");
+
+        let result = provider.send(&rendered(&request)).await.expect("sent");
+        assert_eq!(result.provider, "smtp");
+        assert!(result.message_id.ends_with("@vaya.my"));
+
+        let received = received.lock().expect("log").clone();
+        assert_eq!(received[0], "EHLO vaya.my");
+        assert_eq!(
+            received[1],
+            format!("AUTH PLAIN {}", base64_encode(b"\0mailer\0s3cret"))
+        );
+        assert_eq!(received[2], "MAIL FROM:<noreply@vaya.my>");
+        assert_eq!(received[3], "RCPT TO:<user@example.com>");
+        assert_eq!(received[4], "DATA");
+        assert!(received.contains(&format!("Message-ID: <{}>", result.message_id)));
+        assert!(received.contains(&"Subject: Booking VAY-7KQ2M9XP".to_string()));
+        assert_eq!(received.last().map(String::as_str), Some("QUIT"));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_sandbox_stops_before_data() {
+        let (port, received) = relay(RELAY).await;
+        let config =
+            provider(port, SmtpSecurity::Plaintext).with_provider_sandbox(EmailProviderKind::Smtp);
+        let provider = SmtpProvider::new(&config).expect("smtp");
+        let request = EmailRequest::new("user@example.com", "Hello");
+
+        let result = provider.send(&rendered(&request)).await.expect("sandbox");
+        assert!(result.message_id.starts_with("sandbox_"));
+        let received = received.lock().expect("log").clone();
+        assert!(received.contains(&"RSET".to_string()));
+        assert!(!received.contains(&"DATA".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_smtp_rejected_recipient_is_permanent() {
+        let (port, _) = relay(RELAY).await;
+        let provider = SmtpProvider::new(&provider(port, SmtpSecurity::Plaintext)).expect("smtp");
+        let request = EmailRequest::new("gone@example.com", "Hello");
+
+        let err = provider
+            .send(&rendered(&request))
+            .await
+            .expect_err("rejected");
+        assert!(matches!(err, NotificationError::InvalidRecipient(_)));
+        assert!(err.is_permanent());
+    }
+
+    #[tokio::test]
+    async fn test_smtp_requires_starttls() {
+        let (port, received) = relay(RELAY).await;
+        let provider = SmtpProvider {
+            config: SmtpConfig::new("127.0.0.1")
+                .with_security(SmtpSecurity::StartTls, port)
+                .with_credentials("mailer", "s3cret"),
+            tls: None,
+            timeout: Duration::from_secs(5),
+            max_retries: 0,
+            sandbox: false,
+        };
+        let request = EmailRequest::new("user@example.com", "Hello");
+
+        // The relay never advertises STARTTLS, so nothing is sent in clear
+        let err = provider
+            .send(&rendered(&request))
+            .await
+            .expect_err("no starttls");
+        assert!(matches!(err, NotificationError::DeliveryFailed(_)));
+        let received = received.lock().expect("log").clone();
+        assert!(!received.iter().any(|line| line.starts_with("AUTH")));
+    }
+
+    #[test]
+    fn test_build_message() {
+        let request = EmailRequest::new("user@example.com", "预订确认 VAY-7KQ2M9XP")
+            .with_name("Aisyah")
+            .with_attachment(EmailAttachment::new(
+                "eticket.pdf",
+                "application/pdf",
+                b"%PDF-1.4",
+            ));
+        let message = build_message(
+            &rendered(&request),
+            "abc@vaya.my",
+            Timestamp::from_unix(1_792_141_200),
+        )
+        .expect("message");
+
+        assert!(message.contains("From: \"VAYA Flights\" <noreply@vaya.my>\r\n"));
+        assert!(message.contains("To: \"Aisyah\" <user@example.com>\r\n"));
+        assert!(message.contains("Date: Fri, 16 Oct 2026 09:00:00 +0000\r\n"));
+        assert!(message.contains("Message-ID: <abc@vaya.my>\r\n"));
+        assert!(message.contains("multipart/mixed"));
+        assert!(message.contains("multipart/alternative"));
+        assert!(message.contains("filename=\"eticket.pdf\""));
+        assert!(message.contains("JVBERi0xLjQ="));
+        assert!(message.is_ascii());
+
+        let subject = message
+            .lines()
+            .find_map(|line| line.strip_prefix("Subject: "))
+            .expect("subject");
+        let encoded = subject
+            .strip_prefix("=?UTF-8?B?")
+            .and_then(|s| s.strip_suffix("?="))
+            .expect("encoded word");
+        assert_eq!(
+            base64_decode(encoded).as_deref(),
+            Some("预订确认 VAY-7KQ2M9XP".as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_header_injection_is_stripped() {
+        assert_eq!(
+            encode_header("Hello\r\nBcc: victim@example.com"),
+            "HelloBcc: victim@example.com"
+        );
+        assert!(address("user@example.com>\r\nRCPT TO:<x@y").is_none());
+        assert_eq!(address(" user@example.com "), Some("user@example.com"));
+    }
+}
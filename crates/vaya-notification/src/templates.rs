@@ -1,14 +1,288 @@
 //! Template engine for notifications
-
-use handlebars::Handlebars;
+//!
+//! Templates are Handlebars: `{{#if}}`/`{{else}}` conditionals, `{{#each}}`
+//! loops (passengers and segments in a booking confirmation), and partials
+//! included with `{{> name}}`. Values are HTML-escaped except in templates
+//! whose name ends in `_text`, which render plain text for text email
+//! bodies and SMS.
+//!
+//! Localized variants are registered as `{name}.{locale}`, e.g.
+//! `booking_confirmation_html.ms`. [`TemplateEngine::render_localized`]
+//! tries the full locale tag (`zh-cn`), then its language (`zh`), then the
+//! engine's default locale, then the unlocalized template.
+
+use handlebars::{no_escape, Handlebars};
 use std::collections::HashMap;
 
 use crate::error::{NotificationError, NotificationResult};
 
+/// Locale of the unlocalized default templates
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Locales the built-in templates are translated into
+pub const SUPPORTED_LOCALES: &[&str] = &["en", "ms", "zh"];
+
+/// Lowercase a locale tag and use `-` as the separator (`zh_CN` -> `zh-cn`)
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().to_ascii_lowercase().replace('_', "-")
+}
+
+/// Name of a template's variant in a locale
+#[must_use]
+pub fn localized_name(name: &str, locale: &str) -> String {
+    format!("{name}.{}", normalize_locale(locale))
+}
+
+/// Split a registered name into the template name and its locale
+#[must_use]
+pub fn split_locale(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('.') {
+        Some((base, locale)) => (base, Some(locale)),
+        None => (name, None),
+    }
+}
+
+/// Plain-text templates are rendered without HTML escaping
+fn is_text(name: &str) -> bool {
+    split_locale(name).0.ends_with("_text")
+}
+
+/// Translated templates as (name, locale, source)
+const LOCALIZED_TEMPLATES: &[(&str, &str, &str)] = &[
+    (
+        "booking_confirmation_html",
+        "ms",
+        r#"<!DOCTYPE html>
+<html lang="ms">
+<head>
+    <meta charset="utf-8">
+    <title>Pengesahan Tempahan</title>
+    {{> email_style}}
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>Tempahan Disahkan!</h1>
+        </div>
+        <div class="content">
+            <p>Kepada {{passenger_name}},</p>
+            <p>Penerbangan anda telah berjaya ditempah.</p>
+
+            {{#if assistance}}
+            <div class="assistance" role="note">
+                <h3>Bantuan Diminta</h3>
+                {{#each assistance}}
+                <p><strong>{{this.name}}:</strong> {{#each this.needs}}{{this}}{{#unless @last}}; {{/unless}}{{/each}}</p>
+                {{/each}}
+                <p>Kami telah menghantar permintaan ini kepada syarikat penerbangan dan akan memaklumkan anda setelah ia disahkan.</p>
+            </div>
+            {{/if}}
+
+            <div class="flight-info">
+                <h3>Butiran Penerbangan</h3>
+                <p><strong>Rujukan Tempahan:</strong> {{booking_ref}}</p>
+                {{#if segments}}
+                {{#each segments}}
+                {{> segment_html}}
+                {{/each}}
+                {{else}}
+                <p><strong>Laluan:</strong> {{origin}} → {{destination}}</p>
+                <p><strong>Tarikh:</strong> {{departure_date}}</p>
+                <p><strong>Penerbangan:</strong> {{flight_number}}</p>
+                {{/if}}
+            </div>
+
+            {{#if passengers}}
+            <div class="flight-info">
+                <h3>Penumpang</h3>
+                {{#each passengers}}
+                <p>{{this}}</p>
+                {{/each}}
+            </div>
+            {{/if}}
+
+            <p class="price">Jumlah: {{currency}} {{total_amount}}</p>
+
+            <p>E-tiket anda akan dihantar secara berasingan.</p>
+        </div>
+        <div class="footer">
+            <p>VAYA Flights - Perjalanan anda bermula di sini</p>
+            <p>Perlukan bantuan? Hubungi kami di support@vaya.my</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+    ),
+    (
+        "booking_confirmation_text",
+        "ms",
+        r"TEMPAHAN DISAHKAN
+
+Kepada {{passenger_name}},
+
+Penerbangan anda telah berjaya ditempah.
+{{#if assistance}}
+
+BANTUAN DIMINTA
+---------------
+{{#each assistance}}
+{{this.name}}: {{#each this.needs}}{{this}}{{#unless @last}}; {{/unless}}{{/each}}
+{{/each}}
+Kami telah menghantar permintaan ini kepada syarikat penerbangan dan akan memaklumkan anda setelah ia disahkan.
+{{/if}}
+
+BUTIRAN PENERBANGAN
+-------------------
+Rujukan Tempahan: {{booking_ref}}
+{{#if segments}}
+{{#each segments}}
+{{> segment_text}}
+{{/each}}
+{{else}}
+Laluan: {{origin}} → {{destination}}
+Tarikh: {{departure_date}}
+Penerbangan: {{flight_number}}
+{{/if}}
+{{#if passengers}}
+
+PENUMPANG
+---------
+{{#each passengers}}
+{{this}}
+{{/each}}
+{{/if}}
+
+Jumlah: {{currency}} {{total_amount}}
+
+E-tiket anda akan dihantar secara berasingan.
+
+---
+VAYA Flights - Perjalanan anda bermula di sini
+Perlukan bantuan? Hubungi kami di support@vaya.my",
+    ),
+    (
+        "booking_confirmation_html",
+        "zh",
+        r#"<!DOCTYPE html>
+<html lang="zh">
+<head>
+    <meta charset="utf-8">
+    <title>预订确认</title>
+    {{> email_style}}
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>预订已确认！</h1>
+        </div>
+        <div class="content">
+            <p>尊敬的 {{passenger_name}}：</p>
+            <p>您的航班已预订成功。</p>
+
+            {{#if assistance}}
+            <div class="assistance" role="note">
+                <h3>协助需求</h3>
+                {{#each assistance}}
+                <p><strong>{{this.name}}:</strong> {{#each this.needs}}{{this}}{{#unless @last}}; {{/unless}}{{/each}}</p>
+                {{/each}}
+                <p>我们已将这些需求转交航空公司，确认后会通知您。</p>
+            </div>
+            {{/if}}
+
+            <div class="flight-info">
+                <h3>航班详情</h3>
+                <p><strong>预订参考号：</strong> {{booking_ref}}</p>
+                {{#if segments}}
+                {{#each segments}}
+                {{> segment_html}}
+                {{/each}}
+                {{else}}
+                <p><strong>航线：</strong> {{origin}} → {{destination}}</p>
+                <p><strong>日期：</strong> {{departure_date}}</p>
+                <p><strong>航班：</strong> {{flight_number}}</p>
+                {{/if}}
+            </div>
+
+            {{#if passengers}}
+            <div class="flight-info">
+                <h3>乘客</h3>
+                {{#each passengers}}
+                <p>{{this}}</p>
+                {{/each}}
+            </div>
+            {{/if}}
+
+            <p class="price">总计：{{currency}} {{total_amount}}</p>
+
+            <p>您的电子机票将另行发送。</p>
+        </div>
+        <div class="footer">
+            <p>VAYA Flights - 您的旅程从这里开始</p>
+            <p>需要帮助？请联系 support@vaya.my</p>
+        </div>
+    </div>
+</body>
+</html>"#,
+    ),
+    (
+        "booking_confirmation_text",
+        "zh",
+        r"预订已确认
+
+尊敬的 {{passenger_name}}：
+
+您的航班已预订成功。
+{{#if assistance}}
+
+协助需求
+--------
+{{#each assistance}}
+{{this.name}}: {{#each this.needs}}{{this}}{{#unless @last}}; {{/unless}}{{/each}}
+{{/each}}
+我们已将这些需求转交航空公司，确认后会通知您。
+{{/if}}
+
+航班详情
+--------
+预订参考号：{{booking_ref}}
+{{#if segments}}
+{{#each segments}}
+{{> segment_text}}
+{{/each}}
+{{else}}
+航线：{{origin}} → {{destination}}
+日期：{{departure_date}}
+航班：{{flight_number}}
+{{/if}}
+{{#if passengers}}
+
+乘客
+----
+{{#each passengers}}
+{{this}}
+{{/each}}
+{{/if}}
+
+总计：{{currency}} {{total_amount}}
+
+您的电子机票将另行发送。
+
+---
+VAYA Flights - 您的旅程从这里开始
+需要帮助？请联系 support@vaya.my",
+    ),
+];
+
 /// Template engine using Handlebars
 pub struct TemplateEngine {
-    /// Handlebars instance
+    /// Handlebars instance for HTML templates
     hbs: Handlebars<'static>,
+    /// Handlebars instance for `_text` templates, without escaping
+    text: Handlebars<'static>,
+    /// Template sources, kept for linting
+    sources: HashMap<String, String>,
+    /// Locale tried when the requested one has no variant
+    default_locale: String,
 }
 
 impl TemplateEngine {
@@ -17,32 +291,79 @@ impl TemplateEngine {
     pub fn new() -> Self {
         let mut hbs = Handlebars::new();
         hbs.set_strict_mode(true);
+        let mut text = Handlebars::new();
+        text.set_strict_mode(true);
+        text.register_escape_fn(no_escape);
+
+        let mut engine = Self {
+            hbs,
+            text,
+            sources: HashMap::new(),
+            default_locale: DEFAULT_LOCALE.to_string(),
+        };
 
         // Register default templates
-        Self::register_default_templates(&mut hbs);
+        engine.register_default_partials();
+        engine.register_default_templates();
+        engine.register_localized_templates();
+
+        engine
+    }
+
+    /// Set the locale used when a requested locale has no variant
+    #[must_use]
+    pub fn with_default_locale(mut self, locale: &str) -> Self {
+        self.default_locale = normalize_locale(locale);
+        self
+    }
+
+    /// Register partials shared by the default templates
+    fn register_default_partials(&mut self) {
+        let _ = self.register_partial(
+            "email_style",
+            r"<style>
+        body { font-family: Arial, sans-serif; line-height: 1.6; color: #333; }
+        .container { max-width: 600px; margin: 0 auto; padding: 20px; }
+        .header { background: #1a56db; color: white; padding: 20px; text-align: center; }
+        .content { padding: 20px; background: #f9fafb; }
+        .flight-info { background: white; padding: 15px; margin: 10px 0; border-radius: 8px; }
+        .price { font-size: 24px; color: #1a56db; font-weight: bold; }
+        .assistance { background: #fef3c7; border-left: 4px solid #d97706; padding: 15px; margin: 10px 0; border-radius: 8px; }
+        .footer { text-align: center; padding: 20px; color: #666; font-size: 12px; }
+    </style>",
+        );
 
-        Self { hbs }
+        // One flight segment; used inside {{#each segments}}
+        let _ = self.register_partial(
+            "segment_html",
+            "<p><strong>{{this.flight_number}}</strong> {{this.origin}} {{this.departure}} → {{this.destination}} {{this.arrival}}</p>",
+        );
+        let _ = self.register_partial(
+            "segment_text",
+            "{{this.flight_number}}  {{this.origin}} {{this.departure}} -> {{this.destination}} {{this.arrival}}",
+        );
     }
 
     /// Register default email templates
-    fn register_default_templates(hbs: &mut Handlebars<'static>) {
+    fn register_default_templates(&mut self) {
+        self.register_booking_templates();
+        self.register_payment_templates();
+        self.register_reminder_templates();
+        self.register_price_alert_templates();
+        self.register_account_templates();
+    }
+
+    /// Register the booking confirmation email (HTML and text)
+    fn register_booking_templates(&mut self) {
         // Booking confirmation email (HTML)
-        let _ = hbs.register_template_string(
+        let _ = self.register(
             "booking_confirmation_html",
             r#"<!DOCTYPE html>
 <html>
 <head>
     <meta charset="utf-8">
     <title>Booking Confirmation</title>
-    <style>
-        body { font-family: Arial, sans-serif; line-height: 1.6; color: #333; }
-        .container { max-width: 600px; margin: 0 auto; padding: 20px; }
-        .header { background: #1a56db; color: white; padding: 20px; text-align: center; }
-        .content { padding: 20px; background: #f9fafb; }
-        .flight-info { background: white; padding: 15px; margin: 10px 0; border-radius: 8px; }
-        .price { font-size: 24px; color: #1a56db; font-weight: bold; }
-        .footer { text-align: center; padding: 20px; color: #666; font-size: 12px; }
-    </style>
+    {{> email_style}}
 </head>
 <body>
     <div class="container">
@@ -53,14 +374,39 @@ impl TemplateEngine {
             <p>Dear {{passenger_name}},</p>
             <p>Your flight has been booked successfully.</p>
 
+            {{#if assistance}}
+            <div class="assistance" role="note">
+                <h3>Assistance Requested</h3>
+                {{#each assistance}}
+                <p><strong>{{this.name}}:</strong> {{#each this.needs}}{{this}}{{#unless @last}}; {{/unless}}{{/each}}</p>
+                {{/each}}
+                <p>We have sent these requests to the airline and will let you know once they are confirmed.</p>
+            </div>
+            {{/if}}
+
             <div class="flight-info">
                 <h3>Flight Details</h3>
                 <p><strong>Booking Reference:</strong> {{booking_ref}}</p>
+                {{#if segments}}
+                {{#each segments}}
+                {{> segment_html}}
+                {{/each}}
+                {{else}}
                 <p><strong>Route:</strong> {{origin}} → {{destination}}</p>
                 <p><strong>Date:</strong> {{departure_date}}</p>
                 <p><strong>Flight:</strong> {{flight_number}}</p>
+                {{/if}}
             </div>
 
+            {{#if passengers}}
+            <div class="flight-info">
+                <h3>Passengers</h3>
+                {{#each passengers}}
+                <p>{{this}}</p>
+                {{/each}}
+            </div>
+            {{/if}}
+
             <p class="price">Total: {{currency}} {{total_amount}}</p>
 
             <p>Your e-ticket will be sent separately.</p>
@@ -75,20 +421,43 @@ impl TemplateEngine {
         );
 
         // Booking confirmation email (text)
-        let _ = hbs.register_template_string(
+        let _ = self.register(
             "booking_confirmation_text",
             r"BOOKING CONFIRMED
 
 Dear {{passenger_name}},
 
 Your flight has been booked successfully.
+{{#if assistance}}
+
+ASSISTANCE REQUESTED
+--------------------
+{{#each assistance}}
+{{this.name}}: {{#each this.needs}}{{this}}{{#unless @last}}; {{/unless}}{{/each}}
+{{/each}}
+We have sent these requests to the airline and will let you know once they are confirmed.
+{{/if}}
 
 FLIGHT DETAILS
 --------------
 Booking Reference: {{booking_ref}}
+{{#if segments}}
+{{#each segments}}
+{{> segment_text}}
+{{/each}}
+{{else}}
 Route: {{origin}} → {{destination}}
 Date: {{departure_date}}
 Flight: {{flight_number}}
+{{/if}}
+{{#if passengers}}
+
+PASSENGERS
+----------
+{{#each passengers}}
+{{this}}
+{{/each}}
+{{/if}}
 
 Total: {{currency}} {{total_amount}}
 
@@ -98,9 +467,11 @@ Your e-ticket will be sent separately.
 VAYA Flights - Your journey starts here
 Need help? Contact us at support@vaya.my",
         );
+    }
 
-        // Payment confirmation
-        let _ = hbs.register_template_string(
+    /// Register the payment confirmation email
+    fn register_payment_templates(&mut self) {
+        let _ = self.register(
             "payment_confirmation_html",
             r#"<!DOCTYPE html>
 <html>
@@ -116,15 +487,19 @@ Need help? Contact us at support@vaya.my",
 </body>
 </html>"#,
         );
+    }
 
-        // Flight reminder
-        let _ = hbs.register_template_string(
+    /// Register the pre-departure flight reminder
+    fn register_reminder_templates(&mut self) {
+        let _ = self.register(
             "flight_reminder_text",
             "VAYA Flight Reminder: Your flight {{flight_number}} from {{origin}} to {{destination}} departs in {{hours_until}} hours. Check-in at {{airport_terminal}}.",
         );
+    }
 
-        // Price alert
-        let _ = hbs.register_template_string(
+    /// Register the price drop alert (HTML and text)
+    fn register_price_alert_templates(&mut self) {
+        let _ = self.register(
             "price_alert_html",
             r#"<!DOCTYPE html>
 <html>
@@ -142,8 +517,16 @@ Need help? Contact us at support@vaya.my",
 </html>"#,
         );
 
+        let _ = self.register(
+            "price_alert_text",
+            "VAYA Price Alert: {{origin}} to {{destination}} is now {{currency}} {{new_price}} (was {{currency}} {{old_price}}, save {{currency}} {{savings}}). Book: {{booking_url}}",
+        );
+    }
+
+    /// Register the welcome and password reset emails
+    fn register_account_templates(&mut self) {
         // Welcome email
-        let _ = hbs.register_template_string(
+        let _ = self.register(
             "welcome_html",
             r#"<!DOCTYPE html>
 <html>
@@ -161,7 +544,7 @@ Need help? Contact us at support@vaya.my",
         );
 
         // Password reset
-        let _ = hbs.register_template_string(
+        let _ = self.register(
             "password_reset_html",
             r#"<!DOCTYPE html>
 <html>
@@ -181,13 +564,82 @@ Need help? Contact us at support@vaya.my",
         );
     }
 
+    /// Register the Malay and Chinese booking confirmations
+    fn register_localized_templates(&mut self) {
+        for (name, locale, template) in LOCALIZED_TEMPLATES {
+            let _ = self.register_localized(name, locale, template);
+        }
+    }
+
     /// Register a custom template
+    ///
+    /// Names ending in `_text` (before any `.locale` suffix) render without
+    /// HTML escaping.
     pub fn register(&mut self, name: &str, template: &str) -> NotificationResult<()> {
-        self.hbs
-            .register_template_string(name, template)
-            .map_err(|e| {
-                NotificationError::TemplateError(format!("Failed to register template: {e}"))
-            })
+        let hbs = if is_text(name) {
+            &mut self.text
+        } else {
+            &mut self.hbs
+        };
+        hbs.register_template_string(name, template).map_err(|e| {
+            NotificationError::TemplateError(format!("Failed to register template: {e}"))
+        })?;
+        self.sources.insert(name.to_string(), template.to_string());
+        Ok(())
+    }
+
+    /// Register a template variant for a locale
+    ///
+    /// # Errors
+    ///
+    /// Returns a template error if the source does not parse.
+    pub fn register_localized(
+        &mut self,
+        name: &str,
+        locale: &str,
+        template: &str,
+    ) -> NotificationResult<()> {
+        self.register(&localized_name(name, locale), template)
+    }
+
+    /// Register a partial, included from any template with `{{> name}}`
+    ///
+    /// # Errors
+    ///
+    /// Returns a template error if the source does not parse.
+    pub fn register_partial(&mut self, name: &str, partial: &str) -> NotificationResult<()> {
+        for hbs in [&mut self.hbs, &mut self.text] {
+            hbs.register_partial(name, partial).map_err(|e| {
+                NotificationError::TemplateError(format!("Failed to register partial: {e}"))
+            })?;
+        }
+        self.sources.insert(name.to_string(), partial.to_string());
+        Ok(())
+    }
+
+    /// Registered template to use for a name and locale
+    ///
+    /// Tries the full locale tag, its language, the default locale and
+    /// finally the unlocalized template.
+    #[must_use]
+    pub fn resolve(&self, name: &str, locale: Option<&str>) -> Option<String> {
+        let mut candidates = Vec::new();
+        if let Some(locale) = locale.map(normalize_locale).filter(|l| !l.is_empty()) {
+            if let Some((language, _)) = locale.split_once('-') {
+                let language = language.to_string();
+                candidates.push(locale);
+                candidates.push(language);
+            } else {
+                candidates.push(locale);
+            }
+        }
+        candidates.push(self.default_locale.clone());
+
+        candidates
+            .iter()
+            .map(|locale| localized_name(name, locale))
+            .chain(std::iter::once(name.to_string()))
+            .find(|candidate| self.has_template(candidate))
     }
 
     /// Render a template with context
@@ -196,27 +648,66 @@ Need help? Contact us at support@vaya.my",
         template_name: &str,
         context: &HashMap<String, serde_json::Value>,
     ) -> NotificationResult<String> {
-        if !self.hbs.has_template(template_name) {
+        let hbs = if self.text.has_template(template_name) {
+            &self.text
+        } else if self.hbs.has_template(template_name) {
+            &self.hbs
+        } else {
             return Err(NotificationError::TemplateNotFound(
                 template_name.to_string(),
             ));
-        }
+        };
 
-        self.hbs
-            .render(template_name, context)
+        hbs.render(template_name, context)
             .map_err(NotificationError::from)
     }
 
+    /// Render the best variant of a template for a locale
+    ///
+    /// See [`TemplateEngine::resolve`] for the fallback order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TemplateNotFound` if no variant is registered, or a
+    /// template error if rendering fails.
+    pub fn render_localized(
+        &self,
+        template_name: &str,
+        locale: Option<&str>,
+        context: &HashMap<String, serde_json::Value>,
+    ) -> NotificationResult<String> {
+        let name = self
+            .resolve(template_name, locale)
+            .ok_or_else(|| NotificationError::TemplateNotFound(template_name.to_string()))?;
+        self.render(&name, context)
+    }
+
     /// Check if template exists
     #[must_use]
     pub fn has_template(&self, name: &str) -> bool {
-        self.hbs.has_template(name)
+        self.hbs.has_template(name) || self.text.has_template(name)
     }
 
-    /// Get list of registered templates
+    /// Get the source of a registered template
+    #[must_use]
+    pub fn source(&self, name: &str) -> Option<&str> {
+        self.sources.get(name).map(String::as_str)
+    }
+
+    /// Get list of registered templates, including partials and
+    /// localized variants
     #[must_use]
     pub fn list_templates(&self) -> Vec<String> {
-        self.hbs.get_templates().keys().cloned().collect()
+        let mut names: Vec<String> = self
+            .hbs
+            .get_templates()
+            .keys()
+            .chain(self.text.get_templates().keys())
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        names
     }
 }
 
@@ -261,6 +752,18 @@ mod tests {
         assert!(html.contains("John Doe"));
         assert!(html.contains("VAY123"));
         assert!(html.contains("KUL"));
+        assert!(!html.contains("Assistance Requested"));
+
+        context.insert(
+            "assistance".to_string(),
+            serde_json::json!([{"name": "DOE/JOHN", "needs": ["Blind or visually impaired", "Travelling with a service animal"]}]),
+        );
+        let text = engine
+            .render("booking_confirmation_text", &context)
+            .expect("Should render");
+        assert!(
+            text.contains("DOE/JOHN: Blind or visually impaired; Travelling with a service animal")
+        );
     }
 
     #[test]
@@ -287,6 +790,117 @@ mod tests {
         assert_eq!(rendered.expect("Should render"), "Hello, World!");
     }
 
+    fn confirmation_context() -> HashMap<String, serde_json::Value> {
+        let mut context = HashMap::new();
+        for (key, value) in [
+            ("passenger_name", serde_json::json!("Tom & <Jerry>")),
+            ("booking_ref", serde_json::json!("ABC234")),
+            ("origin", serde_json::json!("KUL")),
+            ("destination", serde_json::json!("NRT")),
+            ("departure_date", serde_json::json!("2030-06-01")),
+            ("flight_number", serde_json::json!("MH88")),
+            ("currency", serde_json::json!("MYR")),
+            ("total_amount", serde_json::json!("1,500.00")),
+            (
+                "passengers",
+                serde_json::json!(["RAHMAN/AISYAH MS", "RAHMAN/IMAN MSTR"]),
+            ),
+            (
+                "segments",
+                serde_json::json!([
+                    {"flight_number": "MH88", "origin": "KUL", "departure": "2030-06-01 23:30 +08:00",
+                     "destination": "NRT", "arrival": "2030-06-02 07:30 +09:00"},
+                    {"flight_number": "MH89", "origin": "NRT", "departure": "2030-06-08 10:30 +09:00",
+                     "destination": "KUL", "arrival": "2030-06-08 16:45 +08:00"},
+                ]),
+            ),
+        ] {
+            context.insert(key.to_string(), value);
+        }
+        context
+    }
+
+    #[test]
+    fn test_loops_partials_and_escaping() {
+        let engine = TemplateEngine::new();
+        let context = confirmation_context();
+
+        let text = engine
+            .render("booking_confirmation_text", &context)
+            .expect("Should render");
+        assert!(text.contains("MH88  KUL 2030-06-01 23:30 +08:00 -> NRT 2030-06-02 07:30 +09:00"));
+        assert!(text.contains("MH89  NRT"));
+        assert!(text.contains("PASSENGERS\n----------\nRAHMAN/AISYAH MS\nRAHMAN/IMAN MSTR\n"));
+        // Segments replace the single-flight summary
+        assert!(!text.contains("Route:"));
+        // Text bodies are not HTML-escaped
+        assert!(text.contains("Dear Tom & <Jerry>,"));
+
+        let html = engine
+            .render("booking_confirmation_html", &context)
+            .expect("Should render");
+        assert!(html.contains("Dear Tom &amp; &lt;Jerry&gt;,"));
+        assert!(html.contains("<p><strong>MH89</strong> NRT 2030-06-08 10:30 +09:00"));
+        assert!(html.contains("<p>RAHMAN/IMAN MSTR</p>"));
+        assert!(html.contains("max-width: 600px"));
+
+        let mut engine = engine;
+        engine
+            .register_partial("signature", "-- {{team}}")
+            .expect("register partial");
+        engine
+            .register(
+                "note_text",
+                "{{#each items}}- {{this}}\n{{/each}}{{> signature}}",
+            )
+            .expect("register");
+        let mut context = HashMap::new();
+        context.insert("items".to_string(), serde_json::json!(["a", "b"]));
+        context.insert("team".to_string(), serde_json::json!("Ops & Support"));
+        assert_eq!(
+            engine.render("note_text", &context).expect("Should render"),
+            "- a\n- b\n-- Ops & Support"
+        );
+    }
+
+    #[test]
+    fn test_localized_templates() {
+        let engine = TemplateEngine::new();
+        let context = confirmation_context();
+        let render = |engine: &TemplateEngine, locale: Option<&str>| {
+            engine
+                .render_localized("booking_confirmation_html", locale, &context)
+                .expect("Should render")
+        };
+
+        assert!(render(&engine, Some("ms-MY")).contains("Tempahan Disahkan!"));
+        assert!(render(&engine, Some("zh_CN")).contains("预订已确认！"));
+        assert!(render(&engine, Some("en")).contains("Booking Confirmed!"));
+        // Unsupported locales fall back to the default
+        assert!(render(&engine, Some("fr")).contains("Booking Confirmed!"));
+        assert!(render(&engine, None).contains("Booking Confirmed!"));
+
+        let text = engine
+            .render_localized("booking_confirmation_text", Some("ms"), &context)
+            .expect("Should render");
+        assert!(text.contains("PENUMPANG"));
+        assert!(text.contains("Kepada Tom & <Jerry>,"));
+
+        let engine = TemplateEngine::new().with_default_locale("ms");
+        assert!(render(&engine, None).contains("Tempahan Disahkan!"));
+        assert_eq!(
+            engine.resolve("welcome_html", Some("zh")).as_deref(),
+            Some("welcome_html")
+        );
+        assert!(engine.resolve("missing_html", Some("ms")).is_none());
+
+        for locale in SUPPORTED_LOCALES {
+            assert!(engine
+                .resolve("booking_confirmation_text", Some(locale))
+                .is_some());
+        }
+    }
+
     #[test]
     fn test_list_templates() {
         let engine = TemplateEngine::new();
@@ -40,6 +40,38 @@ impl NotificationStatus {
         matches!(self, Self::Delivered | Self::Opened | Self::Clicked)
     }
 
+    /// Stable lowercase name, for storage
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Sent => "sent",
+            Self::Delivered => "delivered",
+            Self::Opened => "opened",
+            Self::Clicked => "clicked",
+            Self::Bounced => "bounced",
+            Self::Failed => "failed",
+            Self::SpamComplaint => "spam_complaint",
+        }
+    }
+
+    /// Parse a name produced by [`NotificationStatus::as_str`]
+    #[must_use]
+    pub fn parse(s: &str) -> Option<Self> {
+        [
+            Self::Queued,
+            Self::Sent,
+            Self::Delivered,
+            Self::Opened,
+            Self::Clicked,
+            Self::Bounced,
+            Self::Failed,
+            Self::SpamComplaint,
+        ]
+        .into_iter()
+        .find(|status| status.as_str() == s)
+    }
+
     /// Display name
     #[must_use]
     pub const fn display_name(&self) -> &'static str {
@@ -82,6 +114,67 @@ pub enum NotificationType {
 }
 
 impl NotificationType {
+    /// Every notification type
+    pub const ALL: [Self; 10] = [
+        Self::BookingConfirmation,
+        Self::PaymentConfirmation,
+        Self::ETicket,
+        Self::FlightReminder,
+        Self::FlightChange,
+        Self::FlightCancellation,
+        Self::PriceAlert,
+        Self::Marketing,
+        Self::PasswordReset,
+        Self::Welcome,
+    ];
+
+    /// Context variables the sender supplies for this type
+    #[must_use]
+    pub const fn context_variables(&self) -> &'static [&'static str] {
+        match self {
+            Self::BookingConfirmation => &[
+                "passenger_name",
+                "booking_ref",
+                "origin",
+                "destination",
+                "departure_date",
+                "flight_number",
+                "currency",
+                "total_amount",
+                "assistance",
+                "passengers",
+                "segments",
+            ],
+            Self::PaymentConfirmation => &["passenger_name", "booking_ref", "currency", "amount"],
+            Self::ETicket => &["passenger_name", "booking_ref", "ticket_number"],
+            Self::FlightReminder => &[
+                "flight_number",
+                "origin",
+                "destination",
+                "hours_until",
+                "airport_terminal",
+            ],
+            Self::FlightChange | Self::FlightCancellation => &[
+                "passenger_name",
+                "booking_ref",
+                "flight_number",
+                "origin",
+                "destination",
+            ],
+            Self::PriceAlert => &[
+                "origin",
+                "destination",
+                "currency",
+                "new_price",
+                "old_price",
+                "savings",
+                "booking_url",
+            ],
+            Self::Marketing | Self::Welcome => &["name"],
+            Self::PasswordReset => &["name", "reset_link"],
+        }
+    }
+
     /// Get template name for this type
     #[must_use]
     pub const fn template_name(&self) -> &'static str {
@@ -150,6 +243,8 @@ pub struct EmailRequest {
     pub headers: HashMap<String, String>,
     /// Tags for analytics
     pub tags: Vec<String>,
+    /// Template locale ("ms", "zh-CN"); `None` uses the default locale
+    pub locale: Option<String>,
 }
 
 impl EmailRequest {
@@ -169,6 +264,7 @@ impl EmailRequest {
             reply_to: None,
             headers: HashMap::new(),
             tags: Vec::new(),
+            locale: None,
         }
     }
 
@@ -188,6 +284,7 @@ impl EmailRequest {
             reply_to: None,
             headers: HashMap::new(),
             tags: vec![notification_type.template_name().to_string()],
+            locale: None,
         }
     }
 
@@ -228,6 +325,13 @@ impl EmailRequest {
         self
     }
 
+    /// Set the template locale
+    #[must_use]
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
     /// Add attachment
     #[must_use]
     pub fn with_attachment(mut self, attachment: EmailAttachment) -> Self {
@@ -278,6 +382,93 @@ pub struct EmailAttachment {
     pub content_id: Option<String>,
 }
 
+impl EmailAttachment {
+    /// Create a regular attachment, base64 encoding the content
+    #[must_use]
+    pub fn new(
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        content: &[u8],
+    ) -> Self {
+        Self {
+            filename: filename.into(),
+            content_type: content_type.into(),
+            content: base64_encode(content),
+            disposition: AttachmentDisposition::Attachment,
+            content_id: None,
+        }
+    }
+
+    /// Embed inline under a content ID (referenced as `cid:...`)
+    #[must_use]
+    pub fn inline(mut self, content_id: impl Into<String>) -> Self {
+        self.disposition = AttachmentDisposition::Inline;
+        self.content_id = Some(content_id.into());
+        self
+    }
+}
+
+/// Standard alphabet base64 with padding, as email providers expect
+pub(crate) fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b0 = usize::from(chunk[0]);
+        let b1 = usize::from(chunk.get(1).copied().unwrap_or(0));
+        let b2 = usize::from(chunk.get(2).copied().unwrap_or(0));
+        let n = (b0 << 16) | (b1 << 8) | b2;
+
+        result.push(char::from(ALPHABET[(n >> 18) & 0x3F]));
+        result.push(char::from(ALPHABET[(n >> 12) & 0x3F]));
+        if chunk.len() > 1 {
+            result.push(char::from(ALPHABET[(n >> 6) & 0x3F]));
+        } else {
+            result.push('=');
+        }
+        if chunk.len() > 2 {
+            result.push(char::from(ALPHABET[n & 0x3F]));
+        } else {
+            result.push('=');
+        }
+    }
+    result
+}
+
+/// Decode standard alphabet base64, ignoring line breaks
+pub(crate) fn base64_decode(data: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some(u32::from(c - b'A')),
+            b'a'..=b'z' => Some(u32::from(c - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(c - b'0') + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let clean: Vec<u8> = data.bytes().filter(|c| !c.is_ascii_whitespace()).collect();
+    let mut result = Vec::with_capacity(clean.len() / 4 * 3);
+    for chunk in clean.chunks(4) {
+        if chunk.len() != 4 {
+            return None;
+        }
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 {
+            return None;
+        }
+        let mut n = 0u32;
+        for &c in &chunk[..4 - padding] {
+            n = (n << 6) | value(c)?;
+        }
+        n <<= 6 * padding;
+        let bytes = n.to_be_bytes();
+        result.extend_from_slice(&bytes[1..4 - padding]);
+    }
+    Some(result)
+}
+
 /// Attachment disposition
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AttachmentDisposition {
@@ -292,6 +483,8 @@ pub enum AttachmentDisposition {
 pub struct EmailResult {
     /// Message ID from provider
     pub message_id: String,
+    /// Provider that accepted the message (`sendgrid`, `mailgun`, `smtp`)
+    pub provider: String,
     /// Status
     pub status: NotificationStatus,
     /// Sent timestamp
@@ -311,6 +504,8 @@ pub struct SmsRequest {
     pub context: HashMap<String, serde_json::Value>,
     /// Notification type
     pub notification_type: NotificationType,
+    /// Template locale; `None` uses the default locale
+    pub locale: Option<String>,
 }
 
 impl SmsRequest {
@@ -323,6 +518,7 @@ impl SmsRequest {
             template: None,
             context: HashMap::new(),
             notification_type: NotificationType::FlightReminder,
+            locale: None,
         }
     }
 
@@ -342,6 +538,13 @@ impl SmsRequest {
         self
     }
 
+    /// Set the template locale
+    #[must_use]
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
     /// Validate SMS request
     pub fn validate(&self) -> crate::NotificationResult<()> {
         if self.to_phone.is_empty() {
@@ -389,6 +592,11 @@ mod tests {
     #[test]
     fn test_notification_status() {
         assert!(NotificationStatus::Delivered.is_terminal());
+        assert_eq!(
+            NotificationStatus::parse(NotificationStatus::SpamComplaint.as_str()),
+            Some(NotificationStatus::SpamComplaint)
+        );
+        assert_eq!(NotificationStatus::parse("Delivered"), None);
         assert!(NotificationStatus::Bounced.is_terminal());
         assert!(!NotificationStatus::Sent.is_terminal());
 
@@ -430,6 +638,31 @@ mod tests {
         assert!(email.validate().is_ok());
     }
 
+    #[test]
+    fn test_email_attachment_encoding() {
+        for (data, encoded) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (&[0xfb, 0xff, 0xbf], "+/+/"),
+        ] {
+            assert_eq!(base64_encode(data), encoded);
+            assert_eq!(base64_decode(encoded).as_deref(), Some(data));
+        }
+        assert_eq!(base64_decode("Zm9v\r\nYg==").as_deref(), Some(&b"foob"[..]));
+        assert!(base64_decode("Zm9").is_none());
+        assert!(base64_decode("Zm9*").is_none());
+
+        let pdf = EmailAttachment::new("ticket.pdf", "application/pdf", b"%PDF");
+        assert_eq!(pdf.content, "JVBERg==");
+        assert_eq!(pdf.disposition, AttachmentDisposition::Attachment);
+        let logo = EmailAttachment::new("logo.png", "image/png", b"").inline("logo");
+        assert_eq!(logo.disposition, AttachmentDisposition::Inline);
+        assert_eq!(logo.content_id.as_deref(), Some("logo"));
+    }
+
     #[test]
     fn test_sms_request() {
         let sms = SmsRequest::new("+60123456789", "Your flight is in 2 hours");
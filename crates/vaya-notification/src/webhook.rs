@@ -0,0 +1,219 @@
+//! Delivery status webhooks
+//!
+//! Providers report what happened to a message after accepting it:
+//! `SendGrid` posts a JSON array of events, Twilio posts a form for each
+//! status change. Both are parsed into [`DeliveryReport`]s keyed by the
+//! message ID returned when the message was sent ([`EmailResult`] and
+//! [`SmsResult`]). Events that say nothing about delivery (`processed`,
+//! `deferred`, `queued`, unsubscribes) are dropped.
+//!
+//! Signature verification is left to the HTTP layer, which has the raw
+//! request and headers.
+//!
+//! [`EmailResult`]: crate::EmailResult
+//! [`SmsResult`]: crate::SmsResult
+
+use std::collections::HashMap;
+
+use vaya_common::Timestamp;
+
+use crate::error::{NotificationError, NotificationResult};
+use crate::types::NotificationStatus;
+
+/// A provider's report on one message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryReport {
+    /// Provider message ID, as returned on send
+    pub message_id: String,
+    /// New delivery status
+    pub status: NotificationStatus,
+    /// Provider's reason for a bounce or failure
+    pub reason: Option<String>,
+    /// When the provider saw the event
+    pub timestamp: Option<Timestamp>,
+}
+
+/// Parse a `SendGrid` event webhook body
+///
+/// `sg_message_id` carries the `X-Message-Id` from the send followed by
+/// `.filter...`; only the send ID is kept.
+///
+/// # Errors
+///
+/// Returns `InvalidWebhook` if the body is not a JSON array of events.
+pub fn parse_sendgrid_events(body: &[u8]) -> NotificationResult<Vec<DeliveryReport>> {
+    let events: Vec<serde_json::Value> = serde_json::from_slice(body)
+        .map_err(|e| NotificationError::InvalidWebhook(format!("SendGrid events: {e}")))?;
+
+    Ok(events
+        .iter()
+        .filter_map(|event| {
+            let status = match event.get("event")?.as_str()? {
+                "delivered" => NotificationStatus::Delivered,
+                "bounce" => NotificationStatus::Bounced,
+                "dropped" => NotificationStatus::Failed,
+                "open" => NotificationStatus::Opened,
+                "click" => NotificationStatus::Clicked,
+                "spamreport" => NotificationStatus::SpamComplaint,
+                _ => return None,
+            };
+            let sg_message_id = event.get("sg_message_id")?.as_str()?;
+            let message_id = sg_message_id
+                .split(".filter")
+                .next()
+                .unwrap_or(sg_message_id);
+            let reason = ["reason", "response"]
+                .iter()
+                .find_map(|key| event.get(*key).and_then(serde_json::Value::as_str))
+                .map(str::to_string);
+            Some(DeliveryReport {
+                message_id: message_id.to_string(),
+                status,
+                reason,
+                timestamp: event
+                    .get("timestamp")
+                    .and_then(serde_json::Value::as_i64)
+                    .map(Timestamp::from_unix),
+            })
+        })
+        .collect())
+}
+
+/// Parse a Twilio status callback (`application/x-www-form-urlencoded`)
+///
+/// Returns `None` for statuses that are not a delivery outcome yet
+/// (`queued`, `accepted`, `sending`). `undelivered` means the carrier
+/// rejected the message and is reported as a bounce.
+///
+/// # Errors
+///
+/// Returns `InvalidWebhook` if `MessageSid` or `MessageStatus` is missing.
+pub fn parse_twilio_status(body: &[u8]) -> NotificationResult<Option<DeliveryReport>> {
+    let form = parse_form(body);
+    let field = |name: &str| {
+        form.get(name)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| NotificationError::InvalidWebhook(format!("Twilio: missing {name}")))
+    };
+    let message_id = field("MessageSid")?;
+    let status = match field("MessageStatus")?.as_str() {
+        "sent" => NotificationStatus::Sent,
+        "delivered" => NotificationStatus::Delivered,
+        "undelivered" => NotificationStatus::Bounced,
+        "failed" => NotificationStatus::Failed,
+        _ => return Ok(None),
+    };
+    Ok(Some(DeliveryReport {
+        message_id: message_id.clone(),
+        status,
+        reason: form
+            .get("ErrorCode")
+            .filter(|c| !c.is_empty())
+            .map(|code| format!("Twilio error {code}")),
+        timestamp: None,
+    }))
+}
+
+/// Decode a URL-encoded form body; malformed escapes are kept as-is
+fn parse_form(body: &[u8]) -> HashMap<String, String> {
+    body.split(|b| *b == b'&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut parts = pair.splitn(2, |b| *b == b'=');
+            let key = url_decode(parts.next().unwrap_or_default());
+            let value = url_decode(parts.next().unwrap_or_default());
+            (key, value)
+        })
+        .collect()
+}
+
+fn url_decode(input: &[u8]) -> String {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < input.len() => {
+                let hex = std::str::from_utf8(&input[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sendgrid_events() {
+        let body = br#"[
+            {"email": "a@example.com", "event": "processed", "sg_message_id": "abc123.filter01.1"},
+            {"email": "a@example.com", "event": "delivered", "timestamp": 1906502400,
+             "sg_message_id": "abc123.filter01.1", "response": "250 OK"},
+            {"email": "b@example.com", "event": "bounce", "sg_message_id": "def456.filter02.7",
+             "reason": "550 5.1.1 User unknown"},
+            {"email": "c@example.com", "event": "spamreport", "sg_message_id": "ghi789"},
+            {"email": "d@example.com", "event": "delivered"}
+        ]"#;
+        let reports = parse_sendgrid_events(body).expect("parse");
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].message_id, "abc123");
+        assert_eq!(reports[0].status, NotificationStatus::Delivered);
+        assert_eq!(
+            reports[0].timestamp,
+            Some(Timestamp::from_unix(1_906_502_400))
+        );
+        assert_eq!(reports[1].status, NotificationStatus::Bounced);
+        assert_eq!(reports[1].reason.as_deref(), Some("550 5.1.1 User unknown"));
+        assert_eq!(reports[2].message_id, "ghi789");
+        assert_eq!(reports[2].status, NotificationStatus::SpamComplaint);
+
+        assert!(matches!(
+            parse_sendgrid_events(b"{\"event\":\"delivered\"}"),
+            Err(NotificationError::InvalidWebhook(_))
+        ));
+    }
+
+    #[test]
+    fn test_twilio_status() {
+        let report = parse_twilio_status(
+            b"MessageSid=SM123&MessageStatus=undelivered&ErrorCode=30003&To=%2B60123456789",
+        )
+        .expect("parse")
+        .expect("report");
+        assert_eq!(report.message_id, "SM123");
+        assert_eq!(report.status, NotificationStatus::Bounced);
+        assert_eq!(report.reason.as_deref(), Some("Twilio error 30003"));
+
+        let report = parse_twilio_status(b"MessageStatus=delivered&MessageSid=SM9&ErrorCode=")
+            .expect("parse")
+            .expect("report");
+        assert_eq!(report.status, NotificationStatus::Delivered);
+        assert!(report.reason.is_none());
+
+        assert!(parse_twilio_status(b"MessageSid=SM1&MessageStatus=sending")
+            .expect("parse")
+            .is_none());
+        assert!(matches!(
+            parse_twilio_status(b"MessageStatus=sent"),
+            Err(NotificationError::InvalidWebhook(_))
+        ));
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode(b"a+b%2Bc%3d"), "a b+c=");
+        assert_eq!(url_decode(b"100%"), "100%");
+        assert_eq!(url_decode(b"%zz"), "%zz");
+    }
+}
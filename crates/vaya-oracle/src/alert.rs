@@ -1,9 +1,16 @@
 //! Price alert system
+//!
+//! An alert's [`AlertCondition`] is checked by [`AlertManager`] against an
+//! [`AlertObservation`]: the current fare, plus the 30-day average, trend
+//! and per-cabin fares when the caller has them. Conditions that need a
+//! value the observation lacks don't fire.
 
 use time::{Date, OffsetDateTime};
-use vaya_common::{CurrencyCode, IataCode, MinorUnits};
+use vaya_common::bus::{self, topics};
+use vaya_common::events::AlertTriggered;
+use vaya_common::{CabinClass, CurrencyCode, IataCode, MinorUnits};
 
-use crate::{OracleError, OracleResult};
+use crate::{OracleError, OracleResult, PriceInsight, PriceTrend};
 
 /// Alert trigger type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +23,12 @@ pub enum AlertTrigger {
     AnyPrice,
     /// Trigger on best price in time window
     BestPrice,
+    /// Trigger when price drops below the 30-day average
+    PriceDropsBelowAverage,
+    /// Trigger when the trend turns falling
+    TrendFalling,
+    /// Trigger when a cabin's price drops below threshold
+    CabinPriceBelow,
 }
 
 impl AlertTrigger {
@@ -26,10 +39,175 @@ impl AlertTrigger {
             AlertTrigger::PriceDropsBy => "PRICE_DROPS_BY",
             AlertTrigger::AnyPrice => "ANY_PRICE",
             AlertTrigger::BestPrice => "BEST_PRICE",
+            AlertTrigger::PriceDropsBelowAverage => "PRICE_DROPS_BELOW_AVERAGE",
+            AlertTrigger::TrendFalling => "TREND_FALLING",
+            AlertTrigger::CabinPriceBelow => "CABIN_PRICE_BELOW",
         }
     }
 }
 
+/// When an alert fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertCondition {
+    /// Price at or below a threshold
+    PriceBelow {
+        /// Threshold price
+        threshold: MinorUnits,
+    },
+    /// Price down at least `percent` from a reference price
+    DropsByPercent {
+        /// Price when the alert was set
+        reference: MinorUnits,
+        /// Drop required (1-99)
+        percent: u8,
+    },
+    /// Price below the 30-day average
+    BelowAverage,
+    /// Trend is falling after not falling at the previous check
+    TrendTurnsFalling,
+    /// Fare in a cabin at or below a threshold
+    CabinBelow {
+        /// Cabin watched
+        cabin: CabinClass,
+        /// Threshold price
+        threshold: MinorUnits,
+    },
+    /// Any price available
+    AnyPrice,
+    /// Best price in the time window (decided by the caller)
+    BestPrice,
+}
+
+impl AlertCondition {
+    /// Trigger type reported when the condition fires
+    pub fn trigger(&self) -> AlertTrigger {
+        match self {
+            AlertCondition::PriceBelow { .. } => AlertTrigger::PriceDropsBelow,
+            AlertCondition::DropsByPercent { .. } => AlertTrigger::PriceDropsBy,
+            AlertCondition::BelowAverage => AlertTrigger::PriceDropsBelowAverage,
+            AlertCondition::TrendTurnsFalling => AlertTrigger::TrendFalling,
+            AlertCondition::CabinBelow { .. } => AlertTrigger::CabinPriceBelow,
+            AlertCondition::AnyPrice => AlertTrigger::AnyPrice,
+            AlertCondition::BestPrice => AlertTrigger::BestPrice,
+        }
+    }
+
+    /// Fixed price the condition fires at, if it has one
+    pub fn target_price(&self) -> Option<MinorUnits> {
+        match *self {
+            AlertCondition::PriceBelow { threshold }
+            | AlertCondition::CabinBelow { threshold, .. } => Some(threshold),
+            AlertCondition::DropsByPercent { reference, percent } => Some(MinorUnits::new(
+                reference.as_i64() * (100 - percent as i64) / 100,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Price the condition is judged on: the cabin's fare for cabin alerts
+    pub fn observed_price(&self, observation: &AlertObservation) -> Option<MinorUnits> {
+        match self {
+            AlertCondition::CabinBelow { cabin, .. } => observation.cabin_price(*cabin),
+            _ => Some(observation.price),
+        }
+    }
+
+    /// Whether the observation meets the condition
+    ///
+    /// `previous_trend` is the trend seen at the alert's last check.
+    pub fn is_met(
+        &self,
+        observation: &AlertObservation,
+        previous_trend: Option<PriceTrend>,
+    ) -> bool {
+        match self {
+            AlertCondition::BelowAverage => observation
+                .avg_price_30d
+                .is_some_and(|avg| observation.price < avg),
+            AlertCondition::TrendTurnsFalling => {
+                observation.trend.is_some_and(|t| t.is_falling())
+                    && previous_trend.is_some_and(|t| !t.is_falling())
+            }
+            AlertCondition::AnyPrice | AlertCondition::BestPrice => true,
+            _ => match (self.observed_price(observation), self.target_price()) {
+                (Some(price), Some(target)) => price <= target,
+                _ => false,
+            },
+        }
+    }
+
+    /// Check the condition's parameters
+    pub fn validate(&self) -> OracleResult<()> {
+        match *self {
+            AlertCondition::PriceBelow { threshold }
+            | AlertCondition::CabinBelow { threshold, .. }
+                if threshold.as_i64() <= 0 =>
+            {
+                Err(OracleError::InvalidThreshold(
+                    "Threshold price must be positive".into(),
+                ))
+            }
+            AlertCondition::DropsByPercent { reference, .. } if reference.as_i64() <= 0 => Err(
+                OracleError::InvalidThreshold("Reference price must be positive".into()),
+            ),
+            AlertCondition::DropsByPercent { percent, .. } if !(1..=99).contains(&percent) => Err(
+                OracleError::InvalidThreshold("Drop percentage must be between 1 and 99".into()),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Market state an alert is checked against
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertObservation {
+    /// Current lowest fare
+    pub price: MinorUnits,
+    /// 30-day average fare
+    pub avg_price_30d: Option<MinorUnits>,
+    /// Current trend
+    pub trend: Option<PriceTrend>,
+    /// Lowest fare per cabin
+    pub cabin_prices: Vec<(CabinClass, MinorUnits)>,
+}
+
+impl AlertObservation {
+    /// Observation of the current fare only
+    pub fn price(price: MinorUnits) -> Self {
+        Self {
+            price,
+            avg_price_30d: None,
+            trend: None,
+            cabin_prices: Vec::new(),
+        }
+    }
+
+    /// Observation of a route insight's current fare, average and trend
+    pub fn from_insight(insight: &PriceInsight) -> Self {
+        Self {
+            price: insight.current_price,
+            avg_price_30d: Some(insight.avg_price_30d),
+            trend: Some(insight.trend),
+            cabin_prices: Vec::new(),
+        }
+    }
+
+    /// Add the lowest fare in a cabin
+    pub fn with_cabin_price(mut self, cabin: CabinClass, price: MinorUnits) -> Self {
+        self.cabin_prices.push((cabin, price));
+        self
+    }
+
+    /// Lowest fare observed in a cabin
+    pub fn cabin_price(&self, cabin: CabinClass) -> Option<MinorUnits> {
+        self.cabin_prices
+            .iter()
+            .filter(|(c, _)| *c == cabin)
+            .map(|(_, price)| *price)
+            .min()
+    }
+}
+
 /// Alert status
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AlertStatus {
@@ -78,14 +256,10 @@ pub struct PriceAlert {
     pub departure_date: Date,
     /// End of date range (if flexible)
     pub departure_date_end: Option<Date>,
-    /// Trigger type
-    pub trigger: AlertTrigger,
-    /// Threshold price (for PriceDropsBelow)
-    pub threshold_price: Option<MinorUnits>,
-    /// Threshold percentage (for PriceDropsBy)
-    pub threshold_percent: Option<u8>,
-    /// Reference price (for percentage alerts)
-    pub reference_price: Option<MinorUnits>,
+    /// When the alert fires
+    pub condition: AlertCondition,
+    /// Trend seen at the last check
+    pub last_trend: Option<PriceTrend>,
     /// Currency
     pub currency: CurrencyCode,
     /// Current status
@@ -104,17 +278,19 @@ pub struct PriceAlert {
     pub notification_count: u32,
     /// Max notifications (0 = unlimited)
     pub max_notifications: u32,
+    /// Version for optimistic locking
+    pub version: u32,
 }
 
 impl PriceAlert {
-    /// Create a price drop alert
-    pub fn price_below(
+    /// Create an alert that notifies once when the condition is met
+    pub fn new(
         id: impl Into<String>,
         user_id: impl Into<String>,
         origin: IataCode,
         destination: IataCode,
         departure_date: Date,
-        threshold: MinorUnits,
+        condition: AlertCondition,
         currency: CurrencyCode,
     ) -> Self {
         let now = OffsetDateTime::now_utc().unix_timestamp();
@@ -126,10 +302,8 @@ impl PriceAlert {
             destination,
             departure_date,
             departure_date_end: None,
-            trigger: AlertTrigger::PriceDropsBelow,
-            threshold_price: Some(threshold),
-            threshold_percent: None,
-            reference_price: None,
+            condition,
+            last_trend: None,
             currency,
             status: AlertStatus::Active,
             created_at: now,
@@ -139,9 +313,32 @@ impl PriceAlert {
             expires_at: now + (30 * 24 * 3600), // 30 days default expiry
             notification_count: 0,
             max_notifications: 1, // Trigger once by default
+            version: 1,
         }
     }
 
+    /// Create a price drop alert
+    pub fn price_below(
+        id: impl Into<String>,
+        user_id: impl Into<String>,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        threshold: MinorUnits,
+        currency: CurrencyCode,
+    ) -> Self {
+        let condition = AlertCondition::PriceBelow { threshold };
+        Self::new(
+            id,
+            user_id,
+            origin,
+            destination,
+            departure_date,
+            condition,
+            currency,
+        )
+    }
+
     /// Create a percentage drop alert
     pub fn price_drop_percent(
         id: impl Into<String>,
@@ -153,29 +350,19 @@ impl PriceAlert {
         drop_percent: u8,
         currency: CurrencyCode,
     ) -> Self {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
-
-        Self {
-            id: id.into(),
-            user_id: user_id.into(),
+        let condition = AlertCondition::DropsByPercent {
+            reference: reference_price,
+            percent: drop_percent,
+        };
+        Self::new(
+            id,
+            user_id,
             origin,
             destination,
             departure_date,
-            departure_date_end: None,
-            trigger: AlertTrigger::PriceDropsBy,
-            threshold_price: None,
-            threshold_percent: Some(drop_percent),
-            reference_price: Some(reference_price),
+            condition,
             currency,
-            status: AlertStatus::Active,
-            created_at: now,
-            last_checked_at: None,
-            triggered_at: None,
-            triggered_price: None,
-            expires_at: now + (30 * 24 * 3600),
-            notification_count: 0,
-            max_notifications: 1,
-        }
+        )
     }
 
     /// Create alert for any price availability
@@ -187,29 +374,17 @@ impl PriceAlert {
         departure_date: Date,
         currency: CurrencyCode,
     ) -> Self {
-        let now = OffsetDateTime::now_utc().unix_timestamp();
-
-        Self {
-            id: id.into(),
-            user_id: user_id.into(),
+        let condition = AlertCondition::AnyPrice;
+        Self::new(
+            id,
+            user_id,
             origin,
             destination,
             departure_date,
-            departure_date_end: None,
-            trigger: AlertTrigger::AnyPrice,
-            threshold_price: None,
-            threshold_percent: None,
-            reference_price: None,
+            condition,
             currency,
-            status: AlertStatus::Active,
-            created_at: now,
-            last_checked_at: None,
-            triggered_at: None,
-            triggered_price: None,
-            expires_at: now + (30 * 24 * 3600),
-            notification_count: 0,
-            max_notifications: 0, // Unlimited
-        }
+        )
+        .with_max_notifications(0) // Unlimited
     }
 
     /// Set flexible date range
@@ -233,6 +408,11 @@ impl PriceAlert {
 
     /// Check if alert should trigger for given price
     pub fn should_trigger(&self, current_price: MinorUnits) -> bool {
+        self.should_trigger_on(&AlertObservation::price(current_price))
+    }
+
+    /// Check if alert should trigger for an observation
+    pub fn should_trigger_on(&self, observation: &AlertObservation) -> bool {
         if !self.status.can_notify() {
             return false;
         }
@@ -242,27 +422,7 @@ impl PriceAlert {
             return false;
         }
 
-        match self.trigger {
-            AlertTrigger::PriceDropsBelow => {
-                if let Some(threshold) = self.threshold_price {
-                    current_price.as_i64() <= threshold.as_i64()
-                } else {
-                    false
-                }
-            }
-            AlertTrigger::PriceDropsBy => {
-                if let (Some(reference), Some(percent)) =
-                    (self.reference_price, self.threshold_percent)
-                {
-                    let target = reference.as_i64() * (100 - percent as i64) / 100;
-                    current_price.as_i64() <= target
-                } else {
-                    false
-                }
-            }
-            AlertTrigger::AnyPrice => true,
-            AlertTrigger::BestPrice => true, // Handled externally
-        }
+        self.condition.is_met(observation, self.last_trend)
     }
 
     /// Mark alert as triggered
@@ -280,6 +440,7 @@ impl PriceAlert {
         if self.max_notifications > 0 && self.notification_count >= self.max_notifications {
             self.status = AlertStatus::Triggered;
         }
+        self.version += 1;
 
         Ok(())
     }
@@ -287,11 +448,13 @@ impl PriceAlert {
     /// Mark last check time
     pub fn mark_checked(&mut self) {
         self.last_checked_at = Some(OffsetDateTime::now_utc().unix_timestamp());
+        self.version += 1;
     }
 
     /// Pause alert
     pub fn pause(&mut self) {
         self.status = AlertStatus::Paused;
+        self.version += 1;
     }
 
     /// Resume alert
@@ -300,12 +463,14 @@ impl PriceAlert {
             return Err(OracleError::AlertAlreadyTriggered);
         }
         self.status = AlertStatus::Active;
+        self.version += 1;
         Ok(())
     }
 
     /// Cancel alert
     pub fn cancel(&mut self) {
         self.status = AlertStatus::Cancelled;
+        self.version += 1;
     }
 
     /// Check if alert has expired
@@ -313,6 +478,7 @@ impl PriceAlert {
         let now = OffsetDateTime::now_utc().unix_timestamp();
         if now > self.expires_at && self.status == AlertStatus::Active {
             self.status = AlertStatus::Expired;
+            self.version += 1;
             true
         } else {
             false
@@ -331,23 +497,9 @@ impl PriceAlert {
         ((self.expires_at - now) / 86400).max(0)
     }
 
-    /// Calculate target price for percentage drop
+    /// Price the alert fires at, when the condition has a fixed one
     pub fn target_price(&self) -> Option<MinorUnits> {
-        match self.trigger {
-            AlertTrigger::PriceDropsBelow => self.threshold_price,
-            AlertTrigger::PriceDropsBy => {
-                if let (Some(reference), Some(percent)) =
-                    (self.reference_price, self.threshold_percent)
-                {
-                    Some(MinorUnits::new(
-                        reference.as_i64() * (100 - percent as i64) / 100,
-                    ))
-                } else {
-                    None
-                }
-            }
-            _ => None,
-        }
+        self.condition.target_price()
     }
 }
 
@@ -410,25 +562,7 @@ impl AlertManager {
             });
         }
 
-        // Validate threshold for price drop alerts
-        if alert.trigger == AlertTrigger::PriceDropsBelow && alert.threshold_price.is_none() {
-            return Err(OracleError::InvalidThreshold(
-                "Threshold price required for PRICE_DROPS_BELOW alert".into(),
-            ));
-        }
-
-        if alert.trigger == AlertTrigger::PriceDropsBy {
-            if alert.reference_price.is_none() {
-                return Err(OracleError::InvalidThreshold(
-                    "Reference price required for PRICE_DROPS_BY alert".into(),
-                ));
-            }
-            if alert.threshold_percent.is_none() {
-                return Err(OracleError::InvalidThreshold(
-                    "Threshold percentage required for PRICE_DROPS_BY alert".into(),
-                ));
-            }
-        }
+        alert.condition.validate()?;
 
         // Check departure date is in future
         let today = OffsetDateTime::now_utc().date();
@@ -443,19 +577,49 @@ impl AlertManager {
 
     /// Check an alert against a price
     pub fn check_alert(&self, alert: &mut PriceAlert, price: MinorUnits) -> AlertCheckResult {
+        self.evaluate(alert, &AlertObservation::price(price))
+    }
+
+    /// Check an alert against an observation, triggering it if the condition is met
+    pub fn evaluate(
+        &self,
+        alert: &mut PriceAlert,
+        observation: &AlertObservation,
+    ) -> AlertCheckResult {
         alert.mark_checked();
 
-        let triggered = alert.should_trigger(price);
+        let triggered = alert.should_trigger_on(observation);
+        let price = alert
+            .condition
+            .observed_price(observation)
+            .unwrap_or(observation.price);
+        let target = match alert.condition {
+            AlertCondition::BelowAverage => observation.avg_price_30d,
+            condition => condition.target_price(),
+        };
         let savings = if triggered {
-            alert
-                .target_price()
-                .map(|target| MinorUnits::new((target.as_i64() - price.as_i64()).max(0)))
+            target.map(|target| MinorUnits::new((target.as_i64() - price.as_i64()).max(0)))
         } else {
             None
         };
+        if observation.trend.is_some() {
+            alert.last_trend = observation.trend;
+        }
 
-        if triggered {
-            let _ = alert.trigger(price);
+        if triggered && alert.trigger(price).is_ok() {
+            bus::publish(
+                &topics::ALERT_TRIGGERED,
+                AlertTriggered {
+                    alert_id: alert.id.clone(),
+                    user_id: alert.user_id.clone(),
+                    origin: alert.origin.as_str().to_string(),
+                    destination: alert.destination.as_str().to_string(),
+                    trigger: alert.condition.trigger().as_str().to_string(),
+                    price_minor: price.as_i64(),
+                    savings_minor: savings.map(|s| s.as_i64()),
+                    currency: alert.currency.as_str().to_string(),
+                },
+            );
         }
 
         AlertCheckResult {
@@ -478,7 +642,7 @@ mod tests {
             "user-1",
             IataCode::SIN,
             IataCode::BKK,
-            Date::from_calendar_date(2026, time::Month::July, 15).unwrap(),
+            Date::from_calendar_date(2030, time::Month::July, 15).unwrap(),
             MinorUnits::new(25000),
             CurrencyCode::SGD,
         )
@@ -489,8 +653,13 @@ mod tests {
         let alert = create_test_alert();
 
         assert_eq!(alert.id, "alert-1");
-        assert_eq!(alert.trigger, AlertTrigger::PriceDropsBelow);
-        assert_eq!(alert.threshold_price, Some(MinorUnits::new(25000)));
+        assert_eq!(alert.condition.trigger(), AlertTrigger::PriceDropsBelow);
+        assert_eq!(
+            alert.condition,
+            AlertCondition::PriceBelow {
+                threshold: MinorUnits::new(25000)
+            }
+        );
         assert_eq!(alert.status, AlertStatus::Active);
     }
 
@@ -501,15 +670,20 @@ mod tests {
             "user-1",
             IataCode::SIN,
             IataCode::BKK,
-            Date::from_calendar_date(2026, time::Month::July, 15).unwrap(),
+            Date::from_calendar_date(2030, time::Month::July, 15).unwrap(),
             MinorUnits::new(30000), // Reference $300
             20,                     // 20% drop
             CurrencyCode::SGD,
         );
 
-        assert_eq!(alert.trigger, AlertTrigger::PriceDropsBy);
-        assert_eq!(alert.reference_price, Some(MinorUnits::new(30000)));
-        assert_eq!(alert.threshold_percent, Some(20));
+        assert_eq!(alert.condition.trigger(), AlertTrigger::PriceDropsBy);
+        assert_eq!(
+            alert.condition,
+            AlertCondition::DropsByPercent {
+                reference: MinorUnits::new(30000),
+                percent: 20
+            }
+        );
 
         // Target should be $240 (20% off $300)
         assert_eq!(alert.target_price(), Some(MinorUnits::new(24000)));
@@ -536,7 +710,7 @@ mod tests {
             "user-1",
             IataCode::SIN,
             IataCode::BKK,
-            Date::from_calendar_date(2026, time::Month::July, 15).unwrap(),
+            Date::from_calendar_date(2030, time::Month::July, 15).unwrap(),
             MinorUnits::new(30000),
             20,
             CurrencyCode::SGD,
@@ -620,4 +794,127 @@ mod tests {
         let days = alert.days_until_expiry();
         assert!(days >= 6 && days <= 7);
     }
+
+    fn alert_with(condition: AlertCondition) -> PriceAlert {
+        PriceAlert::new(
+            "alert-3",
+            "user-1",
+            IataCode::SIN,
+            IataCode::BKK,
+            Date::from_calendar_date(2030, time::Month::July, 15).unwrap(),
+            condition,
+            CurrencyCode::SGD,
+        )
+    }
+
+    #[test]
+    fn test_below_average_alert() {
+        let manager = AlertManager::new();
+        let mut alert = alert_with(AlertCondition::BelowAverage);
+        let observation = |price: i64| AlertObservation {
+            avg_price_30d: Some(MinorUnits::new(30000)),
+            ..AlertObservation::price(MinorUnits::new(price))
+        };
+
+        // Without an average there is nothing to compare against
+        assert!(!alert.should_trigger(MinorUnits::new(10000)));
+        assert!(!manager.evaluate(&mut alert, &observation(30000)).triggered);
+
+        let result = manager.evaluate(&mut alert, &observation(27000));
+        assert!(result.triggered);
+        assert_eq!(result.savings, Some(MinorUnits::new(3000)));
+        assert_eq!(alert.status, AlertStatus::Triggered);
+    }
+
+    #[test]
+    fn test_trend_reversal_alert() {
+        let manager = AlertManager::new();
+        let mut alert = alert_with(AlertCondition::TrendTurnsFalling);
+        let observation = |trend: PriceTrend| AlertObservation {
+            trend: Some(trend),
+            ..AlertObservation::price(MinorUnits::new(30000))
+        };
+
+        // Already falling at the first check is not a reversal
+        assert!(
+            !manager
+                .evaluate(&mut alert, &observation(PriceTrend::Down))
+                .triggered
+        );
+        assert!(
+            !manager
+                .evaluate(&mut alert, &observation(PriceTrend::Up))
+                .triggered
+        );
+        assert_eq!(alert.last_trend, Some(PriceTrend::Up));
+
+        // A price-only check keeps the last trend seen
+        assert!(
+            !manager
+                .check_alert(&mut alert, MinorUnits::new(29000))
+                .triggered
+        );
+        assert_eq!(alert.last_trend, Some(PriceTrend::Up));
+
+        let result = manager.evaluate(&mut alert, &observation(PriceTrend::StrongDown));
+        assert!(result.triggered);
+        assert_eq!(result.savings, None);
+        assert_eq!(alert.last_trend, Some(PriceTrend::StrongDown));
+    }
+
+    #[test]
+    fn test_cabin_alert() {
+        let manager = AlertManager::new();
+        let mut alert = alert_with(AlertCondition::CabinBelow {
+            cabin: CabinClass::Business,
+            threshold: MinorUnits::new(150000),
+        });
+        assert_eq!(alert.condition.trigger().as_str(), "CABIN_PRICE_BELOW");
+
+        // A cheap economy fare doesn't count
+        let economy_only = AlertObservation::price(MinorUnits::new(20000))
+            .with_cabin_price(CabinClass::Economy, MinorUnits::new(20000));
+        assert!(!manager.evaluate(&mut alert, &economy_only).triggered);
+
+        let business = economy_only
+            .clone()
+            .with_cabin_price(CabinClass::Business, MinorUnits::new(180000))
+            .with_cabin_price(CabinClass::Business, MinorUnits::new(140000));
+        let result = manager.evaluate(&mut alert, &business);
+        assert!(result.triggered);
+        assert_eq!(result.current_price, MinorUnits::new(140000));
+        assert_eq!(result.savings, Some(MinorUnits::new(10000)));
+        assert_eq!(alert.triggered_price, Some(MinorUnits::new(140000)));
+    }
+
+    #[test]
+    fn test_condition_validation() {
+        let manager = AlertManager::new();
+        let invalid = [
+            AlertCondition::PriceBelow {
+                threshold: MinorUnits::new(0),
+            },
+            AlertCondition::DropsByPercent {
+                reference: MinorUnits::new(30000),
+                percent: 0,
+            },
+            AlertCondition::DropsByPercent {
+                reference: MinorUnits::new(30000),
+                percent: 100,
+            },
+            AlertCondition::CabinBelow {
+                cabin: CabinClass::Business,
+                threshold: MinorUnits::new(-1),
+            },
+        ];
+        for condition in invalid {
+            assert!(matches!(
+                manager.validate_new_alert(0, &alert_with(condition)),
+                Err(OracleError::InvalidThreshold(_))
+            ));
+        }
+        assert!(manager
+            .validate_new_alert(0, &alert_with(AlertCondition::TrendTurnsFalling))
+            .is_ok());
+    }
 }
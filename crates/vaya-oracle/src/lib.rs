@@ -6,6 +6,8 @@
 //! - **Price alerts**: Configurable alerts for price drops
 //! - **Trend analysis**: Historical trend detection
 //! - **Booking recommendations**: When to book based on predictions
+//! - **Prediction traces**: Features and model outputs behind a prediction,
+//!   reproducible as of any past time
 //!
 //! # Example Usage
 //!
@@ -32,14 +34,23 @@ mod alert;
 mod error;
 mod lstm_predictor;
 mod prediction;
+mod trace;
 
-pub use alert::{AlertCheckResult, AlertManager, AlertStatus, AlertTrigger, PriceAlert};
+pub use alert::{
+    AlertCheckResult, AlertCondition, AlertManager, AlertObservation, AlertStatus, AlertTrigger,
+    PriceAlert,
+};
 pub use error::{OracleError, OracleResult};
-pub use lstm_predictor::{EnsemblePredictor, LSTMConfig, LSTMPredictor, TrainingMetrics};
+pub use lstm_predictor::{
+    EnsembleMetrics, EnsemblePredictor, LSTMConfig, LSTMPredictor, TrainingMetrics,
+};
 pub use prediction::{
     BookingRecommendation, ConfidenceLevel, PriceDataPoint, PricePrediction, PricePredictor,
     PriceTrend,
 };
+pub use trace::{
+    FeatureVector, HistoryWindow, MemberOutput, PredictionTrace, ScalingParams, FEATURE_NAMES,
+};
 
 use time::Date;
 use vaya_common::{CurrencyCode, IataCode, MinorUnits};
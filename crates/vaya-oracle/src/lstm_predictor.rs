@@ -5,14 +5,23 @@
 use time::{Date, OffsetDateTime};
 use tracing::{debug, info};
 use vaya_common::{CurrencyCode, IataCode, MinorUnits};
-use vaya_ml::{Matrix, PriceLSTM, StandardScaler};
+use vaya_ml::{
+    BoostingConfig, BoostingLoss, GradientBoostingRegressor, LSTMTrainConfig, Matrix, PriceLSTM,
+    StandardScaler,
+};
 
 use crate::prediction::{PriceDataPoint, PricePrediction, PriceTrend};
-use crate::{OracleError, OracleResult};
+use crate::trace::{
+    self, FeatureVector, HistoryWindow, MemberOutput, PredictionTrace, ScalingParams,
+};
+use crate::{OracleError, OracleResult, Season};
 
 /// Number of features per time step
 const NUM_FEATURES: usize = 5;
 
+/// Number of features the gradient boosting member sees per window
+const GBM_FEATURES: usize = 7;
+
 /// LSTM model configuration
 #[derive(Debug, Clone)]
 pub struct LSTMConfig {
@@ -30,6 +39,8 @@ pub struct LSTMConfig {
     pub max_prediction_days: u32,
     /// Data freshness threshold (hours)
     pub max_data_age_hours: u64,
+    /// Training settings (epochs, batching, clipping, optimizer)
+    pub training: LSTMTrainConfig,
 }
 
 impl Default for LSTMConfig {
@@ -42,6 +53,10 @@ impl Default for LSTMConfig {
             min_samples: 14,
             max_prediction_days: 90,
             max_data_age_hours: 72,
+            training: LSTMTrainConfig {
+                epochs: 30,
+                ..Default::default()
+            },
         }
     }
 }
@@ -135,6 +150,10 @@ impl LSTMPredictor {
     }
 
     /// Train the LSTM model on historical data
+    ///
+    /// Each window of `sequence_length` observations is a sample whose
+    /// target is the change to the next observed price, in the units
+    /// [`Self::predict`] reads from the model output.
     pub fn train(&mut self, training_data: &[PriceDataPoint]) -> OracleResult<TrainingMetrics> {
         if training_data.len() < self.config.min_samples * 2 {
             return Err(OracleError::InsufficientData {
@@ -149,33 +168,114 @@ impl LSTMPredictor {
             self.config.sequence_length
         );
 
+        // Oldest first, so windows run forward in time
+        let mut sorted = training_data.to_vec();
+        sorted.sort_by_key(|d| d.timestamp);
+
         // Convert to feature matrix and fit scaler
-        let feature_matrix = Self::to_feature_matrix(training_data);
+        let feature_matrix = Self::to_feature_matrix(&sorted);
         self.scaler.fit(&feature_matrix);
-
-        // Create sequences for training
-        let _scaled_matrix = self
+        let scaled_matrix = self
             .scaler
             .transform(&feature_matrix)
             .ok_or_else(|| OracleError::ModelError("Failed to scale features".to_string()))?;
+        let steps: Vec<Matrix> = scaled_matrix
+            .data()
+            .chunks(NUM_FEATURES)
+            .map(Matrix::from_slice)
+            .collect();
 
-        let sequences_count = training_data
-            .len()
-            .saturating_sub(self.config.sequence_length);
+        // Create sequences for training
+        let length = self.config.sequence_length;
+        let (sequences, targets): (Vec<Vec<Matrix>>, Vec<Matrix>) =
+            Self::training_windows(&Self::prices(&sorted), length)
+                .into_iter()
+                .map(|(end, target)| {
+                    (
+                        steps[end - length..end].to_vec(),
+                        Matrix::from_slice(&[target]),
+                    )
+                })
+                .unzip();
+        if sequences.is_empty() {
+            return Err(OracleError::InsufficientData {
+                required: length + 1,
+                available: training_data.len(),
+            });
+        }
 
-        debug!("Created {} training sequences", sequences_count);
+        debug!("Created {} training sequences", sequences.len());
 
-        // Note: In a production system, we would implement backpropagation
-        // and train the model here. For now, we use the forward pass capability
-        // and mark as trained to enable inference.
+        let history = self
+            .model
+            .train(&sequences, &targets, &self.config.training)
+            .map_err(|e| OracleError::ModelError(format!("LSTM training failed: {}", e)))?;
         self.is_trained = true;
 
-        Ok(TrainingMetrics {
+        let metrics = TrainingMetrics {
             samples_used: training_data.len(),
-            sequences_created: sequences_count,
-            final_loss: 0.0, // Would be computed during actual training
-            epochs: 0,
-        })
+            sequences_created: sequences.len(),
+            final_loss: history.final_loss().unwrap_or_default() as f64,
+            epochs: history.train_loss.len(),
+            validation_loss: history.final_validation_loss().map(f64::from),
+            loss_history: history.train_loss.iter().map(|&l| l as f64).collect(),
+            validation_loss_history: history.validation_loss.iter().map(|&l| l as f64).collect(),
+        };
+        info!(
+            final_loss = metrics.final_loss,
+            validation_loss = ?metrics.validation_loss,
+            "Trained LSTM model"
+        );
+        Ok(metrics)
+    }
+
+    /// Training windows over time-sorted prices as (end, target) pairs
+    ///
+    /// A window covers `prices[end - length..end]`; its target is the change
+    /// from the window's last price to `prices[end]`, in model output units.
+    fn training_windows(prices: &[i64], length: usize) -> Vec<(usize, f32)> {
+        (length..prices.len())
+            .filter_map(|end| {
+                let base = prices[end - 1] as f32;
+                let next = prices[end] as f32;
+                (base > 0.0).then(|| (end, (next / base - 1.0) * 10.0))
+            })
+            .collect()
+    }
+
+    /// Prices of a set of data points
+    fn prices<'a>(data: impl IntoIterator<Item = &'a PriceDataPoint>) -> Vec<i64> {
+        data.into_iter().map(|d| d.price.as_i64()).collect()
+    }
+
+    /// The last `sequence_length` points of time-sorted data
+    fn most_recent<'a>(&self, sorted: &[&'a PriceDataPoint]) -> Vec<&'a PriceDataPoint> {
+        let start = sorted.len().saturating_sub(self.config.sequence_length);
+        sorted[start..].to_vec()
+    }
+
+    /// Scaled model inputs for a window, empty when the model can't run
+    fn scaled_sequence(&self, window: &[&PriceDataPoint]) -> Vec<Matrix> {
+        if !self.is_trained || !self.scaler.is_fitted() {
+            return Vec::new();
+        }
+        window
+            .iter()
+            .filter_map(|dp| self.scaler.transform(&Self::data_point_to_matrix(dp)))
+            .collect()
+    }
+
+    /// Raw model output for a window, or `None` when the model can't run
+    fn model_output(&self, window: &[&PriceDataPoint]) -> OracleResult<Option<f64>> {
+        let sequence = self.scaled_sequence(window);
+        if sequence.is_empty() {
+            return Ok(None);
+        }
+        let output = self
+            .model
+            .predict(&sequence)
+            .map_err(|e| OracleError::ModelError(format!("LSTM prediction failed: {:?}", e)))?;
+        Ok(Some(output.get(0, 0) as f64))
     }
 
     /// Predict price for a route and date
@@ -187,18 +287,44 @@ impl LSTMPredictor {
         historical_data: &[PriceDataPoint],
         currency: CurrencyCode,
     ) -> OracleResult<PricePrediction> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.trace(
+            origin,
+            destination,
+            departure_date,
+            historical_data,
+            currency,
+            now,
+        )
+        .map(|trace| trace.prediction)
+    }
+
+    /// Predict as of a past time, recording the features and member outputs
+    ///
+    /// Observations made after `as_of` are ignored and data age, days to
+    /// departure and recency weights are measured from `as_of`.
+    pub fn trace(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        historical_data: &[PriceDataPoint],
+        currency: CurrencyCode,
+        as_of: i64,
+    ) -> OracleResult<PredictionTrace> {
+        let (known, after_as_of) = trace::history_as_of(historical_data, as_of);
+
         // Validate data availability
-        if historical_data.len() < self.config.min_samples {
+        if known.len() < self.config.min_samples {
             return Err(OracleError::InsufficientData {
                 required: self.config.min_samples,
-                available: historical_data.len(),
+                available: known.len(),
             });
         }
 
         // Check data freshness
-        if let Some(newest) = historical_data.iter().max_by_key(|d| d.timestamp) {
-            let now = OffsetDateTime::now_utc().unix_timestamp();
-            let age_hours = ((now - newest.timestamp) / 3600) as u64;
+        if let Some(newest) = known.iter().max_by_key(|d| d.timestamp) {
+            let age_hours = ((as_of - newest.timestamp) / 3600) as u64;
             if age_hours > self.config.max_data_age_hours {
                 return Err(OracleError::StaleData {
                     age_hours,
@@ -208,7 +334,7 @@ impl LSTMPredictor {
         }
 
         // Check prediction range
-        let today = OffsetDateTime::now_utc().date();
+        let today = trace::date_at(as_of)?;
         let days_until = (departure_date - today).whole_days().max(0) as u32;
         if days_until > self.config.max_prediction_days {
             return Err(OracleError::DateOutOfRange {
@@ -218,23 +344,38 @@ impl LSTMPredictor {
         }
 
         // Sort data by timestamp (oldest first)
-        let mut sorted_data: Vec<&PriceDataPoint> = historical_data.iter().collect();
+        let mut sorted_data = known;
         sorted_data.sort_by_key(|d| d.timestamp);
 
         // Use the most recent data for prediction
-        let recent_data: Vec<&PriceDataPoint> = sorted_data
-            .iter()
-            .rev()
-            .take(self.config.sequence_length)
-            .rev()
-            .copied()
-            .collect();
-
-        // Get raw features and compute prediction
-        let (predicted_price, confidence) = if self.is_trained && self.scaler.is_fitted() {
-            self.predict_with_lstm(&recent_data, days_until)?
-        } else {
-            self.predict_statistical(&recent_data, days_until)
+        let recent_data = self.most_recent(&sorted_data);
+
+        // Scaled model inputs, when the model can run
+        let sequence = self.scaled_sequence(&recent_data);
+
+        // Run each member; the LSTM's output is used when it ran
+        let (statistical_price, statistical_confidence) =
+            self.predict_statistical(&recent_data, days_until, as_of);
+        let lstm = self.predict_with_lstm(&recent_data, &sequence, as_of)?;
+        let mut members = vec![MemberOutput {
+            name: "statistical",
+            raw_output: statistical_price,
+            predicted_price: statistical_price,
+            confidence: statistical_confidence,
+            selected: lstm.is_none(),
+        }];
+        let (predicted_price, confidence) = match lstm {
+            Some((raw_output, price, confidence)) => {
+                members.push(MemberOutput {
+                    name: "lstm",
+                    raw_output,
+                    predicted_price: price,
+                    confidence,
+                    selected: true,
+                });
+                (price, confidence)
+            }
+            None => (statistical_price, statistical_confidence),
         };
 
         // Calculate trend from historical data
@@ -250,39 +391,67 @@ impl LSTMPredictor {
         );
 
         prediction.model_version = self.version.clone();
+        prediction.predicted_at = as_of;
+        prediction.days_until_departure = days_until;
         prediction = prediction.with_trend(trend, change_percent);
         prediction.calculate_recommendation();
 
-        Ok(prediction)
+        let features = recent_data
+            .iter()
+            .enumerate()
+            .map(|(i, dp)| FeatureVector {
+                scaled: sequence
+                    .get(i)
+                    .map(|m| m.data().iter().map(|v| *v as f64).collect()),
+                ..FeatureVector::from_point(dp)
+            })
+            .collect();
+        let scaling = match (self.scaler.mean(), self.scaler.std()) {
+            (Some(mean), Some(std)) => Some(ScalingParams {
+                mean: mean.iter().map(|v| *v as f64).collect(),
+                std: std.iter().map(|v| *v as f64).collect(),
+            }),
+            _ => None,
+        };
+
+        Ok(PredictionTrace {
+            origin,
+            destination,
+            departure_date,
+            as_of,
+            model_version: self.version.clone(),
+            days_until_departure: days_until,
+            season: Season::for_date(departure_date),
+            window: HistoryWindow::new(historical_data.len(), after_as_of, &recent_data),
+            features,
+            scaling,
+            members,
+            prediction,
+        })
     }
 
     /// Predict using the trained LSTM model
+    ///
+    /// Returns the raw output, the price derived from it and the
+    /// confidence, or `None` when there is no input sequence.
     fn predict_with_lstm(
         &self,
         recent_data: &[&PriceDataPoint],
-        _days_until: u32,
-    ) -> OracleResult<(f64, f64)> {
-        // Convert to sequence of column vector matrices
-        let sequence: Vec<Matrix> = recent_data
-            .iter()
-            .filter_map(|dp| {
-                let input_matrix = Self::data_point_to_matrix(dp);
-                self.scaler.transform(&input_matrix)
-            })
-            .collect();
-
+        sequence: &[Matrix],
+        as_of: i64,
+    ) -> OracleResult<Option<(f64, f64, f64)>> {
         if sequence.is_empty() {
-            return self.predict_statistical(recent_data, _days_until).pipe_ok();
+            return Ok(None);
         }
 
         // Run prediction
         let output = self
             .model
-            .predict(&sequence)
+            .predict(sequence)
             .map_err(|e| OracleError::ModelError(format!("LSTM prediction failed: {:?}", e)))?;
 
         // Get prediction value (single output)
-        let predicted_change = output.get(0, 0);
+        let predicted_change = output.get(0, 0) as f64;
 
         // Use most recent price as baseline
         let base_price = recent_data
@@ -291,16 +460,25 @@ impl LSTMPredictor {
             .unwrap_or(0.0);
 
         // Apply predicted change (output is normalized change)
-        let predicted_price = base_price * (1.0 + predicted_change as f64 * 0.1);
+        let predicted_price = price_from_change(base_price, predicted_change);
 
         // Calculate confidence based on data quality
-        let confidence = self.calculate_confidence(recent_data);
+        let confidence = self.calculate_confidence(recent_data, as_of);
 
-        Ok((predicted_price.max(0.0), confidence))
+        Ok(Some((
+            predicted_change,
+            predicted_price.max(0.0),
+            confidence,
+        )))
     }
 
     /// Statistical fallback prediction (weighted average)
-    fn predict_statistical(&self, recent_data: &[&PriceDataPoint], days_until: u32) -> (f64, f64) {
+    fn predict_statistical(
+        &self,
+        recent_data: &[&PriceDataPoint],
+        days_until: u32,
+        as_of: i64,
+    ) -> (f64, f64) {
         // Filter for similar booking windows
         let relevant: Vec<&PriceDataPoint> = recent_data
             .iter()
@@ -322,12 +500,11 @@ impl LSTMPredictor {
         }
 
         // Weighted average (more recent = higher weight)
-        let now = OffsetDateTime::now_utc().unix_timestamp();
         let mut total_weight = 0.0;
         let mut weighted_sum = 0.0;
 
         for dp in data_to_use {
-            let age_days = ((now - dp.timestamp) / 86400).max(1) as f64;
+            let age_days = ((as_of - dp.timestamp) / 86400).max(1) as f64;
             let weight = 1.0 / age_days.sqrt();
             weighted_sum += dp.price.as_i64() as f64 * weight;
             total_weight += weight;
@@ -339,18 +516,17 @@ impl LSTMPredictor {
             0.0
         };
 
-        let confidence = self.calculate_confidence(recent_data) * 0.7; // Lower confidence for statistical
+        let confidence = self.calculate_confidence(recent_data, as_of) * 0.7; // Lower confidence for statistical
 
         (predicted, confidence)
     }
 
     /// Calculate confidence score based on data quality
-    fn calculate_confidence(&self, data: &[&PriceDataPoint]) -> f64 {
+    fn calculate_confidence(&self, data: &[&PriceDataPoint], as_of: i64) -> f64 {
         let sample_factor = (data.len() as f64 / 20.0).min(1.0);
 
         let recency_factor = if let Some(newest) = data.iter().max_by_key(|d| d.timestamp) {
-            let now = OffsetDateTime::now_utc().unix_timestamp();
-            let hours_old = ((now - newest.timestamp) / 3600) as f64;
+            let hours_old = ((as_of - newest.timestamp) / 3600) as f64;
             (1.0 - hours_old / 168.0).max(0.0)
         } else {
             0.5
@@ -452,15 +628,33 @@ impl Default for LSTMPredictor {
     }
 }
 
-/// Helper trait for converting tuples to Result
-trait PipeOk {
-    fn pipe_ok(self) -> OracleResult<(f64, f64)>;
+/// Price after a change in model output units (tenths of the base price)
+fn price_from_change(base_price: f64, change: f64) -> f64 {
+    base_price * (1.0 + change * 0.1)
 }
 
-impl PipeOk for (f64, f64) {
-    fn pipe_ok(self) -> OracleResult<(f64, f64)> {
-        Ok(self)
-    }
+/// Features the gradient boosting member sees for a window
+///
+/// Price movement is expressed relative to the window so trees fitted on
+/// one fare level carry over to another.
+fn window_features(window: &[&PriceDataPoint]) -> Vec<f32> {
+    let price = |dp: &PriceDataPoint| dp.price.as_i64() as f32;
+    let (Some(first), Some(last)) = (window.first(), window.last()) else {
+        return vec![0.0; GBM_FEATURES];
+    };
+    let previous = window.len().checked_sub(2).map_or(*last, |i| window[i]);
+    let mean = window.iter().map(|dp| price(dp)).sum::<f32>() / window.len() as f32;
+    let relative = |a: f32, b: f32| if b > 0.0 { a / b - 1.0 } else { 0.0 };
+
+    vec![
+        relative(price(last), price(first)),
+        relative(price(last), price(previous)),
+        relative(price(last), mean),
+        last.days_before_departure as f32,
+        last.day_of_week as f32,
+        if last.is_weekend_departure { 1.0 } else { 0.0 },
+        if last.is_holiday { 1.0 } else { 0.0 },
+    ]
 }
 
 /// Training metrics
@@ -474,12 +668,46 @@ pub struct TrainingMetrics {
     pub final_loss: f64,
     /// Number of epochs run
     pub epochs: usize,
+    /// Final loss on the held-out windows, if any were held out
+    pub validation_loss: Option<f64>,
+    /// Training loss per epoch
+    pub loss_history: Vec<f64>,
+    /// Validation loss per epoch
+    pub validation_loss_history: Vec<f64>,
 }
 
-/// Ensemble predictor combining LSTM and statistical methods
+/// Ensemble training metrics
+///
+/// Errors are mean absolute errors of the next-price prediction, in minor
+/// units, over the most recent windows held out from training. They are
+/// `None` when there were too few windows to hold any out.
+#[derive(Debug, Clone)]
+pub struct EnsembleMetrics {
+    /// LSTM member metrics
+    pub lstm: TrainingMetrics,
+    /// Trees kept in the gradient boosting member
+    pub gbm_trees: usize,
+    /// Held-out error of the LSTM alone
+    pub lstm_validation_mae: Option<f64>,
+    /// Held-out error of gradient boosting alone
+    pub gbm_validation_mae: Option<f64>,
+    /// Held-out error of the weighted blend
+    pub ensemble_validation_mae: Option<f64>,
+}
+
+/// Ensemble predictor blending LSTM and gradient boosting predictions
+///
+/// Both members predict the change to the next price from the same
+/// windows. Once trained, the prediction is `lstm_weight` of the LSTM's
+/// price plus the rest of the gradient boosting price; before that, the
+/// LSTM predictor's own output (or its statistical fallback) is used.
 pub struct EnsemblePredictor {
     /// LSTM predictor
     lstm: LSTMPredictor,
+    /// Gradient boosting member, once trained
+    gbm: Option<GradientBoostingRegressor>,
+    /// Gradient boosting settings
+    gbm_config: BoostingConfig,
     /// Weight for LSTM predictions (0-1)
     lstm_weight: f64,
 }
@@ -487,9 +715,23 @@ pub struct EnsemblePredictor {
 impl EnsemblePredictor {
     /// Create a new ensemble predictor
     pub fn new() -> Self {
+        Self::with_config(LSTMConfig::default())
+    }
+
+    /// Create a new ensemble predictor with a custom LSTM config
+    pub fn with_config(config: LSTMConfig) -> Self {
         Self {
-            lstm: LSTMPredictor::new(),
-            lstm_weight: 0.7, // 70% LSTM, 30% statistical
+            lstm: LSTMPredictor::with_config(config),
+            gbm: None,
+            gbm_config: BoostingConfig {
+                subsample: 0.8,
+                // Target units are tenths of the price; moves past 10% are
+                // treated as outliers
+                loss: BoostingLoss::Huber { delta: 1.0 },
+                early_stopping_rounds: Some(10),
+                ..Default::default()
+            },
+            lstm_weight: 0.7, // 70% LSTM, 30% gradient boosting
         }
     }
 
@@ -499,9 +741,93 @@ impl EnsemblePredictor {
         self
     }
 
-    /// Train the ensemble
-    pub fn train(&mut self, data: &[PriceDataPoint]) -> OracleResult<TrainingMetrics> {
-        self.lstm.train(data)
+    /// Set gradient boosting settings
+    pub fn with_gbm_config(mut self, config: BoostingConfig) -> Self {
+        self.gbm_config = config;
+        self
+    }
+
+    /// Check if both members are trained
+    pub fn is_trained(&self) -> bool {
+        self.lstm.is_trained() && self.gbm.is_some()
+    }
+
+    /// Train both members and measure them on held-out windows
+    ///
+    /// The held-out windows are the LSTM's validation windows (the most
+    /// recent ones); gradient boosting uses them for early stopping.
+    pub fn train(&mut self, data: &[PriceDataPoint]) -> OracleResult<EnsembleMetrics> {
+        let lstm = self.lstm.train(data)?;
+
+        let mut sorted: Vec<&PriceDataPoint> = data.iter().collect();
+        sorted.sort_by_key(|d| d.timestamp);
+        let length = self.lstm.config.sequence_length;
+        let windows =
+            LSTMPredictor::training_windows(&LSTMPredictor::prices(sorted.iter().copied()), length);
+
+        let n = windows.len();
+        let split = self.lstm.config.training.validation_split;
+        let n_validation = ((n as f32 * split).round() as usize).min(n - 1);
+        let n_train = n - n_validation;
+
+        let features = |windows: &[(usize, f32)]| {
+            Matrix::from_vec(
+                windows
+                    .iter()
+                    .map(|&(end, _)| window_features(&sorted[end - length..end]))
+                    .collect(),
+            )
+        };
+        let targets = |windows: &[(usize, f32)]| {
+            Matrix::from_slice(&windows.iter().map(|w| w.1).collect::<Vec<_>>())
+        };
+        let (train, validation) = windows.split_at(n_train);
+
+        let mut gbm = GradientBoostingRegressor::with_config(self.gbm_config);
+        let fitted = if validation.is_empty() {
+            gbm.fit(&features(train), &targets(train))
+        } else {
+            gbm.fit_with_validation(
+                &features(train),
+                &targets(train),
+                &features(validation),
+                &targets(validation),
+            )
+        };
+        fitted.map_err(|e| OracleError::ModelError(format!("GBM training failed: {}", e)))?;
+
+        // Next-price errors of each member and the blend on held-out windows
+        let mut errors = [0.0; 3];
+        let gbm_outputs = gbm.predict(&features(validation));
+        for (i, &(end, _)) in validation.iter().enumerate() {
+            let window = &sorted[end - length..end];
+            let base = window[length - 1].price.as_i64() as f64;
+            let actual = sorted[end].price.as_i64() as f64;
+            let lstm_price =
+                price_from_change(base, self.lstm.model_output(window)?.unwrap_or(0.0));
+            let gbm_price = price_from_change(base, gbm_outputs.get(i, 0) as f64);
+            let blend = self.blend(lstm_price, gbm_price);
+            for (error, price) in errors.iter_mut().zip([lstm_price, gbm_price, blend]) {
+                *error += (price - actual).abs();
+            }
+        }
+        let mae = |total: f64| (!validation.is_empty()).then(|| total / validation.len() as f64);
+
+        let metrics = EnsembleMetrics {
+            lstm,
+            gbm_trees: gbm.num_trees(),
+            lstm_validation_mae: mae(errors[0]),
+            gbm_validation_mae: mae(errors[1]),
+            ensemble_validation_mae: mae(errors[2]),
+        };
+        info!(
+            lstm_mae = ?metrics.lstm_validation_mae,
+            gbm_mae = ?metrics.gbm_validation_mae,
+            ensemble_mae = ?metrics.ensemble_validation_mae,
+            "Trained ensemble"
+        );
+        self.gbm = Some(gbm);
+        Ok(metrics)
     }
 
     /// Predict using ensemble
@@ -513,18 +839,85 @@ impl EnsemblePredictor {
         historical_data: &[PriceDataPoint],
         currency: CurrencyCode,
     ) -> OracleResult<PricePrediction> {
-        // Get LSTM prediction
-        let lstm_pred = self.lstm.predict(
+        let as_of = OffsetDateTime::now_utc().unix_timestamp();
+        self.trace(
+            origin,
+            destination,
+            departure_date,
+            historical_data,
+            currency,
+            as_of,
+        )
+        .map(|trace| trace.prediction)
+    }
+
+    /// Predict as of a past time, recording each member's output
+    pub fn trace(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        historical_data: &[PriceDataPoint],
+        currency: CurrencyCode,
+        as_of: i64,
+    ) -> OracleResult<PredictionTrace> {
+        let mut trace = self.lstm.trace(
             origin,
             destination,
             departure_date,
             historical_data,
             currency,
+            as_of,
         )?;
 
-        // For ensemble, we could blend with statistical here
-        // For now, just return LSTM prediction with adjusted confidence
-        Ok(lstm_pred)
+        // Blend only when both members ran
+        let (Some(gbm), Some(lstm_index)) = (
+            &self.gbm,
+            trace.members.iter().position(|m| m.name == "lstm"),
+        ) else {
+            return Ok(trace);
+        };
+        let (mut known, _) = trace::history_as_of(historical_data, as_of);
+        known.sort_by_key(|d| d.timestamp);
+        let window = self.lstm.most_recent(&known);
+        let base = window.last().map_or(0.0, |d| d.price.as_i64() as f64);
+
+        let gbm_output = gbm
+            .predict(&Matrix::from_vec(vec![window_features(&window)]))
+            .get(0, 0) as f64;
+        let gbm_price = price_from_change(base, gbm_output).max(0.0);
+        let lstm = &trace.members[lstm_index];
+        let confidence = lstm.confidence;
+        let price = self.blend(lstm.predicted_price, gbm_price);
+        trace.members.push(MemberOutput {
+            name: "gbm",
+            raw_output: gbm_output,
+            predicted_price: gbm_price,
+            confidence,
+            selected: true,
+        });
+
+        let old = &trace.prediction;
+        let mut prediction = PricePrediction::new(
+            origin,
+            destination,
+            departure_date,
+            MinorUnits::new(price as i64),
+            currency,
+            confidence,
+        );
+        prediction.model_version = old.model_version.clone();
+        prediction.predicted_at = old.predicted_at;
+        prediction.days_until_departure = old.days_until_departure;
+        prediction = prediction.with_trend(old.trend, old.expected_change_percent);
+        prediction.calculate_recommendation();
+        trace.prediction = prediction;
+        Ok(trace)
+    }
+
+    /// Weighted blend of the members' prices
+    fn blend(&self, lstm_price: f64, gbm_price: f64) -> f64 {
+        self.lstm_weight * lstm_price + (1.0 - self.lstm_weight) * gbm_price
     }
 }
 
@@ -553,6 +946,15 @@ mod tests {
             .collect()
     }
 
+    /// A model small enough to train quickly in tests
+    fn small_config() -> LSTMConfig {
+        LSTMConfig {
+            hidden_size: 8,
+            num_layers: 1,
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_lstm_predictor_creation() {
         let predictor = LSTMPredictor::new();
@@ -641,7 +1043,7 @@ mod tests {
 
     #[test]
     fn test_training() {
-        let mut predictor = LSTMPredictor::new();
+        let mut predictor = LSTMPredictor::with_config(small_config());
         let data = make_test_data(50);
 
         let result = predictor.train(&data);
@@ -650,7 +1052,13 @@ mod tests {
 
         let metrics = result.unwrap();
         assert_eq!(metrics.samples_used, 50);
-        assert!(metrics.sequences_created > 0);
+        assert_eq!(metrics.sequences_created, 50 - 14);
+        assert_eq!(metrics.epochs, 30);
+        assert_eq!(metrics.loss_history.len(), 30);
+        assert_eq!(metrics.validation_loss_history.len(), 30);
+        assert_eq!(metrics.final_loss, *metrics.loss_history.last().unwrap());
+        assert!(metrics.final_loss < metrics.loss_history[0]);
+        assert!(metrics.validation_loss.unwrap().is_finite());
     }
 
     #[test]
@@ -702,6 +1110,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_trace_members_and_scaling() {
+        let data = make_test_data(50);
+        let departure = OffsetDateTime::now_utc().date() + time::Duration::days(30);
+        let as_of = OffsetDateTime::now_utc().unix_timestamp();
+
+        let untrained = LSTMPredictor::new()
+            .trace(
+                IataCode::SIN,
+                IataCode::BKK,
+                departure,
+                &data,
+                CurrencyCode::SGD,
+                as_of,
+            )
+            .unwrap();
+        assert!(untrained.scaling.is_none());
+        assert_eq!(untrained.members.len(), 1);
+        assert_eq!(untrained.members[0].name, "statistical");
+
+        let mut ensemble = EnsemblePredictor::with_config(small_config());
+        ensemble.train(&data).unwrap();
+        let trace = ensemble
+            .trace(
+                IataCode::SIN,
+                IataCode::BKK,
+                departure,
+                &data,
+                CurrencyCode::SGD,
+                as_of,
+            )
+            .unwrap();
+
+        assert_eq!(trace.window.used, 14);
+        assert_eq!(trace.features.len(), 14);
+        assert!(trace.features.iter().all(|f| f.scaled.is_some()));
+        assert_eq!(trace.scaling.as_ref().unwrap().mean.len(), NUM_FEATURES);
+        assert_eq!(trace.members.len(), 3);
+        let selected: Vec<&MemberOutput> = trace.members.iter().filter(|m| m.selected).collect();
+        assert_eq!(selected.len(), 2);
+        assert_eq!((selected[0].name, selected[1].name), ("lstm", "gbm"));
+        let blend = 0.7 * selected[0].predicted_price + 0.3 * selected[1].predicted_price;
+        assert_eq!(trace.prediction.predicted_price.as_i64(), blend as i64);
+    }
+
+    #[test]
+    fn test_ensemble_measures_members_on_held_out_windows() {
+        // Prices climb 1% a day with a weekly dip the GBM can pick up
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let data: Vec<PriceDataPoint> = (0..80)
+            .map(|i| {
+                let dip = if i % 7 == 6 { 0.95 } else { 1.0 };
+                PriceDataPoint {
+                    price: MinorUnits::new((20000.0 * 1.01f64.powi(i) * dip) as i64),
+                    currency: CurrencyCode::SGD,
+                    timestamp: now - (80 - i as i64) * 3600,
+                    days_before_departure: 80 - i as u32,
+                    day_of_week: (i % 7) as u8,
+                    is_weekend_departure: false,
+                    is_holiday: false,
+                }
+            })
+            .collect();
+
+        let mut ensemble = EnsemblePredictor::with_config(small_config());
+        assert!(!ensemble.is_trained());
+        let metrics = ensemble.train(&data).unwrap();
+        assert!(ensemble.is_trained());
+
+        assert!(metrics.gbm_trees > 0);
+        let lstm = metrics.lstm_validation_mae.unwrap();
+        let gbm = metrics.gbm_validation_mae.unwrap();
+        let blend = metrics.ensemble_validation_mae.unwrap();
+        // A blend is never worse than its worse member
+        assert!(blend <= lstm.max(gbm) + 1e-6);
+
+        // 66 windows, the last 13 held out; GBM beats predicting no change
+        let price = |i: usize| data[i].price.as_i64() as f64;
+        let naive = (67..80)
+            .map(|i| (price(i) - price(i - 1)).abs())
+            .sum::<f64>()
+            / 13.0;
+        assert!(gbm < naive, "gbm {} vs naive {}", gbm, naive);
+    }
+
     #[test]
     fn test_feature_matrix_conversion() {
         let data = make_test_data(5);
@@ -3,7 +3,8 @@
 use time::{Date, OffsetDateTime};
 use vaya_common::{CurrencyCode, IataCode, MinorUnits};
 
-use crate::{OracleError, OracleResult};
+use crate::trace::{self, FeatureVector, HistoryWindow, MemberOutput, PredictionTrace};
+use crate::{OracleError, OracleResult, Season};
 
 /// Price confidence level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -97,6 +98,11 @@ impl PriceTrend {
             PriceTrend::Down | PriceTrend::StrongDown | PriceTrend::Stable
         )
     }
+
+    /// Check if prices are falling
+    pub fn is_falling(&self) -> bool {
+        matches!(self, PriceTrend::Down | PriceTrend::StrongDown)
+    }
 }
 
 /// Booking recommendation
@@ -319,18 +325,45 @@ impl PricePredictor {
         historical_data: &[PriceDataPoint],
         currency: CurrencyCode,
     ) -> OracleResult<PricePrediction> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        self.trace(
+            origin,
+            destination,
+            departure_date,
+            historical_data,
+            currency,
+            now,
+        )
+        .map(|trace| trace.prediction)
+    }
+
+    /// Predict as of a past time, recording what the prediction was built from
+    ///
+    /// Observations made after `as_of` are ignored and data age, days to
+    /// departure and recency weights are measured from `as_of`, so the
+    /// prediction is the one that would have been made then.
+    pub fn trace(
+        &self,
+        origin: IataCode,
+        destination: IataCode,
+        departure_date: Date,
+        historical_data: &[PriceDataPoint],
+        currency: CurrencyCode,
+        as_of: i64,
+    ) -> OracleResult<PredictionTrace> {
+        let (known, after_as_of) = trace::history_as_of(historical_data, as_of);
+
         // Check data availability
-        if historical_data.len() < self.min_samples {
+        if known.len() < self.min_samples {
             return Err(OracleError::InsufficientData {
                 required: self.min_samples,
-                available: historical_data.len(),
+                available: known.len(),
             });
         }
 
         // Check data freshness
-        if let Some(newest) = historical_data.iter().max_by_key(|d| d.timestamp) {
-            let now = OffsetDateTime::now_utc().unix_timestamp();
-            let age_hours = ((now - newest.timestamp) / 3600) as u64;
+        if let Some(newest) = known.iter().max_by_key(|d| d.timestamp) {
+            let age_hours = ((as_of - newest.timestamp) / 3600) as u64;
             if age_hours > self.max_data_age_hours {
                 return Err(OracleError::StaleData {
                     age_hours,
@@ -340,7 +373,7 @@ impl PricePredictor {
         }
 
         // Check prediction range
-        let today = OffsetDateTime::now_utc().date();
+        let today = trace::date_at(as_of)?;
         let days_until = (departure_date - today).whole_days().max(0) as u32;
         if days_until > self.max_prediction_days {
             return Err(OracleError::DateOutOfRange {
@@ -351,7 +384,8 @@ impl PricePredictor {
 
         // Simple prediction: weighted average of recent prices
         // In production, this would use vaya-ml models
-        let (predicted_price, confidence) = self.calculate_prediction(historical_data, days_until);
+        let (predicted_price, confidence, used) =
+            self.calculate_prediction(&known, days_until, as_of);
 
         if confidence < self.min_confidence {
             return Err(OracleError::LowConfidence {
@@ -361,7 +395,7 @@ impl PricePredictor {
         }
 
         // Calculate trend
-        let (trend, change_percent) = self.calculate_trend(historical_data);
+        let (trend, change_percent) = self.calculate_trend(&known);
 
         let mut prediction = PricePrediction::new(
             origin,
@@ -371,15 +405,48 @@ impl PricePredictor {
             currency,
             confidence,
         );
+        prediction.predicted_at = as_of;
+        prediction.days_until_departure = days_until;
 
         prediction = prediction.with_trend(trend, change_percent);
         prediction.calculate_recommendation();
 
-        Ok(prediction)
+        let mut features: Vec<FeatureVector> =
+            used.iter().map(|d| FeatureVector::from_point(d)).collect();
+        features.sort_by_key(|f| f.observed_at);
+
+        Ok(PredictionTrace {
+            origin,
+            destination,
+            departure_date,
+            as_of,
+            model_version: prediction.model_version.clone(),
+            days_until_departure: days_until,
+            season: Season::for_date(departure_date),
+            window: HistoryWindow::new(historical_data.len(), after_as_of, &used),
+            features,
+            scaling: None,
+            members: vec![MemberOutput {
+                name: "statistical",
+                raw_output: predicted_price,
+                predicted_price,
+                confidence,
+                selected: true,
+            }],
+            prediction,
+        })
     }
 
     /// Calculate prediction from historical data
-    fn calculate_prediction(&self, data: &[PriceDataPoint], days_until: u32) -> (f64, f64) {
+    ///
+    /// Returns the price, the confidence and the observations it was
+    /// computed from.
+    fn calculate_prediction<'a>(
+        &self,
+        data: &[&'a PriceDataPoint],
+        days_until: u32,
+        as_of: i64,
+    ) -> (f64, f64, Vec<&'a PriceDataPoint>) {
         // Filter data for similar booking windows
         let relevant_data: Vec<&PriceDataPoint> = data
             .iter()
@@ -387,6 +454,7 @@ impl PricePredictor {
                 let diff = (d.days_before_departure as i32 - days_until as i32).unsigned_abs();
                 diff <= 7 // Within 7 days of target booking window
             })
+            .copied()
             .collect();
 
         if relevant_data.is_empty() {
@@ -394,16 +462,15 @@ impl PricePredictor {
             let avg: f64 =
                 data.iter().map(|d| d.price.as_i64() as f64).sum::<f64>() / data.len() as f64;
             let confidence = 0.3; // Low confidence for fallback
-            return (avg, confidence);
+            return (avg, confidence, data.to_vec());
         }
 
         // Weighted average (more recent = higher weight)
-        let now = OffsetDateTime::now_utc().unix_timestamp();
         let mut total_weight = 0.0;
         let mut weighted_sum = 0.0;
 
         for dp in &relevant_data {
-            let age_days = ((now - dp.timestamp) / 86400).max(1) as f64;
+            let age_days = ((as_of - dp.timestamp) / 86400).max(1) as f64;
             let weight = 1.0 / age_days.sqrt(); // Diminishing weight with age
             weighted_sum += dp.price.as_i64() as f64 * weight;
             total_weight += weight;
@@ -415,7 +482,7 @@ impl PricePredictor {
         let sample_factor = (relevant_data.len() as f64 / 20.0).min(1.0);
         let recency_factor = {
             if let Some(newest) = relevant_data.iter().max_by_key(|d| d.timestamp) {
-                let hours_old = ((now - newest.timestamp) / 3600) as f64;
+                let hours_old = ((as_of - newest.timestamp) / 3600) as f64;
                 (1.0 - hours_old / 168.0).max(0.0) // 7 days decay
             } else {
                 0.5
@@ -423,17 +490,17 @@ impl PricePredictor {
         };
         let confidence = sample_factor * 0.5 + recency_factor * 0.5;
 
-        (predicted, confidence.min(0.95))
+        (predicted, confidence.min(0.95), relevant_data)
     }
 
     /// Calculate price trend from historical data
-    fn calculate_trend(&self, data: &[PriceDataPoint]) -> (PriceTrend, f64) {
+    fn calculate_trend(&self, data: &[&PriceDataPoint]) -> (PriceTrend, f64) {
         if data.len() < 2 {
             return (PriceTrend::Stable, 0.0);
         }
 
         // Sort by timestamp
-        let mut sorted: Vec<&PriceDataPoint> = data.iter().collect();
+        let mut sorted: Vec<&PriceDataPoint> = data.to_vec();
         sorted.sort_by_key(|d| d.timestamp);
 
         // Compare recent vs older prices
@@ -600,7 +667,7 @@ mod tests {
             .map(|i| make_data_point(20000 + (i * 1000), 30, (10 - i) as i64))
             .collect();
 
-        let (trend, change) = predictor.calculate_trend(&rising);
+        let (trend, change) = predictor.calculate_trend(&rising.iter().collect::<Vec<_>>());
         assert!(matches!(trend, PriceTrend::Up | PriceTrend::StrongUp));
         assert!(change > 0.0);
 
@@ -609,11 +676,64 @@ mod tests {
             .map(|i| make_data_point(30000 - (i * 1000), 30, (10 - i) as i64))
             .collect();
 
-        let (trend, change) = predictor.calculate_trend(&falling);
+        let (trend, change) = predictor.calculate_trend(&falling.iter().collect::<Vec<_>>());
         assert!(matches!(trend, PriceTrend::Down | PriceTrend::StrongDown));
         assert!(change < 0.0);
     }
 
+    #[test]
+    fn test_trace_as_of() {
+        let predictor = PricePredictor::new();
+        let departure = OffsetDateTime::now_utc().date() + time::Duration::days(30);
+
+        // Twelve observations a day apart, plus two made in the last hour
+        let mut data: Vec<PriceDataPoint> = (0..12)
+            .map(|i| make_data_point(25000 + i * 100, 30, 24 + i * 24))
+            .collect();
+        data.push(make_data_point(90000, 30, 1));
+        data.push(make_data_point(90000, 30, 0));
+
+        let as_of = OffsetDateTime::now_utc().unix_timestamp() - 12 * 3600;
+        let trace = predictor
+            .trace(
+                IataCode::SIN,
+                IataCode::BKK,
+                departure,
+                &data,
+                CurrencyCode::SGD,
+                as_of,
+            )
+            .unwrap();
+        assert_eq!(trace.window.supplied, 14);
+        assert_eq!(trace.window.after_as_of, 2);
+        assert_eq!(trace.window.used, 12);
+        assert_eq!(trace.features.len(), 12);
+        assert!(trace.features.iter().all(|f| f.scaled.is_none()));
+        assert!(trace.features[0].observed_at < trace.features[11].observed_at);
+        assert_eq!(trace.days_until_departure, 30);
+        assert_eq!(trace.prediction.predicted_at, as_of);
+        assert!(trace.prediction.predicted_price.as_i64() < 30000);
+        assert_eq!(trace.members.len(), 1);
+        assert!(trace.members[0].selected);
+
+        // The same inputs reproduce the same prediction
+        let again = predictor
+            .trace(
+                IataCode::SIN,
+                IataCode::BKK,
+                departure,
+                &data,
+                CurrencyCode::SGD,
+                as_of,
+            )
+            .unwrap();
+        assert_eq!(
+            again.prediction.predicted_price,
+            trace.prediction.predicted_price
+        );
+        assert_eq!(again.prediction.confidence, trace.prediction.confidence);
+    }
+
     #[test]
     fn test_data_point_features() {
         let dp = make_data_point(25000, 30, 1);
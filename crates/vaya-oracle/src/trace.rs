@@ -0,0 +1,140 @@
+//! Prediction traces for debugging
+//!
+//! A [`PredictionTrace`] records everything a predictor saw and produced
+//! for one prediction: the history window it used, the feature vector
+//! built for each observation (before and after scaling), the scaler's
+//! parameters, and the raw output of every ensemble member. Predictions are
+//! computed "as of" a timestamp, ignoring observations made after it, so
+//! any past prediction can be reproduced offline from the same history.
+
+use time::{Date, OffsetDateTime};
+use vaya_common::IataCode;
+
+use crate::prediction::{PriceDataPoint, PricePrediction};
+use crate::{OracleError, OracleResult, Season};
+
+/// Names of the features in each [`FeatureVector`], in order
+pub const FEATURE_NAMES: [&str; 5] = [
+    "price",
+    "days_before_departure",
+    "day_of_week",
+    "weekend_departure",
+    "holiday",
+];
+
+/// Features built for one observation
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureVector {
+    /// Observation timestamp
+    pub observed_at: i64,
+    /// Raw feature values (see [`FEATURE_NAMES`])
+    pub raw: Vec<f64>,
+    /// Values fed to the model after scaling, if the model scales
+    pub scaled: Option<Vec<f64>>,
+}
+
+impl FeatureVector {
+    /// Raw features for a data point
+    pub fn from_point(point: &PriceDataPoint) -> Self {
+        Self {
+            observed_at: point.timestamp,
+            raw: point.to_features(),
+            scaled: None,
+        }
+    }
+}
+
+/// History used for a prediction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryWindow {
+    /// Observations supplied
+    pub supplied: usize,
+    /// Observations ignored because they were made after the as-of time
+    pub after_as_of: usize,
+    /// Observations the prediction was computed from
+    pub used: usize,
+    /// Oldest observation used
+    pub from: Option<i64>,
+    /// Newest observation used
+    pub to: Option<i64>,
+}
+
+impl HistoryWindow {
+    /// Window over the observations used
+    pub fn new(supplied: usize, after_as_of: usize, used: &[&PriceDataPoint]) -> Self {
+        Self {
+            supplied,
+            after_as_of,
+            used: used.len(),
+            from: used.iter().map(|d| d.timestamp).min(),
+            to: used.iter().map(|d| d.timestamp).max(),
+        }
+    }
+}
+
+/// Feature scaling parameters (z-score)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScalingParams {
+    /// Mean of each scaled column
+    pub mean: Vec<f64>,
+    /// Standard deviation of each scaled column
+    pub std: Vec<f64>,
+}
+
+/// Output of one ensemble member
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberOutput {
+    /// Member name (`lstm`, `gbm`, `statistical`)
+    pub name: &'static str,
+    /// Raw model output before conversion to a price
+    pub raw_output: f64,
+    /// Price derived from the output, in minor units
+    pub predicted_price: f64,
+    /// Member confidence (0-1)
+    pub confidence: f64,
+    /// Whether this member's output became the prediction
+    pub selected: bool,
+}
+
+/// Everything a predictor saw and produced for one prediction
+#[derive(Debug, Clone)]
+pub struct PredictionTrace {
+    /// Origin airport
+    pub origin: IataCode,
+    /// Destination airport
+    pub destination: IataCode,
+    /// Departure date
+    pub departure_date: Date,
+    /// Time the prediction was computed as of (Unix timestamp)
+    pub as_of: i64,
+    /// Model version used
+    pub model_version: String,
+    /// Days from the as-of date to departure
+    pub days_until_departure: u32,
+    /// Season of the departure date
+    pub season: Season,
+    /// History used
+    pub window: HistoryWindow,
+    /// Feature vectors, oldest observation first
+    pub features: Vec<FeatureVector>,
+    /// Scaler parameters, if features were scaled
+    pub scaling: Option<ScalingParams>,
+    /// Ensemble member outputs
+    pub members: Vec<MemberOutput>,
+    /// The resulting prediction
+    pub prediction: PricePrediction,
+}
+
+/// Observations made at or before `as_of`, and how many were later
+pub(crate) fn history_as_of(data: &[PriceDataPoint], as_of: i64) -> (Vec<&PriceDataPoint>, usize) {
+    let known: Vec<&PriceDataPoint> = data.iter().filter(|d| d.timestamp <= as_of).collect();
+    let after = data.len() - known.len();
+    (known, after)
+}
+
+/// UTC date of a Unix timestamp
+pub(crate) fn date_at(as_of: i64) -> OracleResult<Date> {
+    OffsetDateTime::from_unix_timestamp(as_of)
+        .map(|t| t.date())
+        .map_err(|_| OracleError::InvalidData(format!("Invalid as-of timestamp {}", as_of)))
+}
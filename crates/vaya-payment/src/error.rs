@@ -115,6 +115,14 @@ pub enum PaymentError {
     /// Payment method not supported
     #[error("Payment method not supported: {0}")]
     PaymentMethodNotSupported(String),
+
+    /// Amount cannot be expressed in the currency's minor units
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    /// Webhook bookkeeping could not be read or written
+    #[error("Storage error: {0}")]
+    Storage(String),
 }
 
 impl PaymentError {
@@ -178,7 +186,7 @@ impl PaymentError {
     #[must_use]
     pub fn http_status(&self) -> u16 {
         match self {
-            Self::Configuration(_) => 500,
+            Self::Configuration(_) | Self::Storage(_) => 500,
             Self::AuthenticationFailed(_) | Self::InvalidSignature => 401,
             Self::CardDeclined { .. }
             | Self::InsufficientFunds
@@ -206,6 +214,12 @@ impl From<reqwest::Error> for PaymentError {
     }
 }
 
+impl From<vaya_db::DbError> for PaymentError {
+    fn from(err: vaya_db::DbError) -> Self {
+        Self::Storage(err.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,14 +5,25 @@
 //!
 //! - Uses `vaya-common` types (Price, `CurrencyCode`, etc.)
 //! - Uses `vaya-cache` for idempotency key caching
-//! - NO external database dependencies
+//! - Uses `vaya-crypto` for webhook signature verification
+//! - Uses `vaya-db` to remember processed webhook events
 //!
 //! # Supported Payment Methods
 //!
-//! - **Card Payments**: Via Stripe
+//! - **Card Payments**: Via Stripe, with 3-D Secure challenges (see [`three_ds`])
 //! - **FPX**: Malaysian bank transfers
 //! - **`GrabPay`**: Malaysian e-wallet
 //!
+//! FPX and `GrabPay` are redirect payments: the customer approves on the
+//! bank's or wallet's site and the result arrives later, by polling or
+//! webhook (see [`redirect`]).
+//!
+//! # Webhooks
+//!
+//! [`WebhookRouter`] verifies Stripe deliveries, dispatches them to a
+//! handler per event type, skips events already processed, and keeps
+//! events whose handler failed as dead letters for replay.
+//!
 //! # Example
 //!
 //! ```ignore
@@ -36,17 +47,34 @@
 #![warn(clippy::pedantic)]
 
 pub mod error;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+pub mod redirect;
 pub mod stripe;
+pub mod three_ds;
 pub mod types;
 mod webhook;
+mod webhook_db;
+mod webhook_router;
+
+use std::fmt;
+
+use vaya_common::{resolve_secret, Mask, Redact, SecretSource};
 
 pub use error::{PaymentError, PaymentResult};
+pub use redirect::{poll_payment, reconcile, PollConfig};
 pub use stripe::{PaymentProvider, StripeClient};
+pub use three_ds::{challenge_for, expire_stale_challenge, ThreeDsChallenge, ThreeDsConfig};
 pub use types::*;
 pub use webhook::WebhookHandler;
+pub use webhook_db::DbWebhookStore;
+pub use webhook_router::{
+    DeadLetter, MemoryWebhookStore, WebhookEventHandler, WebhookOutcome, WebhookRouter,
+    WebhookStore,
+};
 
 /// Payment configuration
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PaymentConfig {
     /// Stripe secret key
     pub stripe_secret_key: String,
@@ -62,6 +90,25 @@ pub struct PaymentConfig {
     pub default_currency: vaya_common::CurrencyCode,
 }
 
+impl fmt::Debug for PaymentConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PaymentConfig")
+            .field(
+                "stripe_secret_key",
+                &self.stripe_secret_key.redacted(Mask::Full),
+            )
+            .field("stripe_publishable_key", &self.stripe_publishable_key)
+            .field(
+                "stripe_webhook_secret",
+                &self.stripe_webhook_secret.redacted(Mask::Full),
+            )
+            .field("request_timeout_secs", &self.request_timeout_secs)
+            .field("max_retries", &self.max_retries)
+            .field("default_currency", &self.default_currency)
+            .finish()
+    }
+}
+
 impl Default for PaymentConfig {
     fn default() -> Self {
         Self {
@@ -99,6 +146,26 @@ impl PaymentConfig {
         self
     }
 
+    /// Replace `secret://` references in the Stripe keys with the secrets
+    /// they name
+    ///
+    /// Call once at startup, before [`validate`](Self::validate).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Configuration` naming the field whose secret is missing.
+    pub fn resolve_secrets(&mut self, secrets: &dyn SecretSource) -> PaymentResult<()> {
+        for (field, value) in [
+            ("stripe_secret_key", &mut self.stripe_secret_key),
+            ("stripe_publishable_key", &mut self.stripe_publishable_key),
+            ("stripe_webhook_secret", &mut self.stripe_webhook_secret),
+        ] {
+            resolve_secret(value, secrets)
+                .map_err(|e| PaymentError::Configuration(format!("{field}: {e}")))?;
+        }
+        Ok(())
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> PaymentResult<()> {
         if self.stripe_secret_key.is_empty() {
@@ -144,6 +211,28 @@ mod tests {
         assert_eq!(config.request_timeout_secs, 60);
     }
 
+    #[test]
+    fn test_resolve_secrets() {
+        let secrets: std::collections::HashMap<String, String> = [
+            ("stripe/secret_key".to_string(), "sk_live_abc".to_string()),
+            ("stripe/webhook".to_string(), "whsec_xyz".to_string()),
+        ]
+        .into();
+        let mut config = PaymentConfig::new("secret://stripe/secret_key", "pk_live_456")
+            .with_webhook_secret("secret://stripe/webhook");
+        config.resolve_secrets(&secrets).expect("secrets resolve");
+        assert_eq!(config.stripe_secret_key, "sk_live_abc");
+        assert_eq!(config.stripe_publishable_key, "pk_live_456");
+        assert_eq!(config.stripe_webhook_secret, "whsec_xyz");
+        assert!(config.validate().is_ok());
+
+        let mut config = PaymentConfig::new("secret://stripe/missing", "pk_live_456");
+        let err = config
+            .resolve_secrets(&secrets)
+            .expect_err("missing secret");
+        assert!(err.to_string().contains("stripe_secret_key"));
+    }
+
     #[test]
     fn test_config_validation() {
         let config = PaymentConfig::default();
@@ -0,0 +1,165 @@
+//! Mock payment provider for tests
+//!
+//! [`MockPaymentProvider`] settles every payment and refund at once and
+//! records what it was asked to do. Tests script failures, declines and the
+//! statuses a polled payment goes through. Other crates enable it with the
+//! `test-util` feature.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use async_trait::async_trait;
+use vaya_common::{Price, Timestamp};
+
+use crate::error::{PaymentError, PaymentResult};
+use crate::stripe::PaymentProvider;
+use crate::types::{
+    PaymentIntent, PaymentRequest, PaymentStatus, Refund, RefundRequest, RefundStatus,
+};
+
+/// Mock payment provider for testing
+#[derive(Default)]
+pub struct MockPaymentProvider {
+    /// Errors returned by the next calls, in order
+    failures: Mutex<VecDeque<PaymentError>>,
+    /// Whether new payments are declined
+    decline: Mutex<bool>,
+    /// Statuses `get_payment` reports, the last one repeating
+    statuses: Mutex<Vec<PaymentStatus>>,
+    /// Payments created
+    payments: Mutex<Vec<PaymentIntent>>,
+    /// Refunds made
+    refunds: Mutex<Vec<Refund>>,
+}
+
+impl MockPaymentProvider {
+    /// Create a provider that accepts everything
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail the next call, whichever operation it is, with `error`
+    pub fn fail_next(&self, error: PaymentError) {
+        lock(&self.failures).push_back(error);
+    }
+
+    /// Decline payments created from now on
+    pub fn set_decline(&self, decline: bool) {
+        *lock(&self.decline) = decline;
+    }
+
+    /// Report `statuses` from `get_payment` in turn, then keep the last
+    pub fn set_statuses(&self, statuses: Vec<PaymentStatus>) {
+        *lock(&self.statuses) = statuses;
+    }
+
+    /// Payments created so far
+    #[must_use]
+    pub fn payments(&self) -> Vec<PaymentIntent> {
+        lock(&self.payments).clone()
+    }
+
+    /// Refunds made so far, oldest first
+    #[must_use]
+    pub fn refunds(&self) -> Vec<Refund> {
+        lock(&self.refunds).clone()
+    }
+
+    fn take_failure(&self) -> PaymentResult<()> {
+        lock(&self.failures).pop_front().map_or(Ok(()), Err)
+    }
+
+    /// The created payment `payment_id`, or a blank one
+    fn payment(&self, payment_id: &str) -> PaymentIntent {
+        lock(&self.payments)
+            .iter()
+            .find(|p| p.id == payment_id)
+            .cloned()
+            .unwrap_or_else(|| intent(payment_id, Price::myr(0), PaymentStatus::Succeeded))
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for MockPaymentProvider {
+    async fn create_payment(&self, request: &PaymentRequest) -> PaymentResult<PaymentIntent> {
+        self.take_failure()?;
+        let status = if *lock(&self.decline) {
+            PaymentStatus::Failed
+        } else {
+            PaymentStatus::Succeeded
+        };
+        let mut payment = intent(
+            &format!("pi_{}", request.booking_ref),
+            request.amount,
+            status,
+        );
+        payment.booking_ref.clone_from(&request.booking_ref);
+        lock(&self.payments).push(payment.clone());
+        Ok(payment)
+    }
+
+    async fn get_payment(&self, payment_id: &str) -> PaymentResult<PaymentIntent> {
+        self.take_failure()?;
+        let mut payment = self.payment(payment_id);
+        let mut statuses = lock(&self.statuses);
+        if statuses.len() > 1 {
+            payment.status = statuses.remove(0);
+        } else if let Some(status) = statuses.first() {
+            payment.status = *status;
+        }
+        Ok(payment)
+    }
+
+    async fn cancel_payment(&self, payment_id: &str) -> PaymentResult<PaymentIntent> {
+        self.take_failure()?;
+        let mut payment = self.payment(payment_id);
+        payment.status = PaymentStatus::Cancelled;
+        payment.updated_at = Timestamp::now();
+        Ok(payment)
+    }
+
+    async fn create_refund(&self, request: &RefundRequest) -> PaymentResult<Refund> {
+        self.take_failure()?;
+        let refund = Refund {
+            id: format!("re_{}", request.payment_id),
+            payment_id: request.payment_id.clone(),
+            amount: request
+                .amount
+                .unwrap_or_else(|| self.payment(&request.payment_id).amount),
+            status: RefundStatus::Succeeded,
+            created_at: Timestamp::now(),
+            reason: request.reason,
+        };
+        lock(&self.refunds).push(refund.clone());
+        Ok(refund)
+    }
+
+    async fn get_refund(&self, refund_id: &str) -> PaymentResult<Refund> {
+        self.take_failure()?;
+        lock(&self.refunds)
+            .iter()
+            .find(|r| r.id == refund_id)
+            .cloned()
+            .ok_or_else(|| PaymentError::RefundFailed(format!("Unknown refund {refund_id}")))
+    }
+}
+
+fn intent(id: &str, amount: Price, status: PaymentStatus) -> PaymentIntent {
+    PaymentIntent {
+        id: id.to_string(),
+        client_secret: format!("{id}_secret"),
+        amount,
+        status,
+        payment_method: None,
+        created_at: Timestamp::now(),
+        updated_at: Timestamp::now(),
+        booking_ref: String::new(),
+        error_message: None,
+        next_action_url: None,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,264 @@
+//! Payment tracking for redirects and 3-D Secure
+//!
+//! Bank and wallet payments finish outside our checkout: the customer
+//! approves on the bank's or wallet's site, then the bank confirms the
+//! transfer some time later. Card payments awaiting 3-D Secure are settled
+//! the same way once the challenge is done. The status is followed either by polling the
+//! provider or by applying Stripe webhooks to the stored intent. Both
+//! sources can arrive late or out of order, so a status only moves forward
+//! (see [`PaymentStatus::can_transition_to`]).
+
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::error::PaymentResult;
+use crate::stripe::{intent_status, PaymentProvider};
+use crate::types::{PaymentIntent, PaymentStatus, WebhookEvent, WebhookEventType};
+
+/// How long to poll a redirect payment
+#[derive(Debug, Clone, Copy)]
+pub struct PollConfig {
+    /// Delay between status checks
+    pub interval: Duration,
+    /// Maximum number of status checks
+    pub max_attempts: u32,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(3),
+            max_attempts: 40,
+        }
+    }
+}
+
+impl PollConfig {
+    /// Set delay between checks
+    #[must_use]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set maximum number of checks
+    #[must_use]
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts;
+        self
+    }
+}
+
+/// Whether a payment is still waiting on the customer's bank or wallet
+#[must_use]
+pub const fn is_in_flight(status: PaymentStatus) -> bool {
+    matches!(
+        status,
+        PaymentStatus::AwaitingRedirect | PaymentStatus::Processing
+    )
+}
+
+/// Poll a payment until the bank or wallet has settled it
+///
+/// Returns the last intent seen, which is still in flight if the attempts
+/// ran out first.
+///
+/// # Errors
+///
+/// Returns the provider's error if a status check fails.
+pub async fn poll_payment<P: PaymentProvider + ?Sized>(
+    provider: &P,
+    payment_id: &str,
+    config: PollConfig,
+) -> PaymentResult<PaymentIntent> {
+    let mut attempt = 1;
+    loop {
+        let intent = provider.get_payment(payment_id).await?;
+        if !is_in_flight(intent.status) || attempt >= config.max_attempts {
+            return Ok(intent);
+        }
+        debug!(
+            "Payment {} is {:?}, checking again in {:?}",
+            payment_id, intent.status, config.interval
+        );
+        tokio::time::sleep(config.interval).await;
+        attempt += 1;
+    }
+}
+
+/// Apply a payment intent webhook to a stored intent
+///
+/// Returns whether the intent changed. Events for other payments, events
+/// that are not about payment intents, and stale events are ignored.
+pub fn reconcile(intent: &mut PaymentIntent, event: &WebhookEvent) -> bool {
+    let is_intent_event = matches!(
+        event.event_type,
+        WebhookEventType::PaymentIntentSucceeded
+            | WebhookEventType::PaymentIntentFailed
+            | WebhookEventType::PaymentIntentRequiresAction
+            | WebhookEventType::PaymentIntentProcessing
+            | WebhookEventType::PaymentIntentCancelled
+    );
+    if !is_intent_event || event.payment_id.as_deref() != Some(intent.id.as_str()) {
+        return false;
+    }
+    let Some(object) = event.data.get("object") else {
+        return false;
+    };
+
+    let mut status = intent_status(object);
+    // A failed 3-D Secure challenge ends the attempt; a failed bank
+    // redirect goes back to pending so the customer can pick another bank
+    if event.event_type == WebhookEventType::PaymentIntentFailed
+        && intent.status == PaymentStatus::RequiresAction
+    {
+        status = PaymentStatus::Failed;
+    }
+    if status == intent.status || !intent.status.can_transition_to(status) {
+        debug!(
+            "Ignoring {:?} for payment {} in {:?}",
+            event.event_type, intent.id, intent.status
+        );
+        return false;
+    }
+
+    intent.status = status;
+    intent.error_message = object
+        .get("last_payment_error")
+        .and_then(|e| e.get("message"))
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    intent.next_action_url = object
+        .get("next_action")
+        .and_then(|a| a.get("redirect_to_url"))
+        .and_then(|r| r.get("url"))
+        .and_then(|v| v.as_str())
+        .map(String::from);
+    intent.updated_at = event.timestamp;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use vaya_common::{Price, Timestamp};
+
+    use crate::mock::MockPaymentProvider;
+
+    fn intent(id: &str, status: PaymentStatus) -> PaymentIntent {
+        PaymentIntent {
+            id: id.to_string(),
+            client_secret: String::new(),
+            amount: Price::myr(50_000),
+            status,
+            payment_method: None,
+            created_at: Timestamp::now(),
+            updated_at: Timestamp::now(),
+            booking_ref: "VAY123".to_string(),
+            error_message: None,
+            next_action_url: Some("https://bank.example/approve".to_string()),
+        }
+    }
+
+    fn event(event_type: &str, object: &serde_json::Value) -> WebhookEvent {
+        WebhookEvent {
+            id: "evt_1".to_string(),
+            event_type: WebhookEventType::from_stripe(event_type),
+            timestamp: Timestamp::now(),
+            payment_id: object.get("id").and_then(|v| v.as_str()).map(String::from),
+            refund_id: None,
+            data: serde_json::json!({ "object": object }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_until_settled() {
+        let bank = MockPaymentProvider::new();
+        bank.set_statuses(vec![
+            PaymentStatus::AwaitingRedirect,
+            PaymentStatus::Processing,
+            PaymentStatus::Succeeded,
+        ]);
+        let config = PollConfig::default().with_interval(Duration::from_millis(1));
+        let intent = poll_payment(&bank, "pi_1", config).await.expect("poll");
+        assert_eq!(intent.status, PaymentStatus::Succeeded);
+
+        let bank = MockPaymentProvider::new();
+        bank.set_statuses(vec![PaymentStatus::Processing]);
+        let config = config.with_max_attempts(3);
+        let intent = poll_payment(&bank, "pi_1", config).await.expect("poll");
+        assert_eq!(intent.status, PaymentStatus::Processing);
+    }
+
+    #[test]
+    fn test_reconcile_moves_forward_only() {
+        let mut payment = intent("pi_1", PaymentStatus::AwaitingRedirect);
+
+        let processing = event(
+            "payment_intent.processing",
+            &serde_json::json!({"id": "pi_1", "status": "processing"}),
+        );
+        assert!(reconcile(&mut payment, &processing));
+        assert_eq!(payment.status, PaymentStatus::Processing);
+        assert!(payment.next_action_url.is_none());
+
+        let succeeded = event(
+            "payment_intent.succeeded",
+            &serde_json::json!({"id": "pi_1", "status": "succeeded"}),
+        );
+        assert!(reconcile(&mut payment, &succeeded));
+        assert_eq!(payment.status, PaymentStatus::Succeeded);
+
+        // A late processing event does not undo the success
+        assert!(!reconcile(&mut payment, &processing));
+        assert_eq!(payment.status, PaymentStatus::Succeeded);
+
+        // Events for other payments are ignored
+        let mut other = intent("pi_2", PaymentStatus::AwaitingRedirect);
+        assert!(!reconcile(&mut other, &succeeded));
+    }
+
+    #[test]
+    fn test_reconcile_three_ds_outcome() {
+        let mut payment = intent("pi_1", PaymentStatus::RequiresAction);
+        let failed = event(
+            "payment_intent.payment_failed",
+            &serde_json::json!({
+                "id": "pi_1",
+                "status": "requires_payment_method",
+                "last_payment_error": {"message": "Authentication failed"}
+            }),
+        );
+        assert!(reconcile(&mut payment, &failed));
+        assert_eq!(payment.status, PaymentStatus::Failed);
+
+        let mut payment = intent("pi_1", PaymentStatus::RequiresAction);
+        let succeeded = event(
+            "payment_intent.succeeded",
+            &serde_json::json!({"id": "pi_1", "status": "succeeded"}),
+        );
+        assert!(reconcile(&mut payment, &succeeded));
+        assert_eq!(payment.status, PaymentStatus::Succeeded);
+    }
+
+    #[test]
+    fn test_reconcile_failed_bank_approval() {
+        let mut payment = intent("pi_1", PaymentStatus::AwaitingRedirect);
+        let failed = event(
+            "payment_intent.payment_failed",
+            &serde_json::json!({
+                "id": "pi_1",
+                "status": "requires_payment_method",
+                "last_payment_error": {"message": "The customer cancelled at the bank"}
+            }),
+        );
+        assert!(reconcile(&mut payment, &failed));
+        assert_eq!(payment.status, PaymentStatus::Pending);
+        assert_eq!(
+            payment.error_message.as_deref(),
+            Some("The customer cancelled at the bank")
+        );
+    }
+}
@@ -8,8 +8,8 @@ use vaya_common::{CurrencyCode, MinorUnits, Price, Timestamp};
 
 use crate::error::{PaymentError, PaymentResult};
 use crate::types::{
-    CardBrand, PaymentIntent, PaymentMethodDetails, PaymentRequest, PaymentStatus, Refund,
-    RefundReason, RefundRequest, RefundStatus,
+    CardBrand, PaymentIntent, PaymentMethodDetails, PaymentMethodType, PaymentRequest,
+    PaymentStatus, RedirectMethod, Refund, RefundReason, RefundRequest, RefundStatus,
 };
 use crate::PaymentConfig;
 
@@ -50,8 +50,8 @@ impl StripeClient {
         request.validate()?;
 
         let mut params = vec![
-            ("amount", request.amount.amount.as_i64().to_string()),
-            ("currency", request.currency.as_str().to_lowercase()),
+            ("amount", stripe_amount(&request.amount)?),
+            ("currency", request.amount.currency.as_str().to_lowercase()),
             ("receipt_email", request.customer_email.clone()),
             ("metadata[booking_ref]", request.booking_ref.clone()),
         ];
@@ -93,6 +93,105 @@ impl StripeClient {
         self.parse_payment_intent(&response)
     }
 
+    /// Create and confirm an FPX or `GrabPay` payment
+    ///
+    /// The returned intent is normally [`PaymentStatus::AwaitingRedirect`]
+    /// with the bank or wallet page in `next_action_url`. After approval the
+    /// customer comes back to the request's return URL while the payment
+    /// moves to processing and then succeeds or fails; follow it with
+    /// [`crate::poll_payment`] or webhooks and [`crate::reconcile`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request has no return URL, the method cannot
+    /// take the currency, or Stripe rejects the payment.
+    pub async fn create_redirect_payment(
+        &self,
+        request: &PaymentRequest,
+        method: &RedirectMethod,
+    ) -> PaymentResult<PaymentIntent> {
+        request.validate()?;
+        method.validate(request.amount.currency)?;
+        let return_url = request.return_url.clone().ok_or_else(|| {
+            PaymentError::Configuration("Return URL is required for redirect payments".to_string())
+        })?;
+
+        let mut params = vec![
+            ("amount", stripe_amount(&request.amount)?),
+            ("currency", request.amount.currency.as_str().to_lowercase()),
+            ("receipt_email", request.customer_email.clone()),
+            ("metadata[booking_ref]", request.booking_ref.clone()),
+            ("confirm", "true".to_string()),
+            ("return_url", return_url),
+        ];
+        if let Some(ref desc) = request.description {
+            params.push(("description", desc.clone()));
+        }
+        for (key, value) in &request.metadata {
+            params.push((format!("metadata[{key}]").leak(), value.clone()));
+        }
+        params.push((
+            "payment_method_types[0]",
+            method.method_type().stripe_type().to_string(),
+        ));
+        params.extend(redirect_method_params(method));
+
+        let idempotency_key = request
+            .idempotency_key
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let response: serde_json::Value = self
+            .post_with_retry(
+                &format!("{STRIPE_API_BASE}/payment_intents"),
+                &params,
+                Some(&idempotency_key),
+            )
+            .await?;
+
+        self.parse_payment_intent(&response)
+    }
+
+    /// Confirm an existing payment again through a bank or wallet
+    ///
+    /// Used when the customer abandoned or failed the first redirect and
+    /// picks another bank or wallet; the payment is back to pending with the
+    /// failure in `error_message`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Stripe rejects the confirmation.
+    pub async fn confirm_redirect_payment(
+        &self,
+        payment_id: &str,
+        method: &RedirectMethod,
+        return_url: &str,
+    ) -> PaymentResult<PaymentIntent> {
+        let mut params = vec![("return_url", return_url.to_string())];
+        params.extend(redirect_method_params(method));
+
+        let url = format!("{STRIPE_API_BASE}/payment_intents/{payment_id}/confirm");
+        let response: serde_json::Value = self.post_with_retry(&url, &params, None).await?;
+        self.parse_payment_intent(&response)
+    }
+
+    /// Confirm a card payment once the customer completed 3-D Secure
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::RequiresAuthentication`] if the bank wants
+    /// another challenge, [`PaymentError::CardDeclined`] if authentication
+    /// failed, or Stripe's error if the confirmation is rejected.
+    pub async fn confirm_after_authentication(
+        &self,
+        payment_id: &str,
+    ) -> PaymentResult<PaymentIntent> {
+        let url = format!("{STRIPE_API_BASE}/payment_intents/{payment_id}/confirm");
+        let response: serde_json::Value = self.post_with_retry(&url, &[], None).await?;
+        let intent = self.parse_payment_intent(&response)?;
+        authenticated(intent)
+    }
+
     /// Retrieve a payment intent
     pub async fn get_payment(&self, payment_id: &str) -> PaymentResult<PaymentIntent> {
         let url = format!("{STRIPE_API_BASE}/payment_intents/{payment_id}");
@@ -115,7 +214,7 @@ impl StripeClient {
         ];
 
         if let Some(ref amount) = request.amount {
-            params.push(("amount", amount.amount.as_i64().to_string()));
+            params.push(("amount", stripe_amount(amount)?));
         }
 
         let idempotency_key = request
@@ -296,19 +395,7 @@ impl StripeClient {
         let currency = CurrencyCode::new(currency_str);
         let amount = Price::new(MinorUnits::new(amount_cents), currency);
 
-        let status_str = json
-            .get("status")
-            .and_then(|v| v.as_str())
-            .unwrap_or("pending");
-
-        let status = match status_str {
-            "requires_payment_method" | "requires_confirmation" => PaymentStatus::Pending,
-            "requires_action" => PaymentStatus::RequiresAction,
-            "processing" => PaymentStatus::Processing,
-            "succeeded" => PaymentStatus::Succeeded,
-            "canceled" => PaymentStatus::Cancelled,
-            _ => PaymentStatus::Failed,
-        };
+        let status = intent_status(json);
 
         let created_at = json
             .get("created")
@@ -510,6 +597,102 @@ impl PaymentProvider for StripeClient {
     }
 }
 
+/// Payment status of a Stripe payment intent object
+///
+/// `requires_action` means a bank or wallet redirect when the intent is
+/// being confirmed with a redirect method, and card authentication
+/// otherwise.
+pub(crate) fn intent_status(json: &serde_json::Value) -> PaymentStatus {
+    let status_str = json
+        .get("status")
+        .and_then(|v| v.as_str())
+        .unwrap_or("pending");
+
+    match status_str {
+        "requires_payment_method" | "requires_confirmation" => PaymentStatus::Pending,
+        "requires_action" if is_redirect(json) => PaymentStatus::AwaitingRedirect,
+        "requires_action" => PaymentStatus::RequiresAction,
+        "processing" => PaymentStatus::Processing,
+        "succeeded" => PaymentStatus::Succeeded,
+        "canceled" => PaymentStatus::Cancelled,
+        _ => PaymentStatus::Failed,
+    }
+}
+
+/// Outcome of confirming a payment after 3-D Secure
+fn authenticated(intent: PaymentIntent) -> PaymentResult<PaymentIntent> {
+    match intent.status {
+        PaymentStatus::RequiresAction => Err(PaymentError::RequiresAuthentication {
+            client_secret: intent.client_secret,
+        }),
+        // Back to needing a payment method: the challenge was failed
+        PaymentStatus::Pending | PaymentStatus::Failed => Err(PaymentError::CardDeclined {
+            code: "payment_intent_authentication_failure".to_string(),
+            message: intent
+                .error_message
+                .unwrap_or_else(|| "3-D Secure authentication failed".to_string()),
+        }),
+        _ => Ok(intent),
+    }
+}
+
+/// Whether an intent's next action sends the customer to a bank or wallet
+fn is_redirect(json: &serde_json::Value) -> bool {
+    let redirect_action = json
+        .get("next_action")
+        .and_then(|a| a.get("type"))
+        .and_then(|v| v.as_str())
+        == Some("redirect_to_url");
+
+    // The payment method is only an object when expanded; otherwise a
+    // single allowed method type tells us what is being confirmed
+    let method_type = json
+        .get("payment_method")
+        .and_then(|m| m.get("type"))
+        .and_then(|v| v.as_str())
+        .or_else(|| {
+            let types = json.get("payment_method_types")?.as_array()?;
+            match types.as_slice() {
+                [only] => only.as_str(),
+                _ => None,
+            }
+        });
+
+    redirect_action
+        && method_type
+            .and_then(PaymentMethodType::from_stripe)
+            .is_some_and(|t| t.is_redirect())
+}
+
+/// `payment_method_data` parameters for a redirect method
+fn redirect_method_params(method: &RedirectMethod) -> Vec<(&'static str, String)> {
+    let mut params = vec![(
+        "payment_method_data[type]",
+        method.method_type().stripe_type().to_string(),
+    )];
+    if let RedirectMethod::Fpx { bank } = method {
+        params.push(("payment_method_data[fpx][bank]", bank.clone()));
+    }
+    params
+}
+
+/// Stripe `amount` parameter for a price
+///
+/// Stripe takes the currency's smallest unit, which matches our minor units
+/// (whole yen for JPY, fils for KWD), except that three-decimal amounts must
+/// be a multiple of ten.
+fn stripe_amount(price: &Price) -> PaymentResult<String> {
+    let minor = price.amount.as_i64();
+    if price.currency.decimals() == 3 && minor % 10 != 0 {
+        return Err(PaymentError::InvalidAmount(format!(
+            "{} {} must be rounded to 2 decimal places for card payments",
+            price.currency,
+            price.amount.format_decimal(price.currency)
+        )));
+    }
+    Ok(minor.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -531,4 +714,101 @@ mod tests {
         let client = StripeClient::new(&config);
         assert!(client.is_err());
     }
+
+    #[test]
+    fn test_redirect_intent_status() {
+        let fpx = serde_json::json!({
+            "id": "pi_1",
+            "status": "requires_action",
+            "payment_method_types": ["fpx"],
+            "next_action": {
+                "type": "redirect_to_url",
+                "redirect_to_url": {"url": "https://bank.example/approve"}
+            }
+        });
+        assert_eq!(intent_status(&fpx), PaymentStatus::AwaitingRedirect);
+
+        // Card authentication is not a bank redirect
+        let card = serde_json::json!({
+            "status": "requires_action",
+            "payment_method": {"type": "card"},
+            "payment_method_types": ["card", "fpx"],
+            "next_action": {"type": "redirect_to_url"}
+        });
+        assert_eq!(intent_status(&card), PaymentStatus::RequiresAction);
+
+        let processing = serde_json::json!({"status": "processing"});
+        assert_eq!(intent_status(&processing), PaymentStatus::Processing);
+    }
+
+    #[test]
+    fn test_confirm_after_authentication_outcome() {
+        let client =
+            StripeClient::new(&PaymentConfig::new("sk_test_123", "pk_test_456")).expect("client");
+        let intent = |status: &str| {
+            client
+                .parse_payment_intent(&serde_json::json!({
+                    "id": "pi_1",
+                    "client_secret": "pi_1_secret",
+                    "status": status,
+                    "last_payment_error": {"message": "Authentication failed"}
+                }))
+                .expect("intent")
+        };
+
+        let ok = authenticated(intent("succeeded")).expect("succeeded");
+        assert_eq!(ok.status, PaymentStatus::Succeeded);
+        assert!(matches!(
+            authenticated(intent("requires_action")),
+            Err(PaymentError::RequiresAuthentication { client_secret }) if client_secret == "pi_1_secret"
+        ));
+        assert!(matches!(
+            authenticated(intent("requires_payment_method")),
+            Err(PaymentError::CardDeclined { .. })
+        ));
+    }
+
+    #[test]
+    fn test_redirect_method_params() {
+        let params = redirect_method_params(&RedirectMethod::fpx("maybank2u"));
+        assert_eq!(
+            params,
+            vec![
+                ("payment_method_data[type]", "fpx".to_string()),
+                ("payment_method_data[fpx][bank]", "maybank2u".to_string()),
+            ]
+        );
+        let params = redirect_method_params(&RedirectMethod::GrabPay);
+        assert_eq!(
+            params,
+            vec![("payment_method_data[type]", "grabpay".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_stripe_amount_by_currency() {
+        let myr = Price::new(MinorUnits::new(15050), CurrencyCode::MYR);
+        assert_eq!(stripe_amount(&myr).expect("MYR amount"), "15050");
+        // Zero-decimal: whole yen, not yen * 100
+        let jpy = Price::new(
+            MinorUnits::parse_decimal("45000", CurrencyCode::JPY).expect("valid amount"),
+            CurrencyCode::JPY,
+        );
+        assert_eq!(stripe_amount(&jpy).expect("JPY amount"), "45000");
+
+        let kwd = CurrencyCode::new("KWD");
+        let rounded = Price::new(
+            MinorUnits::parse_decimal("12.340", kwd).expect("valid amount"),
+            kwd,
+        );
+        assert_eq!(stripe_amount(&rounded).expect("KWD amount"), "12340");
+        let unrounded = Price::new(
+            MinorUnits::parse_decimal("12.345", kwd).expect("valid amount"),
+            kwd,
+        );
+        assert!(matches!(
+            stripe_amount(&unrounded),
+            Err(PaymentError::InvalidAmount(_))
+        ));
+    }
 }
@@ -0,0 +1,166 @@
+//! 3-D Secure (SCA) challenges
+//!
+//! A card payment that needs strong customer authentication comes back as
+//! [`PaymentStatus::RequiresAction`]. The frontend runs the challenge with
+//! the intent's client secret (or the bank's redirect URL), after which the
+//! payment is confirmed with [`crate::StripeClient::confirm_after_authentication`]
+//! or settled by a webhook (see [`crate::reconcile`]). A challenge the
+//! customer never completes is cancelled once it is older than
+//! [`ThreeDsConfig::challenge_ttl_secs`].
+
+use tracing::info;
+use vaya_common::Timestamp;
+
+use crate::error::PaymentResult;
+use crate::stripe::PaymentProvider;
+use crate::types::{PaymentIntent, PaymentStatus};
+
+/// 3-D Secure settings
+#[derive(Debug, Clone, Copy)]
+pub struct ThreeDsConfig {
+    /// How long a customer has to complete a challenge
+    pub challenge_ttl_secs: i64,
+}
+
+impl Default for ThreeDsConfig {
+    fn default() -> Self {
+        Self {
+            challenge_ttl_secs: 900, // 15 minutes
+        }
+    }
+}
+
+impl ThreeDsConfig {
+    /// Set challenge lifetime
+    #[must_use]
+    pub fn with_challenge_ttl(mut self, secs: i64) -> Self {
+        self.challenge_ttl_secs = secs;
+        self
+    }
+}
+
+/// What the frontend needs to run a 3-D Secure challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreeDsChallenge {
+    /// Payment intent ID
+    pub payment_id: String,
+    /// Client secret for Stripe.js `handleCardAction`
+    pub client_secret: String,
+    /// Bank challenge page, when Stripe asks for a redirect
+    pub redirect_url: Option<String>,
+    /// When the challenge stops being accepted
+    pub expires_at: Timestamp,
+}
+
+impl ThreeDsChallenge {
+    /// Check if the challenge has lapsed
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_past()
+    }
+}
+
+/// The challenge for a payment awaiting authentication, if any
+///
+/// The challenge runs from when the payment last changed, which is when
+/// Stripe asked for authentication.
+#[must_use]
+pub fn challenge_for(intent: &PaymentIntent, config: &ThreeDsConfig) -> Option<ThreeDsChallenge> {
+    if intent.status != PaymentStatus::RequiresAction || intent.client_secret.is_empty() {
+        return None;
+    }
+    Some(ThreeDsChallenge {
+        payment_id: intent.id.clone(),
+        client_secret: intent.client_secret.clone(),
+        redirect_url: intent.next_action_url.clone(),
+        expires_at: intent.updated_at.add_secs(config.challenge_ttl_secs),
+    })
+}
+
+/// Cancel a payment whose challenge has lapsed
+///
+/// Returns whether the payment was cancelled. Payments not awaiting
+/// authentication, or still inside the challenge window, are left alone.
+///
+/// # Errors
+///
+/// Returns the provider's error if the cancellation fails.
+pub async fn expire_stale_challenge<P: PaymentProvider + ?Sized>(
+    provider: &P,
+    intent: &mut PaymentIntent,
+    config: &ThreeDsConfig,
+) -> PaymentResult<bool> {
+    match challenge_for(intent, config) {
+        Some(challenge) if challenge.is_expired() => {
+            *intent = provider.cancel_payment(&intent.id).await?;
+            info!(
+                "Cancelled payment {} after unfinished 3-D Secure challenge",
+                intent.id
+            );
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use vaya_common::Price;
+
+    use crate::mock::MockPaymentProvider;
+
+    fn intent(age_secs: i64) -> PaymentIntent {
+        let asked = Timestamp::now().add_secs(-age_secs);
+        PaymentIntent {
+            id: "pi_1".to_string(),
+            client_secret: "pi_1_secret_abc".to_string(),
+            amount: Price::myr(50_000),
+            status: PaymentStatus::RequiresAction,
+            payment_method: None,
+            created_at: asked,
+            updated_at: asked,
+            booking_ref: "VAY123".to_string(),
+            error_message: None,
+            next_action_url: None,
+        }
+    }
+
+    #[test]
+    fn test_challenge_for_requires_action() {
+        let config = ThreeDsConfig::default();
+        let payment = intent(60);
+        let challenge = challenge_for(&payment, &config).expect("challenge");
+        assert_eq!(challenge.client_secret, "pi_1_secret_abc");
+        assert_eq!(
+            challenge.expires_at,
+            payment.updated_at.add_secs(config.challenge_ttl_secs)
+        );
+        assert!(!challenge.is_expired());
+
+        let mut succeeded = intent(60);
+        succeeded.status = PaymentStatus::Succeeded;
+        assert!(challenge_for(&succeeded, &config).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expire_stale_challenge() {
+        let stripe = MockPaymentProvider::new();
+        let config = ThreeDsConfig::default().with_challenge_ttl(300);
+
+        let mut fresh = intent(60);
+        let expired = expire_stale_challenge(&stripe, &mut fresh, &config)
+            .await
+            .expect("expire");
+        assert!(!expired);
+        assert_eq!(fresh.status, PaymentStatus::RequiresAction);
+
+        let mut stale = intent(600);
+        let expired = expire_stale_challenge(&stripe, &mut stale, &config)
+            .await
+            .expect("expire");
+        assert!(expired);
+        assert_eq!(stale.status, PaymentStatus::Cancelled);
+    }
+}
@@ -11,6 +11,8 @@ pub enum PaymentStatus {
     Processing,
     /// Requires additional authentication
     RequiresAction,
+    /// Customer sent to their bank or e-wallet to approve the payment
+    AwaitingRedirect,
     /// Payment succeeded
     Succeeded,
     /// Payment failed
@@ -47,6 +49,31 @@ impl PaymentStatus {
         matches!(self, Self::Succeeded | Self::PartiallyRefunded)
     }
 
+    /// Whether the status may move to `next`
+    ///
+    /// Polling and webhooks can report a payment out of order; a stale
+    /// report never moves a payment backwards.
+    #[must_use]
+    pub const fn can_transition_to(&self, next: Self) -> bool {
+        match self {
+            Self::Pending => !matches!(next, Self::Pending),
+            Self::RequiresAction | Self::AwaitingRedirect => matches!(
+                next,
+                Self::Pending | Self::Processing | Self::Succeeded | Self::Failed | Self::Cancelled
+            ),
+            Self::Processing => matches!(next, Self::Succeeded | Self::Failed | Self::Cancelled),
+            Self::Succeeded => matches!(
+                next,
+                Self::Refunded | Self::PartiallyRefunded | Self::Disputed
+            ),
+            Self::PartiallyRefunded => {
+                matches!(next, Self::Refunded | Self::Disputed)
+            }
+            Self::Disputed => matches!(next, Self::Succeeded | Self::Refunded),
+            Self::Failed | Self::Cancelled | Self::Refunded => false,
+        }
+    }
+
     /// Display name
     #[must_use]
     pub const fn display_name(&self) -> &'static str {
@@ -54,6 +81,7 @@ impl PaymentStatus {
             Self::Pending => "Pending",
             Self::Processing => "Processing",
             Self::RequiresAction => "Action Required",
+            Self::AwaitingRedirect => "Awaiting Bank Approval",
             Self::Succeeded => "Succeeded",
             Self::Failed => "Failed",
             Self::Cancelled => "Cancelled",
@@ -95,6 +123,29 @@ impl PaymentMethodType {
         }
     }
 
+    /// Parse from a Stripe payment method type
+    #[must_use]
+    pub fn from_stripe(method_type: &str) -> Option<Self> {
+        match method_type {
+            "card" => Some(Self::Card),
+            "fpx" => Some(Self::Fpx),
+            "grabpay" => Some(Self::GrabPay),
+            "tng_ewallet" => Some(Self::TngEwallet),
+            "boost" => Some(Self::Boost),
+            "bank_transfer" => Some(Self::BankTransfer),
+            _ => None,
+        }
+    }
+
+    /// Whether the customer approves the payment on their bank's or wallet's site
+    #[must_use]
+    pub const fn is_redirect(&self) -> bool {
+        matches!(
+            self,
+            Self::Fpx | Self::GrabPay | Self::TngEwallet | Self::Boost
+        )
+    }
+
     /// Display name
     #[must_use]
     pub const fn display_name(&self) -> &'static str {
@@ -109,6 +160,88 @@ impl PaymentMethodType {
     }
 }
 
+/// Stripe FPX bank codes
+pub const FPX_BANKS: &[&str] = &[
+    "affin_bank",
+    "agrobank",
+    "alliance_bank",
+    "ambank",
+    "bank_islam",
+    "bank_muamalat",
+    "bank_rakyat",
+    "bsn",
+    "cimb",
+    "deutsche_bank",
+    "hong_leong_bank",
+    "hsbc",
+    "kfh",
+    "maybank2e",
+    "maybank2u",
+    "ocbc",
+    "pb_enterprise",
+    "public_bank",
+    "rhb",
+    "standard_chartered",
+    "uob",
+];
+
+/// Payment method approved by redirecting the customer
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RedirectMethod {
+    /// FPX online banking through the given bank
+    Fpx {
+        /// Stripe bank code (see [`FPX_BANKS`])
+        bank: String,
+    },
+    /// `GrabPay` wallet
+    GrabPay,
+}
+
+impl RedirectMethod {
+    /// FPX through a bank
+    #[must_use]
+    pub fn fpx(bank: impl Into<String>) -> Self {
+        Self::Fpx { bank: bank.into() }
+    }
+
+    /// Payment method type
+    #[must_use]
+    pub const fn method_type(&self) -> PaymentMethodType {
+        match self {
+            Self::Fpx { .. } => PaymentMethodType::Fpx,
+            Self::GrabPay => PaymentMethodType::GrabPay,
+        }
+    }
+
+    /// Check the method can take a payment in `currency`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an unknown FPX bank, or a currency the method
+    /// does not settle in (FPX is MYR only; `GrabPay` is MYR or SGD).
+    pub fn validate(&self, currency: CurrencyCode) -> crate::PaymentResult<()> {
+        let supported: &[CurrencyCode] = match self {
+            Self::Fpx { bank } => {
+                if !FPX_BANKS.contains(&bank.as_str()) {
+                    return Err(crate::PaymentError::PaymentMethodNotSupported(format!(
+                        "FPX bank {bank}"
+                    )));
+                }
+                &[CurrencyCode::MYR]
+            }
+            Self::GrabPay => &[CurrencyCode::MYR, CurrencyCode::SGD],
+        };
+        if supported.contains(&currency) {
+            Ok(())
+        } else {
+            Err(crate::PaymentError::CurrencyMismatch {
+                expected: supported[0].to_string(),
+                got: currency.to_string(),
+            })
+        }
+    }
+}
+
 /// Card brand
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CardBrand {
@@ -254,7 +387,13 @@ impl PaymentRequest {
     pub fn validate(&self) -> crate::PaymentResult<()> {
         if self.amount.amount == MinorUnits::ZERO {
             return Err(crate::PaymentError::AmountTooSmall {
-                minimum: "0.01".to_string(),
+                minimum: MinorUnits::new(1).format_decimal(self.amount.currency),
+            });
+        }
+        if self.currency != self.amount.currency {
+            return Err(crate::PaymentError::CurrencyMismatch {
+                expected: self.amount.currency.to_string(),
+                got: self.currency.to_string(),
             });
         }
         if self.booking_ref.is_empty() {
@@ -414,6 +553,8 @@ pub enum WebhookEventType {
     PaymentIntentFailed,
     /// Payment requires action
     PaymentIntentRequiresAction,
+    /// Payment approved by the customer and being confirmed by the bank
+    PaymentIntentProcessing,
     /// Payment cancelled
     PaymentIntentCancelled,
     /// Refund succeeded
@@ -436,6 +577,7 @@ impl WebhookEventType {
             "payment_intent.succeeded" => Self::PaymentIntentSucceeded,
             "payment_intent.payment_failed" => Self::PaymentIntentFailed,
             "payment_intent.requires_action" => Self::PaymentIntentRequiresAction,
+            "payment_intent.processing" => Self::PaymentIntentProcessing,
             "payment_intent.canceled" => Self::PaymentIntentCancelled,
             "charge.refunded" => Self::ChargeRefunded,
             "refund.updated" => Self::RefundUpdated,
@@ -444,6 +586,23 @@ impl WebhookEventType {
             other => Self::Unknown(other.to_string()),
         }
     }
+
+    /// Stripe event type string
+    #[must_use]
+    pub fn stripe_type(&self) -> &str {
+        match self {
+            Self::PaymentIntentSucceeded => "payment_intent.succeeded",
+            Self::PaymentIntentFailed => "payment_intent.payment_failed",
+            Self::PaymentIntentRequiresAction => "payment_intent.requires_action",
+            Self::PaymentIntentProcessing => "payment_intent.processing",
+            Self::PaymentIntentCancelled => "payment_intent.canceled",
+            Self::ChargeRefunded => "charge.refunded",
+            Self::RefundUpdated => "refund.updated",
+            Self::ChargeDisputeCreated => "charge.dispute.created",
+            Self::ChargeDisputeClosed => "charge.dispute.closed",
+            Self::Unknown(other) => other,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -469,6 +628,40 @@ mod tests {
         assert_eq!(PaymentMethodType::Fpx.stripe_type(), "fpx");
     }
 
+    #[test]
+    fn test_redirect_status_transitions() {
+        use PaymentStatus::{AwaitingRedirect, Pending, Processing, Succeeded};
+        assert!(Pending.can_transition_to(AwaitingRedirect));
+        assert!(AwaitingRedirect.can_transition_to(Processing));
+        assert!(AwaitingRedirect.can_transition_to(Pending));
+        assert!(Processing.can_transition_to(Succeeded));
+        assert!(!Processing.can_transition_to(AwaitingRedirect));
+        assert!(!Succeeded.can_transition_to(Processing));
+    }
+
+    #[test]
+    fn test_redirect_method_validation() {
+        assert!(PaymentMethodType::Fpx.is_redirect());
+        assert!(!PaymentMethodType::Card.is_redirect());
+        assert_eq!(
+            PaymentMethodType::from_stripe("grabpay"),
+            Some(PaymentMethodType::GrabPay)
+        );
+
+        let fpx = RedirectMethod::fpx("maybank2u");
+        assert!(fpx.validate(CurrencyCode::MYR).is_ok());
+        assert!(matches!(
+            fpx.validate(CurrencyCode::SGD),
+            Err(crate::PaymentError::CurrencyMismatch { .. })
+        ));
+        assert!(matches!(
+            RedirectMethod::fpx("mybank").validate(CurrencyCode::MYR),
+            Err(crate::PaymentError::PaymentMethodNotSupported(_))
+        ));
+        assert!(RedirectMethod::GrabPay.validate(CurrencyCode::SGD).is_ok());
+        assert!(RedirectMethod::GrabPay.validate(CurrencyCode::USD).is_err());
+    }
+
     #[test]
     fn test_card_brand() {
         assert_eq!(CardBrand::from_stripe("visa"), CardBrand::Visa);
@@ -513,5 +706,12 @@ mod tests {
             WebhookEventType::from_stripe("unknown.event"),
             WebhookEventType::Unknown(_)
         ));
+        for name in [
+            "charge.dispute.created",
+            "payment_intent.canceled",
+            "unknown.event",
+        ] {
+            assert_eq!(WebhookEventType::from_stripe(name).stripe_type(), name);
+        }
     }
 }
@@ -1,8 +1,7 @@
 //! Stripe webhook handling
 
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
 use tracing::{debug, info, warn};
+use vaya_crypto::{HmacKey, HmacTag};
 
 use crate::error::{PaymentError, PaymentResult};
 use crate::types::{WebhookEvent, WebhookEventType};
@@ -48,9 +47,12 @@ impl WebhookHandler {
             .map(|d| d.as_secs())
             .unwrap_or(0);
 
-        if current_time > sig_parts.timestamp + self.timestamp_tolerance {
+        // Reject replays of old deliveries, and timestamps from the future
+        if current_time > sig_parts.timestamp + self.timestamp_tolerance
+            || sig_parts.timestamp > current_time + self.timestamp_tolerance
+        {
             warn!(
-                "Webhook timestamp too old: {} vs current {}",
+                "Webhook timestamp outside tolerance: {} vs current {}",
                 sig_parts.timestamp, current_time
             );
             return Err(PaymentError::InvalidSignature);
@@ -66,7 +68,7 @@ impl WebhookHandler {
         debug!("Webhook signature verified successfully");
 
         // Parse event
-        self.parse_event(payload)
+        Self::parse_event(payload)
     }
 
     /// Parse Stripe signature header
@@ -100,24 +102,22 @@ impl WebhookHandler {
         })
     }
 
-    /// Verify HMAC signature
+    /// Verify HMAC-SHA256 signature (constant-time comparison)
     fn verify_signature(&self, signed_payload: &str, signatures: &[String]) -> bool {
-        type HmacSha256 = Hmac<Sha256>;
-
-        let secret_bytes = self.signing_secret.as_bytes();
-        let Ok(mut mac) = HmacSha256::new_from_slice(secret_bytes) else {
+        let Ok(key) = HmacKey::new(self.signing_secret.as_bytes()) else {
+            warn!("Webhook signing secret is too short for HMAC-SHA256");
             return false;
         };
 
-        mac.update(signed_payload.as_bytes());
-        let expected = hex::encode(mac.finalize().into_bytes());
-
         // Check if any signature matches
-        signatures.iter().any(|sig| sig == &expected)
+        signatures
+            .iter()
+            .filter_map(|sig| HmacTag::from_hex(sig).ok())
+            .any(|tag| key.verify(signed_payload.as_bytes(), &tag))
     }
 
     /// Parse event from JSON payload
-    fn parse_event(&self, payload: &str) -> PaymentResult<WebhookEvent> {
+    pub(crate) fn parse_event(payload: &str) -> PaymentResult<WebhookEvent> {
         let json: serde_json::Value = serde_json::from_str(payload).map_err(|e| {
             PaymentError::InvalidResponse(format!("Failed to parse webhook payload: {e}"))
         })?;
@@ -151,13 +151,15 @@ impl WebhookHandler {
             .and_then(|v| v.as_str())
             .map(String::from);
 
-        let refund_id = if matches!(
-            event_type,
-            WebhookEventType::ChargeRefunded | WebhookEventType::RefundUpdated
-        ) {
-            payment_id.clone()
-        } else {
-            None
+        // A charge event carries its refunds, newest first; a refund event
+        // is the refund itself
+        let refund_id = match event_type {
+            WebhookEventType::ChargeRefunded => data
+                .pointer("/object/refunds/data/0/id")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            WebhookEventType::RefundUpdated => payment_id.clone(),
+            _ => None,
         };
 
         info!("Parsed webhook event: {} type={:?}", id, event_type);
@@ -183,11 +185,51 @@ struct SignatureParts {
 mod tests {
     use super::*;
 
+    const SECRET: &str = "whsec_test_secret_0123456789abcdefghij";
+
     fn create_test_handler() -> WebhookHandler {
-        let config = PaymentConfig::default().with_webhook_secret("whsec_test_secret");
+        let config = PaymentConfig::default().with_webhook_secret(SECRET);
         WebhookHandler::new(&config)
     }
 
+    fn sign(payload: &str, timestamp: u64) -> String {
+        let key = HmacKey::new(SECRET.as_bytes()).expect("key");
+        let tag = key.sign(format!("{timestamp}.{payload}").as_bytes());
+        format!("t={timestamp},v1={}", tag.to_hex())
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+
+    #[test]
+    fn test_verify_and_parse() {
+        let handler = create_test_handler();
+        let payload = r#"{"id":"evt_1","type":"charge.refunded","data":{"object":{"id":"ch_1","refunds":{"data":[{"id":"re_1"}]}}}}"#;
+
+        let event = handler
+            .verify_and_parse(payload, &sign(payload, now()))
+            .expect("valid signature");
+        assert_eq!(event.event_type, WebhookEventType::ChargeRefunded);
+        assert_eq!(event.refund_id.as_deref(), Some("re_1"));
+
+        // Tampered payload
+        let tampered = payload.replace("re_1", "re_2");
+        assert!(handler
+            .verify_and_parse(&tampered, &sign(payload, now()))
+            .is_err());
+
+        // Replayed old delivery, and a timestamp from the future
+        for timestamp in [now() - 600, now() + 600] {
+            assert!(matches!(
+                handler.verify_and_parse(payload, &sign(payload, timestamp)),
+                Err(PaymentError::InvalidSignature)
+            ));
+        }
+    }
+
     #[test]
     fn test_parse_signature_header() {
         let handler = create_test_handler();
@@ -212,8 +254,6 @@ mod tests {
 
     #[test]
     fn test_parse_event() {
-        let handler = create_test_handler();
-
         let payload = r#"{
             "id": "evt_123",
             "type": "payment_intent.succeeded",
@@ -225,12 +265,41 @@ mod tests {
             }
         }"#;
 
-        let event = handler.parse_event(payload);
+        let event = WebhookHandler::parse_event(payload);
         assert!(event.is_ok());
 
         let event = event.expect("Should parse");
         assert_eq!(event.id, "evt_123");
         assert_eq!(event.event_type, WebhookEventType::PaymentIntentSucceeded);
         assert_eq!(event.payment_id, Some("pi_123".to_string()));
+        assert_eq!(event.refund_id, None);
+    }
+
+    #[test]
+    fn test_parse_event_refund_ids() {
+        let refund_id = |payload: &str| {
+            WebhookHandler::parse_event(payload)
+                .expect("Should parse")
+                .refund_id
+        };
+
+        // Charge events take the refund from the charge's refund list
+        assert_eq!(
+            refund_id(
+                r#"{"id":"evt_1","type":"charge.refunded","data":{"object":{"id":"ch_1","refunds":{"data":[{"id":"re_2"},{"id":"re_1"}]}}}}"#
+            ),
+            Some("re_2".to_string())
+        );
+        assert_eq!(
+            refund_id(r#"{"id":"evt_2","type":"charge.refunded","data":{"object":{"id":"ch_1"}}}"#),
+            None
+        );
+        // Refund events are the refund object
+        assert_eq!(
+            refund_id(
+                r#"{"id":"evt_3","type":"refund.updated","data":{"object":{"id":"re_3","charge":"ch_1"}}}"#
+            ),
+            Some("re_3".to_string())
+        );
     }
 }
@@ -0,0 +1,165 @@
+//! `VayaDb`-backed webhook store
+//!
+//! Processed event IDs are kept under `webhook:event:{id}` with the time
+//! they were claimed, and dead letters as JSON under `webhook:dead:{id}`.
+//! `VayaDb` has no key iteration, so the dead letter IDs are also listed,
+//! oldest first, under `webhook:dead_index`.
+
+use std::sync::{Arc, Mutex};
+
+use vaya_db::VayaDb;
+
+use crate::error::{PaymentError, PaymentResult};
+use crate::webhook_router::{DeadLetter, WebhookStore};
+
+const EVENT_PREFIX: &str = "webhook:event:";
+const DEAD_PREFIX: &str = "webhook:dead:";
+const DEAD_INDEX_KEY: &[u8] = b"webhook:dead_index";
+
+/// Webhook store persisting to `VayaDb`
+pub struct DbWebhookStore {
+    db: Arc<VayaDb>,
+    /// Serializes claims and dead letter index updates
+    lock: Mutex<()>,
+}
+
+impl DbWebhookStore {
+    /// Create a store over an open database
+    #[must_use]
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn event_key(event_id: &str) -> Vec<u8> {
+        format!("{EVENT_PREFIX}{event_id}").into_bytes()
+    }
+
+    fn dead_key(event_id: &str) -> Vec<u8> {
+        format!("{DEAD_PREFIX}{event_id}").into_bytes()
+    }
+
+    fn read_index(&self) -> PaymentResult<Vec<String>> {
+        let Some(bytes) = self.db.get(DEAD_INDEX_KEY)? else {
+            return Ok(Vec::new());
+        };
+        serde_json::from_slice(&bytes)
+            .map_err(|e| PaymentError::Storage(format!("Corrupt dead letter index: {e}")))
+    }
+
+    fn write_index(&self, ids: &[String]) -> PaymentResult<()> {
+        if ids.is_empty() {
+            self.db.delete(DEAD_INDEX_KEY)?;
+            return Ok(());
+        }
+        let bytes = serde_json::to_vec(ids)
+            .map_err(|e| PaymentError::Storage(format!("Failed to serialize index: {e}")))?;
+        self.db.put(DEAD_INDEX_KEY, &bytes)?;
+        Ok(())
+    }
+
+    fn guard(&self) -> PaymentResult<std::sync::MutexGuard<'_, ()>> {
+        self.lock
+            .lock()
+            .map_err(|_| PaymentError::Storage("Webhook store lock poisoned".to_string()))
+    }
+}
+
+impl WebhookStore for DbWebhookStore {
+    fn claim(&self, event_id: &str, now: i64) -> PaymentResult<bool> {
+        let _guard = self.guard()?;
+        let key = Self::event_key(event_id);
+        if self.db.get(&key)?.is_some() {
+            return Ok(false);
+        }
+        self.db.put(&key, &now.to_le_bytes())?;
+        Ok(true)
+    }
+
+    fn put_dead_letter(&self, letter: &DeadLetter) -> PaymentResult<()> {
+        let _guard = self.guard()?;
+        let bytes = serde_json::to_vec(letter)
+            .map_err(|e| PaymentError::Storage(format!("Failed to serialize dead letter: {e}")))?;
+        self.db.put(&Self::dead_key(&letter.event_id), &bytes)?;
+
+        let mut ids = self.read_index()?;
+        if !ids.contains(&letter.event_id) {
+            ids.push(letter.event_id.clone());
+            self.write_index(&ids)?;
+        }
+        Ok(())
+    }
+
+    fn dead_letter(&self, event_id: &str) -> PaymentResult<Option<DeadLetter>> {
+        let Some(bytes) = self.db.get(&Self::dead_key(event_id))? else {
+            return Ok(None);
+        };
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|e| PaymentError::Storage(format!("Corrupt dead letter: {e}")))
+    }
+
+    fn dead_letters(&self) -> PaymentResult<Vec<DeadLetter>> {
+        let mut letters = Vec::new();
+        for id in self.read_index()? {
+            letters.extend(self.dead_letter(&id)?);
+        }
+        Ok(letters)
+    }
+
+    fn remove_dead_letter(&self, event_id: &str) -> PaymentResult<()> {
+        let _guard = self.guard()?;
+        self.db.delete(&Self::dead_key(event_id))?;
+        let mut ids = self.read_index()?;
+        ids.retain(|id| id != event_id);
+        self.write_index(&ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vaya_db::DbConfig;
+
+    fn open(dir: &std::path::Path) -> Arc<VayaDb> {
+        Arc::new(VayaDb::open(DbConfig::new(dir)).expect("open"))
+    }
+
+    fn letter(event_id: &str) -> DeadLetter {
+        DeadLetter {
+            event_id: event_id.to_string(),
+            event_type: "charge.refunded".to_string(),
+            payload: format!(r#"{{"id":"{event_id}"}}"#),
+            error: "Service unavailable".to_string(),
+            attempts: 1,
+            failed_at: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_claims_and_dead_letters_survive_reopen() {
+        let dir = tempfile::tempdir().expect("tempdir");
+
+        {
+            let db = open(dir.path());
+            let store = DbWebhookStore::new(db.clone());
+            assert!(store.claim("evt_1", 1_700_000_000).expect("claim"));
+            assert!(!store.claim("evt_1", 1_700_000_001).expect("claim"));
+            store.put_dead_letter(&letter("evt_1")).expect("put");
+            store.put_dead_letter(&letter("evt_2")).expect("put");
+            store.remove_dead_letter("evt_2").expect("remove");
+            db.close().expect("close");
+        }
+
+        let store = DbWebhookStore::new(open(dir.path()));
+        assert!(!store.claim("evt_1", 1_700_000_002).expect("claim"));
+        assert!(store.claim("evt_2", 1_700_000_002).expect("claim"));
+        assert_eq!(
+            store.dead_letters().expect("letters"),
+            vec![letter("evt_1")]
+        );
+        assert!(store.dead_letter("evt_2").expect("letter").is_none());
+    }
+}
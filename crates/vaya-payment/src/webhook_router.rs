@@ -0,0 +1,359 @@
+//! Webhook event routing
+//!
+//! [`WebhookRouter`] verifies a Stripe delivery, then hands the event to the
+//! handler registered for its type. Each event ID is claimed in a
+//! [`WebhookStore`] before it is handled, so Stripe's redeliveries and
+//! replays are acknowledged without running the handler twice. An event
+//! whose handler fails is kept as a [`DeadLetter`] with its raw payload
+//! and can be replayed once the cause is fixed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use vaya_common::Timestamp;
+
+use crate::error::{PaymentError, PaymentResult};
+use crate::types::{WebhookEvent, WebhookEventType};
+use crate::webhook::WebhookHandler;
+
+/// Handles events of one type
+#[async_trait]
+pub trait WebhookEventHandler: Send + Sync {
+    /// Handle a verified event
+    async fn handle(&self, event: &WebhookEvent) -> PaymentResult<()>;
+}
+
+/// An event whose handler failed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Stripe event ID
+    pub event_id: String,
+    /// Stripe event type
+    pub event_type: String,
+    /// Raw event payload
+    pub payload: String,
+    /// Last handler error
+    pub error: String,
+    /// Handling attempts so far
+    pub attempts: u32,
+    /// When the event last failed (Unix timestamp)
+    pub failed_at: i64,
+}
+
+/// Processed event IDs and dead letters
+pub trait WebhookStore: Send + Sync {
+    /// Claim an event for processing; false if it was claimed before
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::Storage`] if the store cannot be read or written.
+    fn claim(&self, event_id: &str, now: i64) -> PaymentResult<bool>;
+
+    /// Save or replace a dead letter
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::Storage`] if the store cannot be written.
+    fn put_dead_letter(&self, letter: &DeadLetter) -> PaymentResult<()>;
+
+    /// A dead letter by event ID
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::Storage`] if the store cannot be read.
+    fn dead_letter(&self, event_id: &str) -> PaymentResult<Option<DeadLetter>>;
+
+    /// All dead letters, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::Storage`] if the store cannot be read.
+    fn dead_letters(&self) -> PaymentResult<Vec<DeadLetter>>;
+
+    /// Remove a dead letter after a successful replay
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::Storage`] if the store cannot be written.
+    fn remove_dead_letter(&self, event_id: &str) -> PaymentResult<()>;
+}
+
+/// In-memory webhook store (single instance, lost on restart)
+#[derive(Default)]
+pub struct MemoryWebhookStore {
+    claimed: Mutex<HashMap<String, i64>>,
+    dead: Mutex<Vec<DeadLetter>>,
+}
+
+impl MemoryWebhookStore {
+    /// Create an empty store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl WebhookStore for MemoryWebhookStore {
+    fn claim(&self, event_id: &str, now: i64) -> PaymentResult<bool> {
+        let mut claimed = self.claimed.lock().map_err(|_| poisoned())?;
+        if claimed.contains_key(event_id) {
+            return Ok(false);
+        }
+        claimed.insert(event_id.to_string(), now);
+        Ok(true)
+    }
+
+    fn put_dead_letter(&self, letter: &DeadLetter) -> PaymentResult<()> {
+        let mut dead = self.dead.lock().map_err(|_| poisoned())?;
+        dead.retain(|l| l.event_id != letter.event_id);
+        dead.push(letter.clone());
+        Ok(())
+    }
+
+    fn dead_letter(&self, event_id: &str) -> PaymentResult<Option<DeadLetter>> {
+        let dead = self.dead.lock().map_err(|_| poisoned())?;
+        Ok(dead.iter().find(|l| l.event_id == event_id).cloned())
+    }
+
+    fn dead_letters(&self) -> PaymentResult<Vec<DeadLetter>> {
+        Ok(self.dead.lock().map_err(|_| poisoned())?.clone())
+    }
+
+    fn remove_dead_letter(&self, event_id: &str) -> PaymentResult<()> {
+        let mut dead = self.dead.lock().map_err(|_| poisoned())?;
+        dead.retain(|l| l.event_id != event_id);
+        Ok(())
+    }
+}
+
+fn poisoned() -> PaymentError {
+    PaymentError::Storage("Webhook store lock poisoned".to_string())
+}
+
+/// Result of processing a delivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookOutcome {
+    /// Handler ran successfully
+    Handled,
+    /// Event was processed before; nothing done
+    Duplicate,
+    /// No handler for the event type
+    Ignored,
+    /// Handler failed; event kept as a dead letter
+    DeadLettered,
+}
+
+/// Routes verified Stripe events to their handlers
+pub struct WebhookRouter {
+    verifier: WebhookHandler,
+    store: Arc<dyn WebhookStore>,
+    handlers: HashMap<WebhookEventType, Arc<dyn WebhookEventHandler>>,
+}
+
+impl WebhookRouter {
+    /// Create a router with no handlers
+    #[must_use]
+    pub fn new(verifier: WebhookHandler, store: Arc<dyn WebhookStore>) -> Self {
+        Self {
+            verifier,
+            store,
+            handlers: HashMap::new(),
+        }
+    }
+
+    /// Register the handler for an event type
+    #[must_use]
+    pub fn on(
+        mut self,
+        event_type: WebhookEventType,
+        handler: Arc<dyn WebhookEventHandler>,
+    ) -> Self {
+        self.handlers.insert(event_type, handler);
+        self
+    }
+
+    /// Verify and process a delivery
+    ///
+    /// Handler failures are dead-lettered rather than returned, so Stripe
+    /// gets a success response and does not keep redelivering.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PaymentError::InvalidSignature`] for a bad or stale
+    /// signature, or [`PaymentError::Storage`] if the store fails.
+    pub async fn process(
+        &self,
+        payload: &str,
+        signature_header: &str,
+    ) -> PaymentResult<WebhookOutcome> {
+        let event = self.verifier.verify_and_parse(payload, signature_header)?;
+        if event.id.is_empty() {
+            return Err(PaymentError::InvalidResponse(
+                "Webhook event has no ID".to_string(),
+            ));
+        }
+        if !self.store.claim(&event.id, Timestamp::now().as_unix())? {
+            info!("Skipping duplicate webhook event {}", event.id);
+            return Ok(WebhookOutcome::Duplicate);
+        }
+
+        let Some(handler) = self.handlers.get(&event.event_type) else {
+            return Ok(WebhookOutcome::Ignored);
+        };
+        match handler.handle(&event).await {
+            Ok(()) => Ok(WebhookOutcome::Handled),
+            Err(e) => {
+                warn!("Webhook event {} failed: {}", event.id, e);
+                self.store.put_dead_letter(&DeadLetter {
+                    event_id: event.id.clone(),
+                    event_type: event.event_type.stripe_type().to_string(),
+                    payload: payload.to_string(),
+                    error: e.to_string(),
+                    attempts: 1,
+                    failed_at: Timestamp::now().as_unix(),
+                })?;
+                Ok(WebhookOutcome::DeadLettered)
+            }
+        }
+    }
+
+    /// Run a dead-lettered event through its handler again
+    ///
+    /// The stored payload was verified when it arrived, so it is not
+    /// checked again. The dead letter is removed on success and its
+    /// attempt count raised on failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such dead letter, the handler fails
+    /// again, or the store fails.
+    pub async fn replay(&self, event_id: &str) -> PaymentResult<()> {
+        let mut letter = self
+            .store
+            .dead_letter(event_id)?
+            .ok_or_else(|| PaymentError::Storage(format!("No dead letter for event {event_id}")))?;
+        let event = WebhookHandler::parse_event(&letter.payload)?;
+        let handler = self.handlers.get(&event.event_type).ok_or_else(|| {
+            PaymentError::Configuration(format!("No handler for {}", letter.event_type))
+        })?;
+
+        match handler.handle(&event).await {
+            Ok(()) => {
+                info!("Replayed webhook event {}", event_id);
+                self.store.remove_dead_letter(event_id)
+            }
+            Err(e) => {
+                letter.attempts += 1;
+                letter.error = e.to_string();
+                letter.failed_at = Timestamp::now().as_unix();
+                self.store.put_dead_letter(&letter)?;
+                Err(e)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use vaya_crypto::HmacKey;
+
+    use crate::PaymentConfig;
+
+    const SECRET: &str = "whsec_test_secret_0123456789abcdefghij";
+
+    /// Records handled events; fails the first `fail` calls
+    #[derive(Default)]
+    struct Recorder {
+        fail: AtomicU32,
+        handled: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl WebhookEventHandler for Recorder {
+        async fn handle(&self, event: &WebhookEvent) -> PaymentResult<()> {
+            if self.fail.load(Ordering::SeqCst) > 0 {
+                self.fail.fetch_sub(1, Ordering::SeqCst);
+                return Err(PaymentError::ServiceUnavailable(
+                    "booking store down".into(),
+                ));
+            }
+            self.handled.lock().expect("lock").push(event.id.clone());
+            Ok(())
+        }
+    }
+
+    fn router(store: Arc<dyn WebhookStore>, recorder: &Arc<Recorder>) -> WebhookRouter {
+        let verifier = WebhookHandler::new(&PaymentConfig::default().with_webhook_secret(SECRET));
+        WebhookRouter::new(verifier, store)
+            .on(WebhookEventType::PaymentIntentSucceeded, recorder.clone())
+            .on(WebhookEventType::ChargeRefunded, recorder.clone())
+    }
+
+    fn delivery(id: &str, event_type: &str) -> (String, String) {
+        let payload =
+            format!(r#"{{"id":"{id}","type":"{event_type}","data":{{"object":{{"id":"pi_1"}}}}}}"#);
+        let timestamp = Timestamp::now().as_unix();
+        let tag = HmacKey::new(SECRET.as_bytes())
+            .expect("key")
+            .sign(format!("{timestamp}.{payload}").as_bytes());
+        let header = format!("t={timestamp},v1={}", tag.to_hex());
+        (payload, header)
+    }
+
+    #[tokio::test]
+    async fn test_routes_each_event_once() {
+        let recorder = Arc::new(Recorder::default());
+        let router = router(Arc::new(MemoryWebhookStore::new()), &recorder);
+
+        let (payload, header) = delivery("evt_1", "payment_intent.succeeded");
+        let outcome = router.process(&payload, &header).await.expect("process");
+        assert_eq!(outcome, WebhookOutcome::Handled);
+        let outcome = router.process(&payload, &header).await.expect("process");
+        assert_eq!(outcome, WebhookOutcome::Duplicate);
+
+        let (payload, header) = delivery("evt_2", "charge.dispute.created");
+        let outcome = router.process(&payload, &header).await.expect("process");
+        assert_eq!(outcome, WebhookOutcome::Ignored);
+
+        assert!(router.process(&payload, "t=1,v1=00").await.is_err());
+        assert_eq!(*recorder.handled.lock().expect("lock"), vec!["evt_1"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_events_are_dead_lettered_and_replayed() {
+        let recorder = Arc::new(Recorder {
+            fail: AtomicU32::new(2),
+            ..Default::default()
+        });
+        let store = Arc::new(MemoryWebhookStore::new());
+        let router = router(store.clone(), &recorder);
+
+        let (payload, header) = delivery("evt_3", "charge.refunded");
+        let outcome = router.process(&payload, &header).await.expect("process");
+        assert_eq!(outcome, WebhookOutcome::DeadLettered);
+
+        let letters = store.dead_letters().expect("letters");
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].payload, payload);
+        assert_eq!(letters[0].event_type, "charge.refunded");
+
+        assert!(router.replay("evt_3").await.is_err());
+        assert_eq!(
+            store
+                .dead_letter("evt_3")
+                .expect("letter")
+                .map(|l| l.attempts),
+            Some(2)
+        );
+
+        router.replay("evt_3").await.expect("replay");
+        assert!(store.dead_letters().expect("letters").is_empty());
+        assert_eq!(*recorder.handled.lock().expect("lock"), vec!["evt_3"]);
+    }
+}
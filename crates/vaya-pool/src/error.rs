@@ -37,6 +37,12 @@ pub enum PoolError {
     MemberLimitReached,
     /// Minimum members not reached
     MinMembersNotReached { required: u32, current: u32 },
+    /// Waitlist is at its cap
+    WaitlistFull,
+    /// User is already on the waitlist
+    AlreadyWaitlisted,
+    /// User is not on the waitlist
+    NotWaitlisted,
 
     // === Contribution Errors ===
     /// Invalid contribution amount
@@ -49,6 +55,8 @@ pub enum PoolError {
     ContributionDeadlinePassed,
     /// Contribution already processed
     ContributionAlreadyProcessed,
+    /// Refund not found
+    RefundNotFound(String),
 
     // === Pricing Errors ===
     /// Tier not available
@@ -71,6 +79,10 @@ pub enum PoolError {
     ConcurrentModification,
     /// Lock acquisition failed
     LockFailed,
+    /// Stored pool changed since it was read
+    VersionConflict { expected: u32, actual: u32 },
+    /// Operation ID was already used for a different operation
+    OperationConflict(String),
 
     // === System Errors ===
     /// Internal error
@@ -106,6 +118,9 @@ impl fmt::Display for PoolError {
                     required, current
                 )
             }
+            PoolError::WaitlistFull => write!(f, "Pool waitlist is full"),
+            PoolError::AlreadyWaitlisted => write!(f, "User is already on the waitlist"),
+            PoolError::NotWaitlisted => write!(f, "User is not on the waitlist"),
 
             // Contribution
             PoolError::InvalidContribution(msg) => write!(f, "Invalid contribution: {}", msg),
@@ -119,6 +134,7 @@ impl fmt::Display for PoolError {
             }
             PoolError::ContributionDeadlinePassed => write!(f, "Contribution deadline has passed"),
             PoolError::ContributionAlreadyProcessed => write!(f, "Contribution already processed"),
+            PoolError::RefundNotFound(id) => write!(f, "Refund not found: {}", id),
 
             // Pricing
             PoolError::TierNotAvailable(tier) => write!(f, "Pricing tier not available: {}", tier),
@@ -133,6 +149,14 @@ impl fmt::Display for PoolError {
             // Concurrency
             PoolError::ConcurrentModification => write!(f, "Concurrent modification detected"),
             PoolError::LockFailed => write!(f, "Failed to acquire lock"),
+            PoolError::VersionConflict { expected, actual } => write!(
+                f,
+                "Pool version conflict: expected {}, found {}",
+                expected, actual
+            ),
+            PoolError::OperationConflict(id) => {
+                write!(f, "Operation ID {} was used for a different operation", id)
+            }
 
             // System
             PoolError::Internal(msg) => write!(f, "Internal error: {}", msg),
@@ -148,7 +172,26 @@ impl PoolError {
     pub fn is_retriable(&self) -> bool {
         matches!(
             self,
-            PoolError::LockFailed | PoolError::ConcurrentModification | PoolError::Internal(_)
+            PoolError::LockFailed
+                | PoolError::ConcurrentModification
+                | PoolError::VersionConflict { .. }
+                | PoolError::Internal(_)
+        )
+    }
+
+    /// Check if error is a conflict with the current pool state
+    ///
+    /// Clients should refresh the pool and show its current state rather
+    /// than report a failure (e.g. a double-tapped "Join").
+    pub fn is_conflict(&self) -> bool {
+        matches!(
+            self,
+            PoolError::AlreadyMember
+                | PoolError::AlreadyWaitlisted
+                | PoolError::ContributionAlreadyProcessed
+                | PoolError::ConcurrentModification
+                | PoolError::VersionConflict { .. }
+                | PoolError::OperationConflict(_)
         )
     }
 
@@ -5,7 +5,10 @@
 //! - **Pool management**: Create and manage buying pools with state machine
 //! - **Tiered pricing**: Automatic discounts based on group size
 //! - **Member management**: Join, leave, and contribute to pools
+//! - **Waitlists**: Users queue for full pools and are promoted in order
 //! - **Price locks**: Guaranteed pricing for members at join time
+//! - **Settlement**: Contributions refunded before a failed pool closes
+//! - **Persistence**: Compare-and-swap writes and idempotent join/contribute
 //!
 //! # How It Works
 //!
@@ -16,6 +19,9 @@
 //! 5. When all contributions are received, pool is Locked
 //! 6. System books the flights and pool becomes Completed
 //!
+//! A pool that fails, expires or is cancelled after contributions first
+//! moves to Refunding and closes once every contribution is refunded.
+//!
 //! # Pricing Tiers
 //!
 //! Standard tiers provide increasing discounts:
@@ -27,10 +33,20 @@
 mod error;
 mod pool;
 mod pricing;
+mod settlement;
+mod store;
 
 pub use error::{PoolError, PoolResult};
-pub use pool::{Pool, PoolMember, PoolRoute, PoolStatus, StatusChange};
+pub use pool::{
+    AppliedOperation, Pool, PoolDeadline, PoolMember, PoolOperation, PoolRoute, PoolStatus,
+    StatusChange, WaitlistEntry,
+};
 pub use pricing::{PriceLock, PricingTier, TieredPricing};
+pub use settlement::{MemberRefund, MemberRefundStatus};
+pub use store::{
+    validate_operation_id, MemoryPoolStore, OperationOutcome, PoolService, PoolStore,
+    MAX_OPERATION_ID_LEN,
+};
 
 /// Pool configuration
 #[derive(Debug, Clone)]
@@ -47,6 +63,8 @@ pub struct PoolConfig {
     pub price_lock_duration_secs: i64,
     /// Maximum spots per member
     pub max_spots_per_member: u32,
+    /// Default waitlist cap for full pools
+    pub default_waitlist_cap: u32,
 }
 
 impl Default for PoolConfig {
@@ -58,6 +76,7 @@ impl Default for PoolConfig {
             default_contribution_deadline_secs: 10 * 24 * 3600, // 10 days
             price_lock_duration_secs: 24 * 3600,       // 24 hours
             max_spots_per_member: 10,
+            default_waitlist_cap: 20,
         }
     }
 }
@@ -91,6 +110,21 @@ impl PoolConfig {
         self.default_contribution_deadline_secs = secs;
         self
     }
+
+    /// Set default waitlist cap
+    pub fn with_waitlist_cap(mut self, cap: u32) -> Self {
+        self.default_waitlist_cap = cap;
+        self
+    }
+
+    /// Apply the default limits and deadlines to a newly created pool
+    pub fn apply_to(&self, pool: &mut Pool) {
+        pool.min_members = self.default_min_members;
+        pool.max_members = self.default_max_members;
+        pool.join_deadline = pool.created_at + self.default_join_deadline_secs;
+        pool.contribution_deadline = pool.created_at + self.default_contribution_deadline_secs;
+        pool.waitlist_cap = self.default_waitlist_cap;
+    }
 }
 
 /// Validate pool member count
@@ -147,6 +181,8 @@ pub struct PoolSummary {
     pub is_joinable: bool,
     /// Is pool full
     pub is_full: bool,
+    /// Users on the waitlist
+    pub waitlist_length: u32,
 }
 
 impl PoolSummary {
@@ -166,8 +202,9 @@ impl PoolSummary {
             total_savings: pool.potential_savings(),
             members_to_next_tier: next_tier.map(|(_, needed)| needed),
             time_remaining: pool.time_to_deadline(),
-            is_joinable: pool.status.is_joinable() && !pool.is_full(),
+            is_joinable: pool.status.is_joinable() && !pool.is_full() && pool.waitlist.is_empty(),
             is_full: pool.is_full(),
+            waitlist_length: pool.waitlist.len() as u32,
         }
     }
 }
@@ -196,6 +233,7 @@ mod tests {
         assert_eq!(config.default_min_members, 5);
         assert_eq!(config.default_max_members, 50);
         assert_eq!(config.price_lock_duration_secs, 24 * 3600);
+        assert_eq!(config.default_waitlist_cap, 20);
     }
 
     #[test]
@@ -203,11 +241,19 @@ mod tests {
         let config = PoolConfig::new()
             .with_min_members(3)
             .with_max_members(100)
-            .with_join_deadline(3 * 24 * 3600);
+            .with_join_deadline(3 * 24 * 3600)
+            .with_waitlist_cap(5);
 
         assert_eq!(config.default_min_members, 3);
         assert_eq!(config.default_max_members, 100);
         assert_eq!(config.default_join_deadline_secs, 3 * 24 * 3600);
+        assert_eq!(config.default_waitlist_cap, 5);
+
+        let mut pool = create_test_pool();
+        config.apply_to(&mut pool);
+        assert_eq!(pool.max_members, 100);
+        assert_eq!(pool.waitlist_cap, 5);
+        assert_eq!(pool.join_deadline, pool.created_at + 3 * 24 * 3600);
     }
 
     #[test]
@@ -2,90 +2,14 @@
 
 use ring::rand::{SecureRandom, SystemRandom};
 use time::OffsetDateTime;
+pub use vaya_common::PoolStatus;
 use vaya_common::{IataCode, MinorUnits};
 use vaya_search::FlightOffer;
 
 use crate::pricing::{PriceLock, TieredPricing};
+use crate::settlement::MemberRefund;
 use crate::{PoolError, PoolResult};
 
-/// Pool status (state machine)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum PoolStatus {
-    /// Pool is forming, accepting members
-    Forming,
-    /// Minimum members reached, collecting contributions
-    Active,
-    /// Contributions complete, awaiting booking
-    Locked,
-    /// Booking complete, pool successful
-    Completed,
-    /// Pool expired (deadline passed)
-    Expired,
-    /// Pool cancelled by organizer
-    Cancelled,
-    /// Pool failed (booking failed, refunds pending)
-    Failed,
-}
-
-impl PoolStatus {
-    /// Get status as string
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            PoolStatus::Forming => "FORMING",
-            PoolStatus::Active => "ACTIVE",
-            PoolStatus::Locked => "LOCKED",
-            PoolStatus::Completed => "COMPLETED",
-            PoolStatus::Expired => "EXPIRED",
-            PoolStatus::Cancelled => "CANCELLED",
-            PoolStatus::Failed => "FAILED",
-        }
-    }
-
-    /// Check if this is a terminal state
-    pub fn is_terminal(&self) -> bool {
-        matches!(
-            self,
-            PoolStatus::Completed
-                | PoolStatus::Expired
-                | PoolStatus::Cancelled
-                | PoolStatus::Failed
-        )
-    }
-
-    /// Check if pool is joinable
-    pub fn is_joinable(&self) -> bool {
-        matches!(self, PoolStatus::Forming | PoolStatus::Active)
-    }
-
-    /// Check if pool can receive contributions
-    pub fn can_contribute(&self) -> bool {
-        matches!(self, PoolStatus::Active)
-    }
-
-    /// Validate state transition
-    pub fn can_transition_to(&self, target: PoolStatus) -> bool {
-        match (self, target) {
-            // From Forming
-            (PoolStatus::Forming, PoolStatus::Active) => true,
-            (PoolStatus::Forming, PoolStatus::Expired) => true,
-            (PoolStatus::Forming, PoolStatus::Cancelled) => true,
-
-            // From Active
-            (PoolStatus::Active, PoolStatus::Locked) => true,
-            (PoolStatus::Active, PoolStatus::Expired) => true,
-            (PoolStatus::Active, PoolStatus::Cancelled) => true,
-            (PoolStatus::Active, PoolStatus::Forming) => true, // Member left, below minimum
-
-            // From Locked
-            (PoolStatus::Locked, PoolStatus::Completed) => true,
-            (PoolStatus::Locked, PoolStatus::Failed) => true,
-
-            // All other transitions invalid
-            _ => false,
-        }
-    }
-}
-
 /// Pool member
 #[derive(Debug, Clone)]
 pub struct PoolMember {
@@ -99,6 +23,8 @@ pub struct PoolMember {
     pub contribution: Option<MinorUnits>,
     /// Contribution timestamp
     pub contributed_at: Option<i64>,
+    /// Provider payment the contribution was charged on
+    pub payment_ref: Option<String>,
     /// Price lock at join time
     pub price_lock: Option<PriceLock>,
     /// Is pool organizer
@@ -115,6 +41,7 @@ impl PoolMember {
             joined_at: now,
             contribution: None,
             contributed_at: None,
+            payment_ref: None,
             price_lock: None,
             is_organizer: false,
         }
@@ -140,6 +67,17 @@ impl PoolMember {
     }
 }
 
+/// A user waiting for spots in a full pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitlistEntry {
+    /// User ID
+    pub user_id: String,
+    /// Number of spots wanted
+    pub spots: u32,
+    /// Waitlist join timestamp
+    pub joined_at: i64,
+}
+
 /// Pool route
 #[derive(Debug, Clone)]
 pub struct PoolRoute {
@@ -201,6 +139,10 @@ pub struct Pool {
     pub max_members: u32,
     /// Current members
     pub members: Vec<PoolMember>,
+    /// Users waiting for spots, first come first served
+    pub waitlist: Vec<WaitlistEntry>,
+    /// Maximum waitlist length
+    pub waitlist_cap: u32,
     /// Flight offer (if locked to specific offer)
     pub offer: Option<FlightOffer>,
     /// Creation timestamp
@@ -217,6 +159,14 @@ pub struct Pool {
     pub history: Vec<StatusChange>,
     /// Version for optimistic locking
     pub version: u32,
+    /// Client operations already applied, for idempotent retries
+    pub applied_operations: Vec<AppliedOperation>,
+    /// Contribution refunds, once the pool is closing without a booking
+    pub refunds: Vec<MemberRefund>,
+    /// Status the pool closes as once refunds complete
+    pub settlement_outcome: Option<PoolStatus>,
+    /// Deadlines members have been reminded of
+    pub reminders_sent: Vec<PoolDeadline>,
 }
 
 impl Pool {
@@ -247,6 +197,8 @@ impl Pool {
             min_members: 5, // Default minimum
             max_members: 50,
             members: vec![organizer],
+            waitlist: Vec::new(),
+            waitlist_cap: 20,
             offer: None,
             created_at: now,
             updated_at: now,
@@ -255,6 +207,10 @@ impl Pool {
             booking_ref: None,
             history: Vec::new(),
             version: 1,
+            applied_operations: Vec::new(),
+            refunds: Vec::new(),
+            settlement_outcome: None,
+            reminders_sent: Vec::new(),
         };
 
         // Record initial state
@@ -317,7 +273,7 @@ impl Pool {
         // Check deadline
         let now = OffsetDateTime::now_utc().unix_timestamp();
         if now > self.join_deadline {
-            self.transition(PoolStatus::Expired, "Join deadline passed", "SYSTEM")?;
+            self.close_with_refunds(PoolStatus::Expired, "Join deadline passed", "SYSTEM")?;
             return Err(PoolError::PoolExpired);
         }
 
@@ -326,12 +282,25 @@ impl Pool {
             return Err(PoolError::AlreadyMember);
         }
 
-        // Check capacity
-        if self.total_spots() + spots > self.max_members {
+        // Check capacity; waitlisted users get freed spots first
+        if self.total_spots() + spots > self.max_members || !self.waitlist.is_empty() {
             return Err(PoolError::MemberLimitReached);
         }
 
-        // Add member with price lock
+        self.add_member(user_id, spots);
+        self.updated_at = now;
+        self.version += 1;
+
+        // Check if minimum reached (transition to Active)
+        if self.status == PoolStatus::Forming && self.min_reached() {
+            self.transition(PoolStatus::Active, "Minimum members reached", "SYSTEM")?;
+        }
+
+        Ok(())
+    }
+
+    /// Add a member with a price lock at the pool's new size
+    fn add_member(&mut self, user_id: &str, spots: u32) {
         let mut member = PoolMember::new(user_id, spots);
         let new_total = self.total_spots() + spots;
         let tier = self.pricing.get_tier(new_total);
@@ -345,25 +314,97 @@ impl Pool {
         ));
 
         self.members.push(member);
-        self.updated_at = now;
-        self.version += 1;
+    }
 
-        // Check if minimum reached (transition to Active)
-        if self.status == PoolStatus::Forming && self.min_reached() {
-            self.transition(PoolStatus::Active, "Minimum members reached", "SYSTEM")?;
+    /// Join the waitlist of a full pool, returning the 1-based position
+    pub fn join_waitlist(&mut self, user_id: &str, spots: u32) -> PoolResult<u32> {
+        if !self.status.is_joinable() {
+            return Err(PoolError::PoolNotJoinable(format!(
+                "Pool is in {} status",
+                self.status.as_str()
+            )));
         }
 
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        if now > self.join_deadline {
+            self.close_with_refunds(PoolStatus::Expired, "Join deadline passed", "SYSTEM")?;
+            return Err(PoolError::PoolExpired);
+        }
+
+        if self.get_member(user_id).is_some() {
+            return Err(PoolError::AlreadyMember);
+        }
+        if self.waitlist_position(user_id).is_some() {
+            return Err(PoolError::AlreadyWaitlisted);
+        }
+        if spots == 0 || spots > self.max_members {
+            return Err(PoolError::MemberLimitReached);
+        }
+        if self.waitlist.is_empty() && self.total_spots() + spots <= self.max_members {
+            return Err(PoolError::PoolNotJoinable(
+                "Pool has open spots, join directly".into(),
+            ));
+        }
+        if self.waitlist.len() as u32 >= self.waitlist_cap {
+            return Err(PoolError::WaitlistFull);
+        }
+
+        self.waitlist.push(WaitlistEntry {
+            user_id: user_id.to_string(),
+            spots,
+            joined_at: now,
+        });
+        self.updated_at = now;
+        self.version += 1;
+        Ok(self.waitlist.len() as u32)
+    }
+
+    /// Leave the waitlist
+    pub fn leave_waitlist(&mut self, user_id: &str) -> PoolResult<()> {
+        let pos = self
+            .waitlist
+            .iter()
+            .position(|w| w.user_id == user_id)
+            .ok_or(PoolError::NotWaitlisted)?;
+        self.waitlist.remove(pos);
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        self.version += 1;
         Ok(())
     }
 
-    /// Leave pool
-    pub fn leave(&mut self, user_id: &str) -> PoolResult<()> {
+    /// 1-based waitlist position of a user
+    pub fn waitlist_position(&self, user_id: &str) -> Option<u32> {
+        self.waitlist
+            .iter()
+            .position(|w| w.user_id == user_id)
+            .map(|i| i as u32 + 1)
+    }
+
+    /// Move waitlisted users into free spots in FIFO order
+    ///
+    /// Stops at the first entry that doesn't fit, so nobody is overtaken
+    /// by a later, smaller request.
+    fn promote_waitlist(&mut self) -> Vec<WaitlistEntry> {
+        let mut promoted = Vec::new();
+        while let Some(next) = self.waitlist.first() {
+            if self.total_spots() + next.spots > self.max_members {
+                break;
+            }
+            let entry = self.waitlist.remove(0);
+            self.add_member(&entry.user_id, entry.spots);
+            promoted.push(entry);
+        }
+        promoted
+    }
+
+    /// Leave pool, returning the waitlisted users promoted into the freed spots
+    pub fn leave(&mut self, user_id: &str) -> PoolResult<Vec<WaitlistEntry>> {
         // Check status
         if self.status == PoolStatus::Locked {
             return Err(PoolError::CannotLeave("Pool is locked".into()));
         }
 
-        if self.status.is_terminal() {
+        if self.status.is_terminal() || self.status == PoolStatus::Refunding {
             return Err(PoolError::CannotLeave("Pool is closed".into()));
         }
 
@@ -390,6 +431,7 @@ impl Pool {
 
         // Remove member
         self.members.remove(pos.unwrap());
+        let promoted = self.promote_waitlist();
 
         let now = OffsetDateTime::now_utc().unix_timestamp();
         self.updated_at = now;
@@ -400,7 +442,7 @@ impl Pool {
             self.transition(PoolStatus::Forming, "Dropped below minimum", "SYSTEM")?;
         }
 
-        Ok(())
+        Ok(promoted)
     }
 
     /// Record contribution from member
@@ -444,7 +486,7 @@ impl Pool {
             .unwrap_or(current_price);
 
         let spots = member.spots;
-        let required = price_per_person.as_i64() * spots as i64;
+        let required = (price_per_person * i64::from(spots)).as_i64();
 
         if amount.as_i64() < required {
             return Err(PoolError::InsufficientContribution {
@@ -466,6 +508,20 @@ impl Pool {
         Ok(())
     }
 
+    /// Record the provider payment a member's contribution was charged on
+    ///
+    /// Settlement refunds contributions against this payment.
+    pub fn set_payment_ref(&mut self, user_id: &str, payment_ref: &str) -> PoolResult<()> {
+        let member = self.get_member_mut(user_id).ok_or(PoolError::NotAMember)?;
+        if !member.has_contributed() {
+            return Err(PoolError::ContributionNotFound(user_id.to_string()));
+        }
+        member.payment_ref = Some(payment_ref.to_string());
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        self.version += 1;
+        Ok(())
+    }
+
     /// Check if all members have contributed
     pub fn all_contributed(&self) -> bool {
         self.members.iter().all(|m| m.has_contributed())
@@ -537,11 +593,11 @@ impl Pool {
         if self.status == PoolStatus::Locked || self.status == PoolStatus::Completed {
             return Err(PoolError::InvalidStateTransition {
                 from: self.status.as_str().to_string(),
-                to: "CANCELLED".to_string(),
+                to: PoolStatus::Cancelled.as_str().to_string(),
             });
         }
 
-        self.transition(PoolStatus::Cancelled, reason, user_id)
+        self.close_with_refunds(PoolStatus::Cancelled, reason, user_id)
     }
 
     /// Mark as completed
@@ -549,7 +605,7 @@ impl Pool {
         if self.status != PoolStatus::Locked {
             return Err(PoolError::InvalidStateTransition {
                 from: self.status.as_str().to_string(),
-                to: "COMPLETED".to_string(),
+                to: PoolStatus::Completed.as_str().to_string(),
             });
         }
 
@@ -557,35 +613,38 @@ impl Pool {
         self.transition(PoolStatus::Completed, "Booking completed", actor)
     }
 
-    /// Mark as failed
+    /// Mark as failed, refunding contributions first
     pub fn fail(&mut self, reason: &str, actor: &str) -> PoolResult<()> {
         if self.status != PoolStatus::Locked {
             return Err(PoolError::InvalidStateTransition {
                 from: self.status.as_str().to_string(),
-                to: "FAILED".to_string(),
+                to: PoolStatus::Failed.as_str().to_string(),
             });
         }
 
-        self.transition(PoolStatus::Failed, reason, actor)
+        self.close_with_refunds(PoolStatus::Failed, reason, actor)
     }
 
     /// Check if pool has expired
     pub fn check_expiry(&mut self) -> bool {
+        self.check_expiry_at(OffsetDateTime::now_utc().unix_timestamp())
+    }
+
+    /// Check if pool has expired as of `now` (Unix timestamp)
+    pub fn check_expiry_at(&mut self, now: i64) -> bool {
         if self.status.is_terminal() {
             return false;
         }
 
-        let now = OffsetDateTime::now_utc().unix_timestamp();
-
         // Check join deadline for Forming status
         if self.status == PoolStatus::Forming && now > self.join_deadline {
-            let _ = self.transition(PoolStatus::Expired, "Join deadline passed", "SYSTEM");
+            let _ = self.close_with_refunds(PoolStatus::Expired, "Join deadline passed", "SYSTEM");
             return true;
         }
 
         // Check contribution deadline for Active status
         if self.status == PoolStatus::Active && now > self.contribution_deadline {
-            let _ = self.transition(
+            let _ = self.close_with_refunds(
                 PoolStatus::Expired,
                 "Contribution deadline passed",
                 "SYSTEM",
@@ -599,13 +658,74 @@ impl Pool {
     /// Get time remaining until deadline
     pub fn time_to_deadline(&self) -> Option<i64> {
         let now = OffsetDateTime::now_utc().unix_timestamp();
-        let deadline = match self.status {
-            PoolStatus::Forming => self.join_deadline,
-            PoolStatus::Active => self.contribution_deadline,
-            _ => return None,
-        };
+        let (_, deadline) = self.next_deadline()?;
         Some((deadline - now).max(0))
     }
+
+    /// The deadline the pool is currently working towards
+    pub fn next_deadline(&self) -> Option<(PoolDeadline, i64)> {
+        match self.status {
+            PoolStatus::Forming => Some((PoolDeadline::Join, self.join_deadline)),
+            PoolStatus::Active => Some((PoolDeadline::Contribution, self.contribution_deadline)),
+            _ => None,
+        }
+    }
+
+    /// Record that members were reminded of a deadline
+    ///
+    /// Returns false if they already were.
+    pub fn record_reminder(&mut self, deadline: PoolDeadline) -> bool {
+        if self.reminders_sent.contains(&deadline) {
+            return false;
+        }
+        self.reminders_sent.push(deadline);
+        self.updated_at = OffsetDateTime::now_utc().unix_timestamp();
+        self.version += 1;
+        true
+    }
+}
+
+/// A pool deadline members are reminded of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolDeadline {
+    /// Join deadline, for pools still short of members
+    Join,
+    /// Contribution deadline, for members who haven't paid
+    Contribution,
+}
+
+impl PoolDeadline {
+    /// Get deadline as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolDeadline::Join => "join",
+            PoolDeadline::Contribution => "contribution",
+        }
+    }
+}
+
+/// Kind of client operation on a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolOperation {
+    /// Join with a number of spots
+    Join { spots: u32 },
+    /// Contribute an amount
+    Contribute { amount: MinorUnits },
+    /// Join the waitlist for a number of spots
+    JoinWaitlist { spots: u32 },
+}
+
+/// A client operation applied to a pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedOperation {
+    /// Client-generated operation ID
+    pub operation_id: String,
+    /// User who performed the operation
+    pub user_id: String,
+    /// What was done
+    pub operation: PoolOperation,
+    /// Pool version after the operation
+    pub version: u32,
 }
 
 /// Status change record
@@ -732,6 +852,52 @@ mod tests {
         assert!(pool.leave("user-3").is_err());
     }
 
+    #[test]
+    fn test_waitlist_promotion() {
+        let mut pool =
+            Pool::new("Test Pool", test_route(), test_pricing(), "organizer", 1).unwrap();
+        pool.max_members = 3;
+        pool.waitlist_cap = 2;
+
+        // Waitlist only once the pool is full
+        assert!(pool.join_waitlist("user-4", 1).is_err());
+        pool.join("user-2", 1).unwrap();
+        pool.join("user-3", 1).unwrap();
+        assert!(matches!(
+            pool.join("user-4", 1),
+            Err(PoolError::MemberLimitReached)
+        ));
+
+        assert_eq!(pool.join_waitlist("user-4", 2).unwrap(), 1);
+        assert_eq!(pool.join_waitlist("user-5", 1).unwrap(), 2);
+        assert!(matches!(
+            pool.join_waitlist("user-5", 1),
+            Err(PoolError::AlreadyWaitlisted)
+        ));
+        assert!(matches!(
+            pool.join_waitlist("user-6", 1),
+            Err(PoolError::WaitlistFull)
+        ));
+
+        // One spot frees up: user-4 needs two, so nobody jumps the queue
+        assert!(pool.leave("user-2").unwrap().is_empty());
+        assert!(pool.join("user-6", 1).is_err());
+
+        // Two spots: user-4 is promoted, user-5 moves to the front
+        let promoted = pool.leave("user-3").unwrap();
+        assert_eq!(promoted.len(), 1);
+        assert_eq!(promoted[0].user_id, "user-4");
+        assert_eq!(pool.get_member("user-4").unwrap().spots, 2);
+        assert!(pool.get_member("user-4").unwrap().price_lock.is_some());
+        assert_eq!(pool.waitlist_position("user-5"), Some(1));
+
+        pool.leave_waitlist("user-5").unwrap();
+        assert!(matches!(
+            pool.leave_waitlist("user-5"),
+            Err(PoolError::NotWaitlisted)
+        ));
+    }
+
     #[test]
     fn test_contribution() {
         let mut pool =
@@ -139,15 +139,12 @@ impl TieredPricing {
 
     /// Calculate total price for a pool size
     pub fn calculate_total(&self, member_count: u32) -> MinorUnits {
-        let price_per_person = self.get_price_per_person(member_count);
-        MinorUnits::new(price_per_person.as_i64() * member_count as i64)
+        self.get_price_per_person(member_count) * i64::from(member_count)
     }
 
     /// Get savings compared to individual bookings
     pub fn calculate_savings(&self, member_count: u32) -> MinorUnits {
-        let individual_total = self.base_price.as_i64() * member_count as i64;
-        let pool_total = self.calculate_total(member_count).as_i64();
-        MinorUnits::new(individual_total - pool_total)
+        self.base_price * i64::from(member_count) - self.calculate_total(member_count)
     }
 
     /// Get next tier (if any) and members needed
@@ -183,8 +180,7 @@ impl TieredPricing {
             last_discount = tier.discount_percent;
 
             // Verify price matches discount
-            let expected_price = self.base_price.as_i64()
-                - (self.base_price.as_i64() * tier.discount_percent as i64 / 100);
+            let expected_price = discounted(self.base_price, tier.discount_percent).as_i64();
             let actual_price = tier.price_per_person.as_i64();
 
             // Allow small rounding differences
@@ -205,14 +201,12 @@ impl TieredPricing {
     /// Create standard tiers from base price
     pub fn with_standard_tiers(base_price: MinorUnits, currency: CurrencyCode) -> PoolResult<Self> {
         let mut pricing = Self::new(base_price, currency);
-        let base = base_price.as_i64();
-
         // Tier 1: 5+ members = 5% off
         pricing.add_tier(PricingTier::new(
             "Silver",
             5,
             Some(10),
-            MinorUnits::new(base - base * 5 / 100),
+            discounted(base_price, 5),
             5,
         ))?;
 
@@ -221,7 +215,7 @@ impl TieredPricing {
             "Gold",
             10,
             Some(20),
-            MinorUnits::new(base - base * 10 / 100),
+            discounted(base_price, 10),
             10,
         ))?;
 
@@ -230,7 +224,7 @@ impl TieredPricing {
             "Platinum",
             20,
             Some(50),
-            MinorUnits::new(base - base * 15 / 100),
+            discounted(base_price, 15),
             15,
         ))?;
 
@@ -239,7 +233,7 @@ impl TieredPricing {
             "Diamond",
             50,
             None,
-            MinorUnits::new(base - base * 20 / 100),
+            discounted(base_price, 20),
             20,
         ))?;
 
@@ -250,6 +244,11 @@ impl TieredPricing {
     }
 }
 
+/// Price after a whole-percent discount, rounded to the nearest minor unit
+fn discounted(base: MinorUnits, discount_percent: u8) -> MinorUnits {
+    base - base.percent(u32::from(discount_percent))
+}
+
 /// Price lock for a pool (snapshot of price at join time)
 #[derive(Debug, Clone)]
 pub struct PriceLock {
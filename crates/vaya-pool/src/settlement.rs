@@ -0,0 +1,262 @@
+//! Refund settlement for pools that fail after collecting contributions
+//!
+//! A pool that fails, expires or is cancelled while holding contributions
+//! doesn't close straight away. [`Pool::close_with_refunds`] queues a
+//! [`MemberRefund`] for every contributed member and moves the pool to
+//! [`PoolStatus::Refunding`]. The payment side records each outcome with
+//! [`Pool::complete_refund`] or [`Pool::fail_refund`]; failed refunds stay
+//! pending for the next attempt, and the pool only reaches its terminal
+//! status once every contribution has been refunded.
+
+use time::OffsetDateTime;
+use vaya_common::{CurrencyCode, MinorUnits};
+
+use crate::pool::{Pool, PoolStatus};
+use crate::{PoolError, PoolResult};
+
+/// Status of a member's refund
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberRefundStatus {
+    /// Not yet refunded (queued, or the last attempt failed)
+    Pending,
+    /// Refunded by the payment provider
+    Refunded,
+}
+
+impl MemberRefundStatus {
+    /// Get status as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemberRefundStatus::Pending => "PENDING",
+            MemberRefundStatus::Refunded => "REFUNDED",
+        }
+    }
+}
+
+/// Refund of one member's contribution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberRefund {
+    /// Refund ID, stable across attempts
+    pub id: String,
+    /// Member being refunded
+    pub user_id: String,
+    /// Provider payment the contribution was charged on
+    pub payment_ref: Option<String>,
+    /// Amount to refund
+    pub amount: MinorUnits,
+    /// Currency of the contribution
+    pub currency: CurrencyCode,
+    /// Current status
+    pub status: MemberRefundStatus,
+    /// Provider refund reference once refunded
+    pub provider_ref: Option<String>,
+    /// Attempts made so far
+    pub attempts: u32,
+    /// Error from the last failed attempt
+    pub last_error: Option<String>,
+    /// Last update timestamp
+    pub updated_at: i64,
+}
+
+impl MemberRefund {
+    /// Check if the refund still has to be sent
+    pub fn is_pending(&self) -> bool {
+        self.status == MemberRefundStatus::Pending
+    }
+}
+
+impl Pool {
+    /// Close the pool as `outcome`, refunding contributions first
+    ///
+    /// `outcome` must be Failed, Expired or Cancelled. Without contributions
+    /// the pool moves straight to `outcome`; otherwise it moves to Refunding
+    /// and reaches `outcome` when the last refund completes.
+    pub fn close_with_refunds(
+        &mut self,
+        outcome: PoolStatus,
+        reason: &str,
+        actor: &str,
+    ) -> PoolResult<()> {
+        if !matches!(
+            outcome,
+            PoolStatus::Failed | PoolStatus::Expired | PoolStatus::Cancelled
+        ) || !self.status.can_transition_to(outcome)
+        {
+            return Err(PoolError::InvalidStateTransition {
+                from: self.status.as_str().to_string(),
+                to: outcome.as_str().to_string(),
+            });
+        }
+
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let refunds: Vec<MemberRefund> = self
+            .members
+            .iter()
+            .filter_map(|m| m.contribution.map(|amount| (m, amount)))
+            .enumerate()
+            .map(|(i, (member, amount))| MemberRefund {
+                id: format!("RF-{}-{}", self.id, i + 1),
+                user_id: member.user_id.clone(),
+                payment_ref: member.payment_ref.clone(),
+                amount,
+                currency: self.pricing.currency,
+                status: MemberRefundStatus::Pending,
+                provider_ref: None,
+                attempts: 0,
+                last_error: None,
+                updated_at: now,
+            })
+            .collect();
+        if refunds.is_empty() {
+            return self.transition(outcome, reason, actor);
+        }
+
+        self.refunds = refunds;
+        self.settlement_outcome = Some(outcome);
+        self.transition(PoolStatus::Refunding, reason, actor)
+    }
+
+    /// Refunds still to be sent to the payment provider
+    pub fn pending_refunds(&self) -> impl Iterator<Item = &MemberRefund> {
+        self.refunds.iter().filter(|r| r.is_pending())
+    }
+
+    /// Check if every contribution has been refunded
+    pub fn is_settled(&self) -> bool {
+        self.refunds.iter().all(|r| !r.is_pending())
+    }
+
+    /// Record a successful refund, closing the pool after the last one
+    ///
+    /// Recording a refund that is already complete is a no-op.
+    pub fn complete_refund(
+        &mut self,
+        refund_id: &str,
+        provider_ref: Option<String>,
+    ) -> PoolResult<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let refund = self.refund_mut(refund_id)?;
+        if !refund.is_pending() {
+            return Ok(());
+        }
+        refund.status = MemberRefundStatus::Refunded;
+        refund.provider_ref = provider_ref;
+        refund.attempts += 1;
+        refund.last_error = None;
+        refund.updated_at = now;
+        self.updated_at = now;
+        self.version += 1;
+
+        if self.status == PoolStatus::Refunding && self.is_settled() {
+            let outcome = self.settlement_outcome.unwrap_or(PoolStatus::Failed);
+            self.transition(outcome, "All contributions refunded", "SYSTEM")?;
+        }
+        Ok(())
+    }
+
+    /// Record a failed refund attempt; the refund stays pending
+    pub fn fail_refund(&mut self, refund_id: &str, error: &str) -> PoolResult<()> {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let refund = self.refund_mut(refund_id)?;
+        if !refund.is_pending() {
+            return Ok(());
+        }
+        refund.attempts += 1;
+        refund.last_error = Some(error.to_string());
+        refund.updated_at = now;
+        self.updated_at = now;
+        self.version += 1;
+        Ok(())
+    }
+
+    /// Find a refund by ID
+    pub fn refund(&self, refund_id: &str) -> Option<&MemberRefund> {
+        self.refunds.iter().find(|r| r.id == refund_id)
+    }
+
+    fn refund_mut(&mut self, refund_id: &str) -> PoolResult<&mut MemberRefund> {
+        self.refunds
+            .iter_mut()
+            .find(|r| r.id == refund_id)
+            .ok_or_else(|| PoolError::RefundNotFound(refund_id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::PoolRoute;
+    use crate::pricing::TieredPricing;
+    use vaya_common::IataCode;
+
+    fn locked_pool() -> Pool {
+        let route = PoolRoute::one_way(
+            IataCode::SIN,
+            IataCode::BKK,
+            time::Date::from_calendar_date(2025, time::Month::June, 15).unwrap(),
+        );
+        let pricing =
+            TieredPricing::with_standard_tiers(MinorUnits::new(10000), CurrencyCode::SGD).unwrap();
+        let mut pool = Pool::new("Test Pool", route, pricing, "organizer", 1).unwrap();
+        pool.min_members = 2;
+        pool.join("user-2", 1).unwrap();
+        pool.contribute("organizer", MinorUnits::new(10000))
+            .unwrap();
+        pool.set_payment_ref("organizer", "pi_org").unwrap();
+        pool.contribute("user-2", MinorUnits::new(10000)).unwrap();
+        assert_eq!(pool.status, PoolStatus::Locked);
+        pool
+    }
+
+    #[test]
+    fn test_failed_pool_closes_after_all_refunds() {
+        let mut pool = locked_pool();
+        pool.fail("Airline rejected the group booking", "SYSTEM")
+            .unwrap();
+        assert_eq!(pool.status, PoolStatus::Refunding);
+        assert!(!pool.status.is_terminal());
+        assert_eq!(pool.pending_refunds().count(), 2);
+
+        let ids: Vec<String> = pool.pending_refunds().map(|r| r.id.clone()).collect();
+        assert_eq!(
+            pool.refund(&ids[0]).unwrap().payment_ref.as_deref(),
+            Some("pi_org")
+        );
+
+        pool.complete_refund(&ids[0], Some("re_1".into())).unwrap();
+        pool.fail_refund(&ids[1], "Card expired").unwrap();
+        assert_eq!(pool.status, PoolStatus::Refunding);
+        assert_eq!(pool.refund(&ids[1]).unwrap().attempts, 1);
+
+        // Replays of a completed refund change nothing
+        let version = pool.version;
+        pool.complete_refund(&ids[0], Some("re_dup".into()))
+            .unwrap();
+        assert_eq!(pool.version, version);
+
+        pool.complete_refund(&ids[1], Some("re_2".into())).unwrap();
+        assert_eq!(pool.status, PoolStatus::Failed);
+        assert!(pool.is_settled());
+        assert!(pool.complete_refund("RF-unknown", None).is_err());
+    }
+
+    #[test]
+    fn test_close_without_contributions() {
+        let mut pool = locked_pool();
+        for member in &mut pool.members {
+            member.contribution = None;
+        }
+        pool.status = PoolStatus::Active;
+        pool.close_with_refunds(
+            PoolStatus::Expired,
+            "Contribution deadline passed",
+            "SYSTEM",
+        )
+        .unwrap();
+        assert_eq!(pool.status, PoolStatus::Expired);
+        assert!(pool.refunds.is_empty());
+        assert!(pool
+            .close_with_refunds(PoolStatus::Completed, "", "SYSTEM")
+            .is_err());
+    }
+}
@@ -0,0 +1,507 @@
+//! Pool persistence with optimistic locking
+//!
+//! Every write is a compare-and-swap on the pool version, so two requests
+//! racing on the same pool can never both apply their change to the same
+//! snapshot. [`PoolService`] reloads and retries on a version conflict.
+//!
+//! Join and contribute are idempotent: the client sends an operation ID
+//! with each attempt, the ID is stored on the pool in the same write, and
+//! a retried or double-tapped request returns the original outcome instead
+//! of joining or charging twice.
+//!
+//! Committed joins and status changes are published on the event bus
+//! ([`topics::POOL_MEMBER_JOINED`], [`topics::POOL_STATUS_CHANGED`]), as
+//! are waitlisted users promoted into a pool when a member leaves
+//! ([`topics::POOL_WAITLIST_PROMOTED`]), so notifications can tell them.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use vaya_common::bus::{self, topics};
+use vaya_common::events::{PoolMemberJoined, PoolStatusChanged, PoolWaitlistPromoted};
+use vaya_common::MinorUnits;
+
+use crate::pool::{AppliedOperation, Pool, PoolOperation};
+use crate::{PoolError, PoolResult};
+
+/// Longest accepted operation ID
+pub const MAX_OPERATION_ID_LEN: usize = 128;
+
+/// Pool storage with compare-and-swap writes
+pub trait PoolStore: Send + Sync {
+    /// Load a pool
+    fn load(&self, pool_id: &str) -> PoolResult<Pool>;
+
+    /// Store a new pool
+    fn insert(&self, pool: &Pool) -> PoolResult<()>;
+
+    /// Pools not yet closed, oldest first, for background sweeps
+    fn open_pools(&self, limit: usize) -> PoolResult<Vec<Pool>>;
+
+    /// Replace a pool only if the stored version is still `expected_version`
+    ///
+    /// Fails with [`PoolError::VersionConflict`] otherwise.
+    fn compare_and_swap(&self, pool: &Pool, expected_version: u32) -> PoolResult<()>;
+}
+
+/// In-memory pool store
+#[derive(Debug, Default)]
+pub struct MemoryPoolStore {
+    pools: RwLock<HashMap<String, Pool>>,
+}
+
+impl MemoryPoolStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PoolStore for MemoryPoolStore {
+    fn load(&self, pool_id: &str) -> PoolResult<Pool> {
+        self.pools
+            .read()
+            .map_err(|_| PoolError::LockFailed)?
+            .get(pool_id)
+            .cloned()
+            .ok_or_else(|| PoolError::PoolNotFound(pool_id.to_string()))
+    }
+
+    fn insert(&self, pool: &Pool) -> PoolResult<()> {
+        let mut pools = self.pools.write().map_err(|_| PoolError::LockFailed)?;
+        if pools.contains_key(&pool.id) {
+            return Err(PoolError::PoolExists(pool.id.clone()));
+        }
+        pools.insert(pool.id.clone(), pool.clone());
+        Ok(())
+    }
+
+    fn open_pools(&self, limit: usize) -> PoolResult<Vec<Pool>> {
+        let pools = self.pools.read().map_err(|_| PoolError::LockFailed)?;
+        let mut open: Vec<Pool> = pools
+            .values()
+            .filter(|p| !p.status.is_terminal())
+            .cloned()
+            .collect();
+        open.sort_by_key(|p| p.created_at);
+        open.truncate(limit);
+        Ok(open)
+    }
+
+    fn compare_and_swap(&self, pool: &Pool, expected_version: u32) -> PoolResult<()> {
+        let mut pools = self.pools.write().map_err(|_| PoolError::LockFailed)?;
+        let stored = pools
+            .get_mut(&pool.id)
+            .ok_or_else(|| PoolError::PoolNotFound(pool.id.clone()))?;
+        if stored.version != expected_version {
+            return Err(PoolError::VersionConflict {
+                expected: expected_version,
+                actual: stored.version,
+            });
+        }
+        *stored = pool.clone();
+        Ok(())
+    }
+}
+
+/// Result of an idempotent pool operation
+#[derive(Debug, Clone)]
+pub struct OperationOutcome {
+    /// Pool after the operation
+    pub pool: Pool,
+    /// True if the operation had already been applied and was not repeated
+    pub replayed: bool,
+}
+
+/// Applies pool operations through a [`PoolStore`] with retry
+pub struct PoolService<S: PoolStore> {
+    store: S,
+    max_retries: u32,
+}
+
+impl<S: PoolStore> PoolService<S> {
+    /// Create a service over a store
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            max_retries: 5,
+        }
+    }
+
+    /// Set how many times a version conflict is retried
+    pub fn with_max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Underlying store
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Store a new pool
+    pub fn create(&self, pool: &Pool) -> PoolResult<()> {
+        self.store.insert(pool)
+    }
+
+    /// Join a pool, at most once per operation ID
+    pub fn join(
+        &self,
+        pool_id: &str,
+        user_id: &str,
+        spots: u32,
+        operation_id: &str,
+    ) -> PoolResult<OperationOutcome> {
+        let outcome = self.apply(
+            pool_id,
+            user_id,
+            operation_id,
+            PoolOperation::Join { spots },
+            |pool| pool.join(user_id, spots),
+        )?;
+        if !outcome.replayed {
+            bus::publish(
+                &topics::POOL_MEMBER_JOINED,
+                PoolMemberJoined {
+                    pool_id: pool_id.to_string(),
+                    user_id: user_id.to_string(),
+                    spots,
+                    member_count: outcome.pool.member_count(),
+                },
+            );
+        }
+        Ok(outcome)
+    }
+
+    /// Contribute to a pool, at most once per operation ID
+    pub fn contribute(
+        &self,
+        pool_id: &str,
+        user_id: &str,
+        amount: MinorUnits,
+        operation_id: &str,
+    ) -> PoolResult<OperationOutcome> {
+        self.apply(
+            pool_id,
+            user_id,
+            operation_id,
+            PoolOperation::Contribute { amount },
+            |pool| pool.contribute(user_id, amount),
+        )
+    }
+
+    /// Join a full pool's waitlist, at most once per operation ID
+    pub fn join_waitlist(
+        &self,
+        pool_id: &str,
+        user_id: &str,
+        spots: u32,
+        operation_id: &str,
+    ) -> PoolResult<OperationOutcome> {
+        self.apply(
+            pool_id,
+            user_id,
+            operation_id,
+            PoolOperation::JoinWaitlist { spots },
+            |pool| pool.join_waitlist(user_id, spots).map(|_| ()),
+        )
+    }
+
+    /// Leave a pool's waitlist
+    pub fn leave_waitlist(&self, pool_id: &str, user_id: &str) -> PoolResult<Pool> {
+        self.update(pool_id, |pool| pool.leave_waitlist(user_id))
+    }
+
+    /// Leave a pool, promoting waitlisted users into the freed spots
+    pub fn leave(&self, pool_id: &str, user_id: &str) -> PoolResult<Pool> {
+        let mut promoted = Vec::new();
+        let pool = self.update(pool_id, |pool| {
+            promoted = pool.leave(user_id)?;
+            Ok(())
+        })?;
+        for entry in promoted {
+            bus::publish(
+                &topics::POOL_WAITLIST_PROMOTED,
+                PoolWaitlistPromoted {
+                    pool_id: pool_id.to_string(),
+                    user_id: entry.user_id,
+                    spots: entry.spots,
+                    waitlist_remaining: pool.waitlist.len() as u32,
+                },
+            );
+        }
+        Ok(pool)
+    }
+
+    /// Apply a change to a pool, retrying on version conflicts
+    ///
+    /// For changes that are not client operations (status transitions,
+    /// expiry); the closure may run more than once.
+    pub fn update(
+        &self,
+        pool_id: &str,
+        mut change: impl FnMut(&mut Pool) -> PoolResult<()>,
+    ) -> PoolResult<Pool> {
+        let mut attempt = 0;
+        loop {
+            let mut pool = self.store.load(pool_id)?;
+            let expected = pool.version;
+            let status = pool.status;
+            change(&mut pool)?;
+            if pool.version == expected {
+                // Nothing changed; nothing to write
+                return Ok(pool);
+            }
+            match self.store.compare_and_swap(&pool, expected) {
+                Ok(()) => {
+                    if pool.status != status {
+                        bus::publish(
+                            &topics::POOL_STATUS_CHANGED,
+                            PoolStatusChanged {
+                                pool_id: pool.id.clone(),
+                                from: status.as_str().to_string(),
+                                to: pool.status.as_str().to_string(),
+                                member_count: pool.member_count(),
+                            },
+                        );
+                    }
+                    return Ok(pool);
+                }
+                Err(PoolError::VersionConflict { .. }) if attempt < self.max_retries => {
+                    attempt += 1;
+                    tracing::debug!(pool_id, attempt, "Pool version conflict, retrying");
+                }
+                Err(PoolError::VersionConflict { .. }) => {
+                    return Err(PoolError::ConcurrentModification)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn apply(
+        &self,
+        pool_id: &str,
+        user_id: &str,
+        operation_id: &str,
+        operation: PoolOperation,
+        mut change: impl FnMut(&mut Pool) -> PoolResult<()>,
+    ) -> PoolResult<OperationOutcome> {
+        validate_operation_id(operation_id)?;
+        let mut replayed = false;
+        let pool = self.update(pool_id, |pool| {
+            if let Some(applied) = pool
+                .applied_operations
+                .iter()
+                .find(|op| op.operation_id == operation_id)
+            {
+                if applied.user_id != user_id || applied.operation != operation {
+                    return Err(PoolError::OperationConflict(operation_id.to_string()));
+                }
+                replayed = true;
+                return Ok(());
+            }
+            replayed = false;
+            change(pool)?;
+            pool.applied_operations.push(AppliedOperation {
+                operation_id: operation_id.to_string(),
+                user_id: user_id.to_string(),
+                operation,
+                version: pool.version,
+            });
+            Ok(())
+        })?;
+        Ok(OperationOutcome { pool, replayed })
+    }
+}
+
+/// Check a client-supplied operation ID
+pub fn validate_operation_id(operation_id: &str) -> PoolResult<()> {
+    if operation_id.is_empty() {
+        return Err(PoolError::MissingField("operation_id".into()));
+    }
+    if operation_id.len() > MAX_OPERATION_ID_LEN
+        || !operation_id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        return Err(PoolError::InvalidConfig(
+            "operation_id must be up to 128 letters, digits, '-' or '_'".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pool::{PoolRoute, PoolStatus};
+    use crate::pricing::TieredPricing;
+    use std::sync::Arc;
+    use vaya_common::{CurrencyCode, IataCode};
+
+    fn service_with_pool() -> (PoolService<MemoryPoolStore>, String) {
+        let route = PoolRoute::one_way(
+            IataCode::SIN,
+            IataCode::BKK,
+            time::Date::from_calendar_date(2025, time::Month::June, 15).unwrap(),
+        );
+        let pricing =
+            TieredPricing::with_standard_tiers(MinorUnits::new(10000), CurrencyCode::SGD).unwrap();
+        let mut pool = Pool::new("Test Pool", route, pricing, "organizer", 1).unwrap();
+        pool.min_members = 2;
+        let service = PoolService::new(MemoryPoolStore::new());
+        service.create(&pool).unwrap();
+        (service, pool.id)
+    }
+
+    #[test]
+    fn test_join_is_idempotent() {
+        let (service, id) = service_with_pool();
+
+        let first = service.join(&id, "user-2", 1, "op-1").unwrap();
+        assert!(!first.replayed);
+        let again = service.join(&id, "user-2", 1, "op-1").unwrap();
+        assert!(again.replayed);
+        assert_eq!(again.pool.member_count(), 2);
+        assert_eq!(again.pool.version, first.pool.version);
+
+        // Same ID, different operation
+        let err = service.join(&id, "user-2", 2, "op-1").unwrap_err();
+        assert!(matches!(err, PoolError::OperationConflict(_)));
+        // New ID, same user: typed conflict rather than a second membership
+        let err = service.join(&id, "user-2", 1, "op-2").unwrap_err();
+        assert!(err.is_conflict());
+        assert!(service.join(&id, "user-3", 1, "").is_err());
+    }
+
+    #[test]
+    fn test_contribute_charges_once() {
+        let (service, id) = service_with_pool();
+        service.join(&id, "user-2", 1, "join-2").unwrap();
+        assert_eq!(
+            service.store().load(&id).unwrap().status,
+            PoolStatus::Active
+        );
+
+        let amount = MinorUnits::new(10000);
+        service.contribute(&id, "user-2", amount, "pay-2").unwrap();
+        let retry = service.contribute(&id, "user-2", amount, "pay-2").unwrap();
+        assert!(retry.replayed);
+        assert_eq!(retry.pool.total_contributions().as_i64(), 10000);
+        assert!(matches!(
+            service.contribute(&id, "user-2", amount, "pay-2b"),
+            Err(PoolError::ContributionAlreadyProcessed)
+        ));
+    }
+
+    #[test]
+    fn test_publishes_joins_and_status_changes() {
+        let joins = bus::global().subscribe_buffered(
+            &topics::POOL_MEMBER_JOINED,
+            "test",
+            bus::BufferConfig::default(),
+        );
+        let changes = bus::global().subscribe_buffered(
+            &topics::POOL_STATUS_CHANGED,
+            "test",
+            bus::BufferConfig::default(),
+        );
+        let (service, id) = service_with_pool();
+        service.join(&id, "user-2", 1, "op-1").unwrap();
+        service.join(&id, "user-2", 1, "op-1").unwrap();
+
+        // Other tests publish on the global bus too
+        let joined: Vec<_> = std::iter::from_fn(|| joins.try_recv())
+            .filter(|e| e.pool_id == id)
+            .collect();
+        assert_eq!(joined.len(), 1);
+        assert_eq!(
+            (joined[0].user_id.as_str(), joined[0].member_count),
+            ("user-2", 2)
+        );
+
+        let change = std::iter::from_fn(|| changes.try_recv())
+            .find(|e| e.pool_id == id)
+            .unwrap();
+        assert_eq!(
+            (change.from.as_str(), change.to.as_str()),
+            ("forming", "active")
+        );
+    }
+
+    #[test]
+    fn test_leave_promotes_from_waitlist() {
+        let promotions = bus::global().subscribe_buffered(
+            &topics::POOL_WAITLIST_PROMOTED,
+            "test",
+            bus::BufferConfig::default(),
+        );
+        let (service, id) = service_with_pool();
+        service
+            .update(&id, |pool| {
+                pool.max_members = 2;
+                pool.version += 1;
+                Ok(())
+            })
+            .unwrap();
+        service.join(&id, "user-2", 1, "join-2").unwrap();
+        let queued = service.join_waitlist(&id, "user-3", 1, "wait-3").unwrap();
+        assert_eq!(queued.pool.waitlist_position("user-3"), Some(1));
+        assert!(
+            service
+                .join_waitlist(&id, "user-3", 1, "wait-3")
+                .unwrap()
+                .replayed
+        );
+
+        let pool = service.leave(&id, "user-2").unwrap();
+        assert!(pool.get_member("user-3").is_some());
+        assert!(pool.waitlist.is_empty());
+        let promoted = std::iter::from_fn(|| promotions.try_recv())
+            .find(|e| e.pool_id == id)
+            .unwrap();
+        assert_eq!(promoted.user_id, "user-3");
+        assert!(matches!(
+            service.leave_waitlist(&id, "user-3"),
+            Err(PoolError::NotWaitlisted)
+        ));
+    }
+
+    #[test]
+    fn test_stale_write_is_rejected() {
+        let (service, id) = service_with_pool();
+        let stale = service.store().load(&id).unwrap();
+        service.join(&id, "user-2", 1, "op-1").unwrap();
+
+        let mut copy = stale.clone();
+        copy.join("user-3", 1).unwrap();
+        assert!(matches!(
+            service.store().compare_and_swap(&copy, stale.version),
+            Err(PoolError::VersionConflict { .. })
+        ));
+    }
+
+    #[test]
+    fn test_concurrent_double_tap() {
+        let (service, id) = service_with_pool();
+        let service = Arc::new(service);
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let service = service.clone();
+                let id = id.clone();
+                std::thread::spawn(move || service.join(&id, "user-2", 1, "tap"))
+            })
+            .collect();
+        let outcomes: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(
+            outcomes
+                .iter()
+                .filter(|o| o.as_ref().is_ok_and(|o| !o.replayed))
+                .count(),
+            1
+        );
+        assert_eq!(service.store().load(&id).unwrap().member_count(), 2);
+    }
+}
@@ -0,0 +1,556 @@
+//! Local airport autocomplete
+//!
+//! Suggests airports from an embedded dataset so typing in the origin or
+//! destination box never reaches a GDS. Queries match IATA codes, city
+//! names and airport names by prefix, with one or two typos tolerated on
+//! longer words. Results are ranked by match quality, boosted by how often
+//! each airport is searched, and airports sharing a metro code (Tokyo,
+//! London, Bangkok...) are grouped under a city entry.
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use vaya_common::refdata::{self, AirportInfo};
+
+/// An airport in the embedded dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Airport {
+    /// IATA airport code
+    pub code: &'static str,
+    /// IATA metro (city) code; equals `code` for single-airport cities
+    pub city_code: &'static str,
+    /// Airport name in English
+    pub name: &'static str,
+    /// City name in English
+    pub city: &'static str,
+    /// ISO 3166-1 alpha-2 country code
+    pub country: &'static str,
+    /// Baseline popularity (0-100), used before analytics are available
+    pub popularity: u8,
+    /// City names by locale
+    pub localized: &'static [(&'static str, &'static str)],
+}
+
+impl Airport {
+    /// Timezone and coordinates from the common reference data
+    pub fn reference(&self) -> Option<&'static AirportInfo> {
+        refdata::airport(self.code)
+    }
+
+    /// City name in the given locale, falling back to English
+    pub fn city_name(&self, locale: &str) -> &'static str {
+        let lang = locale.split(['-', '_']).next().unwrap_or(locale);
+        self.localized
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(lang))
+            .map_or(self.city, |(_, name)| name)
+    }
+}
+
+macro_rules! airport {
+    ($code:literal, $city_code:literal, $name:literal, $city:literal, $country:literal, $pop:literal $(, $lang:literal => $local:literal)* $(,)?) => {
+        Airport {
+            code: $code,
+            city_code: $city_code,
+            name: $name,
+            city: $city,
+            country: $country,
+            popularity: $pop,
+            localized: &[$(($lang, $local)),*],
+        }
+    };
+}
+
+/// Embedded airport dataset
+#[rustfmt::skip]
+pub static AIRPORTS: &[Airport] = &[
+    // Malaysia
+    airport!("KUL", "KUL", "Kuala Lumpur International", "Kuala Lumpur", "MY", 95, "zh" => "吉隆坡", "ja" => "クアラルンプール"),
+    airport!("SZB", "KUL", "Sultan Abdul Aziz Shah (Subang)", "Kuala Lumpur", "MY", 40, "zh" => "吉隆坡", "ja" => "クアラルンプール"),
+    airport!("PEN", "PEN", "Penang International", "Penang", "MY", 70, "ms" => "Pulau Pinang", "zh" => "槟城"),
+    airport!("BKI", "BKI", "Kota Kinabalu International", "Kota Kinabalu", "MY", 60, "zh" => "亚庇"),
+    airport!("KCH", "KCH", "Kuching International", "Kuching", "MY", 55, "zh" => "古晋"),
+    airport!("LGK", "LGK", "Langkawi International", "Langkawi", "MY", 55, "zh" => "兰卡威"),
+    airport!("JHB", "JHB", "Senai International", "Johor Bahru", "MY", 50, "zh" => "新山"),
+    airport!("MYY", "MYY", "Miri", "Miri", "MY", 35),
+    airport!("KBR", "KBR", "Sultan Ismail Petra", "Kota Bharu", "MY", 35),
+    airport!("TGG", "TGG", "Sultan Mahmud", "Kuala Terengganu", "MY", 30),
+    airport!("IPH", "IPH", "Sultan Azlan Shah", "Ipoh", "MY", 25, "zh" => "怡保"),
+    // Singapore
+    airport!("SIN", "SIN", "Changi", "Singapore", "SG", 95, "ms" => "Singapura", "zh" => "新加坡", "ja" => "シンガポール"),
+    // Thailand
+    airport!("BKK", "BKK", "Suvarnabhumi", "Bangkok", "TH", 90, "th" => "กรุงเทพมหานคร", "zh" => "曼谷", "ja" => "バンコク"),
+    airport!("DMK", "BKK", "Don Mueang International", "Bangkok", "TH", 70, "th" => "กรุงเทพมหานคร", "zh" => "曼谷", "ja" => "バンコク"),
+    airport!("HKT", "HKT", "Phuket International", "Phuket", "TH", 75, "th" => "ภูเก็ต", "zh" => "普吉"),
+    airport!("CNX", "CNX", "Chiang Mai International", "Chiang Mai", "TH", 60, "th" => "เชียงใหม่", "zh" => "清迈"),
+    airport!("USM", "USM", "Samui", "Koh Samui", "TH", 45, "th" => "เกาะสมุย"),
+    airport!("KBV", "KBV", "Krabi International", "Krabi", "TH", 45, "th" => "กระบี่"),
+    // Indonesia
+    airport!("CGK", "JKT", "Soekarno-Hatta International", "Jakarta", "ID", 85, "zh" => "雅加达", "ja" => "ジャカルタ"),
+    airport!("HLP", "JKT", "Halim Perdanakusuma", "Jakarta", "ID", 40, "zh" => "雅加达", "ja" => "ジャカルタ"),
+    airport!("DPS", "DPS", "Ngurah Rai International", "Bali", "ID", 85, "ms" => "Bali", "zh" => "巴厘岛", "ja" => "バリ"),
+    airport!("SUB", "SUB", "Juanda International", "Surabaya", "ID", 50, "zh" => "泗水"),
+    airport!("KNO", "MES", "Kualanamu International", "Medan", "ID", 50, "zh" => "棉兰"),
+    airport!("YIA", "JOG", "Yogyakarta International", "Yogyakarta", "ID", 40),
+    // Philippines
+    airport!("MNL", "MNL", "Ninoy Aquino International", "Manila", "PH", 80, "zh" => "马尼拉", "ja" => "マニラ"),
+    airport!("CEB", "CEB", "Mactan-Cebu International", "Cebu", "PH", 55, "zh" => "宿务"),
+    // Vietnam
+    airport!("SGN", "SGN", "Tan Son Nhat International", "Ho Chi Minh City", "VN", 80, "vi" => "Thành phố Hồ Chí Minh", "zh" => "胡志明市"),
+    airport!("HAN", "HAN", "Noi Bai International", "Hanoi", "VN", 75, "vi" => "Hà Nội", "zh" => "河内"),
+    airport!("DAD", "DAD", "Da Nang International", "Da Nang", "VN", 55, "vi" => "Đà Nẵng", "zh" => "岘港"),
+    // Cambodia, Myanmar, Brunei, Laos
+    airport!("PNH", "PNH", "Phnom Penh International", "Phnom Penh", "KH", 50, "zh" => "金边"),
+    airport!("SAI", "REP", "Siem Reap-Angkor International", "Siem Reap", "KH", 45, "zh" => "暹粒"),
+    airport!("RGN", "RGN", "Yangon International", "Yangon", "MM", 45, "zh" => "仰光"),
+    airport!("BWN", "BWN", "Brunei International", "Bandar Seri Begawan", "BN", 40),
+    airport!("VTE", "VTE", "Wattay International", "Vientiane", "LA", 35, "zh" => "万象"),
+    // East Asia
+    airport!("HKG", "HKG", "Hong Kong International", "Hong Kong", "HK", 90, "zh" => "香港", "ja" => "香港"),
+    airport!("MFM", "MFM", "Macau International", "Macau", "MO", 50, "zh" => "澳门"),
+    airport!("TPE", "TPE", "Taoyuan International", "Taipei", "TW", 75, "zh" => "台北", "ja" => "台北"),
+    airport!("TSA", "TPE", "Songshan", "Taipei", "TW", 40, "zh" => "台北", "ja" => "台北"),
+    airport!("PVG", "SHA", "Pudong International", "Shanghai", "CN", 80, "zh" => "上海", "ja" => "上海"),
+    airport!("SHA", "SHA", "Hongqiao International", "Shanghai", "CN", 60, "zh" => "上海", "ja" => "上海"),
+    airport!("PEK", "BJS", "Capital International", "Beijing", "CN", 80, "zh" => "北京", "ja" => "北京"),
+    airport!("PKX", "BJS", "Daxing International", "Beijing", "CN", 60, "zh" => "北京", "ja" => "北京"),
+    airport!("CAN", "CAN", "Baiyun International", "Guangzhou", "CN", 65, "zh" => "广州"),
+    airport!("SZX", "SZX", "Bao'an International", "Shenzhen", "CN", 60, "zh" => "深圳"),
+    airport!("NRT", "TYO", "Narita International", "Tokyo", "JP", 85, "ja" => "東京", "zh" => "东京"),
+    airport!("HND", "TYO", "Haneda", "Tokyo", "JP", 85, "ja" => "東京", "zh" => "东京"),
+    airport!("KIX", "OSA", "Kansai International", "Osaka", "JP", 75, "ja" => "大阪", "zh" => "大阪"),
+    airport!("ITM", "OSA", "Itami", "Osaka", "JP", 40, "ja" => "大阪", "zh" => "大阪"),
+    airport!("FUK", "FUK", "Fukuoka", "Fukuoka", "JP", 50, "ja" => "福岡", "zh" => "福冈"),
+    airport!("CTS", "SPK", "New Chitose", "Sapporo", "JP", 55, "ja" => "札幌", "zh" => "札幌"),
+    airport!("OKA", "OKA", "Naha", "Okinawa", "JP", 45, "ja" => "沖縄", "zh" => "冲绳"),
+    airport!("ICN", "SEL", "Incheon International", "Seoul", "KR", 85, "ko" => "서울", "zh" => "首尔", "ja" => "ソウル"),
+    airport!("GMP", "SEL", "Gimpo International", "Seoul", "KR", 50, "ko" => "서울", "zh" => "首尔", "ja" => "ソウル"),
+    airport!("PUS", "PUS", "Gimhae International", "Busan", "KR", 45, "ko" => "부산", "zh" => "釜山"),
+    airport!("CJU", "CJU", "Jeju International", "Jeju", "KR", 40, "ko" => "제주", "zh" => "济州"),
+    // South Asia
+    airport!("DEL", "DEL", "Indira Gandhi International", "Delhi", "IN", 75, "hi" => "दिल्ली"),
+    airport!("BOM", "BOM", "Chhatrapati Shivaji Maharaj International", "Mumbai", "IN", 70, "hi" => "मुंबई"),
+    airport!("MAA", "MAA", "Chennai International", "Chennai", "IN", 60, "ta" => "சென்னை"),
+    airport!("BLR", "BLR", "Kempegowda International", "Bengaluru", "IN", 55),
+    airport!("CMB", "CMB", "Bandaranaike International", "Colombo", "LK", 45),
+    airport!("DAC", "DAC", "Hazrat Shahjalal International", "Dhaka", "BD", 50),
+    airport!("KTM", "KTM", "Tribhuvan International", "Kathmandu", "NP", 40),
+    airport!("MLE", "MLE", "Velana International", "Male", "MV", 45),
+    // Middle East
+    airport!("DXB", "DXB", "Dubai International", "Dubai", "AE", 85, "ar" => "دبي", "zh" => "迪拜"),
+    airport!("DWC", "DXB", "Al Maktoum International", "Dubai", "AE", 25, "ar" => "دبي", "zh" => "迪拜"),
+    airport!("AUH", "AUH", "Zayed International", "Abu Dhabi", "AE", 55, "ar" => "أبو ظبي"),
+    airport!("DOH", "DOH", "Hamad International", "Doha", "QA", 70, "ar" => "الدوحة"),
+    airport!("JED", "JED", "King Abdulaziz International", "Jeddah", "SA", 70, "ar" => "جدة", "ms" => "Jeddah"),
+    airport!("MED", "MED", "Prince Mohammad bin Abdulaziz", "Medina", "SA", 55, "ar" => "المدينة المنورة", "ms" => "Madinah"),
+    airport!("IST", "IST", "Istanbul", "Istanbul", "TR", 65, "tr" => "İstanbul"),
+    // Oceania
+    airport!("SYD", "SYD", "Kingsford Smith", "Sydney", "AU", 80, "zh" => "悉尼", "ja" => "シドニー"),
+    airport!("MEL", "MEL", "Tullamarine", "Melbourne", "AU", 75, "zh" => "墨尔本", "ja" => "メルボルン"),
+    airport!("BNE", "BNE", "Brisbane", "Brisbane", "AU", 55, "zh" => "布里斯班"),
+    airport!("PER", "PER", "Perth", "Perth", "AU", 60, "zh" => "珀斯"),
+    airport!("AKL", "AKL", "Auckland", "Auckland", "NZ", 55, "zh" => "奥克兰"),
+    // Europe
+    airport!("LHR", "LON", "Heathrow", "London", "GB", 90, "zh" => "伦敦", "ja" => "ロンドン"),
+    airport!("LGW", "LON", "Gatwick", "London", "GB", 60, "zh" => "伦敦", "ja" => "ロンドン"),
+    airport!("STN", "LON", "Stansted", "London", "GB", 40, "zh" => "伦敦", "ja" => "ロンドン"),
+    airport!("CDG", "PAR", "Charles de Gaulle", "Paris", "FR", 85, "fr" => "Paris", "zh" => "巴黎", "ja" => "パリ"),
+    airport!("ORY", "PAR", "Orly", "Paris", "FR", 45, "fr" => "Paris", "zh" => "巴黎", "ja" => "パリ"),
+    airport!("AMS", "AMS", "Schiphol", "Amsterdam", "NL", 70, "zh" => "阿姆斯特丹"),
+    airport!("FRA", "FRA", "Frankfurt", "Frankfurt", "DE", 70, "de" => "Frankfurt am Main", "zh" => "法兰克福"),
+    airport!("MUC", "MUC", "Munich", "Munich", "DE", 55, "de" => "München", "zh" => "慕尼黑"),
+    airport!("ZRH", "ZRH", "Zurich", "Zurich", "CH", 50, "de" => "Zürich"),
+    airport!("FCO", "ROM", "Leonardo da Vinci-Fiumicino", "Rome", "IT", 60, "it" => "Roma", "zh" => "罗马"),
+    airport!("MAD", "MAD", "Adolfo Suárez Madrid-Barajas", "Madrid", "ES", 55, "zh" => "马德里"),
+    airport!("BCN", "BCN", "Josep Tarradellas Barcelona-El Prat", "Barcelona", "ES", 55, "zh" => "巴塞罗那"),
+    // Americas
+    airport!("JFK", "NYC", "John F. Kennedy International", "New York", "US", 85, "zh" => "纽约", "ja" => "ニューヨーク"),
+    airport!("EWR", "NYC", "Newark Liberty International", "New York", "US", 60, "zh" => "纽约", "ja" => "ニューヨーク"),
+    airport!("LGA", "NYC", "LaGuardia", "New York", "US", 45, "zh" => "纽约", "ja" => "ニューヨーク"),
+    airport!("LAX", "LAX", "Los Angeles International", "Los Angeles", "US", 80, "zh" => "洛杉矶", "ja" => "ロサンゼルス"),
+    airport!("SFO", "SFO", "San Francisco International", "San Francisco", "US", 70, "zh" => "旧金山", "ja" => "サンフランシスコ"),
+    airport!("YVR", "YVR", "Vancouver International", "Vancouver", "CA", 55, "zh" => "温哥华"),
+];
+
+/// Kind of suggestion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionKind {
+    /// A single airport
+    Airport,
+    /// All airports of a multi-airport city
+    City,
+}
+
+impl SuggestionKind {
+    /// Get kind code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionKind::Airport => "airport",
+            SuggestionKind::City => "city",
+        }
+    }
+}
+
+/// A ranked autocomplete suggestion
+#[derive(Debug, Clone, PartialEq)]
+pub struct AirportSuggestion {
+    /// Airport code, or metro code for a city group
+    pub code: &'static str,
+    /// Airport or city group
+    pub kind: SuggestionKind,
+    /// Display name ("Haneda" or "Tokyo (all airports)")
+    pub name: String,
+    /// City name in the requested locale
+    pub city: &'static str,
+    /// ISO country code
+    pub country: &'static str,
+    /// Airport codes in a city group (empty for single airports)
+    pub airports: Vec<&'static str>,
+    /// Ranking score (higher is better)
+    pub score: f64,
+}
+
+/// Autocomplete settings
+#[derive(Debug, Clone)]
+pub struct AutocompleteConfig {
+    /// Default number of suggestions
+    pub default_limit: usize,
+    /// Hard cap on suggestions per query
+    pub max_limit: usize,
+    /// Words at least this long tolerate one typo
+    pub fuzzy_min_len: usize,
+    /// Words at least this long tolerate two typos
+    pub fuzzy_two_len: usize,
+    /// Weight of search analytics relative to match quality
+    pub popularity_weight: f64,
+}
+
+impl Default for AutocompleteConfig {
+    fn default() -> Self {
+        Self {
+            default_limit: 8,
+            max_limit: 20,
+            fuzzy_min_len: 4,
+            fuzzy_two_len: 7,
+            popularity_weight: 4.0,
+        }
+    }
+}
+
+/// Airport autocomplete over the embedded dataset
+#[derive(Debug)]
+pub struct AirportAutocomplete {
+    airports: &'static [Airport],
+    config: AutocompleteConfig,
+    /// Searches per airport code, from search analytics
+    searches: RwLock<HashMap<&'static str, u64>>,
+}
+
+impl AirportAutocomplete {
+    /// Create an autocomplete over the embedded dataset
+    pub fn new() -> Self {
+        Self::with_airports(AIRPORTS)
+    }
+
+    /// Create an autocomplete over a specific dataset
+    pub fn with_airports(airports: &'static [Airport]) -> Self {
+        Self {
+            airports,
+            config: AutocompleteConfig::default(),
+            searches: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the config
+    pub fn with_config(mut self, config: AutocompleteConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Look up an airport by code
+    pub fn airport(&self, code: &str) -> Option<&'static Airport> {
+        self.airports
+            .iter()
+            .find(|a| a.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Other airports serving the same metro area, e.g. SZB for KUL
+    ///
+    /// A metro code such as TYO returns all of its airports.
+    pub fn metro_airports(&self, code: &str) -> Vec<&'static Airport> {
+        let city = self.airport(code).map_or(code, |a| a.city_code);
+        self.airports
+            .iter()
+            .filter(|a| {
+                a.city_code.eq_ignore_ascii_case(city) && !a.code.eq_ignore_ascii_case(code)
+            })
+            .collect()
+    }
+
+    /// Record that a search used this airport
+    pub fn record_search(&self, code: &str) {
+        if let Some(airport) = self.airport(code) {
+            let mut searches = self.searches.write().unwrap_or_else(|e| e.into_inner());
+            *searches.entry(airport.code).or_insert(0) += 1;
+        }
+    }
+
+    /// Replace search counts with a snapshot from analytics
+    pub fn load_popularity<'a>(&self, counts: impl IntoIterator<Item = (&'a str, u64)>) {
+        let mut searches = self.searches.write().unwrap_or_else(|e| e.into_inner());
+        searches.clear();
+        for (code, count) in counts {
+            if let Some(airport) = self.airport(code) {
+                searches.insert(airport.code, count);
+            }
+        }
+    }
+
+    /// Suggest airports for a partial query
+    ///
+    /// `locale` selects localized city names (e.g. "ms", "zh-CN"); `limit`
+    /// falls back to the configured default and is capped at `max_limit`.
+    pub fn suggest(
+        &self,
+        query: &str,
+        locale: &str,
+        limit: Option<usize>,
+    ) -> Vec<AirportSuggestion> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let limit = limit
+            .unwrap_or(self.config.default_limit)
+            .clamp(1, self.config.max_limit);
+
+        let searches = self.searches.read().unwrap_or_else(|e| e.into_inner());
+        let max_searches = searches.values().copied().max().unwrap_or(0);
+
+        let mut matched: Vec<(&'static Airport, f64)> = self
+            .airports
+            .iter()
+            .filter_map(|airport| {
+                let quality = self.match_quality(airport, &query, locale)?;
+                // Analytics dominate once available; the baseline keeps
+                // ranking sensible on a fresh deployment
+                let popularity = if max_searches > 0 {
+                    let count = searches.get(airport.code).copied().unwrap_or(0);
+                    (count as f64 + 1.0).ln() / (max_searches as f64 + 1.0).ln()
+                } else {
+                    f64::from(airport.popularity) / 100.0
+                };
+                Some((
+                    airport,
+                    quality + popularity * self.config.popularity_weight,
+                ))
+            })
+            .collect();
+        matched.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.code.cmp(b.0.code)));
+
+        let mut suggestions = Vec::new();
+        let mut grouped: Vec<&'static str> = Vec::new();
+        for &(airport, score) in &matched {
+            if suggestions.len() >= limit {
+                break;
+            }
+            let members: Vec<&'static str> = self
+                .airports
+                .iter()
+                .filter(|a| a.city_code == airport.city_code)
+                .map(|a| a.code)
+                .collect();
+            if members.len() > 1 && !grouped.contains(&airport.city_code) {
+                grouped.push(airport.city_code);
+                let city = airport.city_name(locale);
+                suggestions.push(AirportSuggestion {
+                    code: airport.city_code,
+                    kind: SuggestionKind::City,
+                    name: format!("{} (all airports)", city),
+                    city,
+                    country: airport.country,
+                    airports: members,
+                    score,
+                });
+                if suggestions.len() >= limit {
+                    break;
+                }
+            }
+            suggestions.push(AirportSuggestion {
+                code: airport.code,
+                kind: SuggestionKind::Airport,
+                name: airport.name.to_string(),
+                city: airport.city_name(locale),
+                country: airport.country,
+                airports: Vec::new(),
+                score,
+            });
+        }
+        suggestions
+    }
+
+    /// Score how well an airport matches the query, if at all
+    fn match_quality(&self, airport: &Airport, query: &str, locale: &str) -> Option<f64> {
+        let code = airport.code.to_ascii_lowercase();
+        if query == code {
+            return Some(100.0);
+        }
+        if query == airport.city_code.to_ascii_lowercase() {
+            return Some(95.0);
+        }
+
+        let city = normalize(airport.city);
+        let local = normalize(airport.city_name(locale));
+        let name = normalize(airport.name);
+        if city.starts_with(query) || local.starts_with(query) {
+            return Some(80.0);
+        }
+        if query.len() <= 3 && code.starts_with(query) {
+            return Some(75.0);
+        }
+        let words = || {
+            city.split(' ')
+                .chain(local.split(' '))
+                .chain(name.split(' '))
+        };
+        if name.starts_with(query) || words().any(|w| w.starts_with(query)) {
+            return Some(60.0);
+        }
+
+        // Typo tolerance: compare the query against word prefixes of the
+        // same length so partially typed words still match
+        let len = query.chars().count();
+        let allowed = if len >= self.config.fuzzy_two_len {
+            2
+        } else if len >= self.config.fuzzy_min_len {
+            1
+        } else {
+            return None;
+        };
+        let best = words()
+            .filter_map(|w| {
+                let prefix: String = w.chars().take(len).collect();
+                let distance = edit_distance(query, &prefix);
+                (distance <= allowed).then_some(distance)
+            })
+            .min()?;
+        Some(40.0 - 10.0 * best as f64)
+    }
+}
+
+impl Default for AirportAutocomplete {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The process-wide autocomplete over the embedded dataset
+pub fn global() -> &'static AirportAutocomplete {
+    static GLOBAL: OnceLock<AirportAutocomplete> = OnceLock::new();
+    GLOBAL.get_or_init(AirportAutocomplete::new)
+}
+
+/// Lowercase, fold common Latin accents, and collapse punctuation to spaces
+fn normalize(s: &str) -> String {
+    let folded: String = s
+        .trim()
+        .chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' | 'ı' => 'i',
+            'ò' | 'ó' | 'ô' | 'ö' | 'õ' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'đ' => 'd',
+            'ç' => 'c',
+            'ñ' => 'n',
+            '-' | '.' | '\'' | '(' | ')' | ',' => ' ',
+            c => c,
+        })
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        cur[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_matches_reference_data() {
+        for airport in AIRPORTS {
+            let info = airport.reference().unwrap();
+            assert_eq!(info.city_code, airport.city_code, "{}", airport.code);
+            assert_eq!(info.country, airport.country, "{}", airport.code);
+        }
+    }
+
+    #[test]
+    fn test_code_and_prefix_matches() {
+        let ac = AirportAutocomplete::new();
+        let results = ac.suggest("kul", "en", None);
+        assert_eq!(results[0].code, "KUL");
+        assert_eq!(results[0].kind, SuggestionKind::City);
+        assert_eq!(results[0].airports, vec!["KUL", "SZB"]);
+        assert_eq!(results[1].code, "KUL");
+        assert_eq!(results[1].kind, SuggestionKind::Airport);
+
+        let results = ac.suggest("Pen", "en", Some(3));
+        assert_eq!(results[0].code, "PEN");
+        assert!(results.len() <= 3);
+        assert!(ac.suggest("  ", "en", None).is_empty());
+    }
+
+    #[test]
+    fn test_city_grouping_and_fuzzy() {
+        let ac = AirportAutocomplete::new();
+        let results = ac.suggest("tokio", "en", None);
+        assert_eq!(results[0].code, "TYO");
+        assert_eq!(results[0].name, "Tokyo (all airports)");
+        let codes: Vec<_> = results.iter().map(|s| s.code).collect();
+        assert!(codes.contains(&"NRT") && codes.contains(&"HND"));
+
+        let metro: Vec<_> = ac.metro_airports("kul").iter().map(|a| a.code).collect();
+        assert_eq!(metro, vec!["SZB"]);
+        let metro: Vec<_> = ac.metro_airports("TYO").iter().map(|a| a.code).collect();
+        assert!(metro.contains(&"NRT") && metro.contains(&"HND"));
+        assert!(ac.metro_airports("PEN").is_empty());
+
+        assert_eq!(ac.suggest("Bangkock", "en", None)[0].code, "BKK");
+        assert!(ac.suggest("xq", "en", None).is_empty());
+    }
+
+    #[test]
+    fn test_popularity_and_locale() {
+        let ac = AirportAutocomplete::new();
+        // Baseline: Narita and Haneda tie, broken by code
+        let results = ac.suggest("tokyo", "en", None);
+        assert_eq!(results[1].code, "HND");
+
+        ac.load_popularity([("NRT", 500), ("HND", 20)]);
+        let results = ac.suggest("tokyo", "en", None);
+        assert_eq!(results[1].code, "NRT");
+
+        let results = ac.suggest("pulau", "ms", None);
+        assert_eq!(results[0].code, "PEN");
+        assert_eq!(results[0].city, "Pulau Pinang");
+        assert_eq!(ac.suggest("東京", "ja-JP", None)[0].city, "東京");
+        assert_eq!(ac.suggest("singapore", "zh", None)[0].city, "新加坡");
+    }
+}
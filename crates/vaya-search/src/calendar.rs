@@ -0,0 +1,331 @@
+//! Fare calendar: cheapest fare per day over a month
+//!
+//! The UI renders a month grid of prices so travellers can spot cheap days
+//! before running a full search. Each day is priced from recorded
+//! observations when an [`ObservedFares`] source has one, otherwise from a
+//! one-way search through the engine's providers. Prices are per passenger
+//! and scored against the rest of the month with the oracle's deal score.
+
+use time::{Date, Month};
+use vaya_common::{CurrencyCode, IataCode, MinorUnits};
+use vaya_oracle::PriceInsight;
+
+use crate::error::{SearchError, SearchResult};
+use crate::request::SearchRequest;
+use crate::types::{CabinClass, FlightOffer, PassengerType, Passengers};
+
+/// Source of previously observed fares, e.g. the recorded price history
+pub trait ObservedFares: Send + Sync {
+    /// Cheapest recent one-way fare per passenger for a departure date
+    fn cheapest(
+        &self,
+        origin: &IataCode,
+        destination: &IataCode,
+        date: Date,
+        cabin: CabinClass,
+    ) -> Option<(MinorUnits, CurrencyCode)>;
+}
+
+/// Fare calendar request for one route and month
+#[derive(Debug, Clone)]
+pub struct CalendarRequest {
+    /// Origin airport
+    pub origin: IataCode,
+    /// Destination airport
+    pub destination: IataCode,
+    /// Calendar year
+    pub year: i32,
+    /// Calendar month
+    pub month: Month,
+    /// Cabin class
+    pub cabin: CabinClass,
+    /// Passengers
+    pub passengers: Passengers,
+}
+
+impl CalendarRequest {
+    /// Create an economy calendar request for one adult
+    pub fn new(origin: IataCode, destination: IataCode, year: i32, month: Month) -> Self {
+        Self {
+            origin,
+            destination,
+            year,
+            month,
+            cabin: CabinClass::Economy,
+            passengers: Passengers::default(),
+        }
+    }
+
+    /// Create a request from a `YYYY-MM` month
+    pub fn for_month(origin: IataCode, destination: IataCode, month: &str) -> SearchResult<Self> {
+        let (year, month) = parse_month(month)?;
+        Ok(Self::new(origin, destination, year, month))
+    }
+
+    /// Set cabin class
+    pub fn with_cabin(mut self, cabin: CabinClass) -> Self {
+        self.cabin = cabin;
+        self
+    }
+
+    /// Set passengers
+    pub fn with_passengers(mut self, passengers: Passengers) -> Self {
+        self.passengers = passengers;
+        self
+    }
+
+    /// Validate the calendar request
+    pub fn validate(&self) -> SearchResult<()> {
+        if !self.origin.is_valid() || !self.destination.is_valid() {
+            return Err(SearchError::InvalidRoute(
+                "Origin and destination must be IATA codes".into(),
+            ));
+        }
+        if self.origin == self.destination {
+            return Err(SearchError::InvalidRoute(
+                "Origin and destination cannot be the same".into(),
+            ));
+        }
+        if !self.passengers.validate() {
+            return Err(SearchError::InvalidParams("Invalid passenger count".into()));
+        }
+        if Date::from_calendar_date(self.year, self.month, 1).is_err() {
+            return Err(SearchError::InvalidDateRange);
+        }
+        Ok(())
+    }
+
+    /// Every date in the month
+    pub fn days(&self) -> Vec<Date> {
+        let len = self.month.length(self.year);
+        (1..=len)
+            .filter_map(|day| Date::from_calendar_date(self.year, self.month, day).ok())
+            .collect()
+    }
+
+    /// One-way search for a single day of the calendar
+    pub fn search_request(&self, date: Date) -> SearchRequest {
+        SearchRequest::one_way(self.origin, self.destination, date)
+            .with_cabin(self.cabin)
+            .with_passengers(self.passengers)
+    }
+
+    /// Generate cache key for this request
+    pub fn cache_key(&self) -> String {
+        format!(
+            "calendar:{}:{}:{}-{:02}:{}:{}:{}",
+            self.origin.as_str(),
+            self.destination.as_str(),
+            self.year,
+            self.month as u8,
+            self.cabin.code(),
+            self.passengers.adults,
+            self.passengers.children + self.passengers.infants
+        )
+    }
+}
+
+/// Parse a `YYYY-MM` month
+pub fn parse_month(value: &str) -> SearchResult<(i32, Month)> {
+    let invalid = || SearchError::InvalidParams("Month must be YYYY-MM".into());
+    let (year, month) = value.trim().split_once('-').ok_or_else(invalid)?;
+    if year.len() != 4 || month.len() != 2 {
+        return Err(invalid());
+    }
+    let year: i32 = year.parse().map_err(|_| invalid())?;
+    let month: u8 = month.parse().map_err(|_| invalid())?;
+    let month = Month::try_from(month).map_err(|_| invalid())?;
+    Ok((year, month))
+}
+
+/// Where a calendar price came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FareSource {
+    /// Live provider search
+    Live,
+    /// Previously observed fare
+    Observed,
+}
+
+impl FareSource {
+    /// Get source as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FareSource::Live => "live",
+            FareSource::Observed => "observed",
+        }
+    }
+}
+
+/// Cheapest fare found for a day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DayFare {
+    /// Price per passenger
+    pub price: MinorUnits,
+    /// Currency
+    pub currency: CurrencyCode,
+    /// Where the price came from
+    pub source: FareSource,
+}
+
+/// One day of the calendar
+#[derive(Debug, Clone)]
+pub struct CalendarDay {
+    /// Departure date
+    pub date: Date,
+    /// Cheapest fare, if the day could be priced
+    pub fare: Option<DayFare>,
+    /// Deal score against the rest of the month (0-100, higher = better)
+    pub deal_score: Option<u8>,
+    /// Cheapest day of the month
+    pub is_cheapest: bool,
+}
+
+/// Cheapest fare per day for a route and month
+#[derive(Debug, Clone)]
+pub struct FareCalendar {
+    /// Origin airport
+    pub origin: IataCode,
+    /// Destination airport
+    pub destination: IataCode,
+    /// Calendar year
+    pub year: i32,
+    /// Calendar month
+    pub month: Month,
+    /// Cabin class
+    pub cabin: CabinClass,
+    /// Days of the month in order
+    pub days: Vec<CalendarDay>,
+    /// Was this a cached calendar
+    pub from_cache: bool,
+}
+
+impl FareCalendar {
+    /// Create a calendar with every day unpriced
+    pub fn new(request: &CalendarRequest) -> Self {
+        Self {
+            origin: request.origin,
+            destination: request.destination,
+            year: request.year,
+            month: request.month,
+            cabin: request.cabin,
+            days: request
+                .days()
+                .into_iter()
+                .map(|date| CalendarDay {
+                    date,
+                    fare: None,
+                    deal_score: None,
+                    is_cheapest: false,
+                })
+                .collect(),
+            from_cache: false,
+        }
+    }
+
+    /// Number of priced days
+    pub fn priced_days(&self) -> usize {
+        self.days.iter().filter(|d| d.fare.is_some()).count()
+    }
+
+    /// Get the cheapest day
+    pub fn cheapest(&self) -> Option<&CalendarDay> {
+        self.days.iter().find(|d| d.is_cheapest)
+    }
+
+    /// Score each priced day against the month and mark the cheapest
+    ///
+    /// Only days in the currency of the first priced day are compared.
+    pub fn score(&mut self) {
+        let Some(currency) = self.days.iter().find_map(|d| d.fare).map(|f| f.currency) else {
+            return;
+        };
+        let prices: Vec<MinorUnits> = self
+            .days
+            .iter()
+            .filter_map(|d| d.fare)
+            .filter(|f| f.currency == currency)
+            .map(|f| f.price)
+            .collect();
+        let low = prices.iter().map(|p| p.as_i64()).min();
+
+        for day in &mut self.days {
+            let Some(fare) = day.fare.filter(|f| f.currency == currency) else {
+                day.deal_score = None;
+                day.is_cheapest = false;
+                continue;
+            };
+            let insight = PriceInsight::from_data(
+                self.origin,
+                self.destination,
+                fare.price,
+                currency,
+                &prices,
+            );
+            day.deal_score = Some(insight.deal_score);
+            day.is_cheapest = Some(fare.price.as_i64()) == low;
+        }
+    }
+}
+
+/// Price per passenger for an offer: the adult fare if broken down, else an even split
+pub(crate) fn fare_per_passenger(offer: &FlightOffer, passengers: &Passengers) -> MinorUnits {
+    offer
+        .price_per_pax
+        .iter()
+        .find(|(pax, _)| *pax == PassengerType::Adult)
+        .map(|(_, price)| *price)
+        .unwrap_or_else(|| {
+            let count = i64::from(passengers.total().max(1));
+            MinorUnits::new(offer.price.total().as_i64() / count)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::date;
+
+    #[test]
+    fn test_parse_month() {
+        assert_eq!(parse_month("2025-07").unwrap(), (2025, Month::July));
+        assert!(parse_month("2025-13").is_err());
+        assert!(parse_month("2025-7").is_err());
+        assert!(parse_month("July").is_err());
+    }
+
+    #[test]
+    fn test_calendar_request() {
+        let request = CalendarRequest::for_month(IataCode::KUL, IataCode::NRT, "2024-02").unwrap();
+        assert!(request.validate().is_ok());
+        let days = request.days();
+        assert_eq!(days.len(), 29);
+        assert_eq!(days[0], date!(2024 - 02 - 01));
+        assert_eq!(request.cache_key(), "calendar:KUL:NRT:2024-02:Y:1:0");
+
+        let same = CalendarRequest::new(IataCode::KUL, IataCode::KUL, 2024, Month::March);
+        assert!(same.validate().is_err());
+    }
+
+    #[test]
+    fn test_calendar_scoring() {
+        let request = CalendarRequest::new(IataCode::KUL, IataCode::NRT, 2030, Month::June);
+        let mut calendar = FareCalendar::new(&request);
+        assert_eq!(calendar.days.len(), 30);
+        for (day, price) in calendar.days.iter_mut().zip([500, 300, 400]) {
+            day.fare = Some(DayFare {
+                price: MinorUnits::new(price),
+                currency: CurrencyCode::MYR,
+                source: FareSource::Live,
+            });
+        }
+        calendar.score();
+
+        assert_eq!(calendar.priced_days(), 3);
+        let cheapest = calendar.cheapest().unwrap();
+        assert_eq!(cheapest.date, date!(2030 - 06 - 02));
+        assert_eq!(cheapest.deal_score, Some(100));
+        assert_eq!(calendar.days[0].deal_score, Some(0));
+        assert_eq!(calendar.days[3].deal_score, None);
+    }
+}
@@ -0,0 +1,204 @@
+//! Connection risk scoring
+//!
+//! Scores how likely a traveler is to miss a connection, based on layover
+//! length against the airport's minimum connection time. Self-transfers
+//! (separate tickets) need extra time to collect bags and check in again,
+//! and a missed flight is not protected by the airline.
+
+use std::collections::HashMap;
+
+use time::PrimitiveDateTime;
+use vaya_common::{IataCode, TimeZone, ZonedTime};
+
+use crate::types::FlightSegment;
+
+/// Connection risk level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConnectionRisk {
+    Low,
+    Medium,
+    High,
+}
+
+impl ConnectionRisk {
+    /// Get risk code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionRisk::Low => "low",
+            ConnectionRisk::Medium => "medium",
+            ConnectionRisk::High => "high",
+        }
+    }
+}
+
+/// Scored connection
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionAssessment {
+    /// Connection airport
+    pub airport: IataCode,
+    /// Time between arrival and departure
+    pub layover_minutes: i64,
+    /// Minimum time required for this connection
+    pub required_minutes: u16,
+    /// Risk score (0.0 = safe, 1.0 = very likely missed)
+    pub score: f64,
+    /// Risk level
+    pub risk: ConnectionRisk,
+}
+
+/// Connection risk model
+#[derive(Debug, Clone)]
+pub struct ConnectionRiskModel {
+    /// Default minimum connection time in minutes
+    pub default_mct_minutes: u16,
+    /// Minimum connection time per airport
+    pub airport_mct_minutes: HashMap<IataCode, u16>,
+    /// Extra time needed for a self-transfer
+    pub self_transfer_buffer_minutes: u16,
+}
+
+impl Default for ConnectionRiskModel {
+    fn default() -> Self {
+        Self {
+            default_mct_minutes: 60,
+            airport_mct_minutes: HashMap::new(),
+            self_transfer_buffer_minutes: 120,
+        }
+    }
+}
+
+impl ConnectionRiskModel {
+    /// Create a model with default connection times
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum connection time for an airport
+    pub fn with_airport_mct(mut self, airport: IataCode, minutes: u16) -> Self {
+        self.airport_mct_minutes.insert(airport, minutes);
+        self
+    }
+
+    /// Minimum connection time at an airport
+    pub fn mct(&self, airport: &IataCode) -> u16 {
+        self.airport_mct_minutes
+            .get(airport)
+            .copied()
+            .unwrap_or(self.default_mct_minutes)
+    }
+
+    /// Score the connection between two segments
+    ///
+    /// Returns `None` if the segments do not connect at the same airport.
+    pub fn assess(
+        &self,
+        arriving: &FlightSegment,
+        departing: &FlightSegment,
+        self_transfer: bool,
+    ) -> Option<ConnectionAssessment> {
+        if arriving.destination != departing.origin {
+            return None;
+        }
+
+        // Both times are local to the connecting airport, which may change
+        // clocks during the layover
+        let zone = TimeZone::airport(&departing.origin).unwrap_or(TimeZone::UTC);
+        let arrival = ZonedTime::from_local(
+            PrimitiveDateTime::new(arriving.arrival_date, arriving.arrival_time),
+            zone,
+        );
+        let departure = ZonedTime::from_local(
+            PrimitiveDateTime::new(departing.departure_date, departing.departure_time),
+            zone,
+        );
+        let layover_minutes = arrival.minutes_until(&departure);
+
+        let mut required_minutes = self.mct(&departing.origin);
+        if self_transfer {
+            required_minutes += self.self_transfer_buffer_minutes;
+        }
+
+        // Full risk below the minimum, falling off over the next two hours
+        let slack = layover_minutes - required_minutes as i64;
+        let score = if slack < 0 {
+            1.0
+        } else {
+            (1.0 - slack as f64 / 120.0).clamp(0.0, 1.0) * 0.6
+        };
+        let risk = if score >= 0.6 {
+            ConnectionRisk::High
+        } else if score >= 0.3 {
+            ConnectionRisk::Medium
+        } else {
+            ConnectionRisk::Low
+        };
+
+        Some(ConnectionAssessment {
+            airport: departing.origin,
+            layover_minutes,
+            required_minutes,
+            score,
+            risk,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CabinClass;
+    use time::macros::{date, time};
+    use vaya_common::AirlineCode;
+
+    fn segment(from: IataCode, to: IataCode, dep: time::Time, arr: time::Time) -> FlightSegment {
+        FlightSegment {
+            airline: AirlineCode::MH,
+            flight_number: "1".into(),
+            marketing_airline: None,
+            origin: from,
+            destination: to,
+            departure_date: date!(2025 - 01 - 15),
+            departure_time: dep,
+            arrival_date: date!(2025 - 01 - 15),
+            arrival_time: arr,
+            duration_minutes: 60,
+            aircraft: None,
+            cabin: CabinClass::Economy,
+            booking_class: 'Y',
+            seats_remaining: None,
+        }
+    }
+
+    #[test]
+    fn test_assess_connection() {
+        let model = ConnectionRiskModel::new();
+        let first = segment(IataCode::KUL, IataCode::SIN, time!(8:00), time!(9:00));
+        let tight = segment(IataCode::SIN, IataCode::NRT, time!(10:30), time!(18:00));
+        let relaxed = segment(IataCode::SIN, IataCode::NRT, time!(15:00), time!(23:00));
+
+        let protected = model.assess(&first, &tight, false).unwrap();
+        assert_eq!(protected.layover_minutes, 90);
+        assert_eq!(protected.risk, ConnectionRisk::Medium);
+
+        let self_transfer = model.assess(&first, &tight, true).unwrap();
+        assert_eq!(self_transfer.risk, ConnectionRisk::High);
+
+        let relaxed = model.assess(&first, &relaxed, true).unwrap();
+        assert_eq!(relaxed.risk, ConnectionRisk::Low);
+
+        assert!(model.assess(&tight, &first, false).is_none());
+    }
+
+    #[test]
+    fn test_layover_across_clock_change() {
+        let model = ConnectionRiskModel::new();
+        // London clocks go forward at 01:00 on 2025-03-30
+        let mut arriving = segment(IataCode::SIN, IataCode::LHR, time!(17:00), time!(00:30));
+        arriving.arrival_date = date!(2025 - 03 - 30);
+        let mut departing = segment(IataCode::LHR, IataCode::JFK, time!(03:00), time!(06:00));
+        departing.departure_date = date!(2025 - 03 - 30);
+
+        let assessment = model.assess(&arriving, &departing, false).unwrap();
+        assert_eq!(assessment.layover_minutes, 90);
+    }
+}
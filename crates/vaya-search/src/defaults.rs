@@ -0,0 +1,538 @@
+//! Market-aware default search parameters
+//!
+//! The Home screen and search forms open pre-filled: an origin airport, the
+//! market's currency and locale, and a few destination suggestions for that
+//! origin. Inputs are combined in order of how much they say about this
+//! user: their last search, then the market detected from the client IP
+//! (or an explicit override), then the `Accept-Language` region, then the
+//! default market. Destination suggestions per origin come from search
+//! analytics and are cached, since every anonymous visitor from the same
+//! market asks for the same list.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use vaya_cache::Cache;
+use vaya_common::CurrencyCode;
+
+use crate::airports::{self, Airport};
+
+/// A market the site is localized for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Market {
+    /// ISO 3166-1 alpha-2 country code
+    pub country: &'static str,
+    /// Display and pricing currency
+    pub currency: CurrencyCode,
+    /// BCP 47 locale
+    pub locale: &'static str,
+    /// Origin airport when nothing better is known
+    pub default_origin: &'static str,
+}
+
+/// Supported markets; the first entry is the default
+///
+/// Markets sharing a language are listed most-likely first, so a bare
+/// language tag ("zh") resolves to the first market using it.
+#[rustfmt::skip]
+pub static MARKETS: &[Market] = &[
+    Market { country: "MY", currency: CurrencyCode::MYR, locale: "ms-MY", default_origin: "KUL" },
+    Market { country: "SG", currency: CurrencyCode::SGD, locale: "en-SG", default_origin: "SIN" },
+    Market { country: "TH", currency: CurrencyCode::THB, locale: "th-TH", default_origin: "BKK" },
+    Market { country: "ID", currency: CurrencyCode::IDR, locale: "id-ID", default_origin: "CGK" },
+    Market { country: "PH", currency: CurrencyCode::PHP, locale: "en-PH", default_origin: "MNL" },
+    Market { country: "VN", currency: CurrencyCode::VND, locale: "vi-VN", default_origin: "SGN" },
+    Market { country: "JP", currency: CurrencyCode::JPY, locale: "ja-JP", default_origin: "NRT" },
+    Market { country: "KR", currency: CurrencyCode::KRW, locale: "ko-KR", default_origin: "ICN" },
+    Market { country: "CN", currency: CurrencyCode::CNY, locale: "zh-CN", default_origin: "PVG" },
+    Market { country: "HK", currency: CurrencyCode::HKD, locale: "zh-HK", default_origin: "HKG" },
+    Market { country: "TW", currency: CurrencyCode::TWD, locale: "zh-TW", default_origin: "TPE" },
+    Market { country: "AU", currency: CurrencyCode::AUD, locale: "en-AU", default_origin: "SYD" },
+    Market { country: "NZ", currency: CurrencyCode::NZD, locale: "en-NZ", default_origin: "AKL" },
+    Market { country: "GB", currency: CurrencyCode::GBP, locale: "en-GB", default_origin: "LHR" },
+    Market { country: "FR", currency: CurrencyCode::EUR, locale: "fr-FR", default_origin: "CDG" },
+    Market { country: "DE", currency: CurrencyCode::EUR, locale: "de-DE", default_origin: "FRA" },
+    Market { country: "US", currency: CurrencyCode::USD, locale: "en-US", default_origin: "JFK" },
+];
+
+/// Look up a market by country code
+pub fn market(country: &str) -> Option<&'static Market> {
+    MARKETS
+        .iter()
+        .find(|m| m.country.eq_ignore_ascii_case(country))
+}
+
+/// The default market
+pub fn default_market() -> &'static Market {
+    &MARKETS[0]
+}
+
+/// Market implied by an `Accept-Language` header
+///
+/// Only the first language is used. A region subtag ("en-SG") selects that
+/// market; a bare language selects the first market speaking it, except
+/// English, which says nothing about where the user is.
+pub fn market_for_language(accept_language: &str) -> Option<&'static Market> {
+    let tag = accept_language.split(',').next()?.split(';').next()?.trim();
+    let mut parts = tag.split(['-', '_']);
+    let lang = parts.next().filter(|l| !l.is_empty())?;
+    if let Some(region) = parts.find(|p| p.len() == 2) {
+        if let Some(found) = market(region) {
+            return Some(found);
+        }
+    }
+    if lang.eq_ignore_ascii_case("en") {
+        return None;
+    }
+    MARKETS.iter().find(|m| {
+        m.locale
+            .split('-')
+            .next()
+            .is_some_and(|l| l.eq_ignore_ascii_case(lang))
+    })
+}
+
+/// Resolves a client IP to a country
+pub trait GeoIpResolver: Send + Sync {
+    /// ISO country code for the address, if known
+    fn country(&self, ip: IpAddr) -> Option<String>;
+}
+
+/// GeoIP lookup over an in-memory CIDR table
+///
+/// Load it from a GeoIP country export at startup; the longest matching
+/// prefix wins.
+#[derive(Debug, Default)]
+pub struct PrefixGeoIp {
+    /// (network, prefix length, country), IPv4 mapped into IPv6 space
+    ranges: Vec<(u128, u8, String)>,
+}
+
+impl PrefixGeoIp {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a CIDR range ("203.106.0.0/15" or "2001:e68::/32")
+    pub fn with_range(mut self, cidr: &str, country: &str) -> Result<Self, String> {
+        let (addr, len) = cidr
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid CIDR: {}", cidr))?;
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("Invalid address in CIDR: {}", cidr))?;
+        let len: u8 = len
+            .parse()
+            .map_err(|_| format!("Invalid prefix length in CIDR: {}", cidr))?;
+        let len = match addr {
+            IpAddr::V4(_) if len <= 32 => len + 96,
+            IpAddr::V6(_) if len <= 128 => len,
+            _ => return Err(format!("Invalid prefix length in CIDR: {}", cidr)),
+        };
+        self.ranges.push((
+            to_u128(addr) & prefix_mask(len),
+            len,
+            country.to_uppercase(),
+        ));
+        // Longest prefix first, so the first match is the most specific
+        self.ranges.sort_by_key(|r| std::cmp::Reverse(r.1));
+        Ok(self)
+    }
+
+    /// Number of ranges loaded
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+}
+
+impl GeoIpResolver for PrefixGeoIp {
+    fn country(&self, ip: IpAddr) -> Option<String> {
+        let ip = to_u128(ip);
+        self.ranges
+            .iter()
+            .find(|(network, len, _)| ip & prefix_mask(*len) == *network)
+            .map(|(_, _, country)| country.clone())
+    }
+}
+
+fn to_u128(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+fn prefix_mask(len: u8) -> u128 {
+    if len == 0 {
+        0
+    } else {
+        u128::MAX << (128 - u32::from(len))
+    }
+}
+
+/// Destination popularity per origin, from search analytics
+#[derive(Debug, Default)]
+pub struct PopularRoutes {
+    /// origin -> destination -> searches
+    counts: RwLock<HashMap<&'static str, HashMap<&'static str, u64>>>,
+}
+
+impl PopularRoutes {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a search for a route
+    pub fn record(&self, origin: &str, destination: &str) {
+        let autocomplete = airports::global();
+        let (Some(origin), Some(destination)) = (
+            autocomplete.airport(origin),
+            autocomplete.airport(destination),
+        ) else {
+            return;
+        };
+        let mut counts = self.counts.write().unwrap_or_else(|e| e.into_inner());
+        *counts
+            .entry(origin.code)
+            .or_default()
+            .entry(destination.code)
+            .or_insert(0) += 1;
+    }
+
+    /// Most searched destinations from an origin
+    ///
+    /// Topped up from the embedded dataset's baseline popularity when
+    /// analytics have fewer than `limit` destinations.
+    pub fn top(&self, origin: &str, limit: usize) -> Vec<&'static str> {
+        let Some(origin) = airports::global().airport(origin) else {
+            return Vec::new();
+        };
+        let mut ranked: Vec<(&'static str, u64)> = self
+            .counts
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(origin.code)
+            .map(|dests| dests.iter().map(|(d, c)| (*d, *c)).collect())
+            .unwrap_or_default();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        let mut top: Vec<&'static str> = ranked.into_iter().map(|(d, _)| d).collect();
+
+        if top.len() < limit {
+            let mut fallback: Vec<&'static Airport> = airports::AIRPORTS
+                .iter()
+                .filter(|a| a.city_code != origin.city_code && !top.contains(&a.code))
+                .collect();
+            // Prefer trips abroad, then the busiest airports
+            fallback.sort_by(|a, b| {
+                (a.country == origin.country)
+                    .cmp(&(b.country == origin.country))
+                    .then_with(|| b.popularity.cmp(&a.popularity))
+                    .then_with(|| a.code.cmp(b.code))
+            });
+            top.extend(fallback.into_iter().map(|a| a.code));
+        }
+        top.truncate(limit);
+        top
+    }
+}
+
+/// Where the default origin came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginSource {
+    /// The user's most recent search
+    LastSearch,
+    /// The market's main airport, market detected from the client IP
+    GeoIp,
+    /// The market's main airport, market from an override or the locale
+    Market,
+}
+
+impl OriginSource {
+    /// Get source code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OriginSource::LastSearch => "last_search",
+            OriginSource::GeoIp => "geoip",
+            OriginSource::Market => "market",
+        }
+    }
+}
+
+/// What is known about the visitor
+#[derive(Debug, Clone, Default)]
+pub struct DefaultsQuery {
+    /// Client IP address
+    pub ip: Option<IpAddr>,
+    /// `Accept-Language` header
+    pub accept_language: Option<String>,
+    /// Explicit market (country code), e.g. from a market picker
+    pub market: Option<String>,
+    /// Origin of the user's last search
+    pub last_origin: Option<String>,
+    /// Destinations of the user's recent searches, newest first
+    pub recent_destinations: Vec<String>,
+}
+
+/// Pre-filled search form values
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchDefaults {
+    /// Market the defaults were built for
+    pub market: &'static Market,
+    /// Default origin airport
+    pub origin: &'static Airport,
+    /// How the origin was chosen
+    pub origin_source: OriginSource,
+    /// Suggested destinations, recent searches first
+    pub destinations: Vec<&'static Airport>,
+    /// Default departure, in days from today
+    pub departure_in_days: u32,
+    /// Default trip length for return searches, in days
+    pub trip_length_days: u32,
+}
+
+/// Defaults service settings
+#[derive(Debug, Clone)]
+pub struct DefaultsConfig {
+    /// Destination suggestions returned
+    pub suggestions: usize,
+    /// How long per-origin suggestions are cached
+    pub cache_ttl: Duration,
+    /// Default departure, in days from today
+    pub departure_in_days: u32,
+    /// Default trip length for return searches, in days
+    pub trip_length_days: u32,
+}
+
+impl Default for DefaultsConfig {
+    fn default() -> Self {
+        Self {
+            suggestions: 6,
+            cache_ttl: Duration::from_secs(600),
+            departure_in_days: 14,
+            trip_length_days: 7,
+        }
+    }
+}
+
+/// Builds default search parameters from GeoIP, history and analytics
+pub struct SearchDefaultsService {
+    geoip: Box<dyn GeoIpResolver>,
+    popular: PopularRoutes,
+    config: DefaultsConfig,
+    /// Destination suggestions per origin
+    cache: Cache<&'static str, Vec<&'static str>>,
+}
+
+impl SearchDefaultsService {
+    /// Create a service with an empty GeoIP table
+    pub fn new() -> Self {
+        Self::with_geoip(Box::new(PrefixGeoIp::new()))
+    }
+
+    /// Create a service with a GeoIP resolver
+    pub fn with_geoip(geoip: Box<dyn GeoIpResolver>) -> Self {
+        Self {
+            geoip,
+            popular: PopularRoutes::new(),
+            config: DefaultsConfig::default(),
+            cache: Cache::new(1024, 8),
+        }
+    }
+
+    /// Set the config
+    pub fn with_config(mut self, config: DefaultsConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Route popularity used for suggestions
+    pub fn popular_routes(&self) -> &PopularRoutes {
+        &self.popular
+    }
+
+    /// Build defaults for a visitor
+    pub fn defaults(&self, query: &DefaultsQuery) -> SearchDefaults {
+        let autocomplete = airports::global();
+
+        let geo_market = query
+            .ip
+            .and_then(|ip| self.geoip.country(ip))
+            .and_then(|country| market(&country));
+        let market = query
+            .market
+            .as_deref()
+            .and_then(market)
+            .or(geo_market)
+            .or_else(|| {
+                query
+                    .accept_language
+                    .as_deref()
+                    .and_then(market_for_language)
+            })
+            .unwrap_or_else(default_market);
+
+        let last_origin = query
+            .last_origin
+            .as_deref()
+            .and_then(|code| autocomplete.airport(code));
+        let (origin, origin_source) = match last_origin {
+            Some(origin) => (origin, OriginSource::LastSearch),
+            None => {
+                let source = if query.market.is_none() && geo_market == Some(market) {
+                    OriginSource::GeoIp
+                } else {
+                    OriginSource::Market
+                };
+                let origin = autocomplete
+                    .airport(market.default_origin)
+                    .unwrap_or(&airports::AIRPORTS[0]);
+                (origin, source)
+            }
+        };
+
+        let mut destinations: Vec<&'static Airport> = Vec::new();
+        for code in &query.recent_destinations {
+            if let Some(airport) = autocomplete.airport(code) {
+                if airport.city_code != origin.city_code
+                    && !destinations.iter().any(|d| d.code == airport.code)
+                {
+                    destinations.push(airport);
+                }
+            }
+        }
+        for code in self.suggestions_for(origin.code) {
+            if destinations.len() >= self.config.suggestions {
+                break;
+            }
+            if let Some(airport) = autocomplete.airport(code) {
+                if !destinations.iter().any(|d| d.code == airport.code) {
+                    destinations.push(airport);
+                }
+            }
+        }
+        destinations.truncate(self.config.suggestions);
+
+        SearchDefaults {
+            market,
+            origin,
+            origin_source,
+            destinations,
+            departure_in_days: self.config.departure_in_days,
+            trip_length_days: self.config.trip_length_days,
+        }
+    }
+
+    fn suggestions_for(&self, origin: &'static str) -> Vec<&'static str> {
+        if let Some(cached) = self.cache.get(&origin) {
+            return cached;
+        }
+        // Fetch a few extra so recent searches can displace some
+        let top = self.popular.top(origin, self.config.suggestions * 2);
+        self.cache
+            .insert(origin, top.clone(), Some(self.config.cache_ttl));
+        top
+    }
+}
+
+impl Default for SearchDefaultsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Process-wide defaults service
+pub fn global() -> &'static SearchDefaultsService {
+    static GLOBAL: OnceLock<SearchDefaultsService> = OnceLock::new();
+    GLOBAL.get_or_init(SearchDefaultsService::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> SearchDefaultsService {
+        let geoip = PrefixGeoIp::new()
+            .with_range("203.106.0.0/15", "MY")
+            .unwrap()
+            .with_range("203.116.0.0/16", "SG")
+            .unwrap()
+            .with_range("203.116.5.0/24", "TH")
+            .unwrap();
+        SearchDefaultsService::with_geoip(Box::new(geoip))
+    }
+
+    #[test]
+    fn test_market_resolution() {
+        let service = service();
+        let sg = DefaultsQuery {
+            ip: "203.116.1.1".parse().ok(),
+            ..Default::default()
+        };
+        let defaults = service.defaults(&sg);
+        assert_eq!(defaults.market.country, "SG");
+        assert_eq!(defaults.market.currency, CurrencyCode::SGD);
+        assert_eq!(defaults.origin.code, "SIN");
+        assert_eq!(defaults.origin_source, OriginSource::GeoIp);
+
+        // Longest prefix wins
+        let th = DefaultsQuery {
+            ip: "203.116.5.9".parse().ok(),
+            ..Default::default()
+        };
+        assert_eq!(service.defaults(&th).origin.code, "BKK");
+
+        // Unknown IP falls back to the language region, then the default
+        let by_language = DefaultsQuery {
+            ip: "8.8.8.8".parse().ok(),
+            accept_language: Some("ja-JP,ja;q=0.9,en;q=0.8".into()),
+            ..Default::default()
+        };
+        let defaults = service.defaults(&by_language);
+        assert_eq!(defaults.market.country, "JP");
+        assert_eq!(defaults.origin_source, OriginSource::Market);
+        assert_eq!(market_for_language("en").map(|m| m.country), None);
+        assert_eq!(market_for_language("zh").map(|m| m.country), Some("CN"));
+        assert_eq!(
+            service.defaults(&DefaultsQuery::default()).market.country,
+            "MY"
+        );
+
+        assert!(PrefixGeoIp::new().with_range("10.0.0.0/33", "MY").is_err());
+    }
+
+    #[test]
+    fn test_history_and_suggestions() {
+        let service = service();
+        service.popular_routes().record("KUL", "DPS");
+        service.popular_routes().record("KUL", "DPS");
+        service.popular_routes().record("KUL", "NRT");
+
+        let query = DefaultsQuery {
+            ip: "203.106.0.1".parse().ok(),
+            last_origin: Some("pen".into()),
+            recent_destinations: vec!["HKT".into(), "PEN".into()],
+            ..Default::default()
+        };
+        let defaults = service.defaults(&query);
+        assert_eq!(defaults.market.country, "MY");
+        assert_eq!(defaults.origin.code, "PEN");
+        assert_eq!(defaults.origin_source, OriginSource::LastSearch);
+        assert_eq!(defaults.destinations[0].code, "HKT");
+        assert!(defaults.destinations.iter().all(|d| d.code != "PEN"));
+        assert_eq!(defaults.destinations.len(), 6);
+
+        let top = service.popular_routes().top("KUL", 3);
+        assert_eq!(&top[..2], &["DPS", "NRT"]);
+        // Baseline fills the rest, never the origin's own city
+        assert!(!top.contains(&"SZB"));
+    }
+}
@@ -1,12 +1,18 @@
 //! Search engine for processing flight searches
 
-use std::sync::Mutex;
+use std::cmp::Ordering;
+use std::sync::{Mutex, OnceLock};
 
 use vaya_cache::LruCache;
 
+use crate::calendar::{
+    fare_per_passenger, CalendarRequest, DayFare, FareCalendar, FareSource, ObservedFares,
+};
+use crate::grouping::{dedupe_offers, group_offers, OfferGroup};
 use crate::request::{SearchRequest, SortBy, SortOrder};
+use crate::routing::{FareGraph, RoutingEngine};
 use crate::types::FlightOffer;
-use crate::SearchResult;
+use crate::{SearchError, SearchResult};
 
 /// Search response
 #[derive(Debug, Clone)]
@@ -23,6 +29,8 @@ pub struct SearchResponse {
     pub from_cache: bool,
     /// Warnings/notices
     pub warnings: Vec<String>,
+    /// Cursor for the next page, if there are more offers
+    pub next_cursor: Option<String>,
 }
 
 impl SearchResponse {
@@ -46,39 +54,55 @@ impl SearchResponse {
     /// Sort offers by criteria
     pub fn sorted(&self, by: SortBy, order: SortOrder) -> Vec<&FlightOffer> {
         let mut offers: Vec<&FlightOffer> = self.offers.iter().collect();
+        offers.sort_by(|a, b| compare_offers(a, b, by, order));
+        offers
+    }
 
-        match by {
-            SortBy::Price => {
-                offers.sort_by_key(|o| o.price.total().as_i64());
-            }
-            SortBy::Duration => {
-                offers.sort_by_key(|o| o.total_duration_minutes());
-            }
-            SortBy::Departure => {
-                offers.sort_by(|a, b| {
-                    let a_time = a.outbound.departure_time();
-                    let b_time = b.outbound.departure_time();
-                    a_time.cmp(&b_time)
-                });
-            }
-            SortBy::Arrival => {
-                offers.sort_by(|a, b| {
-                    let a_time = a.outbound.arrival_time();
-                    let b_time = b.outbound.arrival_time();
-                    a_time.cmp(&b_time)
-                });
-            }
-            SortBy::Stops => {
-                offers.sort_by_key(|o| o.outbound.stops());
-            }
-        }
-
-        if order == SortOrder::Descending {
-            offers.reverse();
-        }
+    /// Group offers for the same flights into fare families
+    pub fn groups(&self) -> Vec<OfferGroup> {
+        group_offers(self.offers.iter().cloned())
+    }
+}
 
-        offers
+/// Order offers by criteria, breaking ties by price then ID so pages are stable
+fn compare_offers(a: &FlightOffer, b: &FlightOffer, by: SortBy, order: SortOrder) -> Ordering {
+    let ordering = match by {
+        SortBy::Price => Ordering::Equal,
+        SortBy::Duration => a.total_duration_minutes().cmp(&b.total_duration_minutes()),
+        SortBy::Departure => (a.outbound.departure_date(), a.outbound.departure_time())
+            .cmp(&(b.outbound.departure_date(), b.outbound.departure_time())),
+        SortBy::Arrival => (a.outbound.arrival_date(), a.outbound.arrival_time())
+            .cmp(&(b.outbound.arrival_date(), b.outbound.arrival_time())),
+        SortBy::Stops => a.outbound.stops().cmp(&b.outbound.stops()),
     }
+    .then_with(|| a.price.total().as_i64().cmp(&b.price.total().as_i64()));
+    let ordering = match order {
+        SortOrder::Ascending => ordering,
+        SortOrder::Descending => ordering.reverse(),
+    };
+    ordering.then_with(|| a.id.cmp(&b.id))
+}
+
+/// Cursor naming the cached result set, the sort, and the next offset
+fn encode_cursor(request_id: &str, request: &SearchRequest, offset: usize) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        request_id,
+        request.sort_by.as_str(),
+        request.sort_order.as_str(),
+        offset
+    )
+}
+
+/// Offset from a cursor issued for this result set and sort
+fn decode_cursor(cursor: &str, request_id: &str, request: &SearchRequest) -> Option<usize> {
+    let mut parts = cursor.rsplitn(4, ':');
+    let offset = parts.next()?.parse().ok()?;
+    let order = parts.next()?;
+    let by = parts.next()?;
+    let id = parts.next()?;
+    (id == request_id && by == request.sort_by.as_str() && order == request.sort_order.as_str())
+        .then_some(offset)
 }
 
 /// Search engine configuration
@@ -92,6 +116,8 @@ pub struct SearchEngineConfig {
     pub timeout_ms: u64,
     /// Maximum results per search
     pub max_results: usize,
+    /// Fare calendar cache TTL in seconds
+    pub calendar_cache_ttl_secs: u64,
 }
 
 impl Default for SearchEngineConfig {
@@ -101,6 +127,7 @@ impl Default for SearchEngineConfig {
             max_cached_searches: 1000,
             timeout_ms: 30_000,
             max_results: 100,
+            calendar_cache_ttl_secs: 3600, // 1 hour
         }
     }
 }
@@ -112,12 +139,23 @@ struct CachedSearch {
     cached_at: i64,
 }
 
+/// Cached fare calendar
+#[derive(Clone)]
+struct CachedCalendar {
+    calendar: FareCalendar,
+    cached_at: i64,
+}
+
 /// Flight search engine
 pub struct SearchEngine {
     config: SearchEngineConfig,
     cache: Mutex<LruCache<String, CachedSearch>>,
     providers: Vec<Box<dyn SearchProvider>>,
     request_counter: Mutex<u64>,
+    routing: Option<RoutingEngine>,
+    fares: Mutex<FareGraph>,
+    calendars: Mutex<LruCache<String, CachedCalendar>>,
+    observed: Option<Box<dyn ObservedFares>>,
 }
 
 /// Search provider trait
@@ -149,9 +187,13 @@ impl SearchEngine {
     pub fn with_config(config: SearchEngineConfig) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(config.max_cached_searches)),
-            config,
             providers: Vec::new(),
             request_counter: Mutex::new(0),
+            routing: None,
+            fares: Mutex::new(FareGraph::new()),
+            calendars: Mutex::new(LruCache::new(config.max_cached_searches)),
+            observed: None,
+            config,
         }
     }
 
@@ -163,70 +205,257 @@ impl SearchEngine {
             .sort_by(|a, b| b.priority().cmp(&a.priority()));
     }
 
+    /// Enable self-transfer routing through hub airports
+    pub fn enable_routing(&mut self, routing: RoutingEngine) {
+        self.routing = Some(routing);
+    }
+
+    /// Add one-way fares to the routing graph
+    ///
+    /// One-way provider results are added automatically as searches run.
+    pub fn record_fares(&self, offers: impl IntoIterator<Item = FlightOffer>) {
+        let mut fares = self.fares.lock().unwrap();
+        for offer in offers {
+            fares.insert(offer);
+        }
+    }
+
+    /// Price calendar days from previously observed fares before searching live
+    pub fn set_observed_fares(&mut self, observed: Box<dyn ObservedFares>) {
+        self.observed = Some(observed);
+    }
+
+    /// Cheapest fare per day for a route and month
+    ///
+    /// Past days stay unpriced. Each remaining day uses an observed fare when
+    /// one is available, otherwise the cheapest offer of a one-way search.
+    pub fn fare_calendar(&self, request: &CalendarRequest) -> SearchResult<FareCalendar> {
+        request.validate()?;
+
+        let cache_key = request.cache_key();
+        if let Some(cached) = self.get_cached_calendar(&cache_key) {
+            return Ok(cached);
+        }
+
+        let today = time::OffsetDateTime::now_utc().date();
+        let mut calendar = FareCalendar::new(request);
+        for day in &mut calendar.days {
+            if day.date < today {
+                continue;
+            }
+            day.fare = self.day_fare(request, day.date);
+        }
+        calendar.score();
+
+        let mut calendars = self.calendars.lock().unwrap();
+        calendars.insert(
+            cache_key,
+            CachedCalendar {
+                calendar: calendar.clone(),
+                cached_at: time::OffsetDateTime::now_utc().unix_timestamp(),
+            },
+        );
+
+        Ok(calendar)
+    }
+
+    /// Cheapest fare for one calendar day
+    fn day_fare(&self, request: &CalendarRequest, date: time::Date) -> Option<DayFare> {
+        if let Some(observed) = &self.observed {
+            if let Some((price, currency)) =
+                observed.cheapest(&request.origin, &request.destination, date, request.cabin)
+            {
+                return Some(DayFare {
+                    price,
+                    currency,
+                    source: FareSource::Observed,
+                });
+            }
+        }
+
+        let response = match self.search(&request.search_request(date)) {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::debug!("Calendar search for {} failed: {}", date, e);
+                return None;
+            }
+        };
+        response
+            .offers
+            .iter()
+            .filter(|o| !o.is_round_trip())
+            .map(|o| (fare_per_passenger(o, &request.passengers), o.price.currency))
+            .min_by_key(|(price, _)| price.as_i64())
+            .map(|(price, currency)| DayFare {
+                price,
+                currency,
+                source: FareSource::Live,
+            })
+    }
+
+    /// Get cached calendar
+    fn get_cached_calendar(&self, key: &str) -> Option<FareCalendar> {
+        let mut calendars = self.calendars.lock().unwrap();
+        let cached = calendars.get(&key.to_string())?;
+        let age = time::OffsetDateTime::now_utc().unix_timestamp() - cached.cached_at;
+        if age >= self.config.calendar_cache_ttl_secs as i64 {
+            return None;
+        }
+        let mut calendar = cached.calendar.clone();
+        calendar.from_cache = true;
+        Some(calendar)
+    }
+
     /// Execute a search
+    ///
+    /// Aggregated offers are cached unfiltered, so changing filters or sort
+    /// doesn't query providers again. Each call returns one page of
+    /// `max_results`, with a cursor for the next page that stays valid while
+    /// the results are cached and the filters and sort are unchanged.
     pub fn search(&self, request: &SearchRequest) -> SearchResult<SearchResponse> {
         // Validate request
         request.validate()?;
 
         // Check cache
         let cache_key = request.cache_key();
-        if let Some(cached) = self.get_cached(&cache_key) {
-            return Ok(cached);
-        }
+        let results = match self.get_cached(&cache_key) {
+            Some(cached) => cached,
+            None if request.cursor.is_some() => {
+                return Err(SearchError::InvalidParams(
+                    "Search cursor has expired".into(),
+                ));
+            }
+            None => {
+                let results = self.aggregate(request);
+                self.cache_response(&cache_key, &results);
+                results
+            }
+        };
+
+        self.page(results, request)
+    }
 
+    /// Search providers and routing
+    fn aggregate(&self, request: &SearchRequest) -> SearchResponse {
         // Generate request ID
         let request_id = self.generate_request_id();
 
         let start = std::time::Instant::now();
-
-        // Search all providers
+        // Search all providers, once per variation of a flexible request
         let mut all_offers = Vec::new();
         let mut warnings = Vec::new();
 
+        if request.is_flexible() {
+            let today = time::OffsetDateTime::now_utc().date();
+            for (variation, variant) in request.variations() {
+                if variant.departure_date < today {
+                    continue;
+                }
+                for mut offer in self.search_providers(&variant, &mut warnings) {
+                    if all_offers.iter().any(|o: &FlightOffer| o.id == offer.id) {
+                        continue;
+                    }
+                    offer.variation = Some(variation);
+                    all_offers.push(offer);
+                }
+            }
+        } else {
+            all_offers = self.search_providers(request, &mut warnings);
+        }
+
+        // Add self-transfer combinations the providers can't sell on one ticket
+        if let Some(routing) = &self.routing {
+            let mut fares = self.fares.lock().unwrap();
+            fares.prune_expired();
+            let routes = routing.routes(&fares, request);
+            if !routes.is_empty() {
+                warnings.push(format!(
+                    "{} self-transfer itineraries use separate tickets",
+                    routes.len()
+                ));
+            }
+            all_offers.extend(routes.into_iter().map(|r| r.offer));
+        }
+
+        // Providers often sell the same flights at the same fare
+        let all_offers = dedupe_offers(all_offers);
+
+        SearchResponse {
+            request_id,
+            total_count: all_offers.len(),
+            offers: all_offers,
+            duration_ms: start.elapsed().as_millis() as u64,
+            from_cache: false,
+            warnings,
+            next_cursor: None,
+        }
+    }
+
+    /// Query every available provider for one request
+    ///
+    /// Provider problems become warnings, each reported once.
+    fn search_providers(
+        &self,
+        request: &SearchRequest,
+        warnings: &mut Vec<String>,
+    ) -> Vec<FlightOffer> {
+        let mut all_offers = Vec::new();
+        let mut warn = |warning: String| {
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        };
+
         for provider in &self.providers {
             if !provider.is_available() {
-                warnings.push(format!("Provider {} unavailable", provider.name()));
+                warn(format!("Provider {} unavailable", provider.name()));
                 continue;
             }
 
             match provider.search(request) {
                 Ok(offers) => {
+                    if self.routing.is_some() {
+                        self.record_fares(offers.iter().cloned());
+                    }
                     all_offers.extend(offers);
                 }
                 Err(e) => {
-                    warnings.push(format!("Provider {} error: {}", provider.name(), e));
+                    warn(format!("Provider {} error: {}", provider.name(), e));
                 }
             }
         }
 
-        // Apply filters
-        let mut filtered: Vec<FlightOffer> = all_offers
-            .into_iter()
-            .filter(|o| self.passes_filters(o, request))
-            .collect();
-
-        // Sort by price (default)
-        filtered.sort_by_key(|o| o.price.total().as_i64());
-
-        // Limit results
-        let total_count = filtered.len();
-        let max = request.max_results.unwrap_or(self.config.max_results);
-        filtered.truncate(max);
-
-        let duration_ms = start.elapsed().as_millis() as u64;
+        all_offers
+    }
 
-        let response = SearchResponse {
-            request_id,
-            offers: filtered,
-            total_count,
-            duration_ms,
-            from_cache: false,
-            warnings,
+    /// Filter and sort cached results, then cut the page the cursor points at
+    fn page(
+        &self,
+        mut response: SearchResponse,
+        request: &SearchRequest,
+    ) -> SearchResult<SearchResponse> {
+        response.offers.retain(|o| self.passes_filters(o, request));
+        response
+            .offers
+            .sort_by(|a, b| compare_offers(a, b, request.sort_by, request.sort_order));
+
+        let total = response.offers.len();
+        let offset = match &request.cursor {
+            Some(cursor) => decode_cursor(cursor, &response.request_id, request)
+                .filter(|offset| *offset <= total)
+                .ok_or_else(|| {
+                    SearchError::InvalidParams("Search cursor does not match this search".into())
+                })?,
+            None => 0,
         };
+        let max = request.max_results.unwrap_or(self.config.max_results);
+        let end = total.min(offset + max);
 
-        // Cache the response
-        self.cache_response(&cache_key, &response);
-
+        response.total_count = total;
+        response.next_cursor =
+            (end < total).then(|| encode_cursor(&response.request_id, request, end));
+        response.offers.truncate(end);
+        response.offers.drain(..offset);
         Ok(response)
     }
 
@@ -249,14 +478,37 @@ impl SearchEngine {
             return false;
         }
 
-        // Check refundable
+        // Check departure and arrival windows
+        if let Some(departure) = offer.outbound.departure_time() {
+            if !filters.passes_departure_time(departure) {
+                return false;
+            }
+        }
+        if let Some(arrival) = offer.outbound.arrival_time() {
+            if !filters.passes_arrival_time(arrival) {
+                return false;
+            }
+        }
+
+        // Check refundable and flexible
         if filters.refundable_only && !offer.refundable {
             return false;
         }
+        if filters.flexible_only && !offer.changeable {
+            return false;
+        }
 
-        // Check airlines
+        // Check checked baggage
+        let has_checked_bag = matches!(&offer.baggage, Some(b) if b.checked_bags > 0);
+        if filters.baggage_included && !has_checked_bag {
+            return false;
+        }
+
+        // Check airlines and alliance
         for segment in &offer.outbound.segments {
-            if !filters.passes_airline(&segment.airline) {
+            if !filters.passes_airline(&segment.airline)
+                || !filters.passes_alliance(&segment.airline)
+            {
                 return false;
             }
         }
@@ -310,6 +562,7 @@ impl SearchEngine {
     pub fn clear_cache(&self) {
         let mut cache = self.cache.lock().unwrap();
         cache.clear();
+        self.calendars.lock().unwrap().clear();
     }
 
     /// Get cache stats
@@ -325,6 +578,13 @@ impl Default for SearchEngine {
     }
 }
 
+/// The process-wide search engine, so searches and calendars are cached
+/// across requests
+pub fn global() -> &'static SearchEngine {
+    static GLOBAL: OnceLock<SearchEngine> = OnceLock::new();
+    GLOBAL.get_or_init(SearchEngine::new)
+}
+
 /// Mock provider for testing
 #[cfg(test)]
 pub struct MockProvider {
@@ -361,6 +621,108 @@ impl SearchProvider for MockProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::request::{Alliance, SearchFilters};
+    use crate::types::BaggageAllowance;
+    use crate::types::{CabinClass, FlightLeg, FlightSegment, PriceBreakdown};
+    use time::macros::time;
+    use time::{Date, Month, Time};
+    use vaya_common::{AirlineCode, CurrencyCode, IataCode, MinorUnits};
+
+    /// One-segment offer on the request's first route and date
+    fn offer(
+        id: &str,
+        request: &SearchRequest,
+        airline: AirlineCode,
+        departure: Time,
+        total: i64,
+    ) -> FlightOffer {
+        let date = request.departure_date;
+        FlightOffer {
+            id: id.into(),
+            outbound: FlightLeg {
+                segments: vec![FlightSegment {
+                    airline,
+                    flight_number: format!("{}1", airline.as_str()),
+                    marketing_airline: None,
+                    origin: request.origins[0],
+                    destination: request.destinations[0],
+                    departure_date: date,
+                    departure_time: departure,
+                    arrival_date: date,
+                    arrival_time: departure + time::Duration::hours(7),
+                    duration_minutes: 420,
+                    aircraft: None,
+                    cabin: request.cabin,
+                    booking_class: 'Y',
+                    seats_remaining: None,
+                }],
+                total_duration_minutes: 420,
+            },
+            inbound: None,
+            price: PriceBreakdown {
+                base_fare: MinorUnits::new(total),
+                taxes: MinorUnits::ZERO,
+                surcharges: MinorUnits::ZERO,
+                seats: MinorUnits::ZERO,
+                currency: CurrencyCode::MYR,
+            },
+            price_per_pax: vec![],
+            expires_at: None,
+            provider: "test".into(),
+            refundable: false,
+            changeable: true,
+            baggage: None,
+            fare_rules: None,
+            self_transfer: false,
+            variation: None,
+        }
+    }
+
+    /// Prices each departure date at 100.00 plus 1.00 per day of the month
+    struct CalendarProvider;
+
+    impl SearchProvider for CalendarProvider {
+        fn name(&self) -> &str {
+            "calendar"
+        }
+
+        fn search(&self, request: &SearchRequest) -> SearchResult<Vec<FlightOffer>> {
+            let date = request.departure_date;
+            if date.day() == 13 {
+                return Ok(vec![]);
+            }
+            let total =
+                (10_000 + 100 * i64::from(date.day())) * i64::from(request.passengers.total());
+            let id = format!(
+                "CAL-{}-{}-{}",
+                request.origins[0].as_str(),
+                request.destinations[0].as_str(),
+                date
+            );
+            Ok(vec![offer(
+                &id,
+                request,
+                AirlineCode::AK,
+                time!(08:00),
+                total,
+            )])
+        }
+    }
+
+    /// Has seen a cheap fare on the 20th only
+    struct TwentiethObserved;
+
+    impl ObservedFares for TwentiethObserved {
+        fn cheapest(
+            &self,
+            _origin: &IataCode,
+            _destination: &IataCode,
+            date: Date,
+            _cabin: CabinClass,
+        ) -> Option<(MinorUnits, CurrencyCode)> {
+            (date.day() == 20).then_some((MinorUnits::new(9_000), CurrencyCode::MYR))
+        }
+    }
 
     #[test]
     fn test_search_engine_creation() {
@@ -397,8 +759,180 @@ mod tests {
             duration_ms: 100,
             from_cache: false,
             warnings: vec![],
+            next_cursor: None,
         };
         assert!(!response.has_results());
         assert!(response.cheapest().is_none());
     }
+
+    #[test]
+    fn test_fare_calendar() {
+        let mut engine = SearchEngine::new();
+        engine.add_provider(Box::new(CalendarProvider));
+        let request = CalendarRequest::new(IataCode::KUL, IataCode::NRT, 2030, Month::July);
+
+        let calendar = engine.fare_calendar(&request).unwrap();
+        assert!(!calendar.from_cache);
+        assert_eq!(calendar.days.len(), 31);
+        assert_eq!(calendar.priced_days(), 30);
+        assert!(calendar.days[12].fare.is_none());
+
+        let cheapest = calendar.cheapest().unwrap();
+        assert_eq!(cheapest.date.day(), 1);
+        assert_eq!(cheapest.fare.unwrap().price.as_i64(), 10_100);
+        assert_eq!(cheapest.fare.unwrap().source, FareSource::Live);
+        assert_eq!(cheapest.deal_score, Some(100));
+        assert_eq!(calendar.days[30].deal_score, Some(0));
+
+        // Served from the calendar cache the second time
+        assert!(engine.fare_calendar(&request).unwrap().from_cache);
+    }
+
+    #[test]
+    fn test_fare_calendar_prefers_observed_fares() {
+        let mut engine = SearchEngine::new();
+        engine.add_provider(Box::new(CalendarProvider));
+        engine.set_observed_fares(Box::new(TwentiethObserved));
+        let passengers = crate::types::Passengers {
+            adults: 2,
+            ..Default::default()
+        };
+        let request = CalendarRequest::new(IataCode::KUL, IataCode::NRT, 2030, Month::July)
+            .with_passengers(passengers);
+
+        let calendar = engine.fare_calendar(&request).unwrap();
+        // Live fares are split per passenger
+        assert_eq!(calendar.days[0].fare.unwrap().price.as_i64(), 10_100);
+        let cheapest = calendar.cheapest().unwrap();
+        assert_eq!(cheapest.date.day(), 20);
+        assert_eq!(cheapest.fare.unwrap().source, FareSource::Observed);
+
+        let past = CalendarRequest::new(IataCode::KUL, IataCode::NRT, 2020, Month::July);
+        assert_eq!(engine.fare_calendar(&past).unwrap().priced_days(), 0);
+    }
+
+    #[test]
+    fn test_flexible_search_annotates_variations() {
+        let mut engine = SearchEngine::new();
+        engine.add_provider(Box::new(CalendarProvider));
+        let date = Date::from_calendar_date(2030, Month::July, 10).unwrap();
+        let request = SearchRequest::one_way(IataCode::KUL, IataCode::NRT, date)
+            .with_flex_days(2)
+            .with_nearby_airports();
+
+        let response = engine.search(&request).unwrap();
+        // 5 days x KUL/SZB x NRT/HND
+        assert_eq!(response.total_count, 20);
+        assert!(response.offers.iter().all(|o| o.variation.is_some()));
+
+        // Two days early is cheapest with this provider
+        let cheapest = response.cheapest().unwrap().variation.unwrap();
+        assert_eq!(cheapest.date_offset, -2);
+        assert_eq!(cheapest.departure_date.day(), 8);
+
+        let exact = response
+            .offers
+            .iter()
+            .filter(|o| o.variation.is_some_and(|v| v.is_exact()))
+            .count();
+        assert_eq!(exact, 1);
+
+        let plain = engine
+            .search(&SearchRequest::one_way(IataCode::KUL, IataCode::NRT, date))
+            .unwrap();
+        assert_eq!(plain.total_count, 1);
+        assert!(plain.offers[0].variation.is_none());
+    }
+
+    #[test]
+    fn test_filters_and_sorts_after_aggregation() {
+        let date = Date::from_calendar_date(2030, Month::July, 10).unwrap();
+        let base = SearchRequest::one_way(IataCode::KUL, IataCode::NRT, date);
+        let mut bagged = offer("MH-1", &base, AirlineCode::MH, time!(09:00), 60_000);
+        bagged.baggage = Some(BaggageAllowance::default());
+        let offers = vec![
+            offer("AK-1", &base, AirlineCode::AK, time!(06:00), 40_000),
+            bagged,
+            offer("MH-2", &base, AirlineCode::MH, time!(23:00), 50_000),
+        ];
+        let mut engine = SearchEngine::new();
+        engine.add_provider(Box::new(MockProvider::new("mock").with_offers(offers)));
+
+        let ids = |request: &SearchRequest| -> Vec<String> {
+            let response = engine.search(request).unwrap();
+            response.offers.iter().map(|o| o.id.clone()).collect()
+        };
+
+        let by_departure = base
+            .clone()
+            .with_sort(SortBy::Departure, SortOrder::Descending);
+        assert_eq!(ids(&by_departure), ["MH-2", "MH-1", "AK-1"]);
+
+        let oneworld = base
+            .clone()
+            .with_filters(SearchFilters::default().alliance(Alliance::Oneworld));
+        assert_eq!(ids(&oneworld), ["MH-2", "MH-1"]);
+
+        let morning = base
+            .clone()
+            .with_filters(SearchFilters::default().departing_between(time!(05:00), time!(12:00)));
+        assert_eq!(ids(&morning), ["AK-1", "MH-1"]);
+
+        let with_bags = base
+            .clone()
+            .with_filters(SearchFilters::default().with_baggage());
+        assert_eq!(ids(&with_bags), ["MH-1"]);
+    }
+
+    #[test]
+    fn test_pagination_cursors() {
+        let date = Date::from_calendar_date(2030, Month::July, 10).unwrap();
+        let base = SearchRequest::one_way(IataCode::KUL, IataCode::NRT, date);
+        // Pairs of flights share a price, so ties fall back to the ID
+        let offers = (0..5)
+            .map(|i| {
+                let id = format!("O{}", i);
+                offer(
+                    &id,
+                    &base,
+                    AirlineCode::AK,
+                    time!(08:00) + time::Duration::hours(i),
+                    10_000 * (i / 2 + 1),
+                )
+            })
+            .collect();
+        let mut engine = SearchEngine::new();
+        engine.add_provider(Box::new(MockProvider::new("mock").with_offers(offers)));
+
+        let request = base.clone().with_max_results(2);
+        let first = engine.search(&request).unwrap();
+        assert_eq!(first.total_count, 5);
+        assert_eq!(first.offers.len(), 2);
+        let cursor = first.next_cursor.clone().unwrap();
+
+        let second = engine
+            .search(&request.clone().with_cursor(cursor.clone()))
+            .unwrap();
+        assert!(second.from_cache);
+        let third = engine
+            .search(&request.clone().with_cursor(second.next_cursor.unwrap()))
+            .unwrap();
+        assert!(third.next_cursor.is_none());
+
+        let ids: Vec<&str> = first
+            .offers
+            .iter()
+            .chain(&second.offers)
+            .chain(&third.offers)
+            .map(|o| o.id.as_str())
+            .collect();
+        assert_eq!(ids, ["O0", "O1", "O2", "O3", "O4"]);
+
+        // A cursor only continues the sort it was issued for
+        let resorted = request
+            .with_sort(SortBy::Price, SortOrder::Descending)
+            .with_cursor(cursor);
+        assert!(engine.search(&resorted).is_err());
+        assert!(engine.search(&base.with_cursor("bogus")).is_err());
+    }
 }
@@ -0,0 +1,278 @@
+//! Offer deduplication and fare families
+//!
+//! Providers often return the same physical flights, sometimes as
+//! codeshares under another flight number. Offers are keyed by an itinerary
+//! fingerprint built from each segment's operating carrier, route and
+//! departure time. Identical fares for the same flights are dropped, and the
+//! remaining fares are grouped so the UI can show one row per itinerary
+//! with the cheapest fare and its refundable or baggage alternatives.
+
+use std::collections::HashMap;
+
+use vaya_common::MinorUnits;
+
+use crate::types::{FlightLeg, FlightOffer};
+
+/// Canonical fingerprint of the physical flights in an offer
+///
+/// Marketing flight numbers are ignored, so codeshares match the operating
+/// flight.
+pub fn fingerprint(offer: &FlightOffer) -> String {
+    let leg = |leg: &FlightLeg| {
+        leg.segments
+            .iter()
+            .map(|s| {
+                format!(
+                    "{}:{}-{}:{}T{:02}{:02}",
+                    s.airline.as_str(),
+                    s.origin.as_str(),
+                    s.destination.as_str(),
+                    s.departure_date,
+                    s.departure_time.hour(),
+                    s.departure_time.minute()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|")
+    };
+    match &offer.inbound {
+        Some(inbound) => format!("{}/{}", leg(&offer.outbound), leg(inbound)),
+        None => leg(&offer.outbound),
+    }
+}
+
+/// Does the fare include at least one checked bag
+fn has_checked_bag(offer: &FlightOffer) -> bool {
+    matches!(&offer.baggage, Some(b) if b.checked_bags > 0)
+}
+
+/// Same flights sold on the same terms at the same price
+fn same_fare(a: &FlightOffer, b: &FlightOffer) -> bool {
+    a.price.total() == b.price.total()
+        && a.price.currency == b.price.currency
+        && a.refundable == b.refundable
+        && a.changeable == b.changeable
+        && has_checked_bag(a) == has_checked_bag(b)
+        && a.self_transfer == b.self_transfer
+}
+
+/// Drop offers that repeat an earlier offer's flights and fare terms
+pub fn dedupe_offers(offers: Vec<FlightOffer>) -> Vec<FlightOffer> {
+    let mut seen: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut kept: Vec<FlightOffer> = Vec::with_capacity(offers.len());
+    for offer in offers {
+        let same = seen.entry(fingerprint(&offer)).or_default();
+        if same.iter().any(|&i| same_fare(&kept[i], &offer)) {
+            continue;
+        }
+        same.push(kept.len());
+        kept.push(offer);
+    }
+    kept
+}
+
+/// What distinguishes a fare in a family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FareFeature {
+    /// Cheapest fare for the flights
+    Cheapest,
+    /// Cheapest refundable fare
+    Refundable,
+    /// Cheapest fare with a checked bag
+    Baggage,
+}
+
+impl FareFeature {
+    /// Get feature as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FareFeature::Cheapest => "cheapest",
+            FareFeature::Refundable => "refundable",
+            FareFeature::Baggage => "baggage",
+        }
+    }
+}
+
+/// One entry of a fare family
+#[derive(Debug, Clone)]
+pub struct FareAlternative<'a> {
+    /// Why the fare is listed
+    pub feature: FareFeature,
+    /// The fare
+    pub offer: &'a FlightOffer,
+    /// Price over the cheapest fare
+    pub extra: MinorUnits,
+}
+
+/// Offers for the same physical flights
+#[derive(Debug, Clone)]
+pub struct OfferGroup {
+    /// Itinerary fingerprint
+    pub fingerprint: String,
+    /// Fares, cheapest first
+    pub offers: Vec<FlightOffer>,
+}
+
+impl OfferGroup {
+    /// Cheapest fare
+    pub fn cheapest(&self) -> &FlightOffer {
+        &self.offers[0]
+    }
+
+    /// Cheapest fare plus the cheapest refundable and checked-bag fares
+    ///
+    /// An alternative is listed only when the cheapest fare lacks the feature.
+    pub fn fare_family(&self) -> Vec<FareAlternative<'_>> {
+        let cheapest = self.cheapest();
+        let mut family = vec![alternative(FareFeature::Cheapest, cheapest, cheapest)];
+        if !cheapest.refundable {
+            if let Some(offer) = self.offers.iter().find(|o| o.refundable) {
+                family.push(alternative(FareFeature::Refundable, offer, cheapest));
+            }
+        }
+        if !has_checked_bag(cheapest) {
+            if let Some(offer) = self.offers.iter().find(|o| has_checked_bag(o)) {
+                family.push(alternative(FareFeature::Baggage, offer, cheapest));
+            }
+        }
+        family
+    }
+}
+
+fn alternative<'a>(
+    feature: FareFeature,
+    offer: &'a FlightOffer,
+    cheapest: &FlightOffer,
+) -> FareAlternative<'a> {
+    FareAlternative {
+        feature,
+        offer,
+        extra: MinorUnits::new(offer.price.total().as_i64() - cheapest.price.total().as_i64()),
+    }
+}
+
+/// Group offers by fingerprint, cheapest group first
+pub fn group_offers(offers: impl IntoIterator<Item = FlightOffer>) -> Vec<OfferGroup> {
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<OfferGroup> = Vec::new();
+    for offer in offers {
+        let key = fingerprint(&offer);
+        match index.get(&key) {
+            Some(&i) => groups[i].offers.push(offer),
+            None => {
+                index.insert(key.clone(), groups.len());
+                groups.push(OfferGroup {
+                    fingerprint: key,
+                    offers: vec![offer],
+                });
+            }
+        }
+    }
+    for group in &mut groups {
+        group.offers.sort_by_key(|o| o.price.total().as_i64());
+    }
+    groups.sort_by_key(|g| g.cheapest().price.total().as_i64());
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BaggageAllowance, CabinClass, FlightSegment, PriceBreakdown};
+    use time::macros::{date, time};
+    use vaya_common::{AirlineCode, CurrencyCode, IataCode};
+
+    fn offer(id: &str, provider: &str, marketing: Option<AirlineCode>, total: i64) -> FlightOffer {
+        let date = date!(2030 - 06 - 01);
+        FlightOffer {
+            id: id.into(),
+            outbound: FlightLeg {
+                segments: vec![FlightSegment {
+                    airline: AirlineCode::MH,
+                    flight_number: "MH88".into(),
+                    marketing_airline: marketing,
+                    origin: IataCode::KUL,
+                    destination: IataCode::NRT,
+                    departure_date: date,
+                    departure_time: time!(23:30),
+                    arrival_date: date!(2030 - 06 - 02),
+                    arrival_time: time!(07:30),
+                    duration_minutes: 420,
+                    aircraft: None,
+                    cabin: CabinClass::Economy,
+                    booking_class: 'Y',
+                    seats_remaining: None,
+                }],
+                total_duration_minutes: 420,
+            },
+            inbound: None,
+            price: PriceBreakdown {
+                base_fare: MinorUnits::new(total),
+                taxes: MinorUnits::ZERO,
+                surcharges: MinorUnits::ZERO,
+                seats: MinorUnits::ZERO,
+                currency: CurrencyCode::MYR,
+            },
+            price_per_pax: vec![],
+            expires_at: None,
+            provider: provider.into(),
+            refundable: false,
+            changeable: false,
+            baggage: None,
+            fare_rules: None,
+            self_transfer: false,
+            variation: None,
+        }
+    }
+
+    #[test]
+    fn test_dedupe_codeshares() {
+        let operated = offer("a", "amadeus", None, 100_000);
+        let codeshare = offer("b", "sabre", Some(AirlineCode::AK), 100_000);
+        assert_eq!(fingerprint(&operated), fingerprint(&codeshare));
+        assert_eq!(fingerprint(&operated), "MH:KUL-NRT:2030-06-01T2330");
+
+        let dearer = offer("c", "sabre", None, 120_000);
+        let offers = dedupe_offers(vec![operated, codeshare, dearer]);
+        let ids: Vec<&str> = offers.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c"]);
+    }
+
+    #[test]
+    fn test_fare_family() {
+        let mut refundable = offer("flex", "amadeus", None, 150_000);
+        refundable.refundable = true;
+        let mut bagged = offer("bag", "amadeus", None, 120_000);
+        bagged.baggage = Some(BaggageAllowance {
+            checked_bags: 1,
+            checked_weight_kg: Some(20),
+            ..BaggageAllowance::default()
+        });
+        let mut other = offer("other", "amadeus", None, 90_000);
+        other.outbound.segments[0].departure_time = time!(09:00);
+
+        let groups = group_offers(vec![
+            refundable,
+            bagged,
+            offer("lite", "amadeus", None, 100_000),
+            other,
+        ]);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].cheapest().id, "other");
+        assert_eq!(groups[0].fare_family().len(), 1);
+
+        let family = groups[1].fare_family();
+        let entries: Vec<(&str, &str, i64)> = family
+            .iter()
+            .map(|f| (f.feature.as_str(), f.offer.id.as_str(), f.extra.as_i64()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("cheapest", "lite", 0),
+                ("refundable", "flex", 50_000),
+                ("baggage", "bag", 20_000),
+            ]
+        );
+    }
+}
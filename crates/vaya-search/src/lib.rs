@@ -4,6 +4,12 @@
 //! - Flight search request/response types
 //! - Search filtering and sorting
 //! - Multi-provider aggregation
+//! - Offer deduplication and fare families
+//! - Flexible-date and nearby-airport expansion
+//! - Self-transfer routing through hub airports
+//! - Fare calendars with the cheapest fare per day
+//! - Local airport autocomplete
+//! - Market-aware default search parameters
 //! - Result caching
 //!
 //! # Example
@@ -24,15 +30,36 @@
 //! // Add providers and search...
 //! ```
 
+pub mod airports;
+pub mod calendar;
+pub mod connection;
+pub mod defaults;
 pub mod engine;
 pub mod error;
+pub mod grouping;
 pub mod request;
+pub mod routing;
 pub mod types;
 
+pub use airports::{AirportAutocomplete, AirportSuggestion, SuggestionKind};
+pub use calendar::{
+    CalendarDay, CalendarRequest, DayFare, FareCalendar, FareSource, ObservedFares,
+};
+pub use connection::{ConnectionAssessment, ConnectionRisk, ConnectionRiskModel};
+pub use defaults::{
+    DefaultsConfig, DefaultsQuery, GeoIpResolver, Market, OriginSource, PopularRoutes, PrefixGeoIp,
+    SearchDefaults, SearchDefaultsService,
+};
 pub use engine::{SearchEngine, SearchEngineConfig, SearchProvider, SearchResponse};
 pub use error::{SearchError, SearchResult};
-pub use request::{Alliance, SearchFilters, SearchRequest, SortBy, SortOrder};
+pub use grouping::{
+    dedupe_offers, fingerprint, group_offers, FareAlternative, FareFeature, OfferGroup,
+};
+pub use request::{Alliance, SearchFilters, SearchRequest, SortBy, SortOrder, MAX_FLEX_DAYS};
+pub use routing::{
+    FareGraph, RoutingConfig, RoutingEngine, SelfTransferRoute, SELF_TRANSFER_PROVIDER,
+};
 pub use types::{
     BaggageAllowance, CabinClass, FlightLeg, FlightOffer, FlightSegment, PassengerType, Passengers,
-    PriceBreakdown, TripType,
+    PriceBreakdown, SearchVariation, TripType,
 };
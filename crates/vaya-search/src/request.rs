@@ -1,11 +1,14 @@
 //! Search request types
 
-use time::Date;
+use time::{Date, Duration};
 use vaya_common::{AirlineCode, IataCode};
 
-use crate::types::{CabinClass, Passengers, TripType};
+use crate::types::{CabinClass, Passengers, SearchVariation, TripType};
 use crate::{SearchError, SearchResult};
 
+/// Maximum days either side of the requested date for flexible searches
+pub const MAX_FLEX_DAYS: u8 = 3;
+
 /// A search request
 #[derive(Debug, Clone)]
 pub struct SearchRequest {
@@ -27,6 +30,14 @@ pub struct SearchRequest {
     pub filters: SearchFilters,
     /// Maximum results to return
     pub max_results: Option<usize>,
+    /// Also search this many days either side of the departure date
+    pub flex_days: u8,
+    /// Sort criteria
+    pub sort_by: SortBy,
+    /// Sort order
+    pub sort_order: SortOrder,
+    /// Cursor from a previous page's response
+    pub cursor: Option<String>,
 }
 
 impl SearchRequest {
@@ -42,6 +53,10 @@ impl SearchRequest {
             cabin: CabinClass::Economy,
             filters: SearchFilters::default(),
             max_results: None,
+            flex_days: 0,
+            sort_by: SortBy::default(),
+            sort_order: SortOrder::default(),
+            cursor: None,
         }
     }
 
@@ -62,6 +77,10 @@ impl SearchRequest {
             cabin: CabinClass::Economy,
             filters: SearchFilters::default(),
             max_results: None,
+            flex_days: 0,
+            sort_by: SortBy::default(),
+            sort_order: SortOrder::default(),
+            cursor: None,
         }
     }
 
@@ -89,6 +108,106 @@ impl SearchRequest {
         self
     }
 
+    /// Set sort criteria and order
+    pub fn with_sort(mut self, by: SortBy, order: SortOrder) -> Self {
+        self.sort_by = by;
+        self.sort_order = order;
+        self
+    }
+
+    /// Continue from a previous page
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Also search up to `days` either side of the departure date
+    ///
+    /// Round trips keep their length, so the return date moves too.
+    pub fn with_flex_days(mut self, days: u8) -> Self {
+        self.flex_days = days;
+        self
+    }
+
+    /// Also search other airports serving the origin and destination cities
+    pub fn with_nearby_airports(mut self) -> Self {
+        self.filters.include_nearby = true;
+        self
+    }
+
+    /// Does this request expand into several searches
+    pub fn is_flexible(&self) -> bool {
+        self.flex_days > 0 || self.filters.include_nearby
+    }
+
+    /// Expand into one single-route, single-date request per variation
+    ///
+    /// The exact route and date come first, then nearer dates before further
+    /// ones. Nearby airports come from the embedded airport dataset.
+    pub fn variations(&self) -> Vec<(SearchVariation, SearchRequest)> {
+        let expand = |codes: &[IataCode]| {
+            let mut expanded: Vec<(IataCode, bool)> = codes.iter().map(|c| (*c, false)).collect();
+            if self.filters.include_nearby {
+                for code in codes {
+                    for airport in crate::airports::global().metro_airports(code.as_str()) {
+                        let nearby = IataCode::new(airport.code);
+                        if !expanded.iter().any(|(c, _)| *c == nearby) {
+                            expanded.push((nearby, true));
+                        }
+                    }
+                }
+            }
+            expanded
+        };
+        let origins = expand(&self.origins);
+        let destinations = expand(&self.destinations);
+
+        let flex = self.flex_days as i8;
+        let mut offsets = vec![0];
+        for days in 1..=flex {
+            offsets.extend([-days, days]);
+        }
+
+        let mut variations = Vec::new();
+        for offset in offsets {
+            let shift = Duration::days(i64::from(offset));
+            let Some(departure) = self.departure_date.checked_add(shift) else {
+                continue;
+            };
+            let return_date = match self.return_date {
+                Some(ret) => match ret.checked_add(shift) {
+                    Some(ret) => Some(ret),
+                    None => continue,
+                },
+                None => None,
+            };
+            for &(origin, nearby_origin) in &origins {
+                for &(destination, nearby_destination) in &destinations {
+                    if origin == destination {
+                        continue;
+                    }
+                    let variation = SearchVariation {
+                        origin,
+                        destination,
+                        departure_date: departure,
+                        date_offset: offset,
+                        nearby_origin,
+                        nearby_destination,
+                    };
+                    let mut request = self.clone();
+                    request.origins = vec![origin];
+                    request.destinations = vec![destination];
+                    request.departure_date = departure;
+                    request.return_date = return_date;
+                    request.flex_days = 0;
+                    request.filters.include_nearby = false;
+                    variations.push((variation, request));
+                }
+            }
+        }
+        variations
+    }
+
     /// Validate the search request
     pub fn validate(&self) -> SearchResult<()> {
         // Check origins
@@ -118,6 +237,13 @@ impl SearchRequest {
             return Err(SearchError::InvalidParams("Invalid passenger count".into()));
         }
 
+        if self.flex_days > MAX_FLEX_DAYS {
+            return Err(SearchError::InvalidParams(format!(
+                "Date flexibility is limited to {} days",
+                MAX_FLEX_DAYS
+            )));
+        }
+
         // Check return date for round trips
         if self.trip_type == TripType::RoundTrip {
             match self.return_date {
@@ -145,7 +271,7 @@ impl SearchRequest {
             .map(|a: &IataCode| a.as_str())
             .collect();
 
-        format!(
+        let mut key = format!(
             "search:{}:{}:{}:{}:{}:{}:{}",
             origins.join(","),
             dests.join(","),
@@ -154,7 +280,15 @@ impl SearchRequest {
             self.cabin.code(),
             self.passengers.adults,
             self.passengers.children + self.passengers.infants
-        )
+        );
+        if self.is_flexible() {
+            key.push_str(&format!(
+                ":flex{}{}",
+                self.flex_days,
+                if self.filters.include_nearby { "n" } else { "" }
+            ));
+        }
+        key
     }
 }
 
@@ -183,6 +317,8 @@ pub struct SearchFilters {
     pub refundable_only: bool,
     /// Require flexible fares
     pub flexible_only: bool,
+    /// Require at least one checked bag in the fare
+    pub baggage_included: bool,
     /// Include nearby airports
     pub include_nearby: bool,
     /// Preferred connection airports
@@ -208,6 +344,28 @@ impl Alliance {
             Alliance::SkyTeam => "SkyTeam",
         }
     }
+
+    /// Member airline codes
+    pub fn members(&self) -> &'static [&'static str] {
+        match self {
+            Alliance::StarAlliance => &[
+                "A3", "AC", "AI", "AV", "BR", "CA", "CM", "ET", "LH", "LO", "LX", "MS", "NH", "NZ",
+                "OS", "OU", "OZ", "SA", "SN", "SQ", "TG", "TK", "TP", "UA", "ZH",
+            ],
+            Alliance::Oneworld => &[
+                "AA", "AS", "AT", "AY", "BA", "CX", "FJ", "IB", "JL", "MH", "QF", "QR", "RJ", "UL",
+            ],
+            Alliance::SkyTeam => &[
+                "AF", "AM", "AR", "CI", "DL", "GA", "KE", "KL", "KQ", "ME", "MF", "MU", "RO", "SV",
+                "UX", "VN", "VS",
+            ],
+        }
+    }
+
+    /// Is the airline a member
+    pub fn includes(&self, airline: &AirlineCode) -> bool {
+        self.members().contains(&airline.as_str())
+    }
 }
 
 impl SearchFilters {
@@ -243,6 +401,25 @@ impl SearchFilters {
         self
     }
 
+    /// Only depart between two times of day (the window may wrap past midnight)
+    pub fn departing_between(mut self, from: time::Time, to: time::Time) -> Self {
+        self.min_departure_time = Some(from);
+        self.max_departure_time = Some(to);
+        self
+    }
+
+    /// Only fly with one alliance
+    pub fn alliance(mut self, alliance: Alliance) -> Self {
+        self.alliance = Some(alliance);
+        self
+    }
+
+    /// Require a checked bag in the fare
+    pub fn with_baggage(mut self) -> Self {
+        self.baggage_included = true;
+        self
+    }
+
     /// Check if a number of stops passes the filter
     pub fn passes_stops(&self, stops: usize) -> bool {
         match self.max_stops {
@@ -286,6 +463,42 @@ impl SearchFilters {
             None => true,
         }
     }
+
+    /// Check if a departure time passes the filter
+    pub fn passes_departure_time(&self, at: time::Time) -> bool {
+        in_window(at, self.min_departure_time, self.max_departure_time)
+    }
+
+    /// Check if an arrival time passes the filter
+    pub fn passes_arrival_time(&self, at: time::Time) -> bool {
+        in_window(at, self.min_arrival_time, self.max_arrival_time)
+    }
+
+    /// Check if an airline passes the alliance filter
+    pub fn passes_alliance(&self, airline: &AirlineCode) -> bool {
+        match self.alliance {
+            Some(alliance) => alliance.includes(airline),
+            None => true,
+        }
+    }
+}
+
+/// Is a time of day inside a window; `from` after `to` wraps past midnight
+fn in_window(at: time::Time, from: Option<time::Time>, to: Option<time::Time>) -> bool {
+    match (from, to) {
+        (Some(from), Some(to)) if from > to => at >= from || at <= to,
+        (from, to) => {
+            let after_start = match from {
+                Some(from) => at >= from,
+                None => true,
+            };
+            let before_end = match to {
+                Some(to) => at <= to,
+                None => true,
+            };
+            after_start && before_end
+        }
+    }
 }
 
 /// Sort options for results
@@ -299,6 +512,19 @@ pub enum SortBy {
     Stops,
 }
 
+impl SortBy {
+    /// Get sort criteria as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortBy::Price => "price",
+            SortBy::Duration => "duration",
+            SortBy::Departure => "departure",
+            SortBy::Arrival => "arrival",
+            SortBy::Stops => "stops",
+        }
+    }
+}
+
 /// Sort order
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SortOrder {
@@ -307,6 +533,16 @@ pub enum SortOrder {
     Descending,
 }
 
+impl SortOrder {
+    /// Get sort order as string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Ascending => "asc",
+            SortOrder::Descending => "desc",
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -368,6 +604,26 @@ mod tests {
         assert!(!filters.passes_price(50001));
     }
 
+    #[test]
+    fn test_filters_time_windows_and_alliance() {
+        use time::macros::time;
+
+        let filters = SearchFilters::default().departing_between(time!(06:00), time!(12:00));
+        assert!(filters.passes_departure_time(time!(06:00)));
+        assert!(!filters.passes_departure_time(time!(13:00)));
+        assert!(filters.passes_arrival_time(time!(23:59)));
+
+        // Red-eye window wraps past midnight
+        let red_eye = SearchFilters::default().departing_between(time!(22:00), time!(02:00));
+        assert!(red_eye.passes_departure_time(time!(23:30)));
+        assert!(red_eye.passes_departure_time(time!(01:00)));
+        assert!(!red_eye.passes_departure_time(time!(12:00)));
+
+        let oneworld = SearchFilters::default().alliance(Alliance::Oneworld);
+        assert!(oneworld.passes_alliance(&AirlineCode::MH));
+        assert!(!oneworld.passes_alliance(&AirlineCode::AK));
+    }
+
     #[test]
     fn test_cache_key() {
         let origin = IataCode::SIN;
@@ -380,4 +636,41 @@ mod tests {
         assert!(key.contains("SIN"));
         assert!(key.contains("NRT"));
     }
+
+    #[test]
+    fn test_flexible_variations() {
+        let date = Date::from_calendar_date(2030, time::Month::July, 10).unwrap();
+        let ret = Date::from_calendar_date(2030, time::Month::July, 17).unwrap();
+        let request = SearchRequest::round_trip(IataCode::KUL, IataCode::NRT, date, ret)
+            .with_flex_days(1)
+            .with_nearby_airports();
+        assert!(request.is_flexible());
+        assert_ne!(
+            request.cache_key(),
+            SearchRequest::round_trip(IataCode::KUL, IataCode::NRT, date, ret).cache_key()
+        );
+
+        let variations = request.variations();
+        // KUL/SZB x NRT/HND x 3 days
+        assert_eq!(variations.len(), 12);
+        let (exact, first) = &variations[0];
+        assert!(exact.is_exact());
+        assert_eq!(first.origins, vec![IataCode::KUL]);
+        assert!(!first.is_flexible());
+
+        let (variation, shifted) = variations
+            .iter()
+            .find(|(v, _)| v.date_offset == -1 && v.nearby_origin && v.nearby_destination)
+            .unwrap();
+        assert_eq!(variation.origin.as_str(), "SZB");
+        assert_eq!(variation.destination, IataCode::HND);
+        assert_eq!(shifted.departure_date.day(), 9);
+        assert_eq!(shifted.return_date.unwrap().day(), 16);
+
+        assert!(request
+            .clone()
+            .with_flex_days(MAX_FLEX_DAYS + 1)
+            .validate()
+            .is_err());
+    }
 }
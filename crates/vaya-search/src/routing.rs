@@ -0,0 +1,433 @@
+//! Multi-hop routing through hub airports
+//!
+//! GDS queries only return itineraries the airlines sell on one ticket, so
+//! cheap combinations of separate tickets never show up. The routing engine
+//! keeps a [`FareGraph`] of cached one-way fares and joins two legs through
+//! configured hubs into self-transfer offers, priced as the sum of both
+//! tickets and scored with the [`ConnectionRiskModel`].
+
+use std::collections::HashMap;
+
+use vaya_common::{IataCode, MinorUnits};
+
+use crate::connection::{ConnectionAssessment, ConnectionRisk, ConnectionRiskModel};
+use crate::request::SearchRequest;
+use crate::types::{BaggageAllowance, FlightLeg, FlightOffer, PriceBreakdown, TripType};
+
+/// Provider name used for self-transfer offers
+pub const SELF_TRANSFER_PROVIDER: &str = "self_transfer";
+
+/// Graph of cached one-way fares, keyed by departure airport
+#[derive(Debug, Clone, Default)]
+pub struct FareGraph {
+    edges: HashMap<IataCode, Vec<FlightOffer>>,
+}
+
+impl FareGraph {
+    /// Create an empty graph
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a graph from offers
+    pub fn from_offers(offers: impl IntoIterator<Item = FlightOffer>) -> Self {
+        let mut graph = Self::new();
+        for offer in offers {
+            graph.insert(offer);
+        }
+        graph
+    }
+
+    /// Add a fare, replacing any fare with the same ID
+    ///
+    /// Round trips and self-transfer combinations are ignored.
+    pub fn insert(&mut self, offer: FlightOffer) {
+        if offer.is_round_trip() || offer.self_transfer {
+            return;
+        }
+        let Some(origin) = offer.outbound.origin().copied() else {
+            return;
+        };
+        let edges = self.edges.entry(origin).or_default();
+        edges.retain(|o| o.id != offer.id);
+        edges.push(offer);
+    }
+
+    /// Drop expired fares
+    pub fn prune_expired(&mut self) {
+        for edges in self.edges.values_mut() {
+            edges.retain(|o| !o.is_expired());
+        }
+        self.edges.retain(|_, edges| !edges.is_empty());
+    }
+
+    /// Fares departing an airport
+    pub fn departures(&self, from: &IataCode) -> &[FlightOffer] {
+        self.edges.get(from).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Fares between two airports
+    pub fn fares<'a>(
+        &'a self,
+        from: &IataCode,
+        to: &'a IataCode,
+    ) -> impl Iterator<Item = &'a FlightOffer> + 'a {
+        self.departures(from)
+            .iter()
+            .filter(move |o| o.outbound.destination() == Some(to) && !o.is_expired())
+    }
+
+    /// Number of cached fares
+    pub fn len(&self) -> usize {
+        self.edges.values().map(Vec::len).sum()
+    }
+
+    /// Whether the graph has no fares
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Routing engine configuration
+#[derive(Debug, Clone)]
+pub struct RoutingConfig {
+    /// Airports allowed as self-transfer points
+    pub hubs: Vec<IataCode>,
+    /// Shortest layover considered
+    pub min_layover_minutes: u16,
+    /// Longest layover considered
+    pub max_layover_minutes: u16,
+    /// Highest connection risk offered
+    pub max_risk: ConnectionRisk,
+    /// Maximum combinations returned per search
+    pub max_results: usize,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            hubs: vec![IataCode::KUL, IataCode::SIN, IataCode::BKK, IataCode::HKG],
+            min_layover_minutes: 120,
+            max_layover_minutes: 720,
+            max_risk: ConnectionRisk::Medium,
+            max_results: 20,
+        }
+    }
+}
+
+/// A self-transfer combination of two separate tickets
+#[derive(Debug, Clone)]
+pub struct SelfTransferRoute {
+    /// Combined offer
+    pub offer: FlightOffer,
+    /// First ticket
+    pub first: FlightOffer,
+    /// Second ticket
+    pub second: FlightOffer,
+    /// Connection at the hub
+    pub connection: ConnectionAssessment,
+}
+
+/// Builds self-transfer routes from cached fares
+#[derive(Debug, Clone, Default)]
+pub struct RoutingEngine {
+    config: RoutingConfig,
+    risk: ConnectionRiskModel,
+}
+
+impl RoutingEngine {
+    /// Create with default hubs and constraints
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create with custom config
+    pub fn with_config(config: RoutingConfig) -> Self {
+        Self {
+            config,
+            risk: ConnectionRiskModel::default(),
+        }
+    }
+
+    /// Set the connection risk model
+    pub fn with_risk_model(mut self, risk: ConnectionRiskModel) -> Self {
+        self.risk = risk;
+        self
+    }
+
+    /// Get the config
+    pub fn config(&self) -> &RoutingConfig {
+        &self.config
+    }
+
+    /// Find 2-leg self-transfer routes for a one-way request
+    ///
+    /// Results are sorted by price, then connection risk.
+    pub fn routes(&self, graph: &FareGraph, request: &SearchRequest) -> Vec<SelfTransferRoute> {
+        if request.trip_type != TripType::OneWay {
+            return Vec::new();
+        }
+
+        let mut routes = Vec::new();
+        for origin in &request.origins {
+            for destination in &request.destinations {
+                for hub in &self.config.hubs {
+                    if hub == origin || hub == destination {
+                        continue;
+                    }
+                    for first in graph.fares(origin, hub) {
+                        if first.outbound.departure_date() != Some(request.departure_date) {
+                            continue;
+                        }
+                        for second in graph.fares(hub, destination) {
+                            if let Some(route) = self.combine(first, second) {
+                                routes.push(route);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        routes.sort_by_key(|r| (r.offer.price.total().as_i64(), r.connection.risk));
+        routes.truncate(self.config.max_results);
+        routes
+    }
+
+    /// Join two tickets if the connection meets the constraints
+    fn combine(&self, first: &FlightOffer, second: &FlightOffer) -> Option<SelfTransferRoute> {
+        if first.price.currency != second.price.currency {
+            return None;
+        }
+        let arriving = first.outbound.segments.last()?;
+        let departing = second.outbound.segments.first()?;
+        let connection = self.risk.assess(arriving, departing, true)?;
+
+        let layover = connection.layover_minutes;
+        if layover < self.config.min_layover_minutes as i64
+            || layover > self.config.max_layover_minutes as i64
+            || connection.risk > self.config.max_risk
+        {
+            return None;
+        }
+
+        // Per-passenger totals for the types priced on both tickets
+        let price_per_pax = first
+            .price_per_pax
+            .iter()
+            .filter_map(|(pax, amount)| {
+                second
+                    .price_per_pax
+                    .iter()
+                    .find(|(p, _)| p == pax)
+                    .map(|(_, other)| (*pax, MinorUnits::new(amount.as_i64() + other.as_i64())))
+            })
+            .collect();
+
+        let mut segments = first.outbound.segments.clone();
+        segments.extend(second.outbound.segments.iter().cloned());
+        let total_duration_minutes = first.outbound.total_duration_minutes as i64
+            + layover
+            + second.outbound.total_duration_minutes as i64;
+
+        let offer = FlightOffer {
+            id: format!("ST-{}-{}", first.id, second.id),
+            outbound: FlightLeg {
+                segments,
+                total_duration_minutes: total_duration_minutes.min(u16::MAX as i64) as u16,
+            },
+            inbound: None,
+            price: PriceBreakdown {
+                base_fare: MinorUnits::new(
+                    first.price.base_fare.as_i64() + second.price.base_fare.as_i64(),
+                ),
+                taxes: MinorUnits::new(first.price.taxes.as_i64() + second.price.taxes.as_i64()),
+                surcharges: MinorUnits::new(
+                    first.price.surcharges.as_i64() + second.price.surcharges.as_i64(),
+                ),
+                seats: MinorUnits::new(first.price.seats.as_i64() + second.price.seats.as_i64()),
+                currency: first.price.currency,
+            },
+            price_per_pax,
+            expires_at: match (first.expires_at, second.expires_at) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+            provider: SELF_TRANSFER_PROVIDER.to_string(),
+            refundable: first.refundable && second.refundable,
+            changeable: first.changeable && second.changeable,
+            baggage: match (&first.baggage, &second.baggage) {
+                (Some(a), Some(b)) => Some(BaggageAllowance {
+                    carry_on: a.carry_on.min(b.carry_on),
+                    carry_on_weight_kg: a.carry_on_weight_kg.min(b.carry_on_weight_kg),
+                    checked_bags: a.checked_bags.min(b.checked_bags),
+                    checked_weight_kg: a.checked_weight_kg.min(b.checked_weight_kg),
+                }),
+                _ => None,
+            },
+            fare_rules: Some(format!(
+                "Self-transfer at {}: separate tickets, collect and re-check bags, missed connections are not protected",
+                connection.airport.as_str()
+            )),
+            self_transfer: true,
+            variation: None,
+        };
+
+        Some(SelfTransferRoute {
+            offer,
+            first: first.clone(),
+            second: second.clone(),
+            connection,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CabinClass, FlightSegment};
+    use time::macros::{date, time};
+    use time::Time;
+    use vaya_common::{AirlineCode, CurrencyCode};
+
+    fn fare(
+        id: &str,
+        from: IataCode,
+        to: IataCode,
+        dep: Time,
+        arr: Time,
+        total: i64,
+    ) -> FlightOffer {
+        FlightOffer {
+            id: id.into(),
+            outbound: FlightLeg {
+                segments: vec![FlightSegment {
+                    airline: AirlineCode::AK,
+                    flight_number: id.into(),
+                    marketing_airline: None,
+                    origin: from,
+                    destination: to,
+                    departure_date: date!(2025 - 01 - 15),
+                    departure_time: dep,
+                    arrival_date: date!(2025 - 01 - 15),
+                    arrival_time: arr,
+                    duration_minutes: (arr - dep).whole_minutes() as u16,
+                    aircraft: None,
+                    cabin: CabinClass::Economy,
+                    booking_class: 'Y',
+                    seats_remaining: None,
+                }],
+                total_duration_minutes: (arr - dep).whole_minutes() as u16,
+            },
+            inbound: None,
+            price: PriceBreakdown {
+                base_fare: MinorUnits::new(total),
+                taxes: MinorUnits::ZERO,
+                surcharges: MinorUnits::ZERO,
+                seats: MinorUnits::ZERO,
+                currency: CurrencyCode::MYR,
+            },
+            price_per_pax: vec![],
+            expires_at: None,
+            provider: "test".into(),
+            refundable: false,
+            changeable: true,
+            baggage: None,
+            fare_rules: None,
+            self_transfer: false,
+            variation: None,
+        }
+    }
+
+    #[test]
+    fn test_routes_through_hub() {
+        let graph = FareGraph::from_offers([
+            fare(
+                "A",
+                IataCode::SYD,
+                IataCode::KUL,
+                time!(6:00),
+                time!(12:00),
+                30_000,
+            ),
+            // Connects with enough time
+            fare(
+                "B",
+                IataCode::KUL,
+                IataCode::NRT,
+                time!(16:00),
+                time!(23:00),
+                25_000,
+            ),
+            // Too tight for a self-transfer
+            fare(
+                "C",
+                IataCode::KUL,
+                IataCode::NRT,
+                time!(13:00),
+                time!(20:00),
+                10_000,
+            ),
+            // Departs before arrival
+            fare(
+                "D",
+                IataCode::KUL,
+                IataCode::NRT,
+                time!(9:00),
+                time!(16:00),
+                10_000,
+            ),
+        ]);
+        let request = SearchRequest::one_way(IataCode::SYD, IataCode::NRT, date!(2025 - 01 - 15));
+
+        let routes = RoutingEngine::new().routes(&graph, &request);
+        assert_eq!(routes.len(), 1);
+        let route = &routes[0];
+        assert_eq!(route.offer.id, "ST-A-B");
+        assert!(route.offer.self_transfer);
+        assert_eq!(route.offer.price.total().as_i64(), 55_000);
+        assert_eq!(route.offer.outbound.stops(), 1);
+        assert_eq!(route.connection.layover_minutes, 240);
+        assert_eq!(
+            route.offer.outbound.total_duration_minutes,
+            6 * 60 + 240 + 7 * 60
+        );
+        assert!(!route.offer.refundable);
+    }
+
+    #[test]
+    fn test_fare_graph_ignores_combinations() {
+        let mut combined = fare(
+            "X",
+            IataCode::SIN,
+            IataCode::NRT,
+            time!(8:00),
+            time!(9:00),
+            1,
+        );
+        combined.self_transfer = true;
+        let mut graph = FareGraph::new();
+        graph.insert(combined);
+        graph.insert(fare(
+            "Y",
+            IataCode::SIN,
+            IataCode::NRT,
+            time!(8:00),
+            time!(9:00),
+            1,
+        ));
+        graph.insert(fare(
+            "Y",
+            IataCode::SIN,
+            IataCode::NRT,
+            time!(8:00),
+            time!(9:00),
+            2,
+        ));
+        assert_eq!(graph.len(), 1);
+        assert_eq!(
+            graph.departures(&IataCode::SIN)[0].price.total().as_i64(),
+            2
+        );
+    }
+}
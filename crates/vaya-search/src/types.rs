@@ -218,14 +218,21 @@ pub struct PriceBreakdown {
     pub taxes: MinorUnits,
     /// Surcharges
     pub surcharges: MinorUnits,
+    /// Paid seat selections
+    pub seats: MinorUnits,
     /// Currency
     pub currency: CurrencyCode,
 }
 
 impl PriceBreakdown {
+    /// Fare price, excluding seat selections
+    pub fn fare(&self) -> MinorUnits {
+        MinorUnits::new(self.base_fare.as_i64() + self.taxes.as_i64() + self.surcharges.as_i64())
+    }
+
     /// Total price
     pub fn total(&self) -> MinorUnits {
-        MinorUnits::new(self.base_fare.as_i64() + self.taxes.as_i64() + self.surcharges.as_i64())
+        MinorUnits::new(self.fare().as_i64() + self.seats.as_i64())
     }
 }
 
@@ -254,6 +261,10 @@ pub struct FlightOffer {
     pub baggage: Option<BaggageAllowance>,
     /// Fare rules summary
     pub fare_rules: Option<String>,
+    /// Separate tickets joined by the routing engine (no through protection)
+    pub self_transfer: bool,
+    /// Variation of a flexible search that produced this offer
+    pub variation: Option<SearchVariation>,
 }
 
 impl FlightOffer {
@@ -283,6 +294,30 @@ impl FlightOffer {
     }
 }
 
+/// One expanded query of a flexible-date or nearby-airport search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchVariation {
+    /// Origin airport searched
+    pub origin: IataCode,
+    /// Destination airport searched
+    pub destination: IataCode,
+    /// Departure date searched
+    pub departure_date: Date,
+    /// Days from the requested departure date
+    pub date_offset: i8,
+    /// Origin is a nearby airport rather than the requested one
+    pub nearby_origin: bool,
+    /// Destination is a nearby airport rather than the requested one
+    pub nearby_destination: bool,
+}
+
+impl SearchVariation {
+    /// Is this the exact route and date requested
+    pub fn is_exact(&self) -> bool {
+        self.date_offset == 0 && !self.nearby_origin && !self.nearby_destination
+    }
+}
+
 /// Baggage allowance
 #[derive(Debug, Clone)]
 pub struct BaggageAllowance {
@@ -350,6 +385,7 @@ mod tests {
             base_fare: MinorUnits::new(10000),
             taxes: MinorUnits::new(2000),
             surcharges: MinorUnits::new(500),
+            seats: MinorUnits::ZERO,
             currency: CurrencyCode::SGD,
         };
         assert_eq!(price.total().as_i64(), 12500);
@@ -37,6 +37,26 @@ pub enum StoreError {
     Serialization(String),
     /// Record not found
     NotFound,
+    /// Record was changed since it was read
+    Conflict {
+        /// Version the update was based on
+        expected: i64,
+        /// Version currently stored
+        actual: i64,
+    },
+    /// Personal data would leave the store without a redaction rule
+    PiiExposure(String),
+    /// A schema migration could not be planned or run
+    Migration(String),
+    /// A row in an import file was rejected
+    Import {
+        /// Line the row starts on
+        line: usize,
+        /// Why it was rejected
+        error: Box<StoreError>,
+    },
+    /// Reading or writing an import or export file failed
+    Io(std::io::Error),
 }
 
 impl fmt::Display for StoreError {
@@ -58,6 +78,19 @@ impl fmt::Display for StoreError {
             StoreError::InvalidQuery(msg) => write!(f, "Invalid query: {}", msg),
             StoreError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
             StoreError::NotFound => write!(f, "Record not found"),
+            StoreError::Conflict { expected, actual } => write!(
+                f,
+                "Version conflict: expected {}, found {}",
+                expected, actual
+            ),
+            StoreError::PiiExposure(col) => {
+                write!(f, "No redaction rule for personal data in: {}", col)
+            }
+            StoreError::Migration(msg) => write!(f, "Migration error: {}", msg),
+            StoreError::Import { line, error } => {
+                write!(f, "Import failed at line {}: {}", line, error)
+            }
+            StoreError::Io(e) => write!(f, "I/O error: {}", e),
         }
     }
 }
@@ -66,6 +99,8 @@ impl std::error::Error for StoreError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             StoreError::Database(e) => Some(e),
+            StoreError::Import { error, .. } => Some(error.as_ref()),
+            StoreError::Io(e) => Some(e),
             _ => None,
         }
     }
@@ -76,3 +111,9 @@ impl From<DbError> for StoreError {
         StoreError::Database(err)
     }
 }
+
+impl From<std::io::Error> for StoreError {
+    fn from(err: std::io::Error) -> Self {
+        StoreError::Io(err)
+    }
+}
@@ -0,0 +1,688 @@
+//! Bulk export and import of table rows
+//!
+//! [`Table::export_to`] writes the rows matching a [`Query`] as JSON Lines
+//! or CSV, limited to the query's selected columns and passed through a
+//! [`RedactionPolicy`] so personal data only leaves the store as the caller
+//! asked. [`Table::import_from`] reads the same formats back, converting
+//! each field by its column type and checking rows against the schema.
+//!
+//! Values are written as stored: timestamps as Unix milliseconds and bytes
+//! as lowercase hex. In CSV a null is an empty cell and an empty string is
+//! `""`, so both survive a round trip.
+
+use std::io::{BufRead, Write};
+
+use crate::privacy::{RedactionAction, RedactionPolicy};
+use crate::query::Query;
+use crate::schema::{Column, ColumnType, Record, Schema, Value};
+use crate::table::Table;
+use crate::{StoreError, StoreResult};
+
+/// File format for exports and imports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+impl DataFormat {
+    /// Get the format name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataFormat::Jsonl => "jsonl",
+            DataFormat::Csv => "csv",
+        }
+    }
+
+    /// Parse a format name
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "jsonl" | "ndjson" => Some(DataFormat::Jsonl),
+            "csv" => Some(DataFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// How an import treats its rows
+#[derive(Debug, Clone, Copy)]
+pub struct ImportOptions {
+    /// Input format
+    pub format: DataFormat,
+    /// Overwrite rows whose primary key already exists instead of failing
+    pub replace: bool,
+    /// Check every row without writing anything
+    pub dry_run: bool,
+}
+
+impl ImportOptions {
+    /// Insert-only import in the given format
+    pub fn new(format: DataFormat) -> Self {
+        Self {
+            format,
+            replace: false,
+            dry_run: false,
+        }
+    }
+
+    /// Overwrite existing rows
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+
+    /// Only check the rows
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Rows read
+    pub rows: usize,
+    /// Rows inserted (or that would be, on a dry run)
+    pub inserted: usize,
+    /// Existing rows overwritten (or that would be, on a dry run)
+    pub updated: usize,
+    /// Whether nothing was written
+    pub dry_run: bool,
+}
+
+impl Table {
+    /// Write the rows matching `query` to `out`, returning how many
+    ///
+    /// Only the query's selected columns are written (every column if it
+    /// selects none). Fails with [`StoreError::PiiExposure`] if a selected
+    /// personal data column has no rule in `policy`.
+    pub fn export_to(
+        &self,
+        query: &Query,
+        format: DataFormat,
+        policy: &RedactionPolicy,
+        out: &mut dyn Write,
+    ) -> StoreResult<usize> {
+        let schema = self.schema();
+        let selected = if query.select_columns.is_empty() {
+            schema.columns.iter().collect::<Vec<_>>()
+        } else {
+            query
+                .select_columns
+                .iter()
+                .map(|name| {
+                    schema
+                        .get_column(name)
+                        .ok_or_else(|| StoreError::ColumnNotFound(name.clone()))
+                })
+                .collect::<StoreResult<Vec<_>>>()?
+        };
+        // Dropped columns leave the header too
+        let columns: Vec<&Column> = selected
+            .into_iter()
+            .filter(|c| {
+                !c.pii.is_pii() || policy.action_for(&c.name, c.pii) != Some(RedactionAction::Drop)
+            })
+            .collect();
+
+        if format == DataFormat::Csv {
+            let header: Vec<String> = columns.iter().map(|c| csv_field(&c.name)).collect();
+            writeln!(out, "{}", header.join(","))?;
+        }
+
+        let mut rows = 0;
+        for record in self.query(query)? {
+            let mut projected = Record::new();
+            for column in &columns {
+                if let Some(value) = record.get(&column.name) {
+                    projected.set(column.name.as_str(), value.clone());
+                }
+            }
+            let exported = schema.export_record(&projected, policy)?;
+            let line = match format {
+                DataFormat::Jsonl => json_line(&columns, &exported)?,
+                DataFormat::Csv => csv_line(&columns, &exported),
+            };
+            writeln!(out, "{}", line)?;
+            rows += 1;
+        }
+        out.flush()?;
+        Ok(rows)
+    }
+
+    /// Read rows from `input` into the table
+    ///
+    /// Every row is validated before it is written, and the first bad row
+    /// stops the import with [`StoreError::Import`]. Rows before it stay
+    /// written, so check a file with a dry run first.
+    pub fn import_from(
+        &self,
+        input: &mut dyn BufRead,
+        options: &ImportOptions,
+    ) -> StoreResult<ImportReport> {
+        let schema = self.schema();
+        let pk_column = schema
+            .primary_key_column()
+            .ok_or(StoreError::InvalidQuery("Table has no primary key".into()))?
+            .name
+            .clone();
+        let mut report = ImportReport {
+            dry_run: options.dry_run,
+            ..ImportReport::default()
+        };
+
+        let mut reader = RowReader::new(input, options.format, schema)?;
+        while let Some((line, record)) = reader.next_row()? {
+            let at_line = |error| StoreError::Import {
+                line,
+                error: Box::new(error),
+            };
+            schema.validate(&record).map_err(at_line)?;
+            let pk = record
+                .get(&pk_column)
+                .cloned()
+                .ok_or_else(|| at_line(StoreError::NullViolation(pk_column.clone())))?;
+            let exists = self.get(&pk).map_err(at_line)?.is_some();
+            if exists && !options.replace {
+                return Err(at_line(StoreError::PrimaryKeyViolation));
+            }
+            if !options.dry_run {
+                if exists {
+                    self.update(&pk, &record).map_err(at_line)?;
+                } else {
+                    self.insert(&record).map_err(at_line)?;
+                }
+            }
+            report.rows += 1;
+            if exists {
+                report.updated += 1;
+            } else {
+                report.inserted += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl Schema {
+    /// Parse text as a value for `column`
+    ///
+    /// Text is read the way CSV imports read it: numbers and `true`/`false`
+    /// literally, bytes as hex, and an empty string as null for every type
+    /// except strings.
+    pub fn parse_value(&self, column: &str, text: &str) -> StoreResult<Value> {
+        let column = self
+            .get_column(column)
+            .ok_or_else(|| StoreError::ColumnNotFound(column.to_string()))?;
+        if text.is_empty() && column.column_type != ColumnType::String {
+            return Ok(Value::Null);
+        }
+        let invalid = || {
+            StoreError::InvalidColumnType(format!(
+                "Column {} expects {}, got {:?}",
+                column.name,
+                column.column_type.as_str(),
+                text
+            ))
+        };
+        Ok(match column.column_type {
+            ColumnType::Int64 | ColumnType::Timestamp => {
+                Value::Int64(text.parse().map_err(|_| invalid())?)
+            }
+            ColumnType::Float32 => Value::Float32(text.parse().map_err(|_| invalid())?),
+            ColumnType::Float64 => Value::Float64(text.parse().map_err(|_| invalid())?),
+            ColumnType::String => Value::String(text.to_string()),
+            ColumnType::Bytes | ColumnType::Uuid => {
+                Value::Bytes(hex_decode(text).ok_or_else(invalid)?)
+            }
+            ColumnType::Bool => match text {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                _ => return Err(invalid()),
+            },
+        })
+    }
+
+    /// Convert a JSON value for `column`
+    fn json_value(&self, column: &str, json: &serde_json::Value) -> StoreResult<Value> {
+        let column = self
+            .get_column(column)
+            .ok_or_else(|| StoreError::ColumnNotFound(column.to_string()))?;
+        let value = match (column.column_type, json) {
+            (_, serde_json::Value::Null) => Some(Value::Null),
+            (ColumnType::Int64 | ColumnType::Timestamp, json) => json.as_i64().map(Value::Int64),
+            (ColumnType::Float32, json) => json.as_f64().map(|f| Value::Float32(f as f32)),
+            (ColumnType::Float64, json) => json.as_f64().map(Value::Float64),
+            (ColumnType::String, serde_json::Value::String(s)) => Some(Value::String(s.clone())),
+            (ColumnType::Bytes | ColumnType::Uuid, serde_json::Value::String(s)) => {
+                hex_decode(s).map(Value::Bytes)
+            }
+            (ColumnType::Bool, serde_json::Value::Bool(b)) => Some(Value::Bool(*b)),
+            _ => None,
+        };
+        value.ok_or_else(|| {
+            StoreError::InvalidColumnType(format!(
+                "Column {} expects {}, got {}",
+                column.name,
+                column.column_type.as_str(),
+                json
+            ))
+        })
+    }
+}
+
+/// Reads rows from an import file, tracking line numbers
+struct RowReader<'a> {
+    input: &'a mut dyn BufRead,
+    format: DataFormat,
+    schema: &'a Schema,
+    /// CSV header, naming the column of each field
+    header: Vec<String>,
+    /// Lines read so far
+    line: usize,
+}
+
+impl<'a> RowReader<'a> {
+    fn new(
+        input: &'a mut dyn BufRead,
+        format: DataFormat,
+        schema: &'a Schema,
+    ) -> StoreResult<Self> {
+        let mut reader = Self {
+            input,
+            format,
+            schema,
+            header: Vec::new(),
+            line: 0,
+        };
+        if format == DataFormat::Csv {
+            let Some(fields) = reader.read_csv_fields()? else {
+                return Ok(reader);
+            };
+            for name in fields.into_iter().map(Option::unwrap_or_default) {
+                if schema.get_column(&name).is_none() {
+                    return Err(StoreError::Import {
+                        line: 1,
+                        error: Box::new(StoreError::ColumnNotFound(name)),
+                    });
+                }
+                reader.header.push(name);
+            }
+        }
+        Ok(reader)
+    }
+
+    /// Next row and the line it starts on, skipping blank lines
+    fn next_row(&mut self) -> StoreResult<Option<(usize, Record)>> {
+        loop {
+            let start = self.line + 1;
+            let at_line = |error| StoreError::Import {
+                line: start,
+                error: Box::new(error),
+            };
+            let record = match self.format {
+                DataFormat::Jsonl => {
+                    let Some(text) = self.read_line()? else {
+                        return Ok(None);
+                    };
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    self.json_record(&text).map_err(at_line)?
+                }
+                DataFormat::Csv => {
+                    let Some(fields) = self.read_csv_fields()? else {
+                        return Ok(None);
+                    };
+                    if fields.len() == 1 && fields[0].is_none() {
+                        continue;
+                    }
+                    self.csv_record(fields).map_err(at_line)?
+                }
+            };
+            return Ok(Some((start, record)));
+        }
+    }
+
+    fn json_record(&self, text: &str) -> StoreResult<Record> {
+        let json: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| StoreError::Serialization(e.to_string()))?;
+        let serde_json::Value::Object(fields) = json else {
+            return Err(StoreError::Serialization("Expected a JSON object".into()));
+        };
+        let mut record = Record::new();
+        for (name, value) in &fields {
+            record.set(name.as_str(), self.schema.json_value(name, value)?);
+        }
+        Ok(record)
+    }
+
+    fn csv_record(&self, fields: Vec<Option<String>>) -> StoreResult<Record> {
+        if fields.len() != self.header.len() {
+            return Err(StoreError::Serialization(format!(
+                "Expected {} fields, found {}",
+                self.header.len(),
+                fields.len()
+            )));
+        }
+        let mut record = Record::new();
+        for (name, field) in self.header.iter().zip(fields) {
+            let value = match field {
+                Some(text) => self.schema.parse_value(name, &text)?,
+                None => Value::Null,
+            };
+            record.set(name.as_str(), value);
+        }
+        Ok(record)
+    }
+
+    fn read_line(&mut self) -> StoreResult<Option<String>> {
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Read one CSV record, which may span lines inside quotes
+    ///
+    /// Unquoted empty fields come back as `None` (null).
+    fn read_csv_fields(&mut self) -> StoreResult<Option<Vec<Option<String>>>> {
+        let Some(mut text) = self.read_line()? else {
+            return Ok(None);
+        };
+        loop {
+            match parse_csv(&text) {
+                Some(fields) => return Ok(Some(fields)),
+                None => match self.read_line()? {
+                    Some(next) => {
+                        text.push('\n');
+                        text.push_str(&next);
+                    }
+                    None => {
+                        return Err(StoreError::Serialization(
+                            "Unterminated quoted CSV field".into(),
+                        ))
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Split a CSV record into fields, or `None` if a quote is still open
+fn parse_csv(text: &str) -> Option<Vec<Option<String>>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => {
+                in_quotes = true;
+                quoted = true;
+            }
+            (',', false) => {
+                fields.push((quoted || !field.is_empty()).then(|| std::mem::take(&mut field)));
+                quoted = false;
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    fields.push((quoted || !field.is_empty()).then_some(field));
+    Some(fields)
+}
+
+/// Quote a CSV field if it needs it
+fn csv_field(text: &str) -> String {
+    if text.is_empty() || text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn csv_line(columns: &[&Column], record: &Record) -> String {
+    let fields: Vec<String> = columns
+        .iter()
+        .map(|column| match record.get(&column.name) {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::Int64(v)) => v.to_string(),
+            Some(Value::Float32(v)) => v.to_string(),
+            Some(Value::Float64(v)) => v.to_string(),
+            Some(Value::String(s)) => csv_field(s),
+            Some(Value::Bytes(b)) => hex_encode(b),
+            Some(Value::Bool(b)) => b.to_string(),
+        })
+        .collect();
+    fields.join(",")
+}
+
+fn json_line(columns: &[&Column], record: &Record) -> StoreResult<String> {
+    let mut fields = Vec::with_capacity(columns.len());
+    for column in columns {
+        let value = match record.get(&column.name) {
+            None | Some(Value::Null) => serde_json::Value::Null,
+            Some(Value::Int64(v)) => (*v).into(),
+            Some(Value::Float32(v)) => f64::from(*v).into(),
+            Some(Value::Float64(v)) => (*v).into(),
+            Some(Value::String(s)) => s.as_str().into(),
+            Some(Value::Bytes(b)) => hex_encode(b).into(),
+            Some(Value::Bool(b)) => (*b).into(),
+        };
+        // Keep schema order rather than the sorted order of a JSON map
+        let name = serde_json::to_string(&column.name)
+            .map_err(|e| StoreError::Serialization(e.to_string()))?;
+        fields.push(format!("{}:{}", name, value));
+    }
+    Ok(format!("{{{}}}", fields.join(",")))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [hi, lo] => {
+                let digit = |b: &u8| char::from(*b).to_digit(16);
+                Some((digit(hi)? * 16 + digit(lo)?) as u8)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::privacy::REDACTED;
+    use crate::query::Condition;
+    use crate::schema::PiiClass;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use vaya_db::{DbConfig, VayaDb};
+
+    fn payments(db: Arc<VayaDb>) -> Table {
+        let schema = Schema::new("payments")
+            .column(Column::new("id", ColumnType::String).primary_key())
+            .column(Column::new("email", ColumnType::String).pii(PiiClass::Personal))
+            .column(Column::new("amount", ColumnType::Int64).not_null())
+            .column(Column::new("note", ColumnType::String))
+            .column(Column::new("created_at", ColumnType::Timestamp).not_null());
+        Table::create(schema, db).unwrap()
+    }
+
+    fn payment(id: &str, note: Option<&str>, created_at: i64) -> Record {
+        let mut record = Record::new();
+        record.set("id", Value::String(id.into()));
+        record.set("email", Value::String(format!("{}@example.com", id)));
+        record.set("amount", Value::Int64(1250));
+        if let Some(note) = note {
+            record.set("note", Value::String(note.into()));
+        }
+        record.set("created_at", Value::Int64(created_at));
+        record
+    }
+
+    fn export(
+        table: &Table,
+        query: &Query,
+        format: DataFormat,
+        policy: &RedactionPolicy,
+    ) -> String {
+        let mut out = Vec::new();
+        table.export_to(query, format, policy, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export_filters_selects_and_redacts() {
+        let tmp = TempDir::new().unwrap();
+        let db = Arc::new(VayaDb::open(DbConfig::new(tmp.path())).unwrap());
+        let table = payments(db);
+        table
+            .insert(&payment("p1", Some("refund, \"partial\""), 100))
+            .unwrap();
+        table.insert(&payment("p2", None, 200)).unwrap();
+        table.insert(&payment("p3", Some(""), 300)).unwrap();
+
+        let since = Query::new("payments").filter(Condition::ge("created_at", Value::Int64(200)));
+        let jsonl = export(
+            &table,
+            &since,
+            DataFormat::Jsonl,
+            &RedactionPolicy::mask_all(),
+        );
+        assert_eq!(
+            jsonl,
+            format!(
+                "{{\"id\":\"p2\",\"email\":\"{0}\",\"amount\":1250,\"note\":null,\"created_at\":200}}\n\
+                 {{\"id\":\"p3\",\"email\":\"{0}\",\"amount\":1250,\"note\":\"\",\"created_at\":300}}\n",
+                REDACTED
+            )
+        );
+
+        // Personal data needs a rule unless it isn't selected
+        let mut out = Vec::new();
+        let all = Query::new("payments");
+        let err = table
+            .export_to(&all, DataFormat::Csv, &RedactionPolicy::new(), &mut out)
+            .unwrap_err();
+        assert!(matches!(err, StoreError::PiiExposure(_)));
+        let selected = all.clone().select(vec!["id".into(), "note".into()]);
+        let csv = export(&table, &selected, DataFormat::Csv, &RedactionPolicy::new());
+        assert_eq!(
+            csv,
+            "id,note\np1,\"refund, \"\"partial\"\"\"\np2,\np3,\"\"\n"
+        );
+
+        let dropped = RedactionPolicy::new().column("email", RedactionAction::Drop);
+        let csv = export(&table, &all, DataFormat::Csv, &dropped);
+        assert!(csv.starts_with("id,amount,note,created_at\n"));
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let db = Arc::new(VayaDb::open(DbConfig::new(tmp.path())).unwrap());
+        let source = payments(db);
+        source
+            .insert(&payment("p1", Some("line one\nline two"), 100))
+            .unwrap();
+        source.insert(&payment("p2", None, 200)).unwrap();
+        source.insert(&payment("p3", Some(""), 300)).unwrap();
+        let allow = RedactionPolicy::new().class(PiiClass::Personal, RedactionAction::Allow);
+
+        for format in [DataFormat::Jsonl, DataFormat::Csv] {
+            let tmp = TempDir::new().unwrap();
+            let db = Arc::new(VayaDb::open(DbConfig::new(tmp.path())).unwrap());
+            let target = payments(db);
+            let data = export(&source, &Query::new("payments"), format, &allow);
+
+            let dry = ImportOptions::new(format).dry_run(true);
+            let report = target.import_from(&mut data.as_bytes(), &dry).unwrap();
+            assert_eq!((report.rows, report.inserted), (3, 3));
+            assert!(target.get(&Value::String("p1".into())).unwrap().is_none());
+
+            let options = ImportOptions::new(format);
+            target.import_from(&mut data.as_bytes(), &options).unwrap();
+            for id in ["p1", "p2", "p3"] {
+                let pk = Value::String(id.into());
+                let (a, b) = (source.get(&pk).unwrap(), target.get(&pk).unwrap());
+                let (a, b) = (a.unwrap(), b.unwrap());
+                for column in &source.schema().columns {
+                    let name = column.name.as_str();
+                    let null = Value::Null;
+                    assert_eq!(
+                        a.get(name).unwrap_or(&null),
+                        b.get(name).unwrap_or(&null),
+                        "{} in {:?}",
+                        name,
+                        format
+                    );
+                }
+            }
+
+            // Existing rows need `replace`
+            let err = target
+                .import_from(&mut data.as_bytes(), &options)
+                .unwrap_err();
+            assert!(matches!(err, StoreError::Import { .. }), "{}", err);
+            let report = target
+                .import_from(&mut data.as_bytes(), &options.replace(true))
+                .unwrap();
+            assert_eq!((report.inserted, report.updated), (0, 3));
+        }
+    }
+
+    #[test]
+    fn test_import_reports_bad_line() {
+        let tmp = TempDir::new().unwrap();
+        let db = Arc::new(VayaDb::open(DbConfig::new(tmp.path())).unwrap());
+        let table = payments(db);
+
+        let csv = "id,amount,created_at\np1,10,100\np2,ten,200\n";
+        let err = table
+            .import_from(&mut csv.as_bytes(), &ImportOptions::new(DataFormat::Csv))
+            .unwrap_err();
+        match err {
+            StoreError::Import { line, error } => {
+                assert_eq!(line, 3);
+                assert!(matches!(*error, StoreError::InvalidColumnType(_)));
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        let jsonl = "{\"id\":\"p9\",\"amount\":1}\n";
+        let err = table
+            .import_from(
+                &mut jsonl.as_bytes(),
+                &ImportOptions::new(DataFormat::Jsonl),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("line 1"), "{}", err);
+        assert!(err.to_string().contains("created_at"), "{}", err);
+    }
+}
@@ -2,18 +2,33 @@
 //!
 //! This crate provides table-like abstractions, schemas, indexing,
 //! and query capabilities on top of the LSM-tree storage engine.
+//! Columns can be tagged as personal data and tables pinned to a
+//! residency region; see [`privacy`] for export and log redaction.
+//! Rows can expire via a TTL column or table retention; see [`ttl`].
+//! Schema changes ship as versioned migrations; see [`migration`].
+//! Rows can be bulk exported and imported as JSONL or CSV; see [`export`].
 
 pub mod error;
+pub mod export;
 pub mod index;
+pub mod migration;
+pub mod privacy;
 pub mod query;
+pub mod query_cache;
 pub mod schema;
 pub mod table;
+pub mod ttl;
 
 pub use error::{StoreError, StoreResult};
+pub use export::{DataFormat, ImportOptions, ImportReport};
 pub use index::{Index, IndexType};
+pub use migration::{Migration, MigrationReport, MigrationStatus, Migrator};
+pub use privacy::{DataInventory, RedactionAction, RedactionPolicy, TableInventory};
 pub use query::{Query, QueryBuilder};
-pub use schema::{Column, ColumnType, Schema};
+pub use query_cache::{QueryCache, QueryCacheConfig, QueryCacheStats};
+pub use schema::{Column, ColumnType, PiiClass, Retention, Schema};
 pub use table::Table;
+pub use ttl::{TtlSweepReport, TtlSweeper, TtlSweeperHandle};
 
 /// Store version for compatibility checking
 pub const STORE_VERSION: u32 = 1;
@@ -0,0 +1,483 @@
+//! Schema migrations
+//!
+//! Migrations are Rust functions registered with a [`Migrator`] under an
+//! increasing version number. Each one applied is recorded as a row in the
+//! `_migrations` table, so [`Migrator::migrate`] runs only those still
+//! pending, in version order, and [`Migrator::rollback`] runs the `down`
+//! functions of applied ones in reverse.
+//!
+//! The store has no transactions, so a migration that fails part way is
+//! not undone: the run stops at it, earlier migrations stay applied, and
+//! the error names the version. Write migrations so they can be re-run.
+//!
+//! Every run can be a dry run that only reports the plan.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+use vaya_db::VayaDb;
+
+use crate::schema::{Column, ColumnType, RecordBuilder, Schema, Value};
+use crate::table::Table;
+use crate::ttl::now_millis;
+use crate::{StoreError, StoreResult};
+
+/// Table recording applied migrations
+pub const MIGRATIONS_TABLE: &str = "_migrations";
+
+/// Key holding the highest applied version, so a database migrated by a
+/// newer build is recognised
+const HEAD_KEY: &[u8] = b"_migration_head";
+
+/// A migration step
+pub type MigrationFn = Box<dyn Fn(&Arc<VayaDb>) -> StoreResult<()> + Send + Sync>;
+
+/// A versioned schema change
+pub struct Migration {
+    /// Version (unique, applied in increasing order)
+    pub version: u64,
+    /// Short description, e.g. `add_booking_currency`
+    pub name: String,
+    up: MigrationFn,
+    down: Option<MigrationFn>,
+}
+
+impl Migration {
+    /// Migration that runs `up` when applied
+    pub fn new(
+        version: u64,
+        name: impl Into<String>,
+        up: impl Fn(&Arc<VayaDb>) -> StoreResult<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            up: Box::new(up),
+            down: None,
+        }
+    }
+
+    /// Set how to undo the migration (without one it can't be rolled back)
+    pub fn with_down(
+        mut self,
+        down: impl Fn(&Arc<VayaDb>) -> StoreResult<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.down = Some(Box::new(down));
+        self
+    }
+
+    /// Check if the migration can be rolled back
+    pub fn is_reversible(&self) -> bool {
+        self.down.is_some()
+    }
+}
+
+impl fmt::Debug for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("name", &self.name)
+            .field("reversible", &self.is_reversible())
+            .finish()
+    }
+}
+
+/// A migration recorded as applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    /// Version
+    pub version: u64,
+    /// Name at the time it was applied
+    pub name: String,
+    /// When it was applied (milliseconds since Unix epoch)
+    pub applied_at: i64,
+}
+
+/// Where the database stands against the registered migrations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Highest applied version (0 if none)
+    pub current: u64,
+    /// Highest registered version (0 if none)
+    pub latest: u64,
+    /// Registered migrations that have been applied
+    pub applied: Vec<AppliedMigration>,
+    /// Registered migrations not yet applied, as (version, name)
+    pub pending: Vec<(u64, String)>,
+}
+
+impl MigrationStatus {
+    /// Check if every registered migration is applied
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Check if the database was migrated past what this build knows
+    pub fn is_ahead(&self) -> bool {
+        self.current > self.latest
+    }
+}
+
+/// Which way a run goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Applying migrations
+    Up,
+    /// Rolling migrations back
+    Down,
+}
+
+/// Outcome (or, for a dry run, plan) of a migration run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Which way the run went
+    pub direction: Direction,
+    /// Migrations run (or to run), as (version, name), in order
+    pub steps: Vec<(u64, String)>,
+    /// Whether nothing was actually run
+    pub dry_run: bool,
+}
+
+/// Runs registered migrations against a database
+pub struct Migrator {
+    db: Arc<VayaDb>,
+    migrations: Vec<Migration>,
+}
+
+impl Migrator {
+    /// Migrator with no migrations registered
+    pub fn new(db: Arc<VayaDb>) -> Self {
+        Self {
+            db,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Register a migration
+    pub fn with_migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self
+    }
+
+    /// Register several migrations
+    pub fn with_migrations(mut self, migrations: impl IntoIterator<Item = Migration>) -> Self {
+        self.migrations.extend(migrations);
+        self
+    }
+
+    /// Registered migrations, by version
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Compare the database with the registered migrations
+    pub fn status(&self) -> StoreResult<MigrationStatus> {
+        let migrations = self.sorted()?;
+        // Reading creates nothing, so a dry run leaves the database as is
+        let table = match Table::open(MIGRATIONS_TABLE, Arc::clone(&self.db)) {
+            Ok(table) => Some(table),
+            Err(StoreError::TableNotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let mut status = MigrationStatus {
+            current: self.head()?,
+            latest: migrations.last().map_or(0, |m| m.version),
+            applied: Vec::new(),
+            pending: Vec::new(),
+        };
+        for migration in migrations {
+            let record = match &table {
+                Some(table) => table.get(&version_key(migration.version))?,
+                None => None,
+            };
+            match record {
+                Some(record) => status.applied.push(AppliedMigration {
+                    version: migration.version,
+                    name: record
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    applied_at: record
+                        .get("applied_at")
+                        .and_then(Value::as_i64)
+                        .unwrap_or(0),
+                }),
+                None => status
+                    .pending
+                    .push((migration.version, migration.name.clone())),
+            }
+        }
+        Ok(status)
+    }
+
+    /// Apply pending migrations up to and including `target` (all if
+    /// `None`)
+    pub fn migrate(&self, target: Option<u64>, dry_run: bool) -> StoreResult<MigrationReport> {
+        let status = self.status()?;
+        let applied: BTreeSet<u64> = status.applied.iter().map(|m| m.version).collect();
+        let plan: Vec<&Migration> = self
+            .sorted()?
+            .into_iter()
+            .filter(|m| !applied.contains(&m.version))
+            .filter(|m| m.version <= target.unwrap_or(u64::MAX))
+            .collect();
+
+        let report = MigrationReport {
+            direction: Direction::Up,
+            steps: plan.iter().map(|m| (m.version, m.name.clone())).collect(),
+            dry_run,
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        let table = self.table()?;
+        for migration in &plan {
+            tracing::info!(version = migration.version, name = %migration.name, "Applying migration");
+            (migration.up)(&self.db).map_err(|e| failed(migration, "apply", e))?;
+            table.insert(
+                &RecordBuilder::new()
+                    .int64("version", migration.version as i64)
+                    .string("name", migration.name.clone())
+                    .timestamp("applied_at", now_millis())
+                    .build(),
+            )?;
+            self.set_head(self.head()?.max(migration.version))?;
+        }
+        Ok(report)
+    }
+
+    /// Roll back applied migrations above `target`, newest first
+    ///
+    /// Nothing runs unless every migration to roll back has a `down`.
+    pub fn rollback(&self, target: u64, dry_run: bool) -> StoreResult<MigrationReport> {
+        let status = self.status()?;
+        if status.is_ahead() {
+            return Err(StoreError::Migration(format!(
+                "database is at version {}, past this build's latest {}",
+                status.current, status.latest
+            )));
+        }
+        let applied: BTreeSet<u64> = status.applied.iter().map(|m| m.version).collect();
+        let mut plan: Vec<&Migration> = self
+            .sorted()?
+            .into_iter()
+            .filter(|m| m.version > target && applied.contains(&m.version))
+            .collect();
+        plan.reverse();
+
+        if let Some(migration) = plan.iter().find(|m| !m.is_reversible()) {
+            return Err(StoreError::Migration(format!(
+                "migration {} ({}) can't be rolled back",
+                migration.version, migration.name
+            )));
+        }
+
+        let report = MigrationReport {
+            direction: Direction::Down,
+            steps: plan.iter().map(|m| (m.version, m.name.clone())).collect(),
+            dry_run,
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        let table = self.table()?;
+        for migration in &plan {
+            tracing::info!(version = migration.version, name = %migration.name, "Rolling back migration");
+            let down = migration.down.as_ref().ok_or_else(|| {
+                StoreError::Migration(format!("migration {} has no down", migration.version))
+            })?;
+            down(&self.db).map_err(|e| failed(migration, "roll back", e))?;
+            table.delete(&version_key(migration.version))?;
+            let head = applied
+                .range(..migration.version)
+                .next_back()
+                .copied()
+                .unwrap_or(0);
+            self.set_head(head)?;
+        }
+        Ok(report)
+    }
+
+    /// Registered migrations in version order, rejecting duplicates
+    fn sorted(&self) -> StoreResult<Vec<&Migration>> {
+        let mut migrations: Vec<&Migration> = self.migrations.iter().collect();
+        migrations.sort_by_key(|m| m.version);
+        for pair in migrations.windows(2) {
+            if pair[0].version == pair[1].version {
+                return Err(StoreError::Migration(format!(
+                    "version {} is registered twice ({} and {})",
+                    pair[0].version, pair[0].name, pair[1].name
+                )));
+            }
+        }
+        if migrations.first().is_some_and(|m| m.version == 0) {
+            return Err(StoreError::Migration(
+                "migration versions start at 1".into(),
+            ));
+        }
+        Ok(migrations)
+    }
+
+    /// The migrations table, created on first write
+    fn table(&self) -> StoreResult<Table> {
+        match Table::open(MIGRATIONS_TABLE, Arc::clone(&self.db)) {
+            Err(StoreError::TableNotFound(_)) => Table::create(
+                Schema::new(MIGRATIONS_TABLE)
+                    .column(Column::new("version", ColumnType::Int64).primary_key())
+                    .column(Column::new("name", ColumnType::String).not_null())
+                    .column(Column::new("applied_at", ColumnType::Timestamp).not_null()),
+                Arc::clone(&self.db),
+            ),
+            result => result,
+        }
+    }
+
+    fn head(&self) -> StoreResult<u64> {
+        Ok(self
+            .db
+            .get(HEAD_KEY)?
+            .and_then(|bytes| bytes.try_into().ok())
+            .map_or(0, u64::from_be_bytes))
+    }
+
+    fn set_head(&self, version: u64) -> StoreResult<()> {
+        Ok(self.db.put(HEAD_KEY, &version.to_be_bytes())?)
+    }
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrator")
+            .field("migrations", &self.migrations)
+            .finish()
+    }
+}
+
+fn version_key(version: u64) -> Value {
+    Value::Int64(version as i64)
+}
+
+fn failed(migration: &Migration, action: &str, error: StoreError) -> StoreError {
+    StoreError::Migration(format!(
+        "failed to {} migration {} ({}): {}",
+        action, migration.version, migration.name, error
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use vaya_db::DbConfig;
+
+    fn open_db(dir: &TempDir) -> Arc<VayaDb> {
+        Arc::new(VayaDb::open(DbConfig::new(dir.path())).unwrap())
+    }
+
+    fn routes_schema() -> Schema {
+        Schema::new("routes").column(Column::new("code", ColumnType::String).primary_key())
+    }
+
+    fn migrations() -> Vec<Migration> {
+        vec![
+            Migration::new(1, "create_routes", |db| {
+                Table::create(routes_schema(), Arc::clone(db)).map(|_| ())
+            })
+            .with_down(|db| {
+                db.delete(b"_meta_table_routes")?;
+                Ok(())
+            }),
+            Migration::new(2, "seed_kul_sin", |db| {
+                Table::open("routes", Arc::clone(db))?
+                    .insert(&RecordBuilder::new().string("code", "KUL-SIN").build())
+            })
+            .with_down(|db| {
+                Table::open("routes", Arc::clone(db))?
+                    .delete(&Value::String("KUL-SIN".into()))
+                    .map(|_| ())
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_migrate_and_rollback() {
+        let dir = TempDir::new().unwrap();
+        let db = open_db(&dir);
+        let migrator = Migrator::new(Arc::clone(&db)).with_migrations(migrations());
+
+        let status = migrator.status().unwrap();
+        assert_eq!((status.current, status.latest), (0, 2));
+        assert_eq!(status.pending.len(), 2);
+
+        // A dry run only plans
+        let plan = migrator.migrate(None, true).unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert!(!migrator.status().unwrap().is_up_to_date());
+
+        let report = migrator.migrate(Some(1), false).unwrap();
+        assert_eq!(report.steps, [(1, "create_routes".to_string())]);
+        let report = migrator.migrate(None, false).unwrap();
+        assert_eq!(report.steps, [(2, "seed_kul_sin".to_string())]);
+        let status = migrator.status().unwrap();
+        assert!(status.is_up_to_date());
+        assert_eq!(status.current, 2);
+        assert_eq!(status.applied[1].name, "seed_kul_sin");
+        assert!(migrator.migrate(None, false).unwrap().steps.is_empty());
+
+        let routes = Table::open("routes", Arc::clone(&db)).unwrap();
+        assert!(routes
+            .get(&Value::String("KUL-SIN".into()))
+            .unwrap()
+            .is_some());
+
+        let report = migrator.rollback(0, false).unwrap();
+        assert_eq!(report.direction, Direction::Down);
+        assert_eq!(report.steps[0].0, 2);
+        let status = migrator.status().unwrap();
+        assert_eq!(status.current, 0);
+        assert_eq!(status.pending.len(), 2);
+        assert!(Table::open("routes", Arc::clone(&db)).is_err());
+    }
+
+    #[test]
+    fn test_failures_and_guards() {
+        let dir = TempDir::new().unwrap();
+        let db = open_db(&dir);
+
+        let failing = Migrator::new(Arc::clone(&db))
+            .with_migrations(migrations())
+            .with_migration(Migration::new(3, "broken", |_| {
+                Err(StoreError::InvalidQuery("boom".into()))
+            }));
+        let err = failing.migrate(None, false).unwrap_err();
+        assert!(err.to_string().contains("migration 3 (broken)"), "{}", err);
+        assert_eq!(failing.status().unwrap().current, 2);
+
+        // Irreversible migrations block the whole rollback
+        let one_way = Migrator::new(Arc::clone(&db))
+            .with_migrations(migrations())
+            .with_migration(Migration::new(3, "one_way", |_| Ok(())));
+        one_way.migrate(None, false).unwrap();
+        let err = one_way.rollback(0, true).unwrap_err();
+        assert!(err.to_string().contains("can't be rolled back"), "{}", err);
+        assert_eq!(
+            one_way.rollback(2, false).unwrap_err().to_string(),
+            err.to_string()
+        );
+
+        // A build that knows fewer migrations sees the database as ahead
+        let older =
+            Migrator::new(Arc::clone(&db)).with_migrations(migrations().into_iter().take(1));
+        assert!(older.status().unwrap().is_ahead());
+        assert!(older.rollback(0, false).is_err());
+
+        let duplicate = Migrator::new(db)
+            .with_migration(Migration::new(1, "a", |_| Ok(())))
+            .with_migration(Migration::new(1, "b", |_| Ok(())));
+        assert!(duplicate.status().is_err());
+    }
+}
@@ -0,0 +1,305 @@
+//! Personal data handling for exports, logs, and compliance reports
+//!
+//! Columns tagged with a [`PiiClass`] cannot leave the store through
+//! [`Schema::export_record`] unless the caller's [`RedactionPolicy`] says
+//! what to do with them; a missing rule is an error rather than a silent
+//! leak. [`Schema::log_view`] always masks tagged columns, and
+//! [`DataInventory`] lists where personal data lives for compliance.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use crate::schema::{PiiClass, Record, Schema, Value};
+use crate::{StoreError, StoreResult};
+
+/// Placeholder written in place of redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// What to do with a tagged column when it leaves the store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactionAction {
+    /// Include the value as-is (explicit opt-in)
+    Allow,
+    /// Replace the value with a placeholder
+    Mask,
+    /// Leave the column out
+    Drop,
+}
+
+/// Rules for tagged columns in an export
+#[derive(Debug, Clone, Default)]
+pub struct RedactionPolicy {
+    by_column: HashMap<String, RedactionAction>,
+    by_class: HashMap<PiiClass, RedactionAction>,
+}
+
+impl RedactionPolicy {
+    /// Create an empty policy (every tagged column is rejected)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mask every tagged column
+    pub fn mask_all() -> Self {
+        Self::new()
+            .class(PiiClass::Internal, RedactionAction::Mask)
+            .class(PiiClass::Personal, RedactionAction::Mask)
+            .class(PiiClass::Sensitive, RedactionAction::Mask)
+    }
+
+    /// Set the action for a classification
+    pub fn class(mut self, class: PiiClass, action: RedactionAction) -> Self {
+        self.by_class.insert(class, action);
+        self
+    }
+
+    /// Set the action for a single column, overriding its class rule
+    pub fn column(mut self, name: impl Into<String>, action: RedactionAction) -> Self {
+        self.by_column.insert(name.into(), action);
+        self
+    }
+
+    /// Action for a column, if the policy covers it
+    pub fn action_for(&self, column: &str, class: PiiClass) -> Option<RedactionAction> {
+        self.by_column
+            .get(column)
+            .or_else(|| self.by_class.get(&class))
+            .copied()
+    }
+}
+
+/// Masked stand-in for a value
+fn mask(value: &Value) -> Value {
+    match value {
+        Value::Null => Value::Null,
+        Value::String(_) => Value::String(REDACTED.to_string()),
+        _ => Value::Null,
+    }
+}
+
+impl Schema {
+    /// Prepare a record for export under a redaction policy
+    ///
+    /// Fails with [`StoreError::PiiExposure`] if a tagged column in the
+    /// record has no rule in the policy.
+    pub fn export_record(&self, record: &Record, policy: &RedactionPolicy) -> StoreResult<Record> {
+        let mut exported = Record::new();
+        for name in record.field_names() {
+            let Some(value) = record.get(name) else {
+                continue;
+            };
+            let class = self
+                .get_column(name)
+                .map(|c| c.pii)
+                .unwrap_or(PiiClass::None);
+            if !class.is_pii() {
+                exported.set(name, value.clone());
+                continue;
+            }
+            match policy.action_for(name, class) {
+                Some(RedactionAction::Allow) => exported.set(name, value.clone()),
+                Some(RedactionAction::Mask) => exported.set(name, mask(value)),
+                Some(RedactionAction::Drop) => {}
+                None => {
+                    return Err(StoreError::PiiExposure(format!(
+                        "{}.{} ({})",
+                        self.table_name,
+                        name,
+                        class.as_str()
+                    )))
+                }
+            }
+        }
+        Ok(exported)
+    }
+
+    /// View of a record that is safe to log, with tagged columns masked
+    pub fn log_view<'a>(&'a self, record: &'a Record) -> LogView<'a> {
+        LogView {
+            schema: self,
+            record,
+        }
+    }
+}
+
+/// A record formatted for logs, with personal data masked
+pub struct LogView<'a> {
+    schema: &'a Schema,
+    record: &'a Record,
+}
+
+impl fmt::Display for LogView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&str> = self.record.field_names().collect();
+        names.sort_unstable();
+
+        write!(f, "{} {{", self.schema.table_name)?;
+        for (i, name) in names.into_iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            let tagged = self.schema.get_column(name).is_some_and(|c| c.pii.is_pii());
+            match self.record.get(name) {
+                Some(_) if tagged => write!(f, "{}{}: {}", separator, name, REDACTED)?,
+                Some(value) => write!(f, "{}{}: {:?}", separator, name, value)?,
+                None => {}
+            }
+        }
+        write!(f, " }}")
+    }
+}
+
+/// Personal data held by one table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInventory {
+    /// Table name
+    pub table: String,
+    /// Region the data must stay in, if restricted
+    pub residency: Option<String>,
+    /// Tagged columns and their classification
+    pub columns: Vec<(String, PiiClass)>,
+}
+
+impl TableInventory {
+    /// Highest classification in the table
+    pub fn highest_class(&self) -> PiiClass {
+        self.columns
+            .iter()
+            .map(|(_, class)| *class)
+            .max()
+            .unwrap_or(PiiClass::None)
+    }
+}
+
+/// Inventory of where personal data is stored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataInventory {
+    /// Tables holding personal data, sorted by name
+    pub tables: Vec<TableInventory>,
+}
+
+impl DataInventory {
+    /// Build the inventory from table schemas
+    ///
+    /// Tables without tagged columns are left out.
+    pub fn from_schemas<'a>(schemas: impl IntoIterator<Item = &'a Schema>) -> Self {
+        let mut tables: Vec<TableInventory> = schemas
+            .into_iter()
+            .filter_map(|schema| {
+                let columns: Vec<(String, PiiClass)> = schema
+                    .pii_columns()
+                    .into_iter()
+                    .map(|c| (c.name.clone(), c.pii))
+                    .collect();
+                if columns.is_empty() {
+                    return None;
+                }
+                Some(TableInventory {
+                    table: schema.table_name.clone(),
+                    residency: schema.residency.clone(),
+                    columns,
+                })
+            })
+            .collect();
+        tables.sort_by(|a, b| a.table.cmp(&b.table));
+        Self { tables }
+    }
+
+    /// Tables grouped by residency region ("unrestricted" when unset)
+    pub fn by_region(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut regions: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for table in &self.tables {
+            regions
+                .entry(table.residency.as_deref().unwrap_or("unrestricted"))
+                .or_default()
+                .push(&table.table);
+        }
+        regions
+    }
+
+    /// Tables holding data of at least the given classification
+    pub fn tables_at_least(&self, class: PiiClass) -> Vec<&TableInventory> {
+        self.tables
+            .iter()
+            .filter(|t| t.highest_class() >= class)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{Column, ColumnType, RecordBuilder};
+
+    fn users() -> Schema {
+        Schema::new("users")
+            .residency("MY")
+            .column(Column::new("id", ColumnType::Int64).primary_key())
+            .column(Column::new("email", ColumnType::String).pii(PiiClass::Personal))
+            .column(Column::new("passport", ColumnType::String).pii(PiiClass::Sensitive))
+    }
+
+    fn alice() -> Record {
+        RecordBuilder::new()
+            .int64("id", 1)
+            .string("email", "alice@example.com")
+            .string("passport", "A1234567")
+            .build()
+    }
+
+    #[test]
+    fn test_export_requires_rules_for_tagged_columns() {
+        let schema = users();
+        let record = alice();
+
+        let err = schema
+            .export_record(&record, &RedactionPolicy::new())
+            .unwrap_err();
+        assert!(matches!(err, StoreError::PiiExposure(_)));
+
+        let policy = RedactionPolicy::mask_all()
+            .column("email", RedactionAction::Allow)
+            .class(PiiClass::Sensitive, RedactionAction::Drop);
+        let exported = schema.export_record(&record, &policy).unwrap();
+        assert_eq!(exported.get("id"), Some(&Value::Int64(1)));
+        assert_eq!(
+            exported.get("email"),
+            Some(&Value::String("alice@example.com".to_string()))
+        );
+        assert!(!exported.has("passport"));
+
+        let masked = schema
+            .export_record(&record, &RedactionPolicy::mask_all())
+            .unwrap();
+        assert_eq!(
+            masked.get("passport"),
+            Some(&Value::String(REDACTED.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_log_view_masks_tagged_columns() {
+        let schema = users();
+        let record = alice();
+        let line = schema.log_view(&record).to_string();
+        assert!(line.contains("id: Int64(1)"));
+        assert!(!line.contains("alice@example.com"));
+        assert!(!line.contains("A1234567"));
+    }
+
+    #[test]
+    fn test_data_inventory() {
+        let events = Schema::new("events").column(Column::new("id", ColumnType::Int64));
+        let payments = Schema::new("payments")
+            .column(Column::new("user_id", ColumnType::String).pii(PiiClass::Internal));
+        let schemas = [users(), events, payments];
+
+        let inventory = DataInventory::from_schemas(&schemas);
+        assert_eq!(inventory.tables.len(), 2);
+        assert_eq!(inventory.tables[0].table, "payments");
+        assert_eq!(inventory.tables[1].highest_class(), PiiClass::Sensitive);
+
+        let regions = inventory.by_region();
+        assert_eq!(regions["MY"], vec!["users"]);
+        assert_eq!(regions["unrestricted"], vec!["payments"]);
+        assert_eq!(inventory.tables_at_least(PiiClass::Personal).len(), 1);
+    }
+}
@@ -0,0 +1,436 @@
+//! Query result caching
+//!
+//! Results of [`Table::query`](crate::Table::query) can be cached per table.
+//! Entries are keyed by a normalized form of the query, and each table has a
+//! version counter that is bumped on every write (row or index change).
+//! Bumping the version drops the table's cached results, and results computed
+//! against an older version are never stored.
+//!
+//! Hit/miss counters are published to the [`vaya_common::metrics`] registry
+//! under `vaya_store_query_cache_*` with a `table` label.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use vaya_common::metrics::{self, Counter, Gauge};
+
+use crate::query::{CompareOp, Query};
+use crate::schema::{Record, Value};
+
+/// Per-table query cache configuration
+#[derive(Debug, Clone)]
+pub struct QueryCacheConfig {
+    /// Maximum number of cached queries for the table
+    pub max_entries: usize,
+    /// Maximum age of a cached result (`None` = until invalidated)
+    pub ttl: Option<Duration>,
+}
+
+impl Default for QueryCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 256,
+            ttl: None,
+        }
+    }
+}
+
+impl QueryCacheConfig {
+    /// Create a configuration with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of cached queries
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Set the maximum age of cached results
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+}
+
+/// Query cache statistics for a table
+#[derive(Debug, Clone)]
+pub struct QueryCacheStats {
+    /// Number of cache hits
+    pub hits: u64,
+    /// Number of cache misses
+    pub misses: u64,
+    /// Number of write-triggered invalidations
+    pub invalidations: u64,
+    /// Number of entries evicted for capacity or age
+    pub evictions: u64,
+    /// Number of cached queries
+    pub entries: usize,
+    /// Current table version
+    pub version: u64,
+    /// Hit rate (0.0 - 1.0)
+    pub hit_rate: f64,
+}
+
+struct CachedResult {
+    records: Vec<Record>,
+    cached_at: Instant,
+}
+
+struct TableMetrics {
+    hits: Counter,
+    misses: Counter,
+    invalidations: Counter,
+    evictions: Counter,
+    hit_rate: Gauge,
+}
+
+impl TableMetrics {
+    fn register(table: &str) -> Self {
+        let registry = metrics::global();
+        let labels = [("table", table)];
+        Self {
+            hits: registry.counter("vaya_store_query_cache_hits_total", &labels),
+            misses: registry.counter("vaya_store_query_cache_misses_total", &labels),
+            invalidations: registry.counter("vaya_store_query_cache_invalidations_total", &labels),
+            evictions: registry.counter("vaya_store_query_cache_evictions_total", &labels),
+            hit_rate: registry.gauge("vaya_store_query_cache_hit_rate", &labels),
+        }
+    }
+
+    fn record_lookup(&self, hit: bool) {
+        if hit {
+            self.hits.inc();
+        } else {
+            self.misses.inc();
+        }
+        let hits = self.hits.get();
+        let total = hits + self.misses.get();
+        self.hit_rate.set(hits as f64 / total as f64);
+    }
+}
+
+struct TableCache {
+    config: QueryCacheConfig,
+    entries: HashMap<String, CachedResult>,
+    /// Insertion order, oldest first
+    order: VecDeque<String>,
+    metrics: TableMetrics,
+}
+
+impl TableCache {
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+        self.order.retain(|k| k != key);
+    }
+}
+
+/// Cache of query results shared by the tables of a store
+///
+/// Tables opt in through [`QueryCache::enable`]; queries against other
+/// tables always bypass the cache. Version counters are tracked for every
+/// table so that concurrent writers are detected even while caching is off.
+#[derive(Default)]
+pub struct QueryCache {
+    tables: Mutex<HashMap<String, TableCache>>,
+    versions: Mutex<HashMap<String, u64>>,
+}
+
+impl QueryCache {
+    /// Create an empty cache with no tables enabled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable (or reconfigure) caching for a table
+    pub fn enable(&self, table: impl Into<String>, config: QueryCacheConfig) {
+        let table = table.into();
+        let mut tables = self.tables.lock();
+        if let Some(cache) = tables.get_mut(&table) {
+            cache.config = config;
+            cache.clear();
+            return;
+        }
+        let metrics = TableMetrics::register(&table);
+        tables.insert(
+            table,
+            TableCache {
+                config,
+                entries: HashMap::new(),
+                order: VecDeque::new(),
+                metrics,
+            },
+        );
+    }
+
+    /// Disable caching for a table and drop its cached results
+    pub fn disable(&self, table: &str) {
+        self.tables.lock().remove(table);
+    }
+
+    /// Check whether caching is enabled for a table
+    pub fn is_enabled(&self, table: &str) -> bool {
+        self.tables.lock().contains_key(table)
+    }
+
+    /// Current version counter of a table
+    pub fn version(&self, table: &str) -> u64 {
+        self.versions.lock().get(table).copied().unwrap_or(0)
+    }
+
+    /// Record a write to a table, dropping its cached results
+    pub fn invalidate(&self, table: &str) {
+        *self.versions.lock().entry(table.to_string()).or_insert(0) += 1;
+        if let Some(cache) = self.tables.lock().get_mut(table) {
+            if !cache.entries.is_empty() {
+                cache.metrics.invalidations.inc();
+            }
+            cache.clear();
+        }
+    }
+
+    /// Look up cached results for a query against a table
+    ///
+    /// Returns `None` on a miss or if caching is disabled for the table.
+    pub fn get(&self, table: &str, query: &Query) -> Option<Vec<Record>> {
+        let mut tables = self.tables.lock();
+        let cache = tables.get_mut(table)?;
+        let key = normalize(query);
+
+        let expired = match (cache.entries.get(&key), cache.config.ttl) {
+            (Some(entry), Some(ttl)) => entry.cached_at.elapsed() > ttl,
+            _ => false,
+        };
+        if expired {
+            cache.remove(&key);
+            cache.metrics.evictions.inc();
+        }
+
+        let records = cache.entries.get(&key).map(|e| e.records.clone());
+        cache.metrics.record_lookup(records.is_some());
+        records
+    }
+
+    /// Store the results of a query computed at `version`
+    ///
+    /// The results are discarded if the table has been written since
+    /// `version` was read, or if caching is disabled for the table.
+    pub fn insert(&self, table: &str, query: &Query, version: u64, records: Vec<Record>) {
+        let mut tables = self.tables.lock();
+        let Some(cache) = tables.get_mut(table) else {
+            return;
+        };
+        if self.version(table) != version || cache.config.max_entries == 0 {
+            return;
+        }
+
+        let key = normalize(query);
+        if cache.entries.contains_key(&key) {
+            cache.remove(&key);
+        }
+        while cache.entries.len() >= cache.config.max_entries {
+            let Some(oldest) = cache.order.pop_front() else {
+                break;
+            };
+            cache.entries.remove(&oldest);
+            cache.metrics.evictions.inc();
+        }
+        cache.order.push_back(key.clone());
+        cache.entries.insert(
+            key,
+            CachedResult {
+                records,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Get statistics for a cached table
+    pub fn stats(&self, table: &str) -> Option<QueryCacheStats> {
+        let tables = self.tables.lock();
+        let cache = tables.get(table)?;
+        let hits = cache.metrics.hits.get();
+        let misses = cache.metrics.misses.get();
+        Some(QueryCacheStats {
+            hits,
+            misses,
+            invalidations: cache.metrics.invalidations.get(),
+            evictions: cache.metrics.evictions.get(),
+            entries: cache.entries.len(),
+            version: self.version(table),
+            hit_rate: if hits + misses > 0 {
+                hits as f64 / (hits + misses) as f64
+            } else {
+                0.0
+            },
+        })
+    }
+}
+
+impl std::fmt::Debug for QueryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tables: Vec<String> = self.tables.lock().keys().cloned().collect();
+        tables.sort();
+        f.debug_struct("QueryCache")
+            .field("tables", &tables)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Build the cache key for a query
+///
+/// Conditions are ANDed, so their order does not matter; `IN` lists are
+/// treated as sets. Sort order, paging, and the selected columns are kept.
+pub fn normalize(query: &Query) -> String {
+    let mut conditions: Vec<String> = query
+        .conditions
+        .iter()
+        .map(|c| {
+            let mut values: Vec<String> = c.values.iter().map(value_key).collect();
+            if c.op == CompareOp::In {
+                values.sort();
+                values.dedup();
+            }
+            format!("{} {} [{}]", c.column, c.op.as_str(), values.join(","))
+        })
+        .collect();
+    conditions.sort();
+    conditions.dedup();
+
+    let sorts: Vec<String> = query
+        .sorts
+        .iter()
+        .map(|s| format!("{} {:?}", s.column, s.order))
+        .collect();
+
+    let mut columns = query.select_columns.clone();
+    columns.sort();
+
+    format!(
+        "where {} | order {} | limit {:?} offset {:?} | select {}",
+        conditions.join(" AND "),
+        sorts.join(","),
+        query.limit,
+        query.offset,
+        columns.join(",")
+    )
+}
+
+fn value_key(value: &Value) -> String {
+    format!("{:?}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Condition;
+    use crate::schema::RecordBuilder;
+
+    fn rows(n: i64) -> Vec<Record> {
+        (0..n)
+            .map(|i| RecordBuilder::new().int64("id", i).build())
+            .collect()
+    }
+
+    #[test]
+    fn test_normalize_ignores_condition_order() {
+        let a = Query::new("alerts")
+            .eq("user_id", Value::Int64(7))
+            .filter(Condition::in_values(
+                "status",
+                vec![
+                    Value::String("active".into()),
+                    Value::String("paused".into()),
+                ],
+            ));
+        let b = Query::new("alerts")
+            .filter(Condition::in_values(
+                "status",
+                vec![
+                    Value::String("paused".into()),
+                    Value::String("active".into()),
+                ],
+            ))
+            .eq("user_id", Value::Int64(7));
+        assert_eq!(normalize(&a), normalize(&b));
+
+        let c = a.clone().limit(10);
+        assert_ne!(normalize(&a), normalize(&c));
+        assert_ne!(
+            normalize(&Query::new("alerts").order_asc("id")),
+            normalize(&Query::new("alerts").order_desc("id"))
+        );
+    }
+
+    #[test]
+    fn test_hit_miss_and_invalidation() {
+        let cache = QueryCache::new();
+        let query = Query::new("pools").eq("status", Value::String("open".into()));
+
+        // Disabled tables never cache
+        cache.insert("pools", &query, 0, rows(1));
+        assert!(cache.get("pools", &query).is_none());
+        assert!(cache.stats("pools").is_none());
+
+        cache.enable("pools", QueryCacheConfig::new());
+        assert!(cache.get("pools", &query).is_none());
+        cache.insert("pools", &query, cache.version("pools"), rows(2));
+        assert_eq!(cache.get("pools", &query).unwrap().len(), 2);
+
+        cache.invalidate("pools");
+        assert!(cache.get("pools", &query).is_none());
+
+        let stats = cache.stats("pools").unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 2));
+        assert_eq!(stats.invalidations, 1);
+        assert_eq!(stats.version, 1);
+        assert!((stats.hit_rate - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stale_results_are_not_stored() {
+        let cache = QueryCache::new();
+        cache.enable("alerts", QueryCacheConfig::new());
+        let query = Query::new("alerts");
+
+        let version = cache.version("alerts");
+        cache.invalidate("alerts"); // concurrent write
+        cache.insert("alerts", &query, version, rows(1));
+        assert!(cache.get("alerts", &query).is_none());
+    }
+
+    #[test]
+    fn test_capacity_and_ttl_eviction() {
+        let cache = QueryCache::new();
+        cache.enable("t", QueryCacheConfig::new().max_entries(2));
+        let queries: Vec<Query> = (0..3).map(|i| Query::new("t").limit(i)).collect();
+        for q in &queries {
+            cache.insert("t", q, 0, rows(1));
+        }
+        assert!(cache.get("t", &queries[0]).is_none());
+        assert!(cache.get("t", &queries[2]).is_some());
+        assert_eq!(cache.stats("t").unwrap().evictions, 1);
+
+        cache.enable("t", QueryCacheConfig::new().ttl(Duration::ZERO));
+        cache.insert("t", &queries[0], 0, rows(1));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(cache.get("t", &queries[0]).is_none());
+        assert_eq!(cache.stats("t").unwrap().entries, 0);
+    }
+
+    #[test]
+    fn test_metrics_published() {
+        let cache = QueryCache::new();
+        cache.enable("metrics_probe", QueryCacheConfig::new());
+        cache.get("metrics_probe", &Query::new("metrics_probe"));
+        let text = metrics::global().render_prometheus();
+        assert!(text.contains("vaya_store_query_cache_misses_total{table=\"metrics_probe\"}"));
+    }
+}
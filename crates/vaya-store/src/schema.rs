@@ -1,6 +1,7 @@
 //! Schema definitions for tables
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use rkyv::{Archive, Deserialize, Serialize};
 
@@ -59,6 +60,51 @@ impl ColumnType {
     }
 }
 
+/// Personal data classification of a column, from least to most sensitive
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Archive,
+    Serialize,
+    Deserialize,
+)]
+#[archive(check_bytes)]
+pub enum PiiClass {
+    /// Not personal data
+    #[default]
+    None,
+    /// Internal identifiers that link to a person (user IDs, device IDs)
+    Internal,
+    /// Directly identifying data (name, email, phone, address)
+    Personal,
+    /// Sensitive data (passport and ID numbers, date of birth, payment data)
+    Sensitive,
+}
+
+impl PiiClass {
+    /// Get the class name as a string
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PiiClass::None => "none",
+            PiiClass::Internal => "internal",
+            PiiClass::Personal => "personal",
+            PiiClass::Sensitive => "sensitive",
+        }
+    }
+
+    /// Whether the column holds personal data
+    pub fn is_pii(&self) -> bool {
+        *self != PiiClass::None
+    }
+}
+
 /// Column definition
 #[derive(Debug, Clone, Archive, Serialize, Deserialize)]
 #[archive(check_bytes)]
@@ -75,6 +121,12 @@ pub struct Column {
     pub unique: bool,
     /// Default value (serialized)
     pub default: Option<Vec<u8>>,
+    /// Personal data classification
+    pub pii: PiiClass,
+    /// Whether the column holds the row's expiry time
+    pub ttl: bool,
+    /// Whether the column holds the row's optimistic locking version
+    pub version: bool,
 }
 
 impl Column {
@@ -87,6 +139,9 @@ impl Column {
             primary_key: false,
             unique: false,
             default: None,
+            pii: PiiClass::None,
+            ttl: false,
+            version: false,
         }
     }
 
@@ -115,6 +170,41 @@ impl Column {
         self.default = Some(value);
         self
     }
+
+    /// Tag the column with a personal data classification
+    pub fn pii(mut self, class: PiiClass) -> Self {
+        self.pii = class;
+        self
+    }
+
+    /// Mark column as the row's expiry time (timestamp, milliseconds)
+    ///
+    /// Rows whose expiry time has passed are hidden from reads and
+    /// deleted by the TTL sweep. Rows without a value never expire.
+    pub fn ttl_column(mut self) -> Self {
+        self.ttl = true;
+        self
+    }
+
+    /// Mark column as the row's version (int64)
+    ///
+    /// [`Table::update_if_version`](crate::Table::update_if_version)
+    /// compares it before writing, so a stale update fails with a conflict.
+    pub fn version_column(mut self) -> Self {
+        self.version = true;
+        self.nullable = false;
+        self
+    }
+}
+
+/// Table-level retention: rows expire a fixed time after a timestamp column
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub struct Retention {
+    /// Timestamp column the retention is measured from
+    pub column: String,
+    /// Retention period in milliseconds
+    pub millis: u64,
 }
 
 /// Table schema definition
@@ -127,6 +217,10 @@ pub struct Schema {
     pub table_name: String,
     /// Column definitions
     pub columns: Vec<Column>,
+    /// Region the table's data must be stored in (e.g. "MY"), if restricted
+    pub residency: Option<String>,
+    /// Table-level retention period
+    pub retention: Option<Retention>,
     /// Column name to index mapping
     #[with(rkyv::with::Skip)]
     column_indices: HashMap<String, usize>,
@@ -139,10 +233,27 @@ impl Schema {
             version: 1,
             table_name: table_name.into(),
             columns: Vec::new(),
+            residency: None,
+            retention: None,
             column_indices: HashMap::new(),
         }
     }
 
+    /// Restrict the table's data to a region
+    pub fn residency(mut self, region: impl Into<String>) -> Self {
+        self.residency = Some(region.into());
+        self
+    }
+
+    /// Expire rows a fixed time after the value of a timestamp column
+    pub fn retention(mut self, column: impl Into<String>, period: Duration) -> Self {
+        self.retention = Some(Retention {
+            column: column.into(),
+            millis: period.as_millis() as u64,
+        });
+        self
+    }
+
     /// Add a column to the schema
     pub fn column(mut self, column: Column) -> Self {
         let idx = self.columns.len();
@@ -176,6 +287,96 @@ impl Schema {
             .collect()
     }
 
+    /// Get all columns tagged as personal data
+    pub fn pii_columns(&self) -> Vec<&Column> {
+        self.columns.iter().filter(|c| c.pii.is_pii()).collect()
+    }
+
+    /// Get the column marked as the row expiry time
+    pub fn ttl_column(&self) -> Option<&Column> {
+        self.columns.iter().find(|c| c.ttl)
+    }
+
+    /// Get the column marked as the row version
+    pub fn version_column(&self) -> Option<&Column> {
+        self.columns.iter().find(|c| c.version)
+    }
+
+    /// Whether rows in this table can expire
+    pub fn has_ttl(&self) -> bool {
+        self.retention.is_some() || self.ttl_column().is_some()
+    }
+
+    /// Check that the TTL column and retention refer to timestamp columns
+    pub fn validate_ttl(&self) -> StoreResult<()> {
+        let ttl_columns: Vec<&Column> = self.columns.iter().filter(|c| c.ttl).collect();
+        if ttl_columns.len() > 1 {
+            return Err(StoreError::SchemaMismatch(format!(
+                "Table {} has more than one TTL column",
+                self.table_name
+            )));
+        }
+        let retention_column = match &self.retention {
+            Some(retention) => Some(
+                self.get_column(&retention.column)
+                    .ok_or_else(|| StoreError::ColumnNotFound(retention.column.clone()))?,
+            ),
+            None => None,
+        };
+        for column in ttl_columns.into_iter().chain(retention_column) {
+            if !matches!(
+                column.column_type,
+                ColumnType::Timestamp | ColumnType::Int64
+            ) {
+                return Err(StoreError::InvalidColumnType(format!(
+                    "Expiry column {} must be a timestamp, got {:?}",
+                    column.name, column.column_type
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that there is at most one version column and it is an int64
+    pub fn validate_version(&self) -> StoreResult<()> {
+        let mut columns = self.columns.iter().filter(|c| c.version);
+        let Some(column) = columns.next() else {
+            return Ok(());
+        };
+        if columns.next().is_some() {
+            return Err(StoreError::SchemaMismatch(format!(
+                "Table {} has more than one version column",
+                self.table_name
+            )));
+        }
+        if column.column_type != ColumnType::Int64 {
+            return Err(StoreError::InvalidColumnType(format!(
+                "Version column {} must be int64, got {:?}",
+                column.name, column.column_type
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get the time (milliseconds) at which a record expires
+    ///
+    /// When both a TTL column and a retention period apply, the earlier
+    /// time wins.
+    pub fn expires_at(&self, record: &Record) -> Option<i64> {
+        let from_column = self
+            .ttl_column()
+            .and_then(|c| record.get(&c.name))
+            .and_then(Value::as_i64);
+        let from_retention = self.retention.as_ref().and_then(|retention| {
+            let base = record.get(&retention.column).and_then(Value::as_i64)?;
+            Some(base.saturating_add(retention.millis as i64))
+        });
+        match (from_column, from_retention) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
     /// Validate a record against the schema
     pub fn validate(&self, record: &Record) -> StoreResult<()> {
         for column in &self.columns {
@@ -560,6 +761,66 @@ mod tests {
         assert_eq!(schema.unique_columns().len(), 1);
     }
 
+    #[test]
+    fn test_pii_tagging() {
+        let schema = Schema::new("users")
+            .residency("MY")
+            .column(Column::new("id", ColumnType::Int64).primary_key())
+            .column(Column::new("email", ColumnType::String).pii(PiiClass::Personal))
+            .column(Column::new("passport", ColumnType::String).pii(PiiClass::Sensitive));
+
+        assert_eq!(schema.residency.as_deref(), Some("MY"));
+        assert_eq!(schema.pii_columns().len(), 2);
+        assert_eq!(schema.get_column("id").unwrap().pii, PiiClass::None);
+
+        // Tags survive the archived form used to persist schemas
+        let bytes = rkyv::to_bytes::<_, 256>(&schema).unwrap();
+        let archived = rkyv::check_archived_root::<Schema>(&bytes).unwrap();
+        let restored: Schema = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        assert_eq!(restored.columns[2].pii, PiiClass::Sensitive);
+        assert_eq!(restored.residency.as_deref(), Some("MY"));
+    }
+
+    #[test]
+    fn test_row_expiry() {
+        let schema = Schema::new("price_observations")
+            .retention("observed_at", Duration::from_secs(60))
+            .column(Column::new("id", ColumnType::Int64).primary_key())
+            .column(Column::new("observed_at", ColumnType::Timestamp))
+            .column(Column::new("expires_at", ColumnType::Timestamp).ttl_column());
+        schema.validate_ttl().unwrap();
+        assert!(schema.has_ttl());
+
+        let record = RecordBuilder::new()
+            .int64("id", 1)
+            .timestamp("observed_at", 1_000)
+            .build();
+        assert_eq!(schema.expires_at(&record), Some(61_000));
+
+        let record = RecordBuilder::new()
+            .int64("id", 1)
+            .timestamp("observed_at", 1_000)
+            .timestamp("expires_at", 5_000)
+            .build();
+        assert_eq!(schema.expires_at(&record), Some(5_000));
+        assert_eq!(
+            schema.expires_at(&RecordBuilder::new().int64("id", 2).build()),
+            None
+        );
+
+        let bad =
+            Schema::new("sessions").column(Column::new("token", ColumnType::String).ttl_column());
+        assert!(matches!(
+            bad.validate_ttl(),
+            Err(StoreError::InvalidColumnType(_))
+        ));
+        let missing = Schema::new("sessions").retention("created_at", Duration::from_secs(1));
+        assert!(matches!(
+            missing.validate_ttl(),
+            Err(StoreError::ColumnNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_value_serialization() {
         let values = vec![
@@ -1,12 +1,16 @@
 //! Table abstraction on top of vaya-db
 
+use std::collections::BTreeSet;
 use std::sync::Arc;
 
+use parking_lot::Mutex;
 use rkyv::Deserialize;
 use vaya_db::VayaDb;
 
 use crate::index::Index;
+use crate::privacy::RedactionPolicy;
 use crate::query::{Query, SortOrder};
+use crate::query_cache::QueryCache;
 use crate::schema::{Record, Schema, Value};
 use crate::{StoreError, StoreResult, SCHEMA_PREFIX, TABLE_META_PREFIX};
 
@@ -20,6 +24,12 @@ pub struct Table {
     db: Arc<VayaDb>,
     /// Indexes on this table
     indexes: Vec<Index>,
+    /// Shared query result cache
+    query_cache: Option<Arc<QueryCache>>,
+    /// Pending row expiries as (expires_at, primary key bytes)
+    expiries: Mutex<BTreeSet<(i64, Vec<u8>)>>,
+    /// Serializes updates so a version check and its write are atomic
+    update_lock: Mutex<()>,
 }
 
 impl Table {
@@ -31,12 +41,17 @@ impl Table {
             schema,
             db,
             indexes: Vec::new(),
+            query_cache: None,
+            expiries: Mutex::new(BTreeSet::new()),
+            update_lock: Mutex::new(()),
         }
     }
 
     /// Create a table from schema, storing the schema in the database
     pub fn create(schema: Schema, db: Arc<VayaDb>) -> StoreResult<Self> {
         let name = schema.table_name.clone();
+        schema.validate_ttl()?;
+        schema.validate_version()?;
 
         // Check if table already exists
         let meta_key = Self::meta_key(&name);
@@ -58,6 +73,9 @@ impl Table {
             schema,
             db,
             indexes: Vec::new(),
+            query_cache: None,
+            expiries: Mutex::new(BTreeSet::new()),
+            update_lock: Mutex::new(()),
         })
     }
 
@@ -89,9 +107,20 @@ impl Table {
             schema,
             db,
             indexes: Vec::new(),
+            query_cache: None,
+            expiries: Mutex::new(BTreeSet::new()),
+            update_lock: Mutex::new(()),
         })
     }
 
+    /// Attach a query result cache
+    ///
+    /// Results are only cached if the cache has this table enabled.
+    pub fn with_query_cache(mut self, cache: Arc<QueryCache>) -> Self {
+        self.query_cache = Some(cache);
+        self
+    }
+
     /// Get the table name
     pub fn name(&self) -> &str {
         &self.name
@@ -173,6 +202,8 @@ impl Table {
 
         // Update indexes
         self.update_indexes(&pk, record)?;
+        self.track_expiry(&pk, record);
+        self.invalidate_cache();
 
         Ok(())
     }
@@ -182,14 +213,66 @@ impl Table {
         // Validate record
         self.schema.validate(record)?;
 
-        let data_key = self.data_key(pk);
+        let _guard = self.update_lock.lock();
+        let old_record = self.load(pk)?;
+        self.replace(pk, &old_record, record)
+    }
+
+    /// Update a record only if its stored version is `expected_version`
+    ///
+    /// The table must have a version column, and `record` must carry a
+    /// version greater than `expected_version`. If another writer updated
+    /// the row since it was read, fails with [`StoreError::Conflict`] and
+    /// writes nothing; the caller reloads and retries.
+    pub fn update_if_version(
+        &self,
+        pk: &Value,
+        expected_version: i64,
+        record: &Record,
+    ) -> StoreResult<()> {
+        self.schema.validate(record)?;
+
+        let column = self
+            .schema
+            .version_column()
+            .ok_or(StoreError::InvalidQuery(
+                "Table has no version column".into(),
+            ))?;
+        let new_version = record.get(&column.name).and_then(Value::as_i64);
+        if new_version.filter(|&v| v > expected_version).is_none() {
+            return Err(StoreError::InvalidQuery(format!(
+                "Column {} must be greater than {} in the updated record",
+                column.name, expected_version
+            )));
+        }
 
-        // Check if record exists
-        let old_bytes = self.db.get(&data_key)?.ok_or(StoreError::NotFound)?;
+        let _guard = self.update_lock.lock();
+        let old_record = self.load(pk)?;
+        let actual = old_record
+            .get(&column.name)
+            .and_then(Value::as_i64)
+            .unwrap_or(0);
+        if actual != expected_version {
+            return Err(StoreError::Conflict {
+                expected: expected_version,
+                actual,
+            });
+        }
+        self.replace(pk, &old_record, record)
+    }
 
-        let old_record = Record::from_bytes(&old_bytes)
-            .ok_or_else(|| StoreError::Serialization("Invalid record".into()))?;
+    /// Load the stored record for an update
+    fn load(&self, pk: &Value) -> StoreResult<Record> {
+        let old_bytes = self
+            .db
+            .get(&self.data_key(pk))?
+            .ok_or(StoreError::NotFound)?;
+        Record::from_bytes(&old_bytes)
+            .ok_or_else(|| StoreError::Serialization("Invalid record".into()))
+    }
 
+    /// Overwrite `old_record` with `record`, keeping indexes in step
+    fn replace(&self, pk: &Value, old_record: &Record, record: &Record) -> StoreResult<()> {
         // Check unique constraints for changed values
         for col in self.schema.unique_columns() {
             if let (Some(old_val), Some(new_val)) =
@@ -204,14 +287,17 @@ impl Table {
         }
 
         // Remove old index entries
-        self.remove_indexes(pk, &old_record)?;
+        self.remove_indexes(pk, old_record)?;
+        self.untrack_expiry(pk, old_record);
 
         // Serialize and store
         let record_bytes = record.to_bytes();
-        self.db.put(&data_key, &record_bytes)?;
+        self.db.put(&self.data_key(pk), &record_bytes)?;
 
         // Update indexes with new values
         self.update_indexes(pk, record)?;
+        self.track_expiry(pk, record);
+        self.invalidate_cache();
 
         Ok(())
     }
@@ -227,9 +313,11 @@ impl Table {
 
             // Remove index entries
             self.remove_indexes(pk, &old_record)?;
+            self.untrack_expiry(pk, &old_record);
 
             // Delete the record
             self.db.delete(&data_key)?;
+            self.invalidate_cache();
             Ok(true)
         } else {
             Ok(false)
@@ -237,6 +325,8 @@ impl Table {
     }
 
     /// Get a record by primary key
+    ///
+    /// Expired rows are not returned, even before the sweep deletes them.
     pub fn get(&self, pk: &Value) -> StoreResult<Option<Record>> {
         let data_key = self.data_key(pk);
 
@@ -244,12 +334,77 @@ impl Table {
             Some(bytes) => {
                 let record = Record::from_bytes(&bytes)
                     .ok_or_else(|| StoreError::Serialization("Invalid record".into()))?;
+                if self.is_expired(&record, crate::ttl::now_millis()) {
+                    return Ok(None);
+                }
                 Ok(Some(record))
             }
             None => Ok(None),
         }
     }
 
+    /// Delete rows that expired at or before `now` (milliseconds)
+    ///
+    /// Index entries are removed along with the rows. Returns the number
+    /// of rows deleted.
+    pub fn purge_expired(&self, now: i64) -> StoreResult<usize> {
+        let due: Vec<(i64, Vec<u8>)> = {
+            let expiries = self.expiries.lock();
+            expiries
+                .range(..(now.saturating_add(1), Vec::new()))
+                .cloned()
+                .collect()
+        };
+
+        let mut purged = 0;
+        for (expires_at, pk_bytes) in due {
+            let Some(pk) = Value::from_bytes(&pk_bytes) else {
+                self.expiries.lock().remove(&(expires_at, pk_bytes));
+                continue;
+            };
+            // The row may have been updated with a later expiry since the
+            // entry was read, so check the stored record again
+            let data_key = self.data_key(&pk);
+            let expired = match self.db.get(&data_key)? {
+                Some(bytes) => Record::from_bytes(&bytes)
+                    .map(|record| self.is_expired(&record, now))
+                    .unwrap_or(true),
+                None => true,
+            };
+            if expired && self.delete(&pk)? {
+                purged += 1;
+            }
+            self.expiries.lock().remove(&(expires_at, pk_bytes));
+        }
+        Ok(purged)
+    }
+
+    /// Earliest pending row expiry (milliseconds)
+    pub fn next_expiry(&self) -> Option<i64> {
+        self.expiries.lock().first().map(|(at, _)| *at)
+    }
+
+    /// Whether a record has expired at `now` (milliseconds)
+    fn is_expired(&self, record: &Record, now: i64) -> bool {
+        self.schema
+            .expires_at(record)
+            .is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Queue a record's expiry for the TTL sweep
+    fn track_expiry(&self, pk: &Value, record: &Record) {
+        if let Some(expires_at) = self.schema.expires_at(record) {
+            self.expiries.lock().insert((expires_at, pk.to_bytes()));
+        }
+    }
+
+    /// Drop a record's queued expiry
+    fn untrack_expiry(&self, pk: &Value, record: &Record) {
+        if let Some(expires_at) = self.schema.expires_at(record) {
+            self.expiries.lock().remove(&(expires_at, pk.to_bytes()));
+        }
+    }
+
     /// Find a record by a column value (for unique constraint checking)
     fn find_by_value(&self, column: &str, value: &Value) -> StoreResult<Option<Record>> {
         // This is a simple scan for now - indexes would make this faster
@@ -261,9 +416,30 @@ impl Table {
         Ok(None)
     }
 
-    /// Execute a query
+    /// Execute a query, serving it from the query cache when possible
     pub fn query(&self, query: &Query) -> StoreResult<Vec<Record>> {
-        let mut results: Vec<Record> = self.scan()?.filter(|r| query.matches(r)).collect();
+        let Some(cache) = &self.query_cache else {
+            return self.execute_query(query);
+        };
+        if let Some(records) = cache.get(&self.name, query) {
+            return Ok(records);
+        }
+
+        // Read the version before executing so a concurrent write
+        // prevents the (possibly stale) result from being cached
+        let version = cache.version(&self.name);
+        let results = self.execute_query(query)?;
+        cache.insert(&self.name, query, version, results.clone());
+        Ok(results)
+    }
+
+    /// Execute a query against storage
+    fn execute_query(&self, query: &Query) -> StoreResult<Vec<Record>> {
+        let now = crate::ttl::now_millis();
+        let mut results: Vec<Record> = self
+            .scan()?
+            .filter(|r| query.matches(r) && !self.is_expired(r, now))
+            .collect();
 
         // Apply sorting
         if !query.sorts.is_empty() {
@@ -329,18 +505,30 @@ impl Table {
 
     /// Scan all records in the table
     pub fn scan(&self) -> StoreResult<impl Iterator<Item = Record>> {
-        let _prefix = self.data_key_prefix();
-
-        // Collect all matching records
-        // In a real implementation, we'd use a range scan
-        let records = Vec::new();
-
-        // For now, we'll use a simple approach since VayaDb doesn't expose iteration
-        // This would need to be improved with proper range scan support
+        let records = self
+            .db
+            .scan_prefix(&self.data_key_prefix())?
+            .into_iter()
+            .map(|(_, bytes)| {
+                Record::from_bytes(&bytes)
+                    .ok_or_else(|| StoreError::Serialization("Invalid record".into()))
+            })
+            .collect::<StoreResult<Vec<_>>>()?;
 
         Ok(records.into_iter())
     }
 
+    /// Export records matching a query, applying a redaction policy
+    ///
+    /// Fails if any personal data column in the results has no rule in the
+    /// policy.
+    pub fn export(&self, query: &Query, policy: &RedactionPolicy) -> StoreResult<Vec<Record>> {
+        self.query(query)?
+            .iter()
+            .map(|record| self.schema.export_record(record, policy))
+            .collect()
+    }
+
     /// Count records matching a query
     pub fn count(&self, query: &Query) -> StoreResult<usize> {
         Ok(self.query(query)?.len())
@@ -359,9 +547,17 @@ impl Table {
         }
 
         self.indexes.push(index);
+        self.invalidate_cache();
         Ok(())
     }
 
+    /// Bump the table version in the query cache after a write
+    fn invalidate_cache(&self) {
+        if let Some(cache) = &self.query_cache {
+            cache.invalidate(&self.name);
+        }
+    }
+
     /// Update indexes for a record
     fn update_indexes(&self, pk: &Value, record: &Record) -> StoreResult<()> {
         let pk_bytes = pk.to_bytes();
@@ -499,4 +695,83 @@ mod tests {
         assert!(table.get(&Value::Int64(1)).unwrap().is_none());
         assert!(!table.delete(&Value::Int64(1)).unwrap()); // Already deleted
     }
+
+    #[test]
+    fn test_update_if_version_rejects_stale_writes() {
+        let test = create_test_db();
+        let schema = Schema::new("pools")
+            .column(Column::new("id", ColumnType::Int64).primary_key())
+            .column(Column::new("members", ColumnType::Int64))
+            .column(Column::new("version", ColumnType::Int64).version_column());
+        let table = Table::create(schema, test.db.clone()).unwrap();
+
+        let row = |members: i64, version: i64| {
+            RecordBuilder::new()
+                .int64("id", 1)
+                .int64("members", members)
+                .int64("version", version)
+                .build()
+        };
+        table.insert(&row(1, 1)).unwrap();
+
+        // Two nodes read version 1 and both try to add a member
+        table
+            .update_if_version(&Value::Int64(1), 1, &row(2, 2))
+            .unwrap();
+        assert!(matches!(
+            table.update_if_version(&Value::Int64(1), 1, &row(2, 2)),
+            Err(StoreError::Conflict {
+                expected: 1,
+                actual: 2
+            })
+        ));
+
+        // The loser reloads and retries on the current version
+        table
+            .update_if_version(&Value::Int64(1), 2, &row(3, 3))
+            .unwrap();
+        let stored = table.get(&Value::Int64(1)).unwrap().unwrap();
+        assert_eq!(stored.get("members"), Some(&Value::Int64(3)));
+
+        // The new row has to move the version forward
+        assert!(matches!(
+            table.update_if_version(&Value::Int64(1), 3, &row(4, 3)),
+            Err(StoreError::InvalidQuery(_))
+        ));
+        let missing = RecordBuilder::new()
+            .int64("id", 2)
+            .int64("version", 2)
+            .build();
+        assert!(matches!(
+            table.update_if_version(&Value::Int64(2), 1, &missing),
+            Err(StoreError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn test_table_writes_invalidate_query_cache() {
+        let test = create_test_db();
+        let cache = Arc::new(QueryCache::new());
+        cache.enable("users", crate::QueryCacheConfig::new());
+
+        let schema =
+            Schema::new("users").column(Column::new("id", ColumnType::Int64).primary_key());
+        let table = Table::create(schema, test.db.clone())
+            .unwrap()
+            .with_query_cache(cache.clone());
+
+        let query = Query::new("users");
+        table.query(&query).unwrap();
+        table.query(&query).unwrap();
+        assert_eq!(cache.stats("users").unwrap().hits, 1);
+
+        table
+            .insert(&RecordBuilder::new().int64("id", 1).build())
+            .unwrap();
+        assert_eq!(cache.version("users"), 1);
+        assert_eq!(cache.stats("users").unwrap().entries, 0);
+
+        table.delete(&Value::Int64(1)).unwrap();
+        assert_eq!(cache.version("users"), 2);
+    }
 }
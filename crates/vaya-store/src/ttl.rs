@@ -0,0 +1,227 @@
+//! Row expiry (TTL)
+//!
+//! A table's rows can expire in two ways, declared on the [`Schema`]:
+//!
+//! - a column marked with [`Column::ttl_column`] holding the absolute
+//!   expiry time of each row (e.g. a session's `expires_at`)
+//! - a table-level [`Schema::retention`] period measured from a timestamp
+//!   column (e.g. price observations kept for 90 days after `observed_at`)
+//!
+//! Expired rows are hidden from reads immediately. The [`TtlSweeper`]
+//! deletes them, along with their index entries, in the background.
+//!
+//! Pending expiries are tracked in memory as rows are written, so rows
+//! written before a restart are only hidden, not deleted, until they are
+//! rewritten or removed.
+//!
+//! Deleted rows are counted under `vaya_store_ttl_expired_rows_total` with a
+//! `table` label.
+//!
+//! [`Schema`]: crate::Schema
+//! [`Column::ttl_column`]: crate::Column::ttl_column
+//! [`Schema::retention`]: crate::Schema::retention
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use parking_lot::RwLock;
+use vaya_common::metrics;
+
+use crate::table::Table;
+
+/// Metric counting rows deleted by the TTL sweep
+pub const EXPIRED_ROWS_METRIC: &str = "vaya_store_ttl_expired_rows_total";
+
+/// Current time in milliseconds since the Unix epoch
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Result of one sweep over the registered tables
+#[derive(Debug, Clone, Default)]
+pub struct TtlSweepReport {
+    /// Tables swept
+    pub tables: usize,
+    /// Rows deleted
+    pub purged: usize,
+    /// Tables whose sweep failed, with the error
+    pub errors: Vec<(String, String)>,
+}
+
+/// Background sweep that deletes expired rows
+pub struct TtlSweeper {
+    tables: RwLock<Vec<Arc<Table>>>,
+    interval: Duration,
+}
+
+impl TtlSweeper {
+    /// Create a sweeper that runs every `interval`
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            tables: RwLock::new(Vec::new()),
+            interval,
+        }
+    }
+
+    /// Register a table for sweeping
+    ///
+    /// Tables without a TTL column or retention period are ignored.
+    pub fn register(&self, table: Arc<Table>) {
+        if table.schema().has_ttl() {
+            self.tables.write().push(table);
+        }
+    }
+
+    /// Number of registered tables
+    pub fn table_count(&self) -> usize {
+        self.tables.read().len()
+    }
+
+    /// Sweep all tables now
+    pub fn sweep(&self) -> TtlSweepReport {
+        self.sweep_at(now_millis())
+    }
+
+    /// Sweep all tables as of `now` (milliseconds)
+    pub fn sweep_at(&self, now: i64) -> TtlSweepReport {
+        let tables = self.tables.read().clone();
+        let mut report = TtlSweepReport {
+            tables: tables.len(),
+            ..Default::default()
+        };
+
+        for table in tables {
+            match table.purge_expired(now) {
+                Ok(0) => {}
+                Ok(purged) => {
+                    report.purged += purged;
+                    metrics::global()
+                        .counter(EXPIRED_ROWS_METRIC, &[("table", table.name())])
+                        .add(purged as u64);
+                    tracing::debug!(table = table.name(), purged, "Expired rows deleted");
+                }
+                Err(e) => {
+                    tracing::warn!(table = table.name(), error = %e, "TTL sweep failed");
+                    report
+                        .errors
+                        .push((table.name().to_string(), e.to_string()));
+                }
+            }
+        }
+        report
+    }
+
+    /// Run the sweep on a background thread until the handle is stopped
+    pub fn spawn(self: Arc<Self>) -> TtlSweeperHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            std::thread::Builder::new()
+                .name("vaya-store-ttl".into())
+                .spawn(move || {
+                    while !stop.load(Ordering::Acquire) {
+                        self.sweep();
+                        std::thread::park_timeout(self.interval);
+                    }
+                })
+                .expect("failed to spawn TTL sweeper thread")
+        };
+        TtlSweeperHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Handle to a running [`TtlSweeper`]; stops the sweeper when dropped
+pub struct TtlSweeperHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TtlSweeperHandle {
+    /// Stop the sweeper and wait for the thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for TtlSweeperHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::Index;
+    use crate::schema::{Column, ColumnType, RecordBuilder, Schema, Value};
+    use vaya_db::{DbConfig, VayaDb};
+
+    #[test]
+    fn test_sweep_deletes_expired_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = DbConfig::new(dir.path())
+            .memtable_size(1024 * 1024)
+            .wal_enabled(false);
+        let db = Arc::new(VayaDb::open(config).unwrap());
+
+        let schema = Schema::new("sessions")
+            .column(Column::new("id", ColumnType::Int64).primary_key())
+            .column(Column::new("user", ColumnType::String))
+            .column(Column::new("expires_at", ColumnType::Timestamp).ttl_column());
+        let mut table = Table::create(schema, db.clone()).unwrap();
+        table
+            .add_index(Index::btree("sessions_user", "sessions", "user"))
+            .unwrap();
+        let table = Arc::new(table);
+
+        for (id, expires_at) in [(1, 1_000), (2, 2_000), (3, 5_000)] {
+            let record = RecordBuilder::new()
+                .int64("id", id)
+                .string("user", format!("u{}", id))
+                .timestamp("expires_at", expires_at)
+                .build();
+            table.insert(&record).unwrap();
+        }
+        // Extending a session moves its expiry
+        let extended = RecordBuilder::new()
+            .int64("id", 2)
+            .string("user", "u2")
+            .timestamp("expires_at", 9_000)
+            .build();
+        table.update(&Value::Int64(2), &extended).unwrap();
+        assert_eq!(table.next_expiry(), Some(1_000));
+
+        let sweeper = TtlSweeper::new(Duration::from_secs(60));
+        sweeper.register(table.clone());
+        let report = sweeper.sweep_at(5_000);
+        assert_eq!(report.purged, 2);
+        assert!(report.errors.is_empty());
+        assert_eq!(table.next_expiry(), Some(9_000));
+
+        // The index entry for a purged row is gone
+        let index = Index::btree("sessions_user", "sessions", "user");
+        let key = index.key_for_value(&Value::String("u1".into()), &Value::Int64(1).to_bytes());
+        assert!(db.get(&key).unwrap().is_none());
+
+        // Tables without expiry are not registered
+        let plain = Schema::new("plain").column(Column::new("id", ColumnType::Int64).primary_key());
+        sweeper.register(Arc::new(Table::create(plain, db).unwrap()));
+        assert_eq!(sweeper.table_count(), 1);
+    }
+}
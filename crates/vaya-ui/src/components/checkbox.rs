@@ -24,6 +24,9 @@ pub fn Checkbox(
     /// Callback when checked state changes
     #[prop(optional)]
     on_change: Option<Callback<bool>>,
+    /// Optional ID for the input element (defaults to one derived from the label)
+    #[prop(optional, into)]
+    id: Option<String>,
 ) -> impl IntoView {
     let has_error = error.is_some();
 
@@ -38,15 +41,17 @@ pub fn Checkbox(
         classes.join(" ")
     };
 
-    let checkbox_id = format!(
-        "checkbox-{}",
-        label
-            .to_lowercase()
-            .replace(' ', "-")
-            .chars()
-            .take(20)
-            .collect::<String>()
-    );
+    let checkbox_id = id.unwrap_or_else(|| {
+        format!(
+            "checkbox-{}",
+            label
+                .to_lowercase()
+                .replace(' ', "-")
+                .chars()
+                .take(20)
+                .collect::<String>()
+        )
+    });
     let error_id = format!("{}-error", checkbox_id);
     let error_id_attr = error_id.clone();
 
@@ -43,6 +43,9 @@ pub fn SelectInput(
     /// Callback when selection changes
     #[prop(optional)]
     on_change: Option<Callback<String>>,
+    /// Optional ID for the select element (defaults to one derived from the label)
+    #[prop(optional, into)]
+    id: Option<String>,
 ) -> impl IntoView {
     let (is_focused, set_focused) = create_signal(false);
     let has_error = error.is_some();
@@ -61,7 +64,8 @@ pub fn SelectInput(
         classes.join(" ")
     };
 
-    let select_id = format!("select-{}", label.to_lowercase().replace(' ', "-"));
+    let select_id =
+        id.unwrap_or_else(|| format!("select-{}", label.to_lowercase().replace(' ', "-")));
     let error_id = format!("{}-error", select_id);
     let error_id_attr = error_id.clone();
     let placeholder_text = placeholder.unwrap_or_else(|| "Select...".to_string());
@@ -5,7 +5,7 @@
 use gloo_net::http::Request;
 
 use crate::hooks::config;
-use crate::types::{ApiError, Flight, OraclePrediction, SearchRequest};
+use crate::types::{ApiError, Flight, OraclePrediction, SearchDefaults, SearchRequest};
 
 /// Result type for API operations
 pub type ApiResult<T> = Result<T, ApiError>;
@@ -111,6 +111,40 @@ pub async fn get_flight(flight_id: &str) -> ApiResult<Flight> {
     }
 }
 
+/// Get default search parameters for the Home and search forms
+pub async fn get_search_defaults(last_origin: Option<&str>) -> ApiResult<SearchDefaults> {
+    let mut url = format!("{}/defaults", config::api_base());
+    if let Some(origin) = last_origin {
+        url.push_str(&format!("?last_origin={}", origin));
+    }
+
+    let response = Request::get(&url)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| ApiError {
+            code: "NETWORK_ERROR".to_string(),
+            message: "Failed to connect to server".to_string(),
+            details: Some(e.to_string()),
+        })?;
+
+    if response.ok() {
+        response.json().await.map_err(|e| ApiError {
+            code: "PARSE_ERROR".to_string(),
+            message: "Failed to parse response".to_string(),
+            details: Some(e.to_string()),
+        })
+    } else {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        Err(ApiError {
+            code: format!("HTTP_{}", status),
+            message: format!("Request failed with status {}", status),
+            details: Some(body),
+        })
+    }
+}
+
 /// Health check - verify API is reachable
 pub async fn health_check() -> ApiResult<bool> {
     let url = format!("{}/health", config::api_base());
@@ -6,7 +6,7 @@ use leptos::*;
 use leptos_router::use_navigate;
 use web_sys::Storage;
 
-use crate::types::InsuranceType;
+use crate::types::{InsuranceType, Price};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -85,7 +85,7 @@ fn format_price(amount: i64) -> String {
     if amount == 0 {
         "Free".to_string()
     } else {
-        format!("+ RM {:.2}", amount as f64 / 100.0)
+        format!("+ {}", Price::myr(amount))
     }
 }
 
@@ -270,7 +270,7 @@ pub fn ExtrasSelection() -> impl IntoView {
                 <div class="extras-summary">
                     <span class="summary-label">"Extras total"</span>
                     <span class="summary-amount">
-                        {move || format!("RM {:.2}", extras_total() as f64 / 100.0)}
+                        {move || Price::myr(extras_total()).format()}
                     </span>
                 </div>
                 <button
@@ -6,7 +6,7 @@ use leptos::*;
 use leptos_router::use_navigate;
 use web_sys::Storage;
 
-use crate::types::InsuranceType;
+use crate::types::{InsuranceType, Price};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -24,9 +24,18 @@ impl BaggageOption {
     pub fn options() -> Vec<Self> {
         vec![
             BaggageOption { kg: 0, price: 0 },
-            BaggageOption { kg: 20, price: 8000 },
-            BaggageOption { kg: 25, price: 10000 },
-            BaggageOption { kg: 30, price: 12000 },
+            BaggageOption {
+                kg: 20,
+                price: 8000,
+            },
+            BaggageOption {
+                kg: 25,
+                price: 10000,
+            },
+            BaggageOption {
+                kg: 30,
+                price: 12000,
+            },
         ]
     }
 }
@@ -76,7 +85,7 @@ fn format_price(amount: i64) -> String {
     if amount == 0 {
         "Free".to_string()
     } else {
-        format!("+ RM {:.2}", amount as f64 / 100.0)
+        format!("+ {}", Price::myr(amount))
     }
 }
 
@@ -86,7 +95,7 @@ pub fn ExtrasSelection() -> impl IntoView {
     let navigate = use_navigate();
 
     // State
-    let (selected_baggage, set_baggage) = create_signal(0u8);  // kg
+    let (selected_baggage, set_baggage) = create_signal(0u8); // kg
     let (selected_meal, set_meal) = create_signal::<Option<String>>(None);
     let (selected_insurance, set_insurance) = create_signal::<Option<InsuranceType>>(None);
 
@@ -98,13 +107,17 @@ pub fn ExtrasSelection() -> impl IntoView {
             .map(|b| b.price)
             .unwrap_or(0);
 
-        let meal_cost = selected_meal.get()
-            .and_then(|id| MealOption::options().iter().find(|m| m.id == id).map(|m| m.price))
+        let meal_cost = selected_meal
+            .get()
+            .and_then(|id| {
+                MealOption::options()
+                    .iter()
+                    .find(|m| m.id == id)
+                    .map(|m| m.price)
+            })
             .unwrap_or(0);
 
-        let insurance_cost = selected_insurance.get()
-            .map(|i| i.price_myr())
-            .unwrap_or(0);
+        let insurance_cost = selected_insurance.get().map(|i| i.price_myr()).unwrap_or(0);
 
         bag_cost + meal_cost + insurance_cost
     };
@@ -257,7 +270,7 @@ pub fn ExtrasSelection() -> impl IntoView {
                 <div class="extras-summary">
                     <span class="summary-label">"Extras total"</span>
                     <span class="summary-amount">
-                        {move || format!("RM {:.2}", extras_total() as f64 / 100.0)}
+                        {move || Price::myr(extras_total()).format()}
                     </span>
                 </div>
                 <button
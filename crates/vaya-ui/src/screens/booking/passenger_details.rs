@@ -6,14 +6,131 @@ use leptos::*;
 use leptos_router::use_navigate;
 use web_sys::Storage;
 
-use crate::components::{TextInput, DateInput, TitleSelect, CountrySelect};
-use crate::types::{Passenger, PassengerType};
+use crate::components::{Checkbox, TextInput, DateInput, TitleSelect, CountrySelect, SelectInput, SelectOption};
+use crate::types::{AssistanceNeeds, Passenger, PassengerType};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
     web_sys::window()?.session_storage().ok()?
 }
 
+/// Empty string as None
+fn non_empty(value: String) -> Option<String> {
+    if value.is_empty() { None } else { Some(value) }
+}
+
+/// Accessibility assistance section of a passenger form
+#[component]
+fn AssistanceSection(
+    /// Passenger index (1-based), for unique field labels
+    index: usize,
+    /// Assistance needs signal
+    assistance: RwSignal<AssistanceNeeds>,
+) -> impl IntoView {
+    let initial = assistance.get_untracked();
+    let needed = create_rw_signal(!initial.is_empty());
+    let wheelchair = create_rw_signal(initial.wheelchair.clone().unwrap_or_default());
+    let mobility_aid = create_rw_signal(initial.mobility_aid.clone().unwrap_or_default());
+    let battery = create_rw_signal(initial.battery_watt_hours.map(|wh| wh.to_string()).unwrap_or_default());
+    let visual = create_rw_signal(initial.visual);
+    let hearing = create_rw_signal(initial.hearing);
+    let cognitive = create_rw_signal(initial.cognitive);
+    let service_animal = create_rw_signal(initial.service_animal);
+    let remarks = create_rw_signal(initial.remarks.clone().unwrap_or_default());
+
+    create_effect(move |_| {
+        let needs = if needed.get() {
+            AssistanceNeeds {
+                wheelchair: non_empty(wheelchair.get()),
+                mobility_aid: non_empty(mobility_aid.get()),
+                battery_watt_hours: battery.get().trim().parse().ok(),
+                visual: visual.get(),
+                hearing: hearing.get(),
+                cognitive: cognitive.get(),
+                service_animal: service_animal.get(),
+                remarks: non_empty(remarks.get().trim().to_string()),
+            }
+        } else {
+            AssistanceNeeds::default()
+        };
+        assistance.set(needs);
+    });
+
+    let wheelchair_options = vec![
+        SelectOption::new("WCHR", "To the aircraft door - I can climb steps"),
+        SelectOption::new("WCHS", "To my seat - I cannot climb steps"),
+        SelectOption::new("WCHC", "Carried to my seat - I cannot walk"),
+    ];
+    let mobility_options = vec![
+        SelectOption::new("WCMP", "Manual wheelchair"),
+        SelectOption::new("WCBD", "Powered - dry/sealed battery"),
+        SelectOption::new("WCBW", "Powered - wet/spillable battery"),
+        SelectOption::new("WCLB", "Powered - lithium battery"),
+    ];
+    let error = move || assistance.with(|a| a.validate().err());
+
+    view! {
+        <div class="assistance-section" role="group" aria-labelledby=format!("assistance-title-{}", index)>
+            <h4 class="section-title" id=format!("assistance-title-{}", index)>"Accessibility Assistance"</h4>
+            <Checkbox
+                label="I need assistance at the airport or on board"
+                checked=needed
+                id=format!("assistance-needed-{}", index)
+            />
+            <Show when=move || needed.get()>
+                <div class="form-row two-col">
+                    <SelectInput
+                        label="Wheelchair"
+                        id=format!("assistance-wheelchair-{}", index)
+                        options=wheelchair_options.clone()
+                        value=wheelchair
+                        placeholder="No wheelchair needed"
+                    />
+                    <SelectInput
+                        label="Own Wheelchair"
+                        id=format!("assistance-mobility-{}", index)
+                        options=mobility_options.clone()
+                        value=mobility_aid
+                        placeholder="Not bringing one"
+                    />
+                </div>
+                <Show when=move || mobility_aid.get() == "WCLB">
+                    <div class="form-row">
+                        <TextInput
+                            label="Battery Rating (Wh)"
+                            placeholder="e.g. 160"
+                            value=battery
+                            input_type="number"
+                            id=format!("assistance-battery-{}", index)
+                        />
+                    </div>
+                </Show>
+                <div class="form-row assistance-options">
+                    <Checkbox label="Blind or low vision" checked=visual id=format!("assistance-visual-{}", index) />
+                    <Checkbox label="Deaf or hard of hearing" checked=hearing id=format!("assistance-hearing-{}", index) />
+                    <Checkbox label="Intellectual or developmental disability" checked=cognitive id=format!("assistance-cognitive-{}", index) />
+                    <Checkbox label="Travelling with a service animal" checked=service_animal id=format!("assistance-animal-{}", index) />
+                </div>
+                <div class="form-row">
+                    <TextInput
+                        label="Details for the Airline"
+                        placeholder="e.g. Travelling with companion, needs aisle chair"
+                        value=remarks
+                        maxlength=AssistanceNeeds::MAX_REMARKS_LEN as u32
+                        id=format!("assistance-remarks-{}", index)
+                    />
+                </div>
+                {move || error().map(|err| view! {
+                    <p class="assistance-error" role="alert">{err}</p>
+                })}
+                <p class="assistance-note">
+                    "We send these requests to the airline and will confirm once they accept."
+                </p>
+            </Show>
+        </div>
+    }
+}
+
 /// Passenger form for a single passenger
 #[component]
 fn PassengerForm(
@@ -34,6 +151,7 @@ fn PassengerForm(
     let nationality = create_rw_signal(passenger.get().nationality.unwrap_or_default());
     let passport_number = create_rw_signal(passenger.get().passport_number.unwrap_or_default());
     let passport_expiry = create_rw_signal(passenger.get().passport_expiry.unwrap_or_default());
+    let assistance = create_rw_signal(passenger.get().assistance);
 
     // Update main passenger signal when fields change
     create_effect(move |_| {
@@ -45,6 +163,7 @@ fn PassengerForm(
             p.nationality = if nationality.get().is_empty() { None } else { Some(nationality.get()) };
             p.passport_number = if passport_number.get().is_empty() { None } else { Some(passport_number.get()) };
             p.passport_expiry = if passport_expiry.get().is_empty() { None } else { Some(passport_expiry.get()) };
+            p.assistance = assistance.get();
         });
     });
 
@@ -115,6 +234,8 @@ fn PassengerForm(
                         </div>
                     </div>
                 })}
+
+                <AssistanceSection index=index assistance=assistance />
             </div>
         </div>
     }
@@ -170,7 +291,7 @@ pub fn PassengerDetails() -> impl IntoView {
     let is_valid = move || {
         passengers_for_valid.iter().all(|p| {
             let pax = p.get();
-            !pax.first_name.is_empty() && !pax.last_name.is_empty()
+            !pax.first_name.is_empty() && !pax.last_name.is_empty() && pax.assistance.validate().is_ok()
         })
     };
 
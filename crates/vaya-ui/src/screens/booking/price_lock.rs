@@ -8,7 +8,7 @@ use web_sys::Storage;
 
 use crate::components::{FlightMini, PriceLockFee};
 use crate::hooks::{mock_price_lock, set_price_lock, use_booking_state};
-use crate::types::{Flight, PriceLockDuration};
+use crate::types::{Flight, Price, PriceLockDuration};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -190,7 +190,7 @@ pub fn PriceLock() -> impl IntoView {
                 >
                     {move || {
                         if let Some(duration) = selected_duration.get() {
-                            format!("Lock for {} - RM {:.2}", duration.display_text(), duration.fee_myr() as f64 / 100.0)
+                            format!("Lock for {} - {}", duration.display_text(), Price::myr(duration.fee_myr()))
                         } else {
                             "Select duration".to_string()
                         }
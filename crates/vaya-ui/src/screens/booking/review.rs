@@ -7,7 +7,7 @@ use leptos_router::use_navigate;
 use web_sys::Storage;
 
 use crate::components::{FlightCard, PriceBreakdown, PriceLineItem, TermsCheckbox};
-use crate::types::{Flight, Passenger};
+use crate::types::{Flight, Passenger, Price};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -231,7 +231,7 @@ pub fn OrderReview() -> impl IntoView {
             <div class="review-footer">
                 <div class="total-display">
                     <span class="total-label">"Total"</span>
-                    <span class="total-amount">{format!("RM {:.2}", total as f64 / 100.0)}</span>
+                    <span class="total-amount">{Price::myr(total).format()}</span>
                 </div>
                 <button
                     class="btn btn-primary btn-lg btn-full"
@@ -4,7 +4,7 @@
 //! This is the entry point to the Oracle flow.
 
 use crate::components::{AirportPicker, DateInput, SwapButton};
-use crate::hooks::set_search_params;
+use crate::hooks::{get_search_defaults, set_search_params};
 use leptos::*;
 use leptos_router::use_navigate;
 
@@ -20,6 +20,15 @@ pub fn Home() -> impl IntoView {
     let departure_date = create_rw_signal(String::new());
     let (passengers, set_passengers) = create_signal(1u8);
 
+    // Pre-fill the origin from market-aware defaults unless the user already typed one
+    spawn_local(async move {
+        if let Ok(defaults) = get_search_defaults(None).await {
+            if origin.get_untracked().is_empty() {
+                origin.set(defaults.origin.code);
+            }
+        }
+    });
+
     // Swap animation state
     let (_is_swapping, set_swapping) = create_signal(false);
 
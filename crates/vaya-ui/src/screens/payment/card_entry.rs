@@ -10,6 +10,7 @@ use crate::hooks::validation::{
     card_number_rules, cvv_rules, expiry_rules, format_card_number, format_expiry, get_card_type,
     validate,
 };
+use crate::types::Price;
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -283,7 +284,7 @@ pub fn CardEntry() -> impl IntoView {
                     disabled=move || !is_valid()
                     on:click=handle_submit
                 >
-                    {format!("Pay RM {:.2}", total as f64 / 100.0)}
+                    {format!("Pay {}", Price::myr(total))}
                 </button>
             </div>
         </div>
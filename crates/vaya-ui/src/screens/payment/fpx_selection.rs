@@ -6,7 +6,7 @@ use leptos::*;
 use leptos_router::use_navigate;
 use web_sys::Storage;
 
-use crate::types::FpxBank;
+use crate::types::{FpxBank, Price};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -67,7 +67,7 @@ pub fn FpxBankSelection() -> impl IntoView {
             // Total display
             <div class="payment-total-banner">
                 <span class="total-label">"Total to pay"</span>
-                <span class="total-amount">{format!("RM {:.2}", total as f64 / 100.0)}</span>
+                <span class="total-amount">{Price::myr(total).format()}</span>
             </div>
 
             // Bank list
@@ -6,7 +6,7 @@ use leptos::*;
 use leptos_router::use_navigate;
 use web_sys::Storage;
 
-use crate::types::PaymentMethod;
+use crate::types::{PaymentMethod, Price};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -70,7 +70,7 @@ pub fn MethodSelection() -> impl IntoView {
             // Total display
             <div class="payment-total-banner">
                 <span class="total-label">"Total to pay"</span>
-                <span class="total-amount">{format!("RM {:.2}", total as f64 / 100.0)}</span>
+                <span class="total-amount">{Price::myr(total).format()}</span>
             </div>
 
             // Payment methods
@@ -6,7 +6,7 @@ use leptos::*;
 use leptos_router::use_navigate;
 use web_sys::Storage;
 
-use crate::types::Flight;
+use crate::types::{Flight, Price};
 
 /// Get session storage
 fn get_session_storage() -> Option<Storage> {
@@ -160,7 +160,7 @@ pub fn Success() -> impl IntoView {
                 // Total paid
                 <div class="success-total">
                     <span class="total-label">"Total Paid"</span>
-                    <span class="total-amount">{format!("RM {:.2}", total as f64 / 100.0)}</span>
+                    <span class="total-amount">{Price::myr(total).format()}</span>
                 </div>
 
                 // Confirmation email
@@ -92,17 +92,40 @@ impl Price {
         Self::new(sen, "MYR")
     }
 
-    /// Get display amount (major units)
-    pub fn display_amount(&self) -> f64 {
-        let decimals = match self.currency.as_str() {
+    /// Decimal places of the currency (mirrors `CurrencyCode::decimals`)
+    pub fn decimals(&self) -> u32 {
+        match self.currency.as_str() {
             "JPY" | "KRW" | "VND" => 0,
             "BHD" | "KWD" | "OMR" => 3,
             _ => 2,
-        };
-        self.amount as f64 / 10f64.powi(decimals)
+        }
+    }
+
+    /// Get display amount (major units)
+    pub fn display_amount(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals() as i32)
     }
 
-    /// Format for display (e.g., "RM 150.00")
+    /// Amount as a decimal string in the currency's precision (mirrors
+    /// `MinorUnits::format_decimal`; integer math, so no float rounding)
+    pub fn format_decimal(&self) -> String {
+        let decimals = self.decimals();
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let abs = self.amount.unsigned_abs();
+        if decimals == 0 {
+            return format!("{}{}", sign, abs);
+        }
+        let scale = 10u64.pow(decimals);
+        format!(
+            "{}{}.{:0width$}",
+            sign,
+            abs / scale,
+            abs % scale,
+            width = decimals as usize
+        )
+    }
+
+    /// Format for display (e.g., "RM 150.00", "JPY 45000")
     pub fn format(&self) -> String {
         let symbol = match self.currency.as_str() {
             "MYR" => "RM",
@@ -112,7 +135,7 @@ impl Price {
             "GBP" => "£",
             _ => &self.currency,
         };
-        format!("{} {:.2}", symbol, self.display_amount())
+        format!("{} {}", symbol, self.format_decimal())
     }
 }
 
@@ -346,6 +369,28 @@ pub fn confidence_label(confidence: u8) -> &'static str {
     }
 }
 
+/// Airport in search defaults
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DefaultAirport {
+    pub code: String,
+    pub city: String,
+    pub country: String,
+}
+
+/// Pre-filled search form values from `GET /defaults`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchDefaults {
+    pub market: String,
+    pub currency: String,
+    pub locale: String,
+    pub origin: DefaultAirport,
+    /// "last_search", "geoip" or "market"
+    pub origin_source: String,
+    pub destinations: Vec<DefaultAirport>,
+    pub departure_in_days: u32,
+    pub trip_length_days: u32,
+}
+
 // ============================================================================
 // ERROR TYPES
 // ============================================================================
@@ -380,6 +425,69 @@ pub struct Passenger {
     pub nationality: Option<String>,
     pub passport_number: Option<String>,
     pub passport_expiry: Option<String>,
+    #[serde(default)]
+    pub assistance: AssistanceNeeds,
+}
+
+/// Accessibility assistance requested for a passenger
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssistanceNeeds {
+    /// Wheelchair SSR code (WCHR, WCHS, WCHC)
+    pub wheelchair: Option<String>,
+    /// Own mobility aid SSR code (WCMP, WCBD, WCBW, WCLB)
+    pub mobility_aid: Option<String>,
+    /// Lithium battery rating in watt-hours (WCLB only)
+    pub battery_watt_hours: Option<u16>,
+    pub visual: bool,
+    pub hearing: bool,
+    pub cognitive: bool,
+    pub service_animal: bool,
+    /// Details for the airline
+    pub remarks: Option<String>,
+}
+
+impl AssistanceNeeds {
+    /// Maximum length of the details sent to the airline
+    pub const MAX_REMARKS_LEN: usize = 70;
+
+    pub fn is_empty(&self) -> bool {
+        self.wheelchair.is_none()
+            && self.mobility_aid.is_none()
+            && !self.visual
+            && !self.hearing
+            && !self.cognitive
+            && !self.service_animal
+    }
+
+    /// Check the same rules the booking service applies
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.mobility_aid.as_deref() == Some("WCLB")
+            && !matches!(self.battery_watt_hours, Some(1..=300))
+        {
+            return Err("Enter the battery rating (1-300 Wh) shown on your wheelchair");
+        }
+        match self.remarks.as_deref() {
+            Some(remarks) => {
+                if remarks.len() > Self::MAX_REMARKS_LEN {
+                    return Err("Assistance details must be 70 characters or fewer");
+                }
+                if !remarks
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || " .,-/()".contains(c))
+                {
+                    return Err("Use only letters, numbers and basic punctuation");
+                }
+                if self.is_empty() {
+                    return Err("Select the type of assistance you need");
+                }
+            }
+            None if self.cognitive => {
+                return Err("Tell us what assistance you need so the airline can prepare");
+            }
+            None => {}
+        }
+        Ok(())
+    }
 }
 
 /// Passenger type
@@ -755,3 +863,36 @@ pub struct PriceLock {
     pub expires_at: String,
     pub fee: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_format_uses_currency_decimals() {
+        assert_eq!(Price::myr(15050).format(), "RM 150.50");
+        assert_eq!(Price::new(-5, "USD").format(), "$ -0.05");
+        assert_eq!(Price::new(45000, "JPY").format(), "JPY 45000");
+        assert_eq!(Price::new(1234, "KWD").format(), "KWD 1.234");
+    }
+
+    #[test]
+    fn test_assistance_validation() {
+        let mut needs = AssistanceNeeds {
+            wheelchair: Some("WCHS".into()),
+            mobility_aid: Some("WCLB".into()),
+            ..Default::default()
+        };
+        assert!(needs.validate().is_err());
+        needs.battery_watt_hours = Some(160);
+        assert!(needs.validate().is_ok());
+
+        needs.cognitive = true;
+        assert!(needs.validate().is_err());
+        needs.remarks = Some("Needs a quiet seat".into());
+        assert!(needs.validate().is_ok());
+
+        let pax: Passenger = serde_json::from_str(r#"{"id":"pax-1","passenger_type":"adult","title":null,"first_name":"A","last_name":"B","date_of_birth":null,"nationality":null,"passport_number":null,"passport_expiry":null}"#).unwrap();
+        assert!(pax.assistance.is_empty());
+    }
+}